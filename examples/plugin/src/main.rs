@@ -16,6 +16,9 @@ use simba::physics::{GetRealStateReq, GetRealStateResp, Physics, PhysicsRecord};
 use simba::plugin_api::PluginAPI;
 use simba::recordable::Recordable;
 use simba::sensors::external_sensor::{ExternalObservation, ExternalSensorRecord};
+use simba::sensors::observation_types::{
+    ObservationField, ObservationFieldType, ObservationSchema,
+};
 use simba::sensors::{Observation, Sensor, SensorObservation, SensorRecord};
 use simba::simulator::{Simulator, SimulatorConfig};
 use simba::state_estimators::external_estimator::ExternalEstimatorRecord;
@@ -279,9 +282,14 @@ impl Sensor for MyWonderfulSensor {
         self.last_observation = Some(MyWonderfulSensorObservation { data: time });
         self.last_time = time;
         // Return a custom observation here, but you can return an existing one as well (e.g. SpeedObservation)
-        vec![SensorObservation::External(ExternalObservation {
-            observation: serde_json::to_value(self.last_observation.as_ref().unwrap()).unwrap(),
-        })]
+        // The observation type is registered by the plugin (see `observation_types`)
+        vec![SensorObservation::External(
+            ExternalObservation::new(
+                "my_wonderful_observation",
+                self.last_observation.as_ref().unwrap(),
+            )
+            .unwrap(),
+        )]
     }
 
     fn next_time_step(&self) -> f32 {
@@ -373,6 +381,13 @@ impl PluginAPI for MyWonderfulPlugin {
             initial_time,
         ))
     }
+
+    fn observation_types(&self) -> Vec<ObservationSchema> {
+        vec![ObservationSchema::new(
+            "my_wonderful_observation",
+            vec![ObservationField::new("data", ObservationFieldType::Number).with_unit("s")],
+        )]
+    }
 }
 
 fn main() {
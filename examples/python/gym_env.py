@@ -0,0 +1,52 @@
+#!/bin/env python3
+
+import simba
+
+
+def reward(observation: simba.GymObservation) -> float:
+    return -abs(observation.error.lateral)
+
+
+def main():
+    env = simba.GymEnv(
+        "config/config_gym.yaml", "robot1", reward, terminated=lambda obs: obs.time > 2.
+    )
+    for _ in range(2):
+        observation, info = env.reset()
+        assert info["time"] == observation.time
+        last_time = observation.time
+        steps = 0
+        while True:
+            command = simba.UnicycleCommand()
+            command.left_wheel_speed = 1.
+            command.right_wheel_speed = 1.
+            observation, reward, terminated, truncated, info = env.step(
+                simba.Command.from_unicycle_command(command)
+            )
+            assert reward <= 0.
+            assert observation.time >= last_time
+            last_time = observation.time
+            steps += 1
+            if terminated or truncated:
+                break
+        assert terminated and not truncated, "The episode should end with the callback"
+        assert steps > 1
+    env.close()
+
+    try:
+        simba.GymEnv("config/config_gym.yaml", "unknown_robot", reward)
+    except ValueError:
+        pass
+    else:
+        raise AssertionError("An unknown robot should be rejected")
+
+    try:
+        simba.GymEnv("config/config_controller.yaml", "robot2", reward)
+    except ValueError:
+        pass
+    else:
+        raise AssertionError("External modules without plugin API should be rejected")
+
+
+if __name__ == "__main__":
+    main()
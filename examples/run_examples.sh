@@ -21,6 +21,8 @@ echo "Run python example 'state_estimator'"
 ./state_estimator.py
 echo "Run python example 'messages'"
 ./messages.py
+echo "Run python example 'gym_env'"
+./gym_env.py
 
 cd ..
 echo "Run example 'stacked_plugins'"
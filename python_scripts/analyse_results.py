@@ -1,5 +1,7 @@
 #!/usr/bin/python3
 
+import os
+
 import numpy as np
 import matplotlib.pyplot as plt
 import IPython
@@ -58,4 +60,4 @@ def analyse(records: list, config: dict, figure_path: str, figure_type: str, add
     ax.legend()
     
     if figure_path != "":
-        f.savefig(f"{figure_path}/Trajectories_all{figure_type}", bbox_inches='tight')
+        f.savefig(os.path.join(figure_path, f"Trajectories_all{figure_type}"), bbox_inches='tight')
@@ -1,7 +1,12 @@
 use clap::Parser;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use simba::{errors::SimbaResult, gui, simulator::Simulator};
+use simba::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    gui,
+    simulator::{BatchRunner, Simulator},
+    utils::config_parsing::ConfigLoadMode,
+};
 
 #[derive(Parser)]
 #[command(version, about)]
@@ -14,9 +19,60 @@ struct Cli {
     /// Load result from the result file specified in the configuration. Without GUI, processes the results directly.
     #[arg(long, default_value_t = false)]
     load_results: bool,
+    /// Without GUI, run the simulation twice with the same seed and report the first diverging record.
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "no_gui",
+        conflicts_with = "load_results"
+    )]
+    determinism_check: bool,
+    /// With the determinism check, print the field by field diff of both runs at this time,
+    /// instead of at the time of the first divergence.
+    #[arg(long, requires = "determinism_check")]
+    snapshot_time: Option<f32>,
+    /// Run the batch of simulations described by this batch file (seeds and parameter sweep),
+    /// without GUI, and aggregate the records in a single result file.
+    #[arg(long, conflicts_with_all = ["load_results", "determinism_check"])]
+    batch: Option<String>,
+    /// Run only this run of the batch, and write its result next to the batch output.
+    /// Used by the batch worker processes.
+    #[arg(long, requires = "batch", hide = true)]
+    batch_run: Option<usize>,
+    /// Without GUI, downgrade the unknown fields and the unreadable versions of the configuration
+    /// to warnings, summarized after loading. Rejected with the GUI.
+    #[arg(long, default_value_t = false)]
+    permissive: bool,
+    /// Without GUI, deep-merge this overlay file on top of the configuration (repeatable, applied
+    /// in order): only the fields of the overlay change.
+    #[arg(long = "override", value_name = "PATH", conflicts_with = "batch")]
+    overrides: Vec<String>,
 }
 
 fn doit(args: Cli) -> SimbaResult<()> {
+    let load_mode = if args.permissive {
+        ConfigLoadMode::Permissive
+    } else {
+        ConfigLoadMode::Strict
+    };
+    if let Some(batch_path) = &args.batch {
+        Simulator::init_environment();
+        let batch = BatchRunner::from_config_path_with_mode(Path::new(batch_path), load_mode)?;
+        if let Some(index) = args.batch_run {
+            return batch.run_one_to_file(index, None);
+        }
+        let worker = std::env::current_exe().ok();
+        let runs = batch.run(None, worker.as_deref())?;
+        let failed = runs.iter().filter(|r| r.error.is_some()).count();
+        println!("Batch done: {} runs, {} failed", runs.len(), failed);
+        for run in runs.iter().filter(|r| r.error.is_some()) {
+            println!("Run {} failed: {}", run.index, run.error.as_ref().unwrap());
+        }
+        if failed > 0 {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     if args.no_gui {
         let config_path = args
             .config_path
@@ -29,16 +85,34 @@ fn doit(args: Cli) -> SimbaResult<()> {
         // Initialize the environment
         Simulator::init_environment();
         println!("Load configuration...");
-        let mut simulator = Simulator::from_config_path(
+        let mut simulator = Simulator::from_config_path_with_overrides(
             config_path.unwrap(),
             None, //<- plugin API, to load external modules
+            load_mode,
+            args.overrides.iter().map(PathBuf::from).collect(),
         )?;
 
         // Show the simulator loaded configuration
         println!("Configuration loaded:");
         simulator.show_config();
 
-        if !args.load_results {
+        if let Some(time) = args.snapshot_time {
+            // Run twice with the same seed, the results of the first run are kept
+            let snapshot_diff = simulator.determinism_snapshot_diff(None, time)?;
+            print!("{snapshot_diff}");
+            if !snapshot_diff.is_empty() {
+                std::process::exit(1);
+            }
+        } else if args.determinism_check {
+            // Run twice with the same seed, the results of the first run are kept
+            match simulator.determinism_check(None)? {
+                Some(divergence) => {
+                    println!("Simulation is not deterministic!\n{divergence}");
+                    std::process::exit(1);
+                }
+                None => println!("Determinism check passed: both runs gave the same records."),
+            }
+        } else if !args.load_results {
             // Run the simulator for the time given in the configuration
             // It also save the results to json
             simulator.run()?;
@@ -55,6 +129,18 @@ fn doit(args: Cli) -> SimbaResult<()> {
         .as_ref()
         .map(|p| Path::new(unsafe { std::mem::transmute::<&String, &'static String>(p) }));
 
+    if args.permissive {
+        return Err(SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            "--permissive is not supported with the GUI, use it with --no-gui or --batch"
+                .to_string(),
+        ));
+    }
+    if !args.overrides.is_empty() {
+        log::warn!(
+            "--override is ignored with the GUI: the configuration is loaded without overlay."
+        );
+    }
     gui::run_gui(config_path, None, args.load_results);
     Ok(())
 }
@@ -0,0 +1,46 @@
+//! Benchmark of the broker throughput: send, dispatch by `process_messages` and reception,
+//! for several numbers of subscribers.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use simba_com::pub_sub::{Broker, BrokerTrait, BrokerTraitProcessing};
+
+const MESSAGES_PER_ITERATION: usize = 100;
+
+fn throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("broker");
+    for nb_subscribers in [1, 10, 100] {
+        let mut broker = Broker::<String, Vec<f32>, String, u8>::new(0.1);
+        broker.add_channel("bench".to_string());
+        let mut channel = broker.get_channel(&"bench".to_string()).unwrap();
+        let sender = channel.client("sender".to_string(), 0.0);
+        let subscribers: Vec<_> = (0..nb_subscribers)
+            .map(|i| channel.client(format!("subscriber{i}"), 0.0))
+            .collect();
+        let message = vec![0.; 16];
+
+        group.throughput(Throughput::Elements(
+            (MESSAGES_PER_ITERATION * nb_subscribers) as u64,
+        ));
+        group.bench_with_input(
+            BenchmarkId::new("send_dispatch_receive", nb_subscribers),
+            &nb_subscribers,
+            |b, _| {
+                let mut time = 0.;
+                b.iter(|| {
+                    time += 1.;
+                    for _ in 0..MESSAGES_PER_ITERATION {
+                        sender.send(message.clone(), time);
+                    }
+                    broker.process_messages(None);
+                    for subscriber in subscribers.iter().chain([&sender]) {
+                        while subscriber.try_receive(time).is_some() {}
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
@@ -7,12 +7,16 @@
 //!
 //! It also provides concrete implementations:
 //! - [`Broker`] keyed by generic key types,
-//! - [`PathBroker`] keyed by hierarchical [`PathKey`] values.
+//! - [`PathBroker`] keyed by hierarchical [`PathKey`] values, with wildcard subscriptions.
 
 pub use std::str::FromStr;
 use std::{
     collections::HashMap,
     fmt::{Debug, Display},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
 use itertools::Itertools;
@@ -20,8 +24,9 @@ use log::warn;
 use tree_ds::prelude::{AutomatedId, Node, TraversalStrategy, Tree};
 
 use crate::pub_sub::{
-    Client, MultiClientTrait,
-    channel::{Channel, ChannelProcessing},
+    Client, MultiClientTrait, SharedMutex,
+    channel::{Channel, ChannelLimits, ChannelProcessing, ChannelStats, MessageRetention},
+    multi_client::PatternClients,
 };
 
 /// Core broker interface for channel registration and subscription management.
@@ -85,6 +90,22 @@ where
         reception_delay: f32,
         multi_client: &mut dyn MultiClientTrait<KeyType, MessageType, NodeIdType>,
     ) -> Result<(), String>;
+
+    /// Subscribes `node_id` to all the channels matching `pattern`, existing and created later.
+    /// The clients are pushed to `clients`. The subscription ends when `clients` is dropped by
+    /// the subscriber.
+    ///
+    /// Returns an error if the broker does not support patterns.
+    #[allow(unused_variables)]
+    fn subscribe_to_pattern(
+        &mut self,
+        pattern: &KeyType,
+        node_id: NodeIdType,
+        reception_delay: f32,
+        clients: PatternClients<KeyType, MessageType>,
+    ) -> Result<(), String> {
+        Err("Pattern subscriptions are not supported by this broker".to_string())
+    }
 }
 
 /// Extended broker interface supporting conditional delivery channels.
@@ -99,12 +120,33 @@ where
     /// Adds a root-level channel with a per-client delivery condition.
     fn add_channel_conditionnal<F>(&mut self, key: KeyType, condition: F)
     where
-        F: Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone;
+        F: Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone,
+    {
+        self.add_channel_with_delivery(key, move |from_arg, to_arg, _| {
+            condition(from_arg, to_arg).then_some(0.)
+        });
+    }
 
     /// Adds a subchannel with a per-client delivery condition.
     fn add_subchannel_conditionnal<F>(&mut self, key: KeyType, parent_key: &KeyType, condition: F)
     where
-        F: Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone;
+        F: Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone,
+    {
+        self.add_subchannel_with_delivery(key, parent_key, move |from_arg, to_arg, _| {
+            condition(from_arg, to_arg).then_some(0.)
+        });
+    }
+
+    /// Adds a root-level channel with a per-client delivery model, see
+    /// [`Channel::new_with_delivery`].
+    fn add_channel_with_delivery<F>(&mut self, key: KeyType, delivery: F)
+    where
+        F: Fn(ConditionArgType, ConditionArgType, f32) -> Option<f32> + Send + 'static + Clone;
+
+    /// Adds a subchannel with a per-client delivery model, see [`Channel::new_with_delivery`].
+    fn add_subchannel_with_delivery<F>(&mut self, key: KeyType, parent_key: &KeyType, delivery: F)
+    where
+        F: Fn(ConditionArgType, ConditionArgType, f32) -> Option<f32> + Send + 'static + Clone;
 }
 
 /// Runtime processing interface for brokers.
@@ -127,6 +169,35 @@ where
         &mut self,
         key: &KeyType,
     ) -> Option<Channel<MessageType, NodeIdType, ConditionArgType>>;
+
+    /// Returns the number of messages dispatched to subscribers but not yet received by them,
+    /// over all channels.
+    fn pending_messages(&self) -> usize;
+
+    /// Sets the retention of the messages waiting for the subscribers, for all the channels
+    /// (current and future ones).
+    fn set_retention(&mut self, retention: MessageRetention);
+
+    /// Drops the messages not kept by the retention in all channels, at simulation `time`.
+    /// Returns the number of messages dropped.
+    fn collect_garbage(&self, time: f32) -> usize;
+
+    /// Returns the queue metrics of each channel.
+    fn channel_stats(&self) -> Vec<(KeyType, ChannelStats)>;
+
+    /// Returns the number of messages waiting for the clients of `node`, over all channels.
+    fn node_queue_size(&self, node: &NodeIdType) -> usize;
+
+    /// Returns the counter of the messages waiting for the clients of `node`, over all channels.
+    /// The counter can be kept to read the queue size without locking the broker.
+    fn node_queue_counter(&self, node: &NodeIdType) -> Arc<AtomicUsize>;
+
+    /// Sets the transmission limits of the channel `key`, which can be created later.
+    fn set_channel_limits(&mut self, key: &KeyType, limits: ChannelLimits);
+
+    /// Sets the function giving the size of the messages in bytes, used by the bandwidth limits,
+    /// for all the channels (current and future ones).
+    fn set_message_size(&mut self, message_size: fn(&MessageType) -> usize);
 }
 
 #[derive(Debug)]
@@ -142,6 +213,11 @@ where
     key_tree: Tree<AutomatedId, KeyType>,
     key_to_node_id: HashMap<KeyType, AutomatedId>,
     time_round: f32,
+    retention: MessageRetention,
+    limits: HashMap<KeyType, ChannelLimits>,
+    message_size: Option<fn(&MessageType) -> usize>,
+    /// Messages waiting for the clients of each node, over all channels.
+    node_pending_messages: SharedMutex<HashMap<NodeIdType, Arc<AtomicUsize>>>,
     _phantom: std::marker::PhantomData<MessageType>,
 }
 
@@ -164,9 +240,29 @@ where
             key_tree,
             key_to_node_id,
             time_round,
+            retention: MessageRetention::default(),
+            limits: HashMap::new(),
+            message_size: None,
+            node_pending_messages: Arc::new(Mutex::new(HashMap::new())),
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Applies the broker-wide settings to a new channel.
+    fn configure_channel(
+        &self,
+        key: &KeyType,
+        channel: &mut Channel<MessageType, NodeIdType, ConditionArgType>,
+    ) {
+        channel.share_node_pending_messages(self.node_pending_messages.clone());
+        channel.set_retention(self.retention.clone());
+        if let Some(limits) = self.limits.get(key) {
+            channel.set_limits(limits.clone());
+        }
+        if let Some(message_size) = self.message_size {
+            channel.set_message_size(message_size);
+        }
+    }
 }
 
 impl<KeyType, MessageType, NodeIdType, ConditionArgType>
@@ -182,13 +278,12 @@ where
         if self.key_to_node_id.contains_key(&key) {
             return;
         }
-        self.channels.insert(
-            key.clone(),
-            Box::new(Channel::<MessageType, NodeIdType, ConditionArgType>::new(
-                self.time_round,
-                &key.to_string(),
-            )),
+        let mut channel = Channel::<MessageType, NodeIdType, ConditionArgType>::new(
+            self.time_round,
+            &key.to_string(),
         );
+        self.configure_channel(&key, &mut channel);
+        self.channels.insert(key.clone(), Box::new(channel));
         #[cfg(feature = "debug_mode")]
         log::debug!("Adding channel for key: {}", key);
         let new_id = self
@@ -235,13 +330,12 @@ where
         if self.key_to_node_id.contains_key(&key) {
             return;
         }
-        self.channels.insert(
-            key.clone(),
-            Box::new(Channel::<MessageType, NodeIdType, ConditionArgType>::new(
-                self.time_round,
-                &key.to_string(),
-            )),
+        let mut channel = Channel::<MessageType, NodeIdType, ConditionArgType>::new(
+            self.time_round,
+            &key.to_string(),
         );
+        self.configure_channel(&key, &mut channel);
+        self.channels.insert(key.clone(), Box::new(channel));
         let parent_node_id = self
             .key_to_node_id
             .get(parent_key)
@@ -403,23 +497,20 @@ where
     NodeIdType: std::hash::Hash + Eq + Clone + Send + Sync + 'static + Debug,
     ConditionArgType: Clone + Send + 'static + Default + Debug,
 {
-    fn add_channel_conditionnal<F>(&mut self, key: KeyType, condition: F)
+    fn add_channel_with_delivery<F>(&mut self, key: KeyType, delivery: F)
     where
-        F: Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone,
+        F: Fn(ConditionArgType, ConditionArgType, f32) -> Option<f32> + Send + 'static + Clone,
     {
         if self.key_to_node_id.contains_key(&key) {
             return;
         }
-        self.channels.insert(
-            key.clone(),
-            Box::new(
-                Channel::<MessageType, NodeIdType, ConditionArgType>::new_conditionnal(
-                    condition,
-                    self.time_round,
-                    &key.to_string(),
-                ),
-            ),
+        let mut channel = Channel::<MessageType, NodeIdType, ConditionArgType>::new_with_delivery(
+            delivery,
+            self.time_round,
+            &key.to_string(),
         );
+        self.configure_channel(&key, &mut channel);
+        self.channels.insert(key.clone(), Box::new(channel));
         let new_id = self
             .key_tree
             .add_node(
@@ -430,23 +521,20 @@ where
         self.key_to_node_id.insert(key, new_id);
     }
 
-    fn add_subchannel_conditionnal<F>(&mut self, key: KeyType, parent_key: &KeyType, condition: F)
+    fn add_subchannel_with_delivery<F>(&mut self, key: KeyType, parent_key: &KeyType, delivery: F)
     where
-        F: Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone,
+        F: Fn(ConditionArgType, ConditionArgType, f32) -> Option<f32> + Send + 'static + Clone,
     {
         if self.key_to_node_id.contains_key(&key) {
             return;
         }
-        self.channels.insert(
-            key.clone(),
-            Box::new(
-                Channel::<MessageType, NodeIdType, ConditionArgType>::new_conditionnal(
-                    condition,
-                    self.time_round,
-                    &key.to_string(),
-                ),
-            ),
+        let mut channel = Channel::<MessageType, NodeIdType, ConditionArgType>::new_with_delivery(
+            delivery,
+            self.time_round,
+            &key.to_string(),
         );
+        self.configure_channel(&key, &mut channel);
+        self.channels.insert(key.clone(), Box::new(channel));
         let parent_node_id = self
             .key_to_node_id
             .get(parent_key)
@@ -495,6 +583,67 @@ where
             .downcast_mut::<Channel<MessageType, NodeIdType, ConditionArgType>>()
             .cloned()
     }
+
+    fn pending_messages(&self) -> usize {
+        self.channels
+            .values()
+            .map(|channel| channel.pending_messages())
+            .sum()
+    }
+
+    fn set_retention(&mut self, retention: MessageRetention) {
+        for channel in self.channels.values() {
+            channel.set_retention(retention.clone());
+        }
+        self.retention = retention;
+    }
+
+    fn collect_garbage(&self, time: f32) -> usize {
+        self.channels
+            .values()
+            .map(|channel| channel.collect_garbage(time))
+            .sum()
+    }
+
+    fn channel_stats(&self) -> Vec<(KeyType, ChannelStats)> {
+        self.channels
+            .iter()
+            .map(|(key, channel)| (key.clone(), channel.stats()))
+            .collect()
+    }
+
+    fn node_queue_size(&self, node: &NodeIdType) -> usize {
+        self.node_pending_messages
+            .lock()
+            .unwrap()
+            .get(node)
+            .map_or(0, |counter| counter.load(Ordering::Relaxed))
+    }
+
+    fn node_queue_counter(&self, node: &NodeIdType) -> Arc<AtomicUsize> {
+        self.node_pending_messages
+            .lock()
+            .unwrap()
+            .entry(node.clone())
+            .or_default()
+            .clone()
+    }
+
+    fn set_channel_limits(&mut self, key: &KeyType, limits: ChannelLimits) {
+        if let Some(channel) = self.channels.get(key) {
+            channel.set_limits(limits.clone());
+        }
+        self.limits.insert(key.clone(), limits);
+    }
+
+    fn set_message_size(&mut self, message_size: fn(&MessageType) -> usize) {
+        for key in self.channels.keys().cloned().collect::<Vec<_>>() {
+            if let Some(channel) = self.get_channel(&key) {
+                channel.set_message_size(message_size);
+            }
+        }
+        self.message_size = Some(message_size);
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -602,6 +751,43 @@ impl PathKey {
             absolute: other.starts_with('/'),
         }
     }
+
+    /// Returns `true` if the key contains a wildcard segment (`+` or `#`), see
+    /// [`PathKey::matches`].
+    pub fn is_pattern(&self) -> bool {
+        self.path
+            .iter()
+            .any(|segment| segment == "+" || segment == "#")
+    }
+
+    /// Returns `true` if `key` matches this pattern.
+    ///
+    /// A `+` segment matches exactly one segment, and a `#` last segment matches all the remaining
+    /// segments (possibly none): `/sensors/+/gnss` matches `/sensors/robot1/gnss`, and
+    /// `/robot1/#` matches all the keys under `/robot1`. A key without wildcard only matches
+    /// itself.
+    pub fn matches(&self, key: &PathKey) -> bool {
+        if self.absolute != key.absolute {
+            return false;
+        }
+        let mut segments = key.path.iter();
+        for pattern_segment in &self.path {
+            match pattern_segment.as_str() {
+                "#" => return true,
+                "+" => {
+                    if segments.next().is_none() {
+                        return false;
+                    }
+                }
+                _ => {
+                    if segments.next() != Some(pattern_segment) {
+                        return false;
+                    }
+                }
+            }
+        }
+        segments.next().is_none()
+    }
 }
 
 impl Display for PathKey {
@@ -634,6 +820,19 @@ where
     ConditionArgType: Clone + Send + 'static + Default + Debug,
 {
     broker: Broker<String, MessageType, NodeIdType, ConditionArgType>,
+    patterns: Vec<PatternSubscription<MessageType, NodeIdType>>,
+}
+
+/// Pattern subscription of a [`PathBroker`] client.
+#[derive(Debug)]
+struct PatternSubscription<MessageType, NodeIdType>
+where
+    MessageType: Clone + Send + 'static + Default + Debug,
+{
+    pattern: PathKey,
+    node_id: NodeIdType,
+    reception_delay: f32,
+    clients: PatternClients<PathKey, MessageType>,
 }
 
 impl<MessageType, NodeIdType, ConditionArgType>
@@ -647,6 +846,30 @@ where
     pub fn new(time_round: f32) -> Self {
         Self {
             broker: Broker::new(time_round),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Subscribes the pattern subscriptions matching the new channel `key`.
+    fn subscribe_patterns(&mut self, key: &PathKey) {
+        // Drop the subscriptions of the removed multi-clients
+        self.patterns
+            .retain(|subscription| Arc::strong_count(&subscription.clients) > 1);
+        for subscription in &self.patterns {
+            if !subscription.pattern.matches(key) {
+                continue;
+            }
+            let mut clients = subscription.clients.lock().unwrap();
+            if clients.iter().any(|(client_key, _)| client_key == key) {
+                continue;
+            }
+            if let Some(client) = self.broker.subscribe_to(
+                &key.to_string(),
+                subscription.node_id.clone(),
+                subscription.reception_delay,
+            ) {
+                clients.push((key.clone(), client));
+            }
         }
     }
 
@@ -679,6 +902,7 @@ where
             key.to_string(),
             &key.parent().map(|p| p.to_string()).unwrap_or_default(),
         );
+        self.subscribe_patterns(&key);
     }
 
     fn add_metachannel(&mut self, key: PathKey, parent_key: Option<&PathKey>) {
@@ -705,6 +929,7 @@ where
         self.add_metachannel(parent_key.clone(), None);
         self.broker
             .add_subchannel(key.to_string(), &parent_key.to_string());
+        self.subscribe_patterns(&key);
     }
 
     fn channel_exists(&self, key: &PathKey) -> bool {
@@ -783,6 +1008,7 @@ where
 
     fn clear_channels(&mut self) {
         self.broker.clear_channels();
+        self.patterns.clear();
     }
 
     fn subscribe_to(
@@ -810,6 +1036,17 @@ where
                 multi_client.add_client(&key, client);
             } else if self.meta_exists(&key) {
                 self.subscribe_to_meta(&key, reception_delay, multi_client)?;
+            } else if key.is_pattern() {
+                let clients = multi_client.pattern_clients().ok_or(format!(
+                    "Multi-client does not support pattern subscriptions: {}",
+                    key
+                ))?;
+                self.subscribe_to_pattern(
+                    &key,
+                    multi_client.node_id().clone(),
+                    reception_delay,
+                    clients,
+                )?;
             } else {
                 return Err(format!("Failed to subscribe to key: {}", key));
             }
@@ -866,6 +1103,31 @@ where
         }
         Ok(())
     }
+
+    fn subscribe_to_pattern(
+        &mut self,
+        pattern: &PathKey,
+        node_id: NodeIdType,
+        reception_delay: f32,
+        clients: PatternClients<PathKey, MessageType>,
+    ) -> Result<(), String> {
+        if !pattern.absolute() {
+            return Err(format!(
+                "Only absolute keys can be used as patterns: {}",
+                pattern
+            ));
+        }
+        self.patterns.push(PatternSubscription {
+            pattern: pattern.clone(),
+            node_id,
+            reception_delay,
+            clients,
+        });
+        for key in self.channel_list() {
+            self.subscribe_patterns(&key);
+        }
+        Ok(())
+    }
 }
 
 impl<MessageType, NodeIdType, ConditionArgType>
@@ -876,30 +1138,32 @@ where
     NodeIdType: std::hash::Hash + Eq + Clone + Send + Sync + 'static + Debug,
     ConditionArgType: Clone + Send + 'static + Default + Debug,
 {
-    fn add_channel_conditionnal<F>(&mut self, key: PathKey, condition: F)
+    fn add_channel_with_delivery<F>(&mut self, key: PathKey, delivery: F)
     where
-        F: Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone,
+        F: Fn(ConditionArgType, ConditionArgType, f32) -> Option<f32> + Send + 'static + Clone,
     {
         if let Some(parent) = key.parent() {
             self.add_metachannel(parent, None);
         }
-        self.broker.add_subchannel_conditionnal(
+        self.broker.add_subchannel_with_delivery(
             key.to_string(),
             &key.parent().map(|p| p.to_string()).unwrap_or_default(),
-            condition,
+            delivery,
         );
+        self.subscribe_patterns(&key);
     }
 
-    fn add_subchannel_conditionnal<F>(&mut self, key: PathKey, parent_key: &PathKey, condition: F)
+    fn add_subchannel_with_delivery<F>(&mut self, key: PathKey, parent_key: &PathKey, delivery: F)
     where
-        F: Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone,
+        F: Fn(ConditionArgType, ConditionArgType, f32) -> Option<f32> + Send + 'static + Clone,
     {
         self.add_metachannel(parent_key.clone(), None);
-        self.broker.add_subchannel_conditionnal(
+        self.broker.add_subchannel_with_delivery(
             key.to_string(),
             &parent_key.to_string(),
-            condition,
+            delivery,
         );
+        self.subscribe_patterns(&key);
     }
 }
 
@@ -924,6 +1188,42 @@ where
     ) {
         self.broker.process_messages(client_condition_args);
     }
+
+    fn pending_messages(&self) -> usize {
+        self.broker.pending_messages()
+    }
+
+    fn set_retention(&mut self, retention: MessageRetention) {
+        self.broker.set_retention(retention);
+    }
+
+    fn collect_garbage(&self, time: f32) -> usize {
+        self.broker.collect_garbage(time)
+    }
+
+    fn channel_stats(&self) -> Vec<(PathKey, ChannelStats)> {
+        self.broker
+            .channel_stats()
+            .into_iter()
+            .map(|(key, stats)| (PathKey::from_str(&key).unwrap(), stats))
+            .collect()
+    }
+
+    fn node_queue_size(&self, node: &NodeIdType) -> usize {
+        self.broker.node_queue_size(node)
+    }
+
+    fn node_queue_counter(&self, node: &NodeIdType) -> Arc<AtomicUsize> {
+        self.broker.node_queue_counter(node)
+    }
+
+    fn set_channel_limits(&mut self, key: &PathKey, limits: ChannelLimits) {
+        self.broker.set_channel_limits(&key.to_string(), limits);
+    }
+
+    fn set_message_size(&mut self, message_size: fn(&MessageType) -> usize) {
+        self.broker.set_message_size(message_size);
+    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,10 @@
 //! This module defines:
 //! - [`ChannelProcessing`], the trait used by brokers to process pending messages,
 //! - [`Channel`], a concrete channel implementation supporting multi-client fan-out with optional
-//!   delivery conditions.
+//!   delivery conditions (drop or extra delay of the messages, per recipient),
+//! - [`MessageRetention`] and [`ChannelStats`], for the cleanup of the messages waiting in slow
+//!   subscribers and the queue metrics,
+//! - [`ChannelLimits`], for the bandwidth and message rate limits of a channel.
 
 use std::{
     collections::{HashMap, HashSet},
@@ -11,6 +14,7 @@ use std::{
     hash::Hash,
     sync::{
         Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
         mpsc::{self, Receiver, Sender},
     },
 };
@@ -18,7 +22,55 @@ use std::{
 #[cfg(feature = "debug_mode")]
 use log::debug;
 
-use crate::pub_sub::{SharedMutex, client::Client};
+use crate::pub_sub::{
+    SharedMutex,
+    client::{Client, ClientQueueHandle},
+};
+
+/// Retention of the messages dispatched to the clients and not received yet.
+///
+/// Applied by [`ChannelProcessing::collect_garbage`]. By default, all messages are kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageRetention {
+    /// Drop the messages due for more than `max_age` seconds. `None` for no limit.
+    pub max_age: Option<f32>,
+    /// Maximum number of messages waiting for each client, the oldest are dropped.
+    /// `None` for no limit.
+    pub max_queue_size: Option<usize>,
+}
+
+/// Transmission limits of a channel.
+///
+/// The channel is a shared medium: its messages are transmitted one after the other, in sending
+/// order. A message waits until the previous ones are transmitted, then takes `size / bandwidth`
+/// seconds to be transmitted. The queuing and transmission durations are added to the delay of
+/// the message. By default, the channel has no limit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChannelLimits {
+    /// Bandwidth of the channel, in bytes per second. `None` for no limit.
+    pub bandwidth: Option<f32>,
+    /// Maximum number of messages per second: two messages start their transmission at least
+    /// `1 / max_rate` seconds apart. `None` for no limit.
+    pub max_rate: Option<f32>,
+}
+
+/// Queue metrics of a channel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChannelStats {
+    /// Number of clients.
+    pub clients: usize,
+    /// Messages dispatched to the clients and not received yet.
+    pub pending_messages: usize,
+    /// Number of messages waiting for the slowest client.
+    pub max_queue_size: usize,
+    /// Messages dropped by the garbage collection since the creation of the channel.
+    pub dropped_messages: usize,
+    /// Messages delayed by the queuing of the [`ChannelLimits`] since the creation of the channel.
+    pub queued_messages: usize,
+    /// Largest delay added by the [`ChannelLimits`] to a message (queuing and transmission), in
+    /// seconds.
+    pub max_limit_delay: f32,
+}
 
 /// Runtime processing interface for broker-managed channels.
 pub trait ChannelProcessing<NodeIdType, ConditionArgType>: Send + Sync + Debug {
@@ -29,6 +81,71 @@ pub trait ChannelProcessing<NodeIdType, ConditionArgType>: Send + Sync + Debug {
     );
     /// Returns a mutable `Any` view for downcasting to concrete channel types.
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+    /// Returns the number of messages dispatched to subscribers but not yet received by them.
+    fn pending_messages(&self) -> usize;
+    /// Sets the retention of the messages waiting for the subscribers.
+    fn set_retention(&self, retention: MessageRetention);
+    /// Drops the messages waiting for the subscribers which are not kept by the retention, at
+    /// simulation `time`. Returns the number of messages dropped.
+    fn collect_garbage(&self, time: f32) -> usize;
+    /// Returns the queue metrics of the channel.
+    fn stats(&self) -> ChannelStats;
+    /// Returns the number of messages waiting for the clients of `node`.
+    fn node_queue_size(&self, node: &NodeIdType) -> usize;
+    /// Sets the transmission limits of the channel.
+    fn set_limits(&self, limits: ChannelLimits);
+}
+
+/// In-memory size of the message type, the default message size of the [`ChannelLimits`].
+fn memory_size<MessageType>(_message: &MessageType) -> usize {
+    std::mem::size_of::<MessageType>()
+}
+
+/// Transmission state of a channel with [`ChannelLimits`].
+#[derive(Debug)]
+struct ChannelLimiter<MessageType> {
+    limits: ChannelLimits,
+    /// Size of a message in bytes.
+    message_size: fn(&MessageType) -> usize,
+    /// Time at which the next message can start its transmission.
+    free_time: f32,
+    queued_messages: usize,
+    max_delay: f32,
+}
+
+impl<MessageType> ChannelLimiter<MessageType> {
+    fn new() -> Self {
+        Self {
+            limits: ChannelLimits::default(),
+            message_size: memory_size::<MessageType>,
+            free_time: f32::NEG_INFINITY,
+            queued_messages: 0,
+            max_delay: 0.,
+        }
+    }
+
+    fn is_limited(&self) -> bool {
+        self.limits != ChannelLimits::default()
+    }
+
+    /// Transmits the message sent at `time` and returns the delay added by the limits.
+    fn transmit(&mut self, message: &MessageType, time: f32) -> f32 {
+        let start = time.max(self.free_time);
+        if start > time {
+            self.queued_messages += 1;
+        }
+        let end = start
+            + self.limits.bandwidth.map_or(0., |bandwidth| {
+                (self.message_size)(message) as f32 / bandwidth
+            });
+        self.free_time = self
+            .limits
+            .max_rate
+            .map_or(end, |max_rate| end.max(start + 1. / max_rate));
+        let delay = end - time;
+        self.max_delay = self.max_delay.max(delay);
+        delay
+    }
 }
 
 type SenderType<MessageType> = Sender<(MessageType, f32)>;
@@ -45,9 +162,23 @@ pub struct Channel<
 > {
     senders: SharedMutex<HashMap<(NodeIdType, usize), SenderType<MessageType>>>,
     receivers: SharedMutex<HashMap<(NodeIdType, usize), ReceiverType<MessageType>>>,
-    condition: SharedMutex<dyn Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static>,
+    /// Delivery of a message sent at the given time, between two clients: `None` to drop it, or
+    /// the extra delay of the message.
+    delivery: SharedMutex<
+        dyn Fn(ConditionArgType, ConditionArgType, f32) -> Option<f32> + Send + 'static,
+    >,
     time_round: f32,
     client_count: SharedMutex<usize>,
+    /// Messages dispatched to the clients and not received yet, shared with the clients.
+    pending_messages: Arc<AtomicUsize>,
+    /// Messages dispatched and not received yet, per node of the clients. Shared with the
+    /// clients, and with the other channels of the broker.
+    node_pending_messages: SharedMutex<HashMap<NodeIdType, Arc<AtomicUsize>>>,
+    /// Message queues of the clients, for the garbage collection.
+    queues: SharedMutex<HashMap<(NodeIdType, usize), ClientQueueHandle<MessageType>>>,
+    retention: SharedMutex<MessageRetention>,
+    dropped_messages: Arc<AtomicUsize>,
+    limiter: SharedMutex<ChannelLimiter<MessageType>>,
     name: String,
 }
 
@@ -79,9 +210,15 @@ impl<
         Self {
             senders: Arc::new(Mutex::new(HashMap::new())),
             receivers: Arc::new(Mutex::new(HashMap::new())),
-            condition: Arc::new(Mutex::new(|_, _| true)),
+            delivery: Arc::new(Mutex::new(|_, _, _| Some(0.))),
             time_round,
             client_count: Arc::new(Mutex::new(0)),
+            pending_messages: Arc::new(AtomicUsize::new(0)),
+            node_pending_messages: Arc::new(Mutex::new(HashMap::new())),
+            queues: Arc::new(Mutex::new(HashMap::new())),
+            retention: Arc::new(Mutex::new(MessageRetention::default())),
+            dropped_messages: Arc::new(AtomicUsize::new(0)),
+            limiter: Arc::new(Mutex::new(ChannelLimiter::new())),
             name: name.into(),
         }
     }
@@ -94,17 +231,58 @@ impl<
         condition: impl Fn(ConditionArgType, ConditionArgType) -> bool + Send + 'static + Clone,
         time_round: f32,
         name: &str,
+    ) -> Self {
+        Self::new_with_delivery(
+            move |from_arg, to_arg, _| condition(from_arg, to_arg).then_some(0.),
+            time_round,
+            name,
+        )
+    }
+
+    /// Creates a new channel with a custom delivery model.
+    ///
+    /// The `delivery` function receives `(from_arg, to_arg, message_time)` and returns `None` if
+    /// the message is not delivered to the recipient, or the extra delay of the message (added to
+    /// the reception delay of the recipient).
+    pub fn new_with_delivery(
+        delivery: impl Fn(ConditionArgType, ConditionArgType, f32) -> Option<f32>
+        + Send
+        + 'static
+        + Clone,
+        time_round: f32,
+        name: &str,
     ) -> Self {
         Self {
             senders: Arc::new(Mutex::new(HashMap::new())),
             receivers: Arc::new(Mutex::new(HashMap::new())),
-            condition: Arc::new(Mutex::new(condition)),
+            delivery: Arc::new(Mutex::new(delivery)),
             time_round,
             client_count: Arc::new(Mutex::new(0)),
+            pending_messages: Arc::new(AtomicUsize::new(0)),
+            node_pending_messages: Arc::new(Mutex::new(HashMap::new())),
+            queues: Arc::new(Mutex::new(HashMap::new())),
+            retention: Arc::new(Mutex::new(MessageRetention::default())),
+            dropped_messages: Arc::new(AtomicUsize::new(0)),
+            limiter: Arc::new(Mutex::new(ChannelLimiter::new())),
             name: name.into(),
         }
     }
 
+    /// Sets the function giving the size of the messages in bytes, used by the bandwidth limit.
+    /// By default, the in-memory size of the message type is used.
+    pub fn set_message_size(&self, message_size: fn(&MessageType) -> usize) {
+        self.limiter.lock().unwrap().message_size = message_size;
+    }
+
+    /// Shares the per-node pending message counters, so that they count the messages of all the
+    /// channels sharing them.
+    pub(crate) fn share_node_pending_messages(
+        &mut self,
+        node_pending_messages: SharedMutex<HashMap<NodeIdType, Arc<AtomicUsize>>>,
+    ) {
+        self.node_pending_messages = node_pending_messages;
+    }
+
     /// Creates and registers a client endpoint for `node_id`.
     ///
     /// `reception_delay` is applied when constructing the returned [`Client`].
@@ -130,12 +308,26 @@ impl<
             node_id,
             self.receivers.lock().unwrap().len()
         );
-        Client::new(
+        let node_pending_messages = self
+            .node_pending_messages
+            .lock()
+            .unwrap()
+            .entry(node_id.clone())
+            .or_default()
+            .clone();
+        let client = Client::new(
             from_client_tx,
             to_client_rx,
             reception_delay,
             self.time_round,
-        )
+            self.pending_messages.clone(),
+            node_pending_messages,
+        );
+        self.queues
+            .lock()
+            .unwrap()
+            .insert((node_id, id), client.queue_handle());
+        client
     }
 }
 
@@ -166,7 +358,21 @@ impl<
                 messages_to_send.push((from_id.clone(), *receiver_id, message));
             }
         }
+        let mut limiter = self.limiter.lock().unwrap();
+        if limiter.is_limited() {
+            // Transmit the messages in sending order, the client ids breaking the ties for
+            // determinism.
+            messages_to_send.sort_by(|(_, a_id, a), (_, b_id, b)| {
+                a.1.total_cmp(&b.1).then_with(|| a_id.cmp(b_id))
+            });
+        }
+        let node_pending_messages = self.node_pending_messages.lock().unwrap();
         for (from_id, from_sender_id, message) in messages_to_send {
+            let limit_delay = if limiter.is_limited() {
+                limiter.transmit(&message.0, message.1)
+            } else {
+                0.
+            };
             let from_arg = client_condition_args.and_then(|args| args.get(&from_id));
             for ((to_id, sender_id), sender) in senders.iter() {
                 let to_arg = if from_arg.is_some() {
@@ -174,10 +380,14 @@ impl<
                 } else {
                     None
                 };
-                let send = if let Some(to_arg) = to_arg {
-                    (self.condition.lock().unwrap())(from_arg.unwrap().clone(), to_arg.clone())
+                let delivery = if let Some(to_arg) = to_arg {
+                    (self.delivery.lock().unwrap())(
+                        from_arg.unwrap().clone(),
+                        to_arg.clone(),
+                        message.1,
+                    )
                 } else {
-                    true
+                    Some(0.)
                 };
                 // Avoid sending the message back to the sender
                 if &from_id == to_id && *sender_id == from_sender_id {
@@ -188,8 +398,11 @@ impl<
                     );
                     continue;
                 }
-                if send {
-                    if sender.send(message.clone()).is_err() {
+                if let Some(extra_delay) = delivery {
+                    if sender
+                        .send((message.0.clone(), message.1 + limit_delay + extra_delay))
+                        .is_err()
+                    {
                         // panic!(
                         //     "Failed to send message from {:?} to ({:?}, {}): {:?}",
                         //     from_id,
@@ -200,6 +413,10 @@ impl<
                         // Assume dead client
                         dead_clients.insert((to_id.clone(), *sender_id));
                     } else {
+                        self.pending_messages.fetch_add(1, Ordering::Relaxed);
+                        if let Some(node_pending) = node_pending_messages.get(to_id) {
+                            node_pending.fetch_add(1, Ordering::Relaxed);
+                        }
                         #[cfg(feature = "debug_mode")]
                         debug!(
                             "[Channel {}] Message from {:?} to {:?} sent",
@@ -215,6 +432,8 @@ impl<
                 }
             }
         }
+        drop(limiter);
+        drop(node_pending_messages);
         if dead_clients.is_empty() {
             return;
         }
@@ -251,12 +470,76 @@ impl<
 
             // Remove senders
             let _ = senders
-                .remove(&(key, sender_id))
+                .remove(&(key.clone(), sender_id))
                 .expect("Client name to remove does not exist in senders");
+
+            self.queues.lock().unwrap().remove(&(key, sender_id));
         }
     }
 
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
+
+    fn pending_messages(&self) -> usize {
+        self.pending_messages.load(Ordering::Relaxed)
+    }
+
+    fn set_retention(&self, retention: MessageRetention) {
+        *self.retention.lock().unwrap() = retention;
+    }
+
+    fn collect_garbage(&self, time: f32) -> usize {
+        let retention = self.retention.lock().unwrap().clone();
+        if retention == MessageRetention::default() {
+            return 0;
+        }
+        let dropped: usize = self
+            .queues
+            .lock()
+            .unwrap()
+            .values()
+            .map(|queue| queue.collect_garbage(time, &retention))
+            .sum();
+        #[cfg(feature = "debug_mode")]
+        if dropped > 0 {
+            debug!(
+                "[Channel {}] {} messages dropped by the garbage collection",
+                self.name, dropped
+            );
+        }
+        self.dropped_messages.fetch_add(dropped, Ordering::Relaxed);
+        dropped
+    }
+
+    fn stats(&self) -> ChannelStats {
+        let queues = self.queues.lock().unwrap();
+        let limiter = self.limiter.lock().unwrap();
+        ChannelStats {
+            clients: queues.len(),
+            pending_messages: self.pending_messages(),
+            max_queue_size: queues
+                .values()
+                .filter_map(|queue| queue.queue_size())
+                .max()
+                .unwrap_or(0),
+            dropped_messages: self.dropped_messages.load(Ordering::Relaxed),
+            queued_messages: limiter.queued_messages,
+            max_limit_delay: limiter.max_delay,
+        }
+    }
+
+    fn node_queue_size(&self, node: &NodeIdType) -> usize {
+        self.queues
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((client_node, _), _)| client_node == node)
+            .filter_map(|(_, queue)| queue.queue_size())
+            .sum()
+    }
+
+    fn set_limits(&self, limits: ChannelLimits) {
+        self.limiter.lock().unwrap().limits = limits;
+    }
 }
@@ -6,20 +6,24 @@
 use std::{
     fmt::Debug,
     sync::{
-        Arc, Mutex,
+        Arc, Mutex, Weak,
+        atomic::{AtomicUsize, Ordering},
         mpsc::{Receiver, Sender},
     },
 };
 
-use crate::time_ordered_data::TimeOrderedData;
+use crate::{pub_sub::channel::MessageRetention, time_ordered_data::TimeOrderedData};
 
 /// Bidirectional pub/sub client with delayed, time-ordered reception.
 pub struct Client<MessageType: Clone + Default> {
     sender: Sender<(MessageType, f32)>,
     receiver: Arc<Mutex<Receiver<(MessageType, f32)>>>,
     reception_delay: f32,
-    message_buffer: Mutex<TimeOrderedData<MessageType>>,
+    message_buffer: Arc<Mutex<TimeOrderedData<MessageType>>>,
     time_round: f32,
+    /// Pending message counters of the channel and of the node of the client, decreased when a
+    /// message is received.
+    pending_messages: PendingCounters,
 }
 
 impl<MessageType: Clone + Default> Client<MessageType> {
@@ -28,13 +32,29 @@ impl<MessageType: Clone + Default> Client<MessageType> {
         receiver: Receiver<(MessageType, f32)>,
         reception_delay: f32,
         time_round: f32,
+        pending_messages: Arc<AtomicUsize>,
+        node_pending_messages: Arc<AtomicUsize>,
     ) -> Self {
         Self {
             sender,
             receiver: Arc::new(Mutex::new(receiver)),
             reception_delay,
-            message_buffer: Mutex::new(TimeOrderedData::new(time_round)),
+            message_buffer: Arc::new(Mutex::new(TimeOrderedData::new(time_round))),
             time_round,
+            pending_messages: PendingCounters {
+                channel: pending_messages,
+                node: node_pending_messages,
+            },
+        }
+    }
+
+    /// Weak handle on the message queue, for the garbage collection of the channel.
+    pub(crate) fn queue_handle(&self) -> ClientQueueHandle<MessageType> {
+        ClientQueueHandle {
+            receiver: Arc::downgrade(&self.receiver),
+            message_buffer: Arc::downgrade(&self.message_buffer),
+            reception_delay: self.reception_delay,
+            pending_messages: self.pending_messages.clone(),
         }
     }
 
@@ -69,6 +89,7 @@ impl<MessageType: Clone + Default> Client<MessageType> {
             && min_time - time <= self.time_round
         {
             let message = message_buffer.remove(min_time).unwrap().1;
+            self.pending_messages.sub(1);
             return Some(message);
         }
         None
@@ -83,6 +104,7 @@ impl<MessageType: Clone + Default> Client<MessageType> {
             && min_time - time <= self.time_round
         {
             let message = message_buffer.remove(min_time).unwrap().1;
+            self.pending_messages.sub(1);
             return message;
         }
         loop {
@@ -91,6 +113,7 @@ impl<MessageType: Clone + Default> Client<MessageType> {
                     continue;
                 }
                 if msg_time + self.reception_delay - time <= self.time_round {
+                    self.pending_messages.sub(1);
                     return message;
                 } else {
                     message_buffer.insert(msg_time + self.reception_delay, message, false);
@@ -107,6 +130,76 @@ impl<MessageType: Clone + Default> Client<MessageType> {
     }
 }
 
+/// Weak handle on the message queue of a [`Client`], kept by the channel.
+///
+/// Locks are only tried: a client currently receiving is skipped.
+pub(crate) struct ClientQueueHandle<MessageType> {
+    receiver: Weak<Mutex<Receiver<(MessageType, f32)>>>,
+    message_buffer: Weak<Mutex<TimeOrderedData<MessageType>>>,
+    reception_delay: f32,
+    pending_messages: PendingCounters,
+}
+
+/// Pending message counters updated by a client.
+#[derive(Clone)]
+struct PendingCounters {
+    /// Counter of the channel.
+    channel: Arc<AtomicUsize>,
+    /// Counter of the node of the client, over the channels of the broker.
+    node: Arc<AtomicUsize>,
+}
+
+impl PendingCounters {
+    /// Count `count` messages as received or dropped.
+    fn sub(&self, count: usize) {
+        self.channel.fetch_sub(count, Ordering::Relaxed);
+        self.node.fetch_sub(count, Ordering::Relaxed);
+    }
+}
+
+impl<MessageType> ClientQueueHandle<MessageType> {
+    /// Move the messages received by the client into its buffer and apply `f` on the buffer.
+    ///
+    /// Returns `None` if the client was dropped or is currently busy.
+    fn with_buffer<R>(&self, f: impl FnOnce(&mut TimeOrderedData<MessageType>) -> R) -> Option<R> {
+        let message_buffer = self.message_buffer.upgrade()?;
+        let receiver = self.receiver.upgrade()?;
+        let mut message_buffer = message_buffer.try_lock().ok()?;
+        let receiver = receiver.try_lock().ok()?;
+        while let Ok((message, msg_time)) = receiver.try_recv() {
+            if msg_time >= 0. {
+                message_buffer.insert(msg_time + self.reception_delay, message, false);
+            }
+        }
+        Some(f(&mut message_buffer))
+    }
+
+    /// Number of messages waiting for the client.
+    pub fn queue_size(&self) -> Option<usize> {
+        self.with_buffer(|message_buffer| message_buffer.len())
+    }
+
+    /// Drop the messages not kept by `retention` at simulation `time`, and return the number of
+    /// messages dropped.
+    pub fn collect_garbage(&self, time: f32, retention: &MessageRetention) -> usize {
+        let dropped = self
+            .with_buffer(|message_buffer| {
+                let mut dropped = 0;
+                if let Some(max_age) = retention.max_age {
+                    dropped += message_buffer.remove_before_time(time - max_age);
+                }
+                if let Some(max_queue_size) = retention.max_queue_size {
+                    dropped += message_buffer
+                        .remove_oldest(message_buffer.len().saturating_sub(max_queue_size));
+                }
+                dropped
+            })
+            .unwrap_or(0);
+        self.pending_messages.sub(dropped);
+        dropped
+    }
+}
+
 impl<MessageType> Debug for Client<MessageType>
 where
     MessageType: Clone + Default,
@@ -125,5 +218,17 @@ where
 impl<MessageType: Clone + Default> Drop for Client<MessageType> {
     fn drop(&mut self) {
         let _ = self.sender.send((MessageType::default(), -1.));
+        // Messages never received are not pending anymore
+        if let (Ok(message_buffer), Ok(receiver)) =
+            (self.message_buffer.lock(), self.receiver.lock())
+        {
+            let mut unreceived = message_buffer.len();
+            while let Ok((_, msg_time)) = receiver.try_recv() {
+                if msg_time >= 0. {
+                    unreceived += 1;
+                }
+            }
+            self.pending_messages.sub(unreceived);
+        }
     }
 }
@@ -22,6 +22,12 @@ pub use broker::PathBroker;
 pub use broker::PathKey;
 /// Channel implementation used by brokers.
 pub use channel::Channel;
+/// Transmission limits of a channel.
+pub use channel::ChannelLimits;
+/// Queue metrics of a channel.
+pub use channel::ChannelStats;
+/// Retention of the messages waiting for slow subscribers.
+pub use channel::MessageRetention;
 /// Single-channel client endpoint.
 pub use client::Client;
 /// Generic multi-channel client.
@@ -30,6 +36,9 @@ pub use multi_client::MultiClient;
 pub use multi_client::MultiClientTrait;
 /// Path-key based multi-channel client.
 pub use multi_client::PathMultiClient;
+/// Clients of the pattern subscriptions of a multi-client.
+pub use multi_client::PatternClients;
+pub use multi_client::ProcessingLoad;
 
 /// Type alias for per-client delivery condition predicates.
 pub type ConditionType<ConditionArgType> = fn(ConditionArgType, ConditionArgType) -> bool;
@@ -41,13 +50,14 @@ mod tests {
     use std::{
         collections::HashMap,
         str::FromStr,
-        sync::{Arc, Barrier, Mutex},
+        sync::{Arc, Barrier, Mutex, RwLock},
         thread,
     };
 
     use crate::pub_sub::{
-        Broker, BrokerTrait, BrokerTraitExtended, PathBroker, PathKey,
-        broker::BrokerTraitProcessing,
+        Broker, BrokerTrait, BrokerTraitExtended, ChannelLimits, MessageRetention,
+        MultiClientTrait, PathBroker, PathKey, PathMultiClient, ProcessingLoad,
+        broker::BrokerTraitProcessing, channel::ChannelProcessing,
     };
 
     #[test]
@@ -204,6 +214,143 @@ mod tests {
         handle3.join().unwrap();
     }
 
+    #[test]
+    fn pending_messages_count() {
+        let mut broker = Broker::<String, i32, String, u8>::new(0.1);
+        broker.add_channel("hello".to_string());
+        let mut channel = broker.get_channel(&"hello".to_string()).unwrap();
+        let sender = channel.client("sender".to_string(), 0.0);
+        let receiver = channel.client("receiver".to_string(), 0.0);
+        let other_receiver = channel.client("other".to_string(), 0.0);
+
+        sender.send(3, 1.);
+        sender.send(4, 2.);
+        broker.process_messages(None);
+        assert_eq!(broker.pending_messages(), 4);
+
+        assert_eq!(receiver.try_receive(1.), Some(3));
+        assert_eq!(broker.pending_messages(), 3);
+        drop(other_receiver);
+        assert_eq!(broker.pending_messages(), 1);
+        assert_eq!(receiver.try_receive(2.), Some(4));
+        assert_eq!(broker.pending_messages(), 0);
+    }
+
+    #[test]
+    fn node_queue_size() {
+        let mut broker = Broker::<String, i32, String, u8>::new(0.1);
+        broker.add_channel("hello".to_string());
+        let mut channel = broker.get_channel(&"hello".to_string()).unwrap();
+        let sender = channel.client("sender".to_string(), 0.0);
+        let receiver = channel.client("receiver".to_string(), 0.0);
+
+        sender.send(3, 1.);
+        sender.send(4, 2.);
+        broker.process_messages(None);
+        assert_eq!(broker.node_queue_size(&"receiver".to_string()), 2);
+        assert_eq!(broker.node_queue_size(&"sender".to_string()), 0);
+
+        assert_eq!(receiver.try_receive(1.), Some(3));
+        assert_eq!(broker.node_queue_size(&"receiver".to_string()), 1);
+
+        // The counter covers all the channels, and can be read without the broker
+        let counter = broker.node_queue_counter(&"receiver".to_string());
+        broker.add_channel("world".to_string());
+        let mut other_channel = broker.get_channel(&"world".to_string()).unwrap();
+        let other_sender = other_channel.client("sender".to_string(), 0.0);
+        let other_receiver = other_channel.client("receiver".to_string(), 0.0);
+        other_sender.send(5, 1.);
+        broker.process_messages(None);
+        assert_eq!(counter.load(std::sync::atomic::Ordering::Relaxed), 2);
+        assert_eq!(other_channel.node_queue_size(&"receiver".to_string()), 1);
+
+        // Messages never received are not counted anymore
+        drop(other_receiver);
+        assert_eq!(broker.node_queue_size(&"receiver".to_string()), 1);
+    }
+
+    #[test]
+    fn garbage_collection() {
+        let mut broker = Broker::<String, i32, String, u8>::new(0.1);
+        broker.set_retention(MessageRetention {
+            max_age: Some(2.),
+            max_queue_size: Some(2),
+        });
+        broker.add_channel("hello".to_string());
+        let mut channel = broker.get_channel(&"hello".to_string()).unwrap();
+        let sender = channel.client("sender".to_string(), 0.0);
+        let slow_receiver = channel.client("slow".to_string(), 0.0);
+
+        for i in 0..4 {
+            sender.send(i, i as f32);
+        }
+        broker.process_messages(None);
+        assert_eq!(broker.channel_stats()[0].1.max_queue_size, 4);
+
+        // Message 0 is too old, message 1 exceeds the queue size
+        assert_eq!(broker.collect_garbage(3.), 2);
+        // Message 2 is too old
+        assert_eq!(broker.collect_garbage(4.5), 1);
+        let stats = &broker.channel_stats()[0].1;
+        assert_eq!(stats.pending_messages, 1);
+        assert_eq!(stats.dropped_messages, 3);
+        assert_eq!(slow_receiver.try_receive(4.5), Some(3));
+    }
+
+    #[test]
+    fn channel_limits() {
+        let mut broker = Broker::<String, i32, String, u8>::new(0.1);
+        broker.set_channel_limits(
+            &"hello".to_string(),
+            ChannelLimits {
+                bandwidth: Some(10.),
+                max_rate: Some(0.5),
+            },
+        );
+        // The message is its own size in bytes
+        broker.set_message_size(|message| *message as usize);
+        broker.add_channel("hello".to_string());
+        let mut channel = broker.get_channel(&"hello".to_string()).unwrap();
+        let sender = channel.client("sender".to_string(), 0.0);
+        let receiver = channel.client("receiver".to_string(), 0.0);
+
+        // Transmitted in 1 s, then 2 s after the rate limit
+        sender.send(10, 0.);
+        sender.send(20, 0.);
+        broker.process_messages(None);
+        assert_eq!(receiver.try_receive(0.5), None);
+        assert_eq!(receiver.try_receive(1.5), Some(10));
+        assert_eq!(receiver.try_receive(3.5), None);
+        assert_eq!(receiver.try_receive(4.5), Some(20));
+        let stats = &broker.channel_stats()[0].1;
+        assert_eq!(stats.queued_messages, 1);
+        assert_eq!(stats.max_limit_delay, 4.);
+    }
+
+    #[test]
+    fn delivery_model() {
+        let mut broker = Broker::<String, i32, String, i32>::new(0.1);
+        // Drop the messages to node 2, delay the others by 1 s
+        broker.add_channel_with_delivery("hello".to_string(), |_, to: i32, _| {
+            (to != 2).then_some(1.)
+        });
+        let mut channel = broker.get_channel(&"hello".to_string()).unwrap();
+        let sender = channel.client("sender".to_string(), 0.0);
+        let receiver1 = channel.client("receiver1".to_string(), 0.5);
+        let receiver2 = channel.client("receiver2".to_string(), 0.0);
+
+        sender.send(3, 1.);
+        let node_states = HashMap::from([
+            ("sender".to_string(), 0),
+            ("receiver1".to_string(), 1),
+            ("receiver2".to_string(), 2),
+        ]);
+        broker.process_messages(Some(&node_states));
+        assert_eq!(receiver1.try_receive(2.), None);
+        assert_eq!(receiver1.try_receive(2.5), Some(3));
+        assert_eq!(receiver2.try_receive(10.), None);
+    }
+
     #[test]
     fn late_reception() {
         let broker = Arc::new(Mutex::new(Broker::<String, u8, String, u8>::new(0.1)));
@@ -386,6 +533,21 @@ mod tests {
         handle3.join().unwrap();
     }
 
+    #[test]
+    fn processing_load() {
+        let mut load = ProcessingLoad::new(0.5, 0.01);
+        assert!(!load.is_busy(1.));
+        load.consume(1., 1.);
+        assert!(load.is_busy(1.2));
+        assert!(!load.is_busy(1.5));
+        // Second message available at 1.2, processed when the first one is done
+        load.consume(1.5, 1.2);
+        assert_eq!(load.busy_until, 2.);
+        assert_eq!(load.processed_messages, 2);
+        assert!((load.total_delay - 0.3).abs() < 1e-6);
+        assert!((load.max_delay - 0.3).abs() < 1e-6);
+    }
+
     #[test]
     fn path_broker_meta() {
         let broker = Arc::new(Mutex::new(PathBroker::<i8, i8, u8>::new(0.1)));
@@ -464,4 +626,54 @@ mod tests {
         handle2.join().unwrap();
         handle3.join().unwrap();
     }
+    #[test]
+    fn path_broker_pattern() {
+        let key = |path: &str| PathKey::from_str(path).unwrap();
+        assert!(key("/sensors/+/gnss").matches(&key("/sensors/robot1/gnss")));
+        assert!(!key("/sensors/+/gnss").matches(&key("/sensors/robot1/lidar")));
+        assert!(key("/robot1/#").matches(&key("/robot1/a/b")));
+        assert!(!key("/robot1/#").matches(&key("/robot2/a")));
+
+        let broker = Arc::new(RwLock::new(PathBroker::<i32, String, u8>::new(0.1)));
+        broker
+            .write()
+            .unwrap()
+            .add_channel(key("/sensors/robot1/gnss"));
+        let mut unit = PathMultiClient::new(broker.clone(), "unit".to_string(), 0.0, key("/unit"));
+        unit.subscribe(&key("/sensors/+/gnss"));
+        // Channels created after the subscription
+        broker
+            .write()
+            .unwrap()
+            .add_channel(key("/sensors/robot2/gnss"));
+        broker
+            .write()
+            .unwrap()
+            .add_channel(key("/sensors/robot2/lidar"));
+
+        for (channel, message) in [
+            ("/sensors/robot1/gnss", 1),
+            ("/sensors/robot2/gnss", 2),
+            ("/sensors/robot2/lidar", 3),
+        ] {
+            let client = broker
+                .write()
+                .unwrap()
+                .subscribe_to(&key(channel), "robot".to_string(), 0.0)
+                .unwrap();
+            client.send(message, 1.);
+        }
+        broker.read().unwrap().process_messages(None);
+
+        let mut received = vec![unit.try_receive(1.).unwrap(), unit.try_receive(1.).unwrap()];
+        received.sort_by_key(|(_, message)| *message);
+        assert_eq!(
+            received,
+            vec![
+                (key("/sensors/robot1/gnss"), 1),
+                (key("/sensors/robot2/gnss"), 2)
+            ]
+        );
+        assert_eq!(unit.try_receive(1.), None);
+    }
 }
@@ -3,12 +3,14 @@
 //! This module defines:
 //! - [`MultiClientTrait`], a common interface for clients handling multiple subscribed channels,
 //! - [`MultiClient`], a generic key-based implementation,
-//! - [`PathMultiClient`], a [`PathKey`]-aware implementation with relative key transformation.
+//! - [`PathMultiClient`], a [`PathKey`]-aware implementation with relative key transformation and
+//!   wildcard subscriptions,
+//! - [`ProcessingLoad`], a simulated handling time cost of the received messages.
 
 use std::{
     collections::HashMap,
     fmt::{Debug, Display},
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 #[cfg(feature = "debug_mode")]
@@ -17,6 +19,10 @@ use log::warn;
 
 use crate::pub_sub::{BrokerTrait, Client, PathKey};
 
+/// Clients created by the pattern subscriptions of a multi-client, shared with the broker which
+/// adds the clients of the channels created after the subscription.
+pub type PatternClients<KeyType, MessageType> = Arc<Mutex<Vec<(KeyType, Client<MessageType>)>>>;
+
 /// Interface for clients that manage subscriptions over multiple keys.
 pub trait MultiClientTrait<KeyType, MessageType, NodeIdType>: Send + Sync + Debug
 where
@@ -49,6 +55,59 @@ where
     fn transform_key(&self, key: &KeyType) -> KeyType {
         key.clone()
     }
+
+    /// Returns the clients of the pattern subscriptions, see
+    /// [`BrokerTrait::subscribe_to_pattern`]. `None` if pattern subscriptions are not supported.
+    fn pattern_clients(&self) -> Option<PatternClients<KeyType, MessageType>> {
+        None
+    }
+}
+
+/// Simulated handling time of the received messages, shared by the multi-clients of a node.
+///
+/// Each received message keeps the node busy during `cost` (simulation time): the next message
+/// is only delivered when the node is free again. The delays induced by the processing of the
+/// previous messages are accumulated.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingLoad {
+    /// Handling time of one message.
+    pub cost: f32,
+    /// Time until which the node is processing messages.
+    pub busy_until: f32,
+    /// Number of messages processed.
+    pub processed_messages: usize,
+    /// Sum of the delays induced by the processing of the previous messages.
+    pub total_delay: f32,
+    /// Largest delay induced to a message.
+    pub max_delay: f32,
+    time_round: f32,
+}
+
+impl ProcessingLoad {
+    /// Creates a new load with the given per-message `cost`.
+    ///
+    /// `time_round` is the time tolerance used to compare the simulation times.
+    pub fn new(cost: f32, time_round: f32) -> Self {
+        Self {
+            cost,
+            time_round,
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` if the node is still processing messages at `time`.
+    pub fn is_busy(&self, time: f32) -> bool {
+        self.busy_until - time > self.time_round
+    }
+
+    /// Processes a message available since `available_time`, received at `time`.
+    pub fn consume(&mut self, time: f32, available_time: f32) {
+        let delay = (time - available_time).max(0.);
+        self.total_delay += delay;
+        self.max_delay = self.max_delay.max(delay);
+        self.processed_messages += 1;
+        self.busy_until = time.max(self.busy_until) + self.cost;
+    }
 }
 
 #[derive(Debug)]
@@ -60,9 +119,11 @@ where
     NodeIdType: std::hash::Hash + Eq + Clone + Send + Sync + 'static,
 {
     clients: HashMap<KeyType, Client<MessageType>>,
+    pattern_clients: PatternClients<KeyType, MessageType>,
     broker: Arc<RwLock<dyn BrokerTrait<KeyType, MessageType, NodeIdType>>>,
     reception_delay: f32,
     node_id: NodeIdType,
+    processing_load: Option<Arc<Mutex<ProcessingLoad>>>,
 }
 
 impl<KeyType, MessageType, NodeIdType> MultiClient<KeyType, MessageType, NodeIdType>
@@ -79,9 +140,29 @@ where
     ) -> Self {
         Self {
             clients: HashMap::new(),
+            pattern_clients: Arc::new(Mutex::new(Vec::new())),
             broker,
             reception_delay,
             node_id,
+            processing_load: None,
+        }
+    }
+
+    /// Makes the received messages consume time of the shared `processing_load`.
+    pub fn set_processing_load(&mut self, processing_load: Arc<Mutex<ProcessingLoad>>) {
+        self.processing_load = Some(processing_load);
+    }
+
+    /// Subscribes to all the channels matching `pattern`, including the channels created later,
+    /// see [`BrokerTrait::subscribe_to_pattern`].
+    pub fn subscribe_pattern(&mut self, pattern: &KeyType, reception_delay: f32) {
+        if let Err(e) = self.broker.write().unwrap().subscribe_to_pattern(
+            pattern,
+            self.node_id.clone(),
+            reception_delay,
+            self.pattern_clients.clone(),
+        ) {
+            warn!("Failed to subscribe to pattern '{}': {e}", pattern);
         }
     }
 }
@@ -147,8 +228,21 @@ where
     }
 
     fn try_receive(&self, time: f32) -> Option<(KeyType, MessageType)> {
-        for (key, client) in &self.clients {
+        let mut processing_load = self.processing_load.as_ref().map(|l| l.lock().unwrap());
+        if processing_load.as_ref().is_some_and(|l| l.is_busy(time)) {
+            return None;
+        }
+        let pattern_clients = self.pattern_clients.lock().unwrap();
+        for (key, client) in self
+            .clients
+            .iter()
+            .chain(pattern_clients.iter().map(|(key, client)| (key, client)))
+        {
+            let available_time = client.next_message_time();
             if let Some(message) = client.try_receive(time) {
+                if let Some(processing_load) = processing_load.as_mut() {
+                    processing_load.consume(time, available_time.unwrap_or(time));
+                }
                 return Some((key.clone(), message));
             }
         }
@@ -157,25 +251,53 @@ where
 
     fn next_message_time(&self) -> Option<f32> {
         let mut next_time = None;
-        for client in self.clients.values() {
+        let pattern_clients = self.pattern_clients.lock().unwrap();
+        for client in self
+            .clients
+            .values()
+            .chain(pattern_clients.iter().map(|(_, client)| client))
+        {
             if let Some(client_next_time) = client.next_message_time() {
                 next_time = Some(client_next_time.min(next_time.unwrap_or(client_next_time)));
             }
         }
-        next_time
+        // Pending messages wait for the end of the processing of the previous ones
+        match &self.processing_load {
+            Some(processing_load) => {
+                next_time.map(|t| t.max(processing_load.lock().unwrap().busy_until))
+            }
+            None => next_time,
+        }
     }
 
     fn subscribed_keys(&self) -> Vec<KeyType> {
-        self.clients.keys().cloned().collect()
+        self.clients
+            .keys()
+            .cloned()
+            .chain(
+                self.pattern_clients
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(key, _)| key.clone()),
+            )
+            .collect()
     }
 
     fn node_id(&self) -> &NodeIdType {
         &self.node_id
     }
+
+    fn pattern_clients(&self) -> Option<PatternClients<KeyType, MessageType>> {
+        Some(self.pattern_clients.clone())
+    }
 }
 
 #[derive(Debug)]
 /// Path-based multi-channel client using [`PathKey`] hierarchy rules.
+///
+/// Subscribing to a pattern key (see [`PathKey::matches`]), like `/sensors/+/gnss` or
+/// `/robot1/#`, subscribes to all the matching channels, including the ones created later.
 pub struct PathMultiClient<MessageType, NodeIdType>
 where
     MessageType: Clone + Send + 'static + Default + Debug + Sync,
@@ -205,6 +327,11 @@ where
             base_path: client_path,
         }
     }
+
+    /// Makes the received messages consume time of the shared `processing_load`.
+    pub fn set_processing_load(&mut self, processing_load: Arc<Mutex<ProcessingLoad>>) {
+        self.multi_client.set_processing_load(processing_load);
+    }
 }
 
 impl<MessageType, NodeIdType> MultiClientTrait<PathKey, MessageType, NodeIdType>
@@ -219,12 +346,21 @@ where
 
     fn subscribe(&mut self, key: &PathKey) {
         let key = self.transform_key(key);
-        self.multi_client.subscribe(&key);
+        if key.is_pattern() {
+            let reception_delay = self.multi_client.reception_delay;
+            self.multi_client.subscribe_pattern(&key, reception_delay);
+        } else {
+            self.multi_client.subscribe(&key);
+        }
     }
 
     fn subscribe_instantaneous(&mut self, key: &PathKey) {
         let key = self.transform_key(key);
-        self.multi_client.subscribe_instantaneous(&key);
+        if key.is_pattern() {
+            self.multi_client.subscribe_pattern(&key, 0.0);
+        } else {
+            self.multi_client.subscribe_instantaneous(&key);
+        }
     }
 
     fn send(&self, key: &PathKey, message: MessageType, time: f32) {
@@ -255,4 +391,8 @@ where
             self.base_path.join(key)
         }
     }
+
+    fn pattern_clients(&self) -> Option<PatternClients<PathKey, MessageType>> {
+        self.multi_client.pattern_clients()
+    }
 }
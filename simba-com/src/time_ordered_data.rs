@@ -7,6 +7,44 @@ use core::slice::{Iter, IterMut};
 use std::iter::Skip;
 use std::vec::Vec;
 
+/// Retention policy bounding the size of a [`TimeOrderedData`].
+///
+/// The window is relative to the most recent data. Out of the window, one data every
+/// `keyframe_period` seconds can be kept, to keep a coarse history.
+///
+/// ## Example
+/// ```
+/// use simba_com::time_ordered_data::{RetentionPolicy, TimeOrderedData};
+///
+/// let mut tod = TimeOrderedData::<usize>::new(0.001);
+/// tod.set_retention(Some(RetentionPolicy {
+///     max_age: Some(1.),
+///     max_entries: None,
+///     keyframe_period: Some(5.),
+/// }));
+/// for i in 0..100 {
+///     tod.insert(i as f32 * 0.1, i, true);
+/// }
+/// // Keyframes at 0 and 5 s, window from 8.9 s to 9.9 s.
+/// assert_eq!(tod.len(), 2 + 11);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionPolicy {
+    /// Keep the data of the last `max_age` seconds. `None` for no time limit.
+    pub max_age: Option<f32>,
+    /// Keep at most the `max_entries` most recent data, keyframes excluded. `None` for no limit.
+    pub max_entries: Option<usize>,
+    /// Out of the window, keep one data every `keyframe_period` seconds. `None` for no keyframes.
+    pub keyframe_period: Option<f32>,
+}
+
+impl RetentionPolicy {
+    /// Return whether the policy bounds the data (`max_age` or `max_entries` set).
+    pub fn is_bounded(&self) -> bool {
+        self.max_age.is_some() || self.max_entries.is_some()
+    }
+}
+
 /// Data structure to store ordered timed data.
 ///
 /// The generic is the Type to be stored. For now, the time is stored
@@ -38,6 +76,10 @@ pub struct TimeOrderedData<T> {
     /// and is not checked after !
     data: Vec<(f32, T)>,
     time_round: f32,
+    /// Retention policy, applied at each insertion.
+    retention: Option<RetentionPolicy>,
+    /// Number of keyframes at the beginning of `data`, before the retention window.
+    nb_keyframes: usize,
 }
 
 impl<T> TimeOrderedData<T> {
@@ -46,9 +88,78 @@ impl<T> TimeOrderedData<T> {
         Self {
             data: Vec::new(),
             time_round,
+            retention: None,
+            nb_keyframes: 0,
         }
     }
 
+    /// Set the retention policy, applied at each insertion. `None` to keep all the data.
+    ///
+    /// The policy is applied immediately.
+    pub fn set_retention(&mut self, retention: Option<RetentionPolicy>) {
+        self.retention = retention.filter(|policy| policy.is_bounded());
+        self.nb_keyframes = 0;
+        self.prune();
+    }
+
+    /// Current retention policy.
+    pub fn retention(&self) -> Option<&RetentionPolicy> {
+        self.retention.as_ref()
+    }
+
+    /// Apply the retention policy, and return the number of data removed.
+    ///
+    /// Done automatically at each insertion.
+    pub fn prune(&mut self) -> usize {
+        let retention = match &self.retention {
+            Some(retention) => retention,
+            None => return 0,
+        };
+        let mut cut = self.nb_keyframes;
+        if let Some(max_age) = retention.max_age
+            && let Some(&(newest_time, _)) = self.data.last()
+        {
+            let window_start = newest_time - max_age - self.time_round / 2.;
+            cut = cut.max(
+                self.data[self.nb_keyframes..].partition_point(|(t, _)| *t < window_start)
+                    + self.nb_keyframes,
+            );
+        }
+        if let Some(max_entries) = retention.max_entries {
+            cut = cut.max(self.data.len().saturating_sub(max_entries));
+        }
+        if cut <= self.nb_keyframes {
+            return 0;
+        }
+
+        let mut last_keyframe = match self.nb_keyframes {
+            0 => None,
+            n => Some(self.data[n - 1].0),
+        };
+        let keyframe_period = retention.keyframe_period;
+        let time_round = self.time_round;
+        let nb_keyframes = self.nb_keyframes;
+        let mut nb_new_keyframes = 0;
+        let mut index = 0;
+        self.data.retain(|(t, _)| {
+            let i = index;
+            index += 1;
+            if i < nb_keyframes || i >= cut {
+                return true;
+            }
+            let keep = keyframe_period.is_some_and(|period| {
+                last_keyframe.is_none_or(|last| *t >= last + period - time_round / 2.)
+            });
+            if keep {
+                last_keyframe = Some(*t);
+                nb_new_keyframes += 1;
+            }
+            keep
+        });
+        self.nb_keyframes += nb_new_keyframes;
+        cut - nb_keyframes - nb_new_keyframes
+    }
+
     /// Find the index equal or just after the required time.
     ///
     /// This function was primarly made for insertion. So the position returned
@@ -95,10 +206,18 @@ impl<T> TimeOrderedData<T> {
                 self.data[pos] = (time, data);
             } else {
                 self.data.insert(pos + 1, (time, data));
+                if pos < self.nb_keyframes {
+                    self.nb_keyframes += 1;
+                }
             }
         } else {
             self.data.insert(pos, (time, data));
+            // Data inserted before the window (back in time) is kept as a keyframe
+            if pos < self.nb_keyframes {
+                self.nb_keyframes += 1;
+            }
         }
+        self.prune();
     }
 
     /// Get a mutable reference on the data just before or at the given `time`.
@@ -283,10 +402,45 @@ impl<T> TimeOrderedData<T> {
         if !exact {
             return None;
         }
+        if pos < self.nb_keyframes {
+            self.nb_keyframes -= 1;
+        }
 
         Some(self.data.remove(pos))
     }
 
+    /// Remove all the data strictly before `time` (within time_round) and return the number of
+    /// data removed.
+    pub fn remove_before_time(&mut self, time: f32) -> usize {
+        let count = self
+            .data
+            .partition_point(|(t, _)| *t < time - self.time_round / 2.);
+        self.remove_oldest(count)
+    }
+
+    /// Remove all the data in `[from, to[` (within time_round) and return the number of data
+    /// removed.
+    pub fn remove_between_times(&mut self, from: f32, to: f32) -> usize {
+        let begin = self
+            .data
+            .partition_point(|(t, _)| *t < from - self.time_round / 2.);
+        let end = self
+            .data
+            .partition_point(|(t, _)| *t < to - self.time_round / 2.)
+            .max(begin);
+        self.nb_keyframes -= self.nb_keyframes.clamp(begin, end) - begin;
+        self.data.drain(begin..end);
+        end - begin
+    }
+
+    /// Remove the `count` oldest data and return the number of data removed.
+    pub fn remove_oldest(&mut self, count: usize) -> usize {
+        let count = count.min(self.data.len());
+        self.data.drain(..count);
+        self.nb_keyframes = self.nb_keyframes.saturating_sub(count);
+        count
+    }
+
     /// Size of the data structure.
     pub fn len(&self) -> usize {
         self.data.len()
@@ -324,7 +478,7 @@ impl<T> Default for TimeOrderedData<T> {
 
 #[cfg(test)]
 mod tests {
-    use super::TimeOrderedData;
+    use super::{RetentionPolicy, TimeOrderedData};
 
     #[test]
     fn new() {
@@ -433,6 +587,22 @@ mod tests {
         assert_eq!(data.1, String::from("Hello2"));
     }
 
+    #[test]
+    fn remove_between_times() {
+        let mut tod = TimeOrderedData::<usize>::new(0.001);
+        for i in 0..10 {
+            tod.insert(i as f32, i, true);
+        }
+        assert_eq!(tod.remove_between_times(2., 5.), 3);
+        assert_eq!(
+            tod.iter().map(|(_, i)| *i).collect::<Vec<_>>(),
+            vec![0, 1, 5, 6, 7, 8, 9]
+        );
+        assert_eq!(tod.remove_between_times(5., 2.), 0);
+        assert_eq!(tod.remove_between_times(20., 30.), 0);
+        assert_eq!(tod.len(), 7);
+    }
+
     #[test]
     fn get_data_before_and_equal_time() {
         let mut tod = TimeOrderedData::<String>::new(0.001);
@@ -605,4 +775,43 @@ mod tests {
         );
         assert_eq!(iterator.next(), None);
     }
+
+    #[test]
+    fn retention_max_entries() {
+        let mut tod = TimeOrderedData::<usize>::new(0.001);
+        tod.set_retention(Some(RetentionPolicy {
+            max_age: None,
+            max_entries: Some(5),
+            keyframe_period: None,
+        }));
+        for i in 0..20 {
+            tod.insert(i as f32, i, true);
+        }
+        assert_eq!(tod.len(), 5);
+        assert_eq!(tod.min_time().unwrap(), (15., &15));
+        assert_eq!(tod.max_time().unwrap(), (19., &19));
+    }
+
+    #[test]
+    fn retention_keyframes() {
+        let mut tod = TimeOrderedData::<usize>::new(0.001);
+        tod.set_retention(Some(RetentionPolicy {
+            max_age: Some(2.),
+            max_entries: None,
+            keyframe_period: Some(4.),
+        }));
+        for i in 0..=20 {
+            tod.insert(i as f32, i, true);
+        }
+        let times: Vec<f32> = tod.iter().map(|(t, _)| *t).collect();
+        assert_eq!(times, vec![0., 4., 8., 12., 16., 18., 19., 20.]);
+
+        // Back in time insertion out of the window is kept.
+        tod.insert(1., 1, true);
+        assert_eq!(tod.get_data_at_time(1.), Some((1., &1)));
+        tod.insert(21., 21, true);
+        assert_eq!(tod.min_time().unwrap().0, 0.);
+        assert_eq!(tod.max_time().unwrap().0, 21.);
+        assert!(tod.get_data_at_time(18.).is_none());
+    }
 }
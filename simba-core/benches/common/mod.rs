@@ -0,0 +1,30 @@
+//! Shared helpers for the benchmarks: loading the representative configurations.
+
+use std::path::{Path, PathBuf};
+
+use simba::simulator::SimulatorConfig;
+
+/// Path of a benchmark configuration, relative to `benches/configs`.
+pub fn config_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("benches")
+        .join("configs")
+        .join(name)
+}
+
+/// Load the fleet configuration, with `nb_robots` robots (the 4 robots of the file are
+/// duplicated with new names if needed) and the given `max_time`.
+pub fn fleet_config(nb_robots: usize, max_time: f32) -> SimulatorConfig {
+    let mut config = SimulatorConfig::load_from_path(&config_path("fleet.yaml"))
+        .expect("Cannot load the benchmark configuration");
+    let base_robots = config.robots.clone();
+    config.robots = (0..nb_robots)
+        .map(|i| {
+            let mut robot = base_robots[i % base_robots.len()].clone();
+            robot.name = format!("robot{}", i + 1);
+            robot
+        })
+        .collect();
+    config.max_time = max_time;
+    config
+}
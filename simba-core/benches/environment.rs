@@ -0,0 +1,59 @@
+//! Benchmark of the environment observability queries, on a grid map with obstructing
+//! landmarks.
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use nalgebra::Vector2;
+use simba::{
+    environment::Environment, utils::determinist_random_variable::DeterministRandomVariableFactory,
+};
+
+mod common;
+
+fn observability(c: &mut Criterion) {
+    // Map of the fleet configuration, the robots are not needed
+    let config = common::fleet_config(0, 0.);
+    let environment = Environment::from_config(
+        &config.environment,
+        &config,
+        &DeterministRandomVariableFactory::default(),
+    )
+    .unwrap();
+    let observer = Vector2::new(0.3, -0.7);
+    let target = Vector2::new(7.2, 6.1);
+
+    let mut group = c.benchmark_group("environment");
+    group.bench_function("observable_landmarks_xray", |b| {
+        b.iter(|| environment.get_observable_landmarks(black_box(&observer), None, 10., None))
+    });
+    group.bench_function("observable_landmarks_obstruction", |b| {
+        b.iter(|| environment.get_observable_landmarks(black_box(&observer), Some(0.5), 10., None))
+    });
+    group.bench_function("observable_landmarks_cached", |b| {
+        b.iter(|| {
+            environment.get_observable_landmarks(
+                black_box(&observer),
+                Some(0.5),
+                10.,
+                Some("bench".to_string()),
+            )
+        })
+    });
+    group.bench_function("target_observable_obstruction", |b| {
+        b.iter(|| {
+            environment.is_target_observable(
+                black_box(&target),
+                Some(0.5),
+                black_box(&observer),
+                Some(0.5),
+                20.,
+                None,
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, observability);
+criterion_main!(benches);
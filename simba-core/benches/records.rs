@@ -0,0 +1,29 @@
+//! Benchmark of the record serialization, as done when saving the results.
+
+use std::hint::black_box;
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use simba::simulator::{Record, Simulator};
+
+mod common;
+
+fn serialization(c: &mut Criterion) {
+    Simulator::init_environment();
+    let mut simulator = Simulator::from_config(&common::fleet_config(4, 5.), None).unwrap();
+    simulator.run().unwrap();
+    let records = simulator.get_records(true);
+    let serialized = serde_json::to_string(&records).unwrap();
+
+    let mut group = c.benchmark_group("records");
+    group.throughput(Throughput::Elements(records.len() as u64));
+    group.bench_function("serialize", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&records)).unwrap())
+    });
+    group.bench_function("deserialize", |b| {
+        b.iter(|| serde_json::from_str::<Vec<Record>>(black_box(&serialized)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, serialization);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+//! Benchmark of the barrier step loop: a full run of a fleet, for several fleet sizes.
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use simba::simulator::Simulator;
+
+mod common;
+
+fn step_loop(c: &mut Criterion) {
+    Simulator::init_environment();
+    let mut group = c.benchmark_group("step_loop");
+    group.sample_size(10);
+    for nb_robots in [4, 16, 64] {
+        let config = common::fleet_config(nb_robots, 5.);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(nb_robots),
+            &config,
+            |b, config| {
+                b.iter_batched(
+                    || Simulator::from_config(config, None).unwrap(),
+                    |mut simulator| simulator.run().unwrap(),
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step_loop);
+criterion_main!(benches);
@@ -17,7 +17,7 @@ use crate::{
     networking::network::Network,
     physics::Physics,
     plugin_api::PluginAPI,
-    simulator::{Record, Simulator, SimulatorAsyncApi, SimulatorConfig},
+    simulator::{AsyncApiConnector, Record, Simulator, SimulatorAsyncApi, SimulatorConfig},
     state_estimators::StateEstimator,
     utils::{
         SharedMutex, SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory,
@@ -95,6 +95,7 @@ pub struct AsyncApiRunner {
     public_api: AsyncApi,
     private_api: AsyncApiServer,
     simulator: SharedMutex<Simulator>,
+    connector: AsyncApiConnector,
     keep_alive_tx: mpsc::Sender<()>,
     keep_alive_rx: SharedMutex<mpsc::Receiver<()>>,
     thread_handle: Option<JoinHandle<()>>,
@@ -121,6 +122,7 @@ impl AsyncApiRunner {
         let (get_records_call, get_records_host) = rfc::make_pair();
         let (keep_alive_tx, keep_alive_rx) = mpsc::channel();
         let simulator_api = simulator.lock().unwrap().get_async_api();
+        let connector = simulator.lock().unwrap().async_api_connector();
         Self {
             public_api: AsyncApi {
                 simulator_api,
@@ -138,6 +140,7 @@ impl AsyncApiRunner {
                 get_records: Arc::new(get_records_host),
             },
             simulator,
+            connector,
             keep_alive_rx: Arc::new(Mutex::new(keep_alive_rx)),
             keep_alive_tx,
             thread_handle: None,
@@ -151,6 +154,21 @@ impl AsyncApiRunner {
         self.public_api.clone()
     }
 
+    /// Connect an additional client to the records and time of the simulator, with its own record
+    /// stream. It can be called during a run. If an access token was set with
+    /// [`Simulator::set_async_api_token`], the `token` should match.
+    ///
+    /// The API given by [`Self::get_api`] is trusted and always connected.
+    pub fn connect(&self, token: Option<&str>) -> SimbaResult<Arc<SimulatorAsyncApi>> {
+        self.connector.connect(token)
+    }
+
+    /// Number of clients connected to the simulator, including the one of [`Self::get_api`] and the
+    /// one used by the simulator to collect its records (when results are enabled).
+    pub fn client_count(&self) -> usize {
+        self.connector.client_count()
+    }
+
     /// Get the inner simulator.
     /// WARNING: for advanced use only
     pub fn get_simulator(&self) -> SharedMutex<Simulator> {
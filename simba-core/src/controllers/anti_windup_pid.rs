@@ -0,0 +1,684 @@
+//! Saturation-aware PID controller.
+//!
+//! [`AntiWindupPID`] is a PID controller with one independent loop per command axis, each
+//! one computing `u = ff + Kp e + I + Kd ḋ` where:
+//! - `ḋ` is the derivative of the error, filtered by a first-order low-pass filter of time
+//!   constant [`PIDAxisConfig::derivative_filter_time_constant`] to limit the noise
+//!   amplification;
+//! - `I` is the integral term, protected from windup when the output is saturated to
+//!   [`PIDAxisConfig::output_limit`] (see [`AntiWindupConfig`]);
+//! - `ff` is the feedforward term: on the longitudinal axis, the target velocity
+//!   (estimated velocity + velocity error) multiplied by
+//!   [`AntiWindupPIDConfig::velocity_feedforward`]. It is 0 for the other axes.
+//!
+//! The axes depend on the robot model:
+//! - Unicycle: longitudinal (on the velocity error) and angular (on the orientation error)
+//!   velocities, converted to wheel speeds;
+//! - Holonomic: longitudinal (on the velocity error), lateral (on the lateral error) and angular
+//!   (on the orientation error) velocities.
+//!
+//! Contrary to [`PID`](super::pid::PID), the output of the longitudinal loop is the velocity
+//! command itself, not an increment of it: without feedforward, the integral term should
+//! compensate the steady velocity.
+
+use crate::{
+    controllers::{Controller, ControllerError, ControllerRecord},
+    node::{
+        Node,
+        capabilities::{Capabilities, CommandKind},
+    },
+    physics::{
+        PhysicsConfig,
+        internal_physics::InternalPhysicConfig,
+        robot_models::{
+            Command, RobotModelConfig, holonomic::HolonomicCommand, unicycle::UnicycleCommand,
+        },
+    },
+    recordable::Recordable,
+    utils::lock_recovery::RecoverRwLock,
+};
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{UIComponent, utils::string_combobox},
+    simulator::SimulatorConfig,
+    utils::enum_tools::ToVec,
+};
+use config_checker::*;
+use log::warn;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Back-calculation parameters, see [`AntiWindupConfig::BackCalculation`].
+#[config_derives]
+pub struct BackCalculationConfig {
+    /// Gain applied to the saturation excess (`saturated - unsaturated` output) fed back to
+    /// the integral term, in 1/s. Usually taken between `Ki/Kp` and `Kd/Kp`.
+    ///
+    /// Default: `1.0`.
+    pub tracking_gain: f32,
+}
+
+impl Check for BackCalculationConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.tracking_gain < 0. {
+            Err(vec![
+                "Back-calculation tracking gain should be positive".to_string(),
+            ])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for BackCalculationConfig {
+    fn default() -> Self {
+        Self { tracking_gain: 1. }
+    }
+}
+
+/// Anti-windup strategy of the integral term, used when the output of an axis is saturated.
+#[config_derives]
+pub enum AntiWindupConfig {
+    /// No protection: the integral keeps accumulating while saturated.
+    None,
+    /// Conditional integration: the integral is frozen while the output is saturated and the
+    /// error would saturate it further.
+    Clamping,
+    /// The saturation excess is fed back to the integral term, which is unwound while saturated.
+    #[check]
+    BackCalculation(BackCalculationConfig),
+}
+
+impl Default for AntiWindupConfig {
+    fn default() -> Self {
+        Self::Clamping
+    }
+}
+
+/// Gains and limits of one axis of the [`AntiWindupPID`].
+#[config_derives]
+pub struct PIDAxisConfig {
+    /// Proportional gain (`Kp`).
+    ///
+    /// Default: `1.0`.
+    pub kp: f32,
+    /// Integral gain (`Ki`).
+    ///
+    /// Default: `0.0`.
+    pub ki: f32,
+    /// Derivative gain (`Kd`).
+    ///
+    /// Default: `0.0`.
+    pub kd: f32,
+    /// Time constant of the low-pass filter of the derivative, in seconds. `0` disables the filter.
+    ///
+    /// Default: `0.0`.
+    pub derivative_filter_time_constant: f32,
+    /// Saturation of the output (absolute value), in the unit of the command (m/s or rad/s).
+    /// `None` for no saturation.
+    ///
+    /// Default: `None`.
+    pub output_limit: Option<f32>,
+}
+
+impl Check for PIDAxisConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.derivative_filter_time_constant < 0. {
+            errs.push("Derivative filter time constant should be positive".to_string());
+        }
+        if self.output_limit.is_some_and(|limit| limit <= 0.) {
+            errs.push("Output limit should be strictly positive".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+impl Default for PIDAxisConfig {
+    fn default() -> Self {
+        Self {
+            kp: 1.,
+            ki: 0.,
+            kd: 0.,
+            derivative_filter_time_constant: 0.,
+            output_limit: None,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for PIDAxisConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Kp:");
+            ui.add(egui::DragValue::new(&mut self.kp).max_decimals(10));
+            ui.label("Ki:");
+            ui.add(egui::DragValue::new(&mut self.ki).max_decimals(10));
+            ui.label("Kd:");
+            ui.add(egui::DragValue::new(&mut self.kd).max_decimals(10));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Derivative filter time constant:");
+            ui.add(
+                egui::DragValue::new(&mut self.derivative_filter_time_constant)
+                    .speed(0.01)
+                    .range(0.0..=f32::MAX),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Output limit:");
+            if let Some(limit) = &mut self.output_limit {
+                ui.add(egui::DragValue::new(limit).speed(0.1).range(0.0..=f32::MAX));
+                if ui.button("X").clicked() {
+                    self.output_limit = None;
+                }
+            } else if ui.button("+").clicked() {
+                self.output_limit = Some(1.);
+            }
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!("Kp: {}, Ki: {}, Kd: {}", self.kp, self.ki, self.kd));
+        ui.label(format!(
+            "Derivative filter time constant: {}",
+            self.derivative_filter_time_constant
+        ));
+        match self.output_limit {
+            Some(limit) => ui.label(format!("Output limit: {limit}")),
+            None => ui.label("Output limit: none"),
+        };
+    }
+}
+
+/// Configuration of the [`AntiWindupPID`].
+///
+/// ```yaml
+/// controller:
+///   type: AntiWindupPID
+///   robot_model: {type: Unicycle, wheel_distance: 0.25}
+///   anti_windup:
+///     type: BackCalculation
+///     tracking_gain: 2.0
+///   velocity_feedforward: 1.0
+///   longitudinal: {kp: 1.0, ki: 0.5, output_limit: 2.0}
+///   angular: {kp: 2.0, kd: 0.1, derivative_filter_time_constant: 0.05, output_limit: 1.5}
+/// ```
+#[config_derives]
+pub struct AntiWindupPIDConfig {
+    /// Robot model giving the command axes. If not set, the model of the physics is used
+    /// (internal physics only), otherwise a unicycle.
+    ///
+    /// Default: `None`.
+    #[check]
+    pub robot_model: Option<RobotModelConfig>,
+    /// Longitudinal velocity loop, on the velocity error.
+    #[check]
+    pub longitudinal: PIDAxisConfig,
+    /// Lateral velocity loop, on the lateral error. Holonomic model only.
+    #[check]
+    pub lateral: PIDAxisConfig,
+    /// Angular velocity loop, on the orientation error.
+    #[check]
+    pub angular: PIDAxisConfig,
+    /// Anti-windup strategy, for all the axes.
+    ///
+    /// Default: [`AntiWindupConfig::Clamping`].
+    #[check]
+    pub anti_windup: AntiWindupConfig,
+    /// Gain of the target velocity added to the longitudinal command. `0` disables the
+    /// feedforward.
+    ///
+    /// Default: `1.0`.
+    pub velocity_feedforward: f32,
+}
+
+impl Default for AntiWindupPIDConfig {
+    fn default() -> Self {
+        Self {
+            robot_model: None,
+            longitudinal: PIDAxisConfig::default(),
+            lateral: PIDAxisConfig::default(),
+            angular: PIDAxisConfig::default(),
+            anti_windup: AntiWindupConfig::default(),
+            velocity_feedforward: 1.,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for AntiWindupPIDConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Anti-windup PID")
+            .id_salt(format!("anti-windup-pid-{unique_id}"))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(model) = &mut self.robot_model {
+                        model.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Use physics model").clicked() {
+                            self.robot_model = None;
+                        }
+                    } else {
+                        ui.label("Robot model: from physics");
+                        if ui.button("Set model").clicked() {
+                            self.robot_model = Some(RobotModelConfig::default());
+                        }
+                    }
+                });
+                let axes = [
+                    ("Longitudinal", &mut self.longitudinal),
+                    ("Lateral", &mut self.lateral),
+                    ("Angular", &mut self.angular),
+                ];
+                for (name, axis) in axes {
+                    egui::CollapsingHeader::new(name)
+                        .id_salt(format!("anti-windup-pid-{name}-{unique_id}"))
+                        .show(ui, |ui| {
+                            axis.show_mut(
+                                ui,
+                                ctx,
+                                buffer_stack,
+                                global_config,
+                                current_node_name,
+                                unique_id,
+                            );
+                        });
+                }
+
+                let mut current_str = self.anti_windup.to_string();
+                ui.horizontal(|ui| {
+                    ui.label("Anti-windup:");
+                    string_combobox(
+                        ui,
+                        &AntiWindupConfig::to_vec(),
+                        &mut current_str,
+                        format!("anti-windup-choice-{unique_id}"),
+                    );
+                });
+                if current_str != self.anti_windup.to_string() {
+                    self.anti_windup = match current_str.as_str() {
+                        "None" => AntiWindupConfig::None,
+                        "Clamping" => AntiWindupConfig::Clamping,
+                        "BackCalculation" => {
+                            AntiWindupConfig::BackCalculation(BackCalculationConfig::default())
+                        }
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+                if let AntiWindupConfig::BackCalculation(c) = &mut self.anti_windup {
+                    ui.horizontal(|ui| {
+                        ui.label("Tracking gain:");
+                        ui.add(
+                            egui::DragValue::new(&mut c.tracking_gain)
+                                .speed(0.01)
+                                .range(0.0..=f32::MAX),
+                        );
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Velocity feedforward:");
+                    ui.add(egui::DragValue::new(&mut self.velocity_feedforward).speed(0.01));
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Anti-windup PID")
+            .id_salt(format!("anti-windup-pid-{unique_id}"))
+            .show(ui, |ui| {
+                match &self.robot_model {
+                    Some(model) => model.show(ui, ctx, unique_id),
+                    None => {
+                        ui.label("Robot model: from physics");
+                    }
+                }
+                for (name, axis) in [
+                    ("Longitudinal", &self.longitudinal),
+                    ("Lateral", &self.lateral),
+                    ("Angular", &self.angular),
+                ] {
+                    egui::CollapsingHeader::new(name)
+                        .id_salt(format!("anti-windup-pid-{name}-{unique_id}"))
+                        .show(ui, |ui| axis.show(ui, ctx, unique_id));
+                }
+                ui.label(format!("Anti-windup: {}", self.anti_windup));
+                ui.label(format!(
+                    "Velocity feedforward: {}",
+                    self.velocity_feedforward
+                ));
+            });
+    }
+}
+
+/// Terms of one axis for the last command.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PIDAxisRecord {
+    /// Error given to the axis.
+    pub error: f32,
+    /// Proportional term.
+    pub proportional: f32,
+    /// Integral term (already multiplied by `Ki`).
+    pub integral: f32,
+    /// Derivative term, on the filtered derivative.
+    pub derivative: f32,
+    /// Feedforward term.
+    pub feedforward: f32,
+    /// Output after saturation.
+    pub output: f32,
+    /// Whether the output was saturated.
+    pub saturated: bool,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for PIDAxisRecord {
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.vertical(|ui| {
+            ui.label(format!("error: {}", self.error));
+            ui.label(format!(
+                "P: {}, I: {}, D: {}, FF: {}",
+                self.proportional, self.integral, self.derivative, self.feedforward
+            ));
+            ui.label(format!(
+                "output: {}{}",
+                self.output,
+                if self.saturated { " (saturated)" } else { "" }
+            ));
+        });
+    }
+}
+
+/// Record of the [`AntiWindupPID`] controller.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AntiWindupPIDRecord {
+    /// Longitudinal axis.
+    pub longitudinal: PIDAxisRecord,
+    /// Lateral axis, `None` for models without lateral command.
+    pub lateral: Option<PIDAxisRecord>,
+    /// Angular axis.
+    pub angular: PIDAxisRecord,
+    /// Last command produced by the controller.
+    pub command: Command,
+    /// Simulation time associated with `command`.
+    pub last_command_time: f32,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for AntiWindupPIDRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("Longitudinal").show(ui, |ui| {
+                self.longitudinal.show(ui, ctx, unique_id);
+            });
+            if let Some(lateral) = &self.lateral {
+                egui::CollapsingHeader::new("Lateral").show(ui, |ui| {
+                    lateral.show(ui, ctx, unique_id);
+                });
+            }
+            egui::CollapsingHeader::new("Angular").show(ui, |ui| {
+                self.angular.show(ui, ctx, unique_id);
+            });
+            egui::CollapsingHeader::new("Command").show(ui, |ui| {
+                self.command.show(ui, ctx, unique_id);
+            });
+            ui.label(format!("last command time: {}", self.last_command_time));
+        });
+    }
+}
+
+/// State of one axis of the [`AntiWindupPID`].
+#[derive(Debug, Default)]
+struct PIDAxis {
+    /// Integral term, already multiplied by `Ki`
+    integral: f32,
+    /// Previous error, `None` before the first step
+    previous_error: Option<f32>,
+    /// Filtered derivative of the error
+    filtered_derivative: f32,
+}
+
+impl PIDAxis {
+    /// Compute the output of the axis for the given `error`, and update the integral and the
+    /// derivative filter.
+    fn update(
+        &mut self,
+        config: &PIDAxisConfig,
+        anti_windup: &AntiWindupConfig,
+        error: f32,
+        feedforward: f32,
+        dt: f32,
+    ) -> PIDAxisRecord {
+        // No derivative kick on the first step
+        let raw_derivative = self
+            .previous_error
+            .map_or(0., |previous| (error - previous) / dt);
+        self.previous_error = Some(error);
+        let alpha = dt / (config.derivative_filter_time_constant + dt);
+        self.filtered_derivative += alpha * (raw_derivative - self.filtered_derivative);
+
+        let proportional = config.kp * error;
+        let derivative = config.kd * self.filtered_derivative;
+        let saturate = |u: f32| match config.output_limit {
+            Some(limit) => u.clamp(-limit, limit),
+            None => u,
+        };
+
+        let integral_step = config.ki * error * dt;
+        match anti_windup {
+            AntiWindupConfig::None => self.integral += integral_step,
+            AntiWindupConfig::Clamping => {
+                let unsaturated =
+                    feedforward + proportional + self.integral + integral_step + derivative;
+                let excess = unsaturated - saturate(unsaturated);
+                // Integrate only if not saturated, or if the error unwinds the saturation
+                if excess == 0. || excess.signum() != integral_step.signum() {
+                    self.integral += integral_step;
+                }
+            }
+            AntiWindupConfig::BackCalculation(c) => {
+                let unsaturated = feedforward + proportional + self.integral + derivative;
+                self.integral +=
+                    integral_step + c.tracking_gain * (saturate(unsaturated) - unsaturated) * dt;
+            }
+        }
+
+        let unsaturated = feedforward + proportional + self.integral + derivative;
+        let output = saturate(unsaturated);
+        PIDAxisRecord {
+            error,
+            proportional,
+            integral: self.integral,
+            derivative,
+            feedforward,
+            output,
+            saturated: output != unsaturated,
+        }
+    }
+}
+
+/// Saturation-aware PID controller, with derivative filtering and velocity feedforward.
+#[derive(Debug)]
+pub struct AntiWindupPID {
+    config: AntiWindupPIDConfig,
+    robot_model: RobotModelConfig,
+    last_command_time: f32,
+    longitudinal: PIDAxis,
+    lateral: PIDAxis,
+    angular: PIDAxis,
+    current_record: AntiWindupPIDRecord,
+}
+
+impl AntiWindupPID {
+    /// Makes a new [`AntiWindupPID`] from the given `config`, taking the robot model from
+    /// `physics_config` if not given.
+    pub fn from_config(
+        config: &AntiWindupPIDConfig,
+        physics_config: &PhysicsConfig,
+        initial_time: f32,
+    ) -> Self {
+        let robot_model = match (&config.robot_model, physics_config) {
+            (Some(model), _) => model.clone(),
+            (None, PhysicsConfig::Internal(InternalPhysicConfig { model, .. })) => model.clone(),
+            (None, _) => {
+                warn!("No model given in AntiWindupPID Config... using default one");
+                RobotModelConfig::default()
+            }
+        };
+        Self {
+            config: config.clone(),
+            robot_model,
+            last_command_time: initial_time,
+            longitudinal: PIDAxis::default(),
+            lateral: PIDAxis::default(),
+            angular: PIDAxis::default(),
+            current_record: AntiWindupPIDRecord {
+                last_command_time: initial_time,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Controller for AntiWindupPID {
+    fn make_command(&mut self, robot: &mut Node, error: &ControllerError, time: f32) -> Command {
+        let dt = time - self.last_command_time;
+        assert!(
+            dt > 0.,
+            "AntiWindupPID delta time should be positive: {} - {} = {} > 0",
+            time,
+            self.last_command_time,
+            dt
+        );
+        self.last_command_time = time;
+
+        // Target velocity = current velocity + velocity error. The last longitudinal command is
+        // used as current velocity if there is no ego estimate.
+        let current_velocity = robot
+            .state_estimator()
+            .and_then(|state_estimator| state_estimator.read_recover().world_state().ego)
+            .map_or(self.current_record.longitudinal.output, |state| {
+                state.velocity.x
+            });
+        let feedforward = self.config.velocity_feedforward * (current_velocity + error.velocity);
+
+        let anti_windup = &self.config.anti_windup;
+        let longitudinal = self.longitudinal.update(
+            &self.config.longitudinal,
+            anti_windup,
+            error.velocity,
+            feedforward,
+            dt,
+        );
+        let angular = self
+            .angular
+            .update(&self.config.angular, anti_windup, error.theta, 0., dt);
+
+        let (command, lateral) = match &self.robot_model {
+            RobotModelConfig::Unicycle(model) => (
+                Command::Unicycle(UnicycleCommand {
+                    left_wheel_speed: longitudinal.output
+                        - angular.output * model.wheel_distance / 2.,
+                    right_wheel_speed: longitudinal.output
+                        + angular.output * model.wheel_distance / 2.,
+                }),
+                None,
+            ),
+            RobotModelConfig::Holonomic(_) => {
+                let lateral =
+                    self.lateral
+                        .update(&self.config.lateral, anti_windup, error.lateral, 0., dt);
+                (
+                    Command::Holonomic(HolonomicCommand {
+                        longitudinal_velocity: longitudinal.output,
+                        lateral_velocity: lateral.output,
+                        angular_velocity: angular.output,
+                    }),
+                    Some(lateral),
+                )
+            }
+        };
+
+        self.current_record = AntiWindupPIDRecord {
+            longitudinal,
+            lateral,
+            angular,
+            command: command.clone(),
+            last_command_time: time,
+        };
+        command
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            commands: Some(vec![CommandKind::from(&self.robot_model)]),
+            ..Default::default()
+        }
+    }
+}
+
+impl Recordable<ControllerRecord> for AntiWindupPID {
+    fn record(&self) -> ControllerRecord {
+        ControllerRecord::AntiWindupPID(self.current_record.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integral_does_not_wind_up() {
+        let config = PIDAxisConfig {
+            kp: 1.,
+            ki: 1.,
+            output_limit: Some(1.),
+            ..Default::default()
+        };
+        let anti_windup_strategies = [
+            AntiWindupConfig::Clamping,
+            AntiWindupConfig::BackCalculation(BackCalculationConfig { tracking_gain: 5. }),
+        ];
+        for anti_windup in anti_windup_strategies {
+            let mut axis = PIDAxis::default();
+            for _ in 0..100 {
+                let record = axis.update(&config, &anti_windup, 2., 0., 0.1);
+                assert!(record.saturated);
+                assert_eq!(record.output, 1.);
+            }
+            // Saturation is left as soon as the error changes sign
+            let record = axis.update(&config, &anti_windup, -0.2, 0., 0.1);
+            assert!(!record.saturated, "{anti_windup:?}: {record:?}");
+        }
+
+        let mut axis = PIDAxis::default();
+        for _ in 0..100 {
+            axis.update(&config, &AntiWindupConfig::None, 2., 0., 0.1);
+        }
+        let record = axis.update(&config, &AntiWindupConfig::None, -0.2, 0., 0.1);
+        assert!(record.saturated);
+    }
+}
@@ -0,0 +1,436 @@
+//! Gain scheduling of the [`PID`] controller.
+//!
+//! [`GainScheduling`] wraps a [`PID`] and changes its gains at each command, from the estimated
+//! state of the robot:
+//! - in a map region ([`GainSchedulingConfig::regions`]), the gains of the first region
+//!   containing the robot are used;
+//! - otherwise, the gains are interpolated linearly from the estimated speed
+//!   ([`GainSchedulingConfig::speed_schedule`]), and kept constant outside of the schedule range;
+//! - without schedule or ego estimate, the gains of [`GainSchedulingConfig::pid`] are used.
+//!
+//! The gains of the schedule follow the order and size of the robot model of the PID.
+
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::json_config};
+#[cfg(feature = "gui")]
+use crate::simulator::SimulatorConfig;
+use crate::{
+    controllers::{
+        Controller, ControllerError, ControllerRecord,
+        pid::{PID, PIDConfig, PIDRecord},
+    },
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    node::{Node, capabilities::Capabilities},
+    physics::{PhysicsConfig, robot_models::Command},
+    recordable::Recordable,
+    scenario::config::AreaEventTriggerConfig,
+    utils::lock_recovery::RecoverRwLock,
+};
+use config_checker::*;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Set of PID gains, ordered like in [`PIDConfig`].
+#[config_derives]
+#[derive(Default)]
+pub struct ScheduledGainsConfig {
+    /// Proportional gains (`Kp`).
+    pub proportional_gains: Vec<f32>,
+    /// Derivative gains (`Kd`).
+    pub derivative_gains: Vec<f32>,
+    /// Integral gains (`Ki`).
+    pub integral_gains: Vec<f32>,
+}
+
+impl ScheduledGainsConfig {
+    fn from_pid(config: &PIDConfig) -> Self {
+        Self {
+            proportional_gains: config.proportional_gains.clone(),
+            derivative_gains: config.derivative_gains.clone(),
+            integral_gains: config.integral_gains.clone(),
+        }
+    }
+
+    /// Linear interpolation between `self` (`ratio = 0`) and `other` (`ratio = 1`).
+    fn interpolate(&self, other: &Self, ratio: f32) -> Self {
+        let lerp = |a: &Vec<f32>, b: &Vec<f32>| -> Vec<f32> {
+            a.iter().zip(b).map(|(a, b)| a + (b - a) * ratio).collect()
+        };
+        Self {
+            proportional_gains: lerp(&self.proportional_gains, &other.proportional_gains),
+            derivative_gains: lerp(&self.derivative_gains, &other.derivative_gains),
+            integral_gains: lerp(&self.integral_gains, &other.integral_gains),
+        }
+    }
+
+    fn has_size_of(&self, config: &PIDConfig) -> bool {
+        self.proportional_gains.len() == config.proportional_gains.len()
+            && self.derivative_gains.len() == config.derivative_gains.len()
+            && self.integral_gains.len() == config.integral_gains.len()
+    }
+}
+
+/// Gains used at a given speed.
+#[config_derives]
+#[derive(Default)]
+pub struct SpeedGainsConfig {
+    /// Estimated speed (norm of the linear velocity), in m/s.
+    pub speed: f32,
+    /// Gains at this speed.
+    pub gains: ScheduledGainsConfig,
+}
+
+/// Gains used in a map region.
+#[config_derives]
+#[derive(Default)]
+pub struct RegionGainsConfig {
+    /// Region of the map, where the estimated position of the robot should trigger the area.
+    pub area: AreaEventTriggerConfig,
+    /// Gains in this region.
+    pub gains: ScheduledGainsConfig,
+}
+
+/// Configuration of the [`GainScheduling`] controller.
+///
+/// ```yaml
+/// controller:
+///   type: GainScheduling
+///   pid:
+///     robot_model: {type: Unicycle, wheel_distance: 0.25}
+///     proportional_gains: [1.0, 1.0]
+///     derivative_gains: [0.0, 0.1]
+///     integral_gains: [0.0, 0.0]
+///   speed_schedule:
+///   - speed: 0.5
+///     gains: {proportional_gains: [1.0, 2.0], derivative_gains: [0.0, 0.1], integral_gains: [0.0, 0.0]}
+///   - speed: 5.0
+///     gains: {proportional_gains: [0.5, 0.5], derivative_gains: [0.0, 0.2], integral_gains: [0.0, 0.0]}
+///   regions:
+///   - area: {type: Rect, bottom_left: [10.0, -2.0], top_right: [20.0, 2.0], inside: true}
+///     gains: {proportional_gains: [0.2, 3.0], derivative_gains: [0.0, 0.1], integral_gains: [0.0, 0.0]}
+/// ```
+#[config_derives]
+#[derive(Default)]
+pub struct GainSchedulingConfig {
+    /// Scheduled PID: robot model and gains used when no schedule applies.
+    #[check]
+    pub pid: PIDConfig,
+    /// Gains interpolated from the estimated speed, sorted by increasing speed.
+    ///
+    /// Default: empty.
+    pub speed_schedule: Vec<SpeedGainsConfig>,
+    /// Gains by map region, overriding the speed schedule. The first region containing the robot
+    /// is used.
+    ///
+    /// Default: empty.
+    pub regions: Vec<RegionGainsConfig>,
+}
+
+impl Check for GainSchedulingConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self
+            .speed_schedule
+            .windows(2)
+            .any(|points| points[0].speed >= points[1].speed)
+        {
+            errs.push("Speed schedule should be sorted by strictly increasing speed".to_string());
+        }
+        // Gain sizes can only be checked if the model is given, otherwise it is checked at creation
+        if self.pid.robot_model.is_some() {
+            if self
+                .speed_schedule
+                .iter()
+                .map(|point| &point.gains)
+                .chain(self.regions.iter().map(|region| &region.gains))
+                .any(|gains| !gains.has_size_of(&self.pid))
+            {
+                errs.push(format!(
+                    "Scheduled gains should have the size of the PID gains ({} model)",
+                    self.pid.robot_model.as_ref().unwrap()
+                ));
+            }
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for GainSchedulingConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Gain scheduling")
+            .id_salt(format!("gain-scheduling-{unique_id}"))
+            .show(ui, |ui| {
+                self.pid.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+                ui.label("Speed schedule (JSON format):");
+                let mut speed_schedule = serde_json::to_value(&self.speed_schedule).unwrap();
+                json_config(
+                    ui,
+                    &format!("gain-scheduling-speed-{unique_id}"),
+                    &format!("gain-scheduling-speed-error-{unique_id}"),
+                    buffer_stack,
+                    &mut speed_schedule,
+                );
+                if let Ok(speed_schedule) = serde_json::from_value(speed_schedule) {
+                    self.speed_schedule = speed_schedule;
+                }
+                ui.label("Regions (JSON format):");
+                let mut regions = serde_json::to_value(&self.regions).unwrap();
+                json_config(
+                    ui,
+                    &format!("gain-scheduling-regions-{unique_id}"),
+                    &format!("gain-scheduling-regions-error-{unique_id}"),
+                    buffer_stack,
+                    &mut regions,
+                );
+                if let Ok(regions) = serde_json::from_value(regions) {
+                    self.regions = regions;
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Gain scheduling")
+            .id_salt(format!("gain-scheduling-{unique_id}"))
+            .show(ui, |ui| {
+                self.pid.show(ui, ctx, unique_id);
+                for point in &self.speed_schedule {
+                    ui.label(format!(
+                        "At {} m/s: P {:?}, I {:?}, D {:?}",
+                        point.speed,
+                        point.gains.proportional_gains,
+                        point.gains.integral_gains,
+                        point.gains.derivative_gains
+                    ));
+                }
+                for (i, region) in self.regions.iter().enumerate() {
+                    ui.label(format!(
+                        "Region {i}: P {:?}, I {:?}, D {:?}",
+                        region.gains.proportional_gains,
+                        region.gains.integral_gains,
+                        region.gains.derivative_gains
+                    ));
+                }
+            });
+    }
+}
+
+/// Record of the [`GainScheduling`] controller.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GainSchedulingRecord {
+    /// Record of the scheduled PID, with the gains used for the last command.
+    pub pid: PIDRecord,
+    /// Estimated speed used for the last command, `None` without ego estimate.
+    pub speed: Option<f32>,
+    /// Index of the region used for the last command, `None` outside of the regions.
+    pub region: Option<usize>,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for GainSchedulingRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            match self.speed {
+                Some(speed) => ui.label(format!("speed: {speed}")),
+                None => ui.label("speed: no estimate"),
+            };
+            match self.region {
+                Some(region) => ui.label(format!("region: {region}")),
+                None => ui.label("region: none"),
+            };
+            self.pid.show(ui, ctx, unique_id);
+        });
+    }
+}
+
+/// [`PID`] controller with gains scheduled by speed and map region.
+#[derive(Debug)]
+pub struct GainScheduling {
+    pid: PID,
+    base_gains: ScheduledGainsConfig,
+    speed_schedule: Vec<SpeedGainsConfig>,
+    regions: Vec<RegionGainsConfig>,
+    speed: Option<f32>,
+    region: Option<usize>,
+}
+
+impl GainScheduling {
+    /// Makes a new [`GainScheduling`] from the given `config`.
+    ///
+    /// The robot model of the PID is resolved like for [`PID::from_config`], then the scheduled
+    /// gains are checked against it.
+    pub fn from_config(
+        config: &GainSchedulingConfig,
+        physics_config: &PhysicsConfig,
+        initial_time: f32,
+    ) -> SimbaResult<Self> {
+        let pid = PID::from_config(&config.pid, physics_config, initial_time);
+        if let Some(point) = config
+            .speed_schedule
+            .iter()
+            .map(|point| &point.gains)
+            .chain(config.regions.iter().map(|region| &region.gains))
+            .find(|gains| !gains.has_size_of(pid.config()))
+        {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Scheduled gains {:?} do not match the PID gains size for the {} model",
+                    point,
+                    pid.config().robot_model.as_ref().unwrap()
+                ),
+            ));
+        }
+        Ok(Self {
+            base_gains: ScheduledGainsConfig::from_pid(pid.config()),
+            pid,
+            speed_schedule: config.speed_schedule.clone(),
+            regions: config.regions.clone(),
+            speed: None,
+            region: None,
+        })
+    }
+
+    /// Gains at the given speed, from the speed schedule.
+    fn gains_at_speed(&self, speed: f32) -> ScheduledGainsConfig {
+        let (first, last) = match (self.speed_schedule.first(), self.speed_schedule.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return self.base_gains.clone(),
+        };
+        if speed <= first.speed {
+            return first.gains.clone();
+        }
+        if speed >= last.speed {
+            return last.gains.clone();
+        }
+        let i = self
+            .speed_schedule
+            .iter()
+            .position(|point| point.speed > speed)
+            .unwrap();
+        let (low, high) = (&self.speed_schedule[i - 1], &self.speed_schedule[i]);
+        low.gains
+            .interpolate(&high.gains, (speed - low.speed) / (high.speed - low.speed))
+    }
+
+    /// Select the gains for the estimated state of the robot.
+    fn schedule(&mut self, robot: &Node) {
+        let ego = robot
+            .state_estimator()
+            .and_then(|state_estimator| state_estimator.read_recover().world_state().ego);
+        let gains = match ego {
+            Some(state) => {
+                let position = [state.pose.x, state.pose.y];
+                let speed = state.velocity.fixed_view::<2, 1>(0, 0).norm();
+                self.speed = Some(speed);
+                self.region = self
+                    .regions
+                    .iter()
+                    .position(|region| region.area.is_triggered_by(position));
+                match self.region {
+                    Some(i) => self.regions[i].gains.clone(),
+                    None => self.gains_at_speed(speed),
+                }
+            }
+            None => {
+                self.speed = None;
+                self.region = None;
+                self.base_gains.clone()
+            }
+        };
+        self.pid.set_gains(
+            &gains.proportional_gains,
+            &gains.derivative_gains,
+            &gains.integral_gains,
+        );
+    }
+}
+
+impl Controller for GainScheduling {
+    fn post_init(&mut self, node: &mut Node) -> SimbaResult<()> {
+        self.pid.post_init(node)
+    }
+
+    fn make_command(&mut self, robot: &mut Node, error: &ControllerError, time: f32) -> Command {
+        self.schedule(robot);
+        self.pid.make_command(robot, error, time)
+    }
+
+    fn pre_loop_hook(&mut self, node: &mut Node, time: f32) {
+        self.pid.pre_loop_hook(node, time);
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.pid.capabilities()
+    }
+}
+
+impl Recordable<ControllerRecord> for GainScheduling {
+    fn record(&self) -> ControllerRecord {
+        let pid = match self.pid.record() {
+            ControllerRecord::PID(record) => record,
+            _ => unreachable!(),
+        };
+        ControllerRecord::GainScheduling(GainSchedulingRecord {
+            pid,
+            speed: self.speed,
+            region: self.region,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::physics::internal_physics::InternalPhysicConfig;
+
+    use super::*;
+
+    fn gains(p: f32) -> ScheduledGainsConfig {
+        ScheduledGainsConfig {
+            proportional_gains: vec![p, p],
+            derivative_gains: vec![0., 0.],
+            integral_gains: vec![0., 0.],
+        }
+    }
+
+    #[test]
+    fn speed_interpolation() {
+        let config = GainSchedulingConfig {
+            speed_schedule: vec![
+                SpeedGainsConfig {
+                    speed: 1.,
+                    gains: gains(2.),
+                },
+                SpeedGainsConfig {
+                    speed: 3.,
+                    gains: gains(1.),
+                },
+            ],
+            ..Default::default()
+        };
+        let controller = GainScheduling::from_config(
+            &config,
+            &PhysicsConfig::Internal(InternalPhysicConfig::default()),
+            0.,
+        )
+        .unwrap();
+        assert_eq!(controller.gains_at_speed(0.), gains(2.));
+        assert_eq!(controller.gains_at_speed(2.), gains(1.5));
+        assert_eq!(controller.gains_at_speed(10.), gains(1.));
+    }
+}
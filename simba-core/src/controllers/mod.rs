@@ -6,7 +6,10 @@
 //! It also exposes strategy-specific configuration and runtime record enums:
 //! [`ControllerConfig`] and [`ControllerRecord`].
 
+pub mod anti_windup_pid;
 pub mod external_controller;
+pub mod gain_scheduling;
+pub mod mpc;
 pub mod pid;
 pub mod python_controller;
 
@@ -15,6 +18,7 @@ pub mod pybinds;
 use crate::{
     errors::SimbaResult,
     networking::network::Network,
+    node::capabilities::Capabilities,
     physics::{PhysicsConfig, robot_models::Command},
     recordable::Recordable,
     utils::{SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory},
@@ -69,6 +73,15 @@ pub enum ControllerConfig {
     #[check]
     /// Configuration for [`PythonController`](python_controller::PythonControllerConfig).
     Python(python_controller::PythonControllerConfig),
+    #[check]
+    /// Configuration for [`GainScheduling`](gain_scheduling::GainSchedulingConfig).
+    GainScheduling(gain_scheduling::GainSchedulingConfig),
+    #[check]
+    /// Configuration for [`AntiWindupPID`](anti_windup_pid::AntiWindupPIDConfig).
+    AntiWindupPID(anti_windup_pid::AntiWindupPIDConfig),
+    #[check]
+    /// Configuration for [`MpcController`](mpc::MpcControllerConfig).
+    Mpc(mpc::MpcControllerConfig),
 }
 
 #[cfg(feature = "gui")]
@@ -105,6 +118,17 @@ impl UIComponent for ControllerConfig {
                         python_controller::PythonControllerConfig::default(),
                     )
                 }
+                "GainScheduling" => {
+                    *self = ControllerConfig::GainScheduling(
+                        gain_scheduling::GainSchedulingConfig::default(),
+                    )
+                }
+                "AntiWindupPID" => {
+                    *self = ControllerConfig::AntiWindupPID(
+                        anti_windup_pid::AntiWindupPIDConfig::default(),
+                    )
+                }
+                "Mpc" => *self = ControllerConfig::Mpc(mpc::MpcControllerConfig::default()),
                 _ => panic!("Where did you find this value?"),
             };
         }
@@ -133,6 +157,30 @@ impl UIComponent for ControllerConfig {
                 current_node_name,
                 unique_id,
             ),
+            ControllerConfig::GainScheduling(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            ControllerConfig::AntiWindupPID(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            ControllerConfig::Mpc(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
         }
     }
 
@@ -144,6 +192,9 @@ impl UIComponent for ControllerConfig {
             ControllerConfig::PID(c) => c.show(ui, ctx, unique_id),
             ControllerConfig::External(c) => c.show(ui, ctx, unique_id),
             ControllerConfig::Python(c) => c.show(ui, ctx, unique_id),
+            ControllerConfig::GainScheduling(c) => c.show(ui, ctx, unique_id),
+            ControllerConfig::AntiWindupPID(c) => c.show(ui, ctx, unique_id),
+            ControllerConfig::Mpc(c) => c.show(ui, ctx, unique_id),
         }
     }
 }
@@ -157,6 +208,12 @@ pub enum ControllerRecord {
     External(external_controller::ExternalControllerRecord),
     /// Runtime record for [`python_controller::PythonController`].
     Python(python_controller::PythonControllerRecord),
+    /// Runtime record for [`gain_scheduling::GainScheduling`].
+    GainScheduling(gain_scheduling::GainSchedulingRecord),
+    /// Runtime record for [`anti_windup_pid::AntiWindupPID`].
+    AntiWindupPID(anti_windup_pid::AntiWindupPIDRecord),
+    /// Runtime record for [`mpc::MpcController`].
+    Mpc(mpc::MpcControllerRecord),
 }
 
 #[cfg(feature = "gui")]
@@ -178,6 +235,21 @@ impl UIComponent for ControllerRecord {
                     r.show(ui, ctx, unique_id);
                 });
             }
+            Self::GainScheduling(r) => {
+                egui::CollapsingHeader::new("GainScheduling").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::AntiWindupPID(r) => {
+                egui::CollapsingHeader::new("AntiWindupPID").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::Mpc(r) => {
+                egui::CollapsingHeader::new("Mpc").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
         });
     }
 }
@@ -212,6 +284,25 @@ pub trait Controller:
     fn next_time_step(&self) -> Option<f32> {
         None
     }
+
+    /// Optional: [`Command`] variants emitted by the controller, checked against the physics
+    /// when the node is built (see [`capabilities`](crate::node::capabilities)). Nothing is
+    /// declared by default.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Optional: change the gains of the controller at runtime. The gains given as `None` are
+    /// kept.
+    #[allow(unused_variables)]
+    fn set_gains(
+        &mut self,
+        proportional: Option<&[f32]>,
+        derivative: Option<&[f32]>,
+        integral: Option<&[f32]>,
+    ) -> Result<(), String> {
+        Err("This controller has no gains that can be changed".to_string())
+    }
 }
 
 /// Helper function to make the right [`Controller`] from the given configuration.
@@ -251,5 +342,18 @@ pub fn make_controller_from_config(
             python_controller::PythonController::from_config(c, global_config, initial_time)
                 .unwrap(),
         ) as Box<dyn Controller>,
+        ControllerConfig::GainScheduling(c) => Box::new(
+            gain_scheduling::GainScheduling::from_config(c, physics_config, initial_time)?,
+        ) as Box<dyn Controller>,
+        ControllerConfig::AntiWindupPID(c) => Box::new(anti_windup_pid::AntiWindupPID::from_config(
+            c,
+            physics_config,
+            initial_time,
+        )) as Box<dyn Controller>,
+        ControllerConfig::Mpc(c) => Box::new(mpc::MpcController::from_config(
+            c,
+            physics_config,
+            initial_time,
+        )) as Box<dyn Controller>,
     })))
 }
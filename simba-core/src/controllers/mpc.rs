@@ -0,0 +1,750 @@
+//! Model predictive controller.
+//!
+//! [`MpcController`] predicts the evolution of the errors given by the navigator over a horizon
+//! of [`MpcControllerConfig::horizon`] steps of [`MpcControllerConfig::time_step`], and chooses
+//! the commands minimizing a quadratic cost on the predicted errors and on the commands, within
+//! the actuator limits. Only the first command is applied, the problem is solved again at each
+//! control step.
+//!
+//! The errors are predicted with the kinematic model linearized around the target velocity
+//! `v_ref` (estimated velocity + velocity error), the target moving along a straight line:
+//! - longitudinal error: `ė_x = -δv`, where `δv = v - v_ref` is the velocity command deviation;
+//! - lateral error: `ė_y = v_ref e_θ - v_y`, `v_y` being the lateral velocity command;
+//! - orientation error: `ė_θ = -ω`.
+//!
+//! The commands depend on the robot model:
+//! - Unicycle: longitudinal and angular velocities, converted to wheel speeds;
+//! - Holonomic: longitudinal, lateral and angular velocities.
+//!
+//! The box-constrained quadratic problem (QP) is solved by projected coordinate descent, warm
+//! started with the previous solution.
+
+use nalgebra::{DMatrix, DVector};
+
+use crate::{
+    controllers::{Controller, ControllerError, ControllerRecord},
+    node::{
+        Node,
+        capabilities::{Capabilities, CommandKind},
+    },
+    physics::{
+        PhysicsConfig,
+        internal_physics::InternalPhysicConfig,
+        robot_models::{
+            Command, RobotModelConfig, holonomic::HolonomicCommand, unicycle::UnicycleCommand,
+        },
+    },
+    recordable::Recordable,
+    utils::lock_recovery::RecoverRwLock,
+};
+#[cfg(feature = "gui")]
+use crate::{gui::UIComponent, simulator::SimulatorConfig};
+use config_checker::*;
+use log::warn;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Number of predicted errors: longitudinal, lateral and orientation.
+const STATE_SIZE: usize = 3;
+/// Convergence threshold of the QP solver, on the largest command change of an iteration.
+const SOLVER_TOLERANCE: f32 = 1e-5;
+
+/// Weights of the cost of the [`MpcController`].
+///
+/// The cost sums, over the horizon, the squared errors and the squared command deviations
+/// multiplied by their weight. The command weights should be strictly positive.
+#[config_derives]
+pub struct MpcWeightsConfig {
+    /// Weight of the longitudinal error.
+    ///
+    /// Default: `0.0`.
+    pub longitudinal: f32,
+    /// Weight of the lateral error.
+    ///
+    /// Default: `1.0`.
+    pub lateral: f32,
+    /// Weight of the orientation error.
+    ///
+    /// Default: `1.0`.
+    pub orientation: f32,
+    /// Weight of the deviation of the velocity command from the target velocity.
+    ///
+    /// Default: `0.1`.
+    pub velocity: f32,
+    /// Weight of the angular velocity command.
+    ///
+    /// Default: `0.1`.
+    pub angular_velocity: f32,
+    /// Weight of the lateral velocity command. Holonomic model only.
+    ///
+    /// Default: `0.1`.
+    pub lateral_velocity: f32,
+}
+
+impl Check for MpcWeightsConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.longitudinal < 0. || self.lateral < 0. || self.orientation < 0. {
+            errs.push("MPC error weights should be positive".to_string());
+        }
+        if self.velocity <= 0. || self.angular_velocity <= 0. || self.lateral_velocity <= 0. {
+            errs.push("MPC command weights should be strictly positive".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+impl Default for MpcWeightsConfig {
+    fn default() -> Self {
+        Self {
+            longitudinal: 0.,
+            lateral: 1.,
+            orientation: 1.,
+            velocity: 0.1,
+            angular_velocity: 0.1,
+            lateral_velocity: 0.1,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MpcWeightsConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        for (name, weight) in [
+            ("Longitudinal error:", &mut self.longitudinal),
+            ("Lateral error:", &mut self.lateral),
+            ("Orientation error:", &mut self.orientation),
+            ("Velocity:", &mut self.velocity),
+            ("Angular velocity:", &mut self.angular_velocity),
+            ("Lateral velocity:", &mut self.lateral_velocity),
+        ] {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                ui.add(
+                    egui::DragValue::new(weight)
+                        .speed(0.01)
+                        .max_decimals(10)
+                        .range(0.0..=f32::MAX),
+                );
+            });
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!(
+            "Error weights: longitudinal {}, lateral {}, orientation {}",
+            self.longitudinal, self.lateral, self.orientation
+        ));
+        ui.label(format!(
+            "Command weights: velocity {}, angular velocity {}, lateral velocity {}",
+            self.velocity, self.angular_velocity, self.lateral_velocity
+        ));
+    }
+}
+
+/// Actuator limits of the [`MpcController`] (absolute values). `None` for no limit.
+#[derive(Default)]
+#[config_derives]
+pub struct MpcLimitsConfig {
+    /// Longitudinal velocity, in m/s.
+    ///
+    /// Default: `None`.
+    pub max_velocity: Option<f32>,
+    /// Angular velocity, in rad/s.
+    ///
+    /// Default: `None`.
+    pub max_angular_velocity: Option<f32>,
+    /// Lateral velocity, in m/s. Holonomic model only.
+    ///
+    /// Default: `None`.
+    pub max_lateral_velocity: Option<f32>,
+    /// Wheel speed, in m/s. Unicycle model only: both wheel speeds are scaled down to respect
+    /// it, keeping the curvature.
+    ///
+    /// Default: `None`.
+    pub max_wheel_speed: Option<f32>,
+}
+
+impl Check for MpcLimitsConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if [
+            self.max_velocity,
+            self.max_angular_velocity,
+            self.max_lateral_velocity,
+            self.max_wheel_speed,
+        ]
+        .iter()
+        .flatten()
+        .any(|limit| *limit <= 0.)
+        {
+            Err(vec!["MPC limits should be strictly positive".to_string()])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MpcLimitsConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        for (name, limit) in [
+            ("Max velocity:", &mut self.max_velocity),
+            ("Max angular velocity:", &mut self.max_angular_velocity),
+            ("Max lateral velocity:", &mut self.max_lateral_velocity),
+            ("Max wheel speed:", &mut self.max_wheel_speed),
+        ] {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                if let Some(value) = limit {
+                    ui.add(egui::DragValue::new(value).speed(0.1).range(0.0..=f32::MAX));
+                    if ui.button("X").clicked() {
+                        *limit = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    *limit = Some(1.);
+                }
+            });
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        for (name, limit) in [
+            ("Max velocity", self.max_velocity),
+            ("Max angular velocity", self.max_angular_velocity),
+            ("Max lateral velocity", self.max_lateral_velocity),
+            ("Max wheel speed", self.max_wheel_speed),
+        ] {
+            match limit {
+                Some(limit) => ui.label(format!("{name}: {limit}")),
+                None => ui.label(format!("{name}: none")),
+            };
+        }
+    }
+}
+
+/// Configuration of the [`MpcController`].
+///
+/// ```yaml
+/// controller:
+///   type: Mpc
+///   robot_model: {type: Unicycle, wheel_distance: 0.25}
+///   horizon: 10
+///   time_step: 0.1
+///   weights: {lateral: 1.0, orientation: 1.0, velocity: 0.1, angular_velocity: 0.1}
+///   limits: {max_velocity: 2.0, max_angular_velocity: 1.5, max_wheel_speed: 2.0}
+/// ```
+#[config_derives]
+pub struct MpcControllerConfig {
+    /// Robot model giving the commands. If not set, the model of the physics is used
+    /// (internal physics only), otherwise a unicycle.
+    ///
+    /// Default: `None`.
+    #[check]
+    pub robot_model: Option<RobotModelConfig>,
+    /// Number of predicted steps.
+    ///
+    /// Default: `10`.
+    pub horizon: usize,
+    /// Duration of a predicted step, in seconds.
+    ///
+    /// Default: `0.1`.
+    pub time_step: f32,
+    /// Weights of the cost.
+    #[check]
+    pub weights: MpcWeightsConfig,
+    /// Actuator limits.
+    #[check]
+    pub limits: MpcLimitsConfig,
+    /// Maximum number of iterations of the QP solver per control step.
+    ///
+    /// Default: `100`.
+    pub max_iterations: usize,
+}
+
+impl Check for MpcControllerConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.horizon == 0 {
+            errs.push("MPC horizon should be at least 1".to_string());
+        }
+        if self.time_step <= 0. {
+            errs.push("MPC time step should be strictly positive".to_string());
+        }
+        if self.max_iterations == 0 {
+            errs.push("MPC maximum number of iterations should be at least 1".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+impl Default for MpcControllerConfig {
+    fn default() -> Self {
+        Self {
+            robot_model: None,
+            horizon: 10,
+            time_step: 0.1,
+            weights: MpcWeightsConfig::default(),
+            limits: MpcLimitsConfig::default(),
+            max_iterations: 100,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MpcControllerConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("MPC")
+            .id_salt(format!("mpc-{unique_id}"))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(model) = &mut self.robot_model {
+                        model.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Use physics model").clicked() {
+                            self.robot_model = None;
+                        }
+                    } else {
+                        ui.label("Robot model: from physics");
+                        if ui.button("Set model").clicked() {
+                            self.robot_model = Some(RobotModelConfig::default());
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Horizon:");
+                    ui.add(egui::DragValue::new(&mut self.horizon).range(1..=usize::MAX));
+                    ui.label("Time step:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.time_step)
+                            .speed(0.01)
+                            .range(0.001..=f32::MAX),
+                    );
+                });
+                egui::CollapsingHeader::new("Weights")
+                    .id_salt(format!("mpc-weights-{unique_id}"))
+                    .show(ui, |ui| {
+                        self.weights.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                    });
+                egui::CollapsingHeader::new("Limits")
+                    .id_salt(format!("mpc-limits-{unique_id}"))
+                    .show(ui, |ui| {
+                        self.limits.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                    });
+                ui.horizontal(|ui| {
+                    ui.label("Max solver iterations:");
+                    ui.add(egui::DragValue::new(&mut self.max_iterations).range(1..=usize::MAX));
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("MPC")
+            .id_salt(format!("mpc-{unique_id}"))
+            .show(ui, |ui| {
+                match &self.robot_model {
+                    Some(model) => model.show(ui, ctx, unique_id),
+                    None => {
+                        ui.label("Robot model: from physics");
+                    }
+                }
+                ui.label(format!(
+                    "Horizon: {} steps of {} s",
+                    self.horizon, self.time_step
+                ));
+                self.weights.show(ui, ctx, unique_id);
+                self.limits.show(ui, ctx, unique_id);
+                ui.label(format!("Max solver iterations: {}", self.max_iterations));
+            });
+    }
+}
+
+/// Record of the [`MpcController`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MpcControllerRecord {
+    /// Target velocity around which the errors are predicted.
+    pub reference_velocity: f32,
+    /// Longitudinal velocity command.
+    pub velocity: f32,
+    /// Cost of the chosen commands over the horizon.
+    pub cost: f32,
+    /// Iterations of the QP solver.
+    pub iterations: usize,
+    /// Whether the solver reached the maximum number of iterations before converging.
+    pub max_iterations_reached: bool,
+    /// Last command produced by the controller.
+    pub command: Command,
+    /// Simulation time associated with `command`.
+    pub last_command_time: f32,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MpcControllerRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            ui.label(format!("reference velocity: {}", self.reference_velocity));
+            ui.label(format!("velocity: {}", self.velocity));
+            ui.label(format!("cost: {}", self.cost));
+            ui.label(format!(
+                "solver iterations: {}{}",
+                self.iterations,
+                if self.max_iterations_reached {
+                    " (not converged)"
+                } else {
+                    ""
+                }
+            ));
+            egui::CollapsingHeader::new("Command").show(ui, |ui| {
+                self.command.show(ui, ctx, unique_id);
+            });
+            ui.label(format!("last command time: {}", self.last_command_time));
+        });
+    }
+}
+
+/// Quadratic problem `min ½ uᵀ H u + fᵀ u + c` with `lower ≤ u ≤ upper`.
+struct BoxQp {
+    h: DMatrix<f32>,
+    f: DVector<f32>,
+    c: f32,
+    lower: DVector<f32>,
+    upper: DVector<f32>,
+}
+
+impl BoxQp {
+    /// Projected coordinate descent from `u`, which should be within the bounds. `H` should be
+    /// positive definite.
+    ///
+    /// Returns the number of iterations and whether the solver converged.
+    fn solve(&self, u: &mut DVector<f32>, max_iterations: usize) -> (usize, bool) {
+        for iteration in 1..=max_iterations {
+            let mut largest_step: f32 = 0.;
+            for i in 0..u.len() {
+                let gradient = self.f[i] + self.h.row(i).dot(&u.transpose());
+                let value = (u[i] - gradient / self.h[(i, i)]).clamp(self.lower[i], self.upper[i]);
+                largest_step = largest_step.max((value - u[i]).abs());
+                u[i] = value;
+            }
+            if largest_step < SOLVER_TOLERANCE {
+                return (iteration, true);
+            }
+        }
+        (max_iterations, false)
+    }
+
+    /// Value of the cost for `u`.
+    fn cost(&self, u: &DVector<f32>) -> f32 {
+        0.5 * (u.transpose() * &self.h * u)[0] + self.f.dot(u) + self.c
+    }
+}
+
+/// Model predictive controller on the navigator errors, with actuator limits.
+#[derive(Debug)]
+pub struct MpcController {
+    config: MpcControllerConfig,
+    robot_model: RobotModelConfig,
+    last_command_time: f32,
+    /// Commands of the previous solution, for the warm start.
+    previous_solution: Option<DVector<f32>>,
+    current_record: MpcControllerRecord,
+}
+
+impl MpcController {
+    /// Makes a new [`MpcController`] from the given `config`, taking the robot model from
+    /// `physics_config` if not given.
+    pub fn from_config(
+        config: &MpcControllerConfig,
+        physics_config: &PhysicsConfig,
+        initial_time: f32,
+    ) -> Self {
+        let robot_model = match (&config.robot_model, physics_config) {
+            (Some(model), _) => model.clone(),
+            (None, PhysicsConfig::Internal(InternalPhysicConfig { model, .. })) => model.clone(),
+            (None, _) => {
+                warn!("No model given in MpcController Config... using default one");
+                RobotModelConfig::default()
+            }
+        };
+        Self {
+            config: config.clone(),
+            robot_model,
+            last_command_time: initial_time,
+            previous_solution: None,
+            current_record: MpcControllerRecord {
+                last_command_time: initial_time,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Commands of the model, with their weight and bounds, for the target velocity `v_ref`:
+    /// velocity deviation, angular velocity, and lateral velocity for the holonomic model.
+    fn inputs(&self, v_ref: f32) -> Vec<(f32, f32, f32)> {
+        let limits = &self.config.limits;
+        let weights = &self.config.weights;
+        let bounds = |limit: Option<f32>| limit.map_or((f32::MIN, f32::MAX), |l| (-l, l));
+        let (v_min, v_max) = bounds(limits.max_velocity);
+        let mut inputs = vec![(weights.velocity, v_min - v_ref, v_max - v_ref), {
+            let (min, max) = bounds(limits.max_angular_velocity);
+            (weights.angular_velocity, min, max)
+        }];
+        if let RobotModelConfig::Holonomic(_) = self.robot_model {
+            let (min, max) = bounds(limits.max_lateral_velocity);
+            inputs.push((weights.lateral_velocity, min, max));
+        }
+        inputs
+    }
+
+    /// Build the condensed QP of the horizon from the current errors `x0`.
+    fn build_qp(&self, x0: &DVector<f32>, v_ref: f32) -> BoxQp {
+        let horizon = self.config.horizon;
+        let dt = self.config.time_step;
+        let inputs = self.inputs(v_ref);
+        let m = inputs.len();
+
+        let mut a = DMatrix::<f32>::identity(STATE_SIZE, STATE_SIZE);
+        a[(1, 2)] = dt * v_ref;
+        let mut b = DMatrix::<f32>::zeros(STATE_SIZE, m);
+        b[(0, 0)] = -dt;
+        b[(2, 1)] = -dt;
+        if m > 2 {
+            b[(1, 2)] = -dt;
+        }
+        let weights = &self.config.weights;
+        let q = DMatrix::from_diagonal(&DVector::from_vec(vec![
+            weights.longitudinal,
+            weights.lateral,
+            weights.orientation,
+        ]));
+
+        // Predicted errors: x = phi x0 + gamma u
+        let mut phi = DMatrix::<f32>::zeros(horizon * STATE_SIZE, STATE_SIZE);
+        let mut gamma = DMatrix::<f32>::zeros(horizon * STATE_SIZE, horizon * m);
+        let mut a_power = a.clone();
+        let mut a_power_b = vec![b.clone()];
+        for k in 0..horizon {
+            phi.view_mut((k * STATE_SIZE, 0), (STATE_SIZE, STATE_SIZE))
+                .copy_from(&a_power);
+            for j in 0..=k {
+                gamma
+                    .view_mut((k * STATE_SIZE, j * m), (STATE_SIZE, m))
+                    .copy_from(&a_power_b[k - j]);
+            }
+            a_power = &a * &a_power;
+            let next = &a * &a_power_b[k];
+            a_power_b.push(next);
+        }
+        let mut q_bar = DMatrix::<f32>::zeros(horizon * STATE_SIZE, horizon * STATE_SIZE);
+        let mut r_bar = DVector::<f32>::zeros(horizon * m);
+        let mut lower = DVector::<f32>::zeros(horizon * m);
+        let mut upper = DVector::<f32>::zeros(horizon * m);
+        for k in 0..horizon {
+            q_bar
+                .view_mut((k * STATE_SIZE, k * STATE_SIZE), (STATE_SIZE, STATE_SIZE))
+                .copy_from(&q);
+            for (i, (weight, min, max)) in inputs.iter().enumerate() {
+                r_bar[k * m + i] = *weight;
+                lower[k * m + i] = *min;
+                upper[k * m + i] = *max;
+            }
+        }
+
+        // Cost: (phi x0 + gamma u)ᵀ q_bar (phi x0 + gamma u) + uᵀ r_bar u, halved
+        let free_errors = phi * x0;
+        let gamma_t_q = gamma.transpose() * &q_bar;
+        BoxQp {
+            h: &gamma_t_q * &gamma + DMatrix::from_diagonal(&r_bar),
+            f: gamma_t_q * &free_errors,
+            c: 0.5 * free_errors.dot(&(q_bar * &free_errors)),
+            lower,
+            upper,
+        }
+    }
+
+    /// Warm start: previous solution shifted by one step, within the bounds.
+    fn initial_solution(&self, qp: &BoxQp) -> DVector<f32> {
+        let size = qp.f.len();
+        let m = size / self.config.horizon;
+        let mut u = DVector::<f32>::zeros(size);
+        if let Some(previous) = &self.previous_solution
+            && previous.len() == size
+        {
+            u.rows_mut(0, size - m)
+                .copy_from(&previous.rows(m, size - m));
+            u.rows_mut(size - m, m)
+                .copy_from(&previous.rows(size - m, m));
+        }
+        for ((value, min), max) in u.iter_mut().zip(qp.lower.iter()).zip(qp.upper.iter()) {
+            *value = value.clamp(*min, *max);
+        }
+        u
+    }
+}
+
+impl Controller for MpcController {
+    fn make_command(&mut self, robot: &mut Node, error: &ControllerError, time: f32) -> Command {
+        let dt = time - self.last_command_time;
+        assert!(
+            dt > 0.,
+            "MpcController delta time should be positive: {} - {} = {} > 0",
+            time,
+            self.last_command_time,
+            dt
+        );
+        self.last_command_time = time;
+
+        // Target velocity = current velocity + velocity error. The last velocity command is
+        // used as current velocity if there is no ego estimate.
+        let current_velocity = robot
+            .state_estimator()
+            .and_then(|state_estimator| state_estimator.read_recover().world_state().ego)
+            .map_or(self.current_record.velocity, |state| state.velocity.x);
+        let v_ref = current_velocity + error.velocity;
+
+        let x0 = DVector::from_vec(vec![error.longitudinal, error.lateral, error.theta]);
+        let qp = self.build_qp(&x0, v_ref);
+        let mut u = self.initial_solution(&qp);
+        let (iterations, converged) = qp.solve(&mut u, self.config.max_iterations);
+
+        let velocity = v_ref + u[0];
+        let angular_velocity = u[1];
+        let command = match &self.robot_model {
+            RobotModelConfig::Unicycle(model) => {
+                let mut left_wheel_speed = velocity - angular_velocity * model.wheel_distance / 2.;
+                let mut right_wheel_speed = velocity + angular_velocity * model.wheel_distance / 2.;
+                if let Some(max_wheel_speed) = self.config.limits.max_wheel_speed {
+                    let fastest = left_wheel_speed.abs().max(right_wheel_speed.abs());
+                    if fastest > max_wheel_speed {
+                        left_wheel_speed *= max_wheel_speed / fastest;
+                        right_wheel_speed *= max_wheel_speed / fastest;
+                    }
+                }
+                Command::Unicycle(UnicycleCommand {
+                    left_wheel_speed,
+                    right_wheel_speed,
+                })
+            }
+            RobotModelConfig::Holonomic(_) => Command::Holonomic(HolonomicCommand {
+                longitudinal_velocity: velocity,
+                lateral_velocity: u[2],
+                angular_velocity,
+            }),
+        };
+
+        self.current_record = MpcControllerRecord {
+            reference_velocity: v_ref,
+            velocity,
+            cost: 2. * qp.cost(&u),
+            iterations,
+            max_iterations_reached: !converged,
+            command: command.clone(),
+            last_command_time: time,
+        };
+        self.previous_solution = Some(u);
+        command
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            commands: Some(vec![CommandKind::from(&self.robot_model)]),
+            ..Default::default()
+        }
+    }
+}
+
+impl Recordable<ControllerRecord> for MpcController {
+    fn record(&self) -> ControllerRecord {
+        ControllerRecord::Mpc(self.current_record.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::robot_models::unicycle::UnicycleConfig;
+
+    fn unicycle_mpc(limits: MpcLimitsConfig) -> MpcController {
+        MpcController::from_config(
+            &MpcControllerConfig {
+                robot_model: Some(RobotModelConfig::Unicycle(UnicycleConfig::default())),
+                limits,
+                ..Default::default()
+            },
+            &PhysicsConfig::Internal(InternalPhysicConfig::default()),
+            0.,
+        )
+    }
+
+    #[test]
+    fn turns_toward_the_target_within_limits() {
+        let mpc = unicycle_mpc(MpcLimitsConfig::default());
+        let x0 = DVector::from_vec(vec![0., 0.5, 0.3]);
+        let qp = mpc.build_qp(&x0, 1.);
+        let mut u = mpc.initial_solution(&qp);
+        let (_, converged) = qp.solve(&mut u, 1000);
+        assert!(converged);
+        // Target on the left and oriented to the left: turn left, without changing the speed
+        assert!(u[1] > 0., "{u}");
+        assert!(u[0].abs() < 1e-3, "{u}");
+        // Optimality against the unconstrained solution H u = -f
+        let optimum = qp.h.clone().cholesky().unwrap().solve(&-&qp.f);
+        assert!((&u - optimum).amax() < 1e-3);
+
+        let limited = unicycle_mpc(MpcLimitsConfig {
+            max_angular_velocity: Some(0.1),
+            max_velocity: Some(0.5),
+            ..Default::default()
+        });
+        let qp = limited.build_qp(&x0, 1.);
+        let mut u = limited.initial_solution(&qp);
+        qp.solve(&mut u, 1000);
+        assert_eq!(u[1], 0.1);
+        // Velocity brought back within the limit
+        assert!((1. + u[0] - 0.5).abs() < 1e-6, "{u}");
+    }
+}
@@ -505,12 +505,7 @@ impl PID {
     ) -> Self {
         let mut config_clone = config.clone();
         if config.robot_model.is_none() {
-            if let PhysicsConfig::Internal(InternalPhysicConfig {
-                model,
-                faults: _,
-                initial_state: _,
-            }) = physics_config
-            {
+            if let PhysicsConfig::Internal(InternalPhysicConfig { model, .. }) = physics_config {
                 config_clone.robot_model = Some(model.clone());
                 if config_clone.do_check().is_err() {
                     config_clone = PIDConfig::default_from_model(model);
@@ -541,6 +536,28 @@ impl PID {
     }
 }
 
+impl PID {
+    /// Configuration used by the controller, with the robot model resolved from physics if it
+    /// was not given.
+    pub fn config(&self) -> &PIDConfig {
+        &self.config
+    }
+
+    /// Replace the gains of the controller, keeping its internal state (integrals, velocity).
+    ///
+    /// The gains should follow the order and size of the robot model of [`PID::config`].
+    pub fn set_gains(
+        &mut self,
+        proportional_gains: &[f32],
+        derivative_gains: &[f32],
+        integral_gains: &[f32],
+    ) {
+        self.config.proportional_gains = proportional_gains.to_vec();
+        self.config.derivative_gains = derivative_gains.to_vec();
+        self.config.integral_gains = integral_gains.to_vec();
+    }
+}
+
 impl Default for PID {
     fn default() -> Self {
         Self::new()
@@ -550,6 +567,7 @@ impl Default for PID {
 use super::{Controller, ControllerRecord};
 use crate::controllers::ControllerError;
 use crate::node::Node;
+use crate::node::capabilities::{Capabilities, CommandKind};
 
 impl Controller for PID {
     fn make_command(&mut self, _robot: &mut Node, error: &ControllerError, time: f32) -> Command {
@@ -632,6 +650,54 @@ impl Controller for PID {
     }
 
     fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            commands: self
+                .config
+                .robot_model
+                .as_ref()
+                .map(|model| vec![CommandKind::from(model)]),
+            ..Default::default()
+        }
+    }
+
+    fn set_gains(
+        &mut self,
+        proportional: Option<&[f32]>,
+        derivative: Option<&[f32]>,
+        integral: Option<&[f32]>,
+    ) -> Result<(), String> {
+        for (name, new, current) in [
+            (
+                "proportional",
+                proportional,
+                &self.config.proportional_gains,
+            ),
+            ("derivative", derivative, &self.config.derivative_gains),
+            ("integral", integral, &self.config.integral_gains),
+        ] {
+            if let Some(new) = new
+                && new.len() != current.len()
+            {
+                return Err(format!(
+                    "Length of {name} gains mismatch ({} vs {} expected)",
+                    new.len(),
+                    current.len()
+                ));
+            }
+        }
+        if let Some(gains) = proportional {
+            self.config.proportional_gains = gains.to_vec();
+        }
+        if let Some(gains) = derivative {
+            self.config.derivative_gains = gains.to_vec();
+        }
+        if let Some(gains) = integral {
+            self.config.integral_gains = gains.to_vec();
+        }
+        Ok(())
+    }
 }
 
 impl Recordable<ControllerRecord> for PID {
@@ -0,0 +1,172 @@
+//! Spatial index of the map landmarks.
+//!
+//! The [`LandmarkIndex`] is a uniform grid: each cell lists the landmarks whose extent (the
+//! segment for the landmarks with a width) overlaps it. A range query only looks at the
+//! landmarks of the cells covered by the observation circle, instead of all the landmarks of the
+//! map, so the cost of the observations depends on the density of the map, not on its size.
+//!
+//! The index is built when the map is loaded, and updated cell by cell when a landmark moves
+//! ([`Environment::update_landmark`](super::Environment::update_landmark)).
+
+use std::collections::HashMap;
+
+use nalgebra::Vector2;
+
+use crate::environment::oriented_landmark::OrientedLandmark;
+
+/// Default size of the cells of the [`LandmarkIndex`], in meters.
+pub const DEFAULT_INDEX_CELL_SIZE: f32 = 5.;
+
+/// Uniform grid index of the landmarks of a map, by position in the landmark list.
+#[derive(Debug, Clone)]
+pub struct LandmarkIndex {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl Default for LandmarkIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_INDEX_CELL_SIZE)
+    }
+}
+
+impl LandmarkIndex {
+    /// Makes an empty index with square cells of `cell_size` meters.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0., "Cell size should be strictly positive");
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Makes an index of the `landmarks`, with square cells of `cell_size` meters.
+    pub fn build(landmarks: &[OrientedLandmark], cell_size: f32) -> Self {
+        let mut index = Self::new(cell_size);
+        for (i, landmark) in landmarks.iter().enumerate() {
+            index.insert(i, landmark);
+        }
+        index
+    }
+
+    /// Size of the cells, in meters.
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn cell(&self, x: f32, y: f32) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Cells overlapped by the bounding box of the landmark.
+    fn landmark_cells(&self, landmark: &OrientedLandmark) -> impl Iterator<Item = (i32, i32)> {
+        let (p1, p2) = landmark.extremities();
+        let min = self.cell(p1.x.min(p2.x), p1.y.min(p2.y));
+        let max = self.cell(p1.x.max(p2.x), p1.y.max(p2.y));
+        (min.0..=max.0).flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+    }
+
+    /// Add the landmark at position `i` of the landmark list.
+    pub fn insert(&mut self, i: usize, landmark: &OrientedLandmark) {
+        let cells: Vec<_> = self.landmark_cells(landmark).collect();
+        for cell in cells {
+            self.cells.entry(cell).or_default().push(i);
+        }
+    }
+
+    /// Remove the landmark at position `i` of the landmark list, `landmark` being its current
+    /// (indexed) geometry.
+    pub fn remove(&mut self, i: usize, landmark: &OrientedLandmark) {
+        let cells: Vec<_> = self.landmark_cells(landmark).collect();
+        for cell in cells {
+            if let Some(indices) = self.cells.get_mut(&cell) {
+                indices.retain(|&j| j != i);
+                if indices.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    /// Move the landmark at position `i` from the `old` geometry to the `new` one. Only the
+    /// cells which change are updated.
+    pub fn update(&mut self, i: usize, old: &OrientedLandmark, new: &OrientedLandmark) {
+        let old_cells: Vec<_> = self.landmark_cells(old).collect();
+        let new_cells: Vec<_> = self.landmark_cells(new).collect();
+        if old_cells == new_cells {
+            return;
+        }
+        self.remove(i, old);
+        self.insert(i, new);
+    }
+
+    /// Positions in the landmark list of the landmarks which can be at less than `max_distance`
+    /// of `position`, in increasing order. The candidates still need an exact distance check.
+    pub fn candidates(&self, position: &Vector2<f32>, max_distance: f32) -> Vec<usize> {
+        let min = self.cell(position.x - max_distance, position.y - max_distance);
+        let max = self.cell(position.x + max_distance, position.y + max_distance);
+        let mut candidates = Vec::new();
+        let query_cells = (max.0 as i64 - min.0 as i64 + 1) * (max.1 as i64 - min.1 as i64 + 1);
+        if query_cells > self.cells.len() as i64 {
+            // Query larger than the map: look at the occupied cells only
+            for (cell, indices) in &self.cells {
+                if (min.0..=max.0).contains(&cell.0) && (min.1..=max.1).contains(&cell.1) {
+                    candidates.extend_from_slice(indices);
+                }
+            }
+        } else {
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    if let Some(indices) = self.cells.get(&(x, y)) {
+                        candidates.extend_from_slice(indices);
+                    }
+                }
+            }
+        }
+        // Same order as the landmark list, for reproducible observations
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    fn landmark(id: i32, x: f32, y: f32, width: f32) -> OrientedLandmark {
+        OrientedLandmark {
+            id,
+            labels: Vec::new(),
+            pose: Vector3::new(x, y, 0.),
+            height: 0.,
+            width,
+        }
+    }
+
+    #[test]
+    fn candidates_and_moves() {
+        let landmarks = vec![
+            landmark(0, 1., 1., 0.),
+            landmark(1, 30., 0., 0.),
+            // Vertical wall from y = -20 to y = 20
+            landmark(2, 12., 0., 40.),
+        ];
+        let mut index = LandmarkIndex::build(&landmarks, 5.);
+        assert_eq!(index.candidates(&Vector2::new(0., 0.), 2.), vec![0]);
+        assert_eq!(index.candidates(&Vector2::new(10., 18.), 3.), vec![2]);
+        assert_eq!(
+            index.candidates(&Vector2::new(0., 0.), 1000.),
+            vec![0, 1, 2]
+        );
+
+        index.update(1, &landmarks[1], &landmark(1, 2., -1., 0.));
+        assert_eq!(index.candidates(&Vector2::new(0., 0.), 2.), vec![0, 1]);
+        assert!(index.candidates(&Vector2::new(30., 0.), 2.).is_empty());
+    }
+}
@@ -0,0 +1,289 @@
+//! Inaccuracies of the map known by the state estimators.
+//!
+//! The [`MapPerturbationConfig`] describes the errors of the prior map given to the state
+//! estimators ([`Environment::prior_map`](super::Environment::prior_map)): noise on the pose and
+//! the width of the landmarks, missing landmarks and extra landmarks which do not exist. The
+//! sensors still observe the true map, so the estimators relying on a "known" map can be
+//! evaluated against map errors without editing the map file.
+//!
+//! The perturbation is drawn once, when the environment is built, from the simulator seed.
+
+use std::f32::consts::PI;
+
+use nalgebra::Vector3;
+use simba_macros::config_derives;
+
+use crate::{
+    environment::oriented_landmark::OrientedLandmark,
+    utils::{
+        determinist_random_variable::{DeterministRandomVariableFactory, RandomVariableTypeConfig},
+        distributions::uniform::UniformRandomVariableConfig,
+    },
+};
+#[cfg(feature = "gui")]
+use crate::{gui::UIComponent, simulator::SimulatorConfig};
+
+/// Errors of the map known by the state estimators.
+///
+/// Each landmark is removed with the probability `missing_probability`, and the other ones get a
+/// noise on their pose (`x`, `y` and optionally `theta`) and on their width (only for the
+/// landmarks with a width). `extra_landmarks` point landmarks, with new ids, are added at uniform
+/// positions in the bounding box of the map.
+///
+/// Default values: no perturbation.
+///
+/// # Example
+/// ```yaml
+/// environment:
+///   map_path: map.yaml
+///   map_perturbation:
+///     position_noise:
+///       type: Normal
+///       mean: [0., 0.]
+///       covariance: [0.04, 0., 0., 0.04]
+///     missing_probability: 0.1
+///     extra_landmarks: 2
+/// ```
+#[config_derives]
+pub struct MapPerturbationConfig {
+    /// Noise added to the pose of the landmarks, of dimension 2 (`x`, `y`) or 3 (`x`, `y`,
+    /// `theta`).
+    #[check]
+    pub position_noise: RandomVariableTypeConfig,
+    /// One-dimensional noise added to the width of the landmarks with a width. The width stays
+    /// positive.
+    #[check]
+    pub width_noise: RandomVariableTypeConfig,
+    /// Probability for each landmark to be missing from the known map.
+    pub missing_probability: f32,
+    /// Number of landmarks added to the known map, which do not exist.
+    pub extra_landmarks: usize,
+}
+
+impl Default for MapPerturbationConfig {
+    fn default() -> Self {
+        Self {
+            position_noise: RandomVariableTypeConfig::None,
+            width_noise: RandomVariableTypeConfig::None,
+            missing_probability: 0.,
+            extra_landmarks: 0,
+        }
+    }
+}
+
+impl Check for MapPerturbationConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.position_noise.dim() > 3 {
+            errors.push(format!(
+                "Position noise should be of dimension 2 or 3, got dimension {}",
+                self.position_noise.dim()
+            ));
+        }
+        if self.width_noise.dim() > 1 {
+            errors.push(format!(
+                "Width noise should be one-dimensional, got dimension {}",
+                self.width_noise.dim()
+            ));
+        }
+        if !(0. ..=1.).contains(&self.missing_probability) {
+            errors.push(format!(
+                "Missing probability should be between 0 and 1, got {}",
+                self.missing_probability
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MapPerturbationConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Map perturbation").show(ui, |ui| {
+            ui.horizontal_top(|ui| {
+                ui.label("Position noise: ");
+                self.position_noise.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    &format!("position-noise-{unique_id}"),
+                );
+            });
+            ui.horizontal_top(|ui| {
+                ui.label("Width noise: ");
+                self.width_noise.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    &format!("width-noise-{unique_id}"),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Missing probability: ");
+                ui.add(
+                    egui::DragValue::new(&mut self.missing_probability)
+                        .range(0.0..=1.0)
+                        .speed(0.01),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Extra landmarks: ");
+                ui.add(egui::DragValue::new(&mut self.extra_landmarks));
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Map perturbation").show(ui, |ui| {
+            ui.horizontal_top(|ui| {
+                ui.label("Position noise: ");
+                self.position_noise
+                    .show(ui, ctx, &format!("position-noise-{unique_id}"));
+            });
+            ui.horizontal_top(|ui| {
+                ui.label("Width noise: ");
+                self.width_noise
+                    .show(ui, ctx, &format!("width-noise-{unique_id}"));
+            });
+            ui.label(format!("Missing probability: {}", self.missing_probability));
+            ui.label(format!("Extra landmarks: {}", self.extra_landmarks));
+        });
+    }
+}
+
+/// Apply the perturbation of `config` to the `landmarks`, with random variables made by
+/// `va_factory`.
+pub fn perturb_landmarks(
+    config: &MapPerturbationConfig,
+    landmarks: &[OrientedLandmark],
+    va_factory: &DeterministRandomVariableFactory,
+) -> Vec<OrientedLandmark> {
+    let draw = va_factory.make_variable(RandomVariableTypeConfig::Uniform(
+        UniformRandomVariableConfig {
+            min: vec![0.; 3],
+            max: vec![1.; 3],
+        },
+    ));
+    let position_noise = va_factory.make_variable(config.position_noise.clone());
+    let width_noise = va_factory.make_variable(config.width_noise.clone());
+
+    // One draw per landmark, indexed by its position in the map
+    let mut perturbed = Vec::with_capacity(landmarks.len() + config.extra_landmarks);
+    for (i, landmark) in landmarks.iter().enumerate() {
+        let seed = i as f32;
+        if draw.generate(seed)[0] < config.missing_probability {
+            continue;
+        }
+        let noise = position_noise.generate(seed);
+        let mut landmark = landmark.clone();
+        for (j, n) in noise.iter().take(3).enumerate() {
+            landmark.pose[j] += n;
+        }
+        if landmark.width > 0. {
+            landmark.width = (landmark.width + width_noise.generate(seed)[0]).max(0.);
+        }
+        perturbed.push(landmark);
+    }
+
+    if config.extra_landmarks > 0 && !landmarks.is_empty() {
+        let (min, max) = landmarks.iter().fold(
+            (
+                Vector3::new(f32::INFINITY, f32::INFINITY, -PI),
+                Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, PI),
+            ),
+            |(min, max), l| {
+                (
+                    Vector3::new(min.x.min(l.pose.x), min.y.min(l.pose.y), -PI),
+                    Vector3::new(max.x.max(l.pose.x), max.y.max(l.pose.y), PI),
+                )
+            },
+        );
+        let first_id = landmarks.iter().map(|l| l.id).max().unwrap_or(0) + 1;
+        for k in 0..config.extra_landmarks {
+            let u = draw.generate((landmarks.len() + k) as f32);
+            perturbed.push(OrientedLandmark {
+                id: first_id + k as i32,
+                labels: Vec::new(),
+                pose: min + (max - min).component_mul(&Vector3::new(u[0], u[1], u[2])),
+                height: 0.,
+                width: 0.,
+            });
+        }
+    }
+    perturbed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::distributions::fixed::FixedRandomVariableConfig;
+
+    #[test]
+    fn deterministic_perturbation() {
+        let landmarks: Vec<_> = (0..20)
+            .map(|i| OrientedLandmark {
+                id: i,
+                labels: Vec::new(),
+                pose: Vector3::new(i as f32, 0., 0.),
+                height: 1.,
+                width: 1.,
+            })
+            .collect();
+        let config = MapPerturbationConfig {
+            position_noise: RandomVariableTypeConfig::Fixed(FixedRandomVariableConfig {
+                values: vec![0.5, -1.],
+            }),
+            width_noise: RandomVariableTypeConfig::Fixed(FixedRandomVariableConfig {
+                values: vec![-2.],
+            }),
+            missing_probability: 0.5,
+            extra_landmarks: 3,
+        };
+        let perturbed = perturb_landmarks(
+            &config,
+            &landmarks,
+            &DeterministRandomVariableFactory::new(12.),
+        );
+        assert_eq!(
+            perturbed,
+            perturb_landmarks(
+                &config,
+                &landmarks,
+                &DeterministRandomVariableFactory::new(12.)
+            )
+        );
+
+        let (kept, extra): (Vec<_>, Vec<_>) = perturbed.iter().partition(|l| l.id < 20);
+        assert!(kept.len() < 20);
+        for landmark in kept {
+            assert_eq!(landmark.pose.x, landmark.id as f32 + 0.5);
+            assert_eq!(landmark.pose.y, -1.);
+            assert_eq!(landmark.width, 0.);
+        }
+        assert_eq!(
+            extra.iter().map(|l| l.id).collect::<Vec<_>>(),
+            vec![20, 21, 22]
+        );
+        assert!(
+            extra
+                .iter()
+                .all(|l| (0. ..=19.).contains(&l.pose.x) && l.pose.y == 0.)
+        );
+    }
+}
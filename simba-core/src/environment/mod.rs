@@ -5,12 +5,12 @@
 //! - [`Environment`] to query observable landmarks and line-of-sight,
 //! - [`Map`] as the in-memory map representation.
 //!
-//! Landmark geometry is represented by [`OrientedLandmark`].
+//! Landmark geometry is represented by [`OrientedLandmark`]. The landmarks in range of an
+//! observer are found with a [`LandmarkIndex`] built at map load. The map known by the state
+//! estimators can be perturbed with a [`MapPerturbationConfig`].
 
-#[cfg(feature = "gui")]
-use std::collections::BTreeMap;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::Path,
     sync::{Arc, RwLock},
 };
@@ -22,21 +22,31 @@ use serde::{Deserialize, Serialize};
 use simba_macros::config_derives;
 
 use crate::{
-    environment::oriented_landmark::OrientedLandmark,
+    environment::{
+        landmark_index::{DEFAULT_INDEX_CELL_SIZE, LandmarkIndex},
+        map_perturbation::{MapPerturbationConfig, perturb_landmarks},
+        oriented_landmark::OrientedLandmark,
+    },
     errors::{SimbaError, SimbaErrorTypes, SimbaResult},
     logger::{InternalLog, is_enabled},
     node::NodeMetaData,
+    state_estimators::State,
     utils::{
         SharedRoLock, SharedRwLock,
+        config_parsing::load_config_file,
+        determinist_random_variable::DeterministRandomVariableFactory,
         geometry::{
             segment_circle_intersection, segment_to_line_intersection,
             segment_triangle_intersection, segments_intersection,
         },
+        lock_recovery::RecoverRwLock,
     },
 };
 #[cfg(feature = "gui")]
 use crate::{gui::utils::path_finder, simulator::SimulatorConfig};
 
+pub mod landmark_index;
+pub mod map_perturbation;
 pub mod oriented_landmark;
 
 /// Configuration for building an [`Environment`].
@@ -44,13 +54,60 @@ pub mod oriented_landmark;
 /// The map contains the map geometry. It is loaded from a file when `map_path` is provided, or initialized as empty otherwise. The map file should be parsable by the [`Map`] struct, which currently supports a simple custom format (see its documentation for details).
 ///
 /// The map path is relative to the simulator config path.
+///
+/// Default values:
+/// - `map_path`: `None`
+/// - `known_map`: `true`
+/// - `index_cell_size`: `5.`
+/// - `map_perturbation`: `None`
+///
+/// # Example
+/// ```yaml
+/// environment:
+///   map_path: map.yaml
+///   known_map: false # SLAM study: the estimators start without landmarks
+///   index_cell_size: 5.
+/// ```
 #[config_derives]
-#[derive(Default)]
 pub struct EnvironmentConfig {
     /// Optional map file path.
     ///
     /// If `None` (default), an empty [`Map`] is used.
     pub map_path: Option<String>,
+    /// Give the landmarks of the map (ids and poses) to the state estimators as prior
+    /// knowledge ([`Environment::prior_landmarks`]). If `false`, the estimators start without
+    /// landmarks, to study SLAM with the same configuration.
+    pub known_map: bool,
+    /// Size of the cells of the spatial index of the landmarks ([`LandmarkIndex`]), in meters.
+    /// Around the detection distance of the sensors is a good choice for dense maps.
+    pub index_cell_size: f32,
+    /// Errors of the map known by the state estimators (with `known_map`), `None` for the
+    /// exact map.
+    #[check]
+    pub map_perturbation: Option<MapPerturbationConfig>,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            map_path: None,
+            known_map: true,
+            index_cell_size: DEFAULT_INDEX_CELL_SIZE,
+            map_perturbation: None,
+        }
+    }
+}
+
+impl Check for EnvironmentConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.index_cell_size <= 0. {
+            return Err(vec![format!(
+                "Index cell size should be strictly positive, got {}",
+                self.index_cell_size
+            )]);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "gui")]
@@ -58,11 +115,11 @@ impl crate::gui::UIComponent for EnvironmentConfig {
     fn show_mut(
         &mut self,
         ui: &mut egui::Ui,
-        _ctx: &egui::Context,
-        _buffer_stack: &mut BTreeMap<String, String>,
+        ctx: &egui::Context,
+        buffer_stack: &mut BTreeMap<String, String>,
         global_config: &SimulatorConfig,
-        _current_node_name: Option<&String>,
-        _unique_id: &str,
+        current_node_name: Option<&String>,
+        unique_id: &str,
     ) {
         ui.horizontal(|ui| {
             ui.label("Map path: ");
@@ -75,9 +132,38 @@ impl crate::gui::UIComponent for EnvironmentConfig {
                 self.map_path = Some(String::new());
             }
         });
+        ui.checkbox(&mut self.known_map, "Map known by the state estimators");
+        ui.horizontal(|ui| {
+            ui.label("Index cell size: ");
+            ui.add(
+                egui::DragValue::new(&mut self.index_cell_size)
+                    .range(0.01..=f32::MAX)
+                    .suffix(" m"),
+            );
+        });
+        ui.horizontal_top(|ui| {
+            if let Some(map_perturbation) = &mut self.map_perturbation {
+                map_perturbation.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    &format!("map-perturbation-{unique_id}"),
+                );
+                if ui.button("-").clicked() {
+                    self.map_perturbation = None;
+                }
+            } else {
+                ui.label("Map perturbation: ");
+                if ui.button("+").clicked() {
+                    self.map_perturbation = Some(MapPerturbationConfig::default());
+                }
+            }
+        });
     }
 
-    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
         ui.horizontal(|ui| {
             ui.label("Map path: ");
             if let Some(map_path) = &self.map_path {
@@ -86,6 +172,14 @@ impl crate::gui::UIComponent for EnvironmentConfig {
                 ui.label("None");
             }
         });
+        ui.label(format!(
+            "Map known by the state estimators: {}",
+            self.known_map
+        ));
+        ui.label(format!("Index cell size: {} m", self.index_cell_size));
+        if let Some(map_perturbation) = &self.map_perturbation {
+            map_perturbation.show(ui, ctx, &format!("map-perturbation-{unique_id}"));
+        }
     }
 }
 
@@ -96,10 +190,41 @@ type CacheValue = (
     Vec<(OrientedLandmark, Option<TwoPoints>)>,
 );
 
+/// Robots which can obstruct the view from an observer, as landmarks facing it.
+///
+/// Made once for all the targets of an observation with [`Environment::robot_obstructions`], as
+/// the robots are read from their meta-data. The robot with the observer in its footprint is not
+/// included.
+#[derive(Debug, Clone, Default)]
+pub struct RobotObstructions {
+    obstructions: Vec<(OrientedLandmark, Option<TwoPoints>)>,
+    /// Position and footprint radius of the robot of each obstruction.
+    footprints: Vec<(Vector2<f32>, f32)>,
+}
+
+impl RobotObstructions {
+    /// Obstructions of the robots whose footprint does not contain `target`.
+    fn excluding<'a>(
+        &'a self,
+        target: &'a Vector2<f32>,
+    ) -> impl Iterator<Item = &'a (OrientedLandmark, Option<TwoPoints>)> + 'a {
+        self.obstructions
+            .iter()
+            .zip(&self.footprints)
+            .filter(move |(_, (position, radius))| (target - position).norm() > *radius)
+            .map(|(obstruction, _)| obstruction)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 /// Runtime environment state containing map geometry and per-node metadata.
 pub struct Environment {
     map: Map,
+    /// Spatial index of `map.landmarks`.
+    landmark_index: LandmarkIndex,
+    /// Landmarks known by the state estimators: the (perturbed) map with `known_map`, empty
+    /// otherwise.
+    prior_map: Vec<OrientedLandmark>,
     meta_data_list: SharedRwLock<HashMap<String, SharedRoLock<NodeMetaData>>>,
     /// Cache for landmark_in_range, to avoid recomputing it multiple times for the same position and max_distance.
     cache: SharedRwLock<HashMap<String, CacheValue>>,
@@ -109,17 +234,28 @@ impl Environment {
     /// Creates an [`Environment`] from [`EnvironmentConfig`].
     ///
     /// Loads the configured map path when provided; otherwise initializes an empty [`Map`].
+    /// The perturbation of the known map is drawn with `va_factory`.
     pub fn from_config(
         config: &EnvironmentConfig,
         global_config: &SimulatorConfig,
+        va_factory: &DeterministRandomVariableFactory,
     ) -> SimbaResult<Self> {
         let map = if let Some(map_path) = &config.map_path {
             Map::load_from_path(&global_config.base_path.join(map_path))?
         } else {
             Map::new()
         };
+        let prior_map = match (&config.map_perturbation, config.known_map) {
+            (_, false) => Vec::new(),
+            (Some(map_perturbation), true) => {
+                perturb_landmarks(map_perturbation, &map.landmarks, va_factory)
+            }
+            (None, true) => map.landmarks.clone(),
+        };
         Ok(Self {
+            landmark_index: LandmarkIndex::build(&map.landmarks, config.index_cell_size),
             map,
+            prior_map,
             meta_data_list: Arc::new(RwLock::new(HashMap::new())),
             cache: Arc::new(RwLock::new(HashMap::new())),
         })
@@ -130,6 +266,56 @@ impl Environment {
         &self.map
     }
 
+    /// Replace the landmark of the map with the same id as `landmark` (moved landmark), and update
+    /// the spatial index incrementally.
+    ///
+    /// Returns an error if no landmark of the map has this id.
+    pub fn update_landmark(&mut self, landmark: OrientedLandmark) -> SimbaResult<()> {
+        let Some(i) = self.map.landmarks.iter().position(|l| l.id == landmark.id) else {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ImplementationError,
+                format!("No landmark with id {} in the map", landmark.id),
+            ));
+        };
+        self.landmark_index
+            .update(i, &self.map.landmarks[i], &landmark);
+        self.map.landmarks[i] = landmark;
+        self.cache.write().unwrap().clear();
+        Ok(())
+    }
+
+    /// Landmarks known by the state estimators: the landmarks of the map with `known_map`, with
+    /// the errors of the map perturbation, none otherwise (SLAM).
+    ///
+    /// The sensors observe the true map ([`Environment::map`]).
+    pub fn prior_map(&self) -> &[OrientedLandmark] {
+        &self.prior_map
+    }
+
+    /// Poses of the landmarks given to the state estimators as prior knowledge
+    /// ([`Environment::prior_map`]), indexed by id.
+    pub fn prior_landmarks(&self) -> BTreeMap<i32, State> {
+        self.prior_map
+            .iter()
+            .map(|l| (l.id, State::from_vector(l.pose.as_slice())))
+            .collect()
+    }
+
+    /// Returns `true` if the straight path from `from` to `to` crosses no wall of the map
+    /// (landmark with a width), whatever its height. The robots are not considered.
+    ///
+    /// Planners can check their paths against the true map with it, in "known map" scenarios.
+    pub fn is_path_free(&self, from: &Vector2<f32>, to: &Vector2<f32>) -> bool {
+        self.map
+            .landmarks
+            .iter()
+            .filter(|landmark| landmark.width > 0.)
+            .all(|landmark| {
+                let (p1, p2) = landmark.extremities();
+                segments_intersection(from, to, &p1.xy(), &p2.xy()).is_none()
+            })
+    }
+
     /// Get the list of landmarks that are in range from the given position.
     /// For widthed landmarks, they are returned if they are in the observation circle or intersect it.
     /// The intersection points are also returned, which can be extremities of the landmark of intersection with the observation circle.
@@ -157,7 +343,12 @@ impl Environment {
         // Intersections concerns only non-ponctual landmarks and contains either the intersection
         // with the detection circle, or extremitie(s) of the landmark segment if inside the
         // detection circle
-        for landmark in &self.map.landmarks {
+        for landmark in self
+            .landmark_index
+            .candidates(position, max_distance)
+            .into_iter()
+            .map(|i| &self.map.landmarks[i])
+        {
             let d = ((landmark.pose.x - position.x).powi(2)
                 + (landmark.pose.y - position.y).powi(2))
             .sqrt();
@@ -206,8 +397,65 @@ impl Environment {
         in_range_landmarks
     }
 
+    /// Get the robots which can obstruct the view from `position`, closer than `max_distance`.
+    ///
+    /// A robot obstructs the view if it has a height and a footprint. It is seen as a segment of
+    /// the width of its footprint, facing the observer. The robot whose footprint contains the
+    /// observer (the observer itself) is ignored.
+    ///
+    /// The id of a robot is `i32::MIN` plus its index in the sorted node names: it is the same for
+    /// all the observers, and is not used by the map landmarks.
+    pub fn robot_obstructions(
+        &self,
+        position: &Vector2<f32>,
+        max_distance: f32,
+    ) -> RobotObstructions {
+        let mut robots = RobotObstructions::default();
+        let meta_data_list = self.meta_data_list.read_recover();
+        let mut names: Vec<&String> = meta_data_list.keys().collect();
+        names.sort();
+        for (i, name) in names.into_iter().enumerate() {
+            let meta_data = meta_data_list[name].read_recover();
+            let Some(robot_position) = meta_data.position else {
+                continue;
+            };
+            let radius = meta_data.footprint_radius;
+            if radius <= 0. || meta_data.height <= 0. {
+                continue;
+            }
+            let robot_position = Vector2::from(robot_position);
+            let to_robot = robot_position - position;
+            if to_robot.norm() > max_distance + radius || to_robot.norm() <= radius {
+                continue;
+            }
+            let direction = to_robot.normalize();
+            let half_width = Vector2::new(-direction.y, direction.x) * radius;
+            if is_enabled(InternalLog::EnvironmentDetailed) {
+                debug!("Robot {} can obstruct the view", meta_data.name);
+            }
+            robots.obstructions.push((
+                OrientedLandmark {
+                    id: i32::MIN + i as i32,
+                    labels: vec![meta_data.name.clone()],
+                    pose: Vector3::new(
+                        robot_position.x,
+                        robot_position.y,
+                        direction.y.atan2(direction.x),
+                    ),
+                    height: meta_data.height,
+                    width: 2. * radius,
+                },
+                Some((robot_position + half_width, robot_position - half_width)),
+            ));
+            robots.footprints.push((robot_position, radius));
+        }
+        robots
+    }
+
     /// Get the list of landmarks that are observable from the given position, considering the detection distance and possible obstructions.
     ///
+    /// The landmarks of the map, and the robots with a height and a footprint, can obstruct the view.
+    ///
     /// # Arguments
     /// * `position` - The position of the observer.
     /// * `observer_height` - The height of the observer, used for obstruction checks. If None, no obstruction checks are performed (equivalent to xray mode).
@@ -223,6 +471,11 @@ impl Environment {
         cache_key: Option<String>,
     ) -> Vec<OrientedLandmark> {
         let in_range_landmarks = self.landmarks_in_range(position, max_distance, cache_key);
+        let in_range_robots = if observer_height.is_some() {
+            self.robot_obstructions(position, max_distance)
+        } else {
+            RobotObstructions::default()
+        };
 
         let mut observed_landmarks = Vec::new();
 
@@ -236,7 +489,10 @@ impl Environment {
             if let Some(observer_height) = observer_height {
                 // Check for obstruction
                 // TODO: use a more efficient algorithm, and less specific case-oriented
-                for (possible_obstruction, possible_intersect) in &in_range_landmarks {
+                for (possible_obstruction, possible_intersect) in in_range_landmarks
+                    .iter()
+                    .chain(&in_range_robots.obstructions)
+                {
                     if is_enabled(InternalLog::Environment)
                         || is_enabled(InternalLog::EnvironmentDetailed)
                     {
@@ -511,9 +767,12 @@ impl Environment {
 
     /// Returns whether a target point is observable from an observer position.
     ///
-    /// Visibility is constrained by `max_distance` and by occlusions from landmarks with
+    /// Visibility is constrained by `max_distance` and by occlusions from landmarks and robots with
     /// sufficient height. If either `target_height` or `observer_height` is `None`, obstruction
     /// checks are skipped (x-ray behavior).
+    ///
+    /// To check several targets from the same observer, make the [`RobotObstructions`] once and
+    /// use [`Environment::is_target_observable_with`].
     pub fn is_target_observable(
         &self,
         target_position: &Vector2<f32>,
@@ -522,6 +781,36 @@ impl Environment {
         observer_height: Option<f32>,
         max_distance: f32,
         cache_key: Option<String>,
+    ) -> bool {
+        let robots = if target_height.is_some() && observer_height.is_some() {
+            self.robot_obstructions(observer_position, max_distance)
+        } else {
+            RobotObstructions::default()
+        };
+        self.is_target_observable_with(
+            target_position,
+            target_height,
+            observer_position,
+            observer_height,
+            max_distance,
+            cache_key,
+            &robots,
+        )
+    }
+
+    /// Same as [`Environment::is_target_observable`], with the robot obstructions made by
+    /// [`Environment::robot_obstructions`] from the observer position. The robot of the target
+    /// (the one whose footprint contains it) does not obstruct it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn is_target_observable_with(
+        &self,
+        target_position: &Vector2<f32>,
+        target_height: Option<f32>,
+        observer_position: &Vector2<f32>,
+        observer_height: Option<f32>,
+        max_distance: f32,
+        cache_key: Option<String>,
+        robots: &RobotObstructions,
     ) -> bool {
         if (target_position - observer_position).norm() > max_distance {
             return false;
@@ -535,8 +824,10 @@ impl Environment {
 
         let in_range_landmarks =
             self.landmarks_in_range(observer_position, max_distance, cache_key);
-
-        for (possible_obstruction, possible_intersect) in &in_range_landmarks {
+        for (possible_obstruction, possible_intersect) in in_range_landmarks
+            .iter()
+            .chain(robots.excluding(target_position))
+        {
             if is_enabled(InternalLog::EnvironmentDetailed) {
                 debug!(
                     "Checking obstruction of target by landmark {}",
@@ -582,7 +873,7 @@ impl Environment {
 
     /// Clears all stored node metadata.
     pub fn clear_meta_data(&self) {
-        self.meta_data_list.write().unwrap().clear();
+        self.meta_data_list.write_recover().clear();
     }
 
     /// Returns shared access to node metadata indexed by node name.
@@ -593,8 +884,7 @@ impl Environment {
     /// Inserts or replaces metadata for a node.
     pub fn insert_meta_data(&self, node_name: String, meta_data: SharedRoLock<NodeMetaData>) {
         self.meta_data_list
-            .write()
-            .unwrap()
+            .write_recover()
             .insert(node_name, meta_data);
     }
 }
@@ -635,19 +925,102 @@ impl Map {
 
     /// Load the map from the given `path`.
     pub fn load_from_path(path: &Path) -> SimbaResult<Map> {
-        let map: Map = match confy::load_path(path) {
-            Ok(config) => config,
-            Err(error) => {
-                return Err(SimbaError::new(
-                    SimbaErrorTypes::ConfigError,
-                    format!(
-                        "Error from Confy while loading the map file {} : {}",
-                        path.display(),
-                        error
-                    ),
-                ));
-            }
-        };
+        let map: Map = load_config_file(path, "map")?;
         Ok(map)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_free_of_walls() {
+        let landmark = |id, x, y, width| OrientedLandmark {
+            id,
+            labels: Vec::new(),
+            pose: Vector3::new(x, y, 0.),
+            height: 1.,
+            width,
+        };
+        // Wall from (2, -1) to (2, 1), and a point landmark which does not block the paths
+        let environment = Environment {
+            map: Map {
+                landmarks: vec![landmark(1, 2., 0., 2.), landmark(2, 0., 3., 0.)],
+            },
+            ..Default::default()
+        };
+        let free = |from: [f32; 2], to: [f32; 2]| {
+            environment.is_path_free(&Vector2::from(from), &Vector2::from(to))
+        };
+        assert!(!free([0., 0.], [4., 0.]));
+        assert!(!free([4., 0.5], [0., -0.5]));
+        assert!(free([0., 2.], [4., 2.]));
+        assert!(free([0., 0.], [1.5, 0.]));
+        assert!(free([-1., 3.], [1., 3.]));
+    }
+
+    #[test]
+    fn robots_obstruct_the_view() {
+        let environment = Environment::default();
+        for (name, position, height) in [
+            ("observer", [0., 0.], 1.),
+            ("blocker", [2., 0.], 1.),
+            ("target", [4., 0.], 1.),
+            ("aside", [0., 4.], 0.),
+        ] {
+            environment.insert_meta_data(
+                name.to_string(),
+                Arc::new(RwLock::new(NodeMetaData {
+                    name: name.to_string(),
+                    node_type: crate::node::node_factory::NodeType::Robot,
+                    model_name: String::new(),
+                    labels: Vec::new(),
+                    state: crate::node::NodeState::Running,
+                    position: Some(position),
+                    footprint_radius: 0.5,
+                    height,
+                })),
+            );
+        }
+        let observer = Vector2::new(0., 0.);
+        let robots = environment.robot_obstructions(&observer, 10.);
+        // Ids in the order of the names, the observer and the robot without height are ignored
+        let ids: Vec<(i32, String)> = robots
+            .obstructions
+            .iter()
+            .map(|(robot, _)| (robot.id, robot.labels[0].clone()))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                (i32::MIN + 1, "blocker".to_string()),
+                (i32::MIN + 3, "target".to_string())
+            ]
+        );
+        let observable = |target: [f32; 2]| {
+            environment.is_target_observable_with(
+                &Vector2::from(target),
+                Some(0.5),
+                &observer,
+                Some(0.5),
+                10.,
+                None,
+                &robots,
+            )
+        };
+        assert!(!observable([4., 0.]));
+        // A robot does not obstruct itself
+        assert!(observable([2., 0.]));
+        assert!(observable([0., 4.]));
+        // X-ray
+        assert!(environment.is_target_observable(
+            &Vector2::new(4., 0.),
+            Some(0.5),
+            &observer,
+            None,
+            10.,
+            None
+        ));
+    }
+}
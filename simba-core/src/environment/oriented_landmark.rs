@@ -7,7 +7,7 @@
 /// Landmark entry loaded from map data.
 ///
 /// The pose is represented as `[x, y, theta]` in a [`Vector3`] where `theta` is in radians.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct OrientedLandmark {
     /// Unique landmark identifier.
     pub id: i32,
@@ -8,8 +8,13 @@
 //! of errors. They cover a wide range of error categories, from mathematical errors to network errors, and can
 //! be extended as needed.
 use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::RefCell,
     error::Error,
     fmt::{Debug, Display},
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
 };
 
 use simba_macros::EnumToString;
@@ -40,24 +45,87 @@ pub enum SimbaErrorTypes {
     ServiceError(ServiceError),
     /// An error that occurs during the call to the [`PluginAPI`](crate::plugin_api::PluginAPI).
     ExternalAPIError,
+    /// An error that occurs when a client of the asynchronous API gives a wrong access token.
+    AuthenticationError,
+    /// A panic caught in a node thread (including in plugins or Python modules), see [`catch_panic`].
+    PanicError,
 }
 
-/// Error struct used in Simba. It contains the type of the error and a detailed message.
+/// Location of an error in the simulation, filled at module boundaries.
+///
+/// Each field is set by the innermost boundary which knows it: the `with_*` methods of
+/// [`SimbaError`] do not overwrite a field already set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimbaErrorContext {
+    /// Name of the node where the error occurred.
+    pub node: Option<String>,
+    /// Kind of module where the error occurred (e.g. `navigator`, `state_estimator`).
+    pub module: Option<String>,
+    /// Simulation time of the error.
+    pub time: Option<f32>,
+    /// Path of the faulty element in the configuration (e.g. `robots/robot1/navigator`).
+    pub config_path: Option<String>,
+}
+
+impl SimbaErrorContext {
+    /// Returns true if no context field is set.
+    pub fn is_empty(&self) -> bool {
+        self.node.is_none()
+            && self.module.is_none()
+            && self.time.is_none()
+            && self.config_path.is_none()
+    }
+}
+
+impl Display for SimbaErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields = Vec::new();
+        if let Some(node) = &self.node {
+            fields.push(format!("node '{node}'"));
+        }
+        if let Some(module) = &self.module {
+            fields.push(format!("module {module}"));
+        }
+        if let Some(time) = &self.time {
+            fields.push(format!("time {time}"));
+        }
+        if let Some(config_path) = &self.config_path {
+            fields.push(format!("config '{config_path}'"));
+        }
+        write!(f, "{}", fields.join(", "))
+    }
+}
+
+/// Error struct used in Simba. It contains the type of the error, a detailed message and
+/// the [`SimbaErrorContext`] where it occurred.
 #[derive(Clone)]
 pub struct SimbaError {
     error_type: SimbaErrorTypes,
     what: String,
+    // Boxed to keep `SimbaResult` small
+    context: Box<SimbaErrorContext>,
 }
 
 impl SimbaError {
     /// Create a new SimbaError with the given type and message.
     pub fn new(error_type: SimbaErrorTypes, what: String) -> Self {
-        Self { error_type, what }
+        Self {
+            error_type,
+            what,
+            context: Box::default(),
+        }
     }
 
-    /// Get a detailed error message, including the type of the error and the message.
+    /// Get a detailed error message, including the type of the error, the context and the message.
     pub fn detailed_error(&self) -> String {
-        format!("Simba Error of type {}: {}", self.error_type, self.what)
+        if self.context.is_empty() {
+            format!("Simba Error of type {}: {}", self.error_type, self.what)
+        } else {
+            format!(
+                "Simba Error of type {} ({}): {}",
+                self.error_type, self.context, self.what
+            )
+        }
     }
 
     /// Get the type of the error.
@@ -65,13 +133,47 @@ impl SimbaError {
         self.error_type.clone()
     }
 
+    /// Get the context where the error occurred.
+    pub fn context(&self) -> &SimbaErrorContext {
+        &self.context
+    }
+
     /// Chain a new error message to the current error, to provide more context about the error.
     pub fn chain(self, what: String) -> Self {
         Self {
             error_type: self.error_type,
             what: format!("{}\n↪ {}", self.what, what),
+            context: self.context,
         }
     }
+
+    /// Set the node name of the context, if not already set.
+    pub fn with_node(mut self, node: &str) -> Self {
+        self.context.node.get_or_insert_with(|| node.to_string());
+        self
+    }
+
+    /// Set the module kind of the context, if not already set.
+    pub fn with_module(mut self, module: &str) -> Self {
+        self.context
+            .module
+            .get_or_insert_with(|| module.to_string());
+        self
+    }
+
+    /// Set the simulation time of the context, if not already set.
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.context.time.get_or_insert(time);
+        self
+    }
+
+    /// Set the configuration path of the context, if not already set.
+    pub fn with_config_path(mut self, config_path: &str) -> Self {
+        self.context
+            .config_path
+            .get_or_insert_with(|| config_path.to_string());
+        self
+    }
 }
 
 impl Display for SimbaError {
@@ -82,7 +184,7 @@ impl Display for SimbaError {
 
 impl Debug for SimbaError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Simba Error of type {}: {}", self.error_type, self.what)
+        write!(f, "{}", self.detailed_error())
     }
 }
 
@@ -90,3 +192,87 @@ impl Error for SimbaError {}
 
 /// Type alias for results returned by Simba functions.
 pub type SimbaResult<T> = Result<T, SimbaError>;
+
+thread_local! {
+    static PANIC_BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
+/// Run `f`, converting a panic into a [`SimbaErrorTypes::PanicError`] with the panic message
+/// and the backtrace of the panic location.
+///
+/// A panic hook is installed on first call to record the backtrace. It calls the previous hook,
+/// so the panic is still printed.
+pub fn catch_panic<T>(f: impl FnOnce() -> SimbaResult<T>) -> SimbaResult<T> {
+    PANIC_HOOK.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            PANIC_BACKTRACE.with(|b| *b.borrow_mut() = Some(Backtrace::force_capture()));
+            previous_hook(info);
+        }));
+    });
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(ret) => ret,
+        Err(payload) => {
+            let backtrace = PANIC_BACKTRACE
+                .with(|b| b.borrow_mut().take())
+                .map(|b| b.to_string())
+                .unwrap_or_default();
+            Err(SimbaError::new(
+                SimbaErrorTypes::PanicError,
+                format!(
+                    "Panic: {}\nBacktrace:\n{}",
+                    panic_message(&payload),
+                    backtrace
+                ),
+            ))
+        }
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_keeps_innermost_values() {
+        let error = SimbaError::new(SimbaErrorTypes::ConfigError, "Bad value".to_string());
+        assert_eq!(
+            error.detailed_error(),
+            "Simba Error of type ConfigError: Bad value"
+        );
+
+        let error = error
+            .with_module("navigator")
+            .with_node("robot1")
+            .with_time(2.5)
+            .with_module("controller")
+            .with_node("robot2");
+        assert_eq!(error.context().node.as_deref(), Some("robot1"));
+        assert_eq!(error.context().module.as_deref(), Some("navigator"));
+        assert_eq!(
+            error.detailed_error(),
+            "Simba Error of type ConfigError (node 'robot1', module navigator, time 2.5): Bad value"
+        );
+    }
+
+    #[test]
+    fn catch_panic_converts_to_error() {
+        assert_eq!(catch_panic(|| Ok(3)).unwrap(), 3);
+        let error = catch_panic::<()>(|| panic!("Module failure {}", 42)).unwrap_err();
+        assert_eq!(error.error_type(), SimbaErrorTypes::PanicError);
+        assert!(error.detailed_error().contains("Panic: Module failure 42"));
+        assert!(error.detailed_error().contains("Backtrace:"));
+    }
+}
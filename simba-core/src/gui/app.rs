@@ -1,6 +1,6 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
-    path::Path,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     time::{self, Duration},
 };
@@ -16,17 +16,22 @@ use crate::{
     gui::{
         UIComponent,
         drawables::popup::Popup,
-        panels::{broker::BrokerPanel, virtual_nodes::VirtualNodesPanel},
+        panels::{
+            broker::BrokerPanel, fleet_status::FleetStatusPanel,
+            node_inspector::NodeInspectorPanel, virtual_nodes::VirtualNodesPanel,
+        },
     },
     node::node_factory::NodeRecord,
     plugin_api::PluginAPI,
-    simulator::{Record, SimbaBroker, Simulator, SimulatorConfig},
+    simulator::{Record, SimbaBroker, SimulatorConfig},
     utils::{SharedMutex, SharedRoLock, maths::round_precision, numbers::OrderedF32},
 };
 
 use super::{
     configurator::Configurator,
     drawables::{self},
+    placement::FleetPlacement,
+    result_loader::LazyResultLoader,
 };
 
 pub struct PainterInfo {
@@ -135,14 +140,19 @@ struct PrivateParams {
     playing: Option<(f32, std::time::Instant)>,
     simulation_run: bool,
     configurator: Option<Configurator>,
+    placement: Option<FleetPlacement>,
     error_buffer: Vec<(time::Instant, SimbaError)>,
     painter_info: PainterInfo,
     popups: Vec<Popup>,
     record_buffer: SharedMutex<Vec<Record>>,
+    result_loader: SharedMutex<Option<LazyResultLoader>>,
     virtual_nodes_panel: VirtualNodesPanel,
+    node_inspector_panel: NodeInspectorPanel,
     broker_panel: Option<BrokerPanel>,
+    fleet_status_panel: Option<FleetStatusPanel>,
     current_max_time: f32,
     drawable_instants: BTreeSet<OrderedF32>,
+    annotation_text: String,
 }
 
 impl Default for PrivateParams {
@@ -162,14 +172,19 @@ impl Default for PrivateParams {
             playing: None,
             simulation_run: false,
             configurator: None,
+            placement: None,
             error_buffer: Vec::new(),
             painter_info: PainterInfo::default(),
             popups: Vec::new(),
             record_buffer: Arc::new(Mutex::new(Vec::new())),
+            result_loader: Arc::new(Mutex::new(None)),
             virtual_nodes_panel: VirtualNodesPanel::new(),
+            node_inspector_panel: NodeInspectorPanel::new(),
             broker_panel: None,
+            fleet_status_panel: None,
             current_max_time: 0.,
             drawable_instants: BTreeSet::new(),
+            annotation_text: String::new(),
         }
     }
 }
@@ -178,7 +193,9 @@ impl Default for PrivateParams {
 struct EnabledViews {
     configuration: bool,
     virtual_nodes: bool,
+    node_inspector: bool,
     broker: bool,
+    fleet_status: bool,
 }
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
@@ -290,6 +307,9 @@ impl SimbaApp {
                 .unwrap()
                 .get_broker() as SharedRoLock<SimbaBroker>,
         ));
+        n.p.fleet_status_panel = Some(FleetStatusPanel::new(
+            n.p.api.lock().unwrap().simulator_api.fleet_status.clone(),
+        ));
         n
     }
 
@@ -339,6 +359,15 @@ impl SimbaApp {
                 .unwrap()
                 .get_broker() as SharedRoLock<SimbaBroker>,
         ));
+        self.p.fleet_status_panel = Some(FleetStatusPanel::new(
+            self.p
+                .api
+                .lock()
+                .unwrap()
+                .simulator_api
+                .fleet_status
+                .clone(),
+        ));
         self
     }
 
@@ -381,6 +410,9 @@ impl SimbaApp {
                 self.p.current_draw_time,
             )?);
         }
+        if let Some(placement) = &self.p.placement {
+            shapes.extend(placement.draw(&self.p.painter_info, self.drawing_scale)?);
+        }
         for drawable in &self.p.drawables {
             shapes.extend(drawable.draw(
                 ui,
@@ -400,6 +432,9 @@ impl SimbaApp {
         _viewport: Rect,
         response: &Response,
     ) {
+        if let Some(placement) = &mut self.p.placement {
+            placement.react(response, &self.p.painter_info, self.drawing_scale);
+        }
         for robot in self.p.robots.values_mut() {
             robot.react(
                 ui,
@@ -423,13 +458,124 @@ impl SimbaApp {
         }
     }
 
+    /// Apply the index of the result file loaded in view, and request the records around the
+    /// displayed time.
+    fn update_result_loader(&mut self) {
+        let result_loader = self.p.result_loader.clone();
+        let mut result_loader = result_loader.lock().unwrap();
+        let Some(loader) = result_loader.as_mut() else {
+            return;
+        };
+        let now = time::Instant::now();
+        for e in loader.take_errors() {
+            self.p.error_buffer.push((now, e));
+        }
+        if let Some(index) = loader.take_index() {
+            if let Some((_, max_time)) = index.time_range() {
+                self.p.current_max_time = self.p.current_max_time.max(max_time);
+                self.duration = self.duration.max(max_time);
+            }
+            self.p
+                .drawable_instants
+                .extend(index.entries().iter().map(|e| OrderedF32(e.time)));
+            self.p
+                .api
+                .lock()
+                .unwrap()
+                .simulator_api
+                .annotations
+                .extend(index.annotations().iter().cloned());
+        }
+        for (from, to) in loader.request_time(self.p.current_draw_time) {
+            self.remove_results(from, to);
+        }
+    }
+
+    /// Remove the records with a time in `[from, to[`, evicted by the result loader.
+    fn remove_results(&mut self, from: f32, to: f32) {
+        for robot in self.p.robots.values_mut() {
+            robot.remove_records(from, to);
+        }
+        self.p.virtual_nodes_panel.remove_records(from, to);
+        self.p.node_inspector_panel.remove_records(from, to);
+        for drawable in self.p.drawables.iter_mut() {
+            drawable.remove_records(from, to);
+        }
+    }
+
+    /// Draw the annotations as markers on the time slider.
+    fn draw_annotation_markers(&self, ui: &mut egui::Ui, slider: &Response) {
+        if self.duration <= 0. {
+            return;
+        }
+        let annotations = self.p.api.lock().unwrap().simulator_api.annotations.list();
+        // The slider rail is the left part of the widget, the value is on the right.
+        let rail = slider
+            .rect
+            .with_max_x(slider.rect.left() + ui.spacing().slider_width);
+        for annotation in &annotations {
+            let x = rail.left() + annotation.time / self.duration * rail.width();
+            let marker = Rect::from_x_y_ranges(x - 1.0..=x + 1.0, rail.y_range());
+            ui.painter()
+                .rect_filled(marker, 0., Color32::from_rgb(200, 40, 40));
+            if let Some(pointer) = slider.hover_pos()
+                && (pointer.x - x).abs() < 3.
+            {
+                slider.clone().on_hover_text(annotation.to_string());
+            }
+        }
+    }
+
+    /// Add user annotations at the current time, and jump to the existing annotations.
+    fn annotation_bar(&mut self, ui: &mut egui::Ui) {
+        let annotations = self.p.api.lock().unwrap().simulator_api.annotations.clone();
+        ui.horizontal(|ui| {
+            ui.label("Annotation: ");
+            ui.text_edit_singleline(&mut self.p.annotation_text);
+            if ui
+                .add_enabled(
+                    !self.p.annotation_text.is_empty(),
+                    egui::Button::new("Annotate"),
+                )
+                .clicked()
+            {
+                annotations.add(
+                    self.p.current_draw_time,
+                    None,
+                    std::mem::take(&mut self.p.annotation_text),
+                );
+            }
+            ui.menu_button(format!("Annotations ({})", annotations.len()), |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for annotation in annotations.list() {
+                        if ui.button(annotation.to_string()).clicked() {
+                            self.p.current_draw_time = annotation.time;
+                            self.follow_sim_time = false;
+                            ui.close();
+                        }
+                    }
+                });
+            });
+        });
+    }
+
     fn add_result(&mut self, time: f32, node: NodeRecord) {
         let time = round_precision(time, TIME_ROUND).unwrap();
         match &node {
             NodeRecord::ComputationUnit(rec) => {
-                self.p
-                    .virtual_nodes_panel
-                    .add_record(rec.name.clone(), time, node.clone());
+                let retention = self.p.config.as_ref().and_then(|config| {
+                    config
+                        .computation_units
+                        .iter()
+                        .find(|cu| cu.name == rec.name)
+                        .and_then(|cu| cu.history.retention_policy())
+                });
+                self.p.virtual_nodes_panel.add_record(
+                    rec.name.clone(),
+                    time,
+                    node.clone(),
+                    retention,
+                );
             }
             NodeRecord::Robot(n) => {
                 if let Some(r) = self.p.robots.get_mut(&n.name) {
@@ -447,6 +593,21 @@ impl SimbaApp {
                 }
             }
         }
+        let retention = self.p.config.as_ref().and_then(|config| match &node {
+            NodeRecord::Robot(n) => config
+                .robots
+                .iter()
+                .find(|rc| rc.name == n.model_name)
+                .and_then(|rc| rc.history.retention_policy()),
+            NodeRecord::ComputationUnit(n) => config
+                .computation_units
+                .iter()
+                .find(|cu| cu.name == n.model_name)
+                .and_then(|cu| cu.history.retention_policy()),
+        });
+        self.p
+            .node_inspector_panel
+            .add_record(time, node.clone(), retention);
         for drawable in self.p.drawables.iter_mut() {
             drawable.add_record(time, node.clone());
         }
@@ -481,10 +642,19 @@ impl eframe::App for SimbaApp {
         }
         {
             let record_buffer = self.p.record_buffer.clone();
-            for Record { time, node } in record_buffer.lock().unwrap().drain(..) {
-                self.add_result(time, node);
+            let records: Vec<Record> = record_buffer.lock().unwrap().drain(..).collect();
+            let result_loader = self.p.result_loader.clone();
+            let result_loader = result_loader.lock().unwrap();
+            for Record { time, node } in records {
+                if result_loader
+                    .as_ref()
+                    .is_none_or(|loader| loader.is_loaded(time))
+                {
+                    self.add_result(time, node);
+                }
             }
         }
+        self.update_result_loader();
 
         let mut to_close = Vec::new();
         for (i, pup) in self.p.popups.iter_mut().enumerate() {
@@ -514,7 +684,14 @@ impl eframe::App for SimbaApp {
                     ui.menu_button("View", |ui| {
                         ui.checkbox(&mut self.enabled_views.configuration, "Configuration");
                         ui.checkbox(&mut self.enabled_views.virtual_nodes, "Virtual Nodes");
+                        ui.checkbox(&mut self.enabled_views.node_inspector, "Node Inspector");
                         ui.checkbox(&mut self.enabled_views.broker, "Communication Broker");
+                        ui.checkbox(&mut self.enabled_views.fleet_status, "Fleet Status");
+                        ui.menu_button("Observations", |ui| {
+                            for (name, robot) in self.p.robots.iter_mut() {
+                                robot.show_observation_toggles(ui, name);
+                            }
+                        });
                     });
                     ui.add_space(16.0);
                     ui.menu_button("Help", |ui| {
@@ -589,6 +766,14 @@ impl eframe::App for SimbaApp {
                     //Closing
                     self.p.configurator = None;
                 }
+                if ui.button("Place robots").clicked() {
+                    self.p.placement = Some(FleetPlacement::init(&self.config_path));
+                }
+                if let Some(placement) = &mut self.p.placement
+                    && placement.show(ui, ctx)
+                {
+                    self.p.placement = None;
+                }
 
                 ui.add_space(50.);
 
@@ -599,6 +784,7 @@ impl eframe::App for SimbaApp {
                     log::info!("Load previous results");
                     let api = self.p.api.clone();
                     let records = self.p.record_buffer.clone();
+                    let result_loader = self.p.result_loader.clone();
                     let result_path = self.result_path.clone();
                     self.p.popups.push(Popup::new_yes_no(
                         "Viewer only?".to_string(),
@@ -606,8 +792,7 @@ impl eframe::App for SimbaApp {
                         Box::new(move |btn| {
                             if btn == 0 {
                                 log::info!("Load results in view");
-                                let results = Simulator::deserialize_results_from_file(Path::new(&result_path)).unwrap();
-                                records.lock().unwrap().extend(results.records);
+                                *result_loader.lock().unwrap() = Some(LazyResultLoader::start(PathBuf::from(&result_path), records.clone()));
                             } else {
                                 log::info!("Load results in simulator and view");
                                 api.lock().unwrap().load_results.async_call(Some(result_path.clone()));
@@ -616,6 +801,10 @@ impl eframe::App for SimbaApp {
                     ));
                     self.p.simulation_run = true;
                 }
+                if self.p.result_loader.lock().unwrap().as_ref().is_some_and(|loader| loader.is_indexing()) {
+                    ui.spinner();
+                    ui.label("Indexing results...");
+                }
                 if let Some(Err(e)) = self.p.api.lock().unwrap().load_results.try_get_result() {
                     let now = time::Instant::now();
                     self.p.error_buffer.push((now, e.clone()));
@@ -662,6 +851,33 @@ impl eframe::App for SimbaApp {
                     if let Some(Err(e)) = self.p.api.lock().unwrap().run.try_get_result() {
                         self.p.error_buffer.push((time::Instant::now(), e));
                     }
+                    let simulator_api = self.p.api.lock().unwrap().simulator_api.clone();
+                    let run_paused = simulator_api.is_paused();
+                    if ui
+                        .add_enabled(
+                            self.p.config.is_some(),
+                            egui::Button::new(if run_paused {
+                                "Resume run"
+                            } else {
+                                "Pause run"
+                            }),
+                        )
+                        .on_hover_text("Pause or resume the simulation at the end of the time step")
+                        .clicked()
+                    {
+                        if run_paused {
+                            simulator_api.resume();
+                        } else {
+                            simulator_api.pause();
+                        }
+                    }
+                    if ui
+                        .add_enabled(self.p.config.is_some(), egui::Button::new("Step"))
+                        .on_hover_text("Run one time step of the simulation, then pause")
+                        .clicked()
+                    {
+                        simulator_api.step(1);
+                    }
                     let play_pause_btn = if self.p.playing.is_none() {
                         egui::Button::new("Play ")
                     } else {
@@ -702,10 +918,11 @@ impl eframe::App for SimbaApp {
                         }
                         self.follow_sim_time = false;
                     }
-                    ui.add(
+                    let slider = ui.add(
                         egui::Slider::new(&mut self.p.current_draw_time, 0.0..=self.duration)
                             .fixed_decimals(TIME_ROUND_DECIMALS),
                     );
+                    self.draw_annotation_markers(ui, &slider);
                     if ui.button(">").clicked() {
                         if let Some(&next_instant) = self
                             .p
@@ -733,6 +950,7 @@ impl eframe::App for SimbaApp {
                         self.p.error_buffer.push((time::Instant::now(), e));
                     }
                 });
+                self.annotation_bar(ui);
 
                 ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                     egui::warn_if_debug_build(ui);
@@ -761,6 +979,14 @@ impl eframe::App for SimbaApp {
                             self.p.current_draw_time,
                         );
                     }
+                    if self.enabled_views.node_inspector {
+                        self.p.node_inspector_panel.draw(
+                            ui,
+                            ctx,
+                            "node_inspector_panel",
+                            self.p.current_draw_time,
+                        );
+                    }
                     if self.enabled_views.broker {
                         if let Some(panel) = &mut self.p.broker_panel {
                             panel.draw(ui, ctx, "broker_panel", self.p.current_draw_time);
@@ -768,6 +994,13 @@ impl eframe::App for SimbaApp {
                             ui.label("Broker information not available.");
                         }
                     }
+                    if self.enabled_views.fleet_status {
+                        if let Some(panel) = &self.p.fleet_status_panel {
+                            panel.draw(ui, ctx, "fleet_status_panel", self.p.current_draw_time);
+                        } else {
+                            ui.label("Fleet status not available.");
+                        }
+                    }
                 });
                 // Allow resizing the side panel by dragging
                 ui.take_available_width();
@@ -853,7 +1086,13 @@ impl eframe::App for SimbaApp {
                     }
 
                     let size = self.p.painter_info.size() * self.drawing_scale;
-                    let (response, painter) = ui.allocate_painter(size, Sense::click());
+                    // Dragging the map moves the robot markers while placing the robots
+                    let sense = if self.p.placement.is_some() {
+                        Sense::click_and_drag()
+                    } else {
+                        Sense::click()
+                    };
+                    let (response, painter) = ui.allocate_painter(size, sense);
                     painter.extend(shapes);
 
                     self.p.painter_info.set_shift(response.rect.left_top());
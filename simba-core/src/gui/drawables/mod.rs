@@ -19,6 +19,10 @@ pub trait Drawable {
     ///
     fn add_record(&mut self, time: f32, record: NodeRecord);
 
+    /// Function called when the records with a time in `[from, to[` are unloaded, when browsing a
+    /// large result file. The drawable can remove its records of this time range to free memory.
+    fn remove_records(&mut self, _from: f32, _to: f32) {}
+
     /// Draws the drawable for the given time.
     ///
     /// # Arguments
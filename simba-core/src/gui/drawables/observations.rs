@@ -14,15 +14,48 @@ use crate::{
     simulator::SimulatorConfig,
 };
 
+/// Colors given to the sensors of a node, in the order of the sensor configuration.
+pub const SENSOR_COLORS: [Color32; 6] = [
+    Color32::from_rgb(255, 165, 0), // Orange
+    Color32::from_rgb(0, 160, 0),   // Green
+    Color32::from_rgb(200, 0, 200), // Magenta
+    Color32::from_rgb(0, 170, 200), // Cyan
+    Color32::from_rgb(200, 40, 40), // Red
+    Color32::from_rgb(120, 80, 20), // Brown
+];
+
+/// Draw the detection range of a sensor, as a circle around the robot.
+pub fn draw_range(
+    painter_info: &PainterInfo,
+    scale: f32,
+    robot_pose: &Vector3<f32>,
+    detection_distance: f32,
+    color: Color32,
+) -> Shape {
+    let center = painter_info.zero(scale) + Vec2::new(robot_pose.x, robot_pose.y) * scale;
+    Shape::circle_stroke(
+        center,
+        detection_distance * scale,
+        Stroke {
+            color: color.gamma_multiply(0.5),
+            width: 0.01 * scale,
+        },
+    )
+}
+
 pub struct OrientedRobotObservation {
     color: Color32,
     arrow_len: f32,
 }
 
 impl OrientedRobotObservation {
-    pub fn init(_config: &RobotSensorConfig, _sim_config: &SimulatorConfig) -> Self {
+    pub fn init(
+        _config: &RobotSensorConfig,
+        _sim_config: &SimulatorConfig,
+        color: Color32,
+    ) -> Self {
         Self {
-            color: Color32::from_rgb(255, 165, 0), // Orange
+            color,
             arrow_len: 0.2,
         }
     }
@@ -83,9 +116,13 @@ pub struct OrientedLandmarkObservation {
 }
 
 impl OrientedLandmarkObservation {
-    pub fn init(_config: &OrientedLandmarkSensorConfig, _sim_config: &SimulatorConfig) -> Self {
+    pub fn init(
+        _config: &OrientedLandmarkSensorConfig,
+        _sim_config: &SimulatorConfig,
+        color: Color32,
+    ) -> Self {
         Self {
-            color: Color32::from_rgb(255, 165, 0), // Orange
+            color,
             arrow_len: 0.2,
         }
     }
@@ -164,10 +201,8 @@ pub struct GNSSObservation {
 }
 
 impl GNSSObservation {
-    pub fn init(_config: &GNSSSensorConfig, _sim_config: &SimulatorConfig) -> Self {
-        Self {
-            color: Color32::from_rgb(255, 165, 0), // Orange
-        }
+    pub fn init(_config: &GNSSSensorConfig, _sim_config: &SimulatorConfig, color: Color32) -> Self {
+        Self { color }
     }
 
     pub fn draw(
@@ -196,6 +231,26 @@ impl GNSSObservation {
         let obs_position = center + obs_position * scale;
         let arrow_tip = center + arrow_tip * scale;
 
+        // Fix, as a cross
+        let half_size = 0.08 * scale;
+        let stroke = Stroke {
+            color: self.color,
+            width: 0.02 * scale,
+        };
+        shapes.push(Shape::line_segment(
+            [
+                obs_position - Vec2::splat(half_size),
+                obs_position + Vec2::splat(half_size),
+            ],
+            stroke,
+        ));
+        shapes.push(Shape::line_segment(
+            [
+                obs_position + Vec2::new(-half_size, half_size),
+                obs_position + Vec2::new(half_size, -half_size),
+            ],
+            stroke,
+        ));
         shapes.push(Shape::line_segment(
             [obs_position, arrow_tip],
             Stroke {
@@ -212,10 +267,8 @@ pub struct ScanObservation {
 }
 
 impl ScanObservation {
-    pub fn init(_config: &ScanSensorConfig, _sim_config: &SimulatorConfig) -> Self {
-        Self {
-            color: Color32::from_rgb(255, 165, 0), // Orange
-        }
+    pub fn init(_config: &ScanSensorConfig, _sim_config: &SimulatorConfig, color: Color32) -> Self {
+        Self { color }
     }
 
     pub fn draw(
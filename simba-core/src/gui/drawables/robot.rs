@@ -1,73 +1,142 @@
 use egui::{Color32, Rect, Response, Shape, Stroke, Vec2};
-use nalgebra::Vector3;
+use nalgebra::{Matrix2, Vector2, Vector3};
 use simba_com::time_ordered_data::TimeOrderedData;
 
 use crate::{
     constants::TIME_ROUND,
     gui::{UIComponent, app::PainterInfo, drawables},
-    node::node_factory::{RobotConfig, RobotRecord},
+    node::{
+        footprint::FootprintConfig,
+        node_factory::{RobotConfig, RobotRecord},
+    },
     sensors::{SensorConfig, SensorObservationRecord},
     simulator::SimulatorConfig,
+    state_estimators::StateRecord,
 };
 
-use super::observations::{OrientedLandmarkObservation, OrientedRobotObservation};
+use super::observations::{
+    GNSSObservation, OrientedLandmarkObservation, OrientedRobotObservation, SENSOR_COLORS,
+    ScanObservation,
+};
+
+/// Color of the estimated pose and of its uncertainty ellipse.
+const ESTIMATE_COLOR: Color32 = Color32::ORANGE;
+/// Chi-square value of 2 degrees of freedom at 95%, scale of the uncertainty ellipse.
+const ELLIPSE_CHI2_95: f32 = 5.991;
+
+enum ObservationDrawer {
+    OrientedLandmark(OrientedLandmarkObservation),
+    OrientedRobot(OrientedRobotObservation),
+    GNSS(GNSSObservation),
+    Scan(ScanObservation),
+}
+
+/// Drawing of the observations of one sensor, identified by its name.
+struct SensorDrawer {
+    name: String,
+    color: Color32,
+    detection_distance: Option<f32>,
+    drawer: ObservationDrawer,
+}
 
 pub struct Robot {
     color: Color32,
     records: TimeOrderedData<RobotRecord>,
     arrow_len: f32,
-    landmark_obs: Option<OrientedLandmarkObservation>,
-    robot_obs: Option<OrientedRobotObservation>,
-    gnss_obs: Option<drawables::observations::GNSSObservation>,
-    scan_obs: Option<drawables::observations::ScanObservation>,
+    footprint: FootprintConfig,
+    sensors: Vec<SensorDrawer>,
+    show_observations: bool,
+    show_ranges: bool,
+    show_estimate: bool,
     context_info_enabled: bool,
 }
 
 impl Robot {
     pub fn init(config: &RobotConfig, sim_config: &SimulatorConfig) -> Self {
-        let mut landmark_obs = None;
-        let mut robot_obs = None;
-        let mut gnss_obs = None;
-        let mut scan_obs = None;
-        for sensor_conf in &config.sensor_manager.sensors {
-            match &sensor_conf.config {
-                SensorConfig::GNSS(c) => {
-                    gnss_obs = Some(drawables::observations::GNSSObservation::init(
-                        c, sim_config,
-                    ))
-                }
-                SensorConfig::Speed(_) => {}
-                SensorConfig::Displacement(_) => {}
-                SensorConfig::OrientedLandmark(c) => {
-                    landmark_obs = Some(OrientedLandmarkObservation::init(c, sim_config))
-                }
-                SensorConfig::Robot(c) => {
-                    robot_obs = Some(OrientedRobotObservation::init(c, sim_config))
-                }
-                SensorConfig::Scan(c) => {
-                    scan_obs = Some(drawables::observations::ScanObservation::init(
-                        c, sim_config,
-                    ))
-                }
-                SensorConfig::External(_) => {}
-            }
+        let mut sensors = Vec::new();
+        for (i, sensor_conf) in config.sensor_manager.sensors.iter().enumerate() {
+            let color = SENSOR_COLORS[i % SENSOR_COLORS.len()];
+            let (detection_distance, drawer) = match &sensor_conf.config {
+                SensorConfig::GNSS(c) => (
+                    None,
+                    ObservationDrawer::GNSS(GNSSObservation::init(c, sim_config, color)),
+                ),
+                SensorConfig::OrientedLandmark(c) => (
+                    Some(c.detection_distance),
+                    ObservationDrawer::OrientedLandmark(OrientedLandmarkObservation::init(
+                        c, sim_config, color,
+                    )),
+                ),
+                SensorConfig::Robot(c) => (
+                    Some(c.detection_distance),
+                    ObservationDrawer::OrientedRobot(OrientedRobotObservation::init(
+                        c, sim_config, color,
+                    )),
+                ),
+                SensorConfig::Scan(c) => (
+                    Some(c.detection_distance),
+                    ObservationDrawer::Scan(ScanObservation::init(c, sim_config, color)),
+                ),
+                SensorConfig::Lidar(c) => (
+                    Some(c.max_range),
+                    ObservationDrawer::Scan(ScanObservation::init(
+                        &c.scan_config(),
+                        sim_config,
+                        color,
+                    )),
+                ),
+                SensorConfig::Speed(_)
+                | SensorConfig::Displacement(_)
+                | SensorConfig::External(_)
+                | SensorConfig::Imu(_)
+                | SensorConfig::Bearing(_) => continue,
+            };
+            sensors.push(SensorDrawer {
+                name: sensor_conf.name.clone(),
+                color,
+                detection_distance,
+                drawer,
+            });
         }
 
+        let mut records = TimeOrderedData::new(TIME_ROUND);
+        records.set_retention(config.history.retention_policy());
+
         Self {
             color: Color32::BLUE,
-            records: TimeOrderedData::new(TIME_ROUND),
+            records,
             arrow_len: 0.2,
-            landmark_obs,
-            robot_obs,
-            gnss_obs,
-            scan_obs,
+            footprint: config.footprint.clone(),
+            sensors,
+            show_observations: true,
+            show_ranges: false,
+            show_estimate: true,
             context_info_enabled: false,
         }
     }
 
+    /// Checkboxes to toggle the observations, the sensor ranges and the estimated pose of this
+    /// robot, with the color legend of the sensors.
+    pub fn show_observation_toggles(&mut self, ui: &mut egui::Ui, name: &str) {
+        observation_toggles(
+            ui,
+            name,
+            &mut self.show_observations,
+            &mut self.show_ranges,
+            &mut self.show_estimate,
+            &self.sensors,
+        );
+    }
+
     pub fn add_record(&mut self, time: f32, record: RobotRecord) {
         self.records.insert(time, record, true);
     }
+
+    /// Remove the records with a time in `[from, to[`.
+    pub fn remove_records(&mut self, from: f32, to: f32) {
+        self.records.remove_between_times(from, to);
+    }
+
     pub fn draw(
         &self,
         ui: &mut egui::Ui,
@@ -100,7 +169,33 @@ impl Robot {
                     y: self.arrow_len * pose[2].sin() * scale,
                 };
 
-            shapes.push(Shape::circle_filled(position, 0.1 * scale, self.color));
+            match &self.footprint {
+                FootprintConfig::Point => {
+                    shapes.push(Shape::circle_filled(position, 0.1 * scale, self.color));
+                }
+                FootprintConfig::Circle(c) => {
+                    shapes.push(Shape::circle_filled(
+                        position,
+                        c.radius * scale,
+                        self.color.gamma_multiply(0.5),
+                    ));
+                }
+                FootprintConfig::Polygon(_) => {
+                    let outline = self
+                        .footprint
+                        .outline(&pose)
+                        .iter()
+                        .map(|p| center + Vec2::new(p.x, p.y) * scale)
+                        .collect();
+                    shapes.push(Shape::closed_line(
+                        outline,
+                        Stroke {
+                            color: self.color,
+                            width: 0.03 * scale,
+                        },
+                    ));
+                }
+            }
             shapes.push(Shape::line_segment(
                 [position, arrow_tip],
                 Stroke {
@@ -109,46 +204,49 @@ impl Robot {
                 },
             ));
 
-            for obs in &record.sensors.last_observations {
-                match &obs.sensor_observation {
-                    SensorObservationRecord::OrientedLandmark(o) => {
-                        shapes.extend(self.landmark_obs.as_ref().unwrap().draw(
-                            ui,
-                            viewport,
-                            painter_info,
-                            scale,
-                            o,
-                            &Vector3::from(pose),
-                        )?)
-                    }
-                    SensorObservationRecord::OrientedRobot(o) => {
-                        shapes.extend(self.robot_obs.as_ref().unwrap().draw(
-                            ui,
-                            viewport,
+            if self.show_estimate
+                && let Some(estimate) = record.state_estimator.ego()
+            {
+                shapes.extend(draw_estimate(painter_info, scale, estimate, self.arrow_len));
+            }
+
+            let pose = Vector3::from(pose);
+            if self.show_ranges {
+                for sensor in &self.sensors {
+                    if let Some(distance) = sensor.detection_distance
+                        && distance > 0.
+                    {
+                        shapes.push(drawables::observations::draw_range(
                             painter_info,
                             scale,
-                            o,
-                            &Vector3::from(pose),
-                        )?)
+                            &pose,
+                            distance,
+                            sensor.color,
+                        ));
                     }
-                    SensorObservationRecord::GNSS(o) => {
-                        shapes.extend(self.gnss_obs.as_ref().unwrap().draw(
-                            ui,
-                            viewport,
-                            painter_info,
-                            scale,
-                            o,
-                        )?)
+                }
+            }
+            if !self.show_observations {
+                return Ok(shapes);
+            }
+            for obs in &record.sensors.last_observations {
+                let Some(sensor) = self.sensors.iter().find(|s| s.name == obs.sensor_name) else {
+                    continue;
+                };
+                match (&obs.sensor_observation, &sensor.drawer) {
+                    (
+                        SensorObservationRecord::OrientedLandmark(o),
+                        ObservationDrawer::OrientedLandmark(drawer),
+                    ) => shapes.extend(drawer.draw(ui, viewport, painter_info, scale, o, &pose)?),
+                    (
+                        SensorObservationRecord::OrientedRobot(o),
+                        ObservationDrawer::OrientedRobot(drawer),
+                    ) => shapes.extend(drawer.draw(ui, viewport, painter_info, scale, o, &pose)?),
+                    (SensorObservationRecord::GNSS(o), ObservationDrawer::GNSS(drawer)) => {
+                        shapes.extend(drawer.draw(ui, viewport, painter_info, scale, o)?)
                     }
-                    SensorObservationRecord::Scan(o) => {
-                        shapes.extend(self.scan_obs.as_ref().unwrap().draw(
-                            ui,
-                            viewport,
-                            painter_info,
-                            scale,
-                            o,
-                            &Vector3::from(pose),
-                        )?)
+                    (SensorObservationRecord::Scan(o), ObservationDrawer::Scan(drawer)) => {
+                        shapes.extend(drawer.draw(ui, viewport, painter_info, scale, o, &pose)?)
                     }
                     _ => {}
                 }
@@ -180,6 +278,14 @@ impl Robot {
                     }
                     let unique_id = format!("record-robot-{}", record.name);
                     ui.label(format!("Time: {:.3} s", t));
+                    observation_toggles(
+                        ui,
+                        &record.name,
+                        &mut self.show_observations,
+                        &mut self.show_ranges,
+                        &mut self.show_estimate,
+                        &self.sensors,
+                    );
 
                     egui::ScrollArea::both().show(ui, |ui| {
                         record.show(ui, ctx, &unique_id);
@@ -189,3 +295,98 @@ impl Robot {
         }
     }
 }
+
+fn observation_toggles(
+    ui: &mut egui::Ui,
+    name: &str,
+    show_observations: &mut bool,
+    show_ranges: &mut bool,
+    show_estimate: &mut bool,
+    sensors: &[SensorDrawer],
+) {
+    ui.horizontal(|ui| {
+        ui.label(name);
+        ui.checkbox(show_observations, "Observations");
+        ui.checkbox(show_ranges, "Ranges");
+        ui.checkbox(show_estimate, "Estimate");
+    });
+    if *show_observations || *show_ranges {
+        ui.horizontal_wrapped(|ui| {
+            for sensor in sensors {
+                ui.colored_label(sensor.color, &sensor.name);
+            }
+        });
+    }
+}
+
+/// Draw the estimated pose, with the 95% uncertainty ellipse of the position when the estimator
+/// provides a covariance.
+fn draw_estimate(
+    painter_info: &PainterInfo,
+    scale: f32,
+    estimate: &StateRecord,
+    arrow_len: f32,
+) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+    let stroke = Stroke {
+        color: ESTIMATE_COLOR,
+        width: 0.02 * scale,
+    };
+    let center = painter_info.zero(scale);
+    let position = center + Vec2::new(estimate.pose[0], estimate.pose[1]) * scale;
+    let arrow_tip =
+        position + Vec2::new(estimate.pose[2].cos(), estimate.pose[2].sin()) * arrow_len * scale;
+    shapes.push(Shape::circle_stroke(position, 0.05 * scale, stroke));
+    shapes.push(Shape::line_segment([position, arrow_tip], stroke));
+
+    if let Some(covariance) = &estimate.pose_covariance {
+        let outline = uncertainty_ellipse(covariance, 32)
+            .into_iter()
+            .map(|point| position + Vec2::new(point.x, point.y) * scale)
+            .collect();
+        shapes.push(Shape::closed_line(outline, stroke));
+    }
+    shapes
+}
+
+/// `nb_points` points of the 95% uncertainty ellipse of the position of the pose `covariance`,
+/// relative to the position.
+fn uncertainty_ellipse(covariance: &[[f32; 3]; 3], nb_points: usize) -> Vec<Vector2<f32>> {
+    let eigen = Matrix2::from_fn(|i, j| covariance[i][j]).symmetric_eigen();
+    // Axes of the ellipse, along the eigenvectors of the position covariance
+    let axes = eigen.eigenvectors
+        * Matrix2::from_diagonal(
+            &eigen
+                .eigenvalues
+                .map(|value| (value.max(0.) * ELLIPSE_CHI2_95).sqrt()),
+        );
+    (0..nb_points)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::TAU / nb_points as f32;
+            axes * Vector2::new(angle.cos(), angle.sin())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ellipse_along_the_covariance_axes() {
+        // Position variances of 4 along x and 1 along y, the orientation is ignored
+        let covariance = [[4., 0., 0.5], [0., 1., 0.], [0.5, 0., 10.]];
+        let points = uncertainty_ellipse(&covariance, 64);
+        assert_eq!(points.len(), 64);
+        let max_x = points.iter().map(|p| p.x.abs()).fold(0., f32::max);
+        let max_y = points.iter().map(|p| p.y.abs()).fold(0., f32::max);
+        assert!((max_x - (4. * ELLIPSE_CHI2_95).sqrt()).abs() < 1e-3);
+        assert!((max_y - ELLIPSE_CHI2_95.sqrt()).abs() < 1e-3);
+        // A degenerate covariance gives a segment, not NaN
+        let points = uncertainty_ellipse(&[[1., 1., 0.], [1., 1., 0.], [0., 0., 0.]], 8);
+        assert!(points.iter().all(|p| p.x.is_finite() && p.y.is_finite()));
+        for p in &points {
+            assert!((p.x - p.y).abs() < 1e-3);
+        }
+    }
+}
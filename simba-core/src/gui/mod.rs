@@ -13,6 +13,8 @@ mod configurator;
 mod drawables;
 pub use drawables::Drawable;
 mod panels;
+mod placement;
+mod result_loader;
 pub mod utils;
 
 use crate::{
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use egui::Color32;
+
+use crate::{node::NodeState, simulator::FleetStatus};
+
+/// Heartbeats older than this duration are shown as stalled.
+const STALL_DURATION: Duration = Duration::from_secs(2);
+
+pub struct FleetStatusPanel {
+    fleet_status: FleetStatus,
+}
+
+impl FleetStatusPanel {
+    pub fn new(fleet_status: FleetStatus) -> Self {
+        Self { fleet_status }
+    }
+
+    pub fn draw(&self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str, _time: f32) {
+        egui::CollapsingHeader::new("Fleet Status").show(ui, |ui| {
+            let stalled = self.fleet_status.stalled(STALL_DURATION);
+            ui.label(format!(
+                "Running: {}, Zombie: {}, Terminated: {}, Failed: {}, Stalled: {}",
+                self.fleet_status.count(&NodeState::Running),
+                self.fleet_status.count(&NodeState::Zombie),
+                self.fleet_status.count(&NodeState::Terminated),
+                self.fleet_status.count(&NodeState::Failed),
+                stalled.len(),
+            ));
+            egui::Grid::new(format!("fleet-status-{unique_id}"))
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Node");
+                    ui.label("State");
+                    ui.label("Time");
+                    ui.label("Steps");
+                    ui.label("Queued");
+                    ui.label("Step (ms)");
+                    ui.label("Age (s)");
+                    ui.end_row();
+                    for heartbeat in self.fleet_status.list() {
+                        let color = if stalled.contains(&heartbeat.node) {
+                            Color32::ORANGE
+                        } else {
+                            match heartbeat.state {
+                                NodeState::Failed => Color32::RED,
+                                NodeState::Zombie => Color32::YELLOW,
+                                _ => ui.visuals().text_color(),
+                            }
+                        };
+                        ui.colored_label(color, &heartbeat.node);
+                        ui.colored_label(color, heartbeat.state.to_string());
+                        ui.label(
+                            heartbeat
+                                .time
+                                .map_or("-".to_string(), |time| format!("{time:.3}")),
+                        );
+                        ui.label(heartbeat.steps.to_string());
+                        ui.label(heartbeat.queued_messages.to_string());
+                        ui.label(format!(
+                            "{:.2}",
+                            heartbeat.step_duration.as_secs_f32() * 1000.
+                        ));
+                        ui.label(format!("{:.1}", heartbeat.age().as_secs_f32()));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
@@ -1,2 +1,4 @@
 pub mod broker;
+pub mod fleet_status;
+pub mod node_inspector;
 pub mod virtual_nodes;
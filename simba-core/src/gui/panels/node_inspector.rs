@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use egui::Color32;
+use serde_json::Value;
+use simba_com::time_ordered_data::{RetentionPolicy, TimeOrderedData};
+
+use crate::{
+    constants::TIME_ROUND,
+    node::node_factory::NodeRecord,
+    simulator::{Snapshot, diff_snapshots},
+};
+
+/// Inspector of the records of one node: shows the record tree at the current time, and
+/// compares it with a pinned time.
+pub struct NodeInspectorPanel {
+    records: BTreeMap<String, TimeOrderedData<NodeRecord>>,
+    selected: String,
+    pinned_time: Option<f32>,
+}
+
+impl NodeInspectorPanel {
+    pub fn new() -> Self {
+        Self {
+            records: BTreeMap::new(),
+            selected: String::new(),
+            pinned_time: None,
+        }
+    }
+
+    /// Add a record of the node. `retention` is used for the first record of the node only.
+    pub fn add_record(
+        &mut self,
+        time: f32,
+        record: NodeRecord,
+        retention: Option<RetentionPolicy>,
+    ) {
+        let node_name = record.name().clone();
+        if let Some(records) = self.records.get_mut(&node_name) {
+            records.insert(time, record, true);
+        } else {
+            let mut records = TimeOrderedData::new(TIME_ROUND);
+            records.set_retention(retention);
+            records.insert(time, record, true);
+            self.records.insert(node_name, records);
+        }
+    }
+
+    /// Remove the records with a time in `[from, to[`.
+    pub fn remove_records(&mut self, from: f32, to: f32) {
+        for records in self.records.values_mut() {
+            records.remove_between_times(from, to);
+        }
+    }
+
+    /// Record of the selected node at `time`, serialized, with the external records decoded.
+    fn record_value(&self, time: f32) -> Option<(f32, Value)> {
+        let (t, record) = self.records.get(&self.selected)?.get_data_beq_time(time)?;
+        let mut value = serde_json::to_value(record).ok()?;
+        decode_json_strings(&mut value);
+        Some((t, value))
+    }
+
+    pub fn draw(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str, time: f32) {
+        egui::CollapsingHeader::new("Node Inspector").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Node: ");
+                egui::ComboBox::from_id_salt(format!("node-inspector-select-{unique_id}"))
+                    .selected_text(&self.selected)
+                    .show_ui(ui, |ui| {
+                        for node_name in self.records.keys() {
+                            if ui
+                                .selectable_value(&mut self.selected, node_name.clone(), node_name)
+                                .changed()
+                            {
+                                self.pinned_time = None;
+                            }
+                        }
+                    });
+            });
+
+            let Some((record_time, current)) = self.record_value(time) else {
+                ui.label("No record for this node at the current time.");
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                ui.label(format!("Record time: {record_time:.3} s"));
+                if ui.button("Pin").clicked() {
+                    self.pinned_time = Some(record_time);
+                }
+                if let Some(pinned_time) = self.pinned_time {
+                    ui.label(format!("Pinned: {pinned_time:.3} s"));
+                    if ui.button("Unpin").clicked() {
+                        self.pinned_time = None;
+                    }
+                }
+            });
+
+            if let Some(pinned_time) = self.pinned_time
+                && let Some((pinned_time, pinned)) = self.record_value(pinned_time)
+            {
+                egui::CollapsingHeader::new(format!("Changes since {pinned_time:.3} s"))
+                    .id_salt(format!("node-inspector-diff-{unique_id}"))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        let snapshot = |time: f32, value: Value| Snapshot {
+                            time,
+                            nodes: BTreeMap::from([(self.selected.clone(), value)]),
+                        };
+                        let diff = diff_snapshots(
+                            &snapshot(pinned_time, pinned),
+                            &snapshot(record_time, current.clone()),
+                        );
+                        if diff.is_empty() {
+                            ui.label("No change.");
+                        }
+                        for difference in &diff.differences {
+                            let to_string = |value: &Option<Value>| {
+                                value
+                                    .as_ref()
+                                    .map_or("missing".to_string(), |v| v.to_string())
+                            };
+                            ui.colored_label(
+                                Color32::from_rgb(255, 165, 0),
+                                format!(
+                                    "{}.{}: {} -> {}",
+                                    difference.module.as_deref().unwrap_or("record"),
+                                    difference.path,
+                                    to_string(&difference.reference),
+                                    to_string(&difference.other)
+                                ),
+                            );
+                        }
+                    });
+            }
+
+            egui::ScrollArea::both()
+                .id_salt(format!("node-inspector-tree-{unique_id}"))
+                .show(ui, |ui| {
+                    show_value(ui, "record", &current, unique_id);
+                });
+        });
+    }
+}
+
+/// Replace the strings containing a JSON object or array by their decoded value, recursively.
+///
+/// Records of external and Python modules are often given as JSON strings.
+fn decode_json_strings(value: &mut Value) {
+    match value {
+        Value::String(s) if s.starts_with('{') || s.starts_with('[') => {
+            if let Ok(mut decoded) = serde_json::from_str::<Value>(s) {
+                decode_json_strings(&mut decoded);
+                *value = decoded;
+            }
+        }
+        Value::Object(map) => map.values_mut().for_each(decode_json_strings),
+        Value::Array(values) => values.iter_mut().for_each(decode_json_strings),
+        _ => {}
+    }
+}
+
+/// Show a JSON value as a collapsible tree.
+fn show_value(ui: &mut egui::Ui, key: &str, value: &Value, id: &str) {
+    let id = format!("{id}-{key}");
+    match value {
+        Value::Object(map) => {
+            egui::CollapsingHeader::new(key)
+                .id_salt(&id)
+                .show(ui, |ui| {
+                    for (k, v) in map {
+                        show_value(ui, k, v, &id);
+                    }
+                });
+        }
+        Value::Array(values) if !values.iter().all(|v| v.is_number()) => {
+            egui::CollapsingHeader::new(format!("{key} [{}]", values.len()))
+                .id_salt(&id)
+                .show(ui, |ui| {
+                    for (i, v) in values.iter().enumerate() {
+                        show_value(ui, &i.to_string(), v, &id);
+                    }
+                });
+        }
+        _ => {
+            ui.label(format!("{key}: {value}"));
+        }
+    }
+}
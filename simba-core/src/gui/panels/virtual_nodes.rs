@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use simba_com::time_ordered_data::TimeOrderedData;
+use simba_com::time_ordered_data::{RetentionPolicy, TimeOrderedData};
 
 use crate::{gui::UIComponent, node::node_factory::NodeRecord};
 
@@ -15,16 +15,31 @@ impl VirtualNodesPanel {
         }
     }
 
-    pub fn add_record(&mut self, node_name: String, time: f32, record: NodeRecord) {
+    /// Add a record of the node. `retention` is used for the first record of the node only.
+    pub fn add_record(
+        &mut self,
+        node_name: String,
+        time: f32,
+        record: NodeRecord,
+        retention: Option<RetentionPolicy>,
+    ) {
         if let Some(records) = self.records.get_mut(&node_name) {
             records.insert(time, record, true);
         } else {
             let mut records = TimeOrderedData::new(0.01);
+            records.set_retention(retention);
             records.insert(time, record, true);
             self.records.insert(node_name, records);
         }
     }
 
+    /// Remove the records with a time in `[from, to[`.
+    pub fn remove_records(&mut self, from: f32, to: f32) {
+        for records in self.records.values_mut() {
+            records.remove_between_times(from, to);
+        }
+    }
+
     pub fn draw(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str, time: f32) {
         egui::CollapsingHeader::new("Virtual Nodes").show(ui, |ui| {
             for (node_name, records) in &self.records {
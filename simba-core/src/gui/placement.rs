@@ -0,0 +1,262 @@
+//! Interactive placement of the robots on the map.
+//!
+//! The initial pose of each robot with an internal physics is shown as a marker on the map. The
+//! markers can be dragged, or spread apart so that no robots start on top of each other. The
+//! robots whose footprints overlap are shown in red. The poses are written in the `initial_state` of the robots when the configuration is saved.
+
+use std::path::Path;
+
+use egui::{Color32, Response, Shape, Stroke, Vec2};
+use nalgebra::Vector2;
+
+use crate::{
+    node::footprint::FootprintConfig, physics::PhysicsConfig, simulator::SimulatorConfig,
+    state_estimators::StateConfig, utils::geometry::spread_points,
+};
+
+use super::{app::PainterInfo, utils::path_finder};
+
+/// Maximum number of iterations of the spreading of the robots.
+const SPREAD_ITERATIONS: usize = 100;
+
+pub struct FleetPlacement {
+    current_config: SimulatorConfig,
+    save_path: String,
+    /// Robot whose marker is being dragged.
+    dragged: Option<String>,
+    /// Minimal distance between the robots when spreading them.
+    min_distance: f32,
+}
+
+impl FleetPlacement {
+    pub fn init(config_path: &String) -> Self {
+        let mut save_path = config_path.clone();
+        let current_config = match SimulatorConfig::load_from_path(Path::new(&config_path)) {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!(
+                    "Impossible to load config at path {}: {}",
+                    config_path,
+                    e.detailed_error()
+                );
+                save_path = String::new();
+                SimulatorConfig::default()
+            }
+        };
+        FleetPlacement {
+            current_config,
+            save_path,
+            dragged: None,
+            min_distance: 1.,
+        }
+    }
+
+    /// Initial states of the robots which can be placed (internal physics).
+    fn initial_states(&mut self) -> impl Iterator<Item = (&String, &mut StateConfig)> {
+        self.current_config
+            .robots
+            .iter_mut()
+            .filter_map(|robot| match &mut robot.physics {
+                PhysicsConfig::Internal(physics) => {
+                    physics.initial_state.pose.resize(3, 0.);
+                    Some((&robot.name, &mut physics.initial_state))
+                }
+                _ => None,
+            })
+    }
+
+    /// Positions of the markers, with the footprints of the robots, in the order of the robots.
+    fn poses(&self) -> impl Iterator<Item = (&String, &FootprintConfig, [f32; 3])> {
+        self.current_config
+            .robots
+            .iter()
+            .filter_map(|robot| match &robot.physics {
+                PhysicsConfig::Internal(physics) => {
+                    let pose = &physics.initial_state.pose;
+                    let value = |i: usize| pose.get(i).copied().unwrap_or(0.);
+                    Some((
+                        &robot.name,
+                        &robot.footprint,
+                        [value(0), value(1), value(2)],
+                    ))
+                }
+                _ => None,
+            })
+    }
+
+    /// Names of the robots whose footprint overlaps the footprint of another robot.
+    fn overlapping(&self) -> Vec<&String> {
+        let poses = self.poses().collect::<Vec<_>>();
+        poses
+            .iter()
+            .enumerate()
+            .filter(|(i, (_, footprint, pose))| {
+                poses.iter().enumerate().any(|(j, (_, other, other_pose))| {
+                    *i != j && footprint.collides(pose, other, other_pose)
+                })
+            })
+            .map(|(_, (name, _, _))| *name)
+            .collect()
+    }
+
+    /// Move the robots apart, so that they are at least `min_distance` from each other, and that
+    /// their footprints do not overlap.
+    fn spread(&mut self) {
+        let mut positions = self
+            .poses()
+            .map(|(_, _, pose)| Vector2::new(pose[0], pose[1]))
+            .collect::<Vec<_>>();
+        let largest_radius = self
+            .current_config
+            .robots
+            .iter()
+            .map(|robot| robot.footprint.bounding_radius())
+            .fold(0., f32::max);
+        spread_points(
+            &mut positions,
+            self.min_distance.max(2. * largest_radius),
+            SPREAD_ITERATIONS,
+        );
+        for ((_, state), position) in self.initial_states().zip(positions) {
+            state.pose[0] = position.x;
+            state.pose[1] = position.y;
+        }
+    }
+
+    pub fn show(&mut self, _ui: &mut egui::Ui, ctx: &egui::Context) -> bool {
+        let mut closing = false;
+        egui::Window::new("Robot placement").show(ctx, |ui| {
+            if ui.button("Close").clicked() {
+                closing = true;
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Save to: ");
+                path_finder(ui, &mut self.save_path, Path::new("."));
+                if ui.button("Save").clicked() {
+                    match confy::store_path(&self.save_path, &self.current_config) {
+                        Ok(()) => log::info!(
+                            "Robot placement saved to {}, load the configuration to apply it",
+                            self.save_path
+                        ),
+                        Err(e) => log::error!("Impossible to save the configuration: {e}"),
+                    }
+                }
+            });
+            ui.label("Drag the markers on the map to move the robots.");
+            ui.horizontal(|ui| {
+                ui.label("Minimal distance: ");
+                ui.add(
+                    egui::DragValue::new(&mut self.min_distance)
+                        .speed(0.1)
+                        .range(0.0..=f32::MAX),
+                );
+                if ui.button("Spread").clicked() {
+                    self.spread();
+                }
+            });
+
+            let overlapping = self.overlapping().into_iter().cloned().collect::<Vec<_>>();
+            if !overlapping.is_empty() {
+                ui.colored_label(
+                    Color32::RED,
+                    format!("Overlapping footprints: {}", overlapping.join(", ")),
+                );
+            }
+
+            let dragged = self.dragged.clone();
+            egui::Grid::new("robot-placement-grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Robot");
+                    ui.label("x");
+                    ui.label("y");
+                    ui.label("θ");
+                    ui.end_row();
+                    for (name, state) in self.initial_states() {
+                        if dragged.as_ref() == Some(name) {
+                            ui.colored_label(Color32::ORANGE, name);
+                        } else if overlapping.contains(name) {
+                            ui.colored_label(Color32::RED, name);
+                        } else {
+                            ui.label(name);
+                        }
+                        for value in state.pose.iter_mut() {
+                            ui.add(egui::DragValue::new(value).speed(0.01).max_decimals(3));
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        closing
+    }
+
+    pub fn draw(&self, painter_info: &PainterInfo, scale: f32) -> Result<Vec<Shape>, Vec2> {
+        let mut shapes = Vec::new();
+        let center = painter_info.zero(scale);
+        let overlapping = self.overlapping();
+        for (name, _, pose) in self.poses() {
+            let position = Vec2::new(pose[0], pose[1]);
+            if !painter_info.is_inside(&position) {
+                return Err(position);
+            }
+            let position = center + position * scale;
+            let stroke = Stroke {
+                color: if overlapping.contains(&name) {
+                    Color32::RED
+                } else {
+                    Color32::ORANGE
+                },
+                width: 0.03 * scale,
+            };
+            if self.dragged.as_ref() == Some(name) {
+                shapes.push(Shape::circle_filled(
+                    position,
+                    0.15 * scale,
+                    Color32::ORANGE,
+                ));
+            } else {
+                shapes.push(Shape::circle_stroke(position, 0.15 * scale, stroke));
+            }
+            shapes.push(Shape::line_segment(
+                [
+                    position,
+                    position + Vec2::new(pose[2].cos(), pose[2].sin()) * 0.3 * scale,
+                ],
+                stroke,
+            ));
+        }
+        Ok(shapes)
+    }
+
+    /// Select the robot under the pointer (its marker or its footprint) when a drag starts, and
+    /// move it with the pointer.
+    pub fn react(&mut self, response: &Response, painter_info: &PainterInfo, scale: f32) {
+        let pointer = response.interact_pointer_pos();
+        if response.drag_started() {
+            let world_pointer = pointer.map(|pointer| {
+                let position = (pointer - painter_info.zero(scale)) / scale;
+                Vector2::new(position.x, position.y)
+            });
+            self.dragged = self
+                .poses()
+                .find(|(_, footprint, pose)| {
+                    painter_info.is_position_clicked(pointer, scale, Vec2::new(pose[0], pose[1]))
+                        || world_pointer.is_some_and(|point| footprint.contains(pose, &point))
+                })
+                .map(|(name, _, _)| name.clone());
+        }
+        if let Some(dragged) = self.dragged.clone()
+            && let Some(pointer) = pointer
+        {
+            let position = (pointer - painter_info.zero(scale)) / scale;
+            if let Some((_, state)) = self.initial_states().find(|(name, _)| **name == dragged) {
+                state.pose[0] = position.x;
+                state.pose[1] = position.y;
+            }
+        }
+        if response.drag_stopped() {
+            self.dragged = None;
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Lazy loading of a result file in the viewer.
+//!
+//! Parsing a whole result file of several gigabytes takes minutes. The [`LazyResultLoader`] builds
+//! the [`ResultIndex`] of the file in a background thread, then reads the records by time window,
+//! around the displayed time, in the same thread. The records are pushed in the record buffer of
+//! the application, as the records received from the simulator.
+//!
+//! At most [`MAX_LOADED_WINDOWS`] windows are kept: the least recently displayed window is evicted
+//! first, and the application removes its records (see [`LazyResultLoader::request_time`]).
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+};
+
+use crate::{
+    errors::SimbaError,
+    simulator::{Record, ResultIndex},
+    utils::{SharedMutex, lock_recovery::RecoverMutex},
+};
+
+/// Duration of the time windows read at once, in seconds.
+const WINDOW_DURATION: f32 = 10.;
+/// Number of windows kept in memory.
+const MAX_LOADED_WINDOWS: usize = 6;
+
+pub struct LazyResultLoader {
+    index: SharedMutex<Option<Arc<ResultIndex>>>,
+    index_taken: bool,
+    indexing: Arc<AtomicBool>,
+    requests: Sender<i64>,
+    errors: Receiver<SimbaError>,
+    /// Windows requested and not evicted. The reading thread pushes the records of a window only
+    /// while it is in this set.
+    requested: SharedMutex<BTreeSet<i64>>,
+    /// Requested windows, from the least to the most recently displayed.
+    recent: VecDeque<i64>,
+}
+
+impl LazyResultLoader {
+    /// Start indexing the file `path`. The records read are pushed in `record_buffer`.
+    pub fn start(path: PathBuf, record_buffer: SharedMutex<Vec<Record>>) -> Self {
+        let index = Arc::new(Mutex::new(None));
+        let (requests, request_rx) = mpsc::channel::<i64>();
+        let (error_tx, errors) = mpsc::channel();
+        let shared_index = index.clone();
+        let indexing = Arc::new(AtomicBool::new(true));
+        let shared_indexing = indexing.clone();
+        let requested = Arc::new(Mutex::new(BTreeSet::new()));
+        let shared_requested = requested.clone();
+        thread::spawn(move || {
+            log::info!(
+                "Indexing results of `{}`",
+                path.to_str().unwrap_or_default()
+            );
+            let index = ResultIndex::build(&path);
+            shared_indexing.store(false, Ordering::Relaxed);
+            let index = match index {
+                Ok(index) => Arc::new(index),
+                Err(e) => {
+                    let _ = error_tx.send(e);
+                    return;
+                }
+            };
+            log::info!("{} records indexed", index.entries().len());
+            *shared_index.lock().unwrap() = Some(index.clone());
+            for window in request_rx {
+                if !shared_requested.lock_recover().contains(&window) {
+                    // Evicted before being read
+                    continue;
+                }
+                let from = window as f32 * WINDOW_DURATION;
+                match index.read_records(from, from + WINDOW_DURATION) {
+                    Ok(records) => {
+                        // Keep the lock while pushing, so that an eviction cannot happen between
+                        let requested = shared_requested.lock_recover();
+                        if requested.contains(&window) {
+                            record_buffer.lock_recover().extend(records);
+                        }
+                    }
+                    Err(e) => {
+                        let _ = error_tx.send(e);
+                    }
+                }
+            }
+        });
+        Self {
+            index,
+            index_taken: false,
+            indexing,
+            requests,
+            errors,
+            requested,
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Returns `true` while the file is being indexed.
+    pub fn is_indexing(&self) -> bool {
+        self.indexing.load(Ordering::Relaxed)
+    }
+
+    /// Returns the index once, when it is ready.
+    pub fn take_index(&mut self) -> Option<Arc<ResultIndex>> {
+        if self.index_taken {
+            return None;
+        }
+        let index = self.index.lock().unwrap().clone();
+        self.index_taken = index.is_some();
+        index
+    }
+
+    /// Request the windows around `time` (current and next one), if not already read.
+    ///
+    /// Returns the time ranges `[from, to[` of the windows evicted to keep at most
+    /// [`MAX_LOADED_WINDOWS`] windows: their records should be removed by the caller.
+    pub fn request_time(&mut self, time: f32) -> Vec<(f32, f32)> {
+        let window = (time / WINDOW_DURATION).floor() as i64;
+        let mut requested = self.requested.lock_recover();
+        for window in [window, window + 1] {
+            if window < 0 {
+                continue;
+            }
+            self.recent.retain(|w| *w != window);
+            self.recent.push_back(window);
+            if requested.insert(window) {
+                // The requests wait for the index in the channel
+                let _ = self.requests.send(window);
+            }
+        }
+        let mut evicted = Vec::new();
+        while self.recent.len() > MAX_LOADED_WINDOWS
+            && let Some(window) = self.recent.pop_front()
+        {
+            requested.remove(&window);
+            let from = window as f32 * WINDOW_DURATION;
+            evicted.push((from, from + WINDOW_DURATION));
+        }
+        evicted
+    }
+
+    /// Returns `true` if the records at `time` belong to a window still loaded. Records read
+    /// before the eviction of their window are dropped with this check.
+    pub fn is_loaded(&self, time: f32) -> bool {
+        let window = (time / WINDOW_DURATION).floor() as i64;
+        self.requested.lock_recover().contains(&window)
+    }
+
+    /// Errors raised since the last call.
+    pub fn take_errors(&self) -> Vec<SimbaError> {
+        self.errors.try_iter().collect()
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::VecDeque,
+    collections::{BTreeMap, VecDeque},
     sync::{Arc, Mutex},
 };
 
@@ -93,6 +93,7 @@ mod kill_node {
                         message: serde_json::Value::Null,
                         timestamp: time,
                         message_flags: vec![MessageFlag::Kill],
+                        ..Default::default()
                     },
                     time,
                 );
@@ -126,9 +127,7 @@ mod kill_node {
     }
 }
 
-#[test]
-fn kill_node() {
-    let kill_time = 5.;
+fn kill_node_config() -> SimulatorConfig {
     let mut config = SimulatorConfig::default();
     config.log.log_level = LogLevel::Off;
     // config.log.log_level = LogLevel::Internal(vec![crate::logger::InternalLog::All]);
@@ -145,6 +144,9 @@ fn kill_node() {
             config: StateEstimatorConfig::External(ExternalEstimatorConfig {
                 config: serde_json::Value::Null,
             }),
+            sensors: None,
+            watchdog: None,
+            warm_start: None,
         }],
         ..Default::default()
     });
@@ -164,7 +166,12 @@ fn kill_node() {
         }),
         ..Default::default()
     });
+    config
+}
 
+/// Runs the [`kill_node_config`] simulation, where node1 kills node2 at `kill_time`, and
+/// returns the last record time of each node.
+fn run_kill_node(kill_time: f32) -> BTreeMap<String, f32> {
     let plugin_api = PluginAPITest::<kill_node::StateEstimatorTest> {
         se: Mutex::new(Some(kill_node::StateEstimatorTest {
             last_time: 0.,
@@ -173,20 +180,27 @@ fn kill_node() {
     };
 
     let plugin_api = Arc::new(plugin_api);
-    let mut simulator = Simulator::from_config(&config, Some(plugin_api.clone())).unwrap();
+    let mut simulator =
+        Simulator::from_config(&kill_node_config(), Some(plugin_api.clone())).unwrap();
 
     simulator.run().unwrap();
 
     let records = simulator.get_records(false);
-    let mut last_node2_time: f32 = 0.;
+    let mut last_times = BTreeMap::new();
     for record in records {
         let t = record.time;
-        if let NodeRecord::Robot(r) = record.node
-            && r.name.as_str() == "node2"
-        {
-            last_node2_time = last_node2_time.max(t);
+        if let NodeRecord::Robot(r) = record.node {
+            let last_time = last_times.entry(r.name).or_insert(0f32);
+            *last_time = last_time.max(t);
         }
     }
+    last_times
+}
+
+#[test]
+fn kill_node() {
+    let kill_time = 5.;
+    let last_node2_time = run_kill_node(kill_time)["node2"];
     assert!(
         kill_time == last_node2_time,
         "Node not killed at right time (last time is {})",
@@ -194,6 +208,24 @@ fn kill_node() {
     );
 }
 
+/// With `force_hard_determinism`, the kill is applied after the final barrier of the time
+/// step: the other nodes should not wait for the killed node at the next barriers.
+#[cfg(feature = "force_hard_determinism")]
+#[test]
+fn kill_node_hard_determinism() {
+    let kill_time = 5.;
+    let max_time = kill_node_config().max_time;
+    let last_times = run_kill_node(kill_time);
+    assert_eq!(last_times["node2"], kill_time);
+    for node in ["node1", "node3"] {
+        assert!(
+            (last_times[node] - max_time).abs() < 0.2,
+            "{node} stopped before the end (last time is {})",
+            last_times[node]
+        );
+    }
+}
+
 mod trigger_sensor {
     use simba_com::pub_sub::PathKey;
 
@@ -344,6 +376,9 @@ fn trigger_sensor() {
             config: StateEstimatorConfig::External(ExternalEstimatorConfig {
                 config: serde_json::Value::Bool(false),
             }),
+            sensors: None,
+            watchdog: None,
+            warm_start: None,
         }],
         sensor_manager: SensorManagerConfig {
             sensors: vec![ManagedSensorConfig {
@@ -367,6 +402,9 @@ fn trigger_sensor() {
             config: StateEstimatorConfig::External(ExternalEstimatorConfig {
                 config: serde_json::Value::Bool(true),
             }),
+            sensors: None,
+            watchdog: None,
+            warm_start: None,
         }],
         ..Default::default()
     });
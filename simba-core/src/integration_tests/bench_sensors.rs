@@ -0,0 +1,162 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    constants::TIME_ROUND,
+    logger::LogLevel,
+    networking::network::Network,
+    node::{Node, node_factory::RobotConfig},
+    physics::robot_models::Command,
+    plugin_api::PluginAPI,
+    recordable::Recordable,
+    sensors::{
+        Observation, SensorConfig,
+        gnss_sensor::GNSSSensorConfig,
+        sensor_manager::{ManagedSensorConfig, SensorManagerConfig},
+    },
+    simulator::{Simulator, SimulatorConfig},
+    state_estimators::{
+        BenchStateEstimatorConfig, StateEstimator, StateEstimatorConfig, StateEstimatorRecord,
+        WorldState,
+        external_estimator::{ExternalEstimatorConfig, ExternalEstimatorRecord},
+    },
+    utils::{
+        SharedMutex, SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory,
+        maths::round_precision,
+    },
+};
+
+type ReceivedSensors = SharedMutex<BTreeMap<String, BTreeSet<String>>>;
+
+/// Keeps the names of the sensors of the observations it receives.
+struct SensorListingEstimator {
+    name: String,
+    last_time: f32,
+    received: ReceivedSensors,
+}
+
+impl StateEstimator for SensorListingEstimator {
+    fn correction_step(&mut self, _node: &mut Node, observations: &[Observation], _time: f32) {
+        self.received
+            .lock()
+            .unwrap()
+            .entry(self.name.clone())
+            .or_default()
+            .extend(observations.iter().map(|obs| obs.sensor_name.clone()));
+    }
+
+    fn prediction_step(&mut self, _node: &mut Node, _command: Option<Command>, time: f32) {
+        self.last_time = time;
+    }
+
+    fn next_time_step(&self) -> f32 {
+        round_precision(self.last_time + 0.1, TIME_ROUND).unwrap()
+    }
+
+    fn world_state(&self) -> WorldState {
+        WorldState::new()
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+}
+
+impl Recordable<StateEstimatorRecord> for SensorListingEstimator {
+    fn record(&self) -> StateEstimatorRecord {
+        StateEstimatorRecord::External(ExternalEstimatorRecord::default())
+    }
+}
+
+struct PluginAPITest {
+    received: ReceivedSensors,
+}
+
+impl PluginAPI for PluginAPITest {
+    fn get_state_estimator(
+        &self,
+        config: &serde_json::Value,
+        _global_config: &SimulatorConfig,
+        _va_factory: &Arc<DeterministRandomVariableFactory>,
+        _network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> Box<dyn StateEstimator> {
+        Box::new(SensorListingEstimator {
+            name: config.as_str().unwrap().to_string(),
+            last_time: initial_time,
+            received: self.received.clone(),
+        })
+    }
+}
+
+fn listing_estimator(name: &str) -> StateEstimatorConfig {
+    StateEstimatorConfig::External(ExternalEstimatorConfig {
+        config: serde_json::Value::String(name.to_string()),
+    })
+}
+
+fn config(bench_sensors: Vec<String>) -> SimulatorConfig {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 3.;
+    config.results = None;
+    config.robots.push(RobotConfig {
+        name: "robot1".to_string(),
+        state_estimator: listing_estimator("main"),
+        state_estimator_bench: vec![BenchStateEstimatorConfig {
+            name: "bench".to_string(),
+            config: listing_estimator("bench"),
+            sensors: Some(bench_sensors),
+            watchdog: None,
+            warm_start: None,
+        }],
+        sensor_manager: SensorManagerConfig {
+            sensors: ["a", "b"]
+                .into_iter()
+                .map(|name| ManagedSensorConfig {
+                    name: name.to_string(),
+                    config: SensorConfig::GNSS(GNSSSensorConfig::default()),
+                    ..Default::default()
+                })
+                .collect(),
+        },
+        ..Default::default()
+    });
+    config
+}
+
+#[test]
+fn bench_estimator_receives_only_its_sensors() {
+    let received = Arc::new(Mutex::new(BTreeMap::new()));
+    let plugin_api = Arc::new(PluginAPITest {
+        received: received.clone(),
+    });
+    let mut simulator =
+        Simulator::from_config(&config(vec!["a".to_string()]), Some(plugin_api)).unwrap();
+    simulator.run().unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received.get("main"),
+        Some(&BTreeSet::from(["a".to_string(), "b".to_string()]))
+    );
+    assert_eq!(
+        received.get("bench"),
+        Some(&BTreeSet::from(["a".to_string()]))
+    );
+}
+
+#[test]
+fn unknown_bench_sensor_is_rejected() {
+    let plugin_api = Arc::new(PluginAPITest {
+        received: Arc::new(Mutex::new(BTreeMap::new())),
+    });
+    let error = Simulator::from_config(&config(vec!["c".to_string()]), Some(plugin_api))
+        .err()
+        .expect("A bench estimator with an unknown sensor should be rejected");
+    assert!(
+        error.detailed_error().contains("Unknown sensor `c`"),
+        "Unexpected error: {}",
+        error.detailed_error()
+    );
+}
@@ -0,0 +1,73 @@
+use crate::{
+    logger::LogLevel,
+    navigators::{
+        NavigatorConfig, NavigatorRecord,
+        composite_navigator::{
+            CompositeNavigatorConfig, CompositePhaseConfig, PhaseConditionConfig,
+            PhaseTransitionConfig, TimerConditionConfig,
+        },
+    },
+    node::node_factory::{NodeRecord, RobotConfig},
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+};
+
+fn timer_phase(name: &str, to: &str, duration: f32) -> CompositePhaseConfig {
+    CompositePhaseConfig {
+        name: name.to_string(),
+        transitions: vec![PhaseTransitionConfig {
+            to: to.to_string(),
+            condition: PhaseConditionConfig::Timer(TimerConditionConfig { duration }),
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn phase_switching_on_timers() {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 3.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        navigator: NavigatorConfig::Composite(CompositeNavigatorConfig {
+            initial_phase: None,
+            phases: vec![
+                timer_phase("wait", "go", 1.),
+                timer_phase("go", "wait", 0.5),
+            ],
+        }),
+        ..Default::default()
+    });
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let (_, last) = simulator
+        .get_records(false)
+        .into_iter()
+        .filter_map(|record| match record.node {
+            NodeRecord::Robot(r) => match r.navigator {
+                NavigatorRecord::Composite(c) => Some((record.time, c)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .max_by(|(t1, _), (t2, _)| t1.total_cmp(t2))
+        .unwrap();
+    let switches: Vec<_> = last
+        .switches
+        .iter()
+        .map(|switch| (switch.time, switch.from.as_str(), switch.to.as_str()))
+        .collect();
+    assert!(switches.len() >= 3, "{switches:?}");
+    // The switches happen at the end of the timers of the active phase only
+    for ((time, from, to), expected) in
+        switches
+            .iter()
+            .zip([(1., "wait", "go"), (1.5, "go", "wait"), (2.5, "wait", "go")])
+    {
+        assert!((time - expected.0).abs() < 1e-3, "{switches:?}");
+        assert_eq!((*from, *to), (expected.1, expected.2));
+    }
+}
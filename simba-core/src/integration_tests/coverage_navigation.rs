@@ -0,0 +1,55 @@
+use crate::{
+    logger::LogLevel,
+    navigators::{
+        NavigatorConfig, NavigatorRecord,
+        coverage::{CoverageAreaConfig, CoverageNavigatorConfig, CoverageRectConfig},
+    },
+    node::node_factory::{NodeRecord, RobotConfig},
+    physics::PhysicsRecord,
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+};
+
+#[test]
+fn coverage_area_too_small_stops_the_robot() {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 2.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        navigator: NavigatorConfig::Coverage(CoverageNavigatorConfig {
+            area: CoverageAreaConfig::Rect(CoverageRectConfig {
+                bottom_left: [0., 0.],
+                top_right: [2., 2.],
+            }),
+            margin: 2.,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let records: Vec<_> = simulator
+        .get_records(false)
+        .into_iter()
+        .filter_map(|record| match record.node {
+            NodeRecord::Robot(r) => Some(r),
+            _ => None,
+        })
+        .collect();
+    assert!(!records.is_empty());
+    for record in records {
+        let NavigatorRecord::Coverage(coverage) = record.navigator else {
+            panic!("Coverage navigator expected");
+        };
+        assert!(coverage.stopped);
+        assert_eq!(coverage.sweep_lines, 0);
+        assert_eq!(coverage.follower.error.longitudinal, 0.);
+        assert!(coverage.follower.error.velocity <= 0.);
+        if let PhysicsRecord::Internal(physics) = record.physics {
+            assert!(physics.state.velocity[0].abs() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use config_checker::*;
+
+use crate::{
+    config::NumberConfig,
+    logger::LogLevel,
+    navigators::{NavigatorConfig, go_to::GoToConfig},
+    node::node_factory::{NodeRecord, RobotConfig},
+    physics::{
+        PhysicsConfig, PhysicsRecord,
+        fault_models::{
+            additive_robot_centered::{AdditiveRobotCenteredPhysicsFaultConfig, PhysicsVariables},
+            fault_model::PhysicsFaultModelConfig,
+        },
+        internal_physics::InternalPhysicConfig,
+    },
+    scenario::config::{
+        ActuatorFaultEventConfig, ConfigPatch, EventConfig, EventTriggerConfig, EventTypeConfig,
+        SendMessageEventConfig, SensorFaultEventConfig, SensorNoisePatch, SetStateEventConfig,
+        TimeEventTriggerConfig,
+    },
+    sensors::{
+        SensorConfig,
+        fault_models::additive::AdditiveFaultConfig,
+        gnss_sensor::{GNSSSensorConfig, GNSSSensorFaultModelConfig},
+        sensor_manager::{ManagedSensorConfig, SensorManagerConfig},
+    },
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+    utils::{
+        determinist_random_variable::RandomVariableTypeConfig,
+        distributions::fixed::FixedRandomVariableConfig,
+    },
+};
+
+fn time_event(time: f32, event_type: EventTypeConfig) -> EventConfig {
+    EventConfig {
+        trigger: EventTriggerConfig::Time(TimeEventTriggerConfig {
+            time: NumberConfig::Num(time),
+            occurences: NumberConfig::Num(1.),
+        }),
+        event_type,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn fault_activation_events() {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 5.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        physics: PhysicsConfig::Internal(InternalPhysicConfig {
+            faults: vec![PhysicsFaultModelConfig::AdditiveRobotCentered(
+                AdditiveRobotCenteredPhysicsFaultConfig::default(),
+            )],
+            ..Default::default()
+        }),
+        sensor_manager: SensorManagerConfig {
+            sensors: vec![ManagedSensorConfig {
+                name: "gps".to_string(),
+                config: SensorConfig::GNSS(GNSSSensorConfig {
+                    faults: vec![GNSSSensorFaultModelConfig::Additive(
+                        AdditiveFaultConfig::default(),
+                    )],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+        },
+        ..Default::default()
+    });
+    config.scenario.events = vec![
+        time_event(
+            1.,
+            EventTypeConfig::SensorFault(SensorFaultEventConfig {
+                node: "node1".to_string(),
+                sensor: "gps".to_string(),
+                fault: 0,
+                active: false,
+            }),
+        ),
+        time_event(
+            2.,
+            EventTypeConfig::SensorFault(SensorFaultEventConfig {
+                node: "node1".to_string(),
+                sensor: "gps".to_string(),
+                fault: 1,
+                active: true,
+            }),
+        ),
+        time_event(
+            3.,
+            EventTypeConfig::ActuatorFault(ActuatorFaultEventConfig {
+                node: "node1".to_string(),
+                fault: 0,
+                active: false,
+            }),
+        ),
+        time_event(
+            4.,
+            EventTypeConfig::ConfigPatch(ConfigPatch::SensorNoise(SensorNoisePatch {
+                node: "node1".to_string(),
+                sensor: "gps".to_string(),
+                noise_scale: 2.,
+            })),
+        ),
+    ];
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    // Each record holds the reconfigurations received since the previous one
+    let mut records = simulator
+        .get_records(false)
+        .into_iter()
+        .filter_map(|record| match record.node {
+            NodeRecord::Robot(r) => Some((record.time, r)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    records.sort_by(|(t1, _), (t2, _)| t1.total_cmp(t2));
+    let reconfigurations = records
+        .into_iter()
+        .flat_map(|(_, r)| r.sensors.sensors)
+        .filter(|s| s.name == "gps")
+        .flat_map(|s| s.reconfigurations)
+        .collect::<Vec<_>>();
+    assert_eq!(reconfigurations.len(), 3);
+    assert_eq!(
+        reconfigurations[0].message.fault_activation,
+        Some((0, false))
+    );
+    assert!(reconfigurations[0].error.is_none());
+    // The sensor has only one fault model
+    assert!(reconfigurations[1].error.is_some());
+    assert_eq!(reconfigurations[2].message.noise_scale, Some(2.));
+    assert!(reconfigurations[2].error.is_none());
+}
+
+fn actuator_fault_poses(faults: Vec<PhysicsFaultModelConfig>) -> Vec<(f32, [f32; 3])> {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 4.;
+    config.results = Some(ResultConfig::default());
+    let inactive_faults = (0..faults.len()).collect();
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        physics: PhysicsConfig::Internal(InternalPhysicConfig {
+            faults,
+            inactive_faults,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    config.scenario.events = vec![time_event(
+        2.,
+        EventTypeConfig::ActuatorFault(ActuatorFaultEventConfig {
+            node: "node1".to_string(),
+            fault: 0,
+            active: true,
+        }),
+    )];
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let mut poses: Vec<_> = simulator
+        .get_records(false)
+        .into_iter()
+        .filter_map(|record| match record.node {
+            NodeRecord::Robot(r) => match r.physics {
+                PhysicsRecord::Internal(p) => Some((record.time, p.state.pose)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    poses.sort_by(|(t1, _), (t2, _)| t1.total_cmp(t2));
+    poses
+}
+
+#[test]
+fn actuator_fault_event() {
+    let fault =
+        PhysicsFaultModelConfig::AdditiveRobotCentered(AdditiveRobotCenteredPhysicsFaultConfig {
+            distributions: vec![RandomVariableTypeConfig::Fixed(FixedRandomVariableConfig {
+                values: vec![1.],
+            })],
+            variable_order: vec![PhysicsVariables::X],
+            proportional_to: None,
+            proportional_factor: None,
+        });
+
+    let out_of_range = InternalPhysicConfig {
+        faults: vec![fault.clone()],
+        inactive_faults: vec![1],
+        ..Default::default()
+    };
+    assert!(out_of_range.check().is_err());
+
+    let nominal = actuator_fault_poses(Vec::new());
+    let faulty = actuator_fault_poses(vec![fault]);
+    assert_eq!(nominal.len(), faulty.len());
+    for ((time, nominal_pose), (faulty_time, faulty_pose)) in nominal.iter().zip(faulty.iter()) {
+        assert_eq!(time, faulty_time);
+        if *time < 2. {
+            // The fault is inactive at start
+            assert_eq!(nominal_pose, faulty_pose);
+        } else if *time > 2.5 {
+            assert!(faulty_pose[0] - nominal_pose[0] > 0.1);
+        }
+    }
+}
+
+#[test]
+fn set_state_event() {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 2.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        ..Default::default()
+    });
+    config.scenario.events = vec![time_event(
+        1.,
+        EventTypeConfig::SetState(SetStateEventConfig {
+            node: "node1".to_string(),
+            pose: Some([50., -20., 0.]),
+            velocity: None,
+        }),
+    )];
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let poses: Vec<_> = simulator
+        .get_records(false)
+        .into_iter()
+        .filter_map(|record| match record.node {
+            NodeRecord::Robot(r) => match r.physics {
+                PhysicsRecord::Internal(p) => Some((record.time, p.state.pose)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    // Kidnapped at t = 1
+    assert!(
+        poses
+            .iter()
+            .filter(|(time, _)| *time < 1.)
+            .all(|(_, pose)| pose[0] < 10.)
+    );
+    assert!(
+        poses
+            .iter()
+            .filter(|(time, _)| *time > 1.1)
+            .all(|(_, pose)| (pose[0] - 50.).abs() < 10. && (pose[1] + 20.).abs() < 10.)
+    );
+}
+
+#[test]
+fn send_message_event() {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 10.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        navigator: NavigatorConfig::GoTo(GoToConfig::default()),
+        ..Default::default()
+    });
+    config.scenario.events = vec![time_event(
+        1.,
+        EventTypeConfig::SendMessage(SendMessageEventConfig {
+            node: None,
+            message: serde_json::json!({"GoTo": {"target_point": [3., 0.]}}),
+            ..Default::default()
+        }),
+    )];
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let poses: Vec<_> = simulator
+        .get_records(true)
+        .into_iter()
+        .filter_map(|record| match record.node {
+            NodeRecord::Robot(r) => match r.physics {
+                PhysicsRecord::Internal(p) => Some((record.time, p.state.pose)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    // No target before the message
+    assert!(
+        poses
+            .iter()
+            .filter(|(time, _)| *time < 1.)
+            .all(|(_, pose)| pose[0].abs() < 1e-3)
+    );
+    let last_pose = poses.last().unwrap().1;
+    assert!(last_pose[0] > 1.);
+}
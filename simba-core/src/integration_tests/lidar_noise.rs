@@ -0,0 +1,81 @@
+use std::fs;
+
+use crate::{
+    logger::LogLevel,
+    node::node_factory::{NodeRecord, RobotConfig},
+    sensors::{
+        SensorConfig, SensorObservationRecord,
+        lidar_sensor::LidarSensorConfig,
+        scan_sensor::{ScanSensorFilterConfig, ScanSensorVariablesFilter},
+        sensor_filters::range_filter::RangeFilterConfig,
+        sensor_manager::{ManagedSensorConfig, SensorManagerConfig},
+    },
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+};
+
+/// Square room of walls, 5 m around the origin.
+const ROOM_MAP: &str = "landmarks:
+  - {id: 1, x: 5, y: 0, theta: 0, width: 20, height: 1}
+  - {id: 2, x: -5, y: 0, theta: 0, width: 20, height: 1}
+  - {id: 3, x: 0, y: 5, theta: 1.5708, width: 20, height: 1}
+  - {id: 4, x: 0, y: -5, theta: 1.5708, width: 20, height: 1}
+";
+
+#[test]
+fn range_noise_is_filtered() {
+    let directory = std::env::temp_dir().join(format!("simba_lidar_noise_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("room.yaml"), ROOM_MAP).unwrap();
+
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 1.;
+    config.base_path = directory.clone().into_boxed_path();
+    config.environment.map_path = Some("room.yaml".to_string());
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        sensor_manager: SensorManagerConfig {
+            sensors: vec![ManagedSensorConfig {
+                name: "lidar".to_string(),
+                config: SensorConfig::Lidar(LidarSensorConfig {
+                    angular_resolution: 0.05,
+                    range_noise: 2.,
+                    filters: vec![ScanSensorFilterConfig::Range(RangeFilterConfig {
+                        variables: vec![ScanSensorVariablesFilter::R],
+                        min_range: vec![0.],
+                        max_range: vec![6.],
+                        inside: true,
+                    })],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+        },
+        ..Default::default()
+    });
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let mut distances = Vec::new();
+    for record in simulator.get_records(false) {
+        let NodeRecord::Robot(robot) = record.node else {
+            continue;
+        };
+        for observation in robot.sensor_manager.last_observations {
+            if let SensorObservationRecord::Scan(scan) = observation.sensor_observation {
+                distances.extend(scan.distances);
+            }
+        }
+    }
+    // The noise spreads the ranges, and the filter sees the noisy ranges
+    assert!(!distances.is_empty());
+    assert!(distances.iter().all(|distance| *distance <= 6.));
+    assert!(
+        distances
+            .iter()
+            .any(|distance| (*distance - distances[0]).abs() > 0.1)
+    );
+    let _ = fs::remove_dir_all(&directory);
+}
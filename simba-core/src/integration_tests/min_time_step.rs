@@ -0,0 +1,50 @@
+use crate::{
+    logger::LogLevel,
+    node::node_factory::{NodeRecord, RobotConfig},
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+};
+
+#[test]
+fn slow_node_skips_the_close_rounds() {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 2.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "fast".to_string(),
+        ..Default::default()
+    });
+    config.robots.push(RobotConfig {
+        name: "slow".to_string(),
+        min_time_step: Some(0.5),
+        ..Default::default()
+    });
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let mut fast_times = Vec::new();
+    let mut slow_times = Vec::new();
+    let mut slow_skipped_steps = 0;
+    for record in simulator.get_records(false) {
+        let NodeRecord::Robot(robot) = record.node else {
+            continue;
+        };
+        if robot.name == "slow" {
+            slow_times.push(record.time);
+            slow_skipped_steps = slow_skipped_steps.max(robot.skipped_steps);
+        } else {
+            assert_eq!(robot.skipped_steps, 0);
+            fast_times.push(record.time);
+        }
+    }
+    assert!(fast_times.len() > slow_times.len());
+    assert!(slow_skipped_steps > 0);
+    assert!(
+        slow_times
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] >= 0.5 - 1e-4)
+    );
+    // The skipped rounds keep the other node in step until the end
+    assert!(*fast_times.last().unwrap() >= config.max_time - 0.2);
+}
@@ -1,2 +1,14 @@
 mod action_messages;
+mod bench_sensors;
+mod composite_navigation;
+mod coverage_navigation;
+mod fault_events;
+mod lidar_noise;
+mod min_time_step;
+mod node_failure;
+mod observation_latency;
 mod repeatability;
+mod result_index;
+mod result_paths;
+mod run_control;
+mod warm_start;
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use crate::{
+    constants::TIME_ROUND,
+    logger::LogLevel,
+    networking::network::Network,
+    node::{
+        Node,
+        node_factory::{NodeRecord, RobotConfig},
+    },
+    physics::robot_models::Command,
+    plugin_api::PluginAPI,
+    recordable::Recordable,
+    sensors::Observation,
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+    state_estimators::{
+        StateEstimator, StateEstimatorConfig, StateEstimatorRecord, WorldState,
+        external_estimator::{ExternalEstimatorConfig, ExternalEstimatorRecord},
+    },
+    utils::{
+        SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory,
+        maths::round_precision,
+    },
+};
+
+#[derive(Debug, Clone)]
+struct PanickingStateEstimator {
+    last_time: f32,
+    panic_time: f32,
+}
+
+impl StateEstimator for PanickingStateEstimator {
+    fn correction_step(&mut self, _node: &mut Node, _observations: &[Observation], _time: f32) {}
+
+    fn prediction_step(&mut self, _node: &mut Node, _command: Option<Command>, time: f32) {
+        if time >= self.panic_time {
+            panic!("State estimator failure at time {time}");
+        }
+        self.last_time = time;
+    }
+
+    fn next_time_step(&self) -> f32 {
+        round_precision(self.last_time + 0.1, TIME_ROUND).unwrap()
+    }
+
+    fn world_state(&self) -> WorldState {
+        WorldState::new()
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+}
+
+impl Recordable<StateEstimatorRecord> for PanickingStateEstimator {
+    fn record(&self) -> StateEstimatorRecord {
+        StateEstimatorRecord::External(ExternalEstimatorRecord {
+            record: serde_json::Value::default(),
+        })
+    }
+}
+
+struct PluginAPITest {
+    panic_time: f32,
+}
+
+impl PluginAPI for PluginAPITest {
+    fn get_state_estimator(
+        &self,
+        _config: &serde_json::Value,
+        _global_config: &SimulatorConfig,
+        _va_factory: &Arc<DeterministRandomVariableFactory>,
+        _network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> Box<dyn StateEstimator> {
+        Box::new(PanickingStateEstimator {
+            last_time: initial_time,
+            panic_time: self.panic_time,
+        })
+    }
+}
+
+#[test]
+fn failed_node_does_not_stop_the_others() {
+    let panic_time = 2.;
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 5.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        state_estimator: StateEstimatorConfig::External(ExternalEstimatorConfig {
+            config: serde_json::Value::Null,
+        }),
+        ..Default::default()
+    });
+    config.robots.push(RobotConfig {
+        name: "node2".to_string(),
+        ..Default::default()
+    });
+
+    let plugin_api = Arc::new(PluginAPITest { panic_time });
+    let mut simulator = Simulator::from_config(&config, Some(plugin_api)).unwrap();
+
+    // The error of the failed node is returned at the end of the run
+    let error = simulator.run().unwrap_err();
+    assert!(
+        error.detailed_error().contains("node1"),
+        "Error not attributed to the failed node: {}",
+        error.detailed_error()
+    );
+
+    let mut last_times = [0f32; 2];
+    for record in simulator.get_records(false) {
+        if let NodeRecord::Robot(r) = record.node {
+            let index = if r.name == "node1" { 0 } else { 1 };
+            last_times[index] = last_times[index].max(record.time);
+        }
+    }
+    assert!(
+        last_times[0] < panic_time,
+        "Failed node recorded after its failure (last time is {})",
+        last_times[0]
+    );
+    assert!(
+        (last_times[1] - config.max_time).abs() < 0.2,
+        "Other node stopped before the end (last time is {})",
+        last_times[1]
+    );
+}
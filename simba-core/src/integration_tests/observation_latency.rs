@@ -0,0 +1,48 @@
+use crate::{
+    logger::LogLevel,
+    node::node_factory::{NodeRecord, RobotConfig},
+    sensors::{
+        SensorConfig,
+        gnss_sensor::GNSSSensorConfig,
+        sensor_manager::{ManagedSensorConfig, SensorManagerConfig},
+    },
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+};
+
+#[test]
+fn local_observations_have_no_latency() {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 2.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        sensor_manager: SensorManagerConfig {
+            sensors: vec![ManagedSensorConfig {
+                name: "gps".to_string(),
+                config: SensorConfig::GNSS(GNSSSensorConfig::default()),
+                ..Default::default()
+            }],
+        },
+        ..Default::default()
+    });
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let latencies: Vec<_> = simulator
+        .get_records(false)
+        .into_iter()
+        .filter_map(|record| match record.node {
+            NodeRecord::Robot(r) => Some(r.observation_latencies),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    assert!(!latencies.is_empty());
+    for latency in latencies {
+        assert_eq!(latency.sensor_name, "gps");
+        assert_eq!(latency.estimator, "state_estimator");
+        assert_eq!(latency.latency, 0.);
+    }
+}
@@ -63,3 +63,4 @@ macro_rules! replication_test {
 
 replication_test!(config);
 replication_test!(scenario);
+replication_test!(kill_spawn);
@@ -0,0 +1,75 @@
+use std::fs;
+
+use crate::{
+    logger::LogLevel,
+    node::node_factory::RobotConfig,
+    simulator::{ResultConfig, ResultIndex, Simulator, SimulatorConfig},
+};
+
+#[test]
+fn records_by_time_window() {
+    let base_path = std::env::temp_dir().join(format!("simba_result_index_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base_path);
+    fs::create_dir_all(&base_path).unwrap();
+
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 3.;
+    config.base_path = Box::from(base_path.as_path());
+    config.results = Some(ResultConfig {
+        result_path: Some("results.json".to_string()),
+        ..Default::default()
+    });
+    for name in ["robot1", "robot2"] {
+        config.robots.push(RobotConfig {
+            name: name.to_string(),
+            ..Default::default()
+        });
+    }
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+    let records = simulator.get_records(false);
+
+    let index = ResultIndex::build(&base_path.join("results.json")).unwrap();
+    assert_eq!(index.entries().len(), records.len());
+    assert!(index.entries().is_sorted_by(|a, b| a.time <= b.time));
+    let mut nodes = index.nodes().to_vec();
+    nodes.sort();
+    assert_eq!(nodes, vec!["robot1".to_string(), "robot2".to_string()]);
+    let (min_time, max_time) = index.time_range().unwrap();
+    assert!(min_time <= 1. && max_time <= config.max_time && max_time > 2.);
+
+    let window = index.read_records(1., 2.).unwrap();
+    let mut expected: Vec<_> = records
+        .iter()
+        .filter(|record| (1. ..2.).contains(&record.time))
+        .map(|record| (record.time, record.node.name().clone()))
+        .collect();
+    let mut read: Vec<_> = window
+        .iter()
+        .map(|record| (record.time, record.node.name().clone()))
+        .collect();
+    assert!(!read.is_empty());
+    expected.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    read.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    assert_eq!(read, expected);
+    // The records read are the recorded ones, not only at the right times
+    for record in &window {
+        let recorded = records
+            .iter()
+            .find(|r| r.time == record.time && r.node.name() == record.node.name())
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(record).unwrap(),
+            serde_json::to_value(recorded).unwrap()
+        );
+    }
+    assert!(index.read_records(10., 20.).unwrap().is_empty());
+
+    // Compressed files cannot be browsed
+    let compressed = base_path.join("results.json.gz");
+    fs::write(&compressed, [0x1f, 0x8b, 0x08, 0x00]).unwrap();
+    assert!(ResultIndex::build(&compressed).is_err());
+
+    let _ = fs::remove_dir_all(&base_path);
+}
@@ -0,0 +1,144 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    errors::SimbaErrorTypes,
+    logger::LogLevel,
+    node::node_factory::RobotConfig,
+    simulator::{ResultConfig, Simulator, SimulatorConfig, config_path},
+};
+
+/// Fresh temporary directory, removed first if a previous run left it.
+fn temp_directory(name: &std::ffi::OsStr) -> PathBuf {
+    let directory = std::env::temp_dir()
+        .join(format!("simba_result_paths_{}", std::process::id()))
+        .join(name);
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Run a short simulation saving its results in `base_path`, and read them back.
+fn run_and_reload(base_path: &Path) {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 1.;
+    config.base_path = Box::from(base_path);
+    config.results = Some(ResultConfig {
+        result_path: Some("results dir/run results.json".to_string()),
+        ..Default::default()
+    });
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    let result_file = base_path.join("results dir").join("run results.json");
+    assert!(result_file.is_file(), "{} not found", result_file.display());
+    let results = Simulator::deserialize_results_from_file(&result_file).unwrap();
+    assert_eq!(results.config.max_time, 1.);
+}
+
+#[test]
+fn config_path_separators() {
+    let base = Path::new("base dir");
+    assert_eq!(
+        config_path(base, "results/run.json"),
+        base.join("results").join("run.json")
+    );
+    // `\` is a separator on Windows only
+    #[cfg(windows)]
+    assert_eq!(
+        config_path(base, "results\\run.json"),
+        base.join("results").join("run.json")
+    );
+    #[cfg(not(windows))]
+    assert_eq!(
+        config_path(base, "results\\run.json"),
+        base.join("results\\run.json")
+    );
+    let absolute = std::env::temp_dir().join("run.json");
+    assert_eq!(config_path(base, absolute.to_str().unwrap()), absolute);
+}
+
+#[test]
+fn figure_directory_creation_failure() {
+    let base_path = temp_directory("figure failure".as_ref());
+    fs::write(
+        base_path.join("analyse.py"),
+        "def analyse(*args):\n    pass\n",
+    )
+    .unwrap();
+    // A file where the figure directory should be created
+    fs::write(base_path.join("figures"), "").unwrap();
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 1.;
+    config.base_path = Box::from(base_path.as_path());
+    config.results = Some(ResultConfig {
+        analyse_script: Some("analyse.py".to_string()),
+        figures_path: Some("figures/run".to_string()),
+        ..Default::default()
+    });
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+    let error = simulator.compute_results().unwrap_err();
+    assert_eq!(error.error_type(), SimbaErrorTypes::ConfigError);
+    assert!(
+        error
+            .detailed_error()
+            .contains("Impossible to create figure directory"),
+        "{}",
+        error.detailed_error()
+    );
+    let _ = fs::remove_dir_all(&base_path);
+}
+
+#[test]
+fn results_in_directory_with_spaces_and_unicode() {
+    let base_path = temp_directory("dossier de résultats ✓".as_ref());
+    run_and_reload(&base_path);
+    let _ = fs::remove_dir_all(&base_path);
+}
+
+#[cfg(unix)]
+#[test]
+fn results_in_non_utf8_directory() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let base_path = temp_directory(std::ffi::OsStr::from_bytes(b"results \xff dir"));
+    run_and_reload(&base_path);
+    let _ = fs::remove_dir_all(&base_path);
+}
+
+#[test]
+fn continued_run_keeps_valid_json() {
+    let base_path = temp_directory("continued run".as_ref());
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 1.;
+    config.base_path = Box::from(base_path.as_path());
+    config.robots.push(RobotConfig::default());
+    config.results = Some(ResultConfig {
+        result_path: Some("results.json".to_string()),
+        ..Default::default()
+    });
+
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+    simulator.annotate(0.5, "first run");
+    simulator.set_max_time(2.);
+    simulator.run().unwrap();
+
+    // The trailer of the first run is replaced by the records of the second one
+    let content = fs::read_to_string(base_path.join("results.json")).unwrap();
+    let results: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let records = results["records"].as_array().unwrap();
+    let last_time = records.last().unwrap()["time"].as_f64().unwrap();
+    assert!(last_time > 1.5, "{last_time}");
+    assert_eq!(results["annotations"].as_array().unwrap().len(), 1);
+    assert!(results.get("mission").is_some());
+    let _ = fs::remove_dir_all(&base_path);
+}
@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    logger::LogLevel,
+    node::node_factory::RobotConfig,
+    scenario::config::{ConfigPatch, ControllerGainsPatch, NetworkDelayPatch},
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+    utils::read_only_lock::RoLock,
+};
+
+#[test]
+fn config_patches_while_paused() {
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 2.;
+    config.results = Some(ResultConfig::default());
+    config.robots.push(RobotConfig {
+        name: "node1".to_string(),
+        ..Default::default()
+    });
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    let control = simulator.run_control();
+    let api = simulator.get_async_api();
+
+    let delay_patch = ConfigPatch::NetworkDelay(NetworkDelayPatch {
+        node: "node1".to_string(),
+        delay: 0.1,
+    });
+    // Only a paused run can be patched
+    assert!(control.patch_config(delay_patch.clone()).is_err());
+
+    control.pause();
+    let run = std::thread::spawn(move || simulator.run());
+
+    // The run holds at the end of its first time step
+    let start = Instant::now();
+    while *api.current_time.read().unwrap() <= 0. {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let paused_time = *api.current_time.read().unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(control.is_paused());
+    assert_eq!(*api.current_time.read().unwrap(), paused_time);
+
+    let accepted = control.patch_config(delay_patch).unwrap();
+    // The PID controller has fewer gains
+    let rejected = control
+        .patch_config(ConfigPatch::ControllerGains(ControllerGainsPatch {
+            node: "node1".to_string(),
+            proportional_gains: Some(vec![1.; 10]),
+            ..Default::default()
+        }))
+        .unwrap();
+    let unknown_node = control
+        .patch_config(ConfigPatch::NetworkDelay(NetworkDelayPatch {
+            node: "unknown".to_string(),
+            delay: 0.1,
+        }))
+        .unwrap();
+
+    control.step(1);
+    assert!(accepted.wait().is_ok());
+    assert!(rejected.wait().is_err());
+    assert!(unknown_node.wait().is_err());
+    assert!(*api.current_time.read().unwrap() > paused_time);
+
+    control.resume();
+    run.join().unwrap().unwrap();
+}
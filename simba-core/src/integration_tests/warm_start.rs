@@ -0,0 +1,67 @@
+use std::fs;
+
+use crate::{
+    logger::LogLevel,
+    node::node_factory::RobotConfig,
+    simulator::{ResultConfig, Simulator, SimulatorConfig},
+    state_estimators::warm_start::{
+        EstimatorWarmStartConfig, MAIN_ESTIMATOR, ResultWarmStartConfig,
+    },
+};
+
+#[test]
+fn record_from_result_file() {
+    let base_path = std::env::temp_dir().join(format!("simba_warm_start_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base_path);
+    fs::create_dir_all(&base_path).unwrap();
+
+    let mut config = SimulatorConfig::default();
+    config.log.log_level = LogLevel::Off;
+    config.max_time = 2.;
+    config.base_path = Box::from(base_path.as_path());
+    config.results = Some(ResultConfig {
+        result_path: Some("bootstrap.json".to_string()),
+        ..Default::default()
+    });
+    config.robots.push(RobotConfig {
+        name: "robot1".to_string(),
+        ..Default::default()
+    });
+    let mut simulator = Simulator::from_config(&config, None).unwrap();
+    simulator.run().unwrap();
+
+    // Pose estimated by the main estimator at the last record at or before `time`
+    let records = simulator.get_records(false);
+    let recorded_pose = |time: f32| {
+        records
+            .iter()
+            .filter(|record| record.node.name() == "robot1" && record.time <= time + 1e-4)
+            .max_by(|a, b| a.time.total_cmp(&b.time))
+            .and_then(|record| record.node.state_estimator())
+            .and_then(|record| record.ego())
+            .map(|ego| ego.pose)
+            .unwrap()
+    };
+    let warm_start = |node: Option<&str>, estimator: Option<&str>, time: Option<f32>| {
+        EstimatorWarmStartConfig::Result(ResultWarmStartConfig {
+            path: "bootstrap.json".to_string(),
+            node: node.map(str::to_string),
+            estimator: estimator.map(str::to_string),
+            time,
+        })
+        .load_record(&config, "robot1", MAIN_ESTIMATOR)
+    };
+
+    for time in [Some(1.), None] {
+        let record = warm_start(None, None, time).unwrap();
+        assert_eq!(
+            record.ego().unwrap().pose,
+            recorded_pose(time.unwrap_or(config.max_time))
+        );
+    }
+    assert!(warm_start(Some("robot2"), None, None).is_err());
+    assert!(warm_start(None, Some("unknown_bench"), None).is_err());
+    assert!(warm_start(None, None, Some(-1.)).is_err());
+
+    let _ = fs::remove_dir_all(&base_path);
+}
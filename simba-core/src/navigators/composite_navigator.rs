@@ -0,0 +1,769 @@
+//! Phase-based composition of [`Navigator`]s.
+//!
+//! [`CompositeNavigator`] holds one child navigator per mission phase (e.g. explore, return,
+//! dock), and switches between them when a transition condition of the active phase is met:
+//! time spent in the phase, area entered by the robot, or message received on
+//! [`CompositeNavigator::CHANNEL_NAME`]. The phase switches are recorded in
+//! [`CompositeNavigatorRecord::switches`] and added as run annotations.
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::json_config};
+
+use crate::{
+    controllers::ControllerError,
+    errors::SimbaResult,
+    navigators::{Navigator, NavigatorConfig, NavigatorRecord, go_to::GoToConfig},
+    networking::network::Network,
+    node::Node,
+    plugin_api::PluginAPI,
+    recordable::Recordable,
+    scenario::config::AreaEventTriggerConfig,
+    simulator::{SimbaBrokerMultiClient, SimulatorConfig},
+    state_estimators::WorldState,
+    utils::{
+        SharedMutex, SharedRwLock,
+        determinist_random_variable::DeterministRandomVariableFactory,
+        lock_recovery::{RecoverMutex, RecoverRwLock},
+    },
+};
+
+use config_checker::*;
+use serde_derive::{Deserialize, Serialize};
+use simba_com::pub_sub::{MultiClientTrait, PathKey};
+use simba_macros::config_derives;
+
+/// Time condition of a phase transition.
+#[config_derives]
+pub struct TimerConditionConfig {
+    /// Time spent in the phase before the transition, in seconds.
+    ///
+    /// Default: `10.0`.
+    pub duration: f32,
+}
+
+impl Check for TimerConditionConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.duration < 0. {
+            Err(vec!["Timer duration should be positive".to_string()])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for TimerConditionConfig {
+    fn default() -> Self {
+        Self { duration: 10. }
+    }
+}
+
+/// Message condition of a phase transition.
+#[config_derives]
+#[derive(Default)]
+pub struct MessageConditionConfig {
+    /// Content of the string message to receive on [`CompositeNavigator::CHANNEL_NAME`].
+    pub content: String,
+}
+
+/// Condition of a phase transition.
+#[config_derives(tag_content)]
+pub enum PhaseConditionConfig {
+    /// The robot spent the given time in the phase.
+    #[check]
+    Timer(TimerConditionConfig),
+    /// The estimated position of the robot triggers the area.
+    Area(AreaEventTriggerConfig),
+    /// A string message with the given content was received during the phase.
+    Message(MessageConditionConfig),
+}
+
+impl Default for PhaseConditionConfig {
+    fn default() -> Self {
+        Self::Timer(TimerConditionConfig::default())
+    }
+}
+
+/// Transition from a phase to another.
+#[config_derives]
+#[derive(Default)]
+pub struct PhaseTransitionConfig {
+    /// Name of the phase to switch to.
+    pub to: String,
+    /// Condition of the transition.
+    #[check]
+    pub condition: PhaseConditionConfig,
+}
+
+/// Phase of the [`CompositeNavigator`]: a child navigator and the transitions to the next
+/// phases, evaluated in order.
+#[config_derives]
+pub struct CompositePhaseConfig {
+    /// Name of the phase.
+    pub name: String,
+    /// Navigator of the phase.
+    #[check]
+    pub navigator: NavigatorConfig,
+    /// Transitions to the other phases. The first transition whose condition is met is taken.
+    #[check]
+    pub transitions: Vec<PhaseTransitionConfig>,
+}
+
+impl Default for CompositePhaseConfig {
+    fn default() -> Self {
+        Self {
+            name: String::from("phase"),
+            navigator: NavigatorConfig::GoTo(GoToConfig::default()),
+            transitions: Vec::new(),
+        }
+    }
+}
+
+/// Configuration of the [`CompositeNavigator`].
+///
+/// Mission phases are expressed as a list of phases, each one with its navigator and its
+/// transitions:
+///
+/// ```yaml
+/// navigator:
+///   type: Composite
+///   initial_phase: explore
+///   phases:
+///   - name: explore
+///     navigator:
+///       type: TrajectoryFollower
+///       trajectory_path: patrol.yaml
+///     transitions:
+///     - to: return
+///       condition: {type: Timer, value: {duration: 60.0}}
+///     - to: return
+///       condition: {type: Message, value: {content: "return"}}
+///   - name: return
+///     navigator:
+///       type: GoTo
+///       target_point: [0.0, 0.0]
+///     transitions:
+///     - to: dock
+///       condition:
+///         type: Area
+///         value: {type: Circle, center: [0.0, 0.0], radius: 1.0, inside: true}
+///   - name: dock
+///     navigator:
+///       type: GoTo
+///       target_point: [0.0, -1.0]
+///     transitions: []
+/// ```
+#[config_derives]
+#[derive(Default)]
+pub struct CompositeNavigatorConfig {
+    /// Name of the first phase. `None` to start with the first phase of the list.
+    ///
+    /// Default: `None`.
+    pub initial_phase: Option<String>,
+    /// Phases of the mission.
+    ///
+    /// Default: empty.
+    #[check]
+    pub phases: Vec<CompositePhaseConfig>,
+}
+
+impl Check for CompositeNavigatorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.phases.is_empty() {
+            errs.push("Composite navigator needs at least one phase".to_string());
+        }
+        let is_phase = |name: &String| self.phases.iter().any(|phase| &phase.name == name);
+        for (i, phase) in self.phases.iter().enumerate() {
+            if self.phases[..i].iter().any(|p| p.name == phase.name) {
+                errs.push(format!("Phase `{}` is defined twice", phase.name));
+            }
+            for transition in &phase.transitions {
+                if !is_phase(&transition.to) {
+                    errs.push(format!(
+                        "Transition of phase `{}` goes to the unknown phase `{}`",
+                        phase.name, transition.to
+                    ));
+                }
+            }
+        }
+        if let Some(initial_phase) = &self.initial_phase
+            && !is_phase(initial_phase)
+        {
+            errs.push(format!("Unknown initial phase `{initial_phase}`"));
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CompositeNavigatorConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Composite")
+            .id_salt(format!("composite-{unique_id}"))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Initial phase:");
+                    if let Some(initial_phase) = &mut self.initial_phase {
+                        ui.text_edit_singleline(initial_phase);
+                        if ui.button("X").clicked() {
+                            self.initial_phase = None;
+                        }
+                    } else {
+                        ui.label("first phase");
+                        if ui.button("+").clicked() {
+                            self.initial_phase = self.phases.first().map(|p| p.name.clone());
+                        }
+                    }
+                });
+
+                let mut to_remove = None;
+                for (i, phase) in self.phases.iter_mut().enumerate() {
+                    let phase_unique_id = format!("{unique_id}-phase-{i}");
+                    egui::CollapsingHeader::new(format!("Phase {}", phase.name))
+                        .id_salt(&phase_unique_id)
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Name:");
+                                ui.text_edit_singleline(&mut phase.name);
+                                if ui.button("Remove phase").clicked() {
+                                    to_remove = Some(i);
+                                }
+                            });
+                            phase.navigator.show_mut(
+                                ui,
+                                ctx,
+                                buffer_stack,
+                                global_config,
+                                current_node_name,
+                                &phase_unique_id,
+                            );
+                            ui.label("Transitions (JSON format):");
+                            let mut transitions = serde_json::to_value(&phase.transitions).unwrap();
+                            json_config(
+                                ui,
+                                &format!("{phase_unique_id}-transitions"),
+                                &format!("{phase_unique_id}-transitions-error"),
+                                buffer_stack,
+                                &mut transitions,
+                            );
+                            match serde_json::from_value(transitions) {
+                                Ok(transitions) => phase.transitions = transitions,
+                                Err(e) => {
+                                    buffer_stack.insert(
+                                        format!("{phase_unique_id}-transitions-error"),
+                                        e.to_string(),
+                                    );
+                                }
+                            }
+                        });
+                }
+                if let Some(i) = to_remove {
+                    self.phases.remove(i);
+                }
+                if ui.button("Add phase").clicked() {
+                    self.phases.push(CompositePhaseConfig {
+                        name: format!("phase{}", self.phases.len()),
+                        ..Default::default()
+                    });
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Composite")
+            .id_salt(format!("composite-{unique_id}"))
+            .show(ui, |ui| {
+                ui.label(format!(
+                    "Initial phase: {}",
+                    self.initial_phase.as_deref().unwrap_or("first phase")
+                ));
+                for (i, phase) in self.phases.iter().enumerate() {
+                    let phase_unique_id = format!("{unique_id}-phase-{i}");
+                    egui::CollapsingHeader::new(format!("Phase {}", phase.name))
+                        .id_salt(&phase_unique_id)
+                        .show(ui, |ui| {
+                            phase.navigator.show(ui, ctx, &phase_unique_id);
+                            for transition in &phase.transitions {
+                                ui.label(format!(
+                                    "To `{}` on {}",
+                                    transition.to,
+                                    serde_json::to_string(&transition.condition)
+                                        .unwrap_or_default()
+                                ));
+                            }
+                        });
+                }
+            });
+    }
+}
+
+/// Switch between two phases.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PhaseSwitchRecord {
+    /// Time of the switch.
+    pub time: f32,
+    /// Phase before the switch.
+    pub from: String,
+    /// Phase after the switch.
+    pub to: String,
+}
+
+/// Record of the [`CompositeNavigator`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CompositeNavigatorRecord {
+    /// Name of the active phase.
+    pub phase: String,
+    /// Time when the active phase started.
+    pub phase_start_time: f32,
+    /// Phase switches since the start, in order.
+    pub switches: Vec<PhaseSwitchRecord>,
+    /// Record of the navigator of the active phase.
+    pub navigator: Box<NavigatorRecord>,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CompositeNavigatorRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            ui.label(format!(
+                "Phase: {} (since {} s)",
+                self.phase, self.phase_start_time
+            ));
+            egui::CollapsingHeader::new("Switches").show(ui, |ui| {
+                for switch in &self.switches {
+                    ui.label(format!(
+                        "{} s: {} -> {}",
+                        switch.time, switch.from, switch.to
+                    ));
+                }
+            });
+            self.navigator.show(ui, ctx, unique_id);
+        });
+    }
+}
+
+struct Phase {
+    name: String,
+    navigator: SharedRwLock<Box<dyn Navigator>>,
+    transitions: Vec<PhaseTransitionConfig>,
+}
+
+impl std::fmt::Debug for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Phase")
+            .field("name", &self.name)
+            .field("transitions", &self.transitions)
+            .finish()
+    }
+}
+
+/// [`Navigator`] strategy which delegates to the navigator of the active phase, and switches
+/// phase when a transition condition is met.
+///
+/// All the child navigators receive the `pre_loop_hook` (so they keep their messages up to
+/// date), only the active one computes the error.
+#[derive(Debug)]
+pub struct CompositeNavigator {
+    phases: Vec<Phase>,
+    active: usize,
+    phase_start_time: f32,
+    /// String messages received during the active phase
+    received_messages: Vec<String>,
+    switches: Vec<PhaseSwitchRecord>,
+    /// Time of the last loop, given by `pre_loop_hook`
+    last_time: f32,
+
+    message_client: SharedMutex<SimbaBrokerMultiClient>,
+}
+
+impl CompositeNavigator {
+    /// Relative channel used to receive the string messages of the
+    /// [`Message`](PhaseConditionConfig::Message) conditions. The full channel path use the
+    /// [`NODE`](crate::networking::channels::internal::NODE) prefix and the node name, like
+    /// `/simba/nodes/<node_name>/navigator/composite`.
+    pub const CHANNEL_NAME: &'static str = "navigator/composite";
+
+    /// Makes a [`CompositeNavigator`] from the given config, building the child navigators.
+    ///
+    /// ## Arguments
+    /// See [`make_navigator_from_config`](super::make_navigator_from_config).
+    pub fn from_config(
+        config: &CompositeNavigatorConfig,
+        plugin_api: &Option<Arc<dyn PluginAPI>>,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> SimbaResult<Self> {
+        let mut phases = Vec::new();
+        for phase in &config.phases {
+            phases.push(Phase {
+                name: phase.name.clone(),
+                navigator: super::make_navigator_from_config(
+                    &phase.navigator,
+                    plugin_api,
+                    global_config,
+                    va_factory,
+                    network,
+                    initial_time,
+                )?,
+                transitions: phase.transitions.clone(),
+            });
+        }
+        let active = config
+            .initial_phase
+            .as_ref()
+            .and_then(|name| phases.iter().position(|phase| &phase.name == name))
+            .unwrap_or(0);
+
+        let network = network.write().unwrap();
+        let key = network.make_channel(PathKey::from_str(Self::CHANNEL_NAME).unwrap());
+        let message_client = network.subscribe_to(&[key], None);
+        Ok(Self {
+            phases,
+            active,
+            phase_start_time: initial_time,
+            received_messages: Vec::new(),
+            switches: Vec::new(),
+            last_time: initial_time,
+            message_client: Arc::new(Mutex::new(message_client)),
+        })
+    }
+
+    fn is_condition_met(
+        &self,
+        condition: &PhaseConditionConfig,
+        position: Option<[f32; 2]>,
+    ) -> bool {
+        match condition {
+            PhaseConditionConfig::Timer(timer) => {
+                self.last_time - self.phase_start_time >= timer.duration
+            }
+            PhaseConditionConfig::Area(area) => {
+                position.is_some_and(|position| area.is_triggered_by(position))
+            }
+            PhaseConditionConfig::Message(message) => {
+                self.received_messages.contains(&message.content)
+            }
+        }
+    }
+
+    /// Keep the string messages received up to `time`, for the
+    /// [`Message`](PhaseConditionConfig::Message) conditions.
+    fn receive_messages(&mut self, time: f32) {
+        self.last_time = time;
+        while let Some((_, envelope)) = self.message_client.lock_recover().try_receive(time) {
+            if let Ok(content) = serde_json::from_value::<String>(envelope.message) {
+                self.received_messages.push(content);
+            }
+        }
+    }
+
+    /// Switch to the target of the first transition of the active phase whose condition is met.
+    ///
+    /// Returns the switch made, if any.
+    fn update_phase(&mut self, position: Option<[f32; 2]>) -> Option<&PhaseSwitchRecord> {
+        let next = self.phases[self.active]
+            .transitions
+            .iter()
+            .find(|transition| self.is_condition_met(&transition.condition, position))
+            .and_then(|transition| {
+                self.phases
+                    .iter()
+                    .position(|phase| phase.name == transition.to)
+            })?;
+        let switch = PhaseSwitchRecord {
+            time: self.last_time,
+            from: self.phases[self.active].name.clone(),
+            to: self.phases[next].name.clone(),
+        };
+        log::info!(
+            "Switch from phase `{}` to `{}` at {}",
+            switch.from,
+            switch.to,
+            switch.time
+        );
+        self.switches.push(switch);
+        self.active = next;
+        self.phase_start_time = self.last_time;
+        self.received_messages.clear();
+        self.switches.last()
+    }
+}
+
+impl Navigator for CompositeNavigator {
+    fn post_init(&mut self, node: &mut Node) -> SimbaResult<()> {
+        for phase in &self.phases {
+            phase.navigator.write_recover().post_init(node)?;
+        }
+        Ok(())
+    }
+
+    /// Evaluate the transitions of the active phase, and compute the error with the navigator
+    /// of the (new) active phase.
+    fn compute_error(&mut self, node: &mut Node, world_state: WorldState) -> ControllerError {
+        let position = world_state
+            .ego
+            .as_ref()
+            .map(|state| [state.pose.x, state.pose.y]);
+        if let Some(switch) = self.update_phase(position) {
+            node.annotate(
+                switch.time,
+                &format!("Phase `{}` -> `{}`", switch.from, switch.to),
+            );
+        }
+        self.phases[self.active]
+            .navigator
+            .write_recover()
+            .compute_error(node, world_state)
+    }
+
+    fn pre_loop_hook(&mut self, node: &mut Node, time: f32) {
+        self.receive_messages(time);
+        for phase in &self.phases {
+            phase.navigator.write_recover().pre_loop_hook(node, time);
+        }
+    }
+
+    /// Next time needed by the active phase: its navigator, its timers and the messages. The
+    /// inactive phases do not run, and the times already past are ignored.
+    fn next_time_step(&self) -> Option<f32> {
+        let phase = &self.phases[self.active];
+        let timers =
+            phase
+                .transitions
+                .iter()
+                .filter_map(|transition| match &transition.condition {
+                    PhaseConditionConfig::Timer(timer) => {
+                        Some(self.phase_start_time + timer.duration)
+                    }
+                    _ => None,
+                });
+        phase
+            .navigator
+            .read_recover()
+            .next_time_step()
+            .into_iter()
+            .chain(self.message_client.lock_recover().next_message_time())
+            .chain(timers)
+            .filter(|time| *time > self.last_time)
+            .reduce(f32::min)
+    }
+}
+
+impl Recordable<NavigatorRecord> for CompositeNavigator {
+    fn record(&self) -> NavigatorRecord {
+        let phase = &self.phases[self.active];
+        NavigatorRecord::Composite(CompositeNavigatorRecord {
+            phase: phase.name.clone(),
+            phase_start_time: self.phase_start_time,
+            switches: self.switches.clone(),
+            navigator: Box::new(phase.navigator.read_recover().record()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::RwLock;
+
+    use super::*;
+    use crate::{
+        constants::TIME_ROUND, networking::network::Envelope,
+        scenario::config::CircleAreaEventTriggerConfig, simulator::SimbaBroker,
+    };
+
+    fn phase(name: &str, transitions: Vec<(&str, PhaseConditionConfig)>) -> CompositePhaseConfig {
+        CompositePhaseConfig {
+            name: name.to_string(),
+            transitions: transitions
+                .into_iter()
+                .map(|(to, condition)| PhaseTransitionConfig {
+                    to: to.to_string(),
+                    condition,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn make_navigator(
+        phases: Vec<CompositePhaseConfig>,
+    ) -> (CompositeNavigator, SharedRwLock<SimbaBroker>) {
+        let broker = Arc::new(RwLock::new(SimbaBroker::new(TIME_ROUND)));
+        let network = Arc::new(RwLock::new(Network::new("robot1".to_string(), &broker)));
+        let navigator = CompositeNavigator::from_config(
+            &CompositeNavigatorConfig {
+                initial_phase: None,
+                phases,
+            },
+            &None,
+            &SimulatorConfig::default(),
+            &Arc::new(DeterministRandomVariableFactory::default()),
+            &network,
+            0.,
+        )
+        .unwrap();
+        (navigator, broker)
+    }
+
+    fn active_phase(navigator: &CompositeNavigator) -> &str {
+        &navigator.phases[navigator.active].name
+    }
+
+    #[test]
+    fn timer_condition() {
+        let timer = PhaseConditionConfig::Timer(TimerConditionConfig { duration: 2. });
+        let (mut navigator, _broker) = make_navigator(vec![
+            phase("explore", vec![("return", timer.clone())]),
+            phase("return", vec![("explore", timer)]),
+        ]);
+        assert_eq!(navigator.next_time_step(), Some(2.));
+
+        navigator.receive_messages(1.);
+        assert!(navigator.update_phase(None).is_none());
+        assert_eq!(active_phase(&navigator), "explore");
+
+        navigator.receive_messages(2.);
+        assert!(navigator.update_phase(None).is_some());
+        assert_eq!(active_phase(&navigator), "return");
+        // The timer of the new phase starts at the switch
+        assert_eq!(navigator.phase_start_time, 2.);
+        assert_eq!(navigator.next_time_step(), Some(4.));
+        navigator.receive_messages(3.);
+        assert!(navigator.update_phase(None).is_none());
+    }
+
+    #[test]
+    fn area_condition() {
+        let area = PhaseConditionConfig::Area(AreaEventTriggerConfig::Circle(
+            CircleAreaEventTriggerConfig {
+                center: (0., 0.),
+                radius: 1.,
+                inside: true,
+            },
+        ));
+        let (mut navigator, _broker) = make_navigator(vec![
+            phase("return", vec![("dock", area)]),
+            phase("dock", vec![]),
+        ]);
+        navigator.receive_messages(1.);
+        // Without estimated position, the area cannot be triggered
+        assert!(navigator.update_phase(None).is_none());
+        assert!(navigator.update_phase(Some([2., 0.])).is_none());
+        assert_eq!(active_phase(&navigator), "return");
+
+        assert!(navigator.update_phase(Some([0.5, 0.])).is_some());
+        assert_eq!(active_phase(&navigator), "dock");
+    }
+
+    #[test]
+    fn message_condition() {
+        let message = |content: &str| {
+            PhaseConditionConfig::Message(MessageConditionConfig {
+                content: content.to_string(),
+            })
+        };
+        let (mut navigator, broker) = make_navigator(vec![
+            phase("explore", vec![("return", message("return"))]),
+            phase("return", vec![("explore", message("explore"))]),
+        ]);
+        let operator = Network::new("operator".to_string(), &broker);
+        let send = |content: &str, time: f32| {
+            operator.send_to_node(
+                "robot1".to_string(),
+                PathKey::from_str(CompositeNavigator::CHANNEL_NAME).unwrap(),
+                Envelope {
+                    from: "operator".to_string(),
+                    message: serde_json::Value::String(content.to_string()),
+                    timestamp: time,
+                    ..Default::default()
+                },
+                time,
+            );
+            broker.write().unwrap().process_messages(None);
+        };
+
+        // Other contents do not trigger the transition
+        send("explore", 0.);
+        navigator.receive_messages(1.);
+        assert!(navigator.update_phase(None).is_none());
+        assert_eq!(active_phase(&navigator), "explore");
+
+        send("return", 1.);
+        navigator.receive_messages(2.);
+        assert!(navigator.update_phase(None).is_some());
+        assert_eq!(active_phase(&navigator), "return");
+        // The messages received in the previous phase are forgotten
+        assert!(navigator.update_phase(None).is_none());
+        assert_eq!(active_phase(&navigator), "return");
+    }
+
+    #[test]
+    fn no_condition_met() {
+        let (mut navigator, _broker) = make_navigator(vec![
+            phase(
+                "explore",
+                vec![(
+                    "return",
+                    PhaseConditionConfig::Timer(TimerConditionConfig { duration: 10. }),
+                )],
+            ),
+            phase("return", vec![]),
+        ]);
+        for time in [1., 2., 5.] {
+            navigator.receive_messages(time);
+            assert!(navigator.update_phase(Some([0., 0.])).is_none());
+        }
+        assert_eq!(active_phase(&navigator), "explore");
+        assert_eq!(navigator.phase_start_time, 0.);
+        assert!(navigator.switches.is_empty());
+    }
+
+    #[test]
+    fn recorded_switches() {
+        let timer = PhaseConditionConfig::Timer(TimerConditionConfig { duration: 1. });
+        let (mut navigator, _broker) = make_navigator(vec![
+            phase("wait", vec![("go", timer.clone())]),
+            phase("go", vec![("wait", timer)]),
+        ]);
+        for time in [1., 1.5, 2.] {
+            navigator.receive_messages(time);
+            navigator.update_phase(None);
+        }
+        let NavigatorRecord::Composite(record) = navigator.record() else {
+            panic!("The composite navigator should give a composite record");
+        };
+        assert_eq!(record.phase, "wait");
+        assert_eq!(record.phase_start_time, 2.);
+        assert_eq!(
+            record.switches,
+            vec![
+                PhaseSwitchRecord {
+                    time: 1.,
+                    from: "wait".to_string(),
+                    to: "go".to_string(),
+                },
+                PhaseSwitchRecord {
+                    time: 2.,
+                    from: "go".to_string(),
+                    to: "wait".to_string(),
+                },
+            ]
+        );
+        assert!(matches!(*record.navigator, NavigatorRecord::GoTo(_)));
+    }
+}
@@ -0,0 +1,735 @@
+//! Coverage (lawnmower) [`Navigator`].
+//!
+//! [`CoverageNavigator`] covers an area with a boustrophedon path: parallel sweep lines, `spacing`
+//! apart, run alternately in both directions. The area is a rectangle, a polygon, or the bounding
+//! box of the landmarks of the map ([`CoverageAreaConfig`]). The path is followed with a
+//! [`TrajectoryFollower`], for survey or inspection missions.
+//!
+//! The area is shrunk by `margin` on all its sides before the sweep, so the path keeps this
+//! distance to the slanted borders as well. Only convex polygons are accepted: a sweep line of a
+//! concave polygon could cross the parts outside of the area.
+//!
+//! When the area is too small for the margin, the path is empty and the navigator stays stopped:
+//! it asks for a null velocity, and its record is marked as `stopped`.
+
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{UIComponent, utils::string_combobox},
+    simulator::SimulatorConfig,
+    utils::enum_tools::ToVec,
+};
+
+use crate::{
+    controllers::ControllerError,
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    navigators::{
+        Navigator, NavigatorRecord,
+        trajectory::{Trajectory, TrajectoryConfig},
+        trajectory_follower::{
+            TrajectoryFollower, TrajectoryFollowerConfig, TrajectoryFollowerRecord,
+        },
+    },
+    node::Node,
+    recordable::Recordable,
+    state_estimators::WorldState,
+};
+
+use config_checker::*;
+use nalgebra::{Rotation2, Vector2};
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Rectangular area to cover.
+///
+/// Default values:
+/// - `bottom_left`: `[0.0, 0.0]`
+/// - `top_right`: `[10.0, 10.0]`
+#[config_derives]
+pub struct CoverageRectConfig {
+    /// Bottom-left corner `[x, y]` of the rectangle.
+    pub bottom_left: [f32; 2],
+    /// Top-right corner `[x, y]` of the rectangle.
+    pub top_right: [f32; 2],
+}
+
+impl Default for CoverageRectConfig {
+    fn default() -> Self {
+        Self {
+            bottom_left: [0., 0.],
+            top_right: [10., 10.],
+        }
+    }
+}
+
+impl Check for CoverageRectConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.bottom_left[0] >= self.top_right[0] || self.bottom_left[1] >= self.top_right[1] {
+            return Err(vec![format!(
+                "Bottom-left corner {:?} should be below and left of the top-right corner {:?}",
+                self.bottom_left, self.top_right
+            )]);
+        }
+        Ok(())
+    }
+}
+
+/// Polygonal area to cover.
+///
+/// Default values:
+/// - `points`: square of 10 m with its bottom-left corner at the origin
+#[config_derives]
+pub struct CoveragePolygonConfig {
+    /// Vertices `[x, y]` of the polygon, in order, in world coordinates.
+    pub points: Vec<[f32; 2]>,
+}
+
+impl Default for CoveragePolygonConfig {
+    fn default() -> Self {
+        Self {
+            points: vec![[0., 0.], [10., 0.], [10., 10.], [0., 10.]],
+        }
+    }
+}
+
+impl Check for CoveragePolygonConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.points.len() < 3 {
+            return Err(vec![format!(
+                "Coverage polygon needs at least 3 points, got {}",
+                self.points.len()
+            )]);
+        }
+        let points: Vec<Vector2<f32>> = self
+            .points
+            .iter()
+            .map(|p| Vector2::new(p[0], p[1]))
+            .collect();
+        if !is_convex(&points) {
+            return Err(vec![format!(
+                "Coverage polygon should be convex, got {:?}",
+                self.points
+            )]);
+        }
+        Ok(())
+    }
+}
+
+/// Area covered by the [`CoverageNavigator`].
+///
+/// Default value: [`CoverageAreaConfig::Rect`] with [`CoverageRectConfig::default`].
+#[config_derives]
+pub enum CoverageAreaConfig {
+    /// Axis-aligned rectangle.
+    #[check]
+    Rect(CoverageRectConfig),
+    /// Polygon.
+    #[check]
+    Polygon(CoveragePolygonConfig),
+    /// Bounding box of the landmarks of the map of the environment.
+    Map,
+}
+
+impl Default for CoverageAreaConfig {
+    fn default() -> Self {
+        Self::Rect(CoverageRectConfig::default())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CoverageAreaConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        let mut current_str = self.to_string();
+        ui.horizontal(|ui| {
+            ui.label("Area:");
+            string_combobox(
+                ui,
+                &CoverageAreaConfig::to_vec(),
+                &mut current_str,
+                format!("coverage-area-choice-{}", unique_id),
+            );
+        });
+        if current_str != self.to_string() {
+            match current_str.as_str() {
+                "Rect" => *self = Self::Rect(CoverageRectConfig::default()),
+                "Polygon" => *self = Self::Polygon(CoveragePolygonConfig::default()),
+                "Map" => *self = Self::Map,
+                _ => panic!("Where did you find this value?"),
+            };
+        }
+        match self {
+            Self::Rect(c) => {
+                ui.horizontal(|ui| {
+                    ui.label("Bottom left:");
+                    ui.add(egui::DragValue::new(&mut c.bottom_left[0]).speed(0.1));
+                    ui.add(egui::DragValue::new(&mut c.bottom_left[1]).speed(0.1));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Top right:");
+                    ui.add(egui::DragValue::new(&mut c.top_right[0]).speed(0.1));
+                    ui.add(egui::DragValue::new(&mut c.top_right[1]).speed(0.1));
+                });
+            }
+            Self::Polygon(c) => {
+                let mut to_remove = None;
+                for (i, point) in c.points.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut point[0]).speed(0.1));
+                        ui.add(egui::DragValue::new(&mut point[1]).speed(0.1));
+                        if ui.button("X").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = to_remove {
+                    c.points.remove(i);
+                }
+                if ui.button("Add point").clicked() {
+                    c.points.push(c.points.last().copied().unwrap_or([0., 0.]));
+                }
+            }
+            Self::Map => {}
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        match self {
+            Self::Rect(c) => ui.label(format!(
+                "Area: rectangle from {:?} to {:?}",
+                c.bottom_left, c.top_right
+            )),
+            Self::Polygon(c) => ui.label(format!("Area: polygon {:?}", c.points)),
+            Self::Map => ui.label("Area: map bounding box"),
+        };
+    }
+}
+
+/// Configuration of the [`CoverageNavigator`] strategy.
+///
+/// The sweep lines are `spacing` apart (e.g. the swath width of the sensor), in the direction
+/// `sweep_angle` (radians, `0` for lines along the x axis). `margin` is the distance kept between
+/// the path and the border of the area. The path is followed like a trajectory of the
+/// [`TrajectoryFollower`], with the same `forward_distance`, `target_speed`, `stop_distance` and
+/// `stop_ramp_coefficient`.
+///
+/// Default values:
+/// - `area`: `Rect` from `[0, 0]` to `[10, 10]`
+/// - `spacing`: `1.0`
+/// - `sweep_angle`: `0.0`
+/// - `margin`: `0.5`
+/// - `do_loop`: `false`
+/// - `forward_distance`: `1.0`
+/// - `target_speed`: `0.5`
+/// - `stop_distance`: `0.2`
+/// - `stop_ramp_coefficient`: `0.5`
+///
+/// # Example
+/// ```yaml
+/// navigator:
+///   type: Coverage
+///   area:
+///     type: Polygon
+///     points: [[0, 0], [20, 0], [25, 10], [0, 10]]
+///   spacing: 2.
+///   sweep_angle: 1.5708
+///   margin: 1.
+///   target_speed: 1.
+/// ```
+#[config_derives]
+pub struct CoverageNavigatorConfig {
+    /// Area to cover.
+    #[check]
+    pub area: CoverageAreaConfig,
+    /// Distance between two sweep lines, in meters.
+    pub spacing: f32,
+    /// Direction of the sweep lines, in radians.
+    pub sweep_angle: f32,
+    /// Distance between the path and the border of the area, in meters.
+    pub margin: f32,
+    /// Restart the coverage from the first sweep line after the last one.
+    pub do_loop: bool,
+    /// Forward projection distance used during map matching, in meters.
+    pub forward_distance: f32,
+    /// Target linear speed in m/s.
+    pub target_speed: f32,
+    /// Distance threshold to force stop near the end of the path, in meters.
+    pub stop_distance: f32,
+    /// Ramp coefficient applied to remaining distance when reducing speed near the end.
+    pub stop_ramp_coefficient: f32,
+}
+
+impl Default for CoverageNavigatorConfig {
+    fn default() -> Self {
+        Self {
+            area: CoverageAreaConfig::default(),
+            spacing: 1.,
+            sweep_angle: 0.,
+            margin: 0.5,
+            do_loop: false,
+            forward_distance: 1.,
+            target_speed: 0.5,
+            stop_distance: 0.2,
+            stop_ramp_coefficient: 0.5,
+        }
+    }
+}
+
+impl Check for CoverageNavigatorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.spacing <= 0. {
+            errs.push("Spacing should be strictly positive".to_string());
+        }
+        if self.margin < 0. {
+            errs.push("Margin should be positive".to_string());
+        }
+        if self.forward_distance < 0. {
+            errs.push("Forward distance should be positive".to_string());
+        }
+        if self.target_speed < 0. {
+            errs.push("Target speed should be positive".to_string());
+        }
+        if self.stop_distance < 0. {
+            errs.push("Stop distance should be positive".to_string());
+        }
+        if self.stop_ramp_coefficient < 0. {
+            errs.push("Stop ramp coefficient should be positive".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CoverageNavigatorConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Coverage")
+            .id_salt(format!("coverage-{}", unique_id))
+            .show(ui, |ui| {
+                self.area.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Spacing:");
+                    ui.add(egui::DragValue::new(&mut self.spacing).range(0.001..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Sweep angle:");
+                    ui.add(egui::DragValue::new(&mut self.sweep_angle).speed(0.01));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Margin:");
+                    ui.add(egui::DragValue::new(&mut self.margin).range(0.0..=f32::MAX));
+                });
+                ui.checkbox(&mut self.do_loop, "Loop");
+                ui.horizontal(|ui| {
+                    ui.label("Forward distance:");
+                    ui.add(egui::DragValue::new(&mut self.forward_distance).range(0.0..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Target speed:");
+                    ui.add(egui::DragValue::new(&mut self.target_speed).range(0.0..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Stop distance:");
+                    ui.add(egui::DragValue::new(&mut self.stop_distance).range(0.0..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Stop ramp coefficient:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.stop_ramp_coefficient).range(0.0..=f32::MAX),
+                    );
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Coverage")
+            .id_salt(format!("coverage-{}", unique_id))
+            .show(ui, |ui| {
+                self.area.show(ui, ctx, unique_id);
+                ui.label(format!("Spacing: {}", self.spacing));
+                ui.label(format!("Sweep angle: {}", self.sweep_angle));
+                ui.label(format!("Margin: {}", self.margin));
+                ui.label(format!("Loop: {}", self.do_loop));
+                ui.label(format!("Forward distance: {}", self.forward_distance));
+                ui.label(format!("Target speed: {}", self.target_speed));
+                ui.label(format!("Stop distance: {}", self.stop_distance));
+                ui.label(format!(
+                    "Stop ramp coefficient: {}",
+                    self.stop_ramp_coefficient
+                ));
+            });
+    }
+}
+
+/// Record of the [`CoverageNavigator`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CoverageNavigatorRecord {
+    /// Record of the following of the coverage path.
+    pub follower: TrajectoryFollowerRecord,
+    /// Number of sweep lines of the path.
+    pub sweep_lines: usize,
+    /// Length of the path, in meters.
+    pub path_length: f32,
+    /// The path is empty (area too small for the margin): the robot is asked to stop.
+    pub stopped: bool,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CoverageNavigatorRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            ui.label(format!("Sweep lines: {}", self.sweep_lines));
+            ui.label(format!("Path length: {:.2} m", self.path_length));
+            if self.stopped {
+                ui.label("Stopped: area too small for the margin");
+            }
+            self.follower.show(ui, ctx, unique_id);
+        });
+    }
+}
+
+/// Whether the `polygon` is convex. Collinear consecutive vertices are accepted.
+pub fn is_convex(polygon: &[Vector2<f32>]) -> bool {
+    let n = polygon.len();
+    let mut sign = 0.;
+    for i in 0..n {
+        let edge = polygon[(i + 1) % n] - polygon[i];
+        let next_edge = polygon[(i + 2) % n] - polygon[(i + 1) % n];
+        let cross = edge.perp(&next_edge);
+        if cross.abs() < 1e-6 {
+            continue;
+        }
+        if sign * cross < 0. {
+            return false;
+        }
+        sign = cross.signum();
+    }
+    true
+}
+
+/// Convex `polygon` with all its edges moved inward by `margin`.
+///
+/// Returns `None` when the polygon is too small for the margin.
+pub fn shrink_convex_polygon(polygon: &[Vector2<f32>], margin: f32) -> Option<Vec<Vector2<f32>>> {
+    let mut points: Vec<Vector2<f32>> = Vec::with_capacity(polygon.len());
+    for point in polygon {
+        if points
+            .last()
+            .is_none_or(|last| (point - last).norm() > 1e-6)
+        {
+            points.push(*point);
+        }
+    }
+    while points.len() > 1 && (points[0] - points[points.len() - 1]).norm() <= 1e-6 {
+        points.pop();
+    }
+    let n = points.len();
+    let area: f32 = (0..n)
+        .map(|i| points[i].perp(&points[(i + 1) % n]))
+        .sum::<f32>()
+        / 2.;
+    if n < 3 || area.abs() < 1e-6 {
+        return None;
+    }
+    if margin <= 0. {
+        return Some(points);
+    }
+    // Inward normal: on the left of the edges for a counterclockwise polygon
+    let orientation = area.signum();
+    let edges: Vec<(Vector2<f32>, Vector2<f32>)> = (0..n)
+        .map(|i| {
+            let direction = (points[(i + 1) % n] - points[i]).normalize();
+            let normal = Vector2::new(-direction.y, direction.x) * orientation;
+            (points[i] + normal * margin, direction)
+        })
+        .collect();
+    let shrunk: Vec<Vector2<f32>> = (0..n)
+        .map(|i| {
+            let (p1, d1) = edges[(i + n - 1) % n];
+            let (p2, d2) = edges[i];
+            let cross = d1.perp(&d2);
+            if cross.abs() < 1e-6 {
+                p2
+            } else {
+                p1 + d1 * (p2 - p1).perp(&d2) / cross
+            }
+        })
+        .collect();
+    // The edges keep their direction unless the margin is larger than the polygon
+    let keeps_shape = (0..n).all(|i| {
+        let edge = shrunk[(i + 1) % n] - shrunk[i];
+        edge.dot(&edges[i].1) > 1e-6
+    });
+    keeps_shape.then_some(shrunk)
+}
+
+/// Boustrophedon path covering the convex `polygon`: sweep lines in the `sweep_angle` direction,
+/// `spacing` apart, run alternately in both directions, at `margin` from the border.
+///
+/// Returns the extremities of the sweep lines, in the order to follow. The path is empty when
+/// the polygon is too small for the margin.
+pub fn boustrophedon_path(
+    polygon: &[Vector2<f32>],
+    spacing: f32,
+    sweep_angle: f32,
+    margin: f32,
+) -> Vec<Vector2<f32>> {
+    let Some(polygon) = shrink_convex_polygon(polygon, margin) else {
+        return Vec::new();
+    };
+    // Sweep lines along the x axis in the rotated frame
+    let rotation = Rotation2::new(sweep_angle);
+    let points: Vec<Vector2<f32>> = polygon.iter().map(|p| rotation.inverse() * p).collect();
+    let (min_y, max_y) = points
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+            (min.min(p.y), max.max(p.y))
+        });
+    let mut path = Vec::new();
+    let mut y = min_y;
+    while y <= max_y + 1e-6 {
+        // The first and last lines run along the border
+        let y_cut = y.clamp(min_y + 1e-4, max_y - 1e-4);
+        let intersections = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .filter(|(p1, p2)| (p1.y <= y_cut) != (p2.y <= y_cut))
+            .map(|(p1, p2)| p1.x + (y_cut - p1.y) * (p2.x - p1.x) / (p2.y - p1.y));
+        let (min_x, max_x) = intersections
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+        if min_x <= max_x {
+            let (start, end) = if path.len() % 4 == 0 {
+                (min_x, max_x)
+            } else {
+                (max_x, min_x)
+            };
+            path.push(rotation * Vector2::new(start, y));
+            path.push(rotation * Vector2::new(end, y));
+        }
+        y += spacing;
+    }
+    path
+}
+
+/// [`Navigator`] strategy covering an area with a boustrophedon path.
+#[derive(Debug)]
+pub struct CoverageNavigator {
+    config: CoverageNavigatorConfig,
+    follower: TrajectoryFollower,
+    sweep_lines: usize,
+    path_length: f32,
+    stopped: bool,
+    error: ControllerError,
+}
+
+impl CoverageNavigator {
+    /// Makes a [`CoverageNavigator`] from the given config. The path is computed at the
+    /// initialization of the node, when the map is available.
+    pub fn from_config(config: &CoverageNavigatorConfig, _initial_time: f32) -> Self {
+        Self {
+            config: config.clone(),
+            follower: TrajectoryFollower::new(),
+            sweep_lines: 0,
+            path_length: 0.,
+            stopped: false,
+            error: ControllerError::default(),
+        }
+    }
+
+    /// Vertices of the area to cover.
+    fn area_polygon(&self, node: &Node) -> SimbaResult<Vec<Vector2<f32>>> {
+        Ok(match &self.config.area {
+            CoverageAreaConfig::Rect(rect) => vec![
+                Vector2::new(rect.bottom_left[0], rect.bottom_left[1]),
+                Vector2::new(rect.top_right[0], rect.bottom_left[1]),
+                Vector2::new(rect.top_right[0], rect.top_right[1]),
+                Vector2::new(rect.bottom_left[0], rect.top_right[1]),
+            ],
+            CoverageAreaConfig::Polygon(polygon) => polygon
+                .points
+                .iter()
+                .map(|p| Vector2::new(p[0], p[1]))
+                .collect(),
+            CoverageAreaConfig::Map => {
+                let (min, max) = node
+                    .environment()
+                    .map()
+                    .landmarks
+                    .iter()
+                    .flat_map(|landmark| {
+                        let (e1, e2) = landmark.extremities();
+                        [e1.xy(), e2.xy()]
+                    })
+                    .fold(
+                        (
+                            Vector2::repeat(f32::INFINITY),
+                            Vector2::repeat(f32::NEG_INFINITY),
+                        ),
+                        |(min, max), p| (min.inf(&p), max.sup(&p)),
+                    );
+                if min.x > max.x {
+                    return Err(SimbaError::new(
+                        SimbaErrorTypes::ConfigError,
+                        "Coverage of the map area requires a map with landmarks".to_string(),
+                    ));
+                }
+                vec![
+                    min,
+                    Vector2::new(max.x, min.y),
+                    max,
+                    Vector2::new(min.x, max.y),
+                ]
+            }
+        })
+    }
+}
+
+impl Navigator for CoverageNavigator {
+    fn post_init(&mut self, node: &mut Node) -> SimbaResult<()> {
+        let path = boustrophedon_path(
+            &self.area_polygon(node)?,
+            self.config.spacing,
+            self.config.sweep_angle,
+            self.config.margin,
+        );
+        if path.is_empty() {
+            log::warn!(
+                "Coverage area of {} is too small for its margin, the robot will not move",
+                node.name()
+            );
+            self.stopped = true;
+            return Ok(());
+        }
+        self.sweep_lines = path.len() / 2;
+        self.path_length = path.windows(2).map(|w| (w[1] - w[0]).norm()).sum();
+        let trajectory = Trajectory::from_config(&TrajectoryConfig {
+            point_list: path.iter().map(|p| vec![p.x, p.y]).collect(),
+            do_loop: self.config.do_loop,
+        });
+        self.follower = TrajectoryFollower::from_trajectory(
+            trajectory,
+            &TrajectoryFollowerConfig {
+                forward_distance: self.config.forward_distance,
+                target_speed: self.config.target_speed,
+                stop_distance: self.config.stop_distance,
+                stop_ramp_coefficient: self.config.stop_ramp_coefficient,
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    fn compute_error(&mut self, node: &mut Node, world_state: WorldState) -> ControllerError {
+        if self.stopped {
+            let speed = world_state
+                .ego
+                .map_or(0., |state| state.velocity.fixed_rows::<2>(0).norm());
+            self.error = ControllerError {
+                velocity: -speed,
+                ..Default::default()
+            };
+            return self.error.clone();
+        }
+        self.follower.compute_error(node, world_state)
+    }
+
+    fn pre_loop_hook(&mut self, node: &mut Node, time: f32) {
+        self.follower.pre_loop_hook(node, time);
+    }
+}
+
+impl Recordable<NavigatorRecord> for CoverageNavigator {
+    fn record(&self) -> NavigatorRecord {
+        let NavigatorRecord::TrajectoryFollower(mut follower) = self.follower.record() else {
+            unreachable!("TrajectoryFollower always gives a TrajectoryFollower record");
+        };
+        if self.stopped {
+            follower.error = self.error.clone();
+        }
+        NavigatorRecord::Coverage(CoverageNavigatorRecord {
+            follower,
+            sweep_lines: self.sweep_lines,
+            path_length: self.path_length,
+            stopped: self.stopped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle_path() {
+        let rectangle = [
+            Vector2::new(0., 0.),
+            Vector2::new(10., 0.),
+            Vector2::new(10., 4.),
+            Vector2::new(0., 4.),
+        ];
+        let path = boustrophedon_path(&rectangle, 1., 0., 0.5);
+        assert_eq!(path.len(), 8);
+        assert_eq!(path[0], Vector2::new(0.5, 0.5));
+        assert_eq!(path[1], Vector2::new(9.5, 0.5));
+        assert_eq!(path[2], Vector2::new(9.5, 1.5));
+        assert_eq!(path[3], Vector2::new(0.5, 1.5));
+        assert_eq!(path[7], Vector2::new(0.5, 3.5));
+
+        // Vertical sweep lines
+        let path = boustrophedon_path(&rectangle, 1., std::f32::consts::FRAC_PI_2, 0.5);
+        assert_eq!(path.len(), 20);
+        assert!((path[0] - Vector2::new(9.5, 0.5)).norm() < 1e-4);
+        assert!((path[1] - Vector2::new(9.5, 3.5)).norm() < 1e-4);
+
+        // Too small for the margin
+        assert!(boustrophedon_path(&rectangle, 1., 0., 2.5).is_empty());
+    }
+
+    #[test]
+    fn slanted_borders_keep_the_margin() {
+        let triangle = [
+            Vector2::new(0., 0.),
+            Vector2::new(10., 0.),
+            Vector2::new(0., 10.),
+        ];
+        let path = boustrophedon_path(&triangle, 1., 0., 1.);
+        assert!(!path.is_empty());
+        let hypotenuse_distance = |p: &Vector2<f32>| (10. - p.x - p.y) / 2f32.sqrt();
+        for point in &path {
+            assert!(point.x >= 1. - 1e-4 && point.y >= 1. - 1e-4, "{point}");
+            assert!(hypotenuse_distance(point) >= 1. - 1e-4, "{point}");
+        }
+    }
+
+    #[test]
+    fn concave_polygon_rejected() {
+        let config = CoveragePolygonConfig {
+            points: vec![[0., 0.], [10., 0.], [10., 10.], [5., 2.], [0., 10.]],
+        };
+        assert!(config.do_check().is_err());
+        assert!(CoveragePolygonConfig::default().do_check().is_ok());
+    }
+}
@@ -0,0 +1,597 @@
+//! Frontier-based exploration [`Navigator`].
+//!
+//! [`FrontierExplorer`] explores the environment using the occupancy grid of the world state
+//! estimated by the state estimator ([`WorldState::occupancy_grid`]). The cells of the grid are
+//! classified from their occupancy value: free (below `free_threshold`), occupied (above
+//! `occupied_threshold`) or unknown (in between, e.g. 0.5 for an occupancy probability).
+//!
+//! A frontier is a connected group of free cells next to unknown cells. Periodically, and when the
+//! current target is reached, the frontiers are extracted, one is selected
+//! ([`FrontierSelection`]) and a path to it is planned on the free cells (A* on the grid). The
+//! robot follows the path like the [`GoTo`](super::go_to::GoTo) navigator goes to its target
+//! point. When no reachable frontier remains, the exploration is finished: the robot stops and the
+//! frontiers are no longer extracted.
+//!
+//! The explored area (free and occupied cells) is recorded at each step, to compare exploration
+//! strategies over time.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{UIComponent, utils::string_combobox},
+    simulator::SimulatorConfig,
+    utils::enum_tools::ToVec,
+};
+
+use crate::{
+    controllers::ControllerError,
+    navigators::{Navigator, NavigatorRecord},
+    node::Node,
+    recordable::Recordable,
+    state_estimators::WorldState,
+    utils::{geometry::smallest_theta_diff, grid_path_planning, occupancy_grid::OccupancyGrid},
+};
+
+use config_checker::*;
+use nalgebra::Vector2;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Choice of the frontier to explore.
+#[config_derives]
+pub enum FrontierSelection {
+    /// Closest frontier to the robot.
+    Nearest,
+    /// Frontier with the most unknown cells around it (within `gain_radius`), per meter to go.
+    InformationGain,
+}
+
+impl Default for FrontierSelection {
+    fn default() -> Self {
+        Self::Nearest
+    }
+}
+
+/// Configuration of the [`FrontierExplorer`] strategy.
+///
+/// ```yaml
+/// navigator:
+///   type: FrontierExplorer
+///   selection:
+///     type: Nearest
+///   free_threshold: 0.35
+///   occupied_threshold: 0.65
+///   min_frontier_size: 3
+///   gain_radius: 2.
+///   replan_period: 1.
+///   goal_tolerance: 0.5
+///   target_speed: 0.5
+/// ```
+#[config_derives]
+pub struct FrontierExplorerConfig {
+    /// Choice of the frontier to explore.
+    ///
+    /// Default: `Nearest`.
+    pub selection: FrontierSelection,
+    /// Cells with an occupancy value below this threshold are free.
+    ///
+    /// Default: `0.35`.
+    pub free_threshold: f32,
+    /// Cells with an occupancy value above this threshold are occupied. The cells between the two
+    /// thresholds are unknown.
+    ///
+    /// Default: `0.65`.
+    pub occupied_threshold: f32,
+    /// Minimal number of cells of a frontier, smaller frontiers are ignored.
+    ///
+    /// Default: `3`.
+    pub min_frontier_size: usize,
+    /// Radius around a frontier where the unknown cells are counted as its information gain, in
+    /// meters. Only used by the `InformationGain` selection.
+    ///
+    /// Default: `2`.
+    pub gain_radius: f32,
+    /// Period of the frontier extraction, in seconds. The frontiers are also extracted when the
+    /// target is reached.
+    ///
+    /// Default: `1`.
+    pub replan_period: f32,
+    /// Distance under which a point of the path is reached, in meters.
+    ///
+    /// Default: `0.5`.
+    pub goal_tolerance: f32,
+    /// Target linear speed in m/s.
+    ///
+    /// Default: `0.5`.
+    pub target_speed: f32,
+}
+
+impl Default for FrontierExplorerConfig {
+    fn default() -> Self {
+        Self {
+            selection: FrontierSelection::default(),
+            free_threshold: 0.35,
+            occupied_threshold: 0.65,
+            min_frontier_size: 3,
+            gain_radius: 2.,
+            replan_period: 1.,
+            goal_tolerance: 0.5,
+            target_speed: 0.5,
+        }
+    }
+}
+
+impl Check for FrontierExplorerConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.free_threshold > self.occupied_threshold {
+            errs.push(format!(
+                "Free threshold ({}) should be lower than the occupied threshold ({})",
+                self.free_threshold, self.occupied_threshold
+            ));
+        }
+        if self.min_frontier_size == 0 {
+            errs.push("Minimal frontier size should be at least 1".to_string());
+        }
+        if self.gain_radius <= 0. {
+            errs.push("Gain radius should be strictly positive".to_string());
+        }
+        if self.replan_period <= 0. {
+            errs.push("Replan period should be strictly positive".to_string());
+        }
+        if self.goal_tolerance <= 0. {
+            errs.push("Goal tolerance should be strictly positive".to_string());
+        }
+        if self.target_speed < 0. {
+            errs.push("Target speed should be positive".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for FrontierExplorerConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Frontier Explorer")
+            .id_salt(format!("frontier-explorer-{}", unique_id))
+            .show(ui, |ui| {
+                let mut current_str = self.selection.to_string();
+                ui.horizontal(|ui| {
+                    ui.label("Selection: ");
+                    string_combobox(
+                        ui,
+                        &FrontierSelection::to_vec(),
+                        &mut current_str,
+                        format!("frontier-explorer-selection-choice-{}", unique_id),
+                    );
+                });
+                if current_str != self.selection.to_string() {
+                    match current_str.as_str() {
+                        "Nearest" => self.selection = FrontierSelection::Nearest,
+                        "InformationGain" => self.selection = FrontierSelection::InformationGain,
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Free / occupied thresholds:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.free_threshold)
+                            .speed(0.01)
+                            .range(0.0..=self.occupied_threshold),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut self.occupied_threshold)
+                            .speed(0.01)
+                            .range(self.free_threshold..=f32::MAX),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Minimal frontier size:");
+                    ui.add(egui::DragValue::new(&mut self.min_frontier_size).range(1..=usize::MAX));
+                });
+                if let FrontierSelection::InformationGain = self.selection {
+                    ui.horizontal(|ui| {
+                        ui.label("Gain radius:");
+                        ui.add(egui::DragValue::new(&mut self.gain_radius).range(0.001..=f32::MAX));
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Replan period:");
+                    ui.add(egui::DragValue::new(&mut self.replan_period).range(0.001..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Goal tolerance:");
+                    ui.add(egui::DragValue::new(&mut self.goal_tolerance).range(0.001..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Target speed:");
+                    ui.add(egui::DragValue::new(&mut self.target_speed).range(0.0..=f32::MAX));
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Frontier Explorer")
+            .id_salt(format!("frontier-explorer-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Selection: {}", self.selection));
+                ui.label(format!(
+                    "Free / occupied thresholds: {} / {}",
+                    self.free_threshold, self.occupied_threshold
+                ));
+                ui.label(format!("Minimal frontier size: {}", self.min_frontier_size));
+                if let FrontierSelection::InformationGain = self.selection {
+                    ui.label(format!("Gain radius: {}", self.gain_radius));
+                }
+                ui.label(format!("Replan period: {}", self.replan_period));
+                ui.label(format!("Goal tolerance: {}", self.goal_tolerance));
+                ui.label(format!("Target speed: {}", self.target_speed));
+            });
+    }
+}
+
+/// Record of the [`FrontierExplorer`] navigator.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FrontierExplorerRecord {
+    /// Current error
+    pub error: ControllerError,
+    /// Frontier cell targeted, in world coordinates `[x, y]`.
+    pub target: Option<[f32; 2]>,
+    /// Number of frontiers found at the last extraction.
+    pub frontiers: usize,
+    /// Number of targets reached since the start (end of the path).
+    #[serde(default)]
+    pub targets_reached: usize,
+    /// Area of the known (free or occupied) cells of the grid, in square meters.
+    pub explored_area: f32,
+    /// Whether the exploration is finished: no reachable frontier remains.
+    pub finished: bool,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for FrontierExplorerRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("Error").show(ui, |ui| {
+                self.error.show(ui, ctx, unique_id);
+            });
+            match &self.target {
+                Some(target) => ui.label(format!("Target: ({}, {})", target[0], target[1])),
+                None => ui.label("Target: None"),
+            };
+            ui.label(format!("Frontiers: {}", self.frontiers));
+            ui.label(format!("Targets reached: {}", self.targets_reached));
+            ui.label(format!("Explored area: {:.2} m²", self.explored_area));
+            ui.label(format!("Finished: {}", self.finished));
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CellState {
+    Free,
+    Occupied,
+    Unknown,
+}
+
+/// [`Navigator`] strategy exploring the frontiers of the estimated occupancy grid.
+#[derive(Debug)]
+pub struct FrontierExplorer {
+    config: FrontierExplorerConfig,
+    /// Remaining points of the path to the target, the next one first.
+    path: VecDeque<Vector2<f32>>,
+    target: Option<Vector2<f32>>,
+    frontiers: usize,
+    targets_reached: usize,
+    explored_area: f32,
+    finished: bool,
+    next_replan: f32,
+    last_time: f32,
+    /// Last error, stored to make the [`FrontierExplorerRecord`]
+    error: ControllerError,
+}
+
+impl FrontierExplorer {
+    /// Makes a [`FrontierExplorer`] from the given config.
+    pub fn from_config(config: &FrontierExplorerConfig, initial_time: f32) -> Self {
+        Self {
+            config: config.clone(),
+            path: VecDeque::new(),
+            target: None,
+            frontiers: 0,
+            targets_reached: 0,
+            explored_area: 0.,
+            finished: false,
+            next_replan: initial_time,
+            last_time: initial_time,
+            error: ControllerError::default(),
+        }
+    }
+
+    fn cell_state(&self, grid: &OccupancyGrid, row: usize, col: usize) -> CellState {
+        let value = *grid.get_idx(row, col).unwrap();
+        if value < self.config.free_threshold {
+            CellState::Free
+        } else if value > self.config.occupied_threshold {
+            CellState::Occupied
+        } else {
+            CellState::Unknown
+        }
+    }
+
+    /// Frontiers of the grid, as their target cell (the cell closest to the centroid of the
+    /// frontier) and their number of cells.
+    fn find_frontiers(&self, grid: &OccupancyGrid) -> Vec<((usize, usize), usize)> {
+        let nb_cols = grid.nb_cols();
+        let is_frontier: Vec<bool> = (0..grid.nb_rows() * nb_cols)
+            .map(|i| {
+                let cell = (i / nb_cols, i % nb_cols);
+                self.cell_state(grid, cell.0, cell.1) == CellState::Free
+                    && grid_path_planning::neighbors(grid, cell, false)
+                        .any(|(r, c)| self.cell_state(grid, r, c) == CellState::Unknown)
+            })
+            .collect();
+        let mut visited = vec![false; is_frontier.len()];
+        let mut frontiers = Vec::new();
+        for start in 0..is_frontier.len() {
+            if !is_frontier[start] || visited[start] {
+                continue;
+            }
+            // Group the connected frontier cells
+            visited[start] = true;
+            let mut cells = vec![(start / nb_cols, start % nb_cols)];
+            let mut i = 0;
+            while i < cells.len() {
+                for (r, c) in grid_path_planning::neighbors(grid, cells[i], true) {
+                    if is_frontier[r * nb_cols + c] && !visited[r * nb_cols + c] {
+                        visited[r * nb_cols + c] = true;
+                        cells.push((r, c));
+                    }
+                }
+                i += 1;
+            }
+            if cells.len() < self.config.min_frontier_size {
+                continue;
+            }
+            let (sum_row, sum_col) = cells.iter().fold((0., 0.), |(sr, sc), (r, c)| {
+                (sr + *r as f32, sc + *c as f32)
+            });
+            let centroid = (sum_row / cells.len() as f32, sum_col / cells.len() as f32);
+            let target = *cells
+                .iter()
+                .min_by(|a, b| {
+                    let d = |(r, c): &&(usize, usize)| {
+                        (*r as f32 - centroid.0).powi(2) + (*c as f32 - centroid.1).powi(2)
+                    };
+                    d(a).total_cmp(&d(b))
+                })
+                .unwrap();
+            frontiers.push((target, cells.len()));
+        }
+        frontiers
+    }
+
+    /// Number of unknown cells within `gain_radius` of the center of the `cell`.
+    fn information_gain(&self, grid: &OccupancyGrid, cell: (usize, usize)) -> usize {
+        let position = grid.idx_to_pos(cell.0, cell.1).unwrap();
+        // Only the cells of the window around the cell can be within the radius
+        let half_rows = (self.config.gain_radius / grid.cell_height()).ceil() as usize;
+        let half_cols = (self.config.gain_radius / grid.cell_width()).ceil() as usize;
+        let rows = cell.0.saturating_sub(half_rows)..(cell.0 + half_rows + 1).min(grid.nb_rows());
+        let cols = cell.1.saturating_sub(half_cols)..(cell.1 + half_cols + 1).min(grid.nb_cols());
+        rows.flat_map(|row| cols.clone().map(move |col| (row, col)))
+            .filter(|(row, col)| {
+                self.cell_state(grid, *row, *col) == CellState::Unknown
+                    && (grid.idx_to_pos(*row, *col).unwrap() - position).norm()
+                        <= self.config.gain_radius
+            })
+            .count()
+    }
+
+    /// Shortest path on the free cells from `start` to the `goal` cell, as the centers of the
+    /// cells after the start one. The start cell is allowed whatever its state.
+    fn plan_path(
+        &self,
+        grid: &OccupancyGrid,
+        start: Vector2<f32>,
+        goal: (usize, usize),
+    ) -> Option<Vec<Vector2<f32>>> {
+        grid_path_planning::plan_path(grid, start, goal, false, |row, col| {
+            self.cell_state(grid, row, col) == CellState::Free
+        })
+    }
+
+    /// Extract the frontiers of the grid and plan the path to the selected one.
+    fn replan(&mut self, grid: &OccupancyGrid, position: Vector2<f32>) {
+        let frontiers = self.find_frontiers(grid);
+        self.frontiers = frontiers.len();
+        let mut candidates: Vec<(f32, (usize, usize))> = frontiers
+            .into_iter()
+            .map(|(cell, _)| {
+                let target = grid.idx_to_pos(cell.0, cell.1).unwrap();
+                let distance = (target - position).norm();
+                let utility = match self.config.selection {
+                    FrontierSelection::Nearest => -distance,
+                    FrontierSelection::InformationGain => {
+                        self.information_gain(grid, cell) as f32 / (1. + distance)
+                    }
+                };
+                (utility, cell)
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        // Best frontier with a path
+        let planned = candidates.into_iter().find_map(|(_, cell)| {
+            self.plan_path(grid, position, cell)
+                .map(|path| (grid.idx_to_pos(cell.0, cell.1).unwrap(), path))
+        });
+        match planned {
+            Some((target, path)) => {
+                self.target = Some(target);
+                self.path = path.into();
+            }
+            None => {
+                log::info!("Exploration finished at {}", self.last_time);
+                self.target = None;
+                self.path.clear();
+                self.finished = true;
+            }
+        }
+    }
+
+    /// Area of the known cells of the grid.
+    fn explored_area(&self, grid: &OccupancyGrid) -> f32 {
+        let known = (0..grid.nb_rows())
+            .flat_map(|row| (0..grid.nb_cols()).map(move |col| (row, col)))
+            .filter(|(row, col)| self.cell_state(grid, *row, *col) != CellState::Unknown)
+            .count();
+        known as f32 * grid.cell_width() * grid.cell_height()
+    }
+}
+
+impl Navigator for FrontierExplorer {
+    /// Compute the error between the given `state` and the next point of the path.
+    fn compute_error(&mut self, _robot: &mut Node, world_state: WorldState) -> ControllerError {
+        if world_state.ego.is_none() {
+            panic!("StateEstimator should provide an ego estimate for FrontierExplorer navigator.")
+        }
+        let state = world_state.ego.unwrap().theta_modulo();
+        let position = Vector2::new(state.pose.x, state.pose.y);
+        let speed = state.velocity.fixed_rows::<2>(0).norm();
+
+        let had_path = !self.path.is_empty();
+        while self
+            .path
+            .front()
+            .is_some_and(|point| (point - position).norm() < self.config.goal_tolerance)
+        {
+            self.path.pop_front();
+        }
+        if had_path && self.path.is_empty() {
+            self.targets_reached += 1;
+        }
+        if let Some(grid) = &world_state.occupancy_grid {
+            self.explored_area = self.explored_area(grid);
+            // No more replanning once the exploration is finished
+            if !self.finished && (self.path.is_empty() || self.last_time >= self.next_replan) {
+                self.replan(grid, position);
+                self.next_replan = self.last_time + self.config.replan_period;
+            }
+        }
+
+        let Some(next_point) = self.path.front().copied() else {
+            self.error = ControllerError {
+                velocity: -speed,
+                ..Default::default()
+            };
+            return self.error.clone();
+        };
+        self.error.theta = smallest_theta_diff(
+            (next_point.y - position.y).atan2(next_point.x - position.x),
+            state.pose.z,
+        );
+        // Next point projected in the robot frame
+        let relative_point = nalgebra::Rotation2::new(-state.pose.z) * (next_point - position);
+        self.error.longitudinal = relative_point.x;
+        self.error.lateral = relative_point.y;
+        self.error.velocity = self.config.target_speed - speed;
+
+        self.error.clone()
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, time: f32) {
+        self.last_time = time;
+    }
+}
+
+impl Recordable<NavigatorRecord> for FrontierExplorer {
+    fn record(&self) -> NavigatorRecord {
+        NavigatorRecord::FrontierExplorer(FrontierExplorerRecord {
+            error: self.error.clone(),
+            target: self.target.map(|target| [target.x, target.y]),
+            frontiers: self.frontiers,
+            targets_reached: self.targets_reached,
+            explored_area: self.explored_area,
+            finished: self.finished,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    #[test]
+    fn frontiers_and_path() {
+        // 5x5 grid of 1 m cells centered on (0, 0): known left part, wall in the middle column
+        // with a gap on the top row, unknown right part
+        let mut grid = OccupancyGrid::new(Vector3::zeros(), 1., 1., 5, 5);
+        for row in 0..5 {
+            for col in 0..5 {
+                *grid.get_idx_mut(row, col).unwrap() = match col {
+                    0 | 1 => 0.,
+                    2 if row == 4 => 0.,
+                    2 => 1.,
+                    _ => 0.5,
+                };
+            }
+        }
+        let explorer = FrontierExplorer::from_config(
+            &FrontierExplorerConfig {
+                min_frontier_size: 1,
+                ..Default::default()
+            },
+            0.,
+        );
+        assert_eq!(explorer.find_frontiers(&grid), vec![((4, 2), 1)]);
+        assert_eq!(explorer.explored_area(&grid), 15.);
+
+        // From the bottom left cell, around the wall through the gap
+        let path = explorer
+            .plan_path(&grid, Vector2::new(-2., -2.), (4, 2))
+            .unwrap();
+        assert_eq!(path.last(), Some(&Vector2::new(0., 2.)));
+        assert!(path.iter().all(|point| point.x < 0. || point.y > 1.));
+    }
+
+    #[test]
+    fn information_gain_window() {
+        // 20x20 grid of 0.5 m cells, unknown on the right half
+        let mut grid = OccupancyGrid::new(Vector3::zeros(), 0.5, 0.5, 20, 20);
+        for row in 0..20 {
+            for col in 0..20 {
+                *grid.get_idx_mut(row, col).unwrap() = if col < 10 { 0. } else { 0.5 };
+            }
+        }
+        let explorer = FrontierExplorer::from_config(
+            &FrontierExplorerConfig {
+                gain_radius: 1.2,
+                ..Default::default()
+            },
+            0.,
+        );
+        for cell in [(0, 0), (10, 9), (19, 19), (5, 12)] {
+            let position = grid.idx_to_pos(cell.0, cell.1).unwrap();
+            let expected = (0..20)
+                .flat_map(|row| (0..20).map(move |col| (row, col)))
+                .filter(|(row, col)| {
+                    *col >= 10 && (grid.idx_to_pos(*row, *col).unwrap() - position).norm() <= 1.2
+                })
+                .count();
+            assert_eq!(explorer.information_gain(&grid, cell), expected, "{cell:?}");
+        }
+        assert_eq!(explorer.information_gain(&grid, (0, 0)), 0);
+        assert!(explorer.information_gain(&grid, (10, 9)) > 0);
+    }
+}
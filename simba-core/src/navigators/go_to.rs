@@ -4,6 +4,10 @@
 //! startup through [`GoToConfig`] and updated at runtime using [`GoToMessage`] on
 //! [`GoTo::CHANNEL_NAME`].
 //!
+//! A queue of goals ([`GoToGoal`]) can also be given in the configuration, for simple waypoint
+//! missions: each goal is reached in turn, with its own tolerance and dwell time, and the
+//! goal-reached events of each time step are recorded in [`GoToRecord::reached_goals`].
+//!
 //! The controller computes heading, longitudinal, lateral, and velocity errors from the current
 //! ego state and the active target point.
 
@@ -53,10 +57,66 @@ impl GoToMessage {
     }
 }
 
+/// Goal of the [`GoTo`] goal queue.
+#[config_derives]
+pub struct GoToGoal {
+    /// Goal point in world coordinates `[x, y]`.
+    pub point: [f32; 2],
+    /// Distance to the goal under which it is reached, in meters. `None` to use the `stop_distance`
+    /// of the navigator.
+    ///
+    /// Default: `None`.
+    pub tolerance: Option<f32>,
+    /// Time to wait at the goal before going to the next one, in seconds.
+    ///
+    /// Default: `0`.
+    pub dwell_time: f32,
+}
+
+impl Check for GoToGoal {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if let Some(tolerance) = self.tolerance
+            && tolerance <= 0.
+        {
+            errs.push("Goal tolerance should be strictly positive".to_string());
+        }
+        if self.dwell_time < 0. {
+            errs.push("Goal dwell time should be positive".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+impl Default for GoToGoal {
+    fn default() -> Self {
+        Self {
+            point: [0., 0.],
+            tolerance: None,
+            dwell_time: 0.,
+        }
+    }
+}
+
 /// Configuration of the [`GoTo`] strategy.
 ///
 /// The target point can be set at startup through the `target_point` field, and updated at runtime by sending a [`GoToMessage`] on the [`GoTo::CHANNEL_NAME`] channel. If no target point is set, the navigator will not move the robot.
 ///
+/// Instead of a single target point, an ordered queue of `goals` can be given. When a goal is
+/// reached (closer than its tolerance), the robot waits for the goal dwell time and goes to the
+/// next goal. The robot stays at the last goal. A [`GoToMessage`] replaces the current goal and
+/// clears the remaining queue.
+///
+/// ```yaml
+/// navigator:
+///   type: GoTo
+///   goals:
+///   - point: [5.0, 0.0]
+///     tolerance: 0.5
+///     dwell_time: 2.0
+///   - point: [5.0, 5.0]
+/// ```
+///
 /// The target speed is reduced when the robot gets closer to the target point, starting from `stop_distance` meters to the target, using a ramp coefficient of `stop_ramp_coefficient`.
 ///
 /// To reach the target point, the navigator computes the error to the target point in the robot frame:
@@ -79,6 +139,11 @@ pub struct GoToConfig {
     ///
     /// Default: `0.5`.
     pub stop_ramp_coefficient: f32,
+    /// Ordered queue of goals. `target_point` should be `None` if goals are given.
+    ///
+    /// Default: empty.
+    #[check]
+    pub goals: Vec<GoToGoal>,
 }
 
 impl Check for GoToConfig {
@@ -93,6 +158,19 @@ impl Check for GoToConfig {
         if self.stop_ramp_coefficient < 0. {
             errs.push("Stop ramp coefficient should be positive".to_string());
         }
+        if self.target_point.is_some() && !self.goals.is_empty() {
+            errs.push("Target point and goals cannot be both set".to_string());
+        }
+        for (i, goal) in self.goals.iter().enumerate() {
+            if let Some(tolerance) = goal.tolerance
+                && tolerance < self.stop_distance
+            {
+                errs.push(format!(
+                    "Tolerance of goal {i} ({tolerance}) should be larger than the stop distance ({}), else the robot stops before reaching it",
+                    self.stop_distance
+                ));
+            }
+        }
         if errs.is_empty() { Ok(()) } else { Err(errs) }
     }
 }
@@ -104,6 +182,7 @@ impl Default for GoToConfig {
             target_speed: 0.5,
             stop_distance: 0.2,
             stop_ramp_coefficient: 0.5,
+            goals: Vec::new(),
         }
     }
 }
@@ -172,6 +251,37 @@ impl UIComponent for GoToConfig {
                     }
                     ui.add(egui::DragValue::new(&mut self.stop_ramp_coefficient).max_decimals(10));
                 });
+
+                ui.label("Goals:");
+                let mut to_remove = None;
+                for (i, goal) in self.goals.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{i}: ("));
+                        ui.add(egui::DragValue::new(&mut goal.point[0]).fixed_decimals(2));
+                        ui.label(", ");
+                        ui.add(egui::DragValue::new(&mut goal.point[1]).fixed_decimals(2));
+                        ui.label(") Tolerance:");
+                        if let Some(tolerance) = &mut goal.tolerance {
+                            ui.add(egui::DragValue::new(tolerance).range(0.001..=f32::MAX));
+                            if ui.button("X").clicked() {
+                                goal.tolerance = None;
+                            }
+                        } else if ui.button("+").clicked() {
+                            goal.tolerance = Some(self.stop_distance.max(0.001));
+                        }
+                        ui.label("Dwell time:");
+                        ui.add(egui::DragValue::new(&mut goal.dwell_time).range(0.0..=f32::MAX));
+                        if ui.button("-").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = to_remove {
+                    self.goals.remove(i);
+                }
+                if ui.button("Add goal").clicked() {
+                    self.goals.push(GoToGoal::default());
+                }
             });
     }
 
@@ -202,10 +312,32 @@ impl UIComponent for GoToConfig {
                         self.stop_ramp_coefficient
                     ));
                 });
+
+                for (i, goal) in self.goals.iter().enumerate() {
+                    ui.label(format!(
+                        "Goal {i}: ({}, {}), tolerance: {}, dwell time: {} s",
+                        goal.point[0],
+                        goal.point[1],
+                        goal.tolerance
+                            .map_or("stop distance".to_string(), |t| t.to_string()),
+                        goal.dwell_time
+                    ));
+                }
             });
     }
 }
 
+/// Goal of the queue reached by the [`GoTo`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GoalReachedRecord {
+    /// Index of the goal in the configured queue.
+    pub index: usize,
+    /// Goal point in world coordinates `[x, y]`.
+    pub point: [f32; 2],
+    /// Time when the goal was reached.
+    pub time: f32,
+}
+
 /// Record of the [`GoTo`].
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct GoToRecord {
@@ -213,6 +345,20 @@ pub struct GoToRecord {
     pub error: ControllerError,
     /// Current target point in world coordinates `[x, y]`.
     pub current_point: Option<[f32; 2]>,
+    /// Index of the current goal in the configured queue, `None` if the queue is not followed
+    /// (no queue, finished, or replaced by a message).
+    #[serde(default)]
+    pub current_goal: Option<usize>,
+    /// Goals of the queue reached during the time step.
+    #[serde(default)]
+    pub reached_goals: Vec<GoalReachedRecord>,
+    /// Number of goals of the queue reached since the start.
+    #[serde(default)]
+    pub goals_reached: usize,
+    /// Number of targets reached outside the goal queue (initial `target_point` or
+    /// [`GoToMessage`]), closer than `stop_distance`.
+    #[serde(default)]
+    pub targets_reached: usize,
 }
 
 #[cfg(feature = "gui")]
@@ -231,6 +377,18 @@ impl UIComponent for GoToRecord {
             } else {
                 ui.label("Current point: None".to_string());
             }
+
+            if let Some(current_goal) = self.current_goal {
+                ui.label(format!("Current goal: {current_goal}"));
+            }
+            for reached in &self.reached_goals {
+                ui.label(format!(
+                    "Goal {} ({}, {}) reached at {} s",
+                    reached.index, reached.point[0], reached.point[1], reached.time
+                ));
+            }
+            ui.label(format!("Goals reached: {}", self.goals_reached));
+            ui.label(format!("Targets reached: {}", self.targets_reached));
         });
     }
 }
@@ -249,6 +407,22 @@ pub struct GoTo {
     stop_distance: f32,
     /// Coefficient of the target velocity, multiplied by the remaining distance
     stop_ramp_coefficient: f32,
+    /// Configured speed, restored when going to a new target
+    nominal_speed: f32,
+    /// Configured goal queue
+    goals: Vec<GoToGoal>,
+    /// Index of the current goal in `goals`, `goals.len()` if the queue is not followed
+    goal_index: usize,
+    /// End of the dwell at the current goal, `Some` once the goal is reached
+    dwell_until: Option<f32>,
+    /// Goals reached during the current time step
+    reached_goals: Vec<GoalReachedRecord>,
+    goals_reached: usize,
+    /// Whether the current target point is reached, to count it once
+    target_reached: bool,
+    targets_reached: usize,
+    /// Time of the last loop, given by `pre_loop_hook`
+    last_time: f32,
 
     message_client: SharedMutex<SimbaBrokerMultiClient>,
 }
@@ -275,12 +449,62 @@ impl GoTo {
         Self {
             target_speed: config.target_speed,
             error: ControllerError::default(),
-            current_point: config.target_point,
+            current_point: config
+                .target_point
+                .or(config.goals.first().map(|goal| goal.point)),
             stop_distance: config.stop_distance,
             stop_ramp_coefficient: config.stop_ramp_coefficient,
+            nominal_speed: config.target_speed,
+            goals: config.goals.clone(),
+            goal_index: 0,
+            dwell_until: None,
+            reached_goals: Vec::new(),
+            goals_reached: 0,
+            target_reached: false,
+            targets_reached: 0,
+            last_time: 0.,
             message_client: Arc::new(Mutex::new(message_client)),
         }
     }
+
+    /// Go to a new target point, at the configured speed.
+    fn set_target(&mut self, point: Option<[f32; 2]>) {
+        self.current_point = point;
+        self.target_reached = false;
+        self.target_speed = self.nominal_speed;
+    }
+
+    /// Check whether the current goal of the queue is reached, and go to the next one after
+    /// the dwell time.
+    fn update_goal_queue(&mut self, node: &Node, distance_to_goal: f32) {
+        let Some(goal) = self.goals.get(self.goal_index) else {
+            return;
+        };
+        if self.dwell_until.is_none()
+            && distance_to_goal < goal.tolerance.unwrap_or(self.stop_distance)
+        {
+            log::info!("Goal {} reached at {}", self.goal_index, self.last_time);
+            node.annotate(self.last_time, &format!("Goal {} reached", self.goal_index));
+            self.reached_goals.push(GoalReachedRecord {
+                index: self.goal_index,
+                point: goal.point,
+                time: self.last_time,
+            });
+            self.goals_reached += 1;
+            self.dwell_until = Some(self.last_time + goal.dwell_time);
+            self.target_reached = true;
+        }
+        if let Some(dwell_until) = self.dwell_until
+            && self.last_time >= dwell_until
+        {
+            self.dwell_until = None;
+            self.goal_index += 1;
+            // The robot stays at the last goal
+            if let Some(point) = self.goals.get(self.goal_index).map(|goal| goal.point) {
+                self.set_target(Some(point));
+            }
+        }
+    }
 }
 
 use crate::controllers::ControllerError;
@@ -294,13 +518,19 @@ impl Navigator for GoTo {
 
     /// Compute the error between the given `state` and the target point.
     ///
-    fn compute_error(&mut self, _robot: &mut Node, world_state: WorldState) -> ControllerError {
+    fn compute_error(&mut self, robot: &mut Node, world_state: WorldState) -> ControllerError {
         if world_state.ego.is_none() {
             panic!("StateEstimator should provide an ego estimate for GoTo navigator.")
         }
 
         let state = world_state.ego.unwrap().theta_modulo();
 
+        if let Some(point) = self.current_point {
+            let distance_to_goal =
+                (state.pose.fixed_view::<2, 1>(0, 0) - SVector::from_row_slice(&point)).norm();
+            self.update_goal_queue(robot, distance_to_goal);
+        }
+
         if self.current_point.is_none() {
             return ControllerError {
                 longitudinal: 0.,
@@ -315,9 +545,14 @@ impl Navigator for GoTo {
             .target_speed
             .min(distance_to_final * self.stop_ramp_coefficient);
 
-        if distance_to_final < self.stop_distance {
+        if distance_to_final < self.stop_distance || self.dwell_until.is_some() {
             self.target_speed = 0.;
         }
+        if distance_to_final < self.stop_distance && !self.target_reached {
+            // Target outside of the goal queue
+            self.target_reached = true;
+            self.targets_reached += 1;
+        }
 
         let target_direction = atan2(
             (target_point[1] - state.pose.y).into(),
@@ -342,16 +577,26 @@ impl Navigator for GoTo {
     }
 
     fn pre_loop_hook(&mut self, _node: &mut Node, time: f32) {
+        self.last_time = time;
+        // The goals are reached in `compute_error`, after this hook
+        self.reached_goals.clear();
         while let Some((_, envelope)) = self.message_client.lock().unwrap().try_receive(time) {
             if let Ok(msg) = serde_json::from_value::<GoToMessage>(envelope.message) {
-                self.current_point = msg.target_point;
+                // The message replaces the goal queue
+                self.goal_index = self.goals.len();
+                self.dwell_until = None;
+                self.set_target(msg.target_point);
                 log::info!("Update target point to {:?}", self.current_point);
             }
         }
     }
 
     fn next_time_step(&self) -> Option<f32> {
-        self.message_client.lock().unwrap().next_message_time()
+        let next_message_time = self.message_client.lock().unwrap().next_message_time();
+        match (next_message_time, self.dwell_until) {
+            (Some(message_time), Some(dwell_until)) => Some(message_time.min(dwell_until)),
+            (message_time, dwell_until) => message_time.or(dwell_until),
+        }
     }
 }
 
@@ -362,6 +607,10 @@ impl Recordable<NavigatorRecord> for GoTo {
         NavigatorRecord::GoTo(GoToRecord {
             error: self.error.clone(),
             current_point: self.current_point,
+            current_goal: (self.goal_index < self.goals.len()).then_some(self.goal_index),
+            reached_goals: self.reached_goals.clone(),
+            goals_reached: self.goals_reached,
+            targets_reached: self.targets_reached,
         })
     }
 }
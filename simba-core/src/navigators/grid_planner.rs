@@ -0,0 +1,536 @@
+//! Global planner [`Navigator`] on the occupancy grid.
+//!
+//! [`GridPlanner`] plans a path from the estimated position of the robot to a fixed goal on the
+//! occupancy grid of the world state estimated by the state estimator
+//! ([`WorldState::occupancy_grid`]), with A* or Theta* ([`GridPlanningAlgorithm`]). The cells
+//! with an occupancy value above `occupied_threshold` are obstacles, inflated by
+//! `inflation_radius` to keep the robot away from them, by default the radius of the
+//! [footprint](crate::node::footprint) of the robot. The unknown cells (between the two
+//! thresholds) are crossed only if `unknown_traversable` is set.
+//!
+//! The path is planned again each time the obstacles of the grid change, so that the robot goes
+//! around the obstacles discovered on the way. It is followed like the
+//! [`GoTo`](super::go_to::GoTo) navigator goes to its target point, and the robot stops when the
+//! goal is reached or when no path exists. Without occupancy grid, the robot goes straight to the
+//! goal.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{UIComponent, utils::string_combobox},
+    simulator::SimulatorConfig,
+    utils::enum_tools::ToVec,
+};
+
+use crate::{
+    controllers::ControllerError,
+    navigators::{Navigator, NavigatorRecord},
+    node::Node,
+    recordable::Recordable,
+    state_estimators::WorldState,
+    utils::{
+        geometry::smallest_theta_diff, grid_path_planning, lock_recovery::RecoverRoLock,
+        occupancy_grid::OccupancyGrid,
+    },
+};
+
+use config_checker::*;
+use nalgebra::Vector2;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Search algorithm of the path.
+#[config_derives]
+pub enum GridPlanningAlgorithm {
+    /// A* on the 8-connected cells.
+    AStar,
+    /// Theta*: any-angle path, with straight segments between the turning points.
+    ThetaStar,
+}
+
+impl Default for GridPlanningAlgorithm {
+    fn default() -> Self {
+        Self::AStar
+    }
+}
+
+/// Configuration of the [`GridPlanner`] strategy.
+///
+/// ```yaml
+/// navigator:
+///   type: GridPlanner
+///   goal: [10., 5.]
+///   algorithm:
+///     type: ThetaStar
+///   free_threshold: 0.35
+///   occupied_threshold: 0.65
+///   unknown_traversable: true
+///   inflation_radius: 0.3
+///   goal_tolerance: 0.5
+///   target_speed: 0.5
+/// ```
+#[config_derives]
+pub struct GridPlannerConfig {
+    /// Goal `[x, y]` in world coordinates.
+    ///
+    /// Default: `[0, 0]`.
+    pub goal: [f32; 2],
+    /// Search algorithm of the path.
+    ///
+    /// Default: `AStar`.
+    pub algorithm: GridPlanningAlgorithm,
+    /// Cells with an occupancy value below this threshold are free.
+    ///
+    /// Default: `0.35`.
+    pub free_threshold: f32,
+    /// Cells with an occupancy value above this threshold are obstacles. The cells between the
+    /// two thresholds are unknown.
+    ///
+    /// Default: `0.65`.
+    pub occupied_threshold: f32,
+    /// Whether the path can cross the unknown cells.
+    ///
+    /// Default: `true`.
+    pub unknown_traversable: bool,
+    /// Cells closer than this distance to an obstacle, in meters, cannot be crossed. `null` to
+    /// use the bounding radius of the footprint of the robot.
+    ///
+    /// Default: `null`.
+    pub inflation_radius: Option<f32>,
+    /// Distance under which a point of the path, or the goal, is reached, in meters.
+    ///
+    /// Default: `0.5`.
+    pub goal_tolerance: f32,
+    /// Target linear speed in m/s.
+    ///
+    /// Default: `0.5`.
+    pub target_speed: f32,
+}
+
+impl Default for GridPlannerConfig {
+    fn default() -> Self {
+        Self {
+            goal: [0., 0.],
+            algorithm: GridPlanningAlgorithm::default(),
+            free_threshold: 0.35,
+            occupied_threshold: 0.65,
+            unknown_traversable: true,
+            inflation_radius: None,
+            goal_tolerance: 0.5,
+            target_speed: 0.5,
+        }
+    }
+}
+
+impl Check for GridPlannerConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.free_threshold > self.occupied_threshold {
+            errs.push(format!(
+                "Free threshold ({}) should be lower than the occupied threshold ({})",
+                self.free_threshold, self.occupied_threshold
+            ));
+        }
+        if let Some(inflation_radius) = self.inflation_radius
+            && inflation_radius < 0.
+        {
+            errs.push("Inflation radius should be positive".to_string());
+        }
+        if self.goal_tolerance <= 0. {
+            errs.push("Goal tolerance should be strictly positive".to_string());
+        }
+        if self.target_speed < 0. {
+            errs.push("Target speed should be positive".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for GridPlannerConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Grid Planner")
+            .id_salt(format!("grid-planner-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Goal:");
+                    ui.add(egui::DragValue::new(&mut self.goal[0]).speed(0.1));
+                    ui.add(egui::DragValue::new(&mut self.goal[1]).speed(0.1));
+                });
+                let mut current_str = self.algorithm.to_string();
+                ui.horizontal(|ui| {
+                    ui.label("Algorithm: ");
+                    string_combobox(
+                        ui,
+                        &GridPlanningAlgorithm::to_vec(),
+                        &mut current_str,
+                        format!("grid-planner-algorithm-choice-{}", unique_id),
+                    );
+                });
+                if current_str != self.algorithm.to_string() {
+                    match current_str.as_str() {
+                        "AStar" => self.algorithm = GridPlanningAlgorithm::AStar,
+                        "ThetaStar" => self.algorithm = GridPlanningAlgorithm::ThetaStar,
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Free / occupied thresholds:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.free_threshold)
+                            .speed(0.01)
+                            .range(0.0..=self.occupied_threshold),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut self.occupied_threshold)
+                            .speed(0.01)
+                            .range(self.free_threshold..=f32::MAX),
+                    );
+                });
+                ui.checkbox(&mut self.unknown_traversable, "Unknown cells traversable");
+                ui.horizontal(|ui| {
+                    ui.label("Inflation radius:");
+                    if let Some(inflation_radius) = &mut self.inflation_radius {
+                        ui.add(
+                            egui::DragValue::new(inflation_radius)
+                                .speed(0.01)
+                                .range(0.0..=f32::MAX),
+                        );
+                        if ui.button("X").clicked() {
+                            self.inflation_radius = None;
+                        }
+                    } else {
+                        ui.label("footprint");
+                        if ui.button("+").clicked() {
+                            self.inflation_radius = Some(0.);
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Goal tolerance:");
+                    ui.add(egui::DragValue::new(&mut self.goal_tolerance).range(0.001..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Target speed:");
+                    ui.add(egui::DragValue::new(&mut self.target_speed).range(0.0..=f32::MAX));
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Grid Planner")
+            .id_salt(format!("grid-planner-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Goal: ({}, {})", self.goal[0], self.goal[1]));
+                ui.label(format!("Algorithm: {}", self.algorithm));
+                ui.label(format!(
+                    "Free / occupied thresholds: {} / {}",
+                    self.free_threshold, self.occupied_threshold
+                ));
+                ui.label(format!(
+                    "Unknown cells traversable: {}",
+                    self.unknown_traversable
+                ));
+                match self.inflation_radius {
+                    Some(inflation_radius) => {
+                        ui.label(format!("Inflation radius: {}", inflation_radius))
+                    }
+                    None => ui.label("Inflation radius: footprint"),
+                };
+                ui.label(format!("Goal tolerance: {}", self.goal_tolerance));
+                ui.label(format!("Target speed: {}", self.target_speed));
+            });
+    }
+}
+
+/// Record of the [`GridPlanner`] navigator.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GridPlannerRecord {
+    /// Current error
+    pub error: ControllerError,
+    /// Goal `[x, y]` in world coordinates.
+    pub goal: [f32; 2],
+    /// Remaining points `[x, y]` of the planned path, the next one first.
+    pub path: Vec<[f32; 2]>,
+    /// Number of plans since the start.
+    pub replans: usize,
+    /// Whether a path to the goal was found at the last plan.
+    pub path_found: bool,
+    /// Whether the goal is reached.
+    pub goal_reached: bool,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for GridPlannerRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("Error").show(ui, |ui| {
+                self.error.show(ui, ctx, unique_id);
+            });
+            ui.label(format!("Goal: ({}, {})", self.goal[0], self.goal[1]));
+            ui.label(format!("Remaining path points: {}", self.path.len()));
+            ui.label(format!("Plans: {}", self.replans));
+            ui.label(format!("Path found: {}", self.path_found));
+            ui.label(format!("Goal reached: {}", self.goal_reached));
+        });
+    }
+}
+
+/// [`Navigator`] strategy planning a path to a goal on the estimated occupancy grid.
+#[derive(Debug)]
+pub struct GridPlanner {
+    config: GridPlannerConfig,
+    goal: Vector2<f32>,
+    /// Remaining points of the path to the goal, the next one first.
+    path: VecDeque<Vector2<f32>>,
+    /// Non traversable cells of the grid used for the last plan.
+    blocked_cells: Option<Vec<bool>>,
+    /// Inflation of the obstacles, resolved from the footprint of the robot if not configured.
+    inflation_radius: f32,
+    replans: usize,
+    path_found: bool,
+    goal_reached: bool,
+    /// Last error, stored to make the [`GridPlannerRecord`]
+    error: ControllerError,
+}
+
+impl GridPlanner {
+    /// Makes a [`GridPlanner`] from the given config.
+    pub fn from_config(config: &GridPlannerConfig, _initial_time: f32) -> Self {
+        Self {
+            config: config.clone(),
+            goal: Vector2::from(config.goal),
+            path: VecDeque::new(),
+            blocked_cells: None,
+            inflation_radius: config.inflation_radius.unwrap_or(0.),
+            replans: 0,
+            path_found: false,
+            goal_reached: false,
+            error: ControllerError::default(),
+        }
+    }
+
+    /// Non traversable cells of the grid, row by row: obstacles inflated by `inflation_radius`,
+    /// and unknown cells if they are not traversable.
+    fn blocked_cells(&self, grid: &OccupancyGrid) -> Vec<bool> {
+        let nb_cols = grid.nb_cols();
+        let values = (0..grid.nb_rows())
+            .flat_map(|row| (0..nb_cols).map(move |col| *grid.get_idx(row, col).unwrap()));
+        let mut blocked: Vec<bool> = values
+            .clone()
+            .map(|value| {
+                !self.config.unknown_traversable
+                    && value >= self.config.free_threshold
+                    && value <= self.config.occupied_threshold
+            })
+            .collect();
+        let row_radius = (self.inflation_radius / grid.cell_height()).floor() as isize;
+        let col_radius = (self.inflation_radius / grid.cell_width()).floor() as isize;
+        for (i, value) in values.enumerate() {
+            if value <= self.config.occupied_threshold {
+                continue;
+            }
+            let (row, col) = (i / nb_cols, i % nb_cols);
+            for dr in -row_radius..=row_radius {
+                for dc in -col_radius..=col_radius {
+                    let (Some(r), Some(c)) =
+                        (row.checked_add_signed(dr), col.checked_add_signed(dc))
+                    else {
+                        continue;
+                    };
+                    let distance = Vector2::new(
+                        dc as f32 * grid.cell_width(),
+                        dr as f32 * grid.cell_height(),
+                    )
+                    .norm();
+                    if r < grid.nb_rows() && c < nb_cols && distance <= self.inflation_radius {
+                        blocked[r * nb_cols + c] = true;
+                    }
+                }
+            }
+        }
+        blocked
+    }
+
+    /// Plan the path from `position` to the goal if the non traversable cells of the grid
+    /// changed since the last plan.
+    fn update_plan(&mut self, grid: &OccupancyGrid, position: Vector2<f32>) {
+        let blocked = self.blocked_cells(grid);
+        if self.blocked_cells.as_ref() == Some(&blocked) {
+            return;
+        }
+        self.replans += 1;
+        let nb_cols = grid.nb_cols();
+        let path = grid.pos_to_idx(self.goal).and_then(|goal| {
+            grid_path_planning::plan_path(
+                grid,
+                position,
+                goal,
+                matches!(self.config.algorithm, GridPlanningAlgorithm::ThetaStar),
+                |row, col| !blocked[row * nb_cols + col],
+            )
+        });
+        self.path_found = path.is_some();
+        self.path = match path {
+            Some(mut path) => {
+                // The last point is the goal instead of the center of its cell
+                path.pop();
+                path.push(self.goal);
+                path.into()
+            }
+            None => {
+                log::warn!(
+                    "No path to the goal ({}, {}) on the occupancy grid",
+                    self.goal.x,
+                    self.goal.y
+                );
+                VecDeque::new()
+            }
+        };
+        self.blocked_cells = Some(blocked);
+    }
+}
+
+impl Navigator for GridPlanner {
+    /// Compute the error between the given `state` and the next point of the path.
+    fn compute_error(&mut self, robot: &mut Node, world_state: WorldState) -> ControllerError {
+        if world_state.ego.is_none() {
+            panic!("StateEstimator should provide an ego estimate for GridPlanner navigator.")
+        }
+        let state = world_state.ego.unwrap().theta_modulo();
+        let position = Vector2::new(state.pose.x, state.pose.y);
+        let speed = state.velocity.fixed_rows::<2>(0).norm();
+        if self.config.inflation_radius.is_none() {
+            self.inflation_radius = robot.meta_data().read_recover().footprint_radius;
+        }
+
+        if !self.goal_reached && (self.goal - position).norm() < self.config.goal_tolerance {
+            self.goal_reached = true;
+            self.path.clear();
+        }
+        if !self.goal_reached {
+            match &world_state.occupancy_grid {
+                Some(grid) => self.update_plan(grid, position),
+                None => {
+                    self.path = VecDeque::from([self.goal]);
+                    self.path_found = true;
+                }
+            }
+            while self.path.len() > 1
+                && self
+                    .path
+                    .front()
+                    .is_some_and(|point| (point - position).norm() < self.config.goal_tolerance)
+            {
+                self.path.pop_front();
+            }
+        }
+
+        let Some(next_point) = self.path.front().copied() else {
+            self.error = ControllerError {
+                velocity: -speed,
+                ..Default::default()
+            };
+            return self.error.clone();
+        };
+        self.error.theta = smallest_theta_diff(
+            (next_point.y - position.y).atan2(next_point.x - position.x),
+            state.pose.z,
+        );
+        // Next point projected in the robot frame
+        let relative_point = nalgebra::Rotation2::new(-state.pose.z) * (next_point - position);
+        self.error.longitudinal = relative_point.x;
+        self.error.lateral = relative_point.y;
+        self.error.velocity = self.config.target_speed - speed;
+
+        self.error.clone()
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+}
+
+impl Recordable<NavigatorRecord> for GridPlanner {
+    fn record(&self) -> NavigatorRecord {
+        NavigatorRecord::GridPlanner(GridPlannerRecord {
+            error: self.error.clone(),
+            goal: [self.goal.x, self.goal.y],
+            path: self.path.iter().map(|point| [point.x, point.y]).collect(),
+            replans: self.replans,
+            path_found: self.path_found,
+            goal_reached: self.goal_reached,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    #[test]
+    fn replan_on_new_obstacle() {
+        // 5x5 grid of 1 m cells centered on (0, 0), free
+        let mut grid = OccupancyGrid::new(Vector3::zeros(), 1., 1., 5, 5);
+        let mut planner = GridPlanner::from_config(
+            &GridPlannerConfig {
+                goal: [2., 0.],
+                algorithm: GridPlanningAlgorithm::ThetaStar,
+                ..Default::default()
+            },
+            0.,
+        );
+        let start = Vector2::new(-2., 0.);
+        planner.update_plan(&grid, start);
+        assert_eq!(planner.replans, 1);
+        assert_eq!(planner.path, VecDeque::from([Vector2::new(2., 0.)]));
+
+        // Same grid: no new plan
+        planner.update_plan(&grid, start);
+        assert_eq!(planner.replans, 1);
+
+        // Wall in the middle column, open on the top row: the new path goes through the gap
+        for row in 0..4 {
+            *grid.get_idx_mut(row, 2).unwrap() = 1.;
+        }
+        planner.update_plan(&grid, start);
+        assert_eq!(planner.replans, 2);
+        assert!(planner.path_found);
+        assert_eq!(planner.path.back(), Some(&Vector2::new(2., 0.)));
+        assert!(planner.path.iter().any(|point| point.y == 2.));
+
+        // Closed wall: no path
+        *grid.get_idx_mut(4, 2).unwrap() = 1.;
+        planner.update_plan(&grid, start);
+        assert!(!planner.path_found);
+        assert!(planner.path.is_empty());
+    }
+
+    #[test]
+    fn inflation_by_footprint() {
+        // 5x5 grid of 1 m cells centered on (0, 0), obstacle in the center
+        let mut grid = OccupancyGrid::new(Vector3::zeros(), 1., 1., 5, 5);
+        *grid.get_idx_mut(2, 2).unwrap() = 1.;
+        let mut planner = GridPlanner::from_config(&GridPlannerConfig::default(), 0.);
+        assert_eq!(
+            planner.blocked_cells(&grid).iter().filter(|b| **b).count(),
+            1
+        );
+
+        // Footprint of radius 1 m: the 4 neighbours are blocked
+        planner.inflation_radius = 1.;
+        let blocked = planner.blocked_cells(&grid);
+        assert_eq!(blocked.iter().filter(|b| **b).count(), 5);
+        assert!(blocked[2 * 5 + 1] && blocked[2 * 5 + 3] && blocked[5 + 2] && blocked[3 * 5 + 2]);
+        assert!(!blocked[5 + 1]);
+    }
+}
@@ -4,10 +4,16 @@
 //! target behavior. It also exposes strategy-specific configuration and record enums:
 //! [`NavigatorConfig`] and [`NavigatorRecord`].
 //!
-//! Built-in strategies include trajectory following, point-target navigation, and external/Python
-//! implementations.
+//! Built-in strategies include trajectory following, point-target navigation, swarm behaviors,
+//! graph patrolling, frontier exploration, area coverage, grid planning, and external/Python implementations.
 
+pub mod composite_navigator;
+pub mod coverage;
+pub mod frontier_explorer;
 pub mod go_to;
+pub mod grid_planner;
+pub mod patrol;
+pub mod swarm;
 pub mod trajectory;
 pub mod trajectory_follower;
 
@@ -49,6 +55,24 @@ pub enum NavigatorConfig {
     /// Configuration for [`go_to::GoTo`].
     #[check]
     GoTo(go_to::GoToConfig),
+    /// Configuration for [`composite_navigator::CompositeNavigator`].
+    #[check]
+    Composite(composite_navigator::CompositeNavigatorConfig),
+    /// Configuration for [`swarm::Swarm`].
+    #[check]
+    Swarm(swarm::SwarmConfig),
+    /// Configuration for [`patrol::Patrol`].
+    #[check]
+    Patrol(patrol::PatrolConfig),
+    /// Configuration for [`frontier_explorer::FrontierExplorer`].
+    #[check]
+    FrontierExplorer(frontier_explorer::FrontierExplorerConfig),
+    /// Configuration for [`coverage::CoverageNavigator`].
+    #[check]
+    Coverage(coverage::CoverageNavigatorConfig),
+    /// Configuration for [`grid_planner::GridPlanner`].
+    #[check]
+    GridPlanner(grid_planner::GridPlannerConfig),
 }
 
 #[cfg(feature = "gui")]
@@ -89,6 +113,24 @@ impl UIComponent for NavigatorConfig {
                         NavigatorConfig::Python(python_navigator::PythonNavigatorConfig::default())
                 }
                 "GoTo" => *self = NavigatorConfig::GoTo(go_to::GoToConfig::default()),
+                "Composite" => {
+                    *self = NavigatorConfig::Composite(
+                        composite_navigator::CompositeNavigatorConfig::default(),
+                    )
+                }
+                "Swarm" => *self = NavigatorConfig::Swarm(swarm::SwarmConfig::default()),
+                "Patrol" => *self = NavigatorConfig::Patrol(patrol::PatrolConfig::default()),
+                "FrontierExplorer" => {
+                    *self = NavigatorConfig::FrontierExplorer(
+                        frontier_explorer::FrontierExplorerConfig::default(),
+                    )
+                }
+                "Coverage" => {
+                    *self = NavigatorConfig::Coverage(coverage::CoverageNavigatorConfig::default())
+                }
+                "GridPlanner" => {
+                    *self = NavigatorConfig::GridPlanner(grid_planner::GridPlannerConfig::default())
+                }
                 _ => panic!("Where did you find this value?"),
             };
         }
@@ -125,6 +167,54 @@ impl UIComponent for NavigatorConfig {
                 current_node_name,
                 unique_id,
             ),
+            NavigatorConfig::Composite(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            NavigatorConfig::Swarm(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            NavigatorConfig::Patrol(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            NavigatorConfig::FrontierExplorer(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            NavigatorConfig::Coverage(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            NavigatorConfig::GridPlanner(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
         }
     }
 
@@ -138,6 +228,12 @@ impl UIComponent for NavigatorConfig {
             NavigatorConfig::External(c) => c.show(ui, ctx, unique_id),
             NavigatorConfig::Python(c) => c.show(ui, ctx, unique_id),
             NavigatorConfig::GoTo(c) => c.show(ui, ctx, unique_id),
+            NavigatorConfig::Composite(c) => c.show(ui, ctx, unique_id),
+            NavigatorConfig::Swarm(c) => c.show(ui, ctx, unique_id),
+            NavigatorConfig::Patrol(c) => c.show(ui, ctx, unique_id),
+            NavigatorConfig::FrontierExplorer(c) => c.show(ui, ctx, unique_id),
+            NavigatorConfig::Coverage(c) => c.show(ui, ctx, unique_id),
+            NavigatorConfig::GridPlanner(c) => c.show(ui, ctx, unique_id),
         }
     }
 }
@@ -153,6 +249,63 @@ pub enum NavigatorRecord {
     Python(python_navigator::PythonNavigatorRecord),
     /// Runtime record for [`GoTo`](go_to::GoTo).
     GoTo(go_to::GoToRecord),
+    /// Runtime record for [`CompositeNavigator`](composite_navigator::CompositeNavigator).
+    Composite(composite_navigator::CompositeNavigatorRecord),
+    /// Runtime record for [`Swarm`](swarm::Swarm).
+    Swarm(swarm::SwarmRecord),
+    /// Runtime record for [`Patrol`](patrol::Patrol).
+    Patrol(patrol::PatrolRecord),
+    /// Runtime record for [`FrontierExplorer`](frontier_explorer::FrontierExplorer).
+    FrontierExplorer(frontier_explorer::FrontierExplorerRecord),
+    /// Runtime record for [`CoverageNavigator`](coverage::CoverageNavigator).
+    Coverage(coverage::CoverageNavigatorRecord),
+    /// Runtime record for [`GridPlanner`](grid_planner::GridPlanner).
+    GridPlanner(grid_planner::GridPlannerRecord),
+}
+
+/// Goal progress of a navigator at the time of its record, used by the mission summary
+/// ([`MissionSummary`](crate::simulator::MissionSummary)).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    /// Current goal in world coordinates `[x, y]`, `None` without goal.
+    pub goal: Option<[f32; 2]>,
+    /// Number of goals reached since the start of the navigator.
+    pub reached: usize,
+}
+
+impl NavigatorRecord {
+    /// Goal progress of the strategies going to goals: [`GoTo`](go_to::GoTo) (goal queue and
+    /// targets), [`Patrol`](patrol::Patrol) (waypoints) and
+    /// [`FrontierExplorer`](frontier_explorer::FrontierExplorer) (frontiers) and
+    /// [`GridPlanner`](grid_planner::GridPlanner) (goal). The
+    /// [`CompositeNavigator`](composite_navigator::CompositeNavigator) gives the progress of its
+    /// active phase. `None` for the other strategies.
+    pub fn goal_progress(&self) -> Option<GoalProgress> {
+        match self {
+            Self::GoTo(r) => Some(GoalProgress {
+                goal: r.current_point,
+                reached: r.goals_reached + r.targets_reached,
+            }),
+            Self::Patrol(r) => Some(GoalProgress {
+                goal: r.target_point,
+                reached: r.visits,
+            }),
+            Self::FrontierExplorer(r) => Some(GoalProgress {
+                goal: r.target,
+                reached: r.targets_reached,
+            }),
+            Self::GridPlanner(r) => Some(GoalProgress {
+                goal: Some(r.goal),
+                reached: r.goal_reached as usize,
+            }),
+            Self::Composite(r) => r.navigator.goal_progress(),
+            Self::TrajectoryFollower(_)
+            | Self::External(_)
+            | Self::Python(_)
+            | Self::Swarm(_)
+            | Self::Coverage(_) => None,
+        }
+    }
 }
 
 #[cfg(feature = "gui")]
@@ -179,6 +332,36 @@ impl UIComponent for NavigatorRecord {
                     r.show(ui, ctx, unique_id);
                 });
             }
+            Self::Composite(r) => {
+                egui::CollapsingHeader::new("Composite").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::Swarm(r) => {
+                egui::CollapsingHeader::new("Swarm").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::Patrol(r) => {
+                egui::CollapsingHeader::new("Patrol").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::FrontierExplorer(r) => {
+                egui::CollapsingHeader::new("FrontierExplorer").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::Coverage(r) => {
+                egui::CollapsingHeader::new("Coverage").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::GridPlanner(r) => {
+                egui::CollapsingHeader::new("GridPlanner").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
         });
     }
 }
@@ -231,7 +414,7 @@ pub fn make_navigator_from_config(
 ) -> SimbaResult<SharedRwLock<Box<dyn Navigator>>> {
     Ok(Arc::new(RwLock::new(match config {
         NavigatorConfig::TrajectoryFollower(c) => Box::new(
-            trajectory_follower::TrajectoryFollower::from_config(c, global_config, initial_time),
+            trajectory_follower::TrajectoryFollower::from_config(c, global_config, initial_time)?,
         ) as Box<dyn Navigator>,
         NavigatorConfig::External(c) => {
             Box::new(external_navigator::ExternalNavigator::from_config(
@@ -249,5 +432,31 @@ pub fn make_navigator_from_config(
         NavigatorConfig::GoTo(c) => {
             Box::new(go_to::GoTo::from_config(c, network, initial_time)) as Box<dyn Navigator>
         }
+        NavigatorConfig::Composite(c) => {
+            Box::new(composite_navigator::CompositeNavigator::from_config(
+                c,
+                plugin_api,
+                global_config,
+                va_factory,
+                network,
+                initial_time,
+            )?) as Box<dyn Navigator>
+        }
+        NavigatorConfig::Swarm(c) => {
+            Box::new(swarm::Swarm::from_config(c, initial_time)) as Box<dyn Navigator>
+        }
+        NavigatorConfig::Patrol(c) => {
+            Box::new(patrol::Patrol::from_config(c, initial_time)) as Box<dyn Navigator>
+        }
+        NavigatorConfig::FrontierExplorer(c) => Box::new(
+            frontier_explorer::FrontierExplorer::from_config(c, initial_time),
+        ) as Box<dyn Navigator>,
+        NavigatorConfig::Coverage(c) => {
+            Box::new(coverage::CoverageNavigator::from_config(c, initial_time))
+                as Box<dyn Navigator>
+        }
+        NavigatorConfig::GridPlanner(c) => {
+            Box::new(grid_planner::GridPlanner::from_config(c, initial_time)) as Box<dyn Navigator>
+        }
     })))
 }
@@ -0,0 +1,439 @@
+//! Patrolling [`Navigator`] over a graph of waypoints.
+//!
+//! [`Patrol`] visits the waypoints of a graph endlessly, the task of the multi-robot patrolling
+//! benchmarks. A waypoint is visited when the robot comes closer than `visit_radius`, the next
+//! waypoint is then chosen by the [`PatrolStrategy`]:
+//! - [`PatrolStrategy::Cyclic`]: the next waypoint in the listed order, looping,
+//! - [`PatrolStrategy::Conscientious`]: the neighbor (in the graph) that the robot visited the
+//!   longest time ago, i.e. with the highest idleness from the robot point of view.
+//!
+//! The robot starts with the closest waypoint, and goes in straight line between the waypoints:
+//! the edges of the graph should be free of obstacles.
+//!
+//! The idleness of the waypoints at the fleet level (visits of all the robots) is computed by the
+//! simulator, see [`IdlenessConfig`](crate::simulator::IdlenessConfig).
+
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{UIComponent, utils::string_combobox},
+    simulator::SimulatorConfig,
+    utils::enum_tools::ToVec,
+};
+
+use crate::{
+    controllers::ControllerError,
+    navigators::{Navigator, NavigatorRecord},
+    node::Node,
+    recordable::Recordable,
+    state_estimators::WorldState,
+    utils::geometry::smallest_theta_diff,
+};
+
+use config_checker::*;
+use nalgebra::Vector2;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Choice of the next waypoint of the [`Patrol`] navigator.
+#[config_derives]
+pub enum PatrolStrategy {
+    /// Next waypoint in the listed order, looping. The edges are ignored.
+    Cyclic,
+    /// Neighbor visited the longest time ago by the robot.
+    Conscientious,
+}
+
+impl Default for PatrolStrategy {
+    fn default() -> Self {
+        Self::Conscientious
+    }
+}
+
+/// Configuration of the [`Patrol`] strategy.
+///
+/// ```yaml
+/// navigator:
+///   type: Patrol
+///   waypoints: [[0., 0.], [5., 0.], [5., 5.], [0., 5.]]
+///   edges: [[0, 1], [1, 2], [2, 3], [3, 0], [0, 2]]
+///   strategy:
+///     type: Conscientious
+///   visit_radius: 0.5
+///   target_speed: 0.5
+/// ```
+#[config_derives]
+pub struct PatrolConfig {
+    /// Waypoints to patrol, in world coordinates `[x, y]`.
+    ///
+    /// Default: empty (the robot does not move).
+    pub waypoints: Vec<[f32; 2]>,
+    /// Undirected edges of the graph, as pairs of waypoint indices. If empty, the waypoints are
+    /// linked in a cycle, in the listed order.
+    ///
+    /// Default: empty.
+    pub edges: Vec<[usize; 2]>,
+    /// Choice of the next waypoint.
+    ///
+    /// Default: `Conscientious`.
+    pub strategy: PatrolStrategy,
+    /// Distance under which a waypoint is visited, in meters.
+    ///
+    /// Default: `0.5`.
+    pub visit_radius: f32,
+    /// Target linear speed in m/s.
+    ///
+    /// Default: `0.5`.
+    pub target_speed: f32,
+}
+
+impl Default for PatrolConfig {
+    fn default() -> Self {
+        Self {
+            waypoints: Vec::new(),
+            edges: Vec::new(),
+            strategy: PatrolStrategy::default(),
+            visit_radius: 0.5,
+            target_speed: 0.5,
+        }
+    }
+}
+
+impl Check for PatrolConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        for edge in &self.edges {
+            if edge.iter().any(|i| *i >= self.waypoints.len()) {
+                errs.push(format!(
+                    "Edge [{}, {}] links an unknown waypoint ({} waypoints)",
+                    edge[0],
+                    edge[1],
+                    self.waypoints.len()
+                ));
+            } else if edge[0] == edge[1] {
+                errs.push(format!("Edge [{}, {}] is a loop", edge[0], edge[1]));
+            }
+        }
+        if self.visit_radius <= 0. {
+            errs.push("Visit radius should be strictly positive".to_string());
+        }
+        if self.target_speed < 0. {
+            errs.push("Target speed should be positive".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for PatrolConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Patrol")
+            .id_salt(format!("patrol-{}", unique_id))
+            .show(ui, |ui| {
+                let mut current_str = self.strategy.to_string();
+                ui.horizontal(|ui| {
+                    ui.label("Strategy: ");
+                    string_combobox(
+                        ui,
+                        &PatrolStrategy::to_vec(),
+                        &mut current_str,
+                        format!("patrol-strategy-choice-{}", unique_id),
+                    );
+                });
+                if current_str != self.strategy.to_string() {
+                    match current_str.as_str() {
+                        "Cyclic" => self.strategy = PatrolStrategy::Cyclic,
+                        "Conscientious" => self.strategy = PatrolStrategy::Conscientious,
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Visit radius:");
+                    ui.add(egui::DragValue::new(&mut self.visit_radius).range(0.001..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Target speed:");
+                    ui.add(egui::DragValue::new(&mut self.target_speed).range(0.0..=f32::MAX));
+                });
+
+                ui.label("Waypoints:");
+                let mut to_remove = None;
+                for (i, waypoint) in self.waypoints.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{i}: ("));
+                        ui.add(egui::DragValue::new(&mut waypoint[0]).fixed_decimals(2));
+                        ui.label(", ");
+                        ui.add(egui::DragValue::new(&mut waypoint[1]).fixed_decimals(2));
+                        ui.label(")");
+                        if ui.button("-").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = to_remove {
+                    self.waypoints.remove(i);
+                    // Drop the edges of the removed waypoint, and shift the next indices
+                    self.edges.retain(|edge| !edge.contains(&i));
+                    for edge in self.edges.iter_mut() {
+                        for index in edge.iter_mut() {
+                            if *index > i {
+                                *index -= 1;
+                            }
+                        }
+                    }
+                }
+                if ui.button("Add waypoint").clicked() {
+                    self.waypoints.push([0., 0.]);
+                }
+
+                ui.label("Edges (empty for a cycle):");
+                let max_index = self.waypoints.len().saturating_sub(1);
+                let mut to_remove = None;
+                for (i, edge) in self.edges.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut edge[0]).range(0..=max_index));
+                        ui.label(" - ");
+                        ui.add(egui::DragValue::new(&mut edge[1]).range(0..=max_index));
+                        if ui.button("-").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = to_remove {
+                    self.edges.remove(i);
+                }
+                if ui.button("Add edge").clicked() {
+                    self.edges.push([0, max_index]);
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Patrol")
+            .id_salt(format!("patrol-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Strategy: {}", self.strategy));
+                ui.label(format!("Visit radius: {}", self.visit_radius));
+                ui.label(format!("Target speed: {}", self.target_speed));
+                for (i, waypoint) in self.waypoints.iter().enumerate() {
+                    ui.label(format!("Waypoint {i}: ({}, {})", waypoint[0], waypoint[1]));
+                }
+                if self.edges.is_empty() {
+                    ui.label("Edges: cycle");
+                } else {
+                    ui.label(format!(
+                        "Edges: {}",
+                        self.edges
+                            .iter()
+                            .map(|edge| format!("{}-{}", edge[0], edge[1]))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+            });
+    }
+}
+
+/// Record of the [`Patrol`] navigator.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PatrolRecord {
+    /// Current error
+    pub error: ControllerError,
+    /// Index of the waypoint the robot goes to.
+    pub target: Option<usize>,
+    /// Position of the waypoint the robot goes to, in world coordinates `[x, y]`.
+    #[serde(default)]
+    pub target_point: Option<[f32; 2]>,
+    /// Number of waypoint visits since the start.
+    pub visits: usize,
+    /// Time since the last visit of each waypoint by the robot (since the start if never
+    /// visited).
+    pub idleness: Vec<f32>,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for PatrolRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("Error").show(ui, |ui| {
+                self.error.show(ui, ctx, unique_id);
+            });
+            ui.label(format!(
+                "Target: {}",
+                self.target.map_or("None".to_string(), |i| i.to_string())
+            ));
+            ui.label(format!("Visits: {}", self.visits));
+            for (i, idleness) in self.idleness.iter().enumerate() {
+                ui.label(format!("Idleness of {i}: {idleness:.2} s"));
+            }
+        });
+    }
+}
+
+/// [`Navigator`] strategy patrolling a graph of waypoints.
+#[derive(Debug)]
+pub struct Patrol {
+    config: PatrolConfig,
+    /// Neighbors of each waypoint in the graph.
+    neighbors: Vec<Vec<usize>>,
+    /// Time of the last visit of each waypoint by the robot.
+    last_visits: Vec<f32>,
+    target: Option<usize>,
+    visits: usize,
+    last_time: f32,
+    /// Last error, stored to make the [`PatrolRecord`]
+    error: ControllerError,
+}
+
+impl Patrol {
+    /// Makes a [`Patrol`] from the given config.
+    pub fn from_config(config: &PatrolConfig, initial_time: f32) -> Self {
+        let nb_waypoints = config.waypoints.len();
+        let edges: Vec<[usize; 2]> = if config.edges.is_empty() && nb_waypoints > 1 {
+            (0..nb_waypoints)
+                .map(|i| [i, (i + 1) % nb_waypoints])
+                .collect()
+        } else {
+            config.edges.clone()
+        };
+        let mut neighbors = vec![Vec::new(); nb_waypoints];
+        for [a, b] in edges {
+            if !neighbors[a].contains(&b) {
+                neighbors[a].push(b);
+                neighbors[b].push(a);
+            }
+        }
+        for list in neighbors.iter_mut() {
+            list.sort();
+        }
+        Self {
+            config: config.clone(),
+            neighbors,
+            last_visits: vec![initial_time; nb_waypoints],
+            target: None,
+            visits: 0,
+            last_time: initial_time,
+            error: ControllerError::default(),
+        }
+    }
+
+    /// Waypoint to visit after `current`, according to the strategy. Among equally idle
+    /// neighbors, the first one is chosen.
+    fn next_waypoint(&self, current: usize) -> usize {
+        match self.config.strategy {
+            PatrolStrategy::Cyclic => (current + 1) % self.config.waypoints.len(),
+            PatrolStrategy::Conscientious => self.neighbors[current]
+                .iter()
+                .copied()
+                .min_by(|a, b| self.last_visits[*a].total_cmp(&self.last_visits[*b]))
+                .unwrap_or(current),
+        }
+    }
+}
+
+impl Navigator for Patrol {
+    /// Compute the error between the given `state` and the current waypoint.
+    fn compute_error(&mut self, _robot: &mut Node, world_state: WorldState) -> ControllerError {
+        if world_state.ego.is_none() {
+            panic!("StateEstimator should provide an ego estimate for Patrol navigator.")
+        }
+        let state = world_state.ego.unwrap().theta_modulo();
+        let position = Vector2::new(state.pose.x, state.pose.y);
+        let speed = state.velocity.fixed_rows::<2>(0).norm();
+        if self.config.waypoints.is_empty() {
+            self.error = ControllerError {
+                velocity: -speed,
+                ..Default::default()
+            };
+            return self.error.clone();
+        }
+        let distance =
+            |i: usize| (Vector2::from_row_slice(&self.config.waypoints[i]) - position).norm();
+
+        let mut target = self.target.unwrap_or_else(|| {
+            (0..self.config.waypoints.len())
+                .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+                .unwrap()
+        });
+        if distance(target) < self.config.visit_radius {
+            self.last_visits[target] = self.last_time;
+            self.visits += 1;
+            target = self.next_waypoint(target);
+        }
+        self.target = Some(target);
+
+        let target_point = Vector2::from_row_slice(&self.config.waypoints[target]);
+        let target_speed = if distance(target) < self.config.visit_radius {
+            // Single waypoint, or no neighbor: stay on it
+            0.
+        } else {
+            self.config.target_speed
+        };
+        self.error.theta = smallest_theta_diff(
+            (target_point.y - position.y).atan2(target_point.x - position.x),
+            state.pose.z,
+        );
+        // Target point projected in the robot frame
+        let relative_target = nalgebra::Rotation2::new(-state.pose.z) * (target_point - position);
+        self.error.longitudinal = relative_target.x;
+        self.error.lateral = relative_target.y;
+        self.error.velocity = target_speed - speed;
+
+        self.error.clone()
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, time: f32) {
+        self.last_time = time;
+    }
+}
+
+impl Recordable<NavigatorRecord> for Patrol {
+    fn record(&self) -> NavigatorRecord {
+        NavigatorRecord::Patrol(PatrolRecord {
+            error: self.error.clone(),
+            target: self.target,
+            target_point: self.target.map(|i| self.config.waypoints[i]),
+            visits: self.visits,
+            idleness: self
+                .last_visits
+                .iter()
+                .map(|last_visit| self.last_time - last_visit)
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_waypoint() {
+        let mut config = PatrolConfig {
+            waypoints: vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.]],
+            edges: vec![[0, 1], [0, 2], [0, 3]],
+            ..Default::default()
+        };
+        let mut patrol = Patrol::from_config(&config, 0.);
+        // All equally idle: first neighbor
+        assert_eq!(patrol.next_waypoint(0), 1);
+        patrol.last_visits = vec![0., 4., 2., 3.];
+        assert_eq!(patrol.next_waypoint(0), 2);
+        assert_eq!(patrol.next_waypoint(3), 0);
+
+        config.edges.clear();
+        let patrol = Patrol::from_config(&config, 0.);
+        assert_eq!(patrol.neighbors[0], vec![1, 3]);
+
+        config.strategy = PatrolStrategy::Cyclic;
+        let patrol = Patrol::from_config(&config, 0.);
+        assert_eq!(patrol.next_waypoint(3), 0);
+    }
+}
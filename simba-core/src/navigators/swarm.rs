@@ -0,0 +1,431 @@
+//! Swarm [`Navigator`] baselines.
+//!
+//! [`Swarm`] steers the robot from the states of its neighbors, i.e. the other nodes of the
+//! [`WorldState::objects`] estimate (filled by the state estimator from the robot sensor or the
+//! network) closer than `neighbor_radius`. Three classic behaviors are provided:
+//! - [`SwarmBehavior::Flocking`]: Reynolds boids, a weighted sum of separation (avoid the close
+//!   neighbors), alignment (match the heading of the neighbors) and cohesion (go to their
+//!   centroid),
+//! - [`SwarmBehavior::Dispersion`]: move away from the neighbors until none is in range,
+//! - [`SwarmBehavior::Aggregation`]: gather at the centroid of the neighbors, keeping the
+//!   separation distance.
+//!
+//! The weighted rules give a desired direction in world frame, converted into the
+//! [`ControllerError`] like the [`GoTo`](super::go_to::GoTo) navigator does for a target point.
+
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{UIComponent, utils::string_combobox},
+    simulator::SimulatorConfig,
+    utils::enum_tools::ToVec,
+};
+
+use crate::{
+    controllers::ControllerError,
+    navigators::{Navigator, NavigatorRecord},
+    node::Node,
+    recordable::Recordable,
+    state_estimators::WorldState,
+    utils::geometry::smallest_theta_diff,
+};
+
+use config_checker::*;
+use nalgebra::Vector2;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Swarm behavior of the [`Swarm`] navigator.
+#[config_derives]
+pub enum SwarmBehavior {
+    /// Reynolds flocking: separation, alignment and cohesion.
+    Flocking,
+    /// Move away from the neighbors.
+    Dispersion,
+    /// Gather at the centroid of the neighbors.
+    Aggregation,
+}
+
+impl Default for SwarmBehavior {
+    fn default() -> Self {
+        Self::Flocking
+    }
+}
+
+/// Configuration of the [`Swarm`] strategy.
+///
+/// ```yaml
+/// navigator:
+///   type: Swarm
+///   behavior:
+///     type: Flocking
+///   neighbor_radius: 5.
+///   separation_distance: 1.
+///   separation_weight: 1.5
+///   alignment_weight: 1.
+///   cohesion_weight: 1.
+///   target_speed: 0.5
+/// ```
+///
+/// The weights of the rules not used by the behavior are ignored: dispersion only uses the
+/// separation (with `neighbor_radius` as distance), aggregation uses the separation and the
+/// cohesion.
+#[config_derives]
+pub struct SwarmConfig {
+    /// Swarm behavior.
+    ///
+    /// Default: `Flocking`.
+    pub behavior: SwarmBehavior,
+    /// Distance under which the other nodes are neighbors, in meters.
+    ///
+    /// Default: `5`.
+    pub neighbor_radius: f32,
+    /// Distance to keep from the neighbors, in meters.
+    ///
+    /// Default: `1`.
+    pub separation_distance: f32,
+    /// Weight of the separation rule.
+    ///
+    /// Default: `1.5`.
+    pub separation_weight: f32,
+    /// Weight of the alignment rule (flocking only).
+    ///
+    /// Default: `1`.
+    pub alignment_weight: f32,
+    /// Weight of the cohesion rule.
+    ///
+    /// Default: `1`.
+    pub cohesion_weight: f32,
+    /// Linear speed in m/s. For dispersion and aggregation, the speed is reduced when the rules
+    /// are almost satisfied.
+    ///
+    /// Default: `0.5`.
+    pub target_speed: f32,
+}
+
+impl Default for SwarmConfig {
+    fn default() -> Self {
+        Self {
+            behavior: SwarmBehavior::default(),
+            neighbor_radius: 5.,
+            separation_distance: 1.,
+            separation_weight: 1.5,
+            alignment_weight: 1.,
+            cohesion_weight: 1.,
+            target_speed: 0.5,
+        }
+    }
+}
+
+impl Check for SwarmConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.neighbor_radius <= 0. {
+            errs.push("Neighbor radius should be strictly positive".to_string());
+        }
+        if self.separation_distance <= 0. {
+            errs.push("Separation distance should be strictly positive".to_string());
+        }
+        if self.separation_distance > self.neighbor_radius {
+            errs.push(format!(
+                "Separation distance ({}) should be smaller than the neighbor radius ({})",
+                self.separation_distance, self.neighbor_radius
+            ));
+        }
+        if self.separation_weight < 0. || self.alignment_weight < 0. || self.cohesion_weight < 0. {
+            errs.push("Swarm weights should be positive".to_string());
+        }
+        if self.target_speed < 0. {
+            errs.push("Target speed should be positive".to_string());
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for SwarmConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Swarm")
+            .id_salt(format!("swarm-{}", unique_id))
+            .show(ui, |ui| {
+                let mut current_str = self.behavior.to_string();
+                ui.horizontal(|ui| {
+                    ui.label("Behavior: ");
+                    string_combobox(
+                        ui,
+                        &SwarmBehavior::to_vec(),
+                        &mut current_str,
+                        format!("swarm-behavior-choice-{}", unique_id),
+                    );
+                });
+                if current_str != self.behavior.to_string() {
+                    match current_str.as_str() {
+                        "Flocking" => self.behavior = SwarmBehavior::Flocking,
+                        "Dispersion" => self.behavior = SwarmBehavior::Dispersion,
+                        "Aggregation" => self.behavior = SwarmBehavior::Aggregation,
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Neighbor radius:");
+                    ui.add(egui::DragValue::new(&mut self.neighbor_radius).range(0.001..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Separation distance:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.separation_distance)
+                            .range(0.001..=self.neighbor_radius),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Weights (separation, alignment, cohesion):");
+                    ui.add(egui::DragValue::new(&mut self.separation_weight).range(0.0..=f32::MAX));
+                    ui.add(egui::DragValue::new(&mut self.alignment_weight).range(0.0..=f32::MAX));
+                    ui.add(egui::DragValue::new(&mut self.cohesion_weight).range(0.0..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Target speed:");
+                    ui.add(egui::DragValue::new(&mut self.target_speed).range(0.0..=f32::MAX));
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Swarm")
+            .id_salt(format!("swarm-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Behavior: {}", self.behavior));
+                ui.label(format!("Neighbor radius: {}", self.neighbor_radius));
+                ui.label(format!("Separation distance: {}", self.separation_distance));
+                ui.label(format!(
+                    "Weights: separation {}, alignment {}, cohesion {}",
+                    self.separation_weight, self.alignment_weight, self.cohesion_weight
+                ));
+                ui.label(format!("Target speed: {}", self.target_speed));
+            });
+    }
+}
+
+/// Record of the [`Swarm`] navigator.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SwarmRecord {
+    /// Current error
+    pub error: ControllerError,
+    /// Neighbors used at the last step.
+    pub neighbors: Vec<String>,
+    /// Desired direction in world frame `[x, y]`, scaled by the strength of the rules.
+    pub desired_direction: [f32; 2],
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for SwarmRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("Error").show(ui, |ui| {
+                self.error.show(ui, ctx, unique_id);
+            });
+            ui.label(format!("Neighbors: {}", self.neighbors.join(", ")));
+            ui.label(format!(
+                "Desired direction: ({}, {})",
+                self.desired_direction[0], self.desired_direction[1]
+            ));
+        });
+    }
+}
+
+/// Neighbor seen by the [`Swarm`] navigator, in world frame.
+struct Neighbor {
+    position: Vector2<f32>,
+    heading: f32,
+}
+
+/// [`Navigator`] strategy following a swarm behavior.
+#[derive(Debug)]
+pub struct Swarm {
+    config: SwarmConfig,
+    /// Last error, stored to make the [`SwarmRecord`]
+    error: ControllerError,
+    neighbors: Vec<String>,
+    desired_direction: Vector2<f32>,
+}
+
+impl Swarm {
+    /// Makes a [`Swarm`] from the given config.
+    pub fn from_config(config: &SwarmConfig, _initial_time: f32) -> Self {
+        Self {
+            config: config.clone(),
+            error: ControllerError::default(),
+            neighbors: Vec::new(),
+            desired_direction: Vector2::zeros(),
+        }
+    }
+
+    /// Repulsion from the neighbors closer than `distance`, stronger when closer.
+    fn separation(position: &Vector2<f32>, neighbors: &[Neighbor], distance: f32) -> Vector2<f32> {
+        neighbors
+            .iter()
+            .filter_map(|neighbor| {
+                let away = position - neighbor.position;
+                let d = away.norm();
+                (d < distance && d > 0.).then(|| away / d * (distance - d) / distance)
+            })
+            .sum()
+    }
+
+    /// Desired direction of the robot at `position`, scaled by the strength of the rules: a
+    /// norm of 1 or more is a full speed move.
+    fn desired_direction(&self, position: &Vector2<f32>, neighbors: &[Neighbor]) -> Vector2<f32> {
+        if neighbors.is_empty() {
+            return Vector2::zeros();
+        }
+        let centroid = neighbors
+            .iter()
+            .map(|neighbor| neighbor.position)
+            .sum::<Vector2<f32>>()
+            / neighbors.len() as f32;
+        let cohesion = (centroid - position) / self.config.neighbor_radius;
+        match self.config.behavior {
+            SwarmBehavior::Flocking => {
+                let alignment = neighbors
+                    .iter()
+                    .map(|neighbor| Vector2::new(neighbor.heading.cos(), neighbor.heading.sin()))
+                    .sum::<Vector2<f32>>()
+                    / neighbors.len() as f32;
+                Self::separation(position, neighbors, self.config.separation_distance)
+                    * self.config.separation_weight
+                    + alignment * self.config.alignment_weight
+                    + cohesion * self.config.cohesion_weight
+            }
+            SwarmBehavior::Dispersion => {
+                Self::separation(position, neighbors, self.config.neighbor_radius)
+                    * self.config.separation_weight
+            }
+            SwarmBehavior::Aggregation => {
+                // No attraction once the centroid is within the separation distance
+                let cohesion = if (centroid - position).norm() > self.config.separation_distance {
+                    cohesion
+                } else {
+                    Vector2::zeros()
+                };
+                Self::separation(position, neighbors, self.config.separation_distance)
+                    * self.config.separation_weight
+                    + cohesion * self.config.cohesion_weight
+            }
+        }
+    }
+}
+
+impl Navigator for Swarm {
+    /// Compute the error between the given `state` and the swarm direction.
+    fn compute_error(&mut self, _robot: &mut Node, world_state: WorldState) -> ControllerError {
+        if world_state.ego.is_none() {
+            panic!("StateEstimator should provide an ego estimate for Swarm navigator.")
+        }
+        let state = world_state.ego.unwrap().theta_modulo();
+        let position = Vector2::new(state.pose.x, state.pose.y);
+
+        let (names, neighbors): (Vec<_>, Vec<_>) = world_state
+            .objects
+            .iter()
+            .filter(|(_, object)| {
+                (Vector2::new(object.pose.x, object.pose.y) - position).norm()
+                    < self.config.neighbor_radius
+            })
+            .map(|(name, object)| {
+                (
+                    name.clone(),
+                    Neighbor {
+                        position: Vector2::new(object.pose.x, object.pose.y),
+                        heading: object.pose.z,
+                    },
+                )
+            })
+            .unzip();
+        self.neighbors = names;
+        self.desired_direction = self.desired_direction(&position, &neighbors);
+
+        let strength = self.desired_direction.norm();
+        let (target_speed, direction) = match self.config.behavior {
+            // A flock keeps moving, in its current heading if the rules balance out
+            SwarmBehavior::Flocking if strength < f32::EPSILON => (
+                self.config.target_speed,
+                Vector2::new(state.pose.z.cos(), state.pose.z.sin()),
+            ),
+            SwarmBehavior::Flocking => {
+                (self.config.target_speed, self.desired_direction / strength)
+            }
+            _ if strength < f32::EPSILON => {
+                (0., Vector2::new(state.pose.z.cos(), state.pose.z.sin()))
+            }
+            _ => (
+                self.config.target_speed * strength.min(1.),
+                self.desired_direction / strength,
+            ),
+        };
+
+        self.error.theta = smallest_theta_diff(direction.y.atan2(direction.x), state.pose.z);
+        // Direction projected in the robot frame
+        let relative_direction = nalgebra::Rotation2::new(-state.pose.z) * direction;
+        self.error.longitudinal = relative_direction.x;
+        self.error.lateral = relative_direction.y;
+        self.error.velocity = target_speed - state.velocity.fixed_rows::<2>(0).norm();
+
+        self.error.clone()
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+}
+
+impl Recordable<NavigatorRecord> for Swarm {
+    fn record(&self) -> NavigatorRecord {
+        NavigatorRecord::Swarm(SwarmRecord {
+            error: self.error.clone(),
+            neighbors: self.neighbors.clone(),
+            desired_direction: [self.desired_direction.x, self.desired_direction.y],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neighbor(x: f32, y: f32, heading: f32) -> Neighbor {
+        Neighbor {
+            position: Vector2::new(x, y),
+            heading,
+        }
+    }
+
+    #[test]
+    fn behaviors_direction() {
+        let position = Vector2::new(0., 0.);
+        let neighbors = [neighbor(0.5, 0., 0.), neighbor(3., 0., 0.)];
+        let mut swarm = Swarm::from_config(
+            &SwarmConfig {
+                behavior: SwarmBehavior::Dispersion,
+                ..Default::default()
+            },
+            0.,
+        );
+        // Away from both neighbors
+        assert!(swarm.desired_direction(&position, &neighbors).x < 0.);
+
+        swarm.config.behavior = SwarmBehavior::Aggregation;
+        // Toward the centroid, at 1.75 m
+        assert!(swarm.desired_direction(&position, &neighbors).x > 0.);
+
+        swarm.config.behavior = SwarmBehavior::Flocking;
+        swarm.config.separation_weight = 0.;
+        swarm.config.cohesion_weight = 0.;
+        let alignment = swarm.desired_direction(&position, &[neighbor(3., 0., 1.)]);
+        assert!((alignment.y.atan2(alignment.x) - 1.).abs() < 1e-5);
+    }
+}
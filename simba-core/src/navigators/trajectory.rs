@@ -94,6 +94,51 @@ impl Trajectory {
         trajectory
     }
 
+    /// Close the loop (return to the first point after the last one) or not.
+    pub fn set_loop(&mut self, do_loop: bool) {
+        self.do_loop = do_loop;
+    }
+
+    /// Reverse the order of the points. The matching restarts at the first segment.
+    pub fn reverse(&mut self) {
+        let nrows = self.point_list.nrows();
+        self.point_list = DMatrix::from_fn(nrows, 2, |i, j| self.point_list[(nrows - 1 - i, j)]);
+        self.current_segment = 0;
+    }
+
+    /// Start the matching at the segment at the curvilinear distance `offset` from the first
+    /// point. With a loop, the offset is taken modulo the trajectory length, else the matching
+    /// starts at the last segment if the offset is larger than the trajectory.
+    pub fn set_start_offset(&mut self, offset: f32) {
+        let nrows = self.point_list.nrows();
+        let nb_segments = if self.do_loop {
+            nrows
+        } else {
+            nrows.saturating_sub(1)
+        };
+        let lengths: Vec<f32> = (0..nb_segments)
+            .map(|i| {
+                (self.point_list.fixed_view::<1, 2>((i + 1) % nrows, 0)
+                    - self.point_list.fixed_view::<1, 2>(i, 0))
+                .norm()
+            })
+            .collect();
+        let total_length: f32 = lengths.iter().sum();
+        let mut remaining = if self.do_loop && total_length > 0. {
+            offset.rem_euclid(total_length)
+        } else {
+            offset
+        };
+        self.current_segment = nb_segments.saturating_sub(1);
+        for (i, length) in lengths.iter().enumerate() {
+            if remaining < *length {
+                self.current_segment = i;
+                break;
+            }
+            remaining -= length;
+        }
+    }
+
     /// Match a point on the polyline, and returns the projected point and the segment.
     ///
     /// The segment privilieged is the previous matched segment. If the next segment is
@@ -254,4 +299,28 @@ mod tests {
             assert_eq!(row[1], config.point_list[i][1]);
         }
     }
+
+    #[test]
+    fn reverse_and_start_offset() {
+        let config = TrajectoryConfig {
+            point_list: vec![vec![0., 0.], vec![2., 0.], vec![2., 1.], vec![0., 1.]],
+            do_loop: true,
+        };
+        let mut trajectory = Trajectory::from_config(&config);
+        trajectory.set_start_offset(2.5);
+        assert_eq!(trajectory.current_segment, 1);
+        // Loop length is 6
+        trajectory.set_start_offset(6.5);
+        assert_eq!(trajectory.current_segment, 0);
+        trajectory.set_loop(false);
+        trajectory.set_start_offset(10.);
+        assert_eq!(trajectory.current_segment, 2);
+
+        trajectory.reverse();
+        assert_eq!(trajectory.current_segment, 0);
+        assert_eq!(trajectory.point_list[(0, 0)], 0.);
+        assert_eq!(trajectory.point_list[(0, 1)], 1.);
+        assert_eq!(trajectory.point_list[(3, 0)], 0.);
+        assert_eq!(trajectory.point_list[(3, 1)], 0.);
+    }
 }
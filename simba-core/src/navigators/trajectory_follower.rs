@@ -10,12 +10,16 @@
 use crate::gui::{UIComponent, utils::path_finder};
 
 use crate::{
+    errors::SimbaResult,
     navigators::{
         Navigator, NavigatorRecord,
         trajectory::{Trajectory, TrajectoryConfig, TrajectoryRecord},
     },
     simulator::SimulatorConfig,
-    utils::geometry::{mod2pi, smallest_theta_diff},
+    utils::{
+        config_parsing::load_config_file,
+        geometry::{mod2pi, smallest_theta_diff},
+    },
 };
 
 extern crate nalgebra as na;
@@ -39,6 +43,21 @@ use std::path::Path;
 /// The robot will try to reach a target speed of `target_speed` m/s, which is reduced when the robot gets
 /// closer to the end of the trajectory, starting from `stop_distance` meters to the end, using a ramp
 /// coefficient of `stop_ramp_coefficient`.
+///
+/// The same trajectory file can be reused for repetitive experiments: `do_loop` overrides the
+/// looping of the file, `reverse` follows the points in the reverse order, `speed_factor` scales
+/// the target speed, and `start_offset` starts the following at a given distance along the
+/// trajectory.
+///
+/// ```yaml
+/// navigator:
+///   type: TrajectoryFollower
+///   trajectory_path: patrol.yaml
+///   do_loop: true
+///   reverse: true
+///   speed_factor: 1.5
+///   start_offset: 10.0
+/// ```
 #[config_derives]
 pub struct TrajectoryFollowerConfig {
     /// Path of the trajectory configuration file.
@@ -65,6 +84,22 @@ pub struct TrajectoryFollowerConfig {
     /// Default: `0.5`, meaning that the target speed will be reduced to 0.5 times the remaining distance when
     /// the robot is within `stop_distance` of the end of the trajectory.
     pub stop_ramp_coefficient: f32,
+    /// Close the loop of the trajectory or not, overriding the `do_loop` of the trajectory file.
+    ///
+    /// Default: `None`, the value of the trajectory file is used.
+    pub do_loop: Option<bool>,
+    /// Follow the trajectory points in the reverse order.
+    ///
+    /// Default: `false`.
+    pub reverse: bool,
+    /// Factor applied to `target_speed`, to replay the same trajectory faster or slower.
+    ///
+    /// Default: `1.0`.
+    pub speed_factor: f32,
+    /// Curvilinear distance along the trajectory (after reversing) where to start, in meters.
+    ///
+    /// Default: `0.0`.
+    pub start_offset: f32,
 }
 
 impl Check for TrajectoryFollowerConfig {
@@ -82,6 +117,12 @@ impl Check for TrajectoryFollowerConfig {
         if self.stop_ramp_coefficient < 0. {
             errs.push("Stop ramp coefficient should be positive".to_string());
         }
+        if self.speed_factor <= 0. {
+            errs.push("Speed factor should be strictly positive".to_string());
+        }
+        if self.start_offset < 0. {
+            errs.push("Start offset should be positive".to_string());
+        }
         if errs.is_empty() { Ok(()) } else { Err(errs) }
     }
 }
@@ -94,6 +135,10 @@ impl Default for TrajectoryFollowerConfig {
             target_speed: 0.5,
             stop_distance: 0.2,
             stop_ramp_coefficient: 0.5,
+            do_loop: None,
+            reverse: false,
+            speed_factor: 1.0,
+            start_offset: 0.,
         }
     }
 }
@@ -148,6 +193,41 @@ impl UIComponent for TrajectoryFollowerConfig {
                     }
                     ui.add(egui::DragValue::new(&mut self.stop_ramp_coefficient).max_decimals(10));
                 });
+
+                ui.horizontal(|ui| {
+                    ui.label("Loop:");
+                    if let Some(do_loop) = &mut self.do_loop {
+                        ui.checkbox(do_loop, "");
+                        if ui.button("X").clicked() {
+                            self.do_loop = None;
+                        }
+                    } else {
+                        ui.label("from file");
+                        if ui.button("+").clicked() {
+                            self.do_loop = Some(true);
+                        }
+                    }
+                });
+
+                ui.checkbox(&mut self.reverse, "Reverse");
+
+                ui.horizontal(|ui| {
+                    ui.label("Speed factor:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.speed_factor)
+                            .range(0.001..=f32::MAX)
+                            .max_decimals(10),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Start offset:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.start_offset)
+                            .range(0.0..=f32::MAX)
+                            .max_decimals(10),
+                    );
+                });
             });
     }
 
@@ -177,6 +257,15 @@ impl UIComponent for TrajectoryFollowerConfig {
                         self.stop_ramp_coefficient
                     ));
                 });
+
+                ui.label(format!(
+                    "Loop: {}",
+                    self.do_loop
+                        .map_or("from file".to_string(), |l| l.to_string())
+                ));
+                ui.label(format!("Reverse: {}", self.reverse));
+                ui.label(format!("Speed factor: {}", self.speed_factor));
+                ui.label(format!("Start offset: {} m", self.start_offset));
             });
     }
 }
@@ -268,23 +357,42 @@ impl TrajectoryFollower {
     /// * `global_config` - Global configuration of the simulator. Used there to get the
     ///   path of the config, used as relative reference for the trajectory path.
     /// * `initial_time` - Initial simulation time, in seconds. Not used by this navigator, but provided for consistency with other navigators and potential future use.
+    ///
+    /// Returns an error if the trajectory file cannot be read or parsed (a missing file is an
+    /// error, no default file is created).
     pub fn from_config(
         config: &TrajectoryFollowerConfig,
         global_config: &SimulatorConfig,
         _initial_time: f32,
-    ) -> Self {
+    ) -> SimbaResult<Self> {
         let mut path = Path::new(&config.trajectory_path);
         if config.trajectory_path.is_empty() {
-            return Self::new();
+            return Ok(Self::new());
         }
         let joined_path = global_config.base_path.join(&config.trajectory_path);
         if path.is_relative() {
             path = joined_path.as_path();
         }
+        Ok(Self::from_trajectory(
+            Self::load_trajectory_from_path(path)?,
+            config,
+        ))
+    }
+
+    /// Makes a [`TrajectoryFollower`] following the given `trajectory`, with the parameters of
+    /// `config`. The `trajectory_path` of the config is ignored.
+    pub fn from_trajectory(mut trajectory: Trajectory, config: &TrajectoryFollowerConfig) -> Self {
+        if let Some(do_loop) = config.do_loop {
+            trajectory.set_loop(do_loop);
+        }
+        if config.reverse {
+            trajectory.reverse();
+        }
+        trajectory.set_start_offset(config.start_offset);
         TrajectoryFollower {
-            trajectory: Self::load_trajectory_from_path(path),
+            trajectory,
             forward_distance: config.forward_distance,
-            target_speed: config.target_speed,
+            target_speed: config.target_speed * config.speed_factor,
             error: ControllerError::default(),
             projected_point: [0., 0.],
             stop_distance: config.stop_distance,
@@ -294,19 +402,9 @@ impl TrajectoryFollower {
 
     /// Load the trajectory from the given `path`. This file should be compatible
     /// with [`TrajectoryConfig`].
-    fn load_trajectory_from_path(path: &Path) -> Trajectory {
-        let trajectory: TrajectoryConfig = match confy::load_path(path) {
-            Ok(config) => config,
-            Err(error) => {
-                println!(
-                    "Error from Confy while loading the trajectory file {} : {}",
-                    path.display(),
-                    error
-                );
-                return Trajectory::new();
-            }
-        };
-        Trajectory::from_config(&trajectory)
+    fn load_trajectory_from_path(path: &Path) -> SimbaResult<Trajectory> {
+        let trajectory: TrajectoryConfig = load_config_file(path, "trajectory")?;
+        Ok(Trajectory::from_config(&trajectory))
     }
 }
 
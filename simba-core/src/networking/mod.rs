@@ -22,10 +22,14 @@ use pyo3::{pyclass, pymethods};
 use serde::{Deserialize, Serialize};
 use simba_macros::EnumToString;
 
-use crate::{navigators::go_to::GoToMessage, sensors::sensor_manager::SensorTriggerMessage};
+use crate::{
+    navigators::go_to::GoToMessage, networking::reconfigure::ReconfigureMessage,
+    sensors::sensor_manager::SensorTriggerMessage, state_estimators::map_merging::MapShareMessage,
+};
 
 pub mod network;
 pub mod network_manager;
+pub mod reconfigure;
 pub mod service;
 pub mod service_manager;
 
@@ -56,6 +60,10 @@ pub enum MessageTypes {
     GoTo(GoToMessage),
     /// Sensor event payload used by [`SensorTriggerMessage`].
     SensorTrigger(SensorTriggerMessage),
+    /// Runtime reconfiguration payload used by [`ReconfigureMessage`].
+    Reconfigure(ReconfigureMessage),
+    /// Map sharing payload used by [`MapShareMessage`].
+    MapShare(MapShareMessage),
 }
 
 #[pymethods]
@@ -72,6 +80,18 @@ impl MessageTypes {
         MessageTypes::SensorTrigger(message)
     }
 
+    /// Creates a [`MessageTypes::Reconfigure`] from a [`ReconfigureMessage`].
+    #[staticmethod]
+    pub fn from_reconfigure(message: ReconfigureMessage) -> Self {
+        MessageTypes::Reconfigure(message)
+    }
+
+    /// Creates a [`MessageTypes::MapShare`] from a [`MapShareMessage`].
+    #[staticmethod]
+    pub fn from_map_share(message: MapShareMessage) -> Self {
+        MessageTypes::MapShare(message)
+    }
+
     /// Returns the contained [`GoToMessage`] when this value is [`MessageTypes::GoTo`].
     pub fn as_goto(&self) -> Option<GoToMessage> {
         match self {
@@ -89,6 +109,24 @@ impl MessageTypes {
         }
     }
 
+    /// Returns the contained [`ReconfigureMessage`] when this value is
+    /// [`MessageTypes::Reconfigure`].
+    pub fn as_reconfigure(&self) -> Option<ReconfigureMessage> {
+        match self {
+            MessageTypes::Reconfigure(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the contained [`MapShareMessage`] when this value is
+    /// [`MessageTypes::MapShare`].
+    pub fn as_map_share(&self) -> Option<MapShareMessage> {
+        match self {
+            MessageTypes::MapShare(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+
     /// Returns the variant discriminator as a string.
     #[getter]
     pub fn kind(&self) -> String {
@@ -96,6 +134,20 @@ impl MessageTypes {
     }
 }
 
+impl MessageTypes {
+    /// Payload of the [`Envelope`](network::Envelope) carrying this message: the inner message,
+    /// without the variant.
+    pub fn to_payload(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            MessageTypes::String(s) => serde_json::to_value(s),
+            MessageTypes::GoTo(m) => serde_json::to_value(m),
+            MessageTypes::SensorTrigger(m) => serde_json::to_value(m),
+            MessageTypes::Reconfigure(m) => serde_json::to_value(m),
+            MessageTypes::MapShare(m) => serde_json::to_value(m),
+        }
+    }
+}
+
 /// Definition of channels managed by the simulator.
 pub mod channels {
     /// Root of the internal channels, used for internal communication between nodes and the simulator.
@@ -120,6 +172,12 @@ pub mod channels {
         pub const NODE: &str = concat!(super::INTERNAL, "/nodes");
         /// Root path for simulator command channels (special messages such as kill).
         pub const COMMAND: &str = concat!(super::INTERNAL, "/command");
+        /// Root path for node reconfiguration channels, RECONFIGURE/<node_name>, carrying
+        /// `NodeReconfigureMessage`s.
+        pub const RECONFIGURE: &str = concat!(super::INTERNAL, "/reconfigure");
+        /// Channel of the outcomes of the reconfiguration messages sent with the `Acknowledge`
+        /// flag, carrying `ReconfigurationOutcome`s.
+        pub const RECONFIGURE_OUTCOME: &str = concat!(super::INTERNAL, "/reconfigure_outcome");
     }
 }
 
@@ -296,6 +354,9 @@ mod tests {
                 config: StateEstimatorConfig::External(ExternalEstimatorConfig {
                     config: Value::Bool(false),
                 }),
+                sensors: None,
+                watchdog: None,
+                warm_start: None,
             }],
             ..Default::default()
         });
@@ -310,6 +371,9 @@ mod tests {
                 config: StateEstimatorConfig::External(ExternalEstimatorConfig {
                     config: Value::Bool(true),
                 }),
+                sensors: None,
+                watchdog: None,
+                warm_start: None,
             }],
             ..Default::default()
         });
@@ -10,28 +10,41 @@
 //!
 //! [`NetworkConfig`] defaults are:
 //! - `range = 0.0`: no distance filtering;
-//! - `reception_delay = 0.0`: no additional reception delay.
+//! - `reception_delay = 0.0`: no additional reception delay;
+//! - `processing_cost = 0.0`: free message handling;
+//! - `link_model`: ideal links (no loss, no latency), see [`LinkModelConfig`].
 
 extern crate confy;
 use core::f32;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
 use config_checker::*;
 use log::debug;
 use pyo3::pyclass;
 use serde_derive::{Deserialize, Serialize};
 use serde_json::Value;
-use simba_com::pub_sub::{BrokerTrait, BrokerTraitExtended, PathKey};
+use simba_com::pub_sub::{
+    BrokerTrait, BrokerTraitExtended, BrokerTraitProcessing, ChannelLimits, PathKey, ProcessingLoad,
+};
 use simba_macros::config_derives;
 
+use crate::constants::TIME_ROUND;
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::text_singleline_with_apply};
 use crate::logger::is_enabled;
 use crate::networking::channels;
+use crate::recordable::Recordable;
 use crate::simulator::{SimbaBroker, SimbaBrokerMultiClient, SimulatorConfig};
 use crate::utils::SharedRwLock;
-use crate::utils::determinist_random_variable::DeterministRandomVariableFactory;
-#[cfg(feature = "gui")]
-use crate::{constants::TIME_ROUND, gui::UIComponent};
+use crate::utils::determinist_random_variable::{
+    DeterministRandomVariable, DeterministRandomVariableFactory, RandomVariableTypeConfig,
+};
+use crate::utils::distributions::uniform::UniformRandomVariableConfig;
+use crate::utils::lock_recovery::RecoverMutex;
 
 /// Configuration for the [`Network`].
 #[config_derives]
@@ -44,6 +57,14 @@ pub struct NetworkConfig {
     ///
     /// Use `0.0` for no additional delay (default: `0.0`).
     pub reception_delay: f32,
+    /// Simulated time spent by the node to handle each received message.
+    ///
+    /// The next messages are received when the node is done with the previous ones, the
+    /// induced delays are recorded. Use `0.0` for free message handling (default: `0.0`).
+    pub processing_cost: f32,
+    /// Quality of the links (message losses and latency) of the channels created by the node.
+    #[check]
+    pub link_model: LinkModelConfig,
 }
 
 impl Check for NetworkConfig {
@@ -58,6 +79,12 @@ impl Check for NetworkConfig {
                 self.reception_delay
             ));
         }
+        if self.processing_cost < 0. {
+            errors.push(format!(
+                "Processing_cost should be positive, got {}",
+                self.processing_cost
+            ));
+        }
         if errors.is_empty() {
             Ok(())
         } else {
@@ -71,6 +98,8 @@ impl Default for NetworkConfig {
         Self {
             range: 0.,
             reception_delay: 0.,
+            processing_cost: 0.,
+            link_model: LinkModelConfig::default(),
         }
     }
 }
@@ -80,11 +109,11 @@ impl UIComponent for NetworkConfig {
     fn show_mut(
         &mut self,
         ui: &mut egui::Ui,
-        _ctx: &egui::Context,
-        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
-        _global_config: &SimulatorConfig,
-        _current_node_name: Option<&String>,
-        _unique_id: &str,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
     ) {
         egui::CollapsingHeader::new("Network").show(ui, |ui| {
             ui.horizontal(|ui| {
@@ -105,10 +134,30 @@ impl UIComponent for NetworkConfig {
                         .max_decimals((1. / TIME_ROUND) as usize),
                 );
             });
+
+            ui.horizontal(|ui| {
+                ui.label("Processing cost per message: ");
+                if self.processing_cost < 0. {
+                    self.processing_cost = 0.;
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.processing_cost)
+                        .max_decimals((1. / TIME_ROUND) as usize),
+                );
+            });
+
+            self.link_model.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            );
         });
     }
 
-    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
         egui::CollapsingHeader::new("Network").show(ui, |ui| {
             ui.horizontal(|ui| {
                 ui.label(format!("Range (0 for no limit): {}", self.range));
@@ -117,10 +166,330 @@ impl UIComponent for NetworkConfig {
             ui.horizontal(|ui| {
                 ui.label(format!("Reception delay: {}", self.reception_delay));
             });
+
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Processing cost per message: {}",
+                    self.processing_cost
+                ));
+            });
+
+            self.link_model.show(ui, ctx, unique_id);
         });
     }
 }
 
+/// Drop probability of the messages sent from a node to another one.
+#[config_derives]
+pub struct LinkPairConfig {
+    /// Name of the sending node.
+    pub from: String,
+    /// Name of the receiving node.
+    pub to: String,
+    /// Probability to lose each message of this link, replacing
+    /// [`LinkModelConfig::drop_probability`].
+    pub drop_probability: f32,
+}
+
+impl Check for LinkPairConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if !(0. ..=1.).contains(&self.drop_probability) {
+            return Err(vec![format!(
+                "Drop probability of the link from `{}` to `{}` should be between 0 and 1, got {}",
+                self.from, self.to, self.drop_probability
+            )]);
+        }
+        Ok(())
+    }
+}
+
+impl Default for LinkPairConfig {
+    fn default() -> Self {
+        Self {
+            from: String::new(),
+            to: String::new(),
+            drop_probability: 0.,
+        }
+    }
+}
+
+/// Quality of the links of the channels created by a node.
+///
+/// The range limit of the links is given by [`NetworkConfig::range`]. Within range, each message
+/// is lost with the probability
+/// `drop_probability + drop_probability_per_meter * distance` (capped to 1), where the pairs of
+/// `pairs` replace `drop_probability`. The messages delivered get an additional random `latency`.
+/// The draws are deterministic: the messages of a link sent at the same time share the same draw.
+///
+/// Example:
+/// ```yaml
+/// link_model:
+///   drop_probability: 0.05
+///   drop_probability_per_meter: 0.01
+///   pairs:
+///     - from: robot1
+///       to: robot2
+///       drop_probability: 0.5
+///   latency:
+///     type: Exponential
+///     lambda: [20.]
+/// ```
+#[config_derives]
+pub struct LinkModelConfig {
+    /// Probability to lose each message (default: `0.0`).
+    pub drop_probability: f32,
+    /// Drop probability added per meter between the nodes (default: `0.0`). The nodes without
+    /// position are considered at distance 0.
+    pub drop_probability_per_meter: f32,
+    /// Drop probability of specific links (default: empty).
+    #[check]
+    pub pairs: Vec<LinkPairConfig>,
+    /// One-dimensional random variable added to the delay of each message, negative draws are
+    /// ignored (default: no latency).
+    #[check]
+    pub latency: RandomVariableTypeConfig,
+}
+
+impl Check for LinkModelConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if !(0. ..=1.).contains(&self.drop_probability) {
+            errors.push(format!(
+                "Drop_probability should be between 0 and 1, got {}",
+                self.drop_probability
+            ));
+        }
+        if self.drop_probability_per_meter < 0. {
+            errors.push(format!(
+                "Drop_probability_per_meter should be positive, got {}",
+                self.drop_probability_per_meter
+            ));
+        }
+        if self.latency.dim() > 1 {
+            errors.push(format!(
+                "Latency should be one-dimensional, got dimension {}",
+                self.latency.dim()
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for LinkModelConfig {
+    fn default() -> Self {
+        Self {
+            drop_probability: 0.,
+            drop_probability_per_meter: 0.,
+            pairs: Vec::new(),
+            latency: RandomVariableTypeConfig::None,
+        }
+    }
+}
+
+impl LinkModelConfig {
+    /// Returns `true` if the links are ideal (no loss, no latency).
+    pub fn is_ideal(&self) -> bool {
+        self.drop_probability == 0.
+            && self.drop_probability_per_meter == 0.
+            && self.pairs.iter().all(|pair| pair.drop_probability == 0.)
+            && self.latency == RandomVariableTypeConfig::None
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for LinkModelConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Link model").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Drop probability: ");
+                ui.add(
+                    egui::DragValue::new(&mut self.drop_probability)
+                        .range(0.0..=1.0)
+                        .speed(0.01),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Drop probability per meter: ");
+                ui.add(
+                    egui::DragValue::new(&mut self.drop_probability_per_meter)
+                        .range(0.0..=1.0)
+                        .speed(0.001),
+                );
+            });
+            ui.label("Links: ");
+            let mut to_remove = None;
+            for (i, pair) in self.pairs.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label("From: ");
+                    text_singleline_with_apply(
+                        ui,
+                        &format!("link-from-{i}-{unique_id}"),
+                        buffer_stack,
+                        &mut pair.from,
+                    );
+                    ui.label("To: ");
+                    text_singleline_with_apply(
+                        ui,
+                        &format!("link-to-{i}-{unique_id}"),
+                        buffer_stack,
+                        &mut pair.to,
+                    );
+                    ui.label("Drop probability: ");
+                    ui.add(
+                        egui::DragValue::new(&mut pair.drop_probability)
+                            .range(0.0..=1.0)
+                            .speed(0.01),
+                    );
+                    if ui.button("X").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_remove {
+                self.pairs.remove(i);
+            }
+            if ui.button("Add link").clicked() {
+                self.pairs.push(LinkPairConfig::default());
+            }
+            ui.horizontal_top(|ui| {
+                ui.label("Latency: ");
+                self.latency.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    &format!("latency-{unique_id}"),
+                );
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Link model").show(ui, |ui| {
+            ui.label(format!("Drop probability: {}", self.drop_probability));
+            ui.label(format!(
+                "Drop probability per meter: {}",
+                self.drop_probability_per_meter
+            ));
+            for pair in &self.pairs {
+                ui.label(format!(
+                    "Link {} -> {}: drop probability {}",
+                    pair.from, pair.to, pair.drop_probability
+                ));
+            }
+            ui.horizontal_top(|ui| {
+                ui.label("Latency: ");
+                self.latency.show(ui, ctx, &format!("latency-{unique_id}"));
+            });
+        });
+    }
+}
+
+/// Node information given to the delivery model of the channels.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkEndpoint {
+    /// Name of the node.
+    pub node: String,
+    /// Planar position of the node, `None` for the nodes without physics.
+    pub position: Option<[f32; 2]>,
+}
+
+/// Runtime link model, deciding the delivery of each message, see [`LinkModelConfig`].
+///
+/// Each directed link draws from its own random stream (`links/<from>/<to>`), so the messages
+/// sent at the same time on different links get independent draws.
+pub struct LinkModel {
+    config: LinkModelConfig,
+    va_factory: Arc<DeterministRandomVariableFactory>,
+    links: Mutex<BTreeMap<(String, String), LinkVariables>>,
+}
+
+/// Random variables of a directed link.
+struct LinkVariables {
+    draw: DeterministRandomVariable,
+    latency: DeterministRandomVariable,
+}
+
+impl fmt::Debug for LinkModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LinkModel")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LinkModel {
+    /// Builds a link model from [`LinkModelConfig`].
+    pub fn from_config(
+        config: &LinkModelConfig,
+        va_factory: &DeterministRandomVariableFactory,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            va_factory: va_factory.child("links"),
+            links: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Probability to lose a message sent from `from` to `to`.
+    pub fn drop_probability(&self, from: &LinkEndpoint, to: &LinkEndpoint) -> f32 {
+        let base = self
+            .config
+            .pairs
+            .iter()
+            .find(|pair| pair.from == from.node && pair.to == to.node)
+            .map_or(self.config.drop_probability, |pair| pair.drop_probability);
+        let distance = match (from.position, to.position) {
+            (Some(x1), Some(x2)) => ((x1[0] - x2[0]).powi(2) + (x1[1] - x2[1]).powi(2)).sqrt(),
+            _ => 0.,
+        };
+        (base + self.config.drop_probability_per_meter * distance).min(1.)
+    }
+
+    /// Draws the delivery of a message sent at `time` from `from` to `to`: `None` if the message
+    /// is lost, the additional latency otherwise.
+    pub fn delivery(&self, from: &LinkEndpoint, to: &LinkEndpoint, time: f32) -> Option<f32> {
+        let drop_probability = self.drop_probability(from, to);
+        let mut links = self.links.lock_recover();
+        let link = links
+            .entry((from.node.clone(), to.node.clone()))
+            .or_insert_with(|| {
+                let factory = self.va_factory.child(&from.node).child(&to.node);
+                LinkVariables {
+                    draw: factory.make_variable(RandomVariableTypeConfig::Uniform(
+                        UniformRandomVariableConfig {
+                            min: vec![0.],
+                            max: vec![1.],
+                        },
+                    )),
+                    latency: factory.make_variable(self.config.latency.clone()),
+                }
+            });
+        if link.draw.generate(time)[0] < drop_probability {
+            return None;
+        }
+        Some(
+            link.latency
+                .generate(time)
+                .first()
+                .map_or(0., |latency| latency.max(0.)),
+        )
+    }
+}
+
 /// Transmission mode for messages.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[pyclass(get_all, set_all, eq, eq_int)]
@@ -131,6 +500,9 @@ pub enum MessageFlag {
     Unsubscribe,
     /// Ask to terminate the receiving node.
     Kill,
+    /// Ask the receiver of a reconfiguration message to publish its outcome on the
+    /// [`RECONFIGURE_OUTCOME`](super::channels::internal::RECONFIGURE_OUTCOME) channel.
+    Acknowledge,
 }
 
 /// Transport envelope sent through broker channels.
@@ -146,6 +518,54 @@ pub struct Envelope {
     pub timestamp: f32,
     /// Optional transport flags that alter handling behavior.
     pub message_flags: Vec<MessageFlag>,
+    /// Size of the payload, computed once by [`Envelope::message_size`] and shared by the copies
+    /// of the envelope delivered to the subscribers. Reset it if the message is changed.
+    #[serde(skip)]
+    pub size: Arc<OnceLock<usize>>,
+}
+
+impl Envelope {
+    /// Size of the payload in bytes, counted against the channel bandwidth: the length of its
+    /// JSON serialization.
+    ///
+    /// The payload is serialized on the first call only, usually by the channel when the
+    /// envelope is sent: the receivers get the size without serializing it again.
+    pub fn message_size(&self) -> usize {
+        *self
+            .size
+            .get_or_init(|| serde_json::to_vec(&self.message).map_or(0, |bytes| bytes.len()))
+    }
+}
+
+/// Record of the message processing load of a [`Network`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct NetworkRecord {
+    /// Number of messages processed since the start.
+    pub processed_messages: usize,
+    /// Sum of the delays induced by the processing cost, since the start.
+    pub total_processing_delay: f32,
+    /// Largest delay induced by the processing cost.
+    pub max_processing_delay: f32,
+    /// Time until which the node is busy processing the received messages.
+    pub busy_until: f32,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for NetworkRecord {
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        egui::CollapsingHeader::new("Network").show(ui, |ui| {
+            ui.label(format!("Processed messages: {}", self.processed_messages));
+            ui.label(format!(
+                "Total processing delay: {}",
+                self.total_processing_delay
+            ));
+            ui.label(format!(
+                "Max processing delay: {}",
+                self.max_processing_delay
+            ));
+            ui.label(format!("Busy until: {}", self.busy_until));
+        });
+    }
 }
 
 /// Network interface for [`Node`](crate::node::Node).
@@ -160,8 +580,17 @@ pub struct Network {
     range: f32,
     /// Added delay to the messages at reception.
     reception_delay: f32,
+    /// Losses and latency of the channels created by the node, `None` for ideal links.
+    link_model: Option<Arc<LinkModel>>,
+    /// Handling time of the received messages, shared by the multi-clients of the node. `None`
+    /// if message handling is free.
+    processing_load: Option<Arc<Mutex<ProcessingLoad>>>,
+    /// Latency added at runtime to the messages of the channels created by the node.
+    extra_delay: SharedRwLock<f32>,
     /// Shared broker reference for channel management and message routing.
     broker: SharedRwLock<SimbaBroker>,
+    /// Messages waiting for the node, over all channels, counted by the broker.
+    queued_messages: Arc<AtomicUsize>,
 }
 
 impl fmt::Debug for Network {
@@ -170,6 +599,7 @@ impl fmt::Debug for Network {
             .field(&self.from)
             .field(&self.range)
             .field(&self.reception_delay)
+            .field(&self.link_model)
             .finish()
     }
 }
@@ -192,18 +622,35 @@ impl Network {
         from: String,
         config: &NetworkConfig,
         _global_config: &SimulatorConfig,
-        _va_factory: &DeterministRandomVariableFactory,
+        va_factory: &DeterministRandomVariableFactory,
         broker: &SharedRwLock<SimbaBroker>,
         _initial_time: f32,
     ) -> Network {
+        let queued_messages = broker.read().unwrap().node_queue_counter(&from);
         Network {
             from,
             range: config.range,
             reception_delay: config.reception_delay,
+            link_model: (!config.link_model.is_ideal())
+                .then(|| Arc::new(LinkModel::from_config(&config.link_model, va_factory))),
+            processing_load: (config.processing_cost > 0.).then(|| {
+                Arc::new(Mutex::new(ProcessingLoad::new(
+                    config.processing_cost,
+                    TIME_ROUND,
+                )))
+            }),
+            extra_delay: Arc::new(RwLock::new(0.)),
+            queued_messages,
             broker: broker.clone(),
         }
     }
 
+    /// Sets the latency, in seconds, added to the messages of the channels created by the
+    /// node, on top of the link model. Used to tune the network of a paused run.
+    pub fn set_extra_delay(&self, delay: f32) {
+        *self.extra_delay.write().unwrap() = delay;
+    }
+
     /// Creates an internal channel and returns its absolute key.
     ///
     /// Relative paths are namespaced under the current node internal prefix
@@ -226,7 +673,8 @@ impl Network {
     ///
     /// Relative paths are namespaced under the current node internal prefix
     /// [`channels::internal::NODE`]. When `self.range > 0.0`, message delivery is filtered by
-    /// Euclidean distance.
+    /// Euclidean distance. The messages are then lost or delayed following the link model, plus
+    /// the extra delay set with [`Network::set_extra_delay`].
     pub fn make_channel(&self, key: PathKey) -> PathKey {
         let key = if key.absolute() {
             key
@@ -235,29 +683,55 @@ impl Network {
                 .prepend_str(channels::internal::NODE)
         };
         let range = self.range;
+        let link_model = self.link_model.clone();
+        let extra_delay = self.extra_delay.clone();
         if is_enabled(crate::logger::InternalLog::NetworkMessages) {
             debug!("Creating channel '{}' with range {}", key, range);
         }
-        self.broker
-            .write()
-            .unwrap()
-            .add_channel_conditionnal(key.clone(), move |x1, x2| {
-                if range == 0. {
-                    true
-                } else if let Some(x1) = x1
-                    && let Some(x2) = x2
+        self.broker.write().unwrap().add_channel_with_delivery(
+            key.clone(),
+            move |from: LinkEndpoint, to: LinkEndpoint, time| {
+                if range > 0.
+                    && let Some(x1) = from.position
+                    && let Some(x2) = to.position
+                    && ((x1[0] - x2[0]).powi(2) + (x1[1] - x2[1]).powi(2)).sqrt() > range
                 {
-                    ((x1[0] - x2[0]).powi(2) + (x1[1] - x2[1]).powi(2)).sqrt() <= range
-                } else {
-                    true
+                    return None;
                 }
-            });
+                let delay = match &link_model {
+                    Some(link_model) => link_model.delivery(&from, &to, time),
+                    None => Some(0.),
+                };
+                delay.map(|delay| delay + *extra_delay.read().unwrap())
+            },
+        );
         key
     }
 
+    /// Sets the bandwidth and message rate limits of a channel, which can be created later.
+    ///
+    /// Relative paths are namespaced under the current node internal prefix
+    /// [`channels::internal::NODE`].
+    pub fn set_channel_limits(&self, key: PathKey, limits: ChannelLimits) {
+        let key = if key.absolute() {
+            key
+        } else {
+            key.prepend_str(&self.from)
+                .prepend_str(channels::internal::NODE)
+        };
+        if is_enabled(crate::logger::InternalLog::NetworkMessages) {
+            debug!("Limiting channel '{}': {:?}", key, limits);
+        }
+        self.broker
+            .write()
+            .unwrap()
+            .set_channel_limits(&key, limits);
+    }
+
     /// Subscribes a multi-client to the provided channels using the configured reception delay.
     ///
     /// If `multi_client` is `None`, a new [`SimbaBrokerMultiClient`] is created and returned.
+    /// The messages received by the multi-client consume the processing cost of the node.
     pub fn subscribe_to(
         &self,
         keys: &[PathKey],
@@ -273,6 +747,9 @@ impl Network {
                     .join_str(&self.from),
             )
         });
+        if let Some(processing_load) = &self.processing_load {
+            multi_client.set_processing_load(processing_load.clone());
+        }
         if is_enabled(crate::logger::InternalLog::NetworkMessages) {
             debug!("Subscribe to '{:?}'", keys);
             debug!(
@@ -379,4 +856,138 @@ impl Network {
             tmp_client.send(message, time);
         }
     }
+
+    /// Number of messages waiting in the queues of the clients of this node, over all channels.
+    pub fn queue_size(&self) -> usize {
+        self.queued_messages.load(Ordering::Relaxed)
+    }
+}
+
+impl Recordable<NetworkRecord> for Network {
+    fn record(&self) -> NetworkRecord {
+        match &self.processing_load {
+            Some(processing_load) => {
+                let processing_load = processing_load.lock().unwrap();
+                NetworkRecord {
+                    processed_messages: processing_load.processed_messages,
+                    total_processing_delay: processing_load.total_delay,
+                    max_processing_delay: processing_load.max_delay,
+                    busy_until: processing_load.busy_until,
+                }
+            }
+            None => NetworkRecord::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_model_drop_probability() {
+        let config = LinkModelConfig {
+            drop_probability: 0.1,
+            drop_probability_per_meter: 0.1,
+            pairs: vec![LinkPairConfig {
+                from: "node1".to_string(),
+                to: "node2".to_string(),
+                drop_probability: 1.,
+            }],
+            ..Default::default()
+        };
+        let link_model =
+            LinkModel::from_config(&config, &DeterministRandomVariableFactory::new(0.));
+        let endpoint = |node: &str, x: f32| LinkEndpoint {
+            node: node.to_string(),
+            position: Some([x, 0.]),
+        };
+        assert!(
+            (link_model.drop_probability(&endpoint("node2", 0.), &endpoint("node1", 2.)) - 0.3)
+                .abs()
+                < 1e-6
+        );
+        assert_eq!(
+            link_model.drop_probability(&endpoint("node3", 0.), &endpoint("node1", 20.)),
+            1.
+        );
+        // Pair probability, directed
+        assert_eq!(
+            link_model.drop_probability(&endpoint("node1", 0.), &endpoint("node2", 0.)),
+            1.
+        );
+        assert_eq!(
+            link_model.delivery(&endpoint("node1", 0.), &endpoint("node2", 0.), 1.),
+            None
+        );
+    }
+
+    #[test]
+    fn link_model_independent_streams() {
+        let config = LinkModelConfig {
+            drop_probability: 0.5,
+            ..Default::default()
+        };
+        let endpoint = |node: &str| LinkEndpoint {
+            node: node.to_string(),
+            position: None,
+        };
+        let deliveries = |link_model: &LinkModel, to: &str| -> Vec<bool> {
+            (0..50)
+                .map(|i| {
+                    link_model
+                        .delivery(&endpoint("node1"), &endpoint(to), i as f32 * 0.1)
+                        .is_some()
+                })
+                .collect()
+        };
+        let link_model =
+            LinkModel::from_config(&config, &DeterministRandomVariableFactory::new(0.));
+        let to_node2 = deliveries(&link_model, "node2");
+        assert_ne!(to_node2, deliveries(&link_model, "node3"));
+        // Same draws for the same link, whatever the other links used
+        let other_model =
+            LinkModel::from_config(&config, &DeterministRandomVariableFactory::new(0.));
+        assert_eq!(deliveries(&other_model, "node2"), to_node2);
+    }
+
+    #[test]
+    fn envelopes_share_the_channel_bandwidth() {
+        let mut broker = SimbaBroker::new(TIME_ROUND);
+        broker.set_message_size(Envelope::message_size);
+        let key = PathKey::from_str("/unit/map_merging/share").unwrap();
+        broker.set_channel_limits(
+            &key,
+            ChannelLimits {
+                bandwidth: Some(100.),
+                max_rate: None,
+            },
+        );
+        broker.add_channel(key.clone());
+        let receiver = broker.subscribe_to(&key, "unit".to_string(), 0.).unwrap();
+        for robot in ["robot1", "robot2"] {
+            let sender = broker.subscribe_to(&key, robot.to_string(), 0.).unwrap();
+            // 100 bytes serialized, with the quotes
+            sender.send(
+                Envelope {
+                    from: robot.to_string(),
+                    message: Value::String("x".repeat(98)),
+                    timestamp: 0.,
+                    ..Default::default()
+                },
+                0.,
+            );
+        }
+        broker.process_messages(None);
+
+        // The second map waits for the transmission of the first one
+        assert!(receiver.try_receive(0.5).is_none());
+        let first = receiver.try_receive(1.).unwrap();
+        // Size computed when sent
+        assert_eq!(first.size.get(), Some(&100));
+        assert!(receiver.try_receive(1.5).is_none());
+        let second = receiver.try_receive(2.).unwrap();
+        assert_eq!(second.message_size(), 100);
+        assert_ne!(first.from, second.from);
+    }
 }
@@ -5,18 +5,26 @@
 //! node [`Network`](crate::networking::network::Network) instances and advances queued messages in
 //! simulation time order.
 
+use config_checker::*;
 use log::debug;
 use serde_json::Value;
-use simba_com::pub_sub::{BrokerTrait, BrokerTraitProcessing, PathBroker};
+use simba_com::pub_sub::{
+    BrokerTrait, BrokerTraitProcessing, ChannelLimits, ChannelStats, MessageRetention, PathBroker,
+    PathKey,
+};
+use simba_macros::config_derives;
 
 use crate::constants::TIME_ROUND;
 use crate::errors::SimbaResult;
 use crate::logger::is_enabled;
 use crate::simulator::SimbaBroker;
 use crate::utils::SharedRwLock;
+#[cfg(feature = "gui")]
+use crate::{gui::UIComponent, simulator::SimulatorConfig};
 
-use super::network::MessageFlag;
+use super::network::{Envelope, LinkEndpoint, MessageFlag};
 use std::collections::HashMap;
+use std::str::FromStr;
 
 use std::sync::{Arc, RwLock};
 
@@ -48,6 +56,227 @@ pub struct NetworkMessage {
     pub message_flags: Vec<MessageFlag>,
 }
 
+/// Retention of the messages waiting for slow subscribers in the broker channels.
+///
+/// Messages dispatched to a node and never received (e.g. a node subscribed to a chatty
+/// channel but reading it rarely) stay in memory. The garbage collection, done at the end of each
+/// time step, drops the messages not kept by this retention. By default, all messages are kept.
+///
+/// # Example
+/// ```yaml
+/// message_retention:
+///   max_age: 10.        # Drop the messages due for more than 10 s
+///   max_queue_size: 1000
+/// ```
+#[config_derives]
+pub struct MessageRetentionConfig {
+    /// Drop the messages due for more than `max_age` seconds. `null` for no limit.
+    pub max_age: Option<f32>,
+    /// Maximum number of messages waiting for each subscriber, the oldest are dropped.
+    /// `null` for no limit.
+    pub max_queue_size: Option<usize>,
+}
+
+impl Default for MessageRetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            max_queue_size: None,
+        }
+    }
+}
+
+impl Check for MessageRetentionConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Some(max_age) = self.max_age
+            && max_age < 0.
+        {
+            errors.push(format!("Message max_age should be positive, got {max_age}"));
+        }
+        if self.max_queue_size == Some(0) {
+            errors.push("Message max_queue_size should be strictly positive, got 0".to_string());
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MessageRetentionConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Message retention").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Max age (s): ");
+                if let Some(max_age) = &mut self.max_age {
+                    ui.add(egui::DragValue::new(max_age).range(0.0..=f32::MAX));
+                    if ui.button("X").clicked() {
+                        self.max_age = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.max_age = Some(10.);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Max queue size: ");
+                if let Some(max_queue_size) = &mut self.max_queue_size {
+                    ui.add(egui::DragValue::new(max_queue_size).range(1..=usize::MAX));
+                    if ui.button("X").clicked() {
+                        self.max_queue_size = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.max_queue_size = Some(1000);
+                }
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        egui::CollapsingHeader::new("Message retention").show(ui, |ui| {
+            ui.label(format!(
+                "Max age: {}",
+                self.max_age
+                    .map_or("unlimited".to_string(), |a| format!("{a} s"))
+            ));
+            ui.label(format!(
+                "Max queue size: {}",
+                self.max_queue_size
+                    .map_or("unlimited".to_string(), |n| n.to_string())
+            ));
+        });
+    }
+}
+
+/// Bandwidth and message rate limits of a broker channel.
+///
+/// The messages of the channel are transmitted one after the other: a message waits for the
+/// previous ones, then takes `size / bandwidth` seconds to arrive, the size being the length of
+/// its JSON serialization. Large payloads (e.g. shared maps) are then delayed proportionally to
+/// their size. The delay adds up to the link model of the network.
+///
+/// # Example
+/// ```yaml
+/// channel_limits:
+///   - channel: /simba/nodes/robot1/map
+///     bandwidth: 10000.   # 10 kB/s
+///     max_rate: 2.        # At most 2 messages per second
+/// ```
+#[config_derives]
+pub struct ChannelLimitsConfig {
+    /// Absolute path of the channel, e.g. `/simba/nodes/<node>/<channel>` for the channels
+    /// created by the nodes.
+    pub channel: String,
+    /// Bandwidth of the channel, in bytes per second. `null` for no limit.
+    pub bandwidth: Option<f32>,
+    /// Maximum number of messages per second. `null` for no limit.
+    pub max_rate: Option<f32>,
+}
+
+impl Default for ChannelLimitsConfig {
+    fn default() -> Self {
+        Self {
+            channel: String::new(),
+            bandwidth: None,
+            max_rate: None,
+        }
+    }
+}
+
+impl Check for ChannelLimitsConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if !self.channel.starts_with('/') {
+            errors.push(format!(
+                "Limited channel should be an absolute path, got '{}'",
+                self.channel
+            ));
+        }
+        if let Some(bandwidth) = self.bandwidth
+            && bandwidth <= 0.
+        {
+            errors.push(format!(
+                "Channel bandwidth should be strictly positive, got {bandwidth}"
+            ));
+        }
+        if let Some(max_rate) = self.max_rate
+            && max_rate <= 0.
+        {
+            errors.push(format!(
+                "Channel max_rate should be strictly positive, got {max_rate}"
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ChannelLimitsConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("Channel: ");
+                ui.text_edit_singleline(&mut self.channel);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Bandwidth (B/s): ");
+                if let Some(bandwidth) = &mut self.bandwidth {
+                    ui.add(egui::DragValue::new(bandwidth).range(0.001..=f32::MAX));
+                    if ui.button("X").clicked() {
+                        self.bandwidth = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.bandwidth = Some(10000.);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Max rate (msg/s): ");
+                if let Some(max_rate) = &mut self.max_rate {
+                    ui.add(egui::DragValue::new(max_rate).range(0.001..=f32::MAX));
+                    if ui.button("X").clicked() {
+                        self.max_rate = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.max_rate = Some(10.);
+                }
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!(
+            "{}: bandwidth {}, max rate {}",
+            self.channel,
+            self.bandwidth
+                .map_or("unlimited".to_string(), |b| format!("{b} B/s")),
+            self.max_rate
+                .map_or("unlimited".to_string(), |r| format!("{r} msg/s"))
+        ));
+    }
+}
+
 /// Manages all [`Network`](crate::networking::network::Network) instances through a shared broker.
 #[derive(Debug)]
 pub struct NetworkManager {
@@ -57,8 +286,10 @@ pub struct NetworkManager {
 impl NetworkManager {
     /// Creates a new [`NetworkManager`] with an empty broker.
     pub fn new() -> Self {
+        let mut broker = PathBroker::new(TIME_ROUND);
+        broker.set_message_size(Envelope::message_size);
         Self {
-            broker: Arc::new(RwLock::new(PathBroker::new(TIME_ROUND))),
+            broker: Arc::new(RwLock::new(broker)),
         }
     }
 
@@ -67,7 +298,7 @@ impl NetworkManager {
         self.broker.write().unwrap().clear_channels();
     }
 
-    /// Processes queued messages using the optional position map for range filtering.
+    /// Processes queued messages using the position map for range filtering and the link models.
     ///
     /// The `position_map` maps node names to their current 2D position.
     /// Nodes with `None` position are not range-filtered and receive all messages.
@@ -78,13 +309,63 @@ impl NetworkManager {
         if is_enabled(crate::logger::InternalLog::NetworkMessages) {
             debug!("Processing messages");
         }
+        let endpoints: HashMap<String, LinkEndpoint> = position_map
+            .iter()
+            .map(|(node, position)| {
+                (
+                    node.clone(),
+                    LinkEndpoint {
+                        node: node.clone(),
+                        position: *position,
+                    },
+                )
+            })
+            .collect();
         self.broker
             .write()
             .unwrap()
-            .process_messages(Some(position_map));
+            .process_messages(Some(&endpoints));
         Ok(())
     }
 
+    /// Sets the retention of the messages waiting for slow subscribers, for all channels.
+    pub fn set_message_retention(&mut self, config: &MessageRetentionConfig) {
+        self.broker
+            .write()
+            .unwrap()
+            .set_retention(MessageRetention {
+                max_age: config.max_age,
+                max_queue_size: config.max_queue_size,
+            });
+    }
+
+    /// Sets the bandwidth and message rate limits of the channels, which can be created later.
+    pub fn set_channel_limits(&mut self, configs: &[ChannelLimitsConfig]) {
+        let mut broker = self.broker.write().unwrap();
+        for config in configs {
+            broker.set_channel_limits(
+                &PathKey::from_str(&config.channel).unwrap(),
+                ChannelLimits {
+                    bandwidth: config.bandwidth,
+                    max_rate: config.max_rate,
+                },
+            );
+        }
+    }
+
+    /// Drops the messages not kept by the message retention, at simulation `time`.
+    pub fn collect_garbage(&mut self, time: f32) {
+        let dropped = self.broker.read().unwrap().collect_garbage(time);
+        if dropped > 0 && is_enabled(crate::logger::InternalLog::NetworkMessages) {
+            debug!("{dropped} undelivered messages dropped by the message retention");
+        }
+    }
+
+    /// Returns the queue metrics (pending, dropped, largest queue, limits queuing) of each channel.
+    pub fn channel_stats(&self) -> Vec<(PathKey, ChannelStats)> {
+        self.broker.read().unwrap().channel_stats()
+    }
+
     /// Returns a shared handle to the underlying broker.
     pub fn broker(&self) -> SharedRwLock<SimbaBroker> {
         self.broker.clone()
@@ -0,0 +1,190 @@
+//! Runtime reconfiguration of the built-in modules.
+//!
+//! A [`ReconfigureMessage`] changes selected parameters of a running module (observation or
+//! prediction period, noise level of the additive faults, activation of the fault models). It
+//! is received on:
+//! - `/simba/nodes/<node>/sensors/reconfigure/<sensor_name>` for the sensors of the
+//!   [`SensorManager`](crate::sensors::sensor_manager::SensorManager),
+//! - `/simba/nodes/<node>/state_estimator/reconfigure` for the built-in state estimators,
+//! - `/simba/reconfigure/<node>`, wrapped in a [`NodeReconfigureMessage`], for the physics, the
+//!   controller and the network of the node, and to set the real state of the node.
+//!
+//! The change is applied at the next time step of the node. Each received message is recorded
+//! in a [`ReconfigurationRecord`], with the reason of the rejection if it could not be applied.
+
+use std::str::FromStr;
+
+use pyo3::{pyclass, pymethods};
+use serde::{Deserialize, Serialize};
+use simba_com::pub_sub::{MultiClientTrait, PathKey};
+
+use crate::{
+    networking::{
+        self,
+        network::{Envelope, MessageFlag},
+    },
+    physics::robot_models::Command,
+    simulator::SimbaBrokerMultiClient,
+};
+
+/// Message changing parameters of a module at runtime.
+///
+/// Only the given fields are changed, the others keep their current value.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[pyclass(get_all, set_all)]
+pub struct ReconfigureMessage {
+    /// New activation period (observation or prediction period), in seconds. The next
+    /// activation is scheduled one period after the reception.
+    pub period: Option<f32>,
+    /// Scale applied to the additive fault perturbations, relative to the configured
+    /// distributions (1 restores the configured noise, 0 removes it).
+    pub noise_scale: Option<f32>,
+    /// Activates (`true`) or deactivates (`false`) the fault model at the given index of the
+    /// `faults` list of the module configuration. The fault models are active at start, unless
+    /// listed in the `inactive_faults` of the module configuration.
+    pub fault_activation: Option<(usize, bool)>,
+}
+
+#[pymethods]
+impl ReconfigureMessage {
+    /// Creates a reconfiguration message. Fields left to `None` are not changed.
+    #[new]
+    #[pyo3(signature = (period=None, noise_scale=None, fault_activation=None))]
+    pub fn new(
+        period: Option<f32>,
+        noise_scale: Option<f32>,
+        fault_activation: Option<(usize, bool)>,
+    ) -> Self {
+        Self {
+            period,
+            noise_scale,
+            fault_activation,
+        }
+    }
+}
+
+impl ReconfigureMessage {
+    /// Check the requested values before applying them.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(period) = self.period
+            && period <= 0.
+        {
+            return Err(format!("Period should be positive, got {period}"));
+        }
+        if let Some(scale) = self.noise_scale
+            && scale < 0.
+        {
+            return Err(format!(
+                "Noise scale should be positive or null, got {scale}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply the requested fault activation to the activation flags of the fault models.
+    pub fn apply_fault_activation(&self, active_faults: &mut [bool]) -> Result<(), String> {
+        if let Some((index, active)) = self.fault_activation {
+            let nb_faults = active_faults.len();
+            *active_faults.get_mut(index).ok_or_else(|| {
+                format!("No fault model at index {index}, the module has {nb_faults} fault models")
+            })? = active;
+        }
+        Ok(())
+    }
+}
+
+/// Message changing parameters of the modules of a node at runtime, received on
+/// `/simba/reconfigure/<node>`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum NodeReconfigureMessage {
+    /// Changes applied to the physics of the node.
+    Physics(ReconfigureMessage),
+    /// New gains of the controller. The gains left to `None` are not changed.
+    ControllerGains {
+        /// Proportional gains.
+        proportional_gains: Option<Vec<f32>>,
+        /// Derivative gains.
+        derivative_gains: Option<Vec<f32>>,
+        /// Integral gains.
+        integral_gains: Option<Vec<f32>>,
+    },
+    /// Extra latency, in seconds, of the messages of the channels created by the node.
+    NetworkDelay(f32),
+    /// New real state of the node, set in its physics. The values left to `None` are not
+    /// changed.
+    SetState {
+        /// Pose `[x, y, theta]`.
+        pose: Option<[f32; 3]>,
+        /// Velocity `[longitudinal, lateral, angular]`.
+        velocity: Option<[f32; 3]>,
+    },
+    /// Command of a source of the
+    /// [command arbitration](crate::node::command_arbitration) of the node.
+    SourceCommand {
+        /// Name of the source.
+        source: String,
+        /// Command to apply while the source is in control, `None` to release the control.
+        command: Option<Command>,
+    },
+}
+
+/// Record of one received [`ReconfigureMessage`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReconfigurationRecord {
+    /// Time at which the message was applied.
+    pub time: f32,
+    /// Requested changes.
+    pub message: ReconfigureMessage,
+    /// Reason of the rejection, `None` if the changes were applied.
+    pub error: Option<String>,
+}
+
+/// Outcome of a reconfiguration message sent with the [`MessageFlag::Acknowledge`] flag,
+/// published on `/simba/reconfigure_outcome`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReconfigurationOutcome {
+    /// Channel on which the reconfiguration message was received.
+    pub channel: String,
+    /// Reason of the rejection, `None` if the changes were applied.
+    pub error: Option<String>,
+}
+
+impl ReconfigurationOutcome {
+    /// Publishes the outcome `result` of the message received on `channel`, if its
+    /// `message_flags` ask for it.
+    pub(crate) fn acknowledge(
+        client: &SimbaBrokerMultiClient,
+        channel: &PathKey,
+        message_flags: &[MessageFlag],
+        result: &Result<(), String>,
+        time: f32,
+    ) {
+        if !message_flags.contains(&MessageFlag::Acknowledge) {
+            return;
+        }
+        let outcome = Self {
+            channel: channel.to_string(),
+            error: result.as_ref().err().cloned(),
+        };
+        let message = match serde_json::to_value(outcome) {
+            Ok(message) => message,
+            Err(e) => {
+                log::warn!(
+                    "[{}] Cannot serialize the reconfiguration outcome: {e}",
+                    client.node_id()
+                );
+                return;
+            }
+        };
+        client.send(
+            &PathKey::from_str(networking::channels::internal::RECONFIGURE_OUTCOME).unwrap(),
+            Envelope {
+                from: client.node_id().clone(),
+                message,
+                timestamp: time,
+                ..Default::default()
+            },
+            time,
+        );
+    }
+}
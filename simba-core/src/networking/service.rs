@@ -26,7 +26,7 @@ use crate::{
     logger::is_enabled,
     networking::service_manager::ServiceError,
     simulator::TimeCv,
-    utils::{SharedMutex, SharedRwLock},
+    utils::{SharedMutex, SharedRwLock, lock_recovery::RecoverMutex},
 };
 
 use super::network::MessageFlag;
@@ -88,7 +88,7 @@ impl<RequestMsg: Debug + Clone, ResponseMsg: Debug + Clone> ServiceClient<Reques
                 "Service server closed".to_string(),
             ));
         }
-        let _lk = self.time_cv.waiting.lock().unwrap();
+        let _lk = self.time_cv.waiting.lock_recover();
         if is_enabled(crate::logger::InternalLog::ServiceHandling) {
             debug!("Sending a request");
         }
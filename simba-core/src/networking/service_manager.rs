@@ -16,7 +16,7 @@ use crate::{
     physics::{GetRealStateReq, GetRealStateResp, Physics},
     simulator::TimeCv,
     state_estimators::State,
-    utils::SharedRwLock,
+    utils::{SharedRwLock, lock_recovery::RecoverRwLock},
 };
 
 use super::service::{Service, ServiceClient, ServiceInterface};
@@ -70,7 +70,7 @@ impl ServiceManager {
         client_node_name: &str,
     ) -> SimbaResult<ServiceClient<GetRealStateReq, GetRealStateResp>> {
         if let Some(get_real_state) = &self.get_real_state {
-            Ok(get_real_state.write().unwrap().new_client(client_node_name))
+            Ok(get_real_state.write_recover().new_client(client_node_name))
         } else {
             Err(SimbaError::new(
                 SimbaErrorTypes::ServiceError(ServiceError::Unavailable),
@@ -132,7 +132,7 @@ impl ServiceManager {
             if name == &my_name {
                 continue;
             }
-            if let Ok(client) = sm.read().unwrap().get_real_state_client(&my_name) {
+            if let Ok(client) = sm.read_recover().get_real_state_client(&my_name) {
                 self.get_real_state_clients.insert(name.clone(), client);
             }
         }
@@ -141,7 +141,7 @@ impl ServiceManager {
     /// Handles all currently queued incoming service requests at `time`.
     pub fn handle_requests(&self, time: f32) {
         if let Some(get_real_state) = &self.get_real_state {
-            get_real_state.read().unwrap().handle_requests(time);
+            get_real_state.read_recover().handle_requests(time);
         }
     }
 
@@ -149,7 +149,7 @@ impl ServiceManager {
     pub fn process_requests(&self) -> usize {
         let mut s = 0usize;
         if let Some(get_real_state) = &self.get_real_state {
-            s += get_real_state.read().unwrap().process_requests();
+            s += get_real_state.read_recover().process_requests();
         }
         s
     }
@@ -160,7 +160,7 @@ impl ServiceManager {
     pub fn next_time(&self) -> f32 {
         let mut min_time = f32::INFINITY;
         if let Some(get_real_state) = &self.get_real_state {
-            let mt = get_real_state.read().unwrap().next_time();
+            let mt = get_real_state.read_recover().next_time();
             if mt < min_time {
                 min_time = mt;
             }
@@ -172,7 +172,7 @@ impl ServiceManager {
     /// Unsubscribes and deletes all local service channels for this node.
     pub fn unsubscribe_node(&self) {
         if let Some(get_real_state) = &self.get_real_state {
-            get_real_state.write().unwrap().delete();
+            get_real_state.write_recover().delete();
         }
     }
 }
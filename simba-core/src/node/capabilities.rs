@@ -0,0 +1,388 @@
+//! Capabilities declared by the modules of a node, checked when the node is built.
+//!
+//! The controller, the physics, the state estimators and the sensors declare the [`Command`]
+//! variants they emit or accept, the observations they produce or consume (and their
+//! [`ObservationFrame`]) and the services of the node they need, through the `capabilities`
+//! method of their trait. The built-in modules
+//! declare theirs, the undeclared capabilities (e.g. of external modules) are compatible with
+//! everything.
+//!
+//! [`check_capabilities`] compares them at the node construction, so that an incompatible
+//! configuration gives a [`ConfigError`](SimbaErrorTypes::ConfigError) instead of a panic during
+//! the run.
+
+use log::warn;
+use simba_macros::EnumToString;
+
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    node::{Node, node_factory::NodeType},
+    physics::robot_models::{Command, RobotModelConfig},
+    sensors::{SensorObservation, observation_frame::ObservationFrame},
+    utils::lock_recovery::RecoverRwLock,
+};
+
+/// Kind of [`Command`], i.e. the robot model it is made for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumToString)]
+pub enum CommandKind {
+    /// [`Command::Unicycle`].
+    Unicycle,
+    /// [`Command::Holonomic`].
+    Holonomic,
+}
+
+impl From<&Command> for CommandKind {
+    fn from(command: &Command) -> Self {
+        match command {
+            Command::Unicycle(_) => Self::Unicycle,
+            Command::Holonomic(_) => Self::Holonomic,
+        }
+    }
+}
+
+impl From<&RobotModelConfig> for CommandKind {
+    fn from(model: &RobotModelConfig) -> Self {
+        match model {
+            RobotModelConfig::Unicycle(_) => Self::Unicycle,
+            RobotModelConfig::Holonomic(_) => Self::Holonomic,
+        }
+    }
+}
+
+/// Kind of [`SensorObservation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumToString)]
+pub enum ObservationKind {
+    /// [`SensorObservation::OrientedLandmark`].
+    OrientedLandmark,
+    /// [`SensorObservation::Speed`].
+    Speed,
+    /// [`SensorObservation::Displacement`].
+    Displacement,
+    /// [`SensorObservation::GNSS`].
+    GNSS,
+    /// [`SensorObservation::OrientedRobot`].
+    OrientedRobot,
+    /// [`SensorObservation::Scan`].
+    Scan,
+    /// [`SensorObservation::External`].
+    External,
+    /// [`SensorObservation::Imu`].
+    Imu,
+    /// [`SensorObservation::Bearing`].
+    Bearing,
+}
+
+impl From<&SensorObservation> for ObservationKind {
+    fn from(observation: &SensorObservation) -> Self {
+        match observation {
+            SensorObservation::OrientedLandmark(_) => Self::OrientedLandmark,
+            SensorObservation::Speed(_) => Self::Speed,
+            SensorObservation::Displacement(_) => Self::Displacement,
+            SensorObservation::GNSS(_) => Self::GNSS,
+            SensorObservation::OrientedRobot(_) => Self::OrientedRobot,
+            SensorObservation::Scan(_) => Self::Scan,
+            SensorObservation::External(_) => Self::External,
+            SensorObservation::Imu(_) => Self::Imu,
+            SensorObservation::Bearing(_) => Self::Bearing,
+        }
+    }
+}
+
+/// Service of its node that a module needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumToString)]
+pub enum NodeService {
+    /// Real state of the node, given by its physics.
+    RealState,
+}
+
+/// Capabilities of a module.
+///
+/// Default value: nothing declared, compatible with all the other modules.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Capabilities {
+    /// Commands emitted (controller) or accepted (physics), `None` if not declared.
+    pub commands: Option<Vec<CommandKind>>,
+    /// Observations produced (sensor).
+    pub produced_observations: Vec<ObservationKind>,
+    /// Observations used (state estimator), `None` if not declared.
+    pub consumed_observations: Option<Vec<ObservationKind>>,
+    /// Frame of the produced observations (sensor), `None` for their native frame (see
+    /// [`ObservationFrame::native`]).
+    pub observation_frame: Option<ObservationFrame>,
+    /// Frames in which the observations can be used, in addition to their native frame (state
+    /// estimator), `None` if not declared.
+    pub consumed_frames: Option<Vec<ObservationFrame>>,
+    /// Services of the node needed by the module.
+    pub required_services: Vec<NodeService>,
+}
+
+fn join<T: ToString>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Capabilities of all the modules of a node.
+#[derive(Debug, Clone, Default)]
+struct NodeCapabilities {
+    controller: Option<Capabilities>,
+    physics: Option<Capabilities>,
+    /// State estimators (main and bench), with their description.
+    estimators: Vec<(String, Capabilities)>,
+    /// Sensors, with their description.
+    sensors: Vec<(String, Capabilities)>,
+}
+
+impl NodeCapabilities {
+    fn from_node(node: &Node) -> Self {
+        let mut estimators = Vec::new();
+        if let Some(state_estimator) = node.state_estimator() {
+            estimators.push((
+                "state_estimator".to_string(),
+                state_estimator.read_recover().capabilities(),
+            ));
+        }
+        if let Some(bench) = node.state_estimator_bench() {
+            for estimator in bench.read_recover().iter() {
+                estimators.push((
+                    format!("state estimator `{}`", estimator.name),
+                    estimator.state_estimator.read_recover().capabilities(),
+                ));
+            }
+        }
+        Self {
+            controller: node.controller().map(|c| c.read_recover().capabilities()),
+            physics: node.physics().map(|p| p.read_recover().capabilities()),
+            estimators,
+            sensors: node
+                .sensor_manager()
+                .map(|sensor_manager| {
+                    sensor_manager
+                        .read_recover()
+                        .capabilities()
+                        .into_iter()
+                        .map(|(name, capabilities)| (format!("sensor `{name}`"), capabilities))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Incompatibilities between the modules, for a node of type `node_type`.
+    fn errors(&self, node_type: &NodeType) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(emitted) = self.controller.as_ref().and_then(|c| c.commands.as_ref())
+            && let Some(accepted) = self.physics.as_ref().and_then(|p| p.commands.as_ref())
+        {
+            let rejected: Vec<_> = emitted
+                .iter()
+                .filter(|command| !accepted.contains(command))
+                .cloned()
+                .collect();
+            if !rejected.is_empty() {
+                errors.push(format!(
+                    "controller emits {} command but physics only accepts {}",
+                    join(&rejected),
+                    join(accepted)
+                ));
+            }
+        }
+
+        for (sensor, sensor_capabilities) in &self.sensors {
+            let Some(frame) = &sensor_capabilities.observation_frame else {
+                continue;
+            };
+            for kind in &sensor_capabilities.produced_observations {
+                if ObservationFrame::native(*kind) == *frame {
+                    continue;
+                }
+                for (estimator, capabilities) in &self.estimators {
+                    if capabilities
+                        .consumed_observations
+                        .as_ref()
+                        .is_some_and(|consumed| !consumed.contains(kind))
+                    {
+                        continue;
+                    }
+                    if let Some(frames) = &capabilities.consumed_frames
+                        && !frames.contains(frame)
+                    {
+                        errors.push(format!(
+                            "{sensor} produces {kind} observations in the {frame} frame, not supported by {estimator}"
+                        ));
+                    }
+                }
+            }
+        }
+
+        let services = if self.physics.is_some() {
+            vec![NodeService::RealState]
+        } else {
+            Vec::new()
+        };
+        let modules = self
+            .controller
+            .iter()
+            .map(|c| ("controller", c))
+            .chain(self.physics.iter().map(|p| ("physics", p)))
+            .chain(
+                self.estimators
+                    .iter()
+                    .chain(self.sensors.iter())
+                    .map(|(module, capabilities)| (module.as_str(), capabilities)),
+            );
+        for (module, capabilities) in modules {
+            for service in &capabilities.required_services {
+                if !services.contains(service) {
+                    errors.push(format!(
+                        "{module} requires the {service} service, which {node_type:?} nodes do not provide"
+                    ));
+                }
+            }
+        }
+        errors
+    }
+
+    /// Observations of each sensor used by none of the state estimators. Empty if a state
+    /// estimator does not declare the observations it uses.
+    fn unused_observations(&self) -> Vec<(&String, Vec<ObservationKind>)> {
+        if self.estimators.is_empty() {
+            return Vec::new();
+        }
+        let Some(consumed) = self
+            .estimators
+            .iter()
+            .map(|(_, capabilities)| capabilities.consumed_observations.clone())
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Vec::new();
+        };
+        let consumed = consumed.concat();
+        self.sensors
+            .iter()
+            .map(|(sensor, capabilities)| {
+                (
+                    sensor,
+                    capabilities
+                        .produced_observations
+                        .iter()
+                        .filter(|observation| !consumed.contains(observation))
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .filter(|(_, unused)| !unused.is_empty())
+            .collect()
+    }
+}
+
+/// Check that the capabilities of the modules of the `node` are compatible.
+///
+/// Returns a [`ConfigError`](SimbaErrorTypes::ConfigError) listing the incompatibilities:
+/// - the controller emits a command not accepted by the physics;
+/// - a sensor produces observations in a frame not supported by a state estimator using them;
+/// - a module needs a service the node does not provide.
+///
+/// The observations produced by the sensors and used by none of the state estimators only give
+/// a warning, as they can be sent to other nodes.
+pub fn check_capabilities(node: &Node) -> SimbaResult<()> {
+    let capabilities = NodeCapabilities::from_node(node);
+    for (sensor, unused) in capabilities.unused_observations() {
+        warn!(
+            "[{}] {sensor} produces {} observations, used by none of the state estimators of the node",
+            node.name(),
+            join(&unused)
+        );
+    }
+    let errors = capabilities.errors(&node.node_type());
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "Incompatible modules in node {}:\n{}",
+                node.name(),
+                errors.join("\n")
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incompatible_modules() {
+        let mut capabilities = NodeCapabilities {
+            controller: Some(Capabilities {
+                commands: Some(vec![CommandKind::Holonomic]),
+                ..Default::default()
+            }),
+            physics: Some(Capabilities {
+                commands: Some(vec![CommandKind::Unicycle]),
+                ..Default::default()
+            }),
+            estimators: vec![(
+                "state_estimator".to_string(),
+                Capabilities {
+                    consumed_observations: Some(vec![ObservationKind::GNSS]),
+                    ..Default::default()
+                },
+            )],
+            sensors: vec![(
+                "sensor `imu`".to_string(),
+                Capabilities {
+                    produced_observations: vec![ObservationKind::Imu],
+                    required_services: vec![NodeService::RealState],
+                    ..Default::default()
+                },
+            )],
+        };
+        assert_eq!(
+            capabilities.errors(&NodeType::Robot),
+            vec!["controller emits Holonomic command but physics only accepts Unicycle"]
+        );
+        assert_eq!(capabilities.unused_observations().len(), 1);
+
+        // The estimators only supporting the native frames reject the other frames
+        capabilities.estimators[0].1.consumed_frames = Some(Vec::new());
+        capabilities.sensors.push((
+            "sensor `gnss`".to_string(),
+            Capabilities {
+                produced_observations: vec![ObservationKind::GNSS],
+                observation_frame: Some(ObservationFrame::Map),
+                ..Default::default()
+            },
+        ));
+        capabilities.sensors[0].1.observation_frame = Some(ObservationFrame::Sensor);
+        assert_eq!(capabilities.errors(&NodeType::Robot).len(), 1);
+        capabilities.estimators[0].1.consumed_observations = Some(vec![ObservationKind::Imu]);
+        assert_eq!(
+            capabilities.errors(&NodeType::Robot)[1],
+            "sensor `imu` produces Imu observations in the Sensor frame, not supported by state_estimator"
+        );
+        capabilities.sensors.pop();
+        capabilities.sensors[0].1.observation_frame = None;
+        capabilities.estimators[0].1.consumed_observations = Some(vec![ObservationKind::GNSS]);
+        capabilities.estimators[0].1.consumed_frames = None;
+
+        // Undeclared capabilities are compatible with everything
+        capabilities.controller = Some(Capabilities::default());
+        capabilities.estimators[0].1.consumed_observations = None;
+        assert!(capabilities.errors(&NodeType::Robot).is_empty());
+        assert!(capabilities.unused_observations().is_empty());
+
+        capabilities.controller = None;
+        capabilities.physics = None;
+        assert_eq!(
+            capabilities.errors(&NodeType::ComputationUnit),
+            vec![
+                "sensor `imu` requires the RealState service, which ComputationUnit nodes do not provide"
+            ]
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Arbitration of the commands of a robot between several sources.
+//!
+//! Without arbitration, the command of the controller is applied to the physics. With a
+//! [`CommandArbitrationConfig`], other sources (teleoperation, safety supervisor...) can take over
+//! the robot: each source sends its commands on `/simba/reconfigure/<node>`, in a
+//! [`NodeReconfigureMessage::SourceCommand`](crate::networking::reconfigure::NodeReconfigureMessage::SourceCommand),
+//! and the [`CommandArbiter`] applies the command of the source with the highest priority. A
+//! source keeps the control until it releases it, or until it has not sent a command for its
+//! `timeout`. The controller is the [`CONTROLLER_SOURCE`] source, which never times out.
+//!
+//! The source in control at each time step is recorded in the robot record (`command_source`).
+
+use config_checker::*;
+use simba_macros::config_derives;
+
+use crate::physics::robot_models::Command;
+#[cfg(feature = "gui")]
+use crate::{gui::UIComponent, simulator::SimulatorConfig};
+
+/// Name of the source of the commands of the controller.
+pub const CONTROLLER_SOURCE: &str = "controller";
+
+/// Source of commands competing with the controller.
+///
+/// Default values:
+/// - `name`: `"teleop"`
+/// - `priority`: `10`
+/// - `timeout`: `0.5`
+#[config_derives]
+pub struct CommandSourceConfig {
+    /// Name of the source, given in its commands.
+    pub name: String,
+    /// Priority of the source: the source with the highest priority is in control.
+    pub priority: i32,
+    /// Duration, in seconds, after the last command of the source before it loses the control.
+    /// `null` to keep the control until the source releases it.
+    pub timeout: Option<f32>,
+}
+
+impl Default for CommandSourceConfig {
+    fn default() -> Self {
+        Self {
+            name: "teleop".to_string(),
+            priority: 10,
+            timeout: Some(0.5),
+        }
+    }
+}
+
+impl Check for CommandSourceConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.name == CONTROLLER_SOURCE {
+            errors.push(format!(
+                "Command source name `{CONTROLLER_SOURCE}` is reserved for the controller"
+            ));
+        }
+        if let Some(timeout) = self.timeout
+            && timeout <= 0.
+        {
+            errors.push(format!(
+                "Timeout of command source `{}` should be strictly positive, got {timeout}",
+                self.name
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Arbitration of the commands of a robot, see the [module documentation](self).
+///
+/// Default values:
+/// - `controller_priority`: `0`
+/// - `sources`: empty vector
+///
+/// # Example
+/// ```yaml
+/// command_arbitration:
+///   controller_priority: 0
+///   sources:
+///     - name: teleop
+///       priority: 20
+///       timeout: 0.5
+///     - name: safety
+///       priority: 10
+///       timeout: null
+/// ```
+#[config_derives]
+pub struct CommandArbitrationConfig {
+    /// Priority of the controller.
+    pub controller_priority: i32,
+    /// Other sources of commands.
+    #[check]
+    pub sources: Vec<CommandSourceConfig>,
+}
+
+impl Default for CommandArbitrationConfig {
+    fn default() -> Self {
+        Self {
+            controller_priority: 0,
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl Check for CommandArbitrationConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for (i, source) in self.sources.iter().enumerate() {
+            if self.sources[..i]
+                .iter()
+                .any(|other| other.name == source.name)
+            {
+                errors.push(format!("Command source `{}` is defined twice", source.name));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CommandArbitrationConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Command arbitration")
+            .id_salt(format!("command-arbitration-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Controller priority:");
+                    ui.add(egui::DragValue::new(&mut self.controller_priority));
+                });
+                let mut to_remove = None;
+                for (i, source) in self.sources.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut source.name);
+                        ui.label("Priority:");
+                        ui.add(egui::DragValue::new(&mut source.priority));
+                        let mut has_timeout = source.timeout.is_some();
+                        ui.checkbox(&mut has_timeout, "Timeout (s):");
+                        if has_timeout {
+                            let timeout = source.timeout.get_or_insert(0.5);
+                            ui.add(
+                                egui::DragValue::new(timeout)
+                                    .speed(0.01)
+                                    .range(0.001..=f32::MAX),
+                            );
+                        } else {
+                            source.timeout = None;
+                        }
+                        if ui.button("X").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = to_remove {
+                    self.sources.remove(i);
+                }
+                if ui.button("Add source").clicked() {
+                    self.sources.push(CommandSourceConfig::default());
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!(
+            "Command arbitration (controller priority {}):",
+            self.controller_priority
+        ));
+        for source in &self.sources {
+            ui.label(format!(
+                "- {}: priority {}, timeout {:?}",
+                source.name, source.priority, source.timeout
+            ));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ArbitratedSource {
+    name: String,
+    priority: i32,
+    timeout: Option<f32>,
+    /// Time and last command of the source, `None` if the source is not requesting the control.
+    last_command: Option<(f32, Command)>,
+}
+
+impl ArbitratedSource {
+    fn is_valid(&self, time: f32) -> bool {
+        match (&self.last_command, self.timeout) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some((last_time, _)), Some(timeout)) => time < last_time + timeout,
+        }
+    }
+}
+
+/// Runtime arbitration of the commands of a robot, see the [module documentation](self).
+#[derive(Debug)]
+pub struct CommandArbiter {
+    /// Sources, sorted by decreasing priority.
+    sources: Vec<ArbitratedSource>,
+    /// Source in control after the last arbitration.
+    active_source: Option<String>,
+}
+
+impl CommandArbiter {
+    /// Makes a new [`CommandArbiter`] from the given configuration.
+    pub fn from_config(config: &CommandArbitrationConfig) -> Self {
+        let mut sources: Vec<ArbitratedSource> = config
+            .sources
+            .iter()
+            .map(|source| ArbitratedSource {
+                name: source.name.clone(),
+                priority: source.priority,
+                timeout: source.timeout,
+                last_command: None,
+            })
+            .chain(std::iter::once(ArbitratedSource {
+                name: CONTROLLER_SOURCE.to_string(),
+                priority: config.controller_priority,
+                timeout: None,
+                last_command: None,
+            }))
+            .collect();
+        // Stable sort: the first sources of the configuration win the ties, before the controller
+        sources.sort_by_key(|source| std::cmp::Reverse(source.priority));
+        Self {
+            sources,
+            active_source: None,
+        }
+    }
+
+    /// Submits the `command` of the `source` received at `time`. `None` releases the control.
+    ///
+    /// Returns an error if the source is unknown.
+    pub fn submit(
+        &mut self,
+        source: &str,
+        command: Option<Command>,
+        time: f32,
+    ) -> Result<(), String> {
+        let arbitrated_source = self
+            .sources
+            .iter_mut()
+            .find(|s| s.name == source)
+            .ok_or_else(|| format!("unknown command source `{source}`"))?;
+        arbitrated_source.last_command = command.map(|command| (time, command));
+        Ok(())
+    }
+
+    /// Selects the source in control at `time`: the valid source with the highest priority.
+    /// Returns the command to apply, `None` if no source has sent a command.
+    pub fn arbitrate(&mut self, time: f32) -> Option<Command> {
+        let source = self.sources.iter().find(|source| source.is_valid(time));
+        self.active_source = source.map(|source| source.name.clone());
+        source.and_then(|source| source.last_command.as_ref().map(|(_, c)| c.clone()))
+    }
+
+    /// Source in control after the last arbitration.
+    pub fn active_source(&self) -> Option<&str> {
+        self.active_source.as_deref()
+    }
+
+    /// Next time, after `min_time_excluded`, at which a source loses the control by timeout, so
+    /// that the node runs to give the control back.
+    pub fn next_timeout(&self, min_time_excluded: f32) -> Option<f32> {
+        self.sources
+            .iter()
+            .filter_map(|source| match (&source.last_command, source.timeout) {
+                (Some((last_time, _)), Some(timeout)) => Some(last_time + timeout),
+                _ => None,
+            })
+            .filter(|time| *time > min_time_excluded)
+            .min_by(f32::total_cmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::robot_models::unicycle::UnicycleCommand;
+
+    fn command(speed: f32) -> Command {
+        Command::Unicycle(UnicycleCommand {
+            left_wheel_speed: speed,
+            right_wheel_speed: speed,
+        })
+    }
+
+    fn speed(command: Option<Command>) -> f32 {
+        match command {
+            Some(Command::Unicycle(c)) => c.left_wheel_speed,
+            _ => panic!("Unexpected command {command:?}"),
+        }
+    }
+
+    #[test]
+    fn priorities_and_timeouts() {
+        let mut arbiter = CommandArbiter::from_config(&CommandArbitrationConfig {
+            controller_priority: 0,
+            sources: vec![
+                CommandSourceConfig {
+                    name: "safety".to_string(),
+                    priority: 10,
+                    timeout: None,
+                },
+                CommandSourceConfig {
+                    name: "teleop".to_string(),
+                    priority: 20,
+                    timeout: Some(0.5),
+                },
+            ],
+        });
+        assert!(arbiter.arbitrate(0.).is_none());
+        assert_eq!(arbiter.active_source(), None);
+
+        arbiter
+            .submit(CONTROLLER_SOURCE, Some(command(1.)), 0.)
+            .unwrap();
+        assert_eq!(speed(arbiter.arbitrate(0.)), 1.);
+        assert_eq!(arbiter.active_source(), Some(CONTROLLER_SOURCE));
+
+        // Teleop takes over, until its timeout
+        arbiter.submit("teleop", Some(command(2.)), 1.).unwrap();
+        assert_eq!(speed(arbiter.arbitrate(1.2)), 2.);
+        assert_eq!(arbiter.active_source(), Some("teleop"));
+        assert_eq!(arbiter.next_timeout(1.2), Some(1.5));
+        arbiter
+            .submit(CONTROLLER_SOURCE, Some(command(3.)), 1.2)
+            .unwrap();
+        assert_eq!(speed(arbiter.arbitrate(1.6)), 3.);
+        assert_eq!(arbiter.active_source(), Some(CONTROLLER_SOURCE));
+
+        // Safety supervisor, without timeout, until it releases the control
+        arbiter.submit("safety", Some(command(0.)), 2.).unwrap();
+        assert_eq!(speed(arbiter.arbitrate(10.)), 0.);
+        assert_eq!(arbiter.active_source(), Some("safety"));
+        arbiter.submit("safety", None, 10.).unwrap();
+        assert_eq!(speed(arbiter.arbitrate(10.)), 3.);
+
+        assert!(arbiter.submit("unknown", None, 10.).is_err());
+    }
+
+    #[test]
+    fn config_check() {
+        let config = CommandArbitrationConfig {
+            controller_priority: 0,
+            sources: vec![
+                CommandSourceConfig::default(),
+                CommandSourceConfig::default(),
+                CommandSourceConfig {
+                    name: CONTROLLER_SOURCE.to_string(),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(config.do_check().unwrap_err().len(), 1);
+        assert!(config.sources[2].do_check().is_err());
+    }
+}
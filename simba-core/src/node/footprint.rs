@@ -0,0 +1,322 @@
+//! Shape of the robots on the ground.
+//!
+//! The footprint is given in the robot frame (x forward, y to the left). It is used to draw the
+//! robots, to find the overlapping robots in the placement tool ([`FootprintConfig::collides`]),
+//! and as the radius by which the [`GridPlanner`](crate::navigators::grid_planner::GridPlanner)
+//! inflates the obstacles to plan a path for the robot as a point
+//! ([`FootprintConfig::bounding_radius`]).
+
+use config_checker::*;
+use nalgebra::Vector2;
+use simba_macros::config_derives;
+
+use crate::utils::geometry::{segment_circle_intersection, segments_intersection};
+#[cfg(feature = "gui")]
+use crate::{gui::UIComponent, simulator::SimulatorConfig};
+
+/// Circular footprint, centered on the robot pose.
+///
+/// Default values:
+/// - `radius`: `0.1`
+#[config_derives]
+pub struct CircleFootprintConfig {
+    /// Radius of the robot, in meters.
+    pub radius: f32,
+}
+
+impl Default for CircleFootprintConfig {
+    fn default() -> Self {
+        Self { radius: 0.1 }
+    }
+}
+
+impl Check for CircleFootprintConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.radius <= 0. {
+            return Err(vec![format!(
+                "Footprint radius should be strictly positive, got {}",
+                self.radius
+            )]);
+        }
+        Ok(())
+    }
+}
+
+/// Polygonal footprint, with the vertices in the robot frame.
+///
+/// Default values:
+/// - `points`: square of 0.2 m centered on the robot pose
+#[config_derives]
+pub struct PolygonFootprintConfig {
+    /// Vertices `[x, y]` of the polygon, in order, in the robot frame.
+    pub points: Vec<[f32; 2]>,
+}
+
+impl Default for PolygonFootprintConfig {
+    fn default() -> Self {
+        Self {
+            points: vec![[0.1, 0.1], [-0.1, 0.1], [-0.1, -0.1], [0.1, -0.1]],
+        }
+    }
+}
+
+impl Check for PolygonFootprintConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.points.len() < 3 {
+            return Err(vec![format!(
+                "Footprint polygon needs at least 3 points, got {}",
+                self.points.len()
+            )]);
+        }
+        Ok(())
+    }
+}
+
+/// Footprint of a robot.
+///
+/// Default value: [`FootprintConfig::Point`].
+///
+/// # Example
+/// ```yaml
+/// footprint:
+///   type: Polygon
+///   points: [[0.3, 0.2], [-0.1, 0.2], [-0.1, -0.2], [0.3, -0.2]]
+/// ```
+#[config_derives]
+pub enum FootprintConfig {
+    /// The robot is a point.
+    Point,
+    /// Circular robot.
+    #[check]
+    Circle(CircleFootprintConfig),
+    /// Polygonal robot.
+    #[check]
+    Polygon(PolygonFootprintConfig),
+}
+
+impl Default for FootprintConfig {
+    fn default() -> Self {
+        Self::Point
+    }
+}
+
+/// Footprint placed at a pose, in the world frame.
+enum PlacedFootprint {
+    Point(Vector2<f32>),
+    Circle(Vector2<f32>, f32),
+    Polygon(Vec<Vector2<f32>>),
+}
+
+impl PlacedFootprint {
+    fn contains(&self, point: &Vector2<f32>) -> bool {
+        match self {
+            Self::Point(p) => p == point,
+            Self::Circle(center, radius) => (point - center).norm() <= *radius,
+            Self::Polygon(vertices) => polygon_contains(vertices, point),
+        }
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Point(p), other) | (other, Self::Point(p)) => other.contains(p),
+            (Self::Circle(c1, r1), Self::Circle(c2, r2)) => (c1 - c2).norm() <= r1 + r2,
+            (Self::Circle(center, radius), Self::Polygon(vertices))
+            | (Self::Polygon(vertices), Self::Circle(center, radius)) => {
+                // Circle inside the polygon, polygon inside the circle, or crossing edge
+                polygon_contains(vertices, center)
+                    || (vertices[0] - center).norm() <= *radius
+                    || edges(vertices)
+                        .any(|(a, b)| segment_circle_intersection(a, b, center, *radius).is_some())
+            }
+            (Self::Polygon(v1), Self::Polygon(v2)) => {
+                polygon_contains(v1, &v2[0])
+                    || polygon_contains(v2, &v1[0])
+                    || edges(v1).any(|(a1, a2)| {
+                        edges(v2).any(|(b1, b2)| segments_intersection(a1, a2, b1, b2).is_some())
+                    })
+            }
+        }
+    }
+}
+
+/// Returns `true` if the `point` is inside the polygon (ray casting along +x).
+fn polygon_contains(vertices: &[Vector2<f32>], point: &Vector2<f32>) -> bool {
+    let mut inside = false;
+    for (a, b) in edges(vertices) {
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Edges of a closed polygon.
+fn edges(vertices: &[Vector2<f32>]) -> impl Iterator<Item = (&Vector2<f32>, &Vector2<f32>)> {
+    vertices.iter().zip(vertices.iter().cycle().skip(1))
+}
+
+impl FootprintConfig {
+    /// Radius of the smallest circle centered on the robot pose containing the footprint.
+    ///
+    /// Planners considering the robot as a point should inflate the obstacles by this radius.
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            Self::Point => 0.,
+            Self::Circle(c) => c.radius,
+            Self::Polygon(c) => c
+                .points
+                .iter()
+                .map(|p| Vector2::from(*p).norm())
+                .fold(0., f32::max),
+        }
+    }
+
+    /// Vertices of the polygon footprint at `pose` (`[x, y, orientation]`), in the world frame.
+    ///
+    /// Empty for the other footprints.
+    pub fn outline(&self, pose: &[f32; 3]) -> Vec<Vector2<f32>> {
+        match self {
+            Self::Polygon(c) => {
+                let (sin, cos) = pose[2].sin_cos();
+                c.points
+                    .iter()
+                    .map(|[x, y]| {
+                        Vector2::new(pose[0] + cos * x - sin * y, pose[1] + sin * x + cos * y)
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn placed(&self, pose: &[f32; 3]) -> PlacedFootprint {
+        let position = Vector2::new(pose[0], pose[1]);
+        match self {
+            Self::Point => PlacedFootprint::Point(position),
+            Self::Circle(c) => PlacedFootprint::Circle(position, c.radius),
+            Self::Polygon(_) => PlacedFootprint::Polygon(self.outline(pose)),
+        }
+    }
+
+    /// Returns `true` if the robot at `pose` covers the world `point`.
+    pub fn contains(&self, pose: &[f32; 3], point: &Vector2<f32>) -> bool {
+        self.placed(pose).contains(point)
+    }
+
+    /// Returns `true` if this footprint at `pose` overlaps the `other` footprint at `other_pose`.
+    pub fn collides(
+        &self,
+        pose: &[f32; 3],
+        other: &FootprintConfig,
+        other_pose: &[f32; 3],
+    ) -> bool {
+        self.placed(pose).intersects(&other.placed(other_pose))
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for FootprintConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Footprint")
+            .id_salt(format!("footprint-{}", unique_id))
+            .show(ui, |ui| {
+                let mut current_str = self.to_string();
+                ui.horizontal(|ui| {
+                    use crate::{gui::utils::string_combobox, utils::enum_tools::ToVec};
+
+                    ui.label("Shape:");
+                    string_combobox(
+                        ui,
+                        &FootprintConfig::to_vec(),
+                        &mut current_str,
+                        format!("footprint-choice-{}", unique_id),
+                    );
+                });
+                if current_str != self.to_string() {
+                    *self = match current_str.as_str() {
+                        "Point" => FootprintConfig::Point,
+                        "Circle" => FootprintConfig::Circle(CircleFootprintConfig::default()),
+                        "Polygon" => FootprintConfig::Polygon(PolygonFootprintConfig::default()),
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+                match self {
+                    FootprintConfig::Point => {}
+                    FootprintConfig::Circle(c) => {
+                        ui.horizontal(|ui| {
+                            ui.label("Radius:");
+                            ui.add(
+                                egui::DragValue::new(&mut c.radius)
+                                    .speed(0.01)
+                                    .range(0.001..=f32::MAX),
+                            );
+                        });
+                    }
+                    FootprintConfig::Polygon(c) => {
+                        let mut to_remove = None;
+                        for (i, point) in c.points.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("Point {i}:"));
+                                ui.add(egui::DragValue::new(&mut point[0]).speed(0.01));
+                                ui.add(egui::DragValue::new(&mut point[1]).speed(0.01));
+                                if ui.button("X").clicked() {
+                                    to_remove = Some(i);
+                                }
+                            });
+                        }
+                        if let Some(i) = to_remove {
+                            c.points.remove(i);
+                        }
+                        if ui.button("Add point").clicked() {
+                            c.points.push([0., 0.]);
+                        }
+                    }
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        match self {
+            FootprintConfig::Point => ui.label("Footprint: point"),
+            FootprintConfig::Circle(c) => {
+                ui.label(format!("Footprint: circle of radius {}", c.radius))
+            }
+            FootprintConfig::Polygon(c) => ui.label(format!("Footprint: polygon {:?}", c.points)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footprint_collisions() {
+        let circle = FootprintConfig::Circle(CircleFootprintConfig { radius: 0.5 });
+        let rectangle = FootprintConfig::Polygon(PolygonFootprintConfig {
+            points: vec![[1., 0.2], [-1., 0.2], [-1., -0.2], [1., -0.2]],
+        });
+        assert_eq!(rectangle.bounding_radius(), Vector2::new(1., 0.2).norm());
+
+        // Rectangle along x at the origin, circle above its end
+        assert!(circle.collides(&[0.9, 0.6, 0.], &rectangle, &[0., 0., 0.]));
+        assert!(!circle.collides(&[0.9, 0.8, 0.], &rectangle, &[0., 0., 0.]));
+        // Rotated rectangle along y reaches the circle
+        assert!(circle.collides(&[0., 1.4, 0.], &rectangle, &[0., 0., 1.5707964]));
+        assert!(!circle.collides(&[0., 1.4, 0.], &rectangle, &[0., 0., 0.]));
+        // Crossing rectangles, without vertex inside the other
+        assert!(rectangle.collides(&[0., 0., 0.], &rectangle, &[0., 0., 1.5707964]));
+        assert!(FootprintConfig::Point.collides(&[0.5, 0., 0.], &rectangle, &[0., 0., 0.]));
+        assert!(!FootprintConfig::Point.collides(&[0.5, 0.5, 0.], &rectangle, &[0., 0., 0.]));
+    }
+}
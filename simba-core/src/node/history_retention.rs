@@ -0,0 +1,158 @@
+//! Retention of the state history of the nodes.
+//!
+//! The record history of each node (used to display the node at any past time) is a
+//! [`TimeOrderedData`](simba_com::time_ordered_data::TimeOrderedData) which grows with the
+//! simulated time. [`HistoryRetentionConfig`] bounds it to a window (last seconds or last
+//! entries), with optional keyframes to keep a coarse history out of the window.
+//!
+//! Back-in-time data are bounded by the network delays: the window should be larger than the
+//! maximal reception delay of the messages received by the node.
+
+use config_checker::*;
+use simba_com::time_ordered_data::RetentionPolicy;
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::{gui::UIComponent, simulator::SimulatorConfig};
+
+/// Retention window of the state history of a node.
+///
+/// By default, the whole history is kept.
+///
+/// # Example
+/// ```yaml
+/// history:
+///   max_age: 60.         # Keep the last minute
+///   max_entries: null
+///   keyframe_period: 10. # and one state every 10 s before
+/// ```
+#[config_derives]
+pub struct HistoryRetentionConfig {
+    /// Keep the states of the last `max_age` seconds. `null` for no time limit.
+    pub max_age: Option<f32>,
+    /// Keep at most the `max_entries` most recent states (keyframes excluded). `null` for no limit.
+    pub max_entries: Option<usize>,
+    /// Out of the window, keep one state every `keyframe_period` seconds. `null` for no keyframes.
+    pub keyframe_period: Option<f32>,
+}
+
+impl Default for HistoryRetentionConfig {
+    /// Default retention: whole history.
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            max_entries: None,
+            keyframe_period: None,
+        }
+    }
+}
+
+impl Check for HistoryRetentionConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Some(max_age) = self.max_age
+            && max_age <= 0.
+        {
+            errors.push(format!(
+                "History max_age should be strictly positive, got {max_age}"
+            ));
+        }
+        if self.max_entries == Some(0) {
+            errors.push("History max_entries should be strictly positive, got 0".to_string());
+        }
+        if let Some(period) = self.keyframe_period
+            && period <= 0.
+        {
+            errors.push(format!(
+                "History keyframe_period should be strictly positive, got {period}"
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl HistoryRetentionConfig {
+    /// Retention policy to apply to the history, `None` to keep the whole history.
+    pub fn retention_policy(&self) -> Option<RetentionPolicy> {
+        let policy = RetentionPolicy {
+            max_age: self.max_age,
+            max_entries: self.max_entries,
+            keyframe_period: self.keyframe_period,
+        };
+        policy.is_bounded().then_some(policy)
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for HistoryRetentionConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("History").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Max age (s): ");
+                if let Some(max_age) = &mut self.max_age {
+                    ui.add(egui::DragValue::new(max_age).range(0.001..=f32::MAX));
+                    if ui.button("X").clicked() {
+                        self.max_age = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.max_age = Some(60.);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Max entries: ");
+                if let Some(max_entries) = &mut self.max_entries {
+                    ui.add(egui::DragValue::new(max_entries).range(1..=usize::MAX));
+                    if ui.button("X").clicked() {
+                        self.max_entries = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.max_entries = Some(1000);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Keyframe period (s): ");
+                if let Some(period) = &mut self.keyframe_period {
+                    ui.add(egui::DragValue::new(period).range(0.001..=f32::MAX));
+                    if ui.button("X").clicked() {
+                        self.keyframe_period = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.keyframe_period = Some(10.);
+                }
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        let to_string = |value: Option<String>| value.unwrap_or("unlimited".to_string());
+        egui::CollapsingHeader::new("History").show(ui, |ui| {
+            ui.label(format!(
+                "Max age: {}",
+                to_string(self.max_age.map(|a| format!("{a} s")))
+            ));
+            ui.label(format!(
+                "Max entries: {}",
+                to_string(self.max_entries.map(|n| n.to_string()))
+            ));
+            ui.label(format!(
+                "Keyframe period: {}",
+                self.keyframe_period
+                    .map_or("none".to_string(), |p| format!("{p} s"))
+            ));
+        });
+    }
+}
@@ -8,28 +8,38 @@
 //! Node construction is delegated to [`NodeFactory`](crate::node::node_factory::NodeFactory),
 //! which assembles concrete implementations from configuration.
 
+pub mod capabilities;
+pub mod command_arbitration;
+pub mod footprint;
+pub mod history_retention;
 pub mod node_factory;
+pub mod thread_config;
 
+use command_arbitration::{CONTROLLER_SOURCE, CommandArbiter};
 use node_factory::{ComputationUnitRecord, NodeRecord, NodeType, RobotRecord};
 use serde::{Deserialize, Serialize};
 use simba_com::pub_sub::{MultiClientTrait, PathKey};
 use simba_macros::EnumToString;
+use thread_config::ThreadConfig;
 
 use core::f32;
 use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError};
+use std::time::{Duration, Instant};
 
-use log::{debug, info};
+use log::{debug, info, warn};
 
 use crate::environment::Environment;
 use crate::errors::{SimbaError, SimbaErrorTypes};
 use crate::networking;
 use crate::networking::network::MessageFlag;
+use crate::networking::reconfigure::{NodeReconfigureMessage, ReconfigurationOutcome};
 use crate::physics::robot_models::Command;
 use crate::simulator::SimbaBrokerMultiClient;
 use crate::state_estimators::State;
 use crate::time_analysis::TimeAnalysisNode;
+use crate::utils::lock_recovery::{RecoverMutex, RecoverRwLock};
 use crate::utils::read_only_lock::RoLock;
 use crate::utils::{SharedMutex, SharedRoLock, SharedRwLock};
 use crate::{
@@ -39,13 +49,17 @@ use crate::{
     errors::SimbaResult,
     logger::is_enabled,
     navigators::Navigator,
-    networking::network::Network,
+    networking::network::{Network, NetworkRecord},
     networking::service_manager::ServiceManager,
     physics::Physics,
     recordable::Recordable,
-    sensors::sensor_manager::SensorManager,
-    simulator::TimeCv,
-    state_estimators::{BenchStateEstimator, BenchStateEstimatorRecord, StateEstimator},
+    sensors::{Observation, ObservationLatencyRecord, sensor_manager::SensorManager},
+    simulator::{AnnotationLog, NodeHeartbeat, TimeCv},
+    state_estimators::{
+        BenchStateEstimator, StateEstimator, WorldStateRecord,
+        dataset::{EstimatorDatasetEntry, EstimatorDatasetLogger, EstimatorStep},
+        watchdog::{DivergenceRecord, EstimatorWatchdog, WatchdogReinitialization},
+    },
     utils::maths::round_precision,
 };
 
@@ -62,6 +76,8 @@ pub enum NodeState {
     Zombie,
     /// Node has fully terminated and no longer participates in simulation.
     Terminated,
+    /// Node stopped after an error or a panic and no longer participates in simulation.
+    Failed,
 }
 
 /// Metadata describing a node, used for introspection, logging, and inter-node coordination.
@@ -79,6 +95,10 @@ pub struct NodeMetaData {
     pub state: NodeState,
     /// Current ground-truth planar position when available.
     pub position: Option<[f32; 2]>,
+    /// Radius of the footprint of the node, `0` for a point.
+    pub footprint_radius: f32,
+    /// Height of the node, `0` if it does not obstruct the view.
+    pub height: f32,
 }
 
 // Node itself
@@ -134,6 +154,39 @@ pub struct Node {
     pub(self) current_command: Option<Command>,
 
     pub(self) environment: Arc<Environment>,
+
+    /// Kill received, applied at the end of the time step by [`Self::apply_kill_request`].
+    #[cfg(feature = "force_hard_determinism")]
+    pub(self) kill_requested: bool,
+
+    /// Logger of the state estimator inputs and outputs, if enabled.
+    pub(self) estimator_dataset: Option<EstimatorDatasetLogger>,
+
+    /// Scheduling configuration of the node thread.
+    pub(self) thread_config: ThreadConfig,
+
+    /// Annotations of the run, shared with the simulator.
+    pub(self) annotations: AnnotationLog,
+
+    /// Minimal time between two time steps of the node.
+    pub(self) min_time_step: Option<f32>,
+    /// Time of the last time step run.
+    pub(self) last_step_time: Option<f32>,
+    /// Number of rounds skipped because of `min_time_step`.
+    pub(self) skipped_steps: usize,
+    /// Number of calls to [`Node::sync_with_others`] since the creation of the node.
+    pub(self) sync_points: usize,
+    /// Number of calls to [`Node::sync_with_others`] in the last [`Node::run_time_step`],
+    /// reproduced by the skipped time steps.
+    pub(self) time_step_sync_points: usize,
+    /// Latencies of the observations consumed during the current time step.
+    pub(self) observation_latencies: Vec<ObservationLatencyRecord>,
+    /// Divergence watchdogs of the state estimators.
+    pub(self) estimator_watchdogs: Vec<EstimatorWatchdog>,
+    /// Divergences detected by the watchdogs since the last record.
+    pub(self) estimator_divergences: Vec<DivergenceRecord>,
+    /// Arbitration of the commands between the controller and the other sources, if configured.
+    pub(self) command_arbiter: Option<CommandArbiter>,
 }
 
 impl Node {
@@ -151,14 +204,13 @@ impl Node {
         }
         let service_manager = self.service_manager();
         service_manager
-            .write()
-            .unwrap()
+            .write_recover()
             .make_links(service_manager_list, self);
 
         self.other_node_names = service_manager_list
             .iter()
             .filter_map(|n| {
-                if n.0 != &self.node_meta_data.read().unwrap().name {
+                if n.0 != &self.node_meta_data.read_recover().name {
                     Some(n.0.clone())
                 } else {
                     None
@@ -167,41 +219,39 @@ impl Node {
             .collect();
 
         if let Some(physics) = self.physics() {
-            physics.write().unwrap().post_init(self).unwrap();
+            physics.write_recover().post_init(self).unwrap();
         }
         if let Some(state_estimator) = self.state_estimator() {
-            state_estimator.write().unwrap().post_init(self).unwrap();
+            state_estimator.write_recover().post_init(self).unwrap();
         }
         if let Some(state_estimator_bench) = self.state_estimator_bench.clone() {
-            for state_estimator in state_estimator_bench.read().unwrap().iter() {
+            for state_estimator in state_estimator_bench.read_recover().iter() {
                 state_estimator
                     .state_estimator
-                    .write()
-                    .unwrap()
+                    .write_recover()
                     .post_init(self)
                     .unwrap();
             }
         }
         if let Some(sensor_manager) = self.sensor_manager() {
             sensor_manager
-                .write()
-                .unwrap()
+                .write_recover()
                 .post_init(self, initial_time)
                 .unwrap();
         }
         if let Some(navigator) = self.navigator() {
-            navigator.write().unwrap().post_init(self).unwrap();
+            navigator.write_recover().post_init(self).unwrap();
         }
         // services: Vec<SharedRwLock<Box<dyn ServiceInterface>>>>,
         if let Some(controller) = self.controller() {
-            controller.write().unwrap().post_init(self).unwrap();
+            controller.write_recover().post_init(self).unwrap();
         }
 
         let (node_server, node_client) =
-            internal_api::make_node_api(&self.node_meta_data.read().unwrap().node_type);
+            internal_api::make_node_api(&self.node_meta_data.read_recover().node_type);
         self.node_server = Some(node_server);
         {
-            let meta_data = &mut self.node_meta_data.write().unwrap();
+            let meta_data = &mut self.node_meta_data.write_recover();
             let name = meta_data.name.clone();
             let model_name = meta_data.model_name.clone();
             meta_data.labels.push(name);
@@ -220,6 +270,31 @@ impl Node {
         self.run_time_step(time, time_cv)
     }
 
+    /// Returns `true` if the round at `time` is closer to the last time step of the node than its
+    /// minimal time step, so the node should skip it with [`Self::skip_time_step`].
+    pub(crate) fn is_below_time_step_floor(&self, time: f32) -> bool {
+        match (self.min_time_step, self.last_step_time) {
+            (Some(min_time_step), Some(last_time)) => {
+                time < last_time + min_time_step - TIME_ROUND / 2.
+            }
+            _ => false,
+        }
+    }
+
+    /// Skip the round at `time`: the modules are not run, but the node goes through the same
+    /// synchronisation points as [`Self::run_time_step`], and handles its messages, so that the
+    /// other nodes can run their time step.
+    pub(crate) fn skip_time_step(&mut self, time: f32, time_cv: &TimeCv) {
+        if is_enabled(crate::logger::InternalLog::NodeRunningDetailed) {
+            debug!("Skip time {time} (min time step)");
+        }
+        self.skipped_steps += 1;
+        self.process_messages();
+        for _ in 0..self.time_step_sync_points {
+            self.sync_with_others(time_cv, time);
+        }
+    }
+
     /// Process all the messages: one-way (network) and two-way (services).
     ///
     /// Processing messages mean here to transfer all the pending messages from the network to the corresponding modules (physics, state estimator, navigator, controller, sensor manager).
@@ -229,8 +304,7 @@ impl Node {
             .service_manager
             .as_ref()
             .unwrap()
-            .read()
-            .unwrap()
+            .read_recover()
             .process_requests();
         nb_msg += self.node_message_client.next_message_time().is_some() as usize;
         nb_msg
@@ -253,45 +327,47 @@ impl Node {
     ///
     /// Then, the node state is saved.
     fn run_time_step(&mut self, time: f32, time_cv: &TimeCv) -> SimbaResult<()> {
-        if self.node_meta_data.read().unwrap().state != NodeState::Running {
+        if self.node_meta_data.read_recover().state != NodeState::Running {
             return Err(SimbaError::new(
                 SimbaErrorTypes::ImplementationError,
                 "Only a Running node should be run!".to_string(),
             ));
         }
         info!("Run time {}", time);
+        self.last_step_time = Some(time);
+        self.observation_latencies.clear();
+        let first_sync_point = self.sync_points;
 
         // Update the true state
         if let Some(physics) = &self.physics {
-            physics.write().unwrap().update_state(time);
-            let pose = physics.read().unwrap().state(time).pose;
-            self.node_meta_data.write().unwrap().position = Some([pose[0], pose[1]]);
+            physics.write_recover().update_state(time);
+            let pose = physics.read_recover().state(time).pose;
+            self.node_meta_data.write_recover().position = Some([pose[0], pose[1]]);
         }
 
         self.sync_with_others(time_cv, time);
 
         // Pre loop calls to manage messages
         if let Some(state_estimator) = self.state_estimator() {
-            state_estimator.write().unwrap().pre_loop_hook(self, time);
+            state_estimator.write_recover().pre_loop_hook(self, time);
         }
         if let Some(state_estimator_bench) = self.state_estimator_bench.clone() {
-            for state_estimator in state_estimator_bench.read().unwrap().iter() {
+            for state_estimator in state_estimator_bench.read_recover().iter() {
                 state_estimator
                     .state_estimator
-                    .write()
-                    .unwrap()
+                    .write_recover()
                     .pre_loop_hook(self, time);
             }
         }
         if let Some(controller) = self.controller() {
-            controller.write().unwrap().pre_loop_hook(self, time);
+            controller.write_recover().pre_loop_hook(self, time);
         }
         if let Some(navigator) = self.navigator() {
-            navigator.write().unwrap().pre_loop_hook(self, time);
+            navigator.write_recover().pre_loop_hook(self, time);
         }
 
         if let Some(sensor_manager) = &self.sensor_manager() {
-            sensor_manager.write().unwrap().handle_messages(time);
+            sensor_manager.write_recover().handle_messages(time);
         }
         if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
             debug!("Pre prediction step wait");
@@ -302,60 +378,81 @@ impl Node {
 
         // If it is time for the state estimator to do the prediction
         if let Some(state_estimator) = &self.state_estimator()
-            && time >= state_estimator.read().unwrap().next_time_step()
+            && time >= state_estimator.read_recover().next_time_step()
         {
             // Prediction step
             let ta = self.time_analysis.as_ref().map(|time_analysis| {
-                time_analysis.lock().unwrap().time_analysis(
+                time_analysis.lock_recover().time_analysis(
                     time,
                     "control_loop_state_estimator_prediction_step".to_string(),
                 )
             });
-            state_estimator.write().unwrap().prediction_step(
+            let previous_state = self.estimator_previous_state(state_estimator, true);
+            state_estimator.write_recover().prediction_step(
                 self,
                 self.current_command.clone(),
                 time,
             );
             if let Some(time_analysis) = &self.time_analysis {
                 time_analysis
-                    .lock()
-                    .unwrap()
+                    .lock_recover()
                     .finished_time_analysis(ta.unwrap());
             }
+            self.log_estimator_step(
+                "state_estimator",
+                EstimatorStep::Prediction,
+                time,
+                previous_state,
+                state_estimator,
+                &[],
+            )?;
+            self.watch_estimator("state_estimator", state_estimator, &[], time);
             do_control_loop = true;
         }
 
         if let Some(state_estimator_bench) = &self.state_estimator_bench() {
-            for state_estimator in state_estimator_bench.read().unwrap().iter() {
+            for state_estimator in state_estimator_bench.read_recover().iter() {
                 if time
                     >= state_estimator
                         .state_estimator
-                        .read()
-                        .unwrap()
+                        .read_recover()
                         .next_time_step()
                 {
                     let ta = self.time_analysis.as_ref().map(|time_analysis| {
                         time_analysis
-                            .lock()
-                            .unwrap()
+                            .lock_recover()
                             .time_analysis(time, state_estimator.name.clone() + "_prediction_step")
                     });
+                    let previous_state =
+                        self.estimator_previous_state(&state_estimator.state_estimator, false);
                     state_estimator
                         .state_estimator
-                        .write()
-                        .unwrap()
+                        .write_recover()
                         .prediction_step(self, self.current_command.clone(), time);
                     if let Some(time_analysis) = &self.time_analysis {
                         time_analysis
-                            .lock()
-                            .unwrap()
+                            .lock_recover()
                             .finished_time_analysis(ta.unwrap());
                     }
+                    self.log_estimator_step(
+                        &state_estimator.name,
+                        EstimatorStep::Prediction,
+                        time,
+                        previous_state,
+                        &state_estimator.state_estimator,
+                        &[],
+                    )?;
+                    self.watch_estimator(
+                        &state_estimator.name,
+                        &state_estimator.state_estimator,
+                        &[],
+                        time,
+                    );
                 }
             }
         }
         if let Some(sensor_manager) = &self.sensor_manager() {
-            sensor_manager.write().unwrap().handle_messages(time);
+            sensor_manager.write_recover().handle_messages(time);
         }
         if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
             debug!("Post prediction step wait");
@@ -363,11 +460,8 @@ impl Node {
         self.sync_with_others(time_cv, time);
 
         if let Some(sensor_manager) = &self.sensor_manager() {
-            sensor_manager.write().unwrap().handle_messages(time);
-            sensor_manager
-                .write()
-                .unwrap()
-                .make_observations(self, time);
+            sensor_manager.write_recover().handle_messages(time);
+            sensor_manager.write_recover().make_observations(self, time);
         }
 
         if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
@@ -376,9 +470,9 @@ impl Node {
         self.sync_with_others(time_cv, time);
 
         if let Some(sensor_manager) = &self.sensor_manager() {
-            sensor_manager.write().unwrap().handle_messages(time);
+            sensor_manager.write_recover().handle_messages(time);
             // Make observations (if it is the right time)
-            let observations = sensor_manager.write().unwrap().get_observations();
+            let observations = sensor_manager.write_recover().get_observations();
             if is_enabled(crate::logger::InternalLog::SensorManager) {
                 debug!("Got {} observations", observations.len());
             }
@@ -386,42 +480,74 @@ impl Node {
                 // Treat the observations
                 if let Some(state_estimator) = &self.state_estimator() {
                     let ta = self.time_analysis.as_ref().map(|time_analysis| {
-                        time_analysis.lock().unwrap().time_analysis(
+                        time_analysis.lock_recover().time_analysis(
                             time,
                             "control_loop_state_estimator_correction_step".to_string(),
                         )
                     });
+                    let previous_state = self.estimator_previous_state(state_estimator, true);
                     state_estimator
-                        .write()
-                        .unwrap()
+                        .write_recover()
                         .correction_step(self, &observations, time);
                     if let Some(time_analysis) = &self.time_analysis {
                         time_analysis
-                            .lock()
-                            .unwrap()
+                            .lock_recover()
                             .finished_time_analysis(ta.unwrap());
                     }
+                    self.record_observation_latencies("state_estimator", &observations, time);
+                    self.log_estimator_step(
+                        "state_estimator",
+                        EstimatorStep::Correction,
+                        time,
+                        previous_state,
+                        state_estimator,
+                        &observations,
+                    )?;
+                    self.watch_estimator("state_estimator", state_estimator, &observations, time);
                 }
 
                 if let Some(state_estimator_bench) = &self.state_estimator_bench() {
-                    for state_estimator in state_estimator_bench.read().unwrap().iter() {
+                    for state_estimator in state_estimator_bench.read_recover().iter() {
+                        let bench_observations = state_estimator.filter_observations(&observations);
+                        if bench_observations.is_empty() {
+                            continue;
+                        }
                         let ta = self.time_analysis.as_ref().map(|time_analysis| {
-                            time_analysis.lock().unwrap().time_analysis(
+                            time_analysis.lock_recover().time_analysis(
                                 time,
                                 state_estimator.name.clone() + "_correction_step",
                             )
                         });
+                        let previous_state =
+                            self.estimator_previous_state(&state_estimator.state_estimator, false);
                         state_estimator
                             .state_estimator
-                            .write()
-                            .unwrap()
-                            .correction_step(self, &observations, time);
+                            .write_recover()
+                            .correction_step(self, &bench_observations, time);
                         if let Some(time_analysis) = &self.time_analysis {
                             time_analysis
-                                .lock()
-                                .unwrap()
+                                .lock_recover()
                                 .finished_time_analysis(ta.unwrap());
                         }
+                        self.record_observation_latencies(
+                            &state_estimator.name,
+                            &bench_observations,
+                            time,
+                        );
+                        self.log_estimator_step(
+                            &state_estimator.name,
+                            EstimatorStep::Correction,
+                            time,
+                            previous_state,
+                            &state_estimator.state_estimator,
+                            &bench_observations,
+                        )?;
+                        self.watch_estimator(
+                            &state_estimator.name,
+                            &state_estimator.state_estimator,
+                            &bench_observations,
+                            time,
+                        );
                     }
                 }
             }
@@ -439,8 +565,7 @@ impl Node {
                         .navigator()
                         .as_ref()
                         .unwrap()
-                        .read()
-                        .unwrap()
+                        .read_recover()
                         .next_time_step()
                         .unwrap_or(f32::INFINITY))
             || (self.controller().is_some()
@@ -449,81 +574,91 @@ impl Node {
                         .controller()
                         .as_ref()
                         .unwrap()
-                        .read()
-                        .unwrap()
+                        .read_recover()
                         .next_time_step()
                         .unwrap_or(f32::INFINITY))
         {
             let state_estimator = &self.state_estimator().unwrap();
-            let world_state = state_estimator.read().unwrap().world_state();
+            let world_state = state_estimator.read_recover().world_state();
 
             // Compute the error to the planned path
             let ta = self.time_analysis.as_ref().map(|time_analysis| {
                 time_analysis
-                    .lock()
-                    .unwrap()
+                    .lock_recover()
                     .time_analysis(time, "control_loop_navigator_compute_error".to_string())
             });
             let error = self
                 .navigator()
                 .as_ref()
                 .unwrap()
-                .write()
-                .unwrap()
+                .write_recover()
                 .compute_error(self, world_state);
             if let Some(time_analysis) = &self.time_analysis {
                 time_analysis
-                    .lock()
-                    .unwrap()
+                    .lock_recover()
                     .finished_time_analysis(ta.unwrap());
             }
 
             // Compute the command from the error
             let ta = self.time_analysis.as_ref().map(|time_analysis| {
                 time_analysis
-                    .lock()
-                    .unwrap()
+                    .lock_recover()
                     .time_analysis(time, "control_loop_controller_make_command".to_string())
             });
             let command = self
                 .controller()
                 .as_ref()
                 .unwrap()
-                .write()
-                .unwrap()
+                .write_recover()
                 .make_command(self, &error, time);
             if let Some(time_analysis) = &self.time_analysis {
                 time_analysis
-                    .lock()
-                    .unwrap()
+                    .lock_recover()
                     .finished_time_analysis(ta.unwrap());
             }
 
-            // Apply the command to the physics
-            self.physics
-                .as_ref()
-                .unwrap()
-                .write()
-                .unwrap()
-                .apply_command(&command, time);
-            self.current_command = Some(command);
+            if let Some(arbiter) = &mut self.command_arbiter {
+                arbiter
+                    .submit(CONTROLLER_SOURCE, Some(command), time)
+                    .unwrap();
+            } else {
+                self.apply_command(command, time);
+            }
+        }
+
+        if let Some(arbiter) = &mut self.command_arbiter
+            && let Some(command) = arbiter.arbitrate(time)
+        {
+            self.apply_command(command, time);
         }
 
         if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
             debug!("Pre-save wait");
         }
         self.sync_with_others(time_cv, time);
+        self.time_step_sync_points = self.sync_points - first_sync_point;
 
         Ok(())
     }
 
+    /// Apply the command to the physics.
+    fn apply_command(&mut self, command: Command, time: f32) {
+        self.physics
+            .as_ref()
+            .unwrap()
+            .write_recover()
+            .apply_command(&command, time);
+        self.current_command = Some(command);
+    }
+
     /// Synchronize this node with the other nodes at an intermediate barrier.
     ///
     /// The method repeatedly processes pending messages while waiting for the
     /// synchronization parity to change.
     pub(crate) fn sync_with_others(&mut self, time_cv: &TimeCv, time: f32) {
-        let mut lk = time_cv.waiting.lock().unwrap();
-        let waiting_parity = *time_cv.intermediate_parity.lock().unwrap();
+        self.sync_points += 1;
+        let mut lk = time_cv.waiting.lock_recover();
+        let waiting_parity = *time_cv.intermediate_parity.lock_recover();
         *lk += 1;
         if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
             debug!("Increase intermediate waiting nodes: {}", *lk);
@@ -567,15 +702,22 @@ impl Node {
             // }
             // std::mem::drop(circulating_messages);
             time_cv.condvar.notify_all();
-            if self.process_messages() == 0 {
-                lk = time_cv.condvar.wait(lk).unwrap();
+            if self.process_messages() == 0 && !*time_cv.force_finish.lock_recover() {
+                lk = time_cv
+                    .condvar
+                    .wait(lk)
+                    .unwrap_or_else(PoisonError::into_inner);
             }
-            if waiting_parity != *time_cv.intermediate_parity.lock().unwrap() {
+            if waiting_parity != *time_cv.intermediate_parity.lock_recover() {
                 if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
                     debug!("[intermediate wait] End wait");
                 }
                 return;
             }
+            // The simulator stopped synchronizing (e.g. after an error in the simulator loop)
+            if *time_cv.force_finish.lock_recover() {
+                return;
+            }
             if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
                 debug!("[intermediate wait] New loop: waiting = {}", *lk);
             }
@@ -589,17 +731,35 @@ impl Node {
         self.service_manager
             .as_ref()
             .unwrap()
-            .write()
-            .unwrap()
+            .write_recover()
             .handle_requests(time);
+        let command_key = PathKey::from_str(networking::channels::internal::COMMAND)
+            .unwrap()
+            .join_str(self.name().as_str());
+        let reconfigure_key = PathKey::from_str(networking::channels::internal::RECONFIGURE)
+            .unwrap()
+            .join_str(self.name().as_str());
         while let Some((path, message)) = self.node_message_client.try_receive(time) {
-            if path
-                == PathKey::from_str(networking::channels::internal::COMMAND)
-                    .unwrap()
-                    .join_str(self.name().as_str())
-            {
+            if path == reconfigure_key {
+                let result = self.reconfigure_modules(message.message, time);
+                ReconfigurationOutcome::acknowledge(
+                    &self.node_message_client,
+                    &path,
+                    &message.message_flags,
+                    &result,
+                    time,
+                );
+            } else if path == command_key {
                 for flag in message.message_flags {
                     if flag == MessageFlag::Kill {
+                        // The kill message can be received during the intermediate
+                        // synchronization or after the final barrier, depending on the
+                        // thread scheduling: delay the transition to a fixed point.
+                        #[cfg(feature = "force_hard_determinism")]
+                        {
+                            self.kill_requested = true;
+                        }
+                        #[cfg(not(feature = "force_hard_determinism"))]
                         self.pre_kill();
                     }
                 }
@@ -607,11 +767,68 @@ impl Node {
         }
     }
 
+    /// Applies a [`NodeReconfigureMessage`] received on the reconfiguration channel to the modules.
+    ///
+    /// Returns the reason of the rejection if the message is invalid or cannot be applied.
+    fn reconfigure_modules(&mut self, message: serde_json::Value, time: f32) -> Result<(), String> {
+        let message = match serde_json::from_value::<NodeReconfigureMessage>(message) {
+            Ok(m) => m,
+            Err(e) => {
+                warn!("[{}] Invalid reconfiguration message: {e}", self.name());
+                return Err(format!("Invalid reconfiguration message: {e}"));
+            }
+        };
+        let result = match &message {
+            NodeReconfigureMessage::Physics(physics_message) => match &self.physics {
+                Some(physics) => physics_message
+                    .validate()
+                    .and_then(|_| physics.write_recover().reconfigure(physics_message, time)),
+                None => Err("the node has no physics".to_string()),
+            },
+            NodeReconfigureMessage::ControllerGains {
+                proportional_gains,
+                derivative_gains,
+                integral_gains,
+            } => match &self.controller {
+                Some(controller) => controller.write_recover().set_gains(
+                    proportional_gains.as_deref(),
+                    derivative_gains.as_deref(),
+                    integral_gains.as_deref(),
+                ),
+                None => Err("the node has no controller".to_string()),
+            },
+            NodeReconfigureMessage::NetworkDelay(delay) => match &self.network {
+                Some(_) if *delay < 0. => Err(format!("Delay should be positive, got {delay}")),
+                Some(network) => {
+                    network.read_recover().set_extra_delay(*delay);
+                    Ok(())
+                }
+                None => Err("the node has no network".to_string()),
+            },
+            NodeReconfigureMessage::SetState { pose, velocity } => match &self.physics {
+                Some(physics) => physics.write_recover().set_state(*pose, *velocity, time),
+                None => Err("the node has no physics".to_string()),
+            },
+            NodeReconfigureMessage::SourceCommand { source, command } => {
+                match &mut self.command_arbiter {
+                    Some(arbiter) => arbiter.submit(source, command.clone(), time),
+                    None => Err("the node has no command arbitration".to_string()),
+                }
+            }
+        };
+        if let Err(e) = &result {
+            warn!("[{}] Reconfiguration rejected: {e}", self.name());
+        } else if is_enabled(crate::logger::InternalLog::NodeRunning) {
+            debug!("[{}] Reconfigured at time {time}: {message:?}", self.name());
+        }
+        result
+    }
+
     /// Computes the next time step, using state estimator, sensors and received messages.
     pub fn next_time_step(&self, min_time_excluded: f32) -> SimbaResult<f32> {
         let mut next_time_step = f32::INFINITY;
         if let Some(state_estimator) = &self.state_estimator {
-            let next_time = state_estimator.read().unwrap().next_time_step();
+            let next_time = state_estimator.read_recover().next_time_step();
             if next_time > min_time_excluded {
                 next_time_step = next_time_step.min(next_time);
             }
@@ -620,7 +837,7 @@ impl Node {
             }
         }
         if let Some(navigator) = &self.navigator
-            && let Some(next_time) = navigator.read().unwrap().next_time_step()
+            && let Some(next_time) = navigator.read_recover().next_time_step()
         {
             if next_time > min_time_excluded {
                 next_time_step = next_time_step.min(next_time);
@@ -630,7 +847,7 @@ impl Node {
             }
         }
         if let Some(controller) = &self.controller
-            && let Some(next_time) = controller.read().unwrap().next_time_step()
+            && let Some(next_time) = controller.read_recover().next_time_step()
         {
             if next_time > min_time_excluded {
                 next_time_step = next_time_step.min(next_time);
@@ -640,7 +857,7 @@ impl Node {
             }
         }
         if let Some(physics) = &self.physics
-            && let Some(next_time) = physics.read().unwrap().next_time_step()
+            && let Some(next_time) = physics.read_recover().next_time_step()
         {
             if next_time > min_time_excluded {
                 next_time_step = next_time_step.min(next_time);
@@ -652,8 +869,7 @@ impl Node {
 
         if let Some(sensor_manager) = &self.sensor_manager {
             let next_time = sensor_manager
-                .read()
-                .unwrap()
+                .read_recover()
                 .next_time_step()
                 .unwrap_or(f32::INFINITY);
             if next_time > min_time_excluded {
@@ -685,11 +901,10 @@ impl Node {
         //     }
         // }
         if let Some(state_estimator_bench) = &self.state_estimator_bench {
-            for state_estimator in state_estimator_bench.read().unwrap().iter() {
+            for state_estimator in state_estimator_bench.read_recover().iter() {
                 let next_time = state_estimator
                     .state_estimator
-                    .read()
-                    .unwrap()
+                    .read_recover()
                     .next_time_step();
                 if next_time > min_time_excluded {
                     next_time_step = next_time_step.min(next_time);
@@ -703,8 +918,7 @@ impl Node {
             .service_manager
             .as_ref()
             .unwrap()
-            .read()
-            .unwrap()
+            .read_recover()
             .next_time();
         if next_time > min_time_excluded {
             next_time_step = next_time_step.min(next_time);
@@ -712,6 +926,22 @@ impl Node {
         if is_enabled(crate::logger::InternalLog::NodeRunningDetailed) {
             debug!("Next time after service manager: {next_time_step}");
         }
+        if let Some(arbiter) = &self.command_arbiter
+            && let Some(next_time) = arbiter.next_timeout(min_time_excluded)
+        {
+            next_time_step = next_time_step.min(next_time);
+            if is_enabled(crate::logger::InternalLog::NodeRunningDetailed) {
+                debug!("Next time after command arbitration: {next_time_step}");
+            }
+        }
+        if let (Some(min_time_step), Some(last_time)) = (self.min_time_step, self.last_step_time)
+            && next_time_step < last_time + min_time_step
+        {
+            next_time_step = last_time + min_time_step;
+            if is_enabled(crate::logger::InternalLog::NodeRunningDetailed) {
+                debug!("Next time after min time step: {next_time_step}");
+            }
+        }
         next_time_step = round_precision(next_time_step, TIME_ROUND).unwrap();
         if is_enabled(crate::logger::InternalLog::NodeRunningDetailed) {
             debug!("next_time_step: {}", next_time_step);
@@ -724,16 +954,16 @@ impl Node {
 impl Node {
     /// Get the name of the node.
     pub fn name(&self) -> String {
-        self.node_meta_data.read().unwrap().name.clone()
+        self.node_meta_data.read_recover().name.clone()
     }
 
     /// Get the current lifecycle state.
     pub fn state(&self) -> NodeState {
-        self.node_meta_data.read().unwrap().state.clone()
+        self.node_meta_data.read_recover().state.clone()
     }
 
     pub(crate) fn set_state(&mut self, state: NodeState) {
-        self.node_meta_data.write().unwrap().state = state;
+        self.node_meta_data.write_recover().state = state;
     }
 
     /// Return whether this node is configured to emit records.
@@ -741,6 +971,15 @@ impl Node {
         self.send_records
     }
 
+    /// Clear the events kept for the record of the time step (estimator divergences and sensor
+    /// reconfigurations), so that each record only holds the events since the previous one.
+    pub(crate) fn clear_step_events(&mut self) {
+        self.estimator_divergences.clear();
+        if let Some(sensor_manager) = &self.sensor_manager {
+            sensor_manager.write_recover().clear_reconfigurations();
+        }
+    }
+
     /// Get the names of other known nodes in the simulation.
     pub fn other_node_names(&self) -> &[String] {
         &self.other_node_names
@@ -748,7 +987,7 @@ impl Node {
 
     /// Get this node type.
     pub fn node_type(&self) -> NodeType {
-        self.node_meta_data.read().unwrap().node_type.clone()
+        self.node_meta_data.read_recover().node_type.clone()
     }
 
     /// Get a Arc clone of network module.
@@ -807,7 +1046,10 @@ impl Node {
         }
     }
 
-    /// Get the shared simulation [`Environment`].
+    /// Get the shared simulation [`Environment`], read-only.
+    ///
+    /// Navigators and controllers (built-in or plugins) can consult the true map with it
+    /// ([`Environment::map`], [`Environment::is_path_free`]), instead of loading the map file.
     pub fn environment(&self) -> &Arc<Environment> {
         &self.environment
     }
@@ -822,6 +1064,54 @@ impl Node {
         self.node_meta_data.clone() as Arc<dyn RoLock<NodeMetaData>>
     }
 
+    /// Get shared read-write access to this node metadata, used by the simulator to mark the node
+    /// as [`NodeState::Failed`] after the node thread stopped.
+    pub(crate) fn shared_meta_data(&self) -> SharedRwLock<NodeMetaData> {
+        self.node_meta_data.clone()
+    }
+
+    /// Get the scheduling configuration of the node thread.
+    pub fn thread_config(&self) -> &ThreadConfig {
+        &self.thread_config
+    }
+
+    /// Add an annotation of this node at the given simulated `time` (e.g. "filter diverged").
+    ///
+    /// Annotations are saved with the results and shown on the GUI timeline.
+    pub fn annotate(&self, time: f32, text: &str) {
+        self.annotations
+            .add(time, Some(self.name()), text.to_string());
+    }
+
+    /// Get the shared annotation log of the run.
+    pub(crate) fn annotation_log(&self) -> AnnotationLog {
+        self.annotations.clone()
+    }
+
+    /// Current health of the node, published in the [`FleetStatus`](crate::simulator::FleetStatus).
+    ///
+    /// ## Arguments
+    /// * `time` - Time of the last time step.
+    /// * `steps` - Number of time steps run.
+    /// * `step_duration` - Wall-clock duration of the last time step.
+    pub fn heartbeat(&self, time: f32, steps: usize, step_duration: Duration) -> NodeHeartbeat {
+        let meta_data = self.node_meta_data.read_recover();
+        NodeHeartbeat {
+            node: meta_data.name.clone(),
+            node_type: meta_data.node_type.clone(),
+            state: meta_data.state.clone(),
+            time: Some(time),
+            steps,
+            queued_messages: self
+                .network
+                .as_ref()
+                .map(|network| network.read_recover().queue_size())
+                .unwrap_or(0),
+            step_duration,
+            updated_at: Instant::now(),
+        }
+    }
+
     /// Get the optional shared metadata map for all nodes.
     pub fn meta_data_list(
         &self,
@@ -832,14 +1122,26 @@ impl Node {
 
     /// Mark this node as [`NodeState::Zombie`]. The kill is done by [`Self::kill`].
     pub fn pre_kill(&mut self) {
-        self.node_meta_data.write().unwrap().state = NodeState::Zombie;
+        self.node_meta_data.write_recover().state = NodeState::Zombie;
+    }
+
+    /// Mark this node as [`NodeState::Zombie`] if a kill was received during the time step.
+    ///
+    /// With `force_hard_determinism`, the kill messages are only applied here, at the end
+    /// of the time step, so that the other nodes see the transition at the same point.
+    #[cfg(feature = "force_hard_determinism")]
+    pub(crate) fn apply_kill_request(&mut self) {
+        if self.kill_requested {
+            self.kill_requested = false;
+            self.pre_kill();
+        }
     }
 
     /// Terminate this node and publish its final state update.
     pub fn kill(&mut self, time: f32) {
-        self.node_meta_data.write().unwrap().state = NodeState::Terminated;
+        self.node_meta_data.write_recover().state = NodeState::Terminated;
         if let Some(service_manager) = &self.service_manager {
-            service_manager.write().unwrap().unsubscribe_node();
+            service_manager.write_recover().unsubscribe_node();
         }
         self.node_server
             .as_ref()
@@ -851,88 +1153,222 @@ impl Node {
                 time,
                 (
                     State::new(),
-                    self.node_meta_data.read().unwrap().state.clone(),
+                    self.node_meta_data.read_recover().state.clone(),
                 ),
             ))
             .unwrap();
     }
 }
 
+// Estimator dataset part
+impl Node {
+    /// Record the state of the estimator before a step, if the step should be logged in the dataset.
+    fn estimator_previous_state(
+        &self,
+        state_estimator: &SharedRwLock<Box<dyn StateEstimator>>,
+        main_estimator: bool,
+    ) -> Option<WorldStateRecord> {
+        match &self.estimator_dataset {
+            Some(logger) if main_estimator || logger.include_bench() => {
+                Some(state_estimator.read_recover().world_state().record())
+            }
+            _ => None,
+        }
+    }
+
+    /// Complete the estimator dataset file at the end of a run. A continued run writes in a new
+    /// part.
+    pub(crate) fn close_estimator_dataset(&mut self) -> SimbaResult<()> {
+        match &mut self.estimator_dataset {
+            Some(logger) => logger.close(),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs the watchdog of the estimator `estimator_name`, if any, after one of its steps, and
+    /// reinitializes the estimator when a divergence is declared.
+    fn watch_estimator(
+        &mut self,
+        estimator_name: &str,
+        state_estimator: &SharedRwLock<Box<dyn StateEstimator>>,
+        observations: &[Observation],
+        time: f32,
+    ) {
+        let node_name = self.name();
+        let Some(watchdog) = self
+            .estimator_watchdogs
+            .iter_mut()
+            .find(|watchdog| watchdog.estimator() == estimator_name)
+        else {
+            return;
+        };
+        watchdog.observe(observations, &node_name);
+        let Some(reason) =
+            watchdog.check(&**state_estimator.read_recover(), !observations.is_empty())
+        else {
+            return;
+        };
+        warn!("[{node_name}] Divergence of `{estimator_name}` at time {time}: {reason}");
+        let reinitialization = watchdog.reinitialization();
+        let state = match reinitialization {
+            WatchdogReinitialization::None => None,
+            WatchdogReinitialization::LastGnssFix => {
+                Some(watchdog.last_gnss_fix_state(state_estimator.read_recover().world_state().ego))
+            }
+            WatchdogReinitialization::TrueState => Some(match &self.physics {
+                Some(physics) => Ok(physics.read_recover().state(time)),
+                None => Err("the node has no physics".to_string()),
+            }),
+        };
+        let error = state
+            .map(|state| {
+                state.and_then(|state| state_estimator.write_recover().reset_state(state, time))
+            })
+            .and_then(|result| result.err());
+        match &error {
+            Some(e) => warn!("[{node_name}] Reinitialization of `{estimator_name}` failed: {e}"),
+            None if reinitialization != WatchdogReinitialization::None => {
+                watchdog.reinitialized();
+            }
+            None => {}
+        }
+        self.estimator_divergences.push(DivergenceRecord {
+            estimator: estimator_name.to_string(),
+            time,
+            reason,
+            reinitialization,
+            error,
+        });
+    }
+
+    /// Keeps the latency of the observations consumed by the estimator `estimator_name` at
+    /// `time`, for the record of the time step.
+    fn record_observation_latencies(
+        &mut self,
+        estimator_name: &str,
+        observations: &[Observation],
+        time: f32,
+    ) {
+        self.observation_latencies
+            .extend(observations.iter().map(|obs| ObservationLatencyRecord {
+                sensor_name: obs.sensor_name.clone(),
+                observer: obs.observer.clone(),
+                estimator: estimator_name.to_string(),
+                observation_time: obs.time,
+                consumption_time: time,
+                latency: time - obs.time,
+            }));
+    }
+
+    /// Log an estimator step in the dataset. Does nothing if `previous_state` is `None`.
+    ///
+    /// Returns an error if the dataset file cannot be written.
+    fn log_estimator_step(
+        &mut self,
+        estimator_name: &str,
+        step: EstimatorStep,
+        time: f32,
+        previous_state: Option<WorldStateRecord>,
+        state_estimator: &SharedRwLock<Box<dyn StateEstimator>>,
+        observations: &[Observation],
+    ) -> SimbaResult<()> {
+        let (Some(logger), Some(previous_state)) = (&mut self.estimator_dataset, previous_state)
+        else {
+            return Ok(());
+        };
+        logger.log(EstimatorDatasetEntry {
+            time,
+            estimator: estimator_name.to_string(),
+            command: match step {
+                EstimatorStep::Prediction => self.current_command.clone(),
+                EstimatorStep::Correction => None,
+            },
+            step,
+            previous_state,
+            observations: observations.iter().map(|obs| obs.record()).collect(),
+            state: state_estimator.read_recover().world_state().record(),
+        })
+    }
+}
+
 // Record part
 impl Node {
     fn robot_record(&self) -> RobotRecord {
-        let meta_data = self.node_meta_data.read().unwrap();
+        let meta_data = self.node_meta_data.read_recover();
         let mut record = RobotRecord {
             name: meta_data.name.clone(),
             model_name: meta_data.model_name.clone(),
             labels: meta_data.labels.clone(),
-            navigator: self.navigator.as_ref().unwrap().read().unwrap().record(),
-            controller: self.controller.as_ref().unwrap().read().unwrap().record(),
-            physics: self.physics.as_ref().unwrap().read().unwrap().record(),
+            navigator: self.navigator.as_ref().unwrap().read_recover().record(),
+            controller: self.controller.as_ref().unwrap().read_recover().record(),
+            physics: self.physics.as_ref().unwrap().read_recover().record(),
             state_estimator: self
                 .state_estimator
                 .as_ref()
                 .unwrap()
-                .read()
-                .unwrap()
+                .read_recover()
                 .record(),
             state_estimator_bench: Vec::new(),
             sensors: self
                 .sensor_manager
                 .as_ref()
                 .unwrap()
-                .read()
-                .unwrap()
+                .read_recover()
                 .record(),
+            network: self.network_record(),
             state: meta_data.state.clone(),
+            skipped_steps: self.skipped_steps,
+            observation_latencies: self.observation_latencies.clone(),
+            estimator_divergences: self.estimator_divergences.clone(),
+            command_source: self
+                .command_arbiter
+                .as_ref()
+                .and_then(|arbiter| arbiter.active_source().map(str::to_string)),
         };
         let other_state_estimators = self.state_estimator_bench.clone();
         for additional_state_estimator in other_state_estimators
             .as_ref()
             .unwrap()
-            .read()
-            .unwrap()
+            .read_recover()
             .iter()
         {
             record
                 .state_estimator_bench
-                .push(BenchStateEstimatorRecord {
-                    name: additional_state_estimator.name.clone(),
-                    record: additional_state_estimator
-                        .state_estimator
-                        .read()
-                        .unwrap()
-                        .record(),
-                });
+                .push(additional_state_estimator.record());
         }
         record
     }
 
+    fn network_record(&self) -> NetworkRecord {
+        self.network
+            .as_ref()
+            .map(|network| network.read_recover().record())
+            .unwrap_or_default()
+    }
+
     fn computation_unit_record(&self) -> ComputationUnitRecord {
-        let meta_data = self.node_meta_data.read().unwrap();
+        let meta_data = self.node_meta_data.read_recover();
         let mut record = ComputationUnitRecord {
             name: meta_data.name.clone(),
             state_estimators: Vec::new(),
-            sensor_manager: self.sensor_manager().unwrap().read().unwrap().record(),
+            sensor_manager: self.sensor_manager().unwrap().read_recover().record(),
+            network: self.network_record(),
             labels: meta_data.labels.clone(),
             model_name: meta_data.model_name.clone(),
+            skipped_steps: self.skipped_steps,
+            observation_latencies: self.observation_latencies.clone(),
+            estimator_divergences: self.estimator_divergences.clone(),
         };
         let other_state_estimators = self.state_estimator_bench.clone();
         for additional_state_estimator in other_state_estimators
             .as_ref()
             .unwrap()
-            .read()
-            .unwrap()
+            .read_recover()
             .iter()
         {
-            record.state_estimators.push(BenchStateEstimatorRecord {
-                name: additional_state_estimator.name.clone(),
-                record: additional_state_estimator
-                    .state_estimator
-                    .read()
-                    .unwrap()
-                    .record(),
-            });
+            record
+                .state_estimators
+                .push(additional_state_estimator.record());
         }
         record
     }
@@ -941,7 +1377,7 @@ impl Node {
 impl Recordable<NodeRecord> for Node {
     /// Generate the current state record.
     fn record(&self) -> NodeRecord {
-        match &self.node_meta_data.read().unwrap().node_type {
+        match &self.node_meta_data.read_recover().node_type {
             NodeType::Robot => NodeRecord::Robot(Box::new(self.robot_record())),
             NodeType::ComputationUnit => {
                 NodeRecord::ComputationUnit(Box::new(self.computation_unit_record()))
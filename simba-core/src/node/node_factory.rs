@@ -25,17 +25,31 @@ use crate::{
     navigators::{self, NavigatorConfig, NavigatorRecord, go_to},
     networking::{
         self,
-        network::{Network, NetworkConfig},
+        network::{Network, NetworkConfig, NetworkRecord},
         service_manager::ServiceManager,
     },
-    node::{Node, NodeMetaData, NodeState},
+    node::{
+        Node, NodeMetaData, NodeState,
+        capabilities::check_capabilities,
+        command_arbitration::{CommandArbiter, CommandArbitrationConfig},
+        footprint::FootprintConfig,
+        history_retention::HistoryRetentionConfig,
+        thread_config::ThreadConfig,
+    },
     physics::{self, PhysicsConfig, PhysicsRecord, internal_physics},
     plugin_api::PluginAPI,
-    sensors::sensor_manager::{SensorManager, SensorManagerConfig, SensorManagerRecord},
-    simulator::{SimbaBroker, SimbaBrokerMultiClient, SimulatorConfig, TimeCv},
+    sensors::{
+        ObservationLatencyRecord,
+        sensor_manager::{SensorManager, SensorManagerConfig, SensorManagerRecord},
+    },
+    simulator::{AnnotationLog, SimbaBroker, SimbaBrokerMultiClient, SimulatorConfig, TimeCv},
     state_estimators::{
         self, BenchStateEstimator, BenchStateEstimatorConfig, BenchStateEstimatorRecord, State,
-        StateEstimatorConfig, StateEstimatorRecord, perfect_estimator,
+        StateEstimatorConfig, StateEstimatorRecord,
+        dataset::EstimatorDatasetLogger,
+        perfect_estimator,
+        warm_start::{EstimatorWarmStartConfig, MAIN_ESTIMATOR, warm_start_estimator},
+        watchdog::{DivergenceRecord, EstimatorWatchdog, EstimatorWatchdogConfig},
     },
     time_analysis::TimeAnalysisFactory,
     utils::{SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory},
@@ -222,11 +236,19 @@ impl NodeRecord {
 /// - `controller`: default [`pid::PIDConfig`]
 /// - `physics`: default [`internal_physics::InternalPhysicConfig`]
 /// - `state_estimator`: default [`perfect_estimator::PerfectEstimatorConfig`]
+/// - `state_estimator_watchdog`: `None` (no watchdog)
+/// - `state_estimator_warm_start`: `None` (start from the configuration)
 /// - `sensor_manager`: [`SensorManagerConfig::default`]
 /// - `network`: [`NetworkConfig::default`]
 /// - `state_estimator_bench`: empty vector
 /// - `autospawn`: `true`
 /// - `labels`: empty vector
+/// - `thread`: [`ThreadConfig::default`]
+/// - `history`: [`HistoryRetentionConfig::default`] (whole history)
+/// - `footprint`: [`FootprintConfig::Point`]
+/// - `height`: `0.0` (does not obstruct the view)
+/// - `min_time_step`: `None` (no floor)
+/// - `command_arbitration`: `None` (the controller drives the robot)
 ///
 /// # Example
 /// ```yaml
@@ -302,6 +324,12 @@ pub struct RobotConfig {
     /// [`StateEstimator`](crate::state_estimators::StateEstimator) to use, and its configuration.
     #[check]
     pub state_estimator: StateEstimatorConfig,
+    /// Divergence watchdog of the state estimator, `None` to disable it.
+    #[check]
+    pub state_estimator_watchdog: Option<EstimatorWatchdogConfig>,
+    /// Record used to initialize the state estimator, `None` to start from its configuration.
+    #[check]
+    pub state_estimator_warm_start: Option<EstimatorWarmStartConfig>,
     /// [`SensorManager`] configuration, which defines the [`Sensor`](crate::sensors::Sensor)s used.
     #[check]
     pub sensor_manager: SensorManagerConfig,
@@ -317,6 +345,27 @@ pub struct RobotConfig {
     pub autospawn: bool,
     /// Free-form labels attached to the node metadata.
     pub labels: Vec<String>,
+    /// Scheduling configuration (priority, CPU affinity) of the node thread.
+    #[check]
+    pub thread: ThreadConfig,
+    /// Retention window of the state history of the node.
+    #[check]
+    pub history: HistoryRetentionConfig,
+    /// Shape of the robot on the ground.
+    #[check]
+    pub footprint: FootprintConfig,
+    /// Height of the robot. A robot with a height and a footprint obstructs the view of the
+    /// sensors lower than it, as the landmarks do. `0` for a transparent robot.
+    pub height: f32,
+    /// Minimal time between two time steps of the node, in seconds. The time steps requested by
+    /// the modules of the node are delayed to respect it, and the rounds of the other nodes closer
+    /// than it to the last time step are skipped (counted in the records). `None` for no floor.
+    pub min_time_step: Option<f32>,
+    /// Arbitration of the commands between the controller and other sources (teleoperation,
+    /// safety supervisor), see [`command_arbitration`](crate::node::command_arbitration). `None`
+    /// to apply the commands of the controller only.
+    #[check]
+    pub command_arbitration: Option<CommandArbitrationConfig>,
 }
 
 impl Default for RobotConfig {
@@ -336,11 +385,48 @@ impl Default for RobotConfig {
             state_estimator: StateEstimatorConfig::Perfect(
                 perfect_estimator::PerfectEstimatorConfig::default(),
             ),
+            state_estimator_watchdog: None,
+            state_estimator_warm_start: None,
             sensor_manager: SensorManagerConfig::default(),
             network: NetworkConfig::default(),
             state_estimator_bench: Vec::new(),
             autospawn: true,
             labels: Vec::new(),
+            thread: ThreadConfig::default(),
+            history: HistoryRetentionConfig::default(),
+            footprint: FootprintConfig::default(),
+            height: 0.,
+            min_time_step: None,
+            command_arbitration: None,
+        }
+    }
+}
+
+/// Check the minimal time step of a node configuration.
+fn check_min_time_step(min_time_step: Option<f32>) -> Result<(), Vec<String>> {
+    match min_time_step {
+        Some(min_time_step) if min_time_step.is_nan() || min_time_step < 0. => Err(vec![format!(
+            "Minimal time step should be positive, got {min_time_step}"
+        )]),
+        _ => Ok(()),
+    }
+}
+
+impl Check for RobotConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = check_min_time_step(self.min_time_step)
+            .err()
+            .unwrap_or_default();
+        if !self.height.is_finite() || self.height < 0. {
+            errors.push(format!(
+                "Robot height should be positive, got {}",
+                self.height
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
     }
 }
@@ -402,6 +488,52 @@ impl UIComponent for RobotConfig {
                 current_node_name,
                 unique_id,
             );
+            self.thread.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            );
+            self.history.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            );
+            self.footprint.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            );
+            ui.horizontal(|ui| {
+                ui.label("Height:");
+                ui.add(
+                    egui::DragValue::new(&mut self.height)
+                        .speed(0.01)
+                        .range(0.0..=f32::MAX),
+                );
+            });
+            ui.horizontal(|ui| {
+                let mut enabled = self.min_time_step.is_some();
+                ui.checkbox(&mut enabled, "Min time step:");
+                if enabled {
+                    let min_time_step = self.min_time_step.get_or_insert(0.01);
+                    ui.add(
+                        egui::DragValue::new(min_time_step)
+                            .speed(0.001)
+                            .range(0.0..=f32::MAX),
+                    );
+                } else {
+                    self.min_time_step = None;
+                }
+            });
             self.navigator.show_mut(
                 ui,
                 ctx,
@@ -434,6 +566,53 @@ impl UIComponent for RobotConfig {
                 current_node_name,
                 unique_id,
             );
+            let mut watchdog_enabled = self.state_estimator_watchdog.is_some();
+            ui.checkbox(&mut watchdog_enabled, "State estimator watchdog");
+            if watchdog_enabled {
+                self.state_estimator_watchdog
+                    .get_or_insert_default()
+                    .show_mut(
+                        ui,
+                        ctx,
+                        buffer_stack,
+                        global_config,
+                        current_node_name,
+                        unique_id,
+                    );
+            } else {
+                self.state_estimator_watchdog = None;
+            }
+            let mut warm_start_enabled = self.state_estimator_warm_start.is_some();
+            ui.checkbox(&mut warm_start_enabled, "State estimator warm start");
+            if warm_start_enabled {
+                self.state_estimator_warm_start
+                    .get_or_insert_default()
+                    .show_mut(
+                        ui,
+                        ctx,
+                        buffer_stack,
+                        global_config,
+                        current_node_name,
+                        unique_id,
+                    );
+            } else {
+                self.state_estimator_warm_start = None;
+            }
+
+            let mut arbitration_enabled = self.command_arbitration.is_some();
+            ui.checkbox(&mut arbitration_enabled, "Command arbitration");
+            if arbitration_enabled {
+                self.command_arbitration.get_or_insert_default().show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+            } else {
+                self.command_arbitration = None;
+            }
 
             ui.label("State estimator bench:");
             let mut seb_to_remove = None;
@@ -491,10 +670,26 @@ impl UIComponent for RobotConfig {
             });
 
             self.network.show(ui, ctx, unique_id);
+            self.thread.show(ui, ctx, unique_id);
+            self.history.show(ui, ctx, unique_id);
+            self.footprint.show(ui, ctx, unique_id);
+            ui.label(format!("Height: {}", self.height));
+            if let Some(min_time_step) = self.min_time_step {
+                ui.label(format!("Min time step: {}", min_time_step));
+            }
             self.navigator.show(ui, ctx, unique_id);
             self.physics.show(ui, ctx, unique_id);
             self.controller.show(ui, ctx, unique_id);
             self.state_estimator.show(ui, ctx, unique_id);
+            if let Some(watchdog) = &self.state_estimator_watchdog {
+                watchdog.show(ui, ctx, unique_id);
+            }
+            if let Some(warm_start) = &self.state_estimator_warm_start {
+                warm_start.show(ui, ctx, unique_id);
+            }
+            if let Some(arbitration) = &self.command_arbitration {
+                arbitration.show(ui, ctx, unique_id);
+            }
 
             ui.label("State estimator bench:");
             for seb in &self.state_estimator_bench {
@@ -532,10 +727,39 @@ pub struct RobotRecord {
 
     /// Record of the [`SensorManager`] module.
     pub sensors: SensorManagerRecord,
+    /// Record of the message processing load of the [`Network`].
+    #[serde(default)]
+    pub network: NetworkRecord,
     /// Current runtime node state.
     pub state: NodeState,
     /// Labels attached to the node.
     pub labels: Vec<String>,
+    /// Number of rounds skipped by the node since the start, because of its `min_time_step`.
+    #[serde(default)]
+    pub skipped_steps: usize,
+    /// Latencies of the observations consumed by the state estimators during this time step.
+    #[serde(default)]
+    pub observation_latencies: Vec<ObservationLatencyRecord>,
+    /// Divergences of the state estimators detected by their watchdog since the start.
+    #[serde(default)]
+    pub estimator_divergences: Vec<DivergenceRecord>,
+    /// Source of the command applied at this time step, with command arbitration (`controller`
+    /// or the name of a source). `None` without arbitration or before the first command.
+    #[serde(default)]
+    pub command_source: Option<String>,
+}
+
+/// Shows the observation latencies of a node record.
+#[cfg(feature = "gui")]
+fn show_observation_latencies(ui: &mut egui::Ui, latencies: &[ObservationLatencyRecord]) {
+    egui::CollapsingHeader::new("Observation latencies").show(ui, |ui| {
+        for latency in latencies {
+            ui.label(format!(
+                "- {} ({}) -> {}: {:.3} s",
+                latency.sensor_name, latency.observer, latency.estimator, latency.latency
+            ));
+        }
+    });
 }
 
 #[cfg(feature = "gui")]
@@ -554,6 +778,15 @@ impl UIComponent for RobotRecord {
             });
 
             ui.label(format!("State: {}", self.state));
+            ui.label(format!("Skipped steps: {}", self.skipped_steps));
+            show_observation_latencies(ui, &self.observation_latencies);
+            ui.label(format!(
+                "Estimator divergences: {}",
+                self.estimator_divergences.len()
+            ));
+            if let Some(command_source) = &self.command_source {
+                ui.label(format!("Command source: {command_source}"));
+            }
 
             egui::CollapsingHeader::new("Navigator").show(ui, |ui| {
                 self.navigator.show(ui, ctx, unique_id);
@@ -581,6 +814,8 @@ impl UIComponent for RobotRecord {
             egui::CollapsingHeader::new("Sensors").show(ui, |ui| {
                 self.sensors.show(ui, ctx, unique_id);
             });
+
+            self.network.show(ui, ctx, unique_id);
         });
     }
 }
@@ -598,6 +833,9 @@ impl UIComponent for RobotRecord {
 /// - `network`: [`NetworkConfig::default`]
 /// - `state_estimators`: empty vector
 /// - `labels`: empty vector
+/// - `thread`: [`ThreadConfig::default`]
+/// - `history`: [`HistoryRetentionConfig::default`] (whole history)
+/// - `min_time_step`: `None` (no floor)
 ///
 /// # Example
 /// ```yaml
@@ -627,6 +865,16 @@ pub struct ComputationUnitConfig {
 
     /// Free-form labels attached to the node metadata.
     pub labels: Vec<String>,
+    /// Scheduling configuration (priority, CPU affinity) of the node thread.
+    #[check]
+    pub thread: ThreadConfig,
+    /// Retention window of the state history of the node.
+    #[check]
+    pub history: HistoryRetentionConfig,
+    /// Minimal time between two time steps of the node, in seconds. The time steps requested by
+    /// the modules of the node are delayed to respect it, and the rounds of the other nodes closer
+    /// than it to the last time step are skipped (counted in the records). `None` for no floor.
+    pub min_time_step: Option<f32>,
 }
 
 impl Default for ComputationUnitConfig {
@@ -638,10 +886,19 @@ impl Default for ComputationUnitConfig {
             network: NetworkConfig::default(),
             state_estimators: Vec::new(),
             labels: Vec::new(),
+            thread: ThreadConfig::default(),
+            history: HistoryRetentionConfig::default(),
+            min_time_step: None,
         }
     }
 }
 
+impl Check for ComputationUnitConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        check_min_time_step(self.min_time_step)
+    }
+}
+
 #[cfg(feature = "gui")]
 impl UIComponent for ComputationUnitConfig {
     fn show_mut(
@@ -695,6 +952,36 @@ impl UIComponent for ComputationUnitConfig {
                 current_node_name,
                 unique_id,
             );
+            self.thread.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            );
+            self.history.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            );
+            ui.horizontal(|ui| {
+                let mut enabled = self.min_time_step.is_some();
+                ui.checkbox(&mut enabled, "Min time step:");
+                if enabled {
+                    let min_time_step = self.min_time_step.get_or_insert(0.01);
+                    ui.add(
+                        egui::DragValue::new(min_time_step)
+                            .speed(0.001)
+                            .range(0.0..=f32::MAX),
+                    );
+                } else {
+                    self.min_time_step = None;
+                }
+            });
 
             ui.label("State estimators:");
             let mut se_to_remove = None;
@@ -740,6 +1027,11 @@ impl UIComponent for ComputationUnitConfig {
             });
 
             self.network.show(ui, ctx, unique_id);
+            self.thread.show(ui, ctx, unique_id);
+            self.history.show(ui, ctx, unique_id);
+            if let Some(min_time_step) = self.min_time_step {
+                ui.label(format!("Min time step: {}", min_time_step));
+            }
 
             ui.label("State estimators:");
             for seb in &self.state_estimators {
@@ -763,10 +1055,22 @@ pub struct ComputationUnitRecord {
     pub state_estimators: Vec<BenchStateEstimatorRecord>,
     /// Record of the [`SensorManager`] module.
     pub sensor_manager: SensorManagerRecord,
+    /// Record of the message processing load of the [`Network`].
+    #[serde(default)]
+    pub network: NetworkRecord,
     /// Config/model name used to instantiate this unit.
     pub model_name: String,
     /// Labels attached to the node.
     pub labels: Vec<String>,
+    /// Number of rounds skipped by the node since the start, because of its `min_time_step`.
+    #[serde(default)]
+    pub skipped_steps: usize,
+    /// Latencies of the observations consumed by the state estimators during this time step.
+    #[serde(default)]
+    pub observation_latencies: Vec<ObservationLatencyRecord>,
+    /// Divergences of the state estimators detected by their watchdog since the start.
+    #[serde(default)]
+    pub estimator_divergences: Vec<DivergenceRecord>,
 }
 
 #[cfg(feature = "gui")]
@@ -783,6 +1087,12 @@ impl UIComponent for ComputationUnitRecord {
                     ui.label(format!("- '{}'", label));
                 }
             });
+            ui.label(format!("Skipped steps: {}", self.skipped_steps));
+            show_observation_latencies(ui, &self.observation_latencies);
+            ui.label(format!(
+                "Estimator divergences: {}",
+                self.estimator_divergences.len()
+            ));
 
             ui.label("State Estimators:");
             for se in &self.state_estimators {
@@ -794,6 +1104,8 @@ impl UIComponent for ComputationUnitRecord {
             egui::CollapsingHeader::new("Sensor Manager").show(ui, |ui| {
                 self.sensor_manager.show(ui, ctx, unique_id);
             });
+
+            self.network.show(ui, ctx, unique_id);
         });
     }
 }
@@ -824,6 +1136,8 @@ pub struct MakeNodeParams<'a> {
     pub initial_time: f32,
     /// Shared simulation environment.
     pub environment: Arc<Environment>,
+    /// Annotations of the run, shared with the simulator.
+    pub annotations: AnnotationLog,
 }
 
 /// Factory for creating runtime [`Node`] instances.
@@ -843,6 +1157,11 @@ impl NodeFactory {
                 .unwrap()
                 .join_str(node_name.as_str()),
         );
+        broker_lock.add_channel(
+            PathKey::from_str(networking::channels::internal::RECONFIGURE)
+                .unwrap()
+                .join_str(node_name.as_str()),
+        );
         let log_key = PathKey::from_str(networking::channels::internal::LOG)
             .unwrap()
             .join_str(node_name.as_str());
@@ -888,9 +1207,14 @@ impl NodeFactory {
             .write()
             .unwrap()
             .subscribe_to_list(
-                &[PathKey::from_str(networking::channels::internal::COMMAND)
-                    .unwrap()
-                    .join_str(node_name.as_str())],
+                &[
+                    PathKey::from_str(networking::channels::internal::COMMAND)
+                        .unwrap()
+                        .join_str(node_name.as_str()),
+                    PathKey::from_str(networking::channels::internal::RECONFIGURE)
+                        .unwrap()
+                        .join_str(node_name.as_str()),
+                ],
                 0.,
                 &mut client,
             )
@@ -898,17 +1222,25 @@ impl NodeFactory {
         Ok(client)
     }
 
+    /// Add the module kind and its configuration path (`section/model_name/module`) to the context of `e`.
+    fn module_error(e: SimbaError, section: &str, model_name: &str, module: &str) -> SimbaError {
+        e.with_module(module)
+            .with_config_path(&format!("{section}/{model_name}/{module}"))
+    }
+
     /// Builds a robot node from [`RobotConfig`].
     pub fn make_robot(config: &RobotConfig, params: &mut MakeNodeParams) -> SimbaResult<Node> {
         let node_type = NodeType::Robot;
         let node_name = params.new_name.unwrap_or(&config.name).to_string();
+        // Stream of the node, so that its draws do not depend on the other nodes
+        let va_factory = params.va_factory.child(&node_name);
         // Make global channels
         let client = Self::make_global_channels(&node_name, params.broker)?;
         let network = Arc::new(RwLock::new(Network::from_config(
             node_name.clone(),
             &config.network,
             params.global_config,
-            params.va_factory,
+            &va_factory.child("network"),
             params.broker,
             params.initial_time,
         )));
@@ -918,10 +1250,25 @@ impl NodeFactory {
             network: &network,
             node_name: &node_name,
             plugin_api: params.plugin_api,
-            va_factory: params.va_factory,
+            va_factory: &va_factory,
         };
-        let physics = physics::make_physics_from_config(&config.physics, &from_config_args)?;
+        let physics = physics::make_physics_from_config(
+            &config.physics,
+            &config.footprint,
+            &FromConfigArguments {
+                va_factory: &va_factory.child("physics"),
+                ..from_config_args
+            },
+        )
+        .map_err(|e| Self::module_error(e, "robots", &config.name, "physics"))?;
         let initial_state = physics.read().unwrap().state(params.initial_time).clone();
+        Self::check_bench_sensors(
+            &config.state_estimator_bench,
+            &config.sensor_manager,
+            params.global_config,
+            &[node_name.as_str(), config.name.as_str()],
+            &format!("robots/{}/state_estimator_bench", config.name),
+        )?;
         let mut node = Node {
             node_meta_data: Arc::new(RwLock::new(NodeMetaData {
                 name: node_name.clone(),
@@ -937,40 +1284,63 @@ impl NodeFactory {
                     let pose = physics.read().unwrap().state(params.initial_time).pose;
                     Some([pose.x, pose.y])
                 },
+                footprint_radius: config.footprint.bounding_radius(),
+                height: config.height,
             })),
-            navigator: Some(navigators::make_navigator_from_config(
-                &config.navigator,
-                params.plugin_api,
-                params.global_config,
-                params.va_factory,
-                &network,
-                params.initial_time,
-            )?),
-            controller: Some(controllers::make_controller_from_config(
-                &config.controller,
-                params.plugin_api,
-                params.global_config,
-                params.va_factory,
-                &config.physics,
-                &network,
-                params.initial_time,
-            )?),
+            navigator: Some(
+                navigators::make_navigator_from_config(
+                    &config.navigator,
+                    params.plugin_api,
+                    params.global_config,
+                    &va_factory.child("navigator"),
+                    &network,
+                    params.initial_time,
+                )
+                .map_err(|e| Self::module_error(e, "robots", &config.name, "navigator"))?,
+            ),
+            controller: Some(
+                controllers::make_controller_from_config(
+                    &config.controller,
+                    params.plugin_api,
+                    params.global_config,
+                    &va_factory.child("controller"),
+                    &config.physics,
+                    &network,
+                    params.initial_time,
+                )
+                .map_err(|e| Self::module_error(e, "robots", &config.name, "controller"))?,
+            ),
             physics: Some(physics),
             state_estimator: Some(Arc::new(RwLock::new(
                 state_estimators::make_state_estimator_from_config(
                     &config.state_estimator,
                     params.plugin_api,
                     params.global_config,
-                    params.va_factory,
+                    &va_factory.child("state_estimator"),
+                    Some(&config.physics),
                     &network,
                     params.initial_time,
-                )?,
+                )
+                .and_then(|state_estimator| {
+                    Self::warm_start(
+                        state_estimator,
+                        config.state_estimator_warm_start.as_ref(),
+                        params.global_config,
+                        &node_name,
+                        MAIN_ESTIMATOR,
+                        params.initial_time,
+                    )
+                })
+                .map_err(|e| Self::module_error(e, "robots", &config.name, "state_estimator"))?,
+            ))),
+            sensor_manager: Some(Arc::new(RwLock::new(
+                SensorManager::from_config(
+                    &config.sensor_manager,
+                    &from_config_args,
+                    &initial_state,
+                )
+                .map_err(|e| Self::module_error(e, "robots", &config.name, "sensor_manager"))?,
             ))),
-            sensor_manager: Some(Arc::new(RwLock::new(SensorManager::from_config(
-                &config.sensor_manager,
-                &from_config_args,
-                &initial_state,
-            )?))),
             network: Some(network.clone()),
             state_estimator_bench: Some(Arc::new(RwLock::new(Vec::with_capacity(
                 config.state_estimator_bench.len(),
@@ -988,6 +1358,40 @@ impl NodeFactory {
             node_message_client: client,
             current_command: None,
             environment: params.environment.clone(),
+            #[cfg(feature = "force_hard_determinism")]
+            kill_requested: false,
+            estimator_dataset: match params
+                .global_config
+                .results
+                .as_ref()
+                .and_then(|r| r.estimator_dataset.as_ref())
+            {
+                Some(dataset_config) => Some(EstimatorDatasetLogger::from_config(
+                    dataset_config,
+                    params.global_config,
+                    &node_name,
+                )?),
+                None => None,
+            },
+            thread_config: config.thread.clone(),
+            min_time_step: config.min_time_step,
+            last_step_time: None,
+            skipped_steps: 0,
+            sync_points: 0,
+            time_step_sync_points: 0,
+            observation_latencies: Vec::new(),
+            estimator_watchdogs: config
+                .state_estimator_watchdog
+                .iter()
+                .map(|watchdog| EstimatorWatchdog::new("state_estimator".to_string(), watchdog))
+                .chain(Self::bench_watchdogs(&config.state_estimator_bench))
+                .collect(),
+            estimator_divergences: Vec::new(),
+            annotations: params.annotations.clone(),
+            command_arbiter: config
+                .command_arbitration
+                .as_ref()
+                .map(CommandArbiter::from_config),
         };
 
         for state_estimator_config in &config.state_estimator_bench {
@@ -1003,14 +1407,42 @@ impl NodeFactory {
                             &state_estimator_config.config,
                             params.plugin_api,
                             params.global_config,
-                            params.va_factory,
+                            &va_factory
+                                .child("state_estimator_bench")
+                                .child(&state_estimator_config.name),
+                            Some(&config.physics),
                             &network,
                             params.initial_time,
-                        )?,
+                        )
+                        .and_then(|state_estimator| {
+                            Self::warm_start(
+                                state_estimator,
+                                state_estimator_config.warm_start.as_ref(),
+                                params.global_config,
+                                &node_name,
+                                &state_estimator_config.name,
+                                params.initial_time,
+                            )
+                        })
+                        .map_err(|e| {
+                            Self::module_error(
+                                e.with_config_path(&format!(
+                                    "robots/{}/state_estimator_bench/{}",
+                                    config.name, state_estimator_config.name
+                                )),
+                                "robots",
+                                &config.name,
+                                "state_estimator_bench",
+                            )
+                        })?,
                     )),
+                    sensors: state_estimator_config.sensors.clone(),
                 })
         }
 
+        check_capabilities(&node)
+            .map_err(|e| e.with_config_path(&format!("robots/{}", config.name)))?;
+
         let service_manager = Some(Arc::new(RwLock::new(ServiceManager::initialize(
             &node,
             params.time_cv.clone(),
@@ -1024,6 +1456,81 @@ impl NodeFactory {
         Ok(node)
     }
 
+    /// Watchdogs of the bench state estimators having one.
+    /// Warm start the `state_estimator` named `estimator_name` of the node `node_name`, if a
+    /// `warm_start` is configured.
+    fn warm_start(
+        mut state_estimator: Box<dyn state_estimators::StateEstimator>,
+        warm_start: Option<&EstimatorWarmStartConfig>,
+        global_config: &SimulatorConfig,
+        node_name: &str,
+        estimator_name: &str,
+        initial_time: f32,
+    ) -> SimbaResult<Box<dyn state_estimators::StateEstimator>> {
+        if let Some(warm_start) = warm_start {
+            warm_start_estimator(
+                state_estimator.as_mut(),
+                warm_start,
+                global_config,
+                node_name,
+                estimator_name,
+                initial_time,
+            )?;
+        }
+        Ok(state_estimator)
+    }
+
+    fn bench_watchdogs(
+        bench_configs: &[BenchStateEstimatorConfig],
+    ) -> impl Iterator<Item = EstimatorWatchdog> + '_ {
+        bench_configs.iter().filter_map(|bench| {
+            bench
+                .watchdog
+                .as_ref()
+                .map(|watchdog| EstimatorWatchdog::new(bench.name.clone(), watchdog))
+        })
+    }
+
+    /// Checks that the sensors selected by the bench estimators (`sensors` option) are known:
+    /// sensors of the node itself, or sensors of the robots sending their observations to it.
+    /// A misspelled name would silently give no observation to the estimator.
+    fn check_bench_sensors(
+        bench_configs: &[BenchStateEstimatorConfig],
+        own_sensors: &SensorManagerConfig,
+        global_config: &SimulatorConfig,
+        node_names: &[&str],
+        config_path: &str,
+    ) -> SimbaResult<()> {
+        let known_sensors = own_sensors
+            .sensors
+            .iter()
+            .chain(global_config.robots.iter().flat_map(|robot| {
+                robot.sensor_manager.sensors.iter().filter(|sensor| {
+                    sensor
+                        .send_to
+                        .iter()
+                        .any(|to| node_names.contains(&to.as_str()))
+                })
+            }))
+            .map(|sensor| sensor.name.as_str())
+            .collect::<Vec<_>>();
+        for bench in bench_configs {
+            for sensor in bench.sensors.iter().flatten() {
+                if !known_sensors.contains(&sensor.as_str()) {
+                    return Err(SimbaError::new(
+                        SimbaErrorTypes::ConfigError,
+                        format!(
+                            "Unknown sensor `{sensor}` given to the bench state estimator `{}`: it is neither a sensor of the node nor a sensor sending its observations to it. Known sensors: {:?}",
+                            bench.name, known_sensors
+                        ),
+                    )
+                    .with_config_path(&format!("{config_path}/{}/sensors", bench.name)));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Builds a computation-unit node from [`ComputationUnitConfig`].
     pub(crate) fn make_computation_unit(
         config: &ComputationUnitConfig,
@@ -1031,12 +1538,14 @@ impl NodeFactory {
     ) -> SimbaResult<Node> {
         let node_type = NodeType::ComputationUnit;
         let node_name = params.new_name.unwrap_or(&config.name).to_string();
+        // Stream of the node, so that its draws do not depend on the other nodes
+        let va_factory = params.va_factory.child(&node_name);
         let client = Self::make_global_channels(&node_name, params.broker)?;
         let network = Arc::new(RwLock::new(Network::from_config(
             node_name.clone(),
             &config.network,
             params.global_config,
-            params.va_factory,
+            &va_factory.child("network"),
             params.broker,
             params.initial_time,
         )));
@@ -1046,8 +1555,15 @@ impl NodeFactory {
             network: &network,
             node_name: &node_name,
             plugin_api: params.plugin_api,
-            va_factory: params.va_factory,
+            va_factory: &va_factory,
         };
+        Self::check_bench_sensors(
+            &config.state_estimators,
+            &SensorManagerConfig::default(),
+            params.global_config,
+            &[node_name.as_str(), config.name.as_str()],
+            &format!("computation_units/{}/state_estimators", config.name),
+        )?;
         let mut node = Node {
             node_meta_data: Arc::new(RwLock::new(NodeMetaData {
                 name: node_name.clone(),
@@ -1056,16 +1572,23 @@ impl NodeFactory {
                 labels: config.labels.clone(),
                 state: NodeState::Running,
                 position: None,
+                footprint_radius: 0.,
+                height: 0.,
             })),
             navigator: None,
             controller: None,
             physics: None,
             state_estimator: None,
-            sensor_manager: Some(Arc::new(RwLock::new(SensorManager::from_config(
-                &SensorManagerConfig::default(),
-                &from_config_args,
-                &State::default(),
-            )?))),
+            sensor_manager: Some(Arc::new(RwLock::new(
+                SensorManager::from_config(
+                    &SensorManagerConfig::default(),
+                    &from_config_args,
+                    &State::default(),
+                )
+                .map_err(|e| {
+                    Self::module_error(e, "computation_units", &config.name, "sensor_manager")
+                })?,
+            ))),
             network: Some(network.clone()),
             state_estimator_bench: Some(Arc::new(RwLock::new(Vec::with_capacity(
                 config.state_estimators.len(),
@@ -1082,6 +1605,32 @@ impl NodeFactory {
             node_message_client: client,
             current_command: None,
             environment: params.environment.clone(),
+            #[cfg(feature = "force_hard_determinism")]
+            kill_requested: false,
+            estimator_dataset: match params
+                .global_config
+                .results
+                .as_ref()
+                .and_then(|r| r.estimator_dataset.as_ref())
+            {
+                Some(dataset_config) => Some(EstimatorDatasetLogger::from_config(
+                    dataset_config,
+                    params.global_config,
+                    &node_name,
+                )?),
+                None => None,
+            },
+            thread_config: config.thread.clone(),
+            min_time_step: config.min_time_step,
+            last_step_time: None,
+            skipped_steps: 0,
+            sync_points: 0,
+            time_step_sync_points: 0,
+            observation_latencies: Vec::new(),
+            estimator_watchdogs: Self::bench_watchdogs(&config.state_estimators).collect(),
+            estimator_divergences: Vec::new(),
+            annotations: params.annotations.clone(),
+            command_arbiter: None,
         };
 
         for state_estimator_config in &config.state_estimators {
@@ -1097,14 +1646,42 @@ impl NodeFactory {
                             &state_estimator_config.config,
                             params.plugin_api,
                             params.global_config,
-                            params.va_factory,
+                            &va_factory
+                                .child("state_estimator_bench")
+                                .child(&state_estimator_config.name),
+                            None,
                             &network,
                             params.initial_time,
-                        )?,
+                        )
+                        .and_then(|state_estimator| {
+                            Self::warm_start(
+                                state_estimator,
+                                state_estimator_config.warm_start.as_ref(),
+                                params.global_config,
+                                &node_name,
+                                &state_estimator_config.name,
+                                params.initial_time,
+                            )
+                        })
+                        .map_err(|e| {
+                            Self::module_error(
+                                e.with_config_path(&format!(
+                                    "computation_units/{}/state_estimators/{}",
+                                    config.name, state_estimator_config.name
+                                )),
+                                "computation_units",
+                                &config.name,
+                                "state_estimators",
+                            )
+                        })?,
                     )),
+                    sensors: state_estimator_config.sensors.clone(),
                 })
         }
 
+        check_capabilities(&node)
+            .map_err(|e| e.with_config_path(&format!("computation_units/{}", config.name)))?;
+
         let service_manager = Some(Arc::new(RwLock::new(ServiceManager::initialize(
             &node,
             params.time_cv.clone(),
@@ -1160,6 +1737,38 @@ pub struct FromConfigArguments<'a> {
 
 #[cfg(test)]
 mod tests {
+    use config_checker::Check;
+
+    use super::{ComputationUnitConfig, RobotConfig};
+
+    #[test]
+    fn negative_height_rejected() {
+        let mut robot = RobotConfig::default();
+        robot.height = 1.5;
+        assert!(robot.do_check().is_ok());
+        robot.height = -1.;
+        assert!(robot.do_check().is_err());
+        robot.height = f32::NAN;
+        assert!(robot.do_check().is_err());
+    }
+
+    #[test]
+    fn negative_min_time_step_rejected() {
+        let mut robot = RobotConfig::default();
+        assert!(robot.do_check().is_ok());
+        robot.min_time_step = Some(0.1);
+        assert!(robot.do_check().is_ok());
+        robot.min_time_step = Some(-0.1);
+        assert!(robot.do_check().is_err());
+        robot.min_time_step = Some(f32::NAN);
+        assert!(robot.do_check().is_err());
+
+        let computation_unit = ComputationUnitConfig {
+            min_time_step: Some(-1.),
+            ..Default::default()
+        };
+        assert!(computation_unit.do_check().is_err());
+    }
 
     mod node_type_rules {
         use super::super::NodeType;
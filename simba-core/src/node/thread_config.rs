@@ -0,0 +1,259 @@
+//! Scheduling configuration of the node threads.
+//!
+//! Each node runs in its own thread, named after the node (visible in debuggers and profilers).
+//! [`ThreadConfig`] allows to change the priority and the CPU affinity of this thread, for
+//! instance to give a real-time priority to hardware-in-the-loop nodes while the other nodes
+//! run in background.
+//!
+//! Priority and affinity are only supported on Linux. On other platforms, the configuration is
+//! ignored with a warning.
+
+use config_checker::*;
+use log::warn;
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::{gui::UIComponent, simulator::SimulatorConfig};
+
+/// Number of CPU cores which can be given in the affinity (`CPU_SETSIZE` of Linux).
+pub const CPU_SET_SIZE: usize = 1024;
+
+/// Scheduling configuration of a node thread.
+///
+/// Failing to apply the configuration (e.g. missing privileges for real-time priority) produces
+/// a warning, the node runs with the default scheduling.
+///
+/// # Example
+/// ```yaml
+/// thread:
+///   nice: null
+///   realtime_priority: 50
+///   cpu_affinity: [2, 3]
+/// ```
+#[config_derives]
+pub struct ThreadConfig {
+    /// Niceness of the thread, from -20 (highest priority) to 19 (lowest priority).
+    /// Negative values usually require privileges. `null` keeps the default niceness.
+    pub nice: Option<i32>,
+    /// Real-time priority, from 1 to 99, with the `SCHED_FIFO` policy. Usually requires
+    /// privileges. `null` keeps the default policy.
+    pub realtime_priority: Option<i32>,
+    /// CPU cores the thread is allowed to run on, below [`CPU_SET_SIZE`]. Empty for no
+    /// restriction.
+    pub cpu_affinity: Vec<usize>,
+}
+
+impl Default for ThreadConfig {
+    /// Default scheduling: no priority change and no affinity restriction.
+    fn default() -> Self {
+        Self {
+            nice: None,
+            realtime_priority: None,
+            cpu_affinity: Vec::new(),
+        }
+    }
+}
+
+impl Check for ThreadConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Some(nice) = self.nice
+            && !(-20..=19).contains(&nice)
+        {
+            errors.push(format!("Nice value should be in [-20, 19], got {nice}"));
+        }
+        if let Some(priority) = self.realtime_priority
+            && !(1..=99).contains(&priority)
+        {
+            errors.push(format!(
+                "Real-time priority should be in [1, 99], got {priority}"
+            ));
+        }
+        for cpu in &self.cpu_affinity {
+            if *cpu >= CPU_SET_SIZE {
+                errors.push(format!(
+                    "CPU affinity cores should be lower than {CPU_SET_SIZE}, got {cpu}"
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl ThreadConfig {
+    /// Return whether the configuration changes the default scheduling.
+    pub fn is_default(&self) -> bool {
+        self.nice.is_none() && self.realtime_priority.is_none() && self.cpu_affinity.is_empty()
+    }
+
+    /// Apply the configuration to the calling thread. Errors are logged as warnings.
+    pub fn apply_to_current_thread(&self, node_name: &str) {
+        if self.is_default() {
+            return;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(nice) = self.nice {
+                // On Linux, the niceness is a per-thread attribute, set using the thread id.
+                let ret = unsafe {
+                    libc::setpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t, nice)
+                };
+                if ret != 0 {
+                    warn!(
+                        "Node {node_name}: cannot set thread niceness to {nice}: {}",
+                        std::io::Error::last_os_error()
+                    );
+                }
+            }
+            if let Some(priority) = self.realtime_priority {
+                let param = libc::sched_param {
+                    sched_priority: priority,
+                };
+                let ret = unsafe {
+                    libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param)
+                };
+                if ret != 0 {
+                    warn!(
+                        "Node {node_name}: cannot set real-time priority to {priority}: {}",
+                        std::io::Error::from_raw_os_error(ret)
+                    );
+                }
+            }
+            if !self.cpu_affinity.is_empty() {
+                let ret = unsafe {
+                    let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+                    // `CPU_SET` panics out of the set, the checked configurations are in it
+                    for cpu in self.cpu_affinity.iter().filter(|cpu| **cpu < CPU_SET_SIZE) {
+                        libc::CPU_SET(*cpu, &mut cpu_set);
+                    }
+                    libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set)
+                };
+                if ret != 0 {
+                    warn!(
+                        "Node {node_name}: cannot set CPU affinity to {:?}: {}",
+                        self.cpu_affinity,
+                        std::io::Error::last_os_error()
+                    );
+                }
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        warn!(
+            "Node {node_name}: thread priority and affinity are only supported on Linux, ignored"
+        );
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ThreadConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Thread").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Nice: ");
+                if let Some(nice) = &mut self.nice {
+                    ui.add(egui::DragValue::new(nice).range(-20..=19));
+                    if ui.button("X").clicked() {
+                        self.nice = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.nice = Some(0);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Real-time priority: ");
+                if let Some(priority) = &mut self.realtime_priority {
+                    ui.add(egui::DragValue::new(priority).range(1..=99));
+                    if ui.button("X").clicked() {
+                        self.realtime_priority = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.realtime_priority = Some(1);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("CPU affinity: ");
+                let mut to_remove = None;
+                for (i, cpu) in self.cpu_affinity.iter_mut().enumerate() {
+                    ui.add(egui::DragValue::new(cpu).range(0..=CPU_SET_SIZE - 1));
+                    if ui.button("-").clicked() {
+                        to_remove = Some(i);
+                    }
+                }
+                if let Some(i) = to_remove {
+                    self.cpu_affinity.remove(i);
+                }
+                if ui.button("+").clicked() {
+                    self.cpu_affinity.push(0);
+                }
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        egui::CollapsingHeader::new("Thread").show(ui, |ui| {
+            ui.label(format!(
+                "Nice: {}",
+                self.nice.map_or("default".to_string(), |n| n.to_string())
+            ));
+            ui.label(format!(
+                "Real-time priority: {}",
+                self.realtime_priority
+                    .map_or("default".to_string(), |p| p.to_string())
+            ));
+            ui.label(format!("CPU affinity: {:?}", self.cpu_affinity));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_ranges() {
+        assert!(ThreadConfig::default().check().is_ok());
+        let config = ThreadConfig {
+            nice: Some(-20),
+            realtime_priority: Some(99),
+            cpu_affinity: vec![0, CPU_SET_SIZE - 1],
+        };
+        assert!(config.check().is_ok());
+
+        for config in [
+            ThreadConfig {
+                nice: Some(20),
+                ..Default::default()
+            },
+            ThreadConfig {
+                realtime_priority: Some(0),
+                ..Default::default()
+            },
+            ThreadConfig {
+                cpu_affinity: vec![1, CPU_SET_SIZE],
+                ..Default::default()
+            },
+        ] {
+            assert!(config.check().is_err(), "{config:?} should be rejected");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cpu_set_size() {
+        assert_eq!(CPU_SET_SIZE, libc::CPU_SETSIZE as usize);
+    }
+}
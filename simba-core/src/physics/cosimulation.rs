@@ -0,0 +1,713 @@
+/*!
+Co-simulation with external physics engines.
+
+Physics engines (Rapier, a Gazebo instance, MuJoCo...) advance their world with a fixed time
+step, while simba jumps from one event to the next. The [`PhysicsEngine`] trait is the adapter to
+such an engine, and [`CoSimulatedPhysics`] is the [`Physics`] of the robot on top of it,
+negotiating the time between both:
+- when simba updates the physics at time `t`, the engine is stepped with its fixed `step` until
+  `t`, with the last command held;
+- the remainder of the interval, shorter than a step, is handled following
+  [`CoSimulationSync`]: a last shorter step reaching exactly `t` (`Exact`), or nothing, the
+  engine staying at its last full step (`Hold`) for the engines which only support their fixed
+  step;
+- with `lockstep`, the physics asks simba to run the node at each engine step, so that the
+  commands are updated at the engine rate (costly, but needed for contact-rich dynamics with
+  fast controllers).
+
+The engine is given by the plugin with
+[`PluginAPI::get_physics_engine`](crate::plugin_api::PluginAPI::get_physics_engine) (`External`
+engine), or is the [`ReferenceEngine`], which integrates a robot model and can be used as a
+template or to compare an engine with the [`InternalPhysics`](super::internal_physics::InternalPhysics).
+
+```yaml
+physics:
+  type: CoSimulation
+  engine:
+    type: External
+    config:
+      world_file: world.sdf  # Forwarded to the plugin
+  step: 0.01
+  sync:
+    type: Exact
+  lockstep: false
+```
+*/
+
+use std::sync::Arc;
+
+use config_checker::*;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::{
+    UIComponent,
+    utils::{json_config, string_combobox},
+};
+#[cfg(feature = "gui")]
+use crate::utils::enum_tools::ToVec;
+use crate::{
+    constants::TIME_ROUND,
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    networking::service::HasService,
+    node::capabilities::{Capabilities, CommandKind},
+    physics::{
+        GetRealStateReq, GetRealStateResp, Physics, PhysicsRecord,
+        robot_models::{
+            Command, IntegrationMethod, RobotModel, RobotModelConfig, integrate,
+            make_model_from_config,
+        },
+    },
+    plugin_api::PluginAPI,
+    recordable::Recordable,
+    simulator::SimulatorConfig,
+    state_estimators::{State, StateConfig, StateRecord},
+    utils::{
+        determinist_random_variable::DeterministRandomVariableFactory, macros::external_config,
+        maths::round_precision,
+    },
+};
+
+/// Handling of the end of the interval between two simba times, shorter than an engine step.
+///
+/// Default value: [`CoSimulationSync::Exact`].
+#[config_derives]
+pub enum CoSimulationSync {
+    /// A last shorter step reaches exactly the simba time. The engine should support variable
+    /// steps.
+    Exact,
+    /// The engine only does full steps: it stays at its last step before the simba time, and the
+    /// state given lags by less than a step.
+    Hold,
+}
+
+impl Default for CoSimulationSync {
+    fn default() -> Self {
+        Self::Exact
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CoSimulationSync {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        let mut current_str = self.to_string();
+        ui.horizontal(|ui| {
+            ui.label("Synchronization:");
+            string_combobox(
+                ui,
+                &CoSimulationSync::to_vec(),
+                &mut current_str,
+                format!("cosimulation-sync-choice-{}", unique_id),
+            );
+        });
+        if current_str != self.to_string() {
+            *self = match current_str.as_str() {
+                "Exact" => CoSimulationSync::Exact,
+                "Hold" => CoSimulationSync::Hold,
+                _ => panic!("Where did you find this value?"),
+            };
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!("Synchronization: {}", self));
+    }
+}
+
+/// Configuration of the [`ReferenceEngine`].
+///
+/// Default values:
+/// - `model`: [`RobotModelConfig::default`]
+/// - `initial_state`: [`StateConfig::default`]
+#[config_derives]
+pub struct ReferenceEngineConfig {
+    /// Robot kinematic model.
+    #[check]
+    pub model: RobotModelConfig,
+    /// Starting state.
+    #[check]
+    pub initial_state: StateConfig,
+}
+
+impl Default for ReferenceEngineConfig {
+    fn default() -> Self {
+        Self {
+            model: RobotModelConfig::default(),
+            initial_state: StateConfig::default(),
+        }
+    }
+}
+
+external_config!(
+/// Config of an engine given by the plugin.
+///
+/// The `config` is forwarded to
+/// [`PluginAPI::get_physics_engine`](crate::plugin_api::PluginAPI::get_physics_engine).
+    ExternalEngineConfig,
+    "External Engine",
+    "external-engine"
+);
+
+/// Engine of the [`CoSimulatedPhysics`].
+///
+/// Default value: [`CoSimulationEngineConfig::Reference`] with
+/// [`ReferenceEngineConfig::default`].
+#[config_derives]
+pub enum CoSimulationEngineConfig {
+    /// Built-in [`ReferenceEngine`].
+    #[check]
+    Reference(ReferenceEngineConfig),
+    /// Engine given by the plugin, with
+    /// [`PluginAPI::get_physics_engine`](crate::plugin_api::PluginAPI::get_physics_engine).
+    #[check]
+    External(ExternalEngineConfig),
+}
+
+impl Default for CoSimulationEngineConfig {
+    fn default() -> Self {
+        Self::Reference(ReferenceEngineConfig::default())
+    }
+}
+
+/// Configuration of the [`CoSimulatedPhysics`].
+///
+/// Default values:
+/// - `engine`: [`CoSimulationEngineConfig::default`]
+/// - `step`: `0.01`
+/// - `sync`: [`CoSimulationSync::Exact`]
+/// - `lockstep`: `false`
+#[config_derives]
+pub struct CoSimulationPhysicsConfig {
+    /// Physics engine.
+    #[check]
+    pub engine: CoSimulationEngineConfig,
+    /// Fixed step of the engine, in seconds.
+    pub step: f32,
+    /// Handling of the intervals shorter than a step.
+    pub sync: CoSimulationSync,
+    /// Run the node at each engine step, to update the command at the engine rate.
+    pub lockstep: bool,
+}
+
+impl Default for CoSimulationPhysicsConfig {
+    fn default() -> Self {
+        Self {
+            engine: CoSimulationEngineConfig::default(),
+            step: 0.01,
+            sync: CoSimulationSync::default(),
+            lockstep: false,
+        }
+    }
+}
+
+impl Check for CoSimulationPhysicsConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.step <= 0. {
+            return Err(vec![format!(
+                "Co-simulation step should be strictly positive, got {}",
+                self.step
+            )]);
+        }
+        if self.lockstep && self.step < TIME_ROUND {
+            return Err(vec![format!(
+                "Co-simulation step should be at least {TIME_ROUND} s in lockstep, got {}",
+                self.step
+            )]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CoSimulationPhysicsConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Co-simulated Physics")
+            .id_salt(format!("cosimulation-physics-{}", unique_id))
+            .show(ui, |ui| {
+                let mut current_str = self.engine.to_string();
+                ui.horizontal(|ui| {
+                    ui.label("Engine:");
+                    string_combobox(
+                        ui,
+                        &CoSimulationEngineConfig::to_vec(),
+                        &mut current_str,
+                        format!("cosimulation-engine-choice-{}", unique_id),
+                    );
+                });
+                if current_str != self.engine.to_string() {
+                    self.engine = match current_str.as_str() {
+                        "Reference" => {
+                            CoSimulationEngineConfig::Reference(ReferenceEngineConfig::default())
+                        }
+                        "External" => {
+                            CoSimulationEngineConfig::External(ExternalEngineConfig::default())
+                        }
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+                match &mut self.engine {
+                    CoSimulationEngineConfig::Reference(c) => {
+                        c.model.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label("Initial state:");
+                            c.initial_state.show_mut(
+                                ui,
+                                ctx,
+                                buffer_stack,
+                                global_config,
+                                current_node_name,
+                                unique_id,
+                            );
+                        });
+                    }
+                    CoSimulationEngineConfig::External(c) => c.show_mut(
+                        ui,
+                        ctx,
+                        buffer_stack,
+                        global_config,
+                        current_node_name,
+                        unique_id,
+                    ),
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Step (s):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.step)
+                            .speed(0.001)
+                            .range(0.0001..=10.),
+                    );
+                });
+                self.sync.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+                ui.checkbox(&mut self.lockstep, "Lockstep");
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Co-simulated Physics")
+            .id_salt(format!("cosimulation-physics-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Engine: {}", self.engine));
+                match &self.engine {
+                    CoSimulationEngineConfig::Reference(c) => {
+                        c.model.show(ui, ctx, unique_id);
+                        ui.horizontal(|ui| {
+                            ui.label("Initial state:");
+                            c.initial_state.show(ui, ctx, unique_id);
+                        });
+                    }
+                    CoSimulationEngineConfig::External(c) => c.show(ui, ctx, unique_id),
+                }
+                ui.label(format!("Step: {} s", self.step));
+                self.sync.show(ui, ctx, unique_id);
+                ui.label(format!("Lockstep: {}", self.lockstep));
+            });
+    }
+}
+
+/// Record of the [`CoSimulatedPhysics`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CoSimulationPhysicsRecord {
+    /// State given by the engine.
+    pub state: StateRecord,
+    /// Time of the engine world, which can be before the simba time with
+    /// [`CoSimulationSync::Hold`].
+    pub engine_time: f32,
+    /// Current command applied.
+    pub current_command: Command,
+    /// Record of the engine, see [`PhysicsEngine::record`].
+    pub engine: serde_json::Value,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CoSimulationPhysicsRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("State").show(ui, |ui| {
+                self.state.show(ui, ctx, unique_id);
+            });
+            ui.label(format!("Engine time: {}", self.engine_time));
+            egui::CollapsingHeader::new("Current command").show(ui, |ui| {
+                self.current_command.show(ui, ctx, unique_id);
+            });
+            if !self.engine.is_null() {
+                ui.label(format!("Engine: {}", self.engine));
+            }
+        });
+    }
+}
+
+/// Adapter to a physics engine stepped by the [`CoSimulatedPhysics`].
+///
+/// The engine holds the world with the robot; the time is handled by the
+/// [`CoSimulatedPhysics`], which calls [`step`](PhysicsEngine::step) with the fixed step of the
+/// configuration, and with shorter steps for [`CoSimulationSync::Exact`].
+pub trait PhysicsEngine: std::fmt::Debug + std::marker::Send + std::marker::Sync {
+    /// Set the command of the robot, held until the next call.
+    fn set_command(&mut self, command: &Command);
+
+    /// Advance the world of `dt` seconds.
+    fn step(&mut self, dt: f32);
+
+    /// Current state of the robot in the world.
+    fn state(&self) -> State;
+
+    /// Optional: overwrite the `pose` and the `velocity` of the robot, see
+    /// [`Physics::set_state`]. Not supported by default.
+    #[allow(unused_variables)]
+    fn set_state(
+        &mut self,
+        pose: Option<[f32; 3]>,
+        velocity: Option<[f32; 3]>,
+    ) -> Result<(), String> {
+        Err("The state of this physics engine cannot be set".to_string())
+    }
+
+    /// Optional: [`Command`] variants accepted by the engine, see [`Physics::capabilities`].
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Optional: record of the engine, in [`CoSimulationPhysicsRecord::engine`].
+    fn record(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+}
+
+/// Reference [`PhysicsEngine`]: integrates a robot model, exactly, on each step.
+#[derive(Debug)]
+pub struct ReferenceEngine {
+    model: Box<dyn RobotModel>,
+    state: State,
+    command: Command,
+}
+
+impl ReferenceEngine {
+    /// Makes a new [`ReferenceEngine`] from the given config.
+    pub fn from_config(
+        config: &ReferenceEngineConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+    ) -> Self {
+        let model = make_model_from_config(&config.model);
+        let command = model.default_command();
+        Self {
+            model,
+            state: State::from_config(&config.initial_state, va_factory),
+            command,
+        }
+    }
+}
+
+impl PhysicsEngine for ReferenceEngine {
+    fn set_command(&mut self, command: &Command) {
+        self.command = command.clone();
+    }
+
+    fn step(&mut self, dt: f32) {
+        integrate(
+            self.model.as_mut(),
+            IntegrationMethod::Exact,
+            &mut self.state,
+            &self.command,
+            dt,
+        );
+    }
+
+    fn state(&self) -> State {
+        self.state.clone()
+    }
+
+    fn set_state(
+        &mut self,
+        pose: Option<[f32; 3]>,
+        velocity: Option<[f32; 3]>,
+    ) -> Result<(), String> {
+        if let Some(pose) = pose {
+            self.state.pose = pose.into();
+        }
+        if let Some(velocity) = velocity {
+            self.state.velocity = velocity.into();
+        }
+        Ok(())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            commands: Some(vec![CommandKind::from(&self.model.default_command())]),
+            ..Default::default()
+        }
+    }
+}
+
+/// [`Physics`] stepping a [`PhysicsEngine`], see the [module documentation](self).
+#[derive(Debug)]
+pub struct CoSimulatedPhysics {
+    engine: Box<dyn PhysicsEngine>,
+    step: f32,
+    sync: CoSimulationSync,
+    lockstep: bool,
+    /// Time of the engine world.
+    engine_time: f32,
+    /// Current command applied.
+    current_command: Command,
+}
+
+impl CoSimulatedPhysics {
+    /// Makes a new [`CoSimulatedPhysics`] from the given config. The `plugin_api` is required for
+    /// the [`CoSimulationEngineConfig::External`] engines.
+    pub fn from_config(
+        config: &CoSimulationPhysicsConfig,
+        plugin_api: &Option<Arc<dyn PluginAPI>>,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        initial_time: f32,
+    ) -> SimbaResult<Self> {
+        let engine = match &config.engine {
+            CoSimulationEngineConfig::Reference(c) => {
+                Box::new(ReferenceEngine::from_config(c, va_factory)) as Box<dyn PhysicsEngine>
+            }
+            CoSimulationEngineConfig::External(c) => plugin_api
+                .as_ref()
+                .ok_or_else(|| {
+                    SimbaError::new(
+                        SimbaErrorTypes::ExternalAPIError,
+                        "Plugin API not set!".to_string(),
+                    )
+                })?
+                .get_physics_engine(&c.config, global_config, va_factory, initial_time),
+        };
+        Ok(Self::new(engine, config, initial_time))
+    }
+
+    /// Makes a new [`CoSimulatedPhysics`] stepping the given `engine`, e.g. from
+    /// [`PluginAPI::get_physics`](crate::plugin_api::PluginAPI::get_physics). The `engine`
+    /// of the `config` is not used.
+    pub fn new(
+        engine: Box<dyn PhysicsEngine>,
+        config: &CoSimulationPhysicsConfig,
+        initial_time: f32,
+    ) -> Self {
+        Self {
+            engine,
+            step: config.step,
+            sync: config.sync.clone(),
+            lockstep: config.lockstep,
+            engine_time: initial_time,
+            current_command: Command::default(),
+        }
+    }
+
+    /// Time of the engine world.
+    pub fn engine_time(&self) -> f32 {
+        self.engine_time
+    }
+
+    /// Step the engine until `time`, following the synchronization mode.
+    fn advance_to(&mut self, time: f32) {
+        // Tolerance on the float accumulation of the steps
+        let tolerance = self.step * 1e-3;
+        let steps = ((time - self.engine_time + tolerance) / self.step).floor();
+        if steps < 0. {
+            return;
+        }
+        for _ in 0..steps as usize {
+            self.engine.step(self.step);
+        }
+        self.engine_time += steps * self.step;
+
+        let remainder = time - self.engine_time;
+        if remainder.abs() <= tolerance {
+            self.engine_time = time;
+        } else if matches!(self.sync, CoSimulationSync::Exact) {
+            self.engine.step(remainder);
+            self.engine_time = time;
+        }
+    }
+}
+
+impl Physics for CoSimulatedPhysics {
+    fn apply_command(&mut self, command: &Command, time: f32) {
+        self.advance_to(time);
+        self.current_command = command.clone();
+        self.engine.set_command(command);
+    }
+
+    fn update_state(&mut self, time: f32) {
+        self.advance_to(time);
+    }
+
+    /// State at the engine time, which can be before `time` with [`CoSimulationSync::Hold`].
+    fn state(&self, _time: f32) -> State {
+        self.engine.state()
+    }
+
+    fn next_time_step(&self) -> Option<f32> {
+        self.lockstep
+            .then(|| round_precision(self.engine_time + self.step, TIME_ROUND).unwrap())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.engine.capabilities()
+    }
+
+    fn set_state(
+        &mut self,
+        pose: Option<[f32; 3]>,
+        velocity: Option<[f32; 3]>,
+        time: f32,
+    ) -> Result<(), String> {
+        self.advance_to(time);
+        self.engine.set_state(pose, velocity)
+    }
+}
+
+impl Recordable<PhysicsRecord> for CoSimulatedPhysics {
+    fn record(&self) -> PhysicsRecord {
+        PhysicsRecord::CoSimulation(CoSimulationPhysicsRecord {
+            state: self.engine.state().record(),
+            engine_time: self.engine_time,
+            current_command: self.current_command.clone(),
+            engine: self.engine.record(),
+        })
+    }
+}
+
+impl HasService<GetRealStateReq, GetRealStateResp> for CoSimulatedPhysics {
+    fn handle_service_requests(
+        &mut self,
+        _req: GetRealStateReq,
+        time: f32,
+    ) -> Result<GetRealStateResp, String> {
+        Ok(GetRealStateResp {
+            state: self.state(time),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::physics::robot_models::unicycle::UnicycleCommand;
+
+    /// Engine recording its steps.
+    #[derive(Debug, Default)]
+    struct StepCounter {
+        steps: Arc<Mutex<Vec<f32>>>,
+    }
+
+    impl PhysicsEngine for StepCounter {
+        fn set_command(&mut self, _command: &Command) {}
+
+        fn step(&mut self, dt: f32) {
+            self.steps.lock().unwrap().push(dt);
+        }
+
+        fn state(&self) -> State {
+            State::new()
+        }
+    }
+
+    fn cosimulation(
+        sync: CoSimulationSync,
+        lockstep: bool,
+    ) -> (CoSimulatedPhysics, Arc<Mutex<Vec<f32>>>) {
+        let engine = StepCounter::default();
+        let steps = engine.steps.clone();
+        let config = CoSimulationPhysicsConfig {
+            step: 0.1,
+            sync,
+            lockstep,
+            ..Default::default()
+        };
+        (
+            CoSimulatedPhysics::new(Box::new(engine), &config, 0.),
+            steps,
+        )
+    }
+
+    #[test]
+    fn exact_synchronization() {
+        let (mut physics, steps) = cosimulation(CoSimulationSync::Exact, false);
+        physics.update_state(0.35);
+        assert_eq!(physics.engine_time(), 0.35);
+        physics.update_state(0.35);
+        physics.update_state(1.);
+        assert_eq!(physics.engine_time(), 1.);
+        let steps = steps.lock().unwrap();
+        assert_eq!(steps.len(), 4 + 6 + 1);
+        assert!((steps[3] - 0.05).abs() < 1e-5);
+        assert!((steps.iter().sum::<f32>() - 1.).abs() < 1e-5);
+        assert_eq!(physics.next_time_step(), None);
+    }
+
+    #[test]
+    fn hold_synchronization() {
+        let (mut physics, steps) = cosimulation(CoSimulationSync::Hold, true);
+        physics.update_state(0.35);
+        assert!((physics.engine_time() - 0.3).abs() < 1e-5);
+        assert!((physics.next_time_step().unwrap() - 0.4).abs() < 1e-5);
+        physics.update_state(1.);
+        assert_eq!(physics.engine_time(), 1.);
+        let steps = steps.lock().unwrap();
+        assert_eq!(steps.len(), 10);
+        assert!(steps.iter().all(|dt| *dt == 0.1));
+    }
+
+    #[test]
+    fn reference_engine() {
+        let va_factory = Arc::new(DeterministRandomVariableFactory::new(0.));
+        let mut physics = CoSimulatedPhysics::from_config(
+            &CoSimulationPhysicsConfig::default(),
+            &None,
+            &SimulatorConfig::default(),
+            &va_factory,
+            0.,
+        )
+        .unwrap();
+        physics.apply_command(
+            &Command::Unicycle(UnicycleCommand {
+                left_wheel_speed: 1.,
+                right_wheel_speed: 1.,
+            }),
+            0.,
+        );
+        physics.update_state(0.5);
+        assert!(physics.state(0.5).pose.x > 0.);
+        physics.set_state(Some([1., 2., 0.]), None, 0.5).unwrap();
+        assert_eq!(physics.state(0.5).pose.x, 1.);
+        assert!(matches!(physics.record(), PhysicsRecord::CoSimulation(_)));
+    }
+}
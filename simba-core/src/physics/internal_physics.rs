@@ -10,13 +10,15 @@ use std::sync::{Arc, Mutex};
 use crate::{gui::UIComponent, simulator::SimulatorConfig};
 
 use crate::{
-    networking::service::HasService,
+    networking::{reconfigure::ReconfigureMessage, service::HasService},
+    node::capabilities::{Capabilities, CommandKind},
     physics::{
         fault_models::fault_model::{
             PhysicsFaultModel, PhysicsFaultModelConfig, make_physics_fault_model_from_config,
         },
         robot_models::{
-            Command, RobotModel, RobotModelConfig, make_model_from_config, unicycle::UnicycleConfig,
+            Command, IntegrationMethod, RobotModel, RobotModelConfig, integrate,
+            make_model_from_config, unicycle::UnicycleConfig,
         },
     },
     recordable::Recordable,
@@ -33,6 +35,9 @@ use simba_macros::config_derives;
 /// - `model`: [`RobotModelConfig::Unicycle`] with [`UnicycleConfig::default`]
 /// - `initial_state`: [`StateConfig::default`]
 /// - `faults`: empty vector
+/// - `inactive_faults`: empty vector
+/// - `integrator`: [`IntegrationMethod::Exact`]
+/// - `max_sub_step`: `None`
 #[config_derives]
 pub struct InternalPhysicConfig {
     /// Robot kinematic model configuration.
@@ -44,6 +49,42 @@ pub struct InternalPhysicConfig {
     /// Physics fault model configurations applied after each update.
     #[check]
     pub faults: Vec<PhysicsFaultModelConfig>,
+    /// Indexes, in `faults`, of the fault models inactive at start. They can be activated later
+    /// by an `ActuatorFault` scenario event.
+    pub inactive_faults: Vec<usize>,
+    /// Integration scheme of the robot model between two updates.
+    pub integrator: IntegrationMethod,
+    /// Maximal duration of an integration step, in seconds. Longer updates are split in equal
+    /// sub-steps. `None` integrates each update in a single step.
+    pub max_sub_step: Option<f32>,
+}
+
+impl Check for InternalPhysicConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Some(max_sub_step) = self.max_sub_step
+            && max_sub_step <= 0.
+        {
+            errors.push(format!(
+                "Maximal sub-step should be strictly positive, got {max_sub_step}"
+            ));
+        }
+        if let Some(index) = self
+            .inactive_faults
+            .iter()
+            .find(|index| **index >= self.faults.len())
+        {
+            errors.push(format!(
+                "Inactive fault {index} does not exist, the physics has {} fault models",
+                self.faults.len()
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
 #[cfg(feature = "gui")]
@@ -81,6 +122,32 @@ impl UIComponent for InternalPhysicConfig {
                     );
                 });
 
+                self.integrator.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+
+                ui.horizontal(|ui| {
+                    ui.label("Maximal sub-step (s):");
+                    match &mut self.max_sub_step {
+                        Some(step) => {
+                            ui.add(egui::DragValue::new(step).speed(0.001).range(0.001..=10.));
+                            if ui.button("X").clicked() {
+                                self.max_sub_step = None;
+                            }
+                        }
+                        None => {
+                            if ui.button("+").clicked() {
+                                self.max_sub_step = Some(0.01);
+                            }
+                        }
+                    }
+                });
+
                 PhysicsFaultModelConfig::show_faults_mut(
                     &mut self.faults,
                     ui,
@@ -90,6 +157,23 @@ impl UIComponent for InternalPhysicConfig {
                     current_node_name,
                     unique_id,
                 );
+
+                ui.horizontal(|ui| {
+                    ui.label("Inactive faults at start:");
+                    let mut to_remove = None;
+                    for (i, fault) in self.inactive_faults.iter_mut().enumerate() {
+                        ui.add(egui::DragValue::new(fault));
+                        if ui.button("-").clicked() {
+                            to_remove = Some(i);
+                        }
+                    }
+                    if let Some(i) = to_remove {
+                        self.inactive_faults.remove(i);
+                    }
+                    if ui.button("+").clicked() {
+                        self.inactive_faults.push(0);
+                    }
+                });
             });
     }
 
@@ -104,7 +188,20 @@ impl UIComponent for InternalPhysicConfig {
                     self.initial_state.show(ui, ctx, unique_id);
                 });
 
+                self.integrator.show(ui, ctx, unique_id);
+
+                if let Some(step) = self.max_sub_step {
+                    ui.label(format!("Maximal sub-step: {step} s"));
+                }
+
                 PhysicsFaultModelConfig::show_faults(&self.faults, ui, ctx, unique_id);
+
+                if !self.inactive_faults.is_empty() {
+                    ui.label(format!(
+                        "Inactive faults at start: {:?}",
+                        self.inactive_faults
+                    ));
+                }
             });
     }
 }
@@ -115,6 +212,9 @@ impl Default for InternalPhysicConfig {
             model: RobotModelConfig::Unicycle(UnicycleConfig::default()),
             initial_state: StateConfig::default(),
             faults: Vec::new(),
+            inactive_faults: Vec::new(),
+            integrator: IntegrationMethod::default(),
+            max_sub_step: None,
         }
     }
 }
@@ -156,6 +256,10 @@ pub struct InternalPhysics {
     /// Current command applied.
     current_command: Command,
     faults: SharedMutex<Vec<Box<dyn PhysicsFaultModel>>>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
+    integrator: IntegrationMethod,
+    max_sub_step: Option<f32>,
 }
 
 impl InternalPhysics {
@@ -194,6 +298,11 @@ impl InternalPhysics {
                     })
                     .collect(),
             )),
+            active_faults: (0..config.faults.len())
+                .map(|i| !config.inactive_faults.contains(&i))
+                .collect(),
+            integrator: config.integrator,
+            max_sub_step: config.max_sub_step,
         }
     }
 
@@ -211,12 +320,31 @@ impl InternalPhysics {
             return;
         }
 
-        self.model
-            .update_state(&mut self.state, &self.current_command, dt);
+        let sub_steps = match self.max_sub_step {
+            Some(max_sub_step) => (dt / max_sub_step).ceil().max(1.) as usize,
+            _ => 1,
+        };
+        let sub_dt = dt / sub_steps as f32;
+        for _ in 0..sub_steps {
+            integrate(
+                self.model.as_mut(),
+                self.integrator,
+                &mut self.state,
+                &self.current_command,
+                sub_dt,
+            );
+        }
 
         self.last_time_update = time;
 
-        for fault in self.faults.lock().unwrap().iter() {
+        for (fault, _) in self
+            .faults
+            .lock()
+            .unwrap()
+            .iter()
+            .zip(&self.active_faults)
+            .filter(|(_, active)| **active)
+        {
             fault.add_faults(time, &mut self.state);
         }
     }
@@ -252,6 +380,41 @@ impl Physics for InternalPhysics {
         );
         self.state.clone()
     }
+
+    /// Accepts only the commands of its robot model.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            commands: Some(vec![CommandKind::from(&self.model.default_command())]),
+            ..Default::default()
+        }
+    }
+
+    /// Only the activation of the fault models can be changed.
+    fn reconfigure(&mut self, message: &ReconfigureMessage, _time: f32) -> Result<(), String> {
+        if message.period.is_some() || message.noise_scale.is_some() {
+            return Err(
+                "Only the fault activation of the internal physics can be changed".to_string(),
+            );
+        }
+        message.apply_fault_activation(&mut self.active_faults)
+    }
+
+    /// The state is first computed until `time` with the current command, then replaced.
+    fn set_state(
+        &mut self,
+        pose: Option<[f32; 3]>,
+        velocity: Option<[f32; 3]>,
+        time: f32,
+    ) -> Result<(), String> {
+        self.compute_state_until(time);
+        if let Some(pose) = pose {
+            self.state.pose = pose.into();
+        }
+        if let Some(velocity) = velocity {
+            self.state.velocity = velocity.into();
+        }
+        Ok(())
+    }
 }
 
 impl HasService<GetRealStateReq, GetRealStateResp> for InternalPhysics {
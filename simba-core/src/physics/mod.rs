@@ -4,12 +4,15 @@
 //! service request/response types, and factory helpers used to instantiate runtime physics
 //! implementations.
 //! Implementations may include perfect/internal physics, external plugin-backed physics,
-//! or Python-backed physics.
+//! Python-backed physics, an external physics engine co-simulated with simba, or rigid body
+//! physics backed by Rapier.
 
+pub mod cosimulation;
 pub mod external_physics;
 pub mod internal_physics;
 pub mod pybinds;
 pub mod python_physics;
+pub mod rigid_body;
 
 pub mod robot_models;
 
@@ -34,6 +37,12 @@ pub enum PhysicsConfig {
     /// Python-backed physics implementation.
     #[check]
     Python(python_physics::PythonPhysicsConfig),
+    /// Physics engine stepped in co-simulation.
+    #[check]
+    CoSimulation(cosimulation::CoSimulationPhysicsConfig),
+    /// Rigid body physics backed by Rapier. Requires the `rapier` feature.
+    #[check]
+    RigidBody(rigid_body::RigidBodyPhysicsConfig),
 }
 
 #[cfg(feature = "gui")]
@@ -70,6 +79,14 @@ impl UIComponent for PhysicsConfig {
                 "Python" => {
                     *self = PhysicsConfig::Python(python_physics::PythonPhysicsConfig::default())
                 }
+                "CoSimulation" => {
+                    *self = PhysicsConfig::CoSimulation(
+                        cosimulation::CoSimulationPhysicsConfig::default(),
+                    )
+                }
+                "RigidBody" => {
+                    *self = PhysicsConfig::RigidBody(rigid_body::RigidBodyPhysicsConfig::default())
+                }
                 _ => panic!("Where did you find this value?"),
             };
         }
@@ -98,6 +115,22 @@ impl UIComponent for PhysicsConfig {
                 current_node_name,
                 unique_id,
             ),
+            PhysicsConfig::CoSimulation(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            PhysicsConfig::RigidBody(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
         }
     }
 
@@ -109,6 +142,8 @@ impl UIComponent for PhysicsConfig {
             PhysicsConfig::Internal(c) => c.show(ui, ctx, unique_id),
             PhysicsConfig::External(c) => c.show(ui, ctx, unique_id),
             PhysicsConfig::Python(c) => c.show(ui, ctx, unique_id),
+            PhysicsConfig::CoSimulation(c) => c.show(ui, ctx, unique_id),
+            PhysicsConfig::RigidBody(c) => c.show(ui, ctx, unique_id),
         }
     }
 }
@@ -122,6 +157,10 @@ pub enum PhysicsRecord {
     External(external_physics::ExternalPhysicsRecord),
     /// Record emitted by Python physics.
     Python(python_physics::PythonPhysicsRecord),
+    /// Record emitted by co-simulated physics.
+    CoSimulation(cosimulation::CoSimulationPhysicsRecord),
+    /// Record emitted by rigid body physics.
+    RigidBody(rigid_body::RigidBodyPhysicsRecord),
 }
 
 impl PhysicsRecord {
@@ -133,6 +172,8 @@ impl PhysicsRecord {
             Self::External(_) => [0., 0., 0.], // TODO: Find a way to get info from external record
             Self::Python(_) => [0., 0., 0.],   // TODO: Find a way to get info from external record
             Self::Internal(p) => p.state.pose,
+            Self::CoSimulation(p) => p.state.pose,
+            Self::RigidBody(p) => p.state.pose,
         }
     }
 }
@@ -156,14 +197,27 @@ impl UIComponent for PhysicsRecord {
                     r.show(ui, ctx, unique_id);
                 });
             }
+            Self::CoSimulation(r) => {
+                egui::CollapsingHeader::new("CoSimulation").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::RigidBody(r) => {
+                egui::CollapsingHeader::new("RigidBody").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
         });
     }
 }
 
 use crate::{
     errors::SimbaResult,
-    networking::service::HasService,
-    node::{Node, node_factory::FromConfigArguments},
+    networking::{reconfigure::ReconfigureMessage, service::HasService},
+    node::{
+        Node, capabilities::Capabilities, footprint::FootprintConfig,
+        node_factory::FromConfigArguments,
+    },
     physics::robot_models::Command,
     recordable::Recordable,
     simulator::SimulatorConfig,
@@ -220,15 +274,48 @@ pub trait Physics:
     fn next_time_step(&self) -> Option<f32> {
         None
     }
+
+    /// Optional: [`Command`] variants accepted by the physics, checked against the controller
+    /// when the node is built (see [`capabilities`](crate::node::capabilities)). Nothing is
+    /// declared by default.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Apply the changes of a [`ReconfigureMessage`] received at `time`.
+    ///
+    /// Returns the reason of the rejection if the changes are not supported. By default, the
+    /// physics cannot be reconfigured.
+    #[allow(unused_variables)]
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        Err("This physics does not support reconfiguration".to_string())
+    }
+
+    /// Set the real state at `time` (e.g. teleportation of the robot by a scenario event): the
+    /// `pose` `[x, y, theta]` and the `velocity` `[longitudinal, lateral, angular]` given
+    /// replace the current ones.
+    ///
+    /// Returns the reason of the rejection if the state cannot be set, which is the default.
+    #[allow(unused_variables)]
+    fn set_state(
+        &mut self,
+        pose: Option<[f32; 3]>,
+        velocity: Option<[f32; 3]>,
+        time: f32,
+    ) -> Result<(), String> {
+        Err("The state of this physics cannot be set".to_string())
+    }
 }
 
 /// Helper function to create a physics from the given configuration.
 ///
 /// ## Arguments
 /// - `config`: The configuration of the physics.
+/// - `footprint`: The footprint of the robot, from its own configuration (used by the rigid body physics).
 /// - `from_config_args`: Additional arguments needed to create the physics, such as the robot name, random variable factory, initial time, plugin API, global config and network.
 pub fn make_physics_from_config(
     config: &PhysicsConfig,
+    footprint: &FootprintConfig,
     from_config_args: &FromConfigArguments,
 ) -> SimbaResult<SharedRwLock<Box<dyn Physics>>> {
     Ok(Arc::new(RwLock::new(match &config {
@@ -254,5 +341,31 @@ pub fn make_physics_from_config(
             )
             .unwrap(),
         ),
+        PhysicsConfig::CoSimulation(c) => Box::new(cosimulation::CoSimulatedPhysics::from_config(
+            c,
+            from_config_args.plugin_api,
+            from_config_args.global_config,
+            from_config_args.va_factory,
+            from_config_args.initial_time,
+        )?),
+        #[cfg(feature = "rapier")]
+        PhysicsConfig::RigidBody(c) => Box::new(
+            rigid_body::RigidBodyPhysics::from_config(
+                c,
+                footprint,
+                from_config_args.va_factory,
+                from_config_args.initial_time,
+            )
+            .map_err(|e| e.chain(format!("Robot {}", from_config_args.node_name)))?,
+        ),
+        #[cfg(not(feature = "rapier"))]
+        PhysicsConfig::RigidBody(_) => {
+            let _ = footprint;
+            return Err(crate::errors::SimbaError::new(
+                crate::errors::SimbaErrorTypes::ConfigError,
+                "RigidBody physics requires simba to be built with the `rapier` feature"
+                    .to_string(),
+            ));
+        }
     })))
 }
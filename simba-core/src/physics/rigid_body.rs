@@ -0,0 +1,977 @@
+/*!
+Rigid body physics, backed by the [Rapier](https://rapier.rs) engine (feature `rapier`).
+
+Unlike the kinematic [`InternalPhysics`](super::internal_physics::InternalPhysics), the robot is
+a dynamic body with a mass and a collision shape, its [footprint](crate::node::footprint): the
+robot model gives the velocity requested by the command, and the engine resolves the contacts
+with the obstacles, with the given `restitution` and `friction`. The walls of the map (landmarks
+with a width and a height) are static obstacles.
+
+Bodies can be attached to the robot with joints (trailers, towed loads): a `Revolute` joint lets
+the body turn around the anchor, a `Fixed` joint keeps it rigidly attached.
+
+Each robot has its own world: the robots do not collide with each other.
+
+```yaml
+robots:
+  - name: robot1
+    footprint:
+      type: Circle
+      radius: 0.3
+    physics:
+      type: RigidBody
+      model:
+        type: Unicycle
+        wheel_distance: 0.25
+      mass: 10.
+      restitution: 0.2
+      friction: 0.5
+      step: 0.01
+      attached_bodies:
+        - name: trailer
+          footprint:
+            type: Circle
+            radius: 0.2
+          mass: 5.
+          joint:
+            type: Revolute
+          anchor: [-0.4, 0.]
+          body_anchor: [0.3, 0.]
+```
+*/
+
+use config_checker::*;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::string_combobox};
+#[cfg(feature = "gui")]
+use crate::simulator::SimulatorConfig;
+#[cfg(feature = "gui")]
+use crate::utils::enum_tools::ToVec;
+use crate::{
+    node::footprint::{CircleFootprintConfig, FootprintConfig},
+    physics::robot_models::{Command, RobotModelConfig},
+    state_estimators::{StateConfig, StateRecord},
+};
+
+/// Joint between the robot and an attached body.
+///
+/// Default value: [`JointKind::Revolute`].
+#[config_derives]
+pub enum JointKind {
+    /// The body turns freely around the anchor.
+    Revolute,
+    /// The body is rigidly attached at the anchor.
+    Fixed,
+}
+
+impl Default for JointKind {
+    fn default() -> Self {
+        Self::Revolute
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for JointKind {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        let mut current_str = self.to_string();
+        ui.horizontal(|ui| {
+            ui.label("Joint:");
+            string_combobox(
+                ui,
+                &JointKind::to_vec(),
+                &mut current_str,
+                format!("joint-kind-choice-{}", unique_id),
+            );
+        });
+        if current_str != self.to_string() {
+            *self = match current_str.as_str() {
+                "Revolute" => JointKind::Revolute,
+                "Fixed" => JointKind::Fixed,
+                _ => panic!("Where did you find this value?"),
+            };
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!("Joint: {}", self));
+    }
+}
+
+/// Body attached to the robot by a joint.
+///
+/// At the start, the body is placed with the orientation of the robot, its anchor on the anchor
+/// of the robot.
+///
+/// Default values:
+/// - `name`: `"body"`
+/// - `footprint`: [`FootprintConfig::Circle`] with [`CircleFootprintConfig::default`]
+/// - `mass`: `1.`
+/// - `joint`: [`JointKind::Revolute`]
+/// - `anchor`: `[-0.2, 0.]`
+/// - `body_anchor`: `[0.2, 0.]`
+#[config_derives]
+pub struct AttachedBodyConfig {
+    /// Name of the body, in the records.
+    pub name: String,
+    /// Collision shape of the body, in the body frame. A `Point` body does not collide.
+    #[check]
+    pub footprint: FootprintConfig,
+    /// Mass of the body, in kg.
+    pub mass: f32,
+    /// Joint with the robot.
+    pub joint: JointKind,
+    /// Position `[x, y]` of the joint on the robot, in the robot frame.
+    pub anchor: [f32; 2],
+    /// Position `[x, y]` of the joint on the body, in the body frame.
+    pub body_anchor: [f32; 2],
+}
+
+impl Default for AttachedBodyConfig {
+    fn default() -> Self {
+        Self {
+            name: "body".to_string(),
+            footprint: FootprintConfig::Circle(CircleFootprintConfig::default()),
+            mass: 1.,
+            joint: JointKind::default(),
+            anchor: [-0.2, 0.],
+            body_anchor: [0.2, 0.],
+        }
+    }
+}
+
+impl Check for AttachedBodyConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.mass <= 0. {
+            return Err(vec![format!(
+                "Mass of the attached body {} should be strictly positive, got {}",
+                self.name, self.mass
+            )]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for AttachedBodyConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut self.name);
+        });
+        self.footprint.show_mut(
+            ui,
+            ctx,
+            buffer_stack,
+            global_config,
+            current_node_name,
+            unique_id,
+        );
+        ui.horizontal(|ui| {
+            ui.label("Mass (kg):");
+            ui.add(
+                egui::DragValue::new(&mut self.mass)
+                    .speed(0.1)
+                    .range(0.001..=f32::MAX),
+            );
+        });
+        self.joint.show_mut(
+            ui,
+            ctx,
+            buffer_stack,
+            global_config,
+            current_node_name,
+            unique_id,
+        );
+        ui.horizontal(|ui| {
+            ui.label("Anchor on the robot:");
+            ui.add(egui::DragValue::new(&mut self.anchor[0]).speed(0.01));
+            ui.add(egui::DragValue::new(&mut self.anchor[1]).speed(0.01));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Anchor on the body:");
+            ui.add(egui::DragValue::new(&mut self.body_anchor[0]).speed(0.01));
+            ui.add(egui::DragValue::new(&mut self.body_anchor[1]).speed(0.01));
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.label(format!("Attached body {} ({} kg)", self.name, self.mass));
+        self.footprint.show(ui, ctx, unique_id);
+        self.joint.show(ui, ctx, unique_id);
+        ui.label(format!(
+            "Anchors: {:?} on the robot, {:?} on the body",
+            self.anchor, self.body_anchor
+        ));
+    }
+}
+
+/// Configuration of the [`RigidBodyPhysics`]. The collision shape of the robot is its footprint,
+/// which should be a `Circle` or a `Polygon`.
+///
+/// Default values:
+/// - `model`: [`RobotModelConfig::default`]
+/// - `initial_state`: [`StateConfig::default`]
+/// - `mass`: `10.`
+/// - `restitution`: `0.`
+/// - `friction`: `0.5`
+/// - `step`: `0.01`
+/// - `collide_with_map`: `true`
+/// - `attached_bodies`: empty vector
+#[config_derives]
+pub struct RigidBodyPhysicsConfig {
+    /// Robot model, giving the velocity requested by the commands.
+    #[check]
+    pub model: RobotModelConfig,
+    /// Starting state.
+    #[check]
+    pub initial_state: StateConfig,
+    /// Mass of the robot, in kg.
+    pub mass: f32,
+    /// Restitution coefficient of the contacts, from `0.` (no bounce) to `1.` (elastic).
+    pub restitution: f32,
+    /// Friction coefficient of the contacts.
+    pub friction: f32,
+    /// Maximal duration of an engine step, in seconds. Longer updates are split in equal steps.
+    pub step: f32,
+    /// The walls of the map (landmarks with a width and a height) are obstacles.
+    pub collide_with_map: bool,
+    /// Bodies attached to the robot with joints.
+    #[check]
+    pub attached_bodies: Vec<AttachedBodyConfig>,
+}
+
+impl Default for RigidBodyPhysicsConfig {
+    fn default() -> Self {
+        Self {
+            model: RobotModelConfig::default(),
+            initial_state: StateConfig::default(),
+            mass: 10.,
+            restitution: 0.,
+            friction: 0.5,
+            step: 0.01,
+            collide_with_map: true,
+            attached_bodies: Vec::new(),
+        }
+    }
+}
+
+impl Check for RigidBodyPhysicsConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.mass <= 0. {
+            errors.push(format!(
+                "Robot mass should be strictly positive, got {}",
+                self.mass
+            ));
+        }
+        if !(0. ..=1.).contains(&self.restitution) {
+            errors.push(format!(
+                "Restitution should be between 0 and 1, got {}",
+                self.restitution
+            ));
+        }
+        if self.friction < 0. {
+            errors.push(format!(
+                "Friction should be positive, got {}",
+                self.friction
+            ));
+        }
+        if self.step <= 0. {
+            errors.push(format!(
+                "Rigid body step should be strictly positive, got {}",
+                self.step
+            ));
+        }
+        #[cfg(not(feature = "rapier"))]
+        errors.push(
+            "RigidBody physics requires simba to be built with the `rapier` feature".to_string(),
+        );
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for RigidBodyPhysicsConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Rigid Body Physics")
+            .id_salt(format!("rigid-body-physics-{}", unique_id))
+            .show(ui, |ui| {
+                self.model.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Initial state:");
+                    self.initial_state.show_mut(
+                        ui,
+                        ctx,
+                        buffer_stack,
+                        global_config,
+                        current_node_name,
+                        unique_id,
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Mass (kg):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.mass)
+                            .speed(0.1)
+                            .range(0.001..=f32::MAX),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Restitution:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.restitution)
+                            .speed(0.01)
+                            .range(0. ..=1.),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Friction:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.friction)
+                            .speed(0.01)
+                            .range(0. ..=f32::MAX),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Step (s):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.step)
+                            .speed(0.001)
+                            .range(0.0001..=10.),
+                    );
+                });
+                ui.checkbox(&mut self.collide_with_map, "Collide with the map");
+
+                let mut to_remove = None;
+                for (i, body) in self.attached_bodies.iter_mut().enumerate() {
+                    let body_unique_id = format!("{unique_id}-attached-body-{i}");
+                    egui::CollapsingHeader::new(format!("Attached body {}", body.name))
+                        .id_salt(&body_unique_id)
+                        .show(ui, |ui| {
+                            body.show_mut(
+                                ui,
+                                ctx,
+                                buffer_stack,
+                                global_config,
+                                current_node_name,
+                                &body_unique_id,
+                            );
+                            if ui.button("Remove").clicked() {
+                                to_remove = Some(i);
+                            }
+                        });
+                }
+                if let Some(i) = to_remove {
+                    self.attached_bodies.remove(i);
+                }
+                if ui.button("Add attached body").clicked() {
+                    self.attached_bodies.push(AttachedBodyConfig::default());
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Rigid Body Physics")
+            .id_salt(format!("rigid-body-physics-{}", unique_id))
+            .show(ui, |ui| {
+                self.model.show(ui, ctx, unique_id);
+                ui.horizontal(|ui| {
+                    ui.label("Initial state:");
+                    self.initial_state.show(ui, ctx, unique_id);
+                });
+                ui.label(format!("Mass: {} kg", self.mass));
+                ui.label(format!("Restitution: {}", self.restitution));
+                ui.label(format!("Friction: {}", self.friction));
+                ui.label(format!("Step: {} s", self.step));
+                ui.label(format!("Collide with the map: {}", self.collide_with_map));
+                for (i, body) in self.attached_bodies.iter().enumerate() {
+                    body.show(ui, ctx, &format!("{unique_id}-attached-body-{i}"));
+                }
+            });
+    }
+}
+
+/// Pose of a body attached to the robot, in the [`RigidBodyPhysicsRecord`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttachedBodyRecord {
+    /// Name of the body.
+    pub name: String,
+    /// Pose `[x, y, orientation]` of the body.
+    pub pose: [f32; 3],
+}
+
+/// Record of the [`RigidBodyPhysics`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RigidBodyPhysicsRecord {
+    /// State at the time `last_time_update`.
+    pub state: StateRecord,
+    /// Time of the state.
+    pub last_time_update: f32,
+    /// Current command applied.
+    pub current_command: Command,
+    /// Poses of the attached bodies, in the order of the configuration.
+    pub attached_bodies: Vec<AttachedBodyRecord>,
+    /// The robot touches an obstacle.
+    pub in_contact: bool,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for RigidBodyPhysicsRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("State").show(ui, |ui| {
+                self.state.show(ui, ctx, unique_id);
+            });
+            egui::CollapsingHeader::new("Current command").show(ui, |ui| {
+                self.current_command.show(ui, ctx, unique_id);
+            });
+            for body in &self.attached_bodies {
+                ui.label(format!("{}: {:?}", body.name, body.pose));
+            }
+            ui.label(format!("In contact: {}", self.in_contact));
+        });
+    }
+}
+
+#[cfg(feature = "rapier")]
+pub use engine::RigidBodyPhysics;
+
+#[cfg(feature = "rapier")]
+mod engine {
+    use std::sync::Arc;
+
+    use rapier2d::prelude::{
+        CCDSolver, ColliderBuilder, ColliderHandle, ColliderSet, DefaultBroadPhase,
+        FixedJointBuilder, ImpulseJointSet, IntegrationParameters, IslandManager, Isometry,
+        MultibodyJointSet, NarrowPhase, PhysicsPipeline, RevoluteJointBuilder, RigidBodyBuilder,
+        RigidBodyHandle, RigidBodySet, point, vector,
+    };
+
+    use super::{
+        AttachedBodyConfig, AttachedBodyRecord, JointKind, RigidBodyPhysicsConfig,
+        RigidBodyPhysicsRecord,
+    };
+    use crate::{
+        environment::oriented_landmark::OrientedLandmark,
+        errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+        networking::service::HasService,
+        node::{
+            Node,
+            capabilities::{Capabilities, CommandKind},
+            footprint::FootprintConfig,
+        },
+        physics::{
+            GetRealStateReq, GetRealStateResp, Physics, PhysicsRecord,
+            robot_models::{Command, RobotModel, make_model_from_config},
+        },
+        recordable::Recordable,
+        state_estimators::State,
+        utils::determinist_random_variable::DeterministRandomVariableFactory,
+    };
+
+    /// Rapier world of one robot.
+    struct World {
+        pipeline: PhysicsPipeline,
+        parameters: IntegrationParameters,
+        islands: IslandManager,
+        broad_phase: DefaultBroadPhase,
+        narrow_phase: NarrowPhase,
+        bodies: RigidBodySet,
+        colliders: ColliderSet,
+        impulse_joints: ImpulseJointSet,
+        multibody_joints: MultibodyJointSet,
+        ccd_solver: CCDSolver,
+    }
+
+    impl World {
+        fn new() -> Self {
+            Self {
+                pipeline: PhysicsPipeline::new(),
+                parameters: IntegrationParameters::default(),
+                islands: IslandManager::new(),
+                broad_phase: DefaultBroadPhase::new(),
+                narrow_phase: NarrowPhase::new(),
+                bodies: RigidBodySet::new(),
+                colliders: ColliderSet::new(),
+                impulse_joints: ImpulseJointSet::new(),
+                multibody_joints: MultibodyJointSet::new(),
+                ccd_solver: CCDSolver::new(),
+            }
+        }
+
+        fn step(&mut self, dt: f32) {
+            self.parameters.dt = dt;
+            self.pipeline.step(
+                &vector![0., 0.],
+                &self.parameters,
+                &mut self.islands,
+                &mut self.broad_phase,
+                &mut self.narrow_phase,
+                &mut self.bodies,
+                &mut self.colliders,
+                &mut self.impulse_joints,
+                &mut self.multibody_joints,
+                &mut self.ccd_solver,
+                None,
+                &(),
+                &(),
+            );
+        }
+
+        fn pose(&self, handle: RigidBodyHandle) -> [f32; 3] {
+            let body = &self.bodies[handle];
+            [
+                body.translation().x,
+                body.translation().y,
+                body.rotation().angle(),
+            ]
+        }
+    }
+
+    impl std::fmt::Debug for World {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("World")
+                .field("bodies", &self.bodies.len())
+                .field("colliders", &self.colliders.len())
+                .finish_non_exhaustive()
+        }
+    }
+
+    /// Collider of the `footprint`, `None` for a `Point`.
+    fn footprint_collider(footprint: &FootprintConfig) -> Option<ColliderBuilder> {
+        match footprint {
+            FootprintConfig::Point => None,
+            FootprintConfig::Circle(c) => Some(ColliderBuilder::ball(c.radius)),
+            FootprintConfig::Polygon(c) => ColliderBuilder::convex_polyline(
+                c.points.iter().map(|[x, y]| point![*x, *y]).collect(),
+            ),
+        }
+    }
+
+    /// Rigid body [`Physics`] backed by Rapier, see the [module documentation](super).
+    #[derive(Debug)]
+    pub struct RigidBodyPhysics {
+        model: Box<dyn RobotModel>,
+        world: World,
+        robot: RigidBodyHandle,
+        robot_collider: ColliderHandle,
+        /// Handles and names of the attached bodies, in the order of the configuration.
+        attached_bodies: Vec<(String, RigidBodyHandle)>,
+        restitution: f32,
+        friction: f32,
+        step: f32,
+        collide_with_map: bool,
+        /// Time of the current state.
+        last_time_update: f32,
+        /// Current command applied.
+        current_command: Command,
+    }
+
+    impl RigidBodyPhysics {
+        /// Makes a new [`RigidBodyPhysics`] with the given configuration, for a robot with the
+        /// given `footprint`, which should be a `Circle` or a `Polygon`.
+        pub fn from_config(
+            config: &RigidBodyPhysicsConfig,
+            footprint: &FootprintConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            initial_time: f32,
+        ) -> SimbaResult<Self> {
+            let model = make_model_from_config(&config.model);
+            let current_command = model.default_command();
+            let initial_state = State::from_config(&config.initial_state, va_factory);
+            let mut world = World::new();
+
+            let robot_pose = Isometry::new(
+                vector![initial_state.pose.x, initial_state.pose.y],
+                initial_state.pose.z,
+            );
+            let robot = world.bodies.insert(
+                RigidBodyBuilder::dynamic()
+                    .position(robot_pose)
+                    .linvel(vector![initial_state.velocity.x, initial_state.velocity.y])
+                    .angvel(initial_state.velocity.z)
+                    .build(),
+            );
+            let robot_collider = footprint_collider(footprint).ok_or_else(|| {
+                SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    "RigidBody physics needs a Circle or a convex Polygon footprint".to_string(),
+                )
+            })?;
+            let robot_collider = world.colliders.insert_with_parent(
+                robot_collider
+                    .mass(config.mass)
+                    .restitution(config.restitution)
+                    .friction(config.friction),
+                robot,
+                &mut world.bodies,
+            );
+
+            let mut attached_bodies = Vec::new();
+            for body_config in &config.attached_bodies {
+                let handle = Self::attach_body(&mut world, robot, robot_pose, body_config, config)?;
+                attached_bodies.push((body_config.name.clone(), handle));
+            }
+
+            Ok(Self {
+                model,
+                world,
+                robot,
+                robot_collider,
+                attached_bodies,
+                restitution: config.restitution,
+                friction: config.friction,
+                step: config.step,
+                collide_with_map: config.collide_with_map,
+                last_time_update: initial_time,
+                current_command,
+            })
+        }
+
+        fn attach_body(
+            world: &mut World,
+            robot: RigidBodyHandle,
+            robot_pose: Isometry<f32>,
+            body_config: &AttachedBodyConfig,
+            config: &RigidBodyPhysicsConfig,
+        ) -> SimbaResult<RigidBodyHandle> {
+            // Same orientation as the robot, anchors at the same place
+            let [ax, ay] = body_config.anchor;
+            let [bx, by] = body_config.body_anchor;
+            let body_pose = robot_pose * Isometry::translation(ax - bx, ay - by);
+            let handle = world.bodies.insert(
+                RigidBodyBuilder::dynamic()
+                    .position(body_pose)
+                    .additional_mass(body_config.mass)
+                    .build(),
+            );
+            match &body_config.footprint {
+                FootprintConfig::Point => {}
+                footprint => {
+                    let collider = footprint_collider(footprint).ok_or_else(|| {
+                        SimbaError::new(
+                            SimbaErrorTypes::ConfigError,
+                            format!(
+                                "The footprint of the attached body {} should be a convex polygon",
+                                body_config.name
+                            ),
+                        )
+                    })?;
+                    world.colliders.insert_with_parent(
+                        collider
+                            .mass(0.)
+                            .restitution(config.restitution)
+                            .friction(config.friction),
+                        handle,
+                        &mut world.bodies,
+                    );
+                }
+            }
+            match body_config.joint {
+                JointKind::Revolute => world.impulse_joints.insert(
+                    robot,
+                    handle,
+                    RevoluteJointBuilder::new()
+                        .local_anchor1(point![ax, ay])
+                        .local_anchor2(point![bx, by])
+                        .contacts_enabled(false),
+                    true,
+                ),
+                JointKind::Fixed => world.impulse_joints.insert(
+                    robot,
+                    handle,
+                    FixedJointBuilder::new()
+                        .local_anchor1(point![ax, ay])
+                        .local_anchor2(point![bx, by])
+                        .contacts_enabled(false),
+                    true,
+                ),
+            };
+            Ok(handle)
+        }
+
+        /// Add the walls of the map as static obstacles: the landmarks with a width and a height.
+        pub fn add_obstacles(&mut self, landmarks: &[OrientedLandmark]) {
+            for landmark in landmarks.iter().filter(|l| l.width > 0. && l.height > 0.) {
+                let (a, b) = landmark.extremities();
+                self.world.colliders.insert(
+                    ColliderBuilder::segment(point![a.x, a.y], point![b.x, b.y])
+                        .restitution(self.restitution)
+                        .friction(self.friction),
+                );
+            }
+        }
+
+        /// Returns `true` if the robot touches an obstacle.
+        pub fn in_contact(&self) -> bool {
+            self.world
+                .narrow_phase
+                .contact_pairs_with(self.robot_collider)
+                .any(|pair| pair.has_any_active_contact)
+        }
+
+        /// Step the world until `time`, with the velocity requested by the current command.
+        fn compute_state_until(&mut self, time: f32) {
+            let dt = time - self.last_time_update;
+            assert!(
+                dt >= 0.,
+                "Physics delta time should be positive: {} - {} = {} >= 0",
+                time,
+                self.last_time_update,
+                dt
+            );
+            if dt == 0. {
+                return;
+            }
+
+            let steps = (dt / self.step).ceil().max(1.) as usize;
+            let step_dt = dt / steps as f32;
+            let velocity = self
+                .model
+                .body_velocity(&self.current_command)
+                .unwrap_or_else(|| {
+                    // Velocity reached by the model after one step from rest
+                    let mut state = State::new();
+                    self.model
+                        .update_state(&mut state, &self.current_command, step_dt);
+                    state.velocity
+                });
+            for _ in 0..steps {
+                let robot = &mut self.world.bodies[self.robot];
+                let (sin, cos) = robot.rotation().angle().sin_cos();
+                robot.set_linvel(
+                    vector![
+                        cos * velocity.x - sin * velocity.y,
+                        sin * velocity.x + cos * velocity.y
+                    ],
+                    true,
+                );
+                robot.set_angvel(velocity.z, true);
+                self.world.step(step_dt);
+            }
+            self.last_time_update = time;
+        }
+    }
+
+    impl Physics for RigidBodyPhysics {
+        fn post_init(&mut self, node: &mut Node) -> SimbaResult<()> {
+            if self.collide_with_map {
+                let environment = node.environment().clone();
+                self.add_obstacles(&environment.map().landmarks);
+            }
+            Ok(())
+        }
+
+        fn apply_command(&mut self, command: &Command, time: f32) {
+            self.compute_state_until(time);
+            self.current_command = command.clone();
+        }
+
+        fn update_state(&mut self, time: f32) {
+            self.compute_state_until(time);
+        }
+
+        /// State from the engine, with the velocity in the robot frame.
+        fn state(&self, _time: f32) -> State {
+            let robot = &self.world.bodies[self.robot];
+            let pose = self.world.pose(self.robot);
+            let (sin, cos) = pose[2].sin_cos();
+            let linvel = robot.linvel();
+            let mut state = State::new();
+            state.pose = pose.into();
+            state.velocity = [
+                cos * linvel.x + sin * linvel.y,
+                -sin * linvel.x + cos * linvel.y,
+                robot.angvel(),
+            ]
+            .into();
+            state
+        }
+
+        /// Accepts only the commands of its robot model.
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                commands: Some(vec![CommandKind::from(&self.model.default_command())]),
+                ..Default::default()
+            }
+        }
+
+        /// The world is first stepped until `time`, then the robot is moved. The attached bodies
+        /// follow the robot rigidly.
+        fn set_state(
+            &mut self,
+            pose: Option<[f32; 3]>,
+            velocity: Option<[f32; 3]>,
+            time: f32,
+        ) -> Result<(), String> {
+            self.compute_state_until(time);
+            if let Some([x, y, theta]) = pose {
+                let old_pose = *self.world.bodies[self.robot].position();
+                let new_pose = Isometry::new(vector![x, y], theta);
+                let displacement = new_pose * old_pose.inverse();
+                self.world.bodies[self.robot].set_position(new_pose, true);
+                for (_, handle) in &self.attached_bodies {
+                    let body = &mut self.world.bodies[*handle];
+                    let body_pose = displacement * body.position();
+                    body.set_position(body_pose, true);
+                }
+            }
+            if let Some([longitudinal, lateral, angular]) = velocity {
+                let robot = &mut self.world.bodies[self.robot];
+                let (sin, cos) = robot.rotation().angle().sin_cos();
+                robot.set_linvel(
+                    vector![
+                        cos * longitudinal - sin * lateral,
+                        sin * longitudinal + cos * lateral
+                    ],
+                    true,
+                );
+                robot.set_angvel(angular, true);
+            }
+            Ok(())
+        }
+    }
+
+    impl HasService<GetRealStateReq, GetRealStateResp> for RigidBodyPhysics {
+        fn handle_service_requests(
+            &mut self,
+            _req: GetRealStateReq,
+            time: f32,
+        ) -> Result<GetRealStateResp, String> {
+            Ok(GetRealStateResp {
+                state: self.state(time),
+            })
+        }
+    }
+
+    impl Recordable<PhysicsRecord> for RigidBodyPhysics {
+        fn record(&self) -> PhysicsRecord {
+            PhysicsRecord::RigidBody(RigidBodyPhysicsRecord {
+                state: self.state(self.last_time_update).record(),
+                last_time_update: self.last_time_update,
+                current_command: self.current_command.clone(),
+                attached_bodies: self
+                    .attached_bodies
+                    .iter()
+                    .map(|(name, handle)| AttachedBodyRecord {
+                        name: name.clone(),
+                        pose: self.world.pose(*handle),
+                    })
+                    .collect(),
+                in_contact: self.in_contact(),
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use nalgebra::Vector3;
+
+        use super::*;
+        use crate::{
+            node::footprint::CircleFootprintConfig,
+            physics::robot_models::unicycle::UnicycleCommand,
+        };
+
+        fn forward(speed: f32) -> Command {
+            Command::Unicycle(UnicycleCommand {
+                left_wheel_speed: speed,
+                right_wheel_speed: speed,
+            })
+        }
+
+        fn physics(config: &RigidBodyPhysicsConfig) -> RigidBodyPhysics {
+            let va_factory = Arc::new(DeterministRandomVariableFactory::new(0.));
+            RigidBodyPhysics::from_config(
+                config,
+                &FootprintConfig::Circle(CircleFootprintConfig { radius: 0.5 }),
+                &va_factory,
+                0.,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn wall_stops_the_robot() {
+            let mut physics = physics(&RigidBodyPhysicsConfig::default());
+            // Wall along y at x = 2
+            physics.add_obstacles(&[OrientedLandmark {
+                id: 0,
+                labels: Vec::new(),
+                pose: Vector3::new(2., 0., 0.),
+                height: 1.,
+                width: 4.,
+            }]);
+            physics.apply_command(&forward(1.), 0.);
+            physics.update_state(5.);
+            let state = physics.state(5.);
+            assert!(state.pose.x > 1.3, "{}", state.pose.x);
+            assert!(state.pose.x < 1.55, "{}", state.pose.x);
+            assert!(physics.in_contact());
+        }
+
+        #[test]
+        fn trailer_follows() {
+            let config = RigidBodyPhysicsConfig {
+                attached_bodies: vec![AttachedBodyConfig {
+                    anchor: [-0.6, 0.],
+                    body_anchor: [0.4, 0.],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            let mut physics = physics(&config);
+            physics.apply_command(&forward(1.), 0.);
+            physics.update_state(2.);
+            let PhysicsRecord::RigidBody(record) = physics.record() else {
+                panic!("Wrong record type");
+            };
+            let robot_x = record.state.pose[0];
+            let trailer = record.attached_bodies[0].pose;
+            assert!(robot_x > 1.);
+            assert!(
+                (robot_x - 1. - trailer[0]).abs() < 0.05,
+                "{robot_x} {trailer:?}"
+            );
+            assert!(trailer[1].abs() < 0.05);
+        }
+    }
+}
@@ -5,7 +5,7 @@
 
 use config_checker::*;
 use libm::atan2f;
-use nalgebra::SMatrix;
+use nalgebra::{SMatrix, SVector};
 use serde::{Deserialize, Serialize};
 use simba_macros::config_derives;
 
@@ -19,7 +19,7 @@ use crate::{
 /// Command struct, to control the robot using velocity in both directions.
 ///
 /// x is the axis oriented forward, y is the axis oriented to the left.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct HolonomicCommand {
     /// Commanded forward/backward velocity in the robot frame.
     pub longitudinal_velocity: f32,
@@ -229,6 +229,25 @@ impl RobotModel for Holonomic {
         state.velocity = [longitudinal_velocity, lateral_velocity, v_rotation].into();
     }
 
+    fn body_velocity(&self, command: &Command) -> Option<SVector<f32, 3>> {
+        let command = match command {
+            Command::Holonomic(cmd) => cmd,
+            _ => panic!("Holonomic robot model needs a Holonomic command"),
+        };
+        Some(SVector::<f32, 3>::new(
+            command.longitudinal_velocity.clamp(
+                -self.max_longitudinal_velocity,
+                self.max_longitudinal_velocity,
+            ),
+            command
+                .lateral_velocity
+                .clamp(-self.max_lateral_velocity, self.max_lateral_velocity),
+            command
+                .angular_velocity
+                .clamp(-self.max_angular_velocity, self.max_angular_velocity),
+        ))
+    }
+
     fn default_command(&self) -> Command {
         Command::Holonomic(HolonomicCommand {
             angular_velocity: 0.,
@@ -5,6 +5,7 @@
 
 use std::fmt::Debug;
 
+use nalgebra::SVector;
 use serde::{Deserialize, Serialize};
 use simba_macros::config_derives;
 
@@ -16,13 +17,14 @@ use crate::{
         unicycle::{Unicycle, UnicycleCommand, UnicycleConfig},
     },
     state_estimators::State,
+    utils::geometry::mod2pi,
 };
 
 pub mod holonomic;
 pub mod unicycle;
 
 /// Command enum, wrapping the command types of all supported robot models.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Command {
     /// Command payload for the unicycle model.
     Unicycle(UnicycleCommand),
@@ -138,10 +140,119 @@ pub trait RobotModel: std::fmt::Debug + std::marker::Send + std::marker::Sync {
     /// Updates the mutable robot state using the provided command and elapsed time.
     fn update_state(&mut self, previous_state: &mut State, command: &Command, delta_time: f32);
 
+    /// Returns the body velocity [longitudinal, lateral, angular] resulting from the command.
+    ///
+    /// The default implementation returns `None`: the model is then always integrated with
+    /// [`update_state`](RobotModel::update_state), as with [`IntegrationMethod::Exact`].
+    fn body_velocity(&self, _command: &Command) -> Option<SVector<f32, 3>> {
+        None
+    }
+
     /// Returns the neutral/default command for this model.
     fn default_command(&self) -> Command;
 }
 
+/// Numerical integration scheme of the robot model.
+///
+/// Default value: [`IntegrationMethod::Exact`].
+#[config_derives]
+#[derive(Copy)]
+pub enum IntegrationMethod {
+    /// Closed-form integration of the model, with a constant command (Lie exponential).
+    Exact,
+    /// First order integration: the velocity at the start of the step is applied on the whole step.
+    Euler,
+    /// Fourth order Runge-Kutta integration.
+    RK4,
+}
+
+impl Default for IntegrationMethod {
+    fn default() -> Self {
+        Self::Exact
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for IntegrationMethod {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        let mut current_str = self.to_string();
+        ui.horizontal(|ui| {
+            use crate::{gui::utils::string_combobox, utils::enum_tools::ToVec};
+
+            ui.label("Integration method:");
+            string_combobox(
+                ui,
+                &IntegrationMethod::to_vec(),
+                &mut current_str,
+                format!("integration-method-choice-{}", unique_id),
+            );
+        });
+        if current_str != self.to_string() {
+            *self = match current_str.as_str() {
+                "Exact" => IntegrationMethod::Exact,
+                "Euler" => IntegrationMethod::Euler,
+                "RK4" => IntegrationMethod::RK4,
+                _ => panic!("Where did you find this value?"),
+            };
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!("Integration method: {}", self));
+    }
+}
+
+/// Derivative of the pose [x, y, orientation] for a body velocity.
+fn pose_derivative(pose: &SVector<f32, 3>, velocity: &SVector<f32, 3>) -> SVector<f32, 3> {
+    let (s, c) = pose.z.sin_cos();
+    SVector::<f32, 3>::new(
+        c * velocity.x - s * velocity.y,
+        s * velocity.x + c * velocity.y,
+        velocity.z,
+    )
+}
+
+/// Integrates the `state` of the `model` on `delta_time`, with a constant `command`.
+pub fn integrate(
+    model: &mut dyn RobotModel,
+    integrator: IntegrationMethod,
+    state: &mut State,
+    command: &Command,
+    delta_time: f32,
+) {
+    let velocity = match (integrator, model.body_velocity(command)) {
+        (IntegrationMethod::Euler | IntegrationMethod::RK4, Some(velocity)) => velocity,
+        // Models without body velocity fall back to their own update
+        _ => {
+            model.update_state(state, command, delta_time);
+            return;
+        }
+    };
+    let pose = state.pose;
+    let increment = match integrator {
+        IntegrationMethod::RK4 => {
+            // The body velocity is constant on the step, only the orientation changes.
+            let k1 = pose_derivative(&pose, &velocity);
+            let k2 = pose_derivative(&(pose + delta_time / 2. * k1), &velocity);
+            let k3 = pose_derivative(&(pose + delta_time / 2. * k2), &velocity);
+            let k4 = pose_derivative(&(pose + delta_time * k3), &velocity);
+            delta_time / 6. * (k1 + 2. * k2 + 2. * k3 + k4)
+        }
+        _ => delta_time * pose_derivative(&pose, &velocity),
+    };
+    state.pose = pose + increment;
+    state.pose.z = mod2pi(state.pose.z);
+    state.velocity = velocity;
+}
+
 /// Instantiates a runtime robot model from [`RobotModelConfig`].
 pub fn make_model_from_config(config: &RobotModelConfig) -> Box<dyn RobotModel> {
     match config {
@@ -153,3 +264,88 @@ pub fn make_model_from_config(config: &RobotModelConfig) -> Box<dyn RobotModel>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sub_stepped_rk4_follows_exact_arc() {
+        let mut model = make_model_from_config(&RobotModelConfig::default());
+        let command = Command::Unicycle(UnicycleCommand {
+            left_wheel_speed: 0.5,
+            right_wheel_speed: 1.5,
+        });
+        let mut exact = State::new();
+        integrate(
+            model.as_mut(),
+            IntegrationMethod::Exact,
+            &mut exact,
+            &command,
+            2.,
+        );
+
+        let mut single_euler = State::new();
+        integrate(
+            model.as_mut(),
+            IntegrationMethod::Euler,
+            &mut single_euler,
+            &command,
+            2.,
+        );
+        let mut rk4 = State::new();
+        for _ in 0..20 {
+            integrate(
+                model.as_mut(),
+                IntegrationMethod::RK4,
+                &mut rk4,
+                &command,
+                0.1,
+            );
+        }
+        assert!((rk4.pose - exact.pose).norm() < 1e-3);
+        assert!((single_euler.pose - exact.pose).norm() > 0.1);
+        assert_eq!(rk4.velocity, exact.velocity);
+    }
+
+    /// Model giving only its `update_state`.
+    #[derive(Debug)]
+    struct UpdateOnlyModel(Unicycle);
+
+    impl RobotModel for UpdateOnlyModel {
+        fn update_state(&mut self, previous_state: &mut State, command: &Command, delta_time: f32) {
+            self.0.update_state(previous_state, command, delta_time);
+        }
+
+        fn default_command(&self) -> Command {
+            self.0.default_command()
+        }
+    }
+
+    #[test]
+    fn model_without_body_velocity_uses_its_update() {
+        let mut model = UpdateOnlyModel(Unicycle::from_config(&UnicycleConfig::default()));
+        let command = Command::Unicycle(UnicycleCommand {
+            left_wheel_speed: 0.5,
+            right_wheel_speed: 1.5,
+        });
+        let mut exact = State::new();
+        integrate(
+            &mut model,
+            IntegrationMethod::Exact,
+            &mut exact,
+            &command,
+            2.,
+        );
+        let mut euler = State::new();
+        integrate(
+            &mut model,
+            IntegrationMethod::Euler,
+            &mut euler,
+            &command,
+            2.,
+        );
+        assert_eq!(euler.pose, exact.pose);
+        assert_eq!(euler.velocity, exact.velocity);
+    }
+}
@@ -4,7 +4,7 @@
 //! [`RobotModel`], including its command type and configuration.
 
 use libm::atan2f;
-use nalgebra::SMatrix;
+use nalgebra::{SMatrix, SVector};
 use serde::{Deserialize, Serialize};
 use simba_macros::config_derives;
 
@@ -16,7 +16,7 @@ use crate::{
 };
 
 /// Command struct, to control both wheel speed, in m/s.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct UnicycleCommand {
     /// Left wheel speed.
     pub left_wheel_speed: f32,
@@ -183,6 +183,18 @@ impl RobotModel for Unicycle {
         state.velocity.z = v_rotation;
     }
 
+    fn body_velocity(&self, command: &Command) -> Option<SVector<f32, 3>> {
+        let command = match command {
+            Command::Unicycle(cmd) => cmd,
+            _ => panic!("Unicycle robot model needs a Unicycle command"),
+        };
+        Some(SVector::<f32, 3>::new(
+            (command.left_wheel_speed + command.right_wheel_speed) / 2.,
+            0.,
+            (command.right_wheel_speed - command.left_wheel_speed) / self.wheel_distance,
+        ))
+    }
+
     fn default_command(&self) -> Command {
         Command::Unicycle(UnicycleCommand {
             left_wheel_speed: 0.,
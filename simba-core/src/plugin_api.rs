@@ -14,10 +14,10 @@ impl PluginAPI for MyPlugin {
         config: &serde_json::Value,
         global_config: &SimulatorConfig,
     ) -> Box<dyn StateEstimator> {
-        Box::new(MyFilter::from_config(
-            &serde_json::from_value(config.value.clone())
-                .expect("Error during parsing MyFilter config"),
-        ))
+        // `plugin_config_error` gives the location of the config in the file
+        let my_config = serde_json::from_value(config.clone())
+            .unwrap_or_else(|e| panic!("{}", global_config.plugin_config_error(config, e)));
+        Box::new(MyFilter::from_config(&my_config))
     }
 }
 // You should use the simulator as a library. Your main.rs could be:
@@ -47,8 +47,11 @@ use crate::{
     controllers::Controller,
     navigators::Navigator,
     networking::network::Network,
-    physics::Physics,
-    sensors::{Sensor, fault_models::fault_model::FaultModel, sensor_filters::SensorFilter},
+    physics::{Physics, cosimulation::PhysicsEngine},
+    sensors::{
+        Sensor, fault_models::fault_model::FaultModel, observation_types::ObservationSchema,
+        sensor_filters::SensorFilter,
+    },
     simulator::SimulatorConfig,
     state_estimators::StateEstimator,
     utils::{SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory},
@@ -149,6 +152,37 @@ pub trait PluginAPI: Send + Sync {
         panic!("The given PluginAPI does not provide physics");
     }
 
+    /// Return the [`PhysicsEngine`] to be stepped by the
+    /// [`CoSimulatedPhysics`](crate::physics::cosimulation::CoSimulatedPhysics), for the
+    /// `External` engines of the co-simulation.
+    ///
+    /// # Arguments
+    /// * `config` - Config of the engine, given using [`serde_json::Value`].
+    /// * `global_config` - Full configuration of the simulator.
+    ///
+    /// # Return
+    ///
+    /// Returns the [`PhysicsEngine`] to use.
+    fn get_physics_engine(
+        &self,
+        config: &serde_json::Value,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        initial_time: f32,
+    ) -> Box<dyn PhysicsEngine> {
+        panic!("The given PluginAPI does not provide physics engines");
+    }
+
+    /// Return the observation types of the plugin sensors.
+    ///
+    /// The simulator registers them when the configuration is loaded, so that the
+    /// [`ExternalObservation`](crate::sensors::external_sensor::ExternalObservation)s of these
+    /// types are validated and can be decoded by the state estimators and shown by the GUI. See
+    /// [`observation_types`](crate::sensors::observation_types).
+    fn observation_types(&self) -> Vec<ObservationSchema> {
+        Vec::new()
+    }
+
     /// Allow the plugin to check for requests from the simulator and react to them.
     /// This is used at the configuration loading step to allow asynchronous plugins to check for requests, especially [`PythonAPI`](crate::pybinds::PythonAPI).
     fn check_requests(&self) {}
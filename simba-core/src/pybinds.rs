@@ -1,14 +1,22 @@
 //! Python bindings for Simba components, including a plugin API bridge backed and the root Python module.
-use std::sync::{Arc, Mutex};
+//!
+//! It also provides [`GymEnv`], a reinforcement learning environment driving one robot.
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, mpsc},
+    time::Duration,
+};
 
 use log::debug;
-use pyo3::prelude::*;
+use pyo3::{prelude::*, types::PyDict};
 
 use crate::{
     controllers::{
-        Controller,
+        Controller, ControllerConfig, ControllerError, ControllerRecord,
+        external_controller::{ExternalControllerConfig, ExternalControllerRecord},
         pybinds::{ControllerWrapper, PythonController},
     },
+    errors::SimbaResult,
     logger::is_enabled,
     navigators::{
         Navigator,
@@ -18,27 +26,37 @@ use crate::{
     networking::{
         MessageTypes,
         network::{MessageFlag, Network},
+        reconfigure::ReconfigureMessage,
     },
+    node::Node,
     physics::{
         Physics,
         pybinds::{PhysicsWrapper, PythonPhysics},
+        robot_models::{Command, holonomic::HolonomicCommand},
     },
     plugin_api::PluginAPI,
     pywrappers::{
-        CommandWrapper, ControllerErrorWrapper, DisplacementObservationWrapper,
-        GNSSObservationWrapper, MultiClientWrapper, NodeWrapper, ObservationWrapper,
+        BearingObservationWrapper, CommandWrapper, ConfigPatchHandleWrapper,
+        ControllerErrorWrapper, DisplacementObservationWrapper, GNSSObservationWrapper,
+        ImuObservationWrapper, LandmarkWrapper, MetaDataWatcher, MultiClientWrapper,
+        NodeMetaDataWrapper, NodeWrapper, ObservationWrapper, OccupancyGridWrapper,
         OrientedLandmarkObservationWrapper, OrientedRobotObservationWrapper, PluginAPIWrapper,
-        Pose, SensorObservationWrapper, SimulatorWrapper, SpeedObservationWrapper, StateWrapper,
-        UnicycleCommandWrapper, Vec2, Vec3, WorldStateWrapper, run_gui,
+        Pose, RunControlWrapper, SensorObservationWrapper, SimulatorWrapper,
+        SpeedObservationWrapper, StateWrapper, UnicycleCommandWrapper, Vec2, Vec3,
+        WorldStateWrapper, run_gui,
     },
+    recordable::Recordable,
     sensors::sensor_manager::SensorTriggerMessage,
-    simulator::SimulatorConfig,
+    simulator::{AsyncSimulator, SimulatorConfig},
     state_estimators::{
         StateEstimator,
+        map_merging::MapShareMessage,
         pybinds::{PythonStateEstimator, StateEstimatorWrapper},
     },
     utils::{
-        SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory,
+        SharedRwLock,
+        determinist_random_variable::DeterministRandomVariableFactory,
+        lock_recovery::{RecoverMutex, RecoverRwLock},
         python::call_py_method,
     },
 };
@@ -46,6 +64,8 @@ use crate::{
 /// Create Python bindings for Simba components and add them to the provided Python module.
 pub fn make_python_bindings(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<SimulatorWrapper>()?;
+    m.add_class::<RunControlWrapper>()?;
+    m.add_class::<ConfigPatchHandleWrapper>()?;
     m.add_class::<PluginAPIWrapper>()?;
     m.add_class::<ControllerErrorWrapper>()?;
     m.add_class::<PhysicsWrapper>()?;
@@ -54,12 +74,15 @@ pub fn make_python_bindings(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Vec2>()?;
     m.add_class::<Vec3>()?;
     m.add_class::<WorldStateWrapper>()?;
+    m.add_class::<OccupancyGridWrapper>()?;
     m.add_class::<StateEstimatorWrapper>()?;
     m.add_class::<ObservationWrapper>()?;
     m.add_class::<SensorObservationWrapper>()?;
     m.add_class::<GNSSObservationWrapper>()?;
     m.add_class::<SpeedObservationWrapper>()?;
     m.add_class::<DisplacementObservationWrapper>()?;
+    m.add_class::<ImuObservationWrapper>()?;
+    m.add_class::<BearingObservationWrapper>()?;
     m.add_class::<OrientedLandmarkObservationWrapper>()?;
     m.add_class::<OrientedRobotObservationWrapper>()?;
     m.add_class::<ControllerWrapper>()?;
@@ -67,11 +90,18 @@ pub fn make_python_bindings(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<UnicycleCommandWrapper>()?;
     m.add_class::<NavigatorWrapper>()?;
     m.add_class::<NodeWrapper>()?;
+    m.add_class::<NodeMetaDataWrapper>()?;
+    m.add_class::<LandmarkWrapper>()?;
+    m.add_class::<MetaDataWatcher>()?;
     m.add_class::<MultiClientWrapper>()?;
     m.add_class::<MessageFlag>()?;
     m.add_class::<MessageTypes>()?;
     m.add_class::<GoToMessage>()?;
     m.add_class::<SensorTriggerMessage>()?;
+    m.add_class::<ReconfigureMessage>()?;
+    m.add_class::<MapShareMessage>()?;
+    m.add_class::<GymEnv>()?;
+    m.add_class::<GymObservation>()?;
     m.add_function(wrap_pyfunction!(run_gui, m)?)?;
     Ok(())
 }
@@ -251,3 +281,464 @@ impl PluginAPI for PythonAPI {
         st
     }
 }
+
+/// Key of the [`ExternalControllerConfig`] given to the agent robot of a [`GymEnv`].
+const GYM_CONTROLLER_KEY: &str = "gym_env";
+
+/// Observation of the agent robot of a [`GymEnv`], taken at each of its decisions.
+#[derive(Clone, Debug)]
+#[pyclass(get_all)]
+pub struct GymObservation {
+    /// Time of the decision.
+    pub time: f32,
+    /// Agent robot, to use its network or the meta-data of the fleet.
+    pub node: NodeWrapper,
+    /// World state estimated by the main state estimator of the robot.
+    pub world_state: WorldStateWrapper,
+    /// True state of the robot, given by its physics.
+    pub true_state: StateWrapper,
+    /// Error computed by the navigator of the robot.
+    pub error: ControllerErrorWrapper,
+}
+
+/// Controller of the agent robot of a [`GymEnv`]: at each control step, it sends the
+/// observation to the environment and waits for the action.
+#[derive(Debug)]
+struct GymController {
+    observations: mpsc::Sender<GymObservation>,
+    actions: Mutex<mpsc::Receiver<Command>>,
+    last_command: Option<Command>,
+}
+
+impl GymController {
+    /// Command stopping the robot, used once the environment closed the episode.
+    fn stop_command(&self) -> Command {
+        match self.last_command {
+            Some(Command::Holonomic(_)) => Command::Holonomic(HolonomicCommand {
+                longitudinal_velocity: 0.,
+                lateral_velocity: 0.,
+                angular_velocity: 0.,
+            }),
+            _ => Command::default(),
+        }
+    }
+}
+
+impl Controller for GymController {
+    fn make_command(&mut self, node: &mut Node, error: &ControllerError, time: f32) -> Command {
+        let observation = GymObservation {
+            time,
+            node: NodeWrapper::from_rust(node),
+            // The robots always have a state estimator and a physics
+            world_state: WorldStateWrapper::from_rust(
+                &node
+                    .state_estimator()
+                    .map(|state_estimator| state_estimator.read_recover().world_state())
+                    .unwrap_or_default(),
+            ),
+            true_state: StateWrapper::from_rust(
+                &node
+                    .physics()
+                    .map(|physics| physics.read_recover().state(time))
+                    .unwrap_or_default(),
+            ),
+            error: ControllerErrorWrapper::from_rust(error),
+        };
+        let action = match self.observations.send(observation) {
+            Ok(()) => self.actions.lock_recover().recv().ok(),
+            Err(_) => None,
+        };
+        let command = action.unwrap_or_else(|| self.stop_command());
+        self.last_command = Some(command.clone());
+        command
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+}
+
+impl Recordable<ControllerRecord> for GymController {
+    fn record(&self) -> ControllerRecord {
+        ControllerRecord::External(ExternalControllerRecord {
+            record: serde_json::to_value(&self.last_command).unwrap_or_default(),
+        })
+    }
+}
+
+/// Plugin API of a [`GymEnv`] episode: it provides the [`GymController`] of the agent robot and
+/// forwards the other modules to the user plugin API.
+struct GymPluginAPI {
+    plugin_api: Option<Arc<dyn PluginAPI>>,
+    controller: Mutex<Option<GymController>>,
+}
+
+impl GymPluginAPI {
+    /// User plugin API, whose presence is checked by [`GymEnv::new`] for the configurations with
+    /// external modules.
+    fn plugin_api(&self) -> &Arc<dyn PluginAPI> {
+        self.plugin_api
+            .as_ref()
+            .expect("GymEnv::new checks the plugin API of the external modules")
+    }
+}
+
+/// Whether the `config` has external modules, which need a plugin API. The controller of the
+/// agent robot is not one of them.
+fn has_external_modules(config: &serde_json::Value) -> bool {
+    match config {
+        serde_json::Value::Object(map) => {
+            let is_agent = map
+                .get("config")
+                .is_some_and(|config| config.get(GYM_CONTROLLER_KEY).is_some());
+            (map.get("type").is_some_and(|kind| kind == "External") && !is_agent)
+                || map.values().any(has_external_modules)
+        }
+        serde_json::Value::Array(list) => list.iter().any(has_external_modules),
+        _ => false,
+    }
+}
+
+impl PluginAPI for GymPluginAPI {
+    fn get_state_estimator(
+        &self,
+        config: &serde_json::Value,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> Box<dyn StateEstimator> {
+        self.plugin_api().get_state_estimator(
+            config,
+            global_config,
+            va_factory,
+            network,
+            initial_time,
+        )
+    }
+
+    fn get_controller(
+        &self,
+        config: &serde_json::Value,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> Box<dyn Controller> {
+        if config.get(GYM_CONTROLLER_KEY).is_some() {
+            return Box::new(
+                self.controller
+                    .lock_recover()
+                    .take()
+                    .expect("The GymEnv controller was already given"),
+            );
+        }
+        self.plugin_api()
+            .get_controller(config, global_config, va_factory, network, initial_time)
+    }
+
+    fn get_navigator(
+        &self,
+        config: &serde_json::Value,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> Box<dyn Navigator> {
+        self.plugin_api()
+            .get_navigator(config, global_config, va_factory, network, initial_time)
+    }
+
+    fn get_physics(
+        &self,
+        config: &serde_json::Value,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> Box<dyn Physics> {
+        self.plugin_api()
+            .get_physics(config, global_config, va_factory, network, initial_time)
+    }
+
+    fn check_requests(&self) {
+        if let Some(plugin_api) = &self.plugin_api {
+            plugin_api.check_requests();
+        }
+    }
+}
+
+/// Running episode of a [`GymEnv`].
+struct GymEpisode {
+    simulator: AsyncSimulator,
+    plugin_api: Arc<dyn PluginAPI>,
+    observations: Mutex<mpsc::Receiver<GymObservation>>,
+    /// Sender of the actions, dropped to release the agent robot.
+    actions: Option<mpsc::Sender<Command>>,
+    /// Result of the run, once it ended.
+    run_result: Option<SimbaResult<()>>,
+}
+
+impl GymEpisode {
+    /// Period of the servicing of the plugin requests and of the Python signals while waiting for
+    /// the simulation.
+    const POLL_PERIOD: Duration = Duration::from_millis(10);
+
+    /// Wait for the next decision of the agent robot, servicing the plugin requests. The GIL is
+    /// released while waiting, for the Python modules of the simulation.
+    ///
+    /// Returns `None` when the robot does not decide anymore: the run ended or the robot was killed.
+    fn next_observation(&mut self, py: Python<'_>) -> PyResult<Option<GymObservation>> {
+        loop {
+            let observations = &self.observations;
+            match py.detach(|| observations.lock_recover().recv_timeout(Self::POLL_PERIOD)) {
+                Ok(observation) => return Ok(Some(observation)),
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(None),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+            if self.run_result.is_none() {
+                self.run_result = self.simulator.try_run_result();
+            }
+            if self.run_result.is_some() {
+                return Ok(None);
+            }
+            self.plugin_api.check_requests();
+            py.check_signals()?;
+        }
+    }
+
+    /// Release the agent robot, which stops for the rest of the run, and wait for the end of the
+    /// run.
+    fn finish(mut self, py: Python<'_>) -> PyResult<()> {
+        self.actions = None;
+        while self.run_result.is_none() {
+            // The released robot does not wait for the actions anymore
+            let observations = &self.observations;
+            py.detach(|| {
+                let _ = observations.lock_recover().recv_timeout(Self::POLL_PERIOD);
+            });
+            self.run_result = self.simulator.try_run_result();
+            self.plugin_api.check_requests();
+            py.check_signals()?;
+        }
+        self.simulator.stop();
+        match self.run_result {
+            Some(Err(e)) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "Simulation failed: {}",
+                e.detailed_error()
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Reinforcement learning environment with the [Gymnasium](https://gymnasium.farama.org) semantics.
+///
+/// The controller of the agent robot is replaced by the agent: at each control step of the robot
+/// (the decision times), [`GymEnv::step`] applies the action [`Command`] given for the previous
+/// decision and returns the [`GymObservation`] of the new one, with the reward computed by the
+/// user callback.
+///
+/// Each episode is a new run of the configuration.
+#[pyclass]
+#[pyo3(name = "GymEnv")]
+pub struct GymEnv {
+    config: SimulatorConfig,
+    robot: String,
+    reward: Py<PyAny>,
+    terminated: Option<Py<PyAny>>,
+    max_time: Option<f32>,
+    plugin_api: Option<Arc<dyn PluginAPI>>,
+    episode: Option<GymEpisode>,
+    last_observation: Option<GymObservation>,
+}
+
+#[pymethods]
+impl GymEnv {
+    /// Create the environment from a configuration file.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path to the configuration file of the simulator.
+    /// * `robot` - Name of the agent robot, whose controller is replaced by the agent.
+    /// * `reward` - Callable computing the reward from a [`GymObservation`].
+    /// * `terminated` - Optional callable returning `True` when a [`GymObservation`] ends the episode.
+    /// * `max_time` - Maximal time of the episodes, the `max_time` of the configuration by default.
+    /// * `plugin_api` - Optional Python plugin API, as for [`Simulator`](crate::pywrappers::SimulatorWrapper).
+    #[new]
+    #[pyo3(signature = (config_path, robot, reward, terminated=None, max_time=None, plugin_api=None))]
+    pub fn new(
+        config_path: String,
+        robot: String,
+        reward: Py<PyAny>,
+        terminated: Option<Py<PyAny>>,
+        max_time: Option<f32>,
+        plugin_api: Option<Py<PyAny>>,
+    ) -> PyResult<GymEnv> {
+        let mut config = SimulatorConfig::load_from_path(Path::new(&config_path)).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "Failed to load the config: {}",
+                e.detailed_error()
+            ))
+        })?;
+        let robot_config = config
+            .robots
+            .iter_mut()
+            .find(|robot_config| robot_config.name == robot)
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "No robot named '{robot}' in the config"
+                ))
+            })?;
+        robot_config.controller = ControllerConfig::External(ExternalControllerConfig {
+            config: serde_json::json!({ GYM_CONTROLLER_KEY: robot }),
+        });
+        let plugin_api = plugin_api.map(|api| Arc::new(PythonAPI::new(api)) as Arc<dyn PluginAPI>);
+        if plugin_api.is_none()
+            && serde_json::to_value(&config).is_ok_and(|value| has_external_modules(&value))
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "The configuration has external modules: give a plugin_api to the GymEnv",
+            ));
+        }
+        Ok(GymEnv {
+            config,
+            robot,
+            reward,
+            terminated,
+            max_time,
+            plugin_api,
+            episode: None,
+            last_observation: None,
+        })
+    }
+
+    /// Start a new episode, ending the current one, and return the first observation with an
+    /// info dictionary: `(observation, info)`.
+    pub fn reset(&mut self, py: Python<'_>) -> PyResult<(GymObservation, Py<PyDict>)> {
+        self.close(py)?;
+        let (observations_tx, observations_rx) = mpsc::channel();
+        let (actions_tx, actions_rx) = mpsc::channel();
+        let plugin_api: Arc<dyn PluginAPI> = Arc::new(GymPluginAPI {
+            plugin_api: self.plugin_api.clone(),
+            controller: Mutex::new(Some(GymController {
+                observations: observations_tx,
+                actions: Mutex::new(actions_rx),
+                last_command: None,
+            })),
+        });
+        let simulator = AsyncSimulator::from_config(self.config.clone(), &Some(plugin_api.clone()))
+            .map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                    "Failed to create simulator from config: {}",
+                    e.detailed_error()
+                ))
+            })?;
+        simulator.start_run(self.max_time, false);
+        let mut episode = GymEpisode {
+            simulator,
+            plugin_api,
+            observations: Mutex::new(observations_rx),
+            actions: Some(actions_tx),
+            run_result: None,
+        };
+        let observation = episode.next_observation(py)?;
+        self.episode = Some(episode);
+        let observation = observation.ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "The robot '{}' took no decision during the run",
+                self.robot
+            ))
+        })?;
+        self.last_observation = Some(observation.clone());
+        let info = Self::info(py, &observation)?;
+        Ok((observation, info))
+    }
+
+    /// Apply the `action` for the current decision and advance the simulation to the next
+    /// decision of the robot.
+    ///
+    /// Returns `(observation, reward, terminated, truncated, info)`. The episode is truncated when
+    /// the robot does not decide anymore (end of the run): the last observation is then given
+    /// again, with a zero reward.
+    pub fn step(
+        &mut self,
+        py: Python<'_>,
+        action: CommandWrapper,
+    ) -> PyResult<(GymObservation, f32, bool, bool, Py<PyDict>)> {
+        let episode = self.episode.as_mut().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "No running episode, call reset() first",
+            )
+        })?;
+        let last_observation = self.last_observation.clone().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "No observation in the running episode, call reset() first",
+            )
+        })?;
+        let sent = episode
+            .actions
+            .as_ref()
+            .is_some_and(|actions| actions.send(action.to_rust()).is_ok());
+        let observation = if sent {
+            episode.next_observation(py)?
+        } else {
+            None
+        };
+        let Some(observation) = observation else {
+            episode.actions = None;
+            let info = Self::info(py, &last_observation)?;
+            return Ok((last_observation, 0., false, true, info));
+        };
+        let reward = self
+            .reward
+            .call1(py, (observation.clone(),))?
+            .extract::<f32>(py)?;
+        let terminated = match &self.terminated {
+            Some(terminated) => terminated
+                .call1(py, (observation.clone(),))?
+                .extract::<bool>(py)?,
+            None => false,
+        };
+        self.last_observation = Some(observation.clone());
+        let info = Self::info(py, &observation)?;
+        Ok((observation, reward, terminated, false, info))
+    }
+
+    /// End the current episode, if any: the robot stops until the end of the run.
+    pub fn close(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.last_observation = None;
+        match self.episode.take() {
+            Some(episode) => episode.finish(py),
+            None => Ok(()),
+        }
+    }
+}
+
+impl GymEnv {
+    fn info(py: Python<'_>, observation: &GymObservation) -> PyResult<Py<PyDict>> {
+        let info = PyDict::new(py);
+        info.set_item("time", observation.time)?;
+        Ok(info.unbind())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn external_modules_need_a_plugin_api() {
+        let agent = serde_json::json!({
+            "robots": [{
+                "controller": {"type": "External", "config": {GYM_CONTROLLER_KEY: "robot1"}},
+                "navigator": {"type": "GoTo"},
+            }]
+        });
+        assert!(!has_external_modules(&agent));
+        let external = serde_json::json!({
+            "robots": [
+                {"controller": {"type": "External", "config": {GYM_CONTROLLER_KEY: "robot1"}}},
+                {"navigator": {"type": "External", "config": {}}},
+            ]
+        });
+        assert!(has_external_modules(&external));
+    }
+}
@@ -12,9 +12,9 @@
 //! - a default constructor for easy Python-side instantiation.
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     str::FromStr,
-    sync::{Arc, RwLock, Weak},
+    sync::{Arc, Mutex, RwLock, Weak},
 };
 
 use nalgebra::{SVector, Vector2, Vector3};
@@ -30,26 +30,42 @@ use crate::api::async_api::PluginAsyncAPI;
 
 use crate::{
     controllers::{ControllerError, pybinds::ControllerWrapper},
+    environment::{Environment, oriented_landmark::OrientedLandmark},
     navigators::pybinds::NavigatorWrapper,
     networking::{
         MessageTypes,
         network::{Envelope, MessageFlag, Network},
     },
-    node::Node,
+    node::{Node, NodeMetaData, capabilities::ObservationKind},
     physics::{
         pybinds::PhysicsWrapper,
         robot_models::{Command, holonomic::HolonomicCommand, unicycle::UnicycleCommand},
     },
     plugin_api::PluginAPI,
     pybinds::PythonAPI,
+    scenario::config::ConfigPatch,
     sensors::{
-        Observation, SensorObservation, displacement_sensor::DisplacementObservation,
-        gnss_sensor::GNSSObservation, oriented_landmark_sensor::OrientedLandmarkObservation,
-        robot_sensor::OrientedRobotObservation, speed_sensor::SpeedObservation,
+        Observation, SensorObservation,
+        bearing_sensor::{BearingObservation, BearingTarget},
+        displacement_sensor::DisplacementObservation,
+        gnss_sensor::GNSSObservation,
+        imu_sensor::ImuObservation,
+        observation_frame::ObservationFrame,
+        oriented_landmark_sensor::OrientedLandmarkObservation,
+        robot_sensor::OrientedRobotObservation,
+        speed_sensor::SpeedObservation,
+    },
+    simulator::{
+        AnnotationLog, AsyncSimulator, ConfigPatchHandle, RunControl, SimbaBrokerMultiClient,
+        Simulator,
     },
-    simulator::{AsyncSimulator, SimbaBrokerMultiClient, Simulator},
     state_estimators::{State, WorldState, pybinds::StateEstimatorWrapper},
-    utils::occupancy_grid::OccupancyGrid,
+    utils::{
+        SharedRoLock,
+        lock_recovery::{RecoverMutex, RecoverRoLock},
+        occupancy_grid::OccupancyGrid,
+        read_only_lock::RoLock,
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -672,6 +688,135 @@ impl Default for OrientedRobotObservationWrapper {
     }
 }
 
+#[derive(Clone, Debug)]
+#[pyclass(get_all, set_all)]
+#[pyo3(name = "ImuObservation")]
+/// Python wrapper around IMU observation values.
+pub struct ImuObservationWrapper {
+    /// Angular rate.
+    pub angular_velocity: f32,
+    /// Forward linear acceleration.
+    pub longitudinal_acceleration: f32,
+    /// Lateral linear acceleration.
+    pub lateral_acceleration: f32,
+    /// Applied faults in JSON format
+    pub applied_faults: String,
+}
+
+#[pymethods]
+impl ImuObservationWrapper {
+    /// Create a default IMU observation.
+    #[new]
+    pub fn new() -> Self {
+        Self {
+            angular_velocity: 0.,
+            longitudinal_acceleration: 0.,
+            lateral_acceleration: 0.,
+            applied_faults: "[]".to_string(),
+        }
+    }
+}
+
+impl ImuObservationWrapper {
+    /// Convert from the Rust [`ImuObservation`] type.
+    pub fn from_rust(s: &ImuObservation) -> Self {
+        Self {
+            angular_velocity: s.angular_velocity,
+            longitudinal_acceleration: s.longitudinal_acceleration,
+            lateral_acceleration: s.lateral_acceleration,
+            applied_faults: serde_json::to_string(&s.applied_faults).unwrap(),
+        }
+    }
+    /// Convert this wrapper to the Rust [`ImuObservation`] type.
+    pub fn to_rust(&self) -> ImuObservation {
+        ImuObservation {
+            angular_velocity: self.angular_velocity,
+            longitudinal_acceleration: self.longitudinal_acceleration,
+            lateral_acceleration: self.lateral_acceleration,
+            applied_faults: serde_json::from_str(&self.applied_faults).unwrap(),
+        }
+    }
+}
+
+impl Default for ImuObservationWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug)]
+#[pyclass(get_all, set_all)]
+#[pyo3(name = "BearingObservation")]
+/// Python wrapper around bearing-only observation values.
+pub struct BearingObservationWrapper {
+    /// Id of the landmark or name of the robot.
+    pub id: String,
+    /// Kind of the observed target: "Landmark" or "Robot".
+    pub target: String,
+    /// Labels associated with the observed target.
+    pub labels: Vec<String>,
+    /// Bearing of the target in the robot frame.
+    pub bearing: f32,
+    /// Elevation of the target, if measured.
+    pub elevation: Option<f32>,
+    /// Applied faults in JSON format
+    pub applied_faults: String,
+}
+
+#[pymethods]
+impl BearingObservationWrapper {
+    /// Create a default bearing observation.
+    #[new]
+    pub fn new() -> Self {
+        Self {
+            id: "NoName".to_string(),
+            target: "Landmark".to_string(),
+            labels: Vec::new(),
+            bearing: 0.,
+            elevation: None,
+            applied_faults: "[]".to_string(),
+        }
+    }
+}
+
+impl BearingObservationWrapper {
+    /// Convert from the Rust [`BearingObservation`] type.
+    pub fn from_rust(s: &BearingObservation) -> Self {
+        Self {
+            id: s.id.clone(),
+            target: match s.target {
+                BearingTarget::Landmark => "Landmark",
+                BearingTarget::Robot => "Robot",
+            }
+            .to_string(),
+            labels: s.labels.clone(),
+            bearing: s.bearing,
+            elevation: s.elevation,
+            applied_faults: serde_json::to_string(&s.applied_faults).unwrap(),
+        }
+    }
+    /// Convert this wrapper to the Rust [`BearingObservation`] type.
+    pub fn to_rust(&self) -> BearingObservation {
+        BearingObservation {
+            id: self.id.clone(),
+            target: match self.target.as_str() {
+                "Robot" => BearingTarget::Robot,
+                _ => BearingTarget::Landmark,
+            },
+            labels: self.labels.clone(),
+            bearing: self.bearing,
+            elevation: self.elevation,
+            applied_faults: serde_json::from_str(&self.applied_faults).unwrap(),
+        }
+    }
+}
+
+impl Default for BearingObservationWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Clone, EnumToString, Debug)]
 #[pyclass(get_all, set_all)]
 #[pyo3(name = "SensorObservation")]
@@ -687,6 +832,10 @@ pub enum SensorObservationWrapper {
     OrientedRobot(OrientedRobotObservationWrapper),
     /// Observation from a displacement sensor.
     Displacement(DisplacementObservationWrapper),
+    /// Observation from an IMU.
+    Imu(ImuObservationWrapper),
+    /// Observation from a bearing sensor.
+    Bearing(BearingObservationWrapper),
 }
 
 #[pymethods]
@@ -752,6 +901,28 @@ impl SensorObservationWrapper {
         }
     }
 
+    /// Try to convert the observation to an [`ImuObservationWrapper`].
+    pub fn as_imu(&self) -> PyResult<ImuObservationWrapper> {
+        if let Self::Imu(o) = self {
+            Ok(o.clone())
+        } else {
+            Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Impossible to convert this observation to an ImuObservation",
+            ))
+        }
+    }
+
+    /// Try to convert the observation to a [`BearingObservationWrapper`].
+    pub fn as_bearing(&self) -> PyResult<BearingObservationWrapper> {
+        if let Self::Bearing(o) = self {
+            Ok(o.clone())
+        } else {
+            Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Impossible to convert this observation to a BearingObservation",
+            ))
+        }
+    }
+
     #[getter]
     /// Return the variant name as a string.
     pub fn kind(&self) -> String {
@@ -784,6 +955,12 @@ impl SensorObservationWrapper {
             SensorObservation::External(_) => {
                 panic!("ExternalObservation cannot be converted to SensorObservationWrapper yet");
             }
+            SensorObservation::Imu(o) => {
+                SensorObservationWrapper::Imu(ImuObservationWrapper::from_rust(o))
+            }
+            SensorObservation::Bearing(o) => {
+                SensorObservationWrapper::Bearing(BearingObservationWrapper::from_rust(o))
+            }
         }
     }
     /// Convert this wrapper to the Rust [`SensorObservation`] type.
@@ -800,6 +977,8 @@ impl SensorObservationWrapper {
             SensorObservationWrapper::Displacement(o) => {
                 SensorObservation::Displacement(o.to_rust())
             }
+            SensorObservationWrapper::Imu(o) => SensorObservation::Imu(o.to_rust()),
+            SensorObservationWrapper::Bearing(o) => SensorObservation::Bearing(o.to_rust()),
         }
     }
 }
@@ -823,6 +1002,8 @@ pub struct ObservationWrapper {
     pub time: f32,
     /// Concrete sensor observation payload.
     pub sensor_observation: SensorObservationWrapper,
+    /// Frame of the observation: "Robot", "Sensor" or "Map".
+    pub frame: String,
 }
 
 #[pymethods]
@@ -835,6 +1016,7 @@ impl ObservationWrapper {
             observer: "someone".to_string(),
             time: 0.,
             sensor_observation: SensorObservationWrapper::new(),
+            frame: ObservationFrame::Robot.to_string(),
         }
     }
 }
@@ -847,15 +1029,23 @@ impl ObservationWrapper {
             observer: s.observer.clone(),
             time: s.time,
             sensor_observation: SensorObservationWrapper::from_rust(&s.sensor_observation),
+            frame: s.frame.to_string(),
         }
     }
     /// Convert this wrapper to the Rust [`Observation`] type.
     pub fn to_rust(&self) -> Observation {
+        let sensor_observation = self.sensor_observation.to_rust();
         Observation {
             sensor_name: self.sensor_name.clone(),
             observer: self.observer.clone(),
             time: self.time,
-            sensor_observation: self.sensor_observation.to_rust(),
+            frame: match self.frame.as_str() {
+                "Robot" => ObservationFrame::Robot,
+                "Sensor" => ObservationFrame::Sensor,
+                "Map" => ObservationFrame::Map,
+                _ => ObservationFrame::native(ObservationKind::from(&sensor_observation)),
+            },
+            sensor_observation,
         }
     }
 }
@@ -1079,8 +1269,14 @@ pub struct EnvelopeWrapper {
 pub struct NodeWrapper {
     name: String,
     network: Option<Weak<RwLock<Network>>>,
+    meta_data_list: Option<Weak<dyn RoLock<MetaDataList>>>,
+    environment: Weak<Environment>,
+    annotations: AnnotationLog,
 }
 
+/// Shared meta-data of all the nodes, by node name.
+type MetaDataList = HashMap<String, SharedRoLock<NodeMetaData>>;
+
 #[pymethods]
 impl NodeWrapper {
     /// Get the unique name of the node.
@@ -1088,6 +1284,12 @@ impl NodeWrapper {
         self.name.clone()
     }
 
+    /// Add an annotation of this node at the given simulated `time` (e.g. "filter diverged").
+    /// Annotations are saved with the results and shown on the GUI timeline.
+    pub fn annotate(&self, time: f32, text: String) {
+        self.annotations.add(time, Some(self.name.clone()), text);
+    }
+
     /// Send a message to the given channel using the node [`Network`]. It returns an error if the node is not connected to any network.
     ///
     /// Should only be used for one-shot messages as it creates a new client every times. For more persistent communication, it is better to use a [`MultiClientWrapper`] obtained with the [`subscribe`](Self::subscribe) method.
@@ -1107,18 +1309,16 @@ impl NodeWrapper {
         flags: Vec<MessageFlag>,
     ) -> PyResult<()> {
         if let Some(network) = self.network.as_ref().and_then(|n| n.upgrade()) {
-            let msg = match message {
-                MessageTypes::String(s) => serde_json::to_value(s),
-                MessageTypes::GoTo(m) => serde_json::to_value(m),
-                MessageTypes::SensorTrigger(m) => serde_json::to_value(m),
-            }
-            .map_err(|e| PyErr::new::<PyTypeError, _>(format!("Conversion failed: {}", e)))?;
+            let msg = message
+                .to_payload()
+                .map_err(|e| PyErr::new::<PyTypeError, _>(format!("Conversion failed: {}", e)))?;
             let key = PathKey::from_str(to.as_str()).unwrap();
             let msg = Envelope {
                 from: self.name.clone(),
                 message: msg,
                 timestamp: time,
                 message_flags: flags,
+                ..Default::default()
             };
             network.write().unwrap().send_to(key, msg, time);
             Ok(())
@@ -1152,6 +1352,55 @@ impl NodeWrapper {
             Err(PyErr::new::<PyTypeError, _>("No network on this node"))
         }
     }
+
+    /// Get the meta-data of all the nodes of the simulation, sorted by name, as
+    /// [`Node::meta_data_list`] does for Rust modules. It returns an error if the node is not
+    /// attached to a simulation.
+    pub fn meta_data_list(&self) -> PyResult<Vec<NodeMetaDataWrapper>> {
+        Ok(snapshot_meta_data(&self.shared_meta_data_list()?)
+            .into_values()
+            .collect())
+    }
+
+    /// Get the landmarks of the true map of the simulation (see [`Environment::map`]). The
+    /// landmarks with a width are the walls.
+    pub fn map_landmarks(&self) -> PyResult<Vec<LandmarkWrapper>> {
+        Ok(self
+            .shared_environment()?
+            .map()
+            .landmarks
+            .iter()
+            .map(LandmarkWrapper::from_rust)
+            .collect())
+    }
+
+    /// Get the landmarks given to the state estimators as prior knowledge, as `{id: [x, y, theta]}`
+    /// (see [`Environment::prior_landmarks`]). Empty when the map is not known (SLAM).
+    pub fn prior_landmarks(&self) -> PyResult<BTreeMap<i32, [f32; 3]>> {
+        Ok(self
+            .shared_environment()?
+            .prior_landmarks()
+            .iter()
+            .map(|(id, state)| (*id, [state.pose.x, state.pose.y, state.pose.z]))
+            .collect())
+    }
+
+    /// Return whether the straight path between the two points `[x, y]` crosses no wall of the
+    /// true map (see [`Environment::is_path_free`]).
+    pub fn is_path_free(&self, start: [f32; 2], end: [f32; 2]) -> PyResult<bool> {
+        Ok(self
+            .shared_environment()?
+            .is_path_free(&Vector2::from(start), &Vector2::from(end)))
+    }
+
+    /// Get a [`MetaDataWatcher`] returning the meta-data of the nodes which changed since its
+    /// last call. It returns an error if the node is not attached to a simulation.
+    pub fn watch_meta_data(&self) -> PyResult<MetaDataWatcher> {
+        Ok(MetaDataWatcher {
+            meta_data_list: Arc::downgrade(&self.shared_meta_data_list()?),
+            known: BTreeMap::new(),
+        })
+    }
 }
 
 impl NodeWrapper {
@@ -1160,8 +1409,157 @@ impl NodeWrapper {
         Self {
             name: n.name(),
             network: n.network().as_ref().map(Arc::downgrade),
+            meta_data_list: n.meta_data_list().as_ref().map(Arc::downgrade),
+            environment: Arc::downgrade(n.environment()),
+            annotations: n.annotation_log(),
         }
     }
+
+    fn shared_environment(&self) -> PyResult<Arc<Environment>> {
+        self.environment
+            .upgrade()
+            .ok_or_else(|| PyErr::new::<PyTypeError, _>("The simulation environment was dropped"))
+    }
+
+    fn shared_meta_data_list(&self) -> PyResult<SharedRoLock<MetaDataList>> {
+        self.meta_data_list
+            .as_ref()
+            .and_then(|l| l.upgrade())
+            .ok_or_else(|| PyErr::new::<PyTypeError, _>("No meta data list on this node"))
+    }
+}
+
+/// Read the meta-data of all the nodes, by node name.
+fn snapshot_meta_data(
+    meta_data_list: &SharedRoLock<MetaDataList>,
+) -> BTreeMap<String, NodeMetaDataWrapper> {
+    meta_data_list
+        .read_recover()
+        .iter()
+        .map(|(name, meta_data)| {
+            (
+                name.clone(),
+                NodeMetaDataWrapper::from_rust(&meta_data.read_recover()),
+            )
+        })
+        .collect()
+}
+
+/// Wrapper for the [`OrientedLandmark`] of the map.
+#[derive(Clone, Debug)]
+#[pyclass(get_all)]
+#[pyo3(name = "Landmark")]
+pub struct LandmarkWrapper {
+    /// Unique landmark identifier.
+    pub id: i32,
+    /// Labels of the landmark.
+    pub labels: Vec<String>,
+    /// Position x.
+    pub x: f32,
+    /// Position y.
+    pub y: f32,
+    /// Orientation, in radians.
+    pub theta: f32,
+    /// Height, `0` for transparent landmarks.
+    pub height: f32,
+    /// Width, `0` for point landmarks.
+    pub width: f32,
+}
+
+impl LandmarkWrapper {
+    /// Copy the Rust [`OrientedLandmark`].
+    pub fn from_rust(l: &OrientedLandmark) -> Self {
+        Self {
+            id: l.id,
+            labels: l.labels.clone(),
+            x: l.pose.x,
+            y: l.pose.y,
+            theta: l.pose.z,
+            height: l.height,
+            width: l.width,
+        }
+    }
+}
+
+/// Wrapper for the [`NodeMetaData`]: copy of the meta-data of one node at the time of the call.
+#[derive(Clone, Debug, PartialEq)]
+#[pyclass(get_all)]
+#[pyo3(name = "NodeMetaData")]
+pub struct NodeMetaDataWrapper {
+    /// Unique name of the node.
+    pub name: String,
+    /// Type of node (e.g. "Robot", "ComputationUnit").
+    pub node_type: String,
+    /// Name of the model used to instantiate the node.
+    pub model_name: String,
+    /// User-defined labels.
+    pub labels: Vec<String>,
+    /// Lifecycle state of the node (e.g. "Running", "Terminated").
+    pub state: String,
+    /// Ground-truth planar position, when available.
+    pub position: Option<[f32; 2]>,
+    /// Radius of the footprint, `0` for a point.
+    pub footprint_radius: f32,
+    /// Height of the node, `0` if it does not obstruct the view.
+    pub height: f32,
+}
+
+impl NodeMetaDataWrapper {
+    /// Copy the Rust [`NodeMetaData`].
+    pub fn from_rust(m: &NodeMetaData) -> Self {
+        Self {
+            name: m.name.clone(),
+            node_type: format!("{:?}", m.node_type),
+            model_name: m.model_name.clone(),
+            labels: m.labels.clone(),
+            state: m.state.to_string(),
+            position: m.position,
+            footprint_radius: m.footprint_radius,
+            height: m.height,
+        }
+    }
+}
+
+/// Watcher of the meta-data of the nodes, obtained with [`NodeWrapper::watch_meta_data`].
+///
+/// The terminated nodes stay in the list, with their last state.
+///
+/// # Example
+/// ```python
+/// class Navigator(simba.Navigator):
+///     def post_init(self, node: simba.Node):
+///         self.fleet = node.watch_meta_data()
+///
+///     def compute_error(self, node, state):
+///         for meta_data in self.fleet.changes():
+///             print(f"{meta_data.name} is now {meta_data.state} at {meta_data.position}")
+/// ```
+#[derive(Debug)]
+#[pyclass]
+#[pyo3(name = "MetaDataWatcher")]
+pub struct MetaDataWatcher {
+    meta_data_list: Weak<dyn RoLock<MetaDataList>>,
+    known: BTreeMap<String, NodeMetaDataWrapper>,
+}
+
+#[pymethods]
+impl MetaDataWatcher {
+    /// Get the meta-data of the nodes which were added or changed since the last call, sorted by
+    /// name. The first call returns all the nodes. It returns an empty list once the simulation
+    /// is dropped.
+    pub fn changes(&mut self) -> Vec<NodeMetaDataWrapper> {
+        let Some(meta_data_list) = self.meta_data_list.upgrade() else {
+            return Vec::new();
+        };
+        let mut changes = Vec::new();
+        for (name, meta_data) in snapshot_meta_data(&meta_data_list) {
+            if self.known.get(&name) != Some(&meta_data) {
+                changes.push(meta_data.clone());
+                self.known.insert(name, meta_data);
+            }
+        }
+        changes
+    }
 }
 
 /// Wrapper around the [`SimbaBrokerMultiClient`] to be used in Python.
@@ -1208,18 +1606,16 @@ impl MultiClientWrapper {
         time: f32,
         flags: Vec<MessageFlag>,
     ) -> PyResult<()> {
-        let msg = match message {
-            MessageTypes::String(s) => serde_json::to_value(s),
-            MessageTypes::GoTo(m) => serde_json::to_value(m),
-            MessageTypes::SensorTrigger(m) => serde_json::to_value(m),
-        }
-        .map_err(|e| PyErr::new::<PyTypeError, _>(format!("Conversion failed: {}", e)))?;
+        let msg = message
+            .to_payload()
+            .map_err(|e| PyErr::new::<PyTypeError, _>(format!("Conversion failed: {}", e)))?;
         let key = PathKey::from_str(to.as_str()).unwrap();
         let msg = Envelope {
             from: self.client.node_id().to_string(),
             message: msg,
             timestamp: time,
             message_flags: flags,
+            ..Default::default()
         };
         self.client.send(&key, msg, time);
         Ok(())
@@ -1446,6 +1842,80 @@ impl SimulatorWrapper {
         self.simulator.compute_results();
         self.simulator.stop();
     }
+
+    /// Get the handle to pause, resume and step the run. It should be taken before calling
+    /// [`SimulatorWrapper::run`], to be used from the Python modules or another thread.
+    pub fn run_control(&self) -> RunControlWrapper {
+        RunControlWrapper {
+            run_control: self.simulator.run_control(),
+        }
+    }
+}
+
+/// Handle to pause, resume and step the run of a simulator, see [`RunControl`].
+#[pyclass]
+#[pyo3(name = "RunControl")]
+#[derive(Clone)]
+pub struct RunControlWrapper {
+    run_control: RunControl,
+}
+
+#[pymethods]
+impl RunControlWrapper {
+    /// Pause the run at the end of the current time step.
+    pub fn pause(&self) {
+        self.run_control.pause();
+    }
+
+    /// Resume the run until its end.
+    pub fn resume(&self) {
+        self.run_control.resume();
+    }
+
+    /// Run `n_time_steps` more time steps, then pause.
+    #[pyo3(signature = (n_time_steps=1))]
+    pub fn step(&self, n_time_steps: usize) {
+        self.run_control.step(n_time_steps);
+    }
+
+    /// Returns `True` if the run is paused.
+    pub fn is_paused(&self) -> bool {
+        self.run_control.is_paused()
+    }
+
+    /// Apply a [`ConfigPatch`] to the paused run, given in YAML or JSON
+    /// (e.g. `{"type": "SensorNoise", "value": {"node": "robot1", "sensor": "gps", "noise_scale": 2.}}`).
+    ///
+    /// Returns a handle giving the outcome of the patch on the node.
+    pub fn patch_config(&self, patch: String) -> PyResult<ConfigPatchHandleWrapper> {
+        let patch: ConfigPatch = serde_yaml::from_str(&patch).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid config patch: {e}"))
+        })?;
+        self.run_control
+            .patch_config(patch)
+            .map(|handle| ConfigPatchHandleWrapper {
+                handle: Mutex::new(handle),
+            })
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.detailed_error()))
+    }
+}
+
+/// Outcome of a config patch submitted with `RunControl.patch_config`, see
+/// [`ConfigPatchHandle`].
+#[pyclass]
+#[pyo3(name = "ConfigPatchHandle")]
+pub struct ConfigPatchHandleWrapper {
+    handle: Mutex<ConfigPatchHandle>,
+}
+
+#[pymethods]
+impl ConfigPatchHandleWrapper {
+    /// Wait until the node applied the patch, once the run is resumed or stepped. Raises a
+    /// `ValueError` if the node rejected the patch or if the run ended before.
+    pub fn wait(&self, py: Python) -> PyResult<()> {
+        py.detach(|| self.handle.lock_recover().wait())
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.detailed_error()))
+    }
 }
 
 /// Run the GUI of the simulator. This function will block until the GUI is closed but
@@ -1541,3 +2011,51 @@ pub fn run_gui(
 pub fn run_gui(_py: Python, _plugin_api: Option<Py<PyAny>>) {
     unimplemented!("run_gui not available. Compile python package with 'gui' feature");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imu_observation_round_trip() {
+        let observation = SensorObservation::Imu(ImuObservation {
+            angular_velocity: 0.1,
+            longitudinal_acceleration: 0.2,
+            lateral_acceleration: -0.3,
+            applied_faults: Vec::new(),
+        });
+        let wrapper = SensorObservationWrapper::from_rust(&observation);
+        assert!(matches!(wrapper, SensorObservationWrapper::Imu(_)));
+        let SensorObservation::Imu(imu) = wrapper.to_rust() else {
+            panic!("The IMU observation changed kind");
+        };
+        assert_eq!(imu.angular_velocity, 0.1);
+        assert_eq!(imu.longitudinal_acceleration, 0.2);
+        assert_eq!(imu.lateral_acceleration, -0.3);
+    }
+
+    #[test]
+    fn bearing_observation_round_trip() {
+        let observation = SensorObservation::Bearing(BearingObservation {
+            id: "robot2".to_string(),
+            target: BearingTarget::Robot,
+            labels: vec!["team_a".to_string()],
+            bearing: 0.5,
+            elevation: Some(0.1),
+            applied_faults: Vec::new(),
+        });
+        let wrapper = SensorObservationWrapper::from_rust(&observation);
+        let SensorObservationWrapper::Bearing(bearing) = &wrapper else {
+            panic!("The bearing observation changed kind");
+        };
+        assert_eq!(bearing.target, "Robot");
+        let SensorObservation::Bearing(bearing) = wrapper.to_rust() else {
+            panic!("The bearing observation changed kind");
+        };
+        assert_eq!(bearing.id, "robot2");
+        assert_eq!(bearing.target, BearingTarget::Robot);
+        assert_eq!(bearing.labels, vec!["team_a".to_string()]);
+        assert_eq!(bearing.bearing, 0.5);
+        assert_eq!(bearing.elevation, Some(0.1));
+    }
+}
@@ -5,10 +5,11 @@
 //! Triggers are represented by [`EventTriggerConfig`], actions by [`EventTypeConfig`], and
 //! complete entries by [`EventConfig`] inside [`ScenarioConfig`].
 
+use config_checker::*;
 use serde::{Deserialize, Serialize};
 use simba_macros::config_derives;
 
-use crate::config::NumberConfig;
+use crate::{config::NumberConfig, navigators::go_to::GoTo, networking::MessageTypes};
 
 /// Root scenario configuration.
 ///
@@ -45,6 +46,7 @@ pub struct EventConfig {
     /// Trigger condition for the event.
     pub trigger: EventTriggerConfig,
     /// Action executed when the trigger condition is met.
+    #[check]
     pub event_type: EventTypeConfig,
 }
 
@@ -113,6 +115,28 @@ impl Default for AreaEventTriggerConfig {
     }
 }
 
+impl AreaEventTriggerConfig {
+    /// Return whether the `point` triggers the area: inside the area if `inside` is `true`,
+    /// outside otherwise.
+    pub fn is_triggered_by(&self, point: [f32; 2]) -> bool {
+        match self {
+            Self::Rect(rect_config) => {
+                let inside = point[0] >= rect_config.bottom_left.0
+                    && point[0] <= rect_config.top_right.0
+                    && point[1] >= rect_config.bottom_left.1
+                    && point[1] <= rect_config.top_right.1;
+                inside == rect_config.inside
+            }
+            Self::Circle(circle_config) => {
+                let distance_squared = (point[0] - circle_config.center.0).powi(2)
+                    + (point[1] - circle_config.center.1).powi(2);
+                let inside = distance_squared <= circle_config.radius.powi(2);
+                inside == circle_config.inside
+            }
+        }
+    }
+}
+
 /// Rectangular area trigger configuration.
 ///
 /// Default values:
@@ -201,6 +225,24 @@ pub enum EventTypeConfig {
     Spawn(SpawnEventConfig),
     /// Kills a node by name.
     Kill(String),
+    /// Activates or deactivates a fault model of a sensor according to
+    /// [`SensorFaultEventConfig`].
+    SensorFault(SensorFaultEventConfig),
+    /// Activates or deactivates a fault model of the physics according to
+    /// [`ActuatorFaultEventConfig`].
+    ActuatorFault(ActuatorFaultEventConfig),
+    /// Sets the real state of a node (teleportation) according to [`SetStateEventConfig`].
+    #[check]
+    SetState(SetStateEventConfig),
+    /// Sends a message to a node, or to all the nodes, according to
+    /// [`SendMessageEventConfig`].
+    #[check]
+    SendMessage(SendMessageEventConfig),
+    /// Changes a parameter of a node according to [`ConfigPatch`].
+    ///
+    /// Also recorded when a patch is applied to a paused run through the async API.
+    #[check]
+    ConfigPatch(ConfigPatch),
 }
 
 impl Default for EventTypeConfig {
@@ -231,6 +273,306 @@ impl Default for SpawnEventConfig {
     }
 }
 
+/// Sensor fault event configuration.
+///
+/// The fault models are active at start, unless listed in the `inactive_faults` of the managed
+/// sensor: list them there to inject the fault later.
+///
+/// Default values:
+/// - `node`: `"$0"`
+/// - `sensor`: `"sensor"`
+/// - `fault`: `0`
+/// - `active`: `true`
+#[config_derives]
+pub struct SensorFaultEventConfig {
+    /// Name of the node.
+    pub node: String,
+    /// Name of the sensor in the sensor manager of the node.
+    pub sensor: String,
+    /// Index of the fault model in the `faults` list of the sensor.
+    pub fault: usize,
+    /// If `true`, the fault model is activated; otherwise it is deactivated.
+    pub active: bool,
+}
+
+impl Default for SensorFaultEventConfig {
+    fn default() -> Self {
+        Self {
+            node: "$0".to_string(),
+            sensor: "sensor".to_string(),
+            fault: 0,
+            active: true,
+        }
+    }
+}
+
+/// Actuator fault event configuration, for the fault models of the internal physics.
+///
+/// The fault models are active at start, unless listed in the `inactive_faults` of the physics:
+/// list them there to inject the fault later.
+///
+/// Default values:
+/// - `node`: `"$0"`
+/// - `fault`: `0`
+/// - `active`: `true`
+#[config_derives]
+pub struct ActuatorFaultEventConfig {
+    /// Name of the node.
+    pub node: String,
+    /// Index of the fault model in the `faults` list of the physics.
+    pub fault: usize,
+    /// If `true`, the fault model is activated; otherwise it is deactivated.
+    pub active: bool,
+}
+
+impl Default for ActuatorFaultEventConfig {
+    fn default() -> Self {
+        Self {
+            node: "$0".to_string(),
+            fault: 0,
+            active: true,
+        }
+    }
+}
+
+/// Set state event configuration: relocates a robot or resets the state of its physics, e.g. to
+/// test the recovery of a localization algorithm from a kidnapping.
+///
+/// The values left to `None` are not changed.
+///
+/// Default values:
+/// - `node`: `"$0"`
+/// - `pose`: `None`
+/// - `velocity`: `None`
+#[config_derives]
+pub struct SetStateEventConfig {
+    /// Name of the node.
+    pub node: String,
+    /// New pose `[x, y, theta]`.
+    pub pose: Option<[f32; 3]>,
+    /// New velocity `[longitudinal, lateral, angular]`.
+    pub velocity: Option<[f32; 3]>,
+}
+
+impl Default for SetStateEventConfig {
+    fn default() -> Self {
+        Self {
+            node: "$0".to_string(),
+            pose: None,
+            velocity: None,
+        }
+    }
+}
+
+impl Check for SetStateEventConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.pose.is_none() && self.velocity.is_none() {
+            return Err(vec![
+                "SetState event should set the pose or the velocity".to_string(),
+            ]);
+        }
+        Ok(())
+    }
+}
+
+/// Send message event configuration: injects a message in the network, to script the
+/// communication-based behaviors without plugins.
+///
+/// The `message` is a [`MessageTypes`] (e.g. `GoTo: { target_point: [5., 5.] }`). The template
+/// variables (`$0`) are replaced by the triggering node names in `node`, `channel` and
+/// in the strings of the `message`.
+///
+/// If `channel` is relative, it is prefixed with the namespace of the recipient node, like
+/// `/simba/nodes/<node>/<channel>`; if it is absolute, `node` is ignored. If `node` is `None`, the
+/// message is sent to all the nodes.
+/// The message is not subject to the range or the link model of the networks.
+///
+/// Default values:
+/// - `node`: `Some("$0")`
+/// - `channel`: `"navigator/goto"` (see [`GoTo::CHANNEL_NAME`])
+/// - `message`: `null` (invalid)
+///
+/// # Example
+/// ```yaml
+/// event_type:
+///   type: SendMessage
+///   value:
+///     node: $0
+///     channel: navigator/goto
+///     message:
+///       GoTo:
+///         target_point: [5., 5.]
+/// ```
+#[config_derives]
+pub struct SendMessageEventConfig {
+    /// Name of the recipient node, `None` to send the message to all the nodes.
+    pub node: Option<String>,
+    /// Channel of the message, relative to the recipient node namespace or absolute.
+    pub channel: String,
+    /// Message sent, as a [`MessageTypes`].
+    pub message: serde_json::Value,
+}
+
+impl Default for SendMessageEventConfig {
+    fn default() -> Self {
+        Self {
+            node: Some("$0".to_string()),
+            channel: GoTo::CHANNEL_NAME.to_string(),
+            message: serde_json::Value::Null,
+        }
+    }
+}
+
+impl Check for SendMessageEventConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.channel.is_empty() {
+            errors.push("SendMessage event should have a channel".to_string());
+        }
+        if let Err(e) = serde_json::from_value::<MessageTypes>(self.message.clone()) {
+            errors.push(format!("Invalid message in SendMessage event: {e}"));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Runtime change of the configuration of a node.
+///
+/// Only a subset of the parameters can be changed while the simulation runs. The patches can be
+/// scripted as scenario events, or applied to a paused run with
+/// [`RunControl::patch_config`](crate::simulator::RunControl::patch_config)
+/// for interactive tuning.
+///
+/// Default value: [`ConfigPatch::SensorNoise`] with [`SensorNoisePatch::default`].
+#[config_derives(tag_content)]
+pub enum ConfigPatch {
+    /// Changes the gains of the controller of a node.
+    #[check]
+    ControllerGains(ControllerGainsPatch),
+    /// Scales the noise of a sensor.
+    #[check]
+    SensorNoise(SensorNoisePatch),
+    /// Adds latency to the messages of a node.
+    #[check]
+    NetworkDelay(NetworkDelayPatch),
+}
+
+impl Default for ConfigPatch {
+    fn default() -> Self {
+        Self::SensorNoise(SensorNoisePatch::default())
+    }
+}
+
+/// New gains of the controller of a node.
+///
+/// Only the controllers with gains (PID) can be patched. The gains must have the same length as
+/// the configured ones.
+///
+/// Default values:
+/// - `node`: `"$0"`
+/// - `proportional_gains`, `derivative_gains`, `integral_gains`: `None` (unchanged)
+#[config_derives]
+pub struct ControllerGainsPatch {
+    /// Name of the node.
+    pub node: String,
+    /// New proportional gains, `None` to keep the current ones.
+    pub proportional_gains: Option<Vec<f32>>,
+    /// New derivative gains, `None` to keep the current ones.
+    pub derivative_gains: Option<Vec<f32>>,
+    /// New integral gains, `None` to keep the current ones.
+    pub integral_gains: Option<Vec<f32>>,
+}
+
+impl Default for ControllerGainsPatch {
+    fn default() -> Self {
+        Self {
+            node: "$0".to_string(),
+            proportional_gains: None,
+            derivative_gains: None,
+            integral_gains: None,
+        }
+    }
+}
+
+/// New noise scale of a sensor, see the `noise_scale` of the sensor reconfiguration messages.
+///
+/// Default values:
+/// - `node`: `"$0"`
+/// - `sensor`: `"sensor"`
+/// - `noise_scale`: `1.`
+#[config_derives]
+pub struct SensorNoisePatch {
+    /// Name of the node.
+    pub node: String,
+    /// Name of the sensor in the sensor manager of the node.
+    pub sensor: String,
+    /// Factor applied to the standard deviations of the fault models of the sensor.
+    pub noise_scale: f32,
+}
+
+impl Default for SensorNoisePatch {
+    fn default() -> Self {
+        Self {
+            node: "$0".to_string(),
+            sensor: "sensor".to_string(),
+            noise_scale: 1.,
+        }
+    }
+}
+
+impl Check for SensorNoisePatch {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.noise_scale < 0. {
+            Err(vec![format!(
+                "Sensor noise_scale should be positive, got {}",
+                self.noise_scale
+            )])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Extra latency of the messages sent on the channels created by a node, added to the delays
+/// of the link model.
+///
+/// Default values:
+/// - `node`: `"$0"`
+/// - `delay`: `0.`
+#[config_derives]
+pub struct NetworkDelayPatch {
+    /// Name of the node.
+    pub node: String,
+    /// Extra latency in seconds.
+    pub delay: f32,
+}
+
+impl Default for NetworkDelayPatch {
+    fn default() -> Self {
+        Self {
+            node: "$0".to_string(),
+            delay: 0.,
+        }
+    }
+}
+
+impl Check for NetworkDelayPatch {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.delay < 0. {
+            Err(vec![format!(
+                "Network delay should be positive, got {}",
+                self.delay
+            )])
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// Record emitted when an event is evaluated/executed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventRecord {
@@ -23,11 +23,18 @@ use crate::{
     constants::TIME_ROUND,
     errors::SimbaResult,
     logger::{InternalLog, is_enabled},
-    networking::{self, network::Envelope},
+    networking::{
+        self, MessageTypes,
+        network::Envelope,
+        reconfigure::{NodeReconfigureMessage, ReconfigurationOutcome, ReconfigureMessage},
+    },
     scenario::config::{
-        AreaEventTriggerConfig, EventConfig, EventRecord, EventTriggerConfig, EventTypeConfig,
-        ProximityEventTriggerConfig, ScenarioConfig, SpawnEventConfig, TimeEventTriggerConfig,
+        ActuatorFaultEventConfig, AreaEventTriggerConfig, ConfigPatch, EventConfig, EventRecord,
+        EventTriggerConfig, EventTypeConfig, ProximityEventTriggerConfig, ScenarioConfig,
+        SendMessageEventConfig, SensorFaultEventConfig, SetStateEventConfig, SpawnEventConfig,
+        TimeEventTriggerConfig,
     },
+    sensors::sensor_manager::SensorManager,
     simulator::{RunningParameters, SimbaBroker, Simulator, SimulatorConfig},
     utils::{SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory},
 };
@@ -43,6 +50,10 @@ pub struct Scenario {
     last_executed_time: f32,
     broker: SharedRwLock<SimbaBroker>,
     client: Client<Envelope>,
+    /// Client receiving the outcomes of the acknowledged reconfiguration messages.
+    outcome_client: Client<Envelope>,
+    /// Names of the nodes at the last scenario execution, recipients of the broadcast messages.
+    node_names: Vec<String>,
 }
 
 impl Scenario {
@@ -98,6 +109,9 @@ impl Scenario {
             .unwrap()
             .join_str(Self::CHANNEL_NAME);
         broker.write().unwrap().add_channel(channel_key.clone());
+        let outcome_key =
+            PathKey::from_str(networking::channels::internal::RECONFIGURE_OUTCOME).unwrap();
+        broker.write().unwrap().add_channel(outcome_key.clone());
         Self {
             time_events,
             other_events: Mutex::new(other_events.iter().map(Event::from_config).collect()),
@@ -108,6 +122,12 @@ impl Scenario {
                 .unwrap()
                 .subscribe_to(&channel_key, "scenario".to_string(), 0.)
                 .unwrap(),
+            outcome_client: broker
+                .write()
+                .unwrap()
+                .subscribe_to(&outcome_key, "scenario".to_string(), 0.)
+                .unwrap(),
+            node_names: Vec::new(),
         }
     }
 
@@ -121,6 +141,8 @@ impl Scenario {
         if is_enabled(InternalLog::Scenario) {
             debug!("Check scenario");
         }
+        self.node_names = node_states.keys().cloned().collect();
+        self.node_names.sort();
         // Time events
         for (_, event) in self
             .time_events
@@ -208,10 +230,6 @@ impl Scenario {
         let mut event_executed = None;
         match &event.event_type {
             EventTypeConfig::Kill(name) => {
-                use simba_com::pub_sub::PathKey;
-
-                use crate::networking;
-
                 let name = Self::replace_variables(name, trigger_variables);
                 log::info!(
                     "Executing Kill event for node `{}` triggered by {}",
@@ -221,29 +239,225 @@ impl Scenario {
                 let command_key = PathKey::from_str(networking::channels::internal::COMMAND)
                     .unwrap()
                     .join_str(name.as_str());
-                if !self.broker.write().unwrap().channel_exists(&command_key) {
+                if !self.send_to_channel(
+                    &command_key,
+                    serde_json::Value::Null,
+                    vec![MessageFlag::Kill],
+                    time,
+                ) {
                     warn!(
                         "Ignoring error while sending Kill message to node `{}`: this node seems to not exist",
                         name
                     );
                 } else {
-                    let tmp_client = self.broker.write().unwrap().subscribe_to(
-                        &command_key,
-                        "scenario".to_string(),
-                        0.,
+                    event_executed = Some(EventRecord {
+                        trigger: trigger.clone(),
+                        event: EventTypeConfig::Kill(name),
+                    });
+                }
+            }
+            EventTypeConfig::SensorFault(fault_config) => {
+                let node = Self::replace_variables(&fault_config.node, trigger_variables);
+                let sensor = Self::replace_variables(&fault_config.sensor, trigger_variables);
+                log::info!(
+                    "Executing SensorFault event ({} fault {} of sensor `{}` of node `{}`) triggered by {}",
+                    if fault_config.active {
+                        "activating"
+                    } else {
+                        "deactivating"
+                    },
+                    fault_config.fault,
+                    sensor,
+                    node,
+                    trigger
+                );
+                let reconfigure_key = PathKey::from_str(networking::channels::internal::NODE)
+                    .unwrap()
+                    .join_str(node.as_str())
+                    .join_str(SensorManager::CHANNEL_NAME)
+                    .join_str(SensorManager::RECONFIGURE_CHANNEL)
+                    .join_str(sensor.as_str());
+                let message = ReconfigureMessage {
+                    fault_activation: Some((fault_config.fault, fault_config.active)),
+                    ..Default::default()
+                };
+                if !self.send_to_channel(
+                    &reconfigure_key,
+                    serde_json::to_value(message).unwrap(),
+                    Vec::new(),
+                    time,
+                ) {
+                    warn!(
+                        "Ignoring error while sending SensorFault message to sensor `{}` of node `{}`: this sensor seems to not exist",
+                        sensor, node
                     );
-                    tmp_client.unwrap().send(
-                        Envelope {
-                            from: "scenario".to_string(),
-                            message: serde_json::Value::Null,
-                            message_flags: vec![MessageFlag::Kill],
-                            timestamp: time,
-                        },
-                        time,
+                } else {
+                    event_executed = Some(EventRecord {
+                        trigger: trigger.clone(),
+                        event: EventTypeConfig::SensorFault(SensorFaultEventConfig {
+                            node,
+                            sensor,
+                            ..fault_config.clone()
+                        }),
+                    });
+                }
+            }
+            EventTypeConfig::ActuatorFault(fault_config) => {
+                let node = Self::replace_variables(&fault_config.node, trigger_variables);
+                log::info!(
+                    "Executing ActuatorFault event ({} fault {} of the physics of node `{}`) triggered by {}",
+                    if fault_config.active {
+                        "activating"
+                    } else {
+                        "deactivating"
+                    },
+                    fault_config.fault,
+                    node,
+                    trigger
+                );
+                let reconfigure_key =
+                    PathKey::from_str(networking::channels::internal::RECONFIGURE)
+                        .unwrap()
+                        .join_str(node.as_str());
+                let message = NodeReconfigureMessage::Physics(ReconfigureMessage {
+                    fault_activation: Some((fault_config.fault, fault_config.active)),
+                    ..Default::default()
+                });
+                if !self.send_to_channel(
+                    &reconfigure_key,
+                    serde_json::to_value(message).unwrap(),
+                    Vec::new(),
+                    time,
+                ) {
+                    warn!(
+                        "Ignoring error while sending ActuatorFault message to node `{}`: this node seems to not exist",
+                        node
                     );
+                } else {
                     event_executed = Some(EventRecord {
                         trigger: trigger.clone(),
-                        event: EventTypeConfig::Kill(name),
+                        event: EventTypeConfig::ActuatorFault(ActuatorFaultEventConfig {
+                            node,
+                            ..fault_config.clone()
+                        }),
+                    });
+                }
+            }
+            EventTypeConfig::SetState(state_config) => {
+                let node = Self::replace_variables(&state_config.node, trigger_variables);
+                log::info!(
+                    "Executing SetState event (pose {:?}, velocity {:?} for node `{}`) triggered by {}",
+                    state_config.pose,
+                    state_config.velocity,
+                    node,
+                    trigger
+                );
+                let reconfigure_key =
+                    PathKey::from_str(networking::channels::internal::RECONFIGURE)
+                        .unwrap()
+                        .join_str(node.as_str());
+                let message = NodeReconfigureMessage::SetState {
+                    pose: state_config.pose,
+                    velocity: state_config.velocity,
+                };
+                if !self.send_to_channel(
+                    &reconfigure_key,
+                    serde_json::to_value(message).unwrap(),
+                    Vec::new(),
+                    time,
+                ) {
+                    warn!(
+                        "Ignoring error while sending SetState message to node `{}`: this node seems to not exist",
+                        node
+                    );
+                } else {
+                    event_executed = Some(EventRecord {
+                        trigger: trigger.clone(),
+                        event: EventTypeConfig::SetState(SetStateEventConfig {
+                            node,
+                            ..state_config.clone()
+                        }),
+                    });
+                }
+            }
+            EventTypeConfig::SendMessage(message_config) => {
+                let node = message_config
+                    .node
+                    .as_ref()
+                    .map(|node| Self::replace_variables(node, trigger_variables));
+                let channel = Self::replace_variables(&message_config.channel, trigger_variables);
+                let message =
+                    Self::replace_json_variables(&message_config.message, trigger_variables);
+                log::info!(
+                    "Executing SendMessage event ({} on `{}` for node {}) triggered by {}",
+                    message,
+                    channel,
+                    node.as_deref().unwrap_or("all"),
+                    trigger
+                );
+                let payload = serde_json::from_value::<MessageTypes>(message.clone())
+                    .and_then(|message| message.to_payload());
+                match (payload, PathKey::from_str(&channel)) {
+                    (Ok(payload), Ok(channel_key)) => {
+                        let keys = if channel_key.absolute() {
+                            vec![channel_key]
+                        } else {
+                            node.as_ref()
+                                .map_or(self.node_names.as_slice(), std::slice::from_ref)
+                                .iter()
+                                .map(|name| {
+                                    channel_key
+                                        .clone()
+                                        .prepend_str(name)
+                                        .prepend_str(networking::channels::internal::NODE)
+                                })
+                                .collect()
+                        };
+                        let mut sent = false;
+                        for key in keys {
+                            if self.send_to_channel(&key, payload.clone(), Vec::new(), time) {
+                                sent = true;
+                            } else {
+                                warn!(
+                                    "Ignoring error while sending message on `{}`: this channel seems to not exist",
+                                    key
+                                );
+                            }
+                        }
+                        if sent {
+                            event_executed = Some(EventRecord {
+                                trigger: trigger.clone(),
+                                event: EventTypeConfig::SendMessage(SendMessageEventConfig {
+                                    node,
+                                    channel,
+                                    message,
+                                }),
+                            });
+                        }
+                    }
+                    (Err(e), _) => warn!("Ignoring SendMessage event with an invalid message: {e}"),
+                    (_, Err(_)) => {
+                        warn!("Ignoring SendMessage event with an invalid channel `{channel}`")
+                    }
+                }
+            }
+            EventTypeConfig::ConfigPatch(patch) => {
+                let patch = Self::replace_patch_variables(patch, trigger_variables);
+                log::info!(
+                    "Executing ConfigPatch event ({:?}) triggered by {}",
+                    patch,
+                    trigger
+                );
+                let (key, message) = Self::config_patch_message(&patch);
+                if !self.send_to_channel(&key, message, Vec::new(), time) {
+                    warn!(
+                        "Ignoring error while sending ConfigPatch message on `{}`: this channel seems to not exist",
+                        key
+                    );
+                } else {
+                    event_executed = Some(EventRecord {
+                        trigger: trigger.clone(),
+                        event: EventTypeConfig::ConfigPatch(patch),
                     });
                 }
             }
@@ -281,19 +495,183 @@ impl Scenario {
             }
         }
         if let Some(event_executed) = event_executed {
-            self.client.send(
-                Envelope {
-                    from: "scenario".to_string(),
-                    message: serde_json::to_value(event_executed).unwrap(),
-                    timestamp: time,
-                    ..Default::default()
-                },
-                time,
-            );
+            self.publish_event(event_executed, simulator, time);
         }
         Ok(())
     }
 
+    /// Gives an executed event to the hooks and publishes it on the scenario channel.
+    fn publish_event(&self, event_executed: EventRecord, simulator: &Simulator, time: f32) {
+        for hooks in simulator.hooks() {
+            hooks.on_event(time, &event_executed);
+        }
+        self.client.send(
+            Envelope {
+                from: "scenario".to_string(),
+                message: serde_json::to_value(event_executed).unwrap(),
+                timestamp: time,
+                ..Default::default()
+            },
+            time,
+        );
+    }
+
+    /// Channel and reconfiguration message applying `patch`.
+    fn config_patch_message(patch: &ConfigPatch) -> (PathKey, serde_json::Value) {
+        match patch {
+            ConfigPatch::ControllerGains(gains) => (
+                PathKey::from_str(networking::channels::internal::RECONFIGURE)
+                    .unwrap()
+                    .join_str(gains.node.as_str()),
+                serde_json::to_value(NodeReconfigureMessage::ControllerGains {
+                    proportional_gains: gains.proportional_gains.clone(),
+                    derivative_gains: gains.derivative_gains.clone(),
+                    integral_gains: gains.integral_gains.clone(),
+                })
+                .unwrap(),
+            ),
+            ConfigPatch::SensorNoise(noise) => (
+                PathKey::from_str(networking::channels::internal::NODE)
+                    .unwrap()
+                    .join_str(noise.node.as_str())
+                    .join_str(SensorManager::CHANNEL_NAME)
+                    .join_str(SensorManager::RECONFIGURE_CHANNEL)
+                    .join_str(noise.sensor.as_str()),
+                serde_json::to_value(ReconfigureMessage {
+                    noise_scale: Some(noise.noise_scale),
+                    ..Default::default()
+                })
+                .unwrap(),
+            ),
+            ConfigPatch::NetworkDelay(delay) => (
+                PathKey::from_str(networking::channels::internal::RECONFIGURE)
+                    .unwrap()
+                    .join_str(delay.node.as_str()),
+                serde_json::to_value(NodeReconfigureMessage::NetworkDelay(delay.delay)).unwrap(),
+            ),
+        }
+    }
+
+    fn replace_json_variables(
+        value: &serde_json::Value,
+        variables: &[String],
+    ) -> serde_json::Value {
+        match value {
+            serde_json::Value::String(s) => {
+                serde_json::Value::String(Self::replace_variables(s, variables))
+            }
+            serde_json::Value::Array(values) => serde_json::Value::Array(
+                values
+                    .iter()
+                    .map(|v| Self::replace_json_variables(v, variables))
+                    .collect(),
+            ),
+            serde_json::Value::Object(fields) => serde_json::Value::Object(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Self::replace_json_variables(v, variables)))
+                    .collect(),
+            ),
+            value => value.clone(),
+        }
+    }
+
+    fn replace_patch_variables(patch: &ConfigPatch, variables: &[String]) -> ConfigPatch {
+        let mut patch = patch.clone();
+        match &mut patch {
+            ConfigPatch::ControllerGains(gains) => {
+                gains.node = Self::replace_variables(&gains.node, variables);
+            }
+            ConfigPatch::SensorNoise(noise) => {
+                noise.node = Self::replace_variables(&noise.node, variables);
+                noise.sensor = Self::replace_variables(&noise.sensor, variables);
+            }
+            ConfigPatch::NetworkDelay(delay) => {
+                delay.node = Self::replace_variables(&delay.node, variables);
+            }
+        }
+        patch
+    }
+
+    /// Applies a [`ConfigPatch`] submitted while the run is paused.
+    ///
+    /// The patch is recorded as a scenario event triggered at `time`, so it is published on the
+    /// scenario channel and given to the hooks like the configured events. The receiver is asked
+    /// to acknowledge it, see [`Self::take_reconfiguration_outcomes`].
+    ///
+    /// Returns the channel on which the patch was sent, or the reason why it could not be sent.
+    pub fn execute_config_patch(
+        &self,
+        patch: ConfigPatch,
+        simulator: &Simulator,
+        time: f32,
+    ) -> Result<PathKey, String> {
+        log::info!("Executing ConfigPatch ({:?}) submitted during pause", patch);
+        let (key, message) = Self::config_patch_message(&patch);
+        if !self.send_to_channel(&key, message, vec![MessageFlag::Acknowledge], time) {
+            return Err(format!("Channel `{key}` does not exist"));
+        }
+        let trigger = EventTriggerConfig::Time(TimeEventTriggerConfig {
+            time: NumberConfig::Num(time),
+            occurences: NumberConfig::Num(1.),
+        });
+        self.publish_event(
+            EventRecord {
+                trigger,
+                event: EventTypeConfig::ConfigPatch(patch),
+            },
+            simulator,
+            time,
+        );
+        Ok(key)
+    }
+
+    /// Takes the outcomes of the acknowledged reconfiguration messages received up to `time`.
+    pub fn take_reconfiguration_outcomes(&self, time: f32) -> Vec<ReconfigurationOutcome> {
+        let mut outcomes = Vec::new();
+        while let Some(envelope) = self.outcome_client.try_receive(time) {
+            match serde_json::from_value(envelope.message) {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(e) => warn!(
+                    "Invalid reconfiguration outcome from `{}`: {e}",
+                    envelope.from
+                ),
+            }
+        }
+        outcomes
+    }
+
+    /// Sends a message from the scenario on the channel `key`.
+    ///
+    /// Returns `false` if the channel does not exist.
+    fn send_to_channel(
+        &self,
+        key: &PathKey,
+        message: serde_json::Value,
+        message_flags: Vec<MessageFlag>,
+        time: f32,
+    ) -> bool {
+        if !self.broker.write().unwrap().channel_exists(key) {
+            return false;
+        }
+        let tmp_client = self
+            .broker
+            .write()
+            .unwrap()
+            .subscribe_to(key, "scenario".to_string(), 0.);
+        tmp_client.unwrap().send(
+            Envelope {
+                from: "scenario".to_string(),
+                message,
+                message_flags,
+                timestamp: time,
+                ..Default::default()
+            },
+            time,
+        );
+        true
+    }
+
     /// Returns the next scheduled time-triggered event, if any.
     pub fn next_event_time(&self) -> Option<f32> {
         self.time_events.min_time().map(|(a, _)| a)
@@ -320,27 +698,11 @@ impl Scenario {
                 continue;
             }
             let state = state.unwrap();
-            match area_config {
-                AreaEventTriggerConfig::Rect(rect_config) => {
-                    let inside = state[0] >= rect_config.bottom_left.0
-                        && state[0] <= rect_config.top_right.0
-                        && state[1] >= rect_config.bottom_left.1
-                        && state[1] <= rect_config.top_right.1;
-                    if inside == rect_config.inside {
-                        if is_enabled(InternalLog::Scenario) {
-                            debug!("Node `{}` triggered an Area event", node_name);
-                        }
-                        triggering_nodes.push(vec![node_name.clone()]);
-                    }
-                }
-                AreaEventTriggerConfig::Circle(circle_config) => {
-                    let distance_squared = (state[0] - circle_config.center.0).powi(2)
-                        + (state[1] - circle_config.center.1).powi(2);
-                    let inside = distance_squared <= circle_config.radius.powi(2);
-                    if inside == circle_config.inside {
-                        triggering_nodes.push(vec![node_name.clone()]);
-                    }
+            if area_config.is_triggered_by(state) {
+                if is_enabled(InternalLog::Scenario) {
+                    debug!("Node `{}` triggered an Area event", node_name);
                 }
+                triggering_nodes.push(vec![node_name.clone()]);
             }
         }
         triggering_nodes
@@ -0,0 +1,1194 @@
+//! Bearing-only camera sensor implementation.
+//!
+//! This module provides a [`Sensor`] that observes the bearing (and optionally the elevation) of
+//! the landmarks and the robots inside a pinhole-like field of view.
+//! It supports configurable filters through [`BearingSensorFilterConfig`] and fault model
+//! pipelines configured by [`BearingSensorFaultModelConfig`].
+
+use super::fault_models::fault_model::FaultModel;
+use super::{Sensor, SensorObservation, SensorRecord, reconfigure_activation};
+
+use crate::constants::TIME_ROUND;
+use crate::environment::RobotObstructions;
+use crate::errors::{SimbaErrorTypes, SimbaResult};
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+use crate::logger::is_enabled;
+use crate::networking::reconfigure::ReconfigureMessage;
+use crate::networking::service_manager::ServiceError;
+use crate::plugin_api::PluginAPI;
+use crate::recordable::Recordable;
+use crate::sensors::fault_models::additive::{AdditiveFault, AdditiveFaultConfig};
+use crate::sensors::fault_models::calibration::{CalibrationFault, CalibrationFaultConfig};
+use crate::sensors::fault_models::detection_probability::{
+    DetectionProbability, DetectionProbabilityConfig,
+};
+use crate::sensors::fault_models::external_fault::{ExternalFault, ExternalFaultConfig};
+use crate::sensors::fault_models::misdetection::{MisdetectionFault, MisdetectionFaultConfig};
+use crate::sensors::fault_models::python_fault_model::{PythonFaultModel, PythonFaultModelConfig};
+use crate::sensors::sensor_filters::SensorFilter;
+use crate::sensors::sensor_filters::external_filter::{ExternalFilter, ExternalFilterConfig};
+use crate::sensors::sensor_filters::python_filter::{PythonFilter, PythonFilterConfig};
+use crate::sensors::sensor_filters::range_filter::{RangeFilter, RangeFilterConfig};
+use crate::sensors::sensor_filters::string_filter::{StringFilter, StringFilterConfig};
+use crate::simulator::SimulatorConfig;
+use crate::state_estimators::State;
+use crate::utils::determinist_random_variable::DeterministRandomVariableFactory;
+use crate::utils::enum_tools::EnumVariables;
+use crate::utils::geometry::mod2pi;
+use crate::utils::lock_recovery::{RecoverRoLock, RecoverRwLock};
+use crate::utils::periodicity::{Periodicity, PeriodicityConfig};
+use serde_derive::{Deserialize, Serialize};
+
+use log::debug;
+extern crate nalgebra as na;
+use na::Vector2;
+use simba_macros::{EnumToString, UIComponent, config_derives, enum_variables};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+use std::vec;
+
+enum_variables!(
+    "Variables used by bearing observations, filters, and fault models."
+    BearingSensorVariables;
+    "Variables accepted by sensor filters."
+    Filter,
+    "Variables modified by fault models."
+    Faults:
+    "Bearing of the target in the sensor frame."
+    Bearing, "bearing", "theta";
+    Filter, Faults:
+    "Elevation of the target, `0` if the elevation is not measured."
+    Elevation, "elevation";
+    Filter:
+    "Real distance to the target (not measured by the sensor)."
+    R, "r", "range", "d", "distance";
+    Filter:
+    "Absolute observer linear velocity"
+    SelfVelocity, "self_velocity";
+);
+
+/// Configuration enum selecting bearing fault model strategies.
+///
+/// Default value: [`BearingSensorFaultModelConfig::Additive`] with
+/// [`AdditiveFaultConfig::default`].
+#[config_derives]
+#[derive(UIComponent)]
+#[show_all = "Faults"]
+pub enum BearingSensorFaultModelConfig {
+    /// Additive fault model on the bearing and the elevation.
+    Additive(AdditiveFaultConfig<BearingSensorVariablesFaults, BearingSensorVariables>),
+    /// Misdetection fault model.
+    Misdetection(MisdetectionFaultConfig),
+    /// Plugin-provided external fault model.
+    External(ExternalFaultConfig),
+    /// Python-implemented fault model.
+    Python(PythonFaultModelConfig),
+    /// Calibration fault model, the targets are observed from the real mounting pose.
+    Calibration(CalibrationFaultConfig<BearingSensorVariablesFaults>),
+}
+
+impl Default for BearingSensorFaultModelConfig {
+    fn default() -> Self {
+        Self::Additive(AdditiveFaultConfig::default())
+    }
+}
+
+/// Runtime enum containing instantiated bearing fault models.
+#[derive(Debug, EnumToString)]
+pub enum BearingSensorFaultModelType {
+    /// Instantiated additive fault model.
+    Additive(AdditiveFault<BearingSensorVariablesFaults, BearingSensorVariables>),
+    /// Instantiated misdetection fault model.
+    Misdetection(MisdetectionFault),
+    /// Instantiated external fault model.
+    External(ExternalFault),
+    /// Instantiated Python fault model.
+    Python(PythonFaultModel),
+    /// Instantiated calibration fault model.
+    Calibration(CalibrationFault<BearingSensorVariablesFaults>),
+}
+
+impl BearingSensorFaultModelType {
+    /// Initializes fault models that require runtime node context.
+    pub fn post_init(
+        &mut self,
+        node: &mut crate::node::Node,
+        initial_time: f32,
+    ) -> crate::errors::SimbaResult<()> {
+        match self {
+            Self::Python(f) => f.post_init(node, initial_time),
+            Self::External(f) => f.post_init(node, initial_time),
+            Self::Additive(_) | Self::Misdetection(_) | Self::Calibration(_) => Ok(()),
+        }
+    }
+
+    /// Scales the perturbations of the additive fault models, other models are not changed.
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        match self {
+            Self::Additive(f) => f.set_noise_scale(noise_scale),
+            Self::Python(_) | Self::External(_) | Self::Misdetection(_) | Self::Calibration(_) => {}
+        }
+    }
+}
+
+/// Configuration enum selecting among multiple sensor observation filtering strategies for bearing sensors.
+///
+/// When multiple filters are applied to a sensor, all must agree to keep the observation.
+///
+/// Default value: [`BearingSensorFilterConfig::Range`] with [`RangeFilterConfig::default`].
+///
+/// # Config example:
+/// ```yaml
+/// filters:
+///   - type: Range
+///     variables: [r]
+///     max_range: [8.]
+///     min_range: [0.5]
+///     inside: true
+/// ```
+#[config_derives]
+#[derive(UIComponent)]
+#[show_all = "Filter"]
+pub enum BearingSensorFilterConfig {
+    /// Range-based filtering on enumerated variables: excludes observations where numeric values fall outside specified bounds.
+    #[check]
+    Range(RangeFilterConfig<BearingSensorVariablesFilter>),
+    /// String pattern filtering on observed object unique id (name for nodes, id for landmarks): excludes observations matching configured regexp patterns.
+    #[check]
+    Id(StringFilterConfig),
+    /// String pattern filtering on observed object labels: excludes observations matching configured regexp patterns.
+    #[check]
+    Label(StringFilterConfig),
+    /// Python-based custom filtering: delegates exclusion logic to user-defined Python methods.
+    #[check]
+    Python(PythonFilterConfig),
+    /// Plugin-based custom filtering: delegates exclusion logic to external compiled or scripted plugins.
+    #[check]
+    External(ExternalFilterConfig),
+}
+
+impl Default for BearingSensorFilterConfig {
+    fn default() -> Self {
+        Self::Range(RangeFilterConfig::default())
+    }
+}
+
+/// Runtime enum containing instantiated bearing sensor filters.
+#[derive(Debug, EnumToString)]
+pub enum BearingSensorFilterType {
+    /// Instantiated range filter for bearing sensor variables.
+    Range(RangeFilter<BearingSensorVariablesFilter>),
+    /// Instantiated string filter for observed object unique id.
+    Id(StringFilter),
+    /// Instantiated string filter for observed object labels.
+    Label(StringFilter),
+    /// Instantiated Python filter for bearing observations.
+    Python(PythonFilter),
+    /// Instantiated external filter for bearing observations.
+    External(ExternalFilter),
+}
+
+impl BearingSensorFilterType {
+    /// Initializes filters that require runtime node context.
+    pub fn post_init(
+        &mut self,
+        node: &mut crate::node::Node,
+        initial_time: f32,
+    ) -> crate::errors::SimbaResult<()> {
+        match self {
+            Self::Python(f) => f.post_init(node, initial_time),
+            Self::External(f) => f.post_init(node, initial_time),
+            Self::Range(_) | Self::Id(_) | Self::Label(_) => Ok(()),
+        }
+    }
+}
+
+/// Configuration of the [`BearingSensor`].
+///
+/// The sensor is a pinhole camera looking forward: a target is seen if it is inside the horizontal
+/// field of view (and the vertical one when the elevation is measured), closer than
+/// `detection_distance` and not obstructed. The elevation is measured to the middle of the target
+/// height, from `sensor_height`.
+///
+/// Default values:
+/// - `detection_distance`: `10.0`
+/// - `fov`: `PI/2`
+/// - `elevation`: `false`
+/// - `vertical_fov`: `PI/2`
+/// - `sensor_height`: `0.0`
+/// - `observe_landmarks`: `true`
+/// - `observe_robots`: `true`
+/// - `xray`: `false`
+/// - `detection`: `None` (all the visible targets are detected)
+/// - `activation_time`: `Some(PeriodicityConfig { period: 0.1, offset: None, table: None })`
+/// - `faults`: empty vector
+/// - `filters`: empty vector
+#[config_derives]
+pub struct BearingSensorConfig {
+    /// Max distance of detection.
+    pub detection_distance: f32,
+    /// Horizontal field of view, in radians, centered on the robot heading.
+    pub fov: f32,
+    /// If true, the elevation of the targets is also measured.
+    pub elevation: bool,
+    /// Vertical field of view, in radians, centered on the horizontal. Used only with `elevation`.
+    pub vertical_fov: f32,
+    /// Height of the sensor, used for the obstructions and the elevation.
+    pub sensor_height: f32,
+    /// Observe the map landmarks.
+    pub observe_landmarks: bool,
+    /// Observe the other robots.
+    pub observe_robots: bool,
+    /// If true, will detect all targets, even if they are behind obstacles (no raycasting).
+    pub xray: bool,
+    /// Stochastic detection model, applied to the visible targets before the filters.
+    #[check]
+    pub detection: Option<DetectionProbabilityConfig>,
+    /// Periodicity configuration of the sensor.
+    #[check]
+    pub activation_time: Option<PeriodicityConfig>,
+    /// Fault model configurations applied after filtering.
+    #[check]
+    pub faults: Vec<BearingSensorFaultModelConfig>,
+    /// Filter configurations applied before fault injection.
+    #[check]
+    pub filters: Vec<BearingSensorFilterConfig>,
+}
+
+impl Check for BearingSensorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.detection_distance < 0. {
+            errors.push(format!(
+                "Detection distance should be positive, got {}",
+                self.detection_distance
+            ));
+        }
+        if self.fov <= 0. || self.fov > 2. * PI {
+            errors.push(format!(
+                "Field of view should be in ]0, 2*PI], got {}",
+                self.fov
+            ));
+        }
+        if self.vertical_fov <= 0. || self.vertical_fov > PI {
+            errors.push(format!(
+                "Vertical field of view should be in ]0, PI], got {}",
+                self.vertical_fov
+            ));
+        }
+        if self.sensor_height < 0. {
+            errors.push(format!(
+                "Sensor height should be positive, got {}",
+                self.sensor_height
+            ));
+        }
+        if !self.observe_landmarks && !self.observe_robots {
+            errors.push("The sensor observes neither landmarks nor robots".to_string());
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for BearingSensorConfig {
+    fn default() -> Self {
+        Self {
+            detection_distance: 10.0,
+            fov: PI / 2.,
+            elevation: false,
+            vertical_fov: PI / 2.,
+            sensor_height: 0.,
+            observe_landmarks: true,
+            observe_robots: true,
+            xray: false,
+            detection: None,
+            activation_time: Some(PeriodicityConfig {
+                period: crate::config::NumberConfig::Num(0.1),
+                offset: None,
+                table: None,
+            }),
+            faults: Vec::new(),
+            filters: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for BearingSensorConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Bearing sensor")
+            .id_salt(format!("bearing-sensor-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Detection distance:");
+                    if self.detection_distance < 0. {
+                        self.detection_distance = 0.;
+                    }
+                    ui.add(egui::DragValue::new(&mut self.detection_distance));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Field of view:");
+                    ui.add(egui::DragValue::new(&mut self.fov).range(0.01..=2. * PI));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Measure elevation:");
+                    ui.checkbox(&mut self.elevation, "");
+                });
+
+                if self.elevation {
+                    ui.horizontal(|ui| {
+                        ui.label("Vertical field of view:");
+                        ui.add(egui::DragValue::new(&mut self.vertical_fov).range(0.01..=PI));
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Sensor height:");
+                    if self.sensor_height < 0. {
+                        self.sensor_height = 0.;
+                    }
+                    ui.add(egui::DragValue::new(&mut self.sensor_height));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Observe landmarks:");
+                    ui.checkbox(&mut self.observe_landmarks, "");
+                    ui.label("Observe robots:");
+                    ui.checkbox(&mut self.observe_robots, "");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("X-Ray mode:");
+                    ui.checkbox(&mut self.xray, "");
+                });
+
+                ui.horizontal(|ui| {
+                    if let Some(d) = &mut self.detection {
+                        d.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove detection model").clicked() {
+                            self.detection = None;
+                        }
+                    } else if ui.button("Add detection model").clicked() {
+                        self.detection = Some(DetectionProbabilityConfig::default());
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    if let Some(p) = &mut self.activation_time {
+                        p.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove activation").clicked() {
+                            self.activation_time = None;
+                        }
+                    } else if ui.button("Add activation").clicked() {
+                        self.activation_time = Self::default().activation_time;
+                    }
+                });
+
+                BearingSensorFilterConfig::show_all_mut(
+                    &mut self.filters,
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+
+                BearingSensorFaultModelConfig::show_all_mut(
+                    &mut self.faults,
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Bearing sensor")
+            .id_salt(format!("bearing-sensor-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Detection distance: {}", self.detection_distance));
+                ui.label(format!("Field of view: {}", self.fov));
+                if self.elevation {
+                    ui.label(format!("Vertical field of view: {}", self.vertical_fov));
+                } else {
+                    ui.label("No elevation");
+                }
+                ui.label(format!("Sensor height: {}", self.sensor_height));
+                ui.label(format!(
+                    "Observe landmarks: {}, observe robots: {}",
+                    self.observe_landmarks, self.observe_robots
+                ));
+                ui.label(format!("X-Ray mode: {}", self.xray));
+
+                if let Some(d) = &self.detection {
+                    d.show(ui, ctx, unique_id);
+                }
+
+                ui.horizontal(|ui| {
+                    if let Some(p) = &self.activation_time {
+                        p.show(ui, ctx, unique_id);
+                    } else {
+                        ui.label("No activation");
+                    }
+                });
+
+                BearingSensorFilterConfig::show_all(&self.filters, ui, ctx, unique_id);
+
+                BearingSensorFaultModelConfig::show_all(&self.faults, ui, ctx, unique_id);
+            });
+    }
+}
+
+/// Record of the [`BearingSensor`], which contains nothing for now.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BearingSensorRecord {
+    last_time: Option<f32>,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for BearingSensorRecord {
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!(
+            "Last time: {}",
+            match self.last_time {
+                Some(t) => t.to_string(),
+                None => "None".to_string(),
+            }
+        ));
+    }
+}
+
+/// Kind of target observed by the [`BearingSensor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BearingTarget {
+    /// Map landmark, the id is the landmark id.
+    #[default]
+    Landmark,
+    /// Other robot, the id is the node name.
+    Robot,
+}
+
+/// Bearing-only observation of a landmark or a robot.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BearingObservation {
+    /// Id of the landmark or name of the robot.
+    pub id: String,
+    /// Kind of the observed target.
+    pub target: BearingTarget,
+    /// Labels associated with the observed target.
+    pub labels: Vec<String>,
+    /// Bearing of the target in the robot frame, in `]-PI, PI]`.
+    pub bearing: f32,
+    /// Elevation of the target, if measured.
+    pub elevation: Option<f32>,
+    /// Fault models applied to this observation.
+    pub applied_faults: Vec<BearingSensorFaultModelConfig>,
+}
+
+impl Recordable<BearingObservationRecord> for BearingObservation {
+    fn record(&self) -> BearingObservationRecord {
+        BearingObservationRecord {
+            id: self.id.clone(),
+            target: self.target,
+            labels: self.labels.clone(),
+            bearing: self.bearing,
+            elevation: self.elevation,
+            applied_faults: self.applied_faults.clone(),
+        }
+    }
+}
+
+/// Serializable record representation of [`BearingObservation`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BearingObservationRecord {
+    /// Id of the landmark or name of the robot.
+    pub id: String,
+    /// Kind of the observed target.
+    pub target: BearingTarget,
+    /// Labels associated with the observed target.
+    pub labels: Vec<String>,
+    /// Bearing of the target in the robot frame.
+    pub bearing: f32,
+    /// Elevation of the target, if measured.
+    pub elevation: Option<f32>,
+    /// Fault models applied at observation generation time.
+    pub applied_faults: Vec<BearingSensorFaultModelConfig>,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for BearingObservationRecord {
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.vertical(|ui| {
+            ui.label(format!("{:?}: {}", self.target, self.id));
+            ui.label("Labels:");
+            for label in &self.labels {
+                ui.label(format!("- {}", label));
+            }
+            ui.label(format!("Bearing: {}", self.bearing));
+            if let Some(elevation) = self.elevation {
+                ui.label(format!("Elevation: {}", elevation));
+            }
+            if self.applied_faults.is_empty() {
+                ui.label("No applied faults.");
+            } else {
+                ui.label("Applied faults:");
+                for fault in &self.applied_faults {
+                    ui.label(format!("{:?}", fault));
+                }
+            }
+        });
+    }
+}
+
+/// Target in sight of the sensor, before the field of view and detection checks.
+struct BearingCandidate {
+    id: String,
+    target: BearingTarget,
+    labels: Vec<String>,
+    /// Position in the robot frame.
+    position: Vector2<f32>,
+    /// Height of the target.
+    height: f32,
+    /// Size used by the detection probability.
+    size: f32,
+    /// Angle between the target orientation and the line of sight.
+    incidence: f32,
+    seed: f32,
+}
+
+/// Bearing and elevation of a target at `position` in the robot frame, `height_difference` above
+/// the sensor.
+fn bearing_elevation(position: &Vector2<f32>, height_difference: f32) -> (f32, f32) {
+    (
+        position.y.atan2(position.x),
+        height_difference.atan2(position.norm()),
+    )
+}
+
+/// Sensor which observes the bearing of the landmarks and of the other robots.
+#[derive(Debug)]
+pub struct BearingSensor {
+    /// Stochastic detection model.
+    detection: Option<DetectionProbability>,
+    /// Detection distance
+    detection_distance: f32,
+    /// Horizontal field of view.
+    fov: f32,
+    /// Vertical field of view, `None` if the elevation is not measured.
+    vertical_fov: Option<f32>,
+    sensor_height: f32,
+    observe_landmarks: bool,
+    observe_robots: bool,
+    /// If true, will detect all targets, even if they are behind obstacles (no raycasting).
+    xray: bool,
+    /// Observation period
+    activation_time: Option<Periodicity>,
+    /// Last observation time.
+    last_time: Option<f32>,
+    faults: Vec<BearingSensorFaultModelType>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
+    filters: Vec<BearingSensorFilterType>,
+}
+
+impl BearingSensor {
+    /// Makes a new [`BearingSensor`] from the given config.
+    pub fn from_config(
+        config: &BearingSensorConfig,
+        plugin_api: &Option<Arc<dyn PluginAPI>>,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        initial_time: f32,
+    ) -> SimbaResult<Self> {
+        let mut fault_models = Vec::new();
+        for fault_config in &config.faults {
+            fault_models.push(match &fault_config {
+                BearingSensorFaultModelConfig::Additive(c) => {
+                    BearingSensorFaultModelType::Additive(AdditiveFault::from_config(
+                        c,
+                        va_factory,
+                        initial_time,
+                    ))
+                }
+                BearingSensorFaultModelConfig::Misdetection(c) => {
+                    BearingSensorFaultModelType::Misdetection(MisdetectionFault::from_config(
+                        c,
+                        va_factory,
+                        initial_time,
+                    ))
+                }
+                BearingSensorFaultModelConfig::Calibration(c) => {
+                    BearingSensorFaultModelType::Calibration(CalibrationFault::from_config(
+                        c,
+                        initial_time,
+                    ))
+                }
+                BearingSensorFaultModelConfig::External(c) => {
+                    BearingSensorFaultModelType::External(ExternalFault::from_config(
+                        c,
+                        plugin_api,
+                        global_config,
+                        va_factory,
+                        initial_time,
+                    )?)
+                }
+                BearingSensorFaultModelConfig::Python(c) => BearingSensorFaultModelType::Python(
+                    PythonFaultModel::from_config(c, global_config, initial_time)?,
+                ),
+            });
+        }
+
+        let mut filters = Vec::new();
+        for filter_config in &config.filters {
+            filters.push(match &filter_config {
+                BearingSensorFilterConfig::Range(c) => {
+                    BearingSensorFilterType::Range(RangeFilter::from_config(c, initial_time))
+                }
+                BearingSensorFilterConfig::Id(c) => {
+                    BearingSensorFilterType::Id(StringFilter::from_config(c, initial_time))
+                }
+                BearingSensorFilterConfig::Label(c) => {
+                    BearingSensorFilterType::Label(StringFilter::from_config(c, initial_time))
+                }
+                BearingSensorFilterConfig::External(c) => {
+                    BearingSensorFilterType::External(ExternalFilter::from_config(
+                        c,
+                        plugin_api,
+                        global_config,
+                        va_factory,
+                        initial_time,
+                    )?)
+                }
+                BearingSensorFilterConfig::Python(c) => BearingSensorFilterType::Python(
+                    PythonFilter::from_config(c, global_config, initial_time)?,
+                ),
+            });
+        }
+
+        let activation_time = config
+            .activation_time
+            .as_ref()
+            .map(|p| Periodicity::from_config(p, va_factory, initial_time));
+
+        Ok(Self {
+            detection: config
+                .detection
+                .as_ref()
+                .map(|c| DetectionProbability::from_config(c, va_factory)),
+            detection_distance: config.detection_distance,
+            fov: config.fov,
+            vertical_fov: if config.elevation {
+                Some(config.vertical_fov)
+            } else {
+                None
+            },
+            sensor_height: config.sensor_height,
+            observe_landmarks: config.observe_landmarks,
+            observe_robots: config.observe_robots,
+            xray: config.xray,
+            activation_time,
+            last_time: None,
+            active_faults: vec![true; fault_models.len()],
+            faults: fault_models,
+            filters,
+        })
+    }
+
+    /// Observation of the candidate if it is inside the field of view, `None` otherwise.
+    fn observe(&self, candidate: &BearingCandidate) -> Option<BearingObservation> {
+        let (bearing, elevation) = bearing_elevation(
+            &candidate.position,
+            candidate.height / 2. - self.sensor_height,
+        );
+        if bearing.abs() > self.fov / 2. {
+            return None;
+        }
+        let elevation = match self.vertical_fov {
+            Some(vertical_fov) if elevation.abs() > vertical_fov / 2. => return None,
+            Some(_) => Some(elevation),
+            None => None,
+        };
+        Some(BearingObservation {
+            id: candidate.id.clone(),
+            target: candidate.target,
+            labels: candidate.labels.clone(),
+            bearing,
+            elevation,
+            applied_faults: Vec::new(),
+        })
+    }
+
+    /// Targets in range and not obstructed.
+    fn candidates(&self, node: &Node, state: &State, time: f32) -> Vec<BearingCandidate> {
+        let rotation_matrix = nalgebra::geometry::Rotation2::new(state.pose.z).transpose();
+        let position = state.pose.fixed_rows::<2>(0).clone_owned();
+        let observer_height = if self.xray {
+            None
+        } else {
+            Some(self.sensor_height)
+        };
+        let time_factor = 100. * (time - self.last_time.unwrap_or(-1.));
+        let mut candidates = Vec::new();
+
+        if self.observe_landmarks {
+            for (i, landmark) in node
+                .environment()
+                .get_observable_landmarks(
+                    &position,
+                    observer_height,
+                    self.detection_distance,
+                    Some(node.name()),
+                )
+                .into_iter()
+                .enumerate()
+            {
+                let landmark_position = landmark.pose.fixed_rows::<2>(0).clone_owned();
+                candidates.push(BearingCandidate {
+                    id: landmark.id.to_string(),
+                    target: BearingTarget::Landmark,
+                    position: rotation_matrix * (landmark_position - position),
+                    height: landmark.height,
+                    size: landmark.width,
+                    // Angle between the landmark orientation and the line of sight to the sensor
+                    incidence: (position.y - landmark_position.y)
+                        .atan2(position.x - landmark_position.x)
+                        - landmark.pose.z,
+                    seed: (i + 1) as f32 / time_factor * ((landmark.id + 1) as f32),
+                    labels: landmark.labels,
+                });
+            }
+        }
+
+        if self.observe_robots {
+            let robot_obstructions = match observer_height {
+                Some(_) => node
+                    .environment()
+                    .robot_obstructions(&position, self.detection_distance),
+                None => RobotObstructions::default(),
+            };
+            for (i, other_node_name) in node.other_node_names().iter().enumerate() {
+                let service_manager = node.service_manager();
+                let other_state = match service_manager.read_recover().get_real_state(
+                    &other_node_name.to_string(),
+                    node,
+                    time,
+                ) {
+                    Ok(other_state) => other_state,
+                    Err(e) => {
+                        match e.error_type() {
+                            SimbaErrorTypes::ServiceError(
+                                ServiceError::Unavailable | ServiceError::Closed,
+                            ) => (),
+                            _ => log::error!(
+                                "Error trying to get real state of node {}: {}",
+                                other_node_name,
+                                e.detailed_error()
+                            ),
+                        };
+                        continue;
+                    }
+                };
+                let (labels, size, height) = node
+                    .meta_data_list()
+                    .unwrap()
+                    .read_recover()
+                    .get(other_node_name)
+                    .map_or((Vec::new(), 0., 0.), |md| {
+                        let md = md.read_recover();
+                        (md.labels.clone(), 2. * md.footprint_radius, md.height)
+                    });
+                let other_position = other_state.pose.fixed_rows::<2>(0).clone_owned();
+                if !node.environment().is_target_observable_with(
+                    &other_position,
+                    Some(height),
+                    &position,
+                    observer_height,
+                    self.detection_distance,
+                    Some(node.name()),
+                    &robot_obstructions,
+                ) {
+                    continue;
+                }
+                candidates.push(BearingCandidate {
+                    id: other_node_name.clone(),
+                    target: BearingTarget::Robot,
+                    labels,
+                    position: rotation_matrix * (other_position - position),
+                    height,
+                    size,
+                    // Robots are seen the same from all sides: no incidence effect
+                    incidence: 0.,
+                    seed: (i + 1) as f32 / time_factor + 0.5,
+                });
+            }
+        }
+        candidates
+    }
+}
+
+use crate::node::Node;
+use crate::node::capabilities::{Capabilities, ObservationKind};
+
+impl Sensor for BearingSensor {
+    fn post_init(&mut self, node: &mut Node, initial_time: f32) -> crate::errors::SimbaResult<()> {
+        for filter in self.filters.iter_mut() {
+            filter.post_init(node, initial_time)?;
+        }
+        for fault_model in self.faults.iter_mut() {
+            fault_model.post_init(node, initial_time)?;
+        }
+        Ok(())
+    }
+
+    fn get_observations(&mut self, node: &mut Node, time: f32) -> Vec<SensorObservation> {
+        let mut observation_list = Vec::<SensorObservation>::new();
+        if let Some(last_time) = self.last_time
+            && (time - last_time).abs() < TIME_ROUND
+        {
+            return observation_list;
+        }
+        let state = if let Some(arc_physics) = node.physics() {
+            let physics = arc_physics.read_recover();
+            physics.state(time).clone()
+        } else {
+            State::new() // 0
+        };
+
+        for candidate in self.candidates(node, &state, time) {
+            let Some(observation) = self.observe(&candidate) else {
+                continue;
+            };
+            let range = candidate.position.norm();
+            if let Some(detection) = &self.detection
+                && !detection.detected(
+                    time + candidate.seed,
+                    range,
+                    candidate.incidence,
+                    candidate.size,
+                )
+            {
+                if is_enabled(crate::logger::InternalLog::SensorManagerDetailed) {
+                    debug!("Target {} not detected", candidate.id);
+                }
+                continue;
+            }
+
+            let mut keep_observation = Some(SensorObservation::Bearing(observation));
+
+            for filter in self.filters.iter() {
+                if let Some(obs) = keep_observation {
+                    keep_observation = match filter {
+                        BearingSensorFilterType::External(f) => f.filter(time, obs, &state, None),
+                        BearingSensorFilterType::Python(f) => f.filter(time, obs, &state, None),
+                        BearingSensorFilterType::Range(f) => {
+                            if let SensorObservation::Bearing(obs) = obs {
+                                if f.match_exclusion(&BearingSensorVariablesFilter::mapped_values(
+                                    |variant| match variant {
+                                        BearingSensorVariablesFilter::Bearing => obs.bearing,
+                                        BearingSensorVariablesFilter::Elevation => {
+                                            obs.elevation.unwrap_or(0.)
+                                        }
+                                        BearingSensorVariablesFilter::R => range,
+                                        BearingSensorVariablesFilter::SelfVelocity => {
+                                            state.velocity.fixed_rows::<2>(0).norm()
+                                        }
+                                    },
+                                )) {
+                                    None
+                                } else {
+                                    Some(SensorObservation::Bearing(obs))
+                                }
+                            } else {
+                                unreachable!()
+                            }
+                        }
+                        BearingSensorFilterType::Id(f) => {
+                            if let SensorObservation::Bearing(obs) = obs {
+                                if f.match_exclusion(std::slice::from_ref(&obs.id)) {
+                                    None
+                                } else {
+                                    Some(SensorObservation::Bearing(obs))
+                                }
+                            } else {
+                                unreachable!()
+                            }
+                        }
+                        BearingSensorFilterType::Label(f) => {
+                            if let SensorObservation::Bearing(obs) = obs {
+                                if f.match_exclusion(&obs.labels) {
+                                    None
+                                } else {
+                                    Some(SensorObservation::Bearing(obs))
+                                }
+                            } else {
+                                unreachable!()
+                            }
+                        }
+                    }
+                } else {
+                    break;
+                }
+            }
+
+            let mut new_obs = Vec::new();
+            if let Some(observation) = keep_observation {
+                new_obs.push(observation); // Not adding directly to observation_list to apply faults only once
+                for (fault_model, _) in self
+                    .faults
+                    .iter_mut()
+                    .zip(&self.active_faults)
+                    .filter(|(_, active)| **active)
+                {
+                    match fault_model {
+                        BearingSensorFaultModelType::Python(f) => f.add_faults(
+                            time,
+                            time + candidate.seed,
+                            &mut new_obs,
+                            SensorObservation::Bearing(BearingObservation::default()),
+                            node.environment(),
+                        ),
+                        BearingSensorFaultModelType::External(f) => f.add_faults(
+                            time,
+                            time + candidate.seed,
+                            &mut new_obs,
+                            SensorObservation::Bearing(BearingObservation::default()),
+                            node.environment(),
+                        ),
+                        BearingSensorFaultModelType::Additive(f) => {
+                            let obs_list_len = new_obs.len();
+                            for (i, obs) in new_obs
+                                .iter_mut()
+                                .map(|o| {
+                                    if let SensorObservation::Bearing(observation) = o {
+                                        observation
+                                    } else {
+                                        unreachable!()
+                                    }
+                                })
+                                .enumerate()
+                            {
+                                let seed =
+                                    time + candidate.seed + i as f32 / (100. * obs_list_len as f32);
+                                let new_values = f.add_faults(
+                                    seed,
+                                    BearingSensorVariablesFaults::mapped_values(|variant| {
+                                        match variant {
+                                            BearingSensorVariablesFaults::Bearing => obs.bearing,
+                                            BearingSensorVariablesFaults::Elevation => {
+                                                obs.elevation.unwrap_or(0.)
+                                            }
+                                        }
+                                    }),
+                                    &BearingSensorVariables::mapped_values(
+                                        |variant| match variant {
+                                            BearingSensorVariables::Bearing => obs.bearing,
+                                            BearingSensorVariables::Elevation => {
+                                                obs.elevation.unwrap_or(0.)
+                                            }
+                                            BearingSensorVariables::R => range,
+                                            BearingSensorVariables::SelfVelocity => {
+                                                state.velocity.fixed_rows::<2>(0).norm()
+                                            }
+                                        },
+                                    ),
+                                );
+                                if let Some(new_bearing) =
+                                    new_values.get(&BearingSensorVariablesFaults::Bearing)
+                                {
+                                    obs.bearing = mod2pi(*new_bearing);
+                                }
+                                if let Some(elevation) = obs.elevation.as_mut()
+                                    && let Some(new_elevation) =
+                                        new_values.get(&BearingSensorVariablesFaults::Elevation)
+                                {
+                                    *elevation = *new_elevation;
+                                }
+                            }
+                        }
+                        BearingSensorFaultModelType::Calibration(f) => {
+                            // Shift of the bearing and of the elevation when seen from the real
+                            // mounting pose, so that the previous faults are kept
+                            let height_difference = candidate.height / 2. - self.sensor_height;
+                            let (position, _) = f.to_sensor_frame(candidate.position, 0.);
+                            let (bearing, elevation) =
+                                bearing_elevation(&position, height_difference);
+                            let (true_bearing, true_elevation) =
+                                bearing_elevation(&candidate.position, height_difference);
+                            let (bearing_shift, elevation_shift) =
+                                (bearing - true_bearing, elevation - true_elevation);
+                            for obs in new_obs.iter_mut().map(|o| {
+                                if let SensorObservation::Bearing(observation) = o {
+                                    observation
+                                } else {
+                                    unreachable!()
+                                }
+                            }) {
+                                obs.bearing = mod2pi(obs.bearing + bearing_shift);
+                                if let Some(obs_elevation) = obs.elevation.as_mut() {
+                                    *obs_elevation += elevation_shift;
+                                }
+                                let new_values =
+                                    f.add_faults(BearingSensorVariablesFaults::mapped_values(
+                                        |variant| match variant {
+                                            BearingSensorVariablesFaults::Bearing => obs.bearing,
+                                            BearingSensorVariablesFaults::Elevation => {
+                                                obs.elevation.unwrap_or(0.)
+                                            }
+                                        },
+                                    ));
+                                if let Some(new_bearing) =
+                                    new_values.get(&BearingSensorVariablesFaults::Bearing)
+                                {
+                                    obs.bearing = mod2pi(*new_bearing);
+                                }
+                                if let Some(elevation) = obs.elevation.as_mut()
+                                    && let Some(new_elevation) =
+                                        new_values.get(&BearingSensorVariablesFaults::Elevation)
+                                {
+                                    *elevation = *new_elevation;
+                                }
+                                obs.applied_faults.push(
+                                    BearingSensorFaultModelConfig::Calibration(f.config().clone()),
+                                );
+                            }
+                        }
+                        BearingSensorFaultModelType::Misdetection(f) => {
+                            new_obs = new_obs
+                                .iter()
+                                .enumerate()
+                                .filter_map(|(i, obs)| {
+                                    if let SensorObservation::Bearing(observation) = obs {
+                                        if f.detected(time + candidate.seed + (i as f32) / 1000.) {
+                                            Some(SensorObservation::Bearing(observation.clone()))
+                                        } else {
+                                            None
+                                        }
+                                    } else {
+                                        unreachable!()
+                                    }
+                                })
+                                .collect();
+                        }
+                    }
+                }
+            } else if is_enabled(crate::logger::InternalLog::SensorManagerDetailed) {
+                debug!("Observation of target {} was filtered out", candidate.id);
+            }
+            observation_list.extend(new_obs);
+        }
+        if let Some(p) = self.activation_time.as_mut() {
+            p.update(time);
+        }
+        self.last_time = Some(time);
+        observation_list
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::Bearing],
+            ..Default::default()
+        }
+    }
+
+    /// Get the next observation time.
+    fn next_time_step(&self) -> f32 {
+        if let Some(activation) = &self.activation_time {
+            activation.next_time()
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        reconfigure_activation(&mut self.activation_time, message, time)?;
+        message.apply_fault_activation(&mut self.active_faults)?;
+        if let Some(noise_scale) = message.noise_scale {
+            for fault_model in self.faults.iter_mut() {
+                fault_model.set_noise_scale(noise_scale);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Recordable<SensorRecord> for BearingSensor {
+    fn record(&self) -> SensorRecord {
+        SensorRecord::BearingSensor(BearingSensorRecord {
+            last_time: self.last_time,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_of_view() {
+        let sensor = BearingSensor::from_config(
+            &BearingSensorConfig {
+                elevation: true,
+                sensor_height: 1.,
+                ..Default::default()
+            },
+            &None,
+            &SimulatorConfig::default(),
+            &Arc::new(DeterministRandomVariableFactory::default()),
+            0.,
+        )
+        .unwrap();
+        let candidate = |x: f32, y: f32, height: f32| BearingCandidate {
+            id: "0".to_string(),
+            target: BearingTarget::Landmark,
+            labels: Vec::new(),
+            position: Vector2::new(x, y),
+            height,
+            size: 0.,
+            incidence: 0.,
+            seed: 0.,
+        };
+
+        let observation = sensor.observe(&candidate(1., 1., 2.)).unwrap();
+        assert!((observation.bearing - PI / 4.).abs() < 1e-6);
+        // Middle of the target at the sensor height
+        assert!(observation.elevation.unwrap().abs() < 1e-6);
+        // Outside the horizontal field of view (PI/2)
+        assert!(sensor.observe(&candidate(1., 2., 2.)).is_none());
+        assert!(sensor.observe(&candidate(-1., 0., 2.)).is_none());
+        // Outside the vertical field of view (PI/2)
+        assert!(sensor.observe(&candidate(1., 0., 6.)).is_none());
+    }
+}
@@ -8,7 +8,7 @@
 use std::sync::Arc;
 
 use super::fault_models::fault_model::FaultModel;
-use super::{Sensor, SensorObservation, SensorRecord};
+use super::{Sensor, SensorObservation, SensorRecord, reconfigure_activation};
 
 use crate::config::NumberConfig;
 use crate::constants::TIME_ROUND;
@@ -17,9 +17,11 @@ use crate::errors::SimbaResult;
 #[cfg(feature = "gui")]
 use crate::gui::UIComponent;
 use crate::logger::is_enabled;
+use crate::networking::reconfigure::ReconfigureMessage;
 use crate::plugin_api::PluginAPI;
 use crate::recordable::Recordable;
 use crate::sensors::fault_models::additive::{AdditiveFault, AdditiveFaultConfig};
+use crate::sensors::fault_models::calibration::{CalibrationFault, CalibrationFaultConfig};
 use crate::sensors::fault_models::external_fault::{ExternalFault, ExternalFaultConfig};
 use crate::sensors::fault_models::python_fault_model::{PythonFaultModel, PythonFaultModelConfig};
 use crate::sensors::sensor_filters::SensorFilter;
@@ -87,6 +89,8 @@ pub enum DisplacementSensorFaultModelConfig {
     Python(PythonFaultModelConfig),
     /// Plugin-provided external fault model.
     External(ExternalFaultConfig),
+    /// Calibration fault model, the displacement is measured at the real mounting pose.
+    Calibration(CalibrationFaultConfig<DisplacementSensorVariablesFaults>),
 }
 
 impl Default for DisplacementSensorFaultModelConfig {
@@ -110,6 +114,8 @@ pub enum DisplacementSensorFaultModelType {
     Python(PythonFaultModel),
     /// Instantiated external fault model.
     External(ExternalFault),
+    /// Instantiated calibration fault model.
+    Calibration(CalibrationFault<DisplacementSensorVariablesFaults>),
 }
 
 impl DisplacementSensorFaultModelType {
@@ -122,7 +128,19 @@ impl DisplacementSensorFaultModelType {
         match self {
             Self::Python(f) => f.post_init(node, initial_time),
             Self::External(f) => f.post_init(node, initial_time),
-            Self::AdditivePreDisplacement(_) | Self::AdditivePostDisplacement(_) => Ok(()),
+            Self::AdditivePreDisplacement(_)
+            | Self::AdditivePostDisplacement(_)
+            | Self::Calibration(_) => Ok(()),
+        }
+    }
+
+    /// Scales the perturbations of the additive fault models, other models are not changed.
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        match self {
+            Self::AdditivePreDisplacement(f) | Self::AdditivePostDisplacement(f) => {
+                f.set_noise_scale(noise_scale)
+            }
+            Self::Python(_) | Self::External(_) | Self::Calibration(_) => {}
         }
     }
 }
@@ -375,6 +393,8 @@ pub struct DisplacementSensor {
     /// Last observation time.
     last_time: Option<f32>,
     faults: Vec<DisplacementSensorFaultModelType>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
     filters: Vec<DisplacementSensorFilterType>,
     lie_movement: bool,
 }
@@ -409,6 +429,12 @@ impl DisplacementSensor {
                         initial_time,
                     )?)
                 }
+                DisplacementSensorFaultModelConfig::Calibration(cfg) => {
+                    DisplacementSensorFaultModelType::Calibration(CalibrationFault::from_config(
+                        cfg,
+                        initial_time,
+                    ))
+                }
                 DisplacementSensorFaultModelConfig::External(cfg) => {
                     DisplacementSensorFaultModelType::External(ExternalFault::from_config(
                         cfg,
@@ -454,6 +480,7 @@ impl DisplacementSensor {
             last_state: initial_state.clone(),
             activation_time,
             last_time: None,
+            active_faults: vec![true; fault_models.len()],
             faults: fault_models,
             filters,
             lie_movement: config.lie_movement,
@@ -462,6 +489,7 @@ impl DisplacementSensor {
 }
 
 use crate::node::Node;
+use crate::node::capabilities::{Capabilities, NodeService, ObservationKind};
 
 impl Sensor for DisplacementSensor {
     fn post_init(&mut self, node: &mut Node, initial_time: f32) -> SimbaResult<()> {
@@ -569,7 +597,12 @@ impl Sensor for DisplacementSensor {
         let mut observation_list = Vec::<SensorObservation>::new();
         if let Some(obs) = keep_observation {
             observation_list.push(obs);
-            for fault_model in self.faults.iter_mut() {
+            for (fault_model, _) in self
+                .faults
+                .iter_mut()
+                .zip(&self.active_faults)
+                .filter(|(_, active)| **active)
+            {
                 match fault_model {
                     DisplacementSensorFaultModelType::Python(f) => f.add_faults(
                         time,
@@ -743,6 +776,65 @@ impl Sensor for DisplacementSensor {
                             }
                         }
                     }
+                    DisplacementSensorFaultModelType::Calibration(f) => {
+                        let mounting = f.mounting_error();
+                        let mounting_position = Vector2::new(mounting.x, mounting.y);
+                        for obs in observation_list.iter_mut() {
+                            if let SensorObservation::Displacement(o) = obs {
+                                // Displacement of the real mounting pose, in the sensor frame
+                                // before displacement
+                                let (translation, _) = f.to_sensor_frame(
+                                    o.translation
+                                        + na::Rotation2::new(o.rotation) * mounting_position,
+                                    o.rotation,
+                                );
+                                o.translation = translation;
+                                let new_values =
+                                    f.add_faults(DisplacementSensorVariablesFaults::mapped_values(
+                                        |variant| match variant {
+                                            DisplacementSensorVariablesFaults::X => o.translation.x,
+                                            DisplacementSensorVariablesFaults::Y => o.translation.y,
+                                            DisplacementSensorVariablesFaults::Rotation => {
+                                                o.rotation
+                                            }
+                                            DisplacementSensorVariablesFaults::Translation => {
+                                                o.translation.norm()
+                                            }
+                                        },
+                                    ));
+                                if let Some(new_x) =
+                                    new_values.get(&DisplacementSensorVariablesFaults::X)
+                                {
+                                    o.translation.x = *new_x;
+                                }
+                                if let Some(new_y) =
+                                    new_values.get(&DisplacementSensorVariablesFaults::Y)
+                                {
+                                    o.translation.y = *new_y;
+                                }
+                                if let Some(new_r) =
+                                    new_values.get(&DisplacementSensorVariablesFaults::Rotation)
+                                {
+                                    o.rotation = *new_r;
+                                }
+                                if let Some(new_t) =
+                                    new_values.get(&DisplacementSensorVariablesFaults::Translation)
+                                {
+                                    let current_t = o.translation.norm();
+                                    if current_t > 0. {
+                                        o.translation *= new_t / current_t;
+                                    }
+                                }
+                                o.applied_faults.push(
+                                    DisplacementSensorFaultModelConfig::Calibration(
+                                        f.config().clone(),
+                                    ),
+                                );
+                            } else {
+                                unreachable!()
+                            }
+                        }
+                    }
                 }
             }
         } else if is_enabled(crate::logger::InternalLog::SensorManagerDetailed) {
@@ -757,6 +849,15 @@ impl Sensor for DisplacementSensor {
         observation_list
     }
 
+    /// Needs the real state of its node.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::Displacement],
+            required_services: vec![NodeService::RealState],
+            ..Default::default()
+        }
+    }
+
     fn next_time_step(&self) -> f32 {
         if let Some(activation) = &self.activation_time {
             activation.next_time()
@@ -764,6 +865,17 @@ impl Sensor for DisplacementSensor {
             f32::INFINITY
         }
     }
+
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        reconfigure_activation(&mut self.activation_time, message, time)?;
+        message.apply_fault_activation(&mut self.active_faults)?;
+        if let Some(noise_scale) = message.noise_scale {
+            for fault_model in self.faults.iter_mut() {
+                fault_model.set_noise_scale(noise_scale);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Recordable<SensorRecord> for DisplacementSensor {
@@ -3,7 +3,8 @@
 //! This module provides the bridge allowing simulator users to plug custom sensor
 //! implementations through [`PluginAPI`].
 //! External sensor creation is delegated to [`PluginAPI::get_sensor`], and observation/record
-//! payloads are exchanged through [`serde_json::Value`].
+//! payloads are exchanged through [`serde_json::Value`]. The observations can be typed with a
+//! schema registered in [`observation_types`](super::observation_types).
 
 use std::sync::Arc;
 
@@ -27,29 +28,139 @@ use crate::{
     plugin_api::PluginAPI, utils::determinist_random_variable::DeterministRandomVariableFactory,
 };
 
+use crate::sensors::observation_types::{self, ObservationSchema};
 use crate::sensors::{Sensor, SensorObservation, SensorRecord};
 use serde_derive::{Deserialize, Serialize};
 
-external_record_python_methods!(
-/// Record for the external sensor (generic).
-ExternalObservationRecord,
-);
+/// Record of an [`ExternalObservation`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[pyclass]
+pub struct ExternalObservationRecord {
+    /// Name of the registered observation type, `None` for an untyped observation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observation_type: Option<String>,
+    /// Record serialized.
+    #[serde(flatten)]
+    pub record: Value,
+}
+
+#[pymethods]
+impl ExternalObservationRecord {
+    #[getter]
+    fn observation_type(&self) -> Option<String> {
+        self.observation_type.clone()
+    }
+
+    #[getter]
+    fn record(&self) -> String {
+        self.record.to_string()
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ExternalObservationRecord {
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        show_payload(ui, self.observation_type.as_deref(), &self.record);
+    }
+}
+
+/// Show the fields of a typed payload, or the JSON of an untyped one.
+#[cfg(feature = "gui")]
+fn show_payload(ui: &mut egui::Ui, observation_type: Option<&str>, payload: &Value) {
+    let Some(schema) = observation_type.and_then(observation_types::observation_type) else {
+        ui.label(payload.to_string());
+        return;
+    };
+    ui.vertical(|ui| {
+        ui.label(format!("Type: {}", schema.name));
+        for field in &schema.fields {
+            let value = payload
+                .get(&field.name)
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            match &field.unit {
+                Some(unit) => ui.label(format!("{}: {value} {unit}", field.name)),
+                None => ui.label(format!("{}: {value}", field.name)),
+            };
+        }
+    });
+}
 
 /// Runtime observation payload emitted by an external sensor.
+///
+/// The payload is typed when built with [`ExternalObservation::new`]: it follows the
+/// [`ObservationSchema`] registered under its `observation_type`.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ExternalObservation {
-    /// Opaque JSON payload produced by the plugin sensor implementation.
+    /// Name of the registered observation type, `None` for an untyped observation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observation_type: Option<String>,
+    /// JSON payload produced by the plugin sensor implementation.
     pub observation: Value,
 }
 
+impl ExternalObservation {
+    /// Observation of type `observation_type`, validated against its registered schema.
+    ///
+    /// Returns an [`ExternalAPIError`](SimbaErrorTypes::ExternalAPIError) if the type is not
+    /// registered or if the payload does not follow the schema.
+    pub fn new(observation_type: &str, observation: &impl serde::Serialize) -> SimbaResult<Self> {
+        let schema = observation_types::observation_type(observation_type).ok_or_else(|| {
+            SimbaError::new(
+                SimbaErrorTypes::ExternalAPIError,
+                format!("Observation type `{observation_type}` is not registered"),
+            )
+        })?;
+        let observation = serde_json::to_value(observation).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ExternalAPIError,
+                format!("`{observation_type}` observation cannot be serialized: {e}"),
+            )
+        })?;
+        schema
+            .validate(&observation)
+            .map_err(|e| SimbaError::new(SimbaErrorTypes::ExternalAPIError, e))?;
+        Ok(Self {
+            observation_type: Some(observation_type.to_string()),
+            observation,
+        })
+    }
+
+    /// Registered schema of the observation, `None` if untyped or not registered.
+    pub fn schema(&self) -> Option<ObservationSchema> {
+        self.observation_type
+            .as_deref()
+            .and_then(observation_types::observation_type)
+    }
+
+    /// Decode the payload if the observation is of type `observation_type`.
+    ///
+    /// Returns `None` for the observations of another type (or untyped), and an
+    /// [`ExternalAPIError`](SimbaErrorTypes::ExternalAPIError) if the payload cannot be
+    /// deserialized into `T`.
+    pub fn decode<T: serde::de::DeserializeOwned>(
+        &self,
+        observation_type: &str,
+    ) -> Option<SimbaResult<T>> {
+        if self.observation_type.as_deref() != Some(observation_type) {
+            return None;
+        }
+        Some(
+            serde_json::from_value(self.observation.clone()).map_err(|e| {
+                SimbaError::new(
+                    SimbaErrorTypes::ExternalAPIError,
+                    format!("`{observation_type}` observation cannot be decoded: {e}"),
+                )
+            }),
+        )
+    }
+}
+
 #[cfg(feature = "gui")]
 impl UIComponent for ExternalObservation {
     fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
         egui::CollapsingHeader::new("External Observation").show(ui, |ui| {
-            ui.vertical(|ui| {
-                ui.label("Observation (JSON):");
-                ui.label(self.observation.to_string());
-            });
+            show_payload(ui, self.observation_type.as_deref(), &self.observation);
         });
     }
 }
@@ -57,6 +168,7 @@ impl UIComponent for ExternalObservation {
 impl Recordable<ExternalObservationRecord> for ExternalObservation {
     fn record(&self) -> ExternalObservationRecord {
         ExternalObservationRecord {
+            observation_type: self.observation_type.clone(),
             record: self.observation.clone(),
         }
     }
@@ -217,6 +217,8 @@ pub struct AdditiveFault<SVO: EnumVariables, SVProp: EnumVariables> {
     apparition: DeterministBernouilliRandomVariable,
     distributions: SharedMutex<Vec<DeterministRandomVariable>>,
     variable_order: Vec<SVO>,
+    /// Scale applied to the sampled perturbations, changed at runtime by reconfiguration.
+    noise_scale: f32,
     config: AdditiveFaultConfig<SVO, SVProp>,
 }
 
@@ -262,6 +264,7 @@ impl<SVO: EnumVariables, SVProp: EnumVariables> AdditiveFault<SVO, SVProp> {
             ),
             distributions,
             variable_order,
+            noise_scale: 1.,
             config,
         }
     }
@@ -284,6 +287,9 @@ impl<SVO: EnumVariables, SVProp: EnumVariables> AdditiveFault<SVO, SVProp> {
         for d in self.distributions.lock().unwrap().iter() {
             random_sample.extend_from_slice(&d.generate(seed));
         }
+        random_sample
+            .iter_mut()
+            .for_each(|v| *v *= self.noise_scale);
 
         if let Some(prop_var) = &self.config.proportional_to {
             if let Some(prop_value) = proportionnal_map.get(prop_var) {
@@ -320,6 +326,16 @@ impl<SVO: EnumVariables, SVProp: EnumVariables> AdditiveFault<SVO, SVProp> {
         diff_map
     }
 
+    /// Sets the scale applied to the sampled perturbations (1 for the configured noise).
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        self.noise_scale = noise_scale;
+    }
+
+    /// Returns the current scale applied to the sampled perturbations.
+    pub fn noise_scale(&self) -> f32 {
+        self.noise_scale
+    }
+
     /// Returns the configuration used to build this additive fault model.
     pub fn config(&self) -> &AdditiveFaultConfig<SVO, SVProp> {
         &self.config
@@ -0,0 +1,380 @@
+//! Calibration fault model.
+//!
+//! This module defines a deterministic fault model reproducing the calibration errors of a
+//! sensor: scale factors on the measured variables, misalignment of a pair of measurement axes,
+//! and a mounting pose of the sensor which differs from the configured one (the robot frame).
+//! Configuration is provided by [`CalibrationFaultConfig`], while runtime execution is handled
+//! by [`CalibrationFault`].
+//!
+//! The mounting error is applied by each sensor according to its geometry, before the
+//! misalignment and the scale factors.
+
+use std::collections::HashMap;
+
+use nalgebra::{Rotation2, Vector2, Vector3};
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::enum_combobox};
+use crate::utils::enum_tools::EnumVariables;
+
+/// Scale factor applied to one variable.
+///
+/// Default values:
+/// - `variable`: first variable of the sensor
+/// - `factor`: `1.0`
+#[config_derives]
+pub struct ScaleFactorConfig<SV: EnumVariables> {
+    /// Scaled variable.
+    pub variable: SV,
+    /// Multiplicative factor applied to the variable.
+    pub factor: f32,
+}
+
+impl<SV: EnumVariables> Default for ScaleFactorConfig<SV> {
+    fn default() -> Self {
+        Self {
+            variable: SV::to_vec().first().unwrap().clone(),
+            factor: 1.,
+        }
+    }
+}
+
+impl<SV: EnumVariables> Check for ScaleFactorConfig<SV> {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        Ok(())
+    }
+}
+
+/// Misalignment of the two axes of a planar measurement.
+///
+/// The measured x axis is rotated by `angle`, and the measured y axis by
+/// `angle + non_orthogonality`, so the measured components are the projections of the real
+/// vector `(x_axis, y_axis)` on the misaligned axes.
+///
+/// Default values:
+/// - `x_axis`: first variable of the sensor
+/// - `y_axis`: second variable of the sensor
+/// - `angle`: `0.0`
+/// - `non_orthogonality`: `0.0`
+#[config_derives]
+pub struct AxisMisalignmentConfig<SV: EnumVariables> {
+    /// Variable of the x component.
+    pub x_axis: SV,
+    /// Variable of the y component.
+    pub y_axis: SV,
+    /// Rotation of the measurement axes, in radians.
+    pub angle: f32,
+    /// Additional rotation of the y axis, in radians.
+    pub non_orthogonality: f32,
+}
+
+impl<SV: EnumVariables> Default for AxisMisalignmentConfig<SV> {
+    fn default() -> Self {
+        let variables = SV::to_vec();
+        Self {
+            x_axis: variables.first().unwrap().clone(),
+            y_axis: variables.get(1).or(variables.first()).unwrap().clone(),
+            angle: 0.,
+            non_orthogonality: 0.,
+        }
+    }
+}
+
+impl<SV: EnumVariables> Check for AxisMisalignmentConfig<SV> {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.x_axis == self.y_axis {
+            return Err(vec![format!(
+                "Misalignment axes should be different, got `{}` twice",
+                self.x_axis
+            )]);
+        }
+        Ok(())
+    }
+}
+
+/// Configuration of the calibration fault model.
+///
+/// Default values:
+/// - `scale_factors`: empty vector
+/// - `misalignment`: `None`
+/// - `mounting_error`: `[0.0, 0.0, 0.0]`
+///
+/// # Example
+/// ```yaml
+/// faults:
+///  - type: Calibration
+///    scale_factors:
+///      - variable: r
+///        factor: 1.02
+///    misalignment: null
+///    mounting_error: [0.05, 0., 0.02]
+/// ```
+#[config_derives]
+pub struct CalibrationFaultConfig<SV: EnumVariables> {
+    /// Scale factors applied to the measured variables.
+    #[check]
+    pub scale_factors: Vec<ScaleFactorConfig<SV>>,
+    /// Misalignment of a pair of measurement axes.
+    #[check]
+    pub misalignment: Option<AxisMisalignmentConfig<SV>>,
+    /// Real pose `[x, y, theta]` of the sensor in the robot frame, where the sensor is assumed to
+    /// be mounted.
+    pub mounting_error: [f32; 3],
+}
+
+impl<SV: EnumVariables> Default for CalibrationFaultConfig<SV> {
+    fn default() -> Self {
+        Self {
+            scale_factors: Vec::new(),
+            misalignment: None,
+            mounting_error: [0.; 3],
+        }
+    }
+}
+
+impl<SV: EnumVariables> Check for CalibrationFaultConfig<SV> {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for (i, scale_factor) in self.scale_factors.iter().enumerate() {
+            if self.scale_factors[..i]
+                .iter()
+                .any(|other| other.variable == scale_factor.variable)
+            {
+                errors.push(format!(
+                    "Scale factor of the variable `{}` is defined twice",
+                    scale_factor.variable
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl<SV: EnumVariables> UIComponent for CalibrationFaultConfig<SV> {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &crate::simulator::SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        ui.vertical(|ui| {
+            ui.label("Scale factors:");
+            let mut to_remove = None;
+            for (i, scale_factor) in self.scale_factors.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    enum_combobox(
+                        ui,
+                        &mut scale_factor.variable,
+                        format!("calibration-scale-{i}-{unique_id}"),
+                    );
+                    ui.add(egui::DragValue::new(&mut scale_factor.factor).speed(0.001));
+                    if ui.button("X").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_remove {
+                self.scale_factors.remove(i);
+            }
+            if ui.button("Add scale factor").clicked() {
+                self.scale_factors.push(ScaleFactorConfig::default());
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Misalignment:");
+                if self.misalignment.is_some() {
+                    if ui.button("X").clicked() {
+                        self.misalignment = None;
+                    }
+                } else if ui.button("+").clicked() {
+                    self.misalignment = Some(AxisMisalignmentConfig::default());
+                }
+            });
+            if let Some(misalignment) = &mut self.misalignment {
+                ui.horizontal(|ui| {
+                    ui.label("Axes:");
+                    enum_combobox(
+                        ui,
+                        &mut misalignment.x_axis,
+                        format!("calibration-x-axis-{unique_id}"),
+                    );
+                    enum_combobox(
+                        ui,
+                        &mut misalignment.y_axis,
+                        format!("calibration-y-axis-{unique_id}"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Angle / non orthogonality:");
+                    ui.add(egui::DragValue::new(&mut misalignment.angle).speed(0.001));
+                    ui.add(egui::DragValue::new(&mut misalignment.non_orthogonality).speed(0.001));
+                });
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Mounting error (x, y, theta):");
+                for value in self.mounting_error.iter_mut() {
+                    ui.add(egui::DragValue::new(value).speed(0.001));
+                }
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.vertical(|ui| {
+            for scale_factor in &self.scale_factors {
+                ui.label(format!(
+                    "Scale factor of {}: {}",
+                    scale_factor.variable, scale_factor.factor
+                ));
+            }
+            if let Some(misalignment) = &self.misalignment {
+                ui.label(format!(
+                    "Misalignment of ({}, {}): angle {}, non orthogonality {}",
+                    misalignment.x_axis,
+                    misalignment.y_axis,
+                    misalignment.angle,
+                    misalignment.non_orthogonality
+                ));
+            }
+            ui.label(format!(
+                "Mounting error: ({}, {}, {})",
+                self.mounting_error[0], self.mounting_error[1], self.mounting_error[2]
+            ));
+        });
+    }
+}
+
+/// Runtime calibration fault model.
+///
+/// The errors are deterministic: the same observation always gets the same error.
+#[derive(Debug)]
+pub struct CalibrationFault<SV: EnumVariables> {
+    config: CalibrationFaultConfig<SV>,
+}
+
+impl<SV: EnumVariables> CalibrationFault<SV> {
+    /// Builds a runtime calibration fault model from [`CalibrationFaultConfig`].
+    pub fn from_config(config: &CalibrationFaultConfig<SV>, _initial_time: f32) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    /// Applies the misalignment, then the scale factors, to the variables.
+    ///
+    /// Returns a map containing only modified variables and their new values.
+    pub fn add_faults(&self, variable_map: HashMap<SV, f32>) -> HashMap<SV, f32> {
+        let value = |var: &SV| {
+            *variable_map.get(var).unwrap_or_else(|| {
+                panic!(
+                    "Variable '{}' not accepted in this situation. Accepted variables: [{}]",
+                    var,
+                    variable_map
+                        .keys()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+            })
+        };
+        let mut new_values = HashMap::new();
+        if let Some(misalignment) = &self.config.misalignment {
+            let (x, y) = (value(&misalignment.x_axis), value(&misalignment.y_axis));
+            let y_angle = misalignment.angle + misalignment.non_orthogonality;
+            new_values.insert(
+                misalignment.x_axis.clone(),
+                x * misalignment.angle.cos() + y * misalignment.angle.sin(),
+            );
+            new_values.insert(
+                misalignment.y_axis.clone(),
+                -x * y_angle.sin() + y * y_angle.cos(),
+            );
+        }
+        for scale_factor in &self.config.scale_factors {
+            let current = new_values
+                .get(&scale_factor.variable)
+                .copied()
+                .unwrap_or_else(|| value(&scale_factor.variable));
+            new_values.insert(scale_factor.variable.clone(), current * scale_factor.factor);
+        }
+        new_values
+    }
+
+    /// Real pose `[x, y, theta]` of the sensor in the robot frame.
+    pub fn mounting_error(&self) -> Vector3<f32> {
+        Vector3::from(self.config.mounting_error)
+    }
+
+    /// Converts a position and an orientation relative to the robot into the real sensor frame,
+    /// as measured by the misplaced sensor.
+    pub fn to_sensor_frame(&self, position: Vector2<f32>, orientation: f32) -> (Vector2<f32>, f32) {
+        let mounting = self.mounting_error();
+        (
+            Rotation2::new(-mounting.z) * (position - mounting.xy()),
+            orientation - mounting.z,
+        )
+    }
+
+    /// World pose of the real sensor frame, from the world `pose` of the robot.
+    pub fn sensor_pose(&self, pose: Vector3<f32>) -> Vector3<f32> {
+        let mounting = self.mounting_error();
+        let position = pose.xy() + Rotation2::new(pose.z) * mounting.xy();
+        Vector3::new(position.x, position.y, pose.z + mounting.z)
+    }
+
+    /// Returns the fault model configuration.
+    pub fn config(&self) -> &CalibrationFaultConfig<SV> {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::sensors::speed_sensor::SpeedSensorVariablesFaults;
+
+    #[test]
+    fn calibration_errors() {
+        let fault = CalibrationFault::from_config(
+            &CalibrationFaultConfig {
+                scale_factors: vec![ScaleFactorConfig {
+                    variable: SpeedSensorVariablesFaults::V,
+                    factor: 2.,
+                }],
+                misalignment: Some(AxisMisalignmentConfig {
+                    x_axis: SpeedSensorVariablesFaults::V,
+                    y_axis: SpeedSensorVariablesFaults::W,
+                    angle: FRAC_PI_2,
+                    non_orthogonality: 0.,
+                }),
+                mounting_error: [1., 0., FRAC_PI_2],
+            },
+            0.,
+        );
+        let new_values = fault.add_faults(HashMap::from([
+            (SpeedSensorVariablesFaults::V, 1.),
+            (SpeedSensorVariablesFaults::W, 0.),
+        ]));
+        // Rotated, then scaled
+        assert!(new_values[&SpeedSensorVariablesFaults::V].abs() < 1e-6);
+        assert!((new_values[&SpeedSensorVariablesFaults::W] + 1.).abs() < 1e-6);
+
+        let (position, orientation) = fault.to_sensor_frame(Vector2::new(2., 0.), 0.);
+        assert!((position - Vector2::new(0., -1.)).norm() < 1e-6);
+        assert_eq!(orientation, -FRAC_PI_2);
+        let pose = fault.sensor_pose(Vector3::new(1., 1., FRAC_PI_2));
+        assert!((pose - Vector3::new(1., 2., std::f32::consts::PI)).norm() < 1e-6);
+    }
+}
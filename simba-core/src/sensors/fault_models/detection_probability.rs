@@ -0,0 +1,277 @@
+//! Stochastic detection model.
+//!
+//! Without this model, a sensor detects every target in range and in line of sight. With it, each
+//! visible target is detected with a probability depending on its range, on the incidence angle of
+//! the line of sight on the target, and on the apparent size of the target. The same model is used
+//! by the landmark and robot sensors, so that sensors can be compared with the same detection
+//! behaviour.
+//!
+//! The probability is the product of `max_probability` and of the enabled factors:
+//! - range: `1 / (1 + (range / half_range)^range_sharpness)`, `0.5` at `half_range`;
+//! - incidence: `|cos(incidence)|^incidence_exponent`, `1` when the target is seen face on;
+//! - size: `1 - exp(-angular_size / reference_angular_size)`, with the angular size of the target
+//!   seen from the sensor. Point targets (size `0`) are not affected by this factor.
+
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+use crate::utils::{
+    determinist_random_variable::{
+        DeterministRandomVariable, DeterministRandomVariableFactory, RandomVariableTypeConfig,
+    },
+    distributions::uniform::UniformRandomVariableConfig,
+};
+
+/// Configuration of the detection probability of a sensor.
+///
+/// Default values:
+/// - `max_probability`: `1.0`
+/// - `half_range`: `None` (no range effect)
+/// - `range_sharpness`: `4.0`
+/// - `incidence_exponent`: `None` (no incidence effect)
+/// - `reference_angular_size`: `None` (no size effect)
+///
+/// # Example
+/// ```yaml
+/// detection:
+///   max_probability: 0.95
+///   half_range: 8.
+///   incidence_exponent: 1.
+/// ```
+#[config_derives]
+pub struct DetectionProbabilityConfig {
+    /// Probability of detection of a close target, seen face on.
+    pub max_probability: f32,
+    /// Range at which the range factor is `0.5`, in meters.
+    pub half_range: Option<f32>,
+    /// Steepness of the decrease of the range factor around `half_range`.
+    pub range_sharpness: f32,
+    /// Exponent of the cosine of the incidence angle.
+    pub incidence_exponent: Option<f32>,
+    /// Angular size of the target (radians) giving a size factor of `1 - 1/e`.
+    pub reference_angular_size: Option<f32>,
+}
+
+impl Check for DetectionProbabilityConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if !(0. ..=1.).contains(&self.max_probability) {
+            errors.push(format!(
+                "Maximum detection probability should be in [0, 1], got {}",
+                self.max_probability
+            ));
+        }
+        if let Some(half_range) = self.half_range
+            && half_range <= 0.
+        {
+            errors.push(format!(
+                "Half range should be strictly positive, got {}",
+                half_range
+            ));
+        }
+        if self.range_sharpness <= 0. {
+            errors.push(format!(
+                "Range sharpness should be strictly positive, got {}",
+                self.range_sharpness
+            ));
+        }
+        if let Some(exponent) = self.incidence_exponent
+            && exponent < 0.
+        {
+            errors.push(format!(
+                "Incidence exponent should be positive, got {}",
+                exponent
+            ));
+        }
+        if let Some(angular_size) = self.reference_angular_size
+            && angular_size <= 0.
+        {
+            errors.push(format!(
+                "Reference angular size should be strictly positive, got {}",
+                angular_size
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for DetectionProbabilityConfig {
+    fn default() -> Self {
+        Self {
+            max_probability: 1.,
+            half_range: None,
+            range_sharpness: 4.,
+            incidence_exponent: None,
+            reference_angular_size: None,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for DetectionProbabilityConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &crate::simulator::SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Detection probability")
+            .id_salt(format!("detection-probability-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Max probability:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.max_probability)
+                            .speed(0.01)
+                            .range(0.0..=1.0),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    let mut enabled = self.half_range.is_some();
+                    ui.checkbox(&mut enabled, "Half range:");
+                    if enabled {
+                        let half_range = self.half_range.get_or_insert(5.);
+                        ui.add(egui::DragValue::new(half_range).range(0.001..=f32::MAX));
+                        ui.label("Sharpness:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.range_sharpness)
+                                .speed(0.1)
+                                .range(0.001..=f32::MAX),
+                        );
+                    } else {
+                        self.half_range = None;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut enabled = self.incidence_exponent.is_some();
+                    ui.checkbox(&mut enabled, "Incidence exponent:");
+                    if enabled {
+                        let exponent = self.incidence_exponent.get_or_insert(1.);
+                        ui.add(
+                            egui::DragValue::new(exponent)
+                                .speed(0.1)
+                                .range(0.0..=f32::MAX),
+                        );
+                    } else {
+                        self.incidence_exponent = None;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut enabled = self.reference_angular_size.is_some();
+                    ui.checkbox(&mut enabled, "Reference angular size:");
+                    if enabled {
+                        let angular_size = self.reference_angular_size.get_or_insert(0.05);
+                        ui.add(
+                            egui::DragValue::new(angular_size)
+                                .speed(0.001)
+                                .range(0.0001..=f32::MAX),
+                        );
+                    } else {
+                        self.reference_angular_size = None;
+                    }
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Detection probability")
+            .id_salt(format!("detection-probability-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Max probability: {}", self.max_probability));
+                if let Some(half_range) = self.half_range {
+                    ui.label(format!(
+                        "Half range: {} (sharpness {})",
+                        half_range, self.range_sharpness
+                    ));
+                }
+                if let Some(exponent) = self.incidence_exponent {
+                    ui.label(format!("Incidence exponent: {}", exponent));
+                }
+                if let Some(angular_size) = self.reference_angular_size {
+                    ui.label(format!("Reference angular size: {}", angular_size));
+                }
+            });
+    }
+}
+
+/// Runtime detection model, drawing the detection of each target.
+#[derive(Debug)]
+pub struct DetectionProbability {
+    config: DetectionProbabilityConfig,
+    draw: DeterministRandomVariable,
+}
+
+impl DetectionProbability {
+    /// Builds a detection model from [`DetectionProbabilityConfig`].
+    pub fn from_config(
+        config: &DetectionProbabilityConfig,
+        va_factory: &DeterministRandomVariableFactory,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            draw: va_factory.make_variable(RandomVariableTypeConfig::Uniform(
+                UniformRandomVariableConfig {
+                    min: vec![0.],
+                    max: vec![1.],
+                },
+            )),
+        }
+    }
+
+    /// Probability of detection of a target at `range`, seen with the `incidence` angle
+    /// (radians, `0` face on), of width `size` (meters, `0` for a point).
+    pub fn probability(&self, range: f32, incidence: f32, size: f32) -> f32 {
+        let mut probability = self.config.max_probability;
+        if let Some(half_range) = self.config.half_range {
+            probability /= 1. + (range / half_range).powf(self.config.range_sharpness);
+        }
+        if let Some(exponent) = self.config.incidence_exponent {
+            probability *= incidence.cos().abs().powf(exponent);
+        }
+        if let Some(reference) = self.config.reference_angular_size
+            && size > 0.
+        {
+            let angular_size = 2. * (size / (2. * range.max(f32::EPSILON))).atan();
+            probability *= 1. - (-angular_size / reference).exp();
+        }
+        probability
+    }
+
+    /// Draws whether the target is detected, for the provided sampling seed.
+    pub fn detected(&self, seed: f32, range: f32, incidence: f32, size: f32) -> bool {
+        self.draw.generate(seed)[0] < self.probability(range, incidence, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probability_factors() {
+        let va_factory = DeterministRandomVariableFactory::new(0.);
+        let model = DetectionProbability::from_config(
+            &DetectionProbabilityConfig {
+                max_probability: 0.8,
+                half_range: Some(10.),
+                incidence_exponent: Some(1.),
+                reference_angular_size: Some(0.1),
+                ..Default::default()
+            },
+            &va_factory,
+        );
+        assert!((model.probability(10., 0., 0.) - 0.4).abs() < 1e-6);
+        assert!((model.probability(10., std::f32::consts::FRAC_PI_3, 0.) - 0.2).abs() < 1e-6);
+        // Wider targets are more likely detected
+        assert!(model.probability(5., 0., 0.1) < model.probability(5., 0., 1.));
+        assert!(!model.detected(0., 1e6, 0., 0.));
+    }
+}
@@ -0,0 +1,120 @@
+//! Scheduled dropouts of a sensor.
+//!
+//! Unlike the random fault models, a [`DropoutTimeline`] drops all the observations of a sensor
+//! during exact time intervals, loaded from a file. It is used to replay outage patterns recorded
+//! on real sensors deterministically.
+//!
+//! The file lists the intervals in seconds, the start being included and the end excluded. It can
+//! be a CSV file (`.csv` extension) with a `start,end` header:
+//! ```csv
+//! start,end
+//! 12.5,14
+//! 30,31.2
+//! ```
+//! or a YAML file:
+//! ```yaml
+//! - start: 12.5
+//!   end: 14
+//! - start: 30
+//!   end: 31.2
+//! ```
+
+use std::path::Path;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    utils::config_parsing::load_config_file,
+};
+
+/// Time interval during which the observations are dropped.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DropoutInterval {
+    /// Start of the dropout (included), in seconds.
+    pub start: f32,
+    /// End of the dropout (excluded), in seconds.
+    pub end: f32,
+}
+
+/// Sorted list of the dropout intervals of a sensor.
+#[derive(Debug, Clone, Default)]
+pub struct DropoutTimeline {
+    intervals: Vec<DropoutInterval>,
+}
+
+impl DropoutTimeline {
+    /// Make a timeline from the given intervals, which can be in any order.
+    ///
+    /// Fails if an interval ends before its start.
+    pub fn new(mut intervals: Vec<DropoutInterval>) -> SimbaResult<Self> {
+        if let Some(interval) = intervals
+            .iter()
+            .find(|interval| interval.end < interval.start)
+        {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Dropout interval ends before its start: [{}, {}]",
+                    interval.start, interval.end
+                ),
+            ));
+        }
+        intervals.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Ok(Self { intervals })
+    }
+
+    /// Load the timeline from a CSV (`.csv` extension) or YAML file.
+    pub fn load_from_path(path: &Path) -> SimbaResult<Self> {
+        let intervals = if path.extension().is_some_and(|ext| ext == "csv") {
+            let csv_error = |e: csv::Error| {
+                SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    format!(
+                        "Error while reading dropout timeline file '{}': {e}",
+                        path.display()
+                    ),
+                )
+            };
+            csv::Reader::from_path(path)
+                .map_err(csv_error)?
+                .deserialize()
+                .collect::<Result<Vec<DropoutInterval>, _>>()
+                .map_err(csv_error)?
+        } else {
+            load_config_file(path, "dropout timeline")?
+        };
+        Self::new(intervals)
+    }
+
+    /// Returns `true` if the observations made at `time` are dropped.
+    pub fn is_dropped(&self, time: f32) -> bool {
+        self.intervals
+            .iter()
+            .take_while(|interval| interval.start <= time)
+            .any(|interval| time < interval.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropped_inside_intervals_only() {
+        let timeline = DropoutTimeline::new(vec![
+            DropoutInterval { start: 5., end: 6. },
+            DropoutInterval {
+                start: 1.,
+                end: 2.5,
+            },
+        ])
+        .unwrap();
+        assert!(!timeline.is_dropped(0.5));
+        assert!(timeline.is_dropped(1.));
+        assert!(timeline.is_dropped(2.));
+        assert!(!timeline.is_dropped(2.5));
+        assert!(timeline.is_dropped(5.5));
+        assert!(!timeline.is_dropped(7.));
+    }
+}
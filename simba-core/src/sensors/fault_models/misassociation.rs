@@ -33,6 +33,7 @@ use crate::{
             bernouilli::{BernouilliRandomVariableConfig, DeterministBernouilliRandomVariable},
             uniform::UniformRandomVariableConfig,
         },
+        lock_recovery::{RecoverRoLock, RecoverRwLock},
     },
 };
 
@@ -246,12 +247,11 @@ impl MisassociationFault {
         let mut id_list: Vec<(String, Vector2<f32>)> = match &self.source {
             Source::Robots => environment
                 .get_meta_data()
-                .read()
-                .unwrap()
+                .read_recover()
                 .iter()
                 .filter_map(|(name, data)| {
-                    if matches!(data.read().unwrap().state, NodeState::Running)
-                        && let Some(position) = data.read().unwrap().position
+                    if matches!(data.read_recover().state, NodeState::Running)
+                        && let Some(position) = data.read_recover().position
                     {
                         Some((name.clone(), Vector2::new(position[0], position[1])))
                     } else {
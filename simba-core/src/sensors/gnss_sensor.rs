@@ -10,16 +10,18 @@
 use std::sync::Arc;
 
 use super::fault_models::fault_model::FaultModel;
-use super::{Sensor, SensorObservation, SensorRecord};
+use super::{Sensor, SensorObservation, SensorRecord, reconfigure_activation};
 
 use crate::constants::TIME_ROUND;
 use crate::errors::SimbaResult;
 #[cfg(feature = "gui")]
 use crate::gui::UIComponent;
 use crate::logger::is_enabled;
+use crate::networking::reconfigure::ReconfigureMessage;
 use crate::plugin_api::PluginAPI;
 use crate::recordable::Recordable;
 use crate::sensors::fault_models::additive::{AdditiveFault, AdditiveFaultConfig};
+use crate::sensors::fault_models::calibration::{CalibrationFault, CalibrationFaultConfig};
 use crate::sensors::fault_models::clutter::{ClutterFault, ClutterFaultConfig};
 use crate::sensors::fault_models::external_fault::{ExternalFault, ExternalFaultConfig};
 use crate::sensors::fault_models::misdetection::{MisdetectionFault, MisdetectionFaultConfig};
@@ -85,6 +87,8 @@ pub enum GNSSSensorFaultModelConfig {
     Clutter(ClutterFaultConfig<GNSSSensorVariablesFaults>),
     /// Misdetection fault model.
     Misdetection(MisdetectionFaultConfig),
+    /// Calibration fault model, with the mounting error as the antenna lever arm.
+    Calibration(CalibrationFaultConfig<GNSSSensorVariablesFaults>),
     /// Plugin-provided external fault model.
     External(ExternalFaultConfig),
     /// Python-implemented fault model.
@@ -106,6 +110,8 @@ pub enum GNSSSensorFaultModelType {
     Clutter(ClutterFault<GNSSSensorVariablesFaults>),
     /// Instantiated misdetection fault model.
     Misdetection(MisdetectionFault),
+    /// Instantiated calibration fault model.
+    Calibration(CalibrationFault<GNSSSensorVariablesFaults>),
     /// Instantiated external fault model.
     External(ExternalFault),
     /// Instantiated Python fault model.
@@ -122,7 +128,21 @@ impl GNSSSensorFaultModelType {
         match self {
             Self::Python(f) => f.post_init(node, initial_time),
             Self::External(f) => f.post_init(node, initial_time),
-            Self::Additive(_) | Self::Clutter(_) | Self::Misdetection(_) => Ok(()),
+            Self::Additive(_) | Self::Clutter(_) | Self::Misdetection(_) | Self::Calibration(_) => {
+                Ok(())
+            }
+        }
+    }
+
+    /// Scales the perturbations of the additive fault models, other models are not changed.
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        match self {
+            Self::Additive(f) => f.set_noise_scale(noise_scale),
+            Self::Python(_)
+            | Self::External(_)
+            | Self::Clutter(_)
+            | Self::Misdetection(_)
+            | Self::Calibration(_) => {}
         }
     }
 }
@@ -359,6 +379,8 @@ pub struct GNSSSensor {
     last_time: Option<f32>,
     /// Fault models for x and y positions and on x and y velocities
     faults: Vec<GNSSSensorFaultModelType>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
     filters: Vec<GNSSSensorFilterType>,
 }
 
@@ -387,6 +409,12 @@ impl GNSSSensor {
                         initial_time,
                     ))
                 }
+                GNSSSensorFaultModelConfig::Calibration(config) => {
+                    GNSSSensorFaultModelType::Calibration(CalibrationFault::from_config(
+                        config,
+                        initial_time,
+                    ))
+                }
                 GNSSSensorFaultModelConfig::External(config) => {
                     GNSSSensorFaultModelType::External(ExternalFault::from_config(
                         config,
@@ -430,6 +458,7 @@ impl GNSSSensor {
         Ok(Self {
             activation_time,
             last_time: None,
+            active_faults: vec![true; fault_models.len()],
             faults: fault_models,
             filters,
         })
@@ -437,6 +466,7 @@ impl GNSSSensor {
 }
 
 use crate::node::Node;
+use crate::node::capabilities::{Capabilities, NodeService, ObservationKind};
 
 impl Sensor for GNSSSensor {
     fn post_init(&mut self, node: &mut Node, initial_time: f32) -> crate::errors::SimbaResult<()> {
@@ -518,7 +548,12 @@ impl Sensor for GNSSSensor {
         let mut observation_list = Vec::new();
         if let Some(observation) = keep_observation {
             observation_list.push(observation);
-            for fault_model in self.faults.iter_mut() {
+            for (fault_model, _) in self
+                .faults
+                .iter_mut()
+                .zip(&self.active_faults)
+                .filter(|(_, active)| **active)
+            {
                 match fault_model {
                     GNSSSensorFaultModelType::Python(f) => f.add_faults(
                         time,
@@ -618,6 +653,73 @@ impl Sensor for GNSSSensor {
                                 .push(GNSSSensorFaultModelConfig::Additive(f.config().clone()));
                         }
                     }
+                    GNSSSensorFaultModelType::Calibration(f) => {
+                        for obs in observation_list.iter_mut() {
+                            let SensorObservation::GNSS(obs) = obs else {
+                                unreachable!()
+                            };
+                            // Position and velocity of the antenna
+                            let lever_arm =
+                                na::Rotation2::new(obs.pose.z) * f.mounting_error().xy();
+                            obs.pose = f.sensor_pose(obs.pose);
+                            obs.velocity +=
+                                state.velocity.z * Vector2::new(-lever_arm.y, lever_arm.x);
+
+                            let new_values =
+                                f.add_faults(GNSSSensorVariablesFaults::mapped_values(|variant| {
+                                    match variant {
+                                        GNSSSensorVariablesFaults::X => obs.pose.x,
+                                        GNSSSensorVariablesFaults::Y => obs.pose.y,
+                                        GNSSSensorVariablesFaults::Orientation => obs.pose.z,
+                                        GNSSSensorVariablesFaults::R => {
+                                            obs.pose.fixed_rows::<2>(0).norm()
+                                        }
+                                        GNSSSensorVariablesFaults::Theta => {
+                                            obs.pose.y.atan2(obs.pose.x)
+                                        }
+                                        GNSSSensorVariablesFaults::VelocityX => obs.velocity.x,
+                                        GNSSSensorVariablesFaults::VelocityY => obs.velocity.y,
+                                    }
+                                }));
+                            if let Some(value) = new_values.get(&GNSSSensorVariablesFaults::X) {
+                                obs.pose.x = *value;
+                            }
+                            if let Some(value) = new_values.get(&GNSSSensorVariablesFaults::Y) {
+                                obs.pose.y = *value;
+                            }
+                            if let Some(value) =
+                                new_values.get(&GNSSSensorVariablesFaults::Orientation)
+                            {
+                                obs.pose.z = *value;
+                            }
+                            if new_values.contains_key(&GNSSSensorVariablesFaults::R)
+                                || new_values.contains_key(&GNSSSensorVariablesFaults::Theta)
+                            {
+                                let r = new_values
+                                    .get(&GNSSSensorVariablesFaults::R)
+                                    .copied()
+                                    .unwrap_or(obs.pose.fixed_rows::<2>(0).norm());
+                                let theta = new_values
+                                    .get(&GNSSSensorVariablesFaults::Theta)
+                                    .copied()
+                                    .unwrap_or(obs.pose.y.atan2(obs.pose.x));
+                                obs.pose.x = r * theta.cos();
+                                obs.pose.y = r * theta.sin();
+                            }
+                            if let Some(value) =
+                                new_values.get(&GNSSSensorVariablesFaults::VelocityX)
+                            {
+                                obs.velocity.x = *value;
+                            }
+                            if let Some(value) =
+                                new_values.get(&GNSSSensorVariablesFaults::VelocityY)
+                            {
+                                obs.velocity.y = *value;
+                            }
+                            obs.applied_faults
+                                .push(GNSSSensorFaultModelConfig::Calibration(f.config().clone()));
+                        }
+                    }
                     GNSSSensorFaultModelType::Clutter(f) => {
                         let new_obs_from_clutter = f.add_faults(time, 1. / 100.);
                         for (_, obs_params) in new_obs_from_clutter {
@@ -694,6 +796,15 @@ impl Sensor for GNSSSensor {
         observation_list
     }
 
+    /// Needs the real state of its node.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::GNSS],
+            required_services: vec![NodeService::RealState],
+            ..Default::default()
+        }
+    }
+
     fn next_time_step(&self) -> f32 {
         if let Some(activation) = &self.activation_time {
             activation.next_time()
@@ -701,6 +812,17 @@ impl Sensor for GNSSSensor {
             f32::INFINITY
         }
     }
+
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        reconfigure_activation(&mut self.activation_time, message, time)?;
+        message.apply_fault_activation(&mut self.active_faults)?;
+        if let Some(noise_scale) = message.noise_scale {
+            for fault_model in self.faults.iter_mut() {
+                fault_model.set_noise_scale(noise_scale);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Recordable<SensorRecord> for GNSSSensor {
@@ -0,0 +1,891 @@
+//! IMU sensor implementation.
+//!
+//! This module provides a [`Sensor`] that reports the angular rate and the linear acceleration of
+//! the robot, in the robot frame, derived from the physics state.
+//!
+//! The acceleration is the finite difference of the body-frame velocity between two observations,
+//! with the centripetal term of the rotating frame. Each channel is perturbed by a bias, which
+//! follows a random walk between observations, and by a white noise. The bias and the noise are
+//! drawn from the deterministic random variables of the simulator. Other perturbations can be
+//! added through [`ImuSensorFaultModelConfig`], and observations can be filtered through
+//! [`ImuSensorFilterConfig`].
+
+use std::sync::Arc;
+
+use super::fault_models::fault_model::FaultModel;
+use super::{Sensor, SensorObservation, SensorRecord, reconfigure_activation};
+
+use crate::constants::TIME_ROUND;
+
+use crate::errors::SimbaResult;
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+use crate::logger::is_enabled;
+use crate::networking::reconfigure::ReconfigureMessage;
+use crate::node::Node;
+use crate::node::capabilities::{Capabilities, NodeService, ObservationKind};
+use crate::plugin_api::PluginAPI;
+use crate::recordable::Recordable;
+use crate::sensors::fault_models::additive::{AdditiveFault, AdditiveFaultConfig};
+use crate::sensors::fault_models::calibration::{CalibrationFault, CalibrationFaultConfig};
+use crate::sensors::fault_models::external_fault::{ExternalFault, ExternalFaultConfig};
+use crate::sensors::fault_models::python_fault_model::{PythonFaultModel, PythonFaultModelConfig};
+use crate::sensors::sensor_filters::SensorFilter;
+use crate::sensors::sensor_filters::external_filter::{ExternalFilter, ExternalFilterConfig};
+use crate::sensors::sensor_filters::python_filter::{PythonFilter, PythonFilterConfig};
+use crate::sensors::sensor_filters::range_filter::{RangeFilter, RangeFilterConfig};
+use crate::simulator::SimulatorConfig;
+use crate::state_estimators::{State, StateRecord};
+use crate::utils::determinist_random_variable::{
+    DeterministRandomVariable, DeterministRandomVariableFactory, RandomVariableTypeConfig,
+};
+use crate::utils::distributions::normal::NormalRandomVariableConfig;
+use crate::utils::enum_tools::EnumVariables;
+use crate::utils::periodicity::{Periodicity, PeriodicityConfig};
+use log::debug;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::{EnumToString, UIComponent, config_derives, enum_variables};
+
+enum_variables!(
+    "Variables used by IMU sensors and related fault models."
+    ImuSensorVariables;
+    "Variables that can be used by filters."
+    Filter,
+    "Variables that additive IMU faults can modify."
+    Faults:
+    "Angular rate."
+    W, "w", "angular_velocity", "angular";
+    Filter, Faults:
+    "Longitudinal acceleration."
+    Ax, "ax", "longitudinal_acceleration";
+    Filter, Faults:
+    "Lateral acceleration."
+    Ay, "ay", "lateral_acceleration";
+    Filter:
+    "Norm of the linear robot velocity."
+    SelfVelocity, "self_velocity";
+);
+
+/// Configuration enum selecting fault models applied to IMU observations.
+///
+/// Default value: [`ImuSensorFaultModelConfig::Additive`] with
+/// [`AdditiveFaultConfig::default`].
+#[config_derives]
+#[derive(UIComponent)]
+#[show_all = "Faults"]
+pub enum ImuSensorFaultModelConfig {
+    /// Additive perturbation fault model.
+    Additive(AdditiveFaultConfig<ImuSensorVariablesFaults, ImuSensorVariables>),
+    /// Python-implemented custom fault model.
+    Python(PythonFaultModelConfig),
+    /// Plugin-provided external fault model.
+    External(ExternalFaultConfig),
+    /// Calibration fault model, the accelerations are measured at the real mounting pose.
+    Calibration(CalibrationFaultConfig<ImuSensorVariablesFaults>),
+}
+
+impl Default for ImuSensorFaultModelConfig {
+    fn default() -> Self {
+        Self::Additive(AdditiveFaultConfig::default())
+    }
+}
+
+/// Enum of instantiated fault models applied to IMU observations.
+///
+/// The variants correspond to the ones of [`ImuSensorFaultModelConfig`] but contain instantiated fault models instead of their config.
+#[derive(Debug, EnumToString)]
+pub enum ImuSensorFaultModelType {
+    /// Instantiated additive fault model.
+    Additive(AdditiveFault<ImuSensorVariablesFaults, ImuSensorVariables>),
+    /// Instantiated Python fault model.
+    Python(PythonFaultModel),
+    /// Instantiated external fault model.
+    External(ExternalFault),
+    /// Instantiated calibration fault model.
+    Calibration(CalibrationFault<ImuSensorVariablesFaults>),
+}
+
+impl ImuSensorFaultModelType {
+    /// Wraps the post-initialization of fault models that require runtime node context.
+    pub fn post_init(&mut self, node: &mut Node, initial_time: f32) -> SimbaResult<()> {
+        match self {
+            Self::Additive(_) | Self::Calibration(_) => Ok(()),
+            Self::Python(f) => f.post_init(node, initial_time),
+            Self::External(f) => f.post_init(node, initial_time),
+        }
+    }
+
+    /// Scales the perturbations of the additive fault models, other models are not changed.
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        match self {
+            Self::Additive(f) => f.set_noise_scale(noise_scale),
+            Self::Python(_) | Self::External(_) | Self::Calibration(_) => {}
+        }
+    }
+}
+
+/// Configuration enum selecting among multiple sensor observation filtering strategies for IMU sensors.
+///
+/// When multiple filters are applied to a sensor, all must agree to keep the observation.
+///
+/// Default value: [`ImuSensorFilterConfig::Range`] with [`RangeFilterConfig::default`].
+///
+/// # Config example:
+/// ```yaml
+/// filters:
+///   - type: Range
+///     variables: [self_velocity]
+///     min_range: [0.1]
+///     inside: true
+/// ```
+#[config_derives]
+#[derive(UIComponent)]
+#[show_all = "Filter"]
+pub enum ImuSensorFilterConfig {
+    /// Range-based filtering on enumerated variables: excludes observations where numeric values fall outside specified bounds.
+    #[check]
+    Range(RangeFilterConfig<ImuSensorVariablesFilter>),
+    /// Python-based custom filtering.
+    #[check]
+    Python(PythonFilterConfig),
+    /// Plugin-based custom filtering: delegates exclusion logic to external compiled or scripted plugins.
+    #[check]
+    External(ExternalFilterConfig),
+}
+
+impl Default for ImuSensorFilterConfig {
+    fn default() -> Self {
+        Self::Range(RangeFilterConfig::default())
+    }
+}
+
+/// Runtime enum containing instantiated IMU sensor filters.
+#[derive(Debug, EnumToString)]
+pub enum ImuSensorFilterType {
+    /// Instantiated range filter for IMU sensor variables.
+    Range(RangeFilter<ImuSensorVariablesFilter>),
+    /// Instantiated Python filter for IMU sensor observations.
+    Python(PythonFilter),
+    /// Instantiated external filter for IMU sensor observations.
+    External(ExternalFilter),
+}
+
+impl ImuSensorFilterType {
+    /// Initializes filters that require runtime node context.
+    pub fn post_init(&mut self, node: &mut Node, initial_time: f32) -> SimbaResult<()> {
+        match self {
+            Self::Python(f) => f.post_init(node, initial_time),
+            Self::External(f) => f.post_init(node, initial_time),
+            Self::Range(_) => Ok(()),
+        }
+    }
+}
+
+/// Configuration of the [`ImuSensor`].
+///
+/// The noises are standard deviations of white gaussian noises. The bias random walks are the
+/// standard deviations of the bias increments over one second: the bias drifts by
+/// `random_walk * sqrt(dt)` between two observations spaced by `dt`.
+///
+/// Default values:
+/// - `activation_time`: `Some(PeriodicityConfig { period: 0.1, offset: None, table: None })`
+/// - `gyro_noise`: `0.0`
+/// - `accel_noise`: `0.0`
+/// - `gyro_bias`: `0.0`
+/// - `accel_bias`: `[0.0, 0.0]`
+/// - `gyro_bias_random_walk`: `0.0`
+/// - `accel_bias_random_walk`: `0.0`
+/// - `faults`: empty vector
+/// - `filters`: empty vector
+///
+/// # Example
+/// ```yaml
+/// type: Imu
+/// activation_time:
+///   period: {type: Num, value: 0.01}
+/// gyro_noise: 0.002
+/// accel_noise: 0.05
+/// gyro_bias: 0.01
+/// gyro_bias_random_walk: 0.0005
+/// ```
+#[config_derives]
+pub struct ImuSensorConfig {
+    /// Periodicity of the sensor.
+    #[check]
+    pub activation_time: Option<PeriodicityConfig>,
+    /// Standard deviation of the angular rate noise, in rad/s.
+    pub gyro_noise: f32,
+    /// Standard deviation of the acceleration noise, in m/s².
+    pub accel_noise: f32,
+    /// Initial angular rate bias, in rad/s.
+    pub gyro_bias: f32,
+    /// Initial acceleration bias `[longitudinal, lateral]`, in m/s².
+    pub accel_bias: [f32; 2],
+    /// Angular rate bias random walk, in rad/s/√s.
+    pub gyro_bias_random_walk: f32,
+    /// Acceleration bias random walk, in m/s²/√s.
+    pub accel_bias_random_walk: f32,
+    /// Fault models applied after filtering.
+    #[check]
+    pub faults: Vec<ImuSensorFaultModelConfig>,
+    /// Filter chain applied before fault injection.
+    #[check]
+    pub filters: Vec<ImuSensorFilterConfig>,
+}
+
+impl Check for ImuSensorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for (name, value) in [
+            ("Gyroscope noise", self.gyro_noise),
+            ("Accelerometer noise", self.accel_noise),
+            ("Gyroscope bias random walk", self.gyro_bias_random_walk),
+            (
+                "Accelerometer bias random walk",
+                self.accel_bias_random_walk,
+            ),
+        ] {
+            if value < 0. {
+                errors.push(format!("{} should be positive, got {}", name, value));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for ImuSensorConfig {
+    fn default() -> Self {
+        Self {
+            activation_time: Some(PeriodicityConfig {
+                period: crate::config::NumberConfig::Num(0.1),
+                offset: None,
+                table: None,
+            }),
+            gyro_noise: 0.,
+            accel_noise: 0.,
+            gyro_bias: 0.,
+            accel_bias: [0., 0.],
+            gyro_bias_random_walk: 0.,
+            accel_bias_random_walk: 0.,
+            faults: Vec::new(),
+            filters: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ImuSensorConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("IMU sensor")
+            .id_salt(format!("imu-sensor-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(p) = &mut self.activation_time {
+                        p.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove activation").clicked() {
+                            self.activation_time = None;
+                        }
+                    } else if ui.button("Add activation").clicked() {
+                        self.activation_time = Self::default().activation_time;
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Gyroscope noise (std):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.gyro_noise)
+                            .speed(0.001)
+                            .range(0.0..=f32::MAX),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Accelerometer noise (std):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.accel_noise)
+                            .speed(0.001)
+                            .range(0.0..=f32::MAX),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Gyroscope bias:");
+                    ui.add(egui::DragValue::new(&mut self.gyro_bias).speed(0.001));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Accelerometer bias:");
+                    ui.add(egui::DragValue::new(&mut self.accel_bias[0]).speed(0.001));
+                    ui.add(egui::DragValue::new(&mut self.accel_bias[1]).speed(0.001));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Gyroscope bias random walk:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.gyro_bias_random_walk)
+                            .speed(0.0001)
+                            .range(0.0..=f32::MAX),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Accelerometer bias random walk:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.accel_bias_random_walk)
+                            .speed(0.0001)
+                            .range(0.0..=f32::MAX),
+                    );
+                });
+
+                ImuSensorFilterConfig::show_all_mut(
+                    &mut self.filters,
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+
+                ImuSensorFaultModelConfig::show_all_mut(
+                    &mut self.faults,
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("IMU sensor")
+            .id_salt(format!("imu-sensor-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(p) = &self.activation_time {
+                        p.show(ui, ctx, unique_id);
+                    } else {
+                        ui.label("No activation");
+                    }
+                });
+                ui.label(format!("Gyroscope noise (std): {}", self.gyro_noise));
+                ui.label(format!("Accelerometer noise (std): {}", self.accel_noise));
+                ui.label(format!("Gyroscope bias: {}", self.gyro_bias));
+                ui.label(format!("Accelerometer bias: {:?}", self.accel_bias));
+                ui.label(format!(
+                    "Gyroscope bias random walk: {}",
+                    self.gyro_bias_random_walk
+                ));
+                ui.label(format!(
+                    "Accelerometer bias random walk: {}",
+                    self.accel_bias_random_walk
+                ));
+                ImuSensorFilterConfig::show_all(&self.filters, ui, ctx, unique_id);
+
+                ImuSensorFaultModelConfig::show_all(&self.faults, ui, ctx, unique_id);
+            });
+    }
+}
+
+/// Record of the [`ImuSensor`], with the current biases.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ImuSensorRecord {
+    last_time: Option<f32>,
+    last_state: StateRecord,
+    /// Current angular rate bias.
+    pub gyro_bias: f32,
+    /// Current acceleration bias `[longitudinal, lateral]`.
+    pub accel_bias: [f32; 2],
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ImuSensorRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.label(format!(
+            "Last time: {}",
+            match self.last_time {
+                Some(t) => t.to_string(),
+                None => "None".to_string(),
+            }
+        ));
+        ui.label(format!("Gyroscope bias: {}", self.gyro_bias));
+        ui.label(format!("Accelerometer bias: {:?}", self.accel_bias));
+        ui.label("Last state: ");
+        self.last_state.show(ui, ctx, unique_id);
+    }
+}
+
+/// Observation of the IMU, in the robot frame.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ImuObservation {
+    /// Angular rate.
+    pub angular_velocity: f32,
+    /// Forward linear acceleration.
+    pub longitudinal_acceleration: f32,
+    /// Lateral linear acceleration.
+    pub lateral_acceleration: f32,
+    /// Fault models that were applied to produce this observation.
+    pub applied_faults: Vec<ImuSensorFaultModelConfig>,
+}
+
+impl Recordable<ImuObservationRecord> for ImuObservation {
+    fn record(&self) -> ImuObservationRecord {
+        ImuObservationRecord {
+            angular_velocity: self.angular_velocity,
+            longitudinal_acceleration: self.longitudinal_acceleration,
+            lateral_acceleration: self.lateral_acceleration,
+        }
+    }
+}
+
+/// Serializable record for an [`ImuObservation`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ImuObservationRecord {
+    /// Recorded angular rate.
+    pub angular_velocity: f32,
+    /// Recorded forward linear acceleration.
+    pub longitudinal_acceleration: f32,
+    /// Recorded lateral linear acceleration.
+    pub lateral_acceleration: f32,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ImuObservationRecord {
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.vertical(|ui| {
+            ui.label(format!("Angular velocity: {}", self.angular_velocity));
+            ui.label(format!(
+                "Longitudinal acceleration: {}",
+                self.longitudinal_acceleration
+            ));
+            ui.label(format!(
+                "Lateral acceleration: {}",
+                self.lateral_acceleration
+            ));
+        });
+    }
+}
+
+/// Body-frame acceleration `[longitudinal, lateral]` between two states spaced by `dt`.
+///
+/// The velocities of the [`State`] are in the robot frame, so the rotation of the frame adds the
+/// centripetal term to the derivative of the velocity.
+fn body_acceleration(previous: &State, current: &State, dt: f32) -> [f32; 2] {
+    let (dvx, dvy) = if dt > TIME_ROUND {
+        (
+            (current.velocity.x - previous.velocity.x) / dt,
+            (current.velocity.y - previous.velocity.y) / dt,
+        )
+    } else {
+        (0., 0.)
+    };
+    let w = current.velocity.z;
+    [dvx - w * current.velocity.y, dvy + w * current.velocity.x]
+}
+
+/// Sensor which observes the robot's angular rate and linear acceleration.
+#[derive(Debug)]
+pub struct ImuSensor {
+    /// Last state to compute the acceleration.
+    last_state: State,
+    /// Time of `last_state`.
+    last_state_time: f32,
+    /// Observation period
+    activation_time: Option<Periodicity>,
+    /// Last observation time.
+    last_time: Option<f32>,
+    gyro_noise: f32,
+    accel_noise: f32,
+    gyro_bias: f32,
+    accel_bias: [f32; 2],
+    gyro_bias_random_walk: f32,
+    accel_bias_random_walk: f32,
+    /// Standard normal draws `[w, ax, ay]` of the white noise.
+    noise: DeterministRandomVariable,
+    /// Standard normal draws `[w, ax, ay]` of the bias increments.
+    bias_walk: DeterministRandomVariable,
+    faults: Vec<ImuSensorFaultModelType>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
+    filters: Vec<ImuSensorFilterType>,
+}
+
+impl ImuSensor {
+    /// Makes a new [`ImuSensor`] from the given config.
+    pub fn from_config(
+        config: &ImuSensorConfig,
+        plugin_api: &Option<Arc<dyn PluginAPI>>,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        initial_time: f32,
+    ) -> SimbaResult<Self> {
+        let mut fault_models = Vec::new();
+        for fault_config in &config.faults {
+            fault_models.push(match &fault_config {
+                ImuSensorFaultModelConfig::Additive(c) => ImuSensorFaultModelType::Additive(
+                    AdditiveFault::from_config(c, va_factory, initial_time),
+                ),
+                ImuSensorFaultModelConfig::Python(c) => ImuSensorFaultModelType::Python(
+                    PythonFaultModel::from_config(c, global_config, initial_time)?,
+                ),
+                ImuSensorFaultModelConfig::Calibration(c) => ImuSensorFaultModelType::Calibration(
+                    CalibrationFault::from_config(c, initial_time),
+                ),
+                ImuSensorFaultModelConfig::External(c) => {
+                    ImuSensorFaultModelType::External(ExternalFault::from_config(
+                        c,
+                        plugin_api,
+                        global_config,
+                        va_factory,
+                        initial_time,
+                    )?)
+                }
+            });
+        }
+
+        let mut filters = Vec::new();
+        for filter_config in &config.filters {
+            filters.push(match &filter_config {
+                ImuSensorFilterConfig::Range(c) => {
+                    ImuSensorFilterType::Range(RangeFilter::from_config(c, initial_time))
+                }
+                ImuSensorFilterConfig::Python(c) => ImuSensorFilterType::Python(
+                    PythonFilter::from_config(c, global_config, initial_time)?,
+                ),
+                ImuSensorFilterConfig::External(c) => {
+                    ImuSensorFilterType::External(ExternalFilter::from_config(
+                        c,
+                        plugin_api,
+                        global_config,
+                        va_factory,
+                        initial_time,
+                    )?)
+                }
+            });
+        }
+
+        let standard_normal = || {
+            va_factory.make_variable(RandomVariableTypeConfig::Normal(
+                NormalRandomVariableConfig {
+                    mean: vec![0.; 3],
+                    covariance: vec![1., 0., 0., 0., 1., 0., 0., 0., 1.],
+                },
+            ))
+        };
+
+        let period = config
+            .activation_time
+            .as_ref()
+            .map(|p| Periodicity::from_config(p, va_factory, initial_time));
+        Ok(Self {
+            last_state: State::new(),
+            last_state_time: initial_time,
+            activation_time: period,
+            last_time: None,
+            gyro_noise: config.gyro_noise,
+            accel_noise: config.accel_noise,
+            gyro_bias: config.gyro_bias,
+            accel_bias: config.accel_bias,
+            gyro_bias_random_walk: config.gyro_bias_random_walk,
+            accel_bias_random_walk: config.accel_bias_random_walk,
+            noise: standard_normal(),
+            bias_walk: standard_normal(),
+            active_faults: vec![true; fault_models.len()],
+            faults: fault_models,
+            filters,
+        })
+    }
+
+    /// Makes the biases drift for `dt` seconds.
+    fn walk_biases(&mut self, time: f32, dt: f32) {
+        if dt <= 0. || (self.gyro_bias_random_walk == 0. && self.accel_bias_random_walk == 0.) {
+            return;
+        }
+        let draw = self.bias_walk.generate(time);
+        let sqrt_dt = dt.sqrt();
+        self.gyro_bias += self.gyro_bias_random_walk * sqrt_dt * draw[0];
+        self.accel_bias[0] += self.accel_bias_random_walk * sqrt_dt * draw[1];
+        self.accel_bias[1] += self.accel_bias_random_walk * sqrt_dt * draw[2];
+    }
+}
+
+impl Sensor for ImuSensor {
+    fn post_init(&mut self, node: &mut Node, initial_time: f32) -> SimbaResult<()> {
+        self.last_state = node
+            .physics()
+            .expect("Node with IMU sensor should have Physics")
+            .read()
+            .unwrap()
+            .state(initial_time)
+            .clone();
+        self.last_state_time = initial_time;
+        for filter in self.filters.iter_mut() {
+            filter.post_init(node, initial_time)?;
+        }
+        for fault_model in self.faults.iter_mut() {
+            fault_model.post_init(node, initial_time)?;
+        }
+        Ok(())
+    }
+
+    fn get_observations(&mut self, node: &mut Node, time: f32) -> Vec<SensorObservation> {
+        if let Some(last_time) = self.last_time
+            && (time - last_time).abs() < TIME_ROUND
+        {
+            return Vec::new();
+        }
+        let arc_physic = node
+            .physics()
+            .expect("Node with IMU sensor should have Physics");
+        let physic = arc_physic.read().unwrap();
+        let state = physic.state(time).clone();
+        drop(physic);
+
+        let dt = time - self.last_state_time;
+        self.walk_biases(time, dt);
+        let acceleration = body_acceleration(&self.last_state, &state, dt);
+        let angular_acceleration = if dt > TIME_ROUND {
+            (state.velocity.z - self.last_state.velocity.z) / dt
+        } else {
+            0.
+        };
+        let noise = if self.gyro_noise > 0. || self.accel_noise > 0. {
+            self.noise.generate(time)
+        } else {
+            vec![0.; 3]
+        };
+
+        let obs = SensorObservation::Imu(ImuObservation {
+            angular_velocity: state.velocity.z + self.gyro_bias + self.gyro_noise * noise[0],
+            longitudinal_acceleration: acceleration[0]
+                + self.accel_bias[0]
+                + self.accel_noise * noise[1],
+            lateral_acceleration: acceleration[1]
+                + self.accel_bias[1]
+                + self.accel_noise * noise[2],
+            applied_faults: Vec::new(),
+        });
+
+        let mut keep_observation = Some(obs);
+
+        for filter in self.filters.iter() {
+            if let Some(obs) = keep_observation {
+                keep_observation = match filter {
+                    ImuSensorFilterType::Python(f) => f.filter(time, obs, &state, None),
+                    ImuSensorFilterType::External(f) => f.filter(time, obs, &state, None),
+                    ImuSensorFilterType::Range(f) => {
+                        if let SensorObservation::Imu(obs) = obs {
+                            if f.match_exclusion(&ImuSensorVariablesFilter::mapped_values(
+                                |variant| match variant {
+                                    ImuSensorVariablesFilter::W => obs.angular_velocity,
+                                    ImuSensorVariablesFilter::Ax => obs.longitudinal_acceleration,
+                                    ImuSensorVariablesFilter::Ay => obs.lateral_acceleration,
+                                    ImuSensorVariablesFilter::SelfVelocity => {
+                                        state.velocity.fixed_rows::<2>(0).norm()
+                                    }
+                                },
+                            )) {
+                                None
+                            } else {
+                                Some(SensorObservation::Imu(obs))
+                            }
+                        } else {
+                            unreachable!()
+                        }
+                    }
+                };
+            } else {
+                break;
+            }
+        }
+
+        let mut observation_list = Vec::<SensorObservation>::new();
+        if let Some(obs) = keep_observation {
+            observation_list.push(obs);
+            for (fault_model, _) in self
+                .faults
+                .iter_mut()
+                .zip(&self.active_faults)
+                .filter(|(_, active)| **active)
+            {
+                match fault_model {
+                    ImuSensorFaultModelType::Python(f) => f.add_faults(
+                        time,
+                        time,
+                        &mut observation_list,
+                        SensorObservation::Imu(ImuObservation::default()),
+                        node.environment(),
+                    ),
+                    ImuSensorFaultModelType::External(f) => f.add_faults(
+                        time,
+                        time,
+                        &mut observation_list,
+                        SensorObservation::Imu(ImuObservation::default()),
+                        node.environment(),
+                    ),
+                    ImuSensorFaultModelType::Additive(f) => {
+                        let obs_list_len = observation_list.len();
+                        for (i, obs) in observation_list
+                            .iter_mut()
+                            .map(|o| {
+                                if let SensorObservation::Imu(observation) = o {
+                                    observation
+                                } else {
+                                    unreachable!()
+                                }
+                            })
+                            .enumerate()
+                        {
+                            let seed = time + i as f32 / (100. * obs_list_len as f32);
+                            f.add_faults(
+                                seed,
+                                ImuSensorVariablesFaults::mapped_values(|variant| match variant {
+                                    ImuSensorVariablesFaults::W => obs.angular_velocity,
+                                    ImuSensorVariablesFaults::Ax => obs.longitudinal_acceleration,
+                                    ImuSensorVariablesFaults::Ay => obs.lateral_acceleration,
+                                }),
+                                &ImuSensorVariables::mapped_values(|variant| match variant {
+                                    ImuSensorVariables::W => obs.angular_velocity,
+                                    ImuSensorVariables::Ax => obs.longitudinal_acceleration,
+                                    ImuSensorVariables::Ay => obs.lateral_acceleration,
+                                    ImuSensorVariables::SelfVelocity => {
+                                        state.velocity.fixed_rows::<2>(0).norm()
+                                    }
+                                }),
+                            )
+                            .into_iter()
+                            .for_each(|(variant, value)| match variant {
+                                ImuSensorVariablesFaults::W => obs.angular_velocity += value,
+                                ImuSensorVariablesFaults::Ax => {
+                                    obs.longitudinal_acceleration += value
+                                }
+                                ImuSensorVariablesFaults::Ay => obs.lateral_acceleration += value,
+                            });
+                        }
+                    }
+                    ImuSensorFaultModelType::Calibration(f) => {
+                        let mounting = f.mounting_error();
+                        let w = state.velocity.z;
+                        for obs in observation_list.iter_mut() {
+                            let SensorObservation::Imu(obs) = obs else {
+                                unreachable!()
+                            };
+                            // Acceleration of the real mounting point (tangential and centripetal
+                            // terms of the lever arm), in the sensor frame
+                            let acceleration = nalgebra::Rotation2::new(-mounting.z)
+                                * nalgebra::Vector2::new(
+                                    obs.longitudinal_acceleration
+                                        - angular_acceleration * mounting.y
+                                        - w * w * mounting.x,
+                                    obs.lateral_acceleration + angular_acceleration * mounting.x
+                                        - w * w * mounting.y,
+                                );
+                            obs.longitudinal_acceleration = acceleration.x;
+                            obs.lateral_acceleration = acceleration.y;
+                            f.add_faults(ImuSensorVariablesFaults::mapped_values(|variant| {
+                                match variant {
+                                    ImuSensorVariablesFaults::W => obs.angular_velocity,
+                                    ImuSensorVariablesFaults::Ax => obs.longitudinal_acceleration,
+                                    ImuSensorVariablesFaults::Ay => obs.lateral_acceleration,
+                                }
+                            }))
+                            .into_iter()
+                            .for_each(|(variant, value)| match variant {
+                                ImuSensorVariablesFaults::W => obs.angular_velocity = value,
+                                ImuSensorVariablesFaults::Ax => {
+                                    obs.longitudinal_acceleration = value
+                                }
+                                ImuSensorVariablesFaults::Ay => obs.lateral_acceleration = value,
+                            });
+                            obs.applied_faults
+                                .push(ImuSensorFaultModelConfig::Calibration(f.config().clone()));
+                        }
+                    }
+                }
+            }
+        } else if is_enabled(crate::logger::InternalLog::SensorManagerDetailed) {
+            debug!("IMU observation was filtered out");
+        }
+
+        if let Some(p) = self.activation_time.as_mut() {
+            p.update(time);
+        }
+        self.last_time = Some(time);
+        self.last_state = state;
+        self.last_state_time = time;
+        observation_list
+    }
+
+    /// Needs the real state of its node.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::Imu],
+            required_services: vec![NodeService::RealState],
+            ..Default::default()
+        }
+    }
+
+    fn next_time_step(&self) -> f32 {
+        if let Some(activation) = &self.activation_time {
+            activation.next_time()
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        reconfigure_activation(&mut self.activation_time, message, time)?;
+        message.apply_fault_activation(&mut self.active_faults)?;
+        if let Some(noise_scale) = message.noise_scale {
+            for fault_model in self.faults.iter_mut() {
+                fault_model.set_noise_scale(noise_scale);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Recordable<SensorRecord> for ImuSensor {
+    fn record(&self) -> SensorRecord {
+        SensorRecord::ImuSensor(ImuSensorRecord {
+            last_time: self.last_time,
+            last_state: self.last_state.record(),
+            gyro_bias: self.gyro_bias,
+            accel_bias: self.accel_bias,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acceleration_in_rotating_frame() {
+        let mut previous = State::new();
+        previous.velocity.x = 1.;
+        let mut current = State::new();
+        current.velocity.x = 1.2;
+        current.velocity.z = 0.5;
+        let acceleration = body_acceleration(&previous, &current, 0.1);
+        assert!((acceleration[0] - 2.).abs() < 1e-4);
+        // Centripetal acceleration of the turn
+        assert!((acceleration[1] - 0.6).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,390 @@
+//! 2D LIDAR sensor implementation.
+//!
+//! The LIDAR casts regularly spaced rays over its field of view against the landmarks of the
+//! [`Environment`](crate::environment::Environment). The landmarks with a width are the walls of
+//! the map. It produces a [`ScanObservation`](crate::sensors::scan_sensor::ScanObservation), as
+//! the [`ScanSensor`], with a gaussian range noise.
+//!
+//! The ray casting, the filters and the faults are the ones of the [`ScanSensor`]: the LIDAR is a
+//! scan sensor with a ray layout given by its field of view and angular resolution. The range
+//! noise is drawn per ray, before the filters and the faults.
+
+use std::{f32::consts::PI, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+use crate::{
+    config::NumberConfig,
+    errors::SimbaResult,
+    networking::reconfigure::ReconfigureMessage,
+    node::{
+        Node,
+        capabilities::{Capabilities, ObservationKind},
+    },
+    plugin_api::PluginAPI,
+    recordable::Recordable,
+    sensors::{
+        Sensor, SensorObservation, SensorRecord,
+        scan_sensor::{
+            RayConfig, ScanSensor, ScanSensorConfig, ScanSensorFaultModelConfig,
+            ScanSensorFilterConfig,
+        },
+    },
+    simulator::SimulatorConfig,
+    utils::{
+        determinist_random_variable::DeterministRandomVariableFactory,
+        periodicity::PeriodicityConfig,
+    },
+};
+use config_checker::*;
+
+/// Configuration of the [`LidarSensor`].
+///
+/// Default values:
+/// - `max_range`: `10.0`
+/// - `fov`: `2*pi` (full turn)
+/// - `angular_resolution`: `pi/180` (1 degree)
+/// - `range_noise`: `0.0`
+/// - `height`: `0.0`
+/// - `activation_time`: `Some(PeriodicityConfig { period: 0.1, ..Default::default() })`
+/// - `faults`: empty vector
+/// - `filters`: empty vector
+///
+/// # Example
+/// ```yaml
+/// config:
+///   type: Lidar
+///   max_range: 20.
+///   fov: 4.71
+///   angular_resolution: 0.00873
+///   range_noise: 0.02
+/// ```
+#[config_derives]
+pub struct LidarSensorConfig {
+    /// Maximum range of the rays, in meters.
+    pub max_range: f32,
+    /// Field of view, in radians, centered on the front of the robot.
+    pub fov: f32,
+    /// Angle between two consecutive rays, in radians.
+    pub angular_resolution: f32,
+    /// Standard deviation of the gaussian noise added to the range of each ray, in meters,
+    /// before the filters and the faults.
+    pub range_noise: f32,
+    /// Height of the sensor: will only detect objects at this height or higher.
+    pub height: f32,
+    /// Observation period of the sensor.
+    #[check]
+    pub activation_time: Option<PeriodicityConfig>,
+    /// Fault model configuration list applied after filtering.
+    #[check]
+    pub faults: Vec<ScanSensorFaultModelConfig>,
+    /// Filter configuration list applied before fault injection.
+    #[check]
+    pub filters: Vec<ScanSensorFilterConfig>,
+}
+
+impl Check for LidarSensorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.max_range <= 0. {
+            errors.push(format!(
+                "Maximum range should be strictly positive, got {}",
+                self.max_range
+            ));
+        }
+        if self.fov <= 0. || self.fov > 2. * PI {
+            errors.push(format!(
+                "Field of view should be in ]0, 2*pi], got {}",
+                self.fov
+            ));
+        }
+        if self.angular_resolution <= 0. {
+            errors.push(format!(
+                "Angular resolution should be strictly positive, got {}",
+                self.angular_resolution
+            ));
+        }
+        if self.range_noise < 0. {
+            errors.push(format!(
+                "Range noise should be positive, got {}",
+                self.range_noise
+            ));
+        }
+        if self.height < 0. {
+            errors.push(format!("Height should be positive, got {}", self.height));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for LidarSensorConfig {
+    fn default() -> Self {
+        Self {
+            max_range: 10.,
+            fov: 2. * PI,
+            angular_resolution: PI / 180.,
+            range_noise: 0.,
+            height: 0.,
+            activation_time: Some(PeriodicityConfig {
+                period: NumberConfig::Num(0.1),
+                ..Default::default()
+            }),
+            faults: vec![],
+            filters: vec![],
+        }
+    }
+}
+
+impl LidarSensorConfig {
+    /// Scan sensor configuration casting the rays of this LIDAR.
+    pub fn scan_config(&self) -> ScanSensorConfig {
+        ScanSensorConfig {
+            detection_distance: self.max_range,
+            rays: RayConfig::RadianTable(lidar_rays(self.fov, self.angular_resolution)),
+            height: self.height,
+            activation_time: self.activation_time.clone(),
+            faults: self.faults.clone(),
+            filters: self.filters.clone(),
+        }
+    }
+}
+
+/// Angles of the rays, in the sensor frame, spaced by `angular_resolution` over `fov`.
+///
+/// The rays are centered on the front of the sensor. For a full turn, the last ray is not
+/// duplicated at `pi`.
+fn lidar_rays(fov: f32, angular_resolution: f32) -> Vec<f32> {
+    // Tolerance on the number of steps, to keep the last ray despite the rounding
+    let steps = fov / angular_resolution + 1e-3;
+    if fov >= 2. * PI - 1e-4 {
+        (0..steps.floor() as usize)
+            .map(|i| -PI + i as f32 * angular_resolution)
+            .collect()
+    } else {
+        (0..steps.floor() as usize + 1)
+            .map(|i| -fov / 2. + i as f32 * angular_resolution)
+            .collect()
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for LidarSensorConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Lidar sensor")
+            .id_salt(format!("lidar-sensor-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Max range:");
+                    ui.add(egui::DragValue::new(&mut self.max_range).range(0.001..=f32::MAX));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Field of view:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.fov)
+                            .speed(0.01)
+                            .range(0.001..=2. * PI),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Angular resolution:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.angular_resolution)
+                            .speed(0.001)
+                            .range(0.0001..=2. * PI),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Range noise (std):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.range_noise)
+                            .speed(0.001)
+                            .range(0.0..=f32::MAX),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Height:");
+                    ui.add(egui::DragValue::new(&mut self.height).range(0.0..=f32::MAX));
+                });
+
+                ui.horizontal(|ui| {
+                    if let Some(p) = &mut self.activation_time {
+                        p.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove activation").clicked() {
+                            self.activation_time = None;
+                        }
+                    } else if ui.button("Add activation").clicked() {
+                        self.activation_time = Some(Self::default().activation_time.unwrap());
+                    }
+                });
+
+                ScanSensorFilterConfig::show_all_mut(
+                    &mut self.filters,
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+
+                ScanSensorFaultModelConfig::show_all_mut(
+                    &mut self.faults,
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Lidar sensor")
+            .id_salt(format!("lidar-sensor-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Max range: {}", self.max_range));
+                ui.label(format!("Field of view: {}", self.fov));
+                ui.label(format!("Angular resolution: {}", self.angular_resolution));
+                ui.label(format!("Range noise (std): {}", self.range_noise));
+                ui.label(format!("Height: {}", self.height));
+
+                if let Some(p) = &self.activation_time {
+                    ui.horizontal(|ui| {
+                        p.show(ui, ctx, unique_id);
+                    });
+                }
+
+                ScanSensorFilterConfig::show_all(&self.filters, ui, ctx, unique_id);
+
+                ScanSensorFaultModelConfig::show_all(&self.faults, ui, ctx, unique_id);
+            });
+    }
+}
+
+/// Record of the LIDAR sensor internal state.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LidarSensorRecord {
+    last_time: Option<f32>,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for LidarSensorRecord {
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!(
+            "Last time: {}",
+            match self.last_time {
+                Some(t) => t.to_string(),
+                None => "None".to_string(),
+            }
+        ));
+    }
+}
+
+/// 2D LIDAR emitting [`ScanObservation`](crate::sensors::scan_sensor::ScanObservation)s.
+#[derive(Debug)]
+pub struct LidarSensor {
+    scan: ScanSensor,
+    last_time: Option<f32>,
+}
+
+impl LidarSensor {
+    /// Builds a new [`LidarSensor`] from [`LidarSensorConfig`].
+    pub fn from_config(
+        config: &LidarSensorConfig,
+        plugin_api: &Option<Arc<dyn PluginAPI>>,
+        global_config: &SimulatorConfig,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+        initial_time: f32,
+    ) -> SimbaResult<Self> {
+        Ok(Self {
+            scan: ScanSensor::from_config(
+                &config.scan_config(),
+                plugin_api,
+                global_config,
+                va_factory,
+                initial_time,
+            )?
+            .with_range_noise(config.range_noise, va_factory),
+            last_time: None,
+        })
+    }
+}
+
+impl Sensor for LidarSensor {
+    fn post_init(&mut self, node: &mut Node, initial_time: f32) -> SimbaResult<()> {
+        self.scan.post_init(node, initial_time)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::Scan],
+            ..Default::default()
+        }
+    }
+
+    fn next_time_step(&self) -> f32 {
+        self.scan.next_time_step()
+    }
+
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        self.scan.reconfigure(message, time)
+    }
+
+    fn get_observations(&mut self, node: &mut Node, time: f32) -> Vec<SensorObservation> {
+        let observations = self.scan.get_observations(node, time);
+        if !observations.is_empty() {
+            self.last_time = Some(time);
+        }
+        observations
+    }
+}
+
+impl Recordable<SensorRecord> for LidarSensor {
+    fn record(&self) -> SensorRecord {
+        SensorRecord::LidarSensor(LidarSensorRecord {
+            last_time: self.last_time,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rays_cover_the_field_of_view() {
+        let rays = lidar_rays(PI / 2., PI / 4.);
+        assert_eq!(rays.len(), 3);
+        assert!((rays[0] + PI / 4.).abs() < 1e-6);
+        assert!((rays[2] - PI / 4.).abs() < 1e-6);
+
+        // Full turn: the ray at pi is the one at -pi
+        let rays = lidar_rays(2. * PI, PI / 180.);
+        assert_eq!(rays.len(), 360);
+        assert!((rays[0] + PI).abs() < 1e-6);
+        assert!((rays[359] - PI + PI / 180.).abs() < 1e-4);
+    }
+}
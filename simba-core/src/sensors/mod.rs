@@ -11,9 +11,14 @@
 //! 2. Add a corresponding variant to [`SensorConfig`]
 //! 3. Add a corresponding variant to [`SensorRecord`]
 
+pub mod bearing_sensor;
 pub mod displacement_sensor;
 pub mod external_sensor;
 pub mod gnss_sensor;
+pub mod imu_sensor;
+pub mod lidar_sensor;
+pub mod observation_frame;
+pub mod observation_types;
 pub mod oriented_landmark_sensor;
 pub mod robot_sensor;
 pub mod scan_sensor;
@@ -39,13 +44,21 @@ use {
 
 use crate::{
     errors::SimbaResult,
-    node::Node,
+    networking::reconfigure::ReconfigureMessage,
+    node::{
+        Node,
+        capabilities::{Capabilities, ObservationKind},
+    },
     recordable::Recordable,
     sensors::{
+        bearing_sensor::{BearingObservation, BearingObservationRecord},
         displacement_sensor::{DisplacementObservation, DisplacementObservationRecord},
         external_sensor::{ExternalObservation, ExternalObservationRecord},
+        imu_sensor::{ImuObservation, ImuObservationRecord},
+        observation_frame::ObservationFrame,
         scan_sensor::{ScanObservation, ScanObservationRecord},
     },
+    utils::periodicity::Periodicity,
 };
 #[cfg(feature = "gui")]
 use crate::{
@@ -65,6 +78,8 @@ pub struct Observation {
     pub time: f32,
     /// Sensor-specific observation payload.
     pub sensor_observation: SensorObservation,
+    /// Frame in which the observation is expressed.
+    pub frame: ObservationFrame,
 }
 
 impl Observation {
@@ -75,8 +90,15 @@ impl Observation {
             observer: "someone".to_string(),
             time: 0.,
             sensor_observation: SensorObservation::Speed(SpeedObservation::default()),
+            frame: ObservationFrame::Robot,
         }
     }
+
+    /// Whether the observation is expressed in the native frame of its kind (see
+    /// [`ObservationFrame::native`]).
+    pub fn in_native_frame(&self) -> bool {
+        self.frame == ObservationFrame::native(ObservationKind::from(&self.sensor_observation))
+    }
 }
 
 impl Default for Observation {
@@ -92,6 +114,7 @@ impl Recordable<ObservationRecord> for Observation {
             observer: self.observer.clone(),
             time: self.time,
             sensor_observation: self.sensor_observation.record(),
+            frame: self.frame.clone(),
         }
     }
 }
@@ -107,6 +130,30 @@ pub struct ObservationRecord {
     pub time: f32,
     /// Sensor-specific recorded payload.
     pub sensor_observation: SensorObservationRecord,
+    /// Frame in which the observation is expressed.
+    #[serde(default)]
+    pub frame: ObservationFrame,
+}
+
+/// Delay between the generation of an observation and its use by a state estimator.
+///
+/// The latency includes the network delays (observations sent by other nodes) and the waiting
+/// time until the next correction step of the estimator.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ObservationLatencyRecord {
+    /// Sensor name that produced the observation.
+    pub sensor_name: String,
+    /// Name of the observing node.
+    pub observer: String,
+    /// Name of the state estimator which consumed the observation (`state_estimator` for the main
+    /// one, or the name of the bench estimator).
+    pub estimator: String,
+    /// Simulation time at which the observation was generated.
+    pub observation_time: f32,
+    /// Simulation time at which the estimator consumed the observation.
+    pub consumption_time: f32,
+    /// `consumption_time - observation_time`.
+    pub latency: f32,
 }
 
 // Implementation of traits needed for sorting observations, first by time, then by sensor name, then by observer.
@@ -143,6 +190,7 @@ impl UIComponent for ObservationRecord {
         ui.label(format!("Sensor name: {}", self.sensor_name));
         ui.label(format!("Observer: {}", self.observer));
         ui.label(format!("Time: {}", self.time));
+        ui.label(format!("Frame: {}", self.frame));
         self.sensor_observation.show(ui, ctx, unique_id);
     }
 }
@@ -164,6 +212,10 @@ pub enum SensorObservation {
     Scan(ScanObservation),
     /// External sensor observation payload.
     External(ExternalObservation),
+    /// IMU sensor observation payload.
+    Imu(ImuObservation),
+    /// Bearing sensor observation payload.
+    Bearing(BearingObservation),
 }
 
 impl Recordable<SensorObservationRecord> for SensorObservation {
@@ -180,6 +232,8 @@ impl Recordable<SensorObservationRecord> for SensorObservation {
             }
             SensorObservation::Scan(o) => SensorObservationRecord::Scan(o.record()),
             SensorObservation::External(o) => SensorObservationRecord::External(o.record()),
+            SensorObservation::Imu(o) => SensorObservationRecord::Imu(o.record()),
+            SensorObservation::Bearing(o) => SensorObservationRecord::Bearing(o.record()),
         }
     }
 }
@@ -201,6 +255,10 @@ pub enum SensorObservationRecord {
     Scan(ScanObservationRecord),
     /// Record payload for external observations.
     External(ExternalObservationRecord),
+    /// Record payload for IMU observations.
+    Imu(ImuObservationRecord),
+    /// Record payload for bearing observations.
+    Bearing(BearingObservationRecord),
 }
 
 #[cfg(feature = "gui")]
@@ -214,6 +272,8 @@ impl UIComponent for SensorObservationRecord {
             Self::OrientedRobot(r) => r.show(ui, ctx, unique_id),
             Self::Scan(r) => r.show(ui, ctx, unique_id),
             Self::External(r) => r.show(ui, ctx, unique_id),
+            Self::Imu(r) => r.show(ui, ctx, unique_id),
+            Self::Bearing(r) => r.show(ui, ctx, unique_id),
         });
     }
 }
@@ -246,6 +306,15 @@ pub enum SensorConfig {
     /// External sensor configuration.
     #[check]
     External(external_sensor::ExternalSensorConfig),
+    /// 2D LIDAR sensor configuration.
+    #[check]
+    Lidar(lidar_sensor::LidarSensorConfig),
+    /// IMU sensor configuration.
+    #[check]
+    Imu(imu_sensor::ImuSensorConfig),
+    /// Bearing-only camera sensor configuration.
+    #[check]
+    Bearing(bearing_sensor::BearingSensorConfig),
 }
 
 #[cfg(feature = "gui")]
@@ -288,6 +357,11 @@ impl UIComponent for SensorConfig {
                 "External" => {
                     *self = SensorConfig::External(external_sensor::ExternalSensorConfig::default())
                 }
+                "Lidar" => *self = SensorConfig::Lidar(lidar_sensor::LidarSensorConfig::default()),
+                "Imu" => *self = SensorConfig::Imu(imu_sensor::ImuSensorConfig::default()),
+                "Bearing" => {
+                    *self = SensorConfig::Bearing(bearing_sensor::BearingSensorConfig::default())
+                }
                 _ => panic!("Where did you find this value?"),
             };
         }
@@ -348,6 +422,30 @@ impl UIComponent for SensorConfig {
                 current_node_name,
                 unique_id,
             ),
+            SensorConfig::Lidar(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            SensorConfig::Imu(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            SensorConfig::Bearing(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
         }
     }
 
@@ -363,6 +461,9 @@ impl UIComponent for SensorConfig {
             SensorConfig::Robot(c) => c.show(ui, ctx, unique_id),
             SensorConfig::Scan(c) => c.show(ui, ctx, unique_id),
             SensorConfig::External(c) => c.show(ui, ctx, unique_id),
+            SensorConfig::Lidar(c) => c.show(ui, ctx, unique_id),
+            SensorConfig::Imu(c) => c.show(ui, ctx, unique_id),
+            SensorConfig::Bearing(c) => c.show(ui, ctx, unique_id),
         }
     }
 }
@@ -384,6 +485,12 @@ pub enum SensorRecord {
     ScanSensor(scan_sensor::ScanSensorRecord),
     /// Record produced by an external sensor.
     External(external_sensor::ExternalSensorRecord),
+    /// Record produced by a LIDAR sensor.
+    LidarSensor(lidar_sensor::LidarSensorRecord),
+    /// Record produced by an IMU sensor.
+    ImuSensor(imu_sensor::ImuSensorRecord),
+    /// Record produced by a bearing sensor.
+    BearingSensor(bearing_sensor::BearingSensorRecord),
 }
 
 #[cfg(feature = "gui")]
@@ -425,6 +532,21 @@ impl UIComponent for SensorRecord {
                     r.show(ui, ctx, unique_id);
                 });
             }
+            Self::LidarSensor(r) => {
+                egui::CollapsingHeader::new("Lidar").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::ImuSensor(r) => {
+                egui::CollapsingHeader::new("IMU").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::BearingSensor(r) => {
+                egui::CollapsingHeader::new("Bearing").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
         });
     }
 }
@@ -454,4 +576,39 @@ pub trait Sensor:
     /// Get the time of the next observation to trigger the next call to `get_observations`.
     /// This allows the sensor to have a custom observation period, or to trigger observations at specific times.
     fn next_time_step(&self) -> f32;
+
+    /// Observations produced by the sensor and services of the node it needs, checked when the
+    /// node is built (see [`capabilities`](crate::node::capabilities)). Nothing is declared by
+    /// default.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Apply the changes of a [`ReconfigureMessage`] received at `time`.
+    ///
+    /// Returns the reason of the rejection if the changes are not supported. By default, the
+    /// sensor cannot be reconfigured.
+    #[allow(unused_variables)]
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        Err("This sensor does not support reconfiguration".to_string())
+    }
+}
+
+/// Apply the period of a [`ReconfigureMessage`] to the activation of a built-in sensor.
+pub(crate) fn reconfigure_activation(
+    activation_time: &mut Option<Periodicity>,
+    message: &ReconfigureMessage,
+    time: f32,
+) -> Result<(), String> {
+    if let Some(period) = message.period {
+        match activation_time {
+            Some(activation) => activation.set_period(period, time),
+            None => {
+                return Err(
+                    "The sensor has no activation time, its period cannot be changed".to_string(),
+                );
+            }
+        }
+    }
+    Ok(())
 }
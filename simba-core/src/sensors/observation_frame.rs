@@ -0,0 +1,214 @@
+//! Coordinate frame of the observations.
+//!
+//! The sensors compute their observations in their native frame: the robot frame (the map frame
+//! for the GNSS). The [`SensorManager`](super::sensor_manager::SensorManager) can express them in
+//! another [`ObservationFrame`] before they are used by the state estimators or sent to other
+//! nodes: the frame of the sensor, mounted at a given pose on the robot, or the map frame, using
+//! the pose of the robot estimated by the main state estimator of the node. Each
+//! [`Observation`](super::Observation) is tagged with its frame, and the state estimators declare
+//! the frames they support in their [`Capabilities`](crate::node::capabilities::Capabilities).
+//!
+//! The faults are applied before the change of frame, so the noise is transformed with the
+//! observation: a noise of covariance `Σ` on a position becomes `R Σ Rᵀ` in the new frame, where
+//! `R` is the rotation between the two frames, and the angular noises are unchanged. In the map
+//! frame, the error of the estimated pose is added: with `P` the covariance of the estimated pose
+//! and `J` the jacobian of the transformation with respect to the robot pose, the covariance of a
+//! position observation becomes `R Σ Rᵀ + J P Jᵀ`.
+
+use nalgebra::{Rotation2, Vector2, Vector3};
+use simba_macros::config_derives;
+
+use super::{SensorConfig, SensorObservation};
+use crate::{node::capabilities::ObservationKind, utils::geometry::mod2pi};
+
+/// Frame in which the observations of a sensor are expressed.
+#[config_derives]
+pub enum ObservationFrame {
+    /// Frame of the robot carrying the sensor.
+    Robot,
+    /// Frame of the sensor, at its mounting pose on the robot.
+    Sensor,
+    /// Frame of the map, using the estimated pose of the robot.
+    Map,
+}
+
+impl Default for ObservationFrame {
+    fn default() -> Self {
+        Self::Robot
+    }
+}
+
+impl ObservationFrame {
+    /// Native frame of the observations of kind `kind`, in which the sensors compute them.
+    pub fn native(kind: ObservationKind) -> Self {
+        match kind {
+            ObservationKind::GNSS => Self::Map,
+            _ => Self::Robot,
+        }
+    }
+
+    /// Checks that the observations of the sensor `config` can be expressed in this frame, with
+    /// the sensor mounted at `sensor_pose` in the robot frame.
+    pub fn check_sensor(
+        &self,
+        config: &SensorConfig,
+        sensor_pose: &[f32; 3],
+    ) -> Result<(), String> {
+        let supported = match (config, self) {
+            (SensorConfig::GNSS(_), frame) => matches!(frame, Self::Map),
+            (
+                SensorConfig::OrientedLandmark(_)
+                | SensorConfig::Robot(_)
+                | SensorConfig::Speed(_)
+                | SensorConfig::Displacement(_),
+                _,
+            ) => true,
+            (SensorConfig::Bearing(_), Self::Sensor) => {
+                // The bearing of a translated frame depends on the unknown range
+                if sensor_pose[0] != 0. || sensor_pose[1] != 0. {
+                    return Err(
+                        "Bearing observations can only be expressed in a sensor frame which is not translated from the robot frame".to_string(),
+                    );
+                }
+                true
+            }
+            (SensorConfig::Bearing(_), _) => true,
+            (_, frame) => matches!(frame, Self::Robot),
+        };
+        if supported {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} observations cannot be expressed in the {} frame",
+                config, self
+            ))
+        }
+    }
+
+    /// Expresses in this frame an `observation` made in its native frame.
+    ///
+    /// ## Arguments
+    /// * `observation` - Observation to transform in place.
+    /// * `sensor_pose` - Pose `[x, y, theta]` of the sensor in the robot frame.
+    /// * `robot_pose` - Estimated pose of the robot in the map frame, when the observation is made.
+    pub fn transform(
+        &self,
+        observation: &mut SensorObservation,
+        sensor_pose: &Vector3<f32>,
+        robot_pose: &Vector3<f32>,
+    ) {
+        match self {
+            Self::Robot => {}
+            Self::Sensor => to_sensor_frame(observation, sensor_pose),
+            Self::Map => to_map_frame(observation, robot_pose),
+        }
+    }
+}
+
+/// Pose of the frame `pose` (in the robot frame) expressed in the frame `frame`.
+fn pose_in_frame(pose: &Vector3<f32>, frame: &Vector3<f32>) -> Vector3<f32> {
+    let position = Rotation2::new(-frame.z) * (pose.xy() - frame.xy());
+    Vector3::new(position.x, position.y, pose.z - frame.z)
+}
+
+fn to_sensor_frame(observation: &mut SensorObservation, sensor_pose: &Vector3<f32>) {
+    let rotation = Rotation2::new(-sensor_pose.z);
+    match observation {
+        SensorObservation::OrientedLandmark(obs) => {
+            obs.pose = pose_in_frame(&obs.pose, sensor_pose);
+        }
+        SensorObservation::OrientedRobot(obs) => {
+            obs.pose = pose_in_frame(&obs.pose, sensor_pose);
+        }
+        SensorObservation::Speed(obs) => {
+            // Velocity of the mounting point, with the lever arm
+            let velocity = rotation
+                * Vector2::new(
+                    obs.linear_velocity - obs.angular_velocity * sensor_pose.y,
+                    obs.lateral_velocity + obs.angular_velocity * sensor_pose.x,
+                );
+            obs.linear_velocity = velocity.x;
+            obs.lateral_velocity = velocity.y;
+        }
+        SensorObservation::Displacement(obs) => {
+            // Displacement of the mounting point, in the sensor frame before the displacement
+            let mounting = sensor_pose.xy();
+            obs.translation =
+                rotation * (obs.translation + Rotation2::new(obs.rotation) * mounting - mounting);
+        }
+        SensorObservation::Bearing(obs) => {
+            obs.bearing = mod2pi(obs.bearing - sensor_pose.z);
+        }
+        _ => {}
+    }
+}
+
+fn to_map_frame(observation: &mut SensorObservation, robot_pose: &Vector3<f32>) {
+    let rotation = Rotation2::new(robot_pose.z);
+    match observation {
+        SensorObservation::OrientedLandmark(obs) => {
+            let position = robot_pose.xy() + rotation * obs.pose.xy();
+            obs.pose = Vector3::new(position.x, position.y, obs.pose.z + robot_pose.z);
+        }
+        SensorObservation::OrientedRobot(obs) => {
+            let position = robot_pose.xy() + rotation * obs.pose.xy();
+            obs.pose = Vector3::new(position.x, position.y, obs.pose.z + robot_pose.z);
+        }
+        SensorObservation::Speed(obs) => {
+            let velocity = rotation * Vector2::new(obs.linear_velocity, obs.lateral_velocity);
+            obs.linear_velocity = velocity.x;
+            obs.lateral_velocity = velocity.y;
+        }
+        SensorObservation::Displacement(obs) => {
+            // The displacement is expressed in the frame of the robot before the displacement
+            obs.translation = Rotation2::new(robot_pose.z - obs.rotation) * obs.translation;
+        }
+        SensorObservation::Bearing(obs) => {
+            obs.bearing = mod2pi(obs.bearing + robot_pose.z);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::sensors::{
+        displacement_sensor::DisplacementObservation, speed_sensor::SpeedObservation,
+    };
+
+    #[test]
+    fn speed_and_displacement_frames() {
+        let sensor_pose = Vector3::new(1., 0., FRAC_PI_2);
+        let robot_pose = Vector3::new(2., 3., FRAC_PI_2);
+
+        // Pure rotation: the mounting point moves laterally
+        let mut speed = SensorObservation::Speed(SpeedObservation {
+            linear_velocity: 0.,
+            lateral_velocity: 0.,
+            angular_velocity: 1.,
+            applied_faults: Vec::new(),
+        });
+        ObservationFrame::Sensor.transform(&mut speed, &sensor_pose, &robot_pose);
+        let SensorObservation::Speed(obs) = &speed else {
+            unreachable!()
+        };
+        assert!((obs.linear_velocity - 1.).abs() < 1e-6);
+        assert!(obs.lateral_velocity.abs() < 1e-6);
+
+        let mut displacement = SensorObservation::Displacement(DisplacementObservation {
+            translation: Vector2::new(1., 0.),
+            rotation: FRAC_PI_2,
+            applied_faults: Vec::new(),
+        });
+        ObservationFrame::Map.transform(&mut displacement, &sensor_pose, &robot_pose);
+        let SensorObservation::Displacement(obs) = &displacement else {
+            unreachable!()
+        };
+        // The robot was heading along x before the displacement
+        assert!((obs.translation - Vector2::new(1., 0.)).norm() < 1e-6);
+        assert_eq!(obs.rotation, FRAC_PI_2);
+    }
+}
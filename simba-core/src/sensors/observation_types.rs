@@ -0,0 +1,236 @@
+//! Registry of the observation types of the plugin sensors.
+//!
+//! A plugin declares the [`ObservationSchema`] of each kind of observation its sensors emit,
+//! through [`PluginAPI::observation_types`](crate::plugin_api::PluginAPI::observation_types).
+//! The simulator registers them when it loads the configuration.
+//!
+//! An [`ExternalObservation`](super::external_sensor::ExternalObservation) built with
+//! [`ExternalObservation::new`](super::external_sensor::ExternalObservation::new) carries the name
+//! of its type, and its payload is validated against the registered schema. The state estimators
+//! can then decode it with
+//! [`ExternalObservation::decode`](super::external_sensor::ExternalObservation::decode), and the
+//! GUI shows its fields.
+
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use simba_macros::EnumToString;
+
+use crate::errors::{SimbaError, SimbaErrorTypes, SimbaResult};
+
+static OBSERVATION_TYPES: RwLock<BTreeMap<String, ObservationSchema>> =
+    RwLock::new(BTreeMap::new());
+
+/// Type of the value of an [`ObservationField`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumToString)]
+pub enum ObservationFieldType {
+    /// Any number.
+    Number,
+    /// Integer number.
+    Integer,
+    /// Boolean.
+    Bool,
+    /// String.
+    String,
+    /// Array of any values.
+    Array,
+    /// Nested object.
+    Object,
+    /// Any value, not checked.
+    Any,
+}
+
+impl ObservationFieldType {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Bool => value.is_boolean(),
+            Self::String => value.is_string(),
+            Self::Array => value.is_array(),
+            Self::Object => value.is_object(),
+            Self::Any => true,
+        }
+    }
+}
+
+/// Field of an [`ObservationSchema`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObservationField {
+    /// Name of the field in the payload.
+    pub name: String,
+    /// Type of the value.
+    pub field_type: ObservationFieldType,
+    /// The field can be missing (or `null`).
+    pub optional: bool,
+    /// Unit of the value, shown in the GUI.
+    pub unit: Option<String>,
+}
+
+impl ObservationField {
+    /// Required field without unit.
+    pub fn new(name: &str, field_type: ObservationFieldType) -> Self {
+        Self {
+            name: name.to_string(),
+            field_type,
+            optional: false,
+            unit: None,
+        }
+    }
+
+    /// Makes the field optional.
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// Sets the unit of the field.
+    pub fn with_unit(mut self, unit: &str) -> Self {
+        self.unit = Some(unit.to_string());
+        self
+    }
+}
+
+/// Named schema of the payload of an external observation.
+///
+/// The payload is a JSON object with the declared fields only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObservationSchema {
+    /// Name of the observation type, unique among the plugins.
+    pub name: String,
+    /// Fields of the payload.
+    pub fields: Vec<ObservationField>,
+}
+
+impl ObservationSchema {
+    /// Schema `name` with the given `fields`.
+    pub fn new(name: &str, fields: Vec<ObservationField>) -> Self {
+        Self {
+            name: name.to_string(),
+            fields,
+        }
+    }
+
+    /// Check that the `payload` follows the schema.
+    ///
+    /// Returns the list of the differences otherwise.
+    pub fn validate(&self, payload: &Value) -> Result<(), String> {
+        let Some(object) = payload.as_object() else {
+            return Err(format!(
+                "`{}` observation should be an object, got `{payload}`",
+                self.name
+            ));
+        };
+        let mut errors = Vec::new();
+        for field in &self.fields {
+            match object.get(&field.name) {
+                None | Some(Value::Null) if field.optional => {}
+                None => errors.push(format!("missing field `{}`", field.name)),
+                Some(value) if !field.field_type.matches(value) => errors.push(format!(
+                    "field `{}` should be {}, got `{value}`",
+                    field.name, field.field_type
+                )),
+                Some(_) => {}
+            }
+        }
+        for key in object.keys() {
+            if !self.fields.iter().any(|field| &field.name == key) {
+                errors.push(format!("unknown field `{key}`"));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid `{}` observation: {}",
+                self.name,
+                errors.join(", ")
+            ))
+        }
+    }
+}
+
+/// Register the observation type `schema`.
+///
+/// Registering the same schema again does nothing. Returns an
+/// [`ExternalAPIError`](SimbaErrorTypes::ExternalAPIError) if another schema already has this name.
+pub fn register_observation_type(schema: ObservationSchema) -> SimbaResult<()> {
+    let mut types = OBSERVATION_TYPES.write().unwrap();
+    match types.get(&schema.name) {
+        Some(registered) if registered != &schema => Err(SimbaError::new(
+            SimbaErrorTypes::ExternalAPIError,
+            format!(
+                "Observation type `{}` is already registered with another schema",
+                schema.name
+            ),
+        )),
+        Some(_) => Ok(()),
+        None => {
+            types.insert(schema.name.clone(), schema);
+            Ok(())
+        }
+    }
+}
+
+/// Registered schema of the observation type `name`.
+pub fn observation_type(name: &str) -> Option<ObservationSchema> {
+    OBSERVATION_TYPES.read().unwrap().get(name).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::sensors::external_sensor::ExternalObservation;
+
+    #[test]
+    fn schema_validation() {
+        let schema = ObservationSchema::new(
+            "test_schema_validation",
+            vec![
+                ObservationField::new("range", ObservationFieldType::Number).with_unit("m"),
+                ObservationField::new("id", ObservationFieldType::Integer).optional(),
+            ],
+        );
+        assert!(schema.validate(&json!({"range": 2.5, "id": 3})).is_ok());
+        assert!(schema.validate(&json!({"range": 2.5})).is_ok());
+        assert!(schema.validate(&json!({"range": "far"})).is_err());
+        assert!(schema.validate(&json!({"id": 3})).is_err());
+        assert!(schema.validate(&json!({"range": 2.5, "other": 1})).is_err());
+        assert!(schema.validate(&json!(2.5)).is_err());
+
+        register_observation_type(schema.clone()).unwrap();
+        register_observation_type(schema.clone()).unwrap();
+        assert_eq!(observation_type("test_schema_validation"), Some(schema));
+        assert!(
+            register_observation_type(ObservationSchema::new("test_schema_validation", vec![]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn typed_external_observation() {
+        register_observation_type(ObservationSchema::new(
+            "test_typed_external_observation",
+            vec![ObservationField::new("data", ObservationFieldType::Number)],
+        ))
+        .unwrap();
+        let observation =
+            ExternalObservation::new("test_typed_external_observation", &json!({"data": 1.5}))
+                .unwrap();
+        let data: Value = observation
+            .decode("test_typed_external_observation")
+            .unwrap()
+            .unwrap();
+        assert_eq!(data, json!({"data": 1.5}));
+        assert!(observation.decode::<Value>("other").is_none());
+        assert!(
+            ExternalObservation::new("test_typed_external_observation", &json!({"data": "a"}))
+                .is_err()
+        );
+        assert!(ExternalObservation::new("test_unregistered", &json!({})).is_err());
+    }
+}
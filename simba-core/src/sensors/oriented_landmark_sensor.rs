@@ -6,17 +6,22 @@
 //! [`OrientedLandmarkSensorFilterConfig`] and fault model pipelines configured by [`OrientedLandmarkSensorFaultModelConfig`].
 
 use super::fault_models::fault_model::FaultModel;
-use super::{Sensor, SensorObservation, SensorRecord};
+use super::{Sensor, SensorObservation, SensorRecord, reconfigure_activation};
 
 use crate::constants::TIME_ROUND;
 use crate::errors::SimbaResult;
 #[cfg(feature = "gui")]
 use crate::gui::UIComponent;
 use crate::logger::is_enabled;
+use crate::networking::reconfigure::ReconfigureMessage;
 use crate::plugin_api::PluginAPI;
 use crate::recordable::Recordable;
 use crate::sensors::fault_models::additive::{AdditiveFault, AdditiveFaultConfig};
+use crate::sensors::fault_models::calibration::{CalibrationFault, CalibrationFaultConfig};
 use crate::sensors::fault_models::clutter::{ClutterFault, ClutterFaultConfig};
+use crate::sensors::fault_models::detection_probability::{
+    DetectionProbability, DetectionProbabilityConfig,
+};
 use crate::sensors::fault_models::external_fault::{ExternalFault, ExternalFaultConfig};
 use crate::sensors::fault_models::misassociation::{
     MisassociationFault, MisassociationFaultConfig,
@@ -101,6 +106,8 @@ pub enum OrientedLandmarkSensorFaultModelConfig {
     External(ExternalFaultConfig),
     /// Python-implemented fault model.
     Python(PythonFaultModelConfig),
+    /// Calibration fault model, the landmarks are observed from the real mounting pose.
+    Calibration(CalibrationFaultConfig<OrientedLandmarkSensorVariablesFaults>),
 }
 
 impl Default for OrientedLandmarkSensorFaultModelConfig {
@@ -130,6 +137,8 @@ pub enum OrientedLandmarkSensorFaultModelType {
     External(ExternalFault),
     /// Instantiated Python fault model.
     Python(PythonFaultModel),
+    /// Instantiated calibration fault model.
+    Calibration(CalibrationFault<OrientedLandmarkSensorVariablesFaults>),
 }
 
 impl OrientedLandmarkSensorFaultModelType {
@@ -146,7 +155,23 @@ impl OrientedLandmarkSensorFaultModelType {
             | Self::AdditiveObservationCentered(_)
             | Self::Clutter(_)
             | Self::Misdetection(_)
-            | Self::Misassociation(_) => Ok(()),
+            | Self::Misassociation(_)
+            | Self::Calibration(_) => Ok(()),
+        }
+    }
+
+    /// Scales the perturbations of the additive fault models, other models are not changed.
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        match self {
+            Self::AdditiveRobotCentered(f) | Self::AdditiveObservationCentered(f) => {
+                f.set_noise_scale(noise_scale)
+            }
+            Self::Python(_)
+            | Self::External(_)
+            | Self::Clutter(_)
+            | Self::Misdetection(_)
+            | Self::Misassociation(_)
+            | Self::Calibration(_) => {}
         }
     }
 }
@@ -236,6 +261,7 @@ impl OrientedLandmarkSensorFilterType {
 /// - `faults`: empty vector
 /// - `filters`: empty vector
 /// - `xray`: `false`
+/// - `detection`: `None` (all the visible targets are detected)
 #[config_derives]
 pub struct OrientedLandmarkSensorConfig {
     /// Max distance of detection.
@@ -252,6 +278,9 @@ pub struct OrientedLandmarkSensorConfig {
     pub filters: Vec<OrientedLandmarkSensorFilterConfig>,
     /// If true, will detect all landmarks, even if they are behind obstacles (no raycasting).
     pub xray: bool,
+    /// Stochastic detection model, applied to the visible targets before the filters.
+    #[check]
+    pub detection: Option<DetectionProbabilityConfig>,
 }
 
 impl Check for OrientedLandmarkSensorConfig {
@@ -283,6 +312,7 @@ impl Default for OrientedLandmarkSensorConfig {
             faults: Vec::new(),
             filters: Vec::new(),
             xray: false,
+            detection: None,
         }
     }
 }
@@ -332,6 +362,24 @@ impl UIComponent for OrientedLandmarkSensorConfig {
                     ui.checkbox(&mut self.xray, "");
                 });
 
+                ui.horizontal(|ui| {
+                    if let Some(d) = &mut self.detection {
+                        d.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove detection model").clicked() {
+                            self.detection = None;
+                        }
+                    } else if ui.button("Add detection model").clicked() {
+                        self.detection = Some(DetectionProbabilityConfig::default());
+                    }
+                });
+
                 OrientedLandmarkSensorFilterConfig::show_all_mut(
                     &mut self.filters,
                     ui,
@@ -374,6 +422,10 @@ impl UIComponent for OrientedLandmarkSensorConfig {
                     ui.label(format!("X-Ray mode: {}", self.xray));
                 });
 
+                if let Some(d) = &self.detection {
+                    d.show(ui, ctx, unique_id);
+                }
+
                 OrientedLandmarkSensorFilterConfig::show_all(&self.filters, ui, ctx, unique_id);
 
                 OrientedLandmarkSensorFaultModelConfig::show_all(&self.faults, ui, ctx, unique_id);
@@ -494,6 +546,8 @@ impl UIComponent for OrientedLandmarkObservationRecord {
 /// Sensor which observe the map landmarks.
 #[derive(Debug)]
 pub struct OrientedLandmarkSensor {
+    /// Stochastic detection model.
+    detection: Option<DetectionProbability>,
     /// Detection distance
     detection_distance: f32,
     /// Observation period
@@ -501,6 +555,8 @@ pub struct OrientedLandmarkSensor {
     /// Last observation time.
     last_time: Option<f32>,
     faults: Vec<OrientedLandmarkSensorFaultModelType>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
     filters: Vec<OrientedLandmarkSensorFilterType>,
     /// If true, will detect all landmarks, even if they are behind obstacles (no raycasting).
     xray: bool,
@@ -547,6 +603,11 @@ impl OrientedLandmarkSensor {
                         MisassociationFault::from_config(c, va_factory),
                     )
                 }
+                OrientedLandmarkSensorFaultModelConfig::Calibration(c) => {
+                    OrientedLandmarkSensorFaultModelType::Calibration(
+                        CalibrationFault::from_config(c, initial_time),
+                    )
+                }
                 OrientedLandmarkSensorFaultModelConfig::External(c) => {
                     OrientedLandmarkSensorFaultModelType::External(ExternalFault::from_config(
                         c,
@@ -612,14 +673,20 @@ impl OrientedLandmarkSensor {
             detection_distance: config.detection_distance,
             activation_time,
             last_time: None,
+            active_faults: vec![true; fault_models.len()],
             faults: fault_models,
             filters,
             xray: config.xray,
+            detection: config
+                .detection
+                .as_ref()
+                .map(|c| DetectionProbability::from_config(c, va_factory)),
         })
     }
 }
 
 use crate::node::Node;
+use crate::node::capabilities::{Capabilities, ObservationKind};
 
 impl Sensor for OrientedLandmarkSensor {
     fn post_init(&mut self, node: &mut Node, initial_time: f32) -> crate::errors::SimbaResult<()> {
@@ -660,6 +727,23 @@ impl Sensor for OrientedLandmarkSensor {
             let landmark_seed = (i + 1) as f32 / (100. * (time - self.last_time.unwrap_or(-1.)))
                 * ((landmark.id + 1) as f32);
             let pose = rotation_matrix.transpose() * (landmark.pose - state.pose);
+            if let Some(detection) = &self.detection {
+                // Angle between the landmark orientation and the line of sight to the sensor
+                let incidence = (state.pose.y - landmark.pose.y)
+                    .atan2(state.pose.x - landmark.pose.x)
+                    - landmark.pose.z;
+                if !detection.detected(
+                    time + landmark_seed,
+                    pose.fixed_rows::<2>(0).norm(),
+                    incidence,
+                    landmark.width,
+                ) {
+                    if is_enabled(crate::logger::InternalLog::SensorManagerDetailed) {
+                        debug!("Landmark {} not detected", landmark.id);
+                    }
+                    continue;
+                }
+            }
             let obs = SensorObservation::OrientedLandmark(OrientedLandmarkObservation {
                 id: landmark.id,
                 labels: landmark.labels.clone(),
@@ -747,7 +831,12 @@ impl Sensor for OrientedLandmarkSensor {
             let mut new_obs = Vec::new();
             if let Some(observation) = keep_observation {
                 new_obs.push(observation); // Not adding directly to observation_list to apply faults only once
-                for fault_model in self.faults.iter_mut() {
+                for (fault_model, _) in self
+                    .faults
+                    .iter_mut()
+                    .zip(&self.active_faults)
+                    .filter(|(_, active)| **active)
+                {
                     match fault_model {
                         OrientedLandmarkSensorFaultModelType::Python(f) => f.add_faults(
                             time,
@@ -965,6 +1054,88 @@ impl Sensor for OrientedLandmarkSensor {
                                 }
                             }
                         }
+                        OrientedLandmarkSensorFaultModelType::Calibration(f) => {
+                            for obs in new_obs.iter_mut().map(|o| {
+                                if let SensorObservation::OrientedLandmark(observation) = o {
+                                    observation
+                                } else {
+                                    unreachable!()
+                                }
+                            }) {
+                                let (position, orientation) = f.to_sensor_frame(
+                                    obs.pose.fixed_rows::<2>(0).clone_owned(),
+                                    obs.pose.z,
+                                );
+                                obs.pose = Vector3::new(position.x, position.y, orientation);
+                                let new_values = f.add_faults(
+                                    OrientedLandmarkSensorVariablesFaults::mapped_values(
+                                        |variant| match variant {
+                                            OrientedLandmarkSensorVariablesFaults::X => obs.pose.x,
+                                            OrientedLandmarkSensorVariablesFaults::Y => obs.pose.y,
+                                            OrientedLandmarkSensorVariablesFaults::Orientation => {
+                                                obs.pose.z
+                                            }
+                                            OrientedLandmarkSensorVariablesFaults::R => {
+                                                obs.pose.fixed_rows::<2>(0).norm()
+                                            }
+                                            OrientedLandmarkSensorVariablesFaults::Theta => {
+                                                obs.pose.y.atan2(obs.pose.x)
+                                            }
+                                            OrientedLandmarkSensorVariablesFaults::Height => {
+                                                obs.height
+                                            }
+                                            OrientedLandmarkSensorVariablesFaults::Width => {
+                                                obs.width
+                                            }
+                                        },
+                                    ),
+                                );
+                                if let Some(new_x) =
+                                    new_values.get(&OrientedLandmarkSensorVariablesFaults::X)
+                                {
+                                    obs.pose.x = *new_x;
+                                }
+                                if let Some(new_y) =
+                                    new_values.get(&OrientedLandmarkSensorVariablesFaults::Y)
+                                {
+                                    obs.pose.y = *new_y;
+                                }
+                                let new_r =
+                                    new_values.get(&OrientedLandmarkSensorVariablesFaults::R);
+                                let new_theta =
+                                    new_values.get(&OrientedLandmarkSensorVariablesFaults::Theta);
+                                if new_r.is_some() || new_theta.is_some() {
+                                    let r = new_r
+                                        .cloned()
+                                        .unwrap_or_else(|| obs.pose.fixed_rows::<2>(0).norm());
+                                    let theta = new_theta
+                                        .cloned()
+                                        .unwrap_or_else(|| obs.pose.y.atan2(obs.pose.x));
+                                    obs.pose.x = r * theta.cos();
+                                    obs.pose.y = r * theta.sin();
+                                }
+                                if let Some(new_orientation) = new_values
+                                    .get(&OrientedLandmarkSensorVariablesFaults::Orientation)
+                                {
+                                    obs.pose.z = *new_orientation;
+                                }
+                                if let Some(new_height) =
+                                    new_values.get(&OrientedLandmarkSensorVariablesFaults::Height)
+                                {
+                                    obs.height = *new_height;
+                                }
+                                if let Some(new_width) =
+                                    new_values.get(&OrientedLandmarkSensorVariablesFaults::Width)
+                                {
+                                    obs.width = *new_width;
+                                }
+                                obs.applied_faults.push(
+                                    OrientedLandmarkSensorFaultModelConfig::Calibration(
+                                        f.config().clone(),
+                                    ),
+                                );
+                            }
+                        }
                         OrientedLandmarkSensorFaultModelType::Clutter(f) => {
                             let new_obs_from_clutter =
                                 f.add_faults(time + landmark_seed, landmark_seed / 100.);
@@ -1067,6 +1238,13 @@ impl Sensor for OrientedLandmarkSensor {
         observation_list
     }
 
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::OrientedLandmark],
+            ..Default::default()
+        }
+    }
+
     /// Get the next observation time.
     fn next_time_step(&self) -> f32 {
         if let Some(activation) = &self.activation_time {
@@ -1075,6 +1253,17 @@ impl Sensor for OrientedLandmarkSensor {
             f32::INFINITY
         }
     }
+
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        reconfigure_activation(&mut self.activation_time, message, time)?;
+        message.apply_fault_activation(&mut self.active_faults)?;
+        if let Some(noise_scale) = message.noise_scale {
+            for fault_model in self.faults.iter_mut() {
+                fault_model.set_noise_scale(noise_scale);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Recordable<SensorRecord> for OrientedLandmarkSensor {
@@ -5,19 +5,25 @@
 //! [`RobotSensorFilterConfig`] and fault pipelines configured by [`RobotSensorFaultModelConfig`].
 
 use super::fault_models::fault_model::FaultModel;
-use super::{Sensor, SensorObservation, SensorRecord};
+use super::{Sensor, SensorObservation, SensorRecord, reconfigure_activation};
 
 use crate::constants::TIME_ROUND;
+use crate::environment::RobotObstructions;
 
 use crate::errors::{SimbaErrorTypes, SimbaResult};
 #[cfg(feature = "gui")]
 use crate::gui::UIComponent;
 use crate::logger::is_enabled;
+use crate::networking::reconfigure::ReconfigureMessage;
 use crate::networking::service_manager::ServiceError;
 use crate::plugin_api::PluginAPI;
 use crate::recordable::Recordable;
 use crate::sensors::fault_models::additive::{AdditiveFault, AdditiveFaultConfig};
+use crate::sensors::fault_models::calibration::{CalibrationFault, CalibrationFaultConfig};
 use crate::sensors::fault_models::clutter::{ClutterFault, ClutterFaultConfig};
+use crate::sensors::fault_models::detection_probability::{
+    DetectionProbability, DetectionProbabilityConfig,
+};
 use crate::sensors::fault_models::external_fault::{ExternalFault, ExternalFaultConfig};
 use crate::sensors::fault_models::misassociation::{
     MisassociationFault, MisassociationFaultConfig,
@@ -33,6 +39,7 @@ use crate::simulator::SimulatorConfig;
 use crate::state_estimators::State;
 use crate::utils::determinist_random_variable::DeterministRandomVariableFactory;
 use crate::utils::enum_tools::EnumVariables;
+use crate::utils::lock_recovery::{RecoverRoLock, RecoverRwLock};
 use crate::utils::periodicity::{Periodicity, PeriodicityConfig};
 use serde_derive::{Deserialize, Serialize};
 
@@ -102,6 +109,9 @@ pub enum RobotSensorFaultModelConfig {
     /// Plugin-provided external fault model.
     #[check]
     External(ExternalFaultConfig),
+    /// Calibration fault model, the robots are observed from the real mounting pose.
+    #[check]
+    Calibration(CalibrationFaultConfig<RobotSensorVariablesFaults>),
 }
 
 impl Default for RobotSensorFaultModelConfig {
@@ -127,6 +137,8 @@ pub enum RobotSensorFaultModelType {
     Python(PythonFaultModel),
     /// Instantiated external fault model.
     External(ExternalFault),
+    /// Instantiated calibration fault model.
+    Calibration(CalibrationFault<RobotSensorVariablesFaults>),
 }
 
 impl RobotSensorFaultModelType {
@@ -143,7 +155,23 @@ impl RobotSensorFaultModelType {
             | Self::AdditiveObservationCentered(_)
             | Self::Clutter(_)
             | Self::Misdetection(_)
-            | Self::Misassociation(_) => Ok(()),
+            | Self::Misassociation(_)
+            | Self::Calibration(_) => Ok(()),
+        }
+    }
+
+    /// Scales the perturbations of the additive fault models, other models are not changed.
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        match self {
+            Self::AdditiveRobotCentered(f) | Self::AdditiveObservationCentered(f) => {
+                f.set_noise_scale(noise_scale)
+            }
+            Self::Python(_)
+            | Self::External(_)
+            | Self::Clutter(_)
+            | Self::Misdetection(_)
+            | Self::Misassociation(_)
+            | Self::Calibration(_) => {}
         }
     }
 }
@@ -235,6 +263,7 @@ impl RobotSensorFilterType {
 /// - `faults`: empty vector
 /// - `filters`: empty vector
 /// - `xray`: `false`
+/// - `detection`: `None` (all the visible targets are detected)
 #[config_derives]
 pub struct RobotSensorConfig {
     /// Max distance of detection.
@@ -250,6 +279,9 @@ pub struct RobotSensorConfig {
     pub filters: Vec<RobotSensorFilterConfig>,
     /// If `true`, line-of-sight occlusion checks are bypassed.
     pub xray: bool,
+    /// Stochastic detection model, applied to the visible targets before the filters.
+    #[check]
+    pub detection: Option<DetectionProbabilityConfig>,
 }
 
 impl Check for RobotSensorConfig {
@@ -281,6 +313,7 @@ impl Default for RobotSensorConfig {
             faults: Vec::new(),
             filters: Vec::new(),
             xray: false,
+            detection: None,
         }
     }
 }
@@ -330,6 +363,24 @@ impl UIComponent for RobotSensorConfig {
                     ui.checkbox(&mut self.xray, "");
                 });
 
+                ui.horizontal(|ui| {
+                    if let Some(d) = &mut self.detection {
+                        d.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove detection model").clicked() {
+                            self.detection = None;
+                        }
+                    } else if ui.button("Add detection model").clicked() {
+                        self.detection = Some(DetectionProbabilityConfig::default());
+                    }
+                });
+
                 RobotSensorFilterConfig::show_all_mut(
                     &mut self.filters,
                     ui,
@@ -372,6 +423,10 @@ impl UIComponent for RobotSensorConfig {
                     ui.label(format!("X-Ray mode: {}", self.xray));
                 });
 
+                if let Some(d) = &self.detection {
+                    d.show(ui, ctx, unique_id);
+                }
+
                 RobotSensorFilterConfig::show_all(&self.filters, ui, ctx, unique_id);
 
                 RobotSensorFaultModelConfig::show_all(&self.faults, ui, ctx, unique_id);
@@ -452,6 +507,8 @@ impl UIComponent for OrientedRobotObservationRecord {
 /// Sensor which observe the other Robots.
 #[derive(Debug)]
 pub struct RobotSensor {
+    /// Stochastic detection model.
+    detection: Option<DetectionProbability>,
     /// Detection distance
     detection_distance: f32,
     /// Observation period
@@ -460,6 +517,8 @@ pub struct RobotSensor {
     last_time: Option<f32>,
     xray: bool,
     faults: Vec<RobotSensorFaultModelType>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
     filters: Vec<RobotSensorFilterType>,
 }
 
@@ -507,6 +566,12 @@ impl RobotSensor {
                 RobotSensorFaultModelConfig::Python(cfg) => RobotSensorFaultModelType::Python(
                     PythonFaultModel::from_config(cfg, global_config, initial_time)?,
                 ),
+                RobotSensorFaultModelConfig::Calibration(cfg) => {
+                    RobotSensorFaultModelType::Calibration(CalibrationFault::from_config(
+                        cfg,
+                        initial_time,
+                    ))
+                }
                 RobotSensorFaultModelConfig::External(cfg) => {
                     RobotSensorFaultModelType::External(ExternalFault::from_config(
                         cfg,
@@ -554,14 +619,20 @@ impl RobotSensor {
             detection_distance: config.detection_distance,
             activation_time: period,
             last_time: None,
+            active_faults: vec![true; fault_models.len()],
             faults: fault_models,
             xray: config.xray,
+            detection: config
+                .detection
+                .as_ref()
+                .map(|c| DetectionProbability::from_config(c, va_factory)),
             filters,
         })
     }
 }
 
 use crate::node::Node;
+use crate::node::capabilities::{Capabilities, ObservationKind};
 
 impl Sensor for RobotSensor {
     fn post_init(&mut self, node: &mut Node, initial_time: f32) -> crate::errors::SimbaResult<()> {
@@ -585,7 +656,7 @@ impl Sensor for RobotSensor {
             debug!("Start looking for nodes");
         }
         let state = if let Some(arc_physics) = node.physics() {
-            let physics = arc_physics.read().unwrap();
+            let physics = arc_physics.read_recover();
             physics.state(time).clone()
         } else {
             State::new() // 0
@@ -597,6 +668,13 @@ impl Sensor for RobotSensor {
             debug!("Rotation matrix: {}", rotation_matrix);
         }
 
+        let position = state.pose.fixed_rows::<2>(0).clone_owned();
+        let robot_obstructions = if self.xray {
+            RobotObstructions::default()
+        } else {
+            node.environment()
+                .robot_obstructions(&position, self.detection_distance)
+        };
         for (i, other_node_name) in node.other_node_names().iter().enumerate() {
             if is_enabled(crate::logger::InternalLog::SensorManagerDetailed) {
                 debug!("Sensing node {}", other_node_name);
@@ -604,30 +682,47 @@ impl Sensor for RobotSensor {
             assert!(*other_node_name != node.name());
 
             let service_manager = node.service_manager();
-            match service_manager.read().unwrap().get_real_state(
+            match service_manager.read_recover().get_real_state(
                 &other_node_name.to_string(),
                 node,
                 time,
             ) {
                 Ok(other_state) => {
-                    if node.environment().is_target_observable(
+                    if node.environment().is_target_observable_with(
                         &other_state.pose.fixed_rows::<2>(0).clone_owned(),
                         Some(0.),
-                        &state.pose.fixed_rows::<2>(0).clone_owned(),
+                        &position,
                         if self.xray { None } else { Some(0.) },
                         self.detection_distance,
                         Some(node.name().clone()),
+                        &robot_obstructions,
                     ) {
                         let robot_seed =
                             (i as f32) / (100. * (time - self.last_time.unwrap_or(-1.)));
                         let pose = rotation_matrix.transpose() * (other_state.pose - state.pose);
-                        let labels = node
+                        let (labels, size) = node
                             .meta_data_list()
                             .unwrap()
-                            .read()
-                            .unwrap()
+                            .read_recover()
                             .get(other_node_name)
-                            .map_or(Vec::new(), |md| md.read().unwrap().labels.clone());
+                            .map_or((Vec::new(), 0.), |md| {
+                                let md = md.read_recover();
+                                (md.labels.clone(), 2. * md.footprint_radius)
+                            });
+                        // Robots are seen the same from all sides: no incidence effect
+                        if let Some(detection) = &self.detection
+                            && !detection.detected(
+                                time + robot_seed,
+                                pose.fixed_rows::<2>(0).norm(),
+                                0.,
+                                size,
+                            )
+                        {
+                            if is_enabled(crate::logger::InternalLog::SensorManagerDetailed) {
+                                debug!("Robot {} not detected", other_node_name);
+                            }
+                            continue;
+                        }
                         let obs = SensorObservation::OrientedRobot(OrientedRobotObservation {
                             name: other_node_name.clone(),
                             labels,
@@ -713,7 +808,12 @@ impl Sensor for RobotSensor {
                         let mut new_obs = Vec::new();
                         if let Some(observation) = keep_observation {
                             new_obs.push(observation); // Not adding directly to observation_list to apply faults only once
-                            for fault_model in self.faults.iter_mut() {
+                            for (fault_model, _) in self
+                                .faults
+                                .iter_mut()
+                                .zip(&self.active_faults)
+                                .filter(|(_, active)| **active)
+                            {
                                 match fault_model {
                                     RobotSensorFaultModelType::Python(f) => f.add_faults(
                                         time,
@@ -915,6 +1015,75 @@ impl Sensor for RobotSensor {
                                             }
                                         }
                                     }
+                                    RobotSensorFaultModelType::Calibration(f) => {
+                                        for obs in new_obs.iter_mut().map(|o| {
+                                            if let SensorObservation::OrientedRobot(observation) = o
+                                            {
+                                                observation
+                                            } else {
+                                                unreachable!()
+                                            }
+                                        }) {
+                                            let (position, orientation) = f.to_sensor_frame(
+                                                obs.pose.fixed_rows::<2>(0).clone_owned(),
+                                                obs.pose.z,
+                                            );
+                                            obs.pose =
+                                                Vector3::new(position.x, position.y, orientation);
+                                            let new_values = f.add_faults(
+                                                RobotSensorVariablesFaults::mapped_values(
+                                                    |variant| match variant {
+                                                        RobotSensorVariablesFaults::X => obs.pose.x,
+                                                        RobotSensorVariablesFaults::Y => obs.pose.y,
+                                                        RobotSensorVariablesFaults::Orientation => {
+                                                            obs.pose.z
+                                                        }
+                                                        RobotSensorVariablesFaults::R => {
+                                                            obs.pose.fixed_rows::<2>(0).norm()
+                                                        }
+                                                        RobotSensorVariablesFaults::Theta => {
+                                                            obs.pose.y.atan2(obs.pose.x)
+                                                        }
+                                                    },
+                                                ),
+                                            );
+                                            if let Some(new_x) =
+                                                new_values.get(&RobotSensorVariablesFaults::X)
+                                            {
+                                                obs.pose.x = *new_x;
+                                            }
+                                            if let Some(new_y) =
+                                                new_values.get(&RobotSensorVariablesFaults::Y)
+                                            {
+                                                obs.pose.y = *new_y;
+                                            }
+                                            let new_r =
+                                                new_values.get(&RobotSensorVariablesFaults::R);
+                                            let new_theta =
+                                                new_values.get(&RobotSensorVariablesFaults::Theta);
+                                            if new_r.is_some() || new_theta.is_some() {
+                                                let r = new_r.cloned().unwrap_or_else(|| {
+                                                    obs.pose.fixed_rows::<2>(0).norm()
+                                                });
+                                                let theta =
+                                                    new_theta.cloned().unwrap_or_else(|| {
+                                                        obs.pose.y.atan2(obs.pose.x)
+                                                    });
+                                                obs.pose.x = r * theta.cos();
+                                                obs.pose.y = r * theta.sin();
+                                            }
+                                            if let Some(new_orientation) = new_values
+                                                .get(&RobotSensorVariablesFaults::Orientation)
+                                            {
+                                                obs.pose.z = *new_orientation;
+                                            }
+                                            obs.applied_faults.push(
+                                                RobotSensorFaultModelConfig::Calibration(
+                                                    f.config().clone(),
+                                                ),
+                                            );
+                                        }
+                                    }
                                     RobotSensorFaultModelType::Clutter(f) => {
                                         let new_obs_from_clutter =
                                             f.add_faults(time + robot_seed, robot_seed / 100.);
@@ -1034,6 +1203,13 @@ impl Sensor for RobotSensor {
         observation_list
     }
 
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::OrientedRobot],
+            ..Default::default()
+        }
+    }
+
     /// Get the next observation time.
     fn next_time_step(&self) -> f32 {
         if let Some(activation) = &self.activation_time {
@@ -1042,6 +1218,17 @@ impl Sensor for RobotSensor {
             f32::INFINITY
         }
     }
+
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        reconfigure_activation(&mut self.activation_time, message, time)?;
+        message.apply_fault_activation(&mut self.active_faults)?;
+        if let Some(noise_scale) = message.noise_scale {
+            for fault_model in self.faults.iter_mut() {
+                fault_model.set_noise_scale(noise_scale);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Recordable<SensorRecord> for RobotSensor {
@@ -17,7 +17,11 @@ use crate::{
     constants::TIME_ROUND,
     errors::SimbaResult,
     logger::is_enabled,
-    node::Node,
+    networking::reconfigure::ReconfigureMessage,
+    node::{
+        Node,
+        capabilities::{Capabilities, ObservationKind},
+    },
     plugin_api::PluginAPI,
     recordable::Recordable,
     sensors::{
@@ -30,6 +34,7 @@ use crate::{
             misdetection::{MisdetectionFault, MisdetectionFaultConfig},
             python_fault_model::{PythonFaultModel, PythonFaultModelConfig},
         },
+        reconfigure_activation,
         sensor_filters::{
             SensorFilter,
             external_filter::{ExternalFilter, ExternalFilterConfig},
@@ -40,7 +45,10 @@ use crate::{
     simulator::SimulatorConfig,
     state_estimators::State,
     utils::{
-        determinist_random_variable::DeterministRandomVariableFactory,
+        determinist_random_variable::{
+            DeterministRandomVariable, DeterministRandomVariableFactory, RandomVariableTypeConfig,
+        },
+        distributions::normal::NormalRandomVariableConfig,
         enum_tools::EnumVariables,
         geometry::{is_angle_inside, segments_intersection},
         periodicity::{Periodicity, PeriodicityConfig},
@@ -177,6 +185,20 @@ impl FaultModelTypeScanSensor {
             | Self::PointMisdetection(_) => Ok(()),
         }
     }
+
+    /// Scales the perturbations of the additive fault models, other models are not changed.
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        match self {
+            Self::AdditiveRobotCentered(f)
+            | Self::PointAdditiveRobotCentered(f)
+            | Self::PointAdditiveObservationCentered(f) => f.set_noise_scale(noise_scale),
+            Self::Python(_)
+            | Self::External(_)
+            | Self::Clutter(_)
+            | Self::Misdetection(_)
+            | Self::PointMisdetection(_) => {}
+        }
+    }
 }
 
 /// Configuration enum selecting among multiple sensor observation filtering strategies for scan sensors.
@@ -586,11 +608,40 @@ pub struct ScanSensor {
     height: f32,
     activation_time: Option<Periodicity>,
     faults: Vec<FaultModelTypeScanSensor>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
     filters: Vec<ScanSensorFilterType>,
+    /// Standard deviation of the range noise, see [`ScanSensor::with_range_noise`].
+    range_noise: f32,
+    /// One standard normal variable per ray, scaled by `range_noise`.
+    range_noises: Vec<DeterministRandomVariable>,
     last_time: Option<f32>,
 }
 
 impl ScanSensor {
+    /// Add a gaussian noise of standard deviation `range_noise` to the range measured by each
+    /// ray, before the filters and the faults. Each ray has its own random variable.
+    pub fn with_range_noise(
+        mut self,
+        range_noise: f32,
+        va_factory: &Arc<DeterministRandomVariableFactory>,
+    ) -> Self {
+        self.range_noise = range_noise;
+        self.range_noises = if range_noise > 0. {
+            self.rays
+                .iter()
+                .map(|_| {
+                    va_factory.make_variable(RandomVariableTypeConfig::Normal(
+                        NormalRandomVariableConfig::default(),
+                    ))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self
+    }
+
     /// Builds a new [`ScanSensor`] from [`ScanSensorConfig`].
     pub fn from_config(
         config: &ScanSensorConfig,
@@ -688,8 +739,11 @@ impl ScanSensor {
                 .activation_time
                 .as_ref()
                 .map(|p| Periodicity::from_config(p, va_factory, initial_time)),
+            active_faults: vec![true; fault_models.len()],
             faults: fault_models,
             filters,
+            range_noise: 0.,
+            range_noises: Vec::new(),
             last_time: None,
         })
     }
@@ -706,6 +760,13 @@ impl Sensor for ScanSensor {
         Ok(())
     }
 
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::Scan],
+            ..Default::default()
+        }
+    }
+
     fn next_time_step(&self) -> f32 {
         self.activation_time
             .as_ref()
@@ -713,6 +774,17 @@ impl Sensor for ScanSensor {
             .unwrap_or(f32::INFINITY)
     }
 
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        reconfigure_activation(&mut self.activation_time, message, time)?;
+        message.apply_fault_activation(&mut self.active_faults)?;
+        if let Some(noise_scale) = message.noise_scale {
+            for fault_model in self.faults.iter_mut() {
+                fault_model.set_noise_scale(noise_scale);
+            }
+        }
+        Ok(())
+    }
+
     fn get_observations(&mut self, node: &mut Node, time: f32) -> Vec<SensorObservation> {
         if let Some(last_time) = self.last_time
             && (time - last_time).abs() < TIME_ROUND
@@ -772,7 +844,7 @@ impl Sensor for ScanSensor {
 
         let mut observation = ScanObservation::default();
         // Ray casting
-        for ray in &self.rays {
+        for (ray_index, ray) in self.rays.iter().enumerate() {
             let world_ray_angle = state.pose.z + ray;
             let ray_direction =
                 nalgebra::Vector2::new(world_ray_angle.cos(), world_ray_angle.sin());
@@ -810,6 +882,10 @@ impl Sensor for ScanSensor {
                 }
             });
             if let Some((distance, _l, intersection)) = closest_intersection {
+                let distance = match self.range_noises.get(ray_index) {
+                    Some(noise) => (distance + self.range_noise * noise.generate(time)[0]).max(0.),
+                    None => distance,
+                };
                 observation.distances.push(distance);
                 let angle =
                     (intersection.y - position.y).atan2(intersection.x - position.x) - state.pose.z;
@@ -881,7 +957,12 @@ impl Sensor for ScanSensor {
 
         if let Some(filtered_observation) = keep_observation {
             let mut observations = vec![filtered_observation];
-            for fault_model in self.faults.iter_mut() {
+            for (fault_model, _) in self
+                .faults
+                .iter_mut()
+                .zip(&self.active_faults)
+                .filter(|(_, active)| **active)
+            {
                 match fault_model {
                     FaultModelTypeScanSensor::Python(f) => f.add_faults(
                         time,
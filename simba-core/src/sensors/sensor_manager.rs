@@ -14,6 +14,7 @@
 extern crate confy;
 use core::f32;
 use log::{debug, warn};
+use nalgebra::Vector3;
 use pyo3::prelude::*;
 use serde_derive::{Deserialize, Serialize};
 use simba_com::pub_sub::{MultiClientTrait, PathKey};
@@ -23,26 +24,38 @@ use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 
 use crate::constants::TIME_ROUND;
-use crate::errors::SimbaResult;
+use crate::errors::{SimbaError, SimbaErrorTypes, SimbaResult};
 #[cfg(feature = "gui")]
 use crate::gui::{
     UIComponent,
-    utils::{string_checkbox, text_singleline_with_apply},
+    utils::{path_finder, string_checkbox, string_combobox, text_singleline_with_apply},
 };
 use crate::logger::{InternalLog, is_enabled};
 use crate::networking;
 use crate::networking::network::Envelope;
+use crate::networking::reconfigure::{
+    ReconfigurationOutcome, ReconfigurationRecord, ReconfigureMessage,
+};
 use crate::node::Node;
+use crate::node::capabilities::{Capabilities, ObservationKind};
 use crate::node::node_factory::FromConfigArguments;
+use crate::sensors::bearing_sensor::BearingSensor;
 use crate::sensors::displacement_sensor::DisplacementSensor;
 use crate::sensors::external_sensor::ExternalSensor;
+use crate::sensors::fault_models::dropout_timeline::DropoutTimeline;
+use crate::sensors::imu_sensor::ImuSensor;
+use crate::sensors::lidar_sensor::LidarSensor;
 use crate::sensors::scan_sensor::ScanSensor;
 use crate::simulator::SimbaBrokerMultiClient;
 use crate::state_estimators::State;
 use crate::utils::SharedRwLock;
+#[cfg(feature = "gui")]
+use crate::utils::enum_tools::ToVec;
+use crate::utils::lock_recovery::RecoverRwLock;
 use crate::{recordable::Recordable, simulator::SimulatorConfig};
 
 use super::gnss_sensor::GNSSSensor;
+use super::observation_frame::ObservationFrame;
 use super::oriented_landmark_sensor::OrientedLandmarkSensor;
 use super::robot_sensor::RobotSensor;
 use super::speed_sensor::{SpeedSensor, SpeedSensorConfig};
@@ -58,6 +71,10 @@ use super::{Observation, ObservationRecord, Sensor, SensorConfig, SensorRecord};
 /// - `name`: `"some_sensor"`
 /// - `send_to`: empty vector
 /// - `triggered`: `false`, setting it to `true` ignore the activation times of the sensor and wait for [`SensorTriggerMessage`] to produce observations.
+/// - `dropout_timeline`: `None`
+/// - `inactive_faults`: empty vector
+/// - `frame`: `None`, the observations are kept in the native frame of the sensor (the robot frame, or the map frame for the GNSS)
+/// - `sensor_pose`: `[0.0, 0.0, 0.0]`
 /// - `config`: [`SensorConfig::Speed`] with [`SpeedSensorConfig::default`]
 #[config_derives]
 pub struct ManagedSensorConfig {
@@ -67,6 +84,19 @@ pub struct ManagedSensorConfig {
     pub send_to: Vec<String>,
     /// Whether this sensor produces observations only when explicitly triggered.
     pub triggered: bool,
+    /// Path of a CSV or YAML file listing the time intervals during which all the observations
+    /// of this sensor are dropped (see [`DropoutTimeline`]). Relative paths are resolved from the
+    /// directory of the configuration file.
+    pub dropout_timeline: Option<String>,
+    /// Indexes, in the `faults` list of the sensor configuration, of the fault models inactive at
+    /// start. They can be activated later by a `SensorFault` scenario event.
+    pub inactive_faults: Vec<usize>,
+    /// Frame in which the observations are expressed (see [`ObservationFrame`]).
+    pub frame: Option<ObservationFrame>,
+    /// Pose `[x, y, theta]` of the sensor in the robot frame, defining the
+    /// [`Sensor`](ObservationFrame::Sensor) frame. The observations are still made from the robot
+    /// frame.
+    pub sensor_pose: [f32; 3],
     #[check]
     /// Concrete sensor configuration.
     pub config: SensorConfig,
@@ -78,11 +108,26 @@ impl Default for ManagedSensorConfig {
             name: "some_sensor".to_string(),
             send_to: Vec::new(),
             triggered: false,
+            dropout_timeline: None,
+            inactive_faults: Vec::new(),
+            frame: None,
+            sensor_pose: [0.; 3],
             config: SensorConfig::Speed(SpeedSensorConfig::default()),
         }
     }
 }
 
+impl Check for ManagedSensorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        match &self.frame {
+            Some(frame) => frame
+                .check_sensor(&self.config, &self.sensor_pose)
+                .map_err(|e| vec![e]),
+            None => Ok(()),
+        }
+    }
+}
+
 #[cfg(feature = "gui")]
 impl UIComponent for ManagedSensorConfig {
     fn show_mut(
@@ -112,6 +157,70 @@ impl UIComponent for ManagedSensorConfig {
                     ui.checkbox(&mut self.triggered, "");
                 });
 
+                ui.horizontal(|ui| {
+                    ui.label("Dropout timeline: ");
+                    if let Some(path) = &mut self.dropout_timeline {
+                        path_finder(ui, path, &global_config.base_path);
+                        if ui.button("X").clicked() {
+                            self.dropout_timeline = None;
+                        }
+                    } else if ui.button("+").clicked() {
+                        self.dropout_timeline = Some(String::new());
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Inactive faults at start:");
+                    let mut to_remove = None;
+                    for (i, fault) in self.inactive_faults.iter_mut().enumerate() {
+                        ui.add(egui::DragValue::new(fault));
+                        if ui.button("-").clicked() {
+                            to_remove = Some(i);
+                        }
+                    }
+                    if let Some(i) = to_remove {
+                        self.inactive_faults.remove(i);
+                    }
+                    if ui.button("+").clicked() {
+                        self.inactive_faults.push(0);
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Frame: ");
+                    if let Some(frame) = &mut self.frame {
+                        let mut current_str = frame.to_string();
+                        string_combobox(
+                            ui,
+                            &ObservationFrame::to_vec(),
+                            &mut current_str,
+                            format!("managed-sensor-frame-choice-{}", unique_id),
+                        );
+                        if current_str != frame.to_string() {
+                            *frame = match current_str.as_str() {
+                                "Robot" => ObservationFrame::Robot,
+                                "Sensor" => ObservationFrame::Sensor,
+                                "Map" => ObservationFrame::Map,
+                                _ => panic!("Where did you find this value?"),
+                            };
+                        }
+                        if ui.button("X").clicked() {
+                            self.frame = None;
+                        }
+                    } else if ui.button("+").clicked() {
+                        self.frame = Some(ObservationFrame::default());
+                    }
+                });
+
+                if matches!(self.frame, Some(ObservationFrame::Sensor)) {
+                    ui.horizontal(|ui| {
+                        ui.label("Sensor pose: ");
+                        for value in self.sensor_pose.iter_mut() {
+                            ui.add(egui::DragValue::new(value).speed(0.001));
+                        }
+                    });
+                }
+
                 let mut node_list = Vec::from_iter(
                     global_config.robots.iter().map(|x| x.name.clone()).chain(
                         global_config
@@ -154,6 +263,27 @@ impl UIComponent for ManagedSensorConfig {
                     ui.label(format!("Triggered: {}", self.triggered));
                 });
 
+                if let Some(path) = &self.dropout_timeline {
+                    ui.label(format!("Dropout timeline: {path}"));
+                }
+
+                if !self.inactive_faults.is_empty() {
+                    ui.label(format!(
+                        "Inactive faults at start: {:?}",
+                        self.inactive_faults
+                    ));
+                }
+
+                if let Some(frame) = &self.frame {
+                    ui.label(format!("Frame: {frame}"));
+                    if let ObservationFrame::Sensor = frame {
+                        ui.label(format!(
+                            "Sensor pose: [{}, {}, {}]",
+                            self.sensor_pose[0], self.sensor_pose[1], self.sensor_pose[2]
+                        ));
+                    }
+                }
+
                 ui.horizontal_wrapped(|ui| {
                     ui.label("Send to: ");
                     for to in &self.send_to {
@@ -250,6 +380,24 @@ impl UIComponent for SensorManagerRecord {
             for s in &self.sensors {
                 egui::CollapsingHeader::new(&s.name).show(ui, |ui| {
                     s.record.show(ui, ctx, unique_id);
+                    if s.dropped_observations > 0 {
+                        ui.label(format!(
+                            "Dropped observations (dropout timeline): {}",
+                            s.dropped_observations
+                        ));
+                    }
+                    for r in &s.reconfigurations {
+                        ui.label(format!(
+                            "Reconfiguration at {}: period {:?}, noise scale {:?}{}",
+                            r.time,
+                            r.message.period,
+                            r.message.noise_scale,
+                            r.error
+                                .as_ref()
+                                .map(|e| format!(" (rejected: {e})"))
+                                .unwrap_or_default()
+                        ));
+                    }
                 });
             }
         });
@@ -273,6 +421,12 @@ pub struct ManagedSensorRecord {
     pub last_triggered: Option<f32>,
     /// Sensor-specific record payload.
     pub record: SensorRecord,
+    /// Reconfiguration messages received by this sensor since the last record.
+    #[serde(default)]
+    pub reconfigurations: Vec<ReconfigurationRecord>,
+    /// Number of observations dropped by the dropout timeline since the start.
+    #[serde(default)]
+    pub dropped_observations: usize,
 }
 
 /// Runtime manager of one sensor allowing its triggering, observation retrieval, and observation forwarding.
@@ -282,6 +436,11 @@ struct ManagedSensor {
     send_to: Vec<String>,
     triggered: bool,
     last_triggered: Option<f32>,
+    reconfigurations: Vec<ReconfigurationRecord>,
+    dropout_timeline: DropoutTimeline,
+    dropped_observations: usize,
+    frame: Option<ObservationFrame>,
+    sensor_pose: Vector3<f32>,
     sensor: SharedRwLock<Box<dyn Sensor>>,
 }
 
@@ -318,6 +477,8 @@ impl SensorManager {
     pub const CHANNEL_NAME: &'static str = "sensors";
     /// Channel segment used for observation payload messages.
     pub const OBSERVATION_CHANNEL: &'static str = "observations";
+    /// Channel segment used for [`ReconfigureMessage`]s, followed by the sensor name.
+    pub const RECONFIGURE_CHANNEL: &'static str = "reconfigure";
 
     /// Makes a new [`SensorManager`] without any [`Sensor`].
     pub fn new() -> Self {
@@ -354,6 +515,7 @@ impl SensorManager {
                 .clone()
                 .join_str(Self::OBSERVATION_CHANNEL),
         );
+        let sensors_va_factory = from_config_args.va_factory.child("sensors");
         for sensor_config in &config.sensors {
             if sensor_config.triggered {
                 from_config_args
@@ -362,71 +524,132 @@ impl SensorManager {
                     .unwrap()
                     .make_channel(sensor_manager_key.clone().join_str(&sensor_config.name));
             }
+            from_config_args.network.write().unwrap().make_channel(
+                sensor_manager_key
+                    .clone()
+                    .join_str(Self::RECONFIGURE_CHANNEL)
+                    .join_str(&sensor_config.name),
+            );
+
+            let dropout_timeline = match &sensor_config.dropout_timeline {
+                Some(path) => DropoutTimeline::load_from_path(
+                    &from_config_args.global_config.base_path.join(path),
+                )?,
+                None => DropoutTimeline::default(),
+            };
+
+            // Each sensor draws from its own stream, so adding a sensor does not change the others
+            let va_factory = sensors_va_factory.child(&sensor_config.name);
+
+            let mut sensor = match &sensor_config.config {
+                SensorConfig::OrientedLandmark(c) => Box::new(OrientedLandmarkSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::Speed(c) => Box::new(SpeedSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::Displacement(c) => Box::new(DisplacementSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                    initial_state,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::GNSS(c) => Box::new(GNSSSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::Robot(c) => Box::new(RobotSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::Scan(c) => Box::new(ScanSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::External(c) => Box::new(ExternalSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.network,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::Lidar(c) => Box::new(LidarSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::Imu(c) => Box::new(ImuSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+                SensorConfig::Bearing(c) => Box::new(BearingSensor::from_config(
+                    c,
+                    from_config_args.plugin_api,
+                    from_config_args.global_config,
+                    &va_factory,
+                    from_config_args.initial_time,
+                )?) as Box<dyn Sensor>,
+            };
+            for fault in &sensor_config.inactive_faults {
+                sensor
+                    .reconfigure(
+                        &ReconfigureMessage {
+                            fault_activation: Some((*fault, false)),
+                            ..Default::default()
+                        },
+                        from_config_args.initial_time,
+                    )
+                    .map_err(|e| {
+                        SimbaError::new(
+                            SimbaErrorTypes::ConfigError,
+                            format!(
+                                "Cannot deactivate fault {fault} of sensor {}: {e}",
+                                sensor_config.name
+                            ),
+                        )
+                    })?;
+            }
 
             manager.sensors.push(ManagedSensor {
                 name: sensor_config.name.clone(),
                 send_to: sensor_config.send_to.clone(),
-                sensor: Arc::new(RwLock::new(match &sensor_config.config {
-                    SensorConfig::OrientedLandmark(c) => {
-                        Box::new(OrientedLandmarkSensor::from_config(
-                            c,
-                            from_config_args.plugin_api,
-                            from_config_args.global_config,
-                            from_config_args.va_factory,
-                            from_config_args.initial_time,
-                        )?) as Box<dyn Sensor>
-                    }
-                    SensorConfig::Speed(c) => Box::new(SpeedSensor::from_config(
-                        c,
-                        from_config_args.plugin_api,
-                        from_config_args.global_config,
-                        from_config_args.va_factory,
-                        from_config_args.initial_time,
-                    )?) as Box<dyn Sensor>,
-                    SensorConfig::Displacement(c) => Box::new(DisplacementSensor::from_config(
-                        c,
-                        from_config_args.plugin_api,
-                        from_config_args.global_config,
-                        from_config_args.va_factory,
-                        from_config_args.initial_time,
-                        initial_state,
-                    )?) as Box<dyn Sensor>,
-                    SensorConfig::GNSS(c) => Box::new(GNSSSensor::from_config(
-                        c,
-                        from_config_args.plugin_api,
-                        from_config_args.global_config,
-                        from_config_args.va_factory,
-                        from_config_args.initial_time,
-                    )?) as Box<dyn Sensor>,
-                    SensorConfig::Robot(c) => Box::new(RobotSensor::from_config(
-                        c,
-                        from_config_args.plugin_api,
-                        from_config_args.global_config,
-                        from_config_args.va_factory,
-                        from_config_args.initial_time,
-                    )?) as Box<dyn Sensor>,
-                    SensorConfig::Scan(c) => Box::new(ScanSensor::from_config(
-                        c,
-                        from_config_args.plugin_api,
-                        from_config_args.global_config,
-                        from_config_args.va_factory,
-                        from_config_args.initial_time,
-                    )?) as Box<dyn Sensor>,
-                    SensorConfig::External(c) => Box::new(ExternalSensor::from_config(
-                        c,
-                        from_config_args.plugin_api,
-                        from_config_args.global_config,
-                        from_config_args.va_factory,
-                        from_config_args.network,
-                        from_config_args.initial_time,
-                    )?) as Box<dyn Sensor>,
-                })),
+                sensor: Arc::new(RwLock::new(sensor)),
                 triggered: sensor_config.triggered,
                 last_triggered: None,
+                reconfigurations: Vec::new(),
+                dropout_timeline,
+                dropped_observations: 0,
+                frame: sensor_config.frame.clone(),
+                sensor_pose: Vector3::from(sensor_config.sensor_pose),
             });
         }
 
-        // Subscribe to all channels of the sensor manager, to receive both observations and trigger messages:
+        // Subscribe to all channels of the sensor manager, to receive observations, trigger and reconfiguration messages:
         manager.message_client = Some(
             from_config_args
                 .network
@@ -468,7 +691,7 @@ impl SensorManager {
     /// Handles incoming sensor-manager messages at the given simulation time.
     ///
     /// This consumes queued messages from the internal subscriber, updates remote
-    /// observations, and applies trigger messages to targeted sensors.
+    /// observations, and applies trigger and reconfiguration messages to targeted sensors.
     ///
     /// This is where distant observations are collected.
     pub fn handle_messages(&mut self, time: f32) {
@@ -501,6 +724,23 @@ impl SensorManager {
                         envelope.from, envelope.timestamp
                     );
                 }
+            } else if path.parent()
+                == Some(
+                    self.channel_root
+                        .as_ref()
+                        .unwrap()
+                        .join_str(Self::RECONFIGURE_CHANNEL),
+                )
+            {
+                let sensor_name = path.to_vec().last().unwrap().clone();
+                let result = self.reconfigure_sensor(&sensor_name, envelope.message, time);
+                ReconfigurationOutcome::acknowledge(
+                    self.message_client.as_ref().unwrap(),
+                    &path,
+                    &envelope.message_flags,
+                    &result,
+                    time,
+                );
             } else if serde_json::from_value::<SensorTriggerMessage>(envelope.message.clone())
                 .is_ok()
             {
@@ -522,6 +762,56 @@ impl SensorManager {
         }
     }
 
+    /// Applies a [`ReconfigureMessage`] to the sensor `sensor_name` and records the result.
+    ///
+    /// Returns the reason of the rejection if the message is invalid or cannot be applied.
+    fn reconfigure_sensor(
+        &mut self,
+        sensor_name: &str,
+        message: serde_json::Value,
+        time: f32,
+    ) -> Result<(), String> {
+        let Some(sensor) = self.sensors.iter_mut().find(|s| s.name == sensor_name) else {
+            warn!("[Sensor Manager] Reconfiguration of unknown sensor {sensor_name}");
+            return Err(format!("Unknown sensor {sensor_name}"));
+        };
+        let message = match serde_json::from_value::<ReconfigureMessage>(message) {
+            Ok(m) => m,
+            Err(e) => {
+                warn!(
+                    "[Sensor Manager] Invalid reconfiguration message for sensor {sensor_name}: {e}"
+                );
+                return Err(format!("Invalid reconfiguration message: {e}"));
+            }
+        };
+        let result = message
+            .validate()
+            .and_then(|_| sensor.sensor.write().unwrap().reconfigure(&message, time));
+        if let Err(e) = &result {
+            warn!("[Sensor Manager] Reconfiguration of sensor {sensor_name} rejected: {e}");
+        } else if is_enabled(crate::logger::InternalLog::SensorManager) {
+            debug!("Sensor {sensor_name} reconfigured at time {time}: {message:?}");
+        }
+        sensor.reconfigurations.push(ReconfigurationRecord {
+            time,
+            message,
+            error: result.as_ref().err().cloned(),
+        });
+        self.next_time = self
+            .sensors
+            .iter()
+            .map(|s| s.sensor.read().unwrap().next_time_step())
+            .reduce(f32::min);
+        result
+    }
+
+    /// Forget the reconfigurations already recorded.
+    pub(crate) fn clear_reconfigurations(&mut self) {
+        for sensor in self.sensors.iter_mut() {
+            sensor.reconfigurations.clear();
+        }
+    }
+
     /// Consume the last observations. This includes both local observations produced by the node's sensors
     /// and distant observations received from other nodes.
     pub fn get_observations(&mut self) -> Vec<Observation> {
@@ -558,7 +848,7 @@ impl SensorManager {
                     sensor.triggered
                 );
             }
-            let sensor_observations: Vec<Observation> = if (sensor.triggered
+            let mut sensor_observations: Vec<Observation> = if (sensor.triggered
                 && match sensor.last_triggered {
                     Some(t) => (time - t).abs() < TIME_ROUND,
                     None => false,
@@ -578,12 +868,58 @@ impl SensorManager {
                         sensor_name: sensor.name.clone(),
                         observer: node.name(),
                         time,
+                        frame: ObservationFrame::native(ObservationKind::from(&obs)),
                         sensor_observation: obs,
                     })
                     .collect()
             } else {
                 Vec::new()
             };
+            if !sensor_observations.is_empty() && sensor.dropout_timeline.is_dropped(time) {
+                if is_enabled(InternalLog::SensorManager) {
+                    log::debug!(
+                        "Sensor {} is in a scheduled dropout, {} observations dropped",
+                        sensor.name,
+                        sensor_observations.len()
+                    );
+                }
+                sensor.dropped_observations += sensor_observations.len();
+                sensor_observations.clear();
+            }
+
+            if let Some(frame) = &sensor.frame
+                && sensor_observations.iter().any(|obs| obs.frame != *frame)
+            {
+                let robot_pose = match frame {
+                    // The map frame uses the estimate of the node, not its real pose
+                    ObservationFrame::Map => node
+                        .state_estimator()
+                        .and_then(|state_estimator| {
+                            state_estimator.read_recover().world_state().ego
+                        })
+                        .map(|ego| ego.pose),
+                    _ => Some(State::new().pose),
+                };
+                if let Some(robot_pose) = robot_pose {
+                    for observation in sensor_observations.iter_mut() {
+                        frame.transform(
+                            &mut observation.sensor_observation,
+                            &sensor.sensor_pose,
+                            &robot_pose,
+                        );
+                        observation.frame = frame.clone();
+                    }
+                } else {
+                    warn!(
+                        "[{}] Sensor {}: no estimated pose to express the observations in the map frame, {} observations dropped",
+                        node.name(),
+                        sensor.name,
+                        sensor_observations.len()
+                    );
+                    sensor.dropped_observations += sensor_observations.len();
+                    sensor_observations.clear();
+                }
+            }
 
             if !sensor_observations.is_empty() {
                 for to in &sensor.send_to {
@@ -624,6 +960,7 @@ impl SensorManager {
                                 message: obs_serialized,
                                 timestamp: time,
                                 message_flags: Vec::new(),
+                                ..Default::default()
                             },
                             time,
                         );
@@ -639,6 +976,18 @@ impl SensorManager {
     pub fn next_time_step(&self) -> Option<f32> {
         self.next_time
     }
+
+    /// Capabilities of each sensor, with its name.
+    pub fn capabilities(&self) -> Vec<(String, Capabilities)> {
+        self.sensors
+            .iter()
+            .map(|s| {
+                let mut capabilities = s.sensor.read().unwrap().capabilities();
+                capabilities.observation_frame = s.frame.clone();
+                (s.name.clone(), capabilities)
+            })
+            .collect()
+    }
 }
 
 impl Default for SensorManager {
@@ -660,6 +1009,8 @@ impl Recordable<SensorManagerRecord> for SensorManager {
                 name: sensor.name.clone(),
                 record: sensor.sensor.read().unwrap().record(),
                 last_triggered: sensor.last_triggered,
+                reconfigurations: sensor.reconfigurations.clone(),
+                dropped_observations: sensor.dropped_observations,
             });
         }
         record
@@ -8,7 +8,7 @@
 use std::sync::Arc;
 
 use super::fault_models::fault_model::FaultModel;
-use super::{Sensor, SensorObservation, SensorRecord};
+use super::{Sensor, SensorObservation, SensorRecord, reconfigure_activation};
 
 use crate::constants::TIME_ROUND;
 
@@ -16,9 +16,11 @@ use crate::errors::SimbaResult;
 #[cfg(feature = "gui")]
 use crate::gui::UIComponent;
 use crate::logger::is_enabled;
+use crate::networking::reconfigure::ReconfigureMessage;
 use crate::plugin_api::PluginAPI;
 use crate::recordable::Recordable;
 use crate::sensors::fault_models::additive::{AdditiveFault, AdditiveFaultConfig};
+use crate::sensors::fault_models::calibration::{CalibrationFault, CalibrationFaultConfig};
 use crate::sensors::fault_models::external_fault::{ExternalFault, ExternalFaultConfig};
 use crate::sensors::fault_models::python_fault_model::{PythonFaultModel, PythonFaultModelConfig};
 use crate::sensors::sensor_filters::SensorFilter;
@@ -67,6 +69,8 @@ pub enum SpeedSensorFaultModelConfig {
     Python(PythonFaultModelConfig),
     /// Plugin-provided external fault model.
     External(ExternalFaultConfig),
+    /// Calibration fault model, the velocities are measured at the real mounting pose.
+    Calibration(CalibrationFaultConfig<SpeedSensorVariablesFaults>),
 }
 
 impl Default for SpeedSensorFaultModelConfig {
@@ -86,17 +90,27 @@ pub enum SpeedSensorFaultModelType {
     Python(PythonFaultModel),
     /// Instantiated external fault model.
     External(ExternalFault),
+    /// Instantiated calibration fault model.
+    Calibration(CalibrationFault<SpeedSensorVariablesFaults>),
 }
 
 impl SpeedSensorFaultModelType {
     /// Wraps the post-initialization of fault models that require runtime node context.
     pub fn post_init(&mut self, node: &mut Node, initial_time: f32) -> SimbaResult<()> {
         match self {
-            Self::Additive(_) => Ok(()),
+            Self::Additive(_) | Self::Calibration(_) => Ok(()),
             Self::Python(f) => f.post_init(node, initial_time),
             Self::External(f) => f.post_init(node, initial_time),
         }
     }
+
+    /// Scales the perturbations of the additive fault models, other models are not changed.
+    pub fn set_noise_scale(&mut self, noise_scale: f32) {
+        match self {
+            Self::Additive(f) => f.set_noise_scale(noise_scale),
+            Self::Python(_) | Self::External(_) | Self::Calibration(_) => {}
+        }
+    }
 }
 
 /// Configuration enum selecting among multiple sensor observation filtering strategies for speed sensors.
@@ -347,6 +361,8 @@ pub struct SpeedSensor {
     /// Last observation time.
     last_time: Option<f32>,
     faults: Vec<SpeedSensorFaultModelType>,
+    /// Activation of each fault model, changed by the reconfiguration.
+    active_faults: Vec<bool>,
     filters: Vec<SpeedSensorFilterType>,
 }
 
@@ -368,6 +384,12 @@ impl SpeedSensor {
                 SpeedSensorFaultModelConfig::Python(c) => SpeedSensorFaultModelType::Python(
                     PythonFaultModel::from_config(c, global_config, initial_time)?,
                 ),
+                SpeedSensorFaultModelConfig::Calibration(c) => {
+                    SpeedSensorFaultModelType::Calibration(CalibrationFault::from_config(
+                        c,
+                        initial_time,
+                    ))
+                }
                 SpeedSensorFaultModelConfig::External(c) => {
                     SpeedSensorFaultModelType::External(ExternalFault::from_config(
                         c,
@@ -409,6 +431,7 @@ impl SpeedSensor {
             last_state: State::new(),
             activation_time: period,
             last_time: None,
+            active_faults: vec![true; fault_models.len()],
             faults: fault_models,
             filters,
         })
@@ -416,6 +439,7 @@ impl SpeedSensor {
 }
 
 use crate::node::Node;
+use crate::node::capabilities::{Capabilities, NodeService, ObservationKind};
 
 impl Sensor for SpeedSensor {
     fn post_init(&mut self, node: &mut Node, initial_time: f32) -> SimbaResult<()> {
@@ -489,7 +513,12 @@ impl Sensor for SpeedSensor {
         let mut observation_list = Vec::<SensorObservation>::new();
         if let Some(obs) = keep_observation {
             observation_list.push(obs);
-            for fault_model in self.faults.iter_mut() {
+            for (fault_model, _) in self
+                .faults
+                .iter_mut()
+                .zip(&self.active_faults)
+                .filter(|(_, active)| **active)
+            {
                 match fault_model {
                     SpeedSensorFaultModelType::Python(f) => f.add_faults(
                         time,
@@ -542,6 +571,34 @@ impl Sensor for SpeedSensor {
                             });
                         }
                     }
+                    SpeedSensorFaultModelType::Calibration(f) => {
+                        let mounting = f.mounting_error();
+                        for obs in observation_list.iter_mut() {
+                            let SensorObservation::Speed(obs) = obs else {
+                                unreachable!()
+                            };
+                            // Velocity of the real mounting point, in the sensor frame
+                            let velocity = na::Rotation2::new(-mounting.z)
+                                * (na::Vector2::new(obs.linear_velocity, obs.lateral_velocity)
+                                    + obs.angular_velocity
+                                        * na::Vector2::new(-mounting.y, mounting.x));
+                            obs.linear_velocity = velocity.x;
+                            obs.lateral_velocity = velocity.y;
+                            f.add_faults(SpeedSensorVariablesFaults::mapped_values(|variant| {
+                                match variant {
+                                    SpeedSensorVariablesFaults::W => obs.angular_velocity,
+                                    SpeedSensorVariablesFaults::V => obs.linear_velocity,
+                                }
+                            }))
+                            .into_iter()
+                            .for_each(|(variant, value)| match variant {
+                                SpeedSensorVariablesFaults::W => obs.angular_velocity = value,
+                                SpeedSensorVariablesFaults::V => obs.linear_velocity = value,
+                            });
+                            obs.applied_faults
+                                .push(SpeedSensorFaultModelConfig::Calibration(f.config().clone()));
+                        }
+                    }
                 }
             }
         } else if is_enabled(crate::logger::InternalLog::SensorManagerDetailed) {
@@ -556,6 +613,15 @@ impl Sensor for SpeedSensor {
         observation_list
     }
 
+    /// Needs the real state of its node.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            produced_observations: vec![ObservationKind::Speed],
+            required_services: vec![NodeService::RealState],
+            ..Default::default()
+        }
+    }
+
     fn next_time_step(&self) -> f32 {
         if let Some(activation) = &self.activation_time {
             activation.next_time()
@@ -563,6 +629,17 @@ impl Sensor for SpeedSensor {
             f32::INFINITY
         }
     }
+
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        reconfigure_activation(&mut self.activation_time, message, time)?;
+        message.apply_fault_activation(&mut self.active_faults)?;
+        if let Some(noise_scale) = message.noise_scale {
+            for fault_model in self.faults.iter_mut() {
+                fault_model.set_noise_scale(noise_scale);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Recordable<SensorRecord> for SpeedSensor {
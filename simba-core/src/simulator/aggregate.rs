@@ -0,0 +1,475 @@
+//! Statistics across the runs of an experiment.
+//!
+//! The result files of a directory are loaded ([`aggregate_results_directory`]): simulator result
+//! files (JSON) give one run each, batch result files ([`BatchResults`]) give one run per batch
+//! run. The key performance indicators (KPIs) of each run are computed from its records: mission
+//! ([`MissionSummary`](super::MissionSummary)), localization
+//! ([`LocalizationMetrics`](super::LocalizationMetrics)) and idleness.
+//!
+//! For each KPI, the mean, the standard deviation and the confidence interval of the mean
+//! (Student's t distribution) are computed over the runs. The runs whose value is far from the
+//! others (modified z-score, based on the median absolute deviation, above
+//! [`OUTLIER_THRESHOLD`]) are reported with their seed, to look at them individually.
+//!
+//! The [`AggregateResults`] are saved in a single JSON file, to be read by the analysis scripts
+//! (plain JSON) or by Rust tools ([`AggregateResults::load`]).
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{ContinuousCDF, StudentsT};
+
+use crate::errors::{SimbaError, SimbaErrorTypes, SimbaResult};
+
+use super::{
+    BATCH_RESULTS_FORMAT, BatchResults, BatchRunner, FleetRecord, LocalizationMetrics,
+    MAIN_ESTIMATOR_NAME, Record, Simulator, mission::MissionTracker,
+};
+
+/// Confidence level of the intervals of the means.
+pub const CONFIDENCE_LEVEL: f32 = 0.95;
+
+/// Modified z-score above which a run is an outlier for a KPI.
+pub const OUTLIER_THRESHOLD: f32 = 3.5;
+
+/// KPIs of one run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunKpis {
+    /// Name of the run: result file name, with the index of the run for the batch files.
+    pub name: String,
+    /// Seed of the run, if known.
+    pub seed: Option<f32>,
+    /// Error which stopped the run (batch runs), the KPIs are empty then.
+    pub error: Option<String>,
+    /// Value of each KPI. A KPI without value in the run (no goal, no estimator...) is absent.
+    pub kpis: BTreeMap<String, f32>,
+}
+
+impl RunKpis {
+    /// Compute the KPIs of a run from its records.
+    pub fn from_records(
+        name: String,
+        seed: Option<f32>,
+        records: &[Record],
+        fleet_records: &[FleetRecord],
+    ) -> Self {
+        let mut kpis = BTreeMap::new();
+        if let Some(duration) = records.iter().map(|record| record.time).reduce(f32::max) {
+            kpis.insert("simulated_time".to_string(), duration);
+        }
+
+        let mut mission = MissionTracker::default();
+        mission.add_records(records);
+        let fleet = mission.summary().fleet;
+        kpis.insert("goals_attempted".to_string(), fleet.goals_attempted as f32);
+        kpis.insert("goals_reached".to_string(), fleet.goals_reached as f32);
+        kpis.insert("distance".to_string(), fleet.distance);
+        for (kpi, value) in [
+            ("success_rate", fleet.success_rate),
+            ("mean_time_to_goal", fleet.mean_time_to_goal),
+            ("completion_time", fleet.completion_time),
+        ] {
+            if let Some(value) = value {
+                kpis.insert(kpi.to_string(), value);
+            }
+        }
+
+        let metrics = LocalizationMetrics::from_records(records);
+        let main: Vec<_> = metrics
+            .estimators
+            .iter()
+            .filter(|estimator| estimator.estimator == MAIN_ESTIMATOR_NAME)
+            .collect();
+        let position_rmse: Vec<f32> = main.iter().map(|e| e.position_rmse).collect();
+        let heading_rmse: Vec<f32> = main.iter().map(|e| e.heading_rmse).collect();
+        let anees: Vec<f32> = main.iter().filter_map(|e| e.anees).collect();
+        for (kpi, values) in [
+            ("position_rmse", position_rmse),
+            ("heading_rmse", heading_rmse),
+            ("anees", anees),
+        ] {
+            if !values.is_empty() {
+                kpis.insert(
+                    kpi.to_string(),
+                    values.iter().sum::<f32>() / values.len() as f32,
+                );
+            }
+        }
+
+        if !fleet_records.is_empty() {
+            kpis.insert(
+                "average_idleness".to_string(),
+                fleet_records
+                    .iter()
+                    .map(|record| record.average_idleness)
+                    .sum::<f32>()
+                    / fleet_records.len() as f32,
+            );
+            kpis.insert(
+                "worst_idleness".to_string(),
+                fleet_records
+                    .iter()
+                    .map(|record| record.max_idleness)
+                    .fold(0., f32::max),
+            );
+        }
+
+        Self {
+            name,
+            seed,
+            error: None,
+            kpis,
+        }
+    }
+}
+
+/// Statistics of a KPI over the runs where it has a value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KpiStatistics {
+    /// Name of the KPI.
+    pub kpi: String,
+    /// Number of runs with a value.
+    pub count: usize,
+    /// Mean over the runs.
+    pub mean: f32,
+    /// Sample standard deviation over the runs (0 with one run).
+    pub std: f32,
+    /// Smallest value.
+    pub min: f32,
+    /// Median value.
+    pub median: f32,
+    /// Largest value.
+    pub max: f32,
+    /// Confidence interval of the mean `[low, high]`, at [`CONFIDENCE_LEVEL`]. `None` with less
+    /// than two runs.
+    pub confidence_interval: Option<[f32; 2]>,
+}
+
+/// Run whose value of a KPI is far from the other runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Outlier {
+    /// Name of the run.
+    pub run: String,
+    /// Seed of the run, if known.
+    pub seed: Option<f32>,
+    /// KPI for which the run is an outlier.
+    pub kpi: String,
+    /// Value of the KPI in the run.
+    pub value: f32,
+    /// Modified z-score of the value.
+    pub score: f32,
+}
+
+/// Aggregate of the runs of an experiment, saved by [`AggregateResults::save`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AggregateResults {
+    /// KPIs of each run, sorted by name.
+    pub runs: Vec<RunKpis>,
+    /// Statistics of each KPI over the runs without error, sorted by KPI name.
+    pub statistics: Vec<KpiStatistics>,
+    /// Outlier runs, by KPI then run.
+    pub outliers: Vec<Outlier>,
+}
+
+fn median(sorted: &[f32]) -> f32 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        0.5 * (sorted[n / 2 - 1] + sorted[n / 2])
+    }
+}
+
+impl AggregateResults {
+    /// Compute the statistics and the outliers of the `runs`.
+    pub fn from_runs(mut runs: Vec<RunKpis>) -> Self {
+        runs.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut values: BTreeMap<&str, Vec<(&RunKpis, f32)>> = BTreeMap::new();
+        for run in runs.iter().filter(|run| run.error.is_none()) {
+            for (kpi, value) in &run.kpis {
+                if value.is_finite() {
+                    values.entry(kpi).or_default().push((run, *value));
+                }
+            }
+        }
+
+        let mut statistics = Vec::new();
+        let mut outliers = Vec::new();
+        for (kpi, runs_values) in &values {
+            let mut sorted: Vec<f32> = runs_values.iter().map(|(_, value)| *value).collect();
+            sorted.sort_by(f32::total_cmp);
+            let count = sorted.len();
+            let mean = sorted.iter().sum::<f32>() / count as f32;
+            let std = if count > 1 {
+                (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / (count - 1) as f32).sqrt()
+            } else {
+                0.
+            };
+            let confidence_interval = (count > 1).then(|| {
+                let t = StudentsT::new(0., 1., (count - 1) as f64)
+                    .unwrap()
+                    .inverse_cdf(0.5 + CONFIDENCE_LEVEL as f64 / 2.) as f32;
+                let half_width = t * std / (count as f32).sqrt();
+                [mean - half_width, mean + half_width]
+            });
+            let median = median(&sorted);
+            statistics.push(KpiStatistics {
+                kpi: kpi.to_string(),
+                count,
+                mean,
+                std,
+                min: sorted[0],
+                median,
+                max: sorted[count - 1],
+                confidence_interval,
+            });
+
+            // Modified z-score (Iglewicz and Hoaglin), robust to the outliers themselves
+            let mut deviations: Vec<f32> = sorted.iter().map(|v| (v - median).abs()).collect();
+            deviations.sort_by(f32::total_cmp);
+            let mad = median(&deviations);
+            if mad <= 0. {
+                continue;
+            }
+            for (run, value) in runs_values {
+                let score = 0.6745 * (value - median) / mad;
+                if score.abs() > OUTLIER_THRESHOLD {
+                    outliers.push(Outlier {
+                        run: run.name.clone(),
+                        seed: run.seed,
+                        kpi: kpi.to_string(),
+                        value: *value,
+                        score,
+                    });
+                }
+            }
+        }
+
+        Self {
+            runs,
+            statistics,
+            outliers,
+        }
+    }
+
+    /// Statistics of the given `kpi`, if a run has a value for it.
+    pub fn kpi(&self, kpi: &str) -> Option<&KpiStatistics> {
+        self.statistics.iter().find(|stats| stats.kpi == kpi)
+    }
+
+    /// Write the aggregate to `path`, in JSON.
+    pub fn save(&self, path: &Path) -> SimbaResult<()> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ImplementationError,
+                format!("Error during json serialization of the aggregate: {e}"),
+            )
+        })?;
+        fs::write(path, content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to write aggregate file '{}': {}",
+                    path.display(),
+                    e
+                ),
+            )
+        })
+    }
+
+    /// Load an aggregate file written by [`AggregateResults::save`].
+    pub fn load(path: &Path) -> SimbaResult<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to read aggregate file '{}': {}",
+                    path.display(),
+                    e
+                ),
+            )
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Error while parsing aggregate file '{}': {e}",
+                    path.display()
+                ),
+            )
+        })
+    }
+}
+
+/// Format marker of a result file, the other fields are skipped.
+#[derive(Deserialize)]
+struct ResultFileFormat {
+    #[serde(default)]
+    format: Option<String>,
+}
+
+/// Returns `true` if the JSON file at `path` is a batch result file (`format` field equal to
+/// [`BATCH_RESULTS_FORMAT`]).
+fn is_batch_file(path: &Path) -> SimbaResult<bool> {
+    let file = fs::File::open(path).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!("Impossible to read result file '{}': {}", path.display(), e),
+        )
+    })?;
+    let marker: ResultFileFormat =
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!("Error while parsing result file '{}': {e}", path.display()),
+            )
+        })?;
+    Ok(marker.format.as_deref() == Some(BATCH_RESULTS_FORMAT))
+}
+
+/// KPIs of the runs of a result file: one run for a simulator result file, the runs of a batch
+/// result file.
+pub fn load_run_kpis(path: &Path) -> SimbaResult<Vec<RunKpis>> {
+    let file_name = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().to_string(),
+    );
+    if is_batch_file(path)? {
+        let BatchResults { runs, .. } = BatchRunner::load_results(path)?;
+        Ok(runs
+            .into_iter()
+            .map(|run| {
+                let name = format!("{file_name}#{}", run.index);
+                match run.error {
+                    Some(error) => RunKpis {
+                        name,
+                        seed: run.seed,
+                        error: Some(error),
+                        kpis: BTreeMap::new(),
+                    },
+                    None => RunKpis::from_records(name, run.seed, &run.records, &run.fleet_records),
+                }
+            })
+            .collect())
+    } else {
+        let results = Simulator::deserialize_results_from_file(path)?;
+        Ok(vec![RunKpis::from_records(
+            file_name,
+            results.config.random_seed,
+            &results.records,
+            &results.fleet_records,
+        )])
+    }
+}
+
+/// Load the result files (`.json`) of the `directory` and aggregate their runs.
+///
+/// The files which are not result files (e.g. a previous aggregate) are skipped with a warning.
+pub fn aggregate_results_directory(directory: &Path) -> SimbaResult<AggregateResults> {
+    let entries = fs::read_dir(directory).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "Impossible to read result directory '{}': {}",
+                directory.display(),
+                e
+            ),
+        )
+    })?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    let mut runs = Vec::new();
+    for path in &paths {
+        match load_run_kpis(path) {
+            Ok(file_runs) => runs.extend(file_runs),
+            Err(e) => log::warn!("Result file skipped: {}", e.detailed_error()),
+        }
+    }
+    if runs.is_empty() {
+        return Err(SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!("No result file in '{}'", directory.display()),
+        ));
+    }
+    Ok(AggregateResults::from_runs(runs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::BatchRunResult;
+
+    fn run(name: &str, value: f32) -> RunKpis {
+        RunKpis {
+            name: name.to_string(),
+            seed: Some(value),
+            error: None,
+            kpis: BTreeMap::from([("position_rmse".to_string(), value)]),
+        }
+    }
+
+    #[test]
+    fn statistics_and_outliers() {
+        let mut runs: Vec<RunKpis> = [1., 1.1, 0.9, 1.05, 0.95]
+            .iter()
+            .enumerate()
+            .map(|(i, value)| run(&format!("run{i}"), *value))
+            .collect();
+        runs.push(run("run5", 10.));
+        runs.push(RunKpis {
+            error: Some("Panic".to_string()),
+            ..run("run6", 100.)
+        });
+        let aggregate = AggregateResults::from_runs(runs);
+        let stats = aggregate.kpi("position_rmse").unwrap();
+        assert_eq!(stats.count, 6);
+        assert!((stats.median - 1.025).abs() < 1e-5);
+        let [low, high] = stats.confidence_interval.unwrap();
+        assert!(low < stats.mean && stats.mean < high);
+        assert_eq!(aggregate.outliers.len(), 1);
+        assert_eq!(aggregate.outliers[0].run, "run5");
+        assert_eq!(aggregate.outliers[0].seed, Some(10.));
+    }
+
+    #[test]
+    fn batch_file_with_fleet_records() {
+        let path =
+            std::env::temp_dir().join(format!("simba_aggregate_batch_{}.json", std::process::id()));
+        let results = BatchResults {
+            format: BATCH_RESULTS_FORMAT.to_string(),
+            batch: Default::default(),
+            runs: vec![BatchRunResult {
+                index: 0,
+                seed: Some(1.),
+                parameters: BTreeMap::new(),
+                records: Vec::new(),
+                annotations: Vec::new(),
+                fleet_records: vec![FleetRecord {
+                    time: 1.,
+                    idleness: vec![1., 3.],
+                    average_idleness: 2.,
+                    max_idleness: 3.,
+                }],
+                error: None,
+            }],
+        };
+        fs::write(&path, serde_json::to_string(&results).unwrap()).unwrap();
+        assert!(is_batch_file(&path).unwrap());
+        let runs = load_run_kpis(&path).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].kpis.get("average_idleness"), Some(&2.));
+        assert_eq!(runs[0].kpis.get("worst_idleness"), Some(&3.));
+
+        // A "batch" key alone is not a marker
+        fs::write(&path, r#"{"batch": {}, "runs": []}"#).unwrap();
+        assert!(!is_batch_file(&path).unwrap());
+        let _ = fs::remove_file(&path);
+    }
+}
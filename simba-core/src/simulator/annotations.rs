@@ -0,0 +1,86 @@
+//! Timestamped annotations of a run ("collision here", "filter diverged", ...).
+//!
+//! Annotations are added by the modules through [`Node::annotate`](crate::node::Node::annotate),
+//! by the user through the async API ([`SimulatorAsyncApi::annotations`](super::SimulatorAsyncApi::annotations))
+//! or the GUI, and directly with [`Simulator::annotate`](super::Simulator::annotate).
+//! They are saved with the results and shown as markers on the GUI timeline.
+
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{SharedMutex, lock_recovery::RecoverMutex};
+
+/// Timestamped note attached to a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Simulated time of the annotation.
+    pub time: f32,
+    /// Node which added the annotation, `None` if added by the user.
+    pub node: Option<String>,
+    /// Text of the annotation.
+    pub text: String,
+}
+
+impl Display for Annotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.node {
+            Some(node) => write!(f, "[{:.3} s] {node}: {}", self.time, self.text),
+            None => write!(f, "[{:.3} s] {}", self.time, self.text),
+        }
+    }
+}
+
+/// Shared list of the annotations of a run.
+///
+/// Clones share the same list, so the handle can be given to the nodes and the async API.
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationLog {
+    annotations: SharedMutex<Vec<Annotation>>,
+}
+
+impl AnnotationLog {
+    /// Create an empty log.
+    pub fn new() -> Self {
+        Self {
+            annotations: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Add an annotation at `time`. `node` is `None` for user annotations.
+    pub fn add(&self, time: f32, node: Option<String>, text: String) {
+        self.annotations
+            .lock_recover()
+            .push(Annotation { time, node, text });
+    }
+
+    /// Add several annotations (e.g. loaded from a result file).
+    pub fn extend(&self, annotations: impl IntoIterator<Item = Annotation>) {
+        self.annotations.lock_recover().extend(annotations);
+    }
+
+    /// List of the annotations, sorted by time.
+    pub fn list(&self) -> Vec<Annotation> {
+        let mut annotations = self.annotations.lock_recover().clone();
+        annotations.sort_by(|a, b| a.time.total_cmp(&b.time));
+        annotations
+    }
+
+    /// Number of annotations.
+    pub fn len(&self) -> usize {
+        self.annotations.lock_recover().len()
+    }
+
+    /// Return whether there is no annotation.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the annotations.
+    pub fn clear(&self) {
+        self.annotations.lock_recover().clear();
+    }
+}
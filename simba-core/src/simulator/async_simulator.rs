@@ -4,21 +4,33 @@
 //! without blocking the main thread.
 
 use std::{
+    collections::{HashMap, VecDeque},
     path::Path,
-    sync::{Arc, Mutex, RwLock, mpsc},
+    sync::{
+        Arc, Condvar, Mutex, RwLock,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    time::Duration,
 };
 
-use log::debug;
+use config_checker::ConfigCheckable;
+use log::{debug, warn};
 use pyo3::Python;
 
 use crate::{
     api::async_api::{
         AsyncApi, AsyncApiLoadConfigRequest, AsyncApiRunRequest, AsyncApiRunner, PluginAsyncAPI,
     },
-    errors::SimbaResult,
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
     logger::is_enabled,
+    networking::reconfigure::ReconfigurationOutcome,
     plugin_api::PluginAPI,
-    simulator::{Record, Simulator, SimulatorConfig},
+    scenario::config::ConfigPatch,
+    simulator::{
+        AggregateStatistics, AnnotationLog, FleetStatus, MemoryUsage, Record, Simulator,
+        SimulatorConfig,
+    },
     utils::{SharedMutex, SharedRoLock, SharedRwLock},
 };
 
@@ -127,9 +139,7 @@ impl AsyncSimulator {
         max_time: Option<f32>,
         reset: bool,
     ) {
-        self.api
-            .run
-            .async_call(AsyncApiRunRequest { max_time, reset });
+        self.start_run(max_time, reset);
         if let Some(plugin_api) = plugin_api {
             while self.api.run.try_get_result().is_none() {
                 plugin_api.check_requests();
@@ -146,6 +156,21 @@ impl AsyncSimulator {
         }
     }
 
+    /// Start a run without waiting for its end, see [`Self::run`].
+    ///
+    /// The caller is in charge of servicing the plugin requests and of polling the end of the run
+    /// with [`Self::try_run_result`].
+    pub fn start_run(&self, max_time: Option<f32>, reset: bool) {
+        self.api
+            .run
+            .async_call(AsyncApiRunRequest { max_time, reset });
+    }
+
+    /// Result of the run started with [`Self::start_run`], `None` while it is running.
+    pub fn try_run_result(&self) -> Option<SimbaResult<()>> {
+        self.api.run.try_get_result()
+    }
+
     /// Retrieve simulator records.
     ///
     /// When `sorted` is `true`, records are returned sorted by time.
@@ -190,36 +215,404 @@ impl AsyncSimulator {
     pub fn get_simulator(&self) -> Arc<Mutex<Simulator>> {
         self.server.lock().unwrap().get_simulator()
     }
+
+    /// Stream the records to websocket clients connecting on `address` (e.g. `127.0.0.1:9002`),
+    /// see [`RecordStreamServer`](crate::simulator::RecordStreamServer).
+    ///
+    /// The streaming stops when the returned server is dropped.
+    #[cfg(feature = "stream")]
+    pub fn stream_records(
+        &self,
+        address: &str,
+    ) -> SimbaResult<crate::simulator::RecordStreamServer> {
+        let connector = self.get_simulator().lock().unwrap().async_api_connector();
+        crate::simulator::RecordStreamServer::start(address, connector)
+    }
+
+    /// Handle to pause, resume and step the runs, usable while [`Self::run`] is blocking.
+    pub fn run_control(&self) -> RunControl {
+        self.api.simulator_api.run_control.clone()
+    }
+}
+
+/// Interactive control of the runs: pause, resume and step by step.
+///
+/// The simulator checks the control at the end of each time step, while all the nodes wait on the
+/// time step barrier. When paused, the nodes stay on the barrier until the run is resumed or
+/// stepped. A pause requested before a run holds it at the end of its first time step.
+///
+/// While paused, [`ConfigPatch`]es can be submitted with [`RunControl::patch_config`]: they are
+/// applied before the next time step.
+#[derive(Clone, Default)]
+pub struct RunControl {
+    /// Number of time steps allowed before pausing, `None` to run freely.
+    allowed_steps: Arc<(Mutex<Option<usize>>, Condvar)>,
+    /// Patches waiting to be applied by the simulator, with the sender of their outcome.
+    config_patches: SharedMutex<Vec<(ConfigPatch, ConfigPatchOutcomeSender)>>,
+    /// Outcome senders of the patches sent to the nodes, by channel, in sending order.
+    sent_config_patches: SharedMutex<HashMap<String, VecDeque<ConfigPatchOutcomeSender>>>,
+}
+
+type ConfigPatchOutcomeSender = mpsc::Sender<Result<(), String>>;
+
+/// Outcome of a [`ConfigPatch`] submitted with [`RunControl::patch_config`].
+///
+/// The node applies the patch once the run is resumed or stepped.
+pub struct ConfigPatchHandle {
+    outcome: mpsc::Receiver<Result<(), String>>,
+}
+
+impl ConfigPatchHandle {
+    /// Blocks until the node applied the patch. Fails with the reason of the rejection if the node
+    /// rejected it, or if the run ended before the patch was applied.
+    pub fn wait(&self) -> SimbaResult<()> {
+        match self.outcome.recv() {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!("Config patch rejected: {e}"),
+            )),
+            Err(_) => Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                "The run ended before the config patch was applied".to_string(),
+            )),
+        }
+    }
+}
+
+impl RunControl {
+    /// Period of the check of the end of the run while paused.
+    const FINISH_CHECK_PERIOD: Duration = Duration::from_millis(50);
+
+    /// Pause the run at the end of the current time step.
+    pub fn pause(&self) {
+        self.set_allowed_steps(Some(0));
+    }
+
+    /// Resume the run until its end.
+    pub fn resume(&self) {
+        self.set_allowed_steps(None);
+    }
+
+    /// Run `n_time_steps` more time steps, then pause.
+    pub fn step(&self, n_time_steps: usize) {
+        self.set_allowed_steps(Some(n_time_steps));
+    }
+
+    /// Returns `true` if the run is paused (or will pause at the end of the current time step).
+    pub fn is_paused(&self) -> bool {
+        *self.allowed_steps.0.lock().unwrap() == Some(0)
+    }
+
+    /// Submit a change of the configuration of a paused run, applied before the next time step.
+    ///
+    /// The patch is recorded as a scenario event. Fails if the run is not paused or if the patch
+    /// is invalid. The returned handle gives the outcome of the patch on the node, once the run is
+    /// resumed or stepped.
+    pub fn patch_config(&self, patch: ConfigPatch) -> SimbaResult<ConfigPatchHandle> {
+        if !self.is_paused() {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                "Config patches can only be applied while the run is paused".to_string(),
+            ));
+        }
+        patch.check().map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!("Invalid config patch: {e}"),
+            )
+        })?;
+        let (sender, outcome) = mpsc::channel();
+        self.config_patches.lock().unwrap().push((patch, sender));
+        Ok(ConfigPatchHandle { outcome })
+    }
+
+    /// Takes the patches submitted since the last call, in submission order.
+    pub(super) fn take_config_patches(&self) -> Vec<(ConfigPatch, ConfigPatchOutcomeSender)> {
+        std::mem::take(&mut *self.config_patches.lock().unwrap())
+    }
+
+    /// Waits for the outcome of a patch sent on `channel`.
+    pub(super) fn config_patch_sent(&self, channel: String, sender: ConfigPatchOutcomeSender) {
+        self.sent_config_patches
+            .lock()
+            .unwrap()
+            .entry(channel)
+            .or_default()
+            .push_back(sender);
+    }
+
+    /// Gives the outcomes received from the nodes to the handles of the patches, in sending order
+    /// for each channel.
+    pub(super) fn resolve_config_patches(&self, outcomes: Vec<ReconfigurationOutcome>) {
+        let mut sent_config_patches = self.sent_config_patches.lock().unwrap();
+        for outcome in outcomes {
+            if let Some(sender) = sent_config_patches
+                .get_mut(&outcome.channel)
+                .and_then(|senders| senders.pop_front())
+            {
+                // The handle may have been dropped
+                let _ = sender.send(outcome.error.map_or(Ok(()), Err));
+            }
+        }
+        sent_config_patches.retain(|_, senders| !senders.is_empty());
+    }
+
+    /// Drops the patches not applied at the end of the run, so that their handles stop waiting.
+    pub(super) fn abandon_config_patches(&self) {
+        self.config_patches.lock().unwrap().clear();
+        self.sent_config_patches.lock().unwrap().clear();
+    }
+
+    fn set_allowed_steps(&self, allowed_steps: Option<usize>) {
+        let (lock, cv) = &*self.allowed_steps;
+        *lock.lock().unwrap() = allowed_steps;
+        cv.notify_all();
+    }
+
+    /// Called at the end of each time step: wait while the run is paused, unless `force_finish`
+    /// is set. Returns `true` if the run was paused.
+    pub(super) fn wait_next_step(&self, force_finish: &Mutex<bool>) -> bool {
+        let (lock, cv) = &*self.allowed_steps;
+        let mut allowed_steps = lock.lock().unwrap();
+        let mut paused = false;
+        loop {
+            match allowed_steps.as_mut() {
+                None => return paused,
+                Some(n) if *n > 0 => {
+                    *n -= 1;
+                    return paused;
+                }
+                Some(_) => {
+                    if *force_finish.lock().unwrap() {
+                        return paused;
+                    }
+                    if !paused && is_enabled(crate::logger::InternalLog::API) {
+                        debug!("Run paused");
+                    }
+                    paused = true;
+                    allowed_steps = cv
+                        .wait_timeout(allowed_steps, Self::FINISH_CHECK_PERIOD)
+                        .unwrap()
+                        .0;
+                }
+            }
+        }
+    }
 }
 
+/// Number of records a client session can hold before the new records are dropped, in addition
+/// to the past records sent at the connection.
+pub const RECORD_STREAM_CAPACITY: usize = 10_000;
+/// Number of statistics a client session can hold before the new ones are dropped.
+pub const STATISTICS_STREAM_CAPACITY: usize = 64;
+
 /// Client-side asynchronous API exposing current time and streamed records.
+///
+/// Each client is a session of the [`SimulatorAsyncApiServer`], with its own record stream: the
+/// clients read the records at their own pace. The streams are bounded
+/// ([`RECORD_STREAM_CAPACITY`]): when a client does not keep up, the new records are dropped,
+/// see [`SimulatorAsyncApi::dropped_records`]. The session is closed when the client is dropped.
 pub struct SimulatorAsyncApi {
+    /// Identifier of the session of this client.
+    pub session_id: usize,
     /// Shared current simulation time.
     pub current_time: SharedRoLock<f32>,
     /// Stream receiver for emitted records.
     pub records: SharedMutex<mpsc::Receiver<Record>>,
+    /// Low-rate stream of summaries of the run, lighter than the record stream.
+    pub statistics: SharedMutex<mpsc::Receiver<AggregateStatistics>>,
+    /// Approximate memory used by the run, updated at the end of each time step.
+    pub memory_usage: SharedRoLock<MemoryUsage>,
+    /// Annotations of the run. User annotations can be added during the run.
+    pub annotations: AnnotationLog,
+    /// Last heartbeat of each node, updated by the node threads during the run.
+    pub fleet_status: FleetStatus,
+    run_control: RunControl,
+    dropped_records: Arc<AtomicUsize>,
 }
 
+impl SimulatorAsyncApi {
+    /// Number of records dropped because the record stream of this client was full.
+    pub fn dropped_records(&self) -> usize {
+        self.dropped_records.load(Ordering::Relaxed)
+    }
+
+    /// Pause the run at the end of the current time step, see [`RunControl`].
+    pub fn pause(&self) {
+        self.run_control.pause();
+    }
+
+    /// Resume the run until its end.
+    pub fn resume(&self) {
+        self.run_control.resume();
+    }
+
+    /// Run `n_time_steps` more time steps, then pause.
+    pub fn step(&self, n_time_steps: usize) {
+        self.run_control.step(n_time_steps);
+    }
+
+    /// Returns `true` if the run is paused.
+    pub fn is_paused(&self) -> bool {
+        self.run_control.is_paused()
+    }
+
+    /// Submit a change of the configuration of the paused run, see
+    /// [`RunControl::patch_config`].
+    pub fn patch_config(&self, patch: ConfigPatch) -> SimbaResult<ConfigPatchHandle> {
+        self.run_control.patch_config(patch)
+    }
+}
+
+/// Handle to connect new clients to the async API of a simulator, without locking the simulator.
+///
+/// The clients connected during a run receive the records from their connection.
+#[derive(Clone)]
+pub struct AsyncApiConnector {
+    server: SimulatorAsyncApiServer,
+}
+
+impl AsyncApiConnector {
+    pub(super) fn new(server: SimulatorAsyncApiServer) -> Self {
+        Self { server }
+    }
+
+    /// Connect a new client, if `token` matches the access token of the simulator.
+    pub fn connect(&self, token: Option<&str>) -> SimbaResult<Arc<SimulatorAsyncApi>> {
+        Ok(Arc::new(self.server.connect(token, std::iter::empty())?))
+    }
+
+    /// Number of clients connected.
+    pub fn client_count(&self) -> usize {
+        self.server.session_count()
+    }
+}
+
+/// Record stream of a client session.
+struct ClientSession {
+    id: usize,
+    records: mpsc::SyncSender<Record>,
+    statistics: mpsc::SyncSender<AggregateStatistics>,
+    dropped_records: Arc<AtomicUsize>,
+}
+
+impl ClientSession {
+    /// Send the `record` if the stream is not full, otherwise drop it. Returns `false` if the
+    /// client was dropped.
+    fn send_record(&self, record: &Record) -> bool {
+        match self.records.try_send(record.clone()) {
+            Ok(()) => true,
+            Err(mpsc::TrySendError::Full(_)) => {
+                if self.dropped_records.fetch_add(1, Ordering::Relaxed) == 0 {
+                    warn!(
+                        "Record stream of async API session {} full: records dropped",
+                        self.id
+                    );
+                }
+                true
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ClientSessions {
+    next_id: usize,
+    sessions: Vec<ClientSession>,
+}
+
+/// Server side of the [`SimulatorAsyncApi`]s, shared by the node threads.
+///
+/// The sessions are shared between the clones of the server, so that a client connecting during a
+/// run receives the records of the running nodes. The node threads send their records
+/// concurrently, the sessions are only locked for writing to open and close them. When an access
+/// token is set, only the clients connecting with this token are accepted.
 #[derive(Clone)]
 pub(super) struct SimulatorAsyncApiServer {
     current_time: SharedRwLock<f32>,
-    records: Vec<mpsc::Sender<Record>>,
+    sessions: SharedRwLock<ClientSessions>,
+    access_token: SharedRwLock<Option<String>>,
+    memory_usage: SharedRwLock<MemoryUsage>,
+    annotations: AnnotationLog,
+    fleet_status: FleetStatus,
+    run_control: RunControl,
 }
 
 impl SimulatorAsyncApiServer {
-    pub fn new(time: f32) -> Self {
+    pub fn new(time: f32, annotations: AnnotationLog, fleet_status: FleetStatus) -> Self {
         Self {
             current_time: Arc::new(RwLock::new(time)),
-            records: Vec::new(),
+            sessions: Arc::new(RwLock::new(ClientSessions::default())),
+            access_token: Arc::new(RwLock::new(None)),
+            memory_usage: Arc::new(RwLock::new(MemoryUsage::default())),
+            annotations,
+            fleet_status,
+            run_control: RunControl::default(),
+        }
+    }
+
+    /// Set the token required to connect new clients, `None` to accept all the clients.
+    pub fn set_access_token(&self, token: Option<String>) {
+        *self.access_token.write().unwrap() = token;
+    }
+
+    /// Open a session for a trusted client, without token.
+    pub fn new_client(&self) -> SimulatorAsyncApi {
+        self.open_session(std::iter::empty())
+    }
+
+    /// Open a session if `token` matches the access token. The `past_records` are queued first in
+    /// the record stream of the client, so that a late client can read the run from the start.
+    pub fn connect(
+        &self,
+        token: Option<&str>,
+        past_records: impl IntoIterator<Item = Record>,
+    ) -> SimbaResult<SimulatorAsyncApi> {
+        if let Some(access_token) = self.access_token.read().unwrap().as_deref()
+            && token != Some(access_token)
+        {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::AuthenticationError,
+                "Invalid access token for the asynchronous API".to_string(),
+            ));
         }
+        Ok(self.open_session(past_records))
     }
 
-    pub fn new_client(&mut self) -> SimulatorAsyncApi {
-        let (tx, rx) = mpsc::channel();
-        self.records.push(tx);
+    fn open_session(&self, past_records: impl IntoIterator<Item = Record>) -> SimulatorAsyncApi {
+        let past_records: Vec<Record> = past_records.into_iter().collect();
+        // The past records do not take the room of the next ones
+        let (tx, rx) = mpsc::sync_channel(past_records.len() + RECORD_STREAM_CAPACITY);
+        let (statistics_tx, statistics_rx) = mpsc::sync_channel(STATISTICS_STREAM_CAPACITY);
+        for record in past_records {
+            // The receiver is alive and the channel has room for them
+            let _ = tx.try_send(record);
+        }
+        let dropped_records = Arc::new(AtomicUsize::new(0));
+        let mut sessions = self.sessions.write().unwrap();
+        let session_id = sessions.next_id;
+        sessions.next_id += 1;
+        sessions.sessions.push(ClientSession {
+            id: session_id,
+            records: tx,
+            statistics: statistics_tx,
+            dropped_records: dropped_records.clone(),
+        });
+        if is_enabled(crate::logger::InternalLog::API) {
+            debug!("Open async API session {session_id}");
+        }
         SimulatorAsyncApi {
+            session_id,
             current_time: self.current_time.clone() as SharedRoLock<f32>,
             records: Arc::new(Mutex::new(rx)),
+            statistics: Arc::new(Mutex::new(statistics_rx)),
+            memory_usage: self.memory_usage.clone() as SharedRoLock<MemoryUsage>,
+            annotations: self.annotations.clone(),
+            fleet_status: self.fleet_status.clone(),
+            run_control: self.run_control.clone(),
+            dropped_records,
         }
     }
 
@@ -227,9 +620,132 @@ impl SimulatorAsyncApiServer {
         *self.current_time.write().unwrap() = new_time;
     }
 
+    pub fn update_memory_usage(&self, memory_usage: MemoryUsage) {
+        *self.memory_usage.write().unwrap() = memory_usage;
+    }
+
+    pub fn run_control(&self) -> &RunControl {
+        &self.run_control
+    }
+
+    /// Number of open sessions.
+    pub fn session_count(&self) -> usize {
+        self.sessions.read().unwrap().sessions.len()
+    }
+
+    /// Send the summary of the run to all the sessions. The statistics are dropped for the
+    /// clients which do not read them.
+    pub fn send_statistics(&self, statistics: &AggregateStatistics) {
+        for session in &self.sessions.read().unwrap().sessions {
+            // Closed sessions are removed with the records
+            let _ = session.statistics.try_send(statistics.clone());
+        }
+    }
+
+    /// Send the record to all the sessions, closing the sessions of the dropped clients. The
+    /// record is dropped for the sessions whose stream is full.
     pub fn send_record(&self, record: &Record) {
-        for tx in &self.records {
-            tx.send(record.clone()).unwrap();
+        let closed: Vec<usize> = self
+            .sessions
+            .read()
+            .unwrap()
+            .sessions
+            .iter()
+            .filter(|session| !session.send_record(record))
+            .map(|session| session.id)
+            .collect();
+        if closed.is_empty() {
+            return;
         }
+        self.sessions
+            .write()
+            .unwrap()
+            .sessions
+            .retain(|session| !closed.contains(&session.id));
+        if is_enabled(crate::logger::InternalLog::API) {
+            for id in closed {
+                debug!("Close async API session {id}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        node::node_factory::{ComputationUnitRecord, NodeRecord},
+        sensors::sensor_manager::SensorManagerRecord,
+    };
+
+    fn record(time: f32) -> Record {
+        Record {
+            time,
+            node: NodeRecord::ComputationUnit(Box::new(ComputationUnitRecord {
+                name: "unit".to_string(),
+                model_name: "unit".to_string(),
+                state_estimators: Vec::new(),
+                sensor_manager: SensorManagerRecord {
+                    sensors: Vec::new(),
+                    next_time: None,
+                    last_observations: Vec::new(),
+                },
+                network: Default::default(),
+                labels: Vec::new(),
+                skipped_steps: 0,
+                observation_latencies: Vec::new(),
+                estimator_divergences: Vec::new(),
+            })),
+        }
+    }
+
+    fn server() -> SimulatorAsyncApiServer {
+        SimulatorAsyncApiServer::new(0., AnnotationLog::default(), FleetStatus::default())
+    }
+
+    #[test]
+    fn full_stream_drops_new_records() {
+        let server = server();
+        let slow = server.connect(None, [record(-1.)]).unwrap();
+        let fast = server.new_client();
+        for i in 0..RECORD_STREAM_CAPACITY + 5 {
+            server.send_record(&record(i as f32));
+            // The fast client keeps up
+            assert_eq!(
+                fast.records.lock().unwrap().try_recv().unwrap().time,
+                i as f32
+            );
+        }
+        assert_eq!(fast.dropped_records(), 0);
+        assert_eq!(slow.dropped_records(), 5);
+        let received: Vec<f32> = slow
+            .records
+            .lock()
+            .unwrap()
+            .try_iter()
+            .map(|record| record.time)
+            .collect();
+        // The past record, then the first records until the stream was full
+        assert_eq!(received.len(), RECORD_STREAM_CAPACITY + 1);
+        assert_eq!(received[0], -1.);
+        assert_eq!(
+            *received.last().unwrap(),
+            (RECORD_STREAM_CAPACITY - 1) as f32
+        );
+        // Room again for the next records
+        server.send_record(&record(-2.));
+        assert_eq!(slow.records.lock().unwrap().try_recv().unwrap().time, -2.);
+    }
+
+    #[test]
+    fn dropped_clients_are_closed() {
+        let server = server();
+        let client = server.new_client();
+        let other = server.new_client();
+        assert_eq!(server.session_count(), 2);
+        drop(client);
+        server.send_record(&record(0.));
+        assert_eq!(server.session_count(), 1);
+        assert_eq!(other.records.lock().unwrap().try_recv().unwrap().time, 0.);
     }
 }
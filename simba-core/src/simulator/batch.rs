@@ -0,0 +1,817 @@
+/*!
+Monte Carlo batch runner: run a configuration many times, with different seeds and overridden
+configuration fields, and aggregate the records of all the runs in a single result file.
+
+The batch is described by a [`BatchConfig`] file:
+```yaml
+config_path: config.yaml       # Base configuration, from the batch file location
+output_path: batch_results.json
+processes: 4                   # Runs in parallel (worker processes)
+seeds: [1, 2, 3]               # Each parameter combination is run with each seed
+mode:
+  type: Grid                   # All the combinations of the values
+parameters:
+  - field: robots.0.navigator.target_speed
+    values: [0.5, 1.0]
+  - field: robots.0.state_estimator.prediction_period
+    values: [0.1, 0.01]
+```
+gives 12 runs. The fields are dotted paths in the configuration, with the index for the lists.
+
+The result file contains a format marker ([`BATCH_RESULTS_FORMAT`]), the batch configuration and,
+for each run, its seed, its parameter values and its records (see [`BatchResults`]). The runs are written to the file as soon as they
+are done, so that only the records of the runs in progress are in memory. A failing run does not
+stop the batch: its error is saved in place of its records.
+*/
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::Arc,
+};
+
+use config_checker::ConfigCheckable;
+use log::{info, warn};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use simba_macros::config_derives;
+
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    plugin_api::PluginAPI,
+    utils::config_parsing::{ConfigLoadMode, load_config_file},
+};
+
+use super::{Annotation, FleetRecord, Record, ResultConfig, Simulator, SimulatorConfig};
+
+/// Value of the `format` field of the batch result files, to tell them from the simulator result
+/// files.
+pub const BATCH_RESULTS_FORMAT: &str = "simba-batch-results";
+
+/// Combination of the values of the swept parameters.
+///
+/// Default value: [`SweepMode::Grid`].
+#[config_derives]
+pub enum SweepMode {
+    /// Every combination of the values of the parameters (cartesian product).
+    Grid,
+    /// The i-th run takes the i-th value of every parameter. All the parameters need the same
+    /// number of values.
+    List,
+}
+
+impl Default for SweepMode {
+    fn default() -> Self {
+        Self::Grid
+    }
+}
+
+/// Configuration field overridden by the batch.
+///
+/// Default values:
+/// - `field`: `""`
+/// - `values`: empty vector
+#[config_derives]
+pub struct SweepParameterConfig {
+    /// Dotted path of the field in the configuration, e.g. `robots.0.navigator.target_speed`.
+    pub field: String,
+    /// Values taken by the field.
+    pub values: Vec<Value>,
+}
+
+impl Default for SweepParameterConfig {
+    fn default() -> Self {
+        Self {
+            field: String::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl Check for SweepParameterConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.field.is_empty() {
+            errors.push("Swept field path should not be empty".to_string());
+        }
+        if self.values.is_empty() {
+            errors.push(format!("No value given for the swept field {}", self.field));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Configuration of a batch of runs.
+///
+/// Default values:
+/// - `config_path`: `"config.yaml"`
+/// - `output_path`: `"batch_results.json"`
+/// - `processes`: `1`
+/// - `seeds`: empty vector
+/// - `replications`: `1`
+/// - `mode`: [`SweepMode::Grid`]
+/// - `parameters`: empty vector
+#[config_derives]
+pub struct BatchConfig {
+    /// Path of the base configuration, from the batch file location.
+    pub config_path: String,
+    /// Path of the aggregated result file, from the batch file location.
+    pub output_path: String,
+    /// Number of runs in parallel, each in its own process. With `1`, the runs are done one
+    /// after the other in the current process.
+    pub processes: usize,
+    /// Seeds of the runs: each parameter combination is run once with each seed.
+    /// If empty, each combination is run `replications` times: the first replication with the
+    /// seed of the base configuration, the next ones with a seed derived from it and from the
+    /// replication number (a new seed for each run if it is `null`).
+    pub seeds: Vec<f32>,
+    /// Number of runs of each parameter combination when no seed is given.
+    pub replications: usize,
+    /// Combination of the values of the parameters.
+    #[check]
+    pub mode: SweepMode,
+    /// Overridden configuration fields.
+    #[check]
+    pub parameters: Vec<SweepParameterConfig>,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            config_path: "config.yaml".to_string(),
+            output_path: "batch_results.json".to_string(),
+            processes: 1,
+            seeds: Vec::new(),
+            replications: 1,
+            mode: SweepMode::default(),
+            parameters: Vec::new(),
+        }
+    }
+}
+
+impl Check for BatchConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.processes == 0 {
+            errors.push("The number of processes should be at least 1".to_string());
+        }
+        if self.seeds.is_empty() && self.replications == 0 {
+            errors.push("The number of replications should be at least 1".to_string());
+        }
+        if self.mode == SweepMode::List
+            && let Some(first) = self.parameters.first()
+        {
+            for parameter in &self.parameters {
+                if parameter.values.len() != first.values.len() {
+                    errors.push(format!(
+                        "In List mode, all the parameters need the same number of values ({} has {}, {} has {})",
+                        first.field,
+                        first.values.len(),
+                        parameter.field,
+                        parameter.values.len()
+                    ));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl BatchConfig {
+    /// List the runs of the batch: the parameter combinations, each with every seed.
+    pub fn runs(&self) -> Vec<BatchRun> {
+        let combinations: Vec<BTreeMap<String, Value>> = match self.mode {
+            SweepMode::Grid => {
+                self.parameters
+                    .iter()
+                    .fold(vec![BTreeMap::new()], |combinations, parameter| {
+                        combinations
+                            .iter()
+                            .flat_map(|combination| {
+                                parameter.values.iter().map(move |value| {
+                                    let mut combination = combination.clone();
+                                    combination.insert(parameter.field.clone(), value.clone());
+                                    combination
+                                })
+                            })
+                            .collect()
+                    })
+            }
+            SweepMode::List => {
+                let nb_values = self
+                    .parameters
+                    .iter()
+                    .map(|p| p.values.len())
+                    .min()
+                    .unwrap_or(1);
+                (0..nb_values)
+                    .map(|i| {
+                        self.parameters
+                            .iter()
+                            .map(|p| (p.field.clone(), p.values[i].clone()))
+                            .collect()
+                    })
+                    .collect()
+            }
+        };
+        let seeds: Vec<Option<f32>> = if self.seeds.is_empty() {
+            vec![None; self.replications]
+        } else {
+            self.seeds.iter().copied().map(Some).collect()
+        };
+        combinations
+            .iter()
+            .flat_map(|parameters| {
+                seeds
+                    .iter()
+                    .enumerate()
+                    .map(move |(replication, seed)| (replication, *seed, parameters.clone()))
+            })
+            .enumerate()
+            .map(|(index, (replication, seed, parameters))| BatchRun {
+                index,
+                replication,
+                seed,
+                parameters,
+            })
+            .collect()
+    }
+}
+
+/// One run of a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRun {
+    /// Index of the run in the batch.
+    pub index: usize,
+    /// Index of the run among the runs of its parameter combination.
+    pub replication: usize,
+    /// Seed of the run, `None` to keep the one of the base configuration (or to derive one from
+    /// it, see [`replication_seed`]).
+    pub seed: Option<f32>,
+    /// Values of the overridden fields, by field path.
+    pub parameters: BTreeMap<String, Value>,
+}
+
+/// Result of one run of a batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRunResult {
+    /// Index of the run in the batch.
+    pub index: usize,
+    /// Seed used by the run (drawn by the simulator if not given).
+    pub seed: Option<f32>,
+    /// Values of the overridden fields, by field path.
+    pub parameters: BTreeMap<String, Value>,
+    /// Sorted records of the run.
+    pub records: Vec<Record>,
+    /// Annotations of the run, sorted by time.
+    pub annotations: Vec<Annotation>,
+    /// Fleet-level records of the run, empty if the idleness is not enabled.
+    #[serde(default)]
+    pub fleet_records: Vec<FleetRecord>,
+    /// Error which stopped the run, if any.
+    pub error: Option<String>,
+}
+
+/// Outcome of one run of a batch, without its records (which are in the result file).
+#[derive(Debug, Clone)]
+pub struct BatchRunSummary {
+    /// Index of the run in the batch.
+    pub index: usize,
+    /// Seed used by the run.
+    pub seed: Option<f32>,
+    /// Values of the overridden fields, by field path.
+    pub parameters: BTreeMap<String, Value>,
+    /// Error which stopped the run, if any.
+    pub error: Option<String>,
+}
+
+/// Content of the aggregated result file of a batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResults {
+    /// Format marker, [`BATCH_RESULTS_FORMAT`].
+    #[serde(default)]
+    pub format: String,
+    /// Batch configuration.
+    pub batch: BatchConfig,
+    /// Results of the runs, sorted by index.
+    pub runs: Vec<BatchRunResult>,
+}
+
+/// Seed of the `replication`-th run of a parameter combination, when the batch gives no seed and
+/// the base configuration has the seed `base_seed`.
+///
+/// The first replication keeps `base_seed`, the next ones are drawn from it, so that the
+/// replications differ but the batch stays reproducible.
+pub fn replication_seed(base_seed: f32, replication: usize) -> f32 {
+    if replication == 0 {
+        return base_seed;
+    }
+    let mut rng =
+        ChaCha8Rng::seed_from_u64(((base_seed.to_bits() as u64) << 32) | replication as u64);
+    rng.r#gen()
+}
+
+/// Replace the field at the dotted `path` of `config` by `value`.
+///
+/// The path segments are the keys of the maps and the indexes of the lists. A missing last key is
+/// added.
+pub fn set_config_field(config: &mut Value, path: &str, value: Value) -> Result<(), String> {
+    let mut current = config;
+    let segments: Vec<&str> = path.split('.').collect();
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        current = match current {
+            Value::Object(map) => {
+                if last {
+                    map.insert(segment.to_string(), value);
+                    return Ok(());
+                }
+                map.get_mut(*segment)
+                    .ok_or_else(|| format!("No field '{segment}' in '{path}'"))?
+            }
+            Value::Array(list) => {
+                let index: usize = segment
+                    .parse()
+                    .map_err(|_| format!("'{segment}' is not a list index in '{path}'"))?;
+                let len = list.len();
+                let item = list.get_mut(index).ok_or_else(|| {
+                    format!("Index {index} out of range (length {len}) in '{path}'")
+                })?;
+                if last {
+                    *item = value;
+                    return Ok(());
+                }
+                item
+            }
+            _ => {
+                return Err(format!(
+                    "'{}' is not a map nor a list in '{path}'",
+                    segments[..i].join(".")
+                ));
+            }
+        };
+    }
+    Ok(())
+}
+
+/// Runner of the batch described by a [`BatchConfig`] file.
+#[derive(Debug)]
+pub struct BatchRunner {
+    /// Path of the batch file.
+    path: PathBuf,
+    config: BatchConfig,
+    base_config: SimulatorConfig,
+    load_mode: ConfigLoadMode,
+}
+
+impl BatchRunner {
+    /// Load the batch file and its base configuration.
+    pub fn from_config_path(path: &Path) -> SimbaResult<Self> {
+        Self::from_config_path_with_mode(path, ConfigLoadMode::Strict)
+    }
+
+    /// Load the batch file, and its base configuration with the given `mode`.
+    ///
+    /// The mode is also used by the runs, and given to the worker processes (`--permissive`).
+    pub fn from_config_path_with_mode(path: &Path, mode: ConfigLoadMode) -> SimbaResult<Self> {
+        let config: BatchConfig = load_config_file(path, "batch")?;
+        if let Err(e) = config.check() {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!("Error in batch config:\n{e}"),
+            ));
+        }
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        let (base_config, warnings) =
+            SimulatorConfig::load_from_path_with_mode(&base_dir.join(&config.config_path), mode)?;
+        for warning in &warnings {
+            warn!("{warning}");
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            config,
+            base_config,
+            load_mode: mode,
+        })
+    }
+
+    /// Batch configuration.
+    pub fn config(&self) -> &BatchConfig {
+        &self.config
+    }
+
+    fn output_path(&self) -> PathBuf {
+        self.path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(&self.config.output_path)
+    }
+
+    /// Path of the result of the run `index`, written by the worker processes.
+    fn run_output_path(&self, index: usize) -> PathBuf {
+        let mut path = self.output_path().into_os_string();
+        path.push(format!(".run{index}"));
+        PathBuf::from(path)
+    }
+
+    /// Configuration of a run: the base configuration with the run seed and parameters.
+    ///
+    /// The records are kept in memory only: the result file, the analysis script and the
+    /// figures of the base configuration are disabled.
+    pub fn run_config(&self, run: &BatchRun) -> SimbaResult<SimulatorConfig> {
+        let mut value = serde_json::to_value(&self.base_config).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ImplementationError,
+                format!("Error during json serialization of the configuration: {e}"),
+            )
+        })?;
+        for (field, field_value) in &run.parameters {
+            set_config_field(&mut value, field, field_value.clone()).map_err(|e| {
+                SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    format!("Impossible to set the swept field: {e}"),
+                )
+            })?;
+        }
+        let mut config: SimulatorConfig = serde_json::from_value(value).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!("Invalid configuration for run {}: {e}", run.index),
+            )
+        })?;
+        match (run.seed, config.random_seed) {
+            (Some(seed), _) => config.random_seed = Some(seed),
+            (None, Some(base_seed)) => {
+                config.random_seed = Some(replication_seed(base_seed, run.replication))
+            }
+            (None, None) => {}
+        }
+        let results = config.results.get_or_insert_with(ResultConfig::default);
+        results.result_path = None;
+        results.analyse_script = None;
+        results.show_figures = false;
+        results.bench_report = None;
+        results.metrics = None;
+        results.estimator_dataset = None;
+        results.memory_cap = None;
+        Ok(config)
+    }
+
+    /// Run the run `index` of the batch in the current process.
+    pub fn run_one(
+        &self,
+        index: usize,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+    ) -> SimbaResult<BatchRunResult> {
+        let run = self
+            .config
+            .runs()
+            .into_iter()
+            .find(|r| r.index == index)
+            .ok_or_else(|| {
+                SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    format!("No run {index} in the batch"),
+                )
+            })?;
+        let mut result = BatchRunResult {
+            index,
+            seed: run.seed,
+            parameters: run.parameters.clone(),
+            records: Vec::new(),
+            annotations: Vec::new(),
+            fleet_records: Vec::new(),
+            error: None,
+        };
+        let mut simulator = Simulator::new();
+        simulator.set_config_load_mode(self.load_mode);
+        if let Err(e) = self
+            .run_config(&run)
+            .and_then(|config| simulator.load_config(&config, plugin_api))
+        {
+            result.error = Some(e.detailed_error());
+            return Ok(result);
+        }
+        result.seed = simulator.config().random_seed;
+        if let Err(e) = simulator.run() {
+            result.error = Some(e.detailed_error());
+        }
+        result.records = simulator.get_records(true);
+        result.annotations = simulator.annotations();
+        result.fleet_records = simulator.get_fleet_records();
+        Ok(result)
+    }
+
+    /// Run the run `index` and write its result next to the batch output, for
+    /// [`BatchRunner::run`] to aggregate it. Entry point of the worker processes.
+    pub fn run_one_to_file(
+        &self,
+        index: usize,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+    ) -> SimbaResult<()> {
+        let result = self.run_one(index, plugin_api)?;
+        write_json(&self.run_output_path(index), &result)
+    }
+
+    /// Run all the runs of the batch and write the aggregated result file.
+    ///
+    /// With more than one process in the configuration, the runs are done by instances of the
+    /// `worker` executable, called with `--batch <batch file> --batch-run <index>` (as
+    /// `simba-cmd`), which should call [`BatchRunner::run_one_to_file`]. Without `worker`, the
+    /// runs are done one after the other in the current process.
+    ///
+    /// Each run is written to the result file when it is done, in the order of the indexes, and
+    /// its records are dropped: the records are read back with [`BatchRunner::load_results`].
+    pub fn run(
+        &self,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+        worker: Option<&Path>,
+    ) -> SimbaResult<Vec<BatchRunSummary>> {
+        let runs = self.config.runs();
+        let nb_runs = runs.len();
+        let mut writer = BatchResultWriter::create(&self.output_path(), &self.config)?;
+        let mut summaries = Vec::with_capacity(nb_runs);
+        let mut push = |result: BatchRunResult| -> SimbaResult<()> {
+            info!("Batch run {}/{nb_runs} done", result.index + 1);
+            writer.push(&result)?;
+            summaries.push(BatchRunSummary {
+                index: result.index,
+                seed: result.seed,
+                parameters: result.parameters,
+                error: result.error,
+            });
+            Ok(())
+        };
+        match worker {
+            Some(worker) if self.config.processes > 1 => {
+                // The workers are collected in the order they were started, which is the order
+                // of the indexes
+                let mut running: Vec<(usize, Child)> = Vec::new();
+                for run in &runs {
+                    if running.len() >= self.config.processes {
+                        let (index, child) = running.remove(0);
+                        push(self.collect_worker(index, child))?;
+                    }
+                    let mut command = Command::new(worker);
+                    command
+                        .arg("--batch")
+                        .arg(&self.path)
+                        .arg("--batch-run")
+                        .arg(run.index.to_string());
+                    if self.load_mode == ConfigLoadMode::Permissive {
+                        command.arg("--permissive");
+                    }
+                    let child = command.stdout(Stdio::null()).spawn().map_err(|e| {
+                        SimbaError::new(
+                            SimbaErrorTypes::ConfigError,
+                            format!(
+                                "Impossible to start the batch worker '{}': {e}",
+                                worker.to_str().unwrap_or_default()
+                            ),
+                        )
+                    })?;
+                    running.push((run.index, child));
+                }
+                for (index, child) in running {
+                    push(self.collect_worker(index, child))?;
+                }
+            }
+            _ => {
+                if self.config.processes > 1 {
+                    warn!("No worker executable given for the batch, runs are done sequentially");
+                }
+                for run in &runs {
+                    push(self.run_one(run.index, plugin_api.clone())?)?;
+                }
+            }
+        }
+        writer.finish()?;
+        Ok(summaries)
+    }
+
+    /// Wait for a worker process and read its run result.
+    fn collect_worker(&self, index: usize, mut child: Child) -> BatchRunResult {
+        let status = child.wait();
+        let path = self.run_output_path(index);
+        let result = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<BatchRunResult>(&content).map_err(|e| e.to_string())
+            });
+        let _ = fs::remove_file(&path);
+        result.unwrap_or_else(|e| BatchRunResult {
+            index,
+            seed: None,
+            parameters: BTreeMap::new(),
+            records: Vec::new(),
+            annotations: Vec::new(),
+            fleet_records: Vec::new(),
+            error: Some(format!(
+                "Worker failed ({}): {e}",
+                match status {
+                    Ok(status) => status.to_string(),
+                    Err(e) => e.to_string(),
+                }
+            )),
+        })
+    }
+
+    /// Load an aggregated result file written by [`BatchRunner::run`].
+    pub fn load_results(path: &Path) -> SimbaResult<BatchResults> {
+        let file = fs::File::open(path).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to read batch result file '{}': {}",
+                    path.to_str().unwrap_or_default(),
+                    e
+                ),
+            )
+        })?;
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!("Error while parsing batch results: {e}"),
+            )
+        })
+    }
+}
+
+/// Writer of the aggregated result file, a [`BatchResults`] whose runs are written one by one.
+struct BatchResultWriter {
+    path: PathBuf,
+    writer: BufWriter<fs::File>,
+    nb_runs: usize,
+}
+
+impl BatchResultWriter {
+    /// Create the file at `path` and write the batch configuration.
+    fn create(path: &Path, batch: &BatchConfig) -> SimbaResult<Self> {
+        let file = fs::File::create(path).map_err(|e| write_error(path, e))?;
+        let mut writer = Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            nb_runs: 0,
+        };
+        writer.write(format!("{{\"format\":\"{BATCH_RESULTS_FORMAT}\",\"batch\":").as_bytes())?;
+        serde_json::to_writer(&mut writer.writer, batch).map_err(serialization_error)?;
+        writer.write(b",\"runs\":[")?;
+        Ok(writer)
+    }
+
+    fn write(&mut self, content: &[u8]) -> SimbaResult<()> {
+        self.writer
+            .write_all(content)
+            .map_err(|e| write_error(&self.path, e))
+    }
+
+    /// Append the `result` of a run.
+    fn push(&mut self, result: &BatchRunResult) -> SimbaResult<()> {
+        if self.nb_runs > 0 {
+            self.write(b",")?;
+        }
+        serde_json::to_writer(&mut self.writer, result).map_err(serialization_error)?;
+        self.nb_runs += 1;
+        Ok(())
+    }
+
+    /// Close the list of the runs and flush the file.
+    fn finish(mut self) -> SimbaResult<()> {
+        self.write(b"]}")?;
+        self.writer.flush().map_err(|e| write_error(&self.path, e))
+    }
+}
+
+fn write_error(path: &Path, e: impl std::fmt::Display) -> SimbaError {
+    SimbaError::new(
+        SimbaErrorTypes::ConfigError,
+        format!(
+            "Impossible to write batch result file '{}': {}",
+            path.to_str().unwrap_or_default(),
+            e
+        ),
+    )
+}
+
+fn serialization_error(e: serde_json::Error) -> SimbaError {
+    SimbaError::new(
+        SimbaErrorTypes::ImplementationError,
+        format!("Error during json serialization of the batch results: {e}"),
+    )
+}
+
+fn write_json<T: Serialize>(path: &Path, content: &T) -> SimbaResult<()> {
+    let file = fs::File::create(path).map_err(|e| write_error(path, e))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, content).map_err(serialization_error)?;
+    writer.flush().map_err(|e| write_error(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sweep_runs() {
+        let mut config = BatchConfig {
+            seeds: vec![1., 2.],
+            parameters: vec![
+                SweepParameterConfig {
+                    field: "a".to_string(),
+                    values: vec![json!(1), json!(2), json!(3)],
+                },
+                SweepParameterConfig {
+                    field: "b".to_string(),
+                    values: vec![json!("x"), json!("y"), json!("z")],
+                },
+            ],
+            ..Default::default()
+        };
+        let runs = config.runs();
+        assert_eq!(runs.len(), 18);
+        assert_eq!(runs[17].index, 17);
+        assert_eq!(runs[17].replication, 1);
+        assert_eq!(runs[17].seed, Some(2.));
+        assert_eq!(runs[17].parameters["a"], json!(3));
+        assert_eq!(runs[17].parameters["b"], json!("z"));
+
+        config.mode = SweepMode::List;
+        config.seeds = Vec::new();
+        config.replications = 2;
+        let runs = config.runs();
+        assert_eq!(runs.len(), 6);
+        assert_eq!(runs[2].seed, None);
+        assert_eq!(runs[2].replication, 0);
+        assert_eq!(runs[3].replication, 1);
+        assert_eq!(runs[2].parameters["a"], json!(2));
+        assert_eq!(runs[2].parameters["b"], json!("y"));
+    }
+
+    #[test]
+    fn replication_seeds() {
+        assert_eq!(replication_seed(42., 0), 42.);
+        let seeds: Vec<f32> = (0..5).map(|r| replication_seed(42., r)).collect();
+        for (i, seed) in seeds.iter().enumerate() {
+            assert!(!seeds[..i].contains(seed), "seed {seed} drawn twice");
+        }
+        // Reproducible, and specific to the base seed
+        assert_eq!(replication_seed(42., 3), seeds[3]);
+        assert_ne!(replication_seed(43., 3), seeds[3]);
+    }
+
+    #[test]
+    fn streamed_result_file() {
+        let path =
+            std::env::temp_dir().join(format!("simba_batch_results_{}.json", std::process::id()));
+        let batch = BatchConfig::default();
+        let mut writer = BatchResultWriter::create(&path, &batch).unwrap();
+        for index in 0..3 {
+            writer
+                .push(&BatchRunResult {
+                    index,
+                    seed: Some(index as f32),
+                    parameters: BTreeMap::new(),
+                    records: Vec::new(),
+                    annotations: Vec::new(),
+                    fleet_records: Vec::new(),
+                    error: None,
+                })
+                .unwrap();
+        }
+        writer.finish().unwrap();
+        let results = BatchRunner::load_results(&path).unwrap();
+        assert_eq!(results.format, BATCH_RESULTS_FORMAT);
+        assert_eq!(results.batch, batch);
+        assert_eq!(
+            results.runs.iter().map(|r| r.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn override_config_fields() {
+        let mut config =
+            json!({"robots": [{"name": "r1", "navigator": {"speed": 1.}}], "results": null});
+        set_config_field(&mut config, "robots.0.navigator.speed", json!(2.)).unwrap();
+        set_config_field(&mut config, "robots.0.labels", json!(["fast"])).unwrap();
+        assert_eq!(config["robots"][0]["navigator"]["speed"], json!(2.));
+        assert_eq!(config["robots"][0]["labels"], json!(["fast"]));
+        assert!(set_config_field(&mut config, "robots.1.name", json!("r2")).is_err());
+        assert!(set_config_field(&mut config, "results.result_path", json!("a.json")).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+/*!
+Determinism self-check: run the same scenario twice in the same process, with the same seed,
+and compare the records.
+
+The first diverging record is reported with its node, its module (the first field of the
+node record which differs, e.g. `navigator` or `state_estimator`) and its time, along with the
+[`SnapshotDiff`] of all the nodes at this time to localize the divergence.
+*/
+
+use std::{fmt::Display, sync::Arc};
+
+use log::info;
+
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    plugin_api::PluginAPI,
+};
+
+use super::{
+    Record, Simulator,
+    snapshot_diff::{Snapshot, SnapshotDiff, diff_snapshots},
+};
+
+/// First difference found between the records of two runs.
+#[derive(Debug, Clone)]
+pub struct RecordDivergence {
+    /// Time of the diverging record.
+    pub time: f32,
+    /// Name of the node.
+    pub node: String,
+    /// Module of the node record which differs. `None` if the record is missing in one of the runs.
+    pub module: Option<String>,
+    /// Record of the reference run (serialized), `None` if missing.
+    pub reference: Option<String>,
+    /// Record of the second run (serialized), `None` if missing.
+    pub other: Option<String>,
+    /// Field by field difference of all the nodes at the divergence time. Only filled by
+    /// [`Simulator::determinism_check`].
+    pub snapshot_diff: Option<SnapshotDiff>,
+}
+
+impl Display for RecordDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let to_string = |record: &Option<String>| match record {
+            Some(r) => r.clone(),
+            None => String::from("no record"),
+        };
+        write!(
+            f,
+            "First divergence at time {} for node {} (module: {})\n\treference: {}\n\tother:     {}",
+            self.time,
+            self.node,
+            self.module.as_deref().unwrap_or("whole record"),
+            to_string(&self.reference),
+            to_string(&self.other)
+        )?;
+        if let Some(snapshot_diff) = &self.snapshot_diff {
+            write!(f, "\n{snapshot_diff}")?;
+        }
+        Ok(())
+    }
+}
+
+fn record_to_value(record: &Record) -> SimbaResult<serde_json::Value> {
+    serde_json::to_value(&record.node).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::ImplementationError,
+            format!("Error during json serialization of a record: {e}"),
+        )
+    })
+}
+
+/// Return the first field differing between two serialized node records.
+///
+/// Node records are serialized as `{"Robot": {"navigator": ..., ...}}`, the variant level is skipped.
+fn diverging_module(reference: &serde_json::Value, other: &serde_json::Value) -> Option<String> {
+    let (reference, other) = match (reference, other) {
+        (serde_json::Value::Object(r), serde_json::Value::Object(o)) if r.len() == 1 => {
+            match (r.values().next(), o.get(r.keys().next().unwrap())) {
+                (Some(r), Some(o)) => (r, o),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    match (reference, other) {
+        (serde_json::Value::Object(r), serde_json::Value::Object(o)) => r
+            .iter()
+            .find(|(key, value)| o.get(*key) != Some(*value))
+            .map(|(key, _)| key.clone()),
+        _ => None,
+    }
+}
+
+/// Compare two lists of records, sorted with [`Simulator::get_records`], and return the
+/// first divergence.
+pub fn compare_records(
+    reference: &[Record],
+    other: &[Record],
+) -> SimbaResult<Option<RecordDivergence>> {
+    for (reference, other) in reference.iter().zip(other.iter()) {
+        if reference != other {
+            // Different time or node: one of the runs has a record the other doesn't
+            let first = reference.min(other);
+            let in_reference = first == reference;
+            let serialized = serde_json::to_string(&first.node).ok();
+            return Ok(Some(RecordDivergence {
+                time: first.time,
+                node: first.node.name().to_string(),
+                module: None,
+                reference: if in_reference {
+                    serialized.clone()
+                } else {
+                    None
+                },
+                other: if in_reference { None } else { serialized },
+                snapshot_diff: None,
+            }));
+        }
+        let reference_value = record_to_value(reference)?;
+        let other_value = record_to_value(other)?;
+        if reference_value != other_value {
+            return Ok(Some(RecordDivergence {
+                time: reference.time,
+                node: reference.node.name().to_string(),
+                module: diverging_module(&reference_value, &other_value),
+                reference: Some(reference_value.to_string()),
+                other: Some(other_value.to_string()),
+                snapshot_diff: None,
+            }));
+        }
+    }
+    let n = reference.len().min(other.len());
+    if let Some(record) = reference.get(n) {
+        return Ok(Some(RecordDivergence {
+            time: record.time,
+            node: record.node.name().to_string(),
+            module: None,
+            reference: serde_json::to_string(&record.node).ok(),
+            other: None,
+            snapshot_diff: None,
+        }));
+    }
+    if let Some(record) = other.get(n) {
+        return Ok(Some(RecordDivergence {
+            time: record.time,
+            node: record.node.name().to_string(),
+            module: None,
+            reference: None,
+            other: serde_json::to_string(&record.node).ok(),
+            snapshot_diff: None,
+        }));
+    }
+    Ok(None)
+}
+
+impl Simulator {
+    /// Run the loaded scenario twice with the same seed, and return the sorted records of
+    /// both runs.
+    fn run_twice(
+        &mut self,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+    ) -> SimbaResult<(Vec<Record>, Vec<Record>)> {
+        // The configuration holds the seed of the first run, even if not given by the user.
+        let config = self.config();
+        info!("Determinism check: first run");
+        self.run()?;
+        let reference = self.get_records(true);
+
+        info!("Determinism check: second run");
+        let mut second = Simulator::from_config(&config, plugin_api)?;
+        second.run()?;
+        let other = second.get_records(true);
+        Ok((reference, other))
+    }
+
+    /// Run the loaded scenario twice with the same seed, and compare the records.
+    ///
+    /// The simulator keeps the records of the first run, so the results can be computed afterwards.
+    ///
+    /// ## Arguments
+    /// * `plugin_api` - Plugin API used to load the second simulator, the same as for this one.
+    ///
+    /// ## Return
+    /// Returns the first diverging record, with the snapshot diff at its time, or `None` if
+    /// both runs gave the same records.
+    pub fn determinism_check(
+        &mut self,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+    ) -> SimbaResult<Option<RecordDivergence>> {
+        let (reference, other) = self.run_twice(plugin_api)?;
+        let mut divergence = compare_records(&reference, &other)?;
+        if let Some(divergence) = &mut divergence {
+            divergence.snapshot_diff = Some(diff_snapshots(
+                &Snapshot::from_records(&reference, divergence.time)?,
+                &Snapshot::from_records(&other, divergence.time)?,
+            ));
+        }
+        Ok(divergence)
+    }
+
+    /// Run the loaded scenario twice with the same seed, and compare the snapshots of both
+    /// runs at the given `time`.
+    ///
+    /// Useful to look at the state of the runs after the first divergence found by
+    /// [`Simulator::determinism_check`]. The simulator keeps the records of the first run.
+    pub fn determinism_snapshot_diff(
+        &mut self,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+        time: f32,
+    ) -> SimbaResult<SnapshotDiff> {
+        let (reference, other) = self.run_twice(plugin_api)?;
+        Ok(diff_snapshots(
+            &Snapshot::from_records(&reference, time)?,
+            &Snapshot::from_records(&other, time)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        node::node_factory::{ComputationUnitRecord, NodeRecord},
+        sensors::sensor_manager::SensorManagerRecord,
+    };
+
+    fn record(time: f32, name: &str, model_name: &str) -> Record {
+        Record {
+            time,
+            node: NodeRecord::ComputationUnit(Box::new(ComputationUnitRecord {
+                name: name.to_string(),
+                model_name: model_name.to_string(),
+                state_estimators: Vec::new(),
+                sensor_manager: SensorManagerRecord {
+                    sensors: Vec::new(),
+                    next_time: None,
+                    last_observations: Vec::new(),
+                },
+                network: Default::default(),
+                labels: Vec::new(),
+                skipped_steps: 0,
+                observation_latencies: Vec::new(),
+                estimator_divergences: Vec::new(),
+            })),
+        }
+    }
+
+    #[test]
+    fn compare_records_finds_first_divergence() {
+        let reference = vec![
+            record(0., "a", "model"),
+            record(0., "b", "model"),
+            record(1., "a", "model"),
+        ];
+        assert!(compare_records(&reference, &reference).unwrap().is_none());
+
+        let other = vec![
+            record(0., "a", "model"),
+            record(0., "b", "other_model"),
+            record(1., "a", "other_model"),
+        ];
+        let divergence = compare_records(&reference, &other).unwrap().unwrap();
+        assert_eq!(divergence.node, "b");
+        assert_eq!(divergence.time, 0.);
+        assert_eq!(divergence.module.as_deref(), Some("model_name"));
+
+        let divergence = compare_records(&reference, &reference[..2])
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.node, "a");
+        assert_eq!(divergence.time, 1.);
+        assert!(divergence.other.is_none());
+    }
+}
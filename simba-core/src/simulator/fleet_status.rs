@@ -0,0 +1,171 @@
+//! Health of the nodes of a run (heartbeats).
+//!
+//! Each node publishes a [`NodeHeartbeat`] at the end of its time steps (state, last time step,
+//! number of messages waiting in its queues). The simulator aggregates them in a
+//! [`FleetStatus`], refreshing the state of the nodes which do not run anymore (killed, failed).
+//! It is exposed through the async API ([`SimulatorAsyncApi::fleet_status`](super::SimulatorAsyncApi::fleet_status))
+//! and the GUI, so that stalled nodes and zombie transitions are visible during long runs.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    node::{NodeMetaData, NodeState, node_factory::NodeType},
+    utils::{
+        SharedRoLock, SharedRwLock,
+        lock_recovery::{RecoverRoLock, RecoverRwLock},
+    },
+};
+
+/// Last reported health of one node.
+#[derive(Debug, Clone)]
+pub struct NodeHeartbeat {
+    /// Name of the node.
+    pub node: String,
+    /// Type of the node.
+    pub node_type: NodeType,
+    /// Lifecycle state of the node.
+    pub state: NodeState,
+    /// Simulated time of the last time step run by the node, `None` before the first one.
+    pub time: Option<f32>,
+    /// Number of time steps run by the node.
+    pub steps: usize,
+    /// Number of messages waiting in the queues of the node.
+    pub queued_messages: usize,
+    /// Wall-clock duration of the last time step.
+    pub step_duration: Duration,
+    /// Wall-clock instant of the last update of the heartbeat.
+    pub updated_at: Instant,
+}
+
+impl NodeHeartbeat {
+    /// Wall-clock time elapsed since the last update of the heartbeat.
+    pub fn age(&self) -> Duration {
+        self.updated_at.elapsed()
+    }
+}
+
+/// Shared table of the last [`NodeHeartbeat`] of each node.
+///
+/// Clones share the same table, so the handle can be given to the node threads and the async API.
+#[derive(Debug, Clone, Default)]
+pub struct FleetStatus {
+    heartbeats: SharedRwLock<BTreeMap<String, NodeHeartbeat>>,
+}
+
+impl FleetStatus {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self {
+            heartbeats: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    /// Replace the heartbeat of the node.
+    pub fn publish(&self, heartbeat: NodeHeartbeat) {
+        self.heartbeats
+            .write_recover()
+            .insert(heartbeat.node.clone(), heartbeat);
+    }
+
+    /// Update the state of the nodes from their meta-data, and add the nodes without heartbeat.
+    ///
+    /// The heartbeat is only refreshed when the state changed, so the age of the heartbeat of a
+    /// stalled node keeps growing.
+    pub fn refresh_states(&self, meta_data: &HashMap<String, SharedRoLock<NodeMetaData>>) {
+        let mut heartbeats = self.heartbeats.write_recover();
+        for (name, meta_data) in meta_data {
+            let meta_data = meta_data.read_recover();
+            match heartbeats.get_mut(name) {
+                Some(heartbeat) => {
+                    if heartbeat.state != meta_data.state {
+                        heartbeat.state = meta_data.state.clone();
+                        heartbeat.updated_at = Instant::now();
+                    }
+                }
+                None => {
+                    heartbeats.insert(
+                        name.clone(),
+                        NodeHeartbeat {
+                            node: name.clone(),
+                            node_type: meta_data.node_type.clone(),
+                            state: meta_data.state.clone(),
+                            time: None,
+                            steps: 0,
+                            queued_messages: 0,
+                            step_duration: Duration::ZERO,
+                            updated_at: Instant::now(),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Last heartbeat of each node, sorted by node name.
+    pub fn list(&self) -> Vec<NodeHeartbeat> {
+        self.heartbeats.read_recover().values().cloned().collect()
+    }
+
+    /// Last heartbeat of the node `name`.
+    pub fn get(&self, name: &str) -> Option<NodeHeartbeat> {
+        self.heartbeats.read_recover().get(name).cloned()
+    }
+
+    /// Names of the running nodes without heartbeat for more than `max_age`.
+    pub fn stalled(&self, max_age: Duration) -> Vec<String> {
+        self.heartbeats
+            .read_recover()
+            .values()
+            .filter(|h| h.state == NodeState::Running && h.age() > max_age)
+            .map(|h| h.node.clone())
+            .collect()
+    }
+
+    /// Number of nodes in the state `state`.
+    pub fn count(&self, state: &NodeState) -> usize {
+        self.heartbeats
+            .read_recover()
+            .values()
+            .filter(|h| &h.state == state)
+            .count()
+    }
+
+    /// Remove all the heartbeats.
+    pub fn clear(&self) {
+        self.heartbeats.write_recover().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat(node: &str, state: NodeState, updated_at: Instant) -> NodeHeartbeat {
+        NodeHeartbeat {
+            node: node.to_string(),
+            node_type: NodeType::Robot,
+            state,
+            time: Some(1.),
+            steps: 10,
+            queued_messages: 0,
+            step_duration: Duration::ZERO,
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn stalled_nodes() {
+        let status = FleetStatus::new();
+        let old = Instant::now() - Duration::from_secs(10);
+        status.publish(heartbeat("robot1", NodeState::Running, Instant::now()));
+        status.publish(heartbeat("robot2", NodeState::Running, old));
+        status.publish(heartbeat("robot3", NodeState::Terminated, old));
+
+        assert_eq!(status.stalled(Duration::from_secs(5)), vec!["robot2"]);
+        assert_eq!(status.count(&NodeState::Running), 2);
+    }
+}
@@ -0,0 +1,51 @@
+//! Callbacks of the simulator for the applications embedding it.
+//!
+//! The plugin API ([`PluginAPI`](crate::plugin_api::PluginAPI)) builds the modules of the nodes.
+//! Applications which only need to follow the run (progress bar, live export, custom stop
+//! condition, ...) can instead register [`SimulatorHooks`] with
+//! [`Simulator::add_hooks`](super::Simulator::add_hooks). The hooks are called from the simulator
+//! thread, between the time steps: they should return quickly, as the nodes wait for them.
+
+use crate::scenario::config::EventRecord;
+
+use super::{Record, Results};
+
+/// Callbacks called by the [`Simulator`](super::Simulator) during the runs.
+///
+/// All the methods do nothing by default, implement the ones needed.
+///
+/// # Example
+/// ```no_run
+/// use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+/// use simba::simulator::{Record, Simulator, SimulatorHooks};
+///
+/// #[derive(Default)]
+/// struct RecordCounter(AtomicUsize);
+///
+/// impl SimulatorHooks for RecordCounter {
+///     fn on_record(&self, _record: &Record) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counter = Arc::new(RecordCounter::default());
+/// let mut simulator = Simulator::new();
+/// simulator.add_hooks(counter.clone());
+/// ```
+pub trait SimulatorHooks: Send + Sync {
+    /// Called at the end of each time step of the run, at the simulated `time`.
+    fn on_step(&self, _time: f32) {}
+
+    /// Called for each record collected by the simulator, when the results are processed
+    /// (following the save mode). The records are only collected when the `results`
+    /// configuration is set.
+    fn on_record(&self, _record: &Record) {}
+
+    /// Called when a scenario event is executed at the simulated `time`, with the resolved event
+    /// (node names substituted) and its trigger.
+    fn on_event(&self, _time: f32, _event: &EventRecord) {}
+
+    /// Called at the end of each run, with the configuration, the records kept in memory and the
+    /// annotations.
+    fn on_finish(&self, _results: &Results) {}
+}
@@ -0,0 +1,212 @@
+//! Fleet-level idleness of patrolled waypoints.
+//!
+//! The idleness of a waypoint is the time elapsed since its last visit by any node (since the
+//! start of the run if it was never visited), the metric of the multi-robot patrolling
+//! benchmarks. When [`SimulatorConfig::idleness`](super::SimulatorConfig::idleness) is set, the simulator checks the ground-truth
+//! positions of the running nodes at the end of each time step, and makes a [`FleetRecord`] with
+//! the idleness of all the waypoints.
+//!
+//! The fleet records are given by [`Simulator::get_fleet_records`](super::Simulator::get_fleet_records)
+//! and saved with the results: `fleet_records` list of the JSON file, `fleet` table for the CSV
+//! and Parquet formats.
+
+#[cfg(feature = "gui")]
+use crate::{gui::UIComponent, simulator::SimulatorConfig};
+use crate::{navigators::NavigatorConfig, node::node_factory::RobotConfig};
+
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Configuration of the fleet idleness metric.
+///
+/// ```yaml
+/// idleness:
+///   waypoints: [] # Waypoints of the Patrol navigators
+///   visit_radius: 0.5
+/// ```
+#[config_derives]
+pub struct IdlenessConfig {
+    /// Waypoints whose idleness is measured, in world coordinates `[x, y]`. If empty, the
+    /// waypoints of the `Patrol` navigators of the robots are used.
+    ///
+    /// Default: empty.
+    pub waypoints: Vec<[f32; 2]>,
+    /// Distance under which a node visits a waypoint, in meters.
+    ///
+    /// Default: `0.5`.
+    pub visit_radius: f32,
+}
+
+impl Default for IdlenessConfig {
+    fn default() -> Self {
+        Self {
+            waypoints: Vec::new(),
+            visit_radius: 0.5,
+        }
+    }
+}
+
+impl Check for IdlenessConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.visit_radius <= 0. {
+            Err(vec!["Visit radius should be strictly positive".to_string()])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for IdlenessConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Idleness")
+            .id_salt(format!("idleness-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Visit radius:");
+                    ui.add(egui::DragValue::new(&mut self.visit_radius).range(0.001..=f32::MAX));
+                });
+                ui.label("Waypoints (empty for the Patrol navigators ones):");
+                let mut to_remove = None;
+                for (i, waypoint) in self.waypoints.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{i}: ("));
+                        ui.add(egui::DragValue::new(&mut waypoint[0]).fixed_decimals(2));
+                        ui.label(", ");
+                        ui.add(egui::DragValue::new(&mut waypoint[1]).fixed_decimals(2));
+                        ui.label(")");
+                        if ui.button("-").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = to_remove {
+                    self.waypoints.remove(i);
+                }
+                if ui.button("Add waypoint").clicked() {
+                    self.waypoints.push([0., 0.]);
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Idleness")
+            .id_salt(format!("idleness-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!("Visit radius: {}", self.visit_radius));
+                if self.waypoints.is_empty() {
+                    ui.label("Waypoints of the Patrol navigators");
+                }
+                for (i, waypoint) in self.waypoints.iter().enumerate() {
+                    ui.label(format!("Waypoint {i}: ({}, {})", waypoint[0], waypoint[1]));
+                }
+            });
+    }
+}
+
+/// Fleet-level record at a given time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FleetRecord {
+    /// Time of the record.
+    pub time: f32,
+    /// Idleness of each waypoint, in seconds.
+    pub idleness: Vec<f32>,
+    /// Mean idleness of the waypoints.
+    pub average_idleness: f32,
+    /// Highest idleness of the waypoints (worst idleness).
+    pub max_idleness: f32,
+}
+
+/// Tracker of the last visits of the waypoints, making the [`FleetRecord`]s.
+#[derive(Debug)]
+pub(super) struct IdlenessTracker {
+    waypoints: Vec<[f32; 2]>,
+    visit_radius: f32,
+    last_visits: Vec<f32>,
+}
+
+impl IdlenessTracker {
+    /// Make the tracker for the run starting at `start_time`, using the waypoints of the `Patrol`
+    /// navigators of the `robots` if the configuration gives none.
+    pub fn new(config: &IdlenessConfig, robots: &[RobotConfig], start_time: f32) -> Self {
+        let mut waypoints = config.waypoints.clone();
+        if waypoints.is_empty() {
+            for robot in robots {
+                if let NavigatorConfig::Patrol(patrol) = &robot.navigator {
+                    for waypoint in &patrol.waypoints {
+                        if !waypoints.contains(waypoint) {
+                            waypoints.push(*waypoint);
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            last_visits: vec![start_time; waypoints.len()],
+            waypoints,
+            visit_radius: config.visit_radius,
+        }
+    }
+
+    /// Update the visits with the `positions` of the nodes at `time`, and make the record.
+    pub fn update<'a>(
+        &mut self,
+        time: f32,
+        positions: impl Iterator<Item = &'a [f32; 2]>,
+    ) -> FleetRecord {
+        for position in positions {
+            for (waypoint, last_visit) in self.waypoints.iter().zip(self.last_visits.iter_mut()) {
+                if (waypoint[0] - position[0]).hypot(waypoint[1] - position[1]) < self.visit_radius
+                {
+                    *last_visit = time;
+                }
+            }
+        }
+        let idleness: Vec<f32> = self
+            .last_visits
+            .iter()
+            .map(|last_visit| time - last_visit)
+            .collect();
+        FleetRecord {
+            time,
+            average_idleness: if idleness.is_empty() {
+                0.
+            } else {
+                idleness.iter().sum::<f32>() / idleness.len() as f32
+            },
+            max_idleness: idleness.iter().copied().fold(0., f32::max),
+            idleness,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idleness_since_last_visit() {
+        let mut tracker = IdlenessTracker::new(
+            &IdlenessConfig {
+                waypoints: vec![[0., 0.], [10., 0.]],
+                ..Default::default()
+            },
+            &[],
+            0.,
+        );
+        let record = tracker.update(1., [[0.2, 0.]].iter());
+        assert_eq!(record.idleness, vec![0., 1.]);
+        let record = tracker.update(3., [[5., 0.]].iter());
+        assert_eq!(record.idleness, vec![2., 3.]);
+        assert_eq!(record.average_idleness, 2.5);
+        assert_eq!(record.max_idleness, 3.);
+    }
+}
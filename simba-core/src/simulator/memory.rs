@@ -0,0 +1,201 @@
+//! Approximate memory accounting of a run, and memory cap.
+//!
+//! Long runs retain every record produced by the nodes (for the final analysis) and messages
+//! may pile up in the broker when subscribers are slow. [`MemoryUsage`] gives an estimate of
+//! this memory, and [`MemoryCapConfig`] allows to bound it by flushing the records to the
+//! result file or by pruning the oldest ones.
+//!
+//! Sizes are estimated from the JSON serialization of the records, which is of the same order
+//! of magnitude as their in-memory representation. It is not an allocator measurement.
+
+use std::{fmt::Display, io::Write};
+
+use config_checker::*;
+use serde::Serialize;
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{UIComponent, utils::string_combobox},
+    simulator::SimulatorConfig,
+    utils::enum_tools::ToVec,
+};
+
+/// Approximate size of a message waiting in the broker, in bytes.
+///
+/// Messages are generic, so their actual size is not known by the simulator.
+pub const APPROXIMATE_MESSAGE_SIZE: usize = 256;
+
+/// Ratio of the cap to reach when pruning, to avoid pruning at every time step.
+pub const PRUNE_TARGET_RATIO: f32 = 0.75;
+
+/// Action taken when the memory cap is exceeded.
+#[config_derives]
+pub enum MemoryCapAction {
+    /// Write the pending records to the result file and release the records kept in memory.
+    /// Requires a `result_path` and a save mode other than `AtTheEnd`.
+    Flush,
+    /// Drop the oldest records kept in memory. Records not yet written in the result file are lost.
+    Prune,
+}
+
+impl Default for MemoryCapAction {
+    fn default() -> Self {
+        Self::Flush
+    }
+}
+
+/// Memory cap of the run.
+///
+/// The records released by the cap are no longer available for the analysis script
+/// ([`Simulator::compute_results`](crate::simulator::Simulator::compute_results)) nor
+/// [`Simulator::get_records`](crate::simulator::Simulator::get_records).
+/// With [`MemoryCapAction::Flush`], they can be loaded back from the result file.
+///
+/// # Example
+/// ```yaml
+/// memory_cap:
+///   max_memory: 2048 # MB
+///   action:
+///     type: Flush
+/// ```
+#[config_derives]
+pub struct MemoryCapConfig {
+    /// Maximum approximate memory used by the retained records and broker queues, in MB.
+    pub max_memory: f32,
+    /// Action taken when the cap is exceeded.
+    pub action: MemoryCapAction,
+}
+
+impl Default for MemoryCapConfig {
+    fn default() -> Self {
+        Self {
+            max_memory: 1024.,
+            action: MemoryCapAction::default(),
+        }
+    }
+}
+
+impl Check for MemoryCapConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.max_memory <= 0. {
+            Err(vec![format!(
+                "Maximum memory should be strictly positive, got {}",
+                self.max_memory
+            )])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl MemoryCapConfig {
+    /// Cap in bytes.
+    pub fn max_bytes(&self) -> usize {
+        (self.max_memory * 1024. * 1024.) as usize
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MemoryCapConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Memory cap").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Max memory (MB): ");
+                ui.add(egui::DragValue::new(&mut self.max_memory).range(1.0..=f32::MAX));
+            });
+            let mut current_str = self.action.to_string();
+            ui.horizontal(|ui| {
+                ui.label("Action: ");
+                string_combobox(
+                    ui,
+                    &MemoryCapAction::to_vec(),
+                    &mut current_str,
+                    format!("memory-cap-action-choice-{}", unique_id),
+                );
+            });
+            if current_str != self.action.to_string() {
+                match current_str.as_str() {
+                    "Flush" => self.action = MemoryCapAction::Flush,
+                    "Prune" => self.action = MemoryCapAction::Prune,
+                    _ => panic!("Where did you find this value?"),
+                };
+            }
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        egui::CollapsingHeader::new("Memory cap").show(ui, |ui| {
+            ui.label(format!("Max memory: {} MB", self.max_memory));
+            ui.label(format!("Action: {}", self.action));
+        });
+    }
+}
+
+/// Approximate memory used by a run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct MemoryUsage {
+    /// Number of records kept in memory.
+    pub retained_records: usize,
+    /// Approximate size of the records kept in memory, in bytes.
+    pub records_bytes: usize,
+    /// Number of records released from memory by the cap since the beginning of the run.
+    pub released_records: usize,
+    /// Number of messages waiting in the broker queues.
+    pub pending_messages: usize,
+    /// Approximate size of the messages waiting in the broker queues, in bytes.
+    pub pending_messages_bytes: usize,
+}
+
+impl MemoryUsage {
+    /// Total approximate memory, in bytes.
+    pub fn total_bytes(&self) -> usize {
+        self.records_bytes + self.pending_messages_bytes
+    }
+}
+
+impl Display for MemoryUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1} MB ({} records: {:.1} MB, {} released; {} pending messages: {:.1} MB)",
+            self.total_bytes() as f32 / 1048576.,
+            self.retained_records,
+            self.records_bytes as f32 / 1048576.,
+            self.released_records,
+            self.pending_messages,
+            self.pending_messages_bytes as f32 / 1048576.,
+        )
+    }
+}
+
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Approximate size of `value` in bytes, as the length of its JSON serialization.
+///
+/// Nothing is allocated for the serialization.
+pub fn estimate_size<T: Serialize>(value: &T) -> usize {
+    let mut counter = ByteCounter(0);
+    // The counter never fails, a serialization error gives a partial (under-estimated) size.
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0 + std::mem::size_of::<T>()
+}
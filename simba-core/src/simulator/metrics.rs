@@ -0,0 +1,286 @@
+/*!
+Localization metrics computed from the records of a run.
+
+For each robot with an internal physics (the ground truth), the ego pose estimated by the state
+estimator and by each bench state estimator (`state_estimator_bench`) is compared with the real
+pose at each record. The [`LocalizationMetrics`] give, per estimator:
+- the position and heading RMSE, and the largest position error,
+- the ANEES (average Normalized Estimation Error Squared) and the ratio of the NEES within the
+  95% bounds of the chi-square distribution (3 degrees of freedom, about 0.95 for a consistent
+  estimator), when the covariance of the pose is available.
+
+The covariance of the main state estimator is only recorded by the `Ekf`. The other estimators
+providing a covariance (see [`StateEstimator::pose_covariance`](crate::state_estimators::StateEstimator::pose_covariance))
+get their NEES when benched.
+
+The metrics are given by [`Simulator::compute_metrics`](super::Simulator::compute_metrics) after
+a run, and written as a JSON summary when the results are computed if
+[`ResultConfig::metrics`](super::ResultConfig::metrics) is set.
+
+```yaml
+results:
+  metrics:
+    metrics_path: metrics.json
+```
+*/
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use nalgebra::{SMatrix, SVector};
+use serde::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    node::node_factory::NodeRecord,
+    physics::PhysicsRecord,
+    simulator::Record,
+    utils::geometry::smallest_theta_diff,
+};
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{UIComponent, utils::path_finder},
+    simulator::SimulatorConfig,
+};
+
+/// Name of the main state estimator in the [`EstimatorMetrics`].
+pub const MAIN_ESTIMATOR_NAME: &str = "state_estimator";
+
+/// Bounds of the 95% interval of the chi-square distribution with 3 degrees of freedom.
+const NEES_BOUNDS: (f32, f32) = (0.2158, 9.3484);
+
+/// Configuration of the localization metrics summary.
+///
+/// Default values:
+/// - `metrics_path`: `"metrics.json"`
+#[config_derives]
+pub struct MetricsConfig {
+    /// Path of the JSON summary (path from config location).
+    pub metrics_path: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            metrics_path: String::from("metrics.json"),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MetricsConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Metrics").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Metrics path:");
+                path_finder(ui, &mut self.metrics_path, &global_config.base_path);
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        egui::CollapsingHeader::new("Metrics").show(ui, |ui| {
+            ui.label(format!("Metrics path: {}", self.metrics_path));
+        });
+    }
+}
+
+/// Accumulator of the errors between the estimated and the real poses of an estimator.
+#[derive(Default)]
+pub(crate) struct PoseErrorAccumulator {
+    pub samples: usize,
+    position_squared_error: f32,
+    heading_squared_error: f32,
+    max_position_error: f32,
+    nees_sum: f32,
+    nees_samples: usize,
+    nees_within_bounds: usize,
+}
+
+impl PoseErrorAccumulator {
+    /// Add the error of the `estimate` pose `[x, y, orientation]` to the `real` one, with the
+    /// covariance of the estimate if available.
+    pub fn add(
+        &mut self,
+        estimate: &[f32; 3],
+        real: &[f32; 3],
+        covariance: Option<&[[f32; 3]; 3]>,
+    ) {
+        let error = SVector::<f32, 3>::new(
+            estimate[0] - real[0],
+            estimate[1] - real[1],
+            smallest_theta_diff(estimate[2], real[2]),
+        );
+        self.samples += 1;
+        let position_squared_error = error[0].powi(2) + error[1].powi(2);
+        self.position_squared_error += position_squared_error;
+        self.heading_squared_error += error[2].powi(2);
+        self.max_position_error = self.max_position_error.max(position_squared_error.sqrt());
+        if let Some(covariance) = covariance
+            && let Some(inv) = SMatrix::<f32, 3, 3>::from_fn(|i, j| covariance[i][j]).try_inverse()
+        {
+            let nees = (error.transpose() * inv * error)[0];
+            self.nees_sum += nees;
+            self.nees_samples += 1;
+            if (NEES_BOUNDS.0..=NEES_BOUNDS.1).contains(&nees) {
+                self.nees_within_bounds += 1;
+            }
+        }
+    }
+
+    /// Root mean square error on the position (m).
+    pub fn position_rmse(&self) -> f32 {
+        (self.position_squared_error / self.samples.max(1) as f32).sqrt()
+    }
+
+    /// Root mean square error on the orientation (rad).
+    pub fn heading_rmse(&self) -> f32 {
+        (self.heading_squared_error / self.samples.max(1) as f32).sqrt()
+    }
+
+    /// Average NEES, `None` without covariance.
+    pub fn anees(&self) -> Option<f32> {
+        (self.nees_samples > 0).then(|| self.nees_sum / self.nees_samples as f32)
+    }
+
+    /// Ratio of the NEES within the 95% chi-square bounds, `None` without covariance.
+    pub fn nees_within_bounds(&self) -> Option<f32> {
+        (self.nees_samples > 0).then(|| self.nees_within_bounds as f32 / self.nees_samples as f32)
+    }
+}
+
+/// Localization metrics of one state estimator.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EstimatorMetrics {
+    /// Name of the node running the estimator.
+    pub node: String,
+    /// Name of the estimator: [`MAIN_ESTIMATOR_NAME`] or the name of the bench estimator.
+    pub estimator: String,
+    /// Number of records where the estimation was compared to the ground truth.
+    pub samples: usize,
+    /// Root mean square error on the position (m).
+    pub position_rmse: f32,
+    /// Root mean square error on the heading (rad).
+    pub heading_rmse: f32,
+    /// Largest position error (m).
+    pub max_position_error: f32,
+    /// Average NEES on the pose, `None` if the covariance is not available.
+    pub anees: Option<f32>,
+    /// Ratio of the NEES within the 95% chi-square bounds, `None` if the covariance is not
+    /// available.
+    pub nees_within_bounds: Option<f32>,
+}
+
+/// Localization metrics of all the state estimators of a run.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct LocalizationMetrics {
+    /// Metrics of each estimator, sorted by node name, with the main state estimator first.
+    pub estimators: Vec<EstimatorMetrics>,
+}
+
+impl LocalizationMetrics {
+    /// Compute the metrics from the records of a run.
+    ///
+    /// Only robots with an internal physics are considered, as the ground truth is not available
+    /// otherwise.
+    pub fn from_records(records: &[Record]) -> Self {
+        // Key: node, is a bench estimator, estimator name
+        let mut accumulators = BTreeMap::<(String, bool, String), PoseErrorAccumulator>::new();
+        for record in records {
+            let NodeRecord::Robot(robot_record) = &record.node else {
+                continue;
+            };
+            let PhysicsRecord::Internal(physics) = &robot_record.physics else {
+                continue;
+            };
+            let real_pose = physics.state.pose;
+            let state_estimator = &robot_record.state_estimator;
+            let estimates = std::iter::once((
+                false,
+                MAIN_ESTIMATOR_NAME,
+                state_estimator.ego(),
+                state_estimator.pose_covariance(),
+            ))
+            .chain(robot_record.state_estimator_bench.iter().map(|bench| {
+                (
+                    true,
+                    bench.name.as_str(),
+                    bench.ego.as_ref(),
+                    bench.pose_covariance,
+                )
+            }));
+            for (is_bench, name, ego, covariance) in estimates {
+                let Some(ego) = ego else {
+                    continue;
+                };
+                accumulators
+                    .entry((robot_record.name.clone(), is_bench, name.to_string()))
+                    .or_default()
+                    .add(&ego.pose, &real_pose, covariance.as_ref());
+            }
+        }
+
+        Self {
+            estimators: accumulators
+                .into_iter()
+                .map(|((node, _, estimator), acc)| EstimatorMetrics {
+                    node,
+                    estimator,
+                    samples: acc.samples,
+                    position_rmse: acc.position_rmse(),
+                    heading_rmse: acc.heading_rmse(),
+                    max_position_error: acc.max_position_error,
+                    anees: acc.anees(),
+                    nees_within_bounds: acc.nees_within_bounds(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Write the metrics to `path`, in JSON.
+    pub fn save(&self, path: &Path) -> SimbaResult<()> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ImplementationError,
+                format!("Error during json serialization of the metrics: {e}"),
+            )
+        })?;
+        fs::write(path, content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to write metrics file '{}': {}",
+                    path.to_str().unwrap_or_default(),
+                    e
+                ),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pose_error_accumulator() {
+        let mut acc = PoseErrorAccumulator::default();
+        let covariance = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+        acc.add(&[3., 4., 0.], &[0., 0., 0.], Some(&covariance));
+        acc.add(&[0., 0., 0.1], &[0., 0., 0.], None);
+        assert_eq!(acc.samples, 2);
+        assert!((acc.position_rmse() - 12.5_f32.sqrt()).abs() < 1e-5);
+        assert!((acc.heading_rmse() - 0.005_f32.sqrt()).abs() < 1e-5);
+        assert_eq!(acc.max_position_error, 5.);
+        assert_eq!(acc.anees(), Some(25.));
+        assert_eq!(acc.nees_within_bounds(), Some(0.));
+    }
+}
@@ -0,0 +1,249 @@
+//! Mission-level success metrics: goals attempted and reached, times and distances.
+//!
+//! The goals are given by the navigators going to goals, see
+//! [`NavigatorRecord::goal_progress`]. A goal is attempted when the navigator starts going to it,
+//! and reached when the navigator counts it as reached. A change of target before it is reached
+//! (e.g. a replan to another frontier) continues the same attempt: a goal fails only when the
+//! navigator drops it (abort) or when the run ends before it is reached (timeout). The distance
+//! travelled by a robot is computed from its ground-truth pose (internal physics), or from its
+//! estimated pose otherwise. The energy is not modeled.
+//!
+//! The [`MissionSummary`] is built from the records during the run, aggregated per node and for
+//! the fleet, and saved with the results: `mission` field of the JSON file, `mission.json` next
+//! to the tables for the CSV and Parquet formats. It is given by
+//! [`Simulator::get_mission_summary`](super::Simulator::get_mission_summary).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{node::node_factory::NodeRecord, physics::PhysicsRecord, simulator::Record};
+
+/// Goal of a node during the run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoalRecord {
+    /// Goal point in world coordinates `[x, y]`, the last target if the navigator replanned.
+    pub point: [f32; 2],
+    /// Time when the goal was given to the navigator.
+    pub start_time: f32,
+    /// Time when the goal was reached, `None` if it was not reached (dropped or end of the run).
+    pub reached_time: Option<f32>,
+}
+
+/// Mission summary of one node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissionRecord {
+    /// Name of the node.
+    pub node: String,
+    /// Number of goals given to the navigator.
+    pub goals_attempted: usize,
+    /// Number of goals reached.
+    pub goals_reached: usize,
+    /// Ratio of the goals reached, `None` without goal.
+    pub success_rate: Option<f32>,
+    /// Mean time between the start of a goal and its reach, for the goals reached (s).
+    pub mean_time_to_goal: Option<f32>,
+    /// Time when the last goal was reached, `None` if no goal was reached.
+    pub completion_time: Option<f32>,
+    /// Distance travelled during the run (m).
+    pub distance: f32,
+    /// Goals of the node, in order.
+    pub goals: Vec<GoalRecord>,
+}
+
+/// Mission summary of the whole fleet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FleetMissionRecord {
+    /// Number of goals given to all the navigators.
+    pub goals_attempted: usize,
+    /// Number of goals reached by all the nodes.
+    pub goals_reached: usize,
+    /// Ratio of the goals reached, `None` without goal.
+    pub success_rate: Option<f32>,
+    /// Mean time to reach a goal over the fleet (s).
+    pub mean_time_to_goal: Option<f32>,
+    /// Time when the last goal of the fleet was reached.
+    pub completion_time: Option<f32>,
+    /// Distance travelled by all the nodes (m).
+    pub distance: f32,
+}
+
+/// Mission summary of a run, per node and for the fleet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MissionSummary {
+    /// Summary of each node with goals or moving, sorted by name.
+    pub nodes: Vec<MissionRecord>,
+    /// Summary of the fleet.
+    pub fleet: FleetMissionRecord,
+}
+
+/// Goals and distance of a node, updated with its records.
+#[derive(Debug, Default)]
+struct NodeMission {
+    goals: Vec<GoalRecord>,
+    /// Goal of the last record.
+    current_goal: Option<[f32; 2]>,
+    /// Whether the last goal of `goals` is the current goal, not reached yet.
+    goal_open: bool,
+    /// Goals reached counted by the navigator at the last record.
+    reached: usize,
+    last_position: Option<[f32; 2]>,
+    distance: f32,
+}
+
+impl NodeMission {
+    fn update(&mut self, time: f32, goal: Option<[f32; 2]>, reached: usize) {
+        if reached > self.reached && self.goal_open {
+            self.goals.last_mut().unwrap().reached_time = Some(time);
+            self.goal_open = false;
+        }
+        // The count restarts with another navigator (composite navigator phases)
+        self.reached = reached;
+        if goal != self.current_goal {
+            self.current_goal = goal;
+            match goal {
+                // New target of the open goal (replan): same attempt
+                Some(point) if self.goal_open => self.goals.last_mut().unwrap().point = point,
+                Some(point) => {
+                    self.goals.push(GoalRecord {
+                        point,
+                        start_time: time,
+                        reached_time: None,
+                    });
+                    self.goal_open = true;
+                }
+                // Dropped before being reached: failed
+                None => self.goal_open = false,
+            }
+        }
+    }
+
+    fn record(&self, node: String) -> MissionRecord {
+        let times: Vec<f32> = self
+            .goals
+            .iter()
+            .filter_map(|goal| goal.reached_time.map(|time| time - goal.start_time))
+            .collect();
+        MissionRecord {
+            node,
+            goals_attempted: self.goals.len(),
+            goals_reached: times.len(),
+            success_rate: (!self.goals.is_empty())
+                .then(|| times.len() as f32 / self.goals.len() as f32),
+            mean_time_to_goal: mean(&times),
+            completion_time: self
+                .goals
+                .iter()
+                .filter_map(|goal| goal.reached_time)
+                .reduce(f32::max),
+            distance: self.distance,
+            goals: self.goals.clone(),
+        }
+    }
+}
+
+fn mean(values: &[f32]) -> Option<f32> {
+    (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+}
+
+/// Tracker of the goals of the nodes, making the [`MissionSummary`].
+#[derive(Debug, Default)]
+pub(super) struct MissionTracker {
+    nodes: BTreeMap<String, NodeMission>,
+}
+
+impl MissionTracker {
+    /// Update the goals and distances with the new `records`, in time order for each node.
+    pub fn add_records(&mut self, records: &[Record]) {
+        for record in records {
+            let NodeRecord::Robot(robot_record) = &record.node else {
+                continue;
+            };
+            let position = match &robot_record.physics {
+                PhysicsRecord::Internal(physics) => Some(&physics.state.pose),
+                _ => robot_record.state_estimator.ego().map(|ego| &ego.pose),
+            }
+            .map(|pose| [pose[0], pose[1]]);
+            let progress = robot_record.navigator.goal_progress();
+            if progress.is_none() && position.is_none() {
+                continue;
+            }
+            let mission = self.nodes.entry(robot_record.name.clone()).or_default();
+            if let Some(progress) = progress {
+                mission.update(record.time, progress.goal, progress.reached);
+            }
+            if let Some(position) = position {
+                if let Some(last) = mission.last_position {
+                    mission.distance += (position[0] - last[0]).hypot(position[1] - last[1]);
+                }
+                mission.last_position = Some(position);
+            }
+        }
+    }
+
+    /// Summary of the records added.
+    pub fn summary(&self) -> MissionSummary {
+        let nodes: Vec<MissionRecord> = self
+            .nodes
+            .iter()
+            .map(|(name, mission)| mission.record(name.clone()))
+            .collect();
+        let times: Vec<f32> = self
+            .nodes
+            .values()
+            .flat_map(|mission| {
+                mission
+                    .goals
+                    .iter()
+                    .filter_map(|goal| goal.reached_time.map(|time| time - goal.start_time))
+            })
+            .collect();
+        let goals_attempted = nodes.iter().map(|node| node.goals_attempted).sum();
+        let goals_reached = nodes.iter().map(|node| node.goals_reached).sum();
+        let fleet = FleetMissionRecord {
+            goals_attempted,
+            goals_reached,
+            success_rate: (goals_attempted > 0)
+                .then(|| goals_reached as f32 / goals_attempted as f32),
+            mean_time_to_goal: mean(&times),
+            completion_time: nodes
+                .iter()
+                .filter_map(|node| node.completion_time)
+                .reduce(f32::max),
+            distance: nodes.iter().map(|node| node.distance).sum(),
+        };
+        MissionSummary { nodes, fleet }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goals_reached_and_replaced() {
+        let mut mission = NodeMission::default();
+        mission.update(0., Some([1., 0.]), 0);
+        mission.update(1., Some([1., 0.]), 0);
+        // Reached, next goal in the same record
+        mission.update(2., Some([2., 0.]), 1);
+        // Target changed by a replan: same attempt
+        mission.update(3., Some([3., 0.]), 1);
+        mission.update(6., Some([3., 0.]), 2);
+        // Dropped before being reached
+        mission.update(7., Some([4., 0.]), 2);
+        mission.update(8., None, 2);
+        // Not reached at the end of the run
+        mission.update(9., Some([5., 0.]), 2);
+        let record = mission.record("robot1".to_string());
+        assert_eq!(record.goals_attempted, 4);
+        assert_eq!(record.goals_reached, 2);
+        assert_eq!(record.success_rate, Some(0.5));
+        assert_eq!(record.mean_time_to_goal, Some(3.));
+        assert_eq!(record.completion_time, Some(6.));
+        assert_eq!(record.goals[1].point, [3., 0.]);
+        assert_eq!(record.goals[1].reached_time, Some(6.));
+        assert_eq!(record.goals[2].reached_time, None);
+        assert_eq!(record.goals[3].reached_time, None);
+    }
+}
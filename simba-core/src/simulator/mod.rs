@@ -31,31 +31,102 @@ simulator.compute_results().unwrap();
 
 mod results;
 use results::ResultSavingData;
-pub use results::{ResultConfig, ResultSaveMode, Results};
+pub use results::{ResultConfig, ResultFormat, ResultSaveMode, Results, config_path};
 
 mod simulator_config;
 pub use simulator_config::SimulatorConfig;
 
 mod async_simulator;
 use async_simulator::SimulatorAsyncApiServer;
-pub use async_simulator::{AsyncSimulator, SimulatorAsyncApi};
+pub use async_simulator::{
+    AsyncApiConnector, AsyncSimulator, ConfigPatchHandle, RunControl, SimulatorAsyncApi,
+};
+
+mod determinism_check;
+pub use determinism_check::{RecordDivergence, compare_records};
+
+mod snapshot_diff;
+pub use snapshot_diff::{FieldDifference, Snapshot, SnapshotDiff, diff_snapshots};
+
+mod annotations;
+pub use annotations::{Annotation, AnnotationLog};
+
+mod fleet_status;
+pub use fleet_status::{FleetStatus, NodeHeartbeat};
+
+mod idleness;
+use idleness::IdlenessTracker;
+pub use idleness::{FleetRecord, IdlenessConfig};
+
+mod mission;
+use mission::MissionTracker;
+pub use mission::{FleetMissionRecord, GoalRecord, MissionRecord, MissionSummary};
+
+mod robot_templates;
+pub use robot_templates::{
+    CirclePlacementConfig, GridPlacementConfig, RobotPlacementConfig, RobotTemplateConfig,
+    TEMPLATE_INDEX_PLACEHOLDER,
+};
+
+mod metrics;
+pub(crate) use metrics::PoseErrorAccumulator;
+pub use metrics::{EstimatorMetrics, LocalizationMetrics, MAIN_ESTIMATOR_NAME, MetricsConfig};
+
+mod world_snapshots;
+pub use world_snapshots::{
+    SNAPSHOTS_FORMAT_VERSION, SnapshotDetection, SnapshotDetectionKind, SnapshotLandmark,
+    SnapshotRobot, SnapshotRobotModel, WorldSnapshot, WorldSnapshots, WorldSnapshotsConfig,
+};
+
+mod result_tables;
+use result_tables::{ResultTableWriter, result_table_directory};
+
+mod result_reader;
+pub use result_reader::{ResultIndex, ResultIndexEntry, stream_results};
+
+mod batch;
+pub use batch::{
+    BATCH_RESULTS_FORMAT, BatchConfig, BatchResults, BatchRun, BatchRunResult, BatchRunSummary,
+    BatchRunner, SweepMode, SweepParameterConfig, replication_seed, set_config_field,
+};
+
+mod statistics;
+pub use statistics::{AggregateStatistics, DEFAULT_STATISTICS_PERIOD, NodeSnapshot};
+
+mod aggregate;
+pub use aggregate::{
+    AggregateResults, CONFIDENCE_LEVEL, KpiStatistics, OUTLIER_THRESHOLD, Outlier, RunKpis,
+    aggregate_results_directory, load_run_kpis,
+};
+
+mod hooks;
+pub use hooks::SimulatorHooks;
+
+mod memory;
+use memory::{APPROXIMATE_MESSAGE_SIZE, PRUNE_TARGET_RATIO, estimate_size};
+pub use memory::{MemoryCapAction, MemoryCapConfig, MemoryUsage};
+
+#[cfg(feature = "stream")]
+mod record_stream;
+#[cfg(feature = "stream")]
+pub use record_stream::RecordStreamServer;
 
 extern crate confy;
-use config_checker::ConfigCheckable;
 use pyo3::{ffi::c_str, prelude::*};
 use serde_derive::{Deserialize, Serialize};
 
-use simba_com::pub_sub::{PathBroker, PathMultiClient};
+use simba_com::pub_sub::{BrokerTraitProcessing, PathBroker, PathMultiClient};
 
 use crate::{
-    VERSION,
     api::internal_api::NodeClient,
     constants::TIME_ROUND,
     environment::Environment,
-    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult, catch_panic},
     logger::{LoggerConfig, init_log, is_enabled},
     networking::{
-        network::Envelope, network_manager::NetworkManager, service_manager::ServiceManager,
+        network::{Envelope, LinkEndpoint},
+        network_manager::NetworkManager,
+        service_manager::ServiceManager,
     },
     node::{
         Node, NodeState,
@@ -66,10 +137,16 @@ use crate::{
     plugin_api::PluginAPI,
     recordable::Recordable,
     scenario::{Scenario, config::ScenarioConfig},
+    sensors::observation_types::register_observation_type,
+    state_estimators::bench_report::BenchReport,
     time_analysis::{TimeAnalysisConfig, TimeAnalysisFactory},
     utils::{
-        SharedMutex, SharedRwLock, barrier::Barrier,
-        determinist_random_variable::DeterministRandomVariableFactory, maths::round_precision,
+        SharedMutex, SharedRwLock,
+        barrier::Barrier,
+        config_parsing::ConfigLoadMode,
+        determinist_random_variable::DeterministRandomVariableFactory,
+        lock_recovery::{RecoverMutex, RecoverRoLock, RecoverRwLock},
+        maths::round_precision,
         python::CONVERT_TO_DICT,
     },
 };
@@ -78,9 +155,9 @@ use std::{
     cmp::Ordering,
     collections::HashMap,
     fs::OpenOptions,
-    io::SeekFrom,
     path::{Path, PathBuf},
     thread::JoinHandle,
+    time::{Duration, Instant},
 };
 use std::{collections::BTreeMap, ffi::CString};
 
@@ -89,7 +166,7 @@ use serde_json;
 use std::default::Default;
 use std::fs::{self, File};
 use std::io::prelude::*;
-use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock};
 use std::thread::{self, ThreadId};
 
 use log::{debug, info, warn};
@@ -193,7 +270,7 @@ struct NodeSyncParams {
 }
 
 /// Broker type used by the simulator network.
-pub type SimbaBroker = PathBroker<Envelope, String, Option<[f32; 2]>>;
+pub type SimbaBroker = PathBroker<Envelope, String, LinkEndpoint>;
 /// Multi-client handle type associated with [`SimbaBroker`].
 pub type SimbaBrokerMultiClient = PathMultiClient<Envelope, String>;
 
@@ -255,12 +332,47 @@ pub struct Simulator {
 
     result_saving_data: Option<ResultSavingData>,
     records: Vec<Record>,
+    /// Approximate size of `records`, in bytes.
+    records_bytes: usize,
+    /// Number of records released from memory after being written in the result file.
+    flushed_records: usize,
+    /// Number of records dropped from memory by the memory cap.
+    pruned_records: usize,
+    /// Idleness of the patrolled waypoints, if enabled in the configuration.
+    idleness: Option<IdlenessTracker>,
+    /// Fleet-level records, one per time step.
+    fleet_records: Vec<FleetRecord>,
+    /// Goals and distances of the nodes, from the processed records.
+    mission: MissionTracker,
+    /// Writer of the results for the table formats (CSV, Parquet).
+    result_table_writer: Option<ResultTableWriter>,
+    /// Minimal wall-clock duration between two statistics sent to the async API.
+    statistics_period: Duration,
+    /// Wall-clock instant of the last statistics sent.
+    last_statistics: Option<Instant>,
+    /// Wall-clock instant and simulated time of the first statistics of the run.
+    statistics_start: Option<(Instant, f32)>,
+    /// Wall-clock instant and simulated time of the first time step of the run, for the realtime
+    /// pacing.
+    realtime_start: Option<(Instant, f32)>,
     time_analysis_factory: Option<TimeAnalysisFactory>,
     force_send_results: bool,
     scenario: SharedMutex<Scenario>,
     plugin_api: Option<Arc<dyn PluginAPI>>,
     service_managers: BTreeMap<String, SharedRwLock<ServiceManager>>,
     environment: Arc<Environment>,
+    /// Annotations of the run, shared with the nodes and the async API.
+    annotations: AnnotationLog,
+    /// Heartbeats of the nodes, shared with the node threads and the async API.
+    fleet_status: FleetStatus,
+    /// Callbacks of the embedding application.
+    hooks: Vec<Arc<dyn SimulatorHooks>>,
+    /// Strictness of the configuration loading.
+    config_load_mode: ConfigLoadMode,
+    /// Overlay files merged on top of the configuration files loaded.
+    config_overrides: Vec<PathBuf>,
+    /// Issues of the last loaded configuration ignored in permissive mode.
+    config_warnings: Vec<String>,
 }
 
 impl Simulator {
@@ -283,6 +395,17 @@ impl Simulator {
             node_apis: BTreeMap::new(),
             result_saving_data: Some(ResultSavingData::default()),
             records: Vec::new(),
+            records_bytes: 0,
+            flushed_records: 0,
+            pruned_records: 0,
+            idleness: None,
+            fleet_records: Vec::new(),
+            mission: MissionTracker::default(),
+            result_table_writer: None,
+            statistics_period: DEFAULT_STATISTICS_PERIOD,
+            last_statistics: None,
+            statistics_start: None,
+            realtime_start: None,
             time_analysis_factory: Some(
                 TimeAnalysisFactory::init_from_config(&TimeAnalysisConfig::default()).unwrap(),
             ),
@@ -296,9 +419,22 @@ impl Simulator {
             plugin_api: None,
             service_managers: BTreeMap::new(),
             environment: Arc::new(Environment::default()),
+            annotations: AnnotationLog::new(),
+            fleet_status: FleetStatus::new(),
+            hooks: Vec::new(),
+            config_load_mode: ConfigLoadMode::Strict,
+            config_overrides: Vec::new(),
+            config_warnings: Vec::new(),
         }
     }
 
+    /// Create a new [`Simulator`] with the given `hooks` registered, see [`Simulator::add_hooks`].
+    pub fn with_hooks(hooks: Arc<dyn SimulatorHooks>) -> Simulator {
+        let mut simulator = Simulator::new();
+        simulator.add_hooks(hooks);
+        simulator
+    }
+
     /// Load the config from a file compatible with [`confy`], see [`SimulatorConfig`]. Initialize the [`Simulator`].
     ///
     /// ## Arguments
@@ -316,6 +452,55 @@ impl Simulator {
         Ok(sim)
     }
 
+    /// Same as [`Simulator::from_config_path`], loading the configuration in the given `mode`,
+    /// see [`Simulator::set_config_load_mode`].
+    pub fn from_config_path_with_mode(
+        config_path: &Path,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+        mode: ConfigLoadMode,
+    ) -> SimbaResult<Simulator> {
+        let mut sim = Simulator::new();
+        sim.set_config_load_mode(mode);
+        sim.load_config_path(config_path, plugin_api)?;
+        Ok(sim)
+    }
+
+    /// Same as [`Simulator::from_config_path_with_mode`], with the `overrides` files deep-merged
+    /// on top of the configuration, see [`Simulator::set_config_overrides`].
+    pub fn from_config_path_with_overrides(
+        config_path: &Path,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+        mode: ConfigLoadMode,
+        overrides: Vec<PathBuf>,
+    ) -> SimbaResult<Simulator> {
+        let mut sim = Simulator::new();
+        sim.set_config_load_mode(mode);
+        sim.set_config_overrides(overrides);
+        sim.load_config_path(config_path, plugin_api)?;
+        Ok(sim)
+    }
+
+    /// Set how strictly the next configurations are loaded ([`ConfigLoadMode::Strict`] by
+    /// default).
+    ///
+    /// In [`ConfigLoadMode::Permissive`] mode, the unknown fields of the configuration file and
+    /// an unreadable configuration version are accepted, with a warning logged for each, see
+    /// [`Simulator::config_warnings`].
+    pub fn set_config_load_mode(&mut self, mode: ConfigLoadMode) {
+        self.config_load_mode = mode;
+    }
+
+    /// Set the overlay files deep-merged, in order, on top of the next configuration files
+    /// loaded, see [`SimulatorConfig::load_from_path_with_overrides`].
+    pub fn set_config_overrides(&mut self, overrides: Vec<PathBuf>) {
+        self.config_overrides = overrides;
+    }
+
+    /// Issues of the last loaded configuration ignored in permissive mode, with their location.
+    pub fn config_warnings(&self) -> &[String] {
+        &self.config_warnings
+    }
+
     /// Load the config from structure instance.
     ///
     /// ## Arguments
@@ -337,7 +522,20 @@ impl Simulator {
     pub fn reset(&mut self, plugin_api: Option<Arc<dyn PluginAPI>>) -> SimbaResult<()> {
         info!("Reset node");
         self.network_manager.reset();
+        self.network_manager
+            .set_message_retention(&self.config.message_retention);
+        self.network_manager
+            .set_channel_limits(&self.config.channel_limits);
         self.environment.clear_meta_data();
+        self.annotations.clear();
+        self.fleet_status.clear();
+        self.idleness = self
+            .config
+            .idleness
+            .as_ref()
+            .map(|idleness| IdlenessTracker::new(idleness, &self.config.robots, 0.));
+        self.fleet_records.clear();
+        self.mission = MissionTracker::default();
         self.nodes = Vec::new();
         self.time_cv = Arc::new(TimeCv::new());
         let config = self.config.clone();
@@ -356,10 +554,20 @@ impl Simulator {
             save_mode: cfg.save_mode.clone(),
             ..Default::default()
         });
+        self.result_table_writer = None;
 
         self.plugin_api = plugin_api.clone();
+        if let Some(plugin_api) = &plugin_api {
+            for schema in plugin_api.observation_types() {
+                register_observation_type(schema)?;
+            }
+        }
 
-        self.environment = Arc::new(Environment::from_config(&config.environment, &config)?);
+        self.environment = Arc::new(Environment::from_config(
+            &config.environment,
+            &config,
+            &self.determinist_va_factory,
+        )?);
 
         self.service_managers = BTreeMap::new();
         // Create robots
@@ -419,8 +627,12 @@ impl Simulator {
         force_send_results: bool,
     ) -> SimbaResult<()> {
         println!("Load configuration from {:?}", config_path);
-        let config = SimulatorConfig::load_from_path(config_path)?;
-        self.load_config_full(&config, plugin_api, force_send_results)
+        let (config, warnings) = SimulatorConfig::load_from_path_with_overrides(
+            config_path,
+            &self.config_overrides,
+            self.config_load_mode,
+        )?;
+        self.load_config_with_warnings(&config, plugin_api, force_send_results, warnings)
     }
 
     /// Load a simulator configuration into an existing simulator instance.
@@ -438,9 +650,21 @@ impl Simulator {
         plugin_api: Option<Arc<dyn PluginAPI>>,
         force_send_results: bool,
     ) -> SimbaResult<()> {
+        self.load_config_with_warnings(config, plugin_api, force_send_results, Vec::new())
+    }
+
+    /// Load the `config`, with the issues already ignored while reading its file.
+    fn load_config_with_warnings(
+        &mut self,
+        config: &SimulatorConfig,
+        plugin_api: Option<Arc<dyn PluginAPI>>,
+        force_send_results: bool,
+        warnings: Vec<String>,
+    ) -> SimbaResult<()> {
+        self.config_warnings = warnings;
         println!("Checking configuration...");
         Self::init_log(&config.log)?;
-        match config.check() {
+        match config.check_located() {
             Ok(_) => println!("Config valid"),
             Err(e) => {
                 let e = SimbaError::new(
@@ -451,26 +675,24 @@ impl Simulator {
                 return Err(e);
             }
         };
-        let config_version: Vec<usize> = config
-            .version
-            .split(".")
-            .map(|s| s.parse().expect("Config version pattern not recognized"))
-            .collect();
-        if config_version.len() < 2 {
-            return Err(SimbaError::new(
-                SimbaErrorTypes::ConfigError,
-                "Version is expected to be XX.YY at least".to_string(),
-            ));
+        match config.check_version() {
+            Ok(Some(warning)) => warn!("{warning}"),
+            Ok(None) => {}
+            Err(e) if self.config_load_mode == ConfigLoadMode::Permissive => {
+                self.config_warnings.push(e)
+            }
+            Err(e) => {
+                let e = SimbaError::new(SimbaErrorTypes::ConfigError, e);
+                log::error!("{}", e.detailed_error());
+                return Err(e);
+            }
         }
-        if config_version[0] != env!("CARGO_PKG_VERSION_MAJOR").parse::<usize>().unwrap()
-            || config_version[1] != env!("CARGO_PKG_VERSION_MINOR").parse::<usize>().unwrap()
-        {
-            warn!(
-                "Config major version ({}) differs from software version ({})",
-                config.version, VERSION
-            );
+        for warning in &self.config_warnings {
+            warn!("Ignored configuration issue: {warning}");
         }
         self.config = config.clone();
+        // Configurations built without `load_from_path` can still have templates
+        self.config.expand_robot_templates()?;
         if let Some(seed) = config.random_seed {
             self.determinist_va_factory.set_global_seed(seed);
         } else {
@@ -486,6 +708,14 @@ impl Simulator {
         self.config.clone()
     }
 
+    /// Return the random draws recorded since the configuration was loaded.
+    ///
+    /// See [`seed_audit`](crate::utils::seed_audit).
+    #[cfg(feature = "seed_audit")]
+    pub fn seed_audit(&self) -> crate::utils::seed_audit::SeedAudit {
+        self.determinist_va_factory.seed_audit()
+    }
+
     /// Initialize the simulator environment.
     /// - initialize Python interpreter, to be able to run Python scripts in the simulator (for results analysis, or for Python nodes).
     pub fn init_environment() {
@@ -594,10 +824,12 @@ impl Simulator {
                 broker: &self.network_manager.broker(),
                 initial_time,
                 environment: self.environment.clone(),
+                annotations: self.annotations.clone(),
             },
-        )?;
+        )
+        .map_err(|e| e.with_node(&robot_config.name).with_time(initial_time))?;
         let meta_data = new_node.meta_data();
-        let name = meta_data.read().unwrap().name.clone();
+        let name = meta_data.read_recover().name.clone();
         self.environment.insert_meta_data(name, meta_data);
         if new_node.state() != NodeState::Running {
             return Ok(());
@@ -636,10 +868,15 @@ impl Simulator {
                 initial_time,
                 broker: &self.network_manager.broker(),
                 environment: self.environment.clone(),
+                annotations: self.annotations.clone(),
             },
-        )?;
+        )
+        .map_err(|e| {
+            e.with_node(&computation_unit_config.name)
+                .with_time(initial_time)
+        })?;
         let meta_data = new_node.meta_data();
-        let name = meta_data.read().unwrap().name.clone();
+        let name = meta_data.read_recover().name.clone();
         self.environment.insert_meta_data(name, meta_data);
         if new_node.state() != NodeState::Running {
             return Ok(());
@@ -688,6 +925,9 @@ impl Simulator {
     ///
     /// This function starts one thread by [`Node`]. It waits that the thread finishes.
     ///
+    /// A node stopping on an error or a panic is marked as [`NodeState::Failed`] and the other
+    /// nodes run until the end. The error of the node is returned once the run is finished.
+    ///
     /// After the scenario is done, the results are not processed. Use [`Simulator::compute_results`] to process the results and compute the analysis.
     pub fn run(&mut self) -> SimbaResult<()> {
         let mut running_parameters = RunningParameters {
@@ -706,6 +946,9 @@ impl Simulator {
                 _ => self.prepare_save_results()?,
             }
         }
+        self.last_statistics = None;
+        self.statistics_start = None;
+        self.realtime_start = None;
         while let Some(node) = self.nodes.pop() {
             self.spawn_node(node, &mut running_parameters)?;
         }
@@ -716,7 +959,7 @@ impl Simulator {
         if let Err(e) = self.simulator_spin(&mut running_parameters) {
             log::error!("Error in simulator spin: {}", e.detailed_error());
             error = Some(e);
-            *self.time_cv.force_finish.lock().unwrap() = true;
+            *self.time_cv.force_finish.lock_recover() = true;
         }
 
         for handle in running_parameters.handles.drain(0..) {
@@ -729,15 +972,38 @@ impl Simulator {
                 }
             };
         }
+        if let Some(server) = &self.async_api_server {
+            server.run_control().abandon_config_patches();
+        }
+        self.fleet_status
+            .refresh_states(&self.environment.get_meta_data().read_recover());
+
+        let end_time = *self.common_time.read().unwrap();
+        if end_time.is_finite() {
+            self.publish_statistics(end_time, true);
+        }
 
         if let Some(e) = error {
-            self.process_records(None).map_err(|e2| {
+            self.process_records(None, false).map_err(|e2| {
                 SimbaError::new(e2.error_type(), format!("Error while processing previous error.\nPrevious error: {}\nLast error: {}", e.detailed_error(), e2.detailed_error()))
             })?;
             return Err(e);
         }
 
-        self.process_records(None)
+        self.process_records(None, false)?;
+        if !self.hooks.is_empty() {
+            let results = Results {
+                config: self.config.clone(),
+                records: self.get_records(true),
+                annotations: self.annotations.list(),
+                fleet_records: self.fleet_records.clone(),
+                mission: self.mission.summary(),
+            };
+            for hooks in &self.hooks {
+                hooks.on_finish(&results);
+            }
+        }
+        Ok(())
     }
 
     pub(crate) fn spawn_node_from_name(
@@ -760,10 +1026,12 @@ impl Simulator {
                 initial_time: time,
                 broker: &self.network_manager.broker(),
                 environment: self.environment.clone(),
+                annotations: self.annotations.clone(),
             },
-        )?;
+        )
+        .map_err(|e| e.with_node(new_node_name).with_time(time))?;
         let meta_data = node.meta_data();
-        let name = meta_data.read().unwrap().name.clone();
+        let name = meta_data.read_recover().name.clone();
         self.environment.insert_meta_data(name.clone(), meta_data);
 
         node.set_state(NodeState::Running);
@@ -805,6 +1073,7 @@ impl Simulator {
         let max_time = running_parameters.max_time;
         let time_cv = self.time_cv.clone();
         let async_api_server = self.async_api_server.clone();
+        let fleet_status = self.fleet_status.clone();
         let common_time_clone = self.common_time.clone();
         let finishing_cv_clone = running_parameters.finishing_cv.clone();
         let barrier_clone = running_parameters.barrier.clone();
@@ -816,22 +1085,49 @@ impl Simulator {
             .end_time_step_syncs
             .push(end_time_step_sync.clone());
         running_parameters.running_nodes_names.push(node.name());
-        let handle = thread::spawn(move || -> SimbaResult<Option<Node>> {
-            let ret = Self::run_one_node(
-                node,
-                max_time,
-                async_api_server,
-                NodeSyncParams {
-                    nb_nodes,
-                    time_cv: time_cv.clone(),
-                    common_time: common_time_clone,
-                    barrier: barrier_clone,
-                    end_time_step_sync,
-                },
-            );
-            let _lk = time_cv.waiting.lock().unwrap();
+        let node_name = node.name();
+        let node_service_manager = node.service_manager();
+        let node_meta_data = node.shared_meta_data();
+        let thread_config = node.thread_config().clone();
+        // Thread named after the node, to identify it in debuggers and profilers
+        let thread_name = node_name.clone();
+        let thread_builder = thread::Builder::new().name(thread_name.clone());
+        let handle = thread_builder.spawn(move || -> SimbaResult<Option<Node>> {
+            thread_config.apply_to_current_thread(&node_name);
+            let node_sync_params = NodeSyncParams {
+                nb_nodes: nb_nodes.clone(),
+                time_cv: time_cv.clone(),
+                common_time: common_time_clone,
+                barrier: barrier_clone.clone(),
+                end_time_step_sync,
+            };
+            // Panics (also from plugins and Python modules) are converted to errors,
+            // so that the other nodes can continue without this one.
+            let ret = catch_panic(|| {
+                Self::run_one_node(
+                    node,
+                    max_time,
+                    async_api_server,
+                    fleet_status,
+                    node_sync_params,
+                )
+            })
+            .map_err(|e| e.with_node(&node_name));
+            if let Err(e) = &ret {
+                log::error!("Node {node_name} stopped: {}", e.detailed_error());
+            }
+            let _lk = time_cv.waiting.lock_recover();
             match &ret {
-                Err(_) => *time_cv.force_finish.lock().unwrap() = true,
+                Err(_) => {
+                    // Leave the synchronization and close the services, as in `Node::kill`,
+                    // so that the other nodes continue without waiting for this one
+                    *nb_nodes.write_recover() -= 1;
+                    barrier_clone.remove_one();
+                    node_service_manager.read_recover().unsubscribe_node();
+                    // The lock can be poisoned by a panicking module: recover it so that the other
+                    // nodes can still read the meta-data of this node.
+                    node_meta_data.write_recover().state = NodeState::Failed;
+                }
                 Ok(Some(node)) => {
                     // Increase finishing nodes only if the node is still existing
                     // as in case of zombie, the total number of node has been decreased.
@@ -851,11 +1147,19 @@ impl Simulator {
 
             ret
         });
+        let handle = handle.map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ImplementationError,
+                format!("Cannot spawn the thread of node '{thread_name}': {e}"),
+            )
+        })?;
         running_parameters.handles.push(handle);
         Ok(())
     }
 
     /// Returns the list of all [`Record`]s produced by [`Simulator::run`].
+    ///
+    /// Records released by the memory cap ([`MemoryCapConfig`]) are not included.
     pub fn get_records(&self, sorted: bool) -> Vec<Record> {
         let mut records = self.records.clone();
         if sorted {
@@ -864,62 +1168,98 @@ impl Simulator {
         records
     }
 
+    /// Returns the [`FleetRecord`]s produced by [`Simulator::run`], one per time step, if the
+    /// idleness is enabled in the configuration ([`IdlenessConfig`]).
+    pub fn get_fleet_records(&self) -> Vec<FleetRecord> {
+        self.fleet_records.clone()
+    }
+
+    /// Returns the [`MissionSummary`] of the records processed: goals attempted and reached,
+    /// times and distances, per node and for the fleet.
+    pub fn get_mission_summary(&self) -> MissionSummary {
+        self.mission.summary()
+    }
+
     fn prepare_save_results(&mut self) -> SimbaResult<()> {
         if self.config.results.is_none() {
             return Ok(());
         }
         let result_config = self.config.results.clone().unwrap();
-        let filename = result_config.result_path;
-        if filename.is_none() {
+        let Some(filename) = result_config.result_file(&self.config.base_path) else {
+            return Ok(());
+        };
+
+        if result_config.format != ResultFormat::Json {
+            if self.result_table_writer.is_none() {
+                info!(
+                    "Saving results to {}",
+                    result_table_directory(&filename).display()
+                );
+                self.result_table_writer = Some(ResultTableWriter::new(
+                    &filename,
+                    result_config.format,
+                    &self.config,
+                )?);
+            }
             return Ok(());
         }
-        let filename = self.config.base_path.as_ref().join(filename.unwrap());
 
-        if !self.records.is_empty() {
-            // Results already started, just need to remove last line
-            let mut file = match OpenOptions::new().read(true).write(true).open(&filename) {
+        if !self.records.is_empty() || self.flushed_records > 0 {
+            // Results already started: remove the trailer written when the last run was finalized,
+            // to continue the records array
+            let Some(records_end) = self
+                .result_saving_data
+                .as_mut()
+                .and_then(|data| data.records_end.take())
+            else {
+                return Ok(());
+            };
+            let file = match OpenOptions::new().write(true).open(&filename) {
                 Err(e) => {
                     return Err(SimbaError::new(
                         SimbaErrorTypes::ConfigError,
                         format!(
                             "Impossible to open result file '{}': {}",
-                            filename.to_str().unwrap(),
+                            filename.display(),
                             e
                         ),
                     ));
                 }
                 Ok(f) => f,
             };
-            let file_size = file.metadata().unwrap().len();
-
-            let mut buffer = [0u8; 1];
-            let mut pos = file_size.saturating_sub(1);
-
-            while pos > 0 {
-                file.seek(SeekFrom::Start(pos)).unwrap();
-                file.read_exact(&mut buffer).unwrap();
-
-                // Remove last newline
-                // WARNING: assumes that the file does not end with newline, as generated by process_records
-                if buffer[0] == b'\n' {
-                    file.set_len(pos).unwrap();
-                    return Ok(());
-                }
-                pos -= 1;
+            if let Err(e) = file.set_len(records_end) {
+                return Err(SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    format!(
+                        "Impossible to truncate result file '{}': {}",
+                        filename.display(),
+                        e
+                    ),
+                ));
             }
-            unreachable!("Result file should not be empty if records exist");
+            return Ok(());
         }
-        info!(
-            "Saving results to {}",
-            filename.to_str().unwrap_or_default()
-        );
-        let mut recording_file = match File::create(filename.clone()) {
+        info!("Saving results to {}", filename.display());
+        if let Some(directory) = filename.parent()
+            && !directory.as_os_str().is_empty()
+            && let Err(e) = fs::create_dir_all(directory)
+        {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to create result directory '{}': {}",
+                    directory.display(),
+                    e
+                ),
+            ));
+        }
+        let mut recording_file = match File::create(&filename) {
             Err(e) => {
                 return Err(SimbaError::new(
                     SimbaErrorTypes::ConfigError,
                     format!(
                         "Impossible to create result file '{}': {}",
-                        filename.to_str().unwrap(),
+                        filename.display(),
                         e
                     ),
                 ));
@@ -941,7 +1281,9 @@ impl Simulator {
     /// Save the results to the file given during the configuration.
     ///
     /// If the configuration of the [`Simulator`] do not contain a result path, no results are saved.
-    fn process_records(&mut self, time: Option<f32>) -> SimbaResult<()> {
+    ///
+    /// With `force`, the records are saved even if the save mode would wait (batch size or period not reached).
+    fn process_records(&mut self, time: Option<f32>, force: bool) -> SimbaResult<()> {
         if self.config.results.is_none() {
             return Ok(());
         }
@@ -955,7 +1297,7 @@ impl Simulator {
         let result_saving_data = self.result_saving_data.as_mut().unwrap();
         match &mut result_saving_data.save_mode {
             ResultSaveMode::Batch(remaining_size) => {
-                if time.is_some() {
+                if time.is_some() && !force {
                     if *remaining_size <= 1 {
                         *remaining_size = match self.config.results.as_ref().unwrap().save_mode {
                             ResultSaveMode::Batch(s) => s,
@@ -974,7 +1316,9 @@ impl Simulator {
                 // If no time is given, force save (for end)
             }
             ResultSaveMode::Periodic(next_save) => {
-                if let Some(time) = time {
+                if let Some(time) = time
+                    && !force
+                {
                     if *next_save <= time {
                         *next_save = match self.config.results.as_ref().unwrap().save_mode {
                             ResultSaveMode::Periodic(t) => {
@@ -1014,22 +1358,27 @@ impl Simulator {
             }
         }
 
+        self.mission.add_records(&new_records);
         let result_config = self.config.results.clone().unwrap();
-        let filename = result_config.result_path;
-        if let Some(filename) = filename {
-            let filename = self.config.base_path.as_ref().join(filename);
-
-            info!(
-                "Saving results to {}",
-                filename.to_str().unwrap_or_default()
-            );
-            let mut recording_file = match File::options().append(true).open(filename.clone()) {
+        let filename = result_config.result_file(&self.config.base_path);
+        if let Some(writer) = &mut self.result_table_writer {
+            writer.write_records(&new_records)?;
+            if time.is_none() {
+                writer.finish(
+                    &self.annotations.list(),
+                    &self.fleet_records,
+                    &self.mission.summary(),
+                )?;
+            }
+        } else if let Some(filename) = filename {
+            info!("Saving results to {}", filename.display());
+            let mut recording_file = match File::options().append(true).open(&filename) {
                 Err(e) => {
                     return Err(SimbaError::new(
                         SimbaErrorTypes::ConfigError,
                         format!(
                             "Impossible to open result file '{}': {}",
-                            filename.to_str().unwrap(),
+                            filename.display(),
                             e
                         ),
                     ));
@@ -1055,13 +1404,209 @@ impl Simulator {
             }
             if time.is_none() {
                 // Only at the end. If crashes in between, the user need to close the json array+object manually
-                recording_file.write_all(b"\n]}").unwrap();
+                result_saving_data.records_end = Some(
+                    recording_file
+                        .metadata()
+                        .map_err(|e| {
+                            SimbaError::new(
+                                SimbaErrorTypes::ImplementationError,
+                                format!(
+                                    "Impossible to read the size of result file '{}': {}",
+                                    filename.display(),
+                                    e
+                                ),
+                            )
+                        })?
+                        .len(),
+                );
+                recording_file
+                    .write_all(b"\n],\n\"annotations\": ")
+                    .unwrap();
+                if let Err(e) = serde_json::to_writer(&recording_file, &self.annotations.list()) {
+                    return Err(SimbaError::new(
+                        SimbaErrorTypes::ImplementationError,
+                        format!("Error during json serialization of annotations: {e}"),
+                    ));
+                }
+                recording_file.write_all(b",\n\"fleet_records\": ").unwrap();
+                if let Err(e) = serde_json::to_writer(&recording_file, &self.fleet_records) {
+                    return Err(SimbaError::new(
+                        SimbaErrorTypes::ImplementationError,
+                        format!("Error during json serialization of fleet records: {e}"),
+                    ));
+                }
+                recording_file.write_all(b",\n\"mission\": ").unwrap();
+                if let Err(e) = serde_json::to_writer(&recording_file, &self.mission.summary()) {
+                    return Err(SimbaError::new(
+                        SimbaErrorTypes::ImplementationError,
+                        format!("Error during json serialization of mission summary: {e}"),
+                    ));
+                }
+                recording_file.write_all(b"}").unwrap();
+            }
+        }
+        for hooks in &self.hooks {
+            for record in &new_records {
+                hooks.on_record(record);
             }
         }
+        self.records_bytes += new_records.iter().map(estimate_size).sum::<usize>();
         self.records.extend(new_records);
         Ok(())
     }
 
+    /// Add a user annotation at the given simulated `time`, saved with the results.
+    pub fn annotate(&self, time: f32, text: &str) {
+        self.annotations.add(time, None, text.to_string());
+    }
+
+    /// Annotations of the run (added by the nodes and the user), sorted by time.
+    pub fn annotations(&self) -> Vec<Annotation> {
+        self.annotations.list()
+    }
+
+    /// Heartbeats of the nodes of the run.
+    pub fn fleet_status(&self) -> FleetStatus {
+        self.fleet_status.clone()
+    }
+
+    /// Approximate memory used by the run: records kept in memory and messages waiting in the broker.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let pending_messages = self
+            .network_manager
+            .broker()
+            .read_recover()
+            .pending_messages();
+        MemoryUsage {
+            retained_records: self.records.len(),
+            records_bytes: self.records_bytes,
+            released_records: self.flushed_records + self.pruned_records,
+            pending_messages,
+            pending_messages_bytes: pending_messages * APPROXIMATE_MESSAGE_SIZE,
+        }
+    }
+
+    /// Wait until the wall-clock time of `time`, with the `realtime_factor` of the configuration.
+    fn pace_realtime(&mut self, time: f32) {
+        let Some(factor) = self.config.realtime_factor else {
+            return;
+        };
+        if !time.is_finite() {
+            return;
+        }
+        let (start_instant, start_time) =
+            *self.realtime_start.get_or_insert((Instant::now(), time));
+        let target =
+            start_instant + Duration::from_secs_f32(((time - start_time) / factor).max(0.));
+        let now = Instant::now();
+        if target > now {
+            thread::sleep(target - now);
+        }
+    }
+
+    /// Set the minimal wall-clock duration between two [`AggregateStatistics`] sent to the async
+    /// API clients ([`DEFAULT_STATISTICS_PERIOD`] by default).
+    pub fn set_statistics_period(&mut self, period: Duration) {
+        self.statistics_period = period;
+    }
+
+    /// Register callbacks called during the next runs, see [`SimulatorHooks`]. The hooks are kept
+    /// when the configuration is reloaded.
+    pub fn add_hooks(&mut self, hooks: Arc<dyn SimulatorHooks>) {
+        self.hooks.push(hooks);
+    }
+
+    pub(crate) fn hooks(&self) -> &[Arc<dyn SimulatorHooks>] {
+        &self.hooks
+    }
+
+    /// Send the summary of the run to the async API clients, if the statistics period is elapsed
+    /// or if `force`.
+    fn publish_statistics(&mut self, time: f32, force: bool) {
+        let Some(async_api_server) = &self.async_api_server else {
+            return;
+        };
+        let now = Instant::now();
+        if !force
+            && self
+                .last_statistics
+                .is_some_and(|last| now - last < self.statistics_period)
+        {
+            return;
+        }
+        self.last_statistics = Some(now);
+        let (start_instant, start_time) = *self.statistics_start.get_or_insert((now, time));
+        let elapsed = (now - start_instant).as_secs_f32();
+        let real_time_factor = if elapsed > 0. {
+            (time - start_time) / elapsed
+        } else {
+            0.
+        };
+        async_api_server.send_statistics(&AggregateStatistics::collect(
+            time,
+            &self.fleet_status,
+            &self.environment.get_meta_data().read_recover(),
+            self.records.len() + self.flushed_records + self.pruned_records,
+            self.memory_usage(),
+            real_time_factor,
+        ));
+    }
+
+    /// Publish the memory usage to the async API and apply the memory cap of the result
+    /// configuration, if any.
+    fn apply_memory_cap(&mut self, time: f32) -> SimbaResult<()> {
+        let usage = self.memory_usage();
+        let memory_cap = match self
+            .config
+            .results
+            .as_ref()
+            .and_then(|results| results.memory_cap.clone())
+        {
+            Some(memory_cap) if usage.total_bytes() > memory_cap.max_bytes() => memory_cap,
+            _ => {
+                if let Some(async_api_server) = &self.async_api_server {
+                    async_api_server.update_memory_usage(usage);
+                }
+                return Ok(());
+            }
+        };
+        match memory_cap.action {
+            MemoryCapAction::Flush => {
+                info!(
+                    "Memory cap reached at time {time} ({usage}): flushing {} records",
+                    self.records.len()
+                );
+                self.process_records(Some(time), true)?;
+                self.flushed_records += self.records.len();
+                self.records.clear();
+                self.records_bytes = 0;
+            }
+            MemoryCapAction::Prune => {
+                let target = (memory_cap.max_bytes() as f32 * PRUNE_TARGET_RATIO) as usize;
+                let mut to_release = usage.total_bytes().saturating_sub(target);
+                let mut nb_pruned = 0;
+                for record in &self.records {
+                    if to_release == 0 {
+                        break;
+                    }
+                    let size = estimate_size(record);
+                    to_release = to_release.saturating_sub(size);
+                    self.records_bytes = self.records_bytes.saturating_sub(size);
+                    nb_pruned += 1;
+                }
+                self.records.drain(..nb_pruned);
+                self.pruned_records += nb_pruned;
+                warn!(
+                    "Memory cap reached at time {time} ({usage}): {nb_pruned} oldest records pruned"
+                );
+            }
+        }
+        if let Some(async_api_server) = &self.async_api_server {
+            async_api_server.update_memory_usage(self.memory_usage());
+        }
+        Ok(())
+    }
+
     /// Load results from the given result `filename` or from the named specified in the loaded configuration.
     ///
     /// Returns the maximum time found in loaded records.
@@ -1073,6 +1618,15 @@ impl Simulator {
             ));
         }
         let result_config = self.config.results.clone().unwrap();
+        if filename.is_none() && result_config.format != ResultFormat::Json {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Only Json results can be loaded, the configuration uses the {} format",
+                    result_config.format
+                ),
+            ));
+        }
         let filename = filename.or(result_config.result_path);
         if filename.is_none() {
             return Err(SimbaError::new(
@@ -1080,10 +1634,16 @@ impl Simulator {
                 "Request for loading results but no result path in configuration".to_string(),
             ));
         }
-        let filename = self.config.base_path.as_ref().join(filename.unwrap());
+        let filename = config_path(&self.config.base_path, &filename.unwrap());
         let results = Self::deserialize_results_from_file(&filename)?;
 
         self.records = results.records;
+        self.records_bytes = self.records.iter().map(estimate_size).sum();
+        self.annotations.clear();
+        self.annotations.extend(results.annotations);
+        self.fleet_records = results.fleet_records;
+        self.mission = MissionTracker::default();
+        self.mission.add_records(&self.records);
         let mut max_time = self.common_time.write().unwrap();
         for record in &self.records {
             *max_time = max_time.max(record.time);
@@ -1103,16 +1663,32 @@ impl Simulator {
     }
 
     /// Deserialize persisted simulator results from a JSON file.
+    ///
+    /// The whole file is loaded in memory, see [`stream_results`] to read large files record by record.
     pub fn deserialize_results_from_file(filename: &Path) -> SimbaResult<Results> {
-        info!("Loading results from file `{}`", filename.to_str().unwrap());
-        let mut recording_file = File::open(filename).expect("Impossible to open record file");
-        let mut content = String::new();
-        recording_file
-            .read_to_string(&mut content)
-            .expect("Impossible to read record file");
+        info!("Loading results from file `{}`", filename.display());
+        let content = fs::read_to_string(filename).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to read result file '{}': {}",
+                    filename.display(),
+                    e
+                ),
+            )
+        })?;
 
         info!("Deserialize results...");
-        Ok(serde_json::from_str(&content).expect("Error during json parsing"))
+        serde_json::from_str(&content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Error while parsing result file '{}': {}",
+                    filename.display(),
+                    e
+                ),
+            )
+        })
     }
 
     /// Run the loop for the given `node` until reaching `max_time`.
@@ -1121,11 +1697,13 @@ impl Simulator {
     /// * `node` - Node to be run.
     /// * `max_time` - Time to stop the loop.
     /// * `async_api_server` - If the async API is enabled, the node will send its records to the async API server, which will be able to send them to the GUI in real time.
+    /// * `fleet_status` - Table where the node publishes its heartbeat at the end of each time step.
     /// * `node_sync_params` - Parameters to synchronize the node with the other nodes of the simulation.
     fn run_one_node(
         mut node: Node,
         max_time: f32,
         async_api_server: Option<SimulatorAsyncApiServer>,
+        fleet_status: FleetStatus,
         node_sync_params: NodeSyncParams,
     ) -> SimbaResult<Option<Node>> {
         if node.state() != NodeState::Running {
@@ -1143,13 +1721,17 @@ impl Simulator {
         THREAD_NAMES.write().unwrap().push(node.name());
         drop(thread_ids);
         let mut next_time = -1.;
+        let mut steps = 0;
+        let mut step_duration = Duration::ZERO;
         node_sync_params.barrier.wait();
         node_sync_params.barrier.wait();
         loop {
-            if *node_sync_params.time_cv.force_finish.lock().unwrap() {
+            if *node_sync_params.time_cv.force_finish.lock_recover() {
                 break;
             }
-            next_time = node.next_time_step(next_time + TIME_ROUND / 2.)?;
+            next_time = node
+                .next_time_step(next_time + TIME_ROUND / 2.)
+                .map_err(|e| e.with_time(next_time))?;
             if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
                 debug!("Got next_time: {next_time}");
             }
@@ -1183,17 +1765,27 @@ impl Simulator {
                 break;
             }
 
-            node.run_next_time_step(next_time, &node_sync_params.time_cv)?;
-            if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
-                debug!("End of time step wait");
-            }
-            if node.send_records()
-                && let Some(async_api_server) = &async_api_server
-            {
-                async_api_server.send_record(&Record {
-                    time: next_time,
-                    node: node.record(),
-                });
+            if node.is_below_time_step_floor(next_time) {
+                node.skip_time_step(next_time, &node_sync_params.time_cv);
+            } else {
+                let step_start = Instant::now();
+                node.run_next_time_step(next_time, &node_sync_params.time_cv)
+                    .map_err(|e| e.with_time(next_time))?;
+                steps += 1;
+                step_duration = step_start.elapsed();
+                fleet_status.publish(node.heartbeat(next_time, steps, step_duration));
+                if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
+                    debug!("End of time step wait");
+                }
+                if node.send_records()
+                    && let Some(async_api_server) = &async_api_server
+                {
+                    async_api_server.send_record(&Record {
+                        time: next_time,
+                        node: node.record(),
+                    });
+                }
+                node.clear_step_events();
             }
             if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
                 debug!("End of time step sync");
@@ -1217,6 +1809,8 @@ impl Simulator {
             if node.process_messages() > 0 {
                 node.handle_messages(next_time);
             }
+            #[cfg(feature = "force_hard_determinism")]
+            node.apply_kill_request();
             if node.state() == NodeState::Zombie {
                 info!("Killing node {}", node.name());
                 if node.process_messages() > 0 {
@@ -1225,6 +1819,7 @@ impl Simulator {
                 *node_sync_params.nb_nodes.write().unwrap() -= 1;
                 node_sync_params.time_cv.condvar.notify_all();
                 node.kill(next_time);
+                fleet_status.publish(node.heartbeat(next_time, steps, step_duration));
                 node_sync_params.barrier.remove_one();
                 return Ok(None);
             }
@@ -1232,6 +1827,7 @@ impl Simulator {
             node_sync_params.barrier.wait();
         }
 
+        node.close_estimator_dataset()?;
         Ok(Some(node))
     }
 
@@ -1239,12 +1835,12 @@ impl Simulator {
     fn simulator_spin(&mut self, running_parameters: &mut RunningParameters) -> SimbaResult<()> {
         let time_cv = self.time_cv.clone();
         loop {
-            let mut lk = time_cv.waiting.lock().unwrap();
+            let mut lk = time_cv.waiting.lock_recover();
             let mut waiting_nodes = 0;
             while *lk < *running_parameters.nb_nodes.read().unwrap()
                 && *running_parameters.finishing_cv.0.lock().unwrap()
                     < *running_parameters.nb_nodes.read().unwrap()
-                && !*time_cv.force_finish.lock().unwrap()
+                && !*time_cv.force_finish.lock_recover()
             {
                 if is_enabled(crate::logger::InternalLog::NodeSyncDetailed) {
                     debug!(
@@ -1255,9 +1851,12 @@ impl Simulator {
                         waiting_nodes,
                     );
                 }
-                lk = time_cv.condvar.wait(lk).unwrap();
+                lk = time_cv
+                    .condvar
+                    .wait(lk)
+                    .unwrap_or_else(PoisonError::into_inner);
             }
-            if *time_cv.force_finish.lock().unwrap() {
+            if *time_cv.force_finish.lock_recover() {
                 return Ok(());
             }
 
@@ -1273,11 +1872,10 @@ impl Simulator {
             let node_states = HashMap::from_iter(
                 self.environment
                     .get_meta_data()
-                    .read()
-                    .unwrap()
+                    .read_recover()
                     .iter()
                     .filter_map(|(node_name, meta_data)| {
-                        let meta_data = meta_data.read().unwrap();
+                        let meta_data = meta_data.read_recover();
                         if meta_data.state == NodeState::Running {
                             Some((node_name.clone(), meta_data.position))
                         } else {
@@ -1309,7 +1907,10 @@ impl Simulator {
                     );
                 }
                 let current_time = *TIME.read().unwrap();
-                if let Err(e) = self.process_records(Some(current_time)) {
+                if let Err(e) = self
+                    .process_records(Some(current_time), false)
+                    .and_then(|_| self.apply_memory_cap(current_time))
+                {
                     log::error!(
                         "Error in processing records at time {}: {}",
                         current_time,
@@ -1317,19 +1918,62 @@ impl Simulator {
                     );
                     return Err(e);
                 }
+                if let Some(idleness) = &mut self.idleness {
+                    self.fleet_records
+                        .push(idleness.update(current_time, node_states.values().flatten()));
+                }
+                for hooks in &self.hooks {
+                    hooks.on_step(current_time);
+                }
                 let scenario = self.scenario.clone();
-                scenario
-                    .lock()
-                    .unwrap()
-                    .execute_scenario(current_time, self, &node_states, running_parameters)
-                    .unwrap();
-                self.network_manager.process_messages(&node_states).unwrap();
+                let mut scenario = scenario.lock().unwrap();
+                scenario.execute_scenario(current_time, self, &node_states, running_parameters)?;
+                self.network_manager.process_messages(&node_states)?;
+                let outcomes = scenario.take_reconfiguration_outcomes(current_time);
+                if let Some(server) = &self.async_api_server {
+                    server.run_control().resolve_config_patches(outcomes);
+                }
+                std::mem::drop(scenario);
+                self.network_manager.collect_garbage(current_time);
+                self.fleet_status
+                    .refresh_states(&self.environment.get_meta_data().read_recover());
+                self.publish_statistics(current_time, false);
+                self.pace_realtime(current_time);
+                if let Some(server) = &self.async_api_server
+                    && server
+                        .run_control()
+                        .wait_next_step(&self.time_cv.force_finish)
+                {
+                    // Do not catch up the paused duration
+                    self.realtime_start = None;
+                }
+                // Patches submitted while paused, applied as scenario events
+                let patches = self
+                    .async_api_server
+                    .as_ref()
+                    .map(|server| server.run_control().take_config_patches())
+                    .unwrap_or_default();
+                if !patches.is_empty() {
+                    let run_control = self.async_api_server.as_ref().unwrap().run_control();
+                    let scenario = self.scenario.clone();
+                    let scenario = scenario.lock().unwrap();
+                    for (patch, outcome) in patches {
+                        match scenario.execute_config_patch(patch, self, current_time) {
+                            Ok(key) => run_control.config_patch_sent(key.to_string(), outcome),
+                            // The handle may have been dropped
+                            Err(e) => {
+                                let _ = outcome.send(Err(e));
+                            }
+                        }
+                    }
+                    self.network_manager.process_messages(&node_states)?;
+                }
                 for end_time_step_sync in running_parameters.end_time_step_syncs.iter() {
                     end_time_step_sync.lock().unwrap().clone_from(&false);
                 }
                 running_parameters.barrier.remove_one();
             } else {
-                self.network_manager.process_messages(&node_states).unwrap();
+                self.network_manager.process_messages(&node_states)?;
             }
             if *running_parameters.finishing_cv.0.lock().unwrap()
                 >= *running_parameters.nb_nodes.read().unwrap()
@@ -1352,7 +1996,7 @@ impl Simulator {
             }
             // Finishing time step procedure
             *lk = 0;
-            let mut waiting_parity = time_cv.intermediate_parity.lock().unwrap();
+            let mut waiting_parity = time_cv.intermediate_parity.lock_recover();
             *waiting_parity = 1 - *waiting_parity;
             time_cv.condvar.notify_all();
         }
@@ -1361,9 +2005,78 @@ impl Simulator {
     /// Compute configured post-processing results from in-memory records.
     pub fn compute_results(&self) -> SimbaResult<()> {
         let results = self.get_records(false);
+        self.save_bench_report(&results)?;
+        self.save_metrics(&results)?;
+        self.save_world_snapshots(&results)?;
         self._compute_results(results, &self.config)
     }
 
+    /// Compute the localization metrics of the state estimators from the records of the last
+    /// run, see [`LocalizationMetrics`].
+    ///
+    /// Records released by the memory cap ([`MemoryCapConfig`]) are not included.
+    pub fn compute_metrics(&self) -> LocalizationMetrics {
+        LocalizationMetrics::from_records(&self.get_records(false))
+    }
+
+    /// Write the localization metrics summary, if configured.
+    fn save_metrics(&self, results: &[Record]) -> SimbaResult<()> {
+        let Some(metrics_config) = self
+            .config
+            .results
+            .as_ref()
+            .and_then(|r| r.metrics.as_ref())
+        else {
+            return Ok(());
+        };
+        let path = config_path(&self.config.base_path, &metrics_config.metrics_path);
+        info!("Saving localization metrics to {}", path.display());
+        LocalizationMetrics::from_records(results).save(&path)
+    }
+
+    /// Compute the snapshots of the world at each time step of the last run, for external
+    /// renderers, see [`WorldSnapshots`].
+    ///
+    /// Records released by the memory cap ([`MemoryCapConfig`]) are not included.
+    pub fn compute_world_snapshots(&self) -> WorldSnapshots {
+        WorldSnapshots::from_records(
+            &self.get_records(false),
+            self.environment.map(),
+            &self.config,
+        )
+    }
+
+    /// Write the world snapshots, if configured.
+    fn save_world_snapshots(&self, results: &[Record]) -> SimbaResult<()> {
+        let Some(snapshots_config) = self
+            .config
+            .results
+            .as_ref()
+            .and_then(|r| r.world_snapshots.as_ref())
+        else {
+            return Ok(());
+        };
+        let path = config_path(&self.config.base_path, &snapshots_config.snapshots_path);
+        info!("Saving world snapshots to {}", path.display());
+        WorldSnapshots::from_records(results, self.environment.map(), &self.config).save(&path)
+    }
+
+    /// Write the comparison report of the bench state estimators, if configured.
+    fn save_bench_report(&self, results: &[Record]) -> SimbaResult<()> {
+        let Some(report_config) = self
+            .config
+            .results
+            .as_ref()
+            .and_then(|r| r.bench_report.as_ref())
+        else {
+            return Ok(());
+        };
+        let path = config_path(&self.config.base_path, &report_config.report_path);
+        info!("Saving state estimator bench report to {}", path.display());
+        BenchReport::from_records(results, self.time_analysis_factory.as_ref())
+            .save(&path, &report_config.format)
+    }
+
     /// Compute the results from the file where it was saved before.
     ///
     /// If the [`Simulator`] config disabled the computation of the results, this function
@@ -1397,23 +2110,50 @@ def show():
     plt.show()
 "#;
 
-        let script_path = self
-            .config
-            .base_path
-            .as_ref()
-            .join(result_config.analyse_script.unwrap());
-        let python_script = match fs::read_to_string(script_path.clone()) {
+        let script_path = result_config
+            .analyse_script_file(&self.config.base_path)
+            .unwrap();
+        let python_script = match fs::read_to_string(&script_path) {
             Err(e) => {
                 return Err(SimbaError::new(
                     SimbaErrorTypes::ConfigError,
                     format!(
                         "Result analyser script not found ({}): {}",
-                        script_path.to_str().unwrap(),
+                        script_path.display(),
                         e
                     ),
                 ));
             }
-            Ok(s) => CString::new(s).unwrap(),
+            Ok(s) => CString::new(s).map_err(|e| {
+                SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    format!(
+                        "Invalid result analyser script ({}): {}",
+                        script_path.display(),
+                        e
+                    ),
+                )
+            })?,
+        };
+        // Shown in the Python tracebacks
+        let script_filename =
+            CString::new(script_path.to_string_lossy().into_owned()).unwrap_or_default();
+        let figure_path = match result_config.figures_directory(&self.config.base_path) {
+            Some(figure_path) => {
+                fs::create_dir_all(&figure_path).map_err(|e| {
+                    SimbaError::new(
+                        SimbaErrorTypes::ConfigError,
+                        format!(
+                            "Impossible to create figure directory ({}): {}",
+                            figure_path.display(),
+                            e
+                        ),
+                    )
+                })?;
+                figure_path
+            }
+            // Empty string: the script does not save the figures
+            None => PathBuf::new(),
         };
         let res = Python::attach(|py| -> PyResult<()> {
             let script = PyModule::from_code(py, CONVERT_TO_DICT, c_str!(""), c_str!(""))?;
@@ -1423,24 +2163,25 @@ def show():
             let param_dict =
                 convert_fn.call(py, (&result_config.python_params.to_string(),), None)?;
 
-            let script = PyModule::from_code(py, &python_script, c_str!(""), c_str!(""))?;
+            let script = PyModule::from_code(
+                py,
+                &python_script,
+                &script_filename,
+                c_str!("analyse_script"),
+            )?;
             let analyse_fn: Py<PyAny> = script.getattr("analyse")?.into();
             info!("Analyse the results...");
-            let figure_path;
-            if let Some(p) = &result_config.figures_path {
-                figure_path = self.config.base_path.as_ref().join(p);
-                fs::create_dir_all(&figure_path).unwrap_or_else(|_| {
-                    panic!(
-                        "Impossible to create figure directory ({:#?})",
-                        &figure_path
-                    )
-                });
-            } else {
-                figure_path = PathBuf::new();
-            }
+            // Given as `str` (decoded with the file system encoding, as `os.fsdecode`), with the
+            // native separators
             let res = analyse_fn.call(
                 py,
-                (result_dict, config_dict, figure_path, ".pdf", param_dict),
+                (
+                    result_dict,
+                    config_dict,
+                    figure_path.as_os_str(),
+                    ".pdf",
+                    param_dict,
+                ),
                 None,
             );
             if let Err(err) = res {
@@ -1465,12 +2206,56 @@ def show():
         }
     }
 
+    fn async_api_server(&mut self) -> &SimulatorAsyncApiServer {
+        self.async_api_server.get_or_insert_with(|| {
+            SimulatorAsyncApiServer::new(0., self.annotations.clone(), self.fleet_status.clone())
+        })
+    }
+
     /// Create and return a new async API client for the simulator.
+    ///
+    /// This client is trusted: the access token is not checked, see [`Simulator::connect_async_api`].
     pub fn get_async_api(&mut self) -> Arc<SimulatorAsyncApi> {
-        if self.async_api_server.is_none() {
-            self.async_api_server = Some(SimulatorAsyncApiServer::new(0.));
-        }
-        Arc::new(self.async_api_server.as_mut().unwrap().new_client())
+        Arc::new(self.async_api_server().new_client())
+    }
+
+    /// Set the token required by [`Simulator::connect_async_api`]. With `None` (default), all the
+    /// clients are accepted.
+    pub fn set_async_api_token(&mut self, token: Option<String>) {
+        self.async_api_server().set_access_token(token);
+    }
+
+    /// Connect a new async API client, with its own record stream.
+    ///
+    /// ## Arguments
+    /// * `token` - Access token, checked if one was set with [`Simulator::set_async_api_token`].
+    /// * `from_start` - If true, the records kept by the simulator are sent first to the client,
+    ///   so that a client connecting during a run can read it from the start. The records already
+    ///   flushed to the result file are not sent.
+    pub fn connect_async_api(
+        &mut self,
+        token: Option<&str>,
+        from_start: bool,
+    ) -> SimbaResult<Arc<SimulatorAsyncApi>> {
+        let past_records = if from_start {
+            self.get_records(true)
+        } else {
+            Vec::new()
+        };
+        Ok(Arc::new(
+            self.async_api_server().connect(token, past_records)?,
+        ))
+    }
+
+    /// Get a handle to connect async API clients while the simulator is running (and locked).
+    pub fn async_api_connector(&mut self) -> AsyncApiConnector {
+        AsyncApiConnector::new(self.async_api_server().clone())
+    }
+
+    /// Get a handle to pause, resume and step the runs while the simulator is running (and
+    /// locked).
+    pub fn run_control(&mut self) -> RunControl {
+        self.async_api_server().run_control().clone()
     }
 
     /// Get the shared message broker used by the simulator network manager.
@@ -0,0 +1,246 @@
+//! Websocket live-streaming of the records (feature `stream`).
+//!
+//! A [`RecordStreamServer`] listens for websocket clients and sends them each
+//! [`Record`](crate::simulator::Record) as a JSON text message, as soon as it is produced.
+//! External dashboards can follow a run without waiting for the result file.
+//!
+//! Each websocket client is a session of the async API: it receives the records produced after its
+//! connection. The handshake is made in the thread of the client, with a timeout, so that a client
+//! which does not complete it does not block the others. When an access token is set on the simulator, the clients give it in the query of
+//! the URL (`ws://127.0.0.1:9002/?token=...`).
+
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::RecvTimeoutError,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use log::{debug, warn};
+use tungstenite::{
+    Message, WebSocket,
+    handshake::server::{Request, Response},
+};
+
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    logger::is_enabled,
+    simulator::{AsyncApiConnector, SimulatorAsyncApi},
+    utils::lock_recovery::RecoverMutex,
+};
+
+/// Websocket server streaming the records of a simulator to its clients.
+///
+/// The server runs in its own thread, and each client in another one. The server is stopped when
+/// dropped.
+pub struct RecordStreamServer {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RecordStreamServer {
+    /// Period of the check of the stop flag, while waiting for clients or records.
+    const STOP_CHECK_PERIOD: Duration = Duration::from_millis(50);
+    /// Maximal time for a client to send its handshake request.
+    const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Listen for websocket clients on `address` (e.g. `127.0.0.1:9002`, port `0` to let the
+    /// system choose one).
+    pub fn start(address: &str, connector: AsyncApiConnector) -> SimbaResult<Self> {
+        let listener = TcpListener::bind(address)
+            .and_then(|listener| {
+                listener.set_nonblocking(true)?;
+                Ok(listener)
+            })
+            .map_err(|e| {
+                SimbaError::new(
+                    SimbaErrorTypes::InitializationError,
+                    format!("Cannot open the record stream on {address}: {e}"),
+                )
+            })?;
+        let address = listener.local_addr().map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::InitializationError,
+                format!("Cannot get the address of the record stream: {e}"),
+            )
+        })?;
+        if is_enabled(crate::logger::InternalLog::API) {
+            debug!("Record stream listening on ws://{address}");
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread =
+            std::thread::spawn(move || Self::accept_loop(listener, connector, thread_stop));
+        Ok(Self {
+            address,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Stop the server and close the connections of the clients.
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn accept_loop(listener: TcpListener, connector: AsyncApiConnector, stop: Arc<AtomicBool>) {
+        let mut clients = Vec::new();
+        while !stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    let connector = connector.clone();
+                    let client_stop = stop.clone();
+                    clients.push(std::thread::spawn(move || {
+                        match Self::open_client(stream, &connector) {
+                            Ok((websocket, api)) => {
+                                if is_enabled(crate::logger::InternalLog::API) {
+                                    debug!("Record stream client {peer} connected");
+                                }
+                                Self::stream_records(websocket, api, client_stop)
+                            }
+                            Err(e) => warn!(
+                                "Record stream client {peer} rejected: {}",
+                                e.detailed_error()
+                            ),
+                        }
+                    }));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Self::STOP_CHECK_PERIOD);
+                }
+                Err(e) => warn!("Record stream: cannot accept a client: {e}"),
+            }
+            clients.retain(|client| !client.is_finished());
+        }
+        for client in clients {
+            let _ = client.join();
+        }
+    }
+
+    /// Make the websocket handshake, then open an async API session with the token given in the
+    /// query of the URL.
+    fn open_client(
+        stream: TcpStream,
+        connector: &AsyncApiConnector,
+    ) -> SimbaResult<(WebSocket<TcpStream>, Arc<SimulatorAsyncApi>)> {
+        let network_error = |e: String| {
+            SimbaError::new(
+                SimbaErrorTypes::ExternalAPIError,
+                format!("Record stream handshake failed: {e}"),
+            )
+        };
+        stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(Self::HANDSHAKE_TIMEOUT)))
+            .map_err(|e| network_error(e.to_string()))?;
+        let mut token = None;
+        let websocket = tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+            token = request.uri().query().and_then(|query| {
+                query
+                    .split('&')
+                    .find_map(|param| param.strip_prefix("token="))
+                    .map(str::to_string)
+            });
+            Ok(response)
+        })
+        .map_err(|e| network_error(e.to_string()))?;
+        websocket
+            .get_ref()
+            .set_read_timeout(None)
+            .map_err(|e| network_error(e.to_string()))?;
+        let api = connector.connect(token.as_deref())?;
+        Ok((websocket, api))
+    }
+
+    /// Send the records of the session to the client until the client leaves or the server stops.
+    fn stream_records(
+        mut websocket: WebSocket<TcpStream>,
+        api: Arc<SimulatorAsyncApi>,
+        stop: Arc<AtomicBool>,
+    ) {
+        let records = api.records.lock_recover();
+        while !stop.load(Ordering::Relaxed) {
+            let record = match records.recv_timeout(Self::STOP_CHECK_PERIOD) {
+                Ok(record) => record,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            let json = match serde_json::to_string(&record) {
+                Ok(json) => json,
+                Err(e) => {
+                    warn!("Record stream: cannot serialize a record: {e}");
+                    continue;
+                }
+            };
+            if websocket.send(Message::text(json)).is_err() {
+                break;
+            }
+        }
+        let _ = websocket.close(None);
+        let _ = websocket.flush();
+        if is_enabled(crate::logger::InternalLog::API) {
+            debug!("Record stream client of session {} left", api.session_id);
+        }
+    }
+}
+
+impl Drop for RecordStreamServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::TcpStream,
+        time::{Duration, Instant},
+    };
+
+    use crate::simulator::{RecordStreamServer, Simulator};
+
+    #[test]
+    fn stalled_client_does_not_block_the_others() {
+        let mut simulator = Simulator::new();
+        let connector = simulator.async_api_connector();
+        let mut server = RecordStreamServer::start("127.0.0.1:0", connector.clone()).unwrap();
+        let address = server.local_addr();
+
+        // Connected, but never sends its handshake request
+        let _stalled = TcpStream::connect(address).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+
+        let stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let (mut client, _) = tungstenite::client(format!("ws://{address}/"), stream)
+            .unwrap_or_else(|e| panic!("Handshake blocked by the stalled client: {e}"));
+
+        let start = Instant::now();
+        while connector.client_count() < 1 {
+            assert!(start.elapsed() < Duration::from_secs(2));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(connector.client_count(), 1);
+
+        server.stop();
+        // The server closes the connection when stopped
+        if let Ok(message) = client.read() {
+            assert!(message.is_close());
+        }
+    }
+}
@@ -0,0 +1,285 @@
+/*!
+Streaming reader of the result files.
+
+[`Simulator::deserialize_results_from_file`](super::Simulator::deserialize_results_from_file)
+loads the whole file, with the configuration and all the records, in memory. For long runs, the
+result file can be larger than the available memory: [`stream_results`] reads the records one
+by one and gives them to a callback, without keeping them.
+
+The configuration is skipped. The annotations of the run are returned.
+
+To browse a large file, [`ResultIndex::build`] streams it once and keeps, for each record, its
+time, its node and its position in the file. The records of a time window are then read on
+demand with [`ResultIndex::read_records`]. Random access needs an uncompressed file: compressed
+files (gzip, zstd) are rejected and should be decompressed first.
+*/
+
+use std::{
+    cell::Cell,
+    fmt,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+use crate::errors::{SimbaError, SimbaErrorTypes, SimbaResult};
+
+use super::{Annotation, Record};
+
+/// Deserialize the `records` list, giving each record to the callback.
+struct RecordsSeed<'a, F>(&'a mut F);
+
+impl<'de, F: FnMut(Record)> DeserializeSeed<'de> for RecordsSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: FnMut(Record)> Visitor<'de> for RecordsSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(record) = seq.next_element::<Record>()? {
+            (self.0)(record);
+        }
+        Ok(())
+    }
+}
+
+/// Deserialize the result file object, streaming the records and skipping the configuration.
+///
+/// The second member is called just before the `records` list is parsed.
+struct ResultsVisitor<'a, F>(&'a mut F, &'a dyn Fn());
+
+impl<'de, F: FnMut(Record)> Visitor<'de> for ResultsVisitor<'_, F> {
+    type Value = Vec<Annotation>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a result object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Vec<Annotation>, A::Error> {
+        let mut annotations = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "records" => {
+                    (self.1)();
+                    map.next_value_seed(RecordsSeed(&mut *self.0))?
+                }
+                "annotations" => annotations = map.next_value()?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        annotations.sort_by(|a: &Annotation, b| a.time.total_cmp(&b.time));
+        Ok(annotations)
+    }
+}
+
+fn open_result_file(filename: &Path) -> SimbaResult<File> {
+    File::open(filename).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "Impossible to open result file '{}': {}",
+                filename.display(),
+                e
+            ),
+        )
+    })
+}
+
+fn parse_error(filename: &Path, e: impl fmt::Display) -> SimbaError {
+    SimbaError::new(
+        SimbaErrorTypes::ConfigError,
+        format!(
+            "Error while parsing result file '{}': {}",
+            filename.display(),
+            e
+        ),
+    )
+}
+
+/// Parse the result file from `reader`, see [`ResultsVisitor`].
+fn parse_results(
+    reader: impl Read,
+    filename: &Path,
+    on_records_start: &dyn Fn(),
+    mut on_record: impl FnMut(Record),
+) -> SimbaResult<Vec<Annotation>> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    deserializer
+        .deserialize_map(ResultsVisitor(&mut on_record, on_records_start))
+        .map_err(|e| parse_error(filename, e))
+}
+
+/// Read the result file record by record, calling `on_record` for each one, in the file order.
+///
+/// Returns the annotations of the run, sorted by time.
+pub fn stream_results(
+    filename: &Path,
+    on_record: impl FnMut(Record),
+) -> SimbaResult<Vec<Annotation>> {
+    let file = open_result_file(filename)?;
+    parse_results(BufReader::new(file), filename, &|| {}, on_record)
+}
+
+/// Reader counting the bytes consumed by the parser.
+///
+/// `serde_json` reads its input byte by byte and looks ahead of one byte at most, which is not
+/// pending after the closing brace of a record: the count is then the end of the record.
+struct CountingReader<'a, R> {
+    inner: R,
+    count: &'a Cell<u64>,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+/// Position of a record in the result file.
+#[derive(Debug, Clone)]
+pub struct ResultIndexEntry {
+    /// Time of the record.
+    pub time: f32,
+    /// Index of the node of the record in [`ResultIndex::nodes`].
+    pub node: usize,
+    /// Position of the separator before the record.
+    offset: u64,
+    /// Length of the separator and of the record.
+    length: u64,
+}
+
+/// Time and node index of a result file, to read its records by time window.
+#[derive(Debug, Clone)]
+pub struct ResultIndex {
+    filename: PathBuf,
+    /// Entries sorted by time.
+    entries: Vec<ResultIndexEntry>,
+    nodes: Vec<String>,
+    annotations: Vec<Annotation>,
+}
+
+impl ResultIndex {
+    /// Scan the result file, without keeping the records.
+    pub fn build(filename: &Path) -> SimbaResult<Self> {
+        let mut file = open_result_file(filename)?;
+        let mut magic = [0u8; 4];
+        let magic_len = file
+            .read(&mut magic)
+            .map_err(|e| parse_error(filename, e))?;
+        if magic_len >= 2 && magic[..2] == [0x1f, 0x8b]
+            || magic_len == 4 && magic == [0x28, 0xb5, 0x2f, 0xfd]
+        {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Result file '{}' is compressed: decompress it to browse it",
+                    filename.display()
+                ),
+            ));
+        }
+        file.rewind().map_err(|e| parse_error(filename, e))?;
+
+        let count = Cell::new(0);
+        let previous_end = Cell::new(0);
+        let mut entries = Vec::new();
+        let mut nodes: Vec<String> = Vec::new();
+        let reader = CountingReader {
+            inner: BufReader::new(file),
+            count: &count,
+        };
+        let annotations = parse_results(
+            reader,
+            filename,
+            &|| previous_end.set(count.get()),
+            |record| {
+                let name = record.node.name();
+                let node = match nodes.iter().position(|n| n == name) {
+                    Some(node) => node,
+                    None => {
+                        nodes.push(name.clone());
+                        nodes.len() - 1
+                    }
+                };
+                let end = count.get();
+                entries.push(ResultIndexEntry {
+                    time: record.time,
+                    node,
+                    offset: previous_end.get(),
+                    length: end - previous_end.get(),
+                });
+                previous_end.set(end);
+            },
+        )?;
+        entries.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(Self {
+            filename: filename.to_path_buf(),
+            entries,
+            nodes,
+            annotations,
+        })
+    }
+
+    /// Entries of the file, sorted by time.
+    pub fn entries(&self) -> &[ResultIndexEntry] {
+        &self.entries
+    }
+
+    /// Names of the nodes having records.
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    /// Annotations of the run, sorted by time.
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// First and last record times, `None` without records.
+    pub fn time_range(&self) -> Option<(f32, f32)> {
+        Some((self.entries.first()?.time, self.entries.last()?.time))
+    }
+
+    /// Read the records with a time in `[from, to[`, sorted by time.
+    pub fn read_records(&self, from: f32, to: f32) -> SimbaResult<Vec<Record>> {
+        let begin = self.entries.partition_point(|e| e.time < from);
+        let end = self.entries.partition_point(|e| e.time < to);
+        let mut window: Vec<&ResultIndexEntry> = self.entries[begin..end].iter().collect();
+        // Read the file forward
+        window.sort_by_key(|e| e.offset);
+
+        let mut file = open_result_file(&self.filename)?;
+        let mut buffer = Vec::new();
+        let mut records = Vec::with_capacity(window.len());
+        for entry in window {
+            buffer.resize(entry.length as usize, 0);
+            file.seek(SeekFrom::Start(entry.offset))
+                .and_then(|_| file.read_exact(&mut buffer))
+                .map_err(|e| parse_error(&self.filename, e))?;
+            // Skip the separator (`,` or `: [` and spaces)
+            let start = buffer.iter().position(|b| *b == b'{').ok_or_else(|| {
+                parse_error(&self.filename, "record not found at indexed position")
+            })?;
+            records.push(
+                serde_json::from_slice::<Record>(&buffer[start..])
+                    .map_err(|e| parse_error(&self.filename, e))?,
+            );
+        }
+        records.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(records)
+    }
+}
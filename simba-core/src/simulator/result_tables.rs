@@ -0,0 +1,487 @@
+/*!
+Writer of the results as tables, for the [`ResultFormat::Csv`] and [`ResultFormat::Parquet`]
+formats.
+
+The records are written in one table per node, in the result directory. Each record is serialized
+to JSON and flattened: objects give `<parent>.<key>` columns and arrays `<parent>[<index>]`
+columns, so the top-level columns are the modules of the node (`physics.`, `navigator.`, ...). The
+enum variant of the node record (`Robot`, `ComputationUnit`) is skipped.
+
+The columns of a node table are the union of the fields of its records, a missing field is left
+empty. The tables are written at each save of the [`ResultSaveMode`](super::ResultSaveMode), and
+closed at the end of the run. If the run is continued, the CSV tables are appended, and new Parquet
+parts are written (`<node>.1.parquet`, ...). When a save brings fields absent from the rows already
+written (e.g. an optional field set later), the table continues in a new part with the new columns
+(`<node>.1.csv` for the CSV tables).
+*/
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+};
+
+use log::debug;
+use serde_json::Value;
+
+use crate::errors::{SimbaError, SimbaErrorTypes, SimbaResult};
+
+use super::{Annotation, FleetRecord, MissionSummary, Record, ResultFormat, SimulatorConfig};
+
+/// Directory of the tables for the `result_path`: the path without its extension.
+pub(super) fn result_table_directory(result_path: &Path) -> PathBuf {
+    result_path.with_extension("")
+}
+
+fn io_error(path: &Path, e: impl std::fmt::Display) -> SimbaError {
+    SimbaError::new(
+        SimbaErrorTypes::ConfigError,
+        format!(
+            "Error while writing result table '{}': {}",
+            path.display(),
+            e
+        ),
+    )
+}
+
+/// Flatten `value` to scalar columns named after `prefix`.
+fn flatten(prefix: String, value: Value, columns: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Array(values) => {
+            for (i, v) in values.into_iter().enumerate() {
+                flatten(format!("{prefix}[{i}]"), v, columns);
+            }
+        }
+        Value::Object(map) => {
+            for (k, v) in map {
+                let name = if prefix.is_empty() {
+                    k
+                } else {
+                    format!("{prefix}.{k}")
+                };
+                flatten(name, v, columns);
+            }
+        }
+        v => columns.push((prefix, v)),
+    }
+}
+
+/// Flattened columns of the node record.
+fn record_columns(record: &Record) -> Vec<(String, Value)> {
+    let value = match serde_json::to_value(&record.node).unwrap() {
+        // Skip the node record variant
+        Value::Object(map) if map.len() == 1 => map.into_iter().next().unwrap().1,
+        v => v,
+    };
+    let mut columns = Vec::new();
+    flatten(String::new(), value, &mut columns);
+    columns
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnType {
+    Number,
+    Boolean,
+    Text,
+}
+
+impl ColumnType {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Number(_) => Self::Number,
+            Value::Bool(_) => Self::Boolean,
+            _ => Self::Text,
+        }
+    }
+}
+
+fn text_cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+    }
+}
+
+enum TableSink {
+    Csv(csv::Writer<File>),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::arrow::ArrowWriter<File>, arrow_schema::SchemaRef),
+}
+
+/// Table of a node: columns (union of the fields of the rows), and the open file.
+struct Table {
+    name: String,
+    columns: Vec<(String, ColumnType)>,
+    sink: Option<TableSink>,
+    /// Number of closed files of the table.
+    parts: usize,
+    /// Number of changes of the columns after rows were written, each starting a new CSV file.
+    schema_changes: usize,
+}
+
+impl Table {
+    fn new(name: String, columns: &[(String, Value)]) -> Self {
+        Self {
+            name,
+            columns: columns
+                .iter()
+                .map(|(name, value)| (name.clone(), ColumnType::of(value)))
+                .collect(),
+            sink: None,
+            parts: 0,
+            schema_changes: 0,
+        }
+    }
+
+    /// Add the columns of the `rows` absent from the table, typed by their first non-null value.
+    ///
+    /// If rows were already written with the previous columns, the file is closed so that the
+    /// next rows start a new part.
+    fn extend_columns(&mut self, rows: &[(f32, HashMap<String, Value>)]) -> SimbaResult<()> {
+        let mut new_columns: Vec<(String, ColumnType)> = Vec::new();
+        for (_, row) in rows {
+            for (name, value) in row {
+                if self.columns.iter().any(|(column, _)| column == name) {
+                    continue;
+                }
+                match new_columns.iter_mut().find(|(column, _)| column == name) {
+                    Some((_, column_type)) => {
+                        if *column_type == ColumnType::Text && !value.is_null() {
+                            *column_type = ColumnType::of(value);
+                        }
+                    }
+                    None => new_columns.push((name.clone(), ColumnType::of(value))),
+                }
+            }
+        }
+        if new_columns.is_empty() {
+            return Ok(());
+        }
+        new_columns.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if self.sink.is_some() || self.parts > 0 {
+            debug!(
+                "New fields in the records of `{}`, the table continues in a new part",
+                self.name
+            );
+            self.close()?;
+            self.schema_changes += 1;
+        }
+        self.columns.extend(new_columns);
+        Ok(())
+    }
+
+    fn open(&mut self, directory: &Path, format: &ResultFormat) -> SimbaResult<&mut TableSink> {
+        if self.sink.is_none() {
+            let file_name = self.name.replace(['/', '\\'], "_");
+            self.sink = Some(match format {
+                ResultFormat::Csv => {
+                    let path = if self.schema_changes == 0 {
+                        directory.join(format!("{file_name}.csv"))
+                    } else {
+                        directory.join(format!("{file_name}.{}.csv", self.schema_changes))
+                    };
+                    // Append to the table of the previous run part
+                    let append = self.parts > 0 && path.exists();
+                    let file = OpenOptions::new()
+                        .create(true)
+                        .write(true)
+                        .append(append)
+                        .truncate(!append)
+                        .open(&path)
+                        .map_err(|e| io_error(&path, e))?;
+                    let mut writer = csv::Writer::from_writer(file);
+                    if !append {
+                        writer
+                            .write_record(
+                                std::iter::once("time")
+                                    .chain(self.columns.iter().map(|(name, _)| name.as_str())),
+                            )
+                            .map_err(|e| io_error(&path, e))?;
+                    }
+                    TableSink::Csv(writer)
+                }
+                #[cfg(feature = "parquet")]
+                ResultFormat::Parquet => {
+                    use arrow_schema::{DataType, Field, Schema};
+                    let path = if self.parts == 0 {
+                        directory.join(format!("{file_name}.parquet"))
+                    } else {
+                        directory.join(format!("{file_name}.{}.parquet", self.parts))
+                    };
+                    let schema = std::sync::Arc::new(Schema::new(
+                        std::iter::once(Field::new("time", DataType::Float32, false))
+                            .chain(self.columns.iter().map(|(name, column_type)| {
+                                let data_type = match column_type {
+                                    ColumnType::Number => DataType::Float64,
+                                    ColumnType::Boolean => DataType::Boolean,
+                                    ColumnType::Text => DataType::Utf8,
+                                };
+                                Field::new(name, data_type, true)
+                            }))
+                            .collect::<Vec<_>>(),
+                    ));
+                    let file = File::create(&path).map_err(|e| io_error(&path, e))?;
+                    let writer = parquet::arrow::ArrowWriter::try_new(file, schema.clone(), None)
+                        .map_err(|e| io_error(&path, e))?;
+                    TableSink::Parquet(writer, schema)
+                }
+                _ => {
+                    return Err(SimbaError::new(
+                        SimbaErrorTypes::ConfigError,
+                        format!("Result format {format} cannot be written as tables"),
+                    ));
+                }
+            });
+        }
+        Ok(self.sink.as_mut().unwrap())
+    }
+
+    fn write_rows(
+        &mut self,
+        directory: &Path,
+        format: &ResultFormat,
+        rows: Vec<(f32, Vec<(String, Value)>)>,
+    ) -> SimbaResult<()> {
+        let rows: Vec<(f32, HashMap<String, Value>)> = rows
+            .into_iter()
+            .map(|(time, row)| (time, row.into_iter().collect()))
+            .collect();
+        self.extend_columns(&rows)?;
+        let columns = self.columns.clone();
+        let table_path = directory.join(&self.name);
+        match self.open(directory, format)? {
+            TableSink::Csv(writer) => {
+                for (time, row) in &rows {
+                    writer
+                        .write_record(
+                            std::iter::once(time.to_string())
+                                .chain(columns.iter().map(|(name, _)| text_cell(row.get(name)))),
+                        )
+                        .map_err(|e| io_error(&table_path, e))?;
+                }
+                writer.flush().map_err(|e| io_error(&table_path, e))?;
+            }
+            #[cfg(feature = "parquet")]
+            TableSink::Parquet(writer, schema) => {
+                use arrow_array::{
+                    ArrayRef, BooleanArray, Float32Array, Float64Array, RecordBatch, StringArray,
+                };
+                let mut arrays: Vec<ArrayRef> = vec![std::sync::Arc::new(Float32Array::from(
+                    rows.iter().map(|(time, _)| *time).collect::<Vec<_>>(),
+                ))];
+                for (name, column_type) in &columns {
+                    let cells = rows.iter().map(|(_, row)| row.get(name));
+                    arrays.push(match column_type {
+                        ColumnType::Number => std::sync::Arc::new(Float64Array::from(
+                            cells.map(|v| v.and_then(Value::as_f64)).collect::<Vec<_>>(),
+                        )),
+                        ColumnType::Boolean => std::sync::Arc::new(BooleanArray::from(
+                            cells
+                                .map(|v| v.and_then(Value::as_bool))
+                                .collect::<Vec<_>>(),
+                        )),
+                        ColumnType::Text => std::sync::Arc::new(StringArray::from(
+                            cells
+                                .map(|v| v.filter(|v| !v.is_null()).map(|v| text_cell(Some(v))))
+                                .collect::<Vec<_>>(),
+                        )),
+                    });
+                }
+                let batch = RecordBatch::try_new(schema.clone(), arrays)
+                    .map_err(|e| io_error(&table_path, e))?;
+                writer.write(&batch).map_err(|e| io_error(&table_path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the file of the table. The next rows are written in a new part.
+    fn close(&mut self) -> SimbaResult<()> {
+        let table_path = PathBuf::from(&self.name);
+        match self.sink.take() {
+            Some(TableSink::Csv(mut writer)) => {
+                writer.flush().map_err(|e| io_error(&table_path, e))?;
+            }
+            #[cfg(feature = "parquet")]
+            Some(TableSink::Parquet(writer, _)) => {
+                writer.close().map_err(|e| io_error(&table_path, e))?;
+            }
+            None => return Ok(()),
+        }
+        self.parts += 1;
+        Ok(())
+    }
+}
+
+/// Writer of the records in one table per node, see the [module documentation](self).
+pub(super) struct ResultTableWriter {
+    directory: PathBuf,
+    format: ResultFormat,
+    tables: BTreeMap<String, Table>,
+}
+
+impl ResultTableWriter {
+    /// Create the result directory for `result_path`, and write the configuration in it.
+    pub fn new(
+        result_path: &Path,
+        format: ResultFormat,
+        config: &SimulatorConfig,
+    ) -> SimbaResult<Self> {
+        let directory = result_table_directory(result_path);
+        fs::create_dir_all(&directory).map_err(|e| io_error(&directory, e))?;
+        let config_path = directory.join("config.json");
+        let file = File::create(&config_path).map_err(|e| io_error(&config_path, e))?;
+        serde_json::to_writer_pretty(file, config).map_err(|e| io_error(&config_path, e))?;
+        Ok(Self {
+            directory,
+            format,
+            tables: BTreeMap::new(),
+        })
+    }
+
+    /// Append the records to the tables of their nodes.
+    pub fn write_records(&mut self, records: &[Record]) -> SimbaResult<()> {
+        let mut rows_by_node: BTreeMap<&String, Vec<(f32, Vec<(String, Value)>)>> = BTreeMap::new();
+        for record in records {
+            rows_by_node
+                .entry(record.node.name())
+                .or_default()
+                .push((record.time, record_columns(record)));
+        }
+        for (node, rows) in rows_by_node {
+            let table = self
+                .tables
+                .entry(node.clone())
+                .or_insert_with(|| Table::new(node.clone(), &[]));
+            table.write_rows(&self.directory, &self.format, rows)?;
+        }
+        Ok(())
+    }
+
+    /// Write the annotations and fleet tables and the mission summary, and close the node
+    /// tables.
+    pub fn finish(
+        &mut self,
+        annotations: &[Annotation],
+        fleet_records: &[FleetRecord],
+        mission: &MissionSummary,
+    ) -> SimbaResult<()> {
+        let rows: Vec<(f32, Vec<(String, Value)>)> = annotations
+            .iter()
+            .map(|annotation| {
+                (
+                    annotation.time,
+                    vec![
+                        (
+                            "node".to_string(),
+                            annotation.node.clone().map_or(Value::Null, Value::String),
+                        ),
+                        ("text".to_string(), Value::String(annotation.text.clone())),
+                    ],
+                )
+            })
+            .collect();
+        // Written in full each time
+        let mut annotation_table = Table::new(
+            "annotations".to_string(),
+            &[
+                ("node".to_string(), Value::Null),
+                ("text".to_string(), Value::Null),
+            ],
+        );
+        annotation_table.write_rows(&self.directory, &self.format, rows)?;
+        annotation_table.close()?;
+        if !fleet_records.is_empty() {
+            let rows: Vec<(f32, Vec<(String, Value)>)> = fleet_records
+                .iter()
+                .map(|record| {
+                    let mut columns = Vec::new();
+                    if let Value::Object(mut map) = serde_json::to_value(record).unwrap() {
+                        map.remove("time");
+                        flatten(String::new(), Value::Object(map), &mut columns);
+                    }
+                    (record.time, columns)
+                })
+                .collect();
+            let mut fleet_table = Table::new("fleet".to_string(), &[]);
+            fleet_table.write_rows(&self.directory, &self.format, rows)?;
+            fleet_table.close()?;
+        }
+        let mission_path = self.directory.join("mission.json");
+        let file = File::create(&mission_path).map_err(|e| io_error(&mission_path, e))?;
+        serde_json::to_writer_pretty(file, mission).map_err(|e| io_error(&mission_path, e))?;
+        for table in self.tables.values_mut() {
+            table.close()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flatten_columns() {
+        let mut columns = Vec::new();
+        flatten(
+            String::new(),
+            serde_json::json!({"physics": {"pose": [1., 2.]}, "name": "r1", "goal": null}),
+            &mut columns,
+        );
+        let names: Vec<&str> = columns.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["goal", "name", "physics.pose[0]", "physics.pose[1]"]
+        );
+        assert_eq!(ColumnType::of(&columns[2].1), ColumnType::Number);
+        assert_eq!(text_cell(Some(&columns[0].1)), "");
+    }
+
+    #[test]
+    fn union_of_the_record_fields() {
+        let directory =
+            std::env::temp_dir().join(format!("simba_result_tables_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let row = |fields: &[(&str, Value)]| -> Vec<(String, Value)> {
+            fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect()
+        };
+
+        let mut table = Table::new("node".to_string(), &[]);
+        // An optional field only set in the second record
+        table
+            .write_rows(
+                &directory,
+                &ResultFormat::Csv,
+                vec![
+                    (0., row(&[("a", Value::from(1)), ("goal", Value::Null)])),
+                    (
+                        1.,
+                        row(&[("a", Value::from(2)), ("goal", Value::from(3.5))]),
+                    ),
+                ],
+            )
+            .unwrap();
+        // An enum variant with other fields in a later save
+        table
+            .write_rows(
+                &directory,
+                &ResultFormat::Csv,
+                vec![(2., row(&[("a", Value::from(3)), ("b", Value::from("x"))]))],
+            )
+            .unwrap();
+        table.close().unwrap();
+
+        let first = fs::read_to_string(directory.join("node.csv")).unwrap();
+        assert_eq!(first, "time,a,goal\n0,1,\n1,2,3.5\n");
+        let second = fs::read_to_string(directory.join("node.1.csv")).unwrap();
+        assert_eq!(second, "time,a,goal,b\n2,3,,x\n");
+        assert_eq!(table.columns[1].1, ColumnType::Number);
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -10,6 +10,7 @@ use simba_macros::config_derives;
 use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "gui")]
 use crate::{
@@ -21,7 +22,13 @@ use crate::{
     utils::enum_tools::ToVec,
 };
 
-use crate::simulator::{Record, SimulatorConfig};
+use crate::{
+    simulator::{
+        Annotation, FleetRecord, MemoryCapAction, MemoryCapConfig, MetricsConfig, MissionSummary,
+        Record, SimulatorConfig, WorldSnapshotsConfig,
+    },
+    state_estimators::{bench_report::BenchReportConfig, dataset::EstimatorDatasetConfig},
+};
 
 #[config_derives(tag_content)]
 /// Strategy used to save simulation results on disk.
@@ -42,11 +49,36 @@ impl Default for ResultSaveMode {
     }
 }
 
+/// File format of the saved results.
+///
+/// With `Csv` and `Parquet`, the records are written as one table per node, in a directory named
+/// after `result_path` without its extension (`results/run.json` gives `results/run/`):
+/// `<node>.csv` or `<node>.parquet`, with a `time` column then one column per scalar field of the
+/// record (e.g. `physics.Internal.state.pose[0]`). The configuration is written in `config.json`
+/// and the annotations in `annotations.csv` (or `annotations.parquet`). The fleet records, if any,
+/// are written in `fleet.csv` (or `fleet.parquet`).
+#[config_derives]
+pub enum ResultFormat {
+    /// Single JSON file, with the configuration, the records, the annotations and the fleet
+    /// records.
+    Json,
+    /// Comma-separated values, one file per node.
+    Csv,
+    /// Apache Parquet, one file per node. Requires the `parquet` feature.
+    Parquet,
+}
+
+impl Default for ResultFormat {
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
 #[config_derives]
 /// Configuration controlling result persistence and post-processing.
 pub struct ResultConfig {
-    /// Filename to save the results, in JSON format. The directory of this
-    /// file is used to save the figures if results are computed.
+    /// Filename to save the results, in JSON format (see `format` for the other formats). The
+    /// directory of this file is used to save the figures if results are computed.
     ///
     /// Path from config location.
     ///
@@ -65,6 +97,28 @@ pub struct ResultConfig {
     pub python_params: serde_json::Value,
     /// Result save mode.
     pub save_mode: ResultSaveMode,
+    /// File format of the results. Only the `Json` results can be loaded back.
+    pub format: ResultFormat,
+    /// Comparison report of the bench state estimators, written when results are computed.
+    /// If `None`, no report is generated.
+    #[check]
+    pub bench_report: Option<BenchReportConfig>,
+    /// Localization metrics summary (RMSE, NEES) of the state estimators, written when results
+    /// are computed. If `None`, no summary is written.
+    #[check]
+    pub metrics: Option<MetricsConfig>,
+    /// Snapshots of the world at each time step (robot poses, landmarks, detections), for
+    /// external renderers, written when results are computed. If `None`, nothing is written.
+    #[check]
+    pub world_snapshots: Option<WorldSnapshotsConfig>,
+    /// Log the state estimator inputs and outputs at each step, to build datasets.
+    /// If `None`, nothing is logged.
+    #[check]
+    pub estimator_dataset: Option<EstimatorDatasetConfig>,
+    /// Cap on the approximate memory used by the records kept in memory and the broker queues.
+    /// If `None`, the memory is not bounded.
+    #[check]
+    pub memory_cap: Option<MemoryCapConfig>,
 }
 
 impl Default for ResultConfig {
@@ -77,6 +131,79 @@ impl Default for ResultConfig {
             figures_path: None,
             python_params: serde_json::Value::default(),
             save_mode: ResultSaveMode::default(),
+            format: ResultFormat::default(),
+            bench_report: None,
+            metrics: None,
+            world_snapshots: None,
+            estimator_dataset: None,
+            memory_cap: None,
+        }
+    }
+}
+
+impl ResultConfig {
+    /// File of the results, from the configuration directory `base_path`.
+    pub fn result_file(&self, base_path: &Path) -> Option<PathBuf> {
+        self.result_path
+            .as_deref()
+            .map(|path| config_path(base_path, path))
+    }
+
+    /// Python analysis script, from the configuration directory `base_path`.
+    pub fn analyse_script_file(&self, base_path: &Path) -> Option<PathBuf> {
+        self.analyse_script
+            .as_deref()
+            .map(|path| config_path(base_path, path))
+    }
+
+    /// Directory of the figures, from the configuration directory `base_path`.
+    pub fn figures_directory(&self, base_path: &Path) -> Option<PathBuf> {
+        self.figures_path
+            .as_deref()
+            .map(|path| config_path(base_path, path))
+    }
+}
+
+/// Resolve a `path` given in the configuration, relative to the configuration directory
+/// `base_path` (absolute paths are kept).
+///
+/// `/` is a separator on every platform, so the configurations using it can be shared. `\` is a
+/// separator on Windows only: on the other platforms, it is a valid character of the file names
+/// and is kept.
+pub fn config_path(base_path: &Path, path: &str) -> PathBuf {
+    base_path.join(path)
+}
+
+impl Check for ResultConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Some(memory_cap) = &self.memory_cap
+            && memory_cap.action == MemoryCapAction::Flush
+        {
+            if self.result_path.is_none() {
+                errors.push(
+                    "Memory cap with Flush action requires a result_path to flush the records"
+                        .to_string(),
+                );
+            }
+            if self.save_mode == ResultSaveMode::AtTheEnd {
+                errors.push(
+                    "Memory cap with Flush action is not compatible with the AtTheEnd save mode"
+                        .to_string(),
+                );
+            }
+        }
+        #[cfg(not(feature = "parquet"))]
+        if self.format == ResultFormat::Parquet {
+            errors.push(
+                "Parquet result format requires simba to be built with the `parquet` feature"
+                    .to_string(),
+            );
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
     }
 }
@@ -86,10 +213,10 @@ impl UIComponent for ResultConfig {
     fn show_mut(
         &mut self,
         ui: &mut egui::Ui,
-        _ctx: &egui::Context,
+        ctx: &egui::Context,
         buffer_stack: &mut BTreeMap<String, String>,
         global_config: &SimulatorConfig,
-        _current_node_name: Option<&String>,
+        current_node_name: Option<&String>,
         unique_id: &str,
     ) {
         let python_param_key = format!("result-config-python-params-{}", unique_id);
@@ -138,6 +265,25 @@ impl UIComponent for ResultConfig {
                 };
             }
 
+            let mut current_str = self.format.to_string();
+            ui.horizontal(|ui| {
+                ui.label("Format:");
+                string_combobox(
+                    ui,
+                    &ResultFormat::to_vec(),
+                    &mut current_str,
+                    format!("result-format-choice-{}", unique_id),
+                );
+            });
+            if current_str != self.format.to_string() {
+                match current_str.as_str() {
+                    "Json" => self.format = ResultFormat::Json,
+                    "Csv" => self.format = ResultFormat::Csv,
+                    "Parquet" => self.format = ResultFormat::Parquet,
+                    _ => panic!("Where did you find this value?"),
+                };
+            }
+
             ui.horizontal(|ui| {
                 ui.label("Show figures:");
                 ui.checkbox(&mut self.show_figures, "");
@@ -175,10 +321,105 @@ impl UIComponent for ResultConfig {
                     &mut self.python_params,
                 );
             });
+            ui.horizontal(|ui| {
+                ui.label("Bench report:");
+                if let Some(bench_report) = &mut self.bench_report {
+                    if ui.button("X").clicked() {
+                        self.bench_report = None;
+                    } else {
+                        bench_report.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                    }
+                } else if ui.button("+").clicked() {
+                    self.bench_report = Some(BenchReportConfig::default());
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Metrics:");
+                if let Some(metrics) = &mut self.metrics {
+                    if ui.button("X").clicked() {
+                        self.metrics = None;
+                    } else {
+                        metrics.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                    }
+                } else if ui.button("+").clicked() {
+                    self.metrics = Some(MetricsConfig::default());
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("World snapshots:");
+                if let Some(world_snapshots) = &mut self.world_snapshots {
+                    if ui.button("X").clicked() {
+                        self.world_snapshots = None;
+                    } else {
+                        world_snapshots.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                    }
+                } else if ui.button("+").clicked() {
+                    self.world_snapshots = Some(WorldSnapshotsConfig::default());
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Estimator dataset:");
+                if let Some(estimator_dataset) = &mut self.estimator_dataset {
+                    if ui.button("X").clicked() {
+                        self.estimator_dataset = None;
+                    } else {
+                        estimator_dataset.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                    }
+                } else if ui.button("+").clicked() {
+                    self.estimator_dataset = Some(EstimatorDatasetConfig::default());
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Memory cap:");
+                if let Some(memory_cap) = &mut self.memory_cap {
+                    if ui.button("X").clicked() {
+                        self.memory_cap = None;
+                    } else {
+                        memory_cap.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                    }
+                } else if ui.button("+").clicked() {
+                    self.memory_cap = Some(MemoryCapConfig::default());
+                }
+            });
         });
     }
 
-    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
         CollapsingHeader::new("Results").show(ui, |ui| {
             ui.horizontal(|ui| {
                 ui.label("Result Path: ");
@@ -199,6 +440,10 @@ impl UIComponent for ResultConfig {
                 ui.label(format!("Save mode: {}", as_str));
             });
 
+            ui.horizontal(|ui| {
+                ui.label(format!("Format: {}", self.format));
+            });
+
             ui.horizontal(|ui| {
                 ui.label("Show figures: ");
                 if self.show_figures {
@@ -228,6 +473,46 @@ impl UIComponent for ResultConfig {
                 ui.label("Python params: ");
                 ui.label(self.python_params.to_string());
             });
+            ui.horizontal(|ui| {
+                ui.label("Bench report: ");
+                if let Some(bench_report) = &self.bench_report {
+                    bench_report.show(ui, ctx, unique_id);
+                } else {
+                    ui.label("None");
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Metrics: ");
+                if let Some(metrics) = &self.metrics {
+                    metrics.show(ui, ctx, unique_id);
+                } else {
+                    ui.label("None");
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("World snapshots: ");
+                if let Some(world_snapshots) = &self.world_snapshots {
+                    world_snapshots.show(ui, ctx, unique_id);
+                } else {
+                    ui.label("None");
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Estimator dataset: ");
+                if let Some(estimator_dataset) = &self.estimator_dataset {
+                    estimator_dataset.show(ui, ctx, unique_id);
+                } else {
+                    ui.label("None");
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Memory cap: ");
+                if let Some(memory_cap) = &self.memory_cap {
+                    memory_cap.show(ui, ctx, unique_id);
+                } else {
+                    ui.label("None");
+                }
+            });
         });
     }
 }
@@ -239,12 +524,24 @@ pub struct Results {
     pub config: SimulatorConfig,
     /// Recorded events and states generated during simulation.
     pub records: Vec<Record>,
+    /// Annotations of the run, sorted by time.
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Fleet-level records, empty if the idleness is not enabled.
+    #[serde(default)]
+    pub fleet_records: Vec<FleetRecord>,
+    /// Mission summary of the run, per node and for the fleet.
+    #[serde(default)]
+    pub mission: MissionSummary,
 }
 
 #[derive(Clone)]
 pub(super) struct ResultSavingData {
     pub save_mode: ResultSaveMode,
     pub first_row: bool,
+    /// Size of the JSON result file at the end of the records array, before the trailer
+    /// (annotations, fleet records and mission) written when the run is finalized.
+    pub records_end: Option<u64>,
 }
 
 impl Default for ResultSavingData {
@@ -252,6 +549,7 @@ impl Default for ResultSavingData {
         Self {
             save_mode: ResultSaveMode::default(),
             first_row: true,
+            records_end: None,
         }
     }
 }
@@ -0,0 +1,286 @@
+//! Robot templates, replicated into several robots at load time.
+//!
+//! A [`RobotTemplateConfig`] is a [`RobotConfig`] with a number of copies: the `{i}` in the name
+//! of the robot is replaced by the index of the copy (from 1), and the initial pose of each copy
+//! is given by the [`RobotPlacementConfig`]. The templates of
+//! [`SimulatorConfig::robot_templates`](super::SimulatorConfig::robot_templates) are expanded into
+//! the `robots` list when the configuration is loaded, see
+//! [`SimulatorConfig::expand_robot_templates`](super::SimulatorConfig::expand_robot_templates).
+
+use std::f32::consts::PI;
+
+use simba_macros::config_derives;
+
+use crate::{node::node_factory::RobotConfig, physics::PhysicsConfig, utils::geometry::mod2pi};
+
+/// Placeholder of the index of the copy in the name of a template robot.
+pub const TEMPLATE_INDEX_PLACEHOLDER: &str = "{i}";
+
+/// Robots placed on a grid, filled row by row.
+///
+/// Default values:
+/// - `origin`: `[0., 0.]`
+/// - `spacing`: `[1., 1.]`
+/// - `columns`: `5`
+/// - `orientation`: `0.`
+#[config_derives]
+pub struct GridPlacementConfig {
+    /// Position `[x, y]` of the first robot.
+    pub origin: [f32; 2],
+    /// Distance between two columns (along x) and two rows (along y), in meters.
+    pub spacing: [f32; 2],
+    /// Number of robots per row.
+    pub columns: usize,
+    /// Orientation of all the robots, in radians.
+    pub orientation: f32,
+}
+
+impl Default for GridPlacementConfig {
+    fn default() -> Self {
+        Self {
+            origin: [0., 0.],
+            spacing: [1., 1.],
+            columns: 5,
+            orientation: 0.,
+        }
+    }
+}
+
+impl Check for GridPlacementConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.columns == 0 {
+            return Err(vec!["Grid placement needs at least one column".to_string()]);
+        }
+        Ok(())
+    }
+}
+
+/// Robots evenly spread on a circle, facing its center. The first robot is on the x axis of the
+/// center.
+///
+/// Default values:
+/// - `center`: `[0., 0.]`
+/// - `radius`: `5.`
+#[config_derives]
+pub struct CirclePlacementConfig {
+    /// Center `[x, y]` of the circle.
+    pub center: [f32; 2],
+    /// Radius of the circle, in meters.
+    pub radius: f32,
+}
+
+impl Default for CirclePlacementConfig {
+    fn default() -> Self {
+        Self {
+            center: [0., 0.],
+            radius: 5.,
+        }
+    }
+}
+
+impl Check for CirclePlacementConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.radius <= 0. {
+            return Err(vec![format!(
+                "Circle placement radius should be strictly positive, got {}",
+                self.radius
+            )]);
+        }
+        Ok(())
+    }
+}
+
+/// Initial pose of the copies of a template robot.
+///
+/// The placements other than `Fixed` replace the `initial_state.pose` of the internal physics.
+/// The `random` part of the initial state is kept, to add noise to each pose.
+///
+/// Default value: [`RobotPlacementConfig::Fixed`].
+///
+/// # Example
+/// ```yaml
+/// placement:
+///   type: Grid
+///   origin: [0., 0.]
+///   spacing: [2., 2.]
+///   columns: 10
+///   orientation: 0.
+/// ```
+#[config_derives]
+pub enum RobotPlacementConfig {
+    /// All the copies keep the initial state of the template.
+    Fixed,
+    /// Copies placed on a grid.
+    #[check]
+    Grid(GridPlacementConfig),
+    /// Copies placed on a circle.
+    #[check]
+    Circle(CirclePlacementConfig),
+}
+
+impl Default for RobotPlacementConfig {
+    fn default() -> Self {
+        Self::Fixed
+    }
+}
+
+impl RobotPlacementConfig {
+    /// Pose `[x, y, orientation]` of the copy `index` (from 0) among `count`, `None` to keep the
+    /// pose of the template.
+    pub fn pose(&self, index: usize, count: usize) -> Option<[f32; 3]> {
+        match self {
+            Self::Fixed => None,
+            Self::Grid(c) => {
+                let (row, column) = (index / c.columns, index % c.columns);
+                Some([
+                    c.origin[0] + column as f32 * c.spacing[0],
+                    c.origin[1] + row as f32 * c.spacing[1],
+                    c.orientation,
+                ])
+            }
+            Self::Circle(c) => {
+                let angle = 2. * PI * index as f32 / count as f32;
+                Some([
+                    c.center[0] + c.radius * angle.cos(),
+                    c.center[1] + c.radius * angle.sin(),
+                    mod2pi(angle + PI),
+                ])
+            }
+        }
+    }
+}
+
+/// Template of `count` robots sharing the same configuration.
+///
+/// Default values:
+/// - `robot`: default [`RobotConfig`], named `robot{i}`
+/// - `count`: `1`
+/// - `placement`: [`RobotPlacementConfig::Fixed`]
+///
+/// # Example
+/// ```yaml
+/// robot_templates:
+///   - count: 50
+///     placement:
+///       type: Circle
+///       center: [0., 0.]
+///       radius: 20.
+///     robot:
+///       name: robot_{i} # robot_1, robot_2, ..., robot_50
+///       navigator:
+///         type: GoTo
+///         target_point: [0., 0.]
+///       # ... Same fields as the robots list
+/// ```
+#[config_derives]
+pub struct RobotTemplateConfig {
+    /// Configuration of the robots. `{i}` in the name is replaced by the index of the copy, from 1.
+    #[check]
+    pub robot: RobotConfig,
+    /// Number of robots made from the template.
+    pub count: usize,
+    /// Initial pose of each robot.
+    #[check]
+    pub placement: RobotPlacementConfig,
+}
+
+impl Default for RobotTemplateConfig {
+    fn default() -> Self {
+        Self {
+            robot: RobotConfig {
+                name: format!("robot{TEMPLATE_INDEX_PLACEHOLDER}"),
+                ..Default::default()
+            },
+            count: 1,
+            placement: RobotPlacementConfig::default(),
+        }
+    }
+}
+
+impl Check for RobotTemplateConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.count > 1 && !self.robot.name.contains(TEMPLATE_INDEX_PLACEHOLDER) {
+            errors.push(format!(
+                "Name '{}' of a template of {} robots should contain '{TEMPLATE_INDEX_PLACEHOLDER}'",
+                self.robot.name, self.count
+            ));
+        }
+        if !matches!(self.placement, RobotPlacementConfig::Fixed)
+            && !matches!(self.robot.physics, PhysicsConfig::Internal(_))
+        {
+            errors.push(format!(
+                "{} placement needs an Internal physics, got {}",
+                self.placement, self.robot.physics
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl RobotTemplateConfig {
+    /// Name of the copy `index` (from 0).
+    pub fn robot_name(&self, index: usize) -> String {
+        self.robot
+            .name
+            .replace(TEMPLATE_INDEX_PLACEHOLDER, &(index + 1).to_string())
+    }
+
+    /// Configurations of the `count` robots of the template.
+    pub fn expand(&self) -> Vec<RobotConfig> {
+        (0..self.count)
+            .map(|index| {
+                let mut robot = self.robot.clone();
+                robot.name = self.robot_name(index);
+                if let Some(pose) = self.placement.pose(index, self.count)
+                    && let PhysicsConfig::Internal(physics) = &mut robot.physics
+                {
+                    physics.initial_state.pose = pose.to_vec();
+                }
+                robot
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_on_circle() {
+        let template = RobotTemplateConfig {
+            robot: RobotConfig {
+                name: "robot_{i}".to_string(),
+                ..Default::default()
+            },
+            count: 4,
+            placement: RobotPlacementConfig::Circle(CirclePlacementConfig {
+                center: [1., 0.],
+                radius: 2.,
+            }),
+        };
+        assert!(template.check().is_ok());
+        let robots = template.expand();
+        assert_eq!(robots.len(), 4);
+        assert_eq!(robots[0].name, "robot_1");
+        assert_eq!(robots[3].name, "robot_4");
+        let PhysicsConfig::Internal(physics) = &robots[1].physics else {
+            panic!("Internal physics expected");
+        };
+        let pose = &physics.initial_state.pose;
+        assert!((pose[0] - 1.).abs() < 1e-5);
+        assert!((pose[1] - 2.).abs() < 1e-5);
+        assert!((pose[2] + PI / 2.).abs() < 1e-5);
+
+        let unnamed = RobotTemplateConfig {
+            robot: RobotConfig::default(),
+            ..template
+        };
+        assert!(unnamed.check().is_err());
+    }
+}
@@ -9,18 +9,29 @@ use egui::CollapsingHeader;
 use simba_macros::config_derives;
 #[cfg(feature = "gui")]
 use std::collections::BTreeMap;
-use std::path::Path;
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use crate::{
     VERSION,
     environment::EnvironmentConfig,
     errors::{SimbaError, SimbaErrorTypes, SimbaResult},
     logger::LoggerConfig,
+    networking::network_manager::{ChannelLimitsConfig, MessageRetentionConfig},
     node::node_factory::{ComputationUnitConfig, RobotConfig},
     scenario::config::ScenarioConfig,
-    simulator::ResultConfig,
+    simulator::{IdlenessConfig, ResultConfig, RobotTemplateConfig},
     time_analysis::TimeAnalysisConfig,
-    utils::{self, format_option_f32},
+    utils::{
+        config_parsing::{
+            ConfigLoadMode, ConfigLocation, ConfigSourceMap, field_path,
+            load_config_file_with_overrides,
+        },
+        format_option_f32,
+    },
 };
 
 #[cfg(feature = "gui")]
@@ -36,6 +47,7 @@ use crate::{
 /// ```yaml
 /// version: 1.6.0
 /// max_time: 10.0
+/// realtime_factor: null # As fast as possible
 /// log:
 ///   log_level:
 ///     type: Info
@@ -102,6 +114,12 @@ pub struct SimulatorConfig {
     pub base_path: Box<Path>,
     /// Maximum simulated time before stopping the run.
     pub max_time: f32,
+    /// Pace the run on the wall-clock time: simulated seconds per wall-clock second (`1.0` for
+    /// realtime, `2.0` for twice faster). Useful to couple the simulator with external hardware or
+    /// a live GUI. A run slower than this factor is not affected.
+    ///
+    /// If `None` (default), the simulator runs as fast as possible.
+    pub realtime_factor: Option<f32>,
     #[check]
     /// Time-analysis/profiling configuration.
     pub time_analysis: Option<TimeAnalysisConfig>,
@@ -111,6 +129,10 @@ pub struct SimulatorConfig {
     /// List of the robots to run, with their specific configuration.
     #[check]
     pub robots: Vec<RobotConfig>,
+    /// Templates replicated into several robots, added to `robots` when the configuration is
+    /// loaded (see [`expand_robot_templates`](SimulatorConfig::expand_robot_templates)).
+    #[check]
+    pub robot_templates: Vec<RobotTemplateConfig>,
     /// List of computation units to run, with their specific configuration.
     #[check]
     pub computation_units: Vec<ComputationUnitConfig>,
@@ -120,6 +142,19 @@ pub struct SimulatorConfig {
     /// Global environment settings (maps).
     #[check]
     pub environment: EnvironmentConfig,
+    /// Retention of the messages waiting for slow subscribers.
+    #[check]
+    pub message_retention: MessageRetentionConfig,
+    /// Bandwidth and message rate limits of the channels.
+    #[check]
+    pub channel_limits: Vec<ChannelLimitsConfig>,
+    /// Fleet idleness of patrolled waypoints, recorded in the fleet records. Disabled if `None`.
+    #[check]
+    pub idleness: Option<IdlenessConfig>,
+    /// Locations of the fields in the configuration file, filled by
+    /// [`load_from_path`](SimulatorConfig::load_from_path).
+    #[serde(skip)]
+    pub source_map: ConfigSourceMap,
 }
 
 impl Default for SimulatorConfig {
@@ -133,10 +168,53 @@ impl Default for SimulatorConfig {
             time_analysis: Some(TimeAnalysisConfig::default()),
             random_seed: None,
             robots: Vec::new(),
+            robot_templates: Vec::new(),
             computation_units: Vec::new(),
             max_time: 60.,
+            realtime_factor: None,
             scenario: ScenarioConfig::default(),
             environment: EnvironmentConfig::default(),
+            message_retention: MessageRetentionConfig::default(),
+            channel_limits: Vec::new(),
+            idleness: None,
+            source_map: ConfigSourceMap::default(),
+        }
+    }
+}
+
+impl Check for SimulatorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Some(factor) = self.realtime_factor
+            && factor <= 0.
+        {
+            errors.push(format!(
+                "Realtime factor should be strictly positive, got {}",
+                factor
+            ));
+        }
+        for (i, limits) in self.channel_limits.iter().enumerate() {
+            if self.channel_limits[..i]
+                .iter()
+                .any(|other| other.channel == limits.channel)
+            {
+                errors.push(format!("Channel '{}' is limited twice", limits.channel));
+            }
+        }
+        let mut names = BTreeSet::new();
+        for name in self.robots.iter().map(|robot| robot.name.clone()).chain(
+            self.robot_templates
+                .iter()
+                .flat_map(|template| (0..template.count).map(|i| template.robot_name(i))),
+        ) {
+            if !names.insert(name.clone()) {
+                errors.push(format!("Robot name '{name}' is used twice"));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
     }
 }
@@ -146,38 +224,47 @@ impl SimulatorConfig {
     ///
     /// This method also resolves `base_path` from the parent directory of the
     /// input path and expands time-analysis output paths accordingly.
+    ///
+    /// The `include` fields of the file are resolved (see
+    /// [`config_parsing`](crate::utils::config_parsing)): the relative paths of the included
+    /// content (maps, results...) stay relative to the main configuration file.
     pub fn load_from_path(path: &Path) -> SimbaResult<Self> {
-        let mut config: serde_yaml::Value = match confy::load_path(path) {
-            Ok(config) => config,
-            Err(error) => {
-                let what = format!(
-                    "Error from Confy while loading the config file : {}",
-                    utils::confy::detailed_error(&error)
-                );
-                println!("ERROR: {what}");
-                return Err(SimbaError::new(SimbaErrorTypes::ConfigError, what));
-            }
-        };
-        config.apply_merge().map_err(|e| {
-            let what = format!("Error from SerdeYAML while merging YAML tags: {}", e);
-            println!("ERROR: {what}");
-            SimbaError::new(SimbaErrorTypes::ConfigError, what)
-        })?;
-        let mut config: SimulatorConfig = match serde_yaml::from_value(config) {
-            Ok(c) => c,
-            Err(e) => {
-                let what = format!(
-                    "Error from SerdeYAML while loading SimulatorConfig{}: {}",
-                    match e.location() {
-                        Some(loc) => format!(" (line {}, column {})", loc.line(), loc.column()),
-                        None => String::new(),
-                    },
-                    e
-                );
-                println!("ERROR: {what}");
-                return Err(SimbaError::new(SimbaErrorTypes::ConfigError, what));
-            }
-        };
+        Self::load_from_path_with_mode(path, ConfigLoadMode::Strict).map(|(config, _)| config)
+    }
+
+    /// Load a simulator configuration from a YAML file path, in the given `mode`.
+    ///
+    /// Returns the configuration and the issues ignored in [`ConfigLoadMode::Permissive`]
+    /// (unknown fields), with their location.
+    pub fn load_from_path_with_mode(
+        path: &Path,
+        mode: ConfigLoadMode,
+    ) -> SimbaResult<(Self, Vec<String>)> {
+        Self::load_from_path_with_overrides(path, &[], mode)
+    }
+
+    /// Load a simulator configuration from a YAML file path, in the given `mode`, with the
+    /// `overrides` files deep-merged on top of it, in order.
+    ///
+    /// Experiments can change a few fields of a shared configuration:
+    /// ```yaml
+    /// # extra.yaml
+    /// max_time: 120.
+    /// random_seed: 42
+    /// ```
+    pub fn load_from_path_with_overrides(
+        path: &Path,
+        overrides: &[PathBuf],
+        mode: ConfigLoadMode,
+    ) -> SimbaResult<(Self, Vec<String>)> {
+        let loaded =
+            load_config_file_with_overrides::<SimulatorConfig>(path, overrides, "config", mode)
+                .inspect_err(|e| {
+                    println!("ERROR: {}", e.detailed_error());
+                })?;
+        let mut config = loaded.config;
+        config.source_map = loaded.source_map;
+        config.expand_robot_templates()?;
 
         config.base_path = Box::from(path.parent().unwrap());
         if let Some(time_analysis) = &mut config.time_analysis {
@@ -190,7 +277,197 @@ impl SimulatorConfig {
                 .to_string();
         }
 
-        Ok(config)
+        Ok((config, loaded.warnings))
+    }
+
+    /// Replace the [`robot_templates`](SimulatorConfig::robot_templates) by the robots they make,
+    /// appended to [`robots`](SimulatorConfig::robots) in order.
+    ///
+    /// Called when the configuration is loaded; a configuration without templates is not changed.
+    /// Returns an error if a template fails its check.
+    pub fn expand_robot_templates(&mut self) -> SimbaResult<()> {
+        for (i, template) in self.robot_templates.iter().enumerate() {
+            if let Err(e) = template.check() {
+                return Err(SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    self.source_map
+                        .annotate(&format!("robot_templates[{i}]"), e.to_string()),
+                ));
+            }
+        }
+        for template in std::mem::take(&mut self.robot_templates) {
+            self.robots.extend(template.expand());
+        }
+        Ok(())
+    }
+
+    /// Compare the version of the configuration with the version of the simulator.
+    ///
+    /// Returns a warning for a configuration of another version (major and minor numbers), and an
+    /// error for a version which cannot be read.
+    pub fn check_version(&self) -> Result<Option<String>, String> {
+        let mut numbers = self.version.split('.').map(|n| n.trim().parse::<usize>());
+        let config_version = match (numbers.next(), numbers.next()) {
+            (Some(Ok(major)), Some(Ok(minor))) => (major, minor),
+            _ => {
+                return Err(format!(
+                    "Version is expected to be XX.YY at least, got '{}'",
+                    self.version
+                ));
+            }
+        };
+        let software_version: (usize, usize) = (
+            env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap(),
+            env!("CARGO_PKG_VERSION_MINOR").parse().unwrap(),
+        );
+        if config_version > software_version {
+            Ok(Some(format!(
+                "Config version ({}) is newer than the software version ({}): some features may \
+                 not be supported",
+                self.version, VERSION
+            )))
+        } else if config_version < software_version {
+            Ok(Some(format!(
+                "Config major version ({}) differs from software version ({})",
+                self.version, VERSION
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Location in the configuration file of the field at the dotted `path` (e.g.
+    /// `robots[0].physics`), when the configuration was loaded from a file.
+    pub fn source_location(&self, path: &str) -> Option<ConfigLocation> {
+        self.source_map.location(path)
+    }
+
+    /// Check the configuration, with the location in the file of each error.
+    ///
+    /// The errors are reported at the most precise sub-configuration failing its check: the
+    /// modules of the nodes, the scenario, the environment, ...
+    pub fn check_located(&self) -> Result<(), String> {
+        if self.check().is_ok() {
+            return Ok(());
+        }
+        let mut errors = Vec::new();
+        let mut push = |path: &str, result: Result<(), _>| match result {
+            Ok(()) => false,
+            Err(e) => {
+                errors.push(self.source_map.annotate(path, e));
+                true
+            }
+        };
+        let mut failed = push("log", self.log.check().map_err(|e| e.to_string()));
+        if let Some(results) = &self.results {
+            failed |= push("results", results.check().map_err(|e| e.to_string()));
+        }
+        if let Some(time_analysis) = &self.time_analysis {
+            failed |= push(
+                "time_analysis",
+                time_analysis.check().map_err(|e| e.to_string()),
+            );
+        }
+        for (i, event) in self.scenario.events.iter().enumerate() {
+            failed |= push(
+                &format!("scenario.events[{i}]"),
+                event.check().map_err(|e| e.to_string()),
+            );
+        }
+        failed |= push(
+            "environment",
+            self.environment.check().map_err(|e| e.to_string()),
+        );
+        failed |= push(
+            "message_retention",
+            self.message_retention.check().map_err(|e| e.to_string()),
+        );
+        for (i, limits) in self.channel_limits.iter().enumerate() {
+            failed |= push(
+                &format!("channel_limits[{i}]"),
+                limits.check().map_err(|e| e.to_string()),
+            );
+        }
+        if let Some(idleness) = &self.idleness {
+            failed |= push("idleness", idleness.check().map_err(|e| e.to_string()));
+        }
+        for (i, robot) in self.robots.iter().enumerate() {
+            let path = format!("robots[{i}]");
+            let mut robot_failed = false;
+            let mut push_module = |module: &str, result: Result<(), String>| {
+                robot_failed |= push(&format!("{path}.{module}"), result);
+            };
+            push_module(
+                "navigator",
+                robot.navigator.check().map_err(|e| e.to_string()),
+            );
+            push_module(
+                "controller",
+                robot.controller.check().map_err(|e| e.to_string()),
+            );
+            push_module("physics", robot.physics.check().map_err(|e| e.to_string()));
+            push_module(
+                "state_estimator",
+                robot.state_estimator.check().map_err(|e| e.to_string()),
+            );
+            for (j, sensor) in robot.sensor_manager.sensors.iter().enumerate() {
+                push_module(
+                    &format!("sensor_manager.sensors[{j}]"),
+                    sensor.check().map_err(|e| e.to_string()),
+                );
+            }
+            push_module("network", robot.network.check().map_err(|e| e.to_string()));
+            for (j, bench) in robot.state_estimator_bench.iter().enumerate() {
+                push_module(
+                    &format!("state_estimator_bench[{j}]"),
+                    bench.check().map_err(|e| e.to_string()),
+                );
+            }
+            if !robot_failed {
+                robot_failed = push(&path, robot.check().map_err(|e| e.to_string()));
+            }
+            failed |= robot_failed;
+        }
+        for (i, template) in self.robot_templates.iter().enumerate() {
+            failed |= push(
+                &format!("robot_templates[{i}]"),
+                template.check().map_err(|e| e.to_string()),
+            );
+        }
+        for (i, unit) in self.computation_units.iter().enumerate() {
+            let path = format!("computation_units[{i}]");
+            let mut unit_failed = push(
+                &format!("{path}.network"),
+                unit.network.check().map_err(|e| e.to_string()),
+            );
+            for (j, bench) in unit.state_estimators.iter().enumerate() {
+                unit_failed |= push(
+                    &format!("{path}.state_estimators[{j}]"),
+                    bench.check().map_err(|e| e.to_string()),
+                );
+            }
+            if !unit_failed {
+                unit_failed = push(&path, unit.check().map_err(|e| e.to_string()));
+            }
+            failed |= unit_failed;
+        }
+        if !failed {
+            // Error of the root fields (e.g. `realtime_factor`)
+            push("", self.check().map_err(|e| e.to_string()));
+        }
+        Err(errors.join("\n"))
+    }
+
+    /// Error message about the configuration of a plugin, located in the configuration file.
+    ///
+    /// `config` is the configuration given to the [`PluginAPI`](crate::plugin_api::PluginAPI):
+    /// a reference into this configuration, located by its address. A configuration from
+    /// elsewhere (e.g. a copy) has no location.
+    pub fn plugin_config_error(&self, config: &serde_json::Value, error: impl Display) -> String {
+        match field_path(self, config) {
+            Some(path) => self.source_map.annotate(&path, error),
+            None => error.to_string(),
+        }
     }
 }
 
@@ -255,6 +532,21 @@ impl crate::gui::UIComponent for SimulatorConfig {
                 ui.add(egui::DragValue::new(&mut self.max_time).max_decimals(TIME_ROUND_DECIMALS));
             });
 
+            ui.horizontal(|ui| {
+                let mut enabled = self.realtime_factor.is_some();
+                ui.checkbox(&mut enabled, "Realtime factor: ");
+                if enabled {
+                    let factor = self.realtime_factor.get_or_insert(1.);
+                    ui.add(
+                        egui::DragValue::new(factor)
+                            .speed(0.1)
+                            .range(0.001..=f32::MAX),
+                    );
+                } else {
+                    self.realtime_factor = None;
+                }
+            });
+
             ui.horizontal_top(|ui| {
                 if let Some(time_analysis) = &mut self.time_analysis {
                     time_analysis.show_mut(
@@ -288,6 +580,61 @@ impl crate::gui::UIComponent for SimulatorConfig {
                 );
             });
 
+            self.message_retention.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            );
+
+            CollapsingHeader::new("Channel limits").show(ui, |ui| {
+                let mut remove = None;
+                for (i, limits) in self.channel_limits.iter_mut().enumerate() {
+                    ui.horizontal_top(|ui| {
+                        limits.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            &format!("{unique_id}-channel-limits-{i}"),
+                        );
+                        if ui.button("X").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    self.channel_limits.remove(i);
+                }
+                if ui.button("Add").clicked() {
+                    self.channel_limits.push(ChannelLimitsConfig::default());
+                }
+            });
+
+            ui.horizontal_top(|ui| {
+                if let Some(idleness) = &mut self.idleness {
+                    idleness.show_mut(
+                        ui,
+                        ctx,
+                        buffer_stack,
+                        global_config,
+                        current_node_name,
+                        unique_id,
+                    );
+                    if ui.button("X").clicked() {
+                        self.idleness = None;
+                    }
+                } else {
+                    ui.label("Idleness: ");
+                    if ui.button("+").clicked() {
+                        self.idleness = Some(IdlenessConfig::default());
+                    }
+                }
+            });
+
             ui.vertical(|ui| {
                 ui.label("Robots:");
                 let mut remove = None;
@@ -359,6 +706,15 @@ impl crate::gui::UIComponent for SimulatorConfig {
                 ui.label(format!("{}", self.max_time));
             });
 
+            ui.horizontal(|ui| {
+                ui.label("Realtime factor: ");
+                if let Some(factor) = self.realtime_factor {
+                    ui.label(format!("{}", factor));
+                } else {
+                    ui.label("As fast as possible");
+                }
+            });
+
             ui.horizontal(|ui| {
                 if let Some(time_analysis) = &self.time_analysis {
                     time_analysis.show(ui, ctx, unique_id);
@@ -372,6 +728,20 @@ impl crate::gui::UIComponent for SimulatorConfig {
                 self.environment.show(ui, ctx, unique_id);
             });
 
+            self.message_retention.show(ui, ctx, unique_id);
+
+            if !self.channel_limits.is_empty() {
+                CollapsingHeader::new("Channel limits").show(ui, |ui| {
+                    for limits in &self.channel_limits {
+                        limits.show(ui, ctx, unique_id);
+                    }
+                });
+            }
+
+            if let Some(idleness) = &self.idleness {
+                idleness.show(ui, ctx, unique_id);
+            }
+
             ui.vertical(|ui| {
                 ui.label("Robots:");
                 for r in &self.robots {
@@ -0,0 +1,346 @@
+/*!
+Snapshot diff: capture the full records of all the nodes at a given time, and compare two
+snapshots field by field.
+
+A [`Snapshot`] holds, for each node, the last record at or before the snapshot time. Two
+snapshots (e.g. of two runs which should be identical) are compared with [`diff_snapshots`],
+which lists every differing field with its node, its module (first field of the node record,
+e.g. `navigator` or `state_estimator`) and its path in the record.
+
+It is used by the determinism self-check ([`Simulator::determinism_check`](super::Simulator::determinism_check)) to localize the
+divergence at the time of the first diverging record.
+*/
+
+use std::{collections::BTreeMap, fmt::Display, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::{SimbaError, SimbaErrorTypes, SimbaResult};
+
+use super::{Record, TIME_ROUND};
+
+/// Serialized records of all the nodes at a given time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Time of the snapshot.
+    pub time: f32,
+    /// Last record of each node at or before `time`, serialized, by node name.
+    pub nodes: BTreeMap<String, Value>,
+}
+
+impl Snapshot {
+    /// Capture the snapshot at `time` from a list of records (not necessarily sorted).
+    pub fn from_records(records: &[Record], time: f32) -> SimbaResult<Self> {
+        let mut latest: BTreeMap<String, &Record> = BTreeMap::new();
+        for record in records {
+            if record.time > time + TIME_ROUND {
+                continue;
+            }
+            let name = record.node.name();
+            if latest.get(name).is_none_or(|r| r.time <= record.time) {
+                latest.insert(name.clone(), record);
+            }
+        }
+        let mut nodes = BTreeMap::new();
+        for (name, record) in latest {
+            let value = serde_json::to_value(&record.node).map_err(|e| {
+                SimbaError::new(
+                    SimbaErrorTypes::ImplementationError,
+                    format!("Error during json serialization of a record: {e}"),
+                )
+            })?;
+            nodes.insert(name, value);
+        }
+        Ok(Self { time, nodes })
+    }
+
+    /// Save the snapshot as JSON.
+    pub fn save(&self, path: &Path) -> SimbaResult<()> {
+        let content = serde_json::to_string(self).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ImplementationError,
+                format!("Error during json serialization of the snapshot: {e}"),
+            )
+        })?;
+        fs::write(path, content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to write snapshot file '{}': {}",
+                    path.to_str().unwrap_or_default(),
+                    e
+                ),
+            )
+        })
+    }
+
+    /// Load a snapshot saved with [`Snapshot::save`].
+    pub fn load(path: &Path) -> SimbaResult<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to read snapshot file '{}': {}",
+                    path.to_str().unwrap_or_default(),
+                    e
+                ),
+            )
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!("Error while parsing snapshot: {e}"),
+            )
+        })
+    }
+}
+
+/// One differing field between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDifference {
+    /// Name of the node.
+    pub node: String,
+    /// Module of the node record (first field), `None` if the whole record differs
+    /// (missing node or different node type).
+    pub module: Option<String>,
+    /// Path of the field in the module, e.g. `state.pose[2]`. Empty for the whole module.
+    pub path: String,
+    /// Value in the reference snapshot, `None` if missing.
+    pub reference: Option<Value>,
+    /// Value in the other snapshot, `None` if missing.
+    pub other: Option<Value>,
+}
+
+/// Structured difference between two snapshots.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDiff {
+    /// Time of the snapshots.
+    pub time: f32,
+    /// Differing fields, ordered by node, module and path.
+    pub differences: Vec<FieldDifference>,
+}
+
+impl SnapshotDiff {
+    /// Return whether both snapshots are identical.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// List of the (node, module) pairs which differ, without duplicates.
+    pub fn diverging_modules(&self) -> Vec<(String, Option<String>)> {
+        let mut modules: Vec<(String, Option<String>)> = Vec::new();
+        for difference in &self.differences {
+            let key = (difference.node.clone(), difference.module.clone());
+            if modules.last() != Some(&key) {
+                modules.push(key);
+            }
+        }
+        modules
+    }
+}
+
+impl Display for SnapshotDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let to_string = |value: &Option<Value>| match value {
+            Some(v) => v.to_string(),
+            None => String::from("missing"),
+        };
+        if self.is_empty() {
+            return writeln!(f, "Snapshots at time {} are identical", self.time);
+        }
+        writeln!(
+            f,
+            "Snapshot diff at time {} ({} differences):",
+            self.time,
+            self.differences.len()
+        )?;
+        let mut current: Option<(&String, &Option<String>)> = None;
+        for difference in &self.differences {
+            if current != Some((&difference.node, &difference.module)) {
+                writeln!(
+                    f,
+                    "\t{} / {}:",
+                    difference.node,
+                    difference.module.as_deref().unwrap_or("whole record")
+                )?;
+                current = Some((&difference.node, &difference.module));
+            }
+            writeln!(
+                f,
+                "\t\t{}: {} != {}",
+                if difference.path.is_empty() {
+                    "."
+                } else {
+                    &difference.path
+                },
+                to_string(&difference.reference),
+                to_string(&difference.other)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Recursively compare two values, and push the differing leaves.
+fn diff_values(
+    reference: Option<&Value>,
+    other: Option<&Value>,
+    path: String,
+    node: &str,
+    module: &str,
+    differences: &mut Vec<FieldDifference>,
+) {
+    match (reference, other) {
+        (Some(Value::Object(r)), Some(Value::Object(o))) => {
+            for (key, value) in r {
+                diff_values(
+                    Some(value),
+                    o.get(key),
+                    join_path(&path, key),
+                    node,
+                    module,
+                    differences,
+                );
+            }
+            for (key, value) in o.iter().filter(|(key, _)| !r.contains_key(*key)) {
+                diff_values(
+                    None,
+                    Some(value),
+                    join_path(&path, key),
+                    node,
+                    module,
+                    differences,
+                );
+            }
+        }
+        (Some(Value::Array(r)), Some(Value::Array(o))) => {
+            for i in 0..r.len().max(o.len()) {
+                diff_values(
+                    r.get(i),
+                    o.get(i),
+                    format!("{path}[{i}]"),
+                    node,
+                    module,
+                    differences,
+                );
+            }
+        }
+        (r, o) => {
+            if r != o {
+                differences.push(FieldDifference {
+                    node: node.to_string(),
+                    module: Some(module.to_string()),
+                    path,
+                    reference: r.cloned(),
+                    other: o.cloned(),
+                });
+            }
+        }
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Return the node type and the modules of a serialized node record.
+///
+/// Node records are serialized as `{"Robot": {"navigator": ..., ...}}`.
+fn node_modules(value: &Value) -> Option<(&String, &serde_json::Map<String, Value>)> {
+    match value {
+        Value::Object(variant) if variant.len() == 1 => {
+            let (node_type, modules) = variant.iter().next().unwrap();
+            modules.as_object().map(|modules| (node_type, modules))
+        }
+        _ => None,
+    }
+}
+
+/// Compare two snapshots field by field.
+///
+/// The time of the diff is the time of the reference snapshot.
+pub fn diff_snapshots(reference: &Snapshot, other: &Snapshot) -> SnapshotDiff {
+    let mut differences = Vec::new();
+    let node_names: std::collections::BTreeSet<&String> =
+        reference.nodes.keys().chain(other.nodes.keys()).collect();
+    for node in node_names {
+        let reference_node = reference.nodes.get(node);
+        let other_node = other.nodes.get(node);
+        if reference_node == other_node {
+            continue;
+        }
+        match (
+            reference_node.and_then(node_modules),
+            other_node.and_then(node_modules),
+        ) {
+            (Some((r_type, r_modules)), Some((o_type, o_modules))) if r_type == o_type => {
+                let modules: std::collections::BTreeSet<&String> =
+                    r_modules.keys().chain(o_modules.keys()).collect();
+                for module in modules {
+                    diff_values(
+                        r_modules.get(module),
+                        o_modules.get(module),
+                        String::new(),
+                        node,
+                        module,
+                        &mut differences,
+                    );
+                }
+            }
+            _ => differences.push(FieldDifference {
+                node: node.clone(),
+                module: None,
+                path: String::new(),
+                reference: reference_node.cloned(),
+                other: other_node.cloned(),
+            }),
+        }
+    }
+    SnapshotDiff {
+        time: reference.time,
+        differences,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn diff_snapshots_per_module_and_field() {
+        let reference = Snapshot {
+            time: 1.,
+            nodes: BTreeMap::from([
+                (
+                    "robot1".to_string(),
+                    json!({"Robot": {"name": "robot1", "navigator": {"error": [0.1, 0.2]}, "controller": {"command": 1.}}}),
+                ),
+                ("robot2".to_string(), json!({"Robot": {"name": "robot2"}})),
+            ]),
+        };
+        assert!(diff_snapshots(&reference, &reference).is_empty());
+
+        let mut other = reference.clone();
+        other.nodes.insert(
+            "robot1".to_string(),
+            json!({"Robot": {"name": "robot1", "navigator": {"error": [0.1, 0.3]}, "controller": {"command": 1.}}}),
+        );
+        other.nodes.remove("robot2");
+        let diff = diff_snapshots(&reference, &other);
+        assert_eq!(diff.differences.len(), 2);
+        assert_eq!(diff.differences[0].node, "robot1");
+        assert_eq!(diff.differences[0].module.as_deref(), Some("navigator"));
+        assert_eq!(diff.differences[0].path, "error[1]");
+        assert_eq!(diff.differences[0].other, Some(json!(0.3)));
+        assert_eq!(diff.differences[1].node, "robot2");
+        assert!(diff.differences[1].module.is_none());
+        assert!(diff.differences[1].other.is_none());
+    }
+}
@@ -0,0 +1,110 @@
+//! Low-rate summary of a run, for lightweight dashboards.
+//!
+//! The full record stream of the async API gives every record of every node. Dashboards only
+//! showing the progress of the run can instead read the [`AggregateStatistics`] stream
+//! ([`SimulatorAsyncApi::statistics`](super::SimulatorAsyncApi::statistics)): the simulator
+//! publishes a summary (time, position of the nodes, key indicators) at the end of the time steps,
+//! at most once per statistics period of wall-clock time
+//! ([`Simulator::set_statistics_period`](super::Simulator::set_statistics_period)).
+
+use std::{collections::HashMap, time::Duration};
+
+use serde::Serialize;
+
+use crate::{
+    node::{NodeMetaData, NodeState},
+    utils::{SharedRoLock, lock_recovery::RecoverRoLock},
+};
+
+use super::{FleetStatus, MemoryUsage};
+
+/// Default minimal wall-clock duration between two statistics.
+pub const DEFAULT_STATISTICS_PERIOD: Duration = Duration::from_secs(1);
+
+/// Summary of a node in the [`AggregateStatistics`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeSnapshot {
+    /// Name of the node.
+    pub name: String,
+    /// Lifecycle state of the node.
+    pub state: NodeState,
+    /// Ground-truth planar position, `None` for the nodes without physics.
+    pub position: Option<[f32; 2]>,
+    /// Number of time steps run by the node.
+    pub steps: usize,
+    /// Wall-clock duration of the last time step.
+    pub step_duration: Duration,
+}
+
+/// Summary of the run at a simulated time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AggregateStatistics {
+    /// Simulated time of the summary.
+    pub time: f32,
+    /// Nodes of the run, sorted by name.
+    pub nodes: Vec<NodeSnapshot>,
+    /// Number of running nodes.
+    pub running_nodes: usize,
+    /// Number of failed nodes.
+    pub failed_nodes: usize,
+    /// Number of records produced since the beginning of the run, including the released ones.
+    pub records: usize,
+    /// Approximate memory used by the run.
+    pub memory_usage: MemoryUsage,
+    /// Simulated time per wall-clock time since the beginning of the run.
+    pub real_time_factor: f32,
+    /// Mean wall-clock duration of the last time step of the running nodes.
+    pub mean_step_duration: Duration,
+}
+
+impl AggregateStatistics {
+    /// Gather the summary of the nodes from the fleet status and the meta-data.
+    pub(super) fn collect(
+        time: f32,
+        fleet_status: &FleetStatus,
+        meta_data: &HashMap<String, SharedRoLock<NodeMetaData>>,
+        records: usize,
+        memory_usage: MemoryUsage,
+        real_time_factor: f32,
+    ) -> Self {
+        let nodes: Vec<NodeSnapshot> = fleet_status
+            .list()
+            .into_iter()
+            .map(|heartbeat| NodeSnapshot {
+                position: meta_data
+                    .get(&heartbeat.node)
+                    .and_then(|meta_data| meta_data.read_recover().position),
+                name: heartbeat.node,
+                state: heartbeat.state,
+                steps: heartbeat.steps,
+                step_duration: heartbeat.step_duration,
+            })
+            .collect();
+        let running: Vec<&NodeSnapshot> = nodes
+            .iter()
+            .filter(|node| node.state == NodeState::Running)
+            .collect();
+        let mean_step_duration = if running.is_empty() {
+            Duration::ZERO
+        } else {
+            running
+                .iter()
+                .map(|node| node.step_duration)
+                .sum::<Duration>()
+                / running.len() as u32
+        };
+        Self {
+            time,
+            running_nodes: running.len(),
+            failed_nodes: nodes
+                .iter()
+                .filter(|node| node.state == NodeState::Failed)
+                .count(),
+            nodes,
+            records,
+            memory_usage,
+            real_time_factor,
+            mean_step_duration,
+        }
+    }
+}
@@ -0,0 +1,349 @@
+/*!
+Per time step snapshots of the world, for external renderers.
+
+The [`WorldSnapshots`] gather, in a simple JSON file, what is needed to replay a run in an
+external 3D renderer (Unity, Blender scripts...):
+- the static landmarks of the map (the obstacles), with their pose, width and height,
+- the models of the robots, with the radius of their footprint and their height,
+- for each time step, the real pose of the robots and the detections of the landmarks and robots
+  made by their sensors during this step.
+
+Only the robots with an internal physics (the ground truth) are exported. The poses of the
+detections are given in the world frame, computed from the real pose of the observer (and the
+pose of the sensor for the observations in the sensor frame), as well as in the frame of the
+observation, as given by the sensor (with its noise). The detections in the map frame are not
+transformed: they use the pose estimated by the observer.
+
+The snapshots are given by [`Simulator::compute_world_snapshots`](super::Simulator::compute_world_snapshots)
+after a run, and written when the results are computed if
+[`ResultConfig::world_snapshots`](super::ResultConfig::world_snapshots) is set.
+
+```yaml
+results:
+  world_snapshots:
+    snapshots_path: snapshots.json
+```
+
+Format of the file:
+```json
+{
+  "version": 1,
+  "landmarks": [{"id": 1, "labels": [], "pose": [1.0, 2.0, 0.0], "width": 0.0, "height": 1.0}],
+  "robot_models": [{"name": "robot1", "footprint_radius": 0.3, "height": 1.0}],
+  "steps": [
+    {
+      "time": 0.1,
+      "robots": [{"name": "robot1", "model": "robot1", "pose": [0.0, 0.0, 0.0]}],
+      "detections": [{
+        "observer": "robot1", "sensor": "landmark_sensor", "kind": "Landmark", "target": "1",
+        "relative_pose": [1.0, 2.0, 0.0], "pose": [1.0, 2.0, 0.0]
+      }]
+    }
+  ]
+}
+```
+*/
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::path_finder};
+use crate::{
+    constants::TIME_ROUND,
+    environment::Map,
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    node::node_factory::NodeRecord,
+    physics::PhysicsRecord,
+    sensors::{SensorObservationRecord, observation_frame::ObservationFrame},
+    simulator::{Record, SimulatorConfig},
+    utils::geometry::mod2pi,
+};
+
+/// Version of the format of the [`WorldSnapshots`], increased at each breaking change.
+pub const SNAPSHOTS_FORMAT_VERSION: u32 = 1;
+
+/// Configuration of the world snapshots export.
+///
+/// Default values:
+/// - `snapshots_path`: `"snapshots.json"`
+#[config_derives]
+pub struct WorldSnapshotsConfig {
+    /// Path of the JSON snapshots (path from config location).
+    pub snapshots_path: String,
+}
+
+impl Default for WorldSnapshotsConfig {
+    fn default() -> Self {
+        Self {
+            snapshots_path: String::from("snapshots.json"),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for WorldSnapshotsConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("World snapshots").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Snapshots path:");
+                path_finder(ui, &mut self.snapshots_path, &global_config.base_path);
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        egui::CollapsingHeader::new("World snapshots").show(ui, |ui| {
+            ui.label(format!("Snapshots path: {}", self.snapshots_path));
+        });
+    }
+}
+
+/// Static landmark of the map.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotLandmark {
+    /// Id of the landmark.
+    pub id: i32,
+    /// Labels of the landmark.
+    pub labels: Vec<String>,
+    /// Pose `[x, y, theta]` of the landmark.
+    pub pose: [f32; 3],
+    /// Width of the landmark (m), 0 for a point landmark.
+    pub width: f32,
+    /// Height of the landmark (m).
+    pub height: f32,
+}
+
+/// Shape of a robot model.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotRobotModel {
+    /// Name of the robot in the configuration.
+    pub name: String,
+    /// Radius of the bounding circle of the footprint (m).
+    pub footprint_radius: f32,
+    /// Height of the robot (m).
+    pub height: f32,
+}
+
+/// Real pose of a robot at a time step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotRobot {
+    /// Name of the robot.
+    pub name: String,
+    /// Name of the model of the robot, see [`WorldSnapshots::robot_models`].
+    pub model: String,
+    /// Pose `[x, y, theta]` of the robot.
+    pub pose: [f32; 3],
+}
+
+/// Kind of object detected by a sensor.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotDetectionKind {
+    /// Landmark of the map, the target is its id.
+    Landmark,
+    /// Other robot, the target is its name.
+    Robot,
+}
+
+/// Detection made by a sensor during a time step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotDetection {
+    /// Name of the observing robot.
+    pub observer: String,
+    /// Name of the sensor.
+    pub sensor: String,
+    /// Kind of the detected object.
+    pub kind: SnapshotDetectionKind,
+    /// Id of the landmark or name of the robot detected.
+    pub target: String,
+    /// Observed pose `[x, y, theta]`, in the frame of the observation (see
+    /// [`ObservationFrame`]).
+    pub relative_pose: [f32; 3],
+    /// Observed pose `[x, y, theta]`, in the world frame.
+    pub pose: [f32; 3],
+}
+
+/// State of the world at a time step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorldSnapshot {
+    /// Time of the step.
+    pub time: f32,
+    /// Robots, sorted by name.
+    pub robots: Vec<SnapshotRobot>,
+    /// Detections made during the step.
+    pub detections: Vec<SnapshotDetection>,
+}
+
+/// Snapshots of the world at each time step of a run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorldSnapshots {
+    /// Version of the format, see [`SNAPSHOTS_FORMAT_VERSION`].
+    pub version: u32,
+    /// Static landmarks of the map.
+    pub landmarks: Vec<SnapshotLandmark>,
+    /// Models of the robots of the configuration.
+    pub robot_models: Vec<SnapshotRobotModel>,
+    /// Snapshots, sorted by time.
+    pub steps: Vec<WorldSnapshot>,
+}
+
+impl WorldSnapshots {
+    /// Build the snapshots from the records of a run, the `map` of the environment and the
+    /// `config` of the simulator (for the robot models).
+    pub fn from_records(records: &[Record], map: &Map, config: &SimulatorConfig) -> Self {
+        let mut records: Vec<&Record> = records.iter().collect();
+        records.sort();
+
+        let mut steps: Vec<WorldSnapshot> = Vec::new();
+        for record in records {
+            let NodeRecord::Robot(robot_record) = &record.node else {
+                continue;
+            };
+            let PhysicsRecord::Internal(physics) = &robot_record.physics else {
+                continue;
+            };
+            if steps
+                .last()
+                .is_none_or(|step| (step.time - record.time).abs() >= TIME_ROUND)
+            {
+                steps.push(WorldSnapshot {
+                    time: record.time,
+                    robots: Vec::new(),
+                    detections: Vec::new(),
+                });
+            }
+            let step = steps.last_mut().unwrap();
+            let robot_pose = physics.state.pose;
+            step.robots.push(SnapshotRobot {
+                name: robot_record.name.clone(),
+                model: robot_record.model_name.clone(),
+                pose: robot_pose,
+            });
+
+            // The last observations are kept in the records until new ones are emitted
+            for observation in robot_record
+                .sensors
+                .last_observations
+                .iter()
+                .filter(|o| (o.time - record.time).abs() < TIME_ROUND)
+            {
+                let (kind, target, relative_pose) = match &observation.sensor_observation {
+                    SensorObservationRecord::OrientedLandmark(o) => {
+                        (SnapshotDetectionKind::Landmark, o.id.to_string(), o.pose)
+                    }
+                    SensorObservationRecord::OrientedRobot(o) => {
+                        (SnapshotDetectionKind::Robot, o.name.clone(), o.pose)
+                    }
+                    _ => continue,
+                };
+                let pose = match observation.frame {
+                    ObservationFrame::Robot => to_world_frame(&robot_pose, &relative_pose),
+                    ObservationFrame::Sensor => {
+                        let sensor_pose = config
+                            .robots
+                            .iter()
+                            .find(|r| r.name == robot_record.model_name)
+                            .and_then(|r| {
+                                r.sensor_manager
+                                    .sensors
+                                    .iter()
+                                    .find(|s| s.name == observation.sensor_name)
+                            })
+                            .map(|s| s.sensor_pose)
+                            .unwrap_or_default();
+                        to_world_frame(&to_world_frame(&robot_pose, &sensor_pose), &relative_pose)
+                    }
+                    ObservationFrame::Map => relative_pose,
+                };
+                step.detections.push(SnapshotDetection {
+                    observer: observation.observer.clone(),
+                    sensor: observation.sensor_name.clone(),
+                    kind,
+                    target,
+                    relative_pose,
+                    pose,
+                });
+            }
+        }
+
+        Self {
+            version: SNAPSHOTS_FORMAT_VERSION,
+            landmarks: map
+                .landmarks
+                .iter()
+                .map(|l| SnapshotLandmark {
+                    id: l.id,
+                    labels: l.labels.clone(),
+                    pose: l.pose.into(),
+                    width: l.width,
+                    height: l.height,
+                })
+                .collect(),
+            robot_models: config
+                .robots
+                .iter()
+                .map(|r| SnapshotRobotModel {
+                    name: r.name.clone(),
+                    footprint_radius: r.footprint.bounding_radius(),
+                    height: r.height,
+                })
+                .collect(),
+            steps,
+        }
+    }
+
+    /// Write the snapshots to `path`, in JSON.
+    pub fn save(&self, path: &Path) -> SimbaResult<()> {
+        let content = serde_json::to_string(self).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ImplementationError,
+                format!("Error during json serialization of the world snapshots: {e}"),
+            )
+        })?;
+        fs::write(path, content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to write world snapshots file '{}': {}",
+                    path.to_str().unwrap_or_default(),
+                    e
+                ),
+            )
+        })
+    }
+}
+
+/// Pose `relative_pose`, given in the frame of `frame_pose`, in the world frame.
+fn to_world_frame(frame_pose: &[f32; 3], relative_pose: &[f32; 3]) -> [f32; 3] {
+    let (sin, cos) = frame_pose[2].sin_cos();
+    [
+        frame_pose[0] + cos * relative_pose[0] - sin * relative_pose[1],
+        frame_pose[1] + sin * relative_pose[0] + cos * relative_pose[1],
+        mod2pi(frame_pose[2] + relative_pose[2]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    #[test]
+    fn world_frame() {
+        let pose = to_world_frame(&[1., 2., FRAC_PI_2], &[3., 0., FRAC_PI_2]);
+        assert!((pose[0] - 1.).abs() < 1e-5);
+        assert!((pose[1] - 5.).abs() < 1e-5);
+        assert!((pose[2] - mod2pi(2. * FRAC_PI_2)).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,428 @@
+/*!
+Post-run comparison report of the benched state estimators.
+
+For each robot, the ego pose estimated by every bench state estimator
+(`state_estimator_bench` in the robot configuration) is compared with the
+ground truth given by the physics. The report gives, per estimator:
+- the position and orientation RMSE,
+- the ANEES (average Normalized Estimation Error Squared), when the estimator
+  provides a pose covariance (see [`StateEstimator::pose_covariance`](super::StateEstimator::pose_covariance)),
+- the mean runtime of the prediction and correction steps, taken from the time analysis.
+
+The report is written at the end of the simulation, when results are computed, if
+[`ResultConfig::bench_report`](crate::simulator::ResultConfig::bench_report) is set.
+
+```yaml
+results:
+  bench_report:
+    report_path: bench_report.md
+    format: Markdown
+```
+*/
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::{
+    UIComponent,
+    utils::{enum_radio, path_finder},
+};
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    node::node_factory::NodeRecord,
+    physics::PhysicsRecord,
+    simulator::{PoseErrorAccumulator, Record, SimulatorConfig},
+    time_analysis::TimeAnalysisFactory,
+};
+
+/// Output format of the [`BenchReport`].
+#[config_derives]
+#[derive(Default)]
+pub enum BenchReportFormat {
+    /// JSON document, easy to process with other tools.
+    #[default]
+    Json,
+    /// Markdown table, easy to read.
+    Markdown,
+}
+
+/// Configuration of the state estimator bench report.
+///
+/// Default values:
+/// - `report_path`: `"bench_report.json"`
+/// - `format`: [`BenchReportFormat::Json`]
+#[config_derives]
+pub struct BenchReportConfig {
+    /// Path of the report file (path from config location).
+    pub report_path: String,
+    /// Output format of the report.
+    pub format: BenchReportFormat,
+}
+
+impl Default for BenchReportConfig {
+    fn default() -> Self {
+        Self {
+            report_path: String::from("bench_report.json"),
+            format: BenchReportFormat::default(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for BenchReportConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Bench report").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Report path:");
+                path_finder(ui, &mut self.report_path, &global_config.base_path);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Format:");
+                enum_radio(ui, &mut self.format);
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        egui::CollapsingHeader::new("Bench report").show(ui, |ui| {
+            ui.label(format!("Report path: {}", self.report_path));
+            ui.label(format!("Format: {}", self.format));
+        });
+    }
+}
+
+/// Comparison statistics of one bench state estimator.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchEstimatorStatistics {
+    /// Name of the node running the estimator.
+    pub node: String,
+    /// Name of the bench estimator.
+    pub name: String,
+    /// Number of records where the estimation was compared to the ground truth.
+    pub samples: usize,
+    /// Root mean square error on the position (m), `None` without samples.
+    pub position_rmse: Option<f32>,
+    /// Root mean square error on the orientation (rad), `None` without samples.
+    pub orientation_rmse: Option<f32>,
+    /// Average NEES on the pose, `None` if the estimator does not provide a covariance.
+    pub anees: Option<f32>,
+    /// Mean duration of the prediction step (s), `None` without time analysis.
+    pub mean_prediction_time: Option<f32>,
+    /// Mean duration of the correction step (s), `None` without time analysis.
+    pub mean_correction_time: Option<f32>,
+}
+
+/// Comparison report of all the bench state estimators of a simulation.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BenchReport {
+    /// Statistics of each bench estimator, sorted by node name then estimator name.
+    pub estimators: Vec<BenchEstimatorStatistics>,
+}
+
+impl BenchReport {
+    /// Build the report from the simulation records.
+    ///
+    /// Only robots with an internal physics are considered, as the ground truth is not
+    /// available otherwise. Runtimes are filled only if `time_analysis` is given.
+    pub fn from_records(records: &[Record], time_analysis: Option<&TimeAnalysisFactory>) -> Self {
+        let mut accumulators = BTreeMap::<(String, String), PoseErrorAccumulator>::new();
+        for record in records {
+            let robot_record = match &record.node {
+                NodeRecord::Robot(r) => r,
+                NodeRecord::ComputationUnit(_) => continue,
+            };
+            let real_pose = match &robot_record.physics {
+                PhysicsRecord::Internal(r) => r.state.pose,
+                _ => continue,
+            };
+            for bench in &robot_record.state_estimator_bench {
+                let acc = accumulators
+                    .entry((robot_record.name.clone(), bench.name.clone()))
+                    .or_default();
+                if let Some(ego) = &bench.ego {
+                    acc.add(&ego.pose, &real_pose, bench.pose_covariance.as_ref());
+                }
+            }
+        }
+
+        let estimators = accumulators
+            .into_iter()
+            .map(|((node, name), acc)| {
+                let mean_duration = |step: &str| {
+                    time_analysis.and_then(|taf| {
+                        let durations =
+                            taf.profile_durations(&node, &format!("{}_{}_step", name, step));
+                        if durations.is_empty() {
+                            None
+                        } else {
+                            Some(
+                                durations.iter().map(|d| d.as_secs_f32()).sum::<f32>()
+                                    / durations.len() as f32,
+                            )
+                        }
+                    })
+                };
+                BenchEstimatorStatistics {
+                    samples: acc.samples,
+                    // An estimator which never gave an estimate has no error
+                    position_rmse: (acc.samples > 0).then(|| acc.position_rmse()),
+                    orientation_rmse: (acc.samples > 0).then(|| acc.heading_rmse()),
+                    anees: acc.anees(),
+                    mean_prediction_time: mean_duration("prediction"),
+                    mean_correction_time: mean_duration("correction"),
+                    node,
+                    name,
+                }
+            })
+            .collect();
+        Self { estimators }
+    }
+
+    /// Render the report as a Markdown table.
+    pub fn to_markdown(&self) -> String {
+        let optional = |v: Option<f32>, factor: f32| match v {
+            Some(v) => format!("{:.4}", v * factor),
+            None => String::from("-"),
+        };
+        let mut md = String::from(
+            "| Node | Estimator | Samples | Position RMSE (m) | Orientation RMSE (rad) | ANEES | Prediction time (ms) | Correction time (ms) |\n",
+        );
+        md += "|---|---|---|---|---|---|---|---|\n";
+        for stats in &self.estimators {
+            md += &format!(
+                "| {} | {} | {} | {} | {} | {} | {} | {} |\n",
+                stats.node,
+                stats.name,
+                stats.samples,
+                optional(stats.position_rmse, 1.),
+                optional(stats.orientation_rmse, 1.),
+                optional(stats.anees, 1.),
+                optional(stats.mean_prediction_time, 1000.),
+                optional(stats.mean_correction_time, 1000.),
+            );
+        }
+        md
+    }
+
+    /// Write the report to `path` in the given `format`.
+    pub fn save(&self, path: &Path, format: &BenchReportFormat) -> SimbaResult<()> {
+        let content = match format {
+            BenchReportFormat::Json => serde_json::to_string_pretty(self).map_err(|e| {
+                SimbaError::new(
+                    SimbaErrorTypes::ImplementationError,
+                    format!("Error during json serialization of the bench report: {e}"),
+                )
+            })?,
+            BenchReportFormat::Markdown => self.to_markdown(),
+        };
+        fs::write(path, content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to write bench report file '{}': {}",
+                    path.to_str().unwrap_or_default(),
+                    e
+                ),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        controllers::{ControllerRecord, external_controller::ExternalControllerRecord},
+        navigators::{NavigatorRecord, go_to::GoToRecord},
+        node::{NodeState, node_factory::RobotRecord},
+        physics::internal_physics::InternalPhysicsRecord,
+        sensors::sensor_manager::SensorManagerRecord,
+        state_estimators::{
+            BenchStateEstimatorRecord, StateEstimatorRecord, StateRecord,
+            external_estimator::ExternalEstimatorRecord,
+        },
+        time_analysis::TimeAnalysisConfig,
+    };
+
+    fn state(pose: [f32; 3]) -> StateRecord {
+        StateRecord {
+            pose,
+            ..Default::default()
+        }
+    }
+
+    fn bench(
+        name: &str,
+        ego: Option<[f32; 3]>,
+        identity_covariance: bool,
+    ) -> BenchStateEstimatorRecord {
+        BenchStateEstimatorRecord {
+            name: name.to_string(),
+            record: StateEstimatorRecord::External(ExternalEstimatorRecord::default()),
+            ego: ego.map(state),
+            pose_covariance: identity_covariance.then_some([
+                [1., 0., 0.],
+                [0., 1., 0.],
+                [0., 0., 1.],
+            ]),
+        }
+    }
+
+    fn record(time: f32, real_pose: [f32; 3], benches: Vec<BenchStateEstimatorRecord>) -> Record {
+        Record {
+            time,
+            node: NodeRecord::Robot(RobotRecord {
+                name: "robot".to_string(),
+                model_name: "robot".to_string(),
+                navigator: NavigatorRecord::GoTo(GoToRecord::default()),
+                controller: ControllerRecord::External(ExternalControllerRecord::default()),
+                physics: PhysicsRecord::Internal(InternalPhysicsRecord {
+                    state: state(real_pose),
+                    last_time_update: time,
+                    current_command: Default::default(),
+                }),
+                state_estimator: StateEstimatorRecord::External(ExternalEstimatorRecord::default()),
+                state_estimator_bench: benches,
+                sensors: SensorManagerRecord {
+                    sensors: Vec::new(),
+                    next_time: None,
+                    last_observations: Vec::new(),
+                },
+                network: Default::default(),
+                state: NodeState::Running,
+                labels: Vec::new(),
+                skipped_steps: 0,
+                observation_latencies: Vec::new(),
+                estimator_divergences: Vec::new(),
+                command_source: None,
+            }),
+        }
+    }
+
+    fn records() -> Vec<Record> {
+        vec![
+            record(
+                0.,
+                [1., 1., 0.],
+                vec![
+                    bench("ekf", Some([4., 5., 0.]), true),
+                    bench("idle", None, false),
+                ],
+            ),
+            record(
+                1.,
+                [2., 0., 0.5],
+                vec![
+                    bench("ekf", Some([2., 0., 0.6]), false),
+                    bench("idle", None, false),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn statistics_from_records() {
+        let report = BenchReport::from_records(&records(), None);
+        assert_eq!(report.estimators.len(), 2);
+
+        let ekf = &report.estimators[0];
+        assert_eq!((ekf.node.as_str(), ekf.name.as_str()), ("robot", "ekf"));
+        assert_eq!(ekf.samples, 2);
+        // Position errors of 5 m and 0 m, orientation errors of 0 rad and 0.1 rad
+        assert!((ekf.position_rmse.unwrap() - 12.5_f32.sqrt()).abs() < 1e-5);
+        assert!((ekf.orientation_rmse.unwrap() - 0.005_f32.sqrt()).abs() < 1e-5);
+        // Only the first estimate has a covariance
+        assert_eq!(ekf.anees, Some(25.));
+        assert_eq!(ekf.mean_prediction_time, None);
+        assert_eq!(ekf.mean_correction_time, None);
+
+        // An estimator without estimate has no error, instead of a perfect one
+        let idle = &report.estimators[1];
+        assert_eq!(idle.name, "idle");
+        assert_eq!(idle.samples, 0);
+        assert_eq!(idle.position_rmse, None);
+        assert_eq!(idle.orientation_rmse, None);
+        assert_eq!(idle.anees, None);
+    }
+
+    #[test]
+    fn runtimes_from_time_analysis() {
+        let mut time_analysis =
+            TimeAnalysisFactory::init_from_config(&TimeAnalysisConfig::default()).unwrap();
+        let node = time_analysis.new_node("robot".to_string());
+        for time in [0., 1.] {
+            let mut node = node.lock().unwrap();
+            let ta = node.time_analysis(time, "ekf_prediction_step".to_string());
+            node.finished_time_analysis(ta);
+        }
+
+        let report = BenchReport::from_records(&records(), Some(&time_analysis));
+        let ekf = &report.estimators[0];
+        assert!(ekf.mean_prediction_time.is_some_and(|t| t >= 0.));
+        assert_eq!(ekf.mean_correction_time, None);
+        assert_eq!(report.estimators[1].mean_prediction_time, None);
+    }
+
+    #[test]
+    fn markdown_table() {
+        let markdown = BenchReport::from_records(&records(), None).to_markdown();
+        let lines = markdown.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("| Node | Estimator | Samples |"));
+        assert_eq!(
+            lines[2],
+            format!(
+                "| robot | ekf | 2 | {:.4} | {:.4} | 25.0000 | - | - |",
+                12.5_f32.sqrt(),
+                0.005_f32.sqrt()
+            )
+        );
+        assert_eq!(lines[3], "| robot | idle | 0 | - | - | - | - | - |");
+    }
+
+    #[test]
+    fn save_report() {
+        let directory =
+            std::env::temp_dir().join(format!("simba_bench_report_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let report = BenchReport::from_records(&records(), None);
+
+        let json_path = directory.join("bench_report.json");
+        report.save(&json_path, &BenchReportFormat::Json).unwrap();
+        let saved: BenchReport =
+            serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(saved.estimators.len(), 2);
+        assert_eq!(saved.estimators[1].position_rmse, None);
+
+        let markdown_path = directory.join("bench_report.md");
+        report
+            .save(&markdown_path, &BenchReportFormat::Markdown)
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&markdown_path).unwrap(),
+            report.to_markdown()
+        );
+
+        // Writing in a missing directory is reported
+        assert!(
+            report
+                .save(
+                    &directory.join("missing/bench_report.md"),
+                    &BenchReportFormat::Markdown
+                )
+                .is_err()
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
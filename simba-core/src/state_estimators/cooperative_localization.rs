@@ -0,0 +1,490 @@
+/*!
+Module providing the [`CooperativeLocalization`] strategy, a reference implementation of
+cooperative localization meant to run on each robot of a fleet.
+
+The ego pose is estimated by an [`EkfEstimator`], using the odometry, the GNSS and the
+landmarks. After each prediction, the estimated pose and its covariance are sent to the
+neighbors as a [`PoseEstimateMessage`], on their
+[`CooperativeLocalization::ESTIMATE_CHANNEL`] channel (e.g.
+`/simba/nodes/<neighbor_name>/cooperative_localization/estimates`): each neighbor should run a
+[`CooperativeLocalization`] estimator.
+
+The [`OrientedRobotObservation`](crate::sensors::robot_sensor::OrientedRobotObservation)s of a
+neighbor (relative pose, in the robot frame) correct the ego pose like a landmark whose pose is the
+last estimate received from this neighbor. The uncertainty of the neighbor estimate is added to the
+measurement noise. The correlation between the estimates of the robots is ignored, so a pair of
+robots observing each other repeatedly becomes overconfident.
+*/
+
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use nalgebra::{SMatrix, Vector3};
+
+#[cfg(feature = "gui")]
+use super::ekf_estimator::{matrix_show, matrix_show_mut};
+use super::{
+    State, StateEstimator, StateEstimatorRecord, WorldState,
+    ekf_estimator::{
+        EkfEstimator, EkfEstimatorConfig, EkfEstimatorRecord, relative_pose_measurement, to_matrix,
+    },
+};
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::string_checkbox};
+use crate::{
+    networking::network::{Envelope, Network},
+    node::{
+        Node,
+        capabilities::{Capabilities, ObservationKind},
+    },
+    physics::robot_models::Command,
+    recordable::Recordable,
+    sensors::{Observation, SensorObservation},
+    simulator::{SimbaBrokerMultiClient, SimulatorConfig},
+    utils::{
+        SharedMutex, SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory,
+        geometry::mod2pi,
+    },
+};
+use config_checker::*;
+use log::{error, warn};
+use serde_derive::{Deserialize, Serialize};
+use simba_com::pub_sub::{MultiClientTrait, PathKey};
+use simba_macros::config_derives;
+
+type Matrix3 = SMatrix<f32, 3, 3>;
+
+/// Pose estimate shared by a [`CooperativeLocalization`] estimator with its neighbors.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PoseEstimateMessage {
+    /// Estimated pose `[x, y, orientation]`.
+    pub pose: [f32; 3],
+    /// Covariance of the estimated pose.
+    pub covariance: [[f32; 3]; 3],
+    /// Time of the estimate.
+    pub time: f32,
+}
+
+/// Configuration for the [`CooperativeLocalization`] strategy.
+///
+/// Default values:
+/// - `ekf`: [`EkfEstimatorConfig::default`]
+/// - `robot_noise`: `diag(0.04, 0.04, 0.01)`
+/// - `neighbors`: empty vector
+/// - `max_estimate_age`: `0.5`
+///
+/// # Example
+/// ```yaml
+/// state_estimator:
+///   type: CooperativeLocalization
+///   ekf:
+///     prediction_activation:
+///       period: {type: Num, value: 0.1}
+///     initial_pose: [0., 0., 0.]
+///   robot_noise: [[0.04, 0., 0.], [0., 0.04, 0.], [0., 0., 0.01]]
+///   neighbors: [robot2, robot3]
+///   max_estimate_age: 0.5
+/// ```
+#[config_derives]
+pub struct CooperativeLocalizationConfig {
+    /// Filter of the ego pose, also giving the prediction period.
+    #[check]
+    pub ekf: EkfEstimatorConfig,
+    /// Covariance of the robot relative pose measurement.
+    pub robot_noise: [[f32; 3]; 3],
+    /// Names of the nodes to which the estimate is sent.
+    pub neighbors: Vec<String>,
+    /// Maximal time between a robot observation and the estimate of the observed neighbor, in
+    /// seconds. Older estimates are not used.
+    pub max_estimate_age: f32,
+}
+
+impl Default for CooperativeLocalizationConfig {
+    fn default() -> Self {
+        Self {
+            ekf: EkfEstimatorConfig::default(),
+            robot_noise: [[0.04, 0., 0.], [0., 0.04, 0.], [0., 0., 0.01]],
+            neighbors: Vec::new(),
+            max_estimate_age: 0.5,
+        }
+    }
+}
+
+impl Check for CooperativeLocalizationConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        let matrix = &self.robot_noise;
+        if (0..3).any(|i| matrix[i][i] < 0.) {
+            errors.push(format!(
+                "The diagonal of robot_noise should be positive, got {matrix:?}"
+            ));
+        }
+        if (0..3).any(|i| (0..3).any(|j| matrix[i][j] != matrix[j][i])) {
+            errors.push(format!("robot_noise should be symmetric, got {matrix:?}"));
+        }
+        if self.max_estimate_age < 0. {
+            errors.push(format!(
+                "Maximal estimate age should be positive, got {}",
+                self.max_estimate_age
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CooperativeLocalizationConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Cooperative Localization")
+            .id_salt(format!("cooperative-localization-{}", unique_id))
+            .show(ui, |ui| {
+                self.ekf.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+                matrix_show_mut(ui, "Robot noise", &mut self.robot_noise, unique_id);
+                let node_list = Vec::from_iter(
+                    global_config
+                        .robots
+                        .iter()
+                        .map(|x| x.name.clone())
+                        .filter(|name| Some(name) != current_node_name),
+                );
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Neighbors:");
+                    string_checkbox(ui, &node_list, &mut self.neighbors);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Maximal estimate age:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.max_estimate_age)
+                            .speed(0.01)
+                            .range(0.0..=f32::MAX),
+                    );
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Cooperative Localization")
+            .id_salt(format!("cooperative-localization-{}", unique_id))
+            .show(ui, |ui| {
+                self.ekf.show(ui, ctx, unique_id);
+                matrix_show(ui, "Robot noise", &self.robot_noise);
+                ui.label(format!("Neighbors: {}", self.neighbors.join(", ")));
+                ui.label(format!("Maximal estimate age: {}", self.max_estimate_age));
+            });
+    }
+}
+
+/// Record for [`CooperativeLocalization`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CooperativeLocalizationRecord {
+    /// Record of the filter of the ego pose.
+    pub ekf: EkfEstimatorRecord,
+    /// Last estimate received from each neighbor.
+    pub neighbor_estimates: BTreeMap<String, PoseEstimateMessage>,
+    /// Number of robot observations fused since the start.
+    pub fused_observations: usize,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for CooperativeLocalizationRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            self.ekf.show(ui, ctx, unique_id);
+            ui.label("Neighbor estimates:");
+            for (neighbor, estimate) in &self.neighbor_estimates {
+                ui.label(format!(
+                    "- {neighbor}: {:?} at {}",
+                    estimate.pose, estimate.time
+                ));
+            }
+            ui.label(format!("Fused observations: {}", self.fused_observations));
+        });
+    }
+}
+
+/// EKF on the ego pose, corrected with the observations of the neighbors and their shared
+/// estimates.
+#[derive(Debug)]
+pub struct CooperativeLocalization {
+    /// Filter of the ego pose.
+    ekf: EkfEstimator,
+    robot_noise: Matrix3,
+    /// Nodes to which the estimate is sent.
+    neighbors: Vec<String>,
+    max_estimate_age: f32,
+    /// Last estimate received from each neighbor.
+    neighbor_estimates: BTreeMap<String, PoseEstimateMessage>,
+    /// Number of robot observations fused since the start.
+    fused_observations: usize,
+    /// Client receiving the [`PoseEstimateMessage`]s.
+    message_client: SharedMutex<SimbaBrokerMultiClient>,
+    /// Network used to send the estimate.
+    network: SharedRwLock<Network>,
+}
+
+impl CooperativeLocalization {
+    /// Channel (relative to the node) on which the neighbors send their estimates.
+    pub const ESTIMATE_CHANNEL: &'static str = "cooperative_localization/estimates";
+
+    /// Creates a new [`CooperativeLocalization`] from the given `config`.
+    pub fn from_config(
+        config: &CooperativeLocalizationConfig,
+        global_config: &SimulatorConfig,
+        va_factory: &DeterministRandomVariableFactory,
+        network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> Self {
+        let net = network.write().unwrap();
+        let estimate_key = net.make_channel(PathKey::from_str(Self::ESTIMATE_CHANNEL).unwrap());
+        let message_client = net.subscribe_to(&[estimate_key], None);
+        drop(net);
+        Self {
+            ekf: EkfEstimator::from_config(&config.ekf, global_config, va_factory, initial_time),
+            robot_noise: to_matrix(&config.robot_noise),
+            neighbors: config.neighbors.clone(),
+            max_estimate_age: config.max_estimate_age,
+            neighbor_estimates: BTreeMap::new(),
+            fused_observations: 0,
+            message_client: Arc::new(Mutex::new(message_client)),
+            network: network.clone(),
+        }
+    }
+
+    /// Correct the pose with the `observed` pose of a neighbor, relative to the node, whose
+    /// estimate is `neighbor`.
+    fn robot_update(&mut self, neighbor: &PoseEstimateMessage, observed: &Vector3<f32>) {
+        let pose = self.ekf.ego().pose;
+        let (expected, h) = relative_pose_measurement(&pose, &Vector3::from(neighbor.pose));
+        // Jacobian of the measurement with respect to the neighbor pose
+        let (sin, cos) = pose.z.sin_cos();
+        let h_neighbor = Matrix3::new(cos, sin, 0., -sin, cos, 0., 0., 0., 1.);
+        let r = self.robot_noise
+            + h_neighbor * to_matrix(&neighbor.covariance) * h_neighbor.transpose();
+        let mut innovation = observed - expected;
+        innovation.z = mod2pi(innovation.z);
+        self.ekf.update(innovation, h, r);
+        self.fused_observations += 1;
+    }
+
+    /// Estimate of the ego pose, to share with the neighbors.
+    fn pose_estimate(&self, time: f32) -> PoseEstimateMessage {
+        let pose = self.ekf.ego().pose;
+        let covariance = self.ekf.pose_covariance().unwrap();
+        PoseEstimateMessage {
+            pose: [pose.x, pose.y, pose.z],
+            covariance: std::array::from_fn(|i| std::array::from_fn(|j| covariance[(i, j)])),
+            time,
+        }
+    }
+}
+
+impl StateEstimator for CooperativeLocalization {
+    fn post_init(&mut self, node: &mut Node) -> crate::errors::SimbaResult<()> {
+        self.ekf.post_init(node)
+    }
+
+    fn prediction_step(&mut self, node: &mut Node, _command: Option<Command>, time: f32) {
+        // The estimate is shared only when it was predicted
+        if !self.ekf.predict(time) {
+            return;
+        }
+        let message = match serde_json::to_value(self.pose_estimate(time)) {
+            Ok(message) => message,
+            Err(e) => {
+                error!("Impossible to serialize the pose estimate: {e}");
+                return;
+            }
+        };
+        let network = self.network.read().unwrap();
+        for neighbor in &self.neighbors {
+            network.send_to_node(
+                neighbor.clone(),
+                PathKey::from_str(Self::ESTIMATE_CHANNEL).unwrap(),
+                Envelope {
+                    from: node.name(),
+                    message: message.clone(),
+                    timestamp: time,
+                    message_flags: Vec::new(),
+                    ..Default::default()
+                },
+                time,
+            );
+        }
+    }
+
+    fn correction_step(&mut self, node: &mut Node, observations: &[Observation], time: f32) {
+        self.ekf.correction_step(node, observations, time);
+        let node_name = node.name();
+        for observation in observations {
+            // Observations received from other nodes are not about the ego pose, and the
+            // observations in another frame are rejected by the capabilities check
+            if observation.observer != node_name || !observation.in_native_frame() {
+                continue;
+            }
+            let SensorObservation::OrientedRobot(obs) = &observation.sensor_observation else {
+                continue;
+            };
+            let Some(neighbor) = self.neighbor_estimates.get(&obs.name) else {
+                continue;
+            };
+            if (observation.time - neighbor.time).abs() > self.max_estimate_age {
+                continue;
+            }
+            let neighbor = neighbor.clone();
+            self.ekf.propagate(observation.time.min(time));
+            self.robot_update(&neighbor, &obs.pose);
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let mut capabilities = self.ekf.capabilities();
+        if let Some(kinds) = capabilities.consumed_observations.as_mut() {
+            kinds.push(ObservationKind::OrientedRobot);
+        }
+        capabilities
+    }
+
+    fn world_state(&self) -> WorldState {
+        self.ekf.world_state()
+    }
+
+    fn next_time_step(&self) -> f32 {
+        self.ekf.next_time_step()
+    }
+
+    fn pre_loop_hook(&mut self, node: &mut Node, time: f32) {
+        while let Some((_, envelope)) = self.message_client.lock().unwrap().try_receive(time) {
+            match serde_json::from_value::<PoseEstimateMessage>(envelope.message) {
+                Ok(estimate) => {
+                    self.neighbor_estimates.insert(envelope.from, estimate);
+                }
+                Err(e) => {
+                    warn!(
+                        "[{}] Invalid pose estimate sent by {}: {e}",
+                        node.name(),
+                        envelope.from
+                    );
+                }
+            }
+        }
+    }
+
+    fn pose_covariance(&self) -> Option<SMatrix<f32, 3, 3>> {
+        self.ekf.pose_covariance()
+    }
+
+    fn innovation_statistic(&self) -> Option<f32> {
+        self.ekf.innovation_statistic()
+    }
+
+    fn reset_state(&mut self, state: State, time: f32) -> Result<(), String> {
+        self.ekf.reset_state(state, time)
+    }
+
+    /// Resets the ego state to the ego estimate of the `record`, and restores the covariance of
+    /// an `Ekf` or `CooperativeLocalization` record.
+    fn warm_start(&mut self, record: &StateEstimatorRecord, time: f32) -> Result<(), String> {
+        match record {
+            StateEstimatorRecord::CooperativeLocalization(record) => self
+                .ekf
+                .warm_start(&StateEstimatorRecord::Ekf(record.ekf.clone()), time),
+            record => self.ekf.warm_start(record, time),
+        }
+    }
+}
+
+impl Recordable<StateEstimatorRecord> for CooperativeLocalization {
+    fn record(&self) -> StateEstimatorRecord {
+        let StateEstimatorRecord::Ekf(ekf) = self.ekf.record() else {
+            unreachable!()
+        };
+        StateEstimatorRecord::CooperativeLocalization(CooperativeLocalizationRecord {
+            ekf,
+            neighbor_estimates: self.neighbor_estimates.clone(),
+            fused_observations: self.fused_observations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::RwLock;
+
+    use super::*;
+    use crate::{constants::TIME_ROUND, simulator::SimbaBroker};
+
+    fn estimator() -> CooperativeLocalization {
+        let broker = Arc::new(RwLock::new(SimbaBroker::new(TIME_ROUND)));
+        let network = Arc::new(RwLock::new(Network::new("robot1".to_string(), &broker)));
+        CooperativeLocalization::from_config(
+            &CooperativeLocalizationConfig::default(),
+            &SimulatorConfig::default(),
+            &DeterministRandomVariableFactory::default(),
+            &network,
+            0.,
+        )
+    }
+
+    #[test]
+    fn neighbor_uncertainty_weakens_the_correction() {
+        // True pose is (1, 0, 0): a neighbor at (3, 1, 0) is seen at (2, 1, 0)
+        let observed = Vector3::new(2., 1., 0.);
+        let mut certain = estimator();
+        certain.robot_update(
+            &PoseEstimateMessage {
+                pose: [3., 1., 0.],
+                covariance: [[0.; 3]; 3],
+                time: 0.,
+            },
+            &observed,
+        );
+        let mut uncertain = estimator();
+        uncertain.robot_update(
+            &PoseEstimateMessage {
+                pose: [3., 1., 0.],
+                covariance: [[1., 0., 0.], [0., 1., 0.], [0., 0., 0.1]],
+                time: 0.,
+            },
+            &observed,
+        );
+        let certain_pose = certain.ekf.ego().pose;
+        let uncertain_pose = uncertain.ekf.ego().pose;
+        assert!(certain_pose.x > uncertain_pose.x && uncertain_pose.x > 0.);
+        let certain_covariance = certain.pose_covariance().unwrap();
+        let uncertain_covariance = uncertain.pose_covariance().unwrap();
+        assert!(certain_covariance[(0, 0)] < uncertain_covariance[(0, 0)]);
+        assert_eq!(certain.fused_observations, 1);
+    }
+
+    #[test]
+    fn prediction_rejected_too_soon() {
+        let mut estimator = estimator();
+        let next_time = estimator.next_time_step();
+        assert!(!estimator.ekf.predict(next_time + 1.));
+        assert_eq!(estimator.next_time_step(), next_time);
+        assert!(estimator.ekf.predict(next_time));
+        assert!(estimator.next_time_step() > next_time);
+    }
+}
@@ -0,0 +1,532 @@
+/*!
+Logging of the state estimator inputs and outputs, to build supervised datasets.
+
+When [`ResultConfig::estimator_dataset`](crate::simulator::ResultConfig::estimator_dataset)
+is set, each node writes one file in the configured directory, with one [`EstimatorDatasetEntry`]
+per prediction or correction step of an estimator: the state before the step, the inputs (command
+or observations) and the state after the step.
+
+Two formats are available ([`EstimatorDatasetFormat`]):
+- [JSON Lines](https://jsonlines.org/) (`<node_name>.jsonl`), one JSON object per step,
+- [Apache Parquet](https://parquet.apache.org/) (`<node_name>.parquet`, requires the `parquet`
+  feature), one row per step. The `time`, `estimator` and `step` columns are scalars, the
+  `previous_state`, `command`, `observations` and `state` columns hold the JSON of the field.
+
+The files are complete at the end of each run. A continued run appends to the JSON Lines file, and
+writes a new Parquet part (`<node_name>.1.parquet`, ...).
+
+```yaml
+results:
+  estimator_dataset:
+    output_path: dataset    # Directory, path from config location
+    format:
+      type: Parquet         # or JsonLines
+    include_bench: true
+```
+*/
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "gui")]
+use std::collections::BTreeMap;
+
+use config_checker::*;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::{
+    UIComponent,
+    utils::{path_finder, string_combobox},
+};
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    physics::robot_models::Command,
+    sensors::ObservationRecord,
+    simulator::SimulatorConfig,
+    state_estimators::WorldStateRecord,
+};
+
+/// File format of the estimator dataset.
+#[config_derives]
+pub enum EstimatorDatasetFormat {
+    /// JSON Lines, one JSON object per step.
+    JsonLines,
+    /// Apache Parquet, one row per step. Requires the `parquet` feature.
+    Parquet,
+}
+
+impl Default for EstimatorDatasetFormat {
+    fn default() -> Self {
+        Self::JsonLines
+    }
+}
+
+impl EstimatorDatasetFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::JsonLines => "jsonl",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Configuration of the estimator dataset logging.
+///
+/// Default values:
+/// - `output_path`: `"estimator_dataset"`
+/// - `format`: `JsonLines`
+/// - `include_bench`: `true`
+#[config_derives]
+pub struct EstimatorDatasetConfig {
+    /// Directory where the dataset files are written (path from config location).
+    pub output_path: String,
+    /// File format of the dataset.
+    pub format: EstimatorDatasetFormat,
+    /// Also log the steps of the bench state estimators.
+    pub include_bench: bool,
+}
+
+impl Default for EstimatorDatasetConfig {
+    fn default() -> Self {
+        Self {
+            output_path: String::from("estimator_dataset"),
+            format: EstimatorDatasetFormat::default(),
+            include_bench: true,
+        }
+    }
+}
+
+impl Check for EstimatorDatasetConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        #[cfg(not(feature = "parquet"))]
+        if self.format == EstimatorDatasetFormat::Parquet {
+            return Err(vec![
+                "Parquet estimator dataset requires simba to be built with the `parquet` feature"
+                    .to_string(),
+            ]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for EstimatorDatasetConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Estimator dataset").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Output path:");
+                path_finder(ui, &mut self.output_path, &global_config.base_path);
+            });
+            let mut current_str = self.format.to_string();
+            ui.horizontal(|ui| {
+                ui.label("Format:");
+                string_combobox(
+                    ui,
+                    &EstimatorDatasetFormat::to_vec(),
+                    &mut current_str,
+                    format!("estimator-dataset-format-choice-{}", unique_id),
+                );
+            });
+            if current_str != self.format.to_string() {
+                match current_str.as_str() {
+                    "JsonLines" => self.format = EstimatorDatasetFormat::JsonLines,
+                    "Parquet" => self.format = EstimatorDatasetFormat::Parquet,
+                    _ => panic!("Where did you find this value?"),
+                };
+            }
+            ui.horizontal(|ui| {
+                ui.label("Include bench estimators:");
+                ui.checkbox(&mut self.include_bench, "");
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        egui::CollapsingHeader::new("Estimator dataset").show(ui, |ui| {
+            ui.label(format!("Output path: {}", self.output_path));
+            ui.label(format!("Format: {}", self.format));
+            ui.label(format!("Include bench estimators: {}", self.include_bench));
+        });
+    }
+}
+
+/// Step of the state estimator described by an [`EstimatorDatasetEntry`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum EstimatorStep {
+    /// Prediction step, the input is the command.
+    Prediction,
+    /// Correction step, the inputs are the observations.
+    Correction,
+}
+
+/// One line of the estimator dataset.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EstimatorDatasetEntry {
+    /// Simulation time of the step.
+    pub time: f32,
+    /// Name of the estimator: `"state_estimator"` for the main one, or the bench name.
+    pub estimator: String,
+    /// Kind of step.
+    pub step: EstimatorStep,
+    /// Estimated state before the step.
+    pub previous_state: WorldStateRecord,
+    /// Command given to the prediction step.
+    pub command: Option<Command>,
+    /// Observations given to the correction step.
+    pub observations: Vec<ObservationRecord>,
+    /// Estimated state after the step.
+    pub state: WorldStateRecord,
+}
+
+fn write_error(path: &Path, e: impl std::fmt::Display) -> SimbaError {
+    SimbaError::new(
+        SimbaErrorTypes::ConfigError,
+        format!(
+            "Error while writing estimator dataset file '{}': {}",
+            path.display(),
+            e
+        ),
+    )
+}
+
+/// Number of steps written at once in the Parquet files.
+#[cfg(feature = "parquet")]
+const PARQUET_BATCH_ROWS: usize = 1024;
+
+enum DatasetSink {
+    JsonLines(BufWriter<File>),
+    #[cfg(feature = "parquet")]
+    Parquet {
+        writer: parquet::arrow::ArrowWriter<File>,
+        /// Steps not written yet.
+        rows: Vec<EstimatorDatasetEntry>,
+    },
+}
+
+#[cfg(feature = "parquet")]
+fn parquet_schema() -> arrow_schema::SchemaRef {
+    use arrow_schema::{DataType, Field, Schema};
+    std::sync::Arc::new(Schema::new(vec![
+        Field::new("time", DataType::Float32, false),
+        Field::new("estimator", DataType::Utf8, false),
+        Field::new("step", DataType::Utf8, false),
+        Field::new("previous_state", DataType::Utf8, false),
+        Field::new("command", DataType::Utf8, true),
+        Field::new("observations", DataType::Utf8, false),
+        Field::new("state", DataType::Utf8, false),
+    ]))
+}
+
+/// Column of the JSON of the `values` (null cells for `None`).
+#[cfg(feature = "parquet")]
+fn json_column<T: Serialize>(
+    values: impl Iterator<Item = Option<T>>,
+) -> serde_json::Result<arrow_array::ArrayRef> {
+    let cells = values
+        .map(|value| value.map(|v| serde_json::to_string(&v)).transpose())
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    Ok(std::sync::Arc::new(arrow_array::StringArray::from(cells)))
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet_rows(
+    writer: &mut parquet::arrow::ArrowWriter<File>,
+    rows: &[EstimatorDatasetEntry],
+) -> Result<(), String> {
+    use arrow_array::{ArrayRef, Float32Array, RecordBatch, StringArray};
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let columns: Vec<ArrayRef> = vec![
+        std::sync::Arc::new(Float32Array::from(
+            rows.iter().map(|row| row.time).collect::<Vec<_>>(),
+        )),
+        std::sync::Arc::new(StringArray::from(
+            rows.iter()
+                .map(|row| row.estimator.clone())
+                .collect::<Vec<_>>(),
+        )),
+        std::sync::Arc::new(StringArray::from(
+            rows.iter()
+                .map(|row| format!("{:?}", row.step))
+                .collect::<Vec<_>>(),
+        )),
+        json_column(rows.iter().map(|row| Some(&row.previous_state))).map_err(|e| e.to_string())?,
+        json_column(rows.iter().map(|row| row.command.as_ref())).map_err(|e| e.to_string())?,
+        json_column(rows.iter().map(|row| Some(&row.observations))).map_err(|e| e.to_string())?,
+        json_column(rows.iter().map(|row| Some(&row.state))).map_err(|e| e.to_string())?,
+    ];
+    let batch = RecordBatch::try_new(parquet_schema(), columns).map_err(|e| e.to_string())?;
+    writer.write(&batch).map_err(|e| e.to_string())
+}
+
+/// Writer of the estimator dataset of one node.
+///
+/// The file is completed by [`close`](EstimatorDatasetLogger::close) at the end of each run, or
+/// when the logger is dropped. The steps of a continued run are appended to the JSON Lines file,
+/// and written in a new Parquet part (`<node_name>.1.parquet`, ...).
+pub struct EstimatorDatasetLogger {
+    directory: PathBuf,
+    node_name: String,
+    format: EstimatorDatasetFormat,
+    sink: Option<DatasetSink>,
+    /// Number of closed files.
+    parts: usize,
+    include_bench: bool,
+}
+
+impl std::fmt::Debug for EstimatorDatasetLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EstimatorDatasetLogger")
+            .field("path", &self.path())
+            .field("include_bench", &self.include_bench)
+            .finish()
+    }
+}
+
+impl EstimatorDatasetLogger {
+    /// Create the dataset file of the node `node_name`.
+    pub fn from_config(
+        config: &EstimatorDatasetConfig,
+        global_config: &SimulatorConfig,
+        node_name: &String,
+    ) -> SimbaResult<Self> {
+        #[cfg(not(feature = "parquet"))]
+        if config.format == EstimatorDatasetFormat::Parquet {
+            return Err(SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                "Parquet estimator dataset requires simba to be built with the `parquet` feature"
+                    .to_string(),
+            ));
+        }
+        let mut logger = Self {
+            directory: global_config.base_path.as_ref().join(&config.output_path),
+            node_name: node_name.clone(),
+            format: config.format.clone(),
+            sink: None,
+            parts: 0,
+            include_bench: config.include_bench,
+        };
+        fs::create_dir_all(&logger.directory).map_err(|e| write_error(&logger.directory, e))?;
+        logger.open()?;
+        Ok(logger)
+    }
+
+    /// Whether the bench estimators should be logged.
+    pub fn include_bench(&self) -> bool {
+        self.include_bench
+    }
+
+    /// Path of the current dataset file.
+    pub fn path(&self) -> PathBuf {
+        match self.format {
+            EstimatorDatasetFormat::Parquet if self.parts > 0 => self
+                .directory
+                .join(format!("{}.{}.parquet", self.node_name, self.parts)),
+            _ => self
+                .directory
+                .join(format!("{}.{}", self.node_name, self.format.extension())),
+        }
+    }
+
+    fn open(&mut self) -> SimbaResult<&mut DatasetSink> {
+        if self.sink.is_none() {
+            let path = self.path();
+            // The JSON Lines of a continued run are appended
+            let append = self.parts > 0 && self.format == EstimatorDatasetFormat::JsonLines;
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&path)
+                .map_err(|e| write_error(&path, e))?;
+            self.sink = Some(match self.format {
+                EstimatorDatasetFormat::JsonLines => DatasetSink::JsonLines(BufWriter::new(file)),
+                #[cfg(feature = "parquet")]
+                EstimatorDatasetFormat::Parquet => DatasetSink::Parquet {
+                    writer: parquet::arrow::ArrowWriter::try_new(file, parquet_schema(), None)
+                        .map_err(|e| write_error(&path, e))?,
+                    rows: Vec::new(),
+                },
+                #[cfg(not(feature = "parquet"))]
+                EstimatorDatasetFormat::Parquet => unreachable!("Checked at creation"),
+            });
+        }
+        Ok(self.sink.as_mut().unwrap())
+    }
+
+    /// Append an entry to the dataset.
+    pub fn log(&mut self, entry: EstimatorDatasetEntry) -> SimbaResult<()> {
+        let path = self.path();
+        let result = match self.open()? {
+            DatasetSink::JsonLines(writer) => serde_json::to_writer(&mut *writer, &entry)
+                .map_err(|e| e.to_string())
+                .and_then(|_| writer.write_all(b"\n").map_err(|e| e.to_string())),
+            #[cfg(feature = "parquet")]
+            DatasetSink::Parquet { writer, rows } => {
+                rows.push(entry);
+                if rows.len() < PARQUET_BATCH_ROWS {
+                    return Ok(());
+                }
+                let result = write_parquet_rows(writer, rows);
+                rows.clear();
+                result
+            }
+        };
+        result.map_err(|e| write_error(&path, e))
+    }
+
+    /// Write the pending entries and close the file. The next entries are written in a new part.
+    /// Does nothing if the file is already closed.
+    pub fn close(&mut self) -> SimbaResult<()> {
+        let path = self.path();
+        match self.sink.take() {
+            Some(DatasetSink::JsonLines(mut writer)) => {
+                writer.flush().map_err(|e| write_error(&path, e))?
+            }
+            #[cfg(feature = "parquet")]
+            Some(DatasetSink::Parquet { mut writer, rows }) => {
+                write_parquet_rows(&mut writer, &rows).map_err(|e| write_error(&path, e))?;
+                writer.close().map_err(|e| write_error(&path, e))?;
+            }
+            None => return Ok(()),
+        }
+        self.parts += 1;
+        Ok(())
+    }
+}
+
+impl Drop for EstimatorDatasetLogger {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            warn!("{}", e.detailed_error());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        logger::LogLevel,
+        node::node_factory::RobotConfig,
+        simulator::{ResultConfig, Simulator, SimulatorConfig},
+    };
+
+    use super::*;
+
+    fn dataset_config(format: EstimatorDatasetFormat, directory: &Path) -> SimulatorConfig {
+        let mut config = SimulatorConfig::default();
+        config.log.log_level = LogLevel::Off;
+        config.max_time = 1.;
+        config.results = Some(ResultConfig {
+            estimator_dataset: Some(EstimatorDatasetConfig {
+                output_path: directory.to_string_lossy().to_string(),
+                format,
+                include_bench: true,
+            }),
+            ..Default::default()
+        });
+        config.robots.push(RobotConfig {
+            name: "node1".to_string(),
+            ..Default::default()
+        });
+        config
+    }
+
+    #[test]
+    fn json_lines_dataset() {
+        let directory =
+            std::env::temp_dir().join(format!("simba_estimator_dataset_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let config = dataset_config(EstimatorDatasetFormat::JsonLines, &directory);
+
+        let mut simulator = Simulator::from_config(&config, None).unwrap();
+        simulator.run().unwrap();
+
+        let content = fs::read_to_string(directory.join("node1.jsonl")).unwrap();
+        let entries: Vec<EstimatorDatasetEntry> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(!entries.is_empty());
+        assert!(
+            entries
+                .iter()
+                .all(|entry| entry.estimator == "state_estimator")
+        );
+        assert!(entries.iter().any(|entry| {
+            matches!(entry.step, EstimatorStep::Prediction) && entry.command.is_some()
+        }));
+        assert!(entries.windows(2).all(|pair| pair[0].time <= pair[1].time));
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn unwritable_dataset_is_an_error() {
+        let file = std::env::temp_dir().join(format!(
+            "simba_estimator_dataset_file_{}",
+            std::process::id()
+        ));
+        fs::write(&file, "").unwrap();
+        // The output directory is a file
+        let config = dataset_config(EstimatorDatasetFormat::JsonLines, &file);
+        assert!(Simulator::from_config(&config, None).is_err());
+        let _ = fs::remove_file(&file);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_dataset() {
+        use arrow_array::{Array, Float32Array, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let directory = std::env::temp_dir().join(format!(
+            "simba_estimator_dataset_parquet_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        let config = dataset_config(EstimatorDatasetFormat::Parquet, &directory);
+
+        let mut simulator = Simulator::from_config(&config, None).unwrap();
+        simulator.run().unwrap();
+
+        let file = File::open(directory.join("node1.parquet")).unwrap();
+        let batches: Vec<_> = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .map(|batch| batch.unwrap())
+            .collect();
+        assert!(!batches.is_empty());
+        let batch = &batches[0];
+        assert_eq!(batch.num_columns(), 7);
+        let time = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .unwrap();
+        assert_eq!(time.value(0), 0.);
+        let state = batch
+            .column(6)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        serde_json::from_str::<WorldStateRecord>(state.value(0)).unwrap();
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
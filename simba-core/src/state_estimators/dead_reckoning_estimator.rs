@@ -0,0 +1,731 @@
+/*!
+Module providing the [`DeadReckoningEstimator`] strategy, which integrates the motion of the node
+without any correction.
+
+The motion comes from the odometry
+([`SpeedObservation`](crate::sensors::speed_sensor::SpeedObservation)s and
+[`DisplacementObservation`](crate::sensors::displacement_sensor::DisplacementObservation)s) or
+from the commands sent to the physics, converted to velocities with the robot model
+([`DeadReckoningInput`]). The observations of the other sensors are ignored, so the estimate
+drifts: it is a lower bound for the benchmark of the other estimators
+(see [`BenchStateEstimatorConfig`](super::BenchStateEstimatorConfig)).
+
+With both odometry sensors, each interval is integrated once: the velocities move the estimate
+until the next displacement, which then replaces the motion integrated since the previous
+displacement.
+
+The covariance of the pose grows with the elapsed time and with the travelled distance, as
+configured.
+*/
+
+use nalgebra::{Rotation2, SMatrix, Vector2, Vector3};
+
+#[cfg(feature = "gui")]
+use super::ekf_estimator::{matrix_show, matrix_show_mut};
+use super::{
+    State, StateEstimator, StateEstimatorRecord, WorldState, WorldStateRecord,
+    ekf_estimator::to_matrix,
+};
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::string_combobox};
+#[cfg(feature = "gui")]
+use crate::utils::enum_tools::ToVec;
+use crate::{
+    constants::TIME_ROUND,
+    node::{
+        Node,
+        capabilities::{Capabilities, CommandKind, ObservationKind},
+    },
+    physics::{
+        PhysicsConfig,
+        internal_physics::InternalPhysicConfig,
+        rigid_body::RigidBodyPhysicsConfig,
+        robot_models::{Command, RobotModel, RobotModelConfig, make_model_from_config},
+    },
+    recordable::Recordable,
+    sensors::{Observation, SensorObservation},
+    simulator::SimulatorConfig,
+    utils::{
+        determinist_random_variable::DeterministRandomVariableFactory,
+        geometry::mod2pi,
+        periodicity::{Periodicity, PeriodicityConfig},
+    },
+};
+use config_checker::*;
+use log::{error, warn};
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+type Matrix3 = SMatrix<f32, 3, 3>;
+
+/// Source of the motion integrated by the [`DeadReckoningEstimator`].
+#[config_derives]
+pub enum DeadReckoningInput {
+    /// Velocities of the `Speed` observations and displacements of the `Displacement`
+    /// observations of the node. The displacements replace the velocities over the intervals
+    /// they cover.
+    Odometry,
+    /// Velocities requested by the commands sent to the physics, at each prediction: requires
+    /// the `prediction_activation`.
+    Commands,
+}
+
+impl Default for DeadReckoningInput {
+    fn default() -> Self {
+        Self::Odometry
+    }
+}
+
+/// Configuration for the [`DeadReckoningEstimator`] strategy.
+///
+/// The matrices are given row by row, on the `[x, y, orientation]` variables.
+///
+/// Default values:
+/// - `prediction_activation`: period of `0.1` s
+/// - `input`: [`DeadReckoningInput::Odometry`]
+/// - `robot_model`: `None`
+/// - `initial_pose`: `[0, 0, 0]`
+/// - `initial_covariance`: `diag(0, 0, 0)`
+/// - `time_growth`: `diag(0.001, 0.001, 0.0005)`
+/// - `distance_growth`: `diag(0.01, 0.01, 0.005)`
+///
+/// # Example
+/// ```yaml
+/// state_estimator:
+///   type: DeadReckoning
+///   prediction_activation:
+///     period: {type: Num, value: 0.1}
+///   input:
+///     type: Commands
+///   robot_model: {type: Unicycle, wheel_distance: 0.25}
+///   initial_pose: [0., 0., 0.]
+///   initial_covariance: [[0., 0., 0.], [0., 0., 0.], [0., 0., 0.]]
+///   time_growth: [[0.001, 0., 0.], [0., 0.001, 0.], [0., 0., 0.0005]]
+///   distance_growth: [[0.01, 0., 0.], [0., 0.01, 0.], [0., 0., 0.005]]
+/// ```
+#[config_derives]
+pub struct DeadReckoningEstimatorConfig {
+    /// Prediction period.
+    #[check]
+    pub prediction_activation: Option<PeriodicityConfig>,
+    /// Source of the integrated motion.
+    pub input: DeadReckoningInput,
+    /// Robot model converting the commands to velocities, for the
+    /// [`Commands`](DeadReckoningInput::Commands) input. If not set, the model of the physics is
+    /// used (internal and rigid body physics only), otherwise a unicycle.
+    #[check]
+    pub robot_model: Option<RobotModelConfig>,
+    /// Initial estimate of the pose.
+    pub initial_pose: [f32; 3],
+    /// Covariance of the initial estimate.
+    pub initial_covariance: [[f32; 3]; 3],
+    /// Growth of the covariance per second.
+    pub time_growth: [[f32; 3]; 3],
+    /// Growth of the covariance per meter travelled.
+    pub distance_growth: [[f32; 3]; 3],
+}
+
+impl Default for DeadReckoningEstimatorConfig {
+    fn default() -> Self {
+        Self {
+            prediction_activation: Some(PeriodicityConfig {
+                period: crate::config::NumberConfig::Num(0.1),
+                offset: None,
+                table: None,
+            }),
+            input: DeadReckoningInput::default(),
+            robot_model: None,
+            initial_pose: [0., 0., 0.],
+            initial_covariance: [[0., 0., 0.], [0., 0., 0.], [0., 0., 0.]],
+            time_growth: [[0.001, 0., 0.], [0., 0.001, 0.], [0., 0., 0.0005]],
+            distance_growth: [[0.01, 0., 0.], [0., 0.01, 0.], [0., 0., 0.005]],
+        }
+    }
+}
+
+impl Check for DeadReckoningEstimatorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for (name, matrix) in [
+            ("initial_covariance", &self.initial_covariance),
+            ("time_growth", &self.time_growth),
+            ("distance_growth", &self.distance_growth),
+        ] {
+            if (0..3).any(|i| matrix[i][i] < 0.) {
+                errors.push(format!(
+                    "The diagonal of {name} should be positive, got {matrix:?}"
+                ));
+            }
+            if (0..3).any(|i| (0..3).any(|j| matrix[i][j] != matrix[j][i])) {
+                errors.push(format!("{name} should be symmetric, got {matrix:?}"));
+            }
+        }
+        if matches!(self.input, DeadReckoningInput::Commands)
+            && self.prediction_activation.is_none()
+        {
+            errors.push(
+                "The Commands input is integrated at the predictions: prediction_activation is required"
+                    .to_string(),
+            );
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for DeadReckoningEstimatorConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Dead Reckoning Estimator")
+            .id_salt(format!("dead-reckoning-estimator-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Prediction activation:");
+                    if let Some(p) = &mut self.prediction_activation {
+                        p.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove").clicked() {
+                            self.prediction_activation = None;
+                        }
+                    } else {
+                        ui.label("None");
+                        if ui.button("Add").clicked() {
+                            self.prediction_activation = Self::default().prediction_activation;
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Input:");
+                    let mut current_str = self.input.to_string();
+                    string_combobox(
+                        ui,
+                        &DeadReckoningInput::to_vec(),
+                        &mut current_str,
+                        format!("dead-reckoning-input-choice-{}", unique_id),
+                    );
+                    if current_str != self.input.to_string() {
+                        match current_str.as_str() {
+                            "Odometry" => self.input = DeadReckoningInput::Odometry,
+                            "Commands" => self.input = DeadReckoningInput::Commands,
+                            _ => panic!("Where did you find this value?"),
+                        };
+                    }
+                });
+                if matches!(self.input, DeadReckoningInput::Commands) {
+                    ui.horizontal(|ui| {
+                        if let Some(model) = &mut self.robot_model {
+                            model.show_mut(
+                                ui,
+                                ctx,
+                                buffer_stack,
+                                global_config,
+                                current_node_name,
+                                unique_id,
+                            );
+                            if ui.button("Use physics model").clicked() {
+                                self.robot_model = None;
+                            }
+                        } else {
+                            ui.label("Robot model: from physics");
+                            if ui.button("Set model").clicked() {
+                                self.robot_model = Some(RobotModelConfig::default());
+                            }
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Initial pose:");
+                    for value in self.initial_pose.iter_mut() {
+                        ui.add(egui::DragValue::new(value).max_decimals(10));
+                    }
+                });
+                matrix_show_mut(
+                    ui,
+                    "Initial covariance",
+                    &mut self.initial_covariance,
+                    unique_id,
+                );
+                matrix_show_mut(ui, "Growth per second", &mut self.time_growth, unique_id);
+                matrix_show_mut(ui, "Growth per meter", &mut self.distance_growth, unique_id);
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Dead Reckoning Estimator")
+            .id_salt(format!("dead-reckoning-estimator-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Prediction activation:");
+                    if let Some(p) = &self.prediction_activation {
+                        p.show(ui, ctx, unique_id);
+                    } else {
+                        ui.label("None");
+                    }
+                });
+                ui.label(format!("Input: {}", self.input));
+                if matches!(self.input, DeadReckoningInput::Commands) {
+                    match &self.robot_model {
+                        Some(model) => model.show(ui, ctx, unique_id),
+                        None => {
+                            ui.label("Robot model: from physics");
+                        }
+                    }
+                }
+                ui.label(format!("Initial pose: {:?}", self.initial_pose));
+                matrix_show(ui, "Initial covariance", &self.initial_covariance);
+                matrix_show(ui, "Growth per second", &self.time_growth);
+                matrix_show(ui, "Growth per meter", &self.distance_growth);
+            });
+    }
+}
+
+/// Record for [`DeadReckoningEstimator`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeadReckoningEstimatorRecord {
+    /// Current state estimated.
+    pub world_state: WorldStateRecord,
+    /// Covariance of the estimated pose.
+    pub covariance: [[f32; 3]; 3],
+    /// Distance travelled since the start, or since the last reset.
+    pub travelled_distance: f32,
+    /// Last time the state was propagated.
+    pub last_time_prediction: f32,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for DeadReckoningEstimatorRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("World state").show(ui, |ui| {
+                self.world_state.show(ui, ctx, unique_id);
+            });
+            matrix_show(ui, "Covariance", &self.covariance);
+            ui.label(format!("Travelled distance: {}", self.travelled_distance));
+            ui.label(format!(
+                "Last prediction time: {}",
+                self.last_time_prediction
+            ));
+        });
+    }
+}
+
+/// Estimate when the last displacement was applied: the next displacement is applied from it,
+/// in place of the motion integrated from the velocities in between.
+#[derive(Debug, Clone)]
+struct DisplacementAnchor {
+    time: f32,
+    pose: Vector3<f32>,
+    covariance: Matrix3,
+    travelled_distance: f32,
+}
+
+/// Integration of the odometry or of the commands, without correction.
+#[derive(Debug)]
+pub struct DeadReckoningEstimator {
+    /// Estimation of the state on the `last_time_prediction`.
+    world_state: WorldState,
+    /// Covariance of the estimated pose.
+    covariance: Matrix3,
+    /// Covariance used at the initialization, and after a reset.
+    initial_covariance: Matrix3,
+    time_growth: Matrix3,
+    distance_growth: Matrix3,
+    input: DeadReckoningInput,
+    /// Model converting the commands, with the kind of commands it accepts.
+    robot_model: (Box<dyn RobotModel>, CommandKind),
+    travelled_distance: f32,
+    /// Prediction period, in seconds.
+    prediction_activation: Option<Periodicity>,
+    /// Last time the state was propagated.
+    last_time_prediction: f32,
+    displacement_anchor: DisplacementAnchor,
+}
+
+impl DeadReckoningEstimator {
+    /// Creates a new [`DeadReckoningEstimator`] from the given `config`, taking the robot model
+    /// from `physics_config` if not given.
+    pub fn from_config(
+        config: &DeadReckoningEstimatorConfig,
+        _global_config: &SimulatorConfig,
+        va_factory: &DeterministRandomVariableFactory,
+        physics_config: Option<&PhysicsConfig>,
+        initial_time: f32,
+    ) -> Self {
+        let robot_model = match (&config.robot_model, physics_config) {
+            (Some(model), _) => model.clone(),
+            (None, Some(PhysicsConfig::Internal(InternalPhysicConfig { model, .. })))
+            | (None, Some(PhysicsConfig::RigidBody(RigidBodyPhysicsConfig { model, .. }))) => {
+                model.clone()
+            }
+            (None, _) => {
+                if matches!(config.input, DeadReckoningInput::Commands) {
+                    warn!("No model given in DeadReckoningEstimator Config... using default one");
+                }
+                RobotModelConfig::default()
+            }
+        };
+        let mut world_state = WorldState::new();
+        world_state.ego = Some(State::from_vector(&config.initial_pose));
+        let displacement_anchor = DisplacementAnchor {
+            time: initial_time,
+            pose: Vector3::from(config.initial_pose),
+            covariance: to_matrix(&config.initial_covariance),
+            travelled_distance: 0.,
+        };
+        let prediction_activation = config
+            .prediction_activation
+            .as_ref()
+            .map(|p| Periodicity::from_config(p, va_factory, initial_time));
+        Self {
+            world_state,
+            covariance: to_matrix(&config.initial_covariance),
+            initial_covariance: to_matrix(&config.initial_covariance),
+            time_growth: to_matrix(&config.time_growth),
+            distance_growth: to_matrix(&config.distance_growth),
+            input: config.input.clone(),
+            robot_model: (
+                make_model_from_config(&robot_model),
+                CommandKind::from(&robot_model),
+            ),
+            travelled_distance: 0.,
+            prediction_activation,
+            last_time_prediction: initial_time,
+            displacement_anchor,
+        }
+    }
+
+    fn ego(&self) -> &State {
+        self.world_state.ego.as_ref().unwrap()
+    }
+
+    /// Move the pose by `translation` and `rotation`, relative to the current pose, and grow the
+    /// covariance for a move lasting `dt` seconds.
+    fn move_by(&mut self, translation: Vector2<f32>, rotation: f32, dt: f32) {
+        let ego = self.world_state.ego.as_mut().unwrap();
+        let delta = Rotation2::new(ego.pose.z) * translation;
+        let jacobian = Matrix3::new(1., 0., -delta.y, 0., 1., delta.x, 0., 0., 1.);
+        ego.pose.x += delta.x;
+        ego.pose.y += delta.y;
+        ego.pose.z = mod2pi(ego.pose.z + rotation);
+        let distance = translation.norm();
+        self.covariance = jacobian * self.covariance * jacobian.transpose()
+            + self.time_growth * dt
+            + self.distance_growth * distance;
+        self.travelled_distance += distance;
+    }
+
+    /// Keep the current estimate, at `time`, as the start of the next displacement.
+    fn anchor_displacement(&mut self, time: f32) {
+        self.displacement_anchor = DisplacementAnchor {
+            time,
+            pose: self.ego().pose,
+            covariance: self.covariance,
+            travelled_distance: self.travelled_distance,
+        };
+    }
+
+    /// Apply the displacement observed at `time`, since the previous displacement, in place of
+    /// the motion integrated from the velocities since then.
+    fn apply_displacement(&mut self, translation: Vector2<f32>, rotation: f32, time: f32) {
+        let anchor = self.displacement_anchor.clone();
+        self.world_state.ego.as_mut().unwrap().pose = anchor.pose;
+        self.covariance = anchor.covariance;
+        self.travelled_distance = anchor.travelled_distance;
+        self.move_by(translation, rotation, (time - anchor.time).max(0.));
+        // The velocities are integrated again from the displacement
+        self.last_time_prediction = time;
+        self.anchor_displacement(time);
+    }
+
+    /// Propagate the pose up to `time` with the last known velocities.
+    fn propagate(&mut self, time: f32) {
+        let dt = time - self.last_time_prediction;
+        if dt <= 0. {
+            return;
+        }
+        let velocity = self.ego().velocity;
+        self.move_by(
+            Vector2::new(velocity.x, velocity.y) * dt,
+            velocity.z * dt,
+            dt,
+        );
+        self.last_time_prediction = time;
+    }
+
+    /// Integrate the odometry observations of the node `node_name`, up to `time`.
+    fn integrate_odometry(&mut self, node_name: &str, observations: &[Observation], time: f32) {
+        for observation in observations {
+            // Observations received from other nodes are not about the ego motion, and the
+            // observations in another frame are rejected by the capabilities check
+            if observation.observer != node_name || !observation.in_native_frame() {
+                continue;
+            }
+            match &observation.sensor_observation {
+                SensorObservation::Speed(obs) => {
+                    self.propagate(observation.time.min(time));
+                    self.world_state.ego.as_mut().unwrap().velocity = Vector3::new(
+                        obs.linear_velocity,
+                        obs.lateral_velocity,
+                        obs.angular_velocity,
+                    );
+                }
+                SensorObservation::Displacement(obs) => {
+                    self.apply_displacement(
+                        obs.translation,
+                        obs.rotation,
+                        observation.time.min(time),
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Velocity requested by the `command`, `None` if the robot model does not accept it or
+    /// does not give its body velocity.
+    fn command_velocity(&self, command: &Command) -> Option<Vector3<f32>> {
+        let (model, kind) = &self.robot_model;
+        if CommandKind::from(command) != *kind {
+            error!(
+                "{} command not accepted by the {kind} model of the dead reckoning",
+                CommandKind::from(command)
+            );
+            return None;
+        }
+        model.body_velocity(command)
+    }
+}
+
+impl StateEstimator for DeadReckoningEstimator {
+    fn prediction_step(&mut self, _node: &mut Node, command: Option<Command>, time: f32) {
+        if (time - self.next_time_step()).abs() > TIME_ROUND / 2. {
+            error!(
+                "Error trying to update estimate too soon! (it is {} but expecting {})",
+                time,
+                self.next_time_step()
+            );
+            return;
+        }
+        // The current command was applied since the last control step
+        if matches!(self.input, DeadReckoningInput::Commands)
+            && let Some(velocity) = command.and_then(|c| self.command_velocity(&c))
+        {
+            self.world_state.ego.as_mut().unwrap().velocity = velocity;
+        }
+        self.propagate(time);
+        if let Some(p) = self.prediction_activation.as_mut() {
+            p.update(time);
+        }
+    }
+
+    fn correction_step(&mut self, node: &mut Node, observations: &[Observation], time: f32) {
+        if matches!(self.input, DeadReckoningInput::Commands) {
+            return;
+        }
+        self.integrate_odometry(&node.name(), observations, time);
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            consumed_observations: Some(match self.input {
+                DeadReckoningInput::Odometry => {
+                    vec![ObservationKind::Speed, ObservationKind::Displacement]
+                }
+                DeadReckoningInput::Commands => Vec::new(),
+            }),
+            // Observations in their native frame only
+            consumed_frames: Some(Vec::new()),
+            ..Default::default()
+        }
+    }
+
+    fn world_state(&self) -> WorldState {
+        self.world_state.clone()
+    }
+
+    fn next_time_step(&self) -> f32 {
+        if let Some(period) = &self.prediction_activation {
+            period.next_time()
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+
+    fn pose_covariance(&self) -> Option<SMatrix<f32, 3, 3>> {
+        Some(self.covariance)
+    }
+
+    fn reset_state(&mut self, state: State, time: f32) -> Result<(), String> {
+        self.world_state.ego = Some(state);
+        self.covariance = self.initial_covariance;
+        self.travelled_distance = 0.;
+        self.last_time_prediction = time;
+        self.anchor_displacement(time);
+        Ok(())
+    }
+
+    /// Resets the ego state to the ego estimate of the `record`, and restores the covariance of
+    /// a `DeadReckoning` record.
+    fn warm_start(&mut self, record: &StateEstimatorRecord, time: f32) -> Result<(), String> {
+        let ego = record
+            .ego()
+            .ok_or_else(|| "The record has no ego estimate".to_string())?;
+        self.reset_state(State::from_record(ego), time)?;
+        if let StateEstimatorRecord::DeadReckoning(record) = record {
+            self.covariance = to_matrix(&record.covariance);
+            self.travelled_distance = record.travelled_distance;
+            self.anchor_displacement(time);
+        }
+        Ok(())
+    }
+}
+
+impl Recordable<StateEstimatorRecord> for DeadReckoningEstimator {
+    fn record(&self) -> StateEstimatorRecord {
+        StateEstimatorRecord::DeadReckoning(DeadReckoningEstimatorRecord {
+            world_state: self
+                .world_state
+                .record()
+                .with_ego_covariance(self.pose_covariance()),
+            covariance: std::array::from_fn(|i| std::array::from_fn(|j| self.covariance[(i, j)])),
+            travelled_distance: self.travelled_distance,
+            last_time_prediction: self.last_time_prediction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::robot_models::{holonomic::HolonomicCommand, unicycle::UnicycleCommand};
+
+    fn estimator(input: DeadReckoningInput) -> DeadReckoningEstimator {
+        DeadReckoningEstimator::from_config(
+            &DeadReckoningEstimatorConfig {
+                input,
+                ..Default::default()
+            },
+            &SimulatorConfig::default(),
+            &DeterministRandomVariableFactory::default(),
+            None,
+            0.,
+        )
+    }
+
+    #[test]
+    fn integration_and_covariance_growth() {
+        let mut estimator = estimator(DeadReckoningInput::Odometry);
+        estimator.world_state.ego.as_mut().unwrap().velocity = Vector3::new(1., 0., 0.);
+        estimator.propagate(2.);
+        assert!((estimator.ego().pose.x - 2.).abs() < 1e-6);
+        assert!((estimator.travelled_distance - 2.).abs() < 1e-6);
+        let expected = estimator.time_growth * 2. + estimator.distance_growth * 2.;
+        assert!((estimator.covariance - expected).norm() < 1e-6);
+
+        // Quarter turn, then 1 m forward in the robot frame
+        estimator.move_by(Vector2::zeros(), std::f32::consts::FRAC_PI_2, 0.);
+        let covariance = estimator.covariance;
+        estimator.move_by(Vector2::new(1., 0.), 0., 0.);
+        assert!(
+            (estimator.ego().pose - Vector3::new(2., 1., std::f32::consts::FRAC_PI_2)).norm()
+                < 1e-6
+        );
+        // Heading uncertainty grows the uncertainty across the motion
+        assert!(
+            estimator.covariance[(0, 0)] - covariance[(0, 0)] > estimator.distance_growth[(0, 0)]
+        );
+    }
+
+    #[test]
+    fn speed_and_displacement_counted_once() {
+        use crate::sensors::{
+            displacement_sensor::DisplacementObservation, speed_sensor::SpeedObservation,
+        };
+
+        let mut estimator = estimator(DeadReckoningInput::Odometry);
+        let observation = |time: f32, sensor_observation: SensorObservation| Observation {
+            observer: "robot".to_string(),
+            time,
+            sensor_observation,
+            ..Observation::new()
+        };
+        let speed = observation(
+            0.,
+            SensorObservation::Speed(SpeedObservation {
+                linear_velocity: 1.,
+                ..Default::default()
+            }),
+        );
+        let displacement = |time: f32| {
+            observation(
+                time,
+                SensorObservation::Displacement(DisplacementObservation {
+                    translation: Vector2::new(1., 0.),
+                    rotation: 0.,
+                    applied_faults: Vec::new(),
+                }),
+            )
+        };
+        estimator.integrate_odometry("robot", &[speed], 0.);
+        // Integrated from the speed until the displacement
+        estimator.propagate(0.5);
+        assert!((estimator.ego().pose.x - 0.5).abs() < 1e-6);
+        // The displacement replaces the speed integration over [0, 1]
+        estimator.integrate_odometry("robot", &[displacement(1.)], 1.);
+        assert!((estimator.ego().pose.x - 1.).abs() < 1e-6);
+        assert!((estimator.travelled_distance - 1.).abs() < 1e-6);
+        estimator.propagate(1.5);
+        estimator.integrate_odometry("robot", &[displacement(2.)], 2.);
+        assert!((estimator.ego().pose.x - 2.).abs() < 1e-6);
+        assert!((estimator.travelled_distance - 2.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn commands_require_predictions() {
+        let mut config = DeadReckoningEstimatorConfig {
+            input: DeadReckoningInput::Commands,
+            ..Default::default()
+        };
+        assert!(config.check().is_ok());
+        config.prediction_activation = None;
+        assert!(config.check().is_err());
+        config.input = DeadReckoningInput::Odometry;
+        assert!(config.check().is_ok());
+    }
+
+    #[test]
+    fn commands_through_robot_model() {
+        let mut estimator = estimator(DeadReckoningInput::Commands);
+        let command = Command::Unicycle(UnicycleCommand {
+            left_wheel_speed: 1.,
+            right_wheel_speed: 1.,
+        });
+        let velocity = estimator.command_velocity(&command).unwrap();
+        assert_eq!(velocity, Vector3::new(1., 0., 0.));
+        let command = Command::Holonomic(HolonomicCommand {
+            longitudinal_velocity: 1.,
+            lateral_velocity: 0.,
+            angular_velocity: 0.,
+        });
+        assert!(estimator.command_velocity(&command).is_none());
+    }
+}
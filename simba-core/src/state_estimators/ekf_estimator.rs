@@ -0,0 +1,556 @@
+/*!
+Module providing the [`EkfEstimator`] strategy, an Extended Kalman Filter estimating the ego
+pose `[x, y, orientation]` of the node.
+
+The velocities given by the
+[`SpeedObservation`](crate::sensors::speed_sensor::SpeedObservation)s (odometry) are used as input of the
+prediction, with a unicycle-like kinematic model. The pose is corrected with the
+[`GNSSObservation`](crate::sensors::gnss_sensor::GNSSObservation)s (direct measurement of the pose) and the
+[`OrientedLandmarkObservation`](crate::sensors::oriented_landmark_sensor::OrientedLandmarkObservation)s
+(pose of a landmark of the environment map, relative to the node).
+
+The landmarks are the prior knowledge of the map
+([`Environment::prior_landmarks`](crate::environment::Environment::prior_landmarks)): without
+known map, the landmark observations are ignored.
+
+The noise matrices are given in the configuration. The process noise is given per second: it is
+scaled by the duration of each prediction.
+*/
+
+use nalgebra::{SMatrix, Vector3};
+
+use super::{State, StateEstimator, StateEstimatorRecord, WorldState, WorldStateRecord};
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+use crate::{
+    constants::TIME_ROUND,
+    node::{
+        Node,
+        capabilities::{Capabilities, ObservationKind},
+    },
+    physics::robot_models::Command,
+    recordable::Recordable,
+    sensors::{Observation, SensorObservation},
+    simulator::SimulatorConfig,
+    utils::{
+        determinist_random_variable::DeterministRandomVariableFactory,
+        geometry::mod2pi,
+        periodicity::{Periodicity, PeriodicityConfig},
+    },
+};
+use config_checker::*;
+use log::error;
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+type Matrix3 = SMatrix<f32, 3, 3>;
+
+/// Configuration for the [`EkfEstimator`] strategy.
+///
+/// The matrices are given row by row, on the `[x, y, orientation]` variables.
+///
+/// # Example
+/// ```yaml
+/// state_estimator:
+///   type: Ekf
+///   prediction_activation:
+///     period: {type: Num, value: 0.1}
+///   initial_pose: [0., 0., 0.]
+///   initial_covariance: [[1., 0., 0.], [0., 1., 0.], [0., 0., 0.1]]
+///   process_noise: [[0.01, 0., 0.], [0., 0.01, 0.], [0., 0., 0.005]]
+///   gnss_noise: [[0.25, 0., 0.], [0., 0.25, 0.], [0., 0., 0.01]]
+///   landmark_noise: [[0.04, 0., 0.], [0., 0.04, 0.], [0., 0., 0.01]]
+/// ```
+#[config_derives]
+pub struct EkfEstimatorConfig {
+    /// Prediction period.
+    #[check]
+    pub prediction_activation: Option<PeriodicityConfig>,
+    /// Initial estimate of the pose.
+    pub initial_pose: [f32; 3],
+    /// Covariance of the initial estimate.
+    pub initial_covariance: [[f32; 3]; 3],
+    /// Covariance of the process noise, per second of prediction.
+    pub process_noise: [[f32; 3]; 3],
+    /// Covariance of the GNSS pose measurement.
+    pub gnss_noise: [[f32; 3]; 3],
+    /// Covariance of the landmark relative pose measurement.
+    pub landmark_noise: [[f32; 3]; 3],
+}
+
+impl Default for EkfEstimatorConfig {
+    fn default() -> Self {
+        Self {
+            prediction_activation: Some(PeriodicityConfig {
+                period: crate::config::NumberConfig::Num(0.1),
+                offset: None,
+                table: None,
+            }),
+            initial_pose: [0., 0., 0.],
+            initial_covariance: [[1., 0., 0.], [0., 1., 0.], [0., 0., 0.1]],
+            process_noise: [[0.01, 0., 0.], [0., 0.01, 0.], [0., 0., 0.005]],
+            gnss_noise: [[0.25, 0., 0.], [0., 0.25, 0.], [0., 0., 0.01]],
+            landmark_noise: [[0.04, 0., 0.], [0., 0.04, 0.], [0., 0., 0.01]],
+        }
+    }
+}
+
+impl Check for EkfEstimatorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for (name, matrix) in [
+            ("initial_covariance", &self.initial_covariance),
+            ("process_noise", &self.process_noise),
+            ("gnss_noise", &self.gnss_noise),
+            ("landmark_noise", &self.landmark_noise),
+        ] {
+            if (0..3).any(|i| matrix[i][i] < 0.) {
+                errors.push(format!(
+                    "The diagonal of {name} should be positive, got {matrix:?}"
+                ));
+            }
+            if (0..3).any(|i| (0..3).any(|j| matrix[i][j] != matrix[j][i])) {
+                errors.push(format!("{name} should be symmetric, got {matrix:?}"));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub(super) fn matrix_show_mut(
+    ui: &mut egui::Ui,
+    name: &str,
+    matrix: &mut [[f32; 3]; 3],
+    unique_id: &str,
+) {
+    ui.label(format!("{name}:"));
+    egui::Grid::new(format!("{name}-{unique_id}")).show(ui, |ui| {
+        for row in matrix.iter_mut() {
+            for value in row.iter_mut() {
+                ui.add(egui::DragValue::new(value).speed(0.001).max_decimals(6));
+            }
+            ui.end_row();
+        }
+    });
+}
+
+#[cfg(feature = "gui")]
+pub(super) fn matrix_show(ui: &mut egui::Ui, name: &str, matrix: &[[f32; 3]; 3]) {
+    ui.label(format!("{name}: {matrix:?}"));
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for EkfEstimatorConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("EKF Estimator")
+            .id_salt(format!("ekf-estimator-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Prediction activation:");
+                    if let Some(p) = &mut self.prediction_activation {
+                        p.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove").clicked() {
+                            self.prediction_activation = None;
+                        }
+                    } else {
+                        ui.label("None");
+                        if ui.button("Add").clicked() {
+                            self.prediction_activation = Self::default().prediction_activation;
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Initial pose:");
+                    for value in self.initial_pose.iter_mut() {
+                        ui.add(egui::DragValue::new(value).max_decimals(10));
+                    }
+                });
+                matrix_show_mut(
+                    ui,
+                    "Initial covariance",
+                    &mut self.initial_covariance,
+                    unique_id,
+                );
+                matrix_show_mut(ui, "Process noise", &mut self.process_noise, unique_id);
+                matrix_show_mut(ui, "GNSS noise", &mut self.gnss_noise, unique_id);
+                matrix_show_mut(ui, "Landmark noise", &mut self.landmark_noise, unique_id);
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("EKF Estimator")
+            .id_salt(format!("ekf-estimator-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Prediction activation:");
+                    if let Some(p) = &self.prediction_activation {
+                        p.show(ui, ctx, unique_id);
+                    } else {
+                        ui.label("None");
+                    }
+                });
+                ui.label(format!("Initial pose: {:?}", self.initial_pose));
+                matrix_show(ui, "Initial covariance", &self.initial_covariance);
+                matrix_show(ui, "Process noise", &self.process_noise);
+                matrix_show(ui, "GNSS noise", &self.gnss_noise);
+                matrix_show(ui, "Landmark noise", &self.landmark_noise);
+            });
+    }
+}
+
+/// Record for [`EkfEstimator`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EkfEstimatorRecord {
+    /// Current state estimated.
+    pub world_state: WorldStateRecord,
+    /// Covariance of the estimated pose.
+    pub covariance: [[f32; 3]; 3],
+    /// Last time the state was propagated.
+    pub last_time_prediction: f32,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for EkfEstimatorRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("World state").show(ui, |ui| {
+                self.world_state.show(ui, ctx, unique_id);
+            });
+            matrix_show(ui, "Covariance", &self.covariance);
+            ui.label(format!(
+                "Last prediction time: {}",
+                self.last_time_prediction
+            ));
+        });
+    }
+}
+
+pub(super) fn to_matrix(matrix: &[[f32; 3]; 3]) -> Matrix3 {
+    Matrix3::from_fn(|i, j| matrix[i][j])
+}
+
+/// Expected pose of `target` relative to `pose`, with the jacobian of this measurement with
+/// respect to `pose`.
+pub(super) fn relative_pose_measurement(
+    pose: &Vector3<f32>,
+    target: &Vector3<f32>,
+) -> (Vector3<f32>, Matrix3) {
+    let (sin, cos) = pose.z.sin_cos();
+    let (dx, dy) = (target.x - pose.x, target.y - pose.y);
+    let expected = Vector3::new(cos * dx + sin * dy, -sin * dx + cos * dy, target.z - pose.z);
+    let h = Matrix3::new(-cos, -sin, expected.y, sin, -cos, -expected.x, 0., 0., -1.);
+    (expected, h)
+}
+
+/// Extended Kalman Filter on the ego pose.
+#[derive(Debug)]
+pub struct EkfEstimator {
+    /// Estimation of the state on the `last_time_prediction`. The landmarks are the prior
+    /// landmarks of the environment.
+    world_state: WorldState,
+    /// Covariance of the estimated pose.
+    covariance: Matrix3,
+    /// Covariance used at the initialization, and after a reset.
+    initial_covariance: Matrix3,
+    /// Largest normalized innovation squared of the last correction step.
+    last_innovation: Option<f32>,
+    process_noise: Matrix3,
+    gnss_noise: Matrix3,
+    landmark_noise: Matrix3,
+    /// Prediction period, in seconds.
+    prediction_activation: Option<Periodicity>,
+    /// Last time the state was propagated.
+    last_time_prediction: f32,
+}
+
+impl EkfEstimator {
+    /// Creates a new [`EkfEstimator`] from the given `config`.
+    pub fn from_config(
+        config: &EkfEstimatorConfig,
+        _global_config: &SimulatorConfig,
+        va_factory: &DeterministRandomVariableFactory,
+        initial_time: f32,
+    ) -> Self {
+        let mut world_state = WorldState::new();
+        world_state.ego = Some(State::from_vector(&config.initial_pose));
+        let prediction_activation = config
+            .prediction_activation
+            .as_ref()
+            .map(|p| Periodicity::from_config(p, va_factory, initial_time));
+        Self {
+            world_state,
+            covariance: to_matrix(&config.initial_covariance),
+            initial_covariance: to_matrix(&config.initial_covariance),
+            last_innovation: None,
+            process_noise: to_matrix(&config.process_noise),
+            gnss_noise: to_matrix(&config.gnss_noise),
+            landmark_noise: to_matrix(&config.landmark_noise),
+            prediction_activation,
+            last_time_prediction: initial_time,
+        }
+    }
+
+    pub(super) fn ego(&self) -> &State {
+        self.world_state.ego.as_ref().unwrap()
+    }
+
+    /// Propagate the pose up to `time` with the last known velocities.
+    pub(super) fn propagate(&mut self, time: f32) {
+        let dt = time - self.last_time_prediction;
+        if dt <= 0. {
+            return;
+        }
+        let ego = self.world_state.ego.as_mut().unwrap();
+        let (v, v_lat, w) = (ego.velocity.x, ego.velocity.y, ego.velocity.z);
+        let (sin, cos) = ego.pose.z.sin_cos();
+        let dx = (v * cos - v_lat * sin) * dt;
+        let dy = (v * sin + v_lat * cos) * dt;
+        let jacobian = Matrix3::new(1., 0., -dy, 0., 1., dx, 0., 0., 1.);
+        ego.pose.x += dx;
+        ego.pose.y += dy;
+        ego.pose.z = mod2pi(ego.pose.z + w * dt);
+        self.covariance =
+            jacobian * self.covariance * jacobian.transpose() + self.process_noise * dt;
+        self.last_time_prediction = time;
+    }
+
+    /// Prediction step at `time`. Returns `false` if the prediction is rejected because `time`
+    /// is not the next time step.
+    pub(super) fn predict(&mut self, time: f32) -> bool {
+        if (time - self.next_time_step()).abs() > TIME_ROUND / 2. {
+            error!(
+                "Error trying to update estimate too soon! (it is {} but expecting {})",
+                time,
+                self.next_time_step()
+            );
+            return false;
+        }
+        self.propagate(time);
+        if let Some(p) = self.prediction_activation.as_mut() {
+            p.update(time);
+        }
+        true
+    }
+
+    /// Kalman update with the `innovation` of a measurement of jacobian `h` and covariance `r`.
+    ///
+    /// The orientation innovation should already be normalized.
+    pub(super) fn update(&mut self, innovation: Vector3<f32>, h: Matrix3, r: Matrix3) {
+        let s = h * self.covariance * h.transpose() + r;
+        let Some(s_inv) = s.try_inverse() else {
+            error!("Singular innovation covariance, measurement ignored");
+            return;
+        };
+        let nis = innovation.dot(&(s_inv * innovation));
+        self.last_innovation = Some(self.last_innovation.map_or(nis, |last| last.max(nis)));
+        let gain = self.covariance * h.transpose() * s_inv;
+        let ego = self.world_state.ego.as_mut().unwrap();
+        ego.pose += gain * innovation;
+        ego.pose.z = mod2pi(ego.pose.z);
+        self.covariance = (Matrix3::identity() - gain * h) * self.covariance;
+    }
+
+    /// Correct the pose with a measurement of the absolute pose.
+    fn gnss_update(&mut self, pose: &Vector3<f32>) {
+        let mut innovation = pose - self.ego().pose;
+        innovation.z = mod2pi(innovation.z);
+        self.update(innovation, Matrix3::identity(), self.gnss_noise);
+    }
+
+    /// Correct the pose with the `observed` pose of a landmark at `landmark`, relative to the node.
+    fn landmark_update(&mut self, landmark: &Vector3<f32>, observed: &Vector3<f32>) {
+        let (expected, h) = relative_pose_measurement(&self.ego().pose, landmark);
+        let mut innovation = observed - expected;
+        innovation.z = mod2pi(innovation.z);
+        self.update(innovation, h, self.landmark_noise);
+    }
+}
+
+impl StateEstimator for EkfEstimator {
+    fn post_init(&mut self, node: &mut Node) -> crate::errors::SimbaResult<()> {
+        self.world_state.landmarks = node.environment().prior_landmarks();
+        Ok(())
+    }
+
+    fn prediction_step(&mut self, _node: &mut Node, _command: Option<Command>, time: f32) {
+        self.predict(time);
+    }
+
+    fn correction_step(&mut self, node: &mut Node, observations: &[Observation], time: f32) {
+        let node_name = node.name();
+        self.last_innovation = None;
+        for observation in observations {
+            // Observations received from other nodes are not about the ego pose, and the
+            // observations in another frame are rejected by the capabilities check
+            if observation.observer != node_name || !observation.in_native_frame() {
+                continue;
+            }
+            self.propagate(observation.time.min(time));
+            match &observation.sensor_observation {
+                SensorObservation::Speed(obs) => {
+                    self.world_state.ego.as_mut().unwrap().velocity = Vector3::new(
+                        obs.linear_velocity,
+                        obs.lateral_velocity,
+                        obs.angular_velocity,
+                    );
+                }
+                SensorObservation::GNSS(obs) => self.gnss_update(&obs.pose),
+                SensorObservation::OrientedLandmark(obs) => {
+                    if let Some(landmark) = self.world_state.landmarks.get(&obs.id) {
+                        let landmark = landmark.pose;
+                        self.landmark_update(&landmark, &obs.pose);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            consumed_observations: Some(vec![
+                ObservationKind::Speed,
+                ObservationKind::GNSS,
+                ObservationKind::OrientedLandmark,
+            ]),
+            // Observations in their native frame only
+            consumed_frames: Some(Vec::new()),
+            ..Default::default()
+        }
+    }
+
+    fn world_state(&self) -> WorldState {
+        self.world_state.clone()
+    }
+
+    fn next_time_step(&self) -> f32 {
+        if let Some(period) = &self.prediction_activation {
+            period.next_time()
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+
+    fn pose_covariance(&self) -> Option<SMatrix<f32, 3, 3>> {
+        Some(self.covariance)
+    }
+
+    fn innovation_statistic(&self) -> Option<f32> {
+        self.last_innovation
+    }
+
+    fn reset_state(&mut self, state: State, time: f32) -> Result<(), String> {
+        self.world_state.ego = Some(state);
+        self.covariance = self.initial_covariance;
+        self.last_innovation = None;
+        self.last_time_prediction = time;
+        Ok(())
+    }
+
+    /// Resets the ego state to the ego estimate of the `record`, and restores the covariance of
+    /// an `Ekf` record.
+    fn warm_start(&mut self, record: &StateEstimatorRecord, time: f32) -> Result<(), String> {
+        let ego = record
+            .ego()
+            .ok_or_else(|| "The record has no ego estimate".to_string())?;
+        self.reset_state(State::from_record(ego), time)?;
+        if let StateEstimatorRecord::Ekf(record) = record {
+            self.covariance = Matrix3::from_fn(|i, j| record.covariance[i][j]);
+        }
+        Ok(())
+    }
+}
+
+impl Recordable<StateEstimatorRecord> for EkfEstimator {
+    fn record(&self) -> StateEstimatorRecord {
+        StateEstimatorRecord::Ekf(EkfEstimatorRecord {
+            world_state: self
+                .world_state
+                .record()
+                .with_ego_covariance(self.pose_covariance()),
+            covariance: std::array::from_fn(|i| std::array::from_fn(|j| self.covariance[(i, j)])),
+            last_time_prediction: self.last_time_prediction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimator() -> EkfEstimator {
+        EkfEstimator::from_config(
+            &EkfEstimatorConfig::default(),
+            &SimulatorConfig::default(),
+            &DeterministRandomVariableFactory::default(),
+            0.,
+        )
+    }
+
+    #[test]
+    fn prediction_follows_odometry() {
+        let mut ekf = estimator();
+        ekf.world_state.ego.as_mut().unwrap().velocity = Vector3::new(1., 0., 0.);
+        let covariance = ekf.covariance;
+        ekf.propagate(2.);
+        assert!((ekf.ego().pose.x - 2.).abs() < 1e-6);
+        assert!(ekf.covariance[(0, 0)] > covariance[(0, 0)]);
+        // Heading uncertainty grows the lateral uncertainty
+        assert!(ekf.covariance[(1, 1)] - covariance[(1, 1)] > ekf.process_noise[(1, 1)] * 2.);
+    }
+
+    #[test]
+    fn landmark_correction_reduces_error() {
+        let mut ekf = estimator();
+        // True pose is (1, 0, 0): a landmark at (3, 1, 0) is seen at (2, 1, 0)
+        let landmark = Vector3::new(3., 1., 0.);
+        for _ in 0..10 {
+            ekf.landmark_update(&landmark, &Vector3::new(2., 1., 0.));
+        }
+        let pose = ekf.ego().pose;
+        assert!((pose.x - 1.).abs() < 0.1, "{pose}");
+        assert!(pose.y.abs() < 0.1, "{pose}");
+        assert!(ekf.covariance[(0, 0)] < 0.1);
+    }
+
+    #[test]
+    fn warm_start_from_record() {
+        let mut ekf = estimator();
+        ekf.world_state.ego.as_mut().unwrap().velocity = Vector3::new(1., 0., 0.);
+        ekf.propagate(2.);
+        let record = ekf.record();
+        // The covariance is also recorded with the ego estimate
+        assert_eq!(
+            record.ego().unwrap().pose_covariance,
+            record.pose_covariance()
+        );
+
+        let mut warm_ekf = estimator();
+        warm_ekf.warm_start(&record, 0.).unwrap();
+        assert_eq!(warm_ekf.ego().pose, ekf.ego().pose);
+        assert_eq!(warm_ekf.covariance, ekf.covariance);
+        assert_eq!(warm_ekf.last_time_prediction, 0.);
+    }
+}
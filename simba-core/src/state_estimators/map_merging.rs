@@ -0,0 +1,701 @@
+/*!
+Module providing the [`MapMerging`] strategy, a reference implementation of collaborative
+mapping meant to run on a computation unit.
+
+The robots share their partial maps (landmarks and/or occupancy grid) by sending a
+[`MapShareMessage`] on the [`MapMerging::SHARE_CHANNEL`] channel of the unit, e.g.
+`/simba/nodes/<unit_name>/map_merging/share`. Only the last map of each robot is kept, so a
+robot should share its whole current map.
+
+At each prediction step, the maps of all the robots are merged into the landmarks and the
+occupancy grid of the estimated [`WorldState`], which is recorded. The merged map is then
+published on the [`MapMerging::MERGED_CHANNEL`] channel of the unit
+(`/simba/nodes/<unit_name>/map_merging/merged`), as a [`MapShareMessage`].
+
+With a `share_bandwidth`, both channels are limited in bandwidth: the maps are delayed by their
+serialized size, so sharing large grids takes time.
+*/
+
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use nalgebra::Vector3;
+use pyo3::{pyclass, pymethods};
+
+use super::{State, StateEstimator, StateEstimatorRecord, WorldState, WorldStateRecord};
+#[cfg(feature = "gui")]
+use crate::gui::{UIComponent, utils::string_combobox};
+#[cfg(feature = "gui")]
+use crate::utils::enum_tools::ToVec;
+use crate::{
+    constants::TIME_ROUND,
+    networking::network::{Envelope, Network},
+    node::Node,
+    physics::robot_models::Command,
+    pywrappers::OccupancyGridWrapper,
+    recordable::Recordable,
+    sensors::Observation,
+    simulator::{SimbaBrokerMultiClient, SimulatorConfig},
+    utils::{
+        SharedMutex, SharedRwLock,
+        determinist_random_variable::DeterministRandomVariableFactory,
+        geometry::mod2pi,
+        occupancy_grid::OccupancyGrid,
+        periodicity::{Periodicity, PeriodicityConfig},
+    },
+};
+use config_checker::*;
+use log::{error, warn};
+use serde_derive::{Deserialize, Serialize};
+use simba_com::pub_sub::{ChannelLimits, MultiClientTrait, PathKey};
+use simba_macros::config_derives;
+
+/// Partial map shared by a robot, or merged map published by the [`MapMerging`] estimator.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[pyclass]
+pub struct MapShareMessage {
+    /// Landmark poses `[x, y, orientation]` indexed by landmark id.
+    #[pyo3(get, set)]
+    pub landmarks: BTreeMap<i32, [f32; 3]>,
+    /// Occupancy grid of the robot, if it builds one.
+    pub occupancy_grid: Option<OccupancyGrid>,
+}
+
+#[pymethods]
+impl MapShareMessage {
+    /// Creates a map message from landmark poses and an optional occupancy grid.
+    #[new]
+    #[pyo3(signature = (landmarks=BTreeMap::new(), occupancy_grid=None))]
+    pub fn new(
+        landmarks: BTreeMap<i32, [f32; 3]>,
+        occupancy_grid: Option<OccupancyGridWrapper>,
+    ) -> Self {
+        Self {
+            landmarks,
+            occupancy_grid: occupancy_grid.map(|grid| grid.to_rust()),
+        }
+    }
+
+    /// Occupancy grid of the map, if any.
+    #[getter]
+    pub fn get_occupancy_grid(&self) -> Option<OccupancyGridWrapper> {
+        self.occupancy_grid
+            .as_ref()
+            .map(OccupancyGridWrapper::from_rust)
+    }
+
+    /// Replace the occupancy grid of the map.
+    #[setter]
+    pub fn set_occupancy_grid(&mut self, occupancy_grid: Option<OccupancyGridWrapper>) {
+        self.occupancy_grid = occupancy_grid.map(|grid| grid.to_rust());
+    }
+}
+
+impl MapShareMessage {
+    /// Map message from the landmarks and occupancy grid of a [`WorldState`].
+    pub fn from_world_state(world_state: &WorldState) -> Self {
+        Self {
+            landmarks: world_state
+                .landmarks
+                .iter()
+                .map(|(id, state)| (*id, [state.pose.x, state.pose.y, state.pose.z]))
+                .collect(),
+            occupancy_grid: world_state.occupancy_grid.clone(),
+        }
+    }
+}
+
+/// Rule combining the occupancy values given by several robots for the same cell.
+#[config_derives]
+pub enum GridMergeRule {
+    /// Keep the highest occupancy value (conservative: an obstacle seen by one robot is kept).
+    Max,
+    /// Average the occupancy values.
+    Mean,
+}
+
+impl Default for GridMergeRule {
+    fn default() -> Self {
+        Self::Max
+    }
+}
+
+/// Geometry of the merged occupancy grid.
+#[config_derives]
+pub struct MergedGridConfig {
+    /// Pose of the center of the grid `[x, y, orientation]`.
+    pub center: [f32; 3],
+    /// Height of one cell (on the y axis of the grid).
+    pub cell_height: f32,
+    /// Width of one cell (on the x axis of the grid).
+    pub cell_width: f32,
+    /// Number of cells on the y axis.
+    pub nb_rows: usize,
+    /// Number of cells on the x axis.
+    pub nb_cols: usize,
+    /// Rule combining the values of the shared grids.
+    pub merge_rule: GridMergeRule,
+}
+
+impl Default for MergedGridConfig {
+    fn default() -> Self {
+        Self {
+            center: [0., 0., 0.],
+            cell_height: 0.5,
+            cell_width: 0.5,
+            nb_rows: 100,
+            nb_cols: 100,
+            merge_rule: GridMergeRule::default(),
+        }
+    }
+}
+
+impl Check for MergedGridConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errs = Vec::new();
+        if self.cell_height <= 0. || self.cell_width <= 0. {
+            errs.push(format!(
+                "Cell size should be strictly positive, got {}x{}",
+                self.cell_width, self.cell_height
+            ));
+        }
+        if self.nb_rows == 0 || self.nb_cols == 0 {
+            errs.push(format!(
+                "Grid should have at least one cell, got {} rows and {} columns",
+                self.nb_rows, self.nb_cols
+            ));
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MergedGridConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Center (x, y, θ): ");
+            for value in self.center.iter_mut() {
+                ui.add(egui::DragValue::new(value).max_decimals(10));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Cell size (width, height): ");
+            ui.add(egui::DragValue::new(&mut self.cell_width).range(0.001..=f32::MAX));
+            ui.add(egui::DragValue::new(&mut self.cell_height).range(0.001..=f32::MAX));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Cells (rows, columns): ");
+            ui.add(egui::DragValue::new(&mut self.nb_rows).range(1..=usize::MAX));
+            ui.add(egui::DragValue::new(&mut self.nb_cols).range(1..=usize::MAX));
+        });
+        let mut current_str = self.merge_rule.to_string();
+        ui.horizontal(|ui| {
+            ui.label("Merge rule: ");
+            string_combobox(
+                ui,
+                &GridMergeRule::to_vec(),
+                &mut current_str,
+                format!("map-merging-rule-choice-{}", unique_id),
+            );
+        });
+        if current_str != self.merge_rule.to_string() {
+            match current_str.as_str() {
+                "Max" => self.merge_rule = GridMergeRule::Max,
+                "Mean" => self.merge_rule = GridMergeRule::Mean,
+                _ => panic!("Where did you find this value?"),
+            };
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!(
+            "Center: ({}, {}, {})",
+            self.center[0], self.center[1], self.center[2]
+        ));
+        ui.label(format!(
+            "Cells: {} x {} of {} x {}",
+            self.nb_rows, self.nb_cols, self.cell_width, self.cell_height
+        ));
+        ui.label(format!("Merge rule: {}", self.merge_rule));
+    }
+}
+
+/// Configuration for the [`MapMerging`] strategy.
+///
+/// # Example
+/// ```yaml
+/// computation_units:
+/// - name: Mapping Unit
+///   state_estimators:
+///   - name: merged_map
+///     config:
+///       type: MapMerging
+///       prediction_activation:
+///         period: {type: Num, value: 1.0}
+///       grid:
+///         center: [0., 0., 0.]
+///         cell_height: 0.5
+///         cell_width: 0.5
+///         nb_rows: 100
+///         nb_cols: 100
+///         merge_rule:
+///           type: Max
+///       publish: true
+///       share_bandwidth: 50000. # Bytes per second
+/// ```
+#[config_derives]
+pub struct MapMergingConfig {
+    /// Period of the merge (and of the publication of the merged map).
+    #[check]
+    pub prediction_activation: Option<PeriodicityConfig>,
+    /// Geometry of the merged occupancy grid. Without grid, only the landmarks are merged.
+    #[check]
+    pub grid: Option<MergedGridConfig>,
+    /// Publish the merged map on [`MapMerging::MERGED_CHANNEL`] after each merge.
+    pub publish: bool,
+    /// Bandwidth of the [`MapMerging::SHARE_CHANNEL`] and [`MapMerging::MERGED_CHANNEL`]
+    /// channels, in bytes per second. The maps are delayed by the size of their JSON
+    /// serialization. `None` for no limit.
+    pub share_bandwidth: Option<f32>,
+}
+
+impl Default for MapMergingConfig {
+    fn default() -> Self {
+        Self {
+            prediction_activation: Some(PeriodicityConfig {
+                period: crate::config::NumberConfig::Num(1.),
+                offset: None,
+                table: None,
+            }),
+            grid: None,
+            publish: true,
+            share_bandwidth: None,
+        }
+    }
+}
+
+impl Check for MapMergingConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if let Some(bandwidth) = self.share_bandwidth
+            && bandwidth <= 0.
+        {
+            return Err(vec![format!(
+                "Share bandwidth should be strictly positive, got {bandwidth}"
+            )]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MapMergingConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Map Merging")
+            .id_salt(format!("map-merging-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Prediction activation:");
+                    if let Some(p) = &mut self.prediction_activation {
+                        p.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove").clicked() {
+                            self.prediction_activation = None;
+                        }
+                    } else {
+                        ui.label("None");
+                        if ui.button("Add").clicked() {
+                            self.prediction_activation = Self::default().prediction_activation;
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Occupancy grid:");
+                    if self.grid.is_some() {
+                        if ui.button("Remove").clicked() {
+                            self.grid = None;
+                        }
+                    } else {
+                        ui.label("None");
+                        if ui.button("Add").clicked() {
+                            self.grid = Some(MergedGridConfig::default());
+                        }
+                    }
+                });
+                if let Some(grid) = &mut self.grid {
+                    grid.show_mut(
+                        ui,
+                        ctx,
+                        buffer_stack,
+                        global_config,
+                        current_node_name,
+                        unique_id,
+                    );
+                }
+
+                ui.checkbox(&mut self.publish, "Publish the merged map");
+
+                ui.horizontal(|ui| {
+                    ui.label("Share bandwidth (B/s):");
+                    if let Some(bandwidth) = &mut self.share_bandwidth {
+                        ui.add(egui::DragValue::new(bandwidth).range(0.001..=f32::MAX));
+                        if ui.button("X").clicked() {
+                            self.share_bandwidth = None;
+                        }
+                    } else if ui.button("+").clicked() {
+                        self.share_bandwidth = Some(50000.);
+                    }
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Map Merging")
+            .id_salt(format!("map-merging-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Prediction activation:");
+                    if let Some(p) = &self.prediction_activation {
+                        p.show(ui, ctx, unique_id);
+                    } else {
+                        ui.label("None");
+                    }
+                });
+                if let Some(grid) = &self.grid {
+                    grid.show(ui, ctx, unique_id);
+                } else {
+                    ui.label("Occupancy grid: None");
+                }
+                ui.label(format!("Publish: {}", self.publish));
+                ui.label(format!(
+                    "Share bandwidth: {}",
+                    self.share_bandwidth
+                        .map_or("unlimited".to_string(), |b| format!("{b} B/s"))
+                ));
+            });
+    }
+}
+
+/// Record for [`MapMerging`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MapMergingRecord {
+    /// Merged map (landmarks and occupancy grid).
+    pub world_state: WorldStateRecord,
+    /// Last merge time.
+    pub last_time_prediction: f32,
+    /// Time of the last map received from each robot.
+    pub contributors: BTreeMap<String, f32>,
+    /// Total size of the maps received, in bytes.
+    #[serde(default)]
+    pub received_bytes: usize,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MapMergingRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("Merged map").show(ui, |ui| {
+                self.world_state.show(ui, ctx, unique_id);
+            });
+            ui.label(format!("Last merge time: {}", self.last_time_prediction));
+            ui.label("Contributors:");
+            for (robot, time) in &self.contributors {
+                ui.label(format!("- {robot} (last map at {time})"));
+            }
+            ui.label(format!("Received bytes: {}", self.received_bytes));
+        });
+    }
+}
+
+/// Merge of the maps shared by the robots.
+#[derive(Debug)]
+pub struct MapMerging {
+    /// Merged map.
+    world_state: WorldState,
+    /// Merge period.
+    prediction_activation: Option<Periodicity>,
+    /// Last merge time.
+    last_time_prediction: f32,
+    /// Geometry and merge rule of the merged grid.
+    grid_config: Option<MergedGridConfig>,
+    /// Publish the merged map after each merge.
+    publish: bool,
+    /// Last map received from each robot, with its reception time.
+    shared_maps: BTreeMap<String, (f32, MapShareMessage)>,
+    /// Total size of the maps received, in bytes.
+    received_bytes: usize,
+    /// Client receiving the [`MapShareMessage`]s.
+    message_client: SharedMutex<SimbaBrokerMultiClient>,
+    /// Network used to publish the merged map.
+    network: SharedRwLock<Network>,
+}
+
+impl MapMerging {
+    /// Channel (relative to the node) on which the robots share their maps.
+    pub const SHARE_CHANNEL: &'static str = "map_merging/share";
+    /// Channel (relative to the node) on which the merged map is published.
+    pub const MERGED_CHANNEL: &'static str = "map_merging/merged";
+
+    /// Creates a new [`MapMerging`] from the given `config`.
+    pub fn from_config(
+        config: &MapMergingConfig,
+        _global_config: &SimulatorConfig,
+        va_factory: &DeterministRandomVariableFactory,
+        network: &SharedRwLock<Network>,
+        initial_time: f32,
+    ) -> Self {
+        let activation = config
+            .prediction_activation
+            .as_ref()
+            .map(|p| Periodicity::from_config(p, va_factory, initial_time));
+        let last_time = activation
+            .as_ref()
+            .map(|p| p.next_time())
+            .unwrap_or(initial_time);
+        let net = network.write().unwrap();
+        let share_key = net.make_channel(PathKey::from_str(Self::SHARE_CHANNEL).unwrap());
+        let merged_key = net.make_channel(PathKey::from_str(Self::MERGED_CHANNEL).unwrap());
+        if let Some(bandwidth) = config.share_bandwidth {
+            for key in [&share_key, &merged_key] {
+                net.set_channel_limits(
+                    key.clone(),
+                    ChannelLimits {
+                        bandwidth: Some(bandwidth),
+                        max_rate: None,
+                    },
+                );
+            }
+        }
+        let message_client = net.subscribe_to(&[share_key], None);
+        drop(net);
+        let mut world_state = WorldState::new();
+        world_state.occupancy_grid = config.grid.as_ref().map(|grid| {
+            OccupancyGrid::new(
+                Vector3::from(grid.center),
+                grid.cell_height,
+                grid.cell_width,
+                grid.nb_rows,
+                grid.nb_cols,
+            )
+        });
+        Self {
+            world_state,
+            prediction_activation: activation,
+            last_time_prediction: last_time,
+            grid_config: config.grid.clone(),
+            publish: config.publish,
+            shared_maps: BTreeMap::new(),
+            received_bytes: 0,
+            message_client: Arc::new(Mutex::new(message_client)),
+            network: network.clone(),
+        }
+    }
+
+    /// Merge the last maps of the robots into the estimated world state.
+    fn merge(&mut self) {
+        self.world_state.landmarks = merge_landmarks(self.shared_maps.values().map(|(_, m)| m));
+        if let (Some(grid_config), Some(grid)) =
+            (&self.grid_config, &mut self.world_state.occupancy_grid)
+        {
+            let grids = self
+                .shared_maps
+                .values()
+                .filter_map(|(_, map)| map.occupancy_grid.as_ref())
+                .collect::<Vec<_>>();
+            merge_grids(grid, &grids, &grid_config.merge_rule);
+        }
+    }
+}
+
+/// Average the poses of the landmarks seen in several maps.
+fn merge_landmarks<'a>(maps: impl Iterator<Item = &'a MapShareMessage>) -> BTreeMap<i32, State> {
+    // Sum of x, y, cos(θ), sin(θ) and number of maps, by landmark
+    let mut sums: BTreeMap<i32, [f32; 5]> = BTreeMap::new();
+    for map in maps {
+        for (id, pose) in &map.landmarks {
+            let sum = sums.entry(*id).or_default();
+            sum[0] += pose[0];
+            sum[1] += pose[1];
+            sum[2] += pose[2].cos();
+            sum[3] += pose[2].sin();
+            sum[4] += 1.;
+        }
+    }
+    sums.into_iter()
+        .map(|(id, sum)| {
+            (
+                id,
+                State::from_vector(&[
+                    sum[0] / sum[4],
+                    sum[1] / sum[4],
+                    mod2pi(sum[3].atan2(sum[2])),
+                ]),
+            )
+        })
+        .collect()
+}
+
+/// Fill each cell of `merged` from the cells of `grids` containing its center.
+///
+/// Cells covered by none of the grids are set to 0.
+fn merge_grids(merged: &mut OccupancyGrid, grids: &[&OccupancyGrid], rule: &GridMergeRule) {
+    for row in 0..merged.nb_rows() {
+        for col in 0..merged.nb_cols() {
+            let position = merged.idx_to_pos(row, col).unwrap();
+            let values = grids.iter().filter_map(|grid| grid.get_pos(position));
+            let value = match rule {
+                GridMergeRule::Max => values.copied().reduce(f32::max),
+                GridMergeRule::Mean => {
+                    let (sum, count) = values.fold((0., 0), |(sum, count), v| (sum + v, count + 1));
+                    (count > 0).then(|| sum / count as f32)
+                }
+            };
+            *merged.get_idx_mut(row, col).unwrap() = value.unwrap_or(0.);
+        }
+    }
+}
+
+impl StateEstimator for MapMerging {
+    fn prediction_step(&mut self, node: &mut Node, _command: Option<Command>, time: f32) {
+        if (time - self.next_time_step()).abs() > TIME_ROUND / 2. {
+            error!(
+                "Error trying to update estimate too soon! (it is {} but expecting {})",
+                time,
+                self.next_time_step()
+            );
+            return;
+        }
+        self.merge();
+        if self.publish {
+            let message = MapShareMessage::from_world_state(&self.world_state);
+            self.network.read().unwrap().send_to(
+                PathKey::from_str(Self::MERGED_CHANNEL).unwrap(),
+                Envelope {
+                    from: node.name(),
+                    message: serde_json::to_value(message).unwrap(),
+                    timestamp: time,
+                    message_flags: Vec::new(),
+                },
+                time,
+            );
+        }
+        if let Some(p) = self.prediction_activation.as_mut() {
+            p.update(time);
+        }
+        self.last_time_prediction = time;
+    }
+
+    fn correction_step(&mut self, _node: &mut Node, _observations: &[Observation], _time: f32) {}
+
+    fn world_state(&self) -> WorldState {
+        self.world_state.clone()
+    }
+
+    fn next_time_step(&self) -> f32 {
+        if let Some(period) = &self.prediction_activation {
+            period.next_time()
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn pre_loop_hook(&mut self, node: &mut Node, time: f32) {
+        while let Some((_, envelope)) = self.message_client.lock().unwrap().try_receive(time) {
+            self.received_bytes += envelope.message_size();
+            match serde_json::from_value::<MapShareMessage>(envelope.message) {
+                Ok(map) => {
+                    self.shared_maps.insert(envelope.from, (time, map));
+                }
+                Err(e) => {
+                    warn!(
+                        "[{}] Invalid map shared by {}: {e}",
+                        node.name(),
+                        envelope.from
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Recordable<StateEstimatorRecord> for MapMerging {
+    fn record(&self) -> StateEstimatorRecord {
+        StateEstimatorRecord::MapMerging(MapMergingRecord {
+            world_state: self.world_state.record(),
+            last_time_prediction: self.last_time_prediction,
+            contributors: self
+                .shared_maps
+                .iter()
+                .map(|(robot, (time, _))| (robot.clone(), *time))
+                .collect(),
+            received_bytes: self.received_bytes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn landmarks_are_averaged() {
+        let map1 = MapShareMessage {
+            landmarks: BTreeMap::from([(1, [1., 2., 3.]), (2, [0., 0., 0.])]),
+            occupancy_grid: None,
+        };
+        let map2 = MapShareMessage {
+            landmarks: BTreeMap::from([(1, [3., 4., -3.])]),
+            occupancy_grid: None,
+        };
+        let landmarks = merge_landmarks([&map1, &map2].into_iter());
+        assert_eq!(landmarks.len(), 2);
+        let merged = landmarks.get(&1).unwrap();
+        assert!((merged.pose.x - 2.).abs() < 1e-6);
+        assert!((merged.pose.y - 3.).abs() < 1e-6);
+        // Mean of 3 and -3 rad is π, not 0
+        assert!((merged.pose.z.abs() - std::f32::consts::PI).abs() < 1e-5);
+    }
+
+    #[test]
+    fn grids_are_merged() {
+        let mut left = OccupancyGrid::new(Vector3::new(-1., 0., 0.), 1., 1., 2, 2);
+        *left.get_pos_mut([-0.5, 0.5].into()).unwrap() = 0.8;
+        let mut right = OccupancyGrid::new(Vector3::new(0., 0., 0.), 1., 1., 2, 2);
+        *right.get_pos_mut([-0.5, 0.5].into()).unwrap() = 0.2;
+        *right.get_pos_mut([0.5, -0.5].into()).unwrap() = 1.;
+
+        let mut merged = OccupancyGrid::new(Vector3::new(0., 0., 0.), 1., 1., 2, 2);
+        merge_grids(&mut merged, &[&left, &right], &GridMergeRule::Max);
+        assert_eq!(merged.get_pos([-0.5, 0.5].into()), Some(&0.8));
+        assert_eq!(merged.get_pos([0.5, -0.5].into()), Some(&1.));
+        assert_eq!(merged.get_pos([0.5, 0.5].into()), Some(&0.));
+
+        merge_grids(&mut merged, &[&left, &right], &GridMergeRule::Mean);
+        assert!((merged.get_pos([-0.5, 0.5].into()).unwrap() - 0.5).abs() < 1e-6);
+    }
+}
@@ -28,13 +28,22 @@ To create a new state estimation strategy, here are the required steps.
    Record struct as generic type.
 */
 
+pub mod bench_report;
+pub mod cooperative_localization;
+pub mod dataset;
+pub mod dead_reckoning_estimator;
+pub mod ekf_estimator;
 pub mod external_estimator;
+pub mod map_merging;
+pub mod particle_filter_estimator;
 pub mod perfect_estimator;
 pub mod pybinds;
 pub mod python_estimator;
+pub mod warm_start;
+pub mod watchdog;
 
 extern crate nalgebra as na;
-use na::SVector;
+use na::{SMatrix, SVector};
 
 extern crate confy;
 use serde_derive::{Deserialize, Serialize};
@@ -200,6 +209,9 @@ pub struct StateRecord {
     pub pose: [f32; 3],
     /// Linear velocity and angular velocity.
     pub velocity: [f32; 3],
+    /// Covariance of the pose, for the estimates of the estimators providing one.
+    #[serde(default)]
+    pub pose_covariance: Option<[[f32; 3]; 3]>,
 }
 
 impl Default for StateRecord {
@@ -207,6 +219,7 @@ impl Default for StateRecord {
         Self {
             pose: [0., 0., 0.],
             velocity: [0., 0., 0.],
+            pose_covariance: None,
         }
     }
 }
@@ -223,6 +236,12 @@ impl UIComponent for StateRecord {
                 "velocity: ({}, {}, {})",
                 self.velocity[0], self.velocity[1], self.velocity[2]
             ));
+            if let Some(covariance) = &self.pose_covariance {
+                ui.label("pose covariance:");
+                for row in covariance {
+                    ui.label(format!("  ({:.4}, {:.4}, {:.4})", row[0], row[1], row[2]));
+                }
+            }
         });
     }
 }
@@ -330,6 +349,14 @@ impl State {
         state
     }
 
+    /// Creates a [`State`] from its record. The covariance of the record is not kept.
+    pub fn from_record(record: &StateRecord) -> Self {
+        Self {
+            pose: SVector::from(record.pose),
+            velocity: SVector::from(record.velocity),
+        }
+    }
+
     /// Normalize the heading angle to the ]-PI, PI] range.
     pub fn theta_modulo(mut self) -> Self {
         self.pose.z = mod2pi(self.pose.z);
@@ -366,6 +393,7 @@ impl Recordable<StateRecord> for State {
                 }
                 ve
             },
+            pose_covariance: None,
         }
     }
 }
@@ -397,6 +425,18 @@ pub struct WorldStateRecord {
     pub occupancy_grid: Option<OccupancyGrid>,
 }
 
+impl WorldStateRecord {
+    /// Set the covariance of the ego pose (see [`StateEstimator::pose_covariance`]), if there is
+    /// an ego estimate.
+    pub fn with_ego_covariance(mut self, pose_covariance: Option<SMatrix<f32, 3, 3>>) -> Self {
+        if let Some(ego) = &mut self.ego {
+            ego.pose_covariance = pose_covariance
+                .map(|cov| std::array::from_fn(|i| std::array::from_fn(|j| cov[(i, j)])));
+        }
+        self
+    }
+}
+
 #[cfg(feature = "gui")]
 impl UIComponent for WorldStateRecord {
     fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
@@ -471,16 +511,20 @@ impl Recordable<WorldStateRecord> for WorldState {
 #[cfg(feature = "gui")]
 use crate::gui::{
     UIComponent,
-    utils::{string_combobox, text_singleline_with_apply},
+    utils::{string_checkbox, string_combobox, text_singleline_with_apply},
 };
 #[cfg(feature = "gui")]
 use crate::utils::enum_tools::ToVec;
 use crate::utils::geometry::mod2pi;
 use crate::utils::occupancy_grid::OccupancyGrid;
-use crate::{errors::SimbaResult, node::Node};
+use crate::{
+    errors::SimbaResult,
+    node::{Node, capabilities::Capabilities},
+};
 use crate::{networking::network::Network, simulator::SimulatorConfig};
 use crate::{
-    physics::robot_models::Command, utils::determinist_random_variable::RandomVariableTypeConfig,
+    physics::{PhysicsConfig, robot_models::Command},
+    utils::determinist_random_variable::RandomVariableTypeConfig,
 };
 use crate::{
     plugin_api::PluginAPI, utils::determinist_random_variable::DeterministRandomVariableFactory,
@@ -509,6 +553,21 @@ pub enum StateEstimatorConfig {
     /// Python-backed state estimator.
     #[check]
     Python(python_estimator::PythonEstimatorConfig),
+    /// Built-in merge of the maps shared by the robots, for computation units.
+    #[check]
+    MapMerging(map_merging::MapMergingConfig),
+    /// Built-in Extended Kalman Filter on the ego pose.
+    #[check]
+    Ekf(ekf_estimator::EkfEstimatorConfig),
+    /// Built-in particle filter on the ego pose.
+    #[check]
+    ParticleFilter(particle_filter_estimator::ParticleFilterEstimatorConfig),
+    /// Built-in integration of the odometry or of the commands, without correction.
+    #[check]
+    DeadReckoning(dead_reckoning_estimator::DeadReckoningEstimatorConfig),
+    /// Built-in EKF fusing the observations of the other robots and their shared estimates.
+    #[check]
+    CooperativeLocalization(cooperative_localization::CooperativeLocalizationConfig),
 }
 
 #[cfg(feature = "gui")]
@@ -549,6 +608,28 @@ impl UIComponent for StateEstimatorConfig {
                         python_estimator::PythonEstimatorConfig::default(),
                     )
                 }
+                "MapMerging" => {
+                    *self =
+                        StateEstimatorConfig::MapMerging(map_merging::MapMergingConfig::default())
+                }
+                "Ekf" => {
+                    *self = StateEstimatorConfig::Ekf(ekf_estimator::EkfEstimatorConfig::default())
+                }
+                "ParticleFilter" => {
+                    *self = StateEstimatorConfig::ParticleFilter(
+                        particle_filter_estimator::ParticleFilterEstimatorConfig::default(),
+                    )
+                }
+                "DeadReckoning" => {
+                    *self = StateEstimatorConfig::DeadReckoning(
+                        dead_reckoning_estimator::DeadReckoningEstimatorConfig::default(),
+                    )
+                }
+                "CooperativeLocalization" => {
+                    *self = StateEstimatorConfig::CooperativeLocalization(
+                        cooperative_localization::CooperativeLocalizationConfig::default(),
+                    )
+                }
                 _ => panic!("Where did you find this value?"),
             };
         }
@@ -577,6 +658,46 @@ impl UIComponent for StateEstimatorConfig {
                 current_node_name,
                 unique_id,
             ),
+            StateEstimatorConfig::MapMerging(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            StateEstimatorConfig::Ekf(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            StateEstimatorConfig::ParticleFilter(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            StateEstimatorConfig::DeadReckoning(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            StateEstimatorConfig::CooperativeLocalization(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
         }
     }
 
@@ -588,6 +709,11 @@ impl UIComponent for StateEstimatorConfig {
             StateEstimatorConfig::Perfect(c) => c.show(ui, ctx, unique_id),
             StateEstimatorConfig::External(c) => c.show(ui, ctx, unique_id),
             StateEstimatorConfig::Python(c) => c.show(ui, ctx, unique_id),
+            StateEstimatorConfig::MapMerging(c) => c.show(ui, ctx, unique_id),
+            StateEstimatorConfig::Ekf(c) => c.show(ui, ctx, unique_id),
+            StateEstimatorConfig::ParticleFilter(c) => c.show(ui, ctx, unique_id),
+            StateEstimatorConfig::DeadReckoning(c) => c.show(ui, ctx, unique_id),
+            StateEstimatorConfig::CooperativeLocalization(c) => c.show(ui, ctx, unique_id),
         }
     }
 }
@@ -601,6 +727,43 @@ pub enum StateEstimatorRecord {
     External(external_estimator::ExternalEstimatorRecord),
     /// Record for the [`PythonEstimator`](crate::state_estimators::python_estimator::PythonEstimator).
     Python(python_estimator::PythonEstimatorRecord),
+    /// Record for the [`MapMerging`](crate::state_estimators::map_merging::MapMerging).
+    MapMerging(map_merging::MapMergingRecord),
+    /// Record for the [`EkfEstimator`](crate::state_estimators::ekf_estimator::EkfEstimator).
+    Ekf(ekf_estimator::EkfEstimatorRecord),
+    /// Record for the [`ParticleFilterEstimator`](crate::state_estimators::particle_filter_estimator::ParticleFilterEstimator).
+    ParticleFilter(particle_filter_estimator::ParticleFilterEstimatorRecord),
+    /// Record for the [`DeadReckoningEstimator`](crate::state_estimators::dead_reckoning_estimator::DeadReckoningEstimator).
+    DeadReckoning(dead_reckoning_estimator::DeadReckoningEstimatorRecord),
+    /// Record for the [`CooperativeLocalization`](crate::state_estimators::cooperative_localization::CooperativeLocalization).
+    CooperativeLocalization(cooperative_localization::CooperativeLocalizationRecord),
+}
+
+impl StateEstimatorRecord {
+    /// Estimated ego state, `None` without ego estimate or for the external and Python
+    /// estimators, whose record is not known.
+    pub fn ego(&self) -> Option<&StateRecord> {
+        match self {
+            Self::Perfect(r) => r.world_state.ego.as_ref(),
+            Self::MapMerging(r) => r.world_state.ego.as_ref(),
+            Self::Ekf(r) => r.world_state.ego.as_ref(),
+            Self::ParticleFilter(r) => r.world_state.ego.as_ref(),
+            Self::DeadReckoning(r) => r.world_state.ego.as_ref(),
+            Self::CooperativeLocalization(r) => r.ekf.world_state.ego.as_ref(),
+            Self::External(_) | Self::Python(_) => None,
+        }
+    }
+
+    /// Covariance of the estimated ego pose, when recorded by the estimator (`Ekf`,
+    /// `ParticleFilter`, `DeadReckoning` and `CooperativeLocalization`).
+    pub fn pose_covariance(&self) -> Option<[[f32; 3]; 3]> {
+        match self {
+            Self::Ekf(r) => Some(r.covariance),
+            Self::DeadReckoning(r) => Some(r.covariance),
+            Self::CooperativeLocalization(r) => Some(r.ekf.covariance),
+            _ => self.ego().and_then(|ego| ego.pose_covariance),
+        }
+    }
 }
 
 #[cfg(feature = "gui")]
@@ -622,16 +785,44 @@ impl UIComponent for StateEstimatorRecord {
                     r.show(ui, ctx, unique_id);
                 });
             }
+            Self::MapMerging(r) => {
+                egui::CollapsingHeader::new("MapMerging").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::Ekf(r) => {
+                egui::CollapsingHeader::new("Ekf").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::ParticleFilter(r) => {
+                egui::CollapsingHeader::new("ParticleFilter").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::DeadReckoning(r) => {
+                egui::CollapsingHeader::new("DeadReckoning").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
+            Self::CooperativeLocalization(r) => {
+                egui::CollapsingHeader::new("CooperativeLocalization").show(ui, |ui| {
+                    r.show(ui, ctx, unique_id);
+                });
+            }
         });
     }
 }
 
 /// Build the appropriate [`StateEstimator`] from a configuration.
+///
+/// The `physics_config` of the node, if any, is used to infer the robot model of the estimators.
 pub fn make_state_estimator_from_config(
     config: &StateEstimatorConfig,
     plugin_api: &Option<Arc<dyn PluginAPI>>,
     global_config: &SimulatorConfig,
     va_factory: &Arc<DeterministRandomVariableFactory>,
+    physics_config: Option<&PhysicsConfig>,
     network: &SharedRwLock<Network>,
     initial_time: f32,
 ) -> SimbaResult<Box<dyn StateEstimator>> {
@@ -641,6 +832,7 @@ pub fn make_state_estimator_from_config(
                 c,
                 global_config,
                 va_factory,
+                network,
                 initial_time,
             )) as Box<dyn StateEstimator>
         }
@@ -657,6 +849,45 @@ pub fn make_state_estimator_from_config(
         StateEstimatorConfig::Python(c) => Box::new(
             python_estimator::PythonEstimator::from_config(c, global_config, initial_time).unwrap(),
         ) as Box<dyn StateEstimator>,
+        StateEstimatorConfig::MapMerging(c) => Box::new(map_merging::MapMerging::from_config(
+            c,
+            global_config,
+            va_factory,
+            network,
+            initial_time,
+        )) as Box<dyn StateEstimator>,
+        StateEstimatorConfig::Ekf(c) => Box::new(ekf_estimator::EkfEstimator::from_config(
+            c,
+            global_config,
+            va_factory,
+            initial_time,
+        )) as Box<dyn StateEstimator>,
+        StateEstimatorConfig::ParticleFilter(c) => Box::new(
+            particle_filter_estimator::ParticleFilterEstimator::from_config(
+                c,
+                global_config,
+                va_factory,
+                initial_time,
+            ),
+        ) as Box<dyn StateEstimator>,
+        StateEstimatorConfig::DeadReckoning(c) => Box::new(
+            dead_reckoning_estimator::DeadReckoningEstimator::from_config(
+                c,
+                global_config,
+                va_factory,
+                physics_config,
+                initial_time,
+            ),
+        ) as Box<dyn StateEstimator>,
+        StateEstimatorConfig::CooperativeLocalization(c) => Box::new(
+            cooperative_localization::CooperativeLocalization::from_config(
+                c,
+                global_config,
+                va_factory,
+                network,
+                initial_time,
+            ),
+        ) as Box<dyn StateEstimator>,
     })
 }
 
@@ -707,9 +938,61 @@ pub trait StateEstimator:
 
     /// Hook called before each simulation loop iteration, just after the Physics update.
     fn pre_loop_hook(&mut self, node: &mut Node, time: f32);
+
+    /// Observations used by the state estimator and services of the node it needs, checked
+    /// when the node is built (see [`capabilities`](crate::node::capabilities)). Nothing is
+    /// declared by default.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Covariance of the estimated ego pose (x, y, orientation), if the strategy computes one.
+    ///
+    /// It is used by the [`bench_report`] to compute the NEES. Returns `None` by default.
+    fn pose_covariance(&self) -> Option<SMatrix<f32, 3, 3>> {
+        None
+    }
+
+    /// Largest normalized innovation squared of the measurements of the last correction step,
+    /// if the strategy computes it.
+    ///
+    /// It is used by the [`watchdog`] to detect divergences. Returns `None` by default.
+    fn innovation_statistic(&self) -> Option<f32> {
+        None
+    }
+
+    /// Reset the estimated ego state to `state` at `time`, with the initial uncertainty.
+    ///
+    /// It is used by the [`watchdog`] to reinitialize a diverged estimator. Not supported by
+    /// default.
+    #[allow(unused_variables)]
+    fn reset_state(&mut self, state: State, time: f32) -> Result<(), String> {
+        Err("This state estimator can not be reinitialized".to_string())
+    }
+
+    /// Initialize the estimator from a `record` of a previous run, at `time` (see
+    /// [`warm_start`]).
+    ///
+    /// By default, the ego state is reset ([`StateEstimator::reset_state`]) to the ego estimate
+    /// of the record.
+    fn warm_start(&mut self, record: &StateEstimatorRecord, time: f32) -> Result<(), String> {
+        let ego = record
+            .ego()
+            .ok_or_else(|| "The record has no ego estimate".to_string())?;
+        self.reset_state(State::from_record(ego), time)
+    }
 }
 
 /// Allow to run a list of [`StateEstimator`] outside of the simulation control loop.
+///
+/// # Example
+/// ```yaml
+/// state_estimator_bench:
+/// - name: without_gnss
+///   sensors: [odometry, landmarks]
+///   config:
+///     type: Perfect
+/// ```
 #[config_derives]
 pub struct BenchStateEstimatorConfig {
     /// Human-readable estimator name to identify the estimator in the simulation results.
@@ -717,6 +1000,16 @@ pub struct BenchStateEstimatorConfig {
     /// Underlying estimator configuration.
     #[check]
     pub config: StateEstimatorConfig,
+    /// Names of the sensors whose observations are given to this estimator, including
+    /// the sensors of other nodes sending their observations to this node.
+    /// If `None`, the estimator receives all the observations, as the main estimator.
+    pub sensors: Option<Vec<String>>,
+    /// Divergence watchdog of the estimator, `None` to disable it.
+    #[check]
+    pub watchdog: Option<watchdog::EstimatorWatchdogConfig>,
+    /// Record used to initialize the estimator, `None` to start from its configuration.
+    #[check]
+    pub warm_start: Option<warm_start::EstimatorWarmStartConfig>,
 }
 
 impl Default for BenchStateEstimatorConfig {
@@ -726,6 +1019,9 @@ impl Default for BenchStateEstimatorConfig {
             config: StateEstimatorConfig::Perfect(
                 perfect_estimator::PerfectEstimatorConfig::default(),
             ),
+            sensors: None,
+            watchdog: None,
+            warm_start: None,
         }
     }
 }
@@ -752,6 +1048,28 @@ impl UIComponent for BenchStateEstimatorConfig {
                 );
             });
 
+            let mut all_sensors = self.sensors.is_none();
+            ui.horizontal(|ui| {
+                ui.label("All observations: ");
+                ui.checkbox(&mut all_sensors, "");
+            });
+            if all_sensors {
+                self.sensors = None;
+            } else {
+                let sensor_list = Vec::from_iter(global_config.robots.iter().flat_map(|robot| {
+                    robot
+                        .sensor_manager
+                        .sensors
+                        .iter()
+                        .map(|sensor| sensor.name.clone())
+                }));
+                let sensors = self.sensors.get_or_insert_with(Vec::new);
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Sensors:");
+                    string_checkbox(ui, &sensor_list, sensors);
+                });
+            }
+
             self.config.show_mut(
                 ui,
                 ctx,
@@ -760,6 +1078,36 @@ impl UIComponent for BenchStateEstimatorConfig {
                 current_node_name,
                 unique_id,
             );
+
+            let mut enabled = self.watchdog.is_some();
+            ui.checkbox(&mut enabled, "Watchdog");
+            if enabled {
+                self.watchdog.get_or_insert_default().show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+            } else {
+                self.watchdog = None;
+            }
+
+            let mut enabled = self.warm_start.is_some();
+            ui.checkbox(&mut enabled, "Warm start");
+            if enabled {
+                self.warm_start.get_or_insert_default().show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+            } else {
+                self.warm_start = None;
+            }
         });
     }
 
@@ -769,7 +1117,24 @@ impl UIComponent for BenchStateEstimatorConfig {
                 ui.label(format!("Name: {}", self.name));
             });
 
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Sensors:");
+                if let Some(sensors) = &self.sensors {
+                    for sensor in sensors {
+                        ui.label(format!("{}, ", sensor));
+                    }
+                } else {
+                    ui.label("All");
+                }
+            });
+
             self.config.show(ui, ctx, unique_id);
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.show(ui, ctx, unique_id);
+            }
+            if let Some(warm_start) = &self.warm_start {
+                warm_start.show(ui, ctx, unique_id);
+            }
         });
     }
 }
@@ -781,6 +1146,10 @@ pub struct BenchStateEstimatorRecord {
     pub name: String,
     /// Recorded estimator payload.
     pub record: StateEstimatorRecord,
+    /// Estimated ego state, used to compare the estimators whatever their record type.
+    pub ego: Option<StateRecord>,
+    /// Covariance of the estimated ego pose, when provided by the estimator.
+    pub pose_covariance: Option<[[f32; 3]; 3]>,
 }
 
 /// Out-of-control-loop state-estimator instance.
@@ -790,4 +1159,34 @@ pub struct BenchStateEstimator {
     pub name: String,
     /// Shared estimator instance.
     pub state_estimator: SharedRwLock<Box<dyn StateEstimator>>,
+    /// Names of the sensors whose observations are given to the estimator, all if `None`.
+    pub sensors: Option<Vec<String>>,
+}
+
+impl BenchStateEstimator {
+    /// Keep only the observations coming from the sensors given to this estimator.
+    pub fn filter_observations(&self, observations: &[Observation]) -> Vec<Observation> {
+        match &self.sensors {
+            Some(sensors) => observations
+                .iter()
+                .filter(|obs| sensors.contains(&obs.sensor_name))
+                .cloned()
+                .collect(),
+            None => observations.to_vec(),
+        }
+    }
+}
+
+impl Recordable<BenchStateEstimatorRecord> for BenchStateEstimator {
+    fn record(&self) -> BenchStateEstimatorRecord {
+        let state_estimator = self.state_estimator.read().unwrap();
+        BenchStateEstimatorRecord {
+            name: self.name.clone(),
+            record: state_estimator.record(),
+            ego: state_estimator.world_state().ego.map(|ego| ego.record()),
+            pose_covariance: state_estimator
+                .pose_covariance()
+                .map(|cov| std::array::from_fn(|i| std::array::from_fn(|j| cov[(i, j)]))),
+        }
+    }
 }
@@ -0,0 +1,819 @@
+/*!
+Module providing the [`ParticleFilterEstimator`] strategy, a particle filter estimating the ego
+pose `[x, y, orientation]` of the node.
+
+Each particle is a pose hypothesis. The particles are moved with the velocities given by the
+[`SpeedObservation`](crate::sensors::speed_sensor::SpeedObservation)s (odometry) and a random
+motion noise. They are weighted with the likelihood of the
+[`GNSSObservation`](crate::sensors::gnss_sensor::GNSSObservation)s and of the
+[`OrientedLandmarkObservation`](crate::sensors::oriented_landmark_sensor::OrientedLandmarkObservation)s
+(pose of a landmark of the environment map, relative to the node). The particles are resampled
+when the effective sample size gets too low. The landmarks are the prior knowledge of the map
+([`Environment::prior_landmarks`](crate::environment::Environment::prior_landmarks)): without
+known map, the landmark observations are ignored.
+
+All the random draws come from the [`DeterministRandomVariableFactory`], so that the filter gives
+the same results on each replication with the same seed.
+*/
+
+use nalgebra::{SMatrix, Vector3};
+
+use super::{State, StateEstimator, StateEstimatorRecord, WorldState, WorldStateRecord};
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+use crate::{
+    constants::TIME_ROUND,
+    node::{
+        Node,
+        capabilities::{Capabilities, ObservationKind},
+    },
+    physics::robot_models::Command,
+    recordable::Recordable,
+    sensors::{Observation, SensorObservation},
+    simulator::SimulatorConfig,
+    utils::{
+        determinist_random_variable::{
+            DeterministRandomVariable, DeterministRandomVariableFactory, RandomVariableTypeConfig,
+        },
+        distributions::{normal::NormalRandomVariableConfig, uniform::UniformRandomVariableConfig},
+        geometry::mod2pi,
+        periodicity::{Periodicity, PeriodicityConfig},
+    },
+};
+use config_checker::*;
+use log::{error, warn};
+use serde_derive::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+/// Resampling strategy of the [`ParticleFilterEstimator`].
+///
+/// Default value: [`ResamplingStrategy::Systematic`].
+#[config_derives]
+#[derive(Copy)]
+pub enum ResamplingStrategy {
+    /// Independent draw of each particle, proportionally to the weights.
+    Multinomial,
+    /// One random offset, with particles drawn at regular intervals of the cumulated weights.
+    Systematic,
+    /// One random draw in each regular interval of the cumulated weights.
+    Stratified,
+}
+
+impl Default for ResamplingStrategy {
+    fn default() -> Self {
+        Self::Systematic
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ResamplingStrategy {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        let mut current_str = self.to_string();
+        ui.horizontal(|ui| {
+            use crate::{gui::utils::string_combobox, utils::enum_tools::ToVec};
+
+            ui.label("Resampling:");
+            string_combobox(
+                ui,
+                &ResamplingStrategy::to_vec(),
+                &mut current_str,
+                format!("resampling-strategy-choice-{}", unique_id),
+            );
+        });
+        if current_str != self.to_string() {
+            *self = match current_str.as_str() {
+                "Multinomial" => ResamplingStrategy::Multinomial,
+                "Systematic" => ResamplingStrategy::Systematic,
+                "Stratified" => ResamplingStrategy::Stratified,
+                _ => panic!("Where did you find this value?"),
+            };
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.label(format!("Resampling: {}", self));
+    }
+}
+
+/// Configuration for the [`ParticleFilterEstimator`] strategy.
+///
+/// The noises are standard deviations on the `[x, y, orientation]` variables.
+///
+/// # Example
+/// ```yaml
+/// state_estimator:
+///   type: ParticleFilter
+///   prediction_activation:
+///     period: {type: Num, value: 0.1}
+///   particle_count: 200
+///   initial_pose: [0., 0., 0.]
+///   initial_spread: [0.5, 0.5, 0.1]
+///   motion_noise: [0.1, 0.1, 0.05]
+///   gnss_noise: [0.5, 0.5, 0.1]
+///   landmark_noise: [0.2, 0.2, 0.1]
+///   resampling:
+///     type: Systematic
+///   resampling_threshold: 0.5
+///   recorded_particles: 50
+/// ```
+#[config_derives]
+pub struct ParticleFilterEstimatorConfig {
+    /// Prediction period.
+    #[check]
+    pub prediction_activation: Option<PeriodicityConfig>,
+    /// Number of particles.
+    pub particle_count: usize,
+    /// Initial estimate of the pose.
+    pub initial_pose: [f32; 3],
+    /// Standard deviation of the initial particles around `initial_pose`.
+    pub initial_spread: [f32; 3],
+    /// Standard deviation of the motion noise, for one second of prediction.
+    pub motion_noise: [f32; 3],
+    /// Standard deviation of the GNSS pose measurement.
+    pub gnss_noise: [f32; 3],
+    /// Standard deviation of the landmark relative pose measurement.
+    pub landmark_noise: [f32; 3],
+    /// Resampling strategy.
+    pub resampling: ResamplingStrategy,
+    /// The particles are resampled when the effective sample size is lower than this ratio of
+    /// `particle_count`.
+    pub resampling_threshold: f32,
+    /// Maximal number of particles in the record, evenly taken among the particles. `None`
+    /// records all the particles.
+    pub recorded_particles: Option<usize>,
+}
+
+impl Default for ParticleFilterEstimatorConfig {
+    fn default() -> Self {
+        Self {
+            prediction_activation: Some(PeriodicityConfig {
+                period: crate::config::NumberConfig::Num(0.1),
+                offset: None,
+                table: None,
+            }),
+            particle_count: 200,
+            initial_pose: [0., 0., 0.],
+            initial_spread: [0.5, 0.5, 0.1],
+            motion_noise: [0.1, 0.1, 0.05],
+            gnss_noise: [0.5, 0.5, 0.1],
+            landmark_noise: [0.2, 0.2, 0.1],
+            resampling: ResamplingStrategy::default(),
+            resampling_threshold: 0.5,
+            recorded_particles: None,
+        }
+    }
+}
+
+impl Check for ParticleFilterEstimatorConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.particle_count == 0 {
+            errors.push("The particle count should be strictly positive".to_string());
+        }
+        for (name, noise) in [
+            ("initial_spread", &self.initial_spread),
+            ("motion_noise", &self.motion_noise),
+        ] {
+            if noise.iter().any(|sigma| *sigma < 0.) {
+                errors.push(format!("{name} should be positive, got {noise:?}"));
+            }
+        }
+        for (name, noise) in [
+            ("gnss_noise", &self.gnss_noise),
+            ("landmark_noise", &self.landmark_noise),
+        ] {
+            if noise.iter().any(|sigma| *sigma <= 0.) {
+                errors.push(format!("{name} should be strictly positive, got {noise:?}"));
+            }
+        }
+        if !(0. ..=1.).contains(&self.resampling_threshold) {
+            errors.push(format!(
+                "The resampling threshold should be in [0, 1], got {}",
+                self.resampling_threshold
+            ));
+        }
+        if self.recorded_particles == Some(0) {
+            errors.push("The number of recorded particles should be strictly positive".to_string());
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+fn vector_show_mut(ui: &mut egui::Ui, name: &str, vector: &mut [f32; 3]) {
+    ui.horizontal(|ui| {
+        ui.label(format!("{name}:"));
+        for value in vector.iter_mut() {
+            ui.add(egui::DragValue::new(value).speed(0.001).max_decimals(6));
+        }
+    });
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ParticleFilterEstimatorConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Particle Filter Estimator")
+            .id_salt(format!("particle-filter-estimator-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Prediction activation:");
+                    if let Some(p) = &mut self.prediction_activation {
+                        p.show_mut(
+                            ui,
+                            ctx,
+                            buffer_stack,
+                            global_config,
+                            current_node_name,
+                            unique_id,
+                        );
+                        if ui.button("Remove").clicked() {
+                            self.prediction_activation = None;
+                        }
+                    } else {
+                        ui.label("None");
+                        if ui.button("Add").clicked() {
+                            self.prediction_activation = Self::default().prediction_activation;
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Particle count:");
+                    ui.add(egui::DragValue::new(&mut self.particle_count).range(1..=usize::MAX));
+                });
+                vector_show_mut(ui, "Initial pose", &mut self.initial_pose);
+                vector_show_mut(ui, "Initial spread", &mut self.initial_spread);
+                vector_show_mut(ui, "Motion noise", &mut self.motion_noise);
+                vector_show_mut(ui, "GNSS noise", &mut self.gnss_noise);
+                vector_show_mut(ui, "Landmark noise", &mut self.landmark_noise);
+                self.resampling.show_mut(
+                    ui,
+                    ctx,
+                    buffer_stack,
+                    global_config,
+                    current_node_name,
+                    unique_id,
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Resampling threshold:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.resampling_threshold)
+                            .speed(0.01)
+                            .range(0. ..=1.),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Recorded particles:");
+                    match &mut self.recorded_particles {
+                        Some(count) => {
+                            ui.add(egui::DragValue::new(count).range(1..=usize::MAX));
+                            if ui.button("X").clicked() {
+                                self.recorded_particles = None;
+                            }
+                        }
+                        None => {
+                            ui.label("All");
+                            if ui.button("+").clicked() {
+                                self.recorded_particles = Some(50);
+                            }
+                        }
+                    }
+                });
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        egui::CollapsingHeader::new("Particle Filter Estimator")
+            .id_salt(format!("particle-filter-estimator-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Prediction activation:");
+                    if let Some(p) = &self.prediction_activation {
+                        p.show(ui, ctx, unique_id);
+                    } else {
+                        ui.label("None");
+                    }
+                });
+                ui.label(format!("Particle count: {}", self.particle_count));
+                ui.label(format!("Initial pose: {:?}", self.initial_pose));
+                ui.label(format!("Initial spread: {:?}", self.initial_spread));
+                ui.label(format!("Motion noise: {:?}", self.motion_noise));
+                ui.label(format!("GNSS noise: {:?}", self.gnss_noise));
+                ui.label(format!("Landmark noise: {:?}", self.landmark_noise));
+                self.resampling.show(ui, ctx, unique_id);
+                ui.label(format!(
+                    "Resampling threshold: {}",
+                    self.resampling_threshold
+                ));
+                match self.recorded_particles {
+                    Some(count) => ui.label(format!("Recorded particles: {count}")),
+                    None => ui.label("Recorded particles: all"),
+                };
+            });
+    }
+}
+
+/// Record of one particle of the [`ParticleFilterEstimator`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParticleRecord {
+    /// Pose hypothesis `[x, y, orientation]`.
+    pub pose: [f32; 3],
+    /// Normalized weight of the particle, among all the particles of the filter.
+    pub weight: f32,
+}
+
+/// Record for [`ParticleFilterEstimator`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParticleFilterEstimatorRecord {
+    /// Current state estimated (weighted mean of the particles).
+    pub world_state: WorldStateRecord,
+    /// Particles, downsampled to `recorded_particles` if configured.
+    pub particles: Vec<ParticleRecord>,
+    /// Effective sample size of the weights, before any resampling.
+    pub effective_sample_size: f32,
+    /// Last time the particles were propagated.
+    pub last_time_prediction: f32,
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for ParticleFilterEstimatorRecord {
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            egui::CollapsingHeader::new("World state").show(ui, |ui| {
+                self.world_state.show(ui, ctx, unique_id);
+            });
+            ui.label(format!("Recorded particles: {}", self.particles.len()));
+            ui.label(format!(
+                "Effective sample size: {}",
+                self.effective_sample_size
+            ));
+            ui.label(format!(
+                "Last prediction time: {}",
+                self.last_time_prediction
+            ));
+        });
+    }
+}
+
+/// Standard normal random variable on the 3 pose variables.
+fn standard_normal(va_factory: &DeterministRandomVariableFactory) -> DeterministRandomVariable {
+    va_factory.make_variable(RandomVariableTypeConfig::Normal(
+        NormalRandomVariableConfig {
+            mean: vec![0.; 3],
+            covariance: vec![1., 0., 0., 0., 1., 0., 0., 0., 1.],
+        },
+    ))
+}
+
+/// Particle filter on the ego pose.
+#[derive(Debug)]
+pub struct ParticleFilterEstimator {
+    /// Weighted mean of the particles on the `last_time_prediction`. The landmarks are the prior
+    /// landmarks of the environment.
+    world_state: WorldState,
+    /// Pose hypotheses.
+    particles: Vec<Vector3<f32>>,
+    /// Standard deviation of the particles around the pose at the initialization, and after a
+    /// reset.
+    initial_spread: Vector3<f32>,
+    /// Normalized weights of the particles.
+    weights: Vec<f32>,
+    /// Standard normal noise of each particle.
+    particle_noises: Vec<DeterministRandomVariable>,
+    /// Uniform draws in `[0, 1[`, one per particle, used by the resampling.
+    resampling_draws: DeterministRandomVariable,
+    motion_noise: Vector3<f32>,
+    gnss_noise: Vector3<f32>,
+    landmark_noise: Vector3<f32>,
+    resampling: ResamplingStrategy,
+    resampling_threshold: f32,
+    recorded_particles: Option<usize>,
+    /// Effective sample size before the last resampling.
+    effective_sample_size: f32,
+    /// Prediction period, in seconds.
+    prediction_activation: Option<Periodicity>,
+    /// Last time the particles were propagated.
+    last_time_prediction: f32,
+}
+
+impl ParticleFilterEstimator {
+    /// Creates a new [`ParticleFilterEstimator`] from the given `config`.
+    pub fn from_config(
+        config: &ParticleFilterEstimatorConfig,
+        _global_config: &SimulatorConfig,
+        va_factory: &DeterministRandomVariableFactory,
+        initial_time: f32,
+    ) -> Self {
+        let particle_count = config.particle_count.max(1);
+        let particle_noises: Vec<_> = (0..particle_count)
+            .map(|_| standard_normal(va_factory))
+            .collect();
+        let resampling_draws = va_factory.make_variable(RandomVariableTypeConfig::Uniform(
+            UniformRandomVariableConfig {
+                min: vec![0.; particle_count],
+                max: vec![1.; particle_count],
+            },
+        ));
+        let initial_pose = Vector3::from(config.initial_pose);
+        let initial_spread = Vector3::from(config.initial_spread);
+        let particles = particle_noises
+            .iter()
+            .map(|noise| {
+                let mut pose = initial_pose
+                    + initial_spread
+                        .component_mul(&Vector3::from_column_slice(&noise.generate(initial_time)));
+                pose.z = mod2pi(pose.z);
+                pose
+            })
+            .collect();
+        let prediction_activation = config
+            .prediction_activation
+            .as_ref()
+            .map(|p| Periodicity::from_config(p, va_factory, initial_time));
+        let mut world_state = WorldState::new();
+        world_state.ego = Some(State::new());
+        let mut estimator = Self {
+            world_state,
+            particles,
+            initial_spread,
+            weights: vec![1. / particle_count as f32; particle_count],
+            particle_noises,
+            resampling_draws,
+            motion_noise: Vector3::from(config.motion_noise),
+            gnss_noise: Vector3::from(config.gnss_noise),
+            landmark_noise: Vector3::from(config.landmark_noise),
+            resampling: config.resampling,
+            resampling_threshold: config.resampling_threshold,
+            recorded_particles: config.recorded_particles,
+            effective_sample_size: particle_count as f32,
+            prediction_activation,
+            last_time_prediction: initial_time,
+        };
+        estimator.update_estimate();
+        estimator
+    }
+
+    fn ego(&self) -> &State {
+        self.world_state.ego.as_ref().unwrap()
+    }
+
+    /// Draw the particles around `pose`, with the initial spread.
+    fn spread_particles(&mut self, pose: &Vector3<f32>, time: f32) {
+        for (particle, noise) in self.particles.iter_mut().zip(&self.particle_noises) {
+            *particle = pose
+                + self
+                    .initial_spread
+                    .component_mul(&Vector3::from_column_slice(&noise.generate(time)));
+            particle.z = mod2pi(particle.z);
+        }
+        let count = self.particles.len();
+        self.weights = vec![1. / count as f32; count];
+    }
+
+    /// Move the particles up to `time` with the last known velocities.
+    fn propagate(&mut self, time: f32) {
+        let dt = time - self.last_time_prediction;
+        if dt <= 0. {
+            return;
+        }
+        let velocity = self.ego().velocity;
+        let noise_scale = self.motion_noise * dt.sqrt();
+        for (pose, noise) in self.particles.iter_mut().zip(&self.particle_noises) {
+            let (sin, cos) = pose.z.sin_cos();
+            pose.x += (velocity.x * cos - velocity.y * sin) * dt;
+            pose.y += (velocity.x * sin + velocity.y * cos) * dt;
+            pose.z += velocity.z * dt;
+            *pose += noise_scale.component_mul(&Vector3::from_column_slice(&noise.generate(time)));
+            pose.z = mod2pi(pose.z);
+        }
+        self.last_time_prediction = time;
+        self.update_estimate();
+    }
+
+    /// Multiply the weights by the gaussian likelihood of the measurement. `innovation` gives the
+    /// difference between the measurement and the value expected from a particle.
+    fn weight<F>(&mut self, noise: Vector3<f32>, innovation_of: F)
+    where
+        F: Fn(&Vector3<f32>) -> Vector3<f32>,
+    {
+        for (pose, weight) in self.particles.iter().zip(self.weights.iter_mut()) {
+            let mut innovation = innovation_of(pose);
+            innovation.z = mod2pi(innovation.z);
+            let normalized = innovation.component_div(&noise);
+            *weight *= (-0.5 * normalized.norm_squared()).exp();
+        }
+        let sum: f32 = self.weights.iter().sum();
+        if sum > 0. && sum.is_finite() {
+            self.weights.iter_mut().for_each(|w| *w /= sum);
+        } else {
+            warn!("No particle is compatible with the measurement, weights reset");
+            let count = self.weights.len() as f32;
+            self.weights.iter_mut().for_each(|w| *w = 1. / count);
+        }
+    }
+
+    /// Weight the particles with a measurement of the absolute pose.
+    fn gnss_update(&mut self, measured: &Vector3<f32>) {
+        let measured = *measured;
+        self.weight(self.gnss_noise, |pose| measured - pose);
+    }
+
+    /// Weight the particles with the `observed` pose of a landmark at `landmark`, relative to the
+    /// node.
+    fn landmark_update(&mut self, landmark: &Vector3<f32>, observed: &Vector3<f32>) {
+        let (landmark, observed) = (*landmark, *observed);
+        self.weight(self.landmark_noise, |pose| {
+            let (sin, cos) = pose.z.sin_cos();
+            let (dx, dy) = (landmark.x - pose.x, landmark.y - pose.y);
+            observed
+                - Vector3::new(
+                    cos * dx + sin * dy,
+                    -sin * dx + cos * dy,
+                    landmark.z - pose.z,
+                )
+        });
+    }
+
+    /// Draw new particles proportionally to the weights, with the configured strategy.
+    fn resample(&mut self, time: f32) {
+        let count = self.particles.len();
+        let draws = self.resampling_draws.generate(time);
+        let mut positions: Vec<f32> = match self.resampling {
+            ResamplingStrategy::Multinomial => draws,
+            ResamplingStrategy::Systematic => (0..count)
+                .map(|i| (i as f32 + draws[0]) / count as f32)
+                .collect(),
+            ResamplingStrategy::Stratified => (0..count)
+                .map(|i| (i as f32 + draws[i]) / count as f32)
+                .collect(),
+        };
+        positions.sort_by(f32::total_cmp);
+        let mut particles = Vec::with_capacity(count);
+        let mut cumulated = self.weights[0];
+        let mut index = 0;
+        for position in positions {
+            while position > cumulated && index + 1 < count {
+                index += 1;
+                cumulated += self.weights[index];
+            }
+            particles.push(self.particles[index]);
+        }
+        self.particles = particles;
+        self.weights = vec![1. / count as f32; count];
+    }
+
+    /// Compute the weighted mean and the effective sample size of the particles.
+    fn update_estimate(&mut self) {
+        let mut mean = Vector3::zeros();
+        let (mut sin, mut cos) = (0., 0.);
+        for (pose, weight) in self.particles.iter().zip(&self.weights) {
+            mean.x += weight * pose.x;
+            mean.y += weight * pose.y;
+            sin += weight * pose.z.sin();
+            cos += weight * pose.z.cos();
+        }
+        mean.z = f32::atan2(sin, cos);
+        self.world_state.ego.as_mut().unwrap().pose = mean;
+        self.effective_sample_size = 1. / self.weights.iter().map(|w| w * w).sum::<f32>();
+    }
+}
+
+impl StateEstimator for ParticleFilterEstimator {
+    fn post_init(&mut self, node: &mut Node) -> crate::errors::SimbaResult<()> {
+        self.world_state.landmarks = node.environment().prior_landmarks();
+        Ok(())
+    }
+
+    fn prediction_step(&mut self, _node: &mut Node, _command: Option<Command>, time: f32) {
+        if (time - self.next_time_step()).abs() > TIME_ROUND / 2. {
+            error!(
+                "Error trying to update estimate too soon! (it is {} but expecting {})",
+                time,
+                self.next_time_step()
+            );
+            return;
+        }
+        self.propagate(time);
+        if let Some(p) = self.prediction_activation.as_mut() {
+            p.update(time);
+        }
+    }
+
+    fn correction_step(&mut self, node: &mut Node, observations: &[Observation], time: f32) {
+        let node_name = node.name();
+        for observation in observations {
+            // Observations received from other nodes are not about the ego pose, and the
+            // observations in another frame are rejected by the capabilities check
+            if observation.observer != node_name || !observation.in_native_frame() {
+                continue;
+            }
+            self.propagate(observation.time.min(time));
+            match &observation.sensor_observation {
+                SensorObservation::Speed(obs) => {
+                    self.world_state.ego.as_mut().unwrap().velocity = Vector3::new(
+                        obs.linear_velocity,
+                        obs.lateral_velocity,
+                        obs.angular_velocity,
+                    );
+                }
+                SensorObservation::GNSS(obs) => self.gnss_update(&obs.pose),
+                SensorObservation::OrientedLandmark(obs) => {
+                    if let Some(landmark) = self.world_state.landmarks.get(&obs.id) {
+                        let landmark = landmark.pose;
+                        self.landmark_update(&landmark, &obs.pose);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.update_estimate();
+        if self.effective_sample_size < self.resampling_threshold * self.particles.len() as f32 {
+            self.resample(time);
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            consumed_observations: Some(vec![
+                ObservationKind::Speed,
+                ObservationKind::GNSS,
+                ObservationKind::OrientedLandmark,
+            ]),
+            // Observations in their native frame only
+            consumed_frames: Some(Vec::new()),
+            ..Default::default()
+        }
+    }
+
+    fn world_state(&self) -> WorldState {
+        self.world_state.clone()
+    }
+
+    fn next_time_step(&self) -> f32 {
+        if let Some(period) = &self.prediction_activation {
+            period.next_time()
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+
+    fn pose_covariance(&self) -> Option<SMatrix<f32, 3, 3>> {
+        let mean = self.ego().pose;
+        let mut covariance = SMatrix::<f32, 3, 3>::zeros();
+        for (pose, weight) in self.particles.iter().zip(&self.weights) {
+            let mut error = pose - mean;
+            error.z = mod2pi(error.z);
+            covariance += *weight * error * error.transpose();
+        }
+        Some(covariance)
+    }
+
+    fn reset_state(&mut self, state: State, time: f32) -> Result<(), String> {
+        self.spread_particles(&state.pose, time);
+        self.world_state.ego = Some(state);
+        self.last_time_prediction = time;
+        self.update_estimate();
+        Ok(())
+    }
+}
+
+impl Recordable<StateEstimatorRecord> for ParticleFilterEstimator {
+    fn record(&self) -> StateEstimatorRecord {
+        let step = match self.recorded_particles {
+            Some(count) => self.particles.len().div_ceil(count.max(1)),
+            None => 1,
+        };
+        StateEstimatorRecord::ParticleFilter(ParticleFilterEstimatorRecord {
+            world_state: self
+                .world_state
+                .record()
+                .with_ego_covariance(self.pose_covariance()),
+            particles: self
+                .particles
+                .iter()
+                .zip(&self.weights)
+                .step_by(step)
+                .map(|(pose, weight)| ParticleRecord {
+                    pose: [pose.x, pose.y, pose.z],
+                    weight: *weight,
+                })
+                .collect(),
+            effective_sample_size: self.effective_sample_size,
+            last_time_prediction: self.last_time_prediction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimator(config: &ParticleFilterEstimatorConfig, seed: f32) -> ParticleFilterEstimator {
+        ParticleFilterEstimator::from_config(
+            config,
+            &SimulatorConfig::default(),
+            &DeterministRandomVariableFactory::new(seed),
+            0.,
+        )
+    }
+
+    #[test]
+    fn same_seed_gives_same_particles() {
+        let config = ParticleFilterEstimatorConfig::default();
+        assert_ne!(
+            estimator(&config, 12.).particles,
+            estimator(&config, 13.).particles,
+            "Different seeds should give different particles"
+        );
+        let mut filters = [estimator(&config, 12.), estimator(&config, 12.)];
+        for filter in filters.iter_mut() {
+            filter.world_state.ego.as_mut().unwrap().velocity = Vector3::new(1., 0., 0.1);
+            filter.propagate(1.);
+            filter.gnss_update(&Vector3::new(1., 0., 0.1));
+            filter.resample(1.);
+        }
+        assert_eq!(filters[0].particles, filters[1].particles);
+    }
+
+    #[test]
+    fn covariance_around_the_weighted_mean() {
+        let mut filter = estimator(&ParticleFilterEstimatorConfig::default(), 12.);
+        // Orientations on both sides of the angle discontinuity
+        filter.particles = vec![
+            Vector3::new(1., 0., std::f32::consts::PI - 0.1),
+            Vector3::new(-1., 0., -std::f32::consts::PI + 0.1),
+        ];
+        filter.weights = vec![0.5, 0.5];
+        filter.update_estimate();
+        let covariance = filter.pose_covariance().unwrap();
+        let expected = SMatrix::<f32, 3, 3>::new(1., 0., -0.1, 0., 0., 0., -0.1, 0., 0.01);
+        assert!((covariance - expected).norm() < 1e-5);
+        // Recorded with the ego estimate
+        let recorded = filter.record().pose_covariance().unwrap();
+        assert!((SMatrix::<f32, 3, 3>::from_fn(|i, j| recorded[i][j]) - expected).norm() < 1e-5);
+    }
+
+    #[test]
+    fn landmark_correction_and_resampling() {
+        let config = ParticleFilterEstimatorConfig {
+            particle_count: 500,
+            initial_spread: [1., 1., 0.05],
+            recorded_particles: Some(50),
+            ..Default::default()
+        };
+        for resampling in [
+            ResamplingStrategy::Multinomial,
+            ResamplingStrategy::Systematic,
+            ResamplingStrategy::Stratified,
+        ] {
+            let mut filter = estimator(
+                &ParticleFilterEstimatorConfig {
+                    resampling,
+                    ..config.clone()
+                },
+                0.,
+            );
+            // True pose is (1, 0, 0): a landmark at (3, 1, 0) is seen at (2, 1, 0)
+            filter.landmark_update(&Vector3::new(3., 1., 0.), &Vector3::new(2., 1., 0.));
+            filter.update_estimate();
+            let pose = filter.ego().pose;
+            assert!((pose.x - 1.).abs() < 0.2, "{resampling:?}: {pose}");
+            assert!(pose.y.abs() < 0.2, "{resampling:?}: {pose}");
+            assert!(filter.effective_sample_size < 250.);
+
+            filter.resample(0.);
+            assert_eq!(filter.particles.len(), 500);
+            filter.update_estimate();
+            let resampled = filter.ego().pose;
+            assert!(
+                (resampled - pose).norm() < 0.1,
+                "{resampling:?}: {resampled}"
+            );
+            let StateEstimatorRecord::ParticleFilter(record) = filter.record() else {
+                panic!("Wrong record type");
+            };
+            assert_eq!(record.particles.len(), 50);
+        }
+    }
+}
@@ -2,15 +2,29 @@
 Module providing the [`PerfectEstimator`] strategy. This strategy uses directly
 the groundtruth to provide the estimation. It can be used when the state used
 by the controller should be perfect.
+
+The prediction period can be changed at runtime by sending a
+[`ReconfigureMessage`] on the [`PerfectEstimator::CHANNEL_NAME`] channel of the node.
 */
 
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
 use super::{State, WorldState, WorldStateRecord};
 use crate::{
     constants::TIME_ROUND,
     errors::SimbaErrorTypes,
-    networking::service_manager::ServiceError,
+    networking::{
+        network::Network,
+        reconfigure::{ReconfigurationRecord, ReconfigureMessage},
+        service_manager::ServiceError,
+    },
     physics::robot_models::Command,
+    simulator::SimbaBrokerMultiClient,
     utils::{
+        SharedMutex, SharedRwLock,
         determinist_random_variable::DeterministRandomVariableFactory,
         periodicity::{Periodicity, PeriodicityConfig},
     },
@@ -23,6 +37,7 @@ use crate::sensors::Observation;
 use crate::simulator::SimulatorConfig;
 use log::{error, info, warn};
 use serde_derive::{Deserialize, Serialize};
+use simba_com::pub_sub::{MultiClientTrait, PathKey};
 use simba_macros::config_derives;
 
 /// Configuration for the [`PerfectEstimator`] strategy.
@@ -148,6 +163,9 @@ pub struct PerfectEstimatorRecord {
     pub world_state: WorldStateRecord,
     /// Last change of state
     pub last_time_prediction: f32,
+    /// Reconfiguration messages received by the estimator during the time step.
+    #[serde(default)]
+    pub reconfigurations: Vec<ReconfigurationRecord>,
 }
 
 #[cfg(feature = "gui")]
@@ -174,14 +192,22 @@ pub struct PerfectEstimator {
     prediction_activation: Option<Periodicity>,
     /// Last time the state was updated/predicted.
     last_time_prediction: f32,
+    /// Client receiving the [`ReconfigureMessage`]s.
+    message_client: SharedMutex<SimbaBrokerMultiClient>,
+    /// Reconfigurations received during the current time step.
+    reconfigurations: Vec<ReconfigurationRecord>,
 }
 
 impl PerfectEstimator {
+    /// Channel (relative to the node) on which [`ReconfigureMessage`]s are received.
+    pub const CHANNEL_NAME: &'static str = "state_estimator/reconfigure";
+
     /// Creates a new [`PerfectEstimator`] from the given `config`.
     pub fn from_config(
         config: &PerfectEstimatorConfig,
         _global_config: &SimulatorConfig,
         va_factory: &DeterministRandomVariableFactory,
+        network: &SharedRwLock<Network>,
         initial_time: f32,
     ) -> Self {
         let mut world_state = WorldState::new();
@@ -201,16 +227,42 @@ impl PerfectEstimator {
             .as_ref()
             .map(|p| p.next_time())
             .unwrap_or(initial_time);
+        let network = network.write().unwrap();
+        let key = network.make_channel(PathKey::from_str(Self::CHANNEL_NAME).unwrap());
+        let message_client = network.subscribe_to(&[key], None);
         Self {
             prediction_activation: activation,
             world_state,
             last_time_prediction: last_time,
+            message_client: Arc::new(Mutex::new(message_client)),
+            reconfigurations: Vec::new(),
+        }
+    }
+
+    /// Applies the prediction period of a [`ReconfigureMessage`].
+    fn reconfigure(&mut self, message: &ReconfigureMessage, time: f32) -> Result<(), String> {
+        message.validate()?;
+        if message.noise_scale.is_some() {
+            return Err("The perfect estimator has no noise to scale".to_string());
+        }
+        if let Some(period) = message.period {
+            match &mut self.prediction_activation {
+                Some(activation) => activation.set_period(period, time),
+                None => {
+                    return Err(
+                        "The estimator has no prediction activation, its period cannot be changed"
+                            .to_string(),
+                    );
+                }
+            }
         }
+        Ok(())
     }
 }
 
 use super::{StateEstimator, StateEstimatorRecord};
 use crate::node::Node;
+use crate::node::capabilities::{Capabilities, NodeService};
 
 impl StateEstimator for PerfectEstimator {
     fn prediction_step(&mut self, node: &mut Node, _command: Option<Command>, time: f32) {
@@ -278,6 +330,18 @@ impl StateEstimator for PerfectEstimator {
 
     fn correction_step(&mut self, _node: &mut Node, _observations: &[Observation], _time: f32) {}
 
+    /// Needs the real state of its node if the ego state is estimated.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            required_services: if self.world_state.ego.is_some() {
+                vec![NodeService::RealState]
+            } else {
+                Vec::new()
+            },
+            ..Default::default()
+        }
+    }
+
     fn world_state(&self) -> WorldState {
         self.world_state.clone()
     }
@@ -290,7 +354,27 @@ impl StateEstimator for PerfectEstimator {
         }
     }
 
-    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+    fn pre_loop_hook(&mut self, node: &mut Node, time: f32) {
+        self.reconfigurations.clear();
+        while let Some((_, envelope)) = self.message_client.lock().unwrap().try_receive(time) {
+            let message = match serde_json::from_value::<ReconfigureMessage>(envelope.message) {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!("[{}] Invalid reconfiguration message: {e}", node.name());
+                    continue;
+                }
+            };
+            let result = self.reconfigure(&message, time);
+            if let Err(e) = &result {
+                warn!("[{}] Reconfiguration rejected: {e}", node.name());
+            }
+            self.reconfigurations.push(ReconfigurationRecord {
+                time,
+                message,
+                error: result.err(),
+            });
+        }
+    }
 }
 
 impl Recordable<StateEstimatorRecord> for PerfectEstimator {
@@ -298,6 +382,7 @@ impl Recordable<StateEstimatorRecord> for PerfectEstimator {
         StateEstimatorRecord::Perfect(PerfectEstimatorRecord {
             world_state: self.world_state.record(),
             last_time_prediction: self.last_time_prediction,
+            reconfigurations: self.reconfigurations.clone(),
         })
     }
 }
@@ -0,0 +1,342 @@
+/*!
+Warm start of the state estimators from a recorded state.
+
+An [`EstimatorWarmStartConfig`] can be attached to the main state estimator of a robot
+(`state_estimator_warm_start`) and to each bench estimator (`warm_start`). When the node is
+built, the estimator is initialized from a [`StateEstimatorRecord`], read from a file of a single
+record or from the result file of a previous run, so that a long bootstrap phase (convergence
+of a filter) can be skipped when iterating on a later segment of the mission.
+
+The record is given to [`StateEstimator::warm_start`], which resets the ego state from the ego
+estimate of the record. The EKF and the dead reckoning also restore their covariance (and the
+travelled distance) from a record of the same strategy, the cooperative localization from an
+EKF or cooperative localization record. The particle filter spreads its particles around the
+recorded pose. No landmark or map state is restored.
+
+```yaml
+state_estimator_warm_start:
+  type: Result
+  path: results/bootstrap.json
+  time: 120.
+```
+*/
+
+use std::path::Path;
+
+use config_checker::*;
+use serde::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+use crate::{
+    constants::TIME_ROUND,
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    simulator::{SimulatorConfig, config_path, stream_results},
+    state_estimators::{StateEstimator, StateEstimatorRecord},
+    utils::config_parsing::load_config_file,
+};
+#[cfg(feature = "gui")]
+use crate::{
+    gui::{
+        UIComponent,
+        utils::{path_finder, string_combobox},
+    },
+    utils::enum_tools::ToVec,
+};
+
+/// File of a single [`StateEstimatorRecord`].
+///
+/// Default values:
+/// - `path`: `""`
+#[config_derives]
+pub struct RecordWarmStartConfig {
+    /// Path of the record file (YAML or JSON), relative to the configuration path.
+    pub path: String,
+}
+
+impl Default for RecordWarmStartConfig {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+        }
+    }
+}
+
+impl Check for RecordWarmStartConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        if self.path.is_empty() {
+            return Err(vec![
+                "Warm start record path should not be empty".to_string(),
+            ]);
+        }
+        Ok(())
+    }
+}
+
+/// Record of a JSON result file of a previous run.
+///
+/// Default values:
+/// - `path`: `""`
+/// - `node`: `None` (node of the estimator)
+/// - `estimator`: `None` (same estimator)
+/// - `time`: `None` (last record)
+#[config_derives]
+pub struct ResultWarmStartConfig {
+    /// Path of the result file, relative to the configuration path.
+    pub path: String,
+    /// Node whose record is used, `None` for the node of the estimator.
+    pub node: Option<String>,
+    /// Estimator whose record is used: `state_estimator` for the main state estimator, or the
+    /// name of a bench estimator. `None` for the estimator warm started.
+    pub estimator: Option<String>,
+    /// The last record at or before this time is used, `None` for the last record of the run.
+    pub time: Option<f32>,
+}
+
+impl Default for ResultWarmStartConfig {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            node: None,
+            estimator: None,
+            time: None,
+        }
+    }
+}
+
+impl Check for ResultWarmStartConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.path.is_empty() {
+            errors.push("Warm start result path should not be empty".to_string());
+        }
+        if let Some(time) = self.time
+            && time < 0.
+        {
+            errors.push(format!("Warm start time should be positive, got {time}"));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Source of the record used to warm start a state estimator.
+///
+/// Default value: [`EstimatorWarmStartConfig::Record`] with [`RecordWarmStartConfig::default`].
+#[config_derives]
+pub enum EstimatorWarmStartConfig {
+    /// File of a single record.
+    #[check]
+    Record(RecordWarmStartConfig),
+    /// Record of a result file.
+    #[check]
+    Result(ResultWarmStartConfig),
+}
+
+impl Default for EstimatorWarmStartConfig {
+    fn default() -> Self {
+        Self::Record(RecordWarmStartConfig::default())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for EstimatorWarmStartConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Warm start")
+            .id_salt(format!("estimator-warm-start-{}", unique_id))
+            .show(ui, |ui| {
+                let mut current_str = self.to_string();
+                ui.horizontal(|ui| {
+                    ui.label("Source:");
+                    string_combobox(
+                        ui,
+                        &EstimatorWarmStartConfig::to_vec(),
+                        &mut current_str,
+                        format!("estimator-warm-start-choice-{}", unique_id),
+                    );
+                });
+                if current_str != self.to_string() {
+                    match current_str.as_str() {
+                        "Record" => *self = Self::Record(RecordWarmStartConfig::default()),
+                        "Result" => *self = Self::Result(ResultWarmStartConfig::default()),
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+                match self {
+                    Self::Record(c) => {
+                        ui.horizontal(|ui| {
+                            ui.label("Record path:");
+                            path_finder(ui, &mut c.path, &global_config.base_path);
+                        });
+                    }
+                    Self::Result(c) => {
+                        ui.horizontal(|ui| {
+                            ui.label("Result path:");
+                            path_finder(ui, &mut c.path, &global_config.base_path);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Node:");
+                            if let Some(node) = &mut c.node {
+                                ui.text_edit_singleline(node);
+                                if ui.button("X").clicked() {
+                                    c.node = None;
+                                }
+                            } else {
+                                ui.label("same node");
+                                if ui.button("+").clicked() {
+                                    c.node = Some(String::new());
+                                }
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Estimator:");
+                            if let Some(estimator) = &mut c.estimator {
+                                ui.text_edit_singleline(estimator);
+                                if ui.button("X").clicked() {
+                                    c.estimator = None;
+                                }
+                            } else {
+                                ui.label("same estimator");
+                                if ui.button("+").clicked() {
+                                    c.estimator = Some(MAIN_ESTIMATOR.to_string());
+                                }
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Time:");
+                            if let Some(time) = &mut c.time {
+                                ui.add(egui::DragValue::new(time).range(0.0..=f32::MAX));
+                                if ui.button("X").clicked() {
+                                    c.time = None;
+                                }
+                            } else {
+                                ui.label("last record");
+                                if ui.button("+").clicked() {
+                                    c.time = Some(0.);
+                                }
+                            }
+                        });
+                    }
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        match self {
+            Self::Record(c) => {
+                ui.label(format!("Warm start from the record {}", c.path));
+            }
+            Self::Result(c) => {
+                ui.label(format!(
+                    "Warm start from the result file {} (node: {}, estimator: {}, time: {})",
+                    c.path,
+                    c.node.as_deref().unwrap_or("same node"),
+                    c.estimator.as_deref().unwrap_or("same estimator"),
+                    c.time
+                        .map_or("last record".to_string(), |time| time.to_string())
+                ));
+            }
+        }
+    }
+}
+
+/// Name of the main state estimator of a node, to select its record in a result file.
+pub const MAIN_ESTIMATOR: &str = "state_estimator";
+
+impl EstimatorWarmStartConfig {
+    /// Load the record to warm start the estimator `estimator_name` ([`MAIN_ESTIMATOR`] for the
+    /// main state estimator) of the node `node_name`.
+    pub fn load_record(
+        &self,
+        global_config: &SimulatorConfig,
+        node_name: &str,
+        estimator_name: &str,
+    ) -> SimbaResult<StateEstimatorRecord> {
+        match self {
+            Self::Record(c) => load_config_file(
+                &config_path(&global_config.base_path, &c.path),
+                "state estimator record",
+            ),
+            Self::Result(c) => Self::load_result_record(
+                &config_path(&global_config.base_path, &c.path),
+                c.node.as_deref().unwrap_or(node_name),
+                c.estimator.as_deref().unwrap_or(estimator_name),
+                c.time,
+            ),
+        }
+    }
+
+    /// Last record of the estimator `estimator_name` of the node `node_name` at or before `time`
+    /// in the result file at `path`.
+    fn load_result_record(
+        path: &Path,
+        node_name: &str,
+        estimator_name: &str,
+        time: Option<f32>,
+    ) -> SimbaResult<StateEstimatorRecord> {
+        let mut found: Option<(f32, StateEstimatorRecord)> = None;
+        stream_results(path, |record| {
+            if record.node.name() != node_name
+                || time.is_some_and(|time| record.time > time + TIME_ROUND / 2.)
+                || found
+                    .as_ref()
+                    .is_some_and(|(found_time, _)| *found_time > record.time)
+            {
+                return;
+            }
+            let estimator_record = if estimator_name == MAIN_ESTIMATOR {
+                record.node.state_estimator().cloned()
+            } else {
+                record.node.state_estimator_bench().and_then(|bench| {
+                    bench
+                        .iter()
+                        .find(|estimator| estimator.name == estimator_name)
+                        .map(|estimator| estimator.record.clone())
+                })
+            };
+            if let Some(estimator_record) = estimator_record {
+                found = Some((record.time, estimator_record));
+            }
+        })?;
+        found.map(|(_, record)| record).ok_or_else(|| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "No record of the estimator `{estimator_name}` of the node `{node_name}`{} in the result file `{}`",
+                    time.map_or(String::new(), |time| format!(" before {time}")),
+                    path.display()
+                ),
+            )
+        })
+    }
+}
+
+/// Warm start the `state_estimator` with the record given by `config`, at `time`.
+///
+/// See [`EstimatorWarmStartConfig::load_record`] for the other arguments.
+pub fn warm_start_estimator(
+    state_estimator: &mut dyn StateEstimator,
+    config: &EstimatorWarmStartConfig,
+    global_config: &SimulatorConfig,
+    node_name: &str,
+    estimator_name: &str,
+    time: f32,
+) -> SimbaResult<()> {
+    let record = config.load_record(global_config, node_name, estimator_name)?;
+    state_estimator.warm_start(&record, time).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::InitializationError,
+            format!("Warm start of the estimator `{estimator_name}` failed: {e}"),
+        )
+    })
+}
@@ -0,0 +1,403 @@
+/*!
+Divergence watchdog of the state estimators.
+
+An [`EstimatorWatchdogConfig`] can be attached to the main state estimator of a robot
+(`state_estimator_watchdog`) and to each bench estimator (`watchdog`). After each step of the
+estimator, the watchdog compares the trace of the pose covariance and the normalized innovation
+squared (NIS) of the last correction step to the configured thresholds. When the thresholds
+are crossed on `consecutive_checks` checks in a row, a [`DivergenceRecord`] is added to the
+records of the node and the estimator can be reinitialized, so that a long Monte-Carlo run keeps
+producing useful data after a filter blows up.
+
+The statistics are only available for the strategies computing them (see
+[`StateEstimator::pose_covariance`] and [`StateEstimator::innovation_statistic`]): the other
+thresholds are not checked.
+
+```yaml
+state_estimator_watchdog:
+  max_covariance_trace: 10.
+  max_innovation: 50.
+  consecutive_checks: 3
+  reinitialization: LastGnssFix
+```
+*/
+
+use config_checker::*;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+#[cfg(feature = "gui")]
+use crate::simulator::SimulatorConfig;
+use crate::{
+    sensors::{Observation, SensorObservation},
+    state_estimators::{State, StateEstimator},
+};
+
+/// State used to reinitialize a diverged estimator.
+///
+/// Default value: [`WatchdogReinitialization::None`].
+#[config_derives]
+#[derive(Copy)]
+pub enum WatchdogReinitialization {
+    /// The divergence is only recorded.
+    None,
+    /// Pose of the last GNSS observation of the node given to the estimator, with the estimated
+    /// velocity.
+    LastGnssFix,
+    /// True state of the node, from its physics.
+    TrueState,
+}
+
+impl Default for WatchdogReinitialization {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// Configuration of the divergence watchdog of a state estimator.
+///
+/// Default values:
+/// - `max_covariance_trace`: `None` (not checked)
+/// - `max_innovation`: `None` (not checked)
+/// - `consecutive_checks`: `1`
+/// - `reinitialization`: [`WatchdogReinitialization::None`]
+#[config_derives]
+pub struct EstimatorWatchdogConfig {
+    /// Maximal trace of the covariance of the estimated pose.
+    pub max_covariance_trace: Option<f32>,
+    /// Maximal normalized innovation squared of the measurements of a correction step.
+    pub max_innovation: Option<f32>,
+    /// Number of failed checks in a row before declaring the divergence.
+    pub consecutive_checks: usize,
+    /// State used to reinitialize the estimator after a divergence.
+    pub reinitialization: WatchdogReinitialization,
+}
+
+impl Default for EstimatorWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            max_covariance_trace: None,
+            max_innovation: None,
+            consecutive_checks: 1,
+            reinitialization: WatchdogReinitialization::None,
+        }
+    }
+}
+
+impl Check for EstimatorWatchdogConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Some(max_trace) = self.max_covariance_trace
+            && max_trace <= 0.
+        {
+            errors.push(format!(
+                "Watchdog max_covariance_trace should be strictly positive, got {max_trace}"
+            ));
+        }
+        if let Some(max_innovation) = self.max_innovation
+            && max_innovation <= 0.
+        {
+            errors.push(format!(
+                "Watchdog max_innovation should be strictly positive, got {max_innovation}"
+            ));
+        }
+        if self.consecutive_checks == 0 {
+            errors.push("Watchdog consecutive_checks should be at least 1".to_string());
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for EstimatorWatchdogConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &SimulatorConfig,
+        _current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        egui::CollapsingHeader::new("Watchdog")
+            .id_salt(format!("estimator-watchdog-{}", unique_id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Max covariance trace: ");
+                    if let Some(max_trace) = &mut self.max_covariance_trace {
+                        ui.add(egui::DragValue::new(max_trace).range(0.001..=f32::MAX));
+                        if ui.button("X").clicked() {
+                            self.max_covariance_trace = None;
+                        }
+                    } else if ui.button("+").clicked() {
+                        self.max_covariance_trace = Some(10.);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Max innovation: ");
+                    if let Some(max_innovation) = &mut self.max_innovation {
+                        ui.add(egui::DragValue::new(max_innovation).range(0.001..=f32::MAX));
+                        if ui.button("X").clicked() {
+                            self.max_innovation = None;
+                        }
+                    } else if ui.button("+").clicked() {
+                        self.max_innovation = Some(50.);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Consecutive checks: ");
+                    ui.add(
+                        egui::DragValue::new(&mut self.consecutive_checks).range(1..=usize::MAX),
+                    );
+                });
+                let mut current_str = self.reinitialization.to_string();
+                ui.horizontal(|ui| {
+                    use crate::{gui::utils::string_combobox, utils::enum_tools::ToVec};
+
+                    ui.label("Reinitialization:");
+                    string_combobox(
+                        ui,
+                        &WatchdogReinitialization::to_vec(),
+                        &mut current_str,
+                        format!("watchdog-reinitialization-choice-{}", unique_id),
+                    );
+                });
+                if current_str != self.reinitialization.to_string() {
+                    self.reinitialization = match current_str.as_str() {
+                        "None" => WatchdogReinitialization::None,
+                        "LastGnssFix" => WatchdogReinitialization::LastGnssFix,
+                        "TrueState" => WatchdogReinitialization::TrueState,
+                        _ => panic!("Where did you find this value?"),
+                    };
+                }
+            });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, unique_id: &str) {
+        let to_string =
+            |value: Option<f32>| value.map_or("unchecked".to_string(), |v| v.to_string());
+        egui::CollapsingHeader::new("Watchdog")
+            .id_salt(format!("estimator-watchdog-{}", unique_id))
+            .show(ui, |ui| {
+                ui.label(format!(
+                    "Max covariance trace: {}",
+                    to_string(self.max_covariance_trace)
+                ));
+                ui.label(format!(
+                    "Max innovation: {}",
+                    to_string(self.max_innovation)
+                ));
+                ui.label(format!("Consecutive checks: {}", self.consecutive_checks));
+                ui.label(format!("Reinitialization: {}", self.reinitialization));
+            });
+    }
+}
+
+/// Divergence of a state estimator detected by its watchdog.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DivergenceRecord {
+    /// Name of the state estimator (`state_estimator` for the main one, or the name of the bench
+    /// estimator).
+    pub estimator: String,
+    /// Time of the detection.
+    pub time: f32,
+    /// Threshold crossed.
+    pub reason: String,
+    /// Reinitialization applied after the detection.
+    pub reinitialization: WatchdogReinitialization,
+    /// Error of the reinitialization, if it failed.
+    pub error: Option<String>,
+}
+
+/// Runtime watchdog of one state estimator, see the [module documentation](self).
+#[derive(Debug)]
+pub struct EstimatorWatchdog {
+    estimator: String,
+    config: EstimatorWatchdogConfig,
+    /// Number of failed checks in a row.
+    violations: usize,
+    /// Set when a divergence was declared and the estimator was not reinitialized: the next
+    /// divergence is only declared after a successful check.
+    diverged: bool,
+    /// Pose of the last GNSS observation of the node given to the estimator.
+    last_gnss_fix: Option<Vector3<f32>>,
+}
+
+impl EstimatorWatchdog {
+    /// Creates the watchdog of the estimator named `estimator`.
+    pub fn new(estimator: String, config: &EstimatorWatchdogConfig) -> Self {
+        Self {
+            estimator,
+            config: config.clone(),
+            violations: 0,
+            diverged: false,
+            last_gnss_fix: None,
+        }
+    }
+
+    /// Name of the watched state estimator.
+    pub fn estimator(&self) -> &str {
+        &self.estimator
+    }
+
+    /// Reinitialization to apply after a divergence.
+    pub fn reinitialization(&self) -> WatchdogReinitialization {
+        self.config.reinitialization
+    }
+
+    /// Keeps the last GNSS fix of the node `node_name` among the `observations` given to the
+    /// estimator.
+    pub fn observe(&mut self, observations: &[Observation], node_name: &str) {
+        for observation in observations {
+            if observation.observer == node_name
+                && let SensorObservation::GNSS(gnss) = &observation.sensor_observation
+            {
+                self.last_gnss_fix = Some(gnss.pose);
+            }
+        }
+    }
+
+    /// Checks the statistics of the estimator. The innovation is only checked after a correction
+    /// step. Returns the reason of the divergence when it is declared.
+    pub fn check(
+        &mut self,
+        state_estimator: &dyn StateEstimator,
+        after_correction: bool,
+    ) -> Option<String> {
+        let mut checked = false;
+        let mut reason = None;
+        if let Some(max_trace) = self.config.max_covariance_trace
+            && let Some(covariance) = state_estimator.pose_covariance()
+        {
+            checked = true;
+            let trace = covariance.trace();
+            // NaN values also mean a divergence
+            if !(trace <= max_trace) {
+                reason = Some(format!("covariance trace {trace} > {max_trace}"));
+            }
+        }
+        if after_correction
+            && let Some(max_innovation) = self.config.max_innovation
+            && let Some(innovation) = state_estimator.innovation_statistic()
+        {
+            checked = true;
+            if reason.is_none() && !(innovation <= max_innovation) {
+                reason = Some(format!("innovation {innovation} > {max_innovation}"));
+            }
+        }
+        if !checked {
+            return None;
+        }
+        match reason {
+            None => {
+                self.violations = 0;
+                self.diverged = false;
+                None
+            }
+            Some(_) if self.diverged => None,
+            Some(reason) => {
+                self.violations += 1;
+                if self.violations < self.config.consecutive_checks {
+                    return None;
+                }
+                self.violations = 0;
+                self.diverged = true;
+                Some(reason)
+            }
+        }
+    }
+
+    /// State to reinitialize the estimator from the last GNSS fix, with the `estimated` velocity.
+    pub fn last_gnss_fix_state(&self, estimated: Option<State>) -> Result<State, String> {
+        let pose = self
+            .last_gnss_fix
+            .ok_or_else(|| "no GNSS fix received by the estimator".to_string())?;
+        Ok(State {
+            pose,
+            velocity: estimated.map_or_else(Vector3::zeros, |s| s.velocity),
+        })
+    }
+
+    /// Marks the estimator as reinitialized: the divergence can be declared again.
+    pub fn reinitialized(&mut self) {
+        self.diverged = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::SMatrix;
+
+    use super::*;
+    use crate::{
+        node::Node,
+        physics::robot_models::Command,
+        recordable::Recordable,
+        state_estimators::{StateEstimatorRecord, WorldState},
+    };
+
+    #[derive(Debug)]
+    struct CovarianceEstimator(f32);
+
+    impl StateEstimator for CovarianceEstimator {
+        fn prediction_step(&mut self, _node: &mut Node, _command: Option<Command>, _time: f32) {}
+        fn correction_step(&mut self, _node: &mut Node, _obs: &[Observation], _time: f32) {}
+        fn world_state(&self) -> WorldState {
+            WorldState::new()
+        }
+        fn next_time_step(&self) -> f32 {
+            f32::INFINITY
+        }
+        fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+        fn pose_covariance(&self) -> Option<SMatrix<f32, 3, 3>> {
+            Some(SMatrix::identity() * self.0)
+        }
+    }
+
+    impl Recordable<StateEstimatorRecord> for CovarianceEstimator {
+        fn record(&self) -> StateEstimatorRecord {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn divergence_after_consecutive_checks() {
+        let mut watchdog = EstimatorWatchdog::new(
+            "state_estimator".to_string(),
+            &EstimatorWatchdogConfig {
+                max_covariance_trace: Some(3.),
+                consecutive_checks: 2,
+                ..Default::default()
+            },
+        );
+        assert!(watchdog.check(&CovarianceEstimator(0.5), false).is_none());
+        assert!(watchdog.check(&CovarianceEstimator(2.), false).is_none());
+        assert!(watchdog.check(&CovarianceEstimator(2.), false).is_some());
+        // Declared once until the estimator recovers
+        assert!(watchdog.check(&CovarianceEstimator(2.), false).is_none());
+        assert!(watchdog.check(&CovarianceEstimator(2.), false).is_none());
+        assert!(
+            watchdog
+                .check(&CovarianceEstimator(f32::NAN), false)
+                .is_none()
+        );
+        assert!(watchdog.check(&CovarianceEstimator(0.5), false).is_none());
+        assert!(
+            watchdog
+                .check(&CovarianceEstimator(f32::NAN), false)
+                .is_none()
+        );
+        assert!(
+            watchdog
+                .check(&CovarianceEstimator(f32::NAN), false)
+                .is_some()
+        );
+    }
+}
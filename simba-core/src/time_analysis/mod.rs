@@ -141,6 +141,16 @@ impl TimeAnalysisFactory {
         })
     }
 
+    /// Returns the durations of all the executions of the profile `profile_name` in the node `node_name`.
+    pub fn profile_durations(&self, node_name: &str, profile_name: &str) -> Vec<Duration> {
+        self.iter_execution_profiles()
+            .filter(|(name, _)| name == node_name)
+            .flat_map(|(_, profiles)| profiles)
+            .filter(|profile| profile.name == profile_name)
+            .map(|profile| profile.duration)
+            .collect()
+    }
+
     /// Save the time results analysis to the file specified in the config.
     /// Execute the real time analysis to save a more readable report of the results, with statistics such as mean, median, etc. for each profile. The report is saved in the same path as the results, with the extension `.report.csv`.
     pub fn save_results(&self) {
@@ -0,0 +1,1535 @@
+//! Shared parsing of the YAML configuration files (simulator, batch, map and trajectory files).
+//!
+//! The numbers of the configuration files follow the YAML syntax, independently of the locale of
+//! the machine: `.` is the only decimal separator, there is no thousands separator, and the
+//! scientific notation (`1e-3`, `2.5E+4`) is accepted for all the float fields.
+//!
+//! A value written with a locale-dependent number format (`1,5`, `1 000`, `1'000.5`) in a numeric
+//! field is reported as an ambiguous number, with its path in the file and its line, instead of
+//! an `invalid type: string` error. The same strings are accepted in the text fields. `.nan` is
+//! rejected everywhere.
+//!
+//! Missing files are errors: unlike the previous loader, no default file is created.
+//!
+//! The [`ConfigSourceMap`] keeps the line and column of the fields of a file, to report the
+//! errors found after the parsing (e.g. by the checks) at their location.
+//!
+//! The root mapping of a file and the items of its `robots` and `computation_units` lists
+//! ([`INCLUDE_LISTS`]) can include other files with an `include` field (a path or a list of
+//! paths, relative to the including file). The included files are deep-merged in order, then the
+//! other fields of the mapping are deep-merged on top of them, so a fleet can share a base robot
+//! definition. An `include` field anywhere else is kept as is (e.g. in the `config` of a plugin):
+//!
+//! ```yaml
+//! robots:
+//!   - include: base_robot.yaml
+//!     name: robot1
+//!     navigator:
+//!       target_speed: 0.8 # Only this field of the base navigator changes
+//! ```
+//!
+//! Overlay files can be deep-merged on top of a whole file in the same way, see
+//! [`load_config_file_with_overrides`]. Deep merge: the mappings are merged field by field, the
+//! other values (lists included) are replaced. A mapping with another `type` than the one it
+//! is merged on (another variant of an enum) replaces it. The fields coming from other files are
+//! located at their including field in the [`ConfigSourceMap`].
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{
+    Serialize,
+    de::{
+        self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+        value::MapAccessDeserializer,
+    },
+    ser,
+};
+use serde_yaml::{Mapping, Value, value::TaggedValue};
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+use crate::errors::{SimbaError, SimbaErrorTypes, SimbaResult};
+
+/// How strictly the configuration files are loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfigLoadMode {
+    /// Unknown fields and unreadable versions are errors. Another version gives a warning.
+    #[default]
+    Strict,
+    /// Unknown fields are ignored and unreadable versions are accepted, with a warning for each.
+    /// Eases the migration of configurations between versions.
+    Permissive,
+}
+
+/// Configuration loaded from a file by [`load_config_file_with_mode`].
+#[derive(Debug, Clone)]
+pub struct LoadedConfig<T> {
+    /// Parsed configuration.
+    pub config: T,
+    /// Locations of the fields in the file.
+    pub source_map: ConfigSourceMap,
+    /// Issues ignored in [`ConfigLoadMode::Permissive`], with their location.
+    pub warnings: Vec<String>,
+}
+
+/// Read and parse the configuration file at `path`.
+///
+/// `description` names the kind of file in the error messages (e.g. "config", "map").
+pub fn load_config_file<T: DeserializeOwned>(path: &Path, description: &str) -> SimbaResult<T> {
+    load_config_file_with_mode(path, description, ConfigLoadMode::Strict)
+        .map(|loaded| loaded.config)
+}
+
+/// Read and parse the configuration file at `path` in the given `mode`, with the locations of
+/// its fields.
+pub fn load_config_file_with_mode<T: DeserializeOwned>(
+    path: &Path,
+    description: &str,
+    mode: ConfigLoadMode,
+) -> SimbaResult<LoadedConfig<T>> {
+    load_config_file_with_overrides(path, &[], description, mode)
+}
+
+/// Read and parse the configuration file at `path` in the given `mode`, with the `overrides`
+/// files deep-merged on top of it, in order.
+///
+/// The `include` fields of all the files are resolved, see the [module documentation](self).
+pub fn load_config_file_with_overrides<T: DeserializeOwned>(
+    path: &Path,
+    overrides: &[PathBuf],
+    description: &str,
+    mode: ConfigLoadMode,
+) -> SimbaResult<LoadedConfig<T>> {
+    let content = read_config_file(path, description)?;
+    let mut value = resolve_file(path, &content, description, &mut Vec::new())?;
+    for override_path in overrides {
+        let override_content = read_config_file(override_path, description)?;
+        let overlay = resolve_file(
+            override_path,
+            &override_content,
+            description,
+            &mut Vec::new(),
+        )?;
+        deep_merge(&mut value, overlay);
+    }
+    let (config, ignored) = parse_value_with_mode(value, &content, mode).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "Error while parsing {description} file '{}': {}",
+                path.display(),
+                e
+            ),
+        )
+    })?;
+    let source_map = ConfigSourceMap::from_source(path, &content);
+    let warnings = ignored
+        .iter()
+        .map(|(path, message)| source_map.annotate(path, message))
+        .collect();
+    Ok(LoadedConfig {
+        config,
+        source_map,
+        warnings,
+    })
+}
+
+/// Parse a YAML configuration, resolving the merge keys (`<<`) and checking the number formats.
+pub fn parse_config<T: DeserializeOwned>(content: &str) -> Result<T, String> {
+    parse_config_with_mode(content, ConfigLoadMode::Strict).map(|(config, _)| config)
+}
+
+/// Parse a YAML configuration in the given `mode`.
+///
+/// Returns the configuration and the issues ignored in [`ConfigLoadMode::Permissive`], as the
+/// path of the field and the message.
+pub fn parse_config_with_mode<T: DeserializeOwned>(
+    content: &str,
+    mode: ConfigLoadMode,
+) -> Result<(T, Vec<(String, String)>), String> {
+    let mut value: Value = serde_yaml::from_str(content).map_err(|e| e.to_string())?;
+    value
+        .apply_merge()
+        .map_err(|e| format!("error while merging YAML tags: {e}"))?;
+    parse_value_with_mode(value, content, mode)
+}
+
+/// Deserialize the YAML `value` parsed from `content` in the given `mode`, see
+/// [`parse_config_with_mode`].
+fn parse_value_with_mode<T: DeserializeOwned>(
+    mut value: Value,
+    content: &str,
+    mode: ConfigLoadMode,
+) -> Result<(T, Vec<(String, String)>), String> {
+    check_nan(&value, "")?;
+    let mut ignored = Vec::new();
+    loop {
+        let error = match serde_yaml::from_value(value.clone()) {
+            Ok(config) => return Ok((config, ignored)),
+            Err(e) => e.to_string(),
+        };
+        if mode == ConfigLoadMode::Permissive
+            && let Some((path, field)) = unknown_field::<T>(&value)
+            && let Some(field_path) = remove_field(&mut value, &path, &field)
+        {
+            ignored.push((
+                join_path(&field_path),
+                format!("unknown field `{field}` ignored"),
+            ));
+            continue;
+        }
+        // The errors from a `Value` have no location: parsing the text again gives it, when the
+        // merge keys and the ignored fields do not change the error.
+        let error = match serde_yaml::from_str::<T>(content) {
+            Err(located)
+                if located.location().is_some() && located.to_string().contains(&error) =>
+            {
+                located.to_string()
+            }
+            _ => error,
+        };
+        return Err(locale_number_error(&error, content).unwrap_or(error));
+    }
+}
+
+fn read_config_file(path: &Path, description: &str) -> SimbaResult<String> {
+    fs::read_to_string(path).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "Impossible to read {description} file '{}': {}",
+                path.display(),
+                e
+            ),
+        )
+    })
+}
+
+/// Parse the YAML `content` of the file at `path`, resolving its merge keys and its includes.
+///
+/// `stack` holds the files being included, to detect the include cycles.
+fn resolve_file(
+    path: &Path,
+    content: &str,
+    description: &str,
+    stack: &mut Vec<PathBuf>,
+) -> SimbaResult<Value> {
+    let parse_error = |e: String| {
+        SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "Error while parsing {description} file '{}': {}",
+                path.display(),
+                e
+            ),
+        )
+    };
+    let mut value: Value = serde_yaml::from_str(content).map_err(|e| parse_error(e.to_string()))?;
+    value
+        .apply_merge()
+        .map_err(|e| parse_error(format!("error while merging YAML tags: {e}")))?;
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        let cycle: Vec<String> = stack
+            .iter()
+            .chain([&canonical])
+            .map(|file| file.display().to_string())
+            .collect();
+        return Err(SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!("Include cycle: {}", cycle.join(" -> ")),
+        ));
+    }
+    stack.push(canonical);
+    let directory = path.parent().unwrap_or(Path::new("."));
+    resolve_includes(&mut value, directory, description, stack)?;
+    stack.pop();
+    Ok(value)
+}
+
+/// Top-level lists whose items can include files, in addition to the root of the files.
+pub const INCLUDE_LISTS: [&str; 2] = ["robots", "computation_units"];
+
+/// Replace the `include` fields of the root of the file `value` and of the items of the
+/// [`INCLUDE_LISTS`] by the content of the files, relative to `directory`.
+fn resolve_includes(
+    value: &mut Value,
+    directory: &Path,
+    description: &str,
+    stack: &mut Vec<PathBuf>,
+) -> SimbaResult<()> {
+    resolve_include(value, directory, description, stack)?;
+    if let Value::Mapping(map) = value {
+        for list in INCLUDE_LISTS {
+            if let Some(Value::Sequence(items)) = map.get_mut(list) {
+                for item in items {
+                    resolve_include(item, directory, description, stack)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replace the `include` field of the mapping `value`, if any, by the content of the files,
+/// relative to `directory`. The fields of the mapping are not searched.
+fn resolve_include(
+    value: &mut Value,
+    directory: &Path,
+    description: &str,
+    stack: &mut Vec<PathBuf>,
+) -> SimbaResult<()> {
+    match value {
+        Value::Mapping(map) => {
+            let Some(include) = map.remove("include") else {
+                return Ok(());
+            };
+            let invalid = || {
+                SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    format!(
+                        "Invalid include in {description} file '{}': expected a path or a list of paths",
+                        stack
+                            .last()
+                            .map(|file| file.display().to_string())
+                            .unwrap_or_default()
+                    ),
+                )
+            };
+            let paths = match include {
+                Value::String(path) => vec![path],
+                Value::Sequence(list) => list
+                    .into_iter()
+                    .map(|path| match path {
+                        Value::String(path) => Ok(path),
+                        _ => Err(invalid()),
+                    })
+                    .collect::<SimbaResult<_>>()?,
+                _ => return Err(invalid()),
+            };
+            let mut merged = Value::Mapping(Mapping::new());
+            for path in paths {
+                let path = directory.join(path);
+                let content = read_config_file(&path, description)?;
+                let included = resolve_file(&path, &content, description, stack)?;
+                if !included.is_mapping() {
+                    return Err(SimbaError::new(
+                        SimbaErrorTypes::ConfigError,
+                        format!(
+                            "Included {description} file '{}' should contain a mapping",
+                            path.display()
+                        ),
+                    ));
+                }
+                deep_merge(&mut merged, included);
+            }
+            deep_merge(&mut merged, Value::Mapping(std::mem::take(map)));
+            *value = merged;
+            Ok(())
+        }
+        Value::Tagged(tagged) => resolve_include(&mut tagged.value, directory, description, stack),
+        _ => Ok(()),
+    }
+}
+
+/// Merge `overlay` on top of `base`: the mappings are merged field by field, the other values
+/// are replaced, as well as the mappings of another `type`.
+fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay))
+            if base.get("type").is_none_or(|kind| {
+                overlay
+                    .get("type")
+                    .is_none_or(|overlay_kind| overlay_kind == kind)
+            }) =>
+        {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => deep_merge(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Segment of the path of a field in a configuration.
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Dotted display of `path`, e.g. `robots[0].navigator`.
+fn join_path(path: &[PathSegment]) -> String {
+    let mut joined = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) if joined.is_empty() => joined.push_str(key),
+            PathSegment::Key(key) => {
+                joined.push('.');
+                joined.push_str(key);
+            }
+            PathSegment::Index(i) => joined.push_str(&format!("[{i}]")),
+        }
+    }
+    joined
+}
+
+/// Path of the map containing the first unknown field of `value` for `T`, and the name of the
+/// field.
+fn unknown_field<T: DeserializeOwned>(value: &Value) -> Option<(Vec<PathSegment>, String)> {
+    match <T as serde::Deserialize>::deserialize(ValueDeserializer::at(value, Vec::new())) {
+        Err(FieldError {
+            unknown_field: Some(field),
+            path: Some(path),
+            ..
+        }) => Some((path, field)),
+        _ => None,
+    }
+}
+
+/// Value at `path` in `value`, through the YAML tags.
+fn value_at<'a>(value: &'a mut Value, path: &[PathSegment]) -> Option<&'a mut Value> {
+    let mut value = untag(value);
+    for segment in path {
+        value = untag(match segment {
+            PathSegment::Key(key) => value.get_mut(key.as_str())?,
+            PathSegment::Index(i) => value.get_mut(*i)?,
+        });
+    }
+    Some(value)
+}
+
+/// Content of the tagged `value`, the value itself otherwise.
+fn untag(value: &mut Value) -> &mut Value {
+    match value {
+        Value::Tagged(tagged) => untag(&mut tagged.value),
+        value => value,
+    }
+}
+
+/// Paths of the maps with a `field` in `value` (at `path`) and below it.
+fn maps_with_field(
+    value: &Value,
+    path: Vec<PathSegment>,
+    field: &str,
+    found: &mut Vec<Vec<PathSegment>>,
+) {
+    match value {
+        Value::Mapping(map) => {
+            if map.contains_key(field) {
+                found.push(path.clone());
+            }
+            for (key, child) in map {
+                if let Some(key) = key.as_str() {
+                    let mut child_path = path.clone();
+                    child_path.push(PathSegment::Key(key.to_string()));
+                    maps_with_field(child, child_path, field, found);
+                }
+            }
+        }
+        Value::Sequence(list) => {
+            for (i, child) in list.iter().enumerate() {
+                let mut child_path = path.clone();
+                child_path.push(PathSegment::Index(i));
+                maps_with_field(child, child_path, field, found);
+            }
+        }
+        Value::Tagged(tagged) => maps_with_field(&tagged.value, path, field, found),
+        _ => {}
+    }
+}
+
+/// Remove the unknown `field` of the map at `path`. Returns the path of the removed field.
+///
+/// The path of the errors stops at the internally tagged enums (their content is buffered before
+/// being deserialized): when the map at `path` has no `field`, the field is removed only if a
+/// single map below `path` has one.
+fn remove_field(value: &mut Value, path: &[PathSegment], field: &str) -> Option<Vec<PathSegment>> {
+    let target = value_at(value, path)?;
+    let mut found = Vec::new();
+    maps_with_field(target, path.to_vec(), field, &mut found);
+    let map_path = if found.first().is_some_and(|first| first == path) {
+        found.swap_remove(0)
+    } else if found.len() == 1 {
+        found.pop()?
+    } else {
+        return None;
+    };
+    value_at(value, &map_path)?
+        .as_mapping_mut()?
+        .remove(field)?;
+    let mut field_path = map_path;
+    field_path.push(PathSegment::Key(field.to_string()));
+    Some(field_path)
+}
+
+/// Deserialization error of [`ValueDeserializer`], keeping the unknown fields apart from the
+/// other errors.
+#[derive(Debug)]
+struct FieldError {
+    message: String,
+    unknown_field: Option<String>,
+    /// Path of the value whose deserialization failed, set by the innermost value.
+    path: Option<Vec<PathSegment>>,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+impl de::Error for FieldError {
+    fn custom<M: fmt::Display>(message: M) -> Self {
+        Self {
+            message: message.to_string(),
+            unknown_field: None,
+            path: None,
+        }
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Self {
+            message: format!("unknown field `{field}`, expected one of {expected:?}"),
+            unknown_field: Some(field.to_string()),
+            path: None,
+        }
+    }
+}
+
+/// Deserializer of a YAML [`Value`] giving the path of its errors, to find the unknown fields
+/// without parsing the error messages.
+///
+/// The map keys have no path: the unknown field errors raised on a key are located at the map.
+struct ValueDeserializer<'de> {
+    value: &'de Value,
+    path: Option<Vec<PathSegment>>,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    fn at(value: &'de Value, path: Vec<PathSegment>) -> Self {
+        Self {
+            value,
+            path: Some(path),
+        }
+    }
+
+    fn key(value: &'de Value) -> Self {
+        Self { value, path: None }
+    }
+
+    fn child(&self, value: &'de Value, segment: PathSegment) -> Self {
+        Self {
+            value,
+            path: self.path.as_ref().map(|path| {
+                let mut path = path.clone();
+                path.push(segment);
+                path
+            }),
+        }
+    }
+
+    /// Entries of the `map`, as a [`MapAccess`].
+    fn entries(
+        &self,
+        map: &'de Mapping,
+    ) -> Entries<'de, impl Iterator<Item = (Self, ValueDeserializer<'de>)>> {
+        Entries::new(map.iter().map(|(key, value)| {
+            let name = match key {
+                Value::String(key) => key.clone(),
+                key => serde_yaml::to_string(key)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            };
+            (Self::key(key), self.child(value, PathSegment::Key(name)))
+        }))
+    }
+
+    /// Single entry of a tagged value, as a [`MapAccess`].
+    fn tagged_entry(
+        &self,
+        tagged: &'de TaggedValue,
+    ) -> Entries<'de, impl Iterator<Item = (String, ValueDeserializer<'de>)>> {
+        let tag = tagged.tag.to_string().trim_start_matches('!').to_string();
+        let value = self.child(&tagged.value, PathSegment::Key(tag.clone()));
+        Entries::new(std::iter::once((tag, value)))
+    }
+}
+
+/// Set the `path` of the error of `result`, when no inner value did.
+fn locate_error<T>(
+    path: &Option<Vec<PathSegment>>,
+    result: Result<T, FieldError>,
+) -> Result<T, FieldError> {
+    result.map_err(|mut e| {
+        if e.path.is_none() {
+            e.path = path.clone();
+        }
+        e
+    })
+}
+
+impl<'de> IntoDeserializer<'de, FieldError> for ValueDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// [`MapAccess`] over key-value pairs, locating the errors of the values.
+struct Entries<'de, I> {
+    entries: I,
+    value: Option<ValueDeserializer<'de>>,
+}
+
+impl<'de, I> Entries<'de, I> {
+    fn new(entries: I) -> Self {
+        Self {
+            entries,
+            value: None,
+        }
+    }
+}
+
+impl<'de, K, I> MapAccess<'de> for Entries<'de, I>
+where
+    K: IntoDeserializer<'de, FieldError>,
+    I: Iterator<Item = (K, ValueDeserializer<'de>)>,
+{
+    type Error = FieldError;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, FieldError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, FieldError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| <FieldError as de::Error>::custom("value without key"))?;
+        let path = value.path.clone();
+        locate_error(&path, seed.deserialize(value))
+    }
+}
+
+/// [`SeqAccess`] over values, locating the errors of the values.
+struct Elements<I>(I);
+
+impl<'de, I: Iterator<Item = ValueDeserializer<'de>>> SeqAccess<'de> for Elements<I> {
+    type Error = FieldError;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, FieldError> {
+        let Some(value) = self.0.next() else {
+            return Ok(None);
+        };
+        let path = value.path.clone();
+        locate_error(&path, seed.deserialize(value)).map(Some)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = FieldError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        let result = match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Number(n) => {
+                if let Some(n) = n.as_u64() {
+                    visitor.visit_u64(n)
+                } else if let Some(n) = n.as_i64() {
+                    visitor.visit_i64(n)
+                } else {
+                    visitor.visit_f64(n.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            Value::Sequence(list) => visitor.visit_seq(Elements(
+                list.iter()
+                    .enumerate()
+                    .map(|(i, item)| self.child(item, PathSegment::Index(i))),
+            )),
+            Value::Mapping(map) => visitor.visit_map(self.entries(map)),
+            Value::Tagged(tagged) => visitor.visit_map(self.tagged_entry(tagged)),
+        };
+        locate_error(&self.path, result)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        let result = match self.value {
+            Value::String(s) => visitor.visit_enum(s.as_str().into_deserializer()),
+            Value::Mapping(map) => {
+                visitor.visit_enum(MapAccessDeserializer::new(self.entries(map)))
+            }
+            Value::Tagged(tagged) => {
+                visitor.visit_enum(MapAccessDeserializer::new(self.tagged_entry(tagged)))
+            }
+            _ => return self.deserialize_any(visitor),
+        };
+        locate_error(&self.path, result)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Reject the NaN numbers of `value`, at the dotted `path` of the file.
+fn check_nan(value: &Value, path: &str) -> Result<(), String> {
+    match value {
+        Value::Number(n) if n.as_f64().is_some_and(f64::is_nan) => Err(format!(
+            "{}: NaN is not a valid configuration value",
+            display_path(path)
+        )),
+        Value::Sequence(list) => list
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, item)| check_nan(item, &format!("{path}[{i}]"))),
+        Value::Mapping(map) => map.iter().try_for_each(|(key, item)| {
+            let key = match key {
+                Value::String(key) => key.clone(),
+                key => serde_yaml::to_string(key)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            };
+            let path = if path.is_empty() {
+                key
+            } else {
+                format!("{path}.{key}")
+            };
+            check_nan(item, &path)
+        }),
+        Value::Tagged(tagged) => check_nan(&tagged.value, path),
+        _ => Ok(()),
+    }
+}
+
+/// Rewrite the deserialization `error` of `content` when a string with a locale-dependent number
+/// format was given where a number is expected.
+///
+/// Only the numeric fields raise an `invalid type: string` error for these strings, so the text
+/// fields keep them.
+fn locale_number_error(error: &str, content: &str) -> Option<String> {
+    const MARKER: &str = "invalid type: string \"";
+    let start = error.find(MARKER)?;
+    let rest = &error[start + MARKER.len()..];
+    let s = &rest[..rest.find('"')?];
+    if !is_locale_number(s) {
+        return None;
+    }
+    let path = error[..start].trim_end().trim_end_matches(':');
+    Some(format!(
+        "{}: ambiguous number '{s}'{}: use '.' as decimal separator and no thousands separator \
+         (e.g. 1500.5 or 1.5005e3)",
+        display_path(path),
+        locate(content, s)
+            .map(|(line, column)| format!(" at line {line} column {column}"))
+            .unwrap_or_default(),
+    ))
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "root" } else { path }
+}
+
+/// Whether `s` is a number written with a locale-dependent format: digits with `,`, spaces or
+/// `'` as separators, with an optional sign and exponent.
+///
+/// Strings with only `.` separators (e.g. versions `1.2.3`) are not considered as numbers.
+fn is_locale_number(s: &str) -> bool {
+    let s = s.trim();
+    let s = s.strip_prefix(['+', '-']).unwrap_or(s);
+    let mantissa = match s.find(['e', 'E']) {
+        Some(i) => {
+            let exponent = &s[i + 1..];
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if exponent.is_empty() || !exponent.chars().all(|c| c.is_ascii_digit()) {
+                return false;
+            }
+            &s[..i]
+        }
+        None => s,
+    };
+    let is_separator = |c: char| matches!(c, ',' | '.' | ' ' | '\'' | '\u{a0}' | '\u{202f}');
+    mantissa.starts_with(|c: char| c.is_ascii_digit())
+        && mantissa.ends_with(|c: char| c.is_ascii_digit())
+        && mantissa
+            .chars()
+            .all(|c| c.is_ascii_digit() || is_separator(c))
+        && mantissa.chars().any(|c| is_separator(c) && c != '.')
+}
+
+/// Line and column (starting at 1) of `scalar` in `content`, when it appears only once.
+fn locate(content: &str, scalar: &str) -> Option<(usize, usize)> {
+    let mut found = None;
+    for (line, text) in content.lines().enumerate() {
+        for (column, _) in text.match_indices(scalar) {
+            if found.is_some() {
+                return None;
+            }
+            found = Some((line + 1, text[..column].chars().count() + 1));
+        }
+    }
+    found
+}
+
+/// Location of a field in a configuration file, displayed as `file:line:column`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigLocation {
+    /// Configuration file.
+    pub file: PathBuf,
+    /// Line of the field, starting at 1.
+    pub line: usize,
+    /// Column of the field, starting at 1.
+    pub column: usize,
+}
+
+impl fmt::Display for ConfigLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+/// Line and column of the fields of a configuration file, by dotted path
+/// (`robots[0].physics.model`).
+///
+/// The locations are the marks of the events of the YAML parser: a field is located at its key,
+/// a list item at its value. The fields added by merge keys (`<<`) are located at their parent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigSourceMap {
+    file: Option<PathBuf>,
+    locations: BTreeMap<String, (usize, usize)>,
+}
+
+/// Collection being read by [`ConfigSourceMap::from_source`].
+enum SourceFrame {
+    /// Mapping at `path`, with the path of the field whose value is expected, if any.
+    Mapping { path: String, field: Option<String> },
+    /// Sequence at `path`, with the index of the next item.
+    Sequence { path: String, next_index: usize },
+}
+
+/// Receiver of the parser events, building the locations of a [`ConfigSourceMap`].
+#[derive(Default)]
+struct SourceMapBuilder {
+    locations: BTreeMap<String, (usize, usize)>,
+    stack: Vec<SourceFrame>,
+}
+
+impl SourceMapBuilder {
+    /// Path of the node starting at `location`. A mapping key (`key` is its text, `?` for a
+    /// complex key) is stored as a field, and returns the path of this field.
+    fn start_node(&mut self, key: Option<&str>, location: (usize, usize)) -> String {
+        match self.stack.last_mut() {
+            None => String::new(),
+            Some(SourceFrame::Sequence { path, next_index }) => {
+                let path = format!("{path}[{next_index}]");
+                *next_index += 1;
+                self.locations.insert(path.clone(), location);
+                path
+            }
+            Some(SourceFrame::Mapping { path, field }) => match field.take() {
+                Some(field) => field,
+                None => {
+                    let key = key.unwrap_or("?");
+                    let key_path = if path.is_empty() {
+                        key.to_string()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    self.locations.insert(key_path.clone(), location);
+                    *field = Some(key_path.clone());
+                    key_path
+                }
+            },
+        }
+    }
+}
+
+impl MarkedEventReceiver for SourceMapBuilder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let location = (mark.line(), mark.col() + 1);
+        match event {
+            Event::Scalar(value, ..) => {
+                self.start_node(Some(&*value), location);
+            }
+            Event::Alias(..) => {
+                self.start_node(None, location);
+            }
+            Event::MappingStart(..) => {
+                let path = self.start_node(None, location);
+                self.stack.push(SourceFrame::Mapping { path, field: None });
+            }
+            Event::SequenceStart(..) => {
+                let path = self.start_node(None, location);
+                self.stack.push(SourceFrame::Sequence {
+                    path,
+                    next_index: 0,
+                });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ConfigSourceMap {
+    /// Index the fields of the YAML `content` of `file`.
+    ///
+    /// Only the first document is indexed. A content which cannot be parsed keeps the locations
+    /// read before the error.
+    pub fn from_source(file: &Path, content: &str) -> Self {
+        let mut builder = SourceMapBuilder::default();
+        let _ = Parser::new_from_str(content).load(&mut builder, false);
+        Self {
+            file: Some(file.to_path_buf()),
+            locations: builder.locations,
+        }
+    }
+
+    /// Location of the field at the dotted `path`. A field without location (e.g. given by a
+    /// default value) is located at its closest parent in the file.
+    pub fn location(&self, path: &str) -> Option<ConfigLocation> {
+        let file = self.file.as_ref()?;
+        let mut path = path;
+        loop {
+            if let Some((line, column)) = self.locations.get(path) {
+                return Some(ConfigLocation {
+                    file: file.clone(),
+                    line: *line,
+                    column: *column,
+                });
+            }
+            path = &path[..path.rfind(['.', '['])?];
+        }
+    }
+
+    /// Prefix the `message` about the field at `path` with its location, when known.
+    pub fn annotate(&self, path: &str, message: impl fmt::Display) -> String {
+        let path_display = display_path(path);
+        match self.location(path) {
+            Some(location) => format!("{location}: {path_display}: {message}"),
+            None => match &self.file {
+                Some(file) => format!("{}: {path_display}: {message}", file.display()),
+                None => format!("{path_display}: {message}"),
+            },
+        }
+    }
+}
+
+/// Dotted path of `field` in `root`, found by its address: `field` has to be a reference into
+/// `root` (e.g. the configuration of a module given to a plugin).
+///
+/// `root` is walked with its [`Serialize`] implementation, which gives the address of each
+/// field.
+pub fn field_path<R: Serialize + ?Sized, F: ?Sized>(root: &R, field: &F) -> Option<String> {
+    let search = FieldSearch {
+        target: field as *const F as *const (),
+        type_name: std::any::type_name::<F>(),
+        path: String::new(),
+    };
+    match search.visit(root, String::new()) {
+        Err(FieldSearchEnd::Found(path)) => Some(path),
+        _ => None,
+    }
+}
+
+/// End of the walk of [`field_path`], given as the error of the serialization.
+#[derive(Debug)]
+enum FieldSearchEnd {
+    Found(String),
+    Failed(String),
+}
+
+impl fmt::Display for FieldSearchEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Found(path) => write!(f, "field found at '{path}'"),
+            Self::Failed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for FieldSearchEnd {}
+
+impl ser::Error for FieldSearchEnd {
+    fn custom<M: fmt::Display>(message: M) -> Self {
+        Self::Failed(message.to_string())
+    }
+}
+
+/// Serializer looking for the value at address `target` (of type `type_name`) under `path`.
+#[derive(Clone)]
+struct FieldSearch {
+    target: *const (),
+    type_name: &'static str,
+    path: String,
+}
+
+impl FieldSearch {
+    /// Stop at `value` if it is the field searched, else walk it.
+    fn visit<T: Serialize + ?Sized>(&self, value: &T, path: String) -> Result<(), FieldSearchEnd> {
+        if std::ptr::eq(value as *const T as *const (), self.target)
+            && std::any::type_name::<T>() == self.type_name
+        {
+            return Err(FieldSearchEnd::Found(path));
+        }
+        value.serialize(FieldSearch {
+            path,
+            ..self.clone()
+        })
+    }
+
+    fn field_path(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{key}", self.path)
+        }
+    }
+
+    fn compound(self, variant: Option<&str>) -> FieldSearchCompound {
+        let path = match variant {
+            Some(variant) => self.field_path(variant),
+            None => self.path.clone(),
+        };
+        FieldSearchCompound {
+            search: FieldSearch { path, ..self },
+            index: 0,
+            key: None,
+        }
+    }
+}
+
+/// Collection walked by [`FieldSearch`].
+struct FieldSearchCompound {
+    search: FieldSearch,
+    index: usize,
+    key: Option<String>,
+}
+
+impl FieldSearchCompound {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), FieldSearchEnd> {
+        let path = format!("{}[{}]", self.search.path, self.index);
+        self.index += 1;
+        self.search.visit(value, path)
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), FieldSearchEnd> {
+        self.search.visit(value, self.search.field_path(key))
+    }
+}
+
+macro_rules! field_search_leaves {
+    ($($method:ident: $type:ty),*) => {
+        $(fn $method(self, _value: $type) -> Result<(), FieldSearchEnd> {
+            Ok(())
+        })*
+    };
+}
+
+impl ser::Serializer for FieldSearch {
+    type Ok = ();
+    type Error = FieldSearchEnd;
+    type SerializeSeq = FieldSearchCompound;
+    type SerializeTuple = FieldSearchCompound;
+    type SerializeTupleStruct = FieldSearchCompound;
+    type SerializeTupleVariant = FieldSearchCompound;
+    type SerializeMap = FieldSearchCompound;
+    type SerializeStruct = FieldSearchCompound;
+    type SerializeStructVariant = FieldSearchCompound;
+
+    field_search_leaves!(
+        serialize_bool: bool, serialize_i8: i8, serialize_i16: i16, serialize_i32: i32,
+        serialize_i64: i64, serialize_u8: u8, serialize_u16: u16, serialize_u32: u32,
+        serialize_u64: u64, serialize_f32: f32, serialize_f64: f64, serialize_char: char,
+        serialize_str: &str, serialize_bytes: &[u8], serialize_unit_struct: &'static str
+    );
+
+    fn serialize_none(self) -> Result<(), FieldSearchEnd> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), FieldSearchEnd> {
+        self.visit(value, self.path.clone())
+    }
+
+    fn serialize_unit(self) -> Result<(), FieldSearchEnd> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), FieldSearchEnd> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), FieldSearchEnd> {
+        self.visit(value, self.path.clone())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), FieldSearchEnd> {
+        self.visit(value, self.field_path(variant))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<FieldSearchCompound, FieldSearchEnd> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<FieldSearchCompound, FieldSearchEnd> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<FieldSearchCompound, FieldSearchEnd> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<FieldSearchCompound, FieldSearchEnd> {
+        Ok(self.compound(Some(variant)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<FieldSearchCompound, FieldSearchEnd> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<FieldSearchCompound, FieldSearchEnd> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<FieldSearchCompound, FieldSearchEnd> {
+        Ok(self.compound(Some(variant)))
+    }
+}
+
+impl ser::SerializeSeq for FieldSearchCompound {
+    type Ok = ();
+    type Error = FieldSearchEnd;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for FieldSearchCompound {
+    type Ok = ();
+    type Error = FieldSearchEnd;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for FieldSearchCompound {
+    type Ok = ();
+    type Error = FieldSearchEnd;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for FieldSearchCompound {
+    type Ok = ();
+    type Error = FieldSearchEnd;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for FieldSearchCompound {
+    type Ok = ();
+    type Error = FieldSearchEnd;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.key = Some(match serde_json::to_value(key) {
+            Ok(serde_json::Value::String(key)) => key,
+            Ok(key) => key.to_string(),
+            Err(e) => return Err(FieldSearchEnd::Failed(e.to_string())),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self.key.take().unwrap_or_default();
+        self.field(&key, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for FieldSearchCompound {
+    type Ok = ();
+    type Error = FieldSearchEnd;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for FieldSearchCompound {
+    type Ok = ();
+    type Error = FieldSearchEnd;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Sample {
+        value: f32,
+        #[serde(default)]
+        values: Vec<f32>,
+        #[serde(default)]
+        name: String,
+    }
+
+    #[test]
+    fn float_formats() {
+        let sample: Sample =
+            parse_config("value: 1.5e3\nvalues: [1e-3, 2.5E+2, -4, .5]\nname: v1.2.3").unwrap();
+        assert_eq!(sample.value, 1500.);
+        assert_eq!(sample.values, vec![1e-3, 250., -4., 0.5]);
+        assert_eq!(sample.name, "v1.2.3");
+
+        let error = parse_config::<Sample>("value: 2.\nvalues:\n  - 1\n  - 1,5\n").unwrap_err();
+        assert!(error.starts_with("values[1]: ambiguous number '1,5' at line 4 column 5"));
+        for locale in ["1 000", "1'000.5", "-1.000,5e3"] {
+            assert!(is_locale_number(locale), "{locale}");
+        }
+        for other in ["1.2.3", "1,5 km", "a,b", ",5"] {
+            assert!(!is_locale_number(other), "{other}");
+        }
+        assert!(parse_config::<Sample>("value: .nan").is_err());
+
+        // Only the numeric fields are checked
+        let sample: Sample = parse_config("value: 1.\nname: '1,5'\n").unwrap();
+        assert_eq!(sample.name, "1,5");
+    }
+
+    #[test]
+    fn permissive_mode() {
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Root {
+            samples: Vec<Strict>,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Strict {
+            value: f32,
+        }
+
+        let content = "samples:\n  - value: 1.0\n  - value: 2.0\n    old_field: true\n";
+        let error = parse_config::<Root>(content).unwrap_err();
+        assert!(error.contains("unknown field `old_field`"), "{error}");
+
+        let (root, ignored) =
+            parse_config_with_mode::<Root>(content, ConfigLoadMode::Permissive).unwrap();
+        assert_eq!(root.samples[1].value, 2.);
+        assert_eq!(
+            ignored,
+            vec![(
+                "samples[1].old_field".to_string(),
+                "unknown field `old_field` ignored".to_string()
+            )]
+        );
+
+        // In the buffered content of a tagged enum, only an unambiguous field is removed
+        #[derive(Debug, serde::Deserialize)]
+        struct Tagged {
+            kind: Kind,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(tag = "type")]
+        enum Kind {
+            A { inner: Strict, other: Loose },
+        }
+        #[derive(Debug, serde::Deserialize)]
+        struct Loose {
+            #[serde(default)]
+            old_field: bool,
+        }
+        let content =
+            "kind:\n  type: A\n  inner:\n    value: 1.0\n    old_field: true\n  other: {}\n";
+        let (tagged, ignored) =
+            parse_config_with_mode::<Tagged>(content, ConfigLoadMode::Permissive).unwrap();
+        assert_eq!(ignored[0].0, "kind.inner.old_field");
+        let Kind::A { inner, other } = tagged.kind;
+        assert_eq!(inner.value, 1.);
+        assert!(!other.old_field);
+        let content = "kind:\n  type: A\n  inner:\n    value: 1.0\n    old_field: true\n  other:\n    old_field: true\n";
+        assert!(parse_config_with_mode::<Tagged>(content, ConfigLoadMode::Permissive).is_err());
+    }
+
+    #[test]
+    fn includes_and_overrides() {
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Root {
+            robots: Vec<Robot>,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Robot {
+            name: String,
+            model: serde_yaml::Value,
+            speed: f32,
+        }
+
+        let directory = std::env::temp_dir().join("simba_config_includes");
+        fs::create_dir_all(directory.join("robots")).unwrap();
+        fs::write(
+            directory.join("robots/base.yaml"),
+            "name: base\nmodel: {type: Unicycle, wheel_distance: 0.5}\nspeed: 1.0\n",
+        )
+        .unwrap();
+        fs::write(
+            directory.join("config.yaml"),
+            "robots:\n  - include: robots/base.yaml\n    name: robot1\n  - include: [robots/base.yaml]\n    name: robot2\n    model: {type: Holonomic, include: plugin.yaml}\n",
+        )
+        .unwrap();
+        fs::write(
+            directory.join("override.yaml"),
+            "robots:\n  - name: robot3\n    model: {type: Holonomic}\n    speed: 2.0\n",
+        )
+        .unwrap();
+
+        let root = load_config_file::<Root>(&directory.join("config.yaml"), "config").unwrap();
+        assert_eq!(root.robots[0].name, "robot1");
+        assert_eq!(root.robots[0].speed, 1.);
+        assert_eq!(root.robots[0].model["wheel_distance"], 0.5);
+        // Another variant replaces the included one
+        assert!(root.robots[1].model.get("wheel_distance").is_none());
+        // Not a documented include position: kept as is
+        assert_eq!(root.robots[1].model["include"], "plugin.yaml");
+
+        // Lists are replaced by the overlay
+        let root = load_config_file_with_overrides::<Root>(
+            &directory.join("config.yaml"),
+            &[directory.join("override.yaml")],
+            "config",
+            ConfigLoadMode::Strict,
+        )
+        .unwrap()
+        .config;
+        assert_eq!(root.robots.len(), 1);
+        assert_eq!(root.robots[0].speed, 2.);
+
+        fs::write(
+            directory.join("robots/base.yaml"),
+            "include: ../config.yaml\n",
+        )
+        .unwrap();
+        let error = load_config_file::<Root>(&directory.join("config.yaml"), "config").unwrap_err();
+        assert!(error.detailed_error().contains("Include cycle"));
+    }
+
+    #[test]
+    fn field_path_by_address() {
+        #[derive(serde::Serialize)]
+        struct Module {
+            config: serde_json::Value,
+        }
+        #[derive(serde::Serialize)]
+        struct Root {
+            modules: Vec<Module>,
+            named: BTreeMap<String, Option<Module>>,
+        }
+        // Same value everywhere: only the address tells the modules apart
+        let config = serde_json::json!({"gain": 1.});
+        let module = || Module {
+            config: config.clone(),
+        };
+        let root = Root {
+            modules: vec![module(), module()],
+            named: BTreeMap::from([("a".to_string(), Some(module()))]),
+        };
+        assert_eq!(
+            field_path(&root, &root.modules[1].config),
+            Some("modules[1].config".to_string())
+        );
+        assert_eq!(
+            field_path(&root, &root.named["a"].as_ref().unwrap().config),
+            Some("named.a.config".to_string())
+        );
+        assert_eq!(field_path(&root, &config), None);
+    }
+
+    #[test]
+    fn source_map() {
+        let content = "\
+version: 1.5.0
+robots:
+  - name: robot1 # comment
+    physics:
+      model: {type: Unicycle}
+    labels:
+    - a
+    - b
+  -
+    name: \"robot 2\"
+    script: |
+      key: not a field
+scenario:
+  events: []
+";
+        let map = ConfigSourceMap::from_source(Path::new("config.yaml"), content);
+        let location = |path| {
+            map.location(path)
+                .map(|location| (location.line, location.column))
+        };
+        assert_eq!(location("version"), Some((1, 1)));
+        assert_eq!(location("robots[0]"), Some((3, 5)));
+        assert_eq!(location("robots[0].name"), Some((3, 5)));
+        assert_eq!(location("robots[0].physics.model"), Some((5, 7)));
+        assert_eq!(location("robots[0].physics.model.type"), Some((5, 15)));
+        assert_eq!(location("robots[0].labels[1]"), Some((8, 7)));
+        assert_eq!(location("robots[1]"), Some((10, 5)));
+        assert_eq!(location("robots[1].name"), Some((10, 5)));
+        assert_eq!(location("robots[1].script.key"), Some((11, 5)));
+        assert_eq!(location("scenario.events"), Some((14, 3)));
+        assert_eq!(
+            map.annotate("robots[0].physics", "invalid model"),
+            "config.yaml:4:5: robots[0].physics: invalid model"
+        );
+        assert_eq!(
+            ConfigSourceMap::default().annotate("max_time", "negative"),
+            "max_time: negative"
+        );
+
+        // The maps of different files are different
+        let other = ConfigSourceMap::from_source(Path::new("config.yaml"), "version: 1.5.0\n");
+        assert_ne!(map, other);
+        assert_eq!(
+            other,
+            ConfigSourceMap::from_source(Path::new("config.yaml"), "version: 1.6.0\n")
+        );
+    }
+}
@@ -2,11 +2,11 @@
 //!
 //! This module provides reproducible random-variable generation by combining a
 //! global seed with per-variable local seeds. It exposes:
-//! - a factory to create deterministic random variables,
+//! - a factory to create deterministic random variables, with independent child streams,
 //! - runtime wrappers over supported distributions,
 //! - configuration enums used across the simulator and GUI.
 
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use rand::{Rng, SeedableRng, random};
 use rand_chacha::ChaCha8Rng;
@@ -14,22 +14,39 @@ use simba_macros::config_derives;
 
 #[cfg(feature = "gui")]
 use crate::gui::{UIComponent, utils::string_combobox};
+#[cfg(feature = "seed_audit")]
+use crate::utils::seed_audit::{AuditedRandomVariable, SeedAudit, SeedAuditRecorder};
 
 use super::distributions::{
     exponential::{DeterministExponentialRandomVariable, ExponentialRandomVariableConfig},
     fixed::{DeterministFixedRandomVariable, FixedRandomVariableConfig},
+    gauss_markov::{DeterministGaussMarkovRandomVariable, GaussMarkovRandomVariableConfig},
+    markov_switching::{
+        DeterministMarkovSwitchingRandomVariable, MarkovSwitchingRandomVariableConfig,
+    },
+    mixture::{DeterministMixtureRandomVariable, MixtureRandomVariableConfig},
     normal::{DeterministNormalRandomVariable, NormalRandomVariableConfig},
     poisson::{DeterministPoissonRandomVariable, PoissonRandomVariableConfig},
+    student_t::{DeterministStudentTRandomVariable, StudentTRandomVariableConfig},
     uniform::{DeterministUniformRandomVariable, UniformRandomVariableConfig},
 };
 
 /// Factory to create random variables with a deterministic behavior, using a global seed.
 ///
 /// The seeds of the generated random variables are computed by combining the global seed with a local seed generated for each variable, which ensures that the same sequence of random variables is generated across runs with the same global seed.
+///
+/// The local seeds are drawn in sequence, so creating one more variable shifts the seeds of all
+/// the variables created after it. To keep the draws of independent parts of the simulation
+/// stable, each of them uses its own [child](DeterministRandomVariableFactory::child) stream,
+/// keyed by a path (e.g. `robot1/sensors/gps`).
 pub struct DeterministRandomVariableFactory {
     /// Global run seed.
     global_seed: Mutex<f32>,
+    /// Path of the stream, empty for the root factory.
+    path: String,
     seed_generator: Mutex<ChaCha8Rng>,
+    #[cfg(feature = "seed_audit")]
+    seed_audit: Arc<Mutex<SeedAuditRecorder>>,
 }
 
 impl DeterministRandomVariableFactory {
@@ -37,12 +54,83 @@ impl DeterministRandomVariableFactory {
     pub fn new(global_seed: f32) -> Self {
         Self {
             global_seed: Mutex::new(global_seed),
-            seed_generator: Mutex::new(ChaCha8Rng::seed_from_u64(global_seed.to_bits() as u64)),
+            path: String::new(),
+            seed_generator: Mutex::new(Self::seed_generator(global_seed, "")),
+            #[cfg(feature = "seed_audit")]
+            seed_audit: Arc::new(Mutex::new(SeedAuditRecorder::new(global_seed))),
         }
     }
 
+    /// Create the child stream `name` of this factory.
+    ///
+    /// The seeds of the child only depend on the global seed and on its path (`<path>/<name>`),
+    /// not on the variables created by this factory or its other children: adding a sensor to a
+    /// robot does not change the noise of the other sensors and robots. Children with the same
+    /// path give the same variables.
+    pub fn child(&self, name: &str) -> Arc<Self> {
+        let path = if self.path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.path, name)
+        };
+        let global_seed = self.global_seed();
+        Arc::new(Self {
+            global_seed: Mutex::new(global_seed),
+            seed_generator: Mutex::new(Self::seed_generator(global_seed, &path)),
+            path,
+            #[cfg(feature = "seed_audit")]
+            seed_audit: self.seed_audit.clone(),
+        })
+    }
+
+    /// Path of the stream, empty for the root factory.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Generator of the local seeds of the stream `path`.
+    ///
+    /// The root stream is seeded with the global seed only, and the child streams with a FNV-1a
+    /// hash of the global seed and of the path, which is stable across platforms and versions.
+    fn seed_generator(global_seed: f32, path: &str) -> ChaCha8Rng {
+        if path.is_empty() {
+            return ChaCha8Rng::seed_from_u64(global_seed.to_bits() as u64);
+        }
+        let hash = global_seed
+            .to_bits()
+            .to_le_bytes()
+            .iter()
+            .chain(path.as_bytes())
+            .fold(0xcbf29ce484222325_u64, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+            });
+        ChaCha8Rng::seed_from_u64(hash)
+    }
+
     /// Create a new random variable with the given configuration.
+    #[cfg(not(feature = "seed_audit"))]
     pub fn make_variable(&self, config: RandomVariableTypeConfig) -> DeterministRandomVariable {
+        self.new_variable(config)
+    }
+
+    /// Create a new random variable with the given configuration.
+    ///
+    /// The draws of the variable are recorded for the seed audit, with the caller
+    /// location as consumer path.
+    #[cfg(feature = "seed_audit")]
+    #[track_caller]
+    pub fn make_variable(&self, config: RandomVariableTypeConfig) -> DeterministRandomVariable {
+        let location = std::panic::Location::caller();
+        let variable = self.new_variable(config);
+        DeterministRandomVariable::Audited(
+            self.seed_audit
+                .lock()
+                .unwrap()
+                .audit_variable(variable, location),
+        )
+    }
+
+    fn new_variable(&self, config: RandomVariableTypeConfig) -> DeterministRandomVariable {
         let local_seed = self.seed_generator.lock().unwrap().r#gen::<f32>() * 1000000.;
         match config {
             RandomVariableTypeConfig::None => {
@@ -66,28 +154,48 @@ impl DeterministRandomVariableFactory {
             RandomVariableTypeConfig::Exponential(c) => DeterministRandomVariable::Exponential(
                 DeterministExponentialRandomVariable::from_config(local_seed, c),
             ),
+            RandomVariableTypeConfig::StudentT(c) => DeterministRandomVariable::StudentT(
+                DeterministStudentTRandomVariable::from_config(local_seed, c),
+            ),
+            RandomVariableTypeConfig::Mixture(c) => DeterministRandomVariable::Mixture(
+                DeterministMixtureRandomVariable::from_config(local_seed, c, self),
+            ),
+            RandomVariableTypeConfig::MarkovSwitching(c) => {
+                DeterministRandomVariable::MarkovSwitching(
+                    DeterministMarkovSwitchingRandomVariable::from_config(local_seed, c),
+                )
+            }
+            RandomVariableTypeConfig::GaussMarkov(c) => DeterministRandomVariable::GaussMarkov(
+                DeterministGaussMarkovRandomVariable::from_config(local_seed, c),
+            ),
         }
     }
 
     /// Set the global seed and reset the internal seed generator.
+    ///
+    /// The children created before keep the previous global seed.
     pub fn set_global_seed(&self, seed: f32) {
         *self.global_seed.lock().unwrap() = seed;
-        *self.seed_generator.lock().unwrap() = ChaCha8Rng::seed_from_u64(seed.to_bits() as u64);
+        *self.seed_generator.lock().unwrap() = Self::seed_generator(seed, &self.path);
+        #[cfg(feature = "seed_audit")]
+        self.seed_audit.lock().unwrap().reset(seed);
     }
 
     /// Get the current global seed.
     pub fn global_seed(&self) -> f32 {
         *self.global_seed.lock().unwrap()
     }
+
+    /// Get the draws recorded since the last global seed change.
+    #[cfg(feature = "seed_audit")]
+    pub fn seed_audit(&self) -> SeedAudit {
+        self.seed_audit.lock().unwrap().audit()
+    }
 }
 
 impl Default for DeterministRandomVariableFactory {
     fn default() -> Self {
-        let global_seed = random::<f32>() * 1000000.;
-        Self {
-            global_seed: Mutex::new(global_seed),
-            seed_generator: Mutex::new(ChaCha8Rng::seed_from_u64(global_seed.to_bits() as u64)),
-        }
+        Self::new(random::<f32>() * 1000000.)
     }
 }
 
@@ -104,6 +212,17 @@ pub enum DeterministRandomVariable {
     Poisson(DeterministPoissonRandomVariable),
     /// Exponential distribution.
     Exponential(DeterministExponentialRandomVariable),
+    /// Student-t distribution.
+    StudentT(DeterministStudentTRandomVariable),
+    /// Mixture of random variables.
+    Mixture(DeterministMixtureRandomVariable),
+    /// Markov-switching bias.
+    MarkovSwitching(DeterministMarkovSwitchingRandomVariable),
+    /// First-order Gauss-Markov process.
+    GaussMarkov(DeterministGaussMarkovRandomVariable),
+    /// Random variable recording its draws for the seed audit.
+    #[cfg(feature = "seed_audit")]
+    Audited(AuditedRandomVariable),
 }
 
 impl DeterministRandomVariable {
@@ -115,6 +234,12 @@ impl DeterministRandomVariable {
             DeterministRandomVariable::Normal(v) => v.generate(time),
             DeterministRandomVariable::Poisson(v) => v.generate(time),
             DeterministRandomVariable::Exponential(v) => v.generate(time),
+            DeterministRandomVariable::StudentT(v) => v.generate(time),
+            DeterministRandomVariable::Mixture(v) => v.generate(time),
+            DeterministRandomVariable::MarkovSwitching(v) => v.generate(time),
+            DeterministRandomVariable::GaussMarkov(v) => v.generate(time),
+            #[cfg(feature = "seed_audit")]
+            DeterministRandomVariable::Audited(v) => v.generate(time),
         }
     }
 
@@ -126,6 +251,12 @@ impl DeterministRandomVariable {
             DeterministRandomVariable::Normal(v) => v.dim(),
             DeterministRandomVariable::Poisson(v) => v.dim(),
             DeterministRandomVariable::Exponential(v) => v.dim(),
+            DeterministRandomVariable::StudentT(v) => v.dim(),
+            DeterministRandomVariable::Mixture(v) => v.dim(),
+            DeterministRandomVariable::MarkovSwitching(v) => v.dim(),
+            DeterministRandomVariable::GaussMarkov(v) => v.dim(),
+            #[cfg(feature = "seed_audit")]
+            DeterministRandomVariable::Audited(v) => v.dim(),
         }
     }
 }
@@ -150,6 +281,18 @@ pub enum RandomVariableTypeConfig {
     /// Exponential distribution.
     #[check]
     Exponential(ExponentialRandomVariableConfig),
+    /// Student-t distribution, for heavy-tailed noise.
+    #[check]
+    StudentT(StudentTRandomVariableConfig),
+    /// Mixture of random variables, e.g. for outlier models.
+    #[check]
+    Mixture(MixtureRandomVariableConfig),
+    /// Bias switching between several values following a Markov chain.
+    #[check]
+    MarkovSwitching(MarkovSwitchingRandomVariableConfig),
+    /// First-order Gauss-Markov process, for autocorrelated noise.
+    #[check]
+    GaussMarkov(GaussMarkovRandomVariableConfig),
 }
 
 impl RandomVariableTypeConfig {
@@ -162,6 +305,14 @@ impl RandomVariableTypeConfig {
             RandomVariableTypeConfig::Normal(c) => c.mean.len(),
             RandomVariableTypeConfig::Poisson(c) => c.lambda.len(),
             RandomVariableTypeConfig::Exponential(c) => c.lambda.len(),
+            RandomVariableTypeConfig::StudentT(c) => c.location.len(),
+            RandomVariableTypeConfig::Mixture(c) => {
+                c.components.first().map(|c| c.dim()).unwrap_or(0)
+            }
+            RandomVariableTypeConfig::MarkovSwitching(c) => {
+                c.biases.first().map(|b| b.len()).unwrap_or(0)
+            }
+            RandomVariableTypeConfig::GaussMarkov(c) => c.sigma.len(),
         }
     }
 }
@@ -191,6 +342,10 @@ impl UIComponent for RandomVariableTypeConfig {
             "Normal",
             "Poisson",
             "Exponential",
+            "StudentT",
+            "Mixture",
+            "MarkovSwitching",
+            "GaussMarkov",
         ];
         ui.horizontal(|ui| {
             ui.label("Type:");
@@ -224,6 +379,24 @@ impl UIComponent for RandomVariableTypeConfig {
                         ExponentialRandomVariableConfig::default(),
                     )
                 }
+                "StudentT" => {
+                    *self =
+                        RandomVariableTypeConfig::StudentT(StudentTRandomVariableConfig::default())
+                }
+                "Mixture" => {
+                    *self =
+                        RandomVariableTypeConfig::Mixture(MixtureRandomVariableConfig::default())
+                }
+                "MarkovSwitching" => {
+                    *self = RandomVariableTypeConfig::MarkovSwitching(
+                        MarkovSwitchingRandomVariableConfig::default(),
+                    )
+                }
+                "GaussMarkov" => {
+                    *self = RandomVariableTypeConfig::GaussMarkov(
+                        GaussMarkovRandomVariableConfig::default(),
+                    )
+                }
                 _ => panic!("Where did you find this value?"),
             };
         }
@@ -271,6 +444,38 @@ impl UIComponent for RandomVariableTypeConfig {
                 current_node_name,
                 unique_id,
             ),
+            RandomVariableTypeConfig::StudentT(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            RandomVariableTypeConfig::Mixture(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            RandomVariableTypeConfig::MarkovSwitching(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
+            RandomVariableTypeConfig::GaussMarkov(c) => c.show_mut(
+                ui,
+                ctx,
+                buffer_stack,
+                global_config,
+                current_node_name,
+                unique_id,
+            ),
         };
     }
 
@@ -288,6 +493,10 @@ impl UIComponent for RandomVariableTypeConfig {
             RandomVariableTypeConfig::Normal(c) => c.show(ui, ctx, unique_id),
             RandomVariableTypeConfig::Poisson(c) => c.show(ui, ctx, unique_id),
             RandomVariableTypeConfig::Exponential(c) => c.show(ui, ctx, unique_id),
+            RandomVariableTypeConfig::StudentT(c) => c.show(ui, ctx, unique_id),
+            RandomVariableTypeConfig::Mixture(c) => c.show(ui, ctx, unique_id),
+            RandomVariableTypeConfig::MarkovSwitching(c) => c.show(ui, ctx, unique_id),
+            RandomVariableTypeConfig::GaussMarkov(c) => c.show(ui, ctx, unique_id),
         };
     }
 }
@@ -368,3 +577,82 @@ pub fn seed_generation_component(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(factory: &DeterministRandomVariableFactory) -> Vec<f32> {
+        factory
+            .make_variable(RandomVariableTypeConfig::Normal(
+                NormalRandomVariableConfig::default(),
+            ))
+            .generate(1.)
+    }
+
+    #[test]
+    fn child_streams_are_independent() {
+        let factory = DeterministRandomVariableFactory::new(42.);
+        let robot2 = factory.child("robot2");
+        let reference = draw(&robot2.child("sensors").child("gps"));
+
+        // Variables created in another stream do not shift the draws
+        let other = DeterministRandomVariableFactory::new(42.);
+        let robot1 = other.child("robot1");
+        draw(&robot1);
+        draw(&other);
+        let robot2 = other.child("robot2");
+        draw(&robot2.child("sensors").child("odometry"));
+        let gps = robot2.child("sensors").child("gps");
+        assert_eq!(gps.path(), "robot2/sensors/gps");
+        assert_eq!(draw(&gps), reference);
+
+        assert_ne!(draw(&factory.child("robot1")), reference);
+        assert_ne!(
+            draw(
+                &DeterministRandomVariableFactory::new(43.)
+                    .child("robot2")
+                    .child("sensors")
+                    .child("gps")
+            ),
+            reference
+        );
+    }
+
+    #[test]
+    fn mixture_weights_checked() {
+        let mixture = MixtureRandomVariableConfig::default();
+        assert!(mixture.check().is_ok());
+        let factory = DeterministRandomVariableFactory::new(42.);
+        assert_eq!(
+            factory
+                .make_variable(RandomVariableTypeConfig::Mixture(mixture.clone()))
+                .generate(1.)
+                .len(),
+            1
+        );
+
+        for weights in [vec![0.], vec![-1.], vec![f32::NAN], vec![f32::INFINITY]] {
+            let config = MixtureRandomVariableConfig {
+                weights,
+                ..mixture.clone()
+            };
+            assert!(config.check().is_err());
+            // Unchecked configurations do not panic
+            factory
+                .make_variable(RandomVariableTypeConfig::Mixture(config))
+                .generate(1.);
+        }
+        let empty = MixtureRandomVariableConfig {
+            components: Vec::new(),
+            weights: Vec::new(),
+        };
+        assert!(empty.check().is_err());
+        assert!(
+            factory
+                .make_variable(RandomVariableTypeConfig::Mixture(empty))
+                .generate(1.)
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! First-order Gauss-Markov process utilities.
+//!
+//! This module provides configuration and deterministic sampling utilities for
+//! autocorrelated noise, following a first-order Gauss-Markov process (discretized
+//! Ornstein-Uhlenbeck process). It is typically used for slowly drifting sensor biases.
+
+use std::iter::zip;
+
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use simba_macros::config_derives;
+use statrs::distribution::Normal;
+
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+
+/// Contribution under which the past samples are neglected. The cost of a sample is about
+/// `7 * correlation_time / period` draws.
+const NEGLIGIBLE_CORRELATION: f64 = 1e-3;
+
+/// Configuration for a first-order Gauss-Markov process.
+///
+/// Each dimension `x` is an independent stationary process, with standard deviation `sigma`
+/// and autocorrelation `exp(-|dt| / correlation_time)`. The process is discretized with the
+/// given `period`: the value is constant between two steps.
+///
+/// ```yaml
+/// type: GaussMarkov
+/// sigma: [0.1]
+/// correlation_time: [60.]
+/// period: 0.1
+/// ```
+#[config_derives]
+pub struct GaussMarkovRandomVariableConfig {
+    /// Stationary standard deviation of each dimension.
+    pub sigma: Vec<f64>,
+    /// Correlation time of each dimension (s).
+    pub correlation_time: Vec<f64>,
+    /// Discretization period of the process (s).
+    pub period: f32,
+}
+
+impl Check for GaussMarkovRandomVariableConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.sigma.is_empty() {
+            errors.push("Sigma vector cannot be empty.".to_string());
+        }
+        if self.sigma.len() != self.correlation_time.len() {
+            errors.push(format!(
+                "Sigma and correlation time vectors should have the same length. Got {} sigmas and {} correlation times.",
+                self.sigma.len(),
+                self.correlation_time.len()
+            ));
+        }
+        if self.sigma.iter().any(|s| *s < 0.) {
+            errors.push("Sigmas must be non-negative.".to_string());
+        }
+        if self.correlation_time.iter().any(|t| *t <= 0.) {
+            errors.push("Correlation times must be positive.".to_string());
+        }
+        if self.period <= 0. {
+            errors.push(format!("Period must be positive, got {}.", self.period));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for GaussMarkovRandomVariableConfig {
+    fn default() -> Self {
+        Self {
+            sigma: vec![1.],
+            correlation_time: vec![10.],
+            period: 0.1,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for GaussMarkovRandomVariableConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &crate::simulator::SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        ui.vertical(|ui| {
+            let mut to_remove = None;
+            for (i, (sigma, correlation_time)) in
+                zip(self.sigma.iter_mut(), self.correlation_time.iter_mut()).enumerate()
+            {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}: sigma", i + 1));
+                    ui.add(egui::DragValue::new(sigma).max_decimals(10));
+                    ui.label("correlation time");
+                    ui.add(egui::DragValue::new(correlation_time).max_decimals(10));
+                    if ui.button("X").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_remove {
+                self.sigma.remove(i);
+                self.correlation_time.remove(i);
+            }
+            if ui.button("Add").clicked() {
+                self.sigma.push(1.);
+                self.correlation_time.push(10.);
+            }
+            ui.horizontal(|ui| {
+                ui.label("Period:");
+                ui.add(egui::DragValue::new(&mut self.period).max_decimals(10));
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.vertical(|ui| {
+            for (i, (sigma, correlation_time)) in
+                zip(self.sigma.iter(), self.correlation_time.iter()).enumerate()
+            {
+                ui.label(format!(
+                    "{}: sigma {sigma}, correlation time {correlation_time}",
+                    i + 1
+                ));
+            }
+            ui.label(format!("Period: {}", self.period));
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Deterministic first-order Gauss-Markov process generator.
+///
+/// The value at step `k` is `x_k = a * x_{k-1} + w_k`, with `a = exp(-period / correlation_time)`.
+/// Each driving noise `w_k` is drawn from a seed depending only on `k`, and the value is
+/// rebuilt from the last steps until their contribution is negligible. Therefore, the value
+/// at a given time is reproducible and does not depend on the previous calls.
+pub struct DeterministGaussMarkovRandomVariable {
+    /// Seed used, which is the global seed from the factory + the unique seed of this random variable (computed by the factory).
+    my_seed: f32,
+    /// Stationary standard deviation of each dimension.
+    sigma: Vec<f64>,
+    /// Correlation factor between two steps, for each dimension.
+    alpha: Vec<f64>,
+    /// Discretization period.
+    period: f32,
+    /// Number of past steps used to rebuild the value.
+    horizon: u64,
+}
+
+impl DeterministGaussMarkovRandomVariable {
+    /// Build a deterministic Gauss-Markov process from configuration.
+    ///
+    /// `my_seed` should be a deterministic seed component unique to this
+    /// variable instance.
+    pub fn from_config(my_seed: f32, config: GaussMarkovRandomVariableConfig) -> Self {
+        assert!(config.sigma.len() == config.correlation_time.len());
+        assert!(config.period > 0.);
+        let alpha: Vec<f64> = config
+            .correlation_time
+            .iter()
+            .map(|tau| (-config.period as f64 / tau).exp())
+            .collect();
+        let max_alpha = alpha.iter().cloned().fold(0., f64::max);
+        let horizon = if max_alpha <= 0. {
+            0
+        } else {
+            (NEGLIGIBLE_CORRELATION.ln() / max_alpha.ln()).ceil() as u64
+        };
+        Self {
+            my_seed,
+            sigma: config.sigma,
+            alpha,
+            period: config.period,
+            horizon,
+        }
+    }
+
+    /// Standard normal samples of the step `step`, one per dimension.
+    fn step_noise(&self, step: u64) -> Vec<f64> {
+        let mut rng =
+            ChaCha8Rng::seed_from_u64((self.my_seed + step as f32 * self.period).to_bits() as u64);
+        let normal = Normal::new(0., 1.).unwrap();
+        self.sigma.iter().map(|_| normal.sample(&mut rng)).collect()
+    }
+
+    /// Generate the value of the process at a given simulation `time`.
+    ///
+    /// The produced values are reproducible for the same `(my_seed, time)` pair.
+    pub fn generate(&self, time: f32) -> Vec<f32> {
+        let current_step = (time.max(0.) / self.period).floor() as u64;
+        let first_step = current_step.saturating_sub(self.horizon);
+        let mut value = vec![0.; self.sigma.len()];
+        for step in first_step..=current_step {
+            let noise = self.step_noise(step);
+            for (i, v) in value.iter_mut().enumerate() {
+                let std = if step == 0 {
+                    // Initial value drawn from the stationary distribution
+                    self.sigma[i]
+                } else {
+                    self.sigma[i] * (1. - self.alpha[i].powi(2)).sqrt()
+                };
+                *v = self.alpha[i] * *v + std * noise[i];
+            }
+        }
+        value.iter().map(|v| *v as f32).collect()
+    }
+
+    /// Return the output dimension of the random variable.
+    pub fn dim(&self) -> usize {
+        self.sigma.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gauss_markov_is_reproducible_and_stationary() {
+        let variable = DeterministGaussMarkovRandomVariable::from_config(
+            12.,
+            GaussMarkovRandomVariableConfig {
+                sigma: vec![2.],
+                correlation_time: vec![1.],
+                period: 0.1,
+            },
+        );
+        assert_eq!(variable.generate(50.), variable.generate(50.));
+        // Constant between two steps
+        assert_eq!(variable.generate(50.01), variable.generate(50.05));
+
+        let samples: Vec<f32> = (0..5000)
+            .map(|i| variable.generate(i as f32 * 0.1)[0])
+            .collect();
+        let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+        let variance =
+            samples.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / samples.len() as f32;
+        assert!(mean.abs() < 0.5, "Mean is {mean}");
+        assert!(
+            (variance.sqrt() - 2.).abs() < 0.5,
+            "Std is {}",
+            variance.sqrt()
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Markov-switching bias random-variable utilities.
+//!
+//! This module provides configuration and deterministic sampling utilities for
+//! a bias switching between several values following a continuous-time Markov chain,
+//! e.g. to model a sensor with intermittent faults or a multipath GNSS.
+
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use simba_macros::config_derives;
+use statrs::distribution::Exp;
+
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+
+/// Configuration for a Markov-switching bias.
+///
+/// The chain starts in the first state at time 0. The time spent in a state follows an
+/// exponential distribution of mean `mean_durations[state]`, then the chain switches to
+/// one of the other states, chosen uniformly. The generated value is the bias of the
+/// current state.
+///
+/// ```yaml
+/// type: MarkovSwitching
+/// biases:
+///   - [0., 0.]    # Nominal
+///   - [2., -1.]   # Faulty
+/// mean_durations: [30., 5.]
+/// ```
+#[config_derives]
+pub struct MarkovSwitchingRandomVariableConfig {
+    /// Bias vector of each state. All biases should have the same dimension.
+    pub biases: Vec<Vec<f32>>,
+    /// Mean time spent in each state (s).
+    pub mean_durations: Vec<f32>,
+}
+
+impl Check for MarkovSwitchingRandomVariableConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.biases.is_empty() {
+            errors.push("Biases cannot be empty.".to_string());
+        }
+        if self.biases.len() != self.mean_durations.len() {
+            errors.push(format!(
+                "Biases and mean durations should have the same length. Got {} biases and {} mean durations.",
+                self.biases.len(),
+                self.mean_durations.len()
+            ));
+        }
+        if let Some(first) = self.biases.first()
+            && self.biases.iter().any(|b| b.len() != first.len())
+        {
+            errors.push("All the biases should have the same dimension.".to_string());
+        }
+        if self.mean_durations.iter().any(|d| *d <= 0.) {
+            errors.push("Mean durations must be positive.".to_string());
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for MarkovSwitchingRandomVariableConfig {
+    fn default() -> Self {
+        Self {
+            biases: vec![vec![0.], vec![1.]],
+            mean_durations: vec![10., 10.],
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MarkovSwitchingRandomVariableConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &crate::simulator::SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        ui.vertical(|ui| {
+            let dim = self.biases.first().map(|b| b.len()).unwrap_or(1);
+            let mut to_remove = None;
+            for (i, (bias, duration)) in self
+                .biases
+                .iter_mut()
+                .zip(self.mean_durations.iter_mut())
+                .enumerate()
+            {
+                ui.horizontal(|ui| {
+                    ui.label(format!("State {}: bias", i + 1));
+                    bias.resize(dim, 0.);
+                    for b in bias.iter_mut() {
+                        ui.add(egui::DragValue::new(b).max_decimals(10));
+                    }
+                    ui.label("mean duration");
+                    ui.add(egui::DragValue::new(duration).max_decimals(10));
+                    if ui.button("X").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_remove {
+                self.biases.remove(i);
+                self.mean_durations.remove(i);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Add state").clicked() {
+                    self.biases.push(vec![0.; dim]);
+                    self.mean_durations.push(10.);
+                }
+                if ui.button("Add dimension").clicked() {
+                    for bias in self.biases.iter_mut() {
+                        bias.push(0.);
+                    }
+                }
+                if dim > 1 && ui.button("Remove dimension").clicked() {
+                    for bias in self.biases.iter_mut() {
+                        bias.pop();
+                    }
+                }
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.vertical(|ui| {
+            for (i, (bias, duration)) in self
+                .biases
+                .iter()
+                .zip(self.mean_durations.iter())
+                .enumerate()
+            {
+                ui.label(format!(
+                    "State {}: bias {:?}, mean duration {duration}",
+                    i + 1,
+                    bias
+                ));
+            }
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Deterministic Markov-switching bias generator.
+///
+/// The whole chain is drawn from the seed only, so the value at a given time is
+/// reproducible and does not depend on the previous calls.
+pub struct DeterministMarkovSwitchingRandomVariable {
+    /// Seed used, which is the global seed from the factory + the unique seed of this random variable (computed by the factory).
+    my_seed: f32,
+    /// Bias vector of each state.
+    biases: Vec<Vec<f32>>,
+    /// Distribution of the time spent in each state.
+    durations: Vec<Exp>,
+}
+
+impl DeterministMarkovSwitchingRandomVariable {
+    /// Build a deterministic Markov-switching bias from configuration.
+    ///
+    /// `my_seed` should be a deterministic seed component unique to this
+    /// variable instance.
+    pub fn from_config(my_seed: f32, config: MarkovSwitchingRandomVariableConfig) -> Self {
+        assert!(config.biases.len() == config.mean_durations.len());
+        Self {
+            my_seed,
+            biases: config.biases,
+            durations: config
+                .mean_durations
+                .iter()
+                .map(|d| Exp::new(1. / *d as f64).unwrap())
+                .collect(),
+        }
+    }
+
+    /// Generate the bias of the state of the chain at the given simulation `time`.
+    ///
+    /// The chain is replayed from time 0, so the cost grows with the number of switches
+    /// before `time`.
+    pub fn generate(&self, time: f32) -> Vec<f32> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.my_seed.to_bits() as u64);
+        let mut state = 0;
+        let mut switch_time = 0.;
+        loop {
+            switch_time += self.durations[state].sample(&mut rng) as f32;
+            if switch_time > time || self.biases.len() < 2 {
+                break;
+            }
+            // Uniform choice among the other states
+            let next = rng.gen_range(0..self.biases.len() - 1);
+            state = if next >= state { next + 1 } else { next };
+        }
+        self.biases[state].clone()
+    }
+
+    /// Return the output dimension of the random variable.
+    pub fn dim(&self) -> usize {
+        self.biases.first().map(|b| b.len()).unwrap_or(0)
+    }
+}
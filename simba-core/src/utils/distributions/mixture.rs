@@ -0,0 +1,225 @@
+//! Mixture random-variable utilities.
+//!
+//! This module provides configuration and deterministic sampling utilities for
+//! mixtures of random variables. At each sample, one component is drawn according
+//! to the weights, which allows to model outliers: a nominal distribution with a high
+//! weight and a wide distribution with a low weight.
+
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use simba_macros::config_derives;
+
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+use crate::utils::{
+    determinist_random_variable::{
+        DeterministRandomVariable, DeterministRandomVariableFactory, RandomVariableTypeConfig,
+    },
+    distributions::normal::NormalRandomVariableConfig,
+};
+
+/// Configuration for a mixture of random variables.
+///
+/// Example of an outlier model, where 5% of the samples come from a wide distribution:
+/// ```yaml
+/// type: Mixture
+/// components:
+///   - type: Normal
+///     mean: [0.]
+///     covariance: [0.01]
+///   - type: Uniform
+///     min: [-5.]
+///     max: [5.]
+/// weights: [0.95, 0.05]
+/// ```
+#[config_derives]
+pub struct MixtureRandomVariableConfig {
+    /// Components of the mixture. All components should have the same dimension.
+    #[check]
+    pub components: Vec<RandomVariableTypeConfig>,
+    /// Weights of the components, normalized at use.
+    pub weights: Vec<f32>,
+}
+
+impl Check for MixtureRandomVariableConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.components.is_empty() {
+            errors.push("Components cannot be empty.".to_string());
+        }
+        if self.components.len() != self.weights.len() {
+            errors.push(format!(
+                "Components and weights should have the same length. Got {} components and {} weights.",
+                self.components.len(),
+                self.weights.len()
+            ));
+        }
+        if self.weights.iter().any(|w| !w.is_finite() || *w < 0.) {
+            errors.push("Weights must be finite and non-negative.".to_string());
+        }
+        let total = self.weights.iter().sum::<f32>();
+        if !self.weights.is_empty() && (total.is_nan() || total <= 0.) {
+            errors.push("The sum of the weights must be positive.".to_string());
+        }
+        if let Some(first) = self.components.first()
+            && self.components.iter().any(|c| c.dim() != first.dim())
+        {
+            errors.push("All the components should have the same dimension.".to_string());
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for MixtureRandomVariableConfig {
+    /// Single normal component, to be valid out of the box (e.g. when selected in the GUI).
+    fn default() -> Self {
+        Self {
+            components: vec![RandomVariableTypeConfig::Normal(
+                NormalRandomVariableConfig::default(),
+            )],
+            weights: vec![1.],
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for MixtureRandomVariableConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        global_config: &crate::simulator::SimulatorConfig,
+        current_node_name: Option<&String>,
+        unique_id: &str,
+    ) {
+        ui.vertical(|ui| {
+            let mut to_remove = None;
+            for (i, (component, weight)) in self
+                .components
+                .iter_mut()
+                .zip(self.weights.iter_mut())
+                .enumerate()
+            {
+                ui.horizontal_top(|ui| {
+                    ui.label("Weight:");
+                    ui.add(egui::DragValue::new(weight).max_decimals(10));
+                    component.show_mut(
+                        ui,
+                        ctx,
+                        buffer_stack,
+                        global_config,
+                        current_node_name,
+                        &format!("mixture-component-{i}-{unique_id}"),
+                    );
+                    if ui.button("X").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_remove {
+                self.components.remove(i);
+                self.weights.remove(i);
+            }
+            if ui.button("Add").clicked() {
+                self.components.push(RandomVariableTypeConfig::None);
+                self.weights.push(1.);
+            }
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, ctx: &egui::Context, unique_id: &str) {
+        ui.vertical(|ui| {
+            for (i, (component, weight)) in
+                self.components.iter().zip(self.weights.iter()).enumerate()
+            {
+                ui.horizontal_top(|ui| {
+                    ui.label(format!("Weight: {weight}"));
+                    component.show(ui, ctx, &format!("mixture-component-{i}-{unique_id}"));
+                });
+            }
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Deterministic mixture random variable generator.
+///
+/// Sampling is reproducible for the same seed and time input.
+pub struct DeterministMixtureRandomVariable {
+    /// Seed used, which is the global seed from the factory + the unique seed of this random variable (computed by the factory).
+    my_seed: f32,
+    /// Random variables of the components.
+    components: Vec<DeterministRandomVariable>,
+    /// Cumulative normalized weights of the components.
+    cumulative_weights: Vec<f32>,
+}
+
+impl DeterministMixtureRandomVariable {
+    /// Build a deterministic mixture random variable from configuration.
+    ///
+    /// `my_seed` should be a deterministic seed component unique to this
+    /// variable instance. The components are created with the `factory`.
+    ///
+    /// The configuration is expected to be checked. Otherwise, unusable weights are replaced by
+    /// equal weights, and a mixture without component generates empty vectors.
+    pub fn from_config(
+        my_seed: f32,
+        config: MixtureRandomVariableConfig,
+        factory: &DeterministRandomVariableFactory,
+    ) -> Self {
+        let mut weights = config.weights;
+        let total: f32 = weights.iter().sum();
+        if weights.len() != config.components.len()
+            || weights.iter().any(|w| !w.is_finite() || *w < 0.)
+            || total.is_nan()
+            || total <= 0.
+        {
+            log::warn!("Invalid mixture weights {weights:?}, equal weights are used");
+            weights = vec![1.; config.components.len()];
+        }
+        let total: f32 = weights.iter().sum();
+        let mut cumulative = 0.;
+        Self {
+            my_seed,
+            cumulative_weights: weights
+                .iter()
+                .map(|w| {
+                    cumulative += w / total;
+                    cumulative
+                })
+                .collect(),
+            components: config
+                .components
+                .into_iter()
+                .map(|c| factory.make_variable(c))
+                .collect(),
+        }
+    }
+
+    /// Generate one sample vector at a given simulation `time`.
+    ///
+    /// The produced values are reproducible for the same `(my_seed, time)` pair.
+    pub fn generate(&self, time: f32) -> Vec<f32> {
+        if self.components.is_empty() {
+            return Vec::new();
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64((self.my_seed + time).to_bits() as u64);
+        let draw = rng.r#gen::<f32>();
+        let i = self
+            .cumulative_weights
+            .iter()
+            .position(|w| draw < *w)
+            .unwrap_or(self.components.len() - 1);
+        self.components[i].generate(time)
+    }
+
+    /// Return the output dimension of the random variable.
+    pub fn dim(&self) -> usize {
+        self.components.first().map(|c| c.dim()).unwrap_or(0)
+    }
+}
@@ -3,6 +3,10 @@
 pub mod bernouilli;
 pub mod exponential;
 pub mod fixed;
+pub mod gauss_markov;
+pub mod markov_switching;
+pub mod mixture;
 pub mod normal;
 pub mod poisson;
+pub mod student_t;
 pub mod uniform;
@@ -0,0 +1,180 @@
+//! Student-t distribution random-variable utilities.
+//!
+//! This module provides configuration and deterministic sampling utilities for
+//! Student-t random variables, useful for heavy-tailed noise.
+
+use std::iter::zip;
+
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use simba_macros::config_derives;
+use statrs::distribution::StudentsT;
+
+#[cfg(feature = "gui")]
+use crate::gui::UIComponent;
+
+/// Configuration for a Student-t random variable.
+///
+/// Each dimension is an independent Student-t distribution. The lower the degrees of freedom,
+/// the heavier the tails; with a large number of degrees of freedom, it tends to a normal distribution.
+#[config_derives]
+pub struct StudentTRandomVariableConfig {
+    /// Location (median) of the distributions.
+    pub location: Vec<f64>,
+    /// Scale of the distributions.
+    pub scale: Vec<f64>,
+    /// Degrees of freedom of the distributions.
+    pub freedom: Vec<f64>,
+}
+
+impl Check for StudentTRandomVariableConfig {
+    fn do_check(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.location.is_empty() {
+            errors.push("Location vector cannot be empty.".to_string());
+        }
+        if self.location.len() != self.scale.len() || self.location.len() != self.freedom.len() {
+            errors.push(format!(
+                "Location, scale and freedom vectors should have the same length. Got {} locations, {} scales and {} freedoms.",
+                self.location.len(),
+                self.scale.len(),
+                self.freedom.len()
+            ));
+        }
+        if self.scale.iter().any(|s| *s <= 0.) {
+            errors.push("Scales must be positive.".to_string());
+        }
+        if self.freedom.iter().any(|f| *f <= 0.) {
+            errors.push("Degrees of freedom must be positive.".to_string());
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for StudentTRandomVariableConfig {
+    fn default() -> Self {
+        Self {
+            location: vec![0.],
+            scale: vec![1.],
+            freedom: vec![3.],
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl UIComponent for StudentTRandomVariableConfig {
+    fn show_mut(
+        &mut self,
+        ui: &mut egui::Ui,
+        _ctx: &egui::Context,
+        _buffer_stack: &mut std::collections::BTreeMap<String, String>,
+        _global_config: &crate::simulator::SimulatorConfig,
+        _current_node_name: Option<&String>,
+        _unique_id: &str,
+    ) {
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                let mut to_remove = None;
+                for (i, ((location, scale), freedom)) in self
+                    .location
+                    .iter_mut()
+                    .zip(self.scale.iter_mut())
+                    .zip(self.freedom.iter_mut())
+                    .enumerate()
+                {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}: location", i + 1));
+                        ui.add(egui::DragValue::new(location).max_decimals(10));
+                        ui.label("scale");
+                        ui.add(egui::DragValue::new(scale).max_decimals(10));
+                        ui.label("freedom");
+                        ui.add(egui::DragValue::new(freedom).max_decimals(10));
+                        if ui.button("X").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = to_remove {
+                    self.location.remove(i);
+                    self.scale.remove(i);
+                    self.freedom.remove(i);
+                }
+                if ui.button("Add").clicked() {
+                    self.location.push(0.);
+                    self.scale.push(1.);
+                    self.freedom.push(3.);
+                }
+            });
+        });
+    }
+
+    fn show(&self, ui: &mut egui::Ui, _ctx: &egui::Context, _unique_id: &str) {
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                for (i, ((location, scale), freedom)) in self
+                    .location
+                    .iter()
+                    .zip(self.scale.iter())
+                    .zip(self.freedom.iter())
+                    .enumerate()
+                {
+                    ui.label(format!(
+                        "{}: location {location}, scale {scale}, freedom {freedom}",
+                        i + 1
+                    ));
+                }
+            });
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Deterministic Student-t random variable generator.
+///
+/// Sampling is reproducible for the same seed and time input.
+pub struct DeterministStudentTRandomVariable {
+    /// Seed used, which is the global seed from the factory + the unique seed of this random variable (computed by the factory).
+    my_seed: f32,
+    /// Student-t distribution of each dimension.
+    student_t: Vec<StudentsT>,
+}
+
+impl DeterministStudentTRandomVariable {
+    /// Build a deterministic Student-t random variable from configuration.
+    ///
+    /// `my_seed` should be a deterministic seed component unique to this
+    /// variable instance.
+    pub fn from_config(my_seed: f32, config: StudentTRandomVariableConfig) -> Self {
+        assert!(config.location.len() == config.scale.len());
+        assert!(config.location.len() == config.freedom.len());
+        Self {
+            my_seed,
+            student_t: zip(zip(&config.location, &config.scale), &config.freedom)
+                .map(|((location, scale), freedom)| {
+                    StudentsT::new(*location, *scale, *freedom).unwrap()
+                })
+                .collect(),
+        }
+    }
+
+    /// Generate one sample vector at a given simulation `time`.
+    ///
+    /// The produced values are reproducible for the same `(my_seed, time)` pair.
+    pub fn generate(&self, time: f32) -> Vec<f32> {
+        let mut rng = ChaCha8Rng::seed_from_u64((self.my_seed + time).to_bits() as u64);
+        let mut v = Vec::new();
+        for d in &self.student_t {
+            v.push(d.sample(&mut rng) as f32);
+        }
+        v
+    }
+
+    /// Return the output dimension of the random variable.
+    pub fn dim(&self) -> usize {
+        self.student_t.len()
+    }
+}
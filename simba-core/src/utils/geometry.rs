@@ -372,6 +372,43 @@ where
     area < tolerance
 }
 
+/// Move the points apart until they are at least `min_distance` from each other (force-directed
+/// layout with repulsion only).
+///
+/// At each iteration, the two points of each pair too close are pushed apart by half of the missing
+/// distance. Coincident points are pushed along the x axis. Stops when no pair is too close, or
+/// after `max_iterations`.
+pub fn spread_points(points: &mut [SVector<f32, 2>], min_distance: f32, max_iterations: usize) {
+    for _ in 0..max_iterations {
+        let mut displacements = vec![SVector::<f32, 2>::zeros(); points.len()];
+        let mut moved = false;
+        for (i, p1) in points.iter().enumerate() {
+            for (j, p2) in points.iter().enumerate().skip(i + 1) {
+                let diff = p2 - p1;
+                let distance = diff.norm();
+                if distance >= min_distance {
+                    continue;
+                }
+                let direction = if distance > f32::EPSILON {
+                    diff / distance
+                } else {
+                    SVector::<f32, 2>::new(1., 0.)
+                };
+                let push = direction * (min_distance - distance) / 2.;
+                displacements[i] -= push;
+                displacements[j] += push;
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+        for (point, displacement) in points.iter_mut().zip(displacements) {
+            *point += displacement;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{f32::consts::PI, iter::zip};
@@ -640,4 +677,21 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    pub fn test_spread_points() {
+        let mut points = vec![Vector2::new(1., 1.); 3];
+        points.push(Vector2::new(10., 10.));
+        super::spread_points(&mut points, 1., 10);
+        for (i, p1) in points.iter().enumerate() {
+            for p2 in points.iter().skip(i + 1) {
+                assert!(
+                    (p1 - p2).norm() >= 1. - 1e-6,
+                    "{p1:?} and {p2:?} are too close"
+                );
+            }
+        }
+        // Points far enough are not moved
+        assert_eq!(points[3], Vector2::new(10., 10.));
+    }
 }
@@ -0,0 +1,211 @@
+//! Shortest path search on an [`OccupancyGrid`].
+//!
+//! The traversable cells are given by a predicate on the `(row, col)` indices, so that each
+//! navigator classifies the cells from their occupancy value with its own thresholds. The path
+//! is searched with A* on the 8-connected cells, or with Theta*, which connects each cell to the
+//! parent of its predecessor when the segment between them only crosses traversable cells, giving
+//! any-angle paths with fewer points.
+
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use nalgebra::Vector2;
+
+use crate::utils::occupancy_grid::OccupancyGrid;
+
+/// Open cell of the search, ordered by increasing estimated cost.
+#[derive(PartialEq)]
+struct OpenCell {
+    estimated_cost: f32,
+    cell: (usize, usize),
+}
+
+impl Eq for OpenCell {}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for the max-heap to pop the lowest cost first
+        other.estimated_cost.total_cmp(&self.estimated_cost)
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Neighbor offsets of a cell, 8-connectivity.
+const NEIGHBORS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Neighbors of the cell inside the grid, with 8-connectivity, or 4-connectivity if
+/// `diagonals` is false.
+pub fn neighbors(
+    grid: &OccupancyGrid,
+    (row, col): (usize, usize),
+    diagonals: bool,
+) -> impl Iterator<Item = (usize, usize)> + '_ {
+    NEIGHBORS
+        .into_iter()
+        .filter(move |(dr, dc)| diagonals || *dr == 0 || *dc == 0)
+        .filter_map(move |(dr, dc)| {
+            let row = row.checked_add_signed(dr)?;
+            let col = col.checked_add_signed(dc)?;
+            (row < grid.nb_rows() && col < grid.nb_cols()).then_some((row, col))
+        })
+}
+
+/// Whether all the cells crossed by the segment between the centers of `from` and `to` are
+/// traversable, `from` excluded. When the segment goes through a corner, both cells sharing the
+/// corner should be traversable.
+pub fn line_of_sight(
+    from: (usize, usize),
+    to: (usize, usize),
+    is_traversable: impl Fn(usize, usize) -> bool,
+) -> bool {
+    let (delta_row, delta_col) = (
+        to.0 as isize - from.0 as isize,
+        to.1 as isize - from.1 as isize,
+    );
+    let (nb_rows, nb_cols) = (delta_row.abs(), delta_col.abs());
+    let (step_row, step_col) = (delta_row.signum(), delta_col.signum());
+    let (mut row, mut col) = (from.0 as isize, from.1 as isize);
+    let (mut crossed_rows, mut crossed_cols) = (0, 0);
+    while crossed_rows < nb_rows || crossed_cols < nb_cols {
+        // Compare the parameters of the next row boundary and of the next column boundary along
+        // the segment
+        let decision = (1 + 2 * crossed_rows) * nb_cols - (1 + 2 * crossed_cols) * nb_rows;
+        if decision == 0 {
+            if !is_traversable((row + step_row) as usize, col as usize)
+                || !is_traversable(row as usize, (col + step_col) as usize)
+            {
+                return false;
+            }
+            row += step_row;
+            col += step_col;
+            crossed_rows += 1;
+            crossed_cols += 1;
+        } else if decision < 0 {
+            row += step_row;
+            crossed_rows += 1;
+        } else {
+            col += step_col;
+            crossed_cols += 1;
+        }
+        if !is_traversable(row as usize, col as usize) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Shortest path on the traversable cells from `start` to the `goal` cell, as the centers of the
+/// cells after the start one. The start cell is allowed whatever its state, and the diagonal
+/// moves do not cut the corners of the cells which are not traversable.
+///
+/// With `any_angle`, the path is searched with Theta* instead of A*, and only keeps the cells
+/// where the path turns.
+///
+/// Returns `None` if `start` is outside the grid or if the goal cannot be reached.
+pub fn plan_path(
+    grid: &OccupancyGrid,
+    start: Vector2<f32>,
+    goal: (usize, usize),
+    any_angle: bool,
+    is_traversable: impl Fn(usize, usize) -> bool,
+) -> Option<Vec<Vector2<f32>>> {
+    let start = grid.pos_to_idx(start)?;
+    let nb_cols = grid.nb_cols();
+    let index = |(row, col): (usize, usize)| row * nb_cols + col;
+    let position = |cell: (usize, usize)| grid.idx_to_pos(cell.0, cell.1).unwrap();
+    let goal_position = position(goal);
+    let mut costs = vec![f32::INFINITY; grid.nb_rows() * nb_cols];
+    let mut parents: Vec<Option<(usize, usize)>> = vec![None; costs.len()];
+    let mut open = BinaryHeap::new();
+    costs[index(start)] = 0.;
+    open.push(OpenCell {
+        estimated_cost: (goal_position - position(start)).norm(),
+        cell: start,
+    });
+    while let Some(OpenCell { cell, .. }) = open.pop() {
+        if cell == goal {
+            let mut path = Vec::new();
+            let mut current = goal;
+            while current != start {
+                path.push(position(current));
+                current = parents[index(current)].unwrap();
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for neighbor in neighbors(grid, cell, true) {
+            if !line_of_sight(cell, neighbor, &is_traversable) {
+                continue;
+            }
+            // Theta*: connect the neighbor to the parent of the cell when it is visible
+            let parent = match parents[index(cell)] {
+                Some(parent) if any_angle && line_of_sight(parent, neighbor, &is_traversable) => {
+                    parent
+                }
+                _ => cell,
+            };
+            let cost = costs[index(parent)] + (position(neighbor) - position(parent)).norm();
+            if cost < costs[index(neighbor)] {
+                costs[index(neighbor)] = cost;
+                parents[index(neighbor)] = Some(parent);
+                open.push(OpenCell {
+                    estimated_cost: cost + (goal_position - position(neighbor)).norm(),
+                    cell: neighbor,
+                });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    #[test]
+    fn a_star_and_theta_star() {
+        // 7x7 grid of 1 m cells centered on (0, 0), wall on the middle column except on the top
+        // row
+        let grid = OccupancyGrid::new(Vector3::zeros(), 1., 1., 7, 7);
+        let is_traversable = |row: usize, col: usize| col != 3 || row == 6;
+        let start = Vector2::new(-3., -3.);
+        let goal = (0, 6);
+
+        let a_star = plan_path(&grid, start, goal, false, is_traversable).unwrap();
+        let theta_star = plan_path(&grid, start, goal, true, is_traversable).unwrap();
+        for path in [&a_star, &theta_star] {
+            assert_eq!(path.last(), Some(&Vector2::new(3., -3.)));
+            // Each segment of the path only crosses traversable cells
+            let cells: Vec<(usize, usize)> = std::iter::once(start)
+                .chain(path.iter().copied())
+                .map(|point| grid.pos_to_idx(point).unwrap())
+                .collect();
+            assert!(cells.windows(2).all(|segment| line_of_sight(
+                segment[0],
+                segment[1],
+                is_traversable
+            )));
+        }
+        // Only the turning points are kept
+        assert!(a_star.len() > theta_star.len());
+
+        assert!(line_of_sight((0, 0), (5, 2), is_traversable));
+        assert!(!line_of_sight((0, 0), (0, 6), is_traversable));
+        assert!(plan_path(&grid, start, goal, true, |_, col| col != 3).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+//! Recovery of poisoned locks on shared module data.
+//!
+//! A lock is poisoned when a thread panics while holding it. With the standard `.unwrap()`
+//! pattern, a panic in one module of one node then cascades into panics in every other node
+//! accessing the same data (meta-data, physics through services, ...).
+//!
+//! The extension traits of this module implement the recovery policy used by Simba:
+//! the poisoning is logged, the poison flag is cleared and the guard is returned anyway.
+//! The node which panicked is marked as failed by the simulator (see
+//! [`catch_panic`](crate::errors::catch_panic)), while the other nodes continue.
+use std::{
+    fmt::Debug,
+    sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use log::error;
+
+use super::read_only_lock::RoLock;
+
+fn log_poisoned<T: ?Sized>() {
+    error!(
+        "Recovering poisoned lock on `{}`: a thread panicked while holding it",
+        std::any::type_name::<T>()
+    );
+}
+
+/// Poison-tolerant accessors for [`RwLock`] (and so for [`SharedRwLock`](super::SharedRwLock)).
+pub trait RecoverRwLock<T: ?Sized> {
+    /// Acquire a read lock, recovering the data if the lock is poisoned.
+    fn read_recover(&self) -> RwLockReadGuard<'_, T>;
+    /// Acquire a write lock, recovering the data if the lock is poisoned.
+    fn write_recover(&self) -> RwLockWriteGuard<'_, T>;
+}
+
+impl<T: ?Sized> RecoverRwLock<T> for RwLock<T> {
+    fn read_recover(&self) -> RwLockReadGuard<'_, T> {
+        self.read().unwrap_or_else(|e| {
+            log_poisoned::<T>();
+            self.clear_poison();
+            e.into_inner()
+        })
+    }
+
+    fn write_recover(&self) -> RwLockWriteGuard<'_, T> {
+        self.write().unwrap_or_else(|e| {
+            log_poisoned::<T>();
+            self.clear_poison();
+            e.into_inner()
+        })
+    }
+}
+
+/// Poison-tolerant accessor for read-only locks (and so for [`SharedRoLock`](super::SharedRoLock)).
+pub trait RecoverRoLock<T: ?Sized> {
+    /// Acquire a read lock, recovering the data if the lock is poisoned.
+    fn read_recover(&self) -> RwLockReadGuard<'_, T>;
+}
+
+impl<T: ?Sized + Send + Sync + Debug> RecoverRoLock<T> for dyn RoLock<T> {
+    fn read_recover(&self) -> RwLockReadGuard<'_, T> {
+        self.read().unwrap_or_else(|e| {
+            log_poisoned::<T>();
+            self.clear_poison();
+            e.into_inner()
+        })
+    }
+}
+
+/// Poison-tolerant accessor for [`Mutex`] (and so for [`SharedMutex`](super::SharedMutex)).
+pub trait RecoverMutex<T: ?Sized> {
+    /// Acquire the lock, recovering the data if the lock is poisoned.
+    fn lock_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T: ?Sized> RecoverMutex<T> for Mutex<T> {
+    fn lock_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|e| {
+            log_poisoned::<T>();
+            self.clear_poison();
+            e.into_inner()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+
+    #[test]
+    fn poisoned_lock_is_recovered() {
+        let lock = Arc::new(RwLock::new(1));
+        let lock_clone = lock.clone();
+        let _ = thread::spawn(move || {
+            let mut value = lock_clone.write().unwrap();
+            *value = 2;
+            panic!("Poisoning the lock");
+        })
+        .join();
+        assert!(lock.is_poisoned());
+        assert_eq!(*lock.read_recover(), 2);
+        assert!(!lock.is_poisoned());
+        *lock.write_recover() = 3;
+        assert_eq!(*lock.read().unwrap(), 3);
+    }
+}
@@ -3,11 +3,14 @@
 //! This module provides serialization helpers plus common shared-pointer aliases.
 
 pub mod barrier;
+pub mod config_parsing;
 pub mod confy;
 pub mod determinist_random_variable;
 pub mod distributions;
 pub mod enum_tools;
 pub mod geometry;
+pub mod grid_path_planning;
+pub mod lock_recovery;
 pub mod macros;
 pub mod maths;
 pub mod numbers;
@@ -15,6 +18,8 @@ pub mod occupancy_grid;
 pub mod periodicity;
 pub mod python;
 pub mod read_only_lock;
+#[cfg(feature = "seed_audit")]
+pub mod seed_audit;
 
 use serde::Serializer;
 
@@ -51,6 +51,26 @@ impl OccupancyGrid {
         }
     }
 
+    /// Number of rows of the grid.
+    pub fn nb_rows(&self) -> usize {
+        self.nb_rows
+    }
+
+    /// Number of columns of the grid.
+    pub fn nb_cols(&self) -> usize {
+        self.nb_cols
+    }
+
+    /// Height of one cell (on the y axis of the grid).
+    pub fn cell_height(&self) -> f32 {
+        self.cell_height
+    }
+
+    /// Width of one cell (on the x axis of the grid).
+    pub fn cell_width(&self) -> f32 {
+        self.cell_width
+    }
+
     /// Get a cell value by `(row, col)` indices.
     ///
     /// Returns `None` if indices are out of bounds.
@@ -94,6 +114,21 @@ impl OccupancyGrid {
         }
     }
 
+    /// Converts grid (row, col) indices to the world position of the center of the cell.
+    ///
+    /// Returns `None` if the indices are out of bounds.
+    pub fn idx_to_pos(&self, row: usize, col: usize) -> Option<Vector2<f32>> {
+        if row >= self.nb_rows || col >= self.nb_cols {
+            return None;
+        }
+        let local = Vector2::new(
+            (col as f32 + 0.5) * self.cell_width - (self.nb_cols as f32 * self.cell_width) / 2.0,
+            (row as f32 + 0.5) * self.cell_height - (self.nb_rows as f32 * self.cell_height) / 2.0,
+        );
+        let rot = Rotation2::new(self.center.z);
+        Some(rot * local + self.center.xy())
+    }
+
     /// Get a cell value from a world position.
     ///
     /// Returns `None` if the position is outside the grid.
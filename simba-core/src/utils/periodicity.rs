@@ -307,6 +307,23 @@ impl Periodicity {
         self.next_activation_time
     }
 
+    /// Replace the period by a fixed `period` and schedule the next activation from `time`.
+    ///
+    /// With a periodic table, the new period only applies from the next table cycle.
+    pub fn set_period(&mut self, period: f32, time: f32) {
+        let period = round_precision(period.max(TIME_ROUND), TIME_ROUND).unwrap();
+        self.period =
+            DeterministRandomVariable::Fixed(DeterministFixedRandomVariable::from_config(
+                0.,
+                FixedRandomVariableConfig {
+                    values: vec![period],
+                },
+            ));
+        if self.periodic_table.is_none() {
+            self.next_activation_time = round_precision(time + period, TIME_ROUND).unwrap();
+        }
+    }
+
     /// Advance the schedule using the current simulation `time`.
     ///
     /// When `time` reaches the current activation, the next activation is computed
@@ -12,10 +12,17 @@ pub trait RoLock<T: ?Sized + Send + Sync + Debug>: Send + Sync + Debug {
     ///
     /// Returns a `LockResult` containing a `RwLockReadGuard` if the lock was successfully acquired, or an error if the lock is poisoned.
     fn read(&self) -> LockResult<RwLockReadGuard<'_, T>>;
+
+    /// Clear the poisoned state of the underlying lock, if supported.
+    fn clear_poison(&self) {}
 }
 
 impl<T: ?Sized + Send + Sync + Debug> RoLock<T> for RwLock<T> {
     fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
         self.read()
     }
+
+    fn clear_poison(&self) {
+        self.clear_poison()
+    }
 }
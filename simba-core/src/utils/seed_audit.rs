@@ -0,0 +1,300 @@
+/*!
+Seed isolation audit, enabled with the `seed_audit` feature.
+
+Each random variable created by the [`DeterministRandomVariableFactory`](super::determinist_random_variable::DeterministRandomVariableFactory)
+is tagged with its consumer path: the source location where it was created, followed by the
+creation index at this location (e.g. `simba-core/src/sensors/fault_models/additive.rs:236:22#3`).
+Every draw is recorded in a [`SeedAudit`], which can be saved and compared with the audit of
+another run using the same seed. If the simulation is deterministic, each consumer gets exactly
+the same sequence of draws.
+
+The comparison gives, for each consumer, the first divergent draw, which points to the module
+breaking the determinism (e.g. a random variable created in a nondeterministic order, or
+drawn with a time depending on the thread scheduling).
+
+The audit can be run with `simba-tools`:
+```bash
+cargo run -p simba-tools --features seed_audit -- --seed-audit config.yaml
+```
+*/
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    panic::Location,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    utils::determinist_random_variable::DeterministRandomVariable,
+};
+
+/// One random draw of a consumer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SeedAuditDraw {
+    /// Time given to the random variable.
+    pub time: f32,
+    /// Drawn values.
+    pub values: Vec<f32>,
+}
+
+/// Difference between the draws of a consumer in two runs.
+#[derive(Debug, Clone)]
+pub struct SeedAuditDivergence {
+    /// Consumer path.
+    pub consumer: String,
+    /// Index of the first divergent draw.
+    pub index: usize,
+    /// Draw of the reference run, `None` if the reference run has fewer draws.
+    pub reference: Option<SeedAuditDraw>,
+    /// Draw of the compared run, `None` if the compared run has fewer draws.
+    pub other: Option<SeedAuditDraw>,
+}
+
+impl Display for SeedAuditDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let draw_to_string = |draw: &Option<SeedAuditDraw>| match draw {
+            Some(d) => format!("time {} -> {:?}", d.time, d.values),
+            None => String::from("no draw"),
+        };
+        write!(
+            f,
+            "{}: draw {} differs\n\treference: {}\n\tother:     {}",
+            self.consumer,
+            self.index,
+            draw_to_string(&self.reference),
+            draw_to_string(&self.other)
+        )
+    }
+}
+
+/// Draws of every consumer during a run.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SeedAudit {
+    /// Global seed of the run.
+    pub seed: f32,
+    /// Draws, in order, by consumer path.
+    pub consumers: BTreeMap<String, Vec<SeedAuditDraw>>,
+}
+
+impl SeedAudit {
+    /// Save the audit as JSON.
+    pub fn save(&self, path: &Path) -> SimbaResult<()> {
+        let content = serde_json::to_string(self).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ImplementationError,
+                format!("Error during json serialization of the seed audit: {e}"),
+            )
+        })?;
+        fs::write(path, content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to write seed audit file '{}': {}",
+                    path.to_str().unwrap_or_default(),
+                    e
+                ),
+            )
+        })
+    }
+
+    /// Load an audit saved with [`SeedAudit::save`].
+    pub fn load(path: &Path) -> SimbaResult<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!(
+                    "Impossible to read seed audit file '{}': {}",
+                    path.to_str().unwrap_or_default(),
+                    e
+                ),
+            )
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::ConfigError,
+                format!("Error while parsing seed audit: {e}"),
+            )
+        })
+    }
+
+    /// Compare the draws with `other`, and return the first divergence of each consumer.
+    ///
+    /// Consumers present in only one run are reported with a divergence at index 0.
+    pub fn compare(&self, other: &SeedAudit) -> Vec<SeedAuditDivergence> {
+        let empty = Vec::new();
+        let mut consumers: Vec<&String> = self
+            .consumers
+            .keys()
+            .chain(other.consumers.keys())
+            .collect();
+        consumers.sort();
+        consumers.dedup();
+        let mut divergences = Vec::new();
+        for consumer in consumers {
+            let reference = self.consumers.get(consumer).unwrap_or(&empty);
+            let compared = other.consumers.get(consumer).unwrap_or(&empty);
+            let n = reference.len().max(compared.len());
+            if let Some(index) = (0..n).find(|i| reference.get(*i) != compared.get(*i)) {
+                divergences.push(SeedAuditDivergence {
+                    consumer: consumer.clone(),
+                    index,
+                    reference: reference.get(index).cloned(),
+                    other: compared.get(index).cloned(),
+                });
+            }
+        }
+        divergences
+    }
+
+    /// Compare with `other` and build a human readable report.
+    pub fn diff_report(&self, other: &SeedAudit) -> String {
+        let divergences = self.compare(other);
+        let mut report = format!(
+            "Seed audit: {} consumers, {} draws (reference, seed {}) / {} consumers, {} draws (other, seed {})\n",
+            self.consumers.len(),
+            self.consumers.values().map(|d| d.len()).sum::<usize>(),
+            self.seed,
+            other.consumers.len(),
+            other.consumers.values().map(|d| d.len()).sum::<usize>(),
+            other.seed,
+        );
+        if divergences.is_empty() {
+            report += "No divergence: identical draw sequences for every consumer.\n";
+        } else {
+            report += &format!("{} divergent consumers:\n", divergences.len());
+            for divergence in divergences {
+                report += &format!("{divergence}\n");
+            }
+        }
+        report
+    }
+}
+
+/// Recorder of the draws, owned by the random variable factory.
+#[derive(Debug)]
+pub struct SeedAuditRecorder {
+    audit: Arc<Mutex<SeedAudit>>,
+    creation_counts: BTreeMap<String, usize>,
+}
+
+impl SeedAuditRecorder {
+    /// Create an empty recorder for a run with the given `seed`.
+    pub fn new(seed: f32) -> Self {
+        Self {
+            audit: Arc::new(Mutex::new(SeedAudit {
+                seed,
+                consumers: BTreeMap::new(),
+            })),
+            creation_counts: BTreeMap::new(),
+        }
+    }
+
+    /// Clear the recorded draws, for a new run with the given `seed`.
+    pub fn reset(&mut self, seed: f32) {
+        self.creation_counts.clear();
+        *self.audit.lock().unwrap() = SeedAudit {
+            seed,
+            consumers: BTreeMap::new(),
+        };
+    }
+
+    /// Wrap `variable` to record its draws, with a consumer path built from `location`.
+    pub fn audit_variable(
+        &mut self,
+        variable: DeterministRandomVariable,
+        location: &Location,
+    ) -> AuditedRandomVariable {
+        let location = format!(
+            "{}:{}:{}",
+            location.file(),
+            location.line(),
+            location.column()
+        );
+        let count = self.creation_counts.entry(location.clone()).or_default();
+        let consumer = format!("{location}#{count}");
+        *count += 1;
+        AuditedRandomVariable {
+            consumer,
+            variable: Box::new(variable),
+            audit: self.audit.clone(),
+        }
+    }
+
+    /// Copy of the draws recorded since the last reset.
+    pub fn audit(&self) -> SeedAudit {
+        self.audit.lock().unwrap().clone()
+    }
+}
+
+/// Random variable recording its draws in a [`SeedAudit`].
+#[derive(Debug, Clone)]
+pub struct AuditedRandomVariable {
+    consumer: String,
+    variable: Box<DeterministRandomVariable>,
+    audit: Arc<Mutex<SeedAudit>>,
+}
+
+impl AuditedRandomVariable {
+    /// Generate one sample with the wrapped variable, and record it.
+    pub fn generate(&self, time: f32) -> Vec<f32> {
+        let values = self.variable.generate(time);
+        self.audit
+            .lock()
+            .unwrap()
+            .consumers
+            .entry(self.consumer.clone())
+            .or_default()
+            .push(SeedAuditDraw {
+                time,
+                values: values.clone(),
+            });
+        values
+    }
+
+    /// Return the output dimension of the wrapped random variable.
+    pub fn dim(&self) -> usize {
+        self.variable.dim()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audit(draws: &[(&str, f32, f32)]) -> SeedAudit {
+        let mut audit = SeedAudit::default();
+        for (consumer, time, value) in draws {
+            audit
+                .consumers
+                .entry(consumer.to_string())
+                .or_default()
+                .push(SeedAuditDraw {
+                    time: *time,
+                    values: vec![*value],
+                });
+        }
+        audit
+    }
+
+    #[test]
+    fn compare_reports_first_divergence() {
+        let reference = audit(&[("a", 0., 1.), ("a", 1., 2.), ("b", 0., 3.)]);
+        assert!(reference.compare(&reference.clone()).is_empty());
+
+        let other = audit(&[("a", 0., 1.), ("a", 1., 5.), ("c", 0., 3.)]);
+        let divergences = reference.compare(&other);
+        assert_eq!(divergences.len(), 3);
+        assert_eq!(divergences[0].consumer, "a");
+        assert_eq!(divergences[0].index, 1);
+        assert_eq!(divergences[1].consumer, "b");
+        assert!(divergences[1].other.is_none());
+        assert_eq!(divergences[2].consumer, "c");
+        assert!(divergences[2].reference.is_none());
+    }
+}
@@ -0,0 +1,612 @@
+#![warn(missing_docs)]
+#![deny(rustdoc::broken_intra_doc_links)]
+
+//! Stable API to write SiMBA plugins.
+//!
+//! This crate re-exports only the traits and types a plugin needs: the module traits with their
+//! records, the types exchanged with the simulator and helpers for the configuration. Plugins
+//! depending on this crate instead of `simba` are not affected by the refactors of the simulator
+//! internals.
+//!
+//! The modules are given to the simulator by a [`Plugin`](plugin::Plugin), wrapped in a
+//! [`StablePlugin`](plugin::StablePlugin): the arguments of the modules are gathered in a
+//! [`ModuleContext`](plugin::ModuleContext), which keeps the plugins compiling when the
+//! signatures of the simulator [`PluginAPI`] change.
+//!
+//! The crate is versioned independently of `simba` ([`API_VERSION`]), following semver: the
+//! paths of this crate are kept when the items move in `simba`, and a breaking change of the
+//! re-exported items (trait method, field of an exchanged type) bumps the major version. Adding an
+//! item, or a trait method with a default implementation, is a minor version. The `simba`
+//! dependency is pinned to a minor version, so that a new `simba` version is only used once this
+//! crate is checked against it.
+//!
+//! # Example
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use serde::{Deserialize, Serialize};
+//! use simba_plugin_api::prelude::*;
+//!
+//! #[derive(Debug, Default, Serialize, Deserialize)]
+//! struct MyNavigatorConfig {
+//!     speed: f32,
+//! }
+//!
+//! #[derive(Debug)]
+//! struct MyNavigator {
+//!     config: MyNavigatorConfig,
+//! }
+//!
+//! impl Navigator for MyNavigator {
+//!     fn compute_error(&mut self, _node: &mut Node, _state: WorldState) -> ControllerError {
+//!         ControllerError {
+//!             velocity: self.config.speed,
+//!             ..Default::default()
+//!         }
+//!     }
+//!
+//!     fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+//! }
+//!
+//! impl Recordable<NavigatorRecord> for MyNavigator {
+//!     fn record(&self) -> NavigatorRecord {
+//!         external_navigator_record(&self.config)
+//!     }
+//! }
+//!
+//! struct MyPlugin;
+//!
+//! impl Plugin for MyPlugin {
+//!     fn navigator(
+//!         &self,
+//!         config: &serde_json::Value,
+//!         context: &ModuleContext,
+//!     ) -> Box<dyn Navigator> {
+//!         let config = context.parse_config(config).unwrap_or_else(|e| panic!("{e}"));
+//!         Box::new(MyNavigator { config })
+//!     }
+//! }
+//!
+//! fn main() {
+//!     Simulator::init_environment();
+//!     let mut simulator = Simulator::from_config_path(
+//!         std::path::Path::new("config.yaml"),
+//!         Some(Arc::new(StablePlugin(MyPlugin))),
+//!     )
+//!     .unwrap();
+//!     simulator.run().unwrap();
+//! }
+//! ```
+
+pub use simba::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    networking::{network::Network, reconfigure::ReconfigureMessage, service::HasService},
+    node::{
+        Node,
+        capabilities::{Capabilities, CommandKind, NodeService, ObservationKind},
+    },
+    plugin_api::PluginAPI,
+    recordable::Recordable,
+    simulator::{Simulator, SimulatorConfig},
+    utils::{SharedRwLock, determinist_random_variable::DeterministRandomVariableFactory},
+};
+
+/// Version of this API, independent of the version of `simba`.
+pub const API_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of `simba` behind this API.
+pub const SIMBA_VERSION: &str = simba::VERSION;
+
+/// Navigator module: computes the error to the goal from the world state.
+pub mod navigator {
+    pub use simba::navigators::{
+        Navigator, NavigatorRecord, external_navigator::ExternalNavigatorRecord,
+    };
+
+    /// Record of an external navigator, from its serializable record.
+    pub fn external_navigator_record(record: &impl serde::Serialize) -> NavigatorRecord {
+        NavigatorRecord::External(ExternalNavigatorRecord {
+            record: crate::config::to_value(record),
+        })
+    }
+}
+
+/// Controller module: computes the command from the navigator error.
+pub mod controller {
+    pub use simba::controllers::{
+        Controller, ControllerError, ControllerRecord,
+        external_controller::ExternalControllerRecord,
+    };
+
+    /// Record of an external controller, from its serializable record.
+    pub fn external_controller_record(record: &impl serde::Serialize) -> ControllerRecord {
+        ControllerRecord::External(ExternalControllerRecord {
+            record: crate::config::to_value(record),
+        })
+    }
+}
+
+/// State estimator module: builds the world state from the commands and the observations.
+pub mod state_estimator {
+    pub use simba::state_estimators::{
+        State, StateEstimator, StateEstimatorRecord, StateRecord, WorldState, WorldStateRecord,
+        external_estimator::ExternalEstimatorRecord,
+    };
+
+    /// Record of an external state estimator, from its serializable record.
+    pub fn external_estimator_record(record: &impl serde::Serialize) -> StateEstimatorRecord {
+        StateEstimatorRecord::External(ExternalEstimatorRecord {
+            record: crate::config::to_value(record),
+        })
+    }
+}
+
+/// Physics module: moves the robot from the commands, and gives the real state.
+pub mod physics {
+    pub use simba::physics::{
+        GetRealStateReq, GetRealStateResp, Physics, PhysicsRecord,
+        cosimulation::{CoSimulatedPhysics, CoSimulationPhysicsConfig, PhysicsEngine},
+        external_physics::ExternalPhysicsRecord,
+        robot_models::{Command, holonomic::HolonomicCommand, unicycle::UnicycleCommand},
+    };
+
+    /// Record of an external physics, from its serializable record.
+    pub fn external_physics_record(record: &impl serde::Serialize) -> PhysicsRecord {
+        PhysicsRecord::External(ExternalPhysicsRecord {
+            record: crate::config::to_value(record),
+        })
+    }
+}
+
+/// Sensor module: makes the observations, with their filters and fault models.
+pub mod sensor {
+    pub use simba::sensors::{
+        Observation, ObservationRecord, Sensor, SensorObservation, SensorRecord,
+        external_sensor::{ExternalObservation, ExternalSensorRecord},
+        fault_models::fault_model::FaultModel,
+        observation_types::{
+            ObservationField, ObservationFieldType, ObservationSchema, observation_type,
+        },
+        sensor_filters::SensorFilter,
+    };
+
+    /// Untyped observation of an external sensor, from its serializable observation.
+    pub fn external_observation(observation: &impl serde::Serialize) -> SensorObservation {
+        SensorObservation::External(ExternalObservation {
+            observation_type: None,
+            observation: crate::config::to_value(observation),
+        })
+    }
+
+    /// Observation of an external sensor of the registered type `observation_type`.
+    ///
+    /// Returns an error if the type is not registered or if the observation does not follow its
+    /// schema.
+    pub fn typed_external_observation(
+        observation_type: &str,
+        observation: &impl serde::Serialize,
+    ) -> crate::SimbaResult<SensorObservation> {
+        ExternalObservation::new(observation_type, observation).map(SensorObservation::External)
+    }
+
+    /// Record of an external sensor, from its serializable record.
+    pub fn external_sensor_record(record: &impl serde::Serialize) -> SensorRecord {
+        SensorRecord::External(ExternalSensorRecord {
+            record: crate::config::to_value(record),
+        })
+    }
+}
+
+/// Plugin interface independent of the signatures of the simulator [`PluginAPI`].
+///
+/// The arguments given to the modules are gathered in a [`ModuleContext`], so that a new
+/// argument of the [`PluginAPI`] is a new field of the context instead of a breaking change of
+/// every plugin. The [`StablePlugin`] wrapper gives a [`Plugin`] to the simulator.
+pub mod plugin {
+    use std::sync::Arc;
+
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+
+    use crate::{
+        DeterministRandomVariableFactory, Network, PluginAPI, SharedRwLock, SimulatorConfig,
+        controller::Controller,
+        navigator::Navigator,
+        physics::{Physics, PhysicsEngine},
+        sensor::{FaultModel, ObservationSchema, Sensor, SensorFilter},
+        state_estimator::StateEstimator,
+    };
+
+    /// Context of the creation of a plugin module.
+    #[non_exhaustive]
+    pub struct ModuleContext<'a> {
+        /// Full configuration of the simulator.
+        pub global_config: &'a SimulatorConfig,
+        /// Factory of the determinist random variables of the module.
+        pub va_factory: &'a Arc<DeterministRandomVariableFactory>,
+        /// Network of the node, to send messages. `None` for the modules created outside of a
+        /// node (sensor filters, fault models and physics engines).
+        pub network: Option<&'a SharedRwLock<Network>>,
+        /// Initial time of the simulation.
+        pub initial_time: f32,
+    }
+
+    impl ModuleContext<'_> {
+        /// Deserialize the `config` of the module, see [`parse_config`](crate::config::parse_config).
+        pub fn parse_config<T: DeserializeOwned>(&self, config: &Value) -> Result<T, String> {
+            crate::config::parse_config(config, self.global_config)
+        }
+    }
+
+    /// Trait to give the plugin modules to the simulator, wrapped in a [`StablePlugin`].
+    ///
+    /// Each method receives the configuration of the external module, given as a JSON value, and
+    /// the [`ModuleContext`]. The methods of the modules not provided by the plugin panic.
+    #[allow(unused_variables)]
+    pub trait Plugin: Send + Sync {
+        /// [`StateEstimator`] of the `External` state estimators.
+        fn state_estimator(
+            &self,
+            config: &Value,
+            context: &ModuleContext,
+        ) -> Box<dyn StateEstimator> {
+            panic!("The given plugin does not provide a state estimator");
+        }
+
+        /// [`Controller`] of the `External` controllers.
+        fn controller(&self, config: &Value, context: &ModuleContext) -> Box<dyn Controller> {
+            panic!("The given plugin does not provide a controller");
+        }
+
+        /// [`Navigator`] of the `External` navigators.
+        fn navigator(&self, config: &Value, context: &ModuleContext) -> Box<dyn Navigator> {
+            panic!("The given plugin does not provide a navigator");
+        }
+
+        /// [`Physics`] of the `External` physics.
+        fn physics(&self, config: &Value, context: &ModuleContext) -> Box<dyn Physics> {
+            panic!("The given plugin does not provide physics");
+        }
+
+        /// [`PhysicsEngine`] of the `External` engines of the co-simulated physics.
+        fn physics_engine(
+            &self,
+            config: &Value,
+            context: &ModuleContext,
+        ) -> Box<dyn PhysicsEngine> {
+            panic!("The given plugin does not provide physics engines");
+        }
+
+        /// [`Sensor`] of the `External` sensors.
+        fn sensor(&self, config: &Value, context: &ModuleContext) -> Box<dyn Sensor> {
+            panic!("The given plugin does not provide a sensor");
+        }
+
+        /// [`SensorFilter`] of the `External` sensor filters.
+        fn sensor_filter(&self, config: &Value, context: &ModuleContext) -> Box<dyn SensorFilter> {
+            panic!("The given plugin does not provide a sensor filter");
+        }
+
+        /// [`FaultModel`] of the `External` sensor faults.
+        fn sensor_fault(&self, config: &Value, context: &ModuleContext) -> Box<dyn FaultModel> {
+            panic!("The given plugin does not provide a sensor fault model");
+        }
+
+        /// Observation types of the plugin sensors, registered when the configuration is loaded.
+        fn observation_types(&self) -> Vec<ObservationSchema> {
+            Vec::new()
+        }
+    }
+
+    /// [`PluginAPI`] of a [`Plugin`], to give to the [`Simulator`](crate::Simulator).
+    ///
+    /// ```ignore
+    /// Simulator::from_config_path(path, Some(Arc::new(StablePlugin(MyPlugin))))
+    /// ```
+    pub struct StablePlugin<P: Plugin>(pub P);
+
+    impl<P: Plugin> PluginAPI for StablePlugin<P> {
+        fn get_state_estimator(
+            &self,
+            config: &Value,
+            global_config: &SimulatorConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            network: &SharedRwLock<Network>,
+            initial_time: f32,
+        ) -> Box<dyn StateEstimator> {
+            self.0.state_estimator(
+                config,
+                &ModuleContext {
+                    global_config,
+                    va_factory,
+                    network: Some(network),
+                    initial_time,
+                },
+            )
+        }
+
+        fn get_controller(
+            &self,
+            config: &Value,
+            global_config: &SimulatorConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            network: &SharedRwLock<Network>,
+            initial_time: f32,
+        ) -> Box<dyn Controller> {
+            self.0.controller(
+                config,
+                &ModuleContext {
+                    global_config,
+                    va_factory,
+                    network: Some(network),
+                    initial_time,
+                },
+            )
+        }
+
+        fn get_navigator(
+            &self,
+            config: &Value,
+            global_config: &SimulatorConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            network: &SharedRwLock<Network>,
+            initial_time: f32,
+        ) -> Box<dyn Navigator> {
+            self.0.navigator(
+                config,
+                &ModuleContext {
+                    global_config,
+                    va_factory,
+                    network: Some(network),
+                    initial_time,
+                },
+            )
+        }
+
+        fn get_physics(
+            &self,
+            config: &Value,
+            global_config: &SimulatorConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            network: &SharedRwLock<Network>,
+            initial_time: f32,
+        ) -> Box<dyn Physics> {
+            self.0.physics(
+                config,
+                &ModuleContext {
+                    global_config,
+                    va_factory,
+                    network: Some(network),
+                    initial_time,
+                },
+            )
+        }
+
+        fn get_physics_engine(
+            &self,
+            config: &Value,
+            global_config: &SimulatorConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            initial_time: f32,
+        ) -> Box<dyn PhysicsEngine> {
+            self.0.physics_engine(
+                config,
+                &ModuleContext {
+                    global_config,
+                    va_factory,
+                    network: None,
+                    initial_time,
+                },
+            )
+        }
+
+        fn observation_types(&self) -> Vec<ObservationSchema> {
+            self.0.observation_types()
+        }
+
+        fn get_sensor(
+            &self,
+            config: &Value,
+            global_config: &SimulatorConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            network: &SharedRwLock<Network>,
+            initial_time: f32,
+        ) -> Box<dyn Sensor> {
+            self.0.sensor(
+                config,
+                &ModuleContext {
+                    global_config,
+                    va_factory,
+                    network: Some(network),
+                    initial_time,
+                },
+            )
+        }
+
+        fn get_sensor_filter(
+            &self,
+            config: &Value,
+            global_config: &SimulatorConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            initial_time: f32,
+        ) -> Box<dyn SensorFilter> {
+            self.0.sensor_filter(
+                config,
+                &ModuleContext {
+                    global_config,
+                    va_factory,
+                    network: None,
+                    initial_time,
+                },
+            )
+        }
+
+        fn get_sensor_fault(
+            &self,
+            config: &Value,
+            global_config: &SimulatorConfig,
+            va_factory: &Arc<DeterministRandomVariableFactory>,
+            initial_time: f32,
+        ) -> Box<dyn FaultModel> {
+            self.0.sensor_fault(
+                config,
+                &ModuleContext {
+                    global_config,
+                    va_factory,
+                    network: None,
+                    initial_time,
+                },
+            )
+        }
+    }
+}
+
+/// Helpers for the configurations and records of the plugin modules, given as JSON values.
+pub mod config {
+    use serde::{Serialize, de::DeserializeOwned};
+    use serde_json::Value;
+
+    use crate::SimulatorConfig;
+
+    /// Deserialize the `config` of an external module given to the [`PluginAPI`](crate::PluginAPI).
+    ///
+    /// The error message gives the location of the module configuration in the configuration
+    /// file (`file:line:column`).
+    pub fn parse_config<T: DeserializeOwned>(
+        config: &Value,
+        global_config: &SimulatorConfig,
+    ) -> Result<T, String> {
+        serde_json::from_value(config.clone())
+            .map_err(|e| global_config.plugin_config_error(config, e))
+    }
+
+    /// JSON value of a record or an observation.
+    ///
+    /// Panics if the value cannot be serialized (e.g. map with non-string keys).
+    pub fn to_value(value: &impl Serialize) -> Value {
+        serde_json::to_value(value)
+            .unwrap_or_else(|e| panic!("Plugin record cannot be serialized: {e}"))
+    }
+}
+
+/// Everything needed to write a plugin: `use simba_plugin_api::prelude::*;`.
+pub mod prelude {
+    pub use crate::{
+        Capabilities, CommandKind, DeterministRandomVariableFactory, HasService, Network, Node,
+        NodeService, ObservationKind, PluginAPI, Recordable, SharedRwLock, SimbaResult, Simulator,
+        SimulatorConfig,
+        controller::*,
+        navigator::*,
+        physics::*,
+        plugin::{ModuleContext, Plugin, StablePlugin},
+        sensor::*,
+        state_estimator::*,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use simba::{
+        logger::LogLevel,
+        navigators::{NavigatorConfig, external_navigator::ExternalNavigatorConfig},
+        node::node_factory::{NodeRecord, RobotConfig},
+    };
+
+    use super::{config::parse_config, prelude::*};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct MyConfig {
+        period: f32,
+    }
+
+    #[test]
+    fn config_and_records() {
+        let global_config = SimulatorConfig::default();
+        let config: MyConfig = parse_config(&json!({"period": 0.5}), &global_config).unwrap();
+        assert_eq!(config, MyConfig { period: 0.5 });
+        assert!(parse_config::<MyConfig>(&json!({"speed": 1.}), &global_config).is_err());
+
+        let NavigatorRecord::External(record) = external_navigator_record(&config) else {
+            panic!("External record expected");
+        };
+        assert_eq!(record.record, json!({"period": 0.5}));
+        let SensorObservation::External(observation) = external_observation(&config) else {
+            panic!("External observation expected");
+        };
+        assert_eq!(observation.observation, json!({"period": 0.5}));
+    }
+
+    #[derive(Debug)]
+    struct ConstantNavigator {
+        config: MyConfig,
+        initial_time: f32,
+    }
+
+    impl Navigator for ConstantNavigator {
+        fn compute_error(&mut self, _node: &mut Node, _state: WorldState) -> ControllerError {
+            ControllerError {
+                velocity: self.config.period,
+                ..Default::default()
+            }
+        }
+
+        fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+    }
+
+    impl Recordable<NavigatorRecord> for ConstantNavigator {
+        fn record(&self) -> NavigatorRecord {
+            external_navigator_record(&json!({"initial_time": self.initial_time}))
+        }
+    }
+
+    struct MyPlugin;
+
+    impl Plugin for MyPlugin {
+        fn navigator(
+            &self,
+            config: &serde_json::Value,
+            context: &ModuleContext,
+        ) -> Box<dyn Navigator> {
+            assert!(context.network.is_some());
+            Box::new(ConstantNavigator {
+                config: context.parse_config(config).unwrap(),
+                initial_time: context.initial_time,
+            })
+        }
+    }
+
+    #[test]
+    fn stable_plugin_gives_the_modules() {
+        let mut config = SimulatorConfig::default();
+        config.log.log_level = LogLevel::Off;
+        config.max_time = 0.5;
+        config.robots.push(RobotConfig {
+            name: "node1".to_string(),
+            navigator: NavigatorConfig::External(ExternalNavigatorConfig {
+                config: json!({"period": 0.5}),
+            }),
+            ..Default::default()
+        });
+
+        let mut simulator =
+            Simulator::from_config(&config, Some(Arc::new(StablePlugin(MyPlugin)))).unwrap();
+        simulator.run().unwrap();
+
+        let records = simulator.get_records(false);
+        assert!(!records.is_empty());
+        for record in records {
+            let NodeRecord::Robot(robot) = record.node else {
+                continue;
+            };
+            let NavigatorRecord::External(navigator) = robot.navigator else {
+                panic!("External navigator expected");
+            };
+            assert_eq!(navigator.record, json!({"initial_time": 0.}));
+        }
+    }
+}
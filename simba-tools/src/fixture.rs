@@ -0,0 +1,404 @@
+//! Generation of unit-test fixtures from a result file.
+//!
+//! A bug seen in a large run is easier to fix with a minimal test. The inputs of one module of a
+//! node at a chosen time are extracted from the records, and written with the recorded output in
+//! a standalone Rust test or Python script, which calls the module step with these inputs.
+//!
+//! The step used is the last record of the node at or before the requested time. The inputs are:
+//! - controller: the error of the navigator (`navigator.error`), recorded output `controller.command`;
+//! - navigator: the estimated world state (`state_estimator.world_state`), recorded output
+//!   `navigator.error`;
+//! - state estimator: the command of the previous record (`controller.command`) and the
+//!   observations of the step (`sensors.last_observations`), recorded output
+//!   `state_estimator.world_state`.
+//!
+//! Rust modules need a full [`Node`](simba::node::Node), so the Rust fixture calls an entry point
+//! given by the user, which runs the step of the module:
+//! - controller: `fn(&ControllerError, f32) -> Command`;
+//! - navigator: `fn(&WorldStateRecord, f32) -> ControllerError`;
+//! - state estimator: `fn(&Command, &[ObservationRecord], f32) -> WorldStateRecord`.
+//!
+//! The Python fixture loads the module class from a file (`path/to/module.py:Class`) and calls
+//! its methods without node (`None`). The inputs are given as their record, with attribute access.
+//! The output is compared to the recorded one field by field (attributes or keys), with a
+//! tolerance on the numbers ([`PYTHON_REL_TOL`] and [`PYTHON_ABS_TOL`]): the script fails with the
+//! path of the first difference.
+
+use std::{io::Write, path::Path};
+
+use serde_json::Value;
+use simba::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    simulator::{Record, stream_results},
+};
+
+use crate::query::lookup;
+
+/// Relative tolerance of the number comparisons of the Python fixture.
+pub const PYTHON_REL_TOL: f64 = 1e-5;
+/// Absolute tolerance of the number comparisons of the Python fixture.
+pub const PYTHON_ABS_TOL: f64 = 1e-6;
+
+/// Recursive comparison of the module output to the recorded one, in the Python fixture.
+const PYTHON_ASSERT_CLOSE: &str = r#"def assert_close(output, expected, path="output"):
+    """Compare the output to the recorded one, with a tolerance on the numbers."""
+    if isinstance(expected, SimpleNamespace):
+        for key, value in vars(expected).items():
+            if isinstance(output, dict):
+                assert key in output, f"{path}: no `{key}` in the output"
+                item = output[key]
+            else:
+                assert hasattr(output, key), f"{path}: no `{key}` in the output"
+                item = getattr(output, key)
+            assert_close(item, value, f"{path}.{key}")
+    elif isinstance(expected, list):
+        output = list(output)
+        assert len(output) == len(expected), (
+            f"{path}: {len(output)} items, {len(expected)} recorded"
+        )
+        for i, (item, value) in enumerate(zip(output, expected)):
+            assert_close(item, value, f"{path}[{i}]")
+    elif isinstance(expected, (int, float)) and not isinstance(expected, bool):
+        assert math.isclose(output, expected, rel_tol=REL_TOL, abs_tol=ABS_TOL), (
+            f"{path}: {output}, {expected} recorded"
+        )
+    else:
+        assert output == expected, f"{path}: {output!r}, {expected!r} recorded"
+"#;
+
+/// Module for which the fixture is generated.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum FixtureModule {
+    /// Command computed from the navigator error.
+    Controller,
+    /// Error computed from the estimated world state.
+    Navigator,
+    /// Prediction and correction steps of the state estimator.
+    StateEstimator,
+}
+
+/// Language of the generated fixture.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum FixtureLang {
+    /// Rust test, calling a user function.
+    Rust,
+    /// Python script, calling a user module class.
+    Python,
+}
+
+/// Module step to extract, and how to call it.
+pub struct FixtureRequest {
+    /// Node of the module.
+    pub node: String,
+    /// Time of the step. The last record at or before this time is used.
+    pub time: f32,
+    /// Module whose step is extracted.
+    pub module: FixtureModule,
+    /// Language of the fixture.
+    pub lang: FixtureLang,
+    /// Function called by the Rust fixture (e.g. `my_crate::controller_step`), or module class
+    /// loaded by the Python fixture (e.g. `controller.py:Controller`).
+    pub entry_point: String,
+    /// Configuration given to the Python module class, as JSON.
+    pub config: String,
+}
+
+/// Inputs and recorded output of a module step.
+#[derive(Debug)]
+struct ModuleStep {
+    time: f32,
+    inputs: Vec<(&'static str, Value)>,
+    expected: Value,
+}
+
+fn field(record: &Value, path: &str, time: f32) -> SimbaResult<Value> {
+    lookup(record, path).cloned().ok_or_else(|| {
+        SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!("Field `{path}` not found in the record at time {time}"),
+        )
+    })
+}
+
+/// Inputs and recorded output of `module`, from the record of the step and the previous one.
+fn module_step(
+    module: FixtureModule,
+    previous: Option<&Value>,
+    step: &Value,
+    time: f32,
+) -> SimbaResult<ModuleStep> {
+    let (inputs, expected) = match module {
+        FixtureModule::Controller => (
+            vec![("error", field(step, "navigator.error", time)?)],
+            field(step, "controller.command", time)?,
+        ),
+        FixtureModule::Navigator => (
+            vec![(
+                "world_state",
+                field(step, "state_estimator.world_state", time)?,
+            )],
+            field(step, "navigator.error", time)?,
+        ),
+        FixtureModule::StateEstimator => {
+            let previous = previous.ok_or_else(|| {
+                SimbaError::new(
+                    SimbaErrorTypes::ConfigError,
+                    format!("No record before time {time} to get the command of the prediction"),
+                )
+            })?;
+            (
+                vec![
+                    ("command", field(previous, "controller.command", time)?),
+                    (
+                        "observations",
+                        field(step, "sensors.last_observations", time)?,
+                    ),
+                ],
+                field(step, "state_estimator.world_state", time)?,
+            )
+        }
+    };
+    Ok(ModuleStep {
+        time,
+        inputs,
+        expected,
+    })
+}
+
+/// Rust raw string literal containing `content`.
+fn raw_string(content: &str) -> String {
+    let mut hashes = String::from("#");
+    while content.contains(&format!("\"{hashes}")) {
+        hashes.push('#');
+    }
+    format!("r{hashes}\"{content}\"{hashes}")
+}
+
+/// Name usable as an identifier, made of the node name and the step time.
+fn fixture_name(request: &FixtureRequest, time: f32) -> String {
+    format!("{}_{:?}_{time}", request.node, request.module)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn rust_fixture(request: &FixtureRequest, step: &ModuleStep, source: &str) -> String {
+    let (types, call) = match request.module {
+        FixtureModule::Controller => (
+            "use simba::{controllers::ControllerError, physics::robot_models::Command};",
+            "let error: ControllerError = serde_json::from_str(ERROR).unwrap();\n    \
+             let output: Command = {entry}(&error, TIME);",
+        ),
+        FixtureModule::Navigator => (
+            "use simba::{controllers::ControllerError, state_estimators::WorldStateRecord};",
+            "let world_state: WorldStateRecord = serde_json::from_str(WORLD_STATE).unwrap();\n    \
+             let output: ControllerError = {entry}(&world_state, TIME);",
+        ),
+        FixtureModule::StateEstimator => (
+            "use simba::{\n    physics::robot_models::Command, sensors::ObservationRecord,\n    \
+             state_estimators::WorldStateRecord,\n};",
+            "let command: Command = serde_json::from_str(COMMAND).unwrap();\n    \
+             let observations: Vec<ObservationRecord> = serde_json::from_str(OBSERVATIONS).unwrap();\n    \
+             let output: WorldStateRecord = {entry}(&command, &observations, TIME);",
+        ),
+    };
+    let mut fixture = format!(
+        "//! Generated by `simba-tools fixture` from `{source}`: {:?} of node `{}` at time {}.\n\n\
+         {types}\n\nconst TIME: f32 = {:?};\n",
+        request.module, request.node, step.time, step.time
+    );
+    let expected = ("expected", step.expected.clone());
+    for (name, value) in step.inputs.iter().chain([&expected]) {
+        fixture += &format!(
+            "const {}: &str = {};\n",
+            name.to_uppercase(),
+            raw_string(&serde_json::to_string_pretty(value).unwrap())
+        );
+    }
+    fixture += &format!(
+        "\n#[test]\nfn {}() {{\n    {}\n    let expected: serde_json::Value = \
+         serde_json::from_str(EXPECTED).unwrap();\n    \
+         assert_eq!(serde_json::to_value(&output).unwrap(), expected);\n}}\n",
+        fixture_name(request, step.time),
+        call.replace("{entry}", &request.entry_point)
+    );
+    fixture
+}
+
+fn python_fixture(
+    request: &FixtureRequest,
+    step: &ModuleStep,
+    source: &str,
+) -> SimbaResult<String> {
+    let Some((path, class)) = request.entry_point.rsplit_once(':') else {
+        return Err(SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "Python entry point should be `path/to/module.py:Class`, got `{}`",
+                request.entry_point
+            ),
+        ));
+    };
+    let call = match request.module {
+        FixtureModule::Controller => "output = module.make_command(None, ERROR, TIME)",
+        FixtureModule::Navigator => "output = module.compute_error(None, WORLD_STATE)",
+        FixtureModule::StateEstimator => {
+            "module.prediction_step(None, COMMAND, TIME)\n    \
+             module.correction_step(None, OBSERVATIONS, TIME)\n    \
+             output = module.state()"
+        }
+    };
+    let mut fixture = format!(
+        "#!/bin/env python3\n\
+         \"\"\"Generated by `simba-tools fixture` from `{source}`: {:?} of node `{}` at time {}.\"\"\"\n\n\
+         import importlib.util\nimport json\nimport math\nfrom types import SimpleNamespace\n\n\
+         TIME = {:?}\nREL_TOL = {PYTHON_REL_TOL:?}\nABS_TOL = {PYTHON_ABS_TOL:?}\n\
+         CONFIG = json.loads({})\n",
+        request.module,
+        request.node,
+        step.time,
+        step.time,
+        python_string(&request.config)
+    );
+    let expected = ("expected", step.expected.clone());
+    for (name, value) in step.inputs.iter().chain([&expected]) {
+        fixture += &format!(
+            "{} = json.loads({}, object_hook=lambda d: SimpleNamespace(**d))\n",
+            name.to_uppercase(),
+            python_string(&serde_json::to_string_pretty(value).unwrap())
+        );
+    }
+    fixture += "\n\n";
+    fixture += PYTHON_ASSERT_CLOSE;
+    fixture += &format!(
+        "\n\ndef main():\n    \
+         spec = importlib.util.spec_from_file_location(\"module_under_test\", {path:?})\n    \
+         source = importlib.util.module_from_spec(spec)\n    \
+         spec.loader.exec_module(source)\n    \
+         module = source.{class}(CONFIG, TIME)\n    \
+         {call}\n    \
+         assert_close(output, EXPECTED)\n    \
+         print(\"Output matches the recorded one\")\n\n\n\
+         if __name__ == \"__main__\":\n    main()\n"
+    );
+    Ok(fixture)
+}
+
+/// Python string literal containing `content`.
+fn python_string(content: &str) -> String {
+    format!(
+        "'''{}'''",
+        content.replace('\\', "\\\\").replace("'''", "\\'\\'\\'")
+    )
+}
+
+/// Extract the step of the requested module from the result file, and write the fixture to `out`.
+pub fn generate_fixture(
+    result_path: &Path,
+    request: &FixtureRequest,
+    mut out: impl Write,
+) -> SimbaResult<()> {
+    let mut previous: Option<Value> = None;
+    let mut step: Option<(f32, Value)> = None;
+    let mut serialization_error: Option<serde_json::Error> = None;
+    stream_results(result_path, |record: Record| {
+        if serialization_error.is_some()
+            || record.node.name() != &request.node
+            || record.time > request.time
+        {
+            return;
+        }
+        match serde_json::to_value(&record.node) {
+            Ok(value) => previous = step.replace((record.time, value)).map(|(_, v)| v),
+            Err(e) => serialization_error = Some(e),
+        }
+    })?;
+    if let Some(e) = serialization_error {
+        return Err(SimbaError::new(
+            SimbaErrorTypes::UnknownError,
+            format!(
+                "Error while reading the records of node `{}`: {e}",
+                request.node
+            ),
+        ));
+    }
+    let Some((time, step)) = step else {
+        return Err(SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "No record of node `{}` at or before time {}",
+                request.node, request.time
+            ),
+        ));
+    };
+    let step = module_step(request.module, previous.as_ref(), &step, time)?;
+    let source = result_path.to_str().unwrap_or_default();
+    let fixture = match request.lang {
+        FixtureLang::Rust => rust_fixture(request, &step, source),
+        FixtureLang::Python => python_fixture(request, &step, source)?,
+    };
+    out.write_all(fixture.as_bytes()).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::UnknownError,
+            format!("Error while writing the fixture: {e}"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn state_estimator_step_uses_previous_command() {
+        let record = |speed: f32, time: f32| {
+            json!({"Robot": {
+                "controller": {"PID": {"command": {"Unicycle": {"left_wheel_speed": speed}}}},
+                "sensors": {"last_observations": [{"time": time}]},
+                "state_estimator": {"Perfect": {"world_state": {"ego": null}}},
+            }})
+        };
+        let step = module_step(
+            FixtureModule::StateEstimator,
+            Some(&record(1., 0.9)),
+            &record(2., 1.),
+            1.,
+        )
+        .unwrap();
+        assert_eq!(
+            step.inputs[0].1,
+            json!({"Unicycle": {"left_wheel_speed": 1.}})
+        );
+        assert_eq!(step.inputs[1].1, json!([{"time": 1.}]));
+        assert!(module_step(FixtureModule::StateEstimator, None, &record(2., 1.), 1.).is_err());
+        assert_eq!(raw_string("a\"#b"), "r##\"a\"#b\"##");
+    }
+
+    #[test]
+    fn python_fixture_compares_the_output() {
+        let mut request = FixtureRequest {
+            node: "node1".to_string(),
+            time: 1.,
+            module: FixtureModule::Controller,
+            lang: FixtureLang::Python,
+            entry_point: "controller.py".to_string(),
+            config: "{}".to_string(),
+        };
+        let step = ModuleStep {
+            time: 1.,
+            inputs: vec![("error", json!({"theta": 0.5}))],
+            expected: json!({"Unicycle": {"left_wheel_speed": 1.}}),
+        };
+        // No class in the entry point
+        assert!(python_fixture(&request, &step, "result.json").is_err());
+        request.entry_point = "controller.py:Controller".to_string();
+        let fixture = python_fixture(&request, &step, "result.json").unwrap();
+        assert!(fixture.contains("def assert_close(output, expected, path=\"output\"):"));
+        assert!(fixture.contains("output = module.make_command(None, ERROR, TIME)"));
+        assert!(fixture.contains("    assert_close(output, EXPECTED)\n"));
+        assert!(fixture.contains("REL_TOL = 1e-5\nABS_TOL = 1e-6\n"));
+    }
+}
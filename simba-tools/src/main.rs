@@ -1,12 +1,143 @@
 use std::fs;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+mod fixture;
+mod plugin_template;
+mod query;
+mod report;
+mod stress_config;
 
 #[derive(Parser)]
 #[command(version, about)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     #[arg(long)]
     generate_schema: Option<String>,
+    /// Generate a large-fleet stress configuration at the given path (and its map next to it)
+    #[arg(long)]
+    generate_stress_config: Option<String>,
+    /// Number of robots of the stress configuration
+    #[arg(long, default_value_t = 100, requires = "generate_stress_config")]
+    nb_robots: usize,
+    /// Distance between the robots on the starting grid of the stress configuration, in meters
+    #[arg(long, default_value_t = 2.0, requires = "generate_stress_config")]
+    spacing: f32,
+    /// Distance between the landmarks of the stress configuration map, in meters
+    #[arg(long, default_value_t = 5.0, requires = "generate_stress_config")]
+    landmark_spacing: f32,
+    /// Maximum simulated time of the stress configuration
+    #[arg(long, default_value_t = 60.0, requires = "generate_stress_config")]
+    max_time: f32,
+    /// Seed used to draw the goals of the stress configuration
+    #[arg(long, default_value_t = 0, requires = "generate_stress_config")]
+    seed: u64,
+    /// Run the given configuration twice with the same seed, and compare the random draws of each consumer
+    #[cfg(feature = "seed_audit")]
+    #[arg(long)]
+    seed_audit: Option<String>,
+    /// Compare the seed audit with this saved audit instead of running the configuration twice
+    #[cfg(feature = "seed_audit")]
+    #[arg(long, requires = "seed_audit")]
+    seed_audit_reference: Option<String>,
+    /// Save the seed audit of the first run to this path
+    #[cfg(feature = "seed_audit")]
+    #[arg(long, requires = "seed_audit")]
+    seed_audit_output: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Extract records from a result file, as CSV or JSON lines
+    Query {
+        /// Result file
+        result: String,
+        /// Only keep the records of this node
+        #[arg(long)]
+        node: Option<String>,
+        /// Field to extract, as a dot-separated path in the node record (e.g. `physics.pose`).
+        /// Can be repeated. The whole record is extracted if no field is given
+        #[arg(long)]
+        field: Vec<String>,
+        /// Only keep the records between these times (included)
+        #[arg(long, num_args = 2, value_names = ["START", "END"])]
+        between: Option<Vec<f32>>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = query::QueryFormat::Csv)]
+        format: query::QueryFormat,
+    },
+    /// Generate a unit-test fixture calling a module with its inputs at a given time of a result file
+    Fixture {
+        /// Result file
+        result: String,
+        /// Node of the module
+        #[arg(long)]
+        node: String,
+        /// Time of the step. The last record of the node at or before this time is used
+        #[arg(long)]
+        time: f32,
+        /// Module to call
+        #[arg(long, value_enum)]
+        module: fixture::FixtureModule,
+        /// Language of the fixture
+        #[arg(long, value_enum, default_value_t = fixture::FixtureLang::Rust)]
+        lang: fixture::FixtureLang,
+        /// Rust function running the module step (e.g. `my_crate::controller_step`), or Python
+        /// module class (e.g. `controller.py:Controller`)
+        #[arg(long)]
+        entry_point: String,
+        /// Configuration given to the Python module class, as JSON
+        #[arg(long, default_value = "{}")]
+        config: String,
+        /// Output file. Written to the standard output if not given
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Generate a Markdown or LaTeX report of result files: configuration summary, KPI tables
+    /// and figures
+    Report {
+        /// Result files (JSON). With several files, a comparison table is added
+        #[arg(required = true)]
+        results: Vec<String>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = report::ReportFormat::Markdown)]
+        format: report::ReportFormat,
+        /// Directory of figures to embed (e.g. made by the plotting scripts)
+        #[arg(long)]
+        figures: Option<String>,
+        /// Output file. The figures are written in the `<name>_figures` directory next to it
+        #[arg(long)]
+        output: String,
+    },
+    /// Compute the statistics of the KPIs across the runs of a directory of result files
+    /// (simulator and batch results), with the outlier runs
+    Aggregate {
+        /// Directory of the result files (JSON)
+        directory: String,
+        /// Output file. `aggregate.json` in the directory if not given
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Generate the skeleton of a plugin providing the given modules, with an example
+    /// configuration and a test
+    NewPlugin {
+        /// Name of the plugin crate or package
+        name: String,
+        /// Modules provided by the plugin, comma-separated
+        #[arg(long, value_enum, value_delimiter = ',', required = true)]
+        modules: Vec<plugin_template::PluginModule>,
+        /// Language of the plugin
+        #[arg(long, value_enum, default_value_t = plugin_template::PluginLang::Rust)]
+        lang: plugin_template::PluginLang,
+        /// Directory of the plugin, which should not exist or be empty. `<name>` if not given
+        #[arg(long)]
+        output: Option<String>,
+        /// Path of a local copy of the simba repository, whose `simba-plugin-api` is used as
+        /// dependency of a Rust plugin instead of the remote repository
+        #[arg(long)]
+        simba_path: Option<String>,
+    },
 }
 
 fn generate_schema(path: String) {
@@ -19,10 +150,227 @@ fn generate_schema(path: String) {
     println!("Schema generated at: {}", path);
 }
 
+#[cfg(feature = "seed_audit")]
+fn seed_audit(
+    config_path: String,
+    reference_path: Option<String>,
+    output_path: Option<String>,
+) -> simba::errors::SimbaResult<bool> {
+    use simba::{simulator::Simulator, utils::seed_audit::SeedAudit};
+    use std::path::Path;
+
+    Simulator::init_environment();
+    let mut simulator = Simulator::from_config_path(Path::new(&config_path), None)?;
+    let config = simulator.config();
+    simulator.run()?;
+    let audit = simulator.seed_audit();
+    if let Some(output_path) = output_path {
+        audit.save(Path::new(&output_path))?;
+        println!("Seed audit saved at: {}", output_path);
+    }
+
+    let reference = if let Some(reference_path) = reference_path {
+        SeedAudit::load(Path::new(&reference_path))?
+    } else {
+        // Second run with the seed of the first one
+        let mut simulator = Simulator::from_config(&config, None)?;
+        simulator.run()?;
+        simulator.seed_audit()
+    };
+    print!("{}", reference.diff_report(&audit));
+    Ok(reference.compare(&audit).is_empty())
+}
+
 fn main() {
     let args = Cli::parse();
 
+    if let Some(Command::Fixture {
+        result,
+        node,
+        time,
+        module,
+        lang,
+        entry_point,
+        config,
+        output,
+    }) = args.command
+    {
+        let request = fixture::FixtureRequest {
+            node,
+            time,
+            module,
+            lang,
+            entry_point,
+            config,
+        };
+        let result_path = std::path::Path::new(&result);
+        let res = match output {
+            Some(path) => fs::File::create(&path)
+                .map_err(|e| {
+                    simba::errors::SimbaError::new(
+                        simba::errors::SimbaErrorTypes::ConfigError,
+                        format!("Impossible to create fixture file '{path}': {e}"),
+                    )
+                })
+                .and_then(|file| fixture::generate_fixture(result_path, &request, file)),
+            None => fixture::generate_fixture(result_path, &request, std::io::stdout().lock()),
+        };
+        if let Err(e) = res {
+            eprintln!("{}", e.detailed_error());
+            std::process::exit(2);
+        }
+        return;
+    }
+
+    if let Some(Command::Report {
+        results,
+        format,
+        figures,
+        output,
+    }) = args.command
+    {
+        let request = report::ReportRequest {
+            format,
+            figures: figures.map(std::path::PathBuf::from),
+        };
+        let results: Vec<std::path::PathBuf> =
+            results.iter().map(std::path::PathBuf::from).collect();
+        if let Err(e) = report::generate_report(&results, &request, std::path::Path::new(&output)) {
+            eprintln!("{}", e.detailed_error());
+            std::process::exit(2);
+        }
+        println!("Report generated at: {}", output);
+        return;
+    }
+
+    if let Some(Command::NewPlugin {
+        name,
+        modules,
+        lang,
+        output,
+        simba_path,
+    }) = args.command
+    {
+        let directory = std::path::PathBuf::from(output.unwrap_or_else(|| name.clone()));
+        let request = plugin_template::PluginRequest {
+            name,
+            modules,
+            lang,
+            simba_path: simba_path.map(std::path::PathBuf::from),
+        };
+        match plugin_template::generate_plugin(&directory, &request) {
+            Ok(files) => {
+                for file in files {
+                    println!("Created {}", file.display());
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e.detailed_error());
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Aggregate { directory, output }) = args.command {
+        let directory = std::path::Path::new(&directory);
+        let output = output.map_or_else(
+            || directory.join("aggregate.json"),
+            std::path::PathBuf::from,
+        );
+        let aggregate = match simba::simulator::aggregate_results_directory(directory) {
+            Ok(aggregate) => aggregate,
+            Err(e) => {
+                eprintln!("{}", e.detailed_error());
+                std::process::exit(2);
+            }
+        };
+        println!("{} runs aggregated", aggregate.runs.len());
+        for stats in &aggregate.statistics {
+            let interval = stats
+                .confidence_interval
+                .map_or(String::new(), |[low, high]| {
+                    format!(", CI [{low:.4}, {high:.4}]")
+                });
+            println!(
+                "  {}: {:.4} ± {:.4} ({} runs{interval})",
+                stats.kpi, stats.mean, stats.std, stats.count
+            );
+        }
+        for outlier in &aggregate.outliers {
+            println!(
+                "Outlier: {} (seed {}) has {} = {:.4} (score {:.1})",
+                outlier.run,
+                outlier
+                    .seed
+                    .map_or("-".to_string(), |seed| seed.to_string()),
+                outlier.kpi,
+                outlier.value,
+                outlier.score
+            );
+        }
+        if let Err(e) = aggregate.save(&output) {
+            eprintln!("{}", e.detailed_error());
+            std::process::exit(2);
+        }
+        println!("Aggregate written at: {}", output.display());
+        return;
+    }
+
+    if let Some(Command::Query {
+        result,
+        node,
+        field,
+        between,
+        format,
+    }) = args.command
+    {
+        let query = query::Query {
+            node,
+            fields: field,
+            between: between.map(|b| (b[0], b[1])),
+            format,
+        };
+        let out = std::io::BufWriter::new(std::io::stdout().lock());
+        if let Err(e) = query::run_query(std::path::Path::new(&result), &query, out) {
+            eprintln!("{}", e.detailed_error());
+            std::process::exit(2);
+        }
+        return;
+    }
+
     if let Some(schema_path) = args.generate_schema {
         generate_schema(schema_path);
     }
+
+    if let Some(path) = args.generate_stress_config {
+        let params = stress_config::StressConfigParams {
+            nb_robots: args.nb_robots,
+            spacing: args.spacing,
+            landmark_spacing: args.landmark_spacing,
+            max_time: args.max_time,
+            seed: args.seed,
+        };
+        if let Err(e) = stress_config::generate_stress_config(std::path::Path::new(&path), &params)
+        {
+            println!("{}", e.detailed_error());
+            std::process::exit(2);
+        }
+    }
+
+    #[cfg(feature = "seed_audit")]
+    if let Some(config_path) = args.seed_audit {
+        match seed_audit(
+            config_path,
+            args.seed_audit_reference,
+            args.seed_audit_output,
+        ) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                println!("{}", e.detailed_error());
+                std::process::exit(2);
+            }
+        }
+    }
 }
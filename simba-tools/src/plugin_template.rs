@@ -0,0 +1,1163 @@
+//! Generation of a plugin skeleton (`new-plugin` command).
+//!
+//! Instead of copying and pruning the example plugin, a new plugin is generated with only the
+//! requested modules. Each module comes with its configuration, its record and the implementation
+//! of the module trait, with the simplest behaviour which runs. The plugin is given with an
+//! example configuration using the modules as `External`, a program running it and a test running
+//! a short simulation.
+//!
+//! Rust plugins are a crate depending on `simba-plugin-api` (`Cargo.toml`, `src/lib.rs` with the
+//! [`Plugin`], one file per module in `src/`, `src/main.rs`, `tests/plugin.rs`). Python plugins are a package
+//! (`pyproject.toml`, one file per module in the package directory, `__main__.py`,
+//! `tests/test_plugin.py`). The Python API does not support external sensors.
+//!
+//! [`Plugin`]: simba_plugin_api::plugin::Plugin
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use simba::errors::{SimbaError, SimbaErrorTypes, SimbaResult};
+use simba_plugin_api::API_VERSION;
+
+/// Repository of the simulator, used as dependency when no local path is given.
+const SIMBA_REPOSITORY: &str = "https://gitlab.laas.fr/mescourrou/simba.git";
+
+/// Marker replaced by the plugin type prefix (CamelCase) in the templates.
+const NAME_MARKER: &str = "__Name__";
+/// Marker replaced by the crate or package name (snake_case) in the templates.
+const CRATE_MARKER: &str = "__crate__";
+
+/// Language of the generated plugin.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum PluginLang {
+    /// Rust crate.
+    Rust,
+    /// Python package.
+    Python,
+}
+
+/// Module provided by the generated plugin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum PluginModule {
+    /// Navigator, computing the error to the goal from the world state.
+    Navigator,
+    /// Controller, computing the command from the navigator error.
+    Controller,
+    /// State estimator, building the world state.
+    Estimator,
+    /// Physics, moving the robot from the command.
+    Physics,
+    /// Sensor, making observations (Rust only).
+    Sensor,
+}
+
+impl PluginModule {
+    /// Name of the module file and of the configuration field.
+    fn file_name(&self) -> &'static str {
+        match self {
+            Self::Navigator => "navigator",
+            Self::Controller => "controller",
+            Self::Estimator => "state_estimator",
+            Self::Physics => "physics",
+            Self::Sensor => "sensor",
+        }
+    }
+
+    /// Suffix of the generated types, and name of the module trait.
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Navigator => "Navigator",
+            Self::Controller => "Controller",
+            Self::Estimator => "StateEstimator",
+            Self::Physics => "Physics",
+            Self::Sensor => "Sensor",
+        }
+    }
+
+    /// Method of the Rust [`Plugin`](simba_plugin_api::plugin::Plugin) making the module.
+    fn plugin_method(&self) -> &'static str {
+        match self {
+            Self::Navigator => "navigator",
+            Self::Controller => "controller",
+            Self::Estimator => "state_estimator",
+            Self::Physics => "physics",
+            Self::Sensor => "sensor",
+        }
+    }
+
+    /// Method of the Python plugin API making the module.
+    fn getter(&self) -> &'static str {
+        match self {
+            Self::Navigator => "get_navigator",
+            Self::Controller => "get_controller",
+            Self::Estimator => "get_state_estimator",
+            Self::Physics => "get_physics",
+            Self::Sensor => "get_sensor",
+        }
+    }
+
+    fn rust_template(&self) -> &'static str {
+        match self {
+            Self::Navigator => RUST_NAVIGATOR,
+            Self::Controller => RUST_CONTROLLER,
+            Self::Estimator => RUST_STATE_ESTIMATOR,
+            Self::Physics => RUST_PHYSICS,
+            Self::Sensor => RUST_SENSOR,
+        }
+    }
+
+    fn python_template(&self) -> Option<&'static str> {
+        match self {
+            Self::Navigator => Some(PYTHON_NAVIGATOR),
+            Self::Controller => Some(PYTHON_CONTROLLER),
+            Self::Estimator => Some(PYTHON_STATE_ESTIMATOR),
+            Self::Physics => Some(PYTHON_PHYSICS),
+            Self::Sensor => None,
+        }
+    }
+
+    /// Configuration of the module in the example robot.
+    fn example_config(&self) -> &'static str {
+        match self {
+            Self::Navigator => {
+                "    navigator:\n      type: External\n      config:\n        target_point: [5., 5.]\n        target_speed: 0.5\n"
+            }
+            Self::Controller => {
+                "    controller:\n      type: External\n      config:\n        gains: [1., 2.]\n"
+            }
+            Self::Estimator => {
+                "    state_estimator:\n      type: External\n      config:\n        period: 0.1\n"
+            }
+            Self::Physics => {
+                "    physics:\n      type: External\n      config:\n        wheel_distance: 0.25\n"
+            }
+            Self::Sensor => {
+                "    sensor_manager:\n      sensors:\n        - name: __crate___sensor\n          config:\n            type: External\n            config:\n              period: 0.5\n"
+            }
+        }
+    }
+}
+
+/// Plugin to generate.
+pub struct PluginRequest {
+    /// Name of the crate or package. Letters, digits, `_` and `-`, starting with a letter.
+    pub name: String,
+    /// Modules provided by the plugin.
+    pub modules: Vec<PluginModule>,
+    /// Language of the plugin.
+    pub lang: PluginLang,
+    /// Path of a local copy of the simba repository, whose `simba-plugin-api` is used as
+    /// dependency instead of the remote repository (Rust only).
+    pub simba_path: Option<PathBuf>,
+}
+
+fn request_error(message: String) -> SimbaError {
+    SimbaError::new(SimbaErrorTypes::ConfigError, message)
+}
+
+/// Crate or package name (snake_case) and type prefix (CamelCase) of the plugin name.
+fn plugin_names(name: &str) -> SimbaResult<(String, String)> {
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic())
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(request_error(format!(
+            "Invalid plugin name '{name}': use letters, digits, '_' and '-', starting with a letter"
+        )));
+    }
+    let snake = name.replace('-', "_").to_lowercase();
+    let camel = snake
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| word[..1].to_uppercase() + &word[1..])
+        .collect();
+    Ok((snake, camel))
+}
+
+fn fill(template: &str, snake: &str, camel: &str) -> String {
+    template
+        .replace(NAME_MARKER, camel)
+        .replace(CRATE_MARKER, snake)
+}
+
+/// Example configuration running one robot with the plugin modules.
+fn example_config(modules: &[PluginModule]) -> String {
+    let mut config = String::from(
+        "# Example configuration using the plugin modules, see the configuration documentation\n\
+         # for the other fields.\n\
+         max_time: 10.\n\
+         robots:\n  \
+         - name: robot1\n",
+    );
+    for module in modules {
+        config += module.example_config();
+    }
+    config
+}
+
+fn rust_files(
+    request: &PluginRequest,
+    modules: &[PluginModule],
+    snake: &str,
+) -> Vec<(PathBuf, String)> {
+    let api_dependency = match &request.simba_path {
+        Some(path) => format!(
+            "simba-plugin-api = {{ path = {:?} }}",
+            path.join("simba-plugin-api").display().to_string()
+        ),
+        None => format!(
+            "# Use `path = \"<simba repository>/simba-plugin-api\"` for a local copy.\n\
+             simba-plugin-api = {{ git = \"{SIMBA_REPOSITORY}\", version = \"{API_VERSION}\" }}"
+        ),
+    };
+    let mut files = vec![(
+        PathBuf::from("Cargo.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n\
+             [dependencies]\n{api_dependency}\n\
+             serde = {{ version = \"^1.0\", features = [\"derive\"] }}\n\
+             serde_json = \"1.0.145\"\n",
+            request.name
+        ),
+    )];
+
+    let mut lib = format!(
+        "//! Plugin {}: {}.\n\nuse simba_plugin_api::prelude::*;\n\n",
+        request.name,
+        modules
+            .iter()
+            .map(|m| m.file_name().replace('_', " "))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    for module in modules {
+        lib += &format!("pub mod {};\n", module.file_name());
+    }
+    lib += &format!(
+        "\n/// Plugin giving its modules to the simulator, wrapped in a `StablePlugin`.\n\
+         pub struct {NAME_MARKER}Plugin;\n\nimpl Plugin for {NAME_MARKER}Plugin {{\n"
+    );
+    for (i, module) in modules.iter().enumerate() {
+        if i > 0 {
+            lib += "\n";
+        }
+        lib += &format!(
+            "    fn {method}(\n        &self,\n        config: &serde_json::Value,\n        \
+             context: &ModuleContext,\n    ) -> Box<dyn {ty}> {{\n        \
+             // The error gives the location of the config in the file\n        \
+             let config = context.parse_config(config).unwrap_or_else(|e| panic!(\"{{e}}\"));\n        \
+             Box::new({file}::{NAME_MARKER}{ty}::from_config(config, context.initial_time))\n    }}\n",
+            method = module.plugin_method(),
+            ty = module.type_name(),
+            file = module.file_name(),
+        );
+    }
+    lib += "}\n";
+    files.push((PathBuf::from("src/lib.rs"), lib));
+
+    for module in modules {
+        files.push((
+            PathBuf::from(format!("src/{}.rs", module.file_name())),
+            module.rust_template().to_string(),
+        ));
+    }
+    files.push((PathBuf::from("src/main.rs"), RUST_MAIN.to_string()));
+    files.push((PathBuf::from("tests/plugin.rs"), RUST_TEST.to_string()));
+    files.push((
+        PathBuf::from("config/config.yaml"),
+        example_config(modules).replace(CRATE_MARKER, snake),
+    ));
+    files
+}
+
+fn python_files(
+    request: &PluginRequest,
+    modules: &[PluginModule],
+    snake: &str,
+) -> SimbaResult<Vec<(PathBuf, String)>> {
+    let package = PathBuf::from(snake);
+    let mut files = vec![(
+        PathBuf::from("pyproject.toml"),
+        format!(
+            "[build-system]\nrequires = [\"setuptools>=61\"]\n\
+             build-backend = \"setuptools.build_meta\"\n\n\
+             [project]\nname = \"{}\"\nversion = \"0.1.0\"\nrequires-python = \">=3.10\"\n\
+             # simba {}, installed from the simulator repository\ndependencies = [\"simba\"]\n",
+            request.name,
+            simba::VERSION
+        ),
+    )];
+
+    let mut init = format!(
+        "\"\"\"Plugin {}.\"\"\"\n\nimport json\n\nimport simba\n\n",
+        request.name
+    );
+    for module in modules {
+        init += &format!(
+            "from .{} import {NAME_MARKER}{}\n",
+            module.file_name(),
+            module.type_name()
+        );
+    }
+    init += &format!(
+        "\n\nclass {NAME_MARKER}Plugin(simba.PluginAPI):\n    \
+         \"\"\"Plugin API giving the modules of the plugin to the simulator.\"\"\"\n"
+    );
+    for module in modules {
+        let Some(template) = module.python_template() else {
+            return Err(request_error(format!(
+                "The Python API does not support the {} module",
+                module.file_name()
+            )));
+        };
+        init += &format!(
+            "\n    def {}(self, config: str, global_config: str, initial_time: float):\n        \
+             return {NAME_MARKER}{}(json.loads(config), initial_time)\n",
+            module.getter(),
+            module.type_name()
+        );
+        files.push((
+            package.join(format!("{}.py", module.file_name())),
+            template.to_string(),
+        ));
+    }
+    files.push((package.join("__init__.py"), init));
+    files.push((package.join("__main__.py"), PYTHON_MAIN.to_string()));
+    files.push((
+        PathBuf::from("tests/test_plugin.py"),
+        PYTHON_TEST.to_string(),
+    ));
+    files.push((
+        PathBuf::from("config/config.yaml"),
+        example_config(modules).replace(CRATE_MARKER, snake),
+    ));
+    Ok(files)
+}
+
+/// Files of the plugin, relative to the plugin directory, with their content.
+fn plugin_files(request: &PluginRequest) -> SimbaResult<Vec<(PathBuf, String)>> {
+    let (snake, camel) = plugin_names(&request.name)?;
+    let mut modules = request.modules.clone();
+    modules.sort();
+    modules.dedup();
+    if modules.is_empty() {
+        return Err(request_error(
+            "The plugin should provide at least one module".to_string(),
+        ));
+    }
+    let files = match request.lang {
+        PluginLang::Rust => rust_files(request, &modules, &snake),
+        PluginLang::Python => python_files(request, &modules, &snake)?,
+    };
+    Ok(files
+        .into_iter()
+        .map(|(path, content)| (path, fill(&content, &snake, &camel)))
+        .collect())
+}
+
+/// Generate the plugin in `directory`, which should not exist or be empty.
+///
+/// Returns the paths of the generated files.
+pub fn generate_plugin(directory: &Path, request: &PluginRequest) -> SimbaResult<Vec<PathBuf>> {
+    let files = plugin_files(request)?;
+    if directory
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        return Err(request_error(format!(
+            "Directory '{}' is not empty",
+            directory.display()
+        )));
+    }
+    let mut paths = Vec::new();
+    for (path, content) in files {
+        let path = directory.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                request_error(format!(
+                    "Impossible to create directory '{}': {e}",
+                    parent.display()
+                ))
+            })?;
+        }
+        fs::write(&path, content)
+            .map_err(|e| request_error(format!("Impossible to write '{}': {e}", path.display())))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+const RUST_NAVIGATOR: &str = r#"//! Navigator of the __crate__ plugin.
+
+use serde::{Deserialize, Serialize};
+use simba_plugin_api::prelude::*;
+
+/// Configuration of the navigator, in the `config` field of the `External` navigator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct __Name__NavigatorConfig {
+    /// Point `[x, y]` to reach.
+    pub target_point: [f32; 2],
+    /// Speed to reach the point.
+    pub target_speed: f32,
+}
+
+impl Default for __Name__NavigatorConfig {
+    fn default() -> Self {
+        Self {
+            target_point: [0., 0.],
+            target_speed: 0.5,
+        }
+    }
+}
+
+/// Record of the navigator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct __Name__NavigatorRecord {
+    /// Last computed error.
+    pub error: ControllerError,
+}
+
+/// Navigator going in straight line to the target point.
+#[derive(Debug)]
+pub struct __Name__Navigator {
+    config: __Name__NavigatorConfig,
+    error: ControllerError,
+}
+
+impl __Name__Navigator {
+    pub fn from_config(config: __Name__NavigatorConfig, _initial_time: f32) -> Self {
+        Self {
+            config,
+            error: ControllerError::default(),
+        }
+    }
+}
+
+impl Navigator for __Name__Navigator {
+    fn compute_error(&mut self, _node: &mut Node, world_state: WorldState) -> ControllerError {
+        let Some(ego) = world_state.ego else {
+            return ControllerError::default();
+        };
+        let dx = self.config.target_point[0] - ego.pose.x;
+        let dy = self.config.target_point[1] - ego.pose.y;
+        let heading = dy.atan2(dx);
+        let theta = (heading - ego.pose.z + std::f32::consts::PI)
+            .rem_euclid(2. * std::f32::consts::PI)
+            - std::f32::consts::PI;
+        let distance = (dx * dx + dy * dy).sqrt();
+        let target_speed = if distance < 0.1 {
+            0.
+        } else {
+            self.config.target_speed
+        };
+        self.error = ControllerError {
+            lateral: 0.,
+            longitudinal: distance,
+            theta,
+            velocity: target_speed - ego.velocity.x,
+        };
+        self.error.clone()
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+}
+
+impl Recordable<NavigatorRecord> for __Name__Navigator {
+    fn record(&self) -> NavigatorRecord {
+        external_navigator_record(&__Name__NavigatorRecord {
+            error: self.error.clone(),
+        })
+    }
+}
+"#;
+
+const RUST_CONTROLLER: &str = r#"//! Controller of the __crate__ plugin.
+
+use serde::{Deserialize, Serialize};
+use simba_plugin_api::prelude::*;
+
+/// Configuration of the controller, in the `config` field of the `External` controller.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct __Name__ControllerConfig {
+    /// Gains on the velocity and orientation errors.
+    pub gains: [f32; 2],
+}
+
+impl Default for __Name__ControllerConfig {
+    fn default() -> Self {
+        Self { gains: [1., 2.] }
+    }
+}
+
+/// Record of the controller.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct __Name__ControllerRecord {
+    /// Last command, as `[left, right]` wheel speeds.
+    pub command: [f32; 2],
+}
+
+/// Proportional controller of a unicycle robot.
+#[derive(Debug)]
+pub struct __Name__Controller {
+    config: __Name__ControllerConfig,
+    command: [f32; 2],
+}
+
+impl __Name__Controller {
+    pub fn from_config(config: __Name__ControllerConfig, _initial_time: f32) -> Self {
+        Self {
+            config,
+            command: [0., 0.],
+        }
+    }
+}
+
+impl Controller for __Name__Controller {
+    fn make_command(&mut self, _node: &mut Node, error: &ControllerError, _time: f32) -> Command {
+        let velocity = self.config.gains[0] * error.velocity;
+        let rotation = self.config.gains[1] * error.theta;
+        self.command = [velocity - rotation, velocity + rotation];
+        Command::Unicycle(UnicycleCommand {
+            left_wheel_speed: self.command[0],
+            right_wheel_speed: self.command[1],
+        })
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+}
+
+impl Recordable<ControllerRecord> for __Name__Controller {
+    fn record(&self) -> ControllerRecord {
+        external_controller_record(&__Name__ControllerRecord {
+            command: self.command,
+        })
+    }
+}
+"#;
+
+const RUST_STATE_ESTIMATOR: &str = r#"//! State estimator of the __crate__ plugin.
+
+use serde::{Deserialize, Serialize};
+use simba_plugin_api::prelude::*;
+
+/// Configuration of the state estimator, in the `config` field of the `External` state
+/// estimator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct __Name__StateEstimatorConfig {
+    /// Period of the prediction steps, in seconds.
+    pub period: f32,
+}
+
+impl Default for __Name__StateEstimatorConfig {
+    fn default() -> Self {
+        Self { period: 0.1 }
+    }
+}
+
+/// Record of the state estimator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct __Name__StateEstimatorRecord {
+    /// Estimated state.
+    pub state: StateRecord,
+    /// Time of the last prediction.
+    pub last_prediction: f32,
+}
+
+/// State estimator to complete: the state is kept at its initial value.
+#[derive(Debug)]
+pub struct __Name__StateEstimator {
+    config: __Name__StateEstimatorConfig,
+    state: State,
+    last_prediction: f32,
+}
+
+impl __Name__StateEstimator {
+    pub fn from_config(config: __Name__StateEstimatorConfig, initial_time: f32) -> Self {
+        Self {
+            config,
+            state: State::new(),
+            last_prediction: initial_time,
+        }
+    }
+}
+
+impl StateEstimator for __Name__StateEstimator {
+    fn prediction_step(&mut self, _node: &mut Node, _command: Option<Command>, time: f32) {
+        // Predict the state from the command here
+        self.last_prediction = time;
+    }
+
+    fn correction_step(&mut self, _node: &mut Node, _observations: &[Observation], _time: f32) {
+        // Correct the state with the observations here
+    }
+
+    fn world_state(&self) -> WorldState {
+        let mut world_state = WorldState::new();
+        world_state.ego = Some(self.state.clone());
+        world_state
+    }
+
+    fn next_time_step(&self) -> f32 {
+        self.last_prediction + self.config.period
+    }
+
+    fn pre_loop_hook(&mut self, _node: &mut Node, _time: f32) {}
+}
+
+impl Recordable<StateEstimatorRecord> for __Name__StateEstimator {
+    fn record(&self) -> StateEstimatorRecord {
+        external_estimator_record(&__Name__StateEstimatorRecord {
+            state: self.state.record(),
+            last_prediction: self.last_prediction,
+        })
+    }
+}
+"#;
+
+const RUST_PHYSICS: &str = r#"//! Physics of the __crate__ plugin.
+
+use serde::{Deserialize, Serialize};
+use simba_plugin_api::prelude::*;
+
+/// Configuration of the physics, in the `config` field of the `External` physics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct __Name__PhysicsConfig {
+    /// Distance between the wheels, in meters.
+    pub wheel_distance: f32,
+}
+
+impl Default for __Name__PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            wheel_distance: 0.25,
+        }
+    }
+}
+
+/// Record of the physics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct __Name__PhysicsRecord {
+    /// Real state.
+    pub state: StateRecord,
+}
+
+/// Unicycle kinematics, integrated with the Euler method.
+#[derive(Debug)]
+pub struct __Name__Physics {
+    config: __Name__PhysicsConfig,
+    state: State,
+    last_time: f32,
+}
+
+impl __Name__Physics {
+    pub fn from_config(config: __Name__PhysicsConfig, initial_time: f32) -> Self {
+        Self {
+            config,
+            state: State::new(),
+            last_time: initial_time,
+        }
+    }
+}
+
+impl Physics for __Name__Physics {
+    fn apply_command(&mut self, command: &Command, time: f32) {
+        self.update_state(time);
+        if let Command::Unicycle(command) = command {
+            self.state.velocity.x = (command.left_wheel_speed + command.right_wheel_speed) / 2.;
+            self.state.velocity.z = (command.right_wheel_speed - command.left_wheel_speed)
+                / self.config.wheel_distance;
+        }
+    }
+
+    fn update_state(&mut self, time: f32) {
+        let dt = time - self.last_time;
+        let theta = self.state.pose.z;
+        self.state.pose.x += self.state.velocity.x * theta.cos() * dt;
+        self.state.pose.y += self.state.velocity.x * theta.sin() * dt;
+        self.state.pose.z += self.state.velocity.z * dt;
+        self.last_time = time;
+    }
+
+    fn state(&self, _time: f32) -> State {
+        self.state.clone()
+    }
+}
+
+impl HasService<GetRealStateReq, GetRealStateResp> for __Name__Physics {
+    fn handle_service_requests(
+        &mut self,
+        _req: GetRealStateReq,
+        _time: f32,
+    ) -> Result<GetRealStateResp, String> {
+        Ok(GetRealStateResp {
+            state: self.state.clone(),
+        })
+    }
+}
+
+impl Recordable<PhysicsRecord> for __Name__Physics {
+    fn record(&self) -> PhysicsRecord {
+        external_physics_record(&__Name__PhysicsRecord {
+            state: self.state.record(),
+        })
+    }
+}
+"#;
+
+const RUST_SENSOR: &str = r#"//! Sensor of the __crate__ plugin.
+
+use serde::{Deserialize, Serialize};
+use simba_plugin_api::prelude::*;
+
+/// Configuration of the sensor, in the `config` field of the `External` sensor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct __Name__SensorConfig {
+    /// Period of the observations, in seconds.
+    pub period: f32,
+}
+
+impl Default for __Name__SensorConfig {
+    fn default() -> Self {
+        Self { period: 0.5 }
+    }
+}
+
+/// Observation of the sensor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct __Name__SensorObservation {
+    /// Time of the observation.
+    pub time: f32,
+}
+
+/// Sensor to complete: the observations only contain their time.
+#[derive(Debug)]
+pub struct __Name__Sensor {
+    config: __Name__SensorConfig,
+    last_time: f32,
+    last_observation: Option<__Name__SensorObservation>,
+}
+
+impl __Name__Sensor {
+    pub fn from_config(config: __Name__SensorConfig, initial_time: f32) -> Self {
+        Self {
+            config,
+            last_time: initial_time,
+            last_observation: None,
+        }
+    }
+}
+
+impl Sensor for __Name__Sensor {
+    fn get_observations(&mut self, _node: &mut Node, time: f32) -> Vec<SensorObservation> {
+        let observation = __Name__SensorObservation { time };
+        self.last_time = time;
+        self.last_observation = Some(observation.clone());
+        vec![external_observation(&observation)]
+    }
+
+    fn next_time_step(&self) -> f32 {
+        self.last_time + self.config.period
+    }
+}
+
+impl Recordable<SensorRecord> for __Name__Sensor {
+    fn record(&self) -> SensorRecord {
+        external_sensor_record(&self.last_observation)
+    }
+}
+"#;
+
+const RUST_MAIN: &str = r#"use std::{path::Path, sync::Arc};
+
+use __crate__::__Name__Plugin;
+use simba_plugin_api::{Simulator, plugin::StablePlugin};
+
+fn main() {
+    Simulator::init_environment();
+    let mut simulator = Simulator::from_config_path(
+        Path::new("config/config.yaml"),
+        Some(Arc::new(StablePlugin(__Name__Plugin))),
+    )
+    .unwrap_or_else(|e| panic!("{}", e.detailed_error()));
+    simulator
+        .run()
+        .unwrap_or_else(|e| panic!("{}", e.detailed_error()));
+}
+"#;
+
+const RUST_TEST: &str = r#"use std::{path::Path, sync::Arc};
+
+use __crate__::__Name__Plugin;
+use simba_plugin_api::{Simulator, plugin::StablePlugin};
+
+#[test]
+fn example_config_runs() {
+    Simulator::init_environment();
+    let mut simulator = Simulator::from_config_path(
+        Path::new("config/config.yaml"),
+        Some(Arc::new(StablePlugin(__Name__Plugin))),
+    )
+    .unwrap_or_else(|e| panic!("{}", e.detailed_error()));
+    simulator.set_max_time(1.);
+    simulator
+        .run()
+        .unwrap_or_else(|e| panic!("{}", e.detailed_error()));
+    assert!(!simulator.get_records(false).is_empty());
+}
+"#;
+
+const PYTHON_NAVIGATOR: &str = r#""""Navigator of the __crate__ plugin."""
+
+import json
+import math
+
+import simba
+
+
+class __Name__Navigator(simba.Navigator):
+    """Navigator going in straight line to the target point."""
+
+    def __init__(self, config: dict, initial_time: float):
+        self.target_point = config.get("target_point", [0.0, 0.0])
+        self.target_speed = config.get("target_speed", 0.5)
+        self.last_error = None
+
+    def post_init(self, node: simba.Node) -> None:
+        pass
+
+    def record(self) -> str:
+        if self.last_error is None:
+            return json.dumps({})
+        return json.dumps({"theta": self.last_error.theta, "velocity": self.last_error.velocity})
+
+    def compute_error(self, node: simba.Node, world_state: simba.WorldState) -> simba.ControllerError:
+        error = simba.ControllerError()
+        if world_state.ego is None:
+            return error
+        dx = self.target_point[0] - world_state.ego.pose.x
+        dy = self.target_point[1] - world_state.ego.pose.y
+        heading = math.atan2(dy, dx)
+        error.theta = (heading - world_state.ego.pose.theta + math.pi) % (2 * math.pi) - math.pi
+        distance = math.hypot(dx, dy)
+        error.longitudinal = distance
+        target_speed = 0.0 if distance < 0.1 else self.target_speed
+        error.velocity = target_speed - world_state.ego.velocity.x
+        self.last_error = error
+        return error
+
+    def pre_loop_hook(self, node: simba.Node, time: float):
+        pass
+
+    def next_time_step(self):
+        return None
+"#;
+
+const PYTHON_CONTROLLER: &str = r#""""Controller of the __crate__ plugin."""
+
+import json
+
+import simba
+
+
+class __Name__Controller(simba.Controller):
+    """Proportional controller of a unicycle robot."""
+
+    def __init__(self, config: dict, initial_time: float):
+        self.gains = config.get("gains", [1.0, 2.0])
+        self.command = [0.0, 0.0]
+
+    def post_init(self, node: simba.Node) -> None:
+        pass
+
+    def record(self) -> str:
+        return json.dumps({"command": self.command})
+
+    def make_command(self, node: simba.Node, error: simba.ControllerError, time: float) -> simba.Command:
+        velocity = self.gains[0] * error.velocity
+        rotation = self.gains[1] * error.theta
+        self.command = [velocity - rotation, velocity + rotation]
+        command = simba.UnicycleCommand()
+        command.left_wheel_speed = self.command[0]
+        command.right_wheel_speed = self.command[1]
+        return simba.Command.from_unicycle_command(command)
+
+    def pre_loop_hook(self, node: simba.Node, time: float):
+        pass
+
+    def next_time_step(self):
+        return None
+"#;
+
+const PYTHON_STATE_ESTIMATOR: &str = r#""""State estimator of the __crate__ plugin."""
+
+import json
+from typing import List
+
+import simba
+
+
+class __Name__StateEstimator(simba.StateEstimator):
+    """State estimator to complete: the state is kept at its initial value."""
+
+    def __init__(self, config: dict, initial_time: float):
+        self.period = config.get("period", 0.1)
+        self.last_time = initial_time
+        self.pose = [0.0, 0.0, 0.0]
+
+    def post_init(self, node: simba.Node) -> None:
+        pass
+
+    def state(self) -> simba.WorldState:
+        world_state = simba.WorldState()
+        world_state.ego.pose.x = self.pose[0]
+        world_state.ego.pose.y = self.pose[1]
+        world_state.ego.pose.theta = self.pose[2]
+        return world_state
+
+    def record(self) -> str:
+        return json.dumps({"pose": self.pose, "last_time": self.last_time})
+
+    def prediction_step(self, node: simba.Node, command: simba.Command, time: float):
+        # Predict the state from the command here
+        self.last_time = time
+
+    def correction_step(self, node: simba.Node, observations: List[simba.Observation], time: float):
+        # Correct the state with the observations here
+        pass
+
+    def next_time_step(self):
+        return self.last_time + self.period
+
+    def pre_loop_hook(self, node: simba.Node, time: float):
+        pass
+"#;
+
+const PYTHON_PHYSICS: &str = r#""""Physics of the __crate__ plugin."""
+
+import json
+import math
+
+import simba
+
+
+class __Name__Physics(simba.Physics):
+    """Unicycle kinematics, integrated with the Euler method."""
+
+    def __init__(self, config: dict, initial_time: float):
+        self.wheel_distance = config.get("wheel_distance", 0.25)
+        self.last_time = initial_time
+        # x, y, theta, linear velocity, angular velocity
+        self.curr_state = [0.0, 0.0, 0.0, 0.0, 0.0]
+
+    def post_init(self, node: simba.Node) -> None:
+        pass
+
+    def record(self) -> str:
+        return json.dumps({"state": self.curr_state})
+
+    def update_state(self, time: float):
+        dt = time - self.last_time
+        x, y, theta, v, w = self.curr_state
+        self.curr_state[0] = x + v * math.cos(theta) * dt
+        self.curr_state[1] = y + v * math.sin(theta) * dt
+        self.curr_state[2] = theta + w * dt
+        self.last_time = time
+
+    def apply_command(self, command: simba.Command, time: float):
+        self.update_state(time)
+        unicycle_command = command.as_unicycle_command()
+        left = unicycle_command.left_wheel_speed
+        right = unicycle_command.right_wheel_speed
+        self.curr_state[3] = (left + right) / 2
+        self.curr_state[4] = (right - left) / self.wheel_distance
+
+    def state(self, time: float):
+        state = simba.State()
+        state.pose.x = self.curr_state[0]
+        state.pose.y = self.curr_state[1]
+        state.pose.theta = self.curr_state[2]
+        state.velocity.x = self.curr_state[3]
+        return state
+
+    def next_time_step(self):
+        return None
+"#;
+
+const PYTHON_MAIN: &str = r#""""Run the example configuration: `python -m __crate__`."""
+
+import simba
+
+from . import __Name__Plugin
+
+
+def main():
+    simulator = simba.Simulator.from_config("config/config.yaml", __Name__Plugin())
+    simulator.run()
+
+
+if __name__ == "__main__":
+    main()
+"#;
+
+const PYTHON_TEST: &str = r#""""Run the example configuration with the plugin."""
+
+import simba
+
+from __crate__ import __Name__Plugin
+
+
+def test_example_config_runs():
+    simulator = simba.Simulator.from_config("config/config.yaml", __Name__Plugin())
+    simulator.run()
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_and_python_files() {
+        let mut request = PluginRequest {
+            name: "my-planner".to_string(),
+            modules: vec![
+                PluginModule::Controller,
+                PluginModule::Navigator,
+                PluginModule::Navigator,
+            ],
+            lang: PluginLang::Rust,
+            simba_path: None,
+        };
+        let files = plugin_files(&request).unwrap();
+        let paths: Vec<_> = files.iter().map(|(p, _)| p.to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "Cargo.toml",
+                "src/lib.rs",
+                "src/navigator.rs",
+                "src/controller.rs",
+                "src/main.rs",
+                "tests/plugin.rs",
+                "config/config.yaml"
+            ]
+        );
+        let lib = &files[1].1;
+        assert!(lib.contains("pub struct MyPlannerPlugin;"));
+        assert!(lib.contains("navigator::MyPlannerNavigator::from_config"));
+        assert!(lib.contains("impl Plugin for MyPlannerPlugin"));
+        assert!(!lib.contains("fn physics("));
+        assert!(
+            files
+                .iter()
+                .all(|(_, content)| !content.contains(NAME_MARKER)
+                    && !content.contains(CRATE_MARKER))
+        );
+
+        request.lang = PluginLang::Python;
+        assert!(
+            plugin_files(&request)
+                .unwrap()
+                .iter()
+                .any(|(p, _)| p == Path::new("my_planner/__init__.py"))
+        );
+        request.modules.push(PluginModule::Sensor);
+        assert!(plugin_files(&request).is_err());
+        request.name = "1plugin".to_string();
+        assert!(plugin_files(&request).is_err());
+    }
+
+    /// Generate the plugin `name` in a fresh temporary directory, returning the directory and
+    /// the generated files.
+    fn generate_in_temp(
+        name: &str,
+        lang: PluginLang,
+        modules: Vec<PluginModule>,
+    ) -> (PathBuf, Vec<PathBuf>) {
+        let directory = std::env::temp_dir().join(format!("simba_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let simba_path = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let request = PluginRequest {
+            name: name.to_string(),
+            modules,
+            lang,
+            simba_path: Some(simba_path.to_path_buf()),
+        };
+        let files = generate_plugin(&directory, &request).unwrap();
+        (directory, files)
+    }
+
+    /// The generated Rust plugin builds against the local plugin API, so that the templates
+    /// follow the trait signatures. Slow (builds the simulator): run with `--ignored`, as in
+    /// `test.sh`.
+    #[test]
+    #[ignore]
+    fn generated_rust_plugin_compiles() {
+        let (directory, _) = generate_in_temp(
+            "rust_plugin_check",
+            PluginLang::Rust,
+            vec![
+                PluginModule::Navigator,
+                PluginModule::Controller,
+                PluginModule::Estimator,
+                PluginModule::Physics,
+                PluginModule::Sensor,
+            ],
+        );
+        let target = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .unwrap()
+            .join("target")
+            .join("plugin_check");
+        let status = std::process::Command::new(env!("CARGO"))
+            .args(["check", "--all-targets"])
+            .current_dir(&directory)
+            .env("CARGO_TARGET_DIR", target)
+            .status()
+            .unwrap();
+        assert!(
+            status.success(),
+            "cargo check of the generated plugin failed"
+        );
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    /// The generated Python files are valid Python. Needs `python3`: run with `--ignored`, as in
+    /// `test.sh`.
+    #[test]
+    #[ignore]
+    fn generated_python_plugin_compiles() {
+        let (directory, files) = generate_in_temp(
+            "python_plugin_check",
+            PluginLang::Python,
+            vec![
+                PluginModule::Navigator,
+                PluginModule::Controller,
+                PluginModule::Estimator,
+                PluginModule::Physics,
+            ],
+        );
+        let files: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "py"))
+            .collect();
+        assert!(!files.is_empty());
+        let status = std::process::Command::new("python3")
+            .args(["-m", "py_compile"])
+            .args(&files)
+            .status()
+            .unwrap();
+        assert!(
+            status.success(),
+            "the generated Python files do not compile"
+        );
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
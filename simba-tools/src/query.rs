@@ -0,0 +1,323 @@
+//! Extraction of records from a result file, for shell-based analysis.
+//!
+//! The result file is read record by record ([`stream_results`]), so large files are not loaded
+//! in memory. Records are filtered by node and time, and the requested fields are written as CSV
+//! (one column per scalar, arrays and objects are flattened) or as JSON lines.
+//!
+//! The CSV columns are the union of the columns of all the matching records, in order of first
+//! appearance: the file is read a first time to make the header. A record without some of the
+//! columns leaves their cells empty.
+//!
+//! Fields are given as a dot-separated path in the node record, e.g. `physics.state.pose`. The
+//! enum variants of the records (`Robot`, `Internal`, ...) can be omitted: when a key is not found,
+//! it is searched in the nested objects, the shallowest match being used. `physics.pose` is then
+//! the same as `Robot.physics.Internal.state.pose`. Array items are selected with their index,
+//! e.g. `physics.pose.2`.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::{self, Write},
+    path::Path,
+};
+
+use serde_json::{Map, Value};
+use simba::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    simulator::{Record, stream_results},
+};
+
+/// Output format of the query.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum QueryFormat {
+    /// Comma-separated values with a header line.
+    Csv,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Filters and fields of a query.
+pub struct Query {
+    /// Only keep the records of this node. All the nodes if `None`.
+    pub node: Option<String>,
+    /// Fields to extract. The whole record if empty.
+    pub fields: Vec<String>,
+    /// Only keep the records between these times (included). The start should not be greater
+    /// than the end.
+    pub between: Option<(f32, f32)>,
+    /// Output format.
+    pub format: QueryFormat,
+}
+
+impl Query {
+    /// Requested fields of `record`, `None` if the record is filtered out or has none of them.
+    fn select(&self, record: &Record) -> SimbaResult<Option<Vec<(String, Value)>>> {
+        if self
+            .node
+            .as_ref()
+            .is_some_and(|node| node != record.node.name())
+            || self
+                .between
+                .is_some_and(|(start, end)| record.time < start || record.time > end)
+        {
+            return Ok(None);
+        }
+        let value = serde_json::to_value(&record.node).map_err(|e| {
+            SimbaError::new(
+                SimbaErrorTypes::UnknownError,
+                format!(
+                    "Error while reading the record at time {}: {e}",
+                    record.time
+                ),
+            )
+        })?;
+        let fields: Vec<(String, Value)> = if self.fields.is_empty() {
+            vec![("record".to_string(), value)]
+        } else {
+            self.fields
+                .iter()
+                .filter_map(|field| lookup(&value, field).map(|v| (field.clone(), v.clone())))
+                .collect()
+        };
+        Ok((!fields.is_empty()).then_some(fields))
+    }
+}
+
+/// Find the value of the key `segment` in `value`, searching in the nested objects if needed.
+fn resolve_segment<'a>(value: &'a Value, segment: &str) -> Option<&'a Value> {
+    match value {
+        Value::Array(values) => values.get(segment.parse::<usize>().ok()?),
+        Value::Object(map) => {
+            if let Some(v) = map.get(segment) {
+                return Some(v);
+            }
+            // Breadth-first search, to take the shallowest match
+            let mut queue: VecDeque<&Map<String, Value>> =
+                map.values().filter_map(|v| v.as_object()).collect();
+            while let Some(map) = queue.pop_front() {
+                if let Some(v) = map.get(segment) {
+                    return Some(v);
+                }
+                queue.extend(map.values().filter_map(|v| v.as_object()));
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Value at the dot-separated `path` in `value`.
+pub(crate) fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, resolve_segment)
+}
+
+/// Flatten `value` to scalar columns named after `prefix`.
+fn flatten(prefix: String, value: &Value, columns: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Array(values) => {
+            for (i, v) in values.iter().enumerate() {
+                flatten(format!("{prefix}[{i}]"), v, columns);
+            }
+        }
+        Value::Object(map) => {
+            for (k, v) in map {
+                flatten(format!("{prefix}.{k}"), v, columns);
+            }
+        }
+        _ => columns.push((prefix, value.clone())),
+    }
+}
+
+fn csv_cell(value: &Value) -> String {
+    let cell = match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    };
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell
+    }
+}
+
+/// Flattened columns of the fields.
+fn csv_columns(fields: &[(String, Value)]) -> Vec<(String, Value)> {
+    let mut columns = Vec::new();
+    for (name, value) in fields {
+        flatten(name.clone(), value, &mut columns);
+    }
+    columns
+}
+
+/// Union of the CSV columns of the records matching `query`, in order of first appearance.
+fn csv_header(result_path: &Path, query: &Query) -> SimbaResult<Vec<String>> {
+    let mut header = Vec::new();
+    let mut known = HashSet::new();
+    let mut error = None;
+    stream_results(result_path, |record: Record| {
+        if error.is_some() {
+            return;
+        }
+        match query.select(&record) {
+            Ok(Some(fields)) => {
+                for (name, _) in csv_columns(&fields) {
+                    if known.insert(name.clone()) {
+                        header.push(name);
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error = Some(e),
+        }
+    })?;
+    match error {
+        Some(e) => Err(e),
+        None => Ok(header),
+    }
+}
+
+struct QueryWriter<W: Write> {
+    out: W,
+    format: QueryFormat,
+    /// CSV columns, written before the first row.
+    header: Vec<String>,
+    header_written: bool,
+}
+
+impl<W: Write> QueryWriter<W> {
+    fn write_row(&mut self, time: f32, node: &str, fields: Vec<(String, Value)>) -> io::Result<()> {
+        match self.format {
+            QueryFormat::Json => {
+                let mut line = Map::new();
+                line.insert("time".to_string(), time.into());
+                line.insert("node".to_string(), node.into());
+                line.extend(fields);
+                writeln!(self.out, "{}", Value::Object(line))
+            }
+            QueryFormat::Csv => {
+                if !self.header_written {
+                    writeln!(self.out, "time,node,{}", self.header.join(","))?;
+                    self.header_written = true;
+                }
+                let columns: HashMap<String, Value> = csv_columns(&fields).into_iter().collect();
+                // Missing columns are left empty
+                let cells: Vec<String> = self
+                    .header
+                    .iter()
+                    .map(|name| columns.get(name).map_or(String::new(), csv_cell))
+                    .collect();
+                writeln!(
+                    self.out,
+                    "{time},{},{}",
+                    csv_cell(&node.into()),
+                    cells.join(",")
+                )
+            }
+        }
+    }
+}
+
+/// Write the records of the result file matching the query to `out`.
+///
+/// Records without any of the requested fields are skipped.
+pub fn run_query(result_path: &Path, query: &Query, out: impl Write) -> SimbaResult<()> {
+    if let Some((start, end)) = query.between
+        && start > end
+    {
+        return Err(SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!("The start of the time range ({start}) is after its end ({end})"),
+        ));
+    }
+    let header = match query.format {
+        QueryFormat::Csv => csv_header(result_path, query)?,
+        QueryFormat::Json => Vec::new(),
+    };
+    let mut writer = QueryWriter {
+        out,
+        format: query.format,
+        header,
+        header_written: false,
+    };
+    let mut select_error: Option<SimbaError> = None;
+    let mut write_error: Option<io::Error> = None;
+    stream_results(result_path, |record: Record| {
+        if select_error.is_some() || write_error.is_some() {
+            return;
+        }
+        match query.select(&record) {
+            Ok(Some(fields)) => {
+                if let Err(e) = writer.write_row(record.time, record.node.name(), fields) {
+                    write_error = Some(e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => select_error = Some(e),
+        }
+    })?;
+    if let Some(e) = select_error {
+        return Err(e);
+    }
+    match write_error {
+        // Output closed early (e.g. piped to `head`)
+        Some(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        Some(e) => Err(SimbaError::new(
+            SimbaErrorTypes::UnknownError,
+            format!("Error while writing the query output: {e}"),
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn lookup_skips_enum_variants() {
+        let record = json!({"Robot": {"name": "robot1", "physics": {"Internal": {"state": {"pose": [1., 2., 0.5]}}}}});
+        assert_eq!(lookup(&record, "physics.pose"), Some(&json!([1., 2., 0.5])));
+        assert_eq!(lookup(&record, "physics.pose.2"), Some(&json!(0.5)));
+        assert_eq!(lookup(&record, "name"), Some(&json!("robot1")));
+        assert!(lookup(&record, "navigator").is_none());
+    }
+
+    #[test]
+    fn csv_rows_follow_the_header() {
+        let mut out = Vec::new();
+        {
+            let mut writer = QueryWriter {
+                out: &mut out,
+                format: QueryFormat::Csv,
+                header: vec!["a".to_string(), "b[0]".to_string(), "b[1]".to_string()],
+                header_written: false,
+            };
+            writer
+                .write_row(0., "node1", vec![("a".to_string(), json!(1))])
+                .unwrap();
+            writer
+                .write_row(1., "node1", vec![("b".to_string(), json!([2, 3]))])
+                .unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "time,node,a,b[0],b[1]\n0,node1,1,,\n1,node1,,2,3\n"
+        );
+    }
+
+    #[test]
+    fn reversed_time_range_rejected() {
+        let query = Query {
+            node: None,
+            fields: Vec::new(),
+            between: Some((2., 1.)),
+            format: QueryFormat::Csv,
+        };
+        let error = run_query(Path::new("results.json"), &query, io::sink()).unwrap_err();
+        assert!(error.detailed_error().contains("time range"));
+    }
+}
@@ -0,0 +1,644 @@
+//! Report of one or several result files, in Markdown or LaTeX.
+//!
+//! For each result file, the report gives a summary of the configuration (duration, seed, modules
+//! of the robots), the KPI tables computed from the records (mission summary, localization
+//! metrics, fleet idleness if enabled) and a figure of the ground-truth trajectories. With several
+//! result files, a comparison table of the main KPIs comes first.
+//!
+//! The trajectories are drawn as an SVG file for Markdown and as a TikZ picture for LaTeX. The
+//! figures of a directory (e.g. made by the plotting scripts) can be embedded as well: they are
+//! copied next to the report, with the generated figures, in the `<report name>_figures`
+//! directory. Markdown embeds the PNG, JPEG, SVG and GIF files, LaTeX the PNG, JPEG and PDF files.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use simba::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    node::node_factory::NodeRecord,
+    physics::PhysicsRecord,
+    simulator::{LocalizationMetrics, MAIN_ESTIMATOR_NAME, Results, Simulator},
+};
+
+/// Output format of the report.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Markdown, with the figures as image links.
+    Markdown,
+    /// Standalone LaTeX document.
+    Latex,
+}
+
+/// Maximal number of points drawn for one trajectory.
+const MAX_TRAJECTORY_POINTS: usize = 500;
+
+/// Colors of the trajectories, as RGB.
+const PALETTE: [(u8, u8, u8); 8] = [
+    (31, 119, 180),
+    (255, 127, 14),
+    (44, 160, 44),
+    (214, 39, 40),
+    (148, 103, 189),
+    (140, 86, 75),
+    (227, 119, 194),
+    (127, 127, 127),
+];
+
+fn io_error(path: &Path, e: impl std::fmt::Display) -> SimbaError {
+    SimbaError::new(
+        SimbaErrorTypes::ConfigError,
+        format!("Impossible to write '{}': {}", path.display(), e),
+    )
+}
+
+/// Table of the report, rendered in the report format.
+struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn new(headers: Vec<&'static str>) -> Self {
+        Self {
+            headers,
+            rows: Vec::new(),
+        }
+    }
+
+    fn render(&self, format: ReportFormat, out: &mut String) {
+        match format {
+            ReportFormat::Markdown => {
+                let row = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+                out.push_str(&row(self
+                    .headers
+                    .iter()
+                    .map(|header| markdown_escape(header))
+                    .collect()));
+                out.push_str(&row(self
+                    .headers
+                    .iter()
+                    .map(|_| "---".to_string())
+                    .collect()));
+                for cells in &self.rows {
+                    out.push_str(&row(cells
+                        .iter()
+                        .map(|cell| markdown_escape(cell))
+                        .collect()));
+                }
+                out.push('\n');
+            }
+            ReportFormat::Latex => {
+                let row = |cells: Vec<String>| format!("{} \\\\\n", cells.join(" & "));
+                out.push_str("\\begin{center}\n");
+                writeln!(
+                    out,
+                    "\\begin{{tabular}}{{{}}}",
+                    "l".repeat(self.headers.len())
+                )
+                .unwrap();
+                out.push_str("\\toprule\n");
+                out.push_str(&row(self
+                    .headers
+                    .iter()
+                    .map(|header| latex_escape(header))
+                    .collect()));
+                out.push_str("\\midrule\n");
+                for cells in &self.rows {
+                    out.push_str(&row(cells.iter().map(|cell| latex_escape(cell)).collect()));
+                }
+                out.push_str("\\bottomrule\n\\end{tabular}\n\\end{center}\n\n");
+            }
+        }
+    }
+}
+
+fn markdown_escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn number(value: Option<f32>) -> String {
+    value.map_or("-".to_string(), |value| format!("{value:.3}"))
+}
+
+fn heading(format: ReportFormat, level: usize, title: &str, out: &mut String) {
+    match format {
+        ReportFormat::Markdown => {
+            writeln!(out, "{} {}\n", "#".repeat(level), markdown_escape(title)).unwrap()
+        }
+        ReportFormat::Latex => {
+            let command = ["section", "subsection", "subsubsection"][(level - 1).min(2)];
+            writeln!(out, "\\{command}{{{}}}\n", latex_escape(title)).unwrap()
+        }
+    }
+}
+
+/// Result file loaded, with its KPIs.
+struct ResultReport {
+    name: String,
+    results: Results,
+    metrics: LocalizationMetrics,
+    /// Ground-truth trajectories, by robot.
+    trajectories: BTreeMap<String, Vec<[f32; 2]>>,
+    duration: f32,
+}
+
+impl ResultReport {
+    fn load(path: &Path) -> SimbaResult<Self> {
+        let results = Simulator::deserialize_results_from_file(path)?;
+        let metrics = LocalizationMetrics::from_records(&results.records);
+        let mut trajectories: BTreeMap<String, Vec<(f32, [f32; 2])>> = BTreeMap::new();
+        let mut duration = 0f32;
+        for record in &results.records {
+            duration = duration.max(record.time);
+            if let NodeRecord::Robot(robot) = &record.node
+                && let PhysicsRecord::Internal(physics) = &robot.physics
+            {
+                let pose = physics.state.pose;
+                trajectories
+                    .entry(robot.name.clone())
+                    .or_default()
+                    .push((record.time, [pose[0], pose[1]]));
+            }
+        }
+        let trajectories = trajectories
+            .into_iter()
+            .map(|(name, mut points)| {
+                points.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let step = points.len().div_ceil(MAX_TRAJECTORY_POINTS).max(1);
+                let mut kept: Vec<[f32; 2]> = points
+                    .iter()
+                    .step_by(step)
+                    .map(|(_, point)| *point)
+                    .collect();
+                if let Some((_, last)) = points.last()
+                    && kept.last() != Some(last)
+                {
+                    kept.push(*last);
+                }
+                (name, kept)
+            })
+            .collect();
+        Ok(Self {
+            name: path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().to_string(),
+            ),
+            results,
+            metrics,
+            trajectories,
+            duration,
+        })
+    }
+
+    /// Position RMSE of the main state estimators, averaged over the robots.
+    fn mean_position_rmse(&self) -> Option<f32> {
+        let rmse: Vec<f32> = self
+            .metrics
+            .estimators
+            .iter()
+            .filter(|estimator| estimator.estimator == MAIN_ESTIMATOR_NAME)
+            .map(|estimator| estimator.position_rmse)
+            .collect();
+        (!rmse.is_empty()).then(|| rmse.iter().sum::<f32>() / rmse.len() as f32)
+    }
+
+    fn config_summary(&self, format: ReportFormat, out: &mut String) {
+        let config = &self.results.config;
+        heading(format, 2, "Configuration", out);
+        let mut table = Table::new(vec!["Parameter", "Value"]);
+        table
+            .rows
+            .push(vec!["Version".to_string(), config.version.clone()]);
+        table.rows.push(vec![
+            "Maximal time (s)".to_string(),
+            config.max_time.to_string(),
+        ]);
+        table.rows.push(vec![
+            "Simulated time (s)".to_string(),
+            number(Some(self.duration)),
+        ]);
+        table.rows.push(vec![
+            "Random seed".to_string(),
+            config
+                .random_seed
+                .map_or("-".to_string(), |seed| seed.to_string()),
+        ]);
+        table
+            .rows
+            .push(vec!["Robots".to_string(), config.robots.len().to_string()]);
+        table.rows.push(vec![
+            "Computation units".to_string(),
+            config.computation_units.len().to_string(),
+        ]);
+        table.render(format, out);
+
+        if !config.robots.is_empty() {
+            let mut table = Table::new(vec![
+                "Robot",
+                "Navigator",
+                "Controller",
+                "Physics",
+                "State estimator",
+            ]);
+            for robot in &config.robots {
+                table.rows.push(vec![
+                    robot.name.clone(),
+                    robot.navigator.to_string(),
+                    robot.controller.to_string(),
+                    robot.physics.to_string(),
+                    robot.state_estimator.to_string(),
+                ]);
+            }
+            table.render(format, out);
+        }
+    }
+
+    fn kpis(&self, format: ReportFormat, out: &mut String) {
+        heading(format, 2, "Mission", out);
+        let mission = &self.results.mission;
+        let mut table = Table::new(vec![
+            "Node",
+            "Goals reached",
+            "Success rate",
+            "Mean time to goal (s)",
+            "Completion time (s)",
+            "Distance (m)",
+        ]);
+        for node in &mission.nodes {
+            table.rows.push(vec![
+                node.node.clone(),
+                format!("{}/{}", node.goals_reached, node.goals_attempted),
+                number(node.success_rate),
+                number(node.mean_time_to_goal),
+                number(node.completion_time),
+                number(Some(node.distance)),
+            ]);
+        }
+        let fleet = &mission.fleet;
+        table.rows.push(vec![
+            "Fleet".to_string(),
+            format!("{}/{}", fleet.goals_reached, fleet.goals_attempted),
+            number(fleet.success_rate),
+            number(fleet.mean_time_to_goal),
+            number(fleet.completion_time),
+            number(Some(fleet.distance)),
+        ]);
+        table.render(format, out);
+
+        if !self.metrics.estimators.is_empty() {
+            heading(format, 2, "Localization", out);
+            let mut table = Table::new(vec![
+                "Node",
+                "Estimator",
+                "Position RMSE (m)",
+                "Heading RMSE (rad)",
+                "Max position error (m)",
+                "ANEES",
+            ]);
+            for estimator in &self.metrics.estimators {
+                table.rows.push(vec![
+                    estimator.node.clone(),
+                    estimator.estimator.clone(),
+                    number(Some(estimator.position_rmse)),
+                    number(Some(estimator.heading_rmse)),
+                    number(Some(estimator.max_position_error)),
+                    number(estimator.anees),
+                ]);
+            }
+            table.render(format, out);
+        }
+
+        let fleet_records = &self.results.fleet_records;
+        if !fleet_records.is_empty() {
+            heading(format, 2, "Idleness", out);
+            let mut table = Table::new(vec!["Average idleness (s)", "Worst idleness (s)"]);
+            table.rows.push(vec![
+                number(Some(
+                    fleet_records
+                        .iter()
+                        .map(|record| record.average_idleness)
+                        .sum::<f32>()
+                        / fleet_records.len() as f32,
+                )),
+                number(
+                    fleet_records
+                        .iter()
+                        .map(|record| record.max_idleness)
+                        .reduce(f32::max),
+                ),
+            ]);
+            table.render(format, out);
+        }
+    }
+
+    /// Bounds of the trajectories, `[min_x, min_y, max_x, max_y]`.
+    fn bounds(&self) -> Option<[f32; 4]> {
+        self.trajectories
+            .values()
+            .flatten()
+            .fold(None, |bounds: Option<[f32; 4]>, point| {
+                let [min_x, min_y, max_x, max_y] =
+                    bounds.unwrap_or([point[0], point[1], point[0], point[1]]);
+                Some([
+                    min_x.min(point[0]),
+                    min_y.min(point[1]),
+                    max_x.max(point[0]),
+                    max_y.max(point[1]),
+                ])
+            })
+    }
+
+    /// Trajectories as an SVG image.
+    fn trajectories_svg(&self) -> Option<String> {
+        let [min_x, min_y, max_x, max_y] = self.bounds()?;
+        let size = 560.;
+        let margin = 20.;
+        let scale = size / (max_x - min_x).max(max_y - min_y).max(1e-3);
+        let width = (max_x - min_x) * scale + 2. * margin;
+        let height = (max_y - min_y) * scale + 2. * margin;
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{height:.0}\" viewBox=\"0 0 {width:.1} {height:.1}\">"
+        )
+        .unwrap();
+        writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>").unwrap();
+        for (i, (name, points)) in self.trajectories.iter().enumerate() {
+            let (r, g, b) = PALETTE[i % PALETTE.len()];
+            let coordinates: Vec<String> = points
+                .iter()
+                .map(|point| {
+                    format!(
+                        "{:.1},{:.1}",
+                        margin + (point[0] - min_x) * scale,
+                        margin + (max_y - point[1]) * scale
+                    )
+                })
+                .collect();
+            writeln!(
+                svg,
+                "<polyline fill=\"none\" stroke=\"rgb({r},{g},{b})\" stroke-width=\"2\" points=\"{}\"/>",
+                coordinates.join(" ")
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                "<text x=\"{margin}\" y=\"{}\" font-size=\"12\" fill=\"rgb({r},{g},{b})\">{}</text>",
+                margin + 14. * i as f32,
+                name.replace('&', "&amp;").replace('<', "&lt;")
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        Some(svg)
+    }
+
+    /// Trajectories as a TikZ picture.
+    fn trajectories_tikz(&self) -> Option<String> {
+        let [min_x, min_y, max_x, max_y] = self.bounds()?;
+        // 12 cm for the largest side
+        let scale = 12. / (max_x - min_x).max(max_y - min_y).max(1e-3);
+        let mut tikz = format!("\\begin{{tikzpicture}}[x={scale:.4}cm, y={scale:.4}cm]\n");
+        for (i, (name, points)) in self.trajectories.iter().enumerate() {
+            let (r, g, b) = PALETTE[i % PALETTE.len()];
+            let color = format!("{{rgb,255:red,{r};green,{g};blue,{b}}}");
+            let coordinates: Vec<String> = points
+                .iter()
+                .map(|point| format!("({:.3},{:.3})", point[0], point[1]))
+                .collect();
+            writeln!(
+                tikz,
+                "\\draw[color={color}, thick] plot coordinates {{{}}};",
+                coordinates.join(" ")
+            )
+            .unwrap();
+            let last = points.last().unwrap();
+            writeln!(
+                tikz,
+                "\\node[color={color}, anchor=west, font=\\small] at ({:.3},{:.3}) {{{}}};",
+                last[0],
+                last[1],
+                latex_escape(name)
+            )
+            .unwrap();
+        }
+        tikz.push_str("\\end{tikzpicture}\n");
+        Some(tikz)
+    }
+}
+
+/// Content of the report.
+pub struct ReportRequest {
+    /// Format of the report.
+    pub format: ReportFormat,
+    /// Directory of figures to embed.
+    pub figures: Option<PathBuf>,
+}
+
+/// Figures of `directory` which can be embedded in the `format`, sorted by name.
+fn list_figures(directory: &Path, format: ReportFormat) -> SimbaResult<Vec<PathBuf>> {
+    let extensions: &[&str] = match format {
+        ReportFormat::Markdown => &["png", "jpg", "jpeg", "svg", "gif"],
+        ReportFormat::Latex => &["png", "jpg", "jpeg", "pdf"],
+    };
+    let entries = fs::read_dir(directory).map_err(|e| {
+        SimbaError::new(
+            SimbaErrorTypes::ConfigError,
+            format!(
+                "Impossible to read figure directory '{}': {}",
+                directory.display(),
+                e
+            ),
+        )
+    })?;
+    let mut figures: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| extensions.contains(&extension.to_lowercase().as_str()))
+        })
+        .collect();
+    figures.sort();
+    Ok(figures)
+}
+
+fn embed_figure(format: ReportFormat, path: &str, caption: &str, out: &mut String) {
+    match format {
+        ReportFormat::Markdown => {
+            writeln!(out, "![{}]({})\n", markdown_escape(caption), path).unwrap()
+        }
+        ReportFormat::Latex => writeln!(
+            out,
+            "\\begin{{figure}}[h]\n\\centering\n\\includegraphics[width=0.8\\linewidth]{{{path}}}\n\\caption{{{}}}\n\\end{{figure}}\n",
+            latex_escape(caption)
+        )
+        .unwrap(),
+    }
+}
+
+/// Write the report of the `result_paths` to `output`, with its figures in the
+/// `<output name>_figures` directory.
+pub fn generate_report(
+    result_paths: &[PathBuf],
+    request: &ReportRequest,
+    output: &Path,
+) -> SimbaResult<()> {
+    let reports = result_paths
+        .iter()
+        .map(|path| ResultReport::load(path))
+        .collect::<SimbaResult<Vec<_>>>()?;
+    let format = request.format;
+    let stem = output.file_stem().map_or("report".to_string(), |stem| {
+        stem.to_string_lossy().to_string()
+    });
+    let figure_directory_name = format!("{stem}_figures");
+    let figure_directory = output
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(&figure_directory_name);
+
+    let mut out = String::new();
+    if format == ReportFormat::Latex {
+        out.push_str(
+            "\\documentclass{article}\n\\usepackage[margin=2cm]{geometry}\n\\usepackage{booktabs}\n\\usepackage{graphicx}\n\\usepackage{tikz}\n\\begin{document}\n\n",
+        );
+    }
+    let title = "Simulation report";
+    match format {
+        ReportFormat::Markdown => writeln!(out, "# {title}\n").unwrap(),
+        ReportFormat::Latex => {
+            writeln!(out, "\\title{{{title}}}\n\\date{{}}\n\\maketitle\n").unwrap()
+        }
+    }
+
+    if reports.len() > 1 {
+        heading(format, 1, "Comparison", &mut out);
+        let mut table = Table::new(vec![
+            "Result",
+            "Simulated time (s)",
+            "Goals reached",
+            "Success rate",
+            "Distance (m)",
+            "Position RMSE (m)",
+        ]);
+        for report in &reports {
+            let fleet = &report.results.mission.fleet;
+            table.rows.push(vec![
+                report.name.clone(),
+                number(Some(report.duration)),
+                format!("{}/{}", fleet.goals_reached, fleet.goals_attempted),
+                number(fleet.success_rate),
+                number(Some(fleet.distance)),
+                number(report.mean_position_rmse()),
+            ]);
+        }
+        table.render(format, &mut out);
+    }
+
+    for (i, report) in reports.iter().enumerate() {
+        heading(format, 1, &report.name, &mut out);
+        report.config_summary(format, &mut out);
+        report.kpis(format, &mut out);
+        match format {
+            ReportFormat::Markdown => {
+                if let Some(svg) = report.trajectories_svg() {
+                    fs::create_dir_all(&figure_directory)
+                        .map_err(|e| io_error(&figure_directory, e))?;
+                    let name = format!("trajectories_{}.svg", i + 1);
+                    let path = figure_directory.join(&name);
+                    fs::write(&path, svg).map_err(|e| io_error(&path, e))?;
+                    heading(format, 2, "Trajectories", &mut out);
+                    embed_figure(
+                        format,
+                        &format!("{figure_directory_name}/{name}"),
+                        "Ground-truth trajectories",
+                        &mut out,
+                    );
+                }
+            }
+            ReportFormat::Latex => {
+                if let Some(tikz) = report.trajectories_tikz() {
+                    heading(format, 2, "Trajectories", &mut out);
+                    out.push_str("\\begin{figure}[h]\n\\centering\n");
+                    out.push_str(&tikz);
+                    out.push_str("\\caption{Ground-truth trajectories}\n\\end{figure}\n\n");
+                }
+            }
+        }
+    }
+
+    if let Some(directory) = &request.figures {
+        let figures = list_figures(directory, format)?;
+        if !figures.is_empty() {
+            heading(format, 1, "Figures", &mut out);
+            fs::create_dir_all(&figure_directory).map_err(|e| io_error(&figure_directory, e))?;
+        }
+        for figure in figures {
+            let name = figure.file_name().unwrap().to_string_lossy().to_string();
+            let destination = figure_directory.join(&name);
+            fs::copy(&figure, &destination).map_err(|e| io_error(&destination, e))?;
+            let caption = figure
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .replace('_', " ");
+            embed_figure(
+                format,
+                &format!("{figure_directory_name}/{name}"),
+                &caption,
+                &mut out,
+            );
+        }
+    }
+
+    if format == ReportFormat::Latex {
+        out.push_str("\\end{document}\n");
+    }
+    fs::write(output, out).map_err(|e| io_error(output, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_tables() {
+        let mut table = Table::new(vec!["Node", "RMSE"]);
+        table
+            .rows
+            .push(vec!["robot_1|a".to_string(), number(Some(0.12345))]);
+        let mut markdown = String::new();
+        table.render(ReportFormat::Markdown, &mut markdown);
+        assert_eq!(
+            markdown,
+            "| Node | RMSE |\n| --- | --- |\n| robot_1\\|a | 0.123 |\n\n"
+        );
+        let mut latex = String::new();
+        table.render(ReportFormat::Latex, &mut latex);
+        assert!(latex.contains("robot\\_1|a & 0.123 \\\\\n"));
+        assert_eq!(number(None), "-");
+    }
+}
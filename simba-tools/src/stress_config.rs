@@ -0,0 +1,299 @@
+//! Generation of large-fleet stress configurations.
+//!
+//! The generated configuration places `N` robots on a grid, each one going to a random goal in
+//! the world, with a standard sensor suite (landmarks, other robots, odometry and GNSS). A map
+//! with a grid of landmarks covering the world is generated next to the configuration.
+//!
+//! It is used for scalability testing, and as a template for large studies.
+
+use std::{fs, path::Path};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde_yaml::Value;
+use simba::{
+    errors::{SimbaError, SimbaErrorTypes, SimbaResult},
+    simulator::SimulatorConfig,
+};
+
+/// Robot template, the name, initial pose and goal are replaced for each robot.
+const ROBOT_TEMPLATE: &str = r#"
+name: robot
+navigator:
+  type: GoTo
+  target_point: [0.0, 0.0]
+  target_speed: 0.5
+controller:
+  type: PID
+physics:
+  type: Internal
+  model:
+    type: Unicycle
+    wheel_distance: 0.25
+  initial_state:
+    pose: [0.0, 0.0, 0.0]
+    velocity: [0.0, 0.0]
+state_estimator:
+  type: Perfect
+  prediction_activation:
+    period: {type: Num, value: 0.1}
+  targets:
+  - self
+sensor_manager:
+  sensors:
+  - name: LandmarkSensor
+    config:
+      type: OrientedLandmark
+      detection_distance: 5.0
+      activation_time:
+        period: {type: Num, value: 0.1}
+  - name: RobotSensor
+    config:
+      type: Robot
+      detection_distance: 5.0
+      activation_time:
+        period: {type: Num, value: 0.1}
+  - name: OdomSensor
+    config:
+      type: Speed
+      activation_time:
+        period: {type: Num, value: 0.1}
+  - name: GNSSSensor
+    config:
+      type: GNSS
+      activation_time:
+        period: {type: Num, value: 1.0}
+"#;
+
+/// Parameters of the generated stress configuration.
+pub struct StressConfigParams {
+    /// Number of robots.
+    pub nb_robots: usize,
+    /// Distance between two neighbour robots on the starting grid, in meters.
+    pub spacing: f32,
+    /// Distance between two neighbour landmarks, in meters.
+    pub landmark_spacing: f32,
+    /// Maximum simulated time.
+    pub max_time: f32,
+    /// Seed used to draw the goals, also used as random seed of the simulation.
+    pub seed: u64,
+}
+
+impl StressConfigParams {
+    /// Reject the parameters which cannot make a world: no robot, or spacings which are not
+    /// strictly positive.
+    fn check(&self) -> SimbaResult<()> {
+        let mut errors = Vec::new();
+        if self.nb_robots == 0 {
+            errors.push("the number of robots should be at least 1".to_string());
+        }
+        if self.spacing.is_nan() || self.spacing <= 0. {
+            errors.push(format!(
+                "the robot spacing should be strictly positive, got {}",
+                self.spacing
+            ));
+        }
+        if self.landmark_spacing.is_nan() || self.landmark_spacing <= 0. {
+            errors.push(format!(
+                "the landmark spacing should be strictly positive, got {}",
+                self.landmark_spacing
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(config_error(format!(
+                "Invalid stress configuration parameters: {}",
+                errors.join(", ")
+            )))
+        }
+    }
+}
+
+fn config_error(message: String) -> SimbaError {
+    SimbaError::new(SimbaErrorTypes::ConfigError, message)
+}
+
+fn io_error(path: &Path, e: impl std::fmt::Display) -> SimbaError {
+    config_error(format!("Impossible to write '{}': {}", path.display(), e))
+}
+
+fn set(value: &mut Value, path: &[&str], new_value: Value) {
+    let mut current = value;
+    for key in path {
+        current = current
+            .get_mut(*key)
+            .unwrap_or_else(|| panic!("Key `{key}` not in the robot template"));
+    }
+    *current = new_value;
+}
+
+fn yaml_f32_list(values: &[f32]) -> Value {
+    Value::Sequence(values.iter().map(|v| Value::from(*v as f64)).collect())
+}
+
+/// Half size of the square world containing the robots (and their goals).
+fn world_half_size(params: &StressConfigParams) -> f32 {
+    let side = (params.nb_robots as f32).sqrt().ceil();
+    side * params.spacing / 2.
+}
+
+fn generate_map(params: &StressConfigParams) -> Value {
+    let half_size = world_half_size(params) + params.landmark_spacing;
+    let nb_per_side = (2. * half_size / params.landmark_spacing).ceil() as usize + 1;
+    let mut landmarks = Vec::new();
+    for i in 0..nb_per_side {
+        for j in 0..nb_per_side {
+            let mut landmark = serde_yaml::Mapping::new();
+            landmark.insert("id".into(), ((i * nb_per_side + j) as u64).into());
+            landmark.insert(
+                "x".into(),
+                ((-half_size + i as f32 * params.landmark_spacing) as f64).into(),
+            );
+            landmark.insert(
+                "y".into(),
+                ((-half_size + j as f32 * params.landmark_spacing) as f64).into(),
+            );
+            landmark.insert("theta".into(), 0.0.into());
+            landmarks.push(Value::Mapping(landmark));
+        }
+    }
+    let mut map = serde_yaml::Mapping::new();
+    map.insert("landmarks".into(), Value::Sequence(landmarks));
+    Value::Mapping(map)
+}
+
+fn generate_config(params: &StressConfigParams, map_file: &str) -> SimbaResult<Value> {
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let template: Value = serde_yaml::from_str(ROBOT_TEMPLATE)
+        .map_err(|e| config_error(format!("Invalid robot template: {e}")))?;
+    let side = (params.nb_robots as f32).sqrt().ceil() as usize;
+    let half_size = world_half_size(params);
+    let offset = -half_size + params.spacing / 2.;
+
+    let robots = (0..params.nb_robots)
+        .map(|i| {
+            let mut robot = template.clone();
+            let x = offset + (i % side) as f32 * params.spacing;
+            let y = offset + (i / side) as f32 * params.spacing;
+            let goal_x = rng.gen_range(-half_size..half_size);
+            let goal_y = rng.gen_range(-half_size..half_size);
+            set(&mut robot, &["name"], format!("robot{}", i + 1).into());
+            set(
+                &mut robot,
+                &["physics", "initial_state", "pose"],
+                yaml_f32_list(&[x, y, (goal_y - y).atan2(goal_x - x)]),
+            );
+            set(
+                &mut robot,
+                &["navigator", "target_point"],
+                yaml_f32_list(&[goal_x, goal_y]),
+            );
+            robot
+        })
+        .collect();
+
+    let config = format!(
+        r#"
+log:
+  log_level: {{type: Warn}}
+results: null
+time_analysis: null
+max_time: {}
+random_seed: {}
+environment:
+  map_path: {}
+computation_units: []
+"#,
+        params.max_time, params.seed as f32, map_file
+    );
+    let mut config: Value = serde_yaml::from_str(&config)
+        .map_err(|e| config_error(format!("Invalid configuration template: {e}")))?;
+    if let Value::Mapping(mapping) = &mut config {
+        mapping.insert("robots".into(), Value::Sequence(robots));
+    }
+    Ok(config)
+}
+
+/// Write the stress configuration at `path`, and its map next to it (`<stem>_map.yaml`).
+///
+/// The parameters are checked first, and the written configuration is loaded back to check that
+/// it is valid.
+pub fn generate_stress_config(path: &Path, params: &StressConfigParams) -> SimbaResult<()> {
+    params.check()?;
+    let stem = path
+        .file_stem()
+        .ok_or_else(|| config_error(format!("'{}' is not a file path", path.display())))?
+        .to_string_lossy();
+    let map_file = format!("{stem}_map.yaml");
+    let map_path = path.with_file_name(&map_file);
+
+    let header = "# yaml-language-server: $schema=config.schema.json\n";
+    let map = serde_yaml::to_string(&generate_map(params)).map_err(|e| io_error(&map_path, e))?;
+    fs::write(&map_path, map).map_err(|e| io_error(&map_path, e))?;
+    let config = serde_yaml::to_string(&generate_config(params, &map_file)?)
+        .map_err(|e| io_error(path, e))?;
+    fs::write(path, format!("{header}{config}")).map_err(|e| io_error(path, e))?;
+
+    SimulatorConfig::load_from_path(path)?;
+    println!(
+        "Stress configuration with {} robots generated at: {} (map: {})",
+        params.nb_robots,
+        path.display(),
+        map_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> StressConfigParams {
+        StressConfigParams {
+            nb_robots: 5,
+            spacing: 2.,
+            landmark_spacing: 3.,
+            max_time: 1.,
+            seed: 42,
+        }
+    }
+
+    #[test]
+    fn generated_config_loads() {
+        let directory =
+            std::env::temp_dir().join(format!("simba_stress_config_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("stress.yaml");
+        generate_stress_config(&path, &params()).unwrap();
+        let config = SimulatorConfig::load_from_path(&path).unwrap();
+        assert_eq!(config.robots.len(), 5);
+        assert!(directory.join("stress_map.yaml").is_file());
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn invalid_params_rejected() {
+        let path = std::env::temp_dir().join("simba_stress_config_invalid.yaml");
+        for params in [
+            StressConfigParams {
+                nb_robots: 0,
+                ..params()
+            },
+            StressConfigParams {
+                spacing: 0.,
+                ..params()
+            },
+            StressConfigParams {
+                landmark_spacing: -1.,
+                ..params()
+            },
+            StressConfigParams {
+                landmark_spacing: f32::NAN,
+                ..params()
+            },
+        ] {
+            assert!(generate_stress_config(&path, &params).is_err());
+        }
+        assert!(!path.exists());
+    }
+}
@@ -1,4 +1,4 @@
-from typing import Dict, List, Tuple
+from typing import Callable, Dict, List, Tuple
 from enum import Enum
 # from warnings import deprecated # Available in python 3.13
 
@@ -121,6 +121,22 @@ class DisplacementObservation:
         self.rotation: float
         self.applied_faults: str """ Applied faults in JSON format """
 
+class ImuObservation:
+    def __init__(self):
+        self.angular_velocity: float
+        self.longitudinal_acceleration: float
+        self.lateral_acceleration: float
+        self.applied_faults: str """ Applied faults in JSON format """
+
+class BearingObservation:
+    def __init__(self):
+        self.id: str
+        self.target: str  # "Landmark" or "Robot"
+        self.labels: List[str]
+        self.bearing: float
+        self.elevation: float | None
+        self.applied_faults: str """ Applied faults in JSON format """
+
 class GNSSObservation: 
     def __init__(self):
         self.pose: Vec3
@@ -139,6 +155,8 @@ class SensorObservation(Enum):
     Speed: SpeedObservation
     GNSS: GNSSObservation
     OrientedRobot: OrientedRobotObservation
+    Imu: ImuObservation
+    Bearing: BearingObservation
     
     def __init__(self):
         self.kind: str
@@ -154,6 +172,12 @@ class SensorObservation(Enum):
     
     def as_oriented_robot(self) -> OrientedRobotObservation | None:
         raise NotImplementedError()
+
+    def as_imu(self) -> ImuObservation | None:
+        raise NotImplementedError()
+
+    def as_bearing(self) -> BearingObservation | None:
+        raise NotImplementedError()
     
 class Observation:
     def __init__(self):
@@ -161,6 +185,7 @@ class Observation:
         self.observer: str
         self.time: float
         self.sensor_observation: SensorObservation
+        self.frame: str  # "Robot", "Sensor" or "Map"
 
 
 class Command(Enum):
@@ -202,7 +227,24 @@ class GoToMessage:
 class SensorTriggerMessage:
     def __init__(self):
         pass
+
+class ReconfigureMessage:
+    def __init__(self, period: float | None = None, noise_scale: float | None = None, fault_activation: Tuple[int, bool] | None = None):
+        self.period: float | None
+        self.noise_scale: float | None
+        self.fault_activation: Tuple[int, bool] | None
         
+class MapShareMessage:
+    def __init__(self, landmarks: Dict[int, Tuple[float, float, float]] = {}, occupancy_grid: OccupancyGrid | None = None):
+        """Map shared with a MapMerging state estimator, or merged map published by it.
+
+        Args:
+            landmarks (Dict[int, Tuple[float, float, float]]): Landmark poses (x, y, orientation) by id.
+            occupancy_grid (OccupancyGrid | None): Occupancy grid of the map.
+        """
+        self.landmarks: Dict[int, Tuple[float, float, float]]
+        self.occupancy_grid: OccupancyGrid | None
+
 class MessageFlag(Enum):
     # God mode, messages are instaneous.
     God = 1
@@ -210,11 +252,15 @@ class MessageFlag(Enum):
     Unsubscribe = 2
     # Ask to kill the receiving node
     Kill = 3
+    # Ask to publish the outcome of a reconfiguration message
+    Acknowledge = 4
 
 class MessageTypes(Enum):
     String: str
     GoTo: GoToMessage
     SensorTrigger: SensorTriggerMessage
+    Reconfigure: ReconfigureMessage
+    MapShare: MapShareMessage
 
     def __init__(self):
         self.kind: str
@@ -225,12 +271,24 @@ class MessageTypes(Enum):
     def as_sensor_trigger(self) -> SensorTriggerMessage | None: 
         raise NotImplementedError()
 
+    def as_reconfigure(self) -> ReconfigureMessage | None:
+        raise NotImplementedError()
+
+    def as_map_share(self) -> MapShareMessage | None:
+        raise NotImplementedError()
+
     def from_goto(cmd: GoToMessage) -> MessageTypes:
         raise NotImplementedError()
 
     def from_sensor_trigger(cmd: SensorTriggerMessage) -> MessageTypes:
         raise NotImplementedError()
 
+    def from_reconfigure(cmd: ReconfigureMessage) -> MessageTypes:
+        raise NotImplementedError()
+
+    def from_map_share(cmd: MapShareMessage) -> MessageTypes:
+        raise NotImplementedError()
+
 class Envelope:
     def __init__(self):
         self.msg_from: str
@@ -257,6 +315,51 @@ class Node:
     
     def make_channel(self, topic: str) -> None:
         raise NotImplementedError()
+
+    def meta_data_list(self) -> List[NodeMetaData]:
+        """Meta-data of all the nodes of the simulation, sorted by name."""
+        raise NotImplementedError()
+
+    def watch_meta_data(self) -> MetaDataWatcher:
+        raise NotImplementedError()
+
+    def map_landmarks(self) -> List[Landmark]:
+        """Landmarks of the true map. The landmarks with a width are the walls."""
+        raise NotImplementedError()
+
+    def prior_landmarks(self) -> Dict[int, List[float]]:
+        """Landmarks known by the state estimators, {id: [x, y, theta]}. Empty without known map (SLAM)."""
+        raise NotImplementedError()
+
+    def is_path_free(self, start: List[float], end: List[float]) -> bool:
+        """Whether the straight path between the two [x, y] points crosses no wall of the true map."""
+        raise NotImplementedError()
+
+class Landmark:
+    def __init__(self):
+        self.id: int
+        self.labels: List[str]
+        self.x: float
+        self.y: float
+        self.theta: float
+        self.height: float
+        self.width: float
+
+class NodeMetaData:
+    def __init__(self):
+        self.name: str
+        self.node_type: str
+        self.model_name: str
+        self.labels: List[str]
+        self.state: str
+        self.position: List[float] | None
+        self.footprint_radius: float
+        self.height: float
+
+class MetaDataWatcher:
+    def changes(self) -> List[NodeMetaData]:
+        """Meta-data of the nodes added or changed since the last call (all the nodes on the first call)."""
+        raise NotImplementedError()
     
 class Client:
     def subscribe(self, key: str) -> None:
@@ -375,6 +478,51 @@ class Simulator:
     
     def run(self):
         raise NotImplementedError()
+
+    def run_control(self) -> RunControl:
+        raise NotImplementedError()
+
+class RunControl:
+    def pause(self) -> None:
+        raise NotImplementedError()
+
+    def resume(self) -> None:
+        raise NotImplementedError()
+
+    def step(self, n_time_steps: int = 1) -> None:
+        raise NotImplementedError()
+
+    def is_paused(self) -> bool:
+        raise NotImplementedError()
+
+    def patch_config(self, patch: str) -> ConfigPatchHandle:
+        raise NotImplementedError()
+
+class ConfigPatchHandle:
+    def wait(self) -> None:
+        raise NotImplementedError()
+
+class GymObservation:
+    def __init__(self):
+        self.time: float
+        self.node: Node
+        self.world_state: WorldState
+        self.true_state: State
+        self.error: ControllerError
+
+class GymEnv:
+    def __init__(self, config_path: str, robot: str, reward: Callable[[GymObservation], float], terminated: Callable[[GymObservation], bool] | None = None, max_time: float | None = None, plugin_api: PluginAPI | None = None):
+        raise NotImplementedError()
+
+    def reset(self) -> Tuple[GymObservation, Dict]:
+        raise NotImplementedError()
+
+    def step(self, action: Command) -> Tuple[GymObservation, float, bool, bool, Dict]:
+        """ Returns (observation, reward, terminated, truncated, info) """
+        raise NotImplementedError()
+
+    def close(self) -> None:
+        raise NotImplementedError()
     
 def run_gui(plugin_api: PluginAPI | None):
         raise NotImplementedError()
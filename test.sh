@@ -6,6 +6,8 @@ cargo nextest run --no-default-features
 cargo build --release
 cargo nextest run --release
 cargo nextest run --release --all-features
+# Generated plugin templates must build against the current plugin API
+cargo nextest run --release -p simba-tools --run-ignored only -E 'test(generated_)'
 
 target/release/simba-cmd config_example/config_2.yaml --no-gui
 target/release/simba-cmd config_example/config_scenario.yaml --no-gui